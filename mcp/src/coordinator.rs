@@ -0,0 +1,445 @@
+//! Coordinator integration for cluster-level MCP tools
+
+use crate::types::{ToolContent, ToolResult};
+use gpukill::coordinator::{ClusterSnapshot, ContentionAnalysis};
+use serde_json::json;
+use std::collections::{BTreeSet, HashSet};
+use std::env;
+use std::time::Duration;
+
+/// Default timeout for coordinator requests
+pub const DEFAULT_COORDINATOR_TIMEOUT_SECS: u64 = 5;
+
+/// Number of top users listed in the overview text
+const TOP_USERS_SHOWN: usize = 5;
+
+/// HTTP client for a GPU Kill coordinator (`gpukill --server`)
+#[derive(Debug, Clone)]
+pub struct CoordinatorClient {
+    base_url: String,
+    timeout: Duration,
+    client: reqwest::Client,
+}
+
+impl CoordinatorClient {
+    /// Create a client for the coordinator at `base_url`
+    pub fn new(base_url: &str, timeout: Duration) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .connect_timeout(timeout)
+            .build()?;
+
+        Ok(Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            timeout,
+            client,
+        })
+    }
+
+    /// Create a client from COORDINATOR_URL (and optional COORDINATOR_TIMEOUT_SECS)
+    pub fn from_env() -> Option<Self> {
+        let url = env::var("COORDINATOR_URL").ok()?;
+        if url.trim().is_empty() {
+            return None;
+        }
+
+        let timeout_secs = env::var("COORDINATOR_TIMEOUT_SECS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_COORDINATOR_TIMEOUT_SECS);
+
+        match Self::new(url.trim(), Duration::from_secs(timeout_secs)) {
+            Ok(client) => Some(client),
+            Err(e) => {
+                tracing::warn!("Failed to create coordinator client for {}: {}", url, e);
+                None
+            }
+        }
+    }
+
+    /// Base URL of the coordinator
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Fetch a JSON document from the coordinator
+    async fn get_json(&self, path: &str) -> anyhow::Result<serde_json::Value> {
+        let url = format!("{}{}", self.base_url, path);
+        let response = self.client.get(&url).send().await.map_err(|e| {
+            if e.is_timeout() {
+                anyhow::anyhow!(
+                    "Timed out after {}s waiting for coordinator at {}",
+                    self.timeout.as_secs_f32(),
+                    url
+                )
+            } else if e.is_connect() {
+                anyhow::anyhow!("Could not connect to coordinator at {}: {}", url, e)
+            } else {
+                anyhow::anyhow!("Request to coordinator at {} failed: {}", url, e)
+            }
+        })?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow::anyhow!(
+                "Coordinator returned HTTP {} for {}",
+                status,
+                url
+            ));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| anyhow::anyhow!("Invalid JSON from coordinator at {}: {}", url, e))
+    }
+
+    /// Fetch the raw cluster snapshot
+    pub async fn fetch_snapshot(&self) -> anyhow::Result<serde_json::Value> {
+        self.get_json("/api/cluster/snapshot").await
+    }
+
+    /// Fetch the raw cluster snapshot and contention analysis
+    pub async fn fetch_overview(&self) -> anyhow::Result<(serde_json::Value, serde_json::Value)> {
+        let snapshot = self.fetch_snapshot().await?;
+        let contention = self.get_json("/api/cluster/contention").await?;
+        Ok((snapshot, contention))
+    }
+}
+
+/// Summarize a cluster snapshot and contention analysis for an assistant
+pub fn summarize_cluster(
+    snapshot: Option<&ClusterSnapshot>,
+    contention: &ContentionAnalysis,
+) -> String {
+    let Some(snapshot) = snapshot else {
+        return "The coordinator has not received any node snapshots yet.".to_string();
+    };
+
+    let maintenance: HashSet<(&str, u16)> = contention
+        .maintenance_gpus
+        .iter()
+        .map(|m| (m.node_id.as_str(), m.gpu_index))
+        .collect();
+
+    let mut lines = Vec::new();
+    let mut free_total = 0;
+    let mut node_lines = Vec::new();
+    for node in &snapshot.nodes {
+        let free: Vec<String> = node
+            .gpus
+            .iter()
+            .filter(|gpu| {
+                gpu.pids == 0 && !maintenance.contains(&(node.node_id.as_str(), gpu.gpu_index))
+            })
+            .map(|gpu| format!("GPU {} ({})", gpu.gpu_index, gpu.name))
+            .collect();
+        free_total += free.len();
+
+        let free_text = if free.is_empty() {
+            "none free".to_string()
+        } else {
+            format!("free: {}", free.join(", "))
+        };
+        node_lines.push(format!(
+            "- {} ({}): {} GPUs, {}",
+            node.hostname,
+            node.node_id,
+            node.gpus.len(),
+            free_text
+        ));
+    }
+
+    lines.push(format!(
+        "Cluster: {} nodes, {} GPUs ({} free), {:.1}% average utilization, {} active processes",
+        snapshot.nodes.len(),
+        snapshot.total_gpus,
+        free_total,
+        snapshot.utilization_avg,
+        snapshot.active_processes
+    ));
+    lines.extend(node_lines);
+
+    if !contention.top_users.is_empty() {
+        lines.push("Top users:".to_string());
+        for user in contention.top_users.iter().take(TOP_USERS_SHOWN) {
+            lines.push(format!(
+                "- {}: {} GPUs, {} MB, {:.1}% average utilization",
+                user.user, user.gpu_count, user.total_memory_mb, user.avg_utilization
+            ));
+        }
+    }
+
+    if contention.blocked_gpus.is_empty() {
+        lines.push("No blocked GPUs.".to_string());
+    } else {
+        lines.push("Blocked GPUs:".to_string());
+        for blocked in &contention.blocked_gpus {
+            let users: Vec<&str> = blocked
+                .blocking_processes
+                .iter()
+                .map(|p| p.user.as_str())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+            lines.push(format!(
+                "- {} GPU {} ({}): {:.1}% utilization, {}/{} MB, held by {}",
+                blocked.node_id,
+                blocked.gpu_index,
+                blocked.gpu_name,
+                blocked.utilization_pct,
+                blocked.memory_used_mb,
+                blocked.memory_total_mb,
+                users.join(", ")
+            ));
+        }
+    }
+
+    if !contention.maintenance_gpus.is_empty() {
+        lines.push("GPUs in maintenance:".to_string());
+    }
+    for gpu in &contention.maintenance_gpus {
+        lines.push(format!(
+            "- {} GPU {} ({}): {}",
+            gpu.node_id, gpu.gpu_index, gpu.gpu_name, gpu.message
+        ));
+    }
+
+    if !contention.unhealthy_gpus.is_empty() {
+        lines.push("GPUs with memory errors:".to_string());
+    }
+    for gpu in &contention.unhealthy_gpus {
+        let mut details = vec![format!("{} uncorrected ECC errors", gpu.ecc_uncorrected)];
+        if let Some(pages) = gpu.retired_pages {
+            details.push(format!("{} retired pages", pages));
+        }
+        if gpu.rma_flagged {
+            details.push("flagged for RMA".to_string());
+        }
+        lines.push(format!(
+            "- {} GPU {} ({}): {}",
+            gpu.node_id,
+            gpu.gpu_index,
+            gpu.gpu_name,
+            details.join(", ")
+        ));
+    }
+
+    lines.join("\n")
+}
+
+fn error_result(message: String) -> ToolResult {
+    ToolResult {
+        content: vec![ToolContent {
+            content_type: "text".to_string(),
+            text: Some(message),
+            data: None,
+        }],
+        is_error: Some(true),
+    }
+}
+
+/// Run the `get_cluster_overview` tool against an optional coordinator
+pub async fn cluster_overview(client: Option<&CoordinatorClient>) -> ToolResult {
+    let Some(client) = client else {
+        return error_result(
+            "No coordinator configured. Set COORDINATOR_URL to the address of a GPU Kill \
+             coordinator (e.g. COORDINATOR_URL=http://coordinator:8080, started with \
+             `gpukill --server`) and restart the MCP server."
+                .to_string(),
+        );
+    };
+
+    let (snapshot_raw, contention_raw) = match client.fetch_overview().await {
+        Ok(raw) => raw,
+        Err(e) => return error_result(format!("Failed to fetch cluster overview: {}", e)),
+    };
+
+    let snapshot: Option<ClusterSnapshot> = match serde_json::from_value(snapshot_raw.clone()) {
+        Ok(snapshot) => snapshot,
+        Err(e) => return error_result(format!("Unexpected cluster snapshot format: {}", e)),
+    };
+    let contention: ContentionAnalysis = match serde_json::from_value(contention_raw.clone()) {
+        Ok(contention) => contention,
+        Err(e) => return error_result(format!("Unexpected contention analysis format: {}", e)),
+    };
+
+    ToolResult {
+        content: vec![ToolContent {
+            content_type: "text".to_string(),
+            text: Some(summarize_cluster(snapshot.as_ref(), &contention)),
+            data: Some(json!({
+                "coordinator": client.base_url(),
+                "snapshot": snapshot_raw,
+                "contention": contention_raw,
+            })),
+        }],
+        is_error: Some(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use gpukill::coordinator::{BlockedGpu, MaintenanceGpu, NodeSnapshot, UnhealthyGpu, UserUsage};
+    use gpukill::nvml_api::{GpuProc, GpuSnapshot};
+
+    fn gpu(index: u16, name: &str, pids: usize) -> GpuSnapshot {
+        GpuSnapshot {
+            gpu_index: index,
+            name: name.to_string(),
+            vendor: gpukill::vendor::GpuVendor::Nvidia,
+            mem_used_mb: if pids > 0 { 20000 } else { 0 },
+            mem_total_mb: 81920,
+            util_pct: if pids > 0 { 90.0 } else { 0.0 },
+            temp_c: 40,
+            power_w: 100.0,
+            pids,
+            ..Default::default()
+        }
+    }
+
+    fn proc(pid: u32, user: &str) -> GpuProc {
+        GpuProc {
+            gpu_index: 0,
+            pid,
+            user: user.to_string(),
+            proc_name: "python".to_string(),
+            used_mem_mb: 20000,
+            start_time: "0s".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn cluster() -> (ClusterSnapshot, ContentionAnalysis) {
+        let node = |id: &str, gpus: Vec<GpuSnapshot>| NodeSnapshot {
+            node_id: id.to_string(),
+            hostname: format!("{}.example", id),
+            timestamp: Utc::now(),
+            gpus,
+            processes: Vec::new(),
+            status: gpukill::coordinator::NodeStatus::Online,
+            tags: Default::default(),
+            errors: Vec::new(),
+        };
+        let snapshot = ClusterSnapshot {
+            timestamp: Utc::now(),
+            nodes: vec![
+                node(
+                    "node-a",
+                    vec![gpu(0, "NVIDIA A100", 1), gpu(1, "NVIDIA A100", 0)],
+                ),
+                node("node-b", vec![gpu(0, "NVIDIA H100", 0)]),
+            ],
+            total_gpus: 3,
+            total_memory_gb: 240.0,
+            active_processes: 1,
+            utilization_avg: 30.0,
+        };
+        let contention = ContentionAnalysis {
+            blocked_gpus: vec![BlockedGpu {
+                node_id: "node-a".to_string(),
+                gpu_index: 0,
+                gpu_name: "NVIDIA A100".to_string(),
+                blocking_processes: vec![proc(42, "alice")],
+                utilization_pct: 90.0,
+                memory_used_mb: 20000,
+                memory_total_mb: 81920,
+            }],
+            top_users: vec![UserUsage {
+                user: "alice".to_string(),
+                gpu_count: 1,
+                total_memory_mb: 20000,
+                avg_utilization: 90.0,
+                process_count: 1,
+            }],
+            recommendations: Vec::new(),
+            maintenance_gpus: vec![MaintenanceGpu {
+                node_id: "node-b".to_string(),
+                gpu_index: 0,
+                gpu_name: "NVIDIA H100".to_string(),
+                message: "driver upgrade".to_string(),
+            }],
+            node_summaries: Vec::new(),
+            idle_gpus: Vec::new(),
+            unhealthy_gpus: vec![UnhealthyGpu {
+                node_id: "node-a".to_string(),
+                gpu_index: 1,
+                gpu_name: "NVIDIA A100".to_string(),
+                ecc_uncorrected: 3,
+                retired_pages: Some(62),
+                rma_flagged: true,
+            }],
+            fair_share_violations: Vec::new(),
+        };
+        (snapshot, contention)
+    }
+
+    #[test]
+    fn test_summarize_cluster() {
+        let (snapshot, contention) = cluster();
+        let text = summarize_cluster(Some(&snapshot), &contention);
+
+        // The H100 is idle but in maintenance, so only one GPU counts as free
+        assert!(text.starts_with("Cluster: 2 nodes, 3 GPUs (1 free)"));
+        assert!(text.contains("- node-a.example (node-a): 2 GPUs, free: GPU 1 (NVIDIA A100)"));
+        assert!(text.contains("- node-b.example (node-b): 1 GPUs, none free"));
+        assert!(text.contains("- alice: 1 GPUs, 20000 MB"));
+        assert!(text.contains("- node-a GPU 0 (NVIDIA A100): 90.0% utilization"));
+        assert!(text.contains("held by alice"));
+        assert!(text.contains("GPUs in maintenance:\n- node-b GPU 0 (NVIDIA H100): driver upgrade"));
+        assert!(text.contains(
+            "GPUs with memory errors:\n- node-a GPU 1 (NVIDIA A100): 3 uncorrected ECC errors, 62 retired pages, flagged for RMA"
+        ));
+
+        let empty = summarize_cluster(None, &contention);
+        assert!(empty.contains("not received any node snapshots"));
+    }
+
+    #[tokio::test]
+    async fn test_cluster_overview_without_coordinator() {
+        let result = cluster_overview(None).await;
+        assert_eq!(result.is_error, Some(true));
+        let text = result.content[0].text.as_deref().unwrap();
+        assert!(text.contains("COORDINATOR_URL"));
+    }
+
+    #[tokio::test]
+    async fn test_cluster_overview_connection_failure_is_error() {
+        // Grab a free port and close it again so the connection is refused
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let client =
+            CoordinatorClient::new(&format!("http://{}/", addr), Duration::from_secs(2)).unwrap();
+        assert_eq!(client.base_url(), format!("http://{}", addr));
+        let result = cluster_overview(Some(&client)).await;
+        assert_eq!(result.is_error, Some(true));
+        assert!(result.content[0]
+            .text
+            .as_deref()
+            .unwrap()
+            .starts_with("Failed to fetch cluster overview"));
+    }
+
+    #[tokio::test]
+    async fn test_cluster_overview_timeout_is_error() {
+        // Accepts connections but never answers
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let client =
+            CoordinatorClient::new(&format!("http://{}", addr), Duration::from_millis(200))
+                .unwrap();
+        let result = cluster_overview(Some(&client)).await;
+        assert_eq!(result.is_error, Some(true));
+        assert!(result.content[0]
+            .text
+            .as_deref()
+            .unwrap()
+            .contains("Timed out"));
+        drop(listener);
+    }
+}
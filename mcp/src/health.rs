@@ -0,0 +1,369 @@
+//! Health report behind the `gpu://health` resource
+//!
+//! Each GPU is rated `ok`, `warn` or `critical` from its temperature, power draw,
+//! ECC errors and retired pages; the report verdict is the worst GPU rating.
+//! Devices that did not answer the snapshot query are listed as degraded.
+
+use chrono::{DateTime, Utc};
+use gpukill::nvml_api::GpuSnapshot;
+use gpukill::vendor::SnapshotCollection;
+use serde::{Deserialize, Serialize};
+
+/// Version of the `gpu://health` body, bumped on incompatible changes
+pub const HEALTH_SCHEMA_VERSION: u32 = 1;
+
+/// Default temperature (°C) at which a GPU is rated `warn`
+pub const DEFAULT_TEMP_WARN_C: i32 = 85;
+
+/// Default temperature (°C) at which a GPU is rated `critical`
+pub const DEFAULT_TEMP_CRITICAL_C: i32 = 95;
+
+/// Health rating of a GPU or of the whole report, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    Ok,
+    Warn,
+    Critical,
+}
+
+/// Limits the readings are checked against. Power is only checked when a
+/// limit is configured, since draw varies widely between GPU models.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthThresholds {
+    pub temp_warn_c: i32,
+    pub temp_critical_c: i32,
+    pub power_warn_w: Option<f32>,
+    pub power_critical_w: Option<f32>,
+}
+
+impl Default for HealthThresholds {
+    fn default() -> Self {
+        Self {
+            temp_warn_c: DEFAULT_TEMP_WARN_C,
+            temp_critical_c: DEFAULT_TEMP_CRITICAL_C,
+            power_warn_w: None,
+            power_critical_w: None,
+        }
+    }
+}
+
+impl HealthThresholds {
+    /// Read thresholds from MCP_HEALTH_TEMP_WARN_C, MCP_HEALTH_TEMP_CRITICAL_C,
+    /// MCP_HEALTH_POWER_WARN_W and MCP_HEALTH_POWER_CRITICAL_W
+    pub fn from_env() -> anyhow::Result<Self> {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    /// Same as [`HealthThresholds::from_env`] with variables read through `lookup`
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> anyhow::Result<Self> {
+        fn parse<T: std::str::FromStr>(
+            lookup: &impl Fn(&str) -> Option<String>,
+            key: &str,
+        ) -> anyhow::Result<Option<T>>
+        where
+            T::Err: std::fmt::Display,
+        {
+            match lookup(key).filter(|v| !v.trim().is_empty()) {
+                Some(value) => value
+                    .trim()
+                    .parse()
+                    .map(Some)
+                    .map_err(|e| anyhow::anyhow!("Invalid {}: {}", key, e)),
+                None => Ok(None),
+            }
+        }
+
+        let defaults = Self::default();
+        Ok(Self {
+            temp_warn_c: parse(&lookup, "MCP_HEALTH_TEMP_WARN_C")?.unwrap_or(defaults.temp_warn_c),
+            temp_critical_c: parse(&lookup, "MCP_HEALTH_TEMP_CRITICAL_C")?
+                .unwrap_or(defaults.temp_critical_c),
+            power_warn_w: parse(&lookup, "MCP_HEALTH_POWER_WARN_W")?,
+            power_critical_w: parse(&lookup, "MCP_HEALTH_POWER_CRITICAL_W")?,
+        })
+    }
+}
+
+/// Health of one GPU
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpuHealth {
+    pub gpu_index: u16,
+    /// Name and vendor are unknown for a device that did not answer
+    pub name: Option<String>,
+    pub vendor: Option<String>,
+    pub status: HealthStatus,
+    /// The device timed out or failed its snapshot query, so readings are missing
+    pub degraded: bool,
+    pub temperature_c: Option<i32>,
+    pub power_w: Option<f32>,
+    pub ecc_corrected: Option<u64>,
+    pub ecc_uncorrected: Option<u64>,
+    pub retired_pages: Option<u32>,
+    pub rma_flagged: Option<bool>,
+    /// Why the GPU is not `ok`, one entry per failed check
+    pub reasons: Vec<String>,
+}
+
+/// Body of the `gpu://health` resource
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub schema_version: u32,
+    pub generated_at: DateTime<Utc>,
+    pub verdict: HealthStatus,
+    /// Some devices did not answer, so the report may be incomplete
+    pub degraded: bool,
+    pub thresholds: HealthThresholds,
+    pub gpus: Vec<GpuHealth>,
+}
+
+impl HealthReport {
+    /// Rate every GPU in `collection` against `thresholds`
+    pub fn from_collection(collection: &SnapshotCollection, thresholds: &HealthThresholds) -> Self {
+        let mut gpus: Vec<GpuHealth> = collection
+            .snapshots
+            .iter()
+            .map(|gpu| gpu_health(gpu, thresholds))
+            .collect();
+
+        for error in &collection.errors {
+            gpus.push(degraded_gpu(
+                error.gpu_index,
+                format!("snapshot query failed: {}", error.error),
+            ));
+        }
+        gpus.sort_by_key(|gpu| gpu.gpu_index);
+
+        let verdict = gpus
+            .iter()
+            .map(|gpu| gpu.status)
+            .max()
+            .unwrap_or(HealthStatus::Ok);
+
+        Self {
+            schema_version: HEALTH_SCHEMA_VERSION,
+            generated_at: Utc::now(),
+            verdict,
+            degraded: collection.is_degraded(),
+            thresholds: thresholds.clone(),
+            gpus,
+        }
+    }
+}
+
+fn degraded_gpu(gpu_index: u16, reason: String) -> GpuHealth {
+    GpuHealth {
+        gpu_index,
+        name: None,
+        vendor: None,
+        status: HealthStatus::Warn,
+        degraded: true,
+        temperature_c: None,
+        power_w: None,
+        ecc_corrected: None,
+        ecc_uncorrected: None,
+        retired_pages: None,
+        rma_flagged: None,
+        reasons: vec![reason],
+    }
+}
+
+fn gpu_health(gpu: &GpuSnapshot, thresholds: &HealthThresholds) -> GpuHealth {
+    let mut status = HealthStatus::Ok;
+    let mut reasons = Vec::new();
+    let mut flag = |level: HealthStatus, reason: String| {
+        status = status.max(level);
+        reasons.push(reason);
+    };
+
+    if gpu.temp_c >= thresholds.temp_critical_c {
+        flag(
+            HealthStatus::Critical,
+            format!(
+                "temperature {}°C at or above critical threshold {}°C",
+                gpu.temp_c, thresholds.temp_critical_c
+            ),
+        );
+    } else if gpu.temp_c >= thresholds.temp_warn_c {
+        flag(
+            HealthStatus::Warn,
+            format!(
+                "temperature {}°C at or above warning threshold {}°C",
+                gpu.temp_c, thresholds.temp_warn_c
+            ),
+        );
+    }
+
+    match (thresholds.power_critical_w, thresholds.power_warn_w) {
+        (Some(critical), _) if gpu.power_w >= critical => flag(
+            HealthStatus::Critical,
+            format!(
+                "power {:.0}W at or above critical threshold {:.0}W",
+                gpu.power_w, critical
+            ),
+        ),
+        (_, Some(warn)) if gpu.power_w >= warn => flag(
+            HealthStatus::Warn,
+            format!(
+                "power {:.0}W at or above warning threshold {:.0}W",
+                gpu.power_w, warn
+            ),
+        ),
+        _ => {}
+    }
+
+    if let Some(ecc) = gpu.ecc_volatile {
+        if ecc.uncorrected > 0 {
+            flag(
+                HealthStatus::Critical,
+                format!("{} uncorrected ECC errors", ecc.uncorrected),
+            );
+        }
+    }
+
+    let rma_flagged = gpu
+        .rma_flagged
+        .or_else(|| gpu.retired_pages.map(|pages| pages.rma_flagged()));
+    if rma_flagged == Some(true) {
+        flag(HealthStatus::Critical, "flagged for RMA".to_string());
+    } else if let Some(pages) = gpu.retired_pages.filter(|pages| pages.pending) {
+        flag(
+            HealthStatus::Warn,
+            format!(
+                "{} retired pages, retirement pending a driver reload",
+                pages.total()
+            ),
+        );
+    }
+
+    GpuHealth {
+        gpu_index: gpu.gpu_index,
+        name: Some(gpu.name.clone()),
+        vendor: Some(gpu.vendor.to_string()),
+        status,
+        degraded: false,
+        temperature_c: Some(gpu.temp_c),
+        power_w: Some(gpu.power_w),
+        ecc_corrected: gpu.ecc_volatile.map(|ecc| ecc.corrected),
+        ecc_uncorrected: gpu.ecc_volatile.map(|ecc| ecc.uncorrected),
+        retired_pages: gpu.retired_pages.map(|pages| pages.total()),
+        rma_flagged,
+        reasons,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpukill::nvml_api::{DeviceError, EccCounts, RetiredPages};
+    use gpukill::vendor::GpuVendor;
+
+    fn snapshot(gpu_index: u16, temp_c: i32, power_w: f32) -> GpuSnapshot {
+        GpuSnapshot {
+            gpu_index,
+            name: "Test GPU".to_string(),
+            vendor: GpuVendor::Nvidia,
+            mem_used_mb: 0,
+            mem_total_mb: 16384,
+            util_pct: 0.0,
+            temp_c,
+            power_w,
+            pids: 0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_health_report_rates_gpus_and_takes_worst_verdict() {
+        let mut ecc = snapshot(2, 60, 200.0);
+        ecc.ecc_volatile = Some(EccCounts {
+            corrected: 4,
+            uncorrected: 1,
+        });
+        let collection = SnapshotCollection {
+            snapshots: vec![snapshot(0, 50, 100.0), snapshot(1, 88, 320.0), ecc],
+            errors: vec![DeviceError::timed_out(
+                3,
+                GpuVendor::Nvidia,
+                std::time::Duration::from_secs(5),
+            )],
+        };
+        let thresholds = HealthThresholds {
+            power_warn_w: Some(300.0),
+            ..HealthThresholds::default()
+        };
+
+        let report = HealthReport::from_collection(&collection, &thresholds);
+        assert_eq!(report.schema_version, HEALTH_SCHEMA_VERSION);
+        assert_eq!(report.verdict, HealthStatus::Critical);
+        assert!(report.degraded);
+
+        let status: Vec<(u16, HealthStatus, bool)> = report
+            .gpus
+            .iter()
+            .map(|gpu| (gpu.gpu_index, gpu.status, gpu.degraded))
+            .collect();
+        assert_eq!(
+            status,
+            vec![
+                (0, HealthStatus::Ok, false),
+                (1, HealthStatus::Warn, false),
+                (2, HealthStatus::Critical, false),
+                (3, HealthStatus::Warn, true),
+            ]
+        );
+        assert_eq!(report.gpus[1].reasons.len(), 2);
+        assert_eq!(report.gpus[2].ecc_uncorrected, Some(1));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["schema_version"], 1);
+        assert_eq!(json["verdict"], "critical");
+        assert_eq!(json["gpus"][3]["status"], "warn");
+    }
+
+    #[test]
+    fn test_health_report_flags_rma_from_retired_pages() {
+        let mut gpu = snapshot(0, 40, 80.0);
+        gpu.retired_pages = Some(RetiredPages {
+            single_bit: 50,
+            double_bit: 10,
+            pending: false,
+        });
+        let collection = SnapshotCollection {
+            snapshots: vec![gpu],
+            ..SnapshotCollection::default()
+        };
+
+        let report = HealthReport::from_collection(&collection, &HealthThresholds::default());
+        assert_eq!(report.verdict, HealthStatus::Critical);
+        assert_eq!(report.gpus[0].rma_flagged, Some(true));
+        assert!(!report.degraded);
+
+        let empty = HealthReport::from_collection(
+            &SnapshotCollection::default(),
+            &HealthThresholds::default(),
+        );
+        assert_eq!(empty.verdict, HealthStatus::Ok);
+    }
+
+    #[test]
+    fn test_health_thresholds_from_lookup() {
+        let thresholds = HealthThresholds::from_lookup(|key| match key {
+            "MCP_HEALTH_TEMP_WARN_C" => Some("80".to_string()),
+            "MCP_HEALTH_POWER_CRITICAL_W" => Some("450".to_string()),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(thresholds.temp_warn_c, 80);
+        assert_eq!(thresholds.temp_critical_c, DEFAULT_TEMP_CRITICAL_C);
+        assert_eq!(thresholds.power_warn_w, None);
+        assert_eq!(thresholds.power_critical_w, Some(450.0));
+
+        let err = HealthThresholds::from_lookup(|key| {
+            (key == "MCP_HEALTH_TEMP_CRITICAL_C").then(|| "hot".to_string())
+        })
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("MCP_HEALTH_TEMP_CRITICAL_C"), "{}", err);
+    }
+}
@@ -4,11 +4,19 @@
 //! enabling AI assistants and other tools to interact with GPU management
 //! functionality through a standardized interface.
 
+pub mod coordinator;
+pub mod health;
+pub mod logging;
+pub mod policies;
 pub mod resources;
 pub mod server;
+pub mod server_policy;
 pub mod tools;
 pub mod types;
 
+use gpukill::report::InfoReport;
+use serde::Serialize;
+
 pub use server::GpuKillMCPServer;
 pub use types::*;
 
@@ -17,3 +25,53 @@ pub const MCP_VERSION: &str = "2024-11-05";
 
 /// GPU Kill MCP Server capabilities
 pub const CAPABILITIES: &[&str] = &["resources", "tools", "logging"];
+
+/// Output of `gpukill-mcp --info`: the `gpukill --info` fields plus the MCP
+/// protocol version and capabilities
+#[derive(Debug, Clone, Serialize)]
+pub struct McpInfo {
+    #[serde(flatten)]
+    pub gpukill: InfoReport,
+    pub mcp_server_version: &'static str,
+    pub mcp_version: &'static str,
+    pub capabilities: &'static [&'static str],
+}
+
+impl McpInfo {
+    /// Info for this server, with `gpukill` from GPU detection on this host
+    pub fn new(gpukill: InfoReport) -> Self {
+        Self {
+            gpukill,
+            mcp_server_version: env!("CARGO_PKG_VERSION"),
+            mcp_version: MCP_VERSION,
+            capabilities: CAPABILITIES,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpukill::vendor::GpuVendor;
+
+    #[test]
+    fn test_mcp_info_json_has_required_keys() {
+        let info = McpInfo::new(InfoReport::new(vec![GpuVendor::Amd], 1, None));
+        let json: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string(&info).unwrap()).unwrap();
+        for key in [
+            "version",
+            "version_string",
+            "mcp_server_version",
+            "mcp_version",
+            "capabilities",
+            "vendors",
+            "gpu_count",
+        ] {
+            assert!(json.get(key).is_some(), "missing {}", key);
+        }
+        assert_eq!(json["mcp_version"], MCP_VERSION);
+        assert_eq!(json["capabilities"][0], "resources");
+        assert_eq!(json["gpu_count"], 1);
+    }
+}
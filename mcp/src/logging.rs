@@ -0,0 +1,136 @@
+//! Logging setup for the MCP server
+//!
+//! `MCP_LOG_FORMAT` picks the same formats as `gpukill --log-format`: `text`
+//! (or `human`) for readable lines, `json` for one object per event.
+
+use gpukill::args::LogFormat;
+use std::env;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::EnvFilter;
+
+/// Read the log format from MCP_LOG_FORMAT, defaulting to text
+pub fn log_format_from_env() -> anyhow::Result<LogFormat> {
+    log_format_from_lookup(|key| env::var(key).ok())
+}
+
+/// Same as [`log_format_from_env`] with variables read through `lookup`
+pub fn log_format_from_lookup(
+    lookup: impl Fn(&str) -> Option<String>,
+) -> anyhow::Result<LogFormat> {
+    match lookup("MCP_LOG_FORMAT").filter(|v| !v.trim().is_empty()) {
+        Some(value) => value
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid MCP_LOG_FORMAT: {}", e)),
+        None => Ok(LogFormat::Human),
+    }
+}
+
+/// Install the global logger for `format`, filtered by RUST_LOG
+pub fn init_logging(format: LogFormat) -> anyhow::Result<()> {
+    let filter = EnvFilter::from_default_env();
+    match format {
+        LogFormat::Human => tracing_subscriber::fmt().with_env_filter(filter).init(),
+        LogFormat::Json => {
+            tracing::subscriber::set_global_default(json_subscriber(filter, std::io::stdout))
+                .map_err(|e| anyhow::anyhow!("Failed to install JSON logger: {}", e))?
+        }
+    }
+    Ok(())
+}
+
+/// JSON logger: one object per event with `timestamp`, `level` and the event
+/// fields at the top level
+pub fn json_subscriber<W>(filter: EnvFilter, writer: W) -> impl tracing::Subscriber + Send + Sync
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt()
+        .json()
+        .with_env_filter(filter)
+        .with_writer(writer)
+        .with_current_span(true)
+        .with_span_list(false)
+        .flatten_event(true)
+        .finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for CapturedLogs {
+        type Writer = CapturedLogs;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    #[test]
+    fn test_log_format_from_lookup() {
+        let lookup = |value: Option<&'static str>| {
+            move |key: &str| {
+                assert_eq!(key, "MCP_LOG_FORMAT");
+                value.map(str::to_string)
+            }
+        };
+        assert_eq!(
+            log_format_from_lookup(lookup(None)).unwrap(),
+            LogFormat::Human
+        );
+        assert_eq!(
+            log_format_from_lookup(lookup(Some(""))).unwrap(),
+            LogFormat::Human
+        );
+        assert_eq!(
+            log_format_from_lookup(lookup(Some("text"))).unwrap(),
+            LogFormat::Human
+        );
+        assert_eq!(
+            log_format_from_lookup(lookup(Some("JSON"))).unwrap(),
+            LogFormat::Json
+        );
+
+        let err = log_format_from_lookup(lookup(Some("xml")))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("MCP_LOG_FORMAT"), "{}", err);
+    }
+
+    #[test]
+    fn test_json_subscriber_writes_timestamp_and_level() {
+        let logs = CapturedLogs::default();
+        let subscriber = json_subscriber(EnvFilter::new("info"), logs.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(port = 3001, "Starting GPU Kill MCP Server");
+            tracing::debug!("filtered out");
+        });
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let events: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["level"], "INFO");
+        assert_eq!(events[0]["message"], "Starting GPU Kill MCP Server");
+        assert_eq!(events[0]["port"], 3001);
+        assert!(events[0]["timestamp"].is_string());
+    }
+}
@@ -1,15 +1,23 @@
 //! GPU Kill MCP Server - Main entry point
 
-use gpukill_mcp::GpuKillMCPServer;
+use gpukill::report::InfoReport;
+use gpukill_mcp::{logging, GpuKillMCPServer, McpInfo};
 use std::env;
 use tracing::{error, info};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .init();
+    // `--info` prints a JSON probe for orchestration tooling and exits
+    if env::args().skip(1).any(|arg| arg == "--info") {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&McpInfo::new(InfoReport::detect()))?
+        );
+        return Ok(());
+    }
+
+    // Initialize logging (MCP_LOG_FORMAT=json for one JSON object per event)
+    logging::init_logging(logging::log_format_from_env()?)?;
 
     info!("Starting GPU Kill MCP Server");
 
@@ -32,6 +40,8 @@ async fn main() -> anyhow::Result<()> {
     info!("  - gpu://audit - Historical GPU usage data");
     info!("  - gpu://policies - Current Guard Mode policies");
     info!("  - gpu://rogue-detection - Security scan results");
+    info!("  - gpu://health - Per-GPU health and overall verdict");
+    info!("  - gpu://cluster - Cluster snapshot from the coordinator (COORDINATOR_URL)");
 
     info!("Available tools:");
     info!("  - kill_gpu_process - Kill a GPU process by PID");
@@ -40,6 +50,10 @@ async fn main() -> anyhow::Result<()> {
     info!("  - create_user_policy - Create a user policy for Guard Mode");
     info!("  - get_gpu_status - Get detailed status of a specific GPU");
     info!("  - kill_processes_by_name - Kill all processes matching a name pattern");
+    info!("  - get_server_policy - Report disabled tools and dry-run/confirm requirements");
+    info!(
+        "  - get_cluster_overview - Cluster-wide overview from the coordinator (COORDINATOR_URL)"
+    );
 
     // Start the server
     if let Err(e) = server.start(host.as_str(), port).await {
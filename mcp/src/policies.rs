@@ -0,0 +1,424 @@
+//! Guard Mode policy management tools
+
+use crate::types::{ToolContent, ToolResult};
+use gpukill::guard_mode::GuardModeManager;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Tool arguments as received from the MCP client
+pub type ToolArgs = HashMap<String, Value>;
+
+const GUARD_UNAVAILABLE: &str =
+    "Guard Mode not available: the Guard Mode configuration could not be loaded";
+
+pub(crate) fn error_result(message: String) -> ToolResult {
+    ToolResult {
+        content: vec![ToolContent {
+            content_type: "text".to_string(),
+            text: Some(message),
+            data: None,
+        }],
+        is_error: Some(true),
+    }
+}
+
+/// Successful result carrying where and when the configuration was last written
+fn config_result(guard_mode: &GuardModeManager, text: String, mut data: Value) -> ToolResult {
+    let config = guard_mode.get_config();
+    data["config_path"] = json!(guard_mode.get_config_file_path().display().to_string());
+    data["last_modified"] = json!(config.metadata.last_modified);
+    data["enabled"] = json!(config.global.enabled);
+    data["dry_run"] = json!(config.global.dry_run);
+
+    ToolResult {
+        content: vec![ToolContent {
+            content_type: "text".to_string(),
+            text: Some(format!(
+                "{} (config: {}, last modified {})",
+                text,
+                guard_mode.get_config_file_path().display(),
+                config.metadata.last_modified
+            )),
+            data: Some(data),
+        }],
+        is_error: Some(false),
+    }
+}
+
+fn hard_enforcement_refused(change: &str) -> ToolResult {
+    error_result(format!(
+        "{} would turn on hard enforcement: Guard Mode will terminate processes that violate \
+         policies. Repeat the call with \"confirm\": true to apply it.",
+        change
+    ))
+}
+
+/// Run the `list_policies` tool
+pub fn list_policies(guard_mode: Option<&GuardModeManager>) -> ToolResult {
+    let Some(guard_mode) = guard_mode else {
+        return error_result(GUARD_UNAVAILABLE.to_string());
+    };
+    let config = guard_mode.get_config();
+
+    let mut user_policies: Vec<_> = config.user_policies.values().collect();
+    user_policies.sort_by(|a, b| a.username.cmp(&b.username));
+    let mut group_policies: Vec<_> = config.group_policies.values().collect();
+    group_policies.sort_by(|a, b| a.group_name.cmp(&b.group_name));
+    let mut gpu_policies: Vec<_> = config.gpu_policies.values().collect();
+    gpu_policies.sort_by_key(|p| p.gpu_index);
+
+    let mut lines = vec![format!(
+        "Guard Mode is {} ({})",
+        if config.global.enabled {
+            "enabled"
+        } else {
+            "disabled"
+        },
+        if config.global.dry_run {
+            "dry-run"
+        } else {
+            "enforcing"
+        }
+    )];
+    lines.push(format!("User policies: {}", user_policies.len()));
+    for policy in &user_policies {
+        lines.push(format!(
+            "- {}: {}GB memory, {}% utilization, {} processes",
+            policy.username,
+            policy.memory_limit_gb,
+            policy.utilization_limit_pct,
+            policy.max_concurrent_processes
+        ));
+    }
+    lines.push(format!("Group policies: {}", group_policies.len()));
+    for policy in &group_policies {
+        lines.push(format!(
+            "- {}: {}GB memory, {}% utilization, {} processes, {} members",
+            policy.group_name,
+            policy.total_memory_limit_gb,
+            policy.total_utilization_limit_pct,
+            policy.max_concurrent_processes,
+            policy.members.len()
+        ));
+    }
+    lines.push(format!("GPU policies: {}", gpu_policies.len()));
+    for policy in &gpu_policies {
+        lines.push(format!(
+            "- GPU {}: {}GB memory, {}% utilization, {}GB reserved",
+            policy.gpu_index,
+            policy.max_memory_gb,
+            policy.max_utilization_pct,
+            policy.reserved_memory_gb
+        ));
+    }
+
+    config_result(
+        guard_mode,
+        lines.join("\n"),
+        json!({
+            "user_policies": user_policies,
+            "group_policies": group_policies,
+            "gpu_policies": gpu_policies,
+        }),
+    )
+}
+
+/// Run the `remove_user_policy` tool
+pub fn remove_user_policy(
+    guard_mode: Option<&mut GuardModeManager>,
+    args: &ToolArgs,
+) -> ToolResult {
+    let Some(guard_mode) = guard_mode else {
+        return error_result(GUARD_UNAVAILABLE.to_string());
+    };
+    let Some(username) = args.get("username").and_then(|v| v.as_str()) else {
+        return error_result("Missing username".to_string());
+    };
+
+    let Some(removed) = guard_mode.get_config().user_policies.get(username).cloned() else {
+        return error_result(format!("No user policy exists for {}", username));
+    };
+    if let Err(e) = guard_mode.remove_user_policy(username) {
+        return error_result(format!("Failed to remove user policy: {}", e));
+    }
+
+    config_result(
+        guard_mode,
+        format!("Removed user policy for {}", username),
+        json!({ "removed": removed }),
+    )
+}
+
+/// Run the `update_user_policy` tool. Only the limits present in `args` change.
+pub fn update_user_policy(
+    guard_mode: Option<&mut GuardModeManager>,
+    args: &ToolArgs,
+) -> ToolResult {
+    let Some(guard_mode) = guard_mode else {
+        return error_result(GUARD_UNAVAILABLE.to_string());
+    };
+    let Some(username) = args.get("username").and_then(|v| v.as_str()) else {
+        return error_result("Missing username".to_string());
+    };
+    let Some(mut policy) = guard_mode.get_config().user_policies.get(username).cloned() else {
+        return error_result(format!(
+            "No user policy exists for {}; use create_user_policy first",
+            username
+        ));
+    };
+
+    let number = |key: &str| -> Result<Option<f64>, String> {
+        match args.get(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => value
+                .as_f64()
+                .map(Some)
+                .ok_or_else(|| format!("Invalid {}: expected a number", key)),
+        }
+    };
+    let (memory_limit, utilization_limit, process_limit) = match (
+        number("memory_limit_gb"),
+        number("utilization_limit_pct"),
+        number("process_limit"),
+    ) {
+        (Ok(m), Ok(u), Ok(p)) => (m, u, p),
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => return error_result(e),
+    };
+
+    if memory_limit.is_none() && utilization_limit.is_none() && process_limit.is_none() {
+        return error_result(
+            "Nothing to update: pass memory_limit_gb, utilization_limit_pct or process_limit"
+                .to_string(),
+        );
+    }
+    if let Some(memory) = memory_limit {
+        if memory <= 0.0 {
+            return error_result("memory_limit_gb must be greater than 0".to_string());
+        }
+        policy.memory_limit_gb = memory as f32;
+    }
+    if let Some(utilization) = utilization_limit {
+        if !(0.0..=100.0).contains(&utilization) {
+            return error_result("utilization_limit_pct must be between 0 and 100".to_string());
+        }
+        policy.utilization_limit_pct = utilization as f32;
+    }
+    if let Some(processes) = process_limit {
+        if processes < 1.0 || processes.fract() != 0.0 {
+            return error_result("process_limit must be a positive integer".to_string());
+        }
+        policy.max_concurrent_processes = processes as u32;
+    }
+
+    if let Err(e) = guard_mode.add_user_policy(policy.clone()) {
+        return error_result(format!("Failed to update user policy: {}", e));
+    }
+
+    config_result(
+        guard_mode,
+        format!(
+            "Updated user policy for {}: {}GB memory, {}% utilization, {} processes",
+            username,
+            policy.memory_limit_gb,
+            policy.utilization_limit_pct,
+            policy.max_concurrent_processes
+        ),
+        json!({ "policy": policy }),
+    )
+}
+
+/// Run the `set_guard_enabled` tool. Enabling Guard Mode outside dry-run requires `confirm`.
+pub fn set_guard_enabled(guard_mode: Option<&mut GuardModeManager>, args: &ToolArgs) -> ToolResult {
+    let Some(guard_mode) = guard_mode else {
+        return error_result(GUARD_UNAVAILABLE.to_string());
+    };
+    let Some(enabled) = args.get("enabled").and_then(|v| v.as_bool()) else {
+        return error_result("Missing or invalid enabled".to_string());
+    };
+    let confirm = args
+        .get("confirm")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let global = &guard_mode.get_config().global;
+    if enabled && !global.enabled && !global.dry_run && !confirm {
+        return hard_enforcement_refused("Enabling Guard Mode with dry-run off");
+    }
+    if let Err(e) = guard_mode.set_enabled(enabled) {
+        return error_result(format!("Failed to update Guard Mode: {}", e));
+    }
+
+    config_result(
+        guard_mode,
+        format!(
+            "Guard Mode {}",
+            if enabled { "enabled" } else { "disabled" }
+        ),
+        json!({}),
+    )
+}
+
+/// Run the `set_guard_dry_run` tool. Turning dry-run off while enabled requires `confirm`.
+pub fn set_guard_dry_run(guard_mode: Option<&mut GuardModeManager>, args: &ToolArgs) -> ToolResult {
+    let Some(guard_mode) = guard_mode else {
+        return error_result(GUARD_UNAVAILABLE.to_string());
+    };
+    let Some(dry_run) = args.get("dry_run").and_then(|v| v.as_bool()) else {
+        return error_result("Missing or invalid dry_run".to_string());
+    };
+    let confirm = args
+        .get("confirm")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+
+    let global = &guard_mode.get_config().global;
+    if !dry_run && global.dry_run && global.enabled && !confirm {
+        return hard_enforcement_refused("Turning off dry-run while Guard Mode is enabled");
+    }
+    if let Err(e) = guard_mode.set_dry_run(dry_run) {
+        return error_result(format!("Failed to update Guard Mode: {}", e));
+    }
+
+    config_result(
+        guard_mode,
+        format!(
+            "Guard Mode dry-run {}",
+            if dry_run { "enabled" } else { "disabled" }
+        ),
+        json!({}),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpukill::guard_mode::{GuardModeConfig, UserPolicy};
+
+    fn manager(dir: &tempfile::TempDir) -> GuardModeManager {
+        let mut config = GuardModeConfig::default();
+        config.user_policies.insert(
+            "alice".to_string(),
+            UserPolicy {
+                username: "alice".to_string(),
+                memory_limit_gb: 8.0,
+                utilization_limit_pct: 70.0,
+                duration_limit_hours: 24.0,
+                max_concurrent_processes: 3,
+                priority: 0,
+                allowed_gpus: Vec::new(),
+                blocked_gpus: Vec::new(),
+                time_overrides: Vec::new(),
+            },
+        );
+        GuardModeManager::with_config(dir.path().join("guard_mode_config.toml"), config)
+    }
+
+    fn args(value: Value) -> ToolArgs {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn data(result: &ToolResult) -> &Value {
+        result.content[0].data.as_ref().unwrap()
+    }
+
+    #[test]
+    fn test_guard_mode_unavailable() {
+        assert_eq!(list_policies(None).is_error, Some(true));
+        let result = remove_user_policy(None, &args(json!({"username": "alice"})));
+        assert_eq!(result.is_error, Some(true));
+        assert!(result.content[0]
+            .text
+            .as_ref()
+            .unwrap()
+            .contains("Guard Mode not available"));
+    }
+
+    #[test]
+    fn test_list_policies_reports_config_location() {
+        let dir = tempfile::tempdir().unwrap();
+        let guard_mode = manager(&dir);
+
+        let result = list_policies(Some(&guard_mode));
+        assert_eq!(result.is_error, Some(false));
+        let data = data(&result);
+        assert_eq!(data["user_policies"][0]["username"], "alice");
+        assert_eq!(data["user_policies"][0]["memory_limit_gb"], 8.0);
+        assert!(data["config_path"]
+            .as_str()
+            .unwrap()
+            .ends_with("guard_mode_config.toml"));
+        assert!(data["last_modified"].is_string());
+    }
+
+    #[test]
+    fn test_update_user_policy_is_partial() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut guard_mode = manager(&dir);
+
+        let result = update_user_policy(
+            Some(&mut guard_mode),
+            &args(json!({"username": "alice", "memory_limit_gb": 16.0})),
+        );
+        assert_eq!(result.is_error, Some(false));
+        let policy = &guard_mode.get_config().user_policies["alice"];
+        assert_eq!(policy.memory_limit_gb, 16.0);
+        assert_eq!(policy.utilization_limit_pct, 70.0);
+        assert_eq!(policy.max_concurrent_processes, 3);
+        assert!(dir.path().join("guard_mode_config.toml").exists());
+
+        let nothing =
+            update_user_policy(Some(&mut guard_mode), &args(json!({"username": "alice"})));
+        assert_eq!(nothing.is_error, Some(true));
+        let invalid = update_user_policy(
+            Some(&mut guard_mode),
+            &args(json!({"username": "alice", "utilization_limit_pct": 150})),
+        );
+        assert_eq!(invalid.is_error, Some(true));
+        let unknown = update_user_policy(
+            Some(&mut guard_mode),
+            &args(json!({"username": "bob", "process_limit": 2})),
+        );
+        assert_eq!(unknown.is_error, Some(true));
+    }
+
+    #[test]
+    fn test_remove_user_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut guard_mode = manager(&dir);
+
+        let result = remove_user_policy(Some(&mut guard_mode), &args(json!({"username": "alice"})));
+        assert_eq!(result.is_error, Some(false));
+        assert_eq!(data(&result)["removed"]["username"], "alice");
+        assert!(guard_mode.get_config().user_policies.is_empty());
+
+        let again = remove_user_policy(Some(&mut guard_mode), &args(json!({"username": "alice"})));
+        assert_eq!(again.is_error, Some(true));
+    }
+
+    #[test]
+    fn test_hard_enforcement_requires_confirm() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut guard_mode = manager(&dir);
+
+        // Enabling in dry-run mode is safe
+        let result = set_guard_enabled(Some(&mut guard_mode), &args(json!({"enabled": true})));
+        assert_eq!(result.is_error, Some(false));
+        assert!(guard_mode.get_config().global.enabled);
+
+        let refused = set_guard_dry_run(Some(&mut guard_mode), &args(json!({"dry_run": false})));
+        assert_eq!(refused.is_error, Some(true));
+        assert!(guard_mode.get_config().global.dry_run);
+
+        let confirmed = set_guard_dry_run(
+            Some(&mut guard_mode),
+            &args(json!({"dry_run": false, "confirm": true})),
+        );
+        assert_eq!(confirmed.is_error, Some(false));
+        assert_eq!(data(&confirmed)["dry_run"], false);
+
+        set_guard_enabled(Some(&mut guard_mode), &args(json!({"enabled": false})));
+        let refused = set_guard_enabled(Some(&mut guard_mode), &args(json!({"enabled": true})));
+        assert_eq!(refused.is_error, Some(true));
+        assert!(!guard_mode.get_config().global.enabled);
+    }
+}
@@ -1,5 +1,7 @@
 //! MCP Resources for GPU Kill
 
+use crate::coordinator::CoordinatorClient;
+use crate::health::{HealthReport, HealthThresholds};
 use crate::types::*;
 use gpukill::audit::AuditManager;
 use gpukill::guard_mode::GuardModeManager;
@@ -7,39 +9,79 @@ use gpukill::rogue_detection::RogueDetector;
 use gpukill::vendor::GpuManager;
 use serde_json::json;
 use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Version of the `gpu://cluster` body, bumped on incompatible changes
+pub const CLUSTER_SCHEMA_VERSION: u32 = 1;
+
+/// How long `gpu://health` and `gpu://cluster` bodies are reused
+pub const RESOURCE_CACHE_TTL: Duration = Duration::from_secs(5);
+
+/// Recently generated resource bodies by URI, so clients polling a resource
+/// do not query every GPU (or the coordinator) on each read
+pub struct ResourceCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, String)>>,
+}
+
+impl ResourceCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Cached body for `uri`, if it is younger than the TTL
+    pub fn get(&self, uri: &str) -> Option<String> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .get(uri)
+            .filter(|(stored, _)| stored.elapsed() < self.ttl)
+            .map(|(_, body)| body.clone())
+    }
+
+    pub fn insert(&self, uri: &str, body: String) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(uri.to_string(), (Instant::now(), body));
+    }
+}
 
 /// Resource handler for GPU Kill MCP server
 pub struct ResourceHandler {
     gpu_manager: GpuManager,
     guard_mode: Option<GuardModeManager>,
     rogue_detector: Option<RogueDetector>,
-    audit_manager: Option<AuditManager>,
+    coordinator: Option<CoordinatorClient>,
+    health_thresholds: HealthThresholds,
+    cache: ResourceCache,
 }
 
 impl ResourceHandler {
     pub async fn new() -> anyhow::Result<Self> {
-        let gpu_manager = GpuManager::initialize()?;
+        let gpu_manager = GpuManager::initialize()?.with_stable_indices();
 
         // Initialize optional components
         let guard_mode = GuardModeManager::new().ok();
         let audit_manager = AuditManager::new().await.ok();
-        let rogue_detector = if let Some(am) = audit_manager {
-            Some(RogueDetector::new(am))
-        } else {
-            None
-        };
+        let rogue_detector = audit_manager.map(RogueDetector::new);
 
         Ok(Self {
             gpu_manager,
             guard_mode,
             rogue_detector,
-            audit_manager: None, // We moved it to rogue_detector
+            coordinator: CoordinatorClient::from_env(),
+            health_thresholds: HealthThresholds::from_env()?,
+            cache: ResourceCache::new(RESOURCE_CACHE_TTL),
         })
     }
 
-    /// List all available resources
+    /// List all available resources; `gpu://cluster` only when a coordinator is configured
     pub fn list_resources(&self) -> Vec<Resource> {
-        vec![
+        let mut resources = vec![
             Resource {
                 uri: "gpu://list".to_string(),
                 name: "GPU List".to_string(),
@@ -70,7 +112,31 @@ impl ResourceHandler {
                 description: Some("Security scan results and threats".to_string()),
                 mime_type: Some("application/json".to_string()),
             },
-        ]
+            Resource {
+                uri: "gpu://health".to_string(),
+                name: "GPU Health".to_string(),
+                description: Some(
+                    "Per-GPU health (temperature and power against thresholds, ECC errors, \
+                     degraded devices) with an overall ok/warn/critical verdict"
+                        .to_string(),
+                ),
+                mime_type: Some("application/json".to_string()),
+            },
+        ];
+
+        if let Some(coordinator) = &self.coordinator {
+            resources.push(Resource {
+                uri: "gpu://cluster".to_string(),
+                name: "Cluster Snapshot".to_string(),
+                description: Some(format!(
+                    "Cluster-wide snapshot from the coordinator at {}",
+                    coordinator.base_url()
+                )),
+                mime_type: Some("application/json".to_string()),
+            });
+        }
+
+        resources
     }
 
     /// Get resource contents by URI
@@ -81,13 +147,58 @@ impl ResourceHandler {
             "gpu://audit" => self.get_audit_data().await,
             "gpu://policies" => self.get_policies().await,
             "gpu://rogue-detection" => self.get_rogue_detection().await,
+            "gpu://health" => self.cached(uri, self.get_health_json()).await,
+            "gpu://cluster" => self.cached(uri, self.get_cluster_json()).await,
             _ => Err(anyhow::anyhow!("Unknown resource URI: {}", uri)),
         }
     }
 
+    /// Serve `uri` from the cache, or generate its body with `generate` and cache it
+    async fn cached(
+        &self,
+        uri: &str,
+        generate: impl std::future::Future<Output = anyhow::Result<String>>,
+    ) -> anyhow::Result<ResourceContents> {
+        let json_text = match self.cache.get(uri) {
+            Some(body) => body,
+            None => {
+                let body = generate.await?;
+                self.cache.insert(uri, body.clone());
+                body
+            }
+        };
+
+        Ok(ResourceContents {
+            uri: uri.to_string(),
+            mime_type: Some("application/json".to_string()),
+            text: Some(json_text),
+            blob: None,
+        })
+    }
+
+    async fn get_health_json(&self) -> anyhow::Result<String> {
+        let collection = self.gpu_manager.collect_snapshots_async().await?;
+        let report = HealthReport::from_collection(&collection, &self.health_thresholds);
+        Ok(serde_json::to_string_pretty(&report)?)
+    }
+
+    async fn get_cluster_json(&self) -> anyhow::Result<String> {
+        let coordinator = self.coordinator.as_ref().ok_or_else(|| {
+            anyhow::anyhow!("gpu://cluster needs a coordinator; set COORDINATOR_URL")
+        })?;
+        let snapshot = coordinator.fetch_snapshot().await?;
+        let body = json!({
+            "schema_version": CLUSTER_SCHEMA_VERSION,
+            "generated_at": chrono::Utc::now(),
+            "coordinator_url": coordinator.base_url(),
+            "snapshot": snapshot,
+        });
+        Ok(serde_json::to_string_pretty(&body)?)
+    }
+
     async fn get_gpu_list(&self) -> anyhow::Result<ResourceContents> {
-        let gpus = self.gpu_manager.get_all_snapshots()?;
-        let processes = self.gpu_manager.get_all_processes()?;
+        let gpus = self.gpu_manager.get_all_snapshots_async().await?;
+        let processes = self.gpu_manager.get_all_processes_async().await?;
         let mut processes_by_gpu: HashMap<u16, Vec<GpuProcess>> = HashMap::new();
 
         for proc in processes {
@@ -108,6 +219,7 @@ impl ResourceHandler {
                 id: gpu.gpu_index as u32,
                 name: gpu.name,
                 vendor: gpu.vendor.to_string(),
+                uuid: gpu.uuid,
                 memory_used: gpu.mem_used_mb as f64,
                 memory_total: gpu.mem_total_mb as f64,
                 utilization: gpu.util_pct as f64,
@@ -131,7 +243,7 @@ impl ResourceHandler {
     }
 
     async fn get_gpu_processes(&self) -> anyhow::Result<ResourceContents> {
-        let processes = self.gpu_manager.get_all_processes()?;
+        let processes = self.gpu_manager.get_all_processes_async().await?;
         let all_processes: Vec<GpuProcess> = processes
             .into_iter()
             .map(|proc| GpuProcess {
@@ -273,7 +385,12 @@ impl ResourceHandler {
                     threat_type: "data_exfiltrator".to_string(),
                     severity: "high".to_string(),
                     confidence: threat.confidence as f64,
-                    description: format!("Data exfiltrator: {}", threat.process.proc_name),
+                    description: format!(
+                        "Data exfiltrator: {} ({:.0} MB/s for {:.1} min)",
+                        threat.process.proc_name,
+                        threat.observed_mb_per_sec,
+                        threat.duration_minutes
+                    ),
                     process_info: Some(GpuProcess {
                         pid: threat.process.pid,
                         name: threat.process.proc_name,
@@ -303,3 +420,21 @@ impl ResourceHandler {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resource_cache_expires_after_ttl() {
+        let cache = ResourceCache::new(Duration::from_millis(50));
+        assert_eq!(cache.get("gpu://health"), None);
+
+        cache.insert("gpu://health", "{}".to_string());
+        assert_eq!(cache.get("gpu://health").as_deref(), Some("{}"));
+        assert_eq!(cache.get("gpu://cluster"), None);
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(cache.get("gpu://health"), None);
+    }
+}
@@ -6,11 +6,22 @@ use crate::types::*;
 use crate::MCP_VERSION;
 use anyhow::Result;
 use axum::response::IntoResponse;
+use futures_util::future::join_all;
 use serde_json::json;
+use std::future::Future;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{debug, error, info};
 
+/// Tools that only read state; consecutive calls to them in a batch run concurrently
+const READ_ONLY_TOOLS: &[&str] = &[
+    "get_gpu_status",
+    "list_policies",
+    "scan_rogue_activity",
+    "get_cluster_overview",
+    "get_server_policy",
+];
+
 /// GPU Kill MCP Server
 pub struct GpuKillMCPServer {
     resource_handler: Arc<ResourceHandler>,
@@ -66,6 +77,37 @@ impl GpuKillMCPServer {
         }
     }
 
+    /// Handle an HTTP payload: a single request or a JSON-RPC batch array.
+    /// Returns `None` when nothing should be sent back (notifications only).
+    pub async fn handle_payload(&self, payload: serde_json::Value) -> Option<serde_json::Value> {
+        match payload {
+            serde_json::Value::Array(items) => {
+                let responses = process_batch(items, |request| async move {
+                    self.handle_request(request).await.unwrap_or_else(|e| {
+                        error!("Failed to handle batched request: {}", e);
+                        None
+                    })
+                })
+                .await;
+                match responses {
+                    Some(responses) if responses.is_empty() => None,
+                    Some(responses) => Some(json!(responses)),
+                    None => Some(json!(invalid_request(RequestId::Null, "Empty batch"))),
+                }
+            }
+            payload => match parse_request(payload) {
+                Ok(request) => match self.handle_request(request).await {
+                    Ok(response) => response.map(|r| json!(r)),
+                    Err(e) => {
+                        error!("Failed to handle request: {}", e);
+                        None
+                    }
+                },
+                Err(response) => Some(json!(response)),
+            },
+        }
+    }
+
     async fn handle_initialize(
         &self,
         _params: Option<serde_json::Value>,
@@ -156,18 +198,12 @@ impl GpuKillMCPServer {
                 "/mcp",
                 axum::routing::post({
                     let server = server.clone();
-                    move |request: axum::extract::Json<JsonRpcRequest>| {
+                    move |payload: axum::extract::Json<serde_json::Value>| {
                         let server = server.clone();
                         async move {
-                            match server.handle_request(request.0).await {
-                                Ok(Some(response)) => {
-                                    axum::response::Json(response).into_response()
-                                }
-                                Ok(None) => axum::http::StatusCode::NO_CONTENT.into_response(),
-                                Err(e) => {
-                                    error!("Failed to handle HTTP request: {}", e);
-                                    axum::http::StatusCode::INTERNAL_SERVER_ERROR.into_response()
-                                }
+                            match server.handle_payload(payload.0).await {
+                                Some(response) => axum::response::Json(response).into_response(),
+                                None => axum::http::StatusCode::NO_CONTENT.into_response(),
                             }
                         }
                     }
@@ -184,3 +220,207 @@ impl GpuKillMCPServer {
 }
 
 // Remove Default implementation since new() is now async
+
+/// Parse one request, or build the JSON-RPC "Invalid Request" response for it
+fn parse_request(
+    value: serde_json::Value,
+) -> std::result::Result<JsonRpcRequest, Box<JsonRpcResponse>> {
+    let id = value
+        .get("id")
+        .and_then(|id| serde_json::from_value(id.clone()).ok())
+        .unwrap_or_default();
+    serde_json::from_value(value).map_err(|e| Box::new(invalid_request(id, &e.to_string())))
+}
+
+fn invalid_request(id: RequestId, details: &str) -> JsonRpcResponse {
+    JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        id,
+        result: None,
+        error: Some(JsonRpcError {
+            code: -32600,
+            message: "Invalid Request".to_string(),
+            data: Some(json!({ "details": details })),
+        }),
+    }
+}
+
+/// Whether a request changes state and must not overlap with other requests
+fn is_mutating(request: &JsonRpcRequest) -> bool {
+    if request.method != "tools/call" {
+        return false;
+    }
+    let tool = request
+        .params
+        .as_ref()
+        .and_then(|p| p.get("name"))
+        .and_then(|n| n.as_str())
+        .unwrap_or_default();
+    !READ_ONLY_TOOLS.contains(&tool)
+}
+
+/// Run a JSON-RPC batch through `handle` and collect the responses in request order.
+/// Runs of read-only requests are handled concurrently; each mutating tool call runs
+/// on its own after everything before it has finished. Notifications produce no
+/// response. Returns `None` for an empty batch, which is itself an invalid request.
+pub async fn process_batch<F, Fut>(
+    items: Vec<serde_json::Value>,
+    handle: F,
+) -> Option<Vec<JsonRpcResponse>>
+where
+    F: Fn(JsonRpcRequest) -> Fut,
+    Fut: Future<Output = Option<JsonRpcResponse>>,
+{
+    if items.is_empty() {
+        return None;
+    }
+    debug!("Handling MCP batch of {} requests", items.len());
+
+    let mut responses = Vec::new();
+    let mut concurrent = Vec::new();
+    for item in items {
+        let request = match parse_request(item) {
+            Ok(request) => request,
+            Err(response) => {
+                responses.extend(join_all(concurrent.drain(..)).await.into_iter().flatten());
+                responses.push(*response);
+                continue;
+            }
+        };
+        if is_mutating(&request) {
+            responses.extend(join_all(concurrent.drain(..)).await.into_iter().flatten());
+            responses.extend(handle(request).await);
+        } else {
+            concurrent.push(handle(request));
+        }
+    }
+    responses.extend(join_all(concurrent).await.into_iter().flatten());
+    Some(responses)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// Answers with the method and tool name, finishing earlier requests last
+    async fn fake_handler(
+        request: JsonRpcRequest,
+        log: &Mutex<Vec<String>>,
+    ) -> Option<JsonRpcResponse> {
+        let tool = request
+            .params
+            .as_ref()
+            .and_then(|p| p.get("name"))
+            .and_then(|n| n.as_str())
+            .map(str::to_string);
+        let delay = match request.method.as_str() {
+            "tools/list" => 30,
+            _ => 0,
+        };
+        tokio::time::sleep(Duration::from_millis(delay)).await;
+        let label = tool.unwrap_or_else(|| request.method.clone());
+        log.lock().unwrap().push(label.clone());
+        request.id.map(|id| JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id,
+            result: Some(json!({ "handled": label })),
+            error: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_batch_preserves_order_and_ids() {
+        let batch = vec![
+            json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list", "params": {}}),
+            json!({"jsonrpc": "2.0", "id": "status", "method": "tools/call",
+                   "params": {"name": "get_gpu_status", "arguments": {}}}),
+            json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+        ];
+        let log = Mutex::new(Vec::new());
+        let responses = process_batch(batch, |r| fake_handler(r, &log))
+            .await
+            .unwrap();
+
+        // The slow tools/list finished last but is still answered first
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["get_gpu_status", "notifications/initialized", "tools/list"]
+        );
+        // The notification gets no response
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0].id, RequestId::Number(1));
+        assert_eq!(responses[0].result, Some(json!({"handled": "tools/list"})));
+        assert_eq!(responses[1].id, RequestId::String("status".to_string()));
+        assert_eq!(
+            responses[1].result,
+            Some(json!({"handled": "get_gpu_status"}))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_runs_mutating_tools_serially() {
+        let batch = vec![
+            json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"}),
+            json!({"jsonrpc": "2.0", "id": 2, "method": "tools/call",
+                   "params": {"name": "kill_gpu_process", "arguments": {"pid": 1234}}}),
+            json!({"jsonrpc": "2.0", "id": 3, "method": "tools/call",
+                   "params": {"name": "get_gpu_status"}}),
+        ];
+        let log = Mutex::new(Vec::new());
+        let responses = process_batch(batch, |r| fake_handler(r, &log))
+            .await
+            .unwrap();
+
+        // The kill waits for the slow tools/list before it, and the status read after it
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["tools/list", "kill_gpu_process", "get_gpu_status"]
+        );
+        let ids: Vec<RequestId> = responses.into_iter().map(|r| r.id).collect();
+        assert_eq!(
+            ids,
+            vec![
+                RequestId::Number(1),
+                RequestId::Number(2),
+                RequestId::Number(3)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_reports_invalid_entries_in_place() {
+        let batch = vec![
+            json!({"jsonrpc": "2.0", "id": 1, "method": "tools/list"}),
+            json!({"jsonrpc": "1.0", "id": 2, "method": "tools/list"}),
+            json!(42),
+        ];
+        let log = Mutex::new(Vec::new());
+        let responses = process_batch(batch, |r| fake_handler(r, &log))
+            .await
+            .unwrap();
+
+        assert_eq!(responses.len(), 3);
+        assert!(responses[0].error.is_none());
+        assert_eq!(responses[1].id, RequestId::Number(2));
+        assert_eq!(responses[1].error.as_ref().unwrap().code, -32600);
+        assert_eq!(responses[2].id, RequestId::Null);
+        assert_eq!(responses[2].error.as_ref().unwrap().code, -32600);
+
+        assert!(process_batch(Vec::new(), |r| fake_handler(r, &log))
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_batch_of_notifications_has_no_responses() {
+        let batch = vec![json!({"jsonrpc": "2.0", "method": "notifications/initialized"})];
+        let log = Mutex::new(Vec::new());
+        let responses = process_batch(batch, |r| fake_handler(r, &log))
+            .await
+            .unwrap();
+        assert!(responses.is_empty());
+        assert_eq!(log.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,382 @@
+//! Server-side policy for destructive MCP tools
+//!
+//! The policy is read at startup from an optional JSON file (`MCP_POLICY_FILE`)
+//! and environment overrides. It can disable tools entirely, force destructive
+//! tools into dry-run, and require `"confirm": true` before they act.
+
+use crate::policies::{error_result, ToolArgs};
+use crate::types::{ToolContent, ToolResult};
+use gpukill::nvml_api::GpuProc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::env;
+use std::path::Path;
+
+/// Tools that kill processes or reset GPUs
+pub const DESTRUCTIVE_TOOLS: &[&str] = &["kill_gpu_process", "reset_gpu", "kill_processes_by_name"];
+
+/// Policy applied to every tool call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerPolicy {
+    /// Tools that are hidden from tools/list and refused when called
+    pub disabled_tools: BTreeSet<String>,
+    /// Destructive tools only report what they would do
+    pub force_dry_run: bool,
+    /// Destructive tools need `"confirm": true` to act when dry-run is not forced
+    pub require_confirm: bool,
+}
+
+impl Default for ServerPolicy {
+    fn default() -> Self {
+        Self {
+            disabled_tools: BTreeSet::new(),
+            force_dry_run: false,
+            require_confirm: true,
+        }
+    }
+}
+
+/// What a destructive tool call is allowed to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Execution {
+    /// Act for real
+    Execute,
+    /// Only report the candidates; `forced` when the server policy required it
+    DryRun { forced: bool },
+    /// Refused until the call is repeated with `"confirm": true`
+    NeedsConfirm,
+}
+
+impl ServerPolicy {
+    /// Load the policy from MCP_POLICY_FILE, then apply MCP_DISABLED_TOOLS,
+    /// MCP_FORCE_DRY_RUN and MCP_REQUIRE_CONFIRM on top of it
+    pub fn from_env() -> anyhow::Result<Self> {
+        Self::from_lookup(|key| env::var(key).ok())
+    }
+
+    /// Same as [`ServerPolicy::from_env`] with variables read through `lookup`
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> anyhow::Result<Self> {
+        let mut policy = match lookup("MCP_POLICY_FILE").filter(|p| !p.trim().is_empty()) {
+            Some(path) => Self::load(Path::new(path.trim()))?,
+            None => Self::default(),
+        };
+
+        if let Some(tools) = lookup("MCP_DISABLED_TOOLS") {
+            policy.disabled_tools.extend(
+                tools
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+                    .map(str::to_string),
+            );
+        }
+        if let Some(value) = lookup("MCP_FORCE_DRY_RUN") {
+            policy.force_dry_run = parse_bool("MCP_FORCE_DRY_RUN", &value)?;
+        }
+        if let Some(value) = lookup("MCP_REQUIRE_CONFIRM") {
+            policy.require_confirm = parse_bool("MCP_REQUIRE_CONFIRM", &value)?;
+        }
+
+        Ok(policy)
+    }
+
+    /// Load a policy from a JSON file; missing fields keep their defaults
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            anyhow::anyhow!("Failed to read MCP policy file {}: {}", path.display(), e)
+        })?;
+        serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Invalid MCP policy file {}: {}", path.display(), e))
+    }
+
+    /// Whether the tool may be listed and called
+    pub fn is_enabled(&self, tool: &str) -> bool {
+        !self.disabled_tools.contains(tool)
+    }
+
+    /// Decide how a destructive tool call with these arguments may run
+    pub fn execution(&self, args: &ToolArgs) -> Execution {
+        let flag = |key: &str| args.get(key).and_then(|v| v.as_bool()).unwrap_or(false);
+
+        if self.force_dry_run {
+            Execution::DryRun { forced: true }
+        } else if flag("dry_run") {
+            Execution::DryRun { forced: false }
+        } else if self.require_confirm && !flag("confirm") {
+            Execution::NeedsConfirm
+        } else {
+            Execution::Execute
+        }
+    }
+
+    /// Result of the `get_server_policy` tool
+    pub fn describe(&self) -> ToolResult {
+        let destructive = if self.force_dry_run {
+            "dry-run forced, no action is ever taken"
+        } else if self.require_confirm {
+            "act only with \"confirm\": true, or report candidates with \"dry_run\": true"
+        } else {
+            "act without confirmation, or report candidates with \"dry_run\": true"
+        };
+        let disabled = if self.disabled_tools.is_empty() {
+            "none".to_string()
+        } else {
+            self.disabled_tools
+                .iter()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        ToolResult {
+            content: vec![ToolContent {
+                content_type: "text".to_string(),
+                text: Some(format!(
+                    "Destructive tools ({}): {}\nDisabled tools: {}",
+                    DESTRUCTIVE_TOOLS.join(", "),
+                    destructive,
+                    disabled
+                )),
+                data: Some(json!({
+                    "disabled_tools": self.disabled_tools,
+                    "force_dry_run": self.force_dry_run,
+                    "require_confirm": self.require_confirm,
+                    "destructive_tools": DESTRUCTIVE_TOOLS,
+                })),
+            }],
+            is_error: Some(false),
+        }
+    }
+}
+
+/// Result for a call to a disabled tool
+pub fn tool_disabled(tool: &str) -> ToolResult {
+    error_result(format!("Tool {} is disabled by the server policy", tool))
+}
+
+/// Result for a destructive call that did not act, listing what it would have affected.
+/// Returns `None` when the call may execute.
+pub fn withheld_result(
+    execution: Execution,
+    action: &str,
+    gpus: &[u32],
+    processes: &[GpuProc],
+) -> Option<ToolResult> {
+    let pids: Vec<u32> = processes.iter().map(|p| p.pid).collect();
+    let summary = format!(
+        "Would {} (PIDs: {}; GPUs: {})",
+        action,
+        list_or_none(&pids),
+        list_or_none(gpus)
+    );
+    let (text, is_error, forced) = match execution {
+        Execution::Execute => return None,
+        Execution::DryRun { forced } => (
+            format!(
+                "Dry run{}: no action was taken. {}",
+                if forced {
+                    " enforced by server policy"
+                } else {
+                    ""
+                },
+                summary
+            ),
+            false,
+            forced,
+        ),
+        Execution::NeedsConfirm => (
+            format!(
+                "Confirmation required: no action was taken. {}. Repeat the call with \
+                 \"confirm\": true to apply it.",
+                summary
+            ),
+            true,
+            false,
+        ),
+    };
+
+    Some(ToolResult {
+        content: vec![ToolContent {
+            content_type: "text".to_string(),
+            text: Some(text),
+            data: Some(json!({
+                "action_taken": false,
+                "dry_run": matches!(execution, Execution::DryRun { .. }),
+                "forced_by_policy": forced,
+                "confirmation_required": execution == Execution::NeedsConfirm,
+                "candidate_pids": pids,
+                "candidate_gpus": gpus,
+                "processes": processes,
+            })),
+        }],
+        is_error: Some(is_error),
+    })
+}
+
+fn list_or_none(values: &[u32]) -> String {
+    if values.is_empty() {
+        "none".to_string()
+    } else {
+        values
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+fn parse_bool(name: &str, value: &str) -> anyhow::Result<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        other => Err(anyhow::anyhow!(
+            "Invalid {}: expected true or false, got '{}'",
+            name,
+            other
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::collections::HashMap;
+
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |key| vars.get(key).cloned()
+    }
+
+    fn args(value: Value) -> ToolArgs {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn process(pid: u32, gpu_index: u16) -> GpuProc {
+        GpuProc {
+            gpu_index,
+            pid,
+            user: "alice".to_string(),
+            proc_name: "python".to_string(),
+            used_mem_mb: 1024,
+            start_time: "0s".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_default_policy_requires_confirm() {
+        let policy = ServerPolicy::from_lookup(lookup(&[])).unwrap();
+        assert_eq!(policy, ServerPolicy::default());
+
+        assert_eq!(policy.execution(&args(json!({}))), Execution::NeedsConfirm);
+        assert_eq!(
+            policy.execution(&args(json!({"confirm": true}))),
+            Execution::Execute
+        );
+        assert_eq!(
+            policy.execution(&args(json!({"dry_run": true, "confirm": true}))),
+            Execution::DryRun { forced: false }
+        );
+    }
+
+    #[test]
+    fn test_forced_dry_run_ignores_confirm() {
+        let policy = ServerPolicy::from_lookup(lookup(&[("MCP_FORCE_DRY_RUN", "true")])).unwrap();
+        assert_eq!(
+            policy.execution(&args(json!({"confirm": true}))),
+            Execution::DryRun { forced: true }
+        );
+
+        let policy =
+            ServerPolicy::from_lookup(lookup(&[("MCP_REQUIRE_CONFIRM", "false")])).unwrap();
+        assert_eq!(policy.execution(&args(json!({}))), Execution::Execute);
+
+        assert!(ServerPolicy::from_lookup(lookup(&[("MCP_FORCE_DRY_RUN", "maybe")])).is_err());
+    }
+
+    #[test]
+    fn test_policy_file_with_env_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("mcp_policy.json");
+        std::fs::write(
+            &path,
+            r#"{"disabled_tools": ["reset_gpu"], "force_dry_run": true}"#,
+        )
+        .unwrap();
+
+        let policy = ServerPolicy::from_lookup(lookup(&[
+            ("MCP_POLICY_FILE", path.to_str().unwrap()),
+            ("MCP_DISABLED_TOOLS", "kill_processes_by_name, "),
+            ("MCP_FORCE_DRY_RUN", "0"),
+        ]))
+        .unwrap();
+        assert!(!policy.is_enabled("reset_gpu"));
+        assert!(!policy.is_enabled("kill_processes_by_name"));
+        assert!(policy.is_enabled("kill_gpu_process"));
+        assert!(!policy.force_dry_run);
+        assert!(policy.require_confirm);
+
+        let missing = dir.path().join("missing.json");
+        assert!(ServerPolicy::from_lookup(lookup(&[(
+            "MCP_POLICY_FILE",
+            missing.to_str().unwrap()
+        )]))
+        .is_err());
+    }
+
+    #[test]
+    fn test_withheld_result_lists_candidates() {
+        let processes = vec![process(1234, 0), process(5678, 1)];
+
+        assert!(withheld_result(Execution::Execute, "kill", &[0, 1], &processes).is_none());
+
+        let dry_run = withheld_result(
+            Execution::DryRun { forced: true },
+            "kill 2 processes",
+            &[0, 1],
+            &processes,
+        )
+        .unwrap();
+        assert_eq!(dry_run.is_error, Some(false));
+        let text = dry_run.content[0].text.as_ref().unwrap();
+        assert!(text.contains("no action was taken"));
+        assert!(text.contains("1234, 5678"));
+        let data = dry_run.content[0].data.as_ref().unwrap();
+        assert_eq!(data["action_taken"], false);
+        assert_eq!(data["forced_by_policy"], true);
+        assert_eq!(data["candidate_pids"], json!([1234, 5678]));
+        assert_eq!(data["candidate_gpus"], json!([0, 1]));
+
+        let refused = withheld_result(Execution::NeedsConfirm, "reset GPU 0", &[0], &[]).unwrap();
+        assert_eq!(refused.is_error, Some(true));
+        assert!(refused.content[0]
+            .text
+            .as_ref()
+            .unwrap()
+            .contains("\"confirm\": true"));
+    }
+
+    #[test]
+    fn test_describe_reports_policy() {
+        let policy = ServerPolicy {
+            disabled_tools: BTreeSet::from(["reset_gpu".to_string()]),
+            force_dry_run: true,
+            require_confirm: true,
+        };
+        let result = policy.describe();
+        let data = result.content[0].data.as_ref().unwrap();
+        assert_eq!(data["disabled_tools"], json!(["reset_gpu"]));
+        assert_eq!(data["force_dry_run"], true);
+        assert!(result.content[0]
+            .text
+            .as_ref()
+            .unwrap()
+            .contains("Disabled tools: reset_gpu"));
+    }
+}
@@ -1,5 +1,8 @@
 //! MCP Tools for GPU Kill
 
+use crate::coordinator::{self, CoordinatorClient};
+use crate::policies;
+use crate::server_policy::{self, ServerPolicy};
 use crate::types::*;
 use gpukill::audit::AuditManager;
 use gpukill::guard_mode::GuardModeManager;
@@ -17,14 +20,25 @@ pub struct ToolHandler {
     process_manager: Option<EnhancedProcessManager>,
     guard_mode: Option<GuardModeManager>,
     rogue_detector: Option<RogueDetector>,
+    coordinator: Option<CoordinatorClient>,
+    policy: ServerPolicy,
 }
 
 impl ToolHandler {
     pub async fn new() -> anyhow::Result<Self> {
-        let gpu_manager = GpuManager::initialize()?;
+        let gpu_manager = GpuManager::initialize()?.with_stable_indices();
         // Try to initialize NVML API, but don't panic if it fails
-        let process_manager = if let Ok(nvml_api) = NvmlApi::new() {
-            Some(EnhancedProcessManager::new(ProcessManager::new(nvml_api)))
+        // Kills need NVML, but go through the GPU manager so they refresh its cached
+        // process list
+        let process_manager = if NvmlApi::new().is_ok() {
+            // Kills from the MCP server never override the protected process list
+            let protected = gpukill::config::get_config(None)
+                .map(|c| c.config().protected_processes.clone())
+                .unwrap_or_default();
+            Some(
+                EnhancedProcessManager::new(ProcessManager::with_gpu_manager(gpu_manager.clone()))
+                    .with_protection(&protected, false),
+            )
         } else {
             tracing::warn!("NVML API not available, process management will be limited");
             None
@@ -33,22 +47,47 @@ impl ToolHandler {
         // Initialize optional components
         let guard_mode = GuardModeManager::new().ok();
         let audit_manager = AuditManager::new().await.ok();
-        let rogue_detector = audit_manager.map(|am| RogueDetector::new(am));
+        let rogue_detector = audit_manager.map(RogueDetector::new);
+        let coordinator = CoordinatorClient::from_env();
+        let policy = ServerPolicy::from_env()?;
+        if policy.force_dry_run {
+            tracing::info!("Server policy forces dry-run for destructive tools");
+        }
 
         Ok(Self {
             gpu_manager,
             process_manager,
             guard_mode,
             rogue_detector,
+            coordinator,
+            policy,
         })
     }
 
-    /// List all available tools
+    /// Handler over `gpu_manager` alone, such as a mock GPU in tests: processes are
+    /// managed through it, and Guard Mode, rogue detection and the coordinator are off
+    pub fn with_gpu_manager(gpu_manager: GpuManager, policy: ServerPolicy) -> Self {
+        let process_manager =
+            EnhancedProcessManager::new(ProcessManager::with_gpu_manager(gpu_manager.clone()));
+        Self {
+            gpu_manager,
+            process_manager: Some(process_manager),
+            guard_mode: None,
+            rogue_detector: None,
+            coordinator: None,
+            policy,
+        }
+    }
+
+    /// List the tools the server policy allows
     pub fn list_tools(&self) -> Vec<Tool> {
-        vec![
+        let mut tools = vec![
             Tool {
                 name: "kill_gpu_process".to_string(),
-                description: Some("Kill a GPU process by PID".to_string()),
+                description: Some(
+                    "Kill a GPU process by PID (see get_server_policy for dry-run and confirm)"
+                        .to_string(),
+                ),
                 input_schema: json!({
                     "type": "object",
                     "properties": {
@@ -60,6 +99,16 @@ impl ToolHandler {
                             "type": "boolean",
                             "description": "Force kill if graceful termination fails",
                             "default": false
+                        },
+                        "dry_run": {
+                            "type": "boolean",
+                            "description": "Only report what would be affected, without acting",
+                            "default": false
+                        },
+                        "confirm": {
+                            "type": "boolean",
+                            "description": "Confirm the action when the server policy requires it",
+                            "default": false
                         }
                     },
                     "required": ["pid"]
@@ -79,6 +128,16 @@ impl ToolHandler {
                             "type": "boolean",
                             "description": "Force reset even if processes are running",
                             "default": false
+                        },
+                        "dry_run": {
+                            "type": "boolean",
+                            "description": "Only report what would be affected, without acting",
+                            "default": false
+                        },
+                        "confirm": {
+                            "type": "boolean",
+                            "description": "Confirm the action when the server policy requires it",
+                            "default": false
                         }
                     },
                     "required": ["gpu_id"]
@@ -124,6 +183,103 @@ impl ToolHandler {
                     "required": ["username"]
                 }),
             },
+            Tool {
+                name: "list_policies".to_string(),
+                description: Some(
+                    "List Guard Mode user, group and GPU policies with their limits".to_string(),
+                ),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {}
+                }),
+            },
+            Tool {
+                name: "update_user_policy".to_string(),
+                description: Some(
+                    "Update the limits of an existing Guard Mode user policy; omitted limits are kept"
+                        .to_string(),
+                ),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "username": {
+                            "type": "string",
+                            "description": "Username of the policy to update"
+                        },
+                        "memory_limit_gb": {
+                            "type": "number",
+                            "description": "New memory limit in GB"
+                        },
+                        "utilization_limit_pct": {
+                            "type": "number",
+                            "description": "New utilization limit percentage (0-100)"
+                        },
+                        "process_limit": {
+                            "type": "integer",
+                            "description": "New maximum number of processes"
+                        }
+                    },
+                    "required": ["username"]
+                }),
+            },
+            Tool {
+                name: "remove_user_policy".to_string(),
+                description: Some("Remove a Guard Mode user policy".to_string()),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "username": {
+                            "type": "string",
+                            "description": "Username of the policy to remove"
+                        }
+                    },
+                    "required": ["username"]
+                }),
+            },
+            Tool {
+                name: "set_guard_enabled".to_string(),
+                description: Some(
+                    "Enable or disable Guard Mode; enabling it outside dry-run requires confirm"
+                        .to_string(),
+                ),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "enabled": {
+                            "type": "boolean",
+                            "description": "Whether Guard Mode should be enabled"
+                        },
+                        "confirm": {
+                            "type": "boolean",
+                            "description": "Confirm a change that turns on hard enforcement",
+                            "default": false
+                        }
+                    },
+                    "required": ["enabled"]
+                }),
+            },
+            Tool {
+                name: "set_guard_dry_run".to_string(),
+                description: Some(
+                    "Turn Guard Mode dry-run on or off; turning it off while enabled requires confirm"
+                        .to_string(),
+                ),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "dry_run": {
+                            "type": "boolean",
+                            "description": "Whether violations are only reported instead of enforced"
+                        },
+                        "confirm": {
+                            "type": "boolean",
+                            "description": "Confirm a change that turns on hard enforcement",
+                            "default": false
+                        }
+                    },
+                    "required": ["dry_run"]
+                }),
+            },
             Tool {
                 name: "get_gpu_status".to_string(),
                 description: Some("Get detailed status of a specific GPU".to_string()),
@@ -148,16 +304,55 @@ impl ToolHandler {
                             "type": "string",
                             "description": "Process name pattern (supports regex)"
                         },
+                        "match_cmdline": {
+                            "type": "boolean",
+                            "description": "Match the pattern against the full command line (e.g. train.py) instead of the process name",
+                            "default": false
+                        },
                         "force": {
                             "type": "boolean",
                             "description": "Force kill if graceful termination fails",
                             "default": false
+                        },
+                        "dry_run": {
+                            "type": "boolean",
+                            "description": "Only report what would be affected, without acting",
+                            "default": false
+                        },
+                        "confirm": {
+                            "type": "boolean",
+                            "description": "Confirm the action when the server policy requires it",
+                            "default": false
                         }
                     },
                     "required": ["pattern"]
                 }),
             },
-        ]
+            Tool {
+                name: "get_server_policy".to_string(),
+                description: Some(
+                    "Get the server policy: disabled tools, forced dry-run and whether destructive tools need confirm"
+                        .to_string(),
+                ),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {}
+                }),
+            },
+            Tool {
+                name: "get_cluster_overview".to_string(),
+                description: Some(
+                    "Get a cluster-wide overview (nodes, free GPUs, top users, blocked GPUs) from the configured coordinator"
+                        .to_string(),
+                ),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {}
+                }),
+            },
+        ];
+        tools.retain(|tool| self.policy.is_enabled(&tool.name));
+        tools
     }
 
     /// Execute a tool by name with arguments
@@ -166,13 +361,38 @@ impl ToolHandler {
         name: &str,
         arguments: Option<HashMap<String, serde_json::Value>>,
     ) -> anyhow::Result<ToolResult> {
+        if !self.policy.is_enabled(name) {
+            return Ok(server_policy::tool_disabled(name));
+        }
+
         match name {
             "kill_gpu_process" => self.kill_gpu_process(arguments).await,
             "reset_gpu" => self.reset_gpu(arguments).await,
             "scan_rogue_activity" => self.scan_rogue_activity(arguments).await,
             "create_user_policy" => self.create_user_policy(arguments).await,
+            "list_policies" => Ok(policies::list_policies(self.guard_mode.as_ref())),
+            "update_user_policy" => Ok(policies::update_user_policy(
+                self.guard_mode.as_mut(),
+                &arguments.unwrap_or_default(),
+            )),
+            "remove_user_policy" => Ok(policies::remove_user_policy(
+                self.guard_mode.as_mut(),
+                &arguments.unwrap_or_default(),
+            )),
+            "set_guard_enabled" => Ok(policies::set_guard_enabled(
+                self.guard_mode.as_mut(),
+                &arguments.unwrap_or_default(),
+            )),
+            "set_guard_dry_run" => Ok(policies::set_guard_dry_run(
+                self.guard_mode.as_mut(),
+                &arguments.unwrap_or_default(),
+            )),
             "get_gpu_status" => self.get_gpu_status(arguments).await,
             "kill_processes_by_name" => self.kill_processes_by_name(arguments).await,
+            "get_server_policy" => Ok(self.policy.describe()),
+            "get_cluster_overview" => {
+                Ok(coordinator::cluster_overview(self.coordinator.as_ref()).await)
+            }
             _ => Err(anyhow::anyhow!("Unknown tool: {}", name)),
         }
     }
@@ -189,8 +409,29 @@ impl ToolHandler {
 
         let _force = args.get("force").and_then(|v| v.as_bool()).unwrap_or(false);
 
+        let execution = self.policy.execution(&args);
+        if execution != server_policy::Execution::Execute {
+            let processes: Vec<_> = self
+                .gpu_manager
+                .get_all_processes_async()
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|p| p.pid == pid)
+                .collect();
+            let action = format!("kill process {}", pid);
+            if let Some(result) = server_policy::withheld_result(
+                execution,
+                &action,
+                &unique_gpus(&processes),
+                &processes,
+            ) {
+                return Ok(result);
+            }
+        }
+
         if let Some(ref mut pm) = self.process_manager {
-            match pm.process_manager.graceful_kill(pid, 10, _force) {
+            match pm.kill_process(pid, 10, _force) {
                 Ok(_) => Ok(ToolResult {
                     content: vec![ToolContent {
                         content_type: "text".to_string(),
@@ -232,6 +473,24 @@ impl ToolHandler {
 
         let _force = args.get("force").and_then(|v| v.as_bool()).unwrap_or(false);
 
+        let execution = self.policy.execution(&args);
+        if execution != server_policy::Execution::Execute {
+            let processes: Vec<_> = self
+                .gpu_manager
+                .get_all_processes_async()
+                .await
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|p| p.gpu_index as u32 == gpu_id)
+                .collect();
+            let action = format!("reset GPU {}", gpu_id);
+            if let Some(result) =
+                server_policy::withheld_result(execution, &action, &[gpu_id], &processes)
+            {
+                return Ok(result);
+            }
+        }
+
         match self.gpu_manager.reset_gpu(gpu_id) {
             Ok(_) => Ok(ToolResult {
                 content: vec![ToolContent {
@@ -380,7 +639,7 @@ impl ToolHandler {
                 .and_then(|v| v.as_u64())
                 .ok_or_else(|| anyhow::anyhow!("Missing or invalid gpu_id"))? as u32;
 
-        match self.gpu_manager.get_all_snapshots() {
+        match self.gpu_manager.get_all_snapshots_async().await {
             Ok(gpus) => {
                 if let Some(gpu) = gpus.into_iter().find(|g| g.gpu_index as u32 == gpu_id) {
                     Ok(ToolResult {
@@ -432,13 +691,23 @@ impl ToolHandler {
             .ok_or_else(|| anyhow::anyhow!("Missing pattern"))?;
 
         let _force = args.get("force").and_then(|v| v.as_bool()).unwrap_or(false);
+        let match_cmdline = args
+            .get("match_cmdline")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let execution = self.policy.execution(&args);
 
         // Get all GPU processes first
-        match self.gpu_manager.get_all_processes() {
+        match self.gpu_manager.get_all_processes_async().await {
             Ok(all_processes) => {
                 // Filter processes by name pattern
                 if let Some(ref mut pm) = self.process_manager {
-                    match pm.filter_processes_by_name(&all_processes, pattern) {
+                    let filtered = if match_cmdline {
+                        pm.filter_processes_by_cmdline(&all_processes, pattern)
+                    } else {
+                        pm.filter_processes_by_name(&all_processes, pattern)
+                    };
+                    match filtered {
                         Ok(filtered_processes) => {
                             if filtered_processes.is_empty() {
                                 Ok(ToolResult {
@@ -452,26 +721,42 @@ impl ToolHandler {
                                     }],
                                     is_error: Some(false),
                                 })
+                            } else if let Some(result) = server_policy::withheld_result(
+                                execution,
+                                &format!(
+                                    "kill {} processes matching pattern '{}'",
+                                    filtered_processes.len(),
+                                    pattern
+                                ),
+                                &unique_gpus(&filtered_processes),
+                                &filtered_processes,
+                            ) {
+                                Ok(result)
                             } else {
-                                // Kill the filtered processes
-                                match pm.batch_kill_processes(&filtered_processes, 10, _force) {
-                                Ok(killed_pids) => Ok(ToolResult {
-                                    content: vec![ToolContent {
-                                        content_type: "text".to_string(),
-                                        text: Some(format!("Successfully killed {} processes matching pattern '{}'", killed_pids.len(), pattern)),
-                                        data: Some(json!(killed_pids)),
-                                    }],
-                                    is_error: Some(false),
-                                }),
-                                Err(e) => Ok(ToolResult {
-                                    content: vec![ToolContent {
-                                        content_type: "text".to_string(),
-                                        text: Some(format!("Failed to kill processes: {}", e)),
-                                        data: None,
-                                    }],
-                                    is_error: Some(true),
-                                }),
-                            }
+                                // Kill the filtered processes, leaving protected ones alone
+                                let (targets, skipped) = pm.screen_protected(filtered_processes);
+                                let skipped_note = if skipped.is_empty() {
+                                    String::new()
+                                } else {
+                                    let names: Vec<String> = skipped
+                                        .iter()
+                                        .map(|(p, reason)| format!("PID {} ({})", p.pid, reason))
+                                        .collect();
+                                    format!("; skipped protected processes: {}", names.join(", "))
+                                };
+                                match pm.batch_kill_processes(&targets, 10, _force) {
+                                    Ok(outcomes) => {
+                                        Ok(kill_outcomes_result(&outcomes, pattern, &skipped_note))
+                                    }
+                                    Err(e) => Ok(ToolResult {
+                                        content: vec![ToolContent {
+                                            content_type: "text".to_string(),
+                                            text: Some(format!("Failed to kill processes: {}", e)),
+                                            data: None,
+                                        }],
+                                        is_error: Some(true),
+                                    }),
+                                }
                             }
                         }
                         Err(e) => Ok(ToolResult {
@@ -507,3 +792,223 @@ impl ToolHandler {
         }
     }
 }
+
+/// Tool result for a kill by name, listing the processes that needed SIGKILL or
+/// survived. Any survivor makes the result an error, with the outcome of every
+/// process in the data.
+fn kill_outcomes_result(
+    outcomes: &[gpukill::report::KillOutcome],
+    pattern: &str,
+    skipped_note: &str,
+) -> ToolResult {
+    let describe = |o: &gpukill::report::KillOutcome| {
+        format!(
+            "PID {} ({})",
+            o.pid,
+            o.process.as_deref().unwrap_or("unknown")
+        )
+    };
+    let killed: Vec<_> = outcomes.iter().filter(|o| o.succeeded()).collect();
+    let failed: Vec<String> = outcomes
+        .iter()
+        .filter(|o| o.status == gpukill::report::KillStatus::Failed)
+        .map(|o| {
+            format!(
+                "{}: {}",
+                describe(o),
+                o.reason.as_deref().unwrap_or("unknown error")
+            )
+        })
+        .collect();
+    let sigkilled: Vec<String> = killed
+        .iter()
+        .filter(|o| o.needed_sigkill())
+        .map(|o| describe(o))
+        .collect();
+
+    let mut text = if failed.is_empty() {
+        format!(
+            "Successfully killed {} processes matching pattern '{}'",
+            killed.len(),
+            pattern
+        )
+    } else {
+        format!(
+            "Killed {} of {} processes matching pattern '{}'",
+            killed.len(),
+            killed.len() + failed.len(),
+            pattern
+        )
+    };
+    if !sigkilled.is_empty() {
+        text.push_str(&format!("; needed SIGKILL: {}", sigkilled.join(", ")));
+    }
+    if !failed.is_empty() {
+        text.push_str(&format!("; failed: {}", failed.join("; ")));
+    }
+    text.push_str(skipped_note);
+
+    ToolResult {
+        content: vec![ToolContent {
+            content_type: "text".to_string(),
+            text: Some(text),
+            data: Some(json!(outcomes)),
+        }],
+        is_error: Some(!failed.is_empty()),
+    }
+}
+
+/// Sorted GPU indices the processes run on
+fn unique_gpus(processes: &[gpukill::nvml_api::GpuProc]) -> Vec<u32> {
+    let gpus: std::collections::BTreeSet<u32> =
+        processes.iter().map(|p| p.gpu_index as u32).collect();
+    gpus.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpukill::vendor::{MockCall, MockFixture, MockGpu, MockProcess, MockVendor};
+    use std::sync::Arc;
+
+    /// Two mock GPUs, each running one python process
+    fn mock_handler() -> (ToolHandler, Arc<MockVendor>) {
+        let gpu = |pid| MockGpu {
+            name: "Mock A100".to_string(),
+            mem_total_mb: 81920,
+            util_pct: 50.0,
+            processes: vec![MockProcess {
+                pid,
+                user: "alice".to_string(),
+                name: "python".to_string(),
+                used_mem_mb: 4096,
+                cmdline: None,
+            }],
+            ..Default::default()
+        };
+        let vendor = Arc::new(MockVendor::new(MockFixture {
+            gpus: vec![gpu(4242), gpu(4243)],
+            ..Default::default()
+        }));
+        let handler = ToolHandler::with_gpu_manager(vendor.manager(), ServerPolicy::default());
+        (handler, vendor)
+    }
+
+    fn args(value: serde_json::Value) -> Option<HashMap<String, serde_json::Value>> {
+        serde_json::from_value(value).ok()
+    }
+
+    fn text(result: &ToolResult) -> &str {
+        result.content[0].text.as_deref().unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn test_get_gpu_status_reads_mock_gpu() {
+        let (mut handler, _) = mock_handler();
+
+        let result = handler
+            .execute_tool("get_gpu_status", args(json!({"gpu_id": 1})))
+            .await
+            .unwrap();
+        assert_eq!(result.is_error, Some(false));
+        assert!(
+            text(&result).starts_with("GPU 1: Mock A100"),
+            "{}",
+            text(&result)
+        );
+        let data = result.content[0].data.as_ref().unwrap();
+        assert_eq!(data["mem_used_mb"], 4096);
+        assert_eq!(data["top_proc"]["pid"], 4243);
+
+        let result = handler
+            .execute_tool("get_gpu_status", args(json!({"gpu_id": 7})))
+            .await
+            .unwrap();
+        assert_eq!(result.is_error, Some(true));
+        assert_eq!(text(&result), "GPU 7 not found");
+    }
+
+    #[tokio::test]
+    async fn test_kill_by_name_dry_run_lists_mock_processes() {
+        let (mut handler, _) = mock_handler();
+
+        let result = handler
+            .execute_tool(
+                "kill_processes_by_name",
+                args(json!({"pattern": "^python$", "dry_run": true})),
+            )
+            .await
+            .unwrap();
+        assert_eq!(result.is_error, Some(false));
+        assert!(
+            text(&result).contains("PIDs: 4242, 4243; GPUs: 0, 1"),
+            "{}",
+            text(&result)
+        );
+
+        let result = handler
+            .execute_tool("kill_processes_by_name", args(json!({"pattern": "java"})))
+            .await
+            .unwrap();
+        assert_eq!(text(&result), "No processes found matching pattern 'java'");
+    }
+
+    #[test]
+    fn test_kill_by_name_reports_each_process() {
+        use gpukill::report::{KillOutcome, KillStatus};
+
+        let killed = |pid, signal: &str| {
+            let mut outcome = KillOutcome::for_pid(pid, KillStatus::Killed);
+            outcome.process = Some("python".to_string());
+            outcome.signal = Some(signal.to_string());
+            outcome
+        };
+        let mut denied = KillOutcome::for_pid(4244, KillStatus::Failed)
+            .with_reason("Permission denied: PID 4244 is owned by bob");
+        denied.process = Some("python".to_string());
+        let outcomes = vec![killed(4242, "SIGTERM"), killed(4243, "SIGKILL"), denied];
+
+        let result = kill_outcomes_result(&outcomes, "python", "");
+        assert_eq!(result.is_error, Some(true));
+        assert_eq!(
+            text(&result),
+            "Killed 2 of 3 processes matching pattern 'python'; needed SIGKILL: PID 4243 (python); \
+             failed: PID 4244 (python): Permission denied: PID 4244 is owned by bob"
+        );
+        let data = result.content[0].data.as_ref().unwrap();
+        assert_eq!(data[1]["signal"], "SIGKILL");
+        assert_eq!(data[2]["status"], "failed");
+
+        let result = kill_outcomes_result(
+            &outcomes[..1],
+            "python",
+            "; skipped protected processes: PID 1 (init process)",
+        );
+        assert_eq!(result.is_error, Some(false));
+        assert_eq!(
+            text(&result),
+            "Successfully killed 1 processes matching pattern 'python'; skipped protected processes: PID 1 (init process)"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reset_gpu_waits_for_confirm() {
+        let (mut handler, vendor) = mock_handler();
+
+        let result = handler
+            .execute_tool("reset_gpu", args(json!({"gpu_id": 0})))
+            .await
+            .unwrap();
+        assert_eq!(result.is_error, Some(true));
+        assert!(text(&result).starts_with("Confirmation required"));
+        assert!(text(&result).contains("PIDs: 4242"), "{}", text(&result));
+        assert_eq!(vendor.calls(0, MockCall::Reset), 0);
+
+        let result = handler
+            .execute_tool("reset_gpu", args(json!({"gpu_id": 0, "confirm": true})))
+            .await
+            .unwrap();
+        assert_eq!(result.is_error, Some(false));
+        assert_eq!(vendor.calls(0, MockCall::Reset), 1);
+    }
+}
@@ -10,7 +10,7 @@ use std::fmt;
 /// Per MCP specification, the id MUST be a string or integer (null is not allowed).
 ///
 /// This enum supports all valid JSON-RPC 2.0 id types for maximum compatibility.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum RequestId {
     /// String identifier
@@ -18,15 +18,10 @@ pub enum RequestId {
     /// Integer identifier (JSON-RPC uses Number, we use i64 for integers)
     Number(i64),
     /// Null identifier (valid in JSON-RPC 2.0, but not in MCP)
+    #[default]
     Null,
 }
 
-impl Default for RequestId {
-    fn default() -> Self {
-        RequestId::Null
-    }
-}
-
 impl fmt::Display for RequestId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -79,11 +74,13 @@ fn deserialize_request_id<'de, D>(deserializer: D) -> Result<Option<RequestId>,
 where
     D: serde::Deserializer<'de>,
 {
-    let id = Option::<RequestId>::deserialize(deserializer)?;
-    if matches!(id, Some(RequestId::Null)) {
+    // Only called when the field is present (missing ids use `default`), so deserialize
+    // RequestId directly; going through Option would silently map an explicit null to None.
+    let id = RequestId::deserialize(deserializer)?;
+    if id == RequestId::Null {
         return Err(serde::de::Error::custom("jsonrpc id must not be null"));
     }
-    Ok(id)
+    Ok(Some(id))
 }
 
 /// MCP Request/Response types
@@ -258,6 +255,9 @@ pub struct GpuInfo {
     pub id: u32,
     pub name: String,
     pub vendor: String,
+    /// Stable identifier that survives reboots, unlike `id`
+    #[serde(default)]
+    pub uuid: Option<String>,
     pub memory_used: f64,
     pub memory_total: f64,
     pub utilization: f64,
@@ -384,8 +384,12 @@ mod tests {
             "id": null
         });
 
-        let parsed: Result<JsonRpcRequest, _> = from_value(request);
-        assert!(parsed.is_err(), "expected null id to be rejected");
+        // An explicit null must not turn the request into a notification, which would
+        // leave the client waiting for a response that never comes
+        let err = from_value::<JsonRpcRequest>(request)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("jsonrpc id must not be null"), "{}", err);
     }
 
     #[test]
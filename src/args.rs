@@ -1,5 +1,20 @@
+use crate::nvml_api::GpuProc;
+use crate::proc::{KillSignal, PidTarget};
+use crate::selftest::DEFAULT_SELFTEST_MEM_MB;
 use crate::vendor::GpuVendor;
-use clap::{Parser, ValueEnum};
+use chrono::{DateTime, Utc};
+use clap::parser::ValueSource;
+use clap::{ArgGroup, CommandFactory, FromArgMatches, Parser, ValueEnum};
+use std::ffi::OsString;
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+/// Detailed audit records shown when --audit-limit is not given
+pub const DEFAULT_AUDIT_LIMIT: usize = 500;
+use std::time::Duration;
+
+/// Largest `--idle-mem-gb` whose threshold in MB still fits in a u32
+pub const MAX_IDLE_MEM_GB: f64 = (u32::MAX / 1024) as f64;
 
 /// A production-ready CLI tool for GPU management and monitoring
 #[derive(Parser)]
@@ -7,13 +22,21 @@ use clap::{Parser, ValueEnum};
     name = "gpukill",
     version = env!("CARGO_PKG_VERSION"),
     about = "GPU management and monitoring CLI tool",
-    long_about = "gpukill provides comprehensive GPU monitoring, process management, and device control capabilities for NVIDIA, AMD, and Intel GPUs."
+    long_about = "gpukill provides comprehensive GPU monitoring, process management, and device control capabilities for NVIDIA, AMD, and Intel GPUs.",
+    group(ArgGroup::new("remote_target").multiple(true).args(["remote", "remote_file"])),
+    group(ArgGroup::new("coordinator_node").multiple(true).args(["server", "register_node"])),
+    group(ArgGroup::new("idle_window").args(["idle_for", "idle_minutes"])),
+    group(ArgGroup::new("kills_processes").multiple(true).args(["kill", "wait_secs"]))
 )]
 pub struct Cli {
     /// Log level for debugging and diagnostics
     #[arg(long, value_enum, default_value = "info", global = true)]
     pub log_level: LogLevel,
 
+    /// Log output format (defaults to the config file's log_format, then human)
+    #[arg(long, value_enum, global = true)]
+    pub log_format: Option<LogFormat>,
+
     /// Configuration file path (optional)
     #[arg(long, global = true)]
     pub config: Option<String>,
@@ -22,6 +45,25 @@ pub struct Cli {
     #[arg(long, alias = "safe", global = true)]
     pub dry_run: bool,
 
+    /// When to use colors, emoji and Unicode table borders: auto (on a terminal,
+    /// unless NO_COLOR is set), always or never
+    #[arg(
+        long,
+        value_enum,
+        value_name = "WHEN",
+        default_value = "auto",
+        global = true
+    )]
+    pub color: ColorChoice,
+
+    /// Same as --color never
+    #[arg(long, global = true)]
+    pub no_color: bool,
+
+    /// Don't record --list snapshots in the audit log
+    #[arg(long, global = true)]
+    pub no_audit: bool,
+
     /// List GPUs and their current status
     #[arg(long)]
     pub list: bool,
@@ -34,6 +76,41 @@ pub struct Cli {
     #[arg(long)]
     pub reset: bool,
 
+    /// With --reset: unload and reload the NVIDIA kernel modules (Linux, root only)
+    #[arg(long, requires = "reset")]
+    pub hard: bool,
+
+    /// With --reset --gpu: wait up to this many seconds for the GPU's processes to exit
+    /// before resetting; with --force, processes still running then are killed
+    #[arg(
+        long,
+        requires = "reset",
+        conflicts_with_all = ["hard", "all"],
+        value_name = "SECS"
+    )]
+    pub wait_secs: Option<u64>,
+
+    /// Change the power limit or clocks of one GPU (with --gpu or --gpu-uuid)
+    #[arg(long)]
+    pub set: bool,
+
+    /// With --set: board power limit in watts
+    #[arg(long, requires = "set", value_name = "WATTS")]
+    pub power_limit: Option<u32>,
+
+    /// With --set: lock the graphics clock to this frequency
+    #[arg(
+        long,
+        requires = "set",
+        conflicts_with = "reset_clocks",
+        value_name = "MHZ"
+    )]
+    pub lock_clocks: Option<u32>,
+
+    /// With --set: undo --lock-clocks and let the driver manage clocks again
+    #[arg(long, requires = "set")]
+    pub reset_clocks: bool,
+
     /// Show GPU usage audit history
     #[arg(long)]
     pub audit: bool,
@@ -42,6 +119,27 @@ pub struct Cli {
     #[arg(long)]
     pub server: bool,
 
+    /// Serve a local REST API (snapshot, processes, kill, reset, audit) on this host
+    #[arg(long)]
+    pub serve_local: bool,
+
+    /// Port for the --serve-local API
+    #[arg(long, requires = "serve_local", value_name = "PORT", default_value_t = crate::local_api::DEFAULT_LOCAL_API_PORT)]
+    pub port: u16,
+
+    /// Address to bind the --serve-local API to; other than loopback it needs --api-token
+    #[arg(long, requires = "serve_local", value_name = "ADDR", default_value = "127.0.0.1", value_parser = parse_server_host)]
+    pub bind: IpAddr,
+
+    /// Bearer token the --serve-local API requires in the Authorization header
+    #[arg(
+        long,
+        env = "GPUKILL_API_TOKEN",
+        value_name = "TOKEN",
+        hide_env_values = true
+    )]
+    pub api_token: Option<String>,
+
     /// Open browser to dashboard (works with --server and alias 'up')
     #[arg(long, requires = "server", global = true)]
     pub open: bool,
@@ -54,13 +152,47 @@ pub struct Cli {
     #[arg(long)]
     pub watch: bool,
 
+    /// With --watch, show per-GPU memory growth per second and utilization change
+    #[arg(long, requires = "watch")]
+    pub show_rates: bool,
+
+    /// With --watch, stop after N refreshes
+    #[arg(
+        long,
+        requires = "watch",
+        value_name = "N",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub watch_count: Option<u64>,
+
+    /// With --watch, print one line per refresh with the memory and GPU utilization of PID
+    #[arg(
+        long,
+        requires = "watch",
+        value_name = "PID",
+        conflicts_with_all = ["details", "tree", "show_rates", "alert_util", "alert_mem_pct", "alert_temp"]
+    )]
+    pub follow_pid: Option<u32>,
+
+    /// With --watch, exit non-zero if any GPU's utilization goes above PCT percent
+    #[arg(long, requires = "watch", value_name = "PCT")]
+    pub alert_util: Option<f32>,
+
+    /// With --watch, exit non-zero if any GPU uses more than PCT percent of its memory
+    #[arg(long, requires = "watch", value_name = "PCT")]
+    pub alert_mem_pct: Option<f32>,
+
+    /// With --watch, exit non-zero if any GPU gets hotter than CELSIUS
+    #[arg(long, requires = "watch", value_name = "CELSIUS")]
+    pub alert_temp: Option<u32>,
+
     /// Output format
     #[arg(long, value_enum, default_value = "table")]
     pub output: OutputFormat,
 
-    /// Process ID to terminate
-    #[arg(long)]
-    pub pid: Option<u32>,
+    /// Process ID to terminate, or container:<name-or-id>:<pid> for a PID inside a container
+    #[arg(long, value_name = "PID", value_parser = parse_pid_arg)]
+    pub pid: Option<PidTarget>,
 
     /// Timeout in seconds before escalating to SIGKILL
     #[arg(long, default_value = "5")]
@@ -70,10 +202,19 @@ pub struct Cli {
     #[arg(long)]
     pub force: bool,
 
+    /// Signal sent first when killing (--kill, or --reset --wait-secs --force): SIGTERM
+    /// (default), SIGINT, SIGQUIT or SIGHUP
+    #[arg(long, requires = "kills_processes", value_name = "NAME", default_value = "SIGTERM", value_parser = parse_signal_arg)]
+    pub signal: KillSignal,
+
     /// Specific GPU ID to reset
     #[arg(long)]
     pub gpu: Option<u16>,
 
+    /// Select a GPU by UUID or serial number instead of index; accepted wherever --gpu is
+    #[arg(long, value_name = "UUID", conflicts_with = "gpu")]
+    pub gpu_uuid: Option<String>,
+
     /// Reset all GPUs
     #[arg(long)]
     pub all: bool,
@@ -86,14 +227,158 @@ pub struct Cli {
     #[arg(long)]
     pub filter: Option<String>,
 
-    /// Kill multiple processes matching the filter or GPU
+    /// Only kill processes owned by this user (exact name). With --filter, both must match.
+    #[arg(
+        long,
+        requires = "kill",
+        conflicts_with_all = ["pid", "idle_window"],
+        value_name = "USER"
+    )]
+    pub filter_user: Option<String>,
+
+    /// Kill every GPU process of this Slurm job
+    #[arg(
+        long,
+        requires = "kill",
+        conflicts_with_all = ["pid", "filter", "filter_user", "idle_window", "gpu", "gpu_uuid"],
+        value_name = "JOBID",
+        value_parser = parse_job_arg
+    )]
+    pub job: Option<String>,
+
+    /// With --job, cancel the whole job with scancel instead of signaling its GPU processes
+    #[arg(long, requires = "job")]
+    pub use_scancel: bool,
+
+    /// Kill multiple processes matching the filter or GPU. On a terminal the targets
+    /// are shown and confirmed first unless --yes is given.
     #[arg(long)]
     pub batch: bool,
 
+    /// Skip the confirmation prompt before kills and resets
+    #[arg(long)]
+    pub yes: bool,
+
+    /// Also kill all child processes of each target (children first)
+    #[arg(long, requires = "kill")]
+    pub with_children: bool,
+
+    /// Also kill the top-level launcher of each target (e.g. torchrun) and its whole tree
+    #[arg(long, requires = "kill")]
+    pub with_parent: bool,
+
+    /// Let kills include protected processes (display server, sshd, container runtimes)
+    #[arg(long, requires = "kill", visible_alias = "override-protection")]
+    pub allow_protected: bool,
+
+    /// Let --force kills target gpukill itself or its parent shell
+    #[arg(long, requires_all = ["kill", "force"])]
+    pub allow_self: bool,
+
+    /// Add a process name to the protected list in the config file
+    #[arg(long, requires = "kill", value_name = "NAME")]
+    pub protect_process: Option<String>,
+
+    /// Remove a process name from the protected list in the config file
+    #[arg(long, requires = "kill", value_name = "NAME")]
+    pub unprotect_process: Option<String>,
+
+    /// Show the process names kills refuse to touch
+    #[arg(long, requires = "kill")]
+    pub list_protected: bool,
+
+    /// Kill processes whose GPU utilization has stayed idle for this long (e.g. 30m, 2h)
+    #[arg(long, requires = "kill", value_name = "DURATION", value_parser = parse_duration_arg)]
+    pub idle_for: Option<Duration>,
+
+    /// Same as --idle-for, in minutes
+    #[arg(long, requires = "kill", value_name = "MINUTES", value_parser = clap::value_parser!(u64).range(1..))]
+    pub idle_minutes: Option<u64>,
+
+    /// Utilization percentage at or below which a process counts as idle
+    #[arg(
+        long,
+        requires = "idle_window",
+        value_name = "PCT",
+        default_value = "1.0"
+    )]
+    pub idle_max_util: f32,
+
+    /// Only reap idle processes that held more than this much GPU memory (in GB)
+    /// throughout the idle window, e.g. leaked allocations
+    #[arg(long, requires = "idle_window", value_name = "GB")]
+    pub idle_mem_gb: Option<f64>,
+
+    /// Warn, wait this long, then re-check idle processes before killing them
+    #[arg(long, requires = "idle_window", value_name = "DURATION", value_parser = parse_duration_arg)]
+    pub idle_warn: Option<Duration>,
+
     /// Show container information for processes
     #[arg(long, requires = "list")]
     pub containers: bool,
 
+    /// Show full process command lines instead of truncating them to `cmdline_width`
+    #[arg(long, requires = "list")]
+    pub full_cmd: bool,
+
+    /// Show memory in tables as plain MB numbers instead of MiB/GiB
+    #[arg(long, requires = "list")]
+    pub raw_units: bool,
+
+    /// Show only the flat process list across all GPUs, without the GPU table
+    #[arg(
+        long,
+        requires = "list",
+        conflicts_with_all = ["details", "tree", "watch", "save_snapshot", "diff"]
+    )]
+    pub processes_only: bool,
+
+    /// Skip expensive optional metrics (ECC, retired pages, PCIe and NVLink traffic,
+    /// process details for the top process) for a quicker listing
+    #[arg(long, requires = "list")]
+    pub fast: bool,
+
+    /// Print how long each phase of the listing took to stderr
+    #[arg(long, requires = "list")]
+    pub timing: bool,
+
+    /// Wait for the audit write before rendering instead of finishing it in the background
+    #[arg(long, requires = "list", conflicts_with = "no_audit")]
+    pub sync_audit: bool,
+
+    /// Show each GPU's stable index, current enumeration index and UUID or PCI bus id
+    #[arg(
+        long,
+        requires = "list",
+        conflicts_with_all = ["details", "tree", "watch", "processes_only", "save_snapshot", "diff"]
+    )]
+    pub show_ids: bool,
+
+    /// Sort order for --processes-only
+    #[arg(
+        long,
+        value_enum,
+        requires = "processes_only",
+        default_value = "memory"
+    )]
+    pub sort: ProcessSort,
+
+    /// Save the current snapshot as JSON to this file, for use with --diff later
+    #[arg(long, requires = "list", conflicts_with = "watch", value_name = "PATH")]
+    pub save_snapshot: Option<PathBuf>,
+
+    /// Compare the current state against a snapshot saved with --save-snapshot
+    #[arg(long, requires = "list", conflicts_with = "watch", value_name = "PATH")]
+    pub diff: Option<PathBuf>,
+
+    /// Match --filter against the full command line instead of the process name
+    #[arg(long, requires = "filter")]
+    pub match_cmdline: bool,
+
+    /// Show GPU processes as a tree under their top-level launcher
+    #[arg(long, requires = "details")]
+    pub tree: bool,
+
     /// Filter audit by user name
     #[arg(long, requires = "audit")]
     pub audit_user: Option<String>,
@@ -106,10 +391,100 @@ pub struct Cli {
     #[arg(long, requires = "audit", default_value = "24")]
     pub audit_hours: u32,
 
+    /// Show audit records since this RFC3339 timestamp (e.g. 2024-06-01T08:00:00Z)
+    #[arg(
+        long,
+        requires = "audit",
+        conflicts_with = "audit_hours",
+        value_name = "RFC3339",
+        value_parser = parse_rfc3339_timestamp
+    )]
+    pub audit_since: Option<DateTime<Utc>>,
+
+    /// Show audit records up to this RFC3339 timestamp (defaults to now)
+    #[arg(
+        long,
+        requires = "audit",
+        value_name = "RFC3339",
+        value_parser = parse_rfc3339_timestamp
+    )]
+    pub audit_until: Option<DateTime<Utc>>,
+
+    /// Filter audit by GPU index
+    #[arg(long, requires = "audit", value_name = "INDEX")]
+    pub audit_gpu: Option<u16>,
+
+    /// Filter audit by container name
+    #[arg(long, requires = "audit", value_name = "NAME")]
+    pub audit_container: Option<String>,
+
+    /// Show at most N audit records, newest first (0 shows all)
+    #[arg(long, requires = "audit", default_value_t = DEFAULT_AUDIT_LIMIT, value_name = "N")]
+    pub audit_limit: usize,
+
+    /// Skip the N newest matching audit records
+    #[arg(long, requires = "audit", default_value_t = 0, value_name = "N")]
+    pub audit_offset: usize,
+
+    /// Only show audit records older than this RFC3339 timestamp, to page from
+    /// the last record shown
+    #[arg(
+        long,
+        requires = "audit",
+        value_name = "RFC3339",
+        value_parser = parse_rfc3339_timestamp
+    )]
+    pub audit_before: Option<DateTime<Utc>>,
+
+    /// Write JSON audit records one at a time instead of building the array first
+    #[arg(long, requires = "audit")]
+    pub stream: bool,
+
     /// Show audit summary (top users/processes)
     #[arg(long, requires = "audit")]
     pub audit_summary: bool,
 
+    /// With --audit-summary --output html: write the report to this file instead of stdout
+    #[arg(long, requires = "audit_summary", value_name = "PATH")]
+    pub audit_report_file: Option<PathBuf>,
+
+    /// Show the kills and resets run through gpukill, who ran them and how they ended
+    #[arg(long, requires = "audit")]
+    pub audit_actions: bool,
+
+    /// Show per-user and per-process GPU-hours accounting
+    #[arg(long, requires = "audit")]
+    pub accounting: bool,
+
+    /// Accounting range start (YYYY-MM-DD or RFC3339)
+    #[arg(
+        long = "from",
+        requires = "accounting",
+        conflicts_with_all = ["audit_since", "audit_hours"],
+        value_name = "DATE",
+        value_parser = parse_date_arg
+    )]
+    pub accounting_from: Option<DateTime<Utc>>,
+
+    /// Accounting range end (YYYY-MM-DD or RFC3339, defaults to now)
+    #[arg(
+        long = "to",
+        requires = "accounting",
+        conflicts_with = "audit_until",
+        value_name = "DATE",
+        value_parser = parse_date_arg
+    )]
+    pub accounting_to: Option<DateTime<Utc>>,
+
+    /// Longest interval (seconds) a single audit sample may account for
+    #[arg(
+        long,
+        requires = "accounting",
+        default_value_t = crate::audit::DEFAULT_ACCOUNTING_MAX_GAP_SECS,
+        value_name = "SECONDS"
+    )]
+    pub accounting_max_gap: u64,
+
     /// Detect suspicious/rogue GPU usage patterns
     #[arg(long, requires = "audit")]
     pub rogue: bool,
@@ -134,6 +509,18 @@ pub struct Cli {
     #[arg(long, requires = "audit", value_name = "CONFIDENCE")]
     pub rogue_confidence_threshold: Option<f32>,
 
+    /// Update the transfer rate above which data exfiltration is suspected
+    #[arg(long, requires = "audit", value_name = "MB_PER_SEC")]
+    pub rogue_exfil_rate_threshold: Option<f32>,
+
+    /// Update the compute utilization below which heavy transfers look like exfiltration
+    #[arg(long, requires = "audit", value_name = "PERCENT")]
+    pub rogue_exfil_util_threshold: Option<f32>,
+
+    /// Update how long transfers must last before data exfiltration is reported
+    #[arg(long, requires = "audit", value_name = "MINUTES")]
+    pub rogue_exfil_window: Option<f32>,
+
     /// Add process to rogue detection whitelist
     #[arg(long, requires = "audit", value_name = "PROCESS_NAME")]
     pub rogue_whitelist_process: Option<String>,
@@ -150,6 +537,63 @@ pub struct Cli {
     #[arg(long, requires = "audit", value_name = "USERNAME")]
     pub rogue_unwhitelist_user: Option<String>,
 
+    /// Add a custom rogue detection rule with this name (needs --rogue-rule-regex)
+    #[arg(
+        long,
+        requires = "audit",
+        requires = "rogue_rule_regex",
+        value_name = "NAME"
+    )]
+    pub rogue_add_rule: Option<String>,
+
+    /// Process name regex for the rule being added
+    #[arg(long, requires = "rogue_add_rule", value_name = "REGEX")]
+    pub rogue_rule_regex: Option<String>,
+
+    /// Minimum average memory (GB) for the rule being added
+    #[arg(
+        long,
+        requires = "rogue_add_rule",
+        value_name = "MEMORY_GB",
+        default_value_t = 0.0
+    )]
+    pub rogue_rule_min_memory: f32,
+
+    /// Minimum time (hours) the process must match for the rule being added
+    #[arg(
+        long,
+        requires = "rogue_add_rule",
+        value_name = "HOURS",
+        default_value_t = 0.0
+    )]
+    pub rogue_rule_min_duration: f32,
+
+    /// Minimum average GPU utilization (%) for the rule being added
+    #[arg(
+        long,
+        requires = "rogue_add_rule",
+        value_name = "PERCENT",
+        default_value_t = 0.0
+    )]
+    pub rogue_rule_min_util: f32,
+
+    /// Confidence the rule adds when it fires (0-1)
+    #[arg(
+        long,
+        requires = "rogue_add_rule",
+        value_name = "CONFIDENCE",
+        default_value_t = 0.5
+    )]
+    pub rogue_rule_confidence: f32,
+
+    /// List custom rogue detection rules
+    #[arg(long, requires = "audit")]
+    pub rogue_list_rules: bool,
+
+    /// Remove a custom rogue detection rule by name
+    #[arg(long, requires = "audit", value_name = "NAME")]
+    pub rogue_remove_rule: Option<String>,
+
     /// Export rogue detection configuration to JSON
     #[arg(long, requires = "audit")]
     pub rogue_export_config: bool,
@@ -266,41 +710,167 @@ pub struct Cli {
     #[arg(long, requires = "guard", value_name = "USERS")]
     pub guard_gpu_allowed_users: Option<String>,
 
+    /// Send a test email to the Guard Mode notification recipients and report SMTP errors
+    #[arg(long, requires = "guard")]
+    pub test_notification: bool,
+
+    /// Show which operations each GPU vendor supports
+    #[arg(long)]
+    pub capabilities: bool,
+
+    /// With --capabilities, also list which operations are available at the current privilege level
+    #[arg(long, requires = "capabilities")]
+    pub check_privileges: bool,
+
+    /// Show how NVIDIA GPUs are connected to each other (NVLink or PCIe) as a matrix
+    #[arg(long)]
+    pub topology: bool,
+
+    /// Print version, build and detected GPU vendors/count (a probe for orchestration tooling)
+    #[arg(long)]
+    pub info: bool,
+
+    /// Give the GPU with this UUID or PCI bus id a stable index, e.g. after replacing a card
+    #[arg(long, value_name = "UUID=INDEX", value_parser = crate::gpu_index::parse_remap)]
+    pub remap_gpu: Option<(String, u16)>,
+
+    /// Run a self-test: start a workload, check that it is listed, killed and its memory reclaimed
+    #[arg(long)]
+    pub selftest: bool,
+
+    /// With --selftest: program run as `<PATH> <MB>` that allocates MB of GPU memory and sleeps until killed
+    #[arg(long, requires = "selftest", value_name = "PATH")]
+    pub selftest_helper: Option<PathBuf>,
+
+    /// With --selftest: GPU memory the workload allocates, in MB
+    #[arg(
+        long,
+        requires = "selftest",
+        value_name = "MB",
+        default_value_t = DEFAULT_SELFTEST_MEM_MB,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub selftest_mem_mb: u32,
+
     /// Server port for coordinator API
     #[arg(long, requires = "server", default_value = "8080")]
     pub server_port: u16,
 
-    /// Server host for coordinator API
-    #[arg(long, requires = "server", default_value = "0.0.0.0")]
-    pub server_host: String,
+    /// Address to bind the coordinator API to (IPv4 or IPv6, e.g. 127.0.0.1 or ::1)
+    #[arg(long, requires = "server", value_name = "ADDR", default_value = "0.0.0.0", value_parser = parse_server_host)]
+    pub server_host: IpAddr,
+
+    /// PEM certificate chain to serve the coordinator API over HTTPS (with --server-tls-key)
+    #[arg(
+        long,
+        requires = "server",
+        requires = "server_tls_key",
+        value_name = "PATH"
+    )]
+    pub server_tls_cert: Option<PathBuf>,
+
+    /// PEM private key for --server-tls-cert
+    #[arg(
+        long,
+        requires = "server",
+        requires = "server_tls_cert",
+        value_name = "PATH"
+    )]
+    pub server_tls_key: Option<PathBuf>,
+
+    /// Mark a node offline after it has not reported for this long (e.g. 90s, 5m)
+    #[arg(long, requires = "server", value_name = "DURATION", default_value = "90s", value_parser = parse_duration_arg)]
+    pub node_offline_after: Duration,
+
+    /// Remove a node after it has not reported for this long; must exceed --node-offline-after
+    #[arg(long, requires = "server", value_name = "DURATION", default_value = "30m", value_parser = parse_duration_arg)]
+    pub node_remove_after: Duration,
 
     /// Register this node with a coordinator
     #[arg(long, value_name = "COORDINATOR_URL")]
     pub register_node: Option<String>,
 
-    /// Remote host to connect to via SSH
-    #[arg(long)]
+    /// Tag this node as KEY=VALUE when registering (repeatable; overrides node_tags in the config)
+    #[arg(long = "node-tag", requires = "coordinator_node", value_name = "KEY=VALUE", value_parser = parse_node_tag)]
+    pub node_tags: Vec<(String, String)>,
+
+    /// After the first snapshot, send the coordinator only what changed since the previous one
+    #[arg(long, requires = "register_node")]
+    pub snapshot_delta: bool,
+
+    /// How often a node runs rogue detection on its own audit history and reports it (e.g. 15m)
+    #[arg(long, requires = "coordinator_node", value_name = "DURATION", default_value = "15m", value_parser = parse_duration_arg)]
+    pub rogue_scan_interval: Duration,
+
+    /// Flag a node's rogue detection result as stale once it is older than this (e.g. 1h)
+    #[arg(long, requires = "server", value_name = "DURATION", default_value = "1h", value_parser = parse_duration_arg)]
+    pub rogue_stale_after: Duration,
+
+    /// Bearer token for cluster resets: the coordinator requires it on /api/cluster/reset,
+    /// and a node needs it to accept resets from the coordinator
+    #[arg(
+        long,
+        env = "GPUKILL_CLUSTER_TOKEN",
+        value_name = "TOKEN",
+        hide_env_values = true
+    )]
+    pub cluster_token: Option<String>,
+
+    /// Remote host(s) to connect to via SSH (comma-separated for multiple hosts)
+    #[arg(long, value_name = "HOST[,HOST...]")]
     pub remote: Option<String>,
 
+    /// File listing remote hosts, one per line (blank lines and # comments are ignored)
+    #[arg(long, value_name = "FILE")]
+    pub remote_file: Option<PathBuf>,
+
+    /// Maximum number of remote hosts contacted concurrently
+    #[arg(
+        long,
+        requires = "remote_target",
+        default_value_t = 4,
+        value_parser = clap::value_parser!(u16).range(1..)
+    )]
+    pub remote_parallel: u16,
+
     /// SSH username (defaults to current user)
-    #[arg(long, requires = "remote")]
+    #[arg(long, requires = "remote_target")]
     pub ssh_user: Option<String>,
 
     /// SSH port (defaults to 22)
-    #[arg(long, requires = "remote", default_value = "22")]
+    #[arg(long, requires = "remote_target", default_value = "22")]
     pub ssh_port: u16,
 
     /// SSH private key path
-    #[arg(long, requires = "remote")]
+    #[arg(long, requires = "remote_target")]
     pub ssh_key: Option<String>,
 
     /// SSH password (interactive prompt if not provided)
-    #[arg(long, requires = "remote")]
+    #[arg(long, requires = "remote_target")]
     pub ssh_password: Option<String>,
 
     /// SSH connection timeout in seconds
-    #[arg(long, requires = "remote", default_value = "30")]
+    #[arg(long, requires = "remote_target", default_value = "30")]
     pub ssh_timeout: u16,
+
+    /// Jump host(s) to reach the targets through, as [user@]host[:port][,...]
+    /// (defaults to ProxyJump from ~/.ssh/config)
+    #[arg(long, requires = "remote_target", value_name = "SPEC", value_parser = parse_ssh_jump)]
+    pub ssh_jump: Option<String>,
+
+    /// Skip known_hosts verification (insecure)
+    #[arg(long, requires = "remote_target")]
+    pub ssh_insecure: bool,
+
+    /// Options from CONFIGURABLE_ARGS that were given on the command line
+    #[arg(skip)]
+    explicit_args: Vec<String>,
+}
+
+fn parse_ssh_jump(spec: &str) -> Result<String, String> {
+    crate::remote::parse_jump_hosts(spec)
+        .map(|_| spec.to_string())
+        .map_err(|e| e.to_string())
 }
 
 #[derive(ValueEnum, Clone, Debug)]
@@ -312,6 +882,15 @@ pub enum LogLevel {
     Error,
 }
 
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[value(alias = "text")]
+    Human,
+    /// One JSON object per event, for log shippers
+    Json,
+}
+
 #[derive(ValueEnum, Clone, Debug, PartialEq)]
 pub enum VendorFilter {
     Nvidia,
@@ -333,10 +912,56 @@ impl VendorFilter {
     }
 }
 
+/// Sort order for the flat process list
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ProcessSort {
+    /// Largest GPU memory first
+    Memory,
+    /// By GPU index, then PID
+    Gpu,
+    /// By PID
+    Pid,
+    /// By user name, then largest memory
+    User,
+}
+
+impl ProcessSort {
+    /// Sort `procs` in this order; ties fall back to GPU index and PID
+    pub fn sort(&self, procs: &mut [GpuProc]) {
+        procs.sort_by(|a, b| {
+            let primary = match self {
+                ProcessSort::Memory => b.used_mem_mb.cmp(&a.used_mem_mb),
+                ProcessSort::Gpu => a.gpu_index.cmp(&b.gpu_index),
+                ProcessSort::Pid => a.pid.cmp(&b.pid),
+                ProcessSort::User => a
+                    .user
+                    .cmp(&b.user)
+                    .then_with(|| b.used_mem_mb.cmp(&a.used_mem_mb)),
+            };
+            primary.then_with(|| (a.gpu_index, a.pid).cmp(&(b.gpu_index, b.pid)))
+        });
+    }
+}
+
+/// When rich (colored, emoji, Unicode-bordered) output is used
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Rich output on a terminal, unless NO_COLOR is set
+    Auto,
+    Always,
+    Never,
+}
+
 #[derive(ValueEnum, Clone, Debug, PartialEq)]
 pub enum OutputFormat {
     Table,
     Json,
+    /// Comma-separated values (audit accounting only)
+    Csv,
+    /// Newline-delimited JSON, one audit record per line (audit records only)
+    Ndjson,
+    /// Standalone HTML report with inline charts (audit summary only)
+    Html,
 }
 
 impl std::fmt::Display for LogLevel {
@@ -351,18 +976,398 @@ impl std::fmt::Display for LogLevel {
     }
 }
 
+impl std::fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogFormat::Human => write!(f, "human"),
+            LogFormat::Json => write!(f, "json"),
+        }
+    }
+}
+
+impl std::str::FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "human" | "text" => Ok(LogFormat::Human),
+            "json" => Ok(LogFormat::Json),
+            other => Err(anyhow::anyhow!(
+                "Invalid log format '{}' (expected human, text or json)",
+                other
+            )),
+        }
+    }
+}
+
 impl std::fmt::Display for OutputFormat {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             OutputFormat::Table => write!(f, "table"),
             OutputFormat::Json => write!(f, "json"),
+            OutputFormat::Csv => write!(f, "csv"),
+            OutputFormat::Ndjson => write!(f, "ndjson"),
+            OutputFormat::Html => write!(f, "html"),
         }
     }
 }
 
+/// Parse an RFC3339 timestamp argument into UTC
+fn parse_rfc3339_timestamp(value: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|ts| ts.with_timezone(&Utc))
+        .map_err(|e| {
+            format!(
+                "'{}' is not a valid RFC3339 timestamp ({}); expected e.g. 2024-06-01T08:00:00Z",
+                value, e
+            )
+        })
+}
+
+/// Parse a date (YYYY-MM-DD) or RFC3339 timestamp argument into UTC
+fn parse_date_arg(value: &str) -> Result<DateTime<Utc>, String> {
+    crate::util::parse_date_or_timestamp(value).map_err(|e| e.to_string())
+}
+
+/// Parse a bind address for the coordinator server; IPv6 may be given with or without brackets
+fn parse_server_host(value: &str) -> Result<IpAddr, String> {
+    let trimmed = value.trim();
+    let unbracketed = trimmed
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .unwrap_or(trimmed);
+    unbracketed.parse().map_err(|_| {
+        format!(
+            "'{}' is not a valid IP address; expected e.g. 0.0.0.0, 127.0.0.1, :: or ::1",
+            value
+        )
+    })
+}
+
+fn parse_node_tag(value: &str) -> Result<(String, String), String> {
+    crate::coordinator::selector::parse_tag(value).map_err(|e| e.to_string())
+}
+
+fn parse_pid_arg(value: &str) -> Result<PidTarget, String> {
+    value.parse().map_err(|e: anyhow::Error| e.to_string())
+}
+
+fn parse_job_arg(value: &str) -> Result<String, String> {
+    crate::slurm::parse_job_id(value).map_err(|e| e.to_string())
+}
+
+fn parse_signal_arg(value: &str) -> Result<KillSignal, String> {
+    value.parse().map_err(|e: anyhow::Error| e.to_string())
+}
+
+fn parse_duration_arg(value: &str) -> Result<Duration, String> {
+    match crate::util::parse_duration_spec(value) {
+        Ok(duration) if duration.is_zero() => Err("duration must be greater than 0".to_string()),
+        Ok(duration) => Ok(duration),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Options with a default in the config file; only these need to know whether
+/// the command line set them explicitly
+const CONFIGURABLE_ARGS: [&str; 3] = ["output", "timeout_secs", "log_level"];
+
+/// Parse a config file value for a CLI enum, naming the setting on failure
+fn parse_config_value<T: ValueEnum>(setting: &str, value: &str) -> anyhow::Result<T> {
+    T::from_str(value.trim(), true)
+        .map_err(|_| anyhow::anyhow!("Invalid {} '{}' in configuration", setting, value))
+}
+
 impl Cli {
-    /// Parse command line arguments with validation
-    pub fn parse() -> Self {
+    /// Parse `argv`, remembering which options were given on the command line so
+    /// that config file defaults only fill in the rest
+    pub fn try_parse_args_from<I, T>(argv: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let matches = Self::command().try_get_matches_from(argv)?;
+        let mut cli = Self::from_arg_matches(&matches)?;
+        cli.explicit_args = CONFIGURABLE_ARGS
+            .iter()
+            .filter(|id| matches.value_source(id) == Some(ValueSource::CommandLine))
+            .map(|id| id.to_string())
+            .collect();
+        Ok(cli)
+    }
+
+    /// Fill in options that were not given on the command line from the
+    /// configuration, which already includes environment overrides.
+    /// Precedence: CLI > environment > config file > built-in default.
+    pub fn apply_config_defaults(&mut self, config: &crate::config::Config) -> anyhow::Result<()> {
+        let from_cli = |id: &str| self.explicit_args.iter().any(|arg| arg == id);
+        let (output_set, timeout_set, log_level_set) = (
+            from_cli("output"),
+            from_cli("timeout_secs"),
+            from_cli("log_level"),
+        );
+
+        if !output_set {
+            self.output = parse_config_value("output_format", &config.output_format)?;
+        }
+        if !timeout_set {
+            self.timeout_secs = config.default_timeout_secs;
+        }
+        if !log_level_set {
+            self.log_level = parse_config_value("log_level", &config.log_level)?;
+        }
+        if self.vendor.is_none() {
+            if let Some(vendor) = &config.vendor {
+                self.vendor = Some(parse_config_value("vendor", vendor)?);
+            }
+        }
+        // --force has no "off" form, so the config can only turn it on
+        self.force |= config.force;
+        self.no_audit |= !config.audit.log_list;
+        Ok(())
+    }
+
+    /// Resolve the accounting range from --from/--to, falling back to the
+    /// --audit-since/--audit-until range or the --audit-hours window.
+    pub fn accounting_time_range(&self) -> anyhow::Result<(DateTime<Utc>, DateTime<Utc>)> {
+        let (default_start, default_end) = match self.audit_time_range()? {
+            Some(range) => range,
+            None => {
+                let end = Utc::now();
+                (end - chrono::Duration::hours(self.audit_hours as i64), end)
+            }
+        };
+        let end = self.accounting_to.unwrap_or(default_end);
+        let start = match (self.accounting_from, self.accounting_to) {
+            (Some(from), _) => from,
+            (None, Some(_)) => end - chrono::Duration::hours(self.audit_hours as i64),
+            (None, None) => default_start,
+        };
+
+        if start > end {
+            return Err(anyhow::anyhow!(
+                "Invalid argument: --from ({}) must not be later than --to ({})",
+                start.to_rfc3339(),
+                end.to_rfc3339()
+            ));
+        }
+
+        Ok((start, end))
+    }
+
+    /// Resolve the explicit audit time range from --audit-since/--audit-until.
+    /// Returns None when neither is given so callers fall back to --audit-hours.
+    /// With only --audit-until, the range covers the --audit-hours before it.
+    pub fn audit_time_range(&self) -> anyhow::Result<Option<(DateTime<Utc>, DateTime<Utc>)>> {
+        if self.audit_since.is_none() && self.audit_until.is_none() {
+            return Ok(None);
+        }
+
+        let end = self.audit_until.unwrap_or_else(Utc::now);
+        let start = self
+            .audit_since
+            .unwrap_or_else(|| end - chrono::Duration::hours(self.audit_hours as i64));
+
+        if start > end {
+            return Err(anyhow::anyhow!(
+                "Invalid argument: --audit-since ({}) must not be later than --audit-until ({})",
+                start.to_rfc3339(),
+                end.to_rfc3339()
+            ));
+        }
+
+        Ok(Some((start, end)))
+    }
+
+    /// Build the detailed-records audit query from the time range, filter and
+    /// paging flags
+    pub fn audit_query(&self) -> anyhow::Result<crate::audit_store::AuditQuery> {
+        let (start, end) = match self.audit_time_range()? {
+            Some(range) => range,
+            None => {
+                let end = Utc::now();
+                (end - chrono::Duration::hours(self.audit_hours as i64), end)
+            }
+        };
+
+        Ok(crate::audit_store::AuditQuery {
+            user: self.audit_user.clone(),
+            process: self.audit_process.clone(),
+            gpu: self.audit_gpu,
+            container: self.audit_container.clone(),
+            before: self.audit_before,
+            offset: self.audit_offset,
+            limit: (self.audit_limit > 0).then_some(self.audit_limit),
+            ..crate::audit_store::AuditQuery::range(start, end)
+        })
+    }
+
+    /// --color, with --no-color taking precedence
+    pub fn color_choice(&self) -> ColorChoice {
+        if self.no_color {
+            ColorChoice::Never
+        } else {
+            self.color
+        }
+    }
+
+    /// Idle window of the idle reaper, from --idle-for or --idle-minutes
+    pub fn idle_window(&self) -> Option<Duration> {
+        self.idle_for.or_else(|| {
+            self.idle_minutes
+                .map(|minutes| Duration::from_secs(minutes * 60))
+        })
+    }
+
+    /// --alert-util, --alert-mem-pct and --alert-temp
+    pub fn alert_thresholds(&self) -> crate::snapshot::AlertThresholds {
+        crate::snapshot::AlertThresholds {
+            util_pct: self.alert_util,
+            mem_pct: self.alert_mem_pct,
+            temp_c: self.alert_temp,
+        }
+    }
+
+    /// --idle-mem-gb in MB
+    pub fn idle_min_mem_mb(&self) -> Option<u32> {
+        self.idle_mem_gb.map(|gb| (gb * 1024.0).round() as u32)
+    }
+
+    /// What a kill, reset or set is aimed at, for the action audit log
+    pub fn action_target(&self) -> String {
+        if self.set {
+            let mut changes = Vec::new();
+            if let Some(watts) = self.power_limit {
+                changes.push(format!("power limit {} W", watts));
+            }
+            if let Some(mhz) = self.lock_clocks {
+                changes.push(format!("clocks locked at {} MHz", mhz));
+            }
+            if self.reset_clocks {
+                changes.push("clocks reset".to_string());
+            }
+            let gpu = match self.gpu {
+                Some(gpu) => format!("gpu {}", gpu),
+                None => "no gpu".to_string(),
+            };
+            return format!("{}: {}", gpu, changes.join(", "));
+        }
+        if self.reset {
+            return match (self.hard, self.all, self.gpu) {
+                (true, _, _) => "all gpus (hard reset)".to_string(),
+                (false, false, Some(gpu)) => format!("gpu {}", gpu),
+                _ => "all gpus".to_string(),
+            };
+        }
+        if let Some(job) = &self.job {
+            return format!("slurm job {}", job);
+        }
+        if let Some(idle_for) = self.idle_window() {
+            let mut target = format!("processes idle for {}s", idle_for.as_secs());
+            if let Some(gb) = self.idle_mem_gb {
+                target.push_str(&format!(" holding more than {} GB", gb));
+            }
+            return match self.gpu {
+                Some(gpu) => format!("{} on gpu {}", target, gpu),
+                None => target,
+            };
+        }
+        // Same precedence as the kill itself: filter and user, then PID, then GPU
+        match (&self.filter, &self.filter_user, &self.pid, self.gpu) {
+            (Some(filter), Some(user), _, _) if self.match_cmdline => {
+                format!("cmdline {} of user {}", filter, user)
+            }
+            (Some(filter), Some(user), _, _) => format!("filter {} of user {}", filter, user),
+            (Some(filter), None, _, _) if self.match_cmdline => format!("cmdline {}", filter),
+            (Some(filter), None, _, _) => format!("filter {}", filter),
+            (None, Some(user), _, _) => format!("user {}", user),
+            (None, None, Some(pid), _) => format!("pid {}", pid),
+            (None, None, None, Some(gpu)) => format!("gpu {}", gpu),
+            (None, None, None, None) => "none".to_string(),
+        }
+    }
+
+    /// Name of the requested operation, used to tag log events
+    pub fn operation_name(&self) -> &'static str {
+        if self.list {
+            "list"
+        } else if self.kill {
+            "kill"
+        } else if self.reset {
+            "reset"
+        } else if self.set {
+            "set"
+        } else if self.audit {
+            "audit"
+        } else if self.server {
+            "server"
+        } else if self.serve_local {
+            "serve_local"
+        } else if self.guard {
+            "guard"
+        } else if self.selftest {
+            "selftest"
+        } else if self.capabilities {
+            "capabilities"
+        } else if self.topology {
+            "topology"
+        } else if self.info {
+            "info"
+        } else if self.remap_gpu.is_some() {
+            "remap_gpu"
+        } else if self.register_node.is_some() {
+            "register_node"
+        } else {
+            "none"
+        }
+    }
+
+    /// Whether this invocation targets one or more remote hosts
+    pub fn is_remote(&self) -> bool {
+        self.remote.is_some() || self.remote_file.is_some()
+    }
+
+    /// Whether a single GPU was chosen, by --gpu or --gpu-uuid
+    pub fn gpu_selected(&self) -> bool {
+        self.gpu.is_some() || self.gpu_uuid.is_some()
+    }
+
+    /// Collect remote hosts from --remote and --remote-file, in order and without duplicates.
+    pub fn remote_hosts(&self) -> anyhow::Result<Vec<String>> {
+        let mut hosts: Vec<String> = Vec::new();
+        let mut push = |host: &str| {
+            let host = host.trim();
+            if !host.is_empty() && !hosts.iter().any(|h| h == host) {
+                hosts.push(host.to_string());
+            }
+        };
+
+        if let Some(remote) = &self.remote {
+            remote.split(',').for_each(&mut push);
+        }
+
+        if let Some(path) = &self.remote_file {
+            let content = std::fs::read_to_string(path).map_err(|e| {
+                anyhow::anyhow!("Failed to read remote host file {}: {}", path.display(), e)
+            })?;
+            content
+                .lines()
+                .map(|line| line.split('#').next().unwrap_or(""))
+                .for_each(&mut push);
+        }
+
+        if hosts.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Invalid argument: no remote hosts given via --remote or --remote-file"
+            ));
+        }
+
+        Ok(hosts)
+    }
+
+    /// Parse command line arguments. Call `apply_config_defaults` and then
+    /// `validate` before using the result.
+    pub fn parse_args() -> Self {
         // Pre-process argv to support friendly shorthands before clap parsing
         let mut argv: Vec<String> = std::env::args().collect();
 
@@ -371,7 +1376,17 @@ impl Cli {
         let has_operation_flag = argv.iter().any(|a| {
             matches!(
                 a.as_str(),
-                "--list" | "--kill" | "--reset" | "--audit" | "--server" | "--guard"
+                "--list"
+                    | "--kill"
+                    | "--reset"
+                    | "--audit"
+                    | "--server"
+                    | "--guard"
+                    | "--selftest"
+                    | "--capabilities"
+                    | "--topology"
+                    | "--info"
+                    | "--remap-gpu"
             )
         });
         if !has_operation_flag {
@@ -420,7 +1435,17 @@ impl Cli {
         let has_operation_flag = argv.iter().any(|a| {
             matches!(
                 a.as_str(),
-                "--list" | "--kill" | "--reset" | "--audit" | "--server" | "--guard"
+                "--list"
+                    | "--kill"
+                    | "--reset"
+                    | "--audit"
+                    | "--server"
+                    | "--guard"
+                    | "--selftest"
+                    | "--capabilities"
+                    | "--topology"
+                    | "--info"
+                    | "--remap-gpu"
             )
         });
         if !has_operation_flag {
@@ -443,7 +1468,17 @@ impl Cli {
         let has_operation_flag2 = argv.iter().any(|a| {
             matches!(
                 a.as_str(),
-                "--list" | "--kill" | "--reset" | "--audit" | "--server" | "--guard"
+                "--list"
+                    | "--kill"
+                    | "--reset"
+                    | "--audit"
+                    | "--server"
+                    | "--guard"
+                    | "--selftest"
+                    | "--capabilities"
+                    | "--topology"
+                    | "--info"
+                    | "--remap-gpu"
             )
         });
         if !has_operation_flag2 {
@@ -461,34 +1496,145 @@ impl Cli {
             }
         }
 
-        let cli = Self::parse_from(argv);
-        cli.validate();
-        cli
+        Self::try_parse_args_from(argv).unwrap_or_else(|e| e.exit())
     }
 
     /// Validate argument combinations
-    fn validate(&self) {
+    pub fn validate(&self) {
         // Check that exactly one operation is specified
         let operation_count = [
             self.list,
             self.kill,
             self.reset,
+            self.set,
             self.audit,
             self.server,
+            self.serve_local,
             self.guard,
+            self.selftest,
+            self.capabilities,
+            self.topology,
+            self.info,
+            self.remap_gpu.is_some(),
         ]
         .iter()
         .filter(|&&x| x)
         .count();
         if operation_count == 0 {
-            eprintln!("Error: Exactly one of --list, --kill, --reset, --audit, --server, or --guard must be specified");
+            eprintln!("Error: Exactly one of --list, --kill, --reset, --set, --audit, --server, --serve-local, --guard, --selftest, --capabilities, --topology, --info, or --remap-gpu must be specified");
             std::process::exit(3);
         }
         if operation_count > 1 {
-            eprintln!("Error: Only one of --list, --kill, --reset, --audit, --server, or --guard can be specified");
+            eprintln!("Error: Only one of --list, --kill, --reset, --set, --audit, --server, --serve-local, --guard, --selftest, --capabilities, --topology, --info, or --remap-gpu can be specified");
+            std::process::exit(3);
+        }
+
+        if self.output == OutputFormat::Csv && !self.accounting {
+            eprintln!("Error: --output csv is only supported with --audit --accounting");
+            std::process::exit(3);
+        }
+
+        if self.output == OutputFormat::Ndjson
+            && (!self.audit || self.audit_summary || self.rogue || self.accounting)
+        {
+            eprintln!("Error: --output ndjson is only supported with --audit detailed records");
+            std::process::exit(3);
+        }
+
+        if self.output == OutputFormat::Html
+            && (!self.audit_summary || self.rogue || self.accounting || self.audit_actions)
+        {
+            eprintln!("Error: --output html is only supported with --audit --audit-summary");
+            std::process::exit(3);
+        }
+
+        if self.audit_report_file.is_some() && self.output != OutputFormat::Html {
+            eprintln!("Error: --audit-report-file requires --output html");
+            std::process::exit(3);
+        }
+
+        if self.is_remote() && self.output == OutputFormat::Html {
+            eprintln!("Error: --output html only applies to the local host");
+            std::process::exit(3);
+        }
+
+        if self.stream
+            && (self.output != OutputFormat::Json
+                || self.audit_summary
+                || self.rogue
+                || self.accounting)
+        {
+            eprintln!(
+                "Error: --stream is only supported with --audit --output json detailed records"
+            );
+            std::process::exit(3);
+        }
+
+        if self.is_remote() && (self.save_snapshot.is_some() || self.diff.is_some()) {
+            eprintln!("Error: --save-snapshot and --diff only apply to the local host");
+            std::process::exit(3);
+        }
+
+        if self.is_remote() && self.selftest {
+            eprintln!("Error: --selftest only applies to the local host");
+            std::process::exit(3);
+        }
+
+        if self.is_remote() && self.capabilities {
+            eprintln!("Error: --capabilities only applies to the local host");
+            std::process::exit(3);
+        }
+
+        if self.is_remote() && self.topology {
+            eprintln!("Error: --topology only applies to the local host");
+            std::process::exit(3);
+        }
+
+        if self.is_remote() && self.info {
+            eprintln!("Error: --info only applies to the local host");
+            std::process::exit(3);
+        }
+
+        if self.is_remote() && (self.remap_gpu.is_some() || self.show_ids) {
+            eprintln!("Error: --remap-gpu and --show-ids only apply to the local host");
+            std::process::exit(3);
+        }
+
+        if self.is_remote() && self.processes_only {
+            eprintln!("Error: --processes-only only applies to the local host");
+            std::process::exit(3);
+        }
+
+        if self.server && self.node_remove_after <= self.node_offline_after {
+            eprintln!("Error: --node-remove-after must be longer than --node-offline-after");
             std::process::exit(3);
         }
 
+        if (self.server || self.register_node.is_some())
+            && self.cluster_token.as_deref() == Some("")
+        {
+            eprintln!("Error: --cluster-token must not be empty");
+            std::process::exit(3);
+        }
+
+        if self.serve_local {
+            if self.is_remote() {
+                eprintln!("Error: --serve-local only applies to the local host");
+                std::process::exit(3);
+            }
+            if self.api_token.as_deref() == Some("") {
+                eprintln!("Error: --api-token must not be empty");
+                std::process::exit(3);
+            }
+            if !self.bind.is_loopback() && self.api_token.is_none() {
+                eprintln!(
+                    "Error: --serve-local on {} is reachable from other hosts; set --api-token or GPUKILL_API_TOKEN",
+                    self.bind
+                );
+                std::process::exit(3);
+            }
+        }
+
         // Validate kill operation
         if self.kill {
             if self.pid.is_some() && self.filter.is_some() {
@@ -496,8 +1642,45 @@ impl Cli {
                 std::process::exit(3);
             }
 
-            // Allow one of: --pid, --filter, or --gpu (kill-by-GPU)
-            if self.pid.is_none() && self.filter.is_none() && self.gpu.is_none() {
+            if self.idle_window().is_some() {
+                if self.pid.is_some() || self.filter.is_some() {
+                    eprintln!("Error: --idle-for cannot be combined with --pid or --filter");
+                    std::process::exit(3);
+                }
+                if self.with_children || self.with_parent {
+                    eprintln!(
+                        "Error: --idle-for cannot be combined with --with-children or --with-parent"
+                    );
+                    std::process::exit(3);
+                }
+                if !(0.0..=100.0).contains(&self.idle_max_util) {
+                    eprintln!("Error: --idle-max-util must be between 0 and 100");
+                    std::process::exit(3);
+                }
+                if self
+                    .idle_mem_gb
+                    .is_some_and(|gb| !(gb >= 0.0 && gb.is_finite()))
+                {
+                    eprintln!("Error: --idle-mem-gb must be a non-negative number");
+                    std::process::exit(3);
+                }
+                if self.idle_mem_gb.is_some_and(|gb| gb > MAX_IDLE_MEM_GB) {
+                    eprintln!(
+                        "Error: --idle-mem-gb must be at most {} (no GPU has that much memory)",
+                        MAX_IDLE_MEM_GB
+                    );
+                    std::process::exit(3);
+                }
+            }
+
+            // Allow one of: --pid, --filter/--filter-user, --gpu (kill-by-GPU), --idle-for or --job
+            if self.pid.is_none()
+                && self.filter.is_none()
+                && self.filter_user.is_none()
+                && !self.gpu_selected()
+                && self.idle_window().is_none()
+                && self.job.is_none()
+            {
                 // Keep legacy substring for compatibility with tests and tooling, while documenting --gpu
                 eprintln!(
                     "Error: --kill requires either --pid <PID> or --filter <PATTERN> (or --gpu <ID>)"
@@ -505,8 +1688,8 @@ impl Cli {
                 std::process::exit(3);
             }
 
-            if let Some(pid) = self.pid {
-                if pid == 0 {
+            if let Some(target) = &self.pid {
+                if target.pid() == 0 {
                     eprintln!("Error: PID must be greater than 0");
                     std::process::exit(3);
                 }
@@ -515,12 +1698,34 @@ impl Cli {
 
         // Validate reset operation
         if self.reset {
-            if self.gpu.is_none() && !self.all {
-                eprintln!("Error: --reset requires either --gpu <ID> or --all");
+            if self.hard && self.gpu_selected() {
+                eprintln!("Error: --reset --hard reloads the driver for every GPU and cannot target --gpu/--gpu-uuid");
+                std::process::exit(3);
+            }
+            if !self.gpu_selected() && !self.all && !self.hard {
+                eprintln!(
+                    "Error: --reset requires either --gpu <ID> (or --gpu-uuid <UUID>) or --all"
+                );
+                std::process::exit(3);
+            }
+            if self.gpu_selected() && self.all {
+                eprintln!("Error: --reset cannot combine --gpu/--gpu-uuid with --all");
+                std::process::exit(3);
+            }
+        }
+
+        // Validate set operation
+        if self.set {
+            if !self.gpu_selected() {
+                eprintln!("Error: --set requires --gpu <ID> or --gpu-uuid <UUID>");
                 std::process::exit(3);
             }
-            if self.gpu.is_some() && self.all {
-                eprintln!("Error: --reset cannot use both --gpu and --all");
+            if self.power_limit.is_none() && self.lock_clocks.is_none() && !self.reset_clocks {
+                eprintln!("Error: --set requires --power-limit, --lock-clocks or --reset-clocks");
+                std::process::exit(3);
+            }
+            if self.power_limit == Some(0) || self.lock_clocks == Some(0) {
+                eprintln!("Error: --power-limit and --lock-clocks must be greater than 0");
                 std::process::exit(3);
             }
         }
@@ -535,10 +1740,23 @@ impl Cli {
                 eprintln!("Error: --watch requires --list");
                 std::process::exit(3);
             }
+            if self.follow_pid.is_some() && self.output != OutputFormat::Table {
+                eprintln!("Error: --follow-pid prints plain lines and does not support --output");
+                std::process::exit(3);
+            }
             if self.containers && !self.list {
                 eprintln!("Error: --containers requires --list");
                 std::process::exit(3);
             }
+            for (flag, pct) in [
+                ("--alert-util", self.alert_util),
+                ("--alert-mem-pct", self.alert_mem_pct),
+            ] {
+                if pct.is_some_and(|pct| !(0.0..=100.0).contains(&pct)) {
+                    eprintln!("Error: {} must be between 0 and 100", flag);
+                    std::process::exit(3);
+                }
+            }
         }
 
         // Validate kill operation dependencies
@@ -547,10 +1765,10 @@ impl Cli {
                 eprintln!("Error: --filter requires --kill");
                 std::process::exit(3);
             }
-            if self.batch && self.filter.is_none() {
-                // Allow batch with either filter or gpu (kill-by-GPU)
-                if self.gpu.is_none() {
-                    eprintln!("Error: --batch requires --filter or --gpu");
+            if self.batch && self.filter.is_none() && self.filter_user.is_none() {
+                // Allow batch with filter, user, gpu (kill-by-GPU), idle-for or job
+                if !self.gpu_selected() && self.idle_window().is_none() && self.job.is_none() {
+                    eprintln!("Error: --batch requires --filter, --filter-user, --gpu, --gpu-uuid, --idle-for or --job");
                     std::process::exit(3);
                 }
             }
@@ -595,7 +1813,169 @@ mod tests {
     }
 
     #[test]
-    fn test_list_json_output() {
+    fn test_server_host_parsing() {
+        use std::net::{Ipv4Addr, Ipv6Addr};
+
+        let cli = Cli::try_parse_from(["gpukill", "--server"]).unwrap();
+        assert_eq!(cli.server_host, IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+
+        let cli =
+            Cli::try_parse_from(["gpukill", "--server", "--server-host", "127.0.0.1"]).unwrap();
+        assert_eq!(cli.server_host, IpAddr::V4(Ipv4Addr::LOCALHOST));
+
+        let cli = Cli::try_parse_from(["gpukill", "--server", "--server-host", "::1"]).unwrap();
+        assert_eq!(cli.server_host, IpAddr::V6(Ipv6Addr::LOCALHOST));
+
+        let cli = Cli::try_parse_from(["gpukill", "--server", "--server-host", "::"]).unwrap();
+        assert_eq!(cli.server_host, IpAddr::V6(Ipv6Addr::UNSPECIFIED));
+
+        let cli = Cli::try_parse_from(["gpukill", "--server", "--server-host", "[::1]"]).unwrap();
+        assert_eq!(cli.server_host, IpAddr::V6(Ipv6Addr::LOCALHOST));
+    }
+
+    #[test]
+    fn test_server_tls_args() {
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--server",
+            "--server-tls-cert",
+            "cert.pem",
+            "--server-tls-key",
+            "key.pem",
+        ])
+        .unwrap();
+        assert_eq!(cli.server_tls_cert, Some(PathBuf::from("cert.pem")));
+        assert_eq!(cli.server_tls_key, Some(PathBuf::from("key.pem")));
+
+        // Both or neither, and only with --server
+        assert!(Cli::try_parse_from(["gpukill", "--server", "--server-tls-cert", "c"]).is_err());
+        assert!(Cli::try_parse_from(["gpukill", "--server", "--server-tls-key", "k"]).is_err());
+        assert!(Cli::try_parse_from([
+            "gpukill",
+            "--list",
+            "--server-tls-cert",
+            "c",
+            "--server-tls-key",
+            "k"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_serve_local_defaults_to_loopback() {
+        use std::net::Ipv4Addr;
+
+        let cli = Cli::try_parse_from(["gpukill", "--serve-local"]).unwrap();
+        assert_eq!(cli.operation_name(), "serve_local");
+        assert_eq!(cli.port, crate::local_api::DEFAULT_LOCAL_API_PORT);
+        assert_eq!(cli.bind, IpAddr::V4(Ipv4Addr::LOCALHOST));
+
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--serve-local",
+            "--port",
+            "9000",
+            "--bind",
+            "::1",
+        ])
+        .unwrap();
+        assert_eq!(cli.port, 9000);
+        assert!(cli.bind.is_loopback());
+
+        // The port and address only apply to the local API
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--port", "9000"]).is_err());
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--bind", "0.0.0.0"]).is_err());
+    }
+
+    #[test]
+    fn test_server_host_rejects_garbage() {
+        for host in ["localhost", "300.1.1.1", "::g", "0.0.0.0:8080", ""] {
+            let err = Cli::try_parse_from(["gpukill", "--server", "--server-host", host])
+                .err()
+                .expect("invalid host should be rejected")
+                .to_string();
+            assert!(err.contains("not a valid IP address"), "{}: {}", host, err);
+        }
+    }
+
+    #[test]
+    fn test_node_tag_parsing() {
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--register-node",
+            "http://coordinator:8080",
+            "--node-tag",
+            "rack=a1",
+            "--node-tag",
+            "team=nlp",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.node_tags,
+            vec![
+                ("rack".to_string(), "a1".to_string()),
+                ("team".to_string(), "nlp".to_string()),
+            ]
+        );
+
+        assert!(Cli::try_parse_from(["gpukill", "--server", "--node-tag", "team"]).is_err());
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--node-tag", "team=nlp"]).is_err());
+    }
+
+    #[test]
+    fn test_show_rates_requires_watch() {
+        let cli = Cli::try_parse_from(["gpukill", "--list", "--watch", "--show-rates"]).unwrap();
+        assert!(cli.show_rates);
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--show-rates"]).is_err());
+    }
+
+    #[test]
+    fn test_follow_pid_requires_watch() {
+        let cli =
+            Cli::try_parse_from(["gpukill", "--list", "--watch", "--follow-pid", "4242"]).unwrap();
+        assert_eq!(cli.follow_pid, Some(4242));
+
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--follow-pid", "4242"]).is_err());
+        assert!(Cli::try_parse_from([
+            "gpukill",
+            "--list",
+            "--watch",
+            "--details",
+            "--follow-pid",
+            "4242"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_watch_alert_flags() {
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--list",
+            "--watch",
+            "--watch-count",
+            "5",
+            "--alert-util",
+            "95",
+            "--alert-temp",
+            "85",
+        ])
+        .unwrap();
+        assert_eq!(cli.watch_count, Some(5));
+        let thresholds = cli.alert_thresholds();
+        assert_eq!(thresholds.util_pct, Some(95.0));
+        assert_eq!(thresholds.mem_pct, None);
+        assert_eq!(thresholds.temp_c, Some(85));
+
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--alert-util", "95"]).is_err());
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--watch-count", "5"]).is_err());
+        assert!(
+            Cli::try_parse_from(["gpukill", "--list", "--watch", "--watch-count", "0"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_list_json_output() {
         let cli = Cli::try_parse_from(["gpukill", "--list", "--output", "json"]).unwrap();
         assert!(cli.list);
         assert!(matches!(cli.output, OutputFormat::Json));
@@ -605,11 +1985,25 @@ mod tests {
     fn test_kill_operation() {
         let cli = Cli::try_parse_from(["gpukill", "--kill", "--pid", "12345"]).unwrap();
         assert!(cli.kill);
-        assert_eq!(cli.pid, Some(12345));
+        assert_eq!(cli.pid, Some(PidTarget::Host(12345)));
         assert_eq!(cli.timeout_secs, 5);
         assert!(!cli.force);
     }
 
+    #[test]
+    fn test_kill_container_qualified_pid() {
+        let cli =
+            Cli::try_parse_from(["gpukill", "--kill", "--pid", "container:trainer:42"]).unwrap();
+        assert_eq!(
+            cli.pid,
+            Some(PidTarget::Container {
+                container: "trainer".to_string(),
+                pid: 42,
+            })
+        );
+        assert!(Cli::try_parse_from(["gpukill", "--kill", "--pid", "container:42"]).is_err());
+    }
+
     #[test]
     fn test_kill_with_custom_timeout_and_force() {
         let cli = Cli::try_parse_from([
@@ -623,7 +2017,7 @@ mod tests {
         ])
         .unwrap();
         assert!(cli.kill);
-        assert_eq!(cli.pid, Some(12345));
+        assert_eq!(cli.pid, Some(PidTarget::Host(12345)));
         assert_eq!(cli.timeout_secs, 10);
         assert!(cli.force);
     }
@@ -636,6 +2030,531 @@ mod tests {
         assert_eq!(cli.gpu, Some(0));
     }
 
+    #[test]
+    fn test_allow_protected_requires_kill() {
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--kill",
+            "--gpu",
+            "0",
+            "--batch",
+            "--allow-protected",
+        ])
+        .unwrap();
+        assert!(cli.allow_protected);
+
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--allow-protected"]).is_err());
+    }
+
+    #[test]
+    fn test_allow_self_requires_kill_and_force() {
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--kill",
+            "--pid",
+            "42",
+            "--force",
+            "--allow-self",
+        ])
+        .unwrap();
+        assert!(cli.allow_self);
+
+        assert!(Cli::try_parse_from(["gpukill", "--kill", "--pid", "42", "--allow-self"]).is_err());
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--force", "--allow-self"]).is_err());
+    }
+
+    #[test]
+    fn test_override_protection_alias_and_list_edits() {
+        let cli =
+            Cli::try_parse_from(["gpukill", "--kill", "--pid", "42", "--override-protection"])
+                .unwrap();
+        assert!(cli.allow_protected);
+
+        let cli =
+            Cli::try_parse_from(["gpukill", "--kill", "--protect-process", "slurmd"]).unwrap();
+        assert_eq!(cli.protect_process.as_deref(), Some("slurmd"));
+        let cli = Cli::try_parse_from(["gpukill", "--kill", "--list-protected"]).unwrap();
+        assert!(cli.list_protected);
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--unprotect-process", "sshd"]).is_err());
+    }
+
+    #[test]
+    fn test_kill_tree_flags() {
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--kill",
+            "--pid",
+            "1234",
+            "--with-children",
+            "--with-parent",
+        ])
+        .unwrap();
+        assert!(cli.with_children);
+        assert!(cli.with_parent);
+
+        // Tree flags belong to --kill, and --tree to the detailed listing
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--with-children"]).is_err());
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--tree"]).is_err());
+        let cli = Cli::try_parse_from(["gpukill", "--list", "--details", "--tree"]).unwrap();
+        assert!(cli.tree);
+    }
+
+    #[test]
+    fn test_rogue_custom_rule_flags() {
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--audit",
+            "--rogue-add-rule",
+            "workers",
+            "--rogue-rule-regex",
+            r"^worker_\d+$",
+            "--rogue-rule-min-memory",
+            "15",
+            "--rogue-rule-min-duration",
+            "3",
+        ])
+        .unwrap();
+        assert_eq!(cli.rogue_add_rule.as_deref(), Some("workers"));
+        assert_eq!(cli.rogue_rule_regex.as_deref(), Some(r"^worker_\d+$"));
+        assert_eq!(cli.rogue_rule_min_memory, 15.0);
+        assert_eq!(cli.rogue_rule_min_util, 0.0);
+        assert_eq!(cli.rogue_rule_confidence, 0.5);
+
+        // A rule needs a regex, and rule options need a rule
+        assert!(Cli::try_parse_from(["gpukill", "--audit", "--rogue-add-rule", "x"]).is_err());
+        assert!(Cli::try_parse_from(["gpukill", "--audit", "--rogue-rule-regex", "x"]).is_err());
+    }
+
+    #[test]
+    fn test_log_format_flag() {
+        let cli = Cli::try_parse_from(["gpukill", "--list", "--log-format", "json"]).unwrap();
+        assert_eq!(cli.log_format, Some(LogFormat::Json));
+        assert_eq!(cli.operation_name(), "list");
+
+        let cli = Cli::try_parse_from(["gpukill", "--list"]).unwrap();
+        assert_eq!(cli.log_format, None);
+
+        // "text" is accepted as another name for human
+        let cli = Cli::try_parse_from(["gpukill", "--list", "--log-format", "text"]).unwrap();
+        assert_eq!(cli.log_format, Some(LogFormat::Human));
+
+        assert_eq!("JSON".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert_eq!("text".parse::<LogFormat>().unwrap(), LogFormat::Human);
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+
+    #[test]
+    fn test_reset_hard_flag() {
+        let cli = Cli::try_parse_from(["gpukill", "--reset", "--hard"]).unwrap();
+        assert!(cli.reset);
+        assert!(cli.hard);
+
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--hard"]).is_err());
+    }
+
+    fn config_with_defaults() -> crate::config::Config {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(
+            file.path(),
+            r#"
+log_level = "debug"
+output_format = "json"
+default_timeout_secs = 30
+force = true
+vendor = "amd"
+show_details = false
+watch_interval_secs = 2
+max_processes_summary = 10
+table_width = 120
+use_colors = true
+"#,
+        )
+        .unwrap();
+        crate::config::ConfigManager::load_from_file(file.path())
+            .unwrap()
+            .config()
+            .clone()
+    }
+
+    #[test]
+    fn test_config_defaults_apply_when_flags_absent() {
+        let config = config_with_defaults();
+        let mut cli = Cli::try_parse_args_from(["gpukill", "--list"]).unwrap();
+        cli.apply_config_defaults(&config).unwrap();
+
+        assert_eq!(cli.output, OutputFormat::Json);
+        assert_eq!(cli.timeout_secs, 30);
+        assert!(cli.force);
+        assert_eq!(cli.vendor, Some(VendorFilter::Amd));
+        assert!(matches!(cli.log_level, LogLevel::Debug));
+    }
+
+    #[test]
+    fn test_cli_flags_override_config_defaults() {
+        let config = config_with_defaults();
+        let mut cli = Cli::try_parse_args_from([
+            "gpukill",
+            "--list",
+            "--output",
+            "table",
+            "--timeout-secs",
+            "5",
+            "--vendor",
+            "nvidia",
+            "--log-level",
+            "warn",
+        ])
+        .unwrap();
+        cli.apply_config_defaults(&config).unwrap();
+
+        // Explicit values win even when they equal the built-in defaults
+        assert_eq!(cli.output, OutputFormat::Table);
+        assert_eq!(cli.timeout_secs, 5);
+        assert_eq!(cli.vendor, Some(VendorFilter::Nvidia));
+        assert!(matches!(cli.log_level, LogLevel::Warn));
+
+        // Built-in defaults are kept when the config does not change them
+        let mut cli = Cli::try_parse_args_from(["gpukill", "--list"]).unwrap();
+        cli.apply_config_defaults(&crate::config::Config::default())
+            .unwrap();
+        assert_eq!(cli.output, OutputFormat::Table);
+        assert_eq!(cli.timeout_secs, 5);
+        assert!(!cli.force);
+        assert_eq!(cli.vendor, None);
+    }
+
+    #[test]
+    fn test_no_audit_flag_and_config() {
+        let mut cli = Cli::try_parse_args_from(["gpukill", "--list"]).unwrap();
+        cli.apply_config_defaults(&crate::config::Config::default())
+            .unwrap();
+        assert!(!cli.no_audit);
+
+        let mut cli = Cli::try_parse_args_from(["gpukill", "--list", "--no-audit"]).unwrap();
+        cli.apply_config_defaults(&crate::config::Config::default())
+            .unwrap();
+        assert!(cli.no_audit);
+
+        let mut config = crate::config::Config::default();
+        config.audit.log_list = false;
+        let mut cli = Cli::try_parse_args_from(["gpukill", "--list"]).unwrap();
+        cli.apply_config_defaults(&config).unwrap();
+        assert!(cli.no_audit);
+    }
+
+    #[test]
+    fn test_invalid_config_default_is_reported() {
+        let config = crate::config::Config {
+            output_format: "xml".to_string(),
+            ..Default::default()
+        };
+        let mut cli = Cli::try_parse_args_from(["gpukill", "--list"]).unwrap();
+        let err = cli.apply_config_defaults(&config).unwrap_err().to_string();
+        assert!(err.contains("output_format 'xml'"), "{}", err);
+    }
+
+    #[test]
+    fn test_reset_wait_secs_flag() {
+        let cli =
+            Cli::try_parse_from(["gpukill", "--reset", "--gpu", "1", "--wait-secs", "60"]).unwrap();
+        assert_eq!(cli.wait_secs, Some(60));
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--reset",
+            "--gpu",
+            "1",
+            "--wait-secs",
+            "60",
+            "--force",
+            "--signal",
+            "SIGINT",
+        ])
+        .unwrap();
+        assert_eq!(cli.signal, KillSignal::Int);
+        assert!(
+            Cli::try_parse_from(["gpukill", "--reset", "--gpu", "1", "--signal", "SIGINT"])
+                .is_err()
+        );
+
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--wait-secs", "60"]).is_err());
+        assert!(Cli::try_parse_from(["gpukill", "--reset", "--all", "--wait-secs", "60"]).is_err());
+        assert!(
+            Cli::try_parse_from(["gpukill", "--reset", "--hard", "--wait-secs", "60"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_processes_only_flags() {
+        let cli = Cli::try_parse_from(["gpukill", "--list", "--processes-only"]).unwrap();
+        assert!(cli.processes_only);
+        assert_eq!(cli.sort, ProcessSort::Memory);
+
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--list",
+            "--processes-only",
+            "--containers",
+            "--sort",
+            "user",
+        ])
+        .unwrap();
+        assert!(cli.containers);
+        assert_eq!(cli.sort, ProcessSort::User);
+
+        // Needs --list, and replaces the GPU views it conflicts with
+        assert!(Cli::try_parse_from(["gpukill", "--kill", "--processes-only"]).is_err());
+        for flag in ["--details", "--watch"] {
+            assert!(
+                Cli::try_parse_from(["gpukill", "--list", "--processes-only", flag]).is_err(),
+                "{}",
+                flag
+            );
+        }
+        assert!(Cli::try_parse_from([
+            "gpukill",
+            "--list",
+            "--processes-only",
+            "--diff",
+            "before.json"
+        ])
+        .is_err());
+        // --sort only applies to the process list
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--sort", "pid"]).is_err());
+    }
+
+    #[test]
+    fn test_process_sort_orders() {
+        let proc = |gpu: u16, pid: u32, user: &str, mem: u32| GpuProc {
+            gpu_index: gpu,
+            pid,
+            user: user.to_string(),
+            proc_name: "python".to_string(),
+            used_mem_mb: mem,
+            start_time: "1h".to_string(),
+            ..Default::default()
+        };
+        let procs = vec![
+            proc(1, 30, "bob", 1000),
+            proc(0, 20, "alice", 4000),
+            proc(0, 10, "bob", 4000),
+            proc(1, 5, "alice", 500),
+        ];
+        let pids = |sort: ProcessSort| {
+            let mut sorted = procs.clone();
+            sort.sort(&mut sorted);
+            sorted.iter().map(|p| p.pid).collect::<Vec<_>>()
+        };
+        assert_eq!(pids(ProcessSort::Memory), vec![10, 20, 30, 5]);
+        assert_eq!(pids(ProcessSort::Gpu), vec![10, 20, 5, 30]);
+        assert_eq!(pids(ProcessSort::Pid), vec![5, 10, 20, 30]);
+        assert_eq!(pids(ProcessSort::User), vec![20, 5, 10, 30]);
+    }
+
+    #[test]
+    fn test_snapshot_save_and_diff_flags() {
+        let cli =
+            Cli::try_parse_from(["gpukill", "--list", "--save-snapshot", "before.json"]).unwrap();
+        assert_eq!(cli.save_snapshot, Some(PathBuf::from("before.json")));
+        assert_eq!(cli.diff, None);
+
+        let cli = Cli::try_parse_from(["gpukill", "--list", "--diff", "before.json"]).unwrap();
+        assert_eq!(cli.diff, Some(PathBuf::from("before.json")));
+
+        assert!(Cli::try_parse_from(["gpukill", "--kill", "--diff", "before.json"]).is_err());
+        assert!(
+            Cli::try_parse_from(["gpukill", "--list", "--watch", "--diff", "before.json"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_server_node_lifecycle_flags() {
+        let cli = Cli::try_parse_from(["gpukill", "--server"]).unwrap();
+        assert_eq!(
+            cli.node_offline_after,
+            crate::coordinator::DEFAULT_NODE_OFFLINE_AFTER
+        );
+        assert_eq!(
+            cli.node_remove_after,
+            crate::coordinator::DEFAULT_NODE_REMOVE_AFTER
+        );
+
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--server",
+            "--node-offline-after",
+            "2m",
+            "--node-remove-after",
+            "1h",
+        ])
+        .unwrap();
+        assert_eq!(cli.node_offline_after, Duration::from_secs(120));
+        assert_eq!(cli.node_remove_after, Duration::from_secs(3600));
+
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--node-offline-after", "2m"]).is_err());
+    }
+
+    #[test]
+    fn test_audit_html_report_flags() {
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--audit",
+            "--audit-summary",
+            "--output",
+            "html",
+            "--audit-report-file",
+            "usage.html",
+        ])
+        .unwrap();
+        assert_eq!(cli.output, OutputFormat::Html);
+        assert_eq!(cli.audit_report_file, Some(PathBuf::from("usage.html")));
+
+        assert!(
+            Cli::try_parse_from(["gpukill", "--audit", "--audit-report-file", "usage.html"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_rogue_scan_flags() {
+        let cli = Cli::try_parse_from(["gpukill", "--server"]).unwrap();
+        assert_eq!(cli.rogue_scan_interval, Duration::from_secs(15 * 60));
+        assert_eq!(
+            cli.rogue_stale_after,
+            crate::coordinator::DEFAULT_ROGUE_STALE_AFTER
+        );
+
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--register-node",
+            "http://coordinator:8080",
+            "--rogue-scan-interval",
+            "5m",
+        ])
+        .unwrap();
+        assert_eq!(cli.rogue_scan_interval, Duration::from_secs(300));
+
+        assert!(Cli::try_parse_from([
+            "gpukill",
+            "--register-node",
+            "http://coordinator:8080",
+            "--rogue-stale-after",
+            "2h"
+        ])
+        .is_err());
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--rogue-scan-interval", "5m"]).is_err());
+    }
+
+    #[test]
+    fn test_kill_idle_flags() {
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--kill",
+            "--idle-for",
+            "30m",
+            "--idle-max-util",
+            "2",
+            "--idle-warn",
+            "5m",
+            "--batch",
+        ])
+        .unwrap();
+        assert_eq!(cli.idle_for, Some(Duration::from_secs(1800)));
+        assert_eq!(cli.idle_max_util, 2.0);
+        assert_eq!(cli.idle_warn, Some(Duration::from_secs(300)));
+        assert!(cli.batch);
+
+        let cli = Cli::try_parse_from(["gpukill", "--kill", "--idle-for", "1h"]).unwrap();
+        assert_eq!(cli.idle_max_util, 1.0);
+        assert_eq!(cli.idle_warn, None);
+
+        assert!(Cli::try_parse_from(["gpukill", "--kill", "--idle-for", "soon"]).is_err());
+        assert!(Cli::try_parse_from(["gpukill", "--kill", "--idle-for", "0m"]).is_err());
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--idle-for", "30m"]).is_err());
+        assert!(Cli::try_parse_from(["gpukill", "--kill", "--idle-warn", "5m"]).is_err());
+
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--kill",
+            "--idle-mem-gb",
+            "1",
+            "--idle-minutes",
+            "30",
+            "--batch",
+        ])
+        .unwrap();
+        assert_eq!(cli.idle_window(), Some(Duration::from_secs(1800)));
+        assert_eq!(cli.idle_min_mem_mb(), Some(1024));
+        assert_eq!(
+            cli.action_target(),
+            "processes idle for 1800s holding more than 1 GB"
+        );
+        // The largest accepted value does not saturate the MB threshold
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--kill",
+            "--idle-for",
+            "1h",
+            "--idle-mem-gb",
+            &MAX_IDLE_MEM_GB.to_string(),
+        ])
+        .unwrap();
+        assert_eq!(cli.idle_min_mem_mb(), Some(u32::MAX / 1024 * 1024));
+        assert!(Cli::try_parse_from(["gpukill", "--kill", "--idle-mem-gb", "1"]).is_err());
+        assert!(Cli::try_parse_from(["gpukill", "--kill", "--idle-minutes", "0"]).is_err());
+        assert!(Cli::try_parse_from([
+            "gpukill",
+            "--kill",
+            "--idle-for",
+            "30m",
+            "--idle-minutes",
+            "30"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_cmdline_flags() {
+        let cli = Cli::try_parse_from(["gpukill", "--list", "--details", "--full-cmd"]).unwrap();
+        assert!(cli.full_cmd);
+
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--kill",
+            "--filter",
+            r"train\.py",
+            "--match-cmdline",
+        ])
+        .unwrap();
+        assert!(cli.match_cmdline);
+
+        assert!(Cli::try_parse_from(["gpukill", "--kill", "--pid", "1", "--full-cmd"]).is_err());
+        assert!(
+            Cli::try_parse_from(["gpukill", "--kill", "--pid", "1", "--match-cmdline"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_kill_signal_flag() {
+        let cli = Cli::try_parse_from(["gpukill", "--kill", "--pid", "1"]).unwrap();
+        assert_eq!(cli.signal, KillSignal::Term);
+
+        let cli =
+            Cli::try_parse_from(["gpukill", "--kill", "--pid", "1", "--signal", "SIGINT"]).unwrap();
+        assert_eq!(cli.signal, KillSignal::Int);
+        let cli =
+            Cli::try_parse_from(["gpukill", "--kill", "--pid", "1", "--signal", "quit"]).unwrap();
+        assert_eq!(cli.signal, KillSignal::Quit);
+
+        assert!(
+            Cli::try_parse_from(["gpukill", "--kill", "--pid", "1", "--signal", "SIGKILL"])
+                .is_err()
+        );
+        assert!(
+            Cli::try_parse_from(["gpukill", "--kill", "--pid", "1", "--signal", "SIGFOO"]).is_err()
+        );
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--signal", "SIGINT"]).is_err());
+    }
+
     #[test]
     fn test_reset_single_gpu() {
         let cli = Cli::try_parse_from(["gpukill", "--reset", "--gpu", "0"]).unwrap();
@@ -644,6 +2563,95 @@ mod tests {
         assert!(!cli.all);
     }
 
+    #[test]
+    fn test_gpu_uuid_selects_gpu() {
+        let cli =
+            Cli::try_parse_from(["gpukill", "--reset", "--gpu-uuid", "GPU-5fd4a1c2"]).unwrap();
+        assert_eq!(cli.gpu_uuid.as_deref(), Some("GPU-5fd4a1c2"));
+        assert_eq!(cli.gpu, None);
+        assert!(cli.gpu_selected());
+
+        let cli =
+            Cli::try_parse_from(["gpukill", "--kill", "--batch", "--gpu-uuid", "SN42"]).unwrap();
+        assert!(cli.gpu_selected());
+
+        assert!(Cli::try_parse_from([
+            "gpukill",
+            "--reset",
+            "--gpu",
+            "0",
+            "--gpu-uuid",
+            "GPU-5fd4a1c2"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_selftest_flags() {
+        let cli = Cli::try_parse_from(["gpukill", "--selftest"]).unwrap();
+        assert_eq!(cli.operation_name(), "selftest");
+        assert_eq!(cli.selftest_mem_mb, DEFAULT_SELFTEST_MEM_MB);
+        assert_eq!(cli.selftest_helper, None);
+
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--selftest",
+            "--selftest-helper",
+            "/opt/gpukill/alloc",
+            "--selftest-mem-mb",
+            "1024",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.selftest_helper,
+            Some(PathBuf::from("/opt/gpukill/alloc"))
+        );
+        assert_eq!(cli.selftest_mem_mb, 1024);
+
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--selftest-mem-mb", "64"]).is_err());
+        assert!(Cli::try_parse_from(["gpukill", "--selftest", "--selftest-mem-mb", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_topology_flag() {
+        let cli = Cli::try_parse_from(["gpukill", "--topology", "--output", "json"]).unwrap();
+        assert!(cli.topology);
+        assert_eq!(cli.operation_name(), "topology");
+    }
+
+    #[test]
+    fn test_capabilities_flag() {
+        let cli = Cli::try_parse_from(["gpukill", "--capabilities"]).unwrap();
+        assert!(cli.capabilities);
+        assert_eq!(cli.operation_name(), "capabilities");
+        assert!(!cli.check_privileges);
+
+        let cli = Cli::try_parse_from(["gpukill", "--capabilities", "--check-privileges"]).unwrap();
+        assert!(cli.check_privileges);
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--check-privileges"]).is_err());
+    }
+
+    #[test]
+    fn test_show_ids_and_remap_gpu() {
+        let cli = Cli::try_parse_from(["gpukill", "--list", "--show-ids"]).unwrap();
+        assert!(cli.show_ids);
+        assert!(Cli::try_parse_from(["gpukill", "--show-ids"]).is_err());
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--show-ids", "--details"]).is_err());
+
+        let cli = Cli::try_parse_from(["gpukill", "--remap-gpu", "GPU-5fd4=2"]).unwrap();
+        assert_eq!(cli.remap_gpu, Some(("GPU-5fd4".to_string(), 2)));
+        assert_eq!(cli.operation_name(), "remap_gpu");
+        assert!(Cli::try_parse_from(["gpukill", "--remap-gpu", "GPU-5fd4"]).is_err());
+    }
+
+    #[test]
+    fn test_info_flag() {
+        let cli = Cli::try_parse_from(["gpukill", "--info", "--output", "json"]).unwrap();
+        assert!(cli.info);
+        assert_eq!(cli.output, OutputFormat::Json);
+        assert_eq!(cli.operation_name(), "info");
+    }
+
     #[test]
     fn test_reset_all_gpus() {
         let cli = Cli::try_parse_from(["gpukill", "--reset", "--all"]).unwrap();
@@ -660,10 +2668,204 @@ mod tests {
         if let Ok(cli) = result {
             // Validation happens in the parse() method, not try_parse_from
             assert!(cli.kill);
-            assert_eq!(cli.pid, Some(0));
+            assert_eq!(cli.pid, Some(PidTarget::Host(0)));
         }
     }
 
+    #[test]
+    fn test_audit_since_until_range() {
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--audit",
+            "--audit-since",
+            "2024-06-01T08:00:00Z",
+            "--audit-until",
+            "2024-06-01T12:00:00+02:00",
+        ])
+        .unwrap();
+        let (start, end) = cli.audit_time_range().unwrap().unwrap();
+        assert_eq!(start.to_rfc3339(), "2024-06-01T08:00:00+00:00");
+        assert_eq!(end.to_rfc3339(), "2024-06-01T10:00:00+00:00");
+
+        // Only --audit-until: window of --audit-hours ending at that time
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--audit",
+            "--audit-hours",
+            "2",
+            "--audit-until",
+            "2024-06-01T10:00:00Z",
+        ])
+        .unwrap();
+        let (start, _) = cli.audit_time_range().unwrap().unwrap();
+        assert_eq!(start.to_rfc3339(), "2024-06-01T08:00:00+00:00");
+
+        // Neither flag: rolling window
+        let cli = Cli::try_parse_from(["gpukill", "--audit"]).unwrap();
+        assert!(cli.audit_time_range().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_audit_since_conflicts_with_hours() {
+        let err = Cli::try_parse_from([
+            "gpukill",
+            "--audit",
+            "--audit-hours",
+            "12",
+            "--audit-since",
+            "2024-06-01T08:00:00Z",
+        ])
+        .err()
+        .expect("--audit-hours with --audit-since should be rejected");
+        assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+
+        // The default --audit-hours value does not count as a conflict
+        assert!(Cli::try_parse_from([
+            "gpukill",
+            "--audit",
+            "--audit-since",
+            "2024-06-01T08:00:00Z"
+        ])
+        .is_ok());
+    }
+
+    #[test]
+    fn test_audit_since_invalid_timestamp() {
+        let err = Cli::try_parse_from(["gpukill", "--audit", "--audit-since", "2024-06-01"])
+            .err()
+            .expect("non-RFC3339 timestamp should be rejected");
+        assert!(err.to_string().contains("not a valid RFC3339 timestamp"));
+
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--audit",
+            "--audit-since",
+            "2024-06-02T00:00:00Z",
+            "--audit-until",
+            "2024-06-01T00:00:00Z",
+        ])
+        .unwrap();
+        assert!(cli.audit_time_range().is_err());
+    }
+
+    #[test]
+    fn test_audit_query_paging_and_filters() {
+        let cli = Cli::try_parse_from(["gpukill", "--audit"]).unwrap();
+        let query = cli.audit_query().unwrap();
+        assert_eq!(query.limit, Some(DEFAULT_AUDIT_LIMIT));
+        assert_eq!(query.offset, 0);
+        assert_eq!(query.end - query.start, chrono::Duration::hours(24));
+
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--audit",
+            "--audit-limit",
+            "0",
+            "--audit-offset",
+            "20",
+            "--audit-before",
+            "2024-06-01T08:00:00Z",
+            "--audit-gpu",
+            "3",
+            "--audit-container",
+            "trainer-1",
+            "--audit-user",
+            "alice",
+        ])
+        .unwrap();
+        let query = cli.audit_query().unwrap();
+        assert_eq!(query.limit, None);
+        assert_eq!(query.offset, 20);
+        assert_eq!(
+            query.before.unwrap().to_rfc3339(),
+            "2024-06-01T08:00:00+00:00"
+        );
+        assert_eq!(query.gpu, Some(3));
+        assert_eq!(query.container.as_deref(), Some("trainer-1"));
+        assert_eq!(query.user.as_deref(), Some("alice"));
+
+        // Paging flags only make sense with --audit
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--audit-limit", "10"]).is_err());
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--stream"]).is_err());
+    }
+
+    #[test]
+    fn test_accounting_date_range() {
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--audit",
+            "--accounting",
+            "--from",
+            "2024-05-01",
+            "--to",
+            "2024-06-01",
+            "--output",
+            "csv",
+        ])
+        .unwrap();
+        assert!(cli.accounting);
+        assert_eq!(cli.output, OutputFormat::Csv);
+        assert_eq!(cli.accounting_max_gap, 300);
+        let (start, end) = cli.accounting_time_range().unwrap();
+        assert_eq!(start.to_rfc3339(), "2024-05-01T00:00:00+00:00");
+        assert_eq!(end.to_rfc3339(), "2024-06-01T00:00:00+00:00");
+
+        // --from requires --accounting
+        assert!(Cli::try_parse_from(["gpukill", "--audit", "--from", "2024-05-01"]).is_err());
+        // Invalid dates are rejected
+        assert!(
+            Cli::try_parse_from(["gpukill", "--audit", "--accounting", "--from", "May 1st"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_remote_hosts_from_list_and_file() {
+        let cli = Cli::try_parse_from(["gpukill", "--list", "--remote", "gpu-01, gpu-02,,gpu-01"])
+            .unwrap();
+        assert!(cli.is_remote());
+        assert_eq!(cli.remote_parallel, 4);
+        assert_eq!(cli.remote_hosts().unwrap(), vec!["gpu-01", "gpu-02"]);
+
+        let hosts_file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(
+            hosts_file.path(),
+            "# training pool\ngpu-02\n\ngpu-03  # spare\n",
+        )
+        .unwrap();
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--list",
+            "--remote",
+            "gpu-01,gpu-02",
+            "--remote-file",
+            hosts_file.path().to_str().unwrap(),
+            "--remote-parallel",
+            "8",
+            "--ssh-user",
+            "ops",
+        ])
+        .unwrap();
+        assert_eq!(cli.remote_parallel, 8);
+        assert_eq!(
+            cli.remote_hosts().unwrap(),
+            vec!["gpu-01", "gpu-02", "gpu-03"]
+        );
+
+        // SSH options and parallelism require a remote target
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--ssh-user", "ops"]).is_err());
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--remote-parallel", "2"]).is_err());
+        assert!(Cli::try_parse_from([
+            "gpukill",
+            "--list",
+            "--remote",
+            "a",
+            "--remote-parallel",
+            "0"
+        ])
+        .is_err());
+    }
+
     #[test]
     fn test_reset_without_target() {
         // This should fail because neither --gpu nor --all is specified
@@ -677,4 +2879,99 @@ mod tests {
         assert!(cli.gpu.is_none());
         assert!(!cli.all);
     }
+
+    #[test]
+    fn test_color_choice() {
+        let choice = |args: &[&str]| {
+            let mut argv = vec!["gpukill", "--list"];
+            argv.extend_from_slice(args);
+            Cli::try_parse_from(argv).unwrap().color_choice()
+        };
+        assert_eq!(choice(&[]), ColorChoice::Auto);
+        assert_eq!(choice(&["--color", "always"]), ColorChoice::Always);
+        assert_eq!(choice(&["--color", "never"]), ColorChoice::Never);
+        assert_eq!(
+            choice(&["--color", "always", "--no-color"]),
+            ColorChoice::Never
+        );
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--color", "sometimes"]).is_err());
+    }
+
+    #[test]
+    fn test_action_target() {
+        let target = |args: &[&str]| {
+            let mut argv = vec!["gpukill"];
+            argv.extend_from_slice(args);
+            Cli::try_parse_from(argv).unwrap().action_target()
+        };
+        assert_eq!(target(&["--kill", "--pid", "1234"]), "pid 1234");
+        assert_eq!(
+            target(&["--kill", "--filter", "python", "--batch"]),
+            "filter python"
+        );
+        assert_eq!(
+            target(&[
+                "--kill",
+                "--filter",
+                "python",
+                "--filter-user",
+                "bob",
+                "--batch"
+            ]),
+            "filter python of user bob"
+        );
+        assert_eq!(
+            target(&["--kill", "--filter-user", "bob", "--batch"]),
+            "user bob"
+        );
+        assert_eq!(target(&["--kill", "--gpu", "2", "--batch"]), "gpu 2");
+        assert_eq!(target(&["--reset", "--gpu", "1"]), "gpu 1");
+        assert_eq!(target(&["--reset", "--all"]), "all gpus");
+
+        assert!(Cli::try_parse_from(["gpukill", "--list", "--filter-user", "bob"]).is_err());
+        assert!(
+            Cli::try_parse_from(["gpukill", "--kill", "--pid", "1", "--filter-user", "bob"])
+                .is_err()
+        );
+
+        assert_eq!(
+            target(&["--kill", "--job", "4242", "--batch"]),
+            "slurm job 4242"
+        );
+        assert!(Cli::try_parse_from(["gpukill", "--kill", "--job", "42x"]).is_err());
+        assert!(Cli::try_parse_from(["gpukill", "--kill", "--job", "1", "--gpu", "0"]).is_err());
+        assert!(Cli::try_parse_from(["gpukill", "--kill", "--use-scancel"]).is_err());
+
+        assert_eq!(
+            target(&[
+                "--set",
+                "--gpu",
+                "2",
+                "--power-limit",
+                "250",
+                "--lock-clocks",
+                "1410"
+            ]),
+            "gpu 2: power limit 250 W, clocks locked at 1410 MHz"
+        );
+        assert_eq!(
+            target(&["--set", "--gpu", "0", "--reset-clocks"]),
+            "gpu 0: clocks reset"
+        );
+        assert!(Cli::try_parse_from(["gpukill", "--power-limit", "250"]).is_err());
+        assert!(Cli::try_parse_from([
+            "gpukill",
+            "--set",
+            "--gpu",
+            "0",
+            "--lock-clocks",
+            "1410",
+            "--reset-clocks"
+        ])
+        .is_err());
+
+        let cli = Cli::try_parse_from(["gpukill", "--audit", "--audit-actions"]).unwrap();
+        assert!(cli.audit_actions);
+        assert!(Cli::try_parse_from(["gpukill", "--audit-actions"]).is_err());
+    }
 }
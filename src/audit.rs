@@ -1,10 +1,11 @@
+use crate::audit_store::{open_store, AuditQuery, AuditStore};
 use crate::nvml_api::{GpuProc, GpuSnapshot};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
-use std::fs;
+use std::collections::HashMap;
 use std::io::Write;
-use std::path::PathBuf;
 
 /// Audit record for GPU usage
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +25,73 @@ pub struct AuditRecord {
     /// When set, record is from a cluster node; used to group by (node_id, pid).
     #[serde(default)]
     pub node_id: Option<String>,
+    /// Host the record was taken on; the same username on two hosts may be two people.
+    #[serde(default)]
+    pub hostname: Option<String>,
+    /// Full command line of the process, when it could be read
+    #[serde(default)]
+    pub cmdline: Option<String>,
+    /// GPU records only: uncorrected ECC errors since the driver was loaded
+    #[serde(default)]
+    pub ecc_uncorrected: Option<u64>,
+    /// GPU records only: new uncorrected ECC errors since this GPU's previous sample
+    #[serde(default)]
+    pub ecc_uncorrected_delta: Option<u64>,
+    /// PCIe throughput (sent plus received) of the GPU, in MB/s; process records carry
+    /// the value of the GPU the process ran on
+    #[serde(default)]
+    pub pcie_mb_per_sec: Option<f32>,
+    /// Cumulative NVLink traffic counter of the GPU; process records carry the value of
+    /// the GPU the process ran on
+    #[serde(default)]
+    pub nvlink_bytes: Option<u64>,
+    /// Process records only: Slurm job that owned the process
+    #[serde(default)]
+    pub slurm_job_id: Option<String>,
+    /// Process records only: when the process started, in seconds since the Unix epoch
+    #[serde(default)]
+    pub start_unix_secs: Option<u64>,
+}
+
+/// PCIe throughput of a GPU in MB/s, sent plus received; None when neither direction
+/// could be read
+pub fn pcie_mb_per_sec(snapshot: &GpuSnapshot) -> Option<f32> {
+    if snapshot.pcie_tx_kbps.is_none() && snapshot.pcie_rx_kbps.is_none() {
+        return None;
+    }
+    let kbps =
+        snapshot.pcie_tx_kbps.unwrap_or(0) as f32 + snapshot.pcie_rx_kbps.unwrap_or(0) as f32;
+    Some(kbps / 1024.0)
+}
+
+impl AuditRecord {
+    /// Host this record belongs to, falling back to the cluster node ID for records
+    /// written before hostnames were stored.
+    pub fn host(&self) -> &str {
+        self.hostname
+            .as_deref()
+            .or(self.node_id.as_deref())
+            .unwrap_or("unknown")
+    }
+
+    /// When the process started, for process records that carry a start time
+    pub fn started_at(&self) -> Option<DateTime<Utc>> {
+        DateTime::from_timestamp(self.start_unix_secs? as i64, 0)
+    }
+
+    /// How long the process had been running when this record was taken
+    pub fn runtime(&self) -> Option<chrono::Duration> {
+        let started = self.started_at()?;
+        (started <= self.timestamp).then(|| self.timestamp - started)
+    }
+}
+
+/// One page of audit records, newest first
+#[derive(Debug, Clone)]
+pub struct AuditPage {
+    pub records: Vec<AuditRecord>,
+    /// Whether more records match beyond this page
+    pub has_more: bool,
 }
 
 /// Audit summary statistics
@@ -36,39 +104,420 @@ pub struct AuditSummary {
     pub gpu_usage_by_hour: Vec<(u32, u32)>, // (hour, avg_memory_mb)
 }
 
+/// Longest bar, in pixels, of the per-user and per-process charts in the HTML report
+const HTML_TABLE_BAR_PX: u64 = 160;
+/// Width of one hour and height of the hourly chart in the HTML report, in pixels
+const HTML_HOUR_BAR_PX: u64 = 14;
+const HTML_CHART_HEIGHT_PX: u64 = 120;
+
+const HTML_STYLE: &str = "\
+body{font-family:-apple-system,'Segoe UI',Helvetica,Arial,sans-serif;margin:2rem;color:#1f2933}
+h1{font-size:1.5rem;margin-bottom:.25rem}
+h2{font-size:1.15rem;margin-top:2rem}
+.meta{color:#616e7c;margin-top:0}
+table{border-collapse:collapse;min-width:32rem}
+th,td{padding:.35rem .75rem;border-bottom:1px solid #e4e7eb;text-align:left}
+td.num{text-align:right;font-variant-numeric:tabular-nums}
+.bar{fill:#3e7bfa}
+.chart{overflow-x:auto}
+.chart text{font-size:10px;fill:#616e7c}
+.empty{color:#616e7c;font-style:italic}";
+
+impl AuditSummary {
+    /// Render the summary as a standalone HTML page: inline CSS and SVG bar charts,
+    /// nothing loaded from elsewhere, so the file can be mailed or archived as is
+    pub fn to_html(&self, range_label: &str, generated_at: DateTime<Utc>) -> String {
+        use crate::util::html_escape;
+        use std::fmt::Write;
+
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str("<title>GPU Usage Audit Summary</title>\n");
+        let _ = writeln!(html, "<style>\n{}\n</style>\n</head>\n<body>", HTML_STYLE);
+        html.push_str("<h1>GPU Usage Audit Summary</h1>\n");
+        let _ = writeln!(
+            html,
+            "<p class=\"meta\">{} &middot; {} records &middot; generated {}</p>",
+            html_escape(range_label),
+            self.total_records,
+            generated_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+        );
+
+        for (title, column, rows) in [
+            ("Top Users by Memory Usage", "User", &self.top_users),
+            (
+                "Top Processes by Memory Usage",
+                "Process",
+                &self.top_processes,
+            ),
+        ] {
+            let _ = writeln!(html, "<h2>{}</h2>", title);
+            if rows.is_empty() {
+                html.push_str("<p class=\"empty\">No records.</p>\n");
+                continue;
+            }
+            let _ = writeln!(
+                html,
+                "<table>\n<thead><tr><th>#</th><th>{}</th><th>Records</th>\
+                 <th>Total memory (MB)</th><th></th></tr></thead>\n<tbody>",
+                column
+            );
+            let max = rows.iter().map(|(_, _, mb)| *mb as u64).max().unwrap_or(0);
+            for (i, (name, count, memory_mb)) in rows.iter().enumerate() {
+                let width = (*memory_mb as u64 * HTML_TABLE_BAR_PX)
+                    .checked_div(max)
+                    .unwrap_or(0);
+                let _ = writeln!(
+                    html,
+                    "<tr><td class=\"num\">{}</td><td>{}</td><td class=\"num\">{}</td>\
+                     <td class=\"num\">{}</td><td><svg width=\"{}\" height=\"12\">\
+                     <rect class=\"bar\" width=\"{}\" height=\"12\"/></svg></td></tr>",
+                    i + 1,
+                    html_escape(name),
+                    count,
+                    memory_mb,
+                    HTML_TABLE_BAR_PX,
+                    width
+                );
+            }
+            html.push_str("</tbody>\n</table>\n");
+        }
+
+        html.push_str("<h2>Hourly GPU Memory Usage</h2>\n");
+        let max = self
+            .gpu_usage_by_hour
+            .iter()
+            .map(|(_, mb)| *mb as u64)
+            .max()
+            .unwrap_or(0);
+        if max == 0 {
+            html.push_str("<p class=\"empty\">No memory in use during this range.</p>\n");
+        } else {
+            // Bars grow up from a baseline; hour labels sit below it
+            let width = self.gpu_usage_by_hour.len() as u64 * HTML_HOUR_BAR_PX;
+            let _ = writeln!(
+                html,
+                "<div class=\"chart\"><svg width=\"{}\" height=\"{}\" role=\"img\" \
+                 aria-label=\"Average GPU memory per hour, up to {} MB\">",
+                width,
+                HTML_CHART_HEIGHT_PX + 14,
+                max
+            );
+            for (i, (hour, memory_mb)) in self.gpu_usage_by_hour.iter().enumerate() {
+                let height = *memory_mb as u64 * HTML_CHART_HEIGHT_PX / max;
+                let x = i as u64 * HTML_HOUR_BAR_PX;
+                let _ = writeln!(
+                    html,
+                    "<rect class=\"bar\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\">\
+                     <title>Hour {}: {} MB average</title></rect>",
+                    x,
+                    HTML_CHART_HEIGHT_PX - height,
+                    HTML_HOUR_BAR_PX - 2,
+                    height,
+                    hour,
+                    memory_mb
+                );
+                if hour % 6 == 0 {
+                    let _ = writeln!(
+                        html,
+                        "<text x=\"{}\" y=\"{}\">{}</text>",
+                        x,
+                        HTML_CHART_HEIGHT_PX + 12,
+                        hour
+                    );
+                }
+            }
+            html.push_str("</svg></div>\n");
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+/// Usage by one user on one host
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HostUserUsage {
+    pub hostname: String,
+    pub user: String,
+    pub count: u64,
+    pub total_memory_mb: u32,
+}
+
+/// Audit summary with users qualified by host, for cluster-wide reports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostAuditSummary {
+    pub total_records: u64,
+    pub time_range_hours: u32,
+    pub top_users: Vec<HostUserUsage>,
+}
+
+/// Default cap on the interval a single sample may account for. Samples further
+/// apart than this are treated as a gap rather than continuous usage.
+pub const DEFAULT_ACCOUNTING_MAX_GAP_SECS: u64 = 300;
+
+/// GPU-hours and GB-hours consumed by one user or process
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountingEntry {
+    pub name: String,
+    /// Time spent holding a GPU, summed across GPUs (two GPUs for one hour = 2.0)
+    pub gpu_hours: f64,
+    /// GPU memory held over time, in GiB x hours
+    pub gb_hours: f64,
+    pub samples: u64,
+}
+
+/// Usage accounting report for a time range
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountingReport {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub max_gap_secs: u64,
+    pub total_gpu_hours: f64,
+    pub total_gb_hours: f64,
+    pub by_user: Vec<AccountingEntry>,
+    pub by_process: Vec<AccountingEntry>,
+    /// Usage of processes that ran in a Slurm job, per job ID
+    #[serde(default)]
+    pub by_job: Vec<AccountingEntry>,
+}
+
+impl AccountingReport {
+    /// Integrate process samples into per-user, per-process and per-Slurm-job usage.
+    ///
+    /// Samples are grouped per (node, GPU, PID) and each sample accounts for the
+    /// interval since the previous sample in its group, capped at `max_gap_secs`.
+    /// When a sample carries the process start time, the interval starts no earlier
+    /// than that, so the first sample of a run accounts for the time since the
+    /// process started (within the range) and a reused PID does not inherit the
+    /// previous process's gap. Without a start time the first sample of a group
+    /// accounts for nothing, undercounting the run by up to one sampling interval.
+    /// Any interval longer than the cap is counted as exactly the cap.
+    pub fn from_records(
+        records: &[AuditRecord],
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        max_gap_secs: u64,
+    ) -> Self {
+        use std::collections::HashMap;
+
+        let mut groups: HashMap<(Option<&str>, u16, u32), Vec<&AuditRecord>> = HashMap::new();
+        for record in records {
+            if record.timestamp < start || record.timestamp > end {
+                continue;
+            }
+            if let Some(pid) = record.pid {
+                groups
+                    .entry((record.node_id.as_deref(), record.gpu_index, pid))
+                    .or_default()
+                    .push(record);
+            }
+        }
+
+        let mut by_user: HashMap<String, AccountingEntry> = HashMap::new();
+        let mut by_process: HashMap<String, AccountingEntry> = HashMap::new();
+        let mut by_job: HashMap<String, AccountingEntry> = HashMap::new();
+        let mut total_gpu_hours = 0.0;
+        let mut total_gb_hours = 0.0;
+
+        for samples in groups.values_mut() {
+            samples.sort_by_key(|r| r.timestamp);
+            let mut previous: Option<DateTime<Utc>> = None;
+            for record in samples.iter() {
+                let started = record.started_at().map(|started| started.max(start));
+                let interval_secs = previous
+                    .max(started)
+                    .map(|since| {
+                        ((record.timestamp - since).num_milliseconds().max(0) as f64 / 1000.0)
+                            .min(max_gap_secs as f64)
+                    })
+                    .unwrap_or(0.0);
+                previous = Some(record.timestamp);
+
+                let gpu_hours = interval_secs / 3600.0;
+                let gb_hours = gpu_hours * record.memory_used_mb as f64 / 1024.0;
+                total_gpu_hours += gpu_hours;
+                total_gb_hours += gb_hours;
+
+                let user = record.user.clone().unwrap_or_else(|| "unknown".to_string());
+                let process = record
+                    .process_name
+                    .clone()
+                    .unwrap_or_else(|| "unknown".to_string());
+                let job = record.slurm_job_id.clone().map(|job| (&mut by_job, job));
+                for (map, key) in [(&mut by_user, user), (&mut by_process, process)]
+                    .into_iter()
+                    .chain(job)
+                {
+                    let entry = map.entry(key.clone()).or_insert_with(|| AccountingEntry {
+                        name: key,
+                        gpu_hours: 0.0,
+                        gb_hours: 0.0,
+                        samples: 0,
+                    });
+                    entry.gpu_hours += gpu_hours;
+                    entry.gb_hours += gb_hours;
+                    entry.samples += 1;
+                }
+            }
+        }
+
+        let sorted = |map: HashMap<String, AccountingEntry>| {
+            let mut entries: Vec<AccountingEntry> = map.into_values().collect();
+            entries.sort_by(|a, b| {
+                b.gpu_hours
+                    .total_cmp(&a.gpu_hours)
+                    .then_with(|| a.name.cmp(&b.name))
+            });
+            entries
+        };
+
+        Self {
+            start,
+            end,
+            max_gap_secs,
+            total_gpu_hours,
+            total_gb_hours,
+            by_user: sorted(by_user),
+            by_process: sorted(by_process),
+            by_job: sorted(by_job),
+        }
+    }
+
+    /// Render the report as CSV (one row per user, per process and per Slurm job)
+    pub fn to_csv(&self) -> String {
+        use crate::util::csv_escape;
+
+        let mut csv = String::from("kind,name,gpu_hours,gb_hours,samples\n");
+        for (kind, entries) in [
+            ("user", &self.by_user),
+            ("process", &self.by_process),
+            ("job", &self.by_job),
+        ] {
+            for entry in entries {
+                csv.push_str(&format!(
+                    "{},{},{:.4},{:.4},{}\n",
+                    kind,
+                    csv_escape(&entry.name),
+                    entry.gpu_hours,
+                    entry.gb_hours,
+                    entry.samples
+                ));
+            }
+        }
+        csv
+    }
+}
+
+/// How far back to look for a GPU's previous ECC sample when none was logged by this process
+const ECC_LOOKBACK_HOURS: i64 = 24;
+
+/// New uncorrected ECC errors between two samples. The volatile counter restarts
+/// from zero when the driver reloads, so a drop counts everything since the reload.
+pub fn ecc_uncorrected_delta(previous: Option<u64>, current: u64) -> Option<u64> {
+    previous.map(|previous| current.checked_sub(previous).unwrap_or(current))
+}
+
+/// Operation recorded in the action audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    Kill,
+    Reset,
+    /// Power limit or clock change (`--set`)
+    Set,
+}
+
+impl std::fmt::Display for ActionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ActionKind::Kill => write!(f, "kill"),
+            ActionKind::Reset => write!(f, "reset"),
+            ActionKind::Set => write!(f, "set"),
+        }
+    }
+}
+
+/// A kill, reset or set run through gpukill: who ran it, against what, and how it ended
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionRecord {
+    pub timestamp: DateTime<Utc>,
+    pub kind: ActionKind,
+    /// What the operation was aimed at, e.g. `pid 1234`, `gpu 0` or `filter python`
+    pub target: String,
+    /// OS user gpukill ran as
+    pub invoking_user: String,
+    /// User who ran gpukill through sudo, from `SUDO_USER`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sudo_user: Option<String>,
+    pub hostname: String,
+    pub dry_run: bool,
+    pub success: bool,
+    /// Why the operation failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
 /// Audit manager for GPU usage tracking
 pub struct AuditManager {
-    data_dir: PathBuf,
+    store: Box<dyn AuditStore>,
+    /// Last logged uncorrected ECC count per GPU; None until read from the store
+    ecc_counts: std::sync::Mutex<Option<HashMap<u16, u64>>>,
+    /// Records waiting to be written, see [`AuditManager::with_flush_interval`]
+    pending: std::sync::Mutex<PendingWrites>,
+    flush_interval: std::time::Duration,
+}
+
+impl std::fmt::Debug for AuditManager {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditManager")
+            .field("store", &self.store.describe())
+            .field("flush_interval", &self.flush_interval)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Write buffer of an [`AuditManager`]
+#[derive(Default)]
+struct PendingWrites {
+    records: Vec<AuditRecord>,
+    /// When the buffer was last written; None before the first write
+    last_flush: Option<std::time::Instant>,
 }
 
 #[allow(dead_code)]
 impl AuditManager {
-    /// Initialize the audit manager with JSON file storage
+    /// Initialize the audit manager with the storage selected by `audit.database_url`
+    /// in the config file, or the local JSON Lines log when it is unset. Every record
+    /// is written at once; see [`Self::with_flush_interval`] for buffering.
     pub async fn new() -> Result<Self> {
-        let data_dir = Self::get_data_dir()?;
-
-        tracing::debug!("Initializing audit storage at: {}", data_dir.display());
-
-        // Create directory if it doesn't exist
-        fs::create_dir_all(&data_dir)
-            .map_err(|e| anyhow::anyhow!("Failed to create audit directory: {}", e))?;
+        let config = crate::config::get_config(None)?;
+        Self::with_database_url(config.config().audit.database_url.as_deref()).await
+    }
 
-        Ok(Self { data_dir })
+    /// Initialize the audit manager against an explicit database URL (`None` for the
+    /// local JSON Lines log). Migrations run before this returns.
+    pub async fn with_database_url(database_url: Option<&str>) -> Result<Self> {
+        Ok(Self::with_store(open_store(database_url).await?))
     }
 
-    /// Get the data directory path
-    fn get_data_dir() -> Result<PathBuf> {
-        // Try multiple fallback locations for the data directory
-        let mut path = if let Some(data_dir) = dirs::data_dir() {
-            data_dir
-        } else if let Some(home_dir) = dirs::home_dir() {
-            home_dir.join(".local").join("share")
-        } else {
-            std::env::current_dir()?
-        };
+    /// Use an already opened and migrated store
+    pub fn with_store(store: Box<dyn AuditStore>) -> Self {
+        Self {
+            store,
+            ecc_counts: std::sync::Mutex::new(None),
+            pending: std::sync::Mutex::new(PendingWrites::default()),
+            flush_interval: std::time::Duration::ZERO,
+        }
+    }
 
-        path.push("gpukill");
-        Ok(path)
+    /// Coalesce writes: records logged less than `interval` after the last write are
+    /// kept in memory and written together with a later batch, or by [`Self::flush`].
+    /// The first batch is always written at once, so short-lived managers lose
+    /// nothing; long-running writers must call `flush` before they exit.
+    pub fn with_flush_interval(mut self, interval: std::time::Duration) -> Self {
+        self.flush_interval = interval;
+        self
     }
 
     /// Log GPU usage snapshot
@@ -77,10 +526,27 @@ impl AuditManager {
         snapshots: &[GpuSnapshot],
         processes: &[GpuProc],
     ) -> Result<()> {
+        let started = std::time::Instant::now();
         let timestamp = Utc::now();
+        let hostname = crate::util::get_hostname();
+        let mut ecc_counts = self.previous_ecc_counts(&hostname, timestamp).await;
         let mut records = Vec::new();
 
         for snapshot in snapshots {
+            let pcie_mb_per_sec = pcie_mb_per_sec(snapshot);
+            let ecc_uncorrected = snapshot.ecc_volatile.map(|ecc| ecc.uncorrected);
+            let ecc_uncorrected_delta = ecc_uncorrected.and_then(|current| {
+                ecc_uncorrected_delta(ecc_counts.insert(snapshot.gpu_index, current), current)
+            });
+            if let Some(delta) = ecc_uncorrected_delta.filter(|delta| *delta > 0) {
+                tracing::warn!(
+                    gpu = snapshot.gpu_index,
+                    new_errors = delta,
+                    total = ecc_uncorrected.unwrap_or_default(),
+                    "GPU reported new uncorrected ECC errors"
+                );
+            }
+
             // Log GPU-level information
             let gpu_record = AuditRecord {
                 id: timestamp.timestamp_millis(), // Use timestamp as ID
@@ -96,6 +562,14 @@ impl AuditManager {
                 power_w: snapshot.power_w,
                 container: None,
                 node_id: None,
+                hostname: Some(hostname.clone()),
+                cmdline: None,
+                ecc_uncorrected,
+                ecc_uncorrected_delta,
+                pcie_mb_per_sec,
+                nvlink_bytes: snapshot.nvlink_bytes,
+                slurm_job_id: None,
+                start_unix_secs: None,
             };
 
             records.push(gpu_record);
@@ -124,38 +598,106 @@ impl AuditManager {
                     power_w: 0.0,     // Process-level power not available
                     container: process.container.clone(),
                     node_id: None,
+                    hostname: Some(hostname.clone()),
+                    cmdline: process.cmdline.clone(),
+                    ecc_uncorrected: None,
+                    ecc_uncorrected_delta: None,
+                    pcie_mb_per_sec,
+                    nvlink_bytes: snapshot.nvlink_bytes,
+                    slurm_job_id: process.slurm_job_id.clone(),
+                    start_unix_secs: process.start_unix_secs,
                 };
 
                 records.push(process_record);
             }
         }
 
-        // Append records to JSON file
         self.append_records(&records).await?;
+        *self.ecc_counts.lock().unwrap() = Some(ecc_counts);
+        tracing::debug!(
+            records = records.len(),
+            gpus = snapshots.len(),
+            processes = processes.len(),
+            duration_ms = started.elapsed().as_millis() as u64,
+            "Logged audit snapshot"
+        );
         Ok(())
     }
 
-    /// Append records to JSON file
-    async fn append_records(&self, records: &[AuditRecord]) -> Result<()> {
-        let file_path = self.data_dir.join("audit.jsonl");
+    /// Uncorrected ECC counts last logged for each GPU on `hostname`, read from the
+    /// store on first use and remembered afterwards
+    async fn previous_ecc_counts(&self, hostname: &str, now: DateTime<Utc>) -> HashMap<u16, u64> {
+        if let Some(counts) = self.ecc_counts.lock().unwrap().clone() {
+            return counts;
+        }
 
-        // Create a JSON Lines file (one JSON object per line)
-        let mut file = fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&file_path)
-            .map_err(|e| anyhow::anyhow!("Failed to open audit file: {}", e))?;
+        let mut counts = HashMap::new();
+        let mut scan = self
+            .store
+            .scan(now - chrono::Duration::hours(ECC_LOOKBACK_HOURS), now);
+        while let Some(record) = scan.next().await {
+            match record {
+                // Storage order is oldest first, so the last sample wins
+                Ok(record) if record.pid.is_none() && record.host() == hostname => {
+                    if let Some(count) = record.ecc_uncorrected {
+                        counts.insert(record.gpu_index, count);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    tracing::warn!("Failed to read previous ECC counts: {}", e);
+                    break;
+                }
+            }
+        }
+        counts
+    }
 
-        for record in records {
-            let json_line = serde_json::to_string(record)
-                .map_err(|e| anyhow::anyhow!("Failed to serialize record: {}", e))?;
-            writeln!(file, "{}", json_line)
-                .map_err(|e| anyhow::anyhow!("Failed to write to audit file: {}", e))?;
+    /// Append records to the audit store, buffered according to the flush interval
+    pub(crate) async fn append_records(&self, records: &[AuditRecord]) -> Result<()> {
+        let due = {
+            let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+            pending.records.extend_from_slice(records);
+            pending
+                .last_flush
+                .is_none_or(|last| last.elapsed() >= self.flush_interval)
+        };
+        if due {
+            self.flush().await
+        } else {
+            Ok(())
         }
+    }
 
+    /// Write every buffered record in one batch. Call before a long-running writer
+    /// shuts down; on failure the records stay buffered for the next attempt.
+    pub async fn flush(&self) -> Result<()> {
+        let records = {
+            let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+            pending.last_flush = Some(std::time::Instant::now());
+            std::mem::take(&mut pending.records)
+        };
+        if records.is_empty() {
+            return Ok(());
+        }
+        if let Err(e) = self.store.append(&records).await {
+            let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+            let newer = std::mem::replace(&mut pending.records, records);
+            pending.records.extend(newer);
+            return Err(e);
+        }
         Ok(())
     }
 
+    /// Number of records waiting to be written
+    pub fn pending_records(&self) -> usize {
+        self.pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .records
+            .len()
+    }
+
     /// Query audit records with filters
     pub async fn query_records(
         &self,
@@ -163,91 +705,151 @@ impl AuditManager {
         user_filter: Option<&str>,
         process_filter: Option<&str>,
     ) -> Result<Vec<AuditRecord>> {
-        let since = Utc::now() - chrono::Duration::hours(hours as i64);
-        let file_path = self.data_dir.join("audit.jsonl");
-
-        if !file_path.exists() {
-            return Ok(Vec::new());
-        }
+        let end = Utc::now();
+        let start = end - chrono::Duration::hours(hours as i64);
+        self.query_records_range(start, end, user_filter, process_filter)
+            .await
+    }
 
-        let content = fs::read_to_string(&file_path)
-            .map_err(|e| anyhow::anyhow!("Failed to read audit file: {}", e))?;
+    /// Query audit records within an explicit time range (both bounds inclusive)
+    pub async fn query_records_range(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        user_filter: Option<&str>,
+        process_filter: Option<&str>,
+    ) -> Result<Vec<AuditRecord>> {
+        let query = AuditQuery {
+            user: user_filter.map(str::to_string),
+            process: process_filter.map(str::to_string),
+            ..AuditQuery::range(start, end)
+        };
+        self.store
+            .query(&query)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect()
+    }
 
+    /// Fetch one page of records, newest first. One extra record is requested to
+    /// tell whether more pages follow.
+    pub async fn query(&self, query: &AuditQuery) -> Result<AuditPage> {
         let mut records = Vec::new();
-        for line in content.lines() {
-            if line.trim().is_empty() {
-                continue;
-            }
+        let mut scan = self.store.query(&Self::probe(query));
+        while let Some(record) = scan.next().await {
+            records.push(record?);
+        }
 
-            let record: AuditRecord = serde_json::from_str(line)
-                .map_err(|e| anyhow::anyhow!("Failed to parse audit record: {}", e))?;
+        let has_more = matches!(query.limit, Some(limit) if records.len() > limit);
+        if has_more {
+            records.pop();
+        }
+        Ok(AuditPage { records, has_more })
+    }
 
-            // Filter by time
-            if record.timestamp < since {
-                continue;
-            }
+    /// Write one page of records to `out` as a JSON array, one record at a time,
+    /// without collecting the page first. Returns the number of records written
+    /// and whether more pages follow.
+    pub async fn write_records_json<W: Write>(
+        &self,
+        query: &AuditQuery,
+        out: &mut W,
+    ) -> Result<(usize, bool)> {
+        let write_err = |e: std::io::Error| anyhow::anyhow!("Failed to write audit record: {}", e);
+        let mut written = 0;
+        let mut has_more = false;
+        let mut scan = self.store.query(&Self::probe(query));
 
-            // Filter by user
-            if let Some(user) = user_filter {
-                if let Some(ref record_user) = record.user {
-                    if record_user != user {
-                        continue;
-                    }
-                } else {
-                    continue;
-                }
+        out.write_all(b"[").map_err(write_err)?;
+        while let Some(record) = scan.next().await {
+            let record = record?;
+            if matches!(query.limit, Some(limit) if written == limit) {
+                has_more = true;
+                break;
             }
+            out.write_all(if written == 0 { b"\n" } else { b",\n" })
+                .map_err(write_err)?;
+            serde_json::to_writer(&mut *out, &record)
+                .map_err(|e| anyhow::anyhow!("Failed to serialize record: {}", e))?;
+            written += 1;
+        }
+        out.write_all(b"\n]\n").map_err(write_err)?;
+        out.flush().map_err(write_err)?;
+        Ok((written, has_more))
+    }
 
-            // Filter by process
-            if let Some(process) = process_filter {
-                if let Some(ref record_process) = record.process_name {
-                    if !record_process.contains(process) {
-                        continue;
-                    }
-                } else {
-                    continue;
-                }
+    /// The query with its limit raised by one, to detect a following page
+    fn probe(query: &AuditQuery) -> AuditQuery {
+        AuditQuery {
+            limit: query.limit.map(|limit| limit.saturating_add(1)),
+            ..query.clone()
+        }
+    }
+
+    /// Stream every record matching `query`'s time range and filters to `out` as
+    /// newline-delimited JSON.
+    ///
+    /// Records are written in storage order (oldest first), one compact object per
+    /// line, without buffering the result set. Offset and limit are not applied.
+    /// Returns the number of records written.
+    pub async fn export_records_ndjson<W: Write>(
+        &self,
+        query: &AuditQuery,
+        out: &mut W,
+    ) -> Result<usize> {
+        let mut written = 0;
+        let mut scan = self.store.scan(query.start, query.end);
+        while let Some(record) = scan.next().await {
+            let record = record?;
+            if !query.matches(&record) {
+                continue;
             }
 
-            records.push(record);
+            serde_json::to_writer(&mut *out, &record)
+                .map_err(|e| anyhow::anyhow!("Failed to serialize record: {}", e))?;
+            out.write_all(b"\n")
+                .map_err(|e| anyhow::anyhow!("Failed to write audit record: {}", e))?;
+            written += 1;
         }
 
-        // Sort by timestamp descending
-        records.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-        Ok(records)
+        out.flush()
+            .map_err(|e| anyhow::anyhow!("Failed to write audit record: {}", e))?;
+        Ok(written)
+    }
+
+    /// Compute per-user and per-process GPU-hours for a time range
+    pub async fn get_accounting(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        max_gap_secs: u64,
+    ) -> Result<AccountingReport> {
+        let records = self.query_records_range(start, end, None, None).await?;
+        Ok(AccountingReport::from_records(
+            &records,
+            start,
+            end,
+            max_gap_secs,
+        ))
     }
 
     /// Get audit summary statistics
     pub async fn get_summary(&self, hours: u32) -> Result<AuditSummary> {
-        let since = Utc::now() - chrono::Duration::hours(hours as i64);
-        let file_path = self.data_dir.join("audit.jsonl");
-
-        if !file_path.exists() {
-            return Ok(AuditSummary {
-                total_records: 0,
-                time_range_hours: hours,
-                top_users: Vec::new(),
-                top_processes: Vec::new(),
-                gpu_usage_by_hour: Vec::new(),
-            });
-        }
-
-        let content = fs::read_to_string(&file_path)
-            .map_err(|e| anyhow::anyhow!("Failed to read audit file: {}", e))?;
-
-        let mut records = Vec::new();
-        for line in content.lines() {
-            if line.trim().is_empty() {
-                continue;
-            }
-
-            let record: AuditRecord = serde_json::from_str(line)
-                .map_err(|e| anyhow::anyhow!("Failed to parse audit record: {}", e))?;
+        let end = Utc::now();
+        let start = end - chrono::Duration::hours(hours as i64);
+        self.get_summary_range(start, end).await
+    }
 
-            if record.timestamp >= since {
-                records.push(record);
-            }
-        }
+    /// Get audit summary statistics for an explicit time range
+    pub async fn get_summary_range(
+        &self,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<AuditSummary> {
+        // Round partial hours up so the hourly breakdown covers the whole range
+        let hours = ((until - since).num_seconds().max(0) as u64).div_ceil(3600) as u32;
+        let records = self.query_records_range(since, until, None, None).await?;
 
         let total_records = records.len() as u64;
 
@@ -265,7 +867,7 @@ impl AuditManager {
             .into_iter()
             .map(|(user, (count, memory))| (user, count, memory))
             .collect();
-        top_users.sort_by(|a, b| b.2.cmp(&a.2));
+        top_users.sort_by_key(|u| std::cmp::Reverse(u.2));
         top_users.truncate(10);
 
         // Calculate top processes
@@ -282,7 +884,7 @@ impl AuditManager {
             .into_iter()
             .map(|(process, (count, memory))| (process, count, memory))
             .collect();
-        top_processes.sort_by(|a, b| b.2.cmp(&a.2));
+        top_processes.sort_by_key(|p| std::cmp::Reverse(p.2));
         top_processes.truncate(10);
 
         // Calculate GPU usage by hour
@@ -315,53 +917,256 @@ impl AuditManager {
         })
     }
 
+    /// Get an audit summary whose top users are grouped by (hostname, user)
+    pub async fn get_summary_by_host(&self, hours: u32) -> Result<HostAuditSummary> {
+        let end = Utc::now();
+        let start = end - chrono::Duration::hours(hours as i64);
+        self.get_summary_by_host_range(start, end).await
+    }
+
+    /// Get an audit summary grouped by (hostname, user) for an explicit time range
+    pub async fn get_summary_by_host_range(
+        &self,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+    ) -> Result<HostAuditSummary> {
+        let hours = ((until - since).num_seconds().max(0) as u64).div_ceil(3600) as u32;
+        let records = self.query_records_range(since, until, None, None).await?;
+
+        Ok(HostAuditSummary {
+            total_records: records.len() as u64,
+            time_range_hours: hours,
+            top_users: top_users_by_host(&records, 10),
+        })
+    }
+
+    /// Record a kill or reset run through gpukill and how it ended
+    pub async fn log_action(
+        &self,
+        kind: ActionKind,
+        target: &str,
+        invoking_user: &str,
+        dry_run: bool,
+        result: &Result<()>,
+    ) -> Result<ActionRecord> {
+        let record = ActionRecord {
+            timestamp: Utc::now(),
+            kind,
+            target: target.to_string(),
+            invoking_user: invoking_user.to_string(),
+            sudo_user: std::env::var("SUDO_USER").ok().filter(|u| !u.is_empty()),
+            hostname: crate::util::get_hostname(),
+            dry_run,
+            success: result.is_ok(),
+            error: result.as_ref().err().map(|e| format!("{:#}", e)),
+        };
+        self.store
+            .append_actions(std::slice::from_ref(&record))
+            .await?;
+        Ok(record)
+    }
+
+    /// Actions with `start <= timestamp <= end`, newest first, optionally only
+    /// those run by `user` (as the invoking or the sudo user)
+    pub async fn query_actions(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        user: Option<&str>,
+    ) -> Result<Vec<ActionRecord>> {
+        let mut actions: Vec<ActionRecord> = self
+            .store
+            .actions(start, end)
+            .await?
+            .into_iter()
+            .filter(|action| {
+                user.is_none_or(|user| {
+                    action.invoking_user == user || action.sudo_user.as_deref() == Some(user)
+                })
+            })
+            .collect();
+        actions.reverse();
+        Ok(actions)
+    }
+
     /// Clean up old audit records (keep only last N days)
     pub async fn cleanup_old_records(&self, keep_days: u32) -> Result<u64> {
         let cutoff = Utc::now() - chrono::Duration::days(keep_days as i64);
-        let file_path = self.data_dir.join("audit.jsonl");
+        self.store.delete_before(cutoff).await
+    }
+}
 
-        if !file_path.exists() {
-            return Ok(0);
+/// Rank (hostname, user) pairs by total memory, largest first
+fn top_users_by_host(records: &[AuditRecord], limit: usize) -> Vec<HostUserUsage> {
+    let mut stats: std::collections::HashMap<(String, String), (u64, u32)> =
+        std::collections::HashMap::new();
+    for record in records {
+        if let Some(ref user) = record.user {
+            let entry = stats
+                .entry((record.host().to_string(), user.clone()))
+                .or_insert((0, 0));
+            entry.0 += 1;
+            entry.1 += record.memory_used_mb;
         }
+    }
 
-        let content = fs::read_to_string(&file_path)
-            .map_err(|e| anyhow::anyhow!("Failed to read audit file: {}", e))?;
-
-        let mut records = Vec::new();
-        for line in content.lines() {
-            if line.trim().is_empty() {
-                continue;
-            }
+    let mut top_users: Vec<HostUserUsage> = stats
+        .into_iter()
+        .map(
+            |((hostname, user), (count, total_memory_mb))| HostUserUsage {
+                hostname,
+                user,
+                count,
+                total_memory_mb,
+            },
+        )
+        .collect();
+    // Tie-break on host and user so equal totals come out in a stable order
+    top_users.sort_by(|a, b| {
+        b.total_memory_mb
+            .cmp(&a.total_memory_mb)
+            .then_with(|| a.hostname.cmp(&b.hostname))
+            .then_with(|| a.user.cmp(&b.user))
+    });
+    top_users.truncate(limit);
+    top_users
+}
 
-            let record: AuditRecord = serde_json::from_str(line)
-                .map_err(|e| anyhow::anyhow!("Failed to parse audit record: {}", e))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit_store::JsonlStore;
+    use chrono::TimeZone;
 
-            if record.timestamp >= cutoff {
-                records.push(record);
-            }
+    fn test_record(id: i64, timestamp: DateTime<Utc>, user: &str, process: &str) -> AuditRecord {
+        AuditRecord {
+            id,
+            timestamp,
+            gpu_index: 0,
+            gpu_name: "Test GPU".to_string(),
+            pid: Some(1000 + id as u32),
+            user: Some(user.to_string()),
+            process_name: Some(process.to_string()),
+            memory_used_mb: 1024,
+            utilization_pct: 50.0,
+            temperature_c: 0,
+            power_w: 0.0,
+            container: None,
+            node_id: None,
+            hostname: None,
+            cmdline: None,
+            ecc_uncorrected: None,
+            ecc_uncorrected_delta: None,
+            pcie_mb_per_sec: None,
+            nvlink_bytes: None,
+            slurm_job_id: None,
+            start_unix_secs: None,
         }
+    }
 
-        let removed_count = content.lines().count() as u64 - records.len() as u64;
+    fn manager_in(data_dir: std::path::PathBuf) -> AuditManager {
+        AuditManager::with_store(Box::new(JsonlStore::new(data_dir).unwrap()))
+    }
 
-        // Write back the filtered records
-        let mut file = fs::File::create(&file_path)
-            .map_err(|e| anyhow::anyhow!("Failed to create audit file: {}", e))?;
+    #[tokio::test]
+    async fn test_flush_interval_coalesces_appends() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = manager_in(dir.path().to_path_buf())
+            .with_flush_interval(std::time::Duration::from_secs(3600));
+        let now = Utc::now();
 
-        for record in records {
-            let json_line = serde_json::to_string(&record)
-                .map_err(|e| anyhow::anyhow!("Failed to serialize record: {}", e))?;
-            writeln!(file, "{}", json_line)
-                .map_err(|e| anyhow::anyhow!("Failed to write to audit file: {}", e))?;
-        }
+        // The first append is written at once, later ones wait for the interval
+        manager
+            .append_records(&[test_record(1, now, "alice", "python")])
+            .await
+            .unwrap();
+        manager
+            .append_records(&[
+                test_record(2, now, "bob", "python"),
+                test_record(3, now, "carol", "python"),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(manager.pending_records(), 2);
+        assert_eq!(manager.query_records(1, None, None).await.unwrap().len(), 1);
 
-        Ok(removed_count)
+        manager.flush().await.unwrap();
+        assert_eq!(manager.pending_records(), 0);
+        assert_eq!(manager.query_records(1, None, None).await.unwrap().len(), 3);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    // use crate::nvml_api::{GpuSnapshot, GpuProc}; // Unused for now
+    #[tokio::test]
+    async fn test_new_manager_does_not_buffer() {
+        // Buffered records are lost unless the caller flushes, so buffering is opt-in
+        let manager = AuditManager::new().await.unwrap();
+        assert_eq!(manager.flush_interval, std::time::Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_torn_batch_does_not_lose_other_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = manager_in(dir.path().to_path_buf());
+        let now = Utc::now();
+        let batch: Vec<_> = (0..3)
+            .map(|id| test_record(id, now, "alice", "python"))
+            .collect();
+        manager.append_records(&batch).await.unwrap();
+
+        // Simulate a writer killed halfway through its batch
+        let line = serde_json::to_string(&test_record(10, now, "bob", "python")).unwrap();
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(dir.path().join("audit.jsonl"))
+            .unwrap();
+        file.write_all(&line.as_bytes()[..line.len() / 2]).unwrap();
+        drop(file);
+
+        // The torn record is skipped and the next batch starts on its own line
+        assert_eq!(manager.query_records(1, None, None).await.unwrap().len(), 3);
+        manager
+            .append_records(&[test_record(20, now, "carol", "python")])
+            .await
+            .unwrap();
+        let records = manager.query_records(1, None, None).await.unwrap();
+        assert_eq!(records.len(), 4);
+        assert!(records.iter().any(|r| r.user.as_deref() == Some("carol")));
+        assert!(records.iter().all(|r| r.user.as_deref() != Some("bob")));
+    }
+
+    /// Compares writing each node snapshot on its own with coalescing them behind a
+    /// flush interval. Run with `cargo test --release -- --ignored --nocapture bench_`.
+    #[tokio::test]
+    #[ignore]
+    async fn bench_coalesced_snapshot_writes() {
+        const SNAPSHOTS: usize = 2000;
+        const RECORDS_PER_SNAPSHOT: i64 = 8;
+        let now = Utc::now();
+        let snapshots: Vec<Vec<_>> = (0..SNAPSHOTS)
+            .map(|_| {
+                (0..RECORDS_PER_SNAPSHOT)
+                    .map(|id| test_record(id, now, "alice", "python"))
+                    .collect()
+            })
+            .collect();
+
+        for (label, interval) in [
+            ("per-snapshot", std::time::Duration::ZERO),
+            ("coalesced", std::time::Duration::from_millis(250)),
+        ] {
+            let dir = tempfile::tempdir().unwrap();
+            let manager = manager_in(dir.path().to_path_buf()).with_flush_interval(interval);
+            let started = std::time::Instant::now();
+            for snapshot in &snapshots {
+                manager.append_records(snapshot).await.unwrap();
+            }
+            manager.flush().await.unwrap();
+            let elapsed = started.elapsed();
+            println!(
+                "{label}: {SNAPSHOTS} snapshots in {elapsed:?} ({:.0} snapshots/s)",
+                SNAPSHOTS as f64 / elapsed.as_secs_f64()
+            );
+        }
+    }
 
     #[tokio::test]
     async fn test_audit_manager() {
@@ -371,4 +1176,816 @@ mod tests {
             // Test passed - manager created successfully
         }
     }
+
+    fn ecc_gpu(uncorrected: Option<u64>) -> GpuSnapshot {
+        GpuSnapshot {
+            gpu_index: 0,
+            name: "Test GPU".to_string(),
+            vendor: crate::vendor::GpuVendor::Nvidia,
+            mem_used_mb: 0,
+            mem_total_mb: 8192,
+            util_pct: 0.0,
+            temp_c: 40,
+            power_w: 50.0,
+            ecc_volatile: uncorrected.map(|uncorrected| crate::nvml_api::EccCounts {
+                corrected: 0,
+                uncorrected,
+            }),
+            pids: 0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_ecc_uncorrected_delta() {
+        assert_eq!(ecc_uncorrected_delta(None, 3), None);
+        assert_eq!(ecc_uncorrected_delta(Some(1), 3), Some(2));
+        assert_eq!(ecc_uncorrected_delta(Some(3), 3), Some(0));
+        // Driver reload reset the counter
+        assert_eq!(ecc_uncorrected_delta(Some(5), 2), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_log_snapshot_records_ecc_deltas() {
+        let dir = tempfile::tempdir().unwrap();
+        let gpu_records = |records: Vec<AuditRecord>| -> Vec<(Option<u64>, Option<u64>)> {
+            let mut records: Vec<_> = records.into_iter().filter(|r| r.pid.is_none()).collect();
+            records.sort_by_key(|r| r.id);
+            records
+                .iter()
+                .map(|r| (r.ecc_uncorrected, r.ecc_uncorrected_delta))
+                .collect()
+        };
+
+        let manager = manager_in(dir.path().to_path_buf());
+        manager
+            .log_snapshot(&[ecc_gpu(Some(0))], &[])
+            .await
+            .unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        manager
+            .log_snapshot(&[ecc_gpu(Some(2))], &[])
+            .await
+            .unwrap();
+
+        // A new manager (a later gpukill run) picks up the last count from the store
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        let later = manager_in(dir.path().to_path_buf());
+        later.log_snapshot(&[ecc_gpu(Some(3))], &[]).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        later.log_snapshot(&[ecc_gpu(None)], &[]).await.unwrap();
+
+        let records = later.query_records(1, None, None).await.unwrap();
+        assert_eq!(
+            gpu_records(records),
+            vec![
+                (Some(0), None),
+                (Some(2), Some(2)),
+                (Some(3), Some(1)),
+                (None, None)
+            ]
+        );
+    }
+
+    fn sample(minute: i64, gpu: u16, pid: u32, user: &str, memory_mb: u32) -> AuditRecord {
+        let base = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+        AuditRecord {
+            gpu_index: gpu,
+            pid: Some(pid),
+            memory_used_mb: memory_mb,
+            ..test_record(0, base + chrono::Duration::minutes(minute), user, "train")
+        }
+    }
+
+    #[test]
+    fn test_accounting_integrates_samples() {
+        let start = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+        let end = start + chrono::Duration::days(1);
+
+        // One process sampled every minute for an hour at 2 GiB: the first
+        // sample has no predecessor, so 60 intervals of one minute are counted.
+        let records: Vec<AuditRecord> =
+            (0..=60).map(|m| sample(m, 0, 100, "alice", 2048)).collect();
+        let report = AccountingReport::from_records(&records, start, end, 300);
+
+        assert_eq!(report.by_user.len(), 1);
+        assert!((report.by_user[0].gpu_hours - 1.0).abs() < 1e-9);
+        assert!((report.by_user[0].gb_hours - 2.0).abs() < 1e-9);
+        assert_eq!(report.by_user[0].samples, 61);
+        assert!((report.total_gpu_hours - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_accounting_caps_gaps() {
+        let start = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+        let end = start + chrono::Duration::days(1);
+
+        // Samples at 0, 1 and 121 minutes: the two-hour gap counts as the 5 minute cap
+        let records = vec![
+            sample(0, 0, 100, "alice", 1024),
+            sample(1, 0, 100, "alice", 1024),
+            sample(121, 0, 100, "alice", 1024),
+        ];
+        let report = AccountingReport::from_records(&records, start, end, 300);
+        let expected_hours = (60.0 + 300.0) / 3600.0;
+        assert!((report.by_user[0].gpu_hours - expected_hours).abs() < 1e-9);
+        assert!((report.by_user[0].gb_hours - expected_hours).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_accounting_uses_process_start_time() {
+        let start = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+        let end = start + chrono::Duration::days(1);
+        let started = |minute: i64, record: AuditRecord| AuditRecord {
+            start_unix_secs: Some((start + chrono::Duration::minutes(minute)).timestamp() as u64),
+            ..record
+        };
+
+        // Sampled every 5 minutes from minute 10; the process started at minute 7.
+        // Sample spread counts 20 minutes, the start time adds the 3 before the
+        // first sample.
+        let spread: Vec<AuditRecord> = (0..5)
+            .map(|i| sample(10 + i * 5, 0, 100, "alice", 1024))
+            .collect();
+        let report = AccountingReport::from_records(&spread, start, end, 300);
+        assert!((report.total_gpu_hours - 20.0 / 60.0).abs() < 1e-9);
+        let with_start: Vec<AuditRecord> = spread.into_iter().map(|r| started(7, r)).collect();
+        let report = AccountingReport::from_records(&with_start, start, end, 300);
+        assert!((report.total_gpu_hours - 23.0 / 60.0).abs() < 1e-9);
+
+        // A single sample says nothing by itself; with a start time it covers
+        // up to the gap cap
+        let single = [sample(30, 0, 200, "bob", 1024)];
+        let report = AccountingReport::from_records(&single, start, end, 300);
+        assert_eq!(report.total_gpu_hours, 0.0);
+        let report =
+            AccountingReport::from_records(&[started(0, single[0].clone())], start, end, 300);
+        assert!((report.total_gpu_hours - 5.0 / 60.0).abs() < 1e-9);
+
+        // A process that started before the range is only counted from its start
+        let report = AccountingReport::from_records(
+            &[started(-60, sample(2, 0, 300, "carol", 1024))],
+            start,
+            end,
+            300,
+        );
+        assert!((report.total_gpu_hours - 2.0 / 60.0).abs() < 1e-9);
+
+        // A reused PID starts a new run instead of inheriting the old process's
+        // samples: minutes 0-1 for the first, 58-60 for the second
+        let reused = vec![
+            sample(0, 0, 400, "dave", 1024),
+            sample(1, 0, 400, "dave", 1024),
+            started(58, sample(59, 0, 400, "erin", 1024)),
+            started(58, sample(60, 0, 400, "erin", 1024)),
+        ];
+        let report = AccountingReport::from_records(&reused, start, end, 300);
+        let hours = |name: &str| {
+            report
+                .by_user
+                .iter()
+                .find(|e| e.name == name)
+                .unwrap()
+                .gpu_hours
+        };
+        assert!((hours("dave") - 1.0 / 60.0).abs() < 1e-9);
+        assert!((hours("erin") - 2.0 / 60.0).abs() < 1e-9);
+
+        let record = &with_start[0];
+        assert_eq!(record.runtime(), Some(chrono::Duration::minutes(3)));
+        assert_eq!(single[0].runtime(), None);
+    }
+
+    #[test]
+    fn test_accounting_overlapping_processes() {
+        let start = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+        let end = start + chrono::Duration::days(1);
+
+        let mut records = Vec::new();
+        for m in 0..=30 {
+            // alice and bob share GPU 0 for the same 30 minutes
+            records.push(sample(m, 0, 100, "alice", 1024));
+            records.push(sample(m, 0, 200, "bob", 4096));
+            // alice's process also holds GPU 1 at the same time
+            records.push(sample(m, 1, 100, "alice", 1024));
+        }
+        // Sample outside the range is ignored
+        records.push(sample(60 * 48, 0, 200, "bob", 4096));
+
+        let report = AccountingReport::from_records(&records, start, end, 300);
+        let alice = report.by_user.iter().find(|e| e.name == "alice").unwrap();
+        let bob = report.by_user.iter().find(|e| e.name == "bob").unwrap();
+
+        // Each (GPU, PID) accrues time independently
+        assert!((alice.gpu_hours - 1.0).abs() < 1e-9);
+        assert!((bob.gpu_hours - 0.5).abs() < 1e-9);
+        assert!((bob.gb_hours - 2.0).abs() < 1e-9);
+        assert_eq!(report.by_user[0].name, "alice");
+        assert!((report.total_gpu_hours - 1.5).abs() < 1e-9);
+        assert_eq!(report.by_process.len(), 1);
+        assert!((report.by_process[0].gpu_hours - 1.5).abs() < 1e-9);
+
+        let csv = report.to_csv();
+        assert!(csv.starts_with("kind,name,gpu_hours,gb_hours,samples\n"));
+        assert!(csv.contains("user,alice,1.0000,1.0000,62\n"));
+        assert!(csv.contains("process,train,1.5000,"));
+    }
+
+    #[test]
+    fn test_accounting_groups_by_slurm_job() {
+        let start = Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap();
+        let end = start + chrono::Duration::days(1);
+
+        let mut records = Vec::new();
+        for m in 0..=60 {
+            // Job 4242 runs on two GPUs; bob's process is outside Slurm
+            for gpu in [0, 1] {
+                records.push(AuditRecord {
+                    slurm_job_id: Some("4242".to_string()),
+                    ..sample(m, gpu, 100 + gpu as u32, "alice", 1024)
+                });
+            }
+            records.push(sample(m, 2, 300, "bob", 1024));
+        }
+
+        let report = AccountingReport::from_records(&records, start, end, 300);
+        assert_eq!(report.by_job.len(), 1);
+        assert_eq!(report.by_job[0].name, "4242");
+        assert!((report.by_job[0].gpu_hours - 2.0).abs() < 1e-9);
+        assert!((report.total_gpu_hours - 3.0).abs() < 1e-9);
+        assert!(report.to_csv().contains("job,4242,2.0000,2.0000,122\n"));
+    }
+
+    #[tokio::test]
+    async fn test_query_records_range() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = manager_in(temp_dir.path().to_path_buf());
+
+        let t = |h: u32| Utc.with_ymd_and_hms(2024, 6, 1, h, 0, 0).unwrap();
+        manager
+            .append_records(&[
+                test_record(1, t(6), "alice", "python"),
+                test_record(2, t(8), "alice", "python"),
+                test_record(3, t(10), "bob", "trainer"),
+                test_record(4, t(12), "alice", "python"),
+            ])
+            .await
+            .unwrap();
+
+        // Bounds are inclusive and results are newest first
+        let records = manager
+            .query_records_range(t(8), t(10), None, None)
+            .await
+            .unwrap();
+        let ids: Vec<i64> = records.iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![3, 2]);
+
+        // User and process filters still apply within the range
+        let records = manager
+            .query_records_range(t(0), t(23), Some("alice"), None)
+            .await
+            .unwrap();
+        assert_eq!(records.len(), 3);
+        let records = manager
+            .query_records_range(t(9), t(23), None, Some("train"))
+            .await
+            .unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].id, 3);
+
+        // Empty range
+        let records = manager
+            .query_records_range(t(13), t(23), None, None)
+            .await
+            .unwrap();
+        assert!(records.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_query_paging_and_filters() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = manager_in(temp_dir.path().to_path_buf());
+
+        let t = |m: i64| {
+            Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap() + chrono::Duration::minutes(m)
+        };
+        let seeded: Vec<AuditRecord> = (0..20)
+            .map(|i| AuditRecord {
+                gpu_index: (i % 4) as u16,
+                container: (i % 2 == 0).then(|| "trainer".to_string()),
+                ..test_record(i, t(i), "alice", "python")
+            })
+            .collect();
+        manager.append_records(&seeded).await.unwrap();
+        let all = AuditQuery::range(t(0), t(60));
+        let ids = |page: &AuditPage| page.records.iter().map(|r| r.id).collect::<Vec<_>>();
+
+        // Newest first, one page at a time
+        let first = manager
+            .query(&AuditQuery {
+                limit: Some(5),
+                ..all.clone()
+            })
+            .await
+            .unwrap();
+        assert_eq!(ids(&first), vec![19, 18, 17, 16, 15]);
+        assert!(first.has_more);
+        let second = manager
+            .query(&AuditQuery {
+                offset: 5,
+                limit: Some(5),
+                ..all.clone()
+            })
+            .await
+            .unwrap();
+        assert_eq!(ids(&second), vec![14, 13, 12, 11, 10]);
+
+        // The cursor picks up strictly before the last record shown
+        let cursor = manager
+            .query(&AuditQuery {
+                before: Some(first.records[4].timestamp),
+                limit: Some(5),
+                ..all.clone()
+            })
+            .await
+            .unwrap();
+        assert_eq!(ids(&cursor), ids(&second));
+
+        // The last page knows it is the last
+        let last = manager
+            .query(&AuditQuery {
+                offset: 15,
+                limit: Some(5),
+                ..all.clone()
+            })
+            .await
+            .unwrap();
+        assert_eq!(ids(&last), vec![4, 3, 2, 1, 0]);
+        assert!(!last.has_more);
+
+        // GPU and container filters combine with paging
+        let gpu = manager
+            .query(&AuditQuery {
+                gpu: Some(2),
+                limit: Some(3),
+                ..all.clone()
+            })
+            .await
+            .unwrap();
+        assert_eq!(ids(&gpu), vec![18, 14, 10]);
+        assert!(gpu.has_more);
+        let container = manager
+            .query(&AuditQuery {
+                gpu: Some(1),
+                container: Some("trainer".to_string()),
+                ..all.clone()
+            })
+            .await
+            .unwrap();
+        assert!(container.records.is_empty());
+        let container = manager
+            .query(&AuditQuery {
+                container: Some("trainer".to_string()),
+                ..all.clone()
+            })
+            .await
+            .unwrap();
+        assert_eq!(container.records.len(), 10);
+        assert!(!container.has_more);
+    }
+
+    #[tokio::test]
+    async fn test_write_records_json_streams_a_page() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = manager_in(temp_dir.path().to_path_buf());
+
+        let t = |h: u32| Utc.with_ymd_and_hms(2024, 6, 1, h, 0, 0).unwrap();
+        manager
+            .append_records(&[
+                test_record(1, t(6), "alice", "python"),
+                test_record(2, t(8), "bob", "trainer"),
+                test_record(3, t(10), "alice", "python"),
+            ])
+            .await
+            .unwrap();
+
+        let mut out = Vec::new();
+        let (written, has_more) = manager
+            .write_records_json(
+                &AuditQuery {
+                    limit: Some(2),
+                    ..AuditQuery::range(t(0), t(23))
+                },
+                &mut out,
+            )
+            .await
+            .unwrap();
+        assert_eq!((written, has_more), (2, true));
+        let parsed: Vec<AuditRecord> = serde_json::from_slice(&out).unwrap();
+        assert_eq!(parsed.iter().map(|r| r.id).collect::<Vec<_>>(), vec![3, 2]);
+
+        // An empty result is still a valid array
+        let mut out = Vec::new();
+        let (written, has_more) = manager
+            .write_records_json(&AuditQuery::range(t(20), t(23)), &mut out)
+            .await
+            .unwrap();
+        assert_eq!((written, has_more), (0, false));
+        let parsed: Vec<AuditRecord> = serde_json::from_slice(&out).unwrap();
+        assert!(parsed.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_export_records_ndjson() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = manager_in(temp_dir.path().to_path_buf());
+
+        let t = |h: u32| Utc.with_ymd_and_hms(2024, 6, 1, h, 0, 0).unwrap();
+        manager
+            .append_records(&[
+                test_record(1, t(6), "alice", "python"),
+                test_record(2, t(8), "alice", "python"),
+                test_record(3, t(10), "bob", "trainer"),
+                test_record(4, t(12), "alice", "python"),
+                test_record(5, t(14), "bob", "trainer"),
+            ])
+            .await
+            .unwrap();
+
+        let mut out = Vec::new();
+        let written = manager
+            .export_records_ndjson(&AuditQuery::range(t(8), t(14)), &mut out)
+            .await
+            .unwrap();
+        assert_eq!(written, 4);
+
+        // Every line is a standalone compact record, in storage order
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.ends_with('\n'));
+        let ids: Vec<i64> = text
+            .lines()
+            .map(|line| {
+                assert!(!line.contains('\n') && !line.starts_with('['));
+                serde_json::from_str::<AuditRecord>(line).unwrap().id
+            })
+            .collect();
+        assert_eq!(ids, vec![2, 3, 4, 5]);
+
+        // Filters match query_records_range
+        let mut out = Vec::new();
+        let written = manager
+            .export_records_ndjson(
+                &AuditQuery {
+                    user: Some("bob".to_string()),
+                    process: Some("train".to_string()),
+                    ..AuditQuery::range(t(0), t(23))
+                },
+                &mut out,
+            )
+            .await
+            .unwrap();
+        assert_eq!(written, 2);
+        assert_eq!(String::from_utf8(out).unwrap().lines().count(), 2);
+
+        // A missing audit file exports nothing
+        let empty = manager_in(temp_dir.path().join("missing"));
+        let mut out = Vec::new();
+        assert_eq!(
+            empty
+                .export_records_ndjson(&AuditQuery::range(t(0), t(23)), &mut out)
+                .await
+                .unwrap(),
+            0
+        );
+        assert!(out.is_empty());
+    }
+
+    /// Check that every element opened in `html` is closed in order. Void elements
+    /// and self-closing tags need no end tag; text and attributes are not validated.
+    fn assert_well_formed(html: &str) {
+        const VOID: [&str; 2] = ["meta", "br"];
+        let mut open: Vec<&str> = Vec::new();
+        let mut rest = html;
+        while let Some(start) = rest.find('<') {
+            let end = rest[start..].find('>').expect("unterminated tag") + start;
+            let tag = &rest[start + 1..end];
+            rest = &rest[end + 1..];
+            if tag.starts_with('!') {
+                continue;
+            }
+            if let Some(name) = tag.strip_prefix('/') {
+                assert_eq!(open.pop(), Some(name), "unbalanced </{}>", name);
+                continue;
+            }
+            let name = tag.split_whitespace().next().unwrap_or_default();
+            if !tag.ends_with('/') && !VOID.contains(&name) {
+                open.push(name);
+            }
+        }
+        assert!(open.is_empty(), "unclosed elements: {:?}", open);
+    }
+
+    #[tokio::test]
+    async fn test_summary_html_report() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = manager_in(temp_dir.path().to_path_buf());
+
+        let t = |h: u32| Utc.with_ymd_and_hms(2024, 6, 1, h, 0, 0).unwrap();
+        let record = |id: i64, hour: u32, user: &str, memory_mb: u32| AuditRecord {
+            memory_used_mb: memory_mb,
+            ..test_record(id, t(hour), user, "python")
+        };
+        manager
+            .append_records(&[
+                record(1, 0, "alice", 4096),
+                record(2, 1, "alice", 4096),
+                record(3, 1, "bob", 2048),
+                record(4, 2, "<script>", 512),
+            ])
+            .await
+            .unwrap();
+        let summary = manager.get_summary_range(t(0), t(6)).await.unwrap();
+
+        let html = summary.to_html("2024-06-01 00:00 to 06:00", t(6));
+        assert_well_formed(&html);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<td>alice</td><td class=\"num\">2</td><td class=\"num\">8192</td>"));
+        assert!(html.contains("<td>bob</td>"));
+        assert!(html.contains("<td>&lt;script&gt;</td>"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("<title>Hour 1: 3072 MB average</title>"));
+        // Self-contained: no scripts, stylesheets or images from elsewhere
+        assert!(!html.contains("http://") && !html.contains("https://"));
+
+        let empty = AuditSummary {
+            total_records: 0,
+            time_range_hours: 1,
+            top_users: Vec::new(),
+            top_processes: Vec::new(),
+            gpu_usage_by_hour: vec![(0, 0)],
+        };
+        let html = empty.to_html("Last 1 hours", t(6));
+        assert_well_formed(&html);
+        assert!(html.contains("No memory in use during this range."));
+    }
+
+    #[tokio::test]
+    async fn test_summary_by_host_separates_same_username() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = manager_in(temp_dir.path().to_path_buf());
+
+        let t = |h: u32| Utc.with_ymd_and_hms(2024, 6, 1, h, 0, 0).unwrap();
+        let on_host = |id: i64, host: &str, user: &str, memory_mb: u32| AuditRecord {
+            hostname: Some(host.to_string()),
+            memory_used_mb: memory_mb,
+            ..test_record(id, t(8), user, "python")
+        };
+        manager
+            .append_records(&[
+                on_host(1, "gpu-a", "alice", 4096),
+                on_host(2, "gpu-a", "alice", 4096),
+                on_host(3, "gpu-b", "alice", 1024),
+                on_host(4, "gpu-b", "bob", 2048),
+            ])
+            .await
+            .unwrap();
+
+        // The plain summary merges both alices
+        let summary = manager.get_summary_range(t(0), t(12)).await.unwrap();
+        assert_eq!(summary.top_users[0], ("alice".to_string(), 3, 9216));
+
+        let summary = manager
+            .get_summary_by_host_range(t(0), t(12))
+            .await
+            .unwrap();
+        assert_eq!(summary.total_records, 4);
+        let users: Vec<(&str, &str, u64, u32)> = summary
+            .top_users
+            .iter()
+            .map(|u| {
+                (
+                    u.hostname.as_str(),
+                    u.user.as_str(),
+                    u.count,
+                    u.total_memory_mb,
+                )
+            })
+            .collect();
+        assert_eq!(
+            users,
+            vec![
+                ("gpu-a", "alice", 2, 8192),
+                ("gpu-b", "bob", 1, 2048),
+                ("gpu-b", "alice", 1, 1024),
+            ]
+        );
+    }
+
+    /// Behaviour every audit store must share. Run against an empty store.
+    async fn check_store_semantics(manager: AuditManager) {
+        let t = |h: u32| Utc.with_ymd_and_hms(2024, 6, 1, h, 0, 0).unwrap();
+        let on_host = |id: i64, h: u32, host: &str, user: &str, process: &str| AuditRecord {
+            hostname: Some(host.to_string()),
+            cmdline: Some(format!("{} --rank {}", process, id)),
+            ..test_record(id, t(h), user, process)
+        };
+        // Two nodes writing concurrently
+        let node_a = [
+            on_host(1, 6, "gpu-a", "alice", "python"),
+            on_host(2, 8, "gpu-a", "alice", "python"),
+        ];
+        let node_b = [
+            on_host(3, 8, "gpu-b", "alice", "trainer"),
+            on_host(4, 10, "gpu-b", "bob", "trainer"),
+        ];
+        let (a, b) = tokio::join!(
+            manager.append_records(&node_a),
+            manager.append_records(&node_b)
+        );
+        a.unwrap();
+        b.unwrap();
+        manager
+            .append_records(&[AuditRecord {
+                gpu_index: 3,
+                pid: None,
+                user: None,
+                process_name: None,
+                node_id: Some("node-7".to_string()),
+                ..test_record(5, t(12), "", "")
+            }])
+            .await
+            .unwrap();
+
+        // Inclusive bounds, newest first, every field round-trips
+        let records = manager
+            .query_records_range(t(8), t(10), None, None)
+            .await
+            .unwrap();
+        let mut ids: Vec<i64> = records.iter().map(|r| r.id).collect();
+        assert_eq!(ids[0], 4);
+        ids.sort();
+        assert_eq!(ids, vec![2, 3, 4]);
+        let bob = &records[0];
+        assert_eq!(bob.hostname.as_deref(), Some("gpu-b"));
+        assert_eq!(bob.pid, Some(1004));
+        assert_eq!(bob.cmdline.as_deref(), Some("trainer --rank 4"));
+        assert_eq!(bob.timestamp, t(10));
+
+        // Exact user and substring process filters
+        let records = manager
+            .query_records_range(t(0), t(23), Some("alice"), Some("py"))
+            .await
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        let gpu_level = manager
+            .query_records_range(t(12), t(12), None, None)
+            .await
+            .unwrap();
+        assert_eq!(gpu_level.len(), 1);
+        assert_eq!(gpu_level[0].host(), "node-7");
+        assert_eq!(gpu_level[0].user, None);
+
+        let summary = manager.get_summary_range(t(0), t(12)).await.unwrap();
+        assert_eq!(summary.total_records, 5);
+        assert_eq!(summary.time_range_hours, 12);
+        assert_eq!(summary.top_users[0], ("alice".to_string(), 3, 3072));
+        let by_host = manager
+            .get_summary_by_host_range(t(0), t(12))
+            .await
+            .unwrap();
+        assert_eq!(by_host.top_users[0].hostname, "gpu-a");
+        assert_eq!(by_host.top_users[0].count, 2);
+
+        let mut out = Vec::new();
+        let written = manager
+            .export_records_ndjson(
+                &AuditQuery {
+                    user: Some("bob".to_string()),
+                    ..AuditQuery::range(t(0), t(23))
+                },
+                &mut out,
+            )
+            .await
+            .unwrap();
+        assert_eq!(written, 1);
+
+        // Paging is newest first; records 2 and 3 share a timestamp
+        let page = manager
+            .query(&AuditQuery {
+                offset: 1,
+                limit: Some(3),
+                ..AuditQuery::range(t(0), t(23))
+            })
+            .await
+            .unwrap();
+        assert_eq!(page.records[0].id, 4);
+        assert!(page.has_more);
+        let gpu = manager
+            .query(&AuditQuery {
+                gpu: Some(3),
+                ..AuditQuery::range(t(0), t(23))
+            })
+            .await
+            .unwrap();
+        assert_eq!(gpu.records.len(), 1);
+        assert_eq!(gpu.records[0].id, 5);
+
+        // Everything is older than the cutoff
+        assert_eq!(manager.cleanup_old_records(0).await.unwrap(), 5);
+        assert!(manager
+            .query_records_range(t(0), t(23), None, None)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_jsonl_store_semantics() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        check_store_semantics(manager_in(temp_dir.path().to_path_buf())).await;
+    }
+
+    /// Runs the shared suite against PostgreSQL when `GPUKILL_TEST_DATABASE_URL` points
+    /// at a scratch database. The audit table in that database is cleared.
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_postgres_store_semantics() {
+        let Ok(url) = std::env::var("GPUKILL_TEST_DATABASE_URL") else {
+            eprintln!("GPUKILL_TEST_DATABASE_URL not set; skipping Postgres audit test");
+            return;
+        };
+        let manager = AuditManager::with_database_url(Some(&url)).await.unwrap();
+        // Migrations are idempotent
+        AuditManager::with_database_url(Some(&url)).await.unwrap();
+        manager
+            .store
+            .delete_before(Utc::now() + chrono::Duration::days(1))
+            .await
+            .unwrap();
+        check_store_semantics(manager).await;
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_kill_is_recorded_in_action_log() {
+        use crate::proc::{graceful_kill_pid, KillSignal};
+
+        let dir = tempfile::tempdir().unwrap();
+        let manager = manager_in(dir.path().to_path_buf());
+        let start = Utc::now() - chrono::Duration::seconds(1);
+
+        // A real kill of a throwaway process, recorded the way main.rs records --kill
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let pid = child.id();
+        let target = format!("pid {}", pid);
+        // Reap the child as soon as it exits so the kill sees it disappear
+        let reaper = std::thread::spawn(move || child.wait());
+        let result = graceful_kill_pid(pid, KillSignal::Term, 5, true).map(|_| ());
+        reaper.join().unwrap().unwrap();
+        manager
+            .log_action(ActionKind::Kill, &target, "alice", false, &result)
+            .await
+            .unwrap();
+        manager
+            .log_action(
+                ActionKind::Reset,
+                "gpu 3",
+                "bob",
+                true,
+                &Err(anyhow::anyhow!("GPU 3 not found")),
+            )
+            .await
+            .unwrap();
+
+        let end = Utc::now() + chrono::Duration::seconds(1);
+        let actions = manager.query_actions(start, end, None).await.unwrap();
+        assert_eq!(actions.len(), 2);
+        // Newest first
+        assert_eq!(actions[0].kind, ActionKind::Reset);
+        assert!(actions[0].dry_run);
+        assert!(!actions[0].success);
+        assert_eq!(actions[0].error.as_deref(), Some("GPU 3 not found"));
+
+        let kills = manager
+            .query_actions(start, end, Some("alice"))
+            .await
+            .unwrap();
+        assert_eq!(kills.len(), 1);
+        assert_eq!(kills[0].kind, ActionKind::Kill);
+        assert_eq!(kills[0].target, target);
+        assert_eq!(kills[0].invoking_user, "alice");
+        assert!(!kills[0].dry_run);
+        assert!(kills[0].success);
+        assert_eq!(kills[0].hostname, crate::util::get_hostname());
+
+        // Outside the time range nothing is returned
+        assert!(manager
+            .query_actions(end, end + chrono::Duration::hours(1), None)
+            .await
+            .unwrap()
+            .is_empty());
+    }
 }
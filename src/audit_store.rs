@@ -0,0 +1,1061 @@
+//! Storage backends for audit records
+//!
+//! The default backend is a JSON Lines file in the local data directory. With the
+//! `postgres` feature, `audit.database_url` in the config file points every node at
+//! a shared PostgreSQL database instead, so cluster-wide reports need no scraping.
+
+use crate::audit::{ActionRecord, AuditRecord};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures_util::stream::{self, BoxStream, StreamExt};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+/// Version of the on-disk audit log layout, stored next to the log. Version 2
+/// added `hostname` to each record.
+const AUDIT_SCHEMA_VERSION: u32 = 2;
+
+/// Filters and paging for an audit query. Results are newest first; `offset` and
+/// `limit` page through them and `before` is an exclusive cursor for the next page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditQuery {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Exact user name
+    pub user: Option<String>,
+    /// Substring of the process name
+    pub process: Option<String>,
+    pub gpu: Option<u16>,
+    /// Exact container name
+    pub container: Option<String>,
+    /// Only records strictly older than this
+    pub before: Option<DateTime<Utc>>,
+    pub offset: usize,
+    /// Maximum number of records; None returns everything that matches
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    /// Every record with `start <= timestamp <= end`, unfiltered and unlimited
+    pub fn range(start: DateTime<Utc>, end: DateTime<Utc>) -> Self {
+        Self {
+            start,
+            end,
+            user: None,
+            process: None,
+            gpu: None,
+            container: None,
+            before: None,
+            offset: 0,
+            limit: None,
+        }
+    }
+
+    /// Whether a record passes the time range and filters (paging aside)
+    pub fn matches(&self, record: &AuditRecord) -> bool {
+        if record.timestamp < self.start || record.timestamp > self.end {
+            return false;
+        }
+        if matches!(self.before, Some(before) if record.timestamp >= before) {
+            return false;
+        }
+        if let Some(ref user) = self.user {
+            if record.user.as_ref() != Some(user) {
+                return false;
+            }
+        }
+        if let Some(ref process) = self.process {
+            match record.process_name {
+                Some(ref name) if name.contains(process.as_str()) => {}
+                _ => return false,
+            }
+        }
+        if matches!(self.gpu, Some(gpu) if record.gpu_index != gpu) {
+            return false;
+        }
+        if let Some(ref container) = self.container {
+            if record.container.as_ref() != Some(container) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Persistent storage for audit records. Every backend must return the same
+/// records for the same calls; filtering and summaries are done by `AuditManager`.
+#[async_trait]
+pub trait AuditStore: Send + Sync {
+    /// Short description of where records are kept, for logs
+    fn describe(&self) -> String;
+
+    /// Bring the storage up to the current schema. Safe to run concurrently from
+    /// several nodes and to run again when already up to date.
+    async fn migrate(&self) -> Result<()>;
+
+    /// Append records in order
+    async fn append(&self, records: &[AuditRecord]) -> Result<()>;
+
+    /// Records with `start <= timestamp <= end`, in the order they were appended
+    fn scan(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> BoxStream<'_, Result<AuditRecord>>;
+
+    /// Records matching `query`, newest first (ties latest appended first), with
+    /// its offset and limit applied
+    fn query<'a>(&'a self, query: &AuditQuery) -> BoxStream<'a, Result<AuditRecord>>;
+
+    /// Delete records older than `cutoff`, returning how many were removed
+    async fn delete_before(&self, cutoff: DateTime<Utc>) -> Result<u64>;
+
+    /// Append kill and reset actions run through gpukill
+    async fn append_actions(&self, actions: &[ActionRecord]) -> Result<()>;
+
+    /// Actions with `start <= timestamp <= end`, in the order they were appended
+    async fn actions(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<ActionRecord>>;
+
+    /// Append coordinator cluster events in order
+    async fn append_events(&self, events: &[EventRecord]) -> Result<()>;
+
+    /// Up to `limit` events newer than `since`, oldest first
+    async fn events_since(&self, since: DateTime<Utc>, limit: usize) -> Result<Vec<EventRecord>>;
+}
+
+/// A coordinator cluster event as stored: the event's JSON document and its
+/// timestamp. Stores treat the document as opaque apart from its `type` field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventRecord {
+    pub timestamp: DateTime<Utc>,
+    pub event: serde_json::Value,
+}
+
+impl EventRecord {
+    /// Parse a stored document, taking the timestamp from its `timestamp` field
+    fn from_json(event: serde_json::Value) -> Result<Self> {
+        let timestamp = event
+            .get("timestamp")
+            .and_then(|t| t.as_str())
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .ok_or_else(|| anyhow::anyhow!("Stored cluster event has no valid timestamp"))?
+            .with_timezone(&Utc);
+        Ok(Self { timestamp, event })
+    }
+}
+
+/// Open the store selected by `database_url`, or the local JSON Lines log when unset
+pub async fn open_store(database_url: Option<&str>) -> Result<Box<dyn AuditStore>> {
+    let store: Box<dyn AuditStore> = match database_url {
+        None => Box::new(JsonlStore::new(JsonlStore::default_dir()?)?),
+        Some(url) if url.starts_with("postgres://") || url.starts_with("postgresql://") => {
+            open_postgres(url).await?
+        }
+        Some(url) => {
+            return Err(anyhow::anyhow!(
+                "Unsupported audit.database_url '{}': expected a postgres:// URL",
+                redact_url(url)
+            ))
+        }
+    };
+    store.migrate().await?;
+    tracing::debug!("Audit storage: {}", store.describe());
+    Ok(store)
+}
+
+#[cfg(feature = "postgres")]
+async fn open_postgres(url: &str) -> Result<Box<dyn AuditStore>> {
+    Ok(Box::new(postgres::PostgresStore::connect(url).await?))
+}
+
+#[cfg(not(feature = "postgres"))]
+async fn open_postgres(url: &str) -> Result<Box<dyn AuditStore>> {
+    Err(anyhow::anyhow!(
+        "audit.database_url is set to {}, but this gpukill was built without the \
+         `postgres` feature; rebuild with `cargo build --features postgres`",
+        redact_url(url)
+    ))
+}
+
+/// Hide the password in a database URL before it is logged
+fn redact_url(url: &str) -> String {
+    match (url.find("://"), url.rfind('@')) {
+        (Some(scheme_end), Some(at)) if at > scheme_end => {
+            let credentials = &url[scheme_end + 3..at];
+            match credentials.split_once(':') {
+                Some((user, _)) => format!("{}{}:***{}", &url[..scheme_end + 3], user, &url[at..]),
+                None => url.to_string(),
+            }
+        }
+        _ => url.to_string(),
+    }
+}
+
+/// Audit records as one JSON object per line in `audit.jsonl`
+pub struct JsonlStore {
+    data_dir: PathBuf,
+}
+
+impl JsonlStore {
+    /// Store records in `data_dir`, creating it if needed
+    pub fn new(data_dir: PathBuf) -> Result<Self> {
+        fs::create_dir_all(&data_dir)
+            .map_err(|e| anyhow::anyhow!("Failed to create audit directory: {}", e))?;
+        Ok(Self { data_dir })
+    }
+
+    /// Default data directory path
+    pub fn default_dir() -> Result<PathBuf> {
+        // Try multiple fallback locations for the data directory
+        let mut path = if let Some(data_dir) = dirs::data_dir() {
+            data_dir
+        } else if let Some(home_dir) = dirs::home_dir() {
+            home_dir.join(".local").join("share")
+        } else {
+            std::env::current_dir()?
+        };
+
+        path.push("gpukill");
+        Ok(path)
+    }
+
+    fn log_path(&self) -> PathBuf {
+        self.data_dir.join("audit.jsonl")
+    }
+
+    fn actions_path(&self) -> PathBuf {
+        self.data_dir.join("actions.jsonl")
+    }
+
+    fn events_path(&self) -> PathBuf {
+        self.data_dir.join("cluster_events.jsonl")
+    }
+
+    /// Stamp `hostname` on local records that predate it. Records from cluster nodes
+    /// keep no hostname and are grouped by their node ID instead.
+    fn migrate_add_hostname(&self, hostname: &str) -> Result<u64> {
+        let file_path = self.log_path();
+        if !file_path.exists() {
+            return Ok(0);
+        }
+
+        let content = fs::read_to_string(&file_path)
+            .map_err(|e| anyhow::anyhow!("Failed to read audit file: {}", e))?;
+
+        let mut records = Vec::new();
+        let mut updated = 0;
+        for line in content.lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let mut record: AuditRecord = serde_json::from_str(line)
+                .map_err(|e| anyhow::anyhow!("Failed to parse audit record: {}", e))?;
+            if record.hostname.is_none() && record.node_id.is_none() {
+                record.hostname = Some(hostname.to_string());
+                updated += 1;
+            }
+            records.push(record);
+        }
+
+        if updated == 0 {
+            return Ok(0);
+        }
+
+        // Write to a temporary file first so an interrupted migration keeps the old log
+        self.replace_log(&records)?;
+        Ok(updated)
+    }
+
+    /// Atomically replace the log with `records`
+    fn replace_log(&self, records: &[AuditRecord]) -> Result<()> {
+        let tmp_path = self.data_dir.join("audit.jsonl.tmp");
+        let mut file = fs::File::create(&tmp_path)
+            .map_err(|e| anyhow::anyhow!("Failed to create audit file: {}", e))?;
+        for record in records {
+            let json_line = serde_json::to_string(record)
+                .map_err(|e| anyhow::anyhow!("Failed to serialize record: {}", e))?;
+            writeln!(file, "{}", json_line)
+                .map_err(|e| anyhow::anyhow!("Failed to write to audit file: {}", e))?;
+        }
+        fs::rename(&tmp_path, self.log_path())
+            .map_err(|e| anyhow::anyhow!("Failed to replace audit file: {}", e))
+    }
+
+    /// One page of matching records, newest first. With a limit only the newest
+    /// `offset + limit` matches are held in memory while the log is read.
+    fn read_page(&self, query: &AuditQuery) -> Result<Vec<AuditRecord>> {
+        let keep = query.limit.map(|limit| query.offset.saturating_add(limit));
+        // Min-heap on (timestamp, position), so the oldest kept match is evicted first
+        let mut newest = BinaryHeap::new();
+        for (position, record) in self.read_log()?.enumerate() {
+            let record = record?;
+            if !query.matches(&record) {
+                continue;
+            }
+            newest.push(Reverse(PagedRecord { position, record }));
+            if matches!(keep, Some(keep) if newest.len() > keep) {
+                newest.pop();
+            }
+        }
+
+        // Ascending order of Reverse is newest first
+        let records = newest.into_sorted_vec().into_iter().map(|r| r.0.record);
+        Ok(records
+            .skip(query.offset)
+            .take(query.limit.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    /// Records in the log in storage order, read lazily. Lines that do not parse,
+    /// such as a record torn by a writer that was killed mid-batch, are skipped.
+    fn read_log(&self) -> Result<impl Iterator<Item = Result<AuditRecord>> + Send> {
+        let file_path = self.log_path();
+        let file = if file_path.exists() {
+            Some(
+                fs::File::open(&file_path)
+                    .map_err(|e| anyhow::anyhow!("Failed to open audit file: {}", e))?,
+            )
+        } else {
+            None
+        };
+
+        Ok(file
+            .into_iter()
+            .flat_map(|file| BufReader::new(file).lines())
+            .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+            .filter_map(|line| {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(e) => {
+                        return Some(Err(anyhow::anyhow!("Failed to read audit file: {}", e)))
+                    }
+                };
+                match serde_json::from_str(&line) {
+                    Ok(record) => Some(Ok(record)),
+                    Err(e) => {
+                        tracing::warn!("Skipping unreadable audit record: {}", e);
+                        None
+                    }
+                }
+            }))
+    }
+}
+
+/// Whether `file` is empty or its last byte is a newline
+fn ends_with_newline(file: &mut fs::File) -> std::io::Result<bool> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    if file.metadata()?.len() == 0 {
+        return Ok(true);
+    }
+    file.seek(SeekFrom::End(-1))?;
+    let mut last = [0u8; 1];
+    file.read_exact(&mut last)?;
+    Ok(last[0] == b'\n')
+}
+
+/// A record ordered by timestamp, then by position in the log
+struct PagedRecord {
+    position: usize,
+    record: AuditRecord,
+}
+
+impl PagedRecord {
+    fn key(&self) -> (DateTime<Utc>, usize) {
+        (self.record.timestamp, self.position)
+    }
+}
+
+impl PartialEq for PagedRecord {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for PagedRecord {}
+
+impl PartialOrd for PagedRecord {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PagedRecord {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+#[async_trait]
+impl AuditStore for JsonlStore {
+    fn describe(&self) -> String {
+        format!("JSON Lines log at {}", self.log_path().display())
+    }
+
+    async fn migrate(&self) -> Result<()> {
+        let version_path = self.data_dir.join("audit.version");
+        let version = fs::read_to_string(&version_path)
+            .ok()
+            .and_then(|v| v.trim().parse::<u32>().ok())
+            .unwrap_or(1);
+
+        if version < 2 {
+            let updated = self.migrate_add_hostname(&crate::util::get_hostname())?;
+            if updated > 0 {
+                tracing::info!("Added hostname to {} existing audit records", updated);
+            }
+        }
+
+        if version != AUDIT_SCHEMA_VERSION {
+            fs::write(&version_path, AUDIT_SCHEMA_VERSION.to_string())
+                .map_err(|e| anyhow::anyhow!("Failed to write audit version: {}", e))?;
+        }
+        Ok(())
+    }
+
+    async fn append(&self, records: &[AuditRecord]) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        // Serialize the whole batch first and write it with a single call, so a batch
+        // costs one write however many records it holds
+        let mut batch = String::new();
+        for record in records {
+            let json_line = serde_json::to_string(record)
+                .map_err(|e| anyhow::anyhow!("Failed to serialize record: {}", e))?;
+            batch.push_str(&json_line);
+            batch.push('\n');
+        }
+
+        // Create a JSON Lines file (one JSON object per line)
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(self.log_path())
+            .map_err(|e| anyhow::anyhow!("Failed to open audit file: {}", e))?;
+        // A writer killed mid-batch leaves a line without its newline; end it so the
+        // torn record does not swallow the first record of this batch
+        if !ends_with_newline(&mut file)
+            .map_err(|e| anyhow::anyhow!("Failed to read audit file: {}", e))?
+        {
+            batch.insert(0, '\n');
+        }
+        file.write_all(batch.as_bytes())
+            .map_err(|e| anyhow::anyhow!("Failed to write to audit file: {}", e))?;
+
+        Ok(())
+    }
+
+    fn scan(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> BoxStream<'_, Result<AuditRecord>> {
+        match self.read_log() {
+            Ok(records) => stream::iter(records.filter(
+                move |record| !matches!(record, Ok(r) if r.timestamp < start || r.timestamp > end),
+            ))
+            .boxed(),
+            Err(e) => stream::once(async { Err(e) }).boxed(),
+        }
+    }
+
+    fn query<'a>(&'a self, query: &AuditQuery) -> BoxStream<'a, Result<AuditRecord>> {
+        match self.read_page(query) {
+            Ok(records) => stream::iter(records.into_iter().map(Ok)).boxed(),
+            Err(e) => stream::once(async { Err(e) }).boxed(),
+        }
+    }
+
+    async fn delete_before(&self, cutoff: DateTime<Utc>) -> Result<u64> {
+        let mut kept = Vec::new();
+        let mut removed = 0;
+        for record in self.read_log()? {
+            let record = record?;
+            if record.timestamp >= cutoff {
+                kept.push(record);
+            } else {
+                removed += 1;
+            }
+        }
+
+        if removed > 0 {
+            self.replace_log(&kept)?;
+        }
+        Ok(removed)
+    }
+
+    async fn append_actions(&self, actions: &[ActionRecord]) -> Result<()> {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.actions_path())
+            .map_err(|e| anyhow::anyhow!("Failed to open action log: {}", e))?;
+        for action in actions {
+            let json_line = serde_json::to_string(action)
+                .map_err(|e| anyhow::anyhow!("Failed to serialize action: {}", e))?;
+            writeln!(file, "{}", json_line)
+                .map_err(|e| anyhow::anyhow!("Failed to write to action log: {}", e))?;
+        }
+        Ok(())
+    }
+
+    async fn actions(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Vec<ActionRecord>> {
+        let file = match fs::File::open(self.actions_path()) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(anyhow::anyhow!("Failed to open action log: {}", e)),
+        };
+
+        let mut actions = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str::<ActionRecord>(&line) {
+                Ok(action) if action.timestamp >= start && action.timestamp <= end => {
+                    actions.push(action)
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Skipping unreadable action record: {}", e),
+            }
+        }
+        Ok(actions)
+    }
+
+    async fn append_events(&self, events: &[EventRecord]) -> Result<()> {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.events_path())
+            .map_err(|e| anyhow::anyhow!("Failed to open cluster event log: {}", e))?;
+        for event in events {
+            writeln!(file, "{}", event.event)
+                .map_err(|e| anyhow::anyhow!("Failed to write cluster event: {}", e))?;
+        }
+        Ok(())
+    }
+
+    async fn events_since(&self, since: DateTime<Utc>, limit: usize) -> Result<Vec<EventRecord>> {
+        let file = match fs::File::open(self.events_path()) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(anyhow::anyhow!("Failed to open cluster event log: {}", e)),
+        };
+
+        let mut events = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event = serde_json::from_str(&line)
+                .map_err(anyhow::Error::from)
+                .and_then(EventRecord::from_json);
+            match event {
+                Ok(event) if event.timestamp > since => {
+                    events.push(event);
+                    if events.len() >= limit {
+                        break;
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Skipping unreadable cluster event: {}", e),
+            }
+        }
+        Ok(events)
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod postgres {
+    use super::{AuditQuery, AuditStore, EventRecord};
+    use crate::audit::{ActionKind, ActionRecord, AuditRecord};
+    use anyhow::{Context, Result};
+    use async_trait::async_trait;
+    use chrono::{DateTime, Utc};
+    use futures_util::stream::{BoxStream, StreamExt};
+    use sqlx::postgres::{PgConnection, PgPool, PgPoolOptions, PgRow};
+    use sqlx::{Connection, QueryBuilder, Row};
+
+    /// Arbitrary key for the advisory lock that serializes migrations across nodes
+    const MIGRATION_LOCK_KEY: i64 = 0x0067_7075_6b69_6c6c;
+
+    /// Schema migrations, applied in order; the index + 1 is the schema version
+    const MIGRATIONS: &[&[&str]] = &[
+        &[
+            "CREATE TABLE IF NOT EXISTS gpukill_audit_records (
+                seq BIGSERIAL PRIMARY KEY,
+                id BIGINT NOT NULL,
+                timestamp TIMESTAMPTZ NOT NULL,
+                gpu_index INTEGER NOT NULL,
+                gpu_name TEXT NOT NULL,
+                pid BIGINT,
+                user_name TEXT,
+                process_name TEXT,
+                memory_used_mb BIGINT NOT NULL,
+                utilization_pct REAL NOT NULL,
+                temperature_c INTEGER NOT NULL,
+                power_w REAL NOT NULL,
+                container TEXT,
+                node_id TEXT,
+                cmdline TEXT
+            )",
+            "CREATE INDEX IF NOT EXISTS gpukill_audit_records_timestamp
+                ON gpukill_audit_records (timestamp)",
+        ],
+        &[
+            "ALTER TABLE gpukill_audit_records ADD COLUMN IF NOT EXISTS hostname TEXT",
+            "CREATE INDEX IF NOT EXISTS gpukill_audit_records_hostname
+                ON gpukill_audit_records (hostname, timestamp)",
+        ],
+        &[
+            "ALTER TABLE gpukill_audit_records ADD COLUMN IF NOT EXISTS ecc_uncorrected BIGINT",
+            "ALTER TABLE gpukill_audit_records ADD COLUMN IF NOT EXISTS ecc_uncorrected_delta BIGINT",
+        ],
+        &[
+            "ALTER TABLE gpukill_audit_records ADD COLUMN IF NOT EXISTS pcie_mb_per_sec REAL",
+            "ALTER TABLE gpukill_audit_records ADD COLUMN IF NOT EXISTS nvlink_bytes BIGINT",
+        ],
+        &[
+            "CREATE INDEX IF NOT EXISTS gpukill_audit_records_gpu
+                ON gpukill_audit_records (gpu_index, timestamp)",
+            "CREATE INDEX IF NOT EXISTS gpukill_audit_records_container
+                ON gpukill_audit_records (container, timestamp)",
+        ],
+        &[
+            "CREATE TABLE IF NOT EXISTS gpukill_cluster_events (
+                seq BIGSERIAL PRIMARY KEY,
+                timestamp TIMESTAMPTZ NOT NULL,
+                kind TEXT NOT NULL,
+                event TEXT NOT NULL
+            )",
+            "CREATE INDEX IF NOT EXISTS gpukill_cluster_events_timestamp
+                ON gpukill_cluster_events (timestamp)",
+        ],
+        &[
+            "CREATE TABLE IF NOT EXISTS gpukill_audit_actions (
+                seq BIGSERIAL PRIMARY KEY,
+                timestamp TIMESTAMPTZ NOT NULL,
+                kind TEXT NOT NULL,
+                target TEXT NOT NULL,
+                invoking_user TEXT NOT NULL,
+                sudo_user TEXT,
+                hostname TEXT NOT NULL,
+                dry_run BOOLEAN NOT NULL,
+                success BOOLEAN NOT NULL,
+                error TEXT
+            )",
+            "CREATE INDEX IF NOT EXISTS gpukill_audit_actions_timestamp
+                ON gpukill_audit_actions (timestamp)",
+        ],
+        &["ALTER TABLE gpukill_audit_records ADD COLUMN IF NOT EXISTS slurm_job_id TEXT"],
+        &["ALTER TABLE gpukill_audit_records ADD COLUMN IF NOT EXISTS start_unix_secs BIGINT"],
+    ];
+
+    const COLUMNS: &str = "id, timestamp, gpu_index, gpu_name, pid, user_name, process_name, \
+        memory_used_mb, utilization_pct, temperature_c, power_w, container, node_id, \
+        hostname, cmdline, ecc_uncorrected, ecc_uncorrected_delta, pcie_mb_per_sec, nvlink_bytes, \
+        slurm_job_id, start_unix_secs";
+
+    /// Filtered, newest-first page of records. Unset filters are bound as NULL and
+    /// folded away by the planner, so the gpu and container predicates can use
+    /// their indexes; a NULL limit means no limit.
+    const QUERY_PAGE: &str = "SELECT id, timestamp, gpu_index, gpu_name, pid, user_name, \
+        process_name, memory_used_mb, utilization_pct, temperature_c, power_w, container, \
+        node_id, hostname, cmdline, ecc_uncorrected, ecc_uncorrected_delta, pcie_mb_per_sec, \
+        nvlink_bytes, slurm_job_id, start_unix_secs \
+        FROM gpukill_audit_records \
+        WHERE timestamp >= $1 AND timestamp <= $2 \
+        AND ($3::timestamptz IS NULL OR timestamp < $3) \
+        AND ($4::text IS NULL OR user_name = $4) \
+        AND ($5::text IS NULL OR strpos(process_name, $5) > 0) \
+        AND ($6::integer IS NULL OR gpu_index = $6) \
+        AND ($7::text IS NULL OR container = $7) \
+        ORDER BY timestamp DESC, seq DESC LIMIT $8 OFFSET $9";
+
+    /// Audit records in a shared PostgreSQL database
+    pub struct PostgresStore {
+        pool: PgPool,
+    }
+
+    impl PostgresStore {
+        pub async fn connect(url: &str) -> Result<Self> {
+            let pool = PgPoolOptions::new()
+                .max_connections(4)
+                .connect(url)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to connect to audit database {}",
+                        super::redact_url(url)
+                    )
+                })?;
+            Ok(Self { pool })
+        }
+    }
+
+    fn record_from_row(row: PgRow) -> Result<AuditRecord> {
+        Ok(AuditRecord {
+            id: row.try_get("id")?,
+            timestamp: row.try_get("timestamp")?,
+            gpu_index: row.try_get::<i32, _>("gpu_index")? as u16,
+            gpu_name: row.try_get("gpu_name")?,
+            pid: row.try_get::<Option<i64>, _>("pid")?.map(|p| p as u32),
+            user: row.try_get("user_name")?,
+            process_name: row.try_get("process_name")?,
+            memory_used_mb: row.try_get::<i64, _>("memory_used_mb")? as u32,
+            utilization_pct: row.try_get("utilization_pct")?,
+            temperature_c: row.try_get("temperature_c")?,
+            power_w: row.try_get("power_w")?,
+            container: row.try_get("container")?,
+            node_id: row.try_get("node_id")?,
+            hostname: row.try_get("hostname")?,
+            cmdline: row.try_get("cmdline")?,
+            ecc_uncorrected: row
+                .try_get::<Option<i64>, _>("ecc_uncorrected")?
+                .map(|c| c as u64),
+            ecc_uncorrected_delta: row
+                .try_get::<Option<i64>, _>("ecc_uncorrected_delta")?
+                .map(|c| c as u64),
+            pcie_mb_per_sec: row.try_get("pcie_mb_per_sec")?,
+            nvlink_bytes: row
+                .try_get::<Option<i64>, _>("nvlink_bytes")?
+                .map(|b| b as u64),
+            slurm_job_id: row.try_get("slurm_job_id")?,
+            start_unix_secs: row
+                .try_get::<Option<i64>, _>("start_unix_secs")?
+                .map(|s| s as u64),
+        })
+    }
+
+    /// Apply pending migrations on one connection. The advisory lock makes nodes
+    /// starting together apply each migration once.
+    async fn apply_migrations(conn: &mut PgConnection) -> Result<()> {
+        let mut tx = conn.begin().await?;
+        sqlx::query("SELECT pg_advisory_xact_lock($1)")
+            .bind(MIGRATION_LOCK_KEY)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("CREATE TABLE IF NOT EXISTS gpukill_audit_schema (version INTEGER NOT NULL)")
+            .execute(&mut *tx)
+            .await?;
+        let version: i32 =
+            sqlx::query_scalar("SELECT COALESCE(MAX(version), 0) FROM gpukill_audit_schema")
+                .fetch_one(&mut *tx)
+                .await?;
+
+        for (index, statements) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+            for statement in *statements {
+                sqlx::query(statement)
+                    .execute(&mut *tx)
+                    .await
+                    .with_context(|| format!("Audit database migration {} failed", index + 1))?;
+            }
+            sqlx::query("INSERT INTO gpukill_audit_schema (version) VALUES ($1)")
+                .bind(index as i32 + 1)
+                .execute(&mut *tx)
+                .await?;
+            tracing::info!("Applied audit database migration {}", index + 1);
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    #[async_trait]
+    impl AuditStore for PostgresStore {
+        fn describe(&self) -> String {
+            "PostgreSQL database".to_string()
+        }
+
+        async fn migrate(&self) -> Result<()> {
+            let mut conn = self.pool.acquire().await?;
+            apply_migrations(&mut conn).await
+        }
+
+        async fn append(&self, records: &[AuditRecord]) -> Result<()> {
+            if records.is_empty() {
+                return Ok(());
+            }
+            // One statement per batch, so concurrent writers never interleave a snapshot
+            let mut query =
+                QueryBuilder::new(format!("INSERT INTO gpukill_audit_records ({}) ", COLUMNS));
+            query.push_values(records, |mut row, record| {
+                row.push_bind(record.id)
+                    .push_bind(record.timestamp)
+                    .push_bind(record.gpu_index as i32)
+                    .push_bind(&record.gpu_name)
+                    .push_bind(record.pid.map(|p| p as i64))
+                    .push_bind(&record.user)
+                    .push_bind(&record.process_name)
+                    .push_bind(record.memory_used_mb as i64)
+                    .push_bind(record.utilization_pct)
+                    .push_bind(record.temperature_c)
+                    .push_bind(record.power_w)
+                    .push_bind(&record.container)
+                    .push_bind(&record.node_id)
+                    .push_bind(&record.hostname)
+                    .push_bind(&record.cmdline)
+                    .push_bind(record.ecc_uncorrected.map(|c| c as i64))
+                    .push_bind(record.ecc_uncorrected_delta.map(|c| c as i64))
+                    .push_bind(record.pcie_mb_per_sec)
+                    .push_bind(record.nvlink_bytes.map(|b| b as i64))
+                    .push_bind(&record.slurm_job_id)
+                    .push_bind(record.start_unix_secs.map(|s| s as i64));
+            });
+            query
+                .build()
+                .execute(&self.pool)
+                .await
+                .context("Failed to write audit records")?;
+            Ok(())
+        }
+
+        fn scan(
+            &self,
+            start: DateTime<Utc>,
+            end: DateTime<Utc>,
+        ) -> BoxStream<'_, Result<AuditRecord>> {
+            sqlx::query(
+                "SELECT id, timestamp, gpu_index, gpu_name, pid, user_name, process_name, \
+                 memory_used_mb, utilization_pct, temperature_c, power_w, container, node_id, \
+                 hostname, cmdline, ecc_uncorrected, ecc_uncorrected_delta, pcie_mb_per_sec, \
+                 nvlink_bytes, slurm_job_id, start_unix_secs \
+                 FROM gpukill_audit_records \
+                 WHERE timestamp >= $1 AND timestamp <= $2 ORDER BY seq",
+            )
+            .bind(start)
+            .bind(end)
+            .fetch(&self.pool)
+            .map(|row| record_from_row(row.context("Failed to read audit records")?))
+            .boxed()
+        }
+
+        fn query<'a>(&'a self, query: &AuditQuery) -> BoxStream<'a, Result<AuditRecord>> {
+            sqlx::query(QUERY_PAGE)
+                .bind(query.start)
+                .bind(query.end)
+                .bind(query.before)
+                .bind(query.user.clone())
+                .bind(query.process.clone())
+                .bind(query.gpu.map(|gpu| gpu as i32))
+                .bind(query.container.clone())
+                .bind(query.limit.map(|limit| limit.min(i64::MAX as usize) as i64))
+                .bind(query.offset.min(i64::MAX as usize) as i64)
+                .fetch(&self.pool)
+                .map(|row| record_from_row(row.context("Failed to read audit records")?))
+                .boxed()
+        }
+
+        async fn delete_before(&self, cutoff: DateTime<Utc>) -> Result<u64> {
+            let result = sqlx::query("DELETE FROM gpukill_audit_records WHERE timestamp < $1")
+                .bind(cutoff)
+                .execute(&self.pool)
+                .await
+                .context("Failed to delete old audit records")?;
+            Ok(result.rows_affected())
+        }
+
+        async fn append_actions(&self, actions: &[ActionRecord]) -> Result<()> {
+            if actions.is_empty() {
+                return Ok(());
+            }
+            let mut query = QueryBuilder::new(
+                "INSERT INTO gpukill_audit_actions (timestamp, kind, target, invoking_user, \
+                 sudo_user, hostname, dry_run, success, error) ",
+            );
+            query.push_values(actions, |mut row, action| {
+                row.push_bind(action.timestamp)
+                    .push_bind(action.kind.to_string())
+                    .push_bind(&action.target)
+                    .push_bind(&action.invoking_user)
+                    .push_bind(&action.sudo_user)
+                    .push_bind(&action.hostname)
+                    .push_bind(action.dry_run)
+                    .push_bind(action.success)
+                    .push_bind(&action.error);
+            });
+            query
+                .build()
+                .execute(&self.pool)
+                .await
+                .context("Failed to write action records")?;
+            Ok(())
+        }
+
+        async fn actions(
+            &self,
+            start: DateTime<Utc>,
+            end: DateTime<Utc>,
+        ) -> Result<Vec<ActionRecord>> {
+            let rows = sqlx::query(
+                "SELECT timestamp, kind, target, invoking_user, sudo_user, hostname, dry_run, \
+                 success, error FROM gpukill_audit_actions \
+                 WHERE timestamp >= $1 AND timestamp <= $2 ORDER BY seq",
+            )
+            .bind(start)
+            .bind(end)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to read action records")?;
+            rows.into_iter()
+                .map(|row| {
+                    let kind: String = row.try_get("kind")?;
+                    Ok(ActionRecord {
+                        timestamp: row.try_get("timestamp")?,
+                        kind: match kind.as_str() {
+                            "reset" => ActionKind::Reset,
+                            "set" => ActionKind::Set,
+                            _ => ActionKind::Kill,
+                        },
+                        target: row.try_get("target")?,
+                        invoking_user: row.try_get("invoking_user")?,
+                        sudo_user: row.try_get("sudo_user")?,
+                        hostname: row.try_get("hostname")?,
+                        dry_run: row.try_get("dry_run")?,
+                        success: row.try_get("success")?,
+                        error: row.try_get("error")?,
+                    })
+                })
+                .collect()
+        }
+
+        async fn append_events(&self, events: &[EventRecord]) -> Result<()> {
+            if events.is_empty() {
+                return Ok(());
+            }
+            let mut query =
+                QueryBuilder::new("INSERT INTO gpukill_cluster_events (timestamp, kind, event) ");
+            query.push_values(events, |mut row, event| {
+                row.push_bind(event.timestamp)
+                    .push_bind(
+                        event
+                            .event
+                            .get("type")
+                            .and_then(|kind| kind.as_str())
+                            .unwrap_or("unknown")
+                            .to_string(),
+                    )
+                    .push_bind(event.event.to_string());
+            });
+            query
+                .build()
+                .execute(&self.pool)
+                .await
+                .context("Failed to write cluster events")?;
+            Ok(())
+        }
+
+        async fn events_since(
+            &self,
+            since: DateTime<Utc>,
+            limit: usize,
+        ) -> Result<Vec<EventRecord>> {
+            let rows = sqlx::query(
+                "SELECT event FROM gpukill_cluster_events WHERE timestamp > $1 \
+                 ORDER BY timestamp, seq LIMIT $2",
+            )
+            .bind(since)
+            .bind(limit.min(i64::MAX as usize) as i64)
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to read cluster events")?;
+            rows.into_iter()
+                .map(|row| {
+                    let event: String = row.try_get("event")?;
+                    EventRecord::from_json(serde_json::from_str(&event)?)
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn record(id: i64, timestamp: DateTime<Utc>) -> AuditRecord {
+        AuditRecord {
+            id,
+            timestamp,
+            gpu_index: 0,
+            gpu_name: "Test GPU".to_string(),
+            pid: Some(1000),
+            user: Some("alice".to_string()),
+            process_name: Some("python".to_string()),
+            memory_used_mb: 1024,
+            utilization_pct: 50.0,
+            temperature_c: 0,
+            power_w: 0.0,
+            container: None,
+            node_id: None,
+            hostname: None,
+            cmdline: None,
+            ecc_uncorrected: None,
+            ecc_uncorrected_delta: None,
+            pcie_mb_per_sec: None,
+            nvlink_bytes: None,
+            slurm_job_id: None,
+            start_unix_secs: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_migrate_adds_hostname_to_local_records() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let store = JsonlStore::new(temp_dir.path().to_path_buf()).unwrap();
+
+        // A record written before the hostname field existed
+        let t = Utc.with_ymd_and_hms(2024, 6, 1, 8, 0, 0).unwrap();
+        let mut legacy = serde_json::to_value(record(1, t)).unwrap();
+        legacy.as_object_mut().unwrap().remove("hostname");
+        let node = AuditRecord {
+            node_id: Some("node-7".to_string()),
+            ..record(2, t)
+        };
+        fs::write(
+            temp_dir.path().join("audit.jsonl"),
+            format!("{}\n{}\n", legacy, serde_json::to_string(&node).unwrap()),
+        )
+        .unwrap();
+
+        assert_eq!(store.migrate_add_hostname("gpu-a").unwrap(), 1);
+        // Running it again finds nothing left to do
+        assert_eq!(store.migrate_add_hostname("gpu-a").unwrap(), 0);
+
+        let records: Vec<AuditRecord> = store
+            .scan(t, t)
+            .map(|r| r.unwrap())
+            .collect::<Vec<_>>()
+            .await;
+        let hosts: Vec<(i64, &str)> = records.iter().map(|r| (r.id, r.host())).collect();
+        assert!(hosts.contains(&(1, "gpu-a")));
+        assert!(hosts.contains(&(2, "node-7")));
+
+        store.migrate().await.unwrap();
+        let version = fs::read_to_string(temp_dir.path().join("audit.version")).unwrap();
+        assert_eq!(version, AUDIT_SCHEMA_VERSION.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_database_url_selection() {
+        let err = open_store(Some("mysql://root:secret@db/gpukill"))
+            .await
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("expected a postgres:// URL"), "{}", err);
+        assert!(!err.contains("secret"), "{}", err);
+
+        #[cfg(not(feature = "postgres"))]
+        {
+            let err = open_store(Some("postgres://gpukill:secret@db/gpukill"))
+                .await
+                .err()
+                .unwrap()
+                .to_string();
+            assert!(err.contains("--features postgres"), "{}", err);
+            assert!(err.contains("gpukill:***@db"), "{}", err);
+        }
+    }
+}
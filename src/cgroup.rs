@@ -0,0 +1,454 @@
+//! Host-side throttling of Guard Mode offenders through a cgroup v2 slice.
+//!
+//! A GPU cannot be throttled per process, so throttling constrains the host side
+//! instead: the CPU time and memory of the process that feeds the GPU. Offenders are
+//! moved into a leaf cgroup per severity level under `<root>/gpukill.slice`, whose
+//! `cpu.max` and `memory.high` hold that level's limits, and moved back to the cgroup
+//! they came from once the violation clears. Every filesystem change is a
+//! [`CgroupOp`], so dry-run output can list exactly what would be written.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where the cgroup v2 unified hierarchy is mounted on most distributions
+pub const DEFAULT_CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Slice that holds the per-level throttle cgroups
+pub const DEFAULT_SLICE: &str = "gpukill.slice";
+
+/// `cpu.max` period; quotas are a percentage of it
+const CPU_PERIOD_US: u64 = 100_000;
+
+/// Controllers the throttle cgroups need
+const CONTROLLERS: [&str; 2] = ["cpu", "memory"];
+
+/// Host-side limits applied to a throttled process
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThrottleLimits {
+    /// CPU time as a percentage of one CPU (`200` = two full CPUs)
+    pub cpu_max_pct: u32,
+    /// Memory above which the kernel reclaims aggressively (`memory.high`); unset = no limit
+    #[serde(default)]
+    pub memory_high_mb: Option<u64>,
+}
+
+impl ThrottleLimits {
+    /// Value for `cpu.max`: quota and period in microseconds
+    pub fn cpu_max(&self) -> String {
+        let quota = CPU_PERIOD_US * self.cpu_max_pct as u64 / 100;
+        format!("{} {}", quota, CPU_PERIOD_US)
+    }
+
+    /// Value for `memory.high` in bytes, or `max` for no limit
+    pub fn memory_high(&self) -> String {
+        self.memory_high_mb
+            .map(|mb| (mb * 1024 * 1024).to_string())
+            .unwrap_or_else(|| "max".to_string())
+    }
+}
+
+/// One change to the cgroup filesystem
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CgroupOp {
+    Create(PathBuf),
+    Write { path: PathBuf, value: String },
+    Remove(PathBuf),
+}
+
+impl fmt::Display for CgroupOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CgroupOp::Create(path) => write!(f, "mkdir {}", path.display()),
+            CgroupOp::Write { path, value } => {
+                write!(f, "write '{}' to {}", value, path.display())
+            }
+            CgroupOp::Remove(path) => write!(f, "rmdir {}", path.display()),
+        }
+    }
+}
+
+/// Plans and applies throttle operations under one cgroup root
+#[derive(Debug, Clone)]
+pub struct CgroupManager {
+    root: PathBuf,
+    slice: String,
+    proc_root: PathBuf,
+}
+
+impl CgroupManager {
+    pub fn new(root: impl Into<PathBuf>, slice: impl Into<String>) -> Self {
+        Self {
+            root: root.into(),
+            slice: slice.into(),
+            proc_root: PathBuf::from("/proc"),
+        }
+    }
+
+    /// Read process cgroup membership from `proc_root` instead of `/proc`
+    #[allow(dead_code)]
+    pub fn with_proc_root(mut self, proc_root: impl Into<PathBuf>) -> Self {
+        self.proc_root = proc_root.into();
+        self
+    }
+
+    pub fn slice_path(&self) -> PathBuf {
+        self.root.join(&self.slice)
+    }
+
+    /// Leaf cgroup holding the processes throttled at `level`
+    pub fn level_path(&self, level: &str) -> PathBuf {
+        self.slice_path().join(level)
+    }
+
+    /// Check that cgroup v2 is mounted with the cpu and memory controllers and that
+    /// this process may move processes between cgroups
+    pub fn check_access(&self) -> Result<()> {
+        if !cfg!(target_os = "linux") {
+            return Err(anyhow::anyhow!(
+                "cgroup throttling is not supported on this platform (Linux with cgroup v2 only)"
+            ));
+        }
+
+        let controllers = fs::read_to_string(self.root.join("cgroup.controllers"))
+            .map_err(|e| {
+                anyhow::anyhow!(
+                    "cgroup v2 is not mounted at {} ({}); throttling needs the unified cgroup hierarchy",
+                    self.root.display(),
+                    e
+                )
+            })?;
+        let available: Vec<&str> = controllers.split_whitespace().collect();
+        for controller in CONTROLLERS {
+            if !available.contains(&controller) {
+                return Err(anyhow::anyhow!(
+                    "cgroup controller '{}' is not available at {}; throttling is not supported without it",
+                    controller,
+                    self.root.display()
+                ));
+            }
+        }
+
+        if !is_writable(&self.root) {
+            return Err(anyhow::anyhow!(
+                "permission denied: throttling moves processes between cgroups and needs root or write access to {}",
+                self.root.display()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Operations that move `pid` into the cgroup for `level` with `limits` applied
+    pub fn plan_throttle(&self, pid: u32, level: &str, limits: &ThrottleLimits) -> Vec<CgroupOp> {
+        let mut ops = Vec::new();
+        let slice = self.slice_path();
+        let leaf = self.level_path(level);
+        let enable = CONTROLLERS
+            .iter()
+            .map(|c| format!("+{}", c))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        if !slice.exists() {
+            ops.push(CgroupOp::Write {
+                path: self.root.join("cgroup.subtree_control"),
+                value: enable.clone(),
+            });
+            ops.push(CgroupOp::Create(slice.clone()));
+            ops.push(CgroupOp::Write {
+                path: slice.join("cgroup.subtree_control"),
+                value: enable,
+            });
+        }
+        if !leaf.exists() {
+            ops.push(CgroupOp::Create(leaf.clone()));
+        }
+        ops.push(CgroupOp::Write {
+            path: leaf.join("cpu.max"),
+            value: limits.cpu_max(),
+        });
+        ops.push(CgroupOp::Write {
+            path: leaf.join("memory.high"),
+            value: limits.memory_high(),
+        });
+        ops.push(CgroupOp::Write {
+            path: leaf.join("cgroup.procs"),
+            value: pid.to_string(),
+        });
+        ops
+    }
+
+    /// Operations that move `pid` back to the cgroup it was in before throttling
+    pub fn plan_release(&self, pid: u32, original: &Path) -> Vec<CgroupOp> {
+        vec![CgroupOp::Write {
+            path: original.join("cgroup.procs"),
+            value: pid.to_string(),
+        }]
+    }
+
+    /// Operations that remove level cgroups with no processes left, and the slice
+    /// itself once it is empty
+    pub fn plan_cleanup(&self) -> Vec<CgroupOp> {
+        let slice = self.slice_path();
+        let Ok(entries) = fs::read_dir(&slice) else {
+            return Vec::new();
+        };
+
+        let mut ops = Vec::new();
+        let mut remaining = 0;
+        let mut leaves: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.is_dir())
+            .collect();
+        leaves.sort();
+        for leaf in leaves {
+            let empty = fs::read_to_string(leaf.join("cgroup.procs"))
+                .map(|procs| procs.trim().is_empty())
+                .unwrap_or(true);
+            if empty {
+                ops.push(CgroupOp::Remove(leaf));
+            } else {
+                remaining += 1;
+            }
+        }
+        if remaining == 0 {
+            ops.push(CgroupOp::Remove(slice));
+        }
+        ops
+    }
+
+    /// Apply operations in order, stopping at the first failure
+    pub fn apply(&self, ops: &[CgroupOp]) -> Result<()> {
+        for op in ops {
+            match op {
+                CgroupOp::Create(path) => fs::create_dir(path),
+                CgroupOp::Write { path, value } => fs::write(path, value),
+                CgroupOp::Remove(path) => fs::remove_dir(path),
+            }
+            .with_context(|| format!("Failed to {}", op))?;
+        }
+        Ok(())
+    }
+
+    /// Cgroup directory `pid` is in now
+    pub fn current_cgroup(&self, pid: u32) -> Result<PathBuf> {
+        let path = self.proc_root.join(pid.to_string()).join("cgroup");
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let relative = parse_cgroup_v2_path(&contents).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Process {} is not in a cgroup v2 hierarchy; throttling is not supported for it",
+                pid
+            )
+        })?;
+        Ok(self.root.join(relative.trim_start_matches('/')))
+    }
+
+    /// Whether `pid` still exists
+    pub fn process_exists(&self, pid: u32) -> bool {
+        self.proc_root.join(pid.to_string()).exists()
+    }
+}
+
+impl Default for CgroupManager {
+    fn default() -> Self {
+        Self::new(DEFAULT_CGROUP_ROOT, DEFAULT_SLICE)
+    }
+}
+
+/// The unified hierarchy path from `/proc/<pid>/cgroup` (the `0::` line)
+pub fn parse_cgroup_v2_path(contents: &str) -> Option<String> {
+    contents
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .map(|path| path.trim().to_string())
+}
+
+#[cfg(unix)]
+fn is_writable(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+    let Ok(path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: `path` is a valid NUL-terminated string for the duration of the call
+    unsafe { libc::access(path.as_ptr(), libc::W_OK) == 0 }
+}
+
+#[cfg(not(unix))]
+fn is_writable(_path: &Path) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake_root() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("cgroup.controllers"),
+            "cpuset cpu io memory pids\n",
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_limit_values() {
+        let limits = ThrottleLimits {
+            cpu_max_pct: 50,
+            memory_high_mb: Some(2048),
+        };
+        assert_eq!(limits.cpu_max(), "50000 100000");
+        assert_eq!(limits.memory_high(), "2147483648");
+
+        let limits = ThrottleLimits {
+            cpu_max_pct: 200,
+            memory_high_mb: None,
+        };
+        assert_eq!(limits.cpu_max(), "200000 100000");
+        assert_eq!(limits.memory_high(), "max");
+    }
+
+    #[test]
+    fn test_parse_cgroup_v2_path() {
+        assert_eq!(
+            parse_cgroup_v2_path("0::/user.slice/user-1000.slice/session-2.scope\n"),
+            Some("/user.slice/user-1000.slice/session-2.scope".to_string())
+        );
+        assert_eq!(
+            parse_cgroup_v2_path("12:cpu,cpuacct:/\n0::/system.slice\n"),
+            Some("/system.slice".to_string())
+        );
+        assert_eq!(parse_cgroup_v2_path("4:memory:/user\n"), None);
+    }
+
+    #[test]
+    fn test_throttle_plan_creates_slice_once() {
+        let dir = fake_root();
+        let manager = CgroupManager::new(dir.path(), DEFAULT_SLICE);
+        let limits = ThrottleLimits {
+            cpu_max_pct: 25,
+            memory_high_mb: None,
+        };
+
+        let ops = manager.plan_throttle(4242, "high", &limits);
+        let slice = dir.path().join("gpukill.slice");
+        let leaf = slice.join("high");
+        assert_eq!(
+            ops,
+            vec![
+                CgroupOp::Write {
+                    path: dir.path().join("cgroup.subtree_control"),
+                    value: "+cpu +memory".to_string(),
+                },
+                CgroupOp::Create(slice.clone()),
+                CgroupOp::Write {
+                    path: slice.join("cgroup.subtree_control"),
+                    value: "+cpu +memory".to_string(),
+                },
+                CgroupOp::Create(leaf.clone()),
+                CgroupOp::Write {
+                    path: leaf.join("cpu.max"),
+                    value: "25000 100000".to_string(),
+                },
+                CgroupOp::Write {
+                    path: leaf.join("memory.high"),
+                    value: "max".to_string(),
+                },
+                CgroupOp::Write {
+                    path: leaf.join("cgroup.procs"),
+                    value: "4242".to_string(),
+                },
+            ]
+        );
+        assert_eq!(ops[1].to_string(), format!("mkdir {}", slice.display()));
+
+        manager.apply(&ops).unwrap();
+        assert_eq!(
+            fs::read_to_string(leaf.join("cgroup.procs")).unwrap(),
+            "4242"
+        );
+
+        // With the slice and level in place only the limits and the move remain
+        let ops = manager.plan_throttle(4243, "high", &limits);
+        assert_eq!(ops.len(), 3);
+        assert!(matches!(&ops[2], CgroupOp::Write { value, .. } if value == "4243"));
+    }
+
+    #[test]
+    fn test_cleanup_removes_only_empty_levels() {
+        let dir = fake_root();
+        let manager = CgroupManager::new(dir.path(), DEFAULT_SLICE);
+        let slice = manager.slice_path();
+        fs::create_dir_all(slice.join("high")).unwrap();
+        fs::create_dir_all(slice.join("medium")).unwrap();
+        fs::write(slice.join("high").join("cgroup.procs"), "4242\n").unwrap();
+        fs::write(slice.join("medium").join("cgroup.procs"), "").unwrap();
+
+        assert_eq!(
+            manager.plan_cleanup(),
+            vec![CgroupOp::Remove(slice.join("medium"))]
+        );
+
+        fs::write(slice.join("high").join("cgroup.procs"), "").unwrap();
+        assert_eq!(
+            manager.plan_cleanup(),
+            vec![
+                CgroupOp::Remove(slice.join("high")),
+                CgroupOp::Remove(slice.join("medium")),
+                CgroupOp::Remove(slice.clone()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_current_cgroup_and_release() {
+        let dir = fake_root();
+        let proc_root = tempfile::tempdir().unwrap();
+        fs::create_dir(proc_root.path().join("4242")).unwrap();
+        fs::write(
+            proc_root.path().join("4242").join("cgroup"),
+            "0::/user.slice/session-2.scope\n",
+        )
+        .unwrap();
+        let manager =
+            CgroupManager::new(dir.path(), DEFAULT_SLICE).with_proc_root(proc_root.path());
+
+        let original = manager.current_cgroup(4242).unwrap();
+        assert_eq!(original, dir.path().join("user.slice/session-2.scope"));
+        assert!(manager.process_exists(4242));
+        assert!(!manager.process_exists(4243));
+        assert!(manager.current_cgroup(4243).is_err());
+        assert_eq!(
+            manager.plan_release(4242, &original),
+            vec![CgroupOp::Write {
+                path: original.join("cgroup.procs"),
+                value: "4242".to_string(),
+            }]
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_check_access_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = CgroupManager::new(dir.path(), DEFAULT_SLICE);
+        let err = manager.check_access().unwrap_err().to_string();
+        assert!(err.contains("cgroup v2 is not mounted"), "{}", err);
+
+        fs::write(dir.path().join("cgroup.controllers"), "cpuset io pids\n").unwrap();
+        let err = manager.check_access().unwrap_err().to_string();
+        assert!(err.contains("controller 'cpu'"), "{}", err);
+        assert_eq!(
+            crate::report::ErrorKind::classify(&err),
+            crate::report::ErrorKind::Unsupported
+        );
+
+        fs::write(dir.path().join("cgroup.controllers"), "cpu memory\n").unwrap();
+        assert!(manager.check_access().is_ok());
+    }
+}
@@ -0,0 +1,408 @@
+//! Thin seam over external tools (`rocm-smi`, `modprobe`, ...) so callers can be
+//! tested without running them.
+//!
+//! Vendor tools run through [`ToolRunner`], which kills a tool that hangs, retries
+//! once and stops running a vendor's tools for a while after repeated failures, so
+//! one wedged `rocm-smi` cannot stall every query.
+
+use anyhow::{Context, Result};
+use std::fmt;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Default time a vendor query tool may run before it is killed
+pub const DEFAULT_TOOL_TIMEOUT_SECS: u64 = 5;
+
+/// Default time a vendor's tools are skipped after its circuit breaker trips
+pub const DEFAULT_TOOL_COOLDOWN_SECS: u64 = 60;
+
+/// Failed runs in a row after which a vendor's tools are skipped
+pub const BREAKER_THRESHOLD: u32 = 2;
+
+/// Time a control command (reset, power limit, clocks) may run; these change the
+/// device and are neither retried nor skipped
+pub const CONTROL_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Captured result of an external command
+#[derive(Debug, Clone, Default)]
+pub struct CommandOutput {
+    pub success: bool,
+    /// Exit code; `None` if the command was killed by a signal
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Runs external commands; tests substitute a runner that returns canned output
+pub trait CommandRunner {
+    fn run(&self, program: &str, args: &[&str]) -> Result<CommandOutput>;
+
+    /// Run a command that changes the device, such as a reset. Runners that retry
+    /// or skip queries run these exactly once.
+    fn run_control(&self, program: &str, args: &[&str]) -> Result<CommandOutput> {
+        self.run(program, args)
+    }
+}
+
+/// Runs commands on the local host
+pub struct SystemCommandRunner;
+
+impl CommandRunner for SystemCommandRunner {
+    fn run(&self, program: &str, args: &[&str]) -> Result<CommandOutput> {
+        let output = Command::new(program)
+            .args(args)
+            .output()
+            .with_context(|| format!("Failed to run {}", program))?;
+        Ok(CommandOutput {
+            success: output.status.success(),
+            code: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        })
+    }
+}
+
+/// A vendor tool that gave no answer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolError {
+    /// Killed after running longer than its timeout
+    TimedOut { program: String, timeout: Duration },
+    /// Not run because the vendor's tools failed repeatedly
+    Skipped { program: String, retry_in: Duration },
+}
+
+impl fmt::Display for ToolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ToolError::TimedOut { program, timeout } => {
+                write!(f, "{} timed out after {}s", program, timeout.as_secs_f64())
+            }
+            ToolError::Skipped { program, retry_in } => write!(
+                f,
+                "{} skipped after repeated failures; retrying in {}s",
+                program,
+                retry_in.as_secs().max(1)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ToolError {}
+
+/// The [`ToolError`] behind `error`, if a vendor tool hung or was skipped
+pub fn tool_error(error: &anyhow::Error) -> Option<&ToolError> {
+    error.chain().find_map(|e| e.downcast_ref::<ToolError>())
+}
+
+/// Timeout and breaker cooldown for vendor tools
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ToolLimits {
+    pub timeout: Duration,
+    pub cooldown: Duration,
+}
+
+impl Default for ToolLimits {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(DEFAULT_TOOL_TIMEOUT_SECS),
+            cooldown: Duration::from_secs(DEFAULT_TOOL_COOLDOWN_SECS),
+        }
+    }
+}
+
+/// Process-wide tool limits, set once from the configuration
+static TOOL_LIMITS: OnceLock<ToolLimits> = OnceLock::new();
+
+/// Set the limits vendors created from now on use. Only the first call has an effect.
+pub fn init_tool_limits(limits: ToolLimits) {
+    let _ = TOOL_LIMITS.set(limits);
+}
+
+/// Limits from [`init_tool_limits`], or the defaults
+pub fn tool_limits() -> ToolLimits {
+    TOOL_LIMITS.get().copied().unwrap_or_default()
+}
+
+/// Run `program`, killing it if it has not exited after `timeout`
+pub fn run_with_timeout(program: &str, args: &[&str], timeout: Duration) -> Result<CommandOutput> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", program))?;
+
+    // Drain both pipes while waiting so a chatty tool cannot block on a full pipe
+    let drain = |pipe: Option<Box<dyn Read + Send>>| {
+        std::thread::spawn(move || {
+            let mut buffer = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buffer);
+            }
+            String::from_utf8_lossy(&buffer).into_owned()
+        })
+    };
+    let stdout = drain(
+        child
+            .stdout
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>),
+    );
+    let stderr = drain(
+        child
+            .stderr
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>),
+    );
+
+    let deadline = Instant::now() + timeout;
+    let mut poll = Duration::from_millis(1);
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(ToolError::TimedOut {
+                program: program.to_string(),
+                timeout,
+            }
+            .into());
+        }
+        std::thread::sleep(poll.min(deadline - now));
+        poll = (poll * 2).min(Duration::from_millis(50));
+    };
+
+    Ok(CommandOutput {
+        success: status.success(),
+        code: status.code(),
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/// Whether a failed run is worth retrying: the tool hung or could not be started
+/// for a reason other than not being installed or not being executable
+fn is_transient(error: &anyhow::Error) -> bool {
+    if tool_error(error).is_some() {
+        return true;
+    }
+    !error.chain().any(|e| {
+        e.downcast_ref::<std::io::Error>().is_some_and(|e| {
+            matches!(
+                e.kind(),
+                std::io::ErrorKind::NotFound | std::io::ErrorKind::PermissionDenied
+            )
+        })
+    })
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    failures: u32,
+    open_until: Option<Instant>,
+}
+
+/// Skips a vendor's tools for `cooldown` after `threshold` failed runs in a row.
+/// Once the cooldown is over one run is let through; it closes the breaker if it
+/// succeeds and opens it again if it fails.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            cooldown,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    /// Time left before tools may run again, or None if they may run now
+    pub fn blocked_for(&self, now: Instant) -> Option<Duration> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state
+            .open_until
+            .filter(|until| *until > now)
+            .map(|until| until - now)
+    }
+
+    pub fn record_success(&self) {
+        *self.state.lock().unwrap_or_else(|e| e.into_inner()) = BreakerState::default();
+    }
+
+    /// Count a failed run; returns whether this opened the breaker
+    pub fn record_failure(&self, now: Instant) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.failures += 1;
+        if state.failures < self.threshold {
+            return false;
+        }
+        state.open_until = Some(now + self.cooldown);
+        true
+    }
+}
+
+/// Runs one vendor's tools with a timeout, one retry after a transient failure and a
+/// [`CircuitBreaker`] shared by all of the vendor's queries
+pub struct ToolRunner {
+    timeout: Duration,
+    breaker: CircuitBreaker,
+}
+
+impl ToolRunner {
+    pub fn new(limits: ToolLimits) -> Self {
+        Self {
+            timeout: limits.timeout,
+            breaker: CircuitBreaker::new(BREAKER_THRESHOLD, limits.cooldown),
+        }
+    }
+}
+
+impl CommandRunner for ToolRunner {
+    fn run(&self, program: &str, args: &[&str]) -> Result<CommandOutput> {
+        if let Some(retry_in) = self.breaker.blocked_for(Instant::now()) {
+            return Err(ToolError::Skipped {
+                program: program.to_string(),
+                retry_in,
+            }
+            .into());
+        }
+
+        let mut result = run_with_timeout(program, args, self.timeout);
+        if let Err(e) = &result {
+            if is_transient(e) {
+                tracing::debug!("Retrying {}: {:#}", program, e);
+                result = run_with_timeout(program, args, self.timeout);
+            }
+        }
+        match &result {
+            Ok(_) => self.breaker.record_success(),
+            Err(e) if is_transient(e) => {
+                if self.breaker.record_failure(Instant::now()) {
+                    tracing::warn!(
+                        "{} keeps failing ({:#}); skipping it for {}s",
+                        program,
+                        e,
+                        self.breaker.cooldown.as_secs()
+                    );
+                }
+            }
+            Err(_) => {}
+        }
+        result
+    }
+
+    fn run_control(&self, program: &str, args: &[&str]) -> Result<CommandOutput> {
+        run_with_timeout(program, args, CONTROL_TIMEOUT.max(self.timeout))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_circuit_breaker_opens_after_threshold_and_half_opens() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(30));
+        let start = Instant::now();
+
+        assert!(!breaker.record_failure(start));
+        assert_eq!(breaker.blocked_for(start), None);
+        assert!(breaker.record_failure(start));
+        assert_eq!(
+            breaker.blocked_for(start + Duration::from_secs(10)),
+            Some(Duration::from_secs(20))
+        );
+
+        // After the cooldown one run goes through; another failure reopens at once
+        let later = start + Duration::from_secs(30);
+        assert_eq!(breaker.blocked_for(later), None);
+        assert!(breaker.record_failure(later));
+        assert!(breaker.blocked_for(later).is_some());
+
+        // A success closes it and starts the count over
+        breaker.record_success();
+        assert_eq!(breaker.blocked_for(later), None);
+        assert!(!breaker.record_failure(later));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_with_timeout_kills_a_hung_child() {
+        let started = Instant::now();
+        let error = run_with_timeout("sleep", &["10"], Duration::from_millis(100)).unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(
+            tool_error(&error),
+            Some(&ToolError::TimedOut {
+                program: "sleep".to_string(),
+                timeout: Duration::from_millis(100),
+            })
+        );
+
+        let output = run_with_timeout(
+            "sh",
+            &["-c", "echo out; echo err >&2; exit 3"],
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        assert!(!output.success);
+        assert_eq!(output.code, Some(3));
+        assert_eq!(output.stdout, "out\n");
+        assert_eq!(output.stderr, "err\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_tool_runner_retries_then_skips_a_hung_tool() {
+        let runner = ToolRunner::new(ToolLimits {
+            timeout: Duration::from_millis(50),
+            cooldown: Duration::from_secs(60),
+        });
+
+        // Each run is tried twice; the second failed run trips the breaker
+        for _ in 0..BREAKER_THRESHOLD {
+            let started = Instant::now();
+            let error = runner.run("sleep", &["10"]).unwrap_err();
+            assert!(matches!(
+                tool_error(&error),
+                Some(ToolError::TimedOut { .. })
+            ));
+            assert!(started.elapsed() >= Duration::from_millis(100));
+        }
+
+        let started = Instant::now();
+        let error = runner.run("sleep", &["10"]).unwrap_err();
+        assert!(matches!(
+            tool_error(&error),
+            Some(ToolError::Skipped { .. })
+        ));
+        assert!(started.elapsed() < Duration::from_millis(50));
+        assert!(error
+            .to_string()
+            .contains("sleep skipped after repeated failures"));
+
+        // Control commands still run
+        assert!(runner.run_control("true", &[]).unwrap().success);
+    }
+
+    #[test]
+    fn test_tool_runner_does_not_retry_missing_tools() {
+        let runner = ToolRunner::new(ToolLimits::default());
+        for _ in 0..BREAKER_THRESHOLD + 1 {
+            let error = runner.run("gpukill-no-such-tool", &[]).unwrap_err();
+            assert!(tool_error(&error).is_none());
+        }
+        assert_eq!(runner.breaker.blocked_for(Instant::now()), None);
+    }
+}
@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
 
@@ -9,12 +10,24 @@ pub struct Config {
     /// Default log level
     pub log_level: String,
 
+    /// Log output format: "human" or "json"
+    #[serde(default = "default_log_format")]
+    pub log_format: String,
+
     /// Default output format
     pub output_format: String,
 
     /// Default timeout for process termination
     pub default_timeout_secs: u16,
 
+    /// Escalate to SIGKILL after the timeout by default (as with --force)
+    #[serde(default)]
+    pub force: bool,
+
+    /// Default GPU vendor filter: nvidia, amd, intel, apple or all (as with --vendor)
+    #[serde(default)]
+    pub vendor: Option<String>,
+
     /// Whether to show detailed process information by default
     pub show_details: bool,
 
@@ -29,23 +42,174 @@ pub struct Config {
 
     /// Whether to use colors in output
     pub use_colors: bool,
+
+    /// Seconds a single GPU may take to answer a snapshot query before it is skipped
+    #[serde(default = "default_snapshot_timeout_secs")]
+    pub snapshot_timeout_secs: u64,
+
+    /// Milliseconds GPU snapshots and process lists are reused before the vendors are
+    /// queried again; 0 disables the cache
+    #[serde(default = "default_snapshot_cache_ms")]
+    pub snapshot_cache_ms: u64,
+
+    /// Seconds a vendor tool such as rocm-smi may run before it is killed
+    #[serde(default = "default_tool_timeout_secs")]
+    pub tool_timeout_secs: u64,
+
+    /// Seconds a vendor's tools are skipped after failing repeatedly
+    #[serde(default = "default_tool_cooldown_secs")]
+    pub tool_cooldown_secs: u64,
+
+    /// Characters of a process command line shown in tables (see --full-cmd)
+    #[serde(default = "default_cmdline_width")]
+    pub cmdline_width: usize,
+
+    /// Process names batch kills skip in addition to the built-in protection list
+    #[serde(default)]
+    pub protected_processes: Vec<String>,
+
+    /// Per-host SSH overrides for remote operations, keyed by host name
+    #[serde(default)]
+    pub remote_hosts: BTreeMap<String, RemoteHostConfig>,
+
+    /// Where audit records are stored
+    #[serde(default)]
+    pub audit: AuditConfig,
+
+    /// Tags this node reports when registering with a coordinator (see --node-tag)
+    #[serde(default)]
+    pub node_tags: BTreeMap<String, String>,
+}
+
+/// Audit storage settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditConfig {
+    /// Shared database for audit records, e.g. `postgres://gpukill@db/gpukill`
+    /// (needs the `postgres` feature). Unset keeps records in a local JSON Lines log.
+    pub database_url: Option<String>,
+
+    /// Record each `--list` snapshot in the audit log (see --no-audit)
+    #[serde(default = "default_log_list")]
+    pub log_list: bool,
+
+    /// Seconds a long-running writer (the coordinator) may hold audit records in
+    /// memory so several snapshots are written together; 0 writes every snapshot
+    #[serde(default = "default_audit_flush_interval_secs")]
+    pub flush_interval_secs: u64,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            database_url: None,
+            log_list: default_log_list(),
+            flush_interval_secs: default_audit_flush_interval_secs(),
+        }
+    }
+}
+
+/// SSH settings for a single remote host; unset fields fall back to the CLI flags
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RemoteHostConfig {
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub key: Option<String>,
+    pub timeout_secs: Option<u16>,
+    /// Jump host chain, as for --ssh-jump
+    pub jump: Option<String>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             log_level: "info".to_string(),
+            log_format: default_log_format(),
             output_format: "table".to_string(),
             default_timeout_secs: 5,
+            force: false,
+            vendor: None,
             show_details: false,
             watch_interval_secs: 2,
             max_processes_summary: 10,
             table_width: 120,
             use_colors: true,
+            snapshot_timeout_secs: default_snapshot_timeout_secs(),
+            snapshot_cache_ms: default_snapshot_cache_ms(),
+            tool_timeout_secs: default_tool_timeout_secs(),
+            tool_cooldown_secs: default_tool_cooldown_secs(),
+            cmdline_width: default_cmdline_width(),
+            protected_processes: Vec::new(),
+            remote_hosts: BTreeMap::new(),
+            audit: AuditConfig::default(),
+            node_tags: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Add a name to the protected process list; false if it was already listed
+    pub fn protect_process(&mut self, name: &str) -> bool {
+        if self.protected_processes.iter().any(|p| p == name) {
+            return false;
         }
+        self.protected_processes.push(name.to_string());
+        true
+    }
+
+    /// Node tags from the config with `overrides` (from --node-tag) applied on top
+    pub fn node_tags_with(
+        &self,
+        overrides: &[(String, String)],
+    ) -> std::collections::HashMap<String, String> {
+        let mut tags: std::collections::HashMap<String, String> = self
+            .node_tags
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        tags.extend(overrides.iter().cloned());
+        tags
+    }
+
+    /// Remove a name from the protected process list; false if it was not listed
+    pub fn unprotect_process(&mut self, name: &str) -> bool {
+        let before = self.protected_processes.len();
+        self.protected_processes.retain(|p| p != name);
+        self.protected_processes.len() != before
     }
 }
 
+fn default_log_format() -> String {
+    "human".to_string()
+}
+
+fn default_log_list() -> bool {
+    true
+}
+
+fn default_audit_flush_interval_secs() -> u64 {
+    10
+}
+
+fn default_snapshot_timeout_secs() -> u64 {
+    crate::vendor::DEFAULT_SNAPSHOT_TIMEOUT_SECS
+}
+
+fn default_snapshot_cache_ms() -> u64 {
+    crate::vendor::DEFAULT_SNAPSHOT_CACHE_MS
+}
+
+fn default_tool_timeout_secs() -> u64 {
+    crate::command::DEFAULT_TOOL_TIMEOUT_SECS
+}
+
+fn default_tool_cooldown_secs() -> u64 {
+    crate::command::DEFAULT_TOOL_COOLDOWN_SECS
+}
+
+fn default_cmdline_width() -> usize {
+    crate::render::DEFAULT_CMDLINE_WIDTH
+}
+
 /// Configuration manager
 pub struct ConfigManager {
     config: Config,
@@ -75,7 +239,7 @@ impl ConfigManager {
         let content = fs::read_to_string(config_path)
             .with_context(|| format!("Failed to read config file: {:?}", config_path))?;
 
-        let config: Config = toml::from_str(&content)
+        let config: Config = crate::env_expand::from_toml_str(&content)
             .with_context(|| format!("Failed to parse config file: {:?}", config_path))?;
 
         tracing::info!("Loaded configuration from {:?}", config_path);
@@ -102,8 +266,8 @@ impl ConfigManager {
     /// Save configuration to file
     pub fn save_to_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
         let config_path = path.as_ref();
-        let content =
-            toml::to_string_pretty(&self.config).context("Failed to serialize configuration")?;
+        let content = crate::env_expand::to_toml_string_for(&self.config, config_path)
+            .context("Failed to serialize configuration")?;
 
         fs::write(config_path, content)
             .with_context(|| format!("Failed to write config file: {:?}", config_path))?;
@@ -149,6 +313,10 @@ fn apply_env_overrides(config: &mut Config) {
         config.log_level = log_level;
     }
 
+    if let Ok(log_format) = std::env::var("GPUKILL_LOG_FORMAT") {
+        config.log_format = log_format;
+    }
+
     if let Ok(output_format) = std::env::var("GPUKILL_OUTPUT_FORMAT") {
         config.output_format = output_format;
     }
@@ -159,6 +327,14 @@ fn apply_env_overrides(config: &mut Config) {
         }
     }
 
+    if let Ok(force) = std::env::var("GPUKILL_FORCE") {
+        config.force = force.parse().unwrap_or(false);
+    }
+
+    if let Ok(vendor) = std::env::var("GPUKILL_VENDOR") {
+        config.vendor = Some(vendor);
+    }
+
     if let Ok(show_details) = std::env::var("GPUKILL_SHOW_DETAILS") {
         config.show_details = show_details.parse().unwrap_or(false);
     }
@@ -178,6 +354,64 @@ fn apply_env_overrides(config: &mut Config) {
     if let Ok(use_colors) = std::env::var("GPUKILL_USE_COLORS") {
         config.use_colors = use_colors.parse().unwrap_or(true);
     }
+
+    if let Ok(snapshot_timeout) = std::env::var("GPUKILL_SNAPSHOT_TIMEOUT") {
+        if let Ok(timeout_secs) = snapshot_timeout.parse::<u64>() {
+            config.snapshot_timeout_secs = timeout_secs;
+        }
+    }
+
+    if let Ok(snapshot_cache) = std::env::var("GPUKILL_SNAPSHOT_CACHE_MS") {
+        if let Ok(cache_ms) = snapshot_cache.parse::<u64>() {
+            config.snapshot_cache_ms = cache_ms;
+        }
+    }
+
+    if let Ok(tool_timeout) = std::env::var("GPUKILL_TOOL_TIMEOUT") {
+        if let Ok(timeout_secs) = tool_timeout.parse::<u64>() {
+            config.tool_timeout_secs = timeout_secs;
+        }
+    }
+
+    if let Ok(database_url) = std::env::var("GPUKILL_AUDIT_DATABASE_URL") {
+        config.audit.database_url = Some(database_url);
+    }
+
+    if let Ok(log_list) = std::env::var("GPUKILL_AUDIT_LOG_LIST") {
+        config.audit.log_list = log_list.parse().unwrap_or(true);
+    }
+
+    if let Ok(cmdline_width) = std::env::var("GPUKILL_CMDLINE_WIDTH") {
+        if let Ok(width) = cmdline_width.parse::<usize>() {
+            config.cmdline_width = width;
+        }
+    }
+}
+
+/// Change the config file at `config_path` (the default location when None) and save
+/// it, returning the path written. A missing file starts from the defaults, and
+/// environment overrides are not written back.
+pub fn edit_config_file<T>(
+    config_path: Option<&str>,
+    edit: impl FnOnce(&mut Config) -> T,
+) -> Result<(std::path::PathBuf, T)> {
+    let path = match config_path {
+        Some(path) => std::path::PathBuf::from(path),
+        None => ConfigManager::default_config_path()?,
+    };
+    let mut manager = if path.exists() {
+        ConfigManager::load_from_file(&path)?
+    } else {
+        ConfigManager::new()
+    };
+    let result = edit(manager.config_mut());
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create config directory: {:?}", parent))?;
+    }
+    manager.save_to_file(&path)?;
+    Ok((path, result))
 }
 
 /// Get configuration with fallback chain
@@ -219,6 +453,23 @@ mod tests {
         assert_eq!(config.output_format, deserialized.output_format);
     }
 
+    #[test]
+    fn test_node_tags_cli_overrides_config() {
+        let mut config = Config::default();
+        config
+            .node_tags
+            .insert("rack".to_string(), "a1".to_string());
+        config
+            .node_tags
+            .insert("team".to_string(), "infra".to_string());
+        let config: Config = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+
+        let tags = config.node_tags_with(&[("team".to_string(), "nlp".to_string())]);
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags["rack"], "a1");
+        assert_eq!(tags["team"], "nlp");
+    }
+
     #[test]
     fn test_config_file_loading() {
         let config = Config::default();
@@ -260,6 +511,39 @@ mod tests {
         std::env::remove_var("GPUKILL_WATCH_INTERVAL");
     }
 
+    #[test]
+    fn test_remote_host_overrides() {
+        let toml_str = r#"
+log_level = "info"
+output_format = "table"
+default_timeout_secs = 5
+show_details = false
+watch_interval_secs = 2
+max_processes_summary = 10
+table_width = 120
+use_colors = true
+
+[remote_hosts."gpu-07"]
+user = "ops"
+port = 2222
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let host = config.remote_hosts.get("gpu-07").unwrap();
+        assert_eq!(host.user.as_deref(), Some("ops"));
+        assert_eq!(host.port, Some(2222));
+        assert!(host.key.is_none());
+
+        // Older config files without the section still load
+        let legacy = toml_str.split("[remote_hosts").next().unwrap();
+        let config: Config = toml::from_str(legacy).unwrap();
+        assert!(config.remote_hosts.is_empty());
+        assert_eq!(config.snapshot_timeout_secs, 10);
+        assert_eq!(config.snapshot_cache_ms, 500);
+        assert_eq!(config.tool_timeout_secs, 5);
+        assert_eq!(config.tool_cooldown_secs, 60);
+        assert_eq!(config.cmdline_width, 40);
+    }
+
     #[test]
     fn test_load_from_file_non_existent_returns_error() {
         let result = ConfigManager::load_from_file("non_existent_at_all.toml");
@@ -277,4 +561,72 @@ mod tests {
             "get_config should return Err for explicit non-existent path"
         );
     }
+
+    #[test]
+    fn test_config_file_expands_environment_variables() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let content = toml::to_string_pretty(&Config::default()).unwrap().replace(
+            "log_level = \"info\"",
+            "log_level = \"${GPUKILL_TEST_CONFIG_LEVEL}\"",
+        );
+        std::fs::write(&path, content).unwrap();
+
+        std::env::remove_var("GPUKILL_TEST_CONFIG_LEVEL");
+        let err = ConfigManager::load_from_file(&path).err().unwrap();
+        assert!(format!("{:#}", err).contains("GPUKILL_TEST_CONFIG_LEVEL is not set"));
+
+        std::env::set_var("GPUKILL_TEST_CONFIG_LEVEL", "debug");
+        let loaded = ConfigManager::load_from_file(&path).unwrap();
+        assert_eq!(loaded.config().log_level, "debug");
+
+        // Editing the file keeps the reference instead of writing the value
+        edit_config_file(path.to_str(), |c| c.protect_process("slurmd")).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(saved.contains("log_level = \"${GPUKILL_TEST_CONFIG_LEVEL}\""));
+        assert!(saved.contains("slurmd"));
+    }
+
+    #[test]
+    fn test_edit_config_file_protected_processes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gpukill").join("config.toml");
+        let path_str = path.to_str().unwrap();
+
+        // A missing file starts from the defaults
+        let (written, added) =
+            edit_config_file(Some(path_str), |c| c.protect_process("slurmd")).unwrap();
+        assert_eq!(written, path);
+        assert!(added);
+        let (_, added) = edit_config_file(Some(path_str), |c| c.protect_process("slurmd")).unwrap();
+        assert!(!added);
+
+        std::fs::write(
+            &path,
+            std::fs::read_to_string(&path)
+                .unwrap()
+                .replace("default_timeout_secs = 5", "default_timeout_secs = 9"),
+        )
+        .unwrap();
+        edit_config_file(Some(path_str), |c| c.protect_process("vllm-router")).unwrap();
+        let loaded = ConfigManager::load_from_file(&path).unwrap();
+        assert_eq!(
+            loaded.config().protected_processes,
+            vec!["slurmd".to_string(), "vllm-router".to_string()]
+        );
+        // Other settings in the file are kept
+        assert_eq!(loaded.config().default_timeout_secs, 9);
+
+        let (_, removed) =
+            edit_config_file(Some(path_str), |c| c.unprotect_process("slurmd")).unwrap();
+        assert!(removed);
+        let (_, removed) =
+            edit_config_file(Some(path_str), |c| c.unprotect_process("slurmd")).unwrap();
+        assert!(!removed);
+        let loaded = ConfigManager::load_from_file(&path).unwrap();
+        assert_eq!(
+            loaded.config().protected_processes,
+            vec!["vllm-router".to_string()]
+        );
+    }
 }
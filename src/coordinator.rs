@@ -1,7 +1,14 @@
-use crate::nvml_api::{GpuProc, GpuSnapshot};
-use anyhow::Result;
+use crate::audit_store::{AuditStore, EventRecord};
+use crate::drain::NodeDrain;
+use crate::grafana::MetricHistory;
+use crate::guard_mode::{GuardModeConfig, GuardModeManager, ViolationSeverity, ViolationType};
+use crate::nvml_api::{DeviceError, GpuProc, GpuSnapshot};
+use crate::rogue_detection::{
+    CryptoMiner, DataExfiltrator, ResourceAbuser, RogueDetectionResult, SuspiciousProcess,
+};
+use anyhow::{Context, Result};
 use axum::{
-    extract::{Path, State, WebSocketUpgrade},
+    extract::{Path, Query, State, WebSocketUpgrade},
     http::StatusCode,
     response::Json,
     routing::{get, post},
@@ -10,7 +17,8 @@ use axum::{
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    path::PathBuf,
     sync::Arc,
     time::Duration,
 };
@@ -27,7 +35,31 @@ pub struct NodeInfo {
     pub status: NodeStatus,
     pub gpu_count: u32,
     pub total_memory_gb: f32,
+    /// Labels such as `rack=a1` or `team=nlp`, set with `--node-tag` on the agent
+    #[serde(default)]
     pub tags: HashMap<String, String>,
+    /// Set while the node is drained for maintenance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drain: Option<NodeDrain>,
+}
+
+/// A node as listed by `GET /api/nodes`, with how long ago it last reported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeListing {
+    #[serde(flatten)]
+    pub node: NodeInfo,
+    /// Seconds since the node last registered or sent a snapshot
+    pub last_seen_secs: u64,
+}
+
+impl NodeListing {
+    pub fn new(node: NodeInfo, now: DateTime<Utc>) -> Self {
+        let last_seen_secs = (now - node.last_seen).num_seconds().max(0) as u64;
+        Self {
+            node,
+            last_seen_secs,
+        }
+    }
 }
 
 /// Node status
@@ -36,6 +68,10 @@ pub enum NodeStatus {
     Online,
     Offline,
     Degraded,
+    /// Drained for maintenance: its GPUs no longer count as available
+    Draining {
+        deadline: Option<DateTime<Utc>>,
+    },
 }
 
 /// Cluster snapshot combining all nodes
@@ -49,6 +85,39 @@ pub struct ClusterSnapshot {
     pub utilization_avg: f32,
 }
 
+impl ClusterSnapshot {
+    /// Cluster snapshot of `nodes`, with the totals computed from them
+    pub fn from_nodes(timestamp: DateTime<Utc>, nodes: Vec<NodeSnapshot>) -> Self {
+        let gpus = || nodes.iter().flat_map(|n| n.gpus.iter());
+        let gpu_count = gpus().count();
+        let utilization_avg = if gpu_count > 0 {
+            gpus().map(|gpu| gpu.util_pct).sum::<f32>() / gpu_count as f32
+        } else {
+            0.0
+        };
+
+        Self {
+            timestamp,
+            total_gpus: gpu_count as u32,
+            total_memory_gb: gpus().map(|gpu| gpu.mem_total_mb as f32 / 1024.0).sum(),
+            active_processes: nodes.iter().map(|n| n.processes.len() as u32).sum(),
+            utilization_avg,
+            nodes,
+        }
+    }
+
+    /// Only the nodes whose tags match `selector`, with the totals recomputed
+    pub fn select(&self, selector: &LabelSelector) -> Self {
+        let nodes = self
+            .nodes
+            .iter()
+            .filter(|n| selector.matches(&n.tags))
+            .cloned()
+            .collect();
+        Self::from_nodes(self.timestamp, nodes)
+    }
+}
+
 /// Node snapshot with GPU and process data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NodeSnapshot {
@@ -58,6 +127,210 @@ pub struct NodeSnapshot {
     pub gpus: Vec<GpuSnapshot>,
     pub processes: Vec<GpuProc>,
     pub status: NodeStatus,
+    /// Tags of the node, filled in from its registration by the coordinator
+    #[serde(default)]
+    pub tags: HashMap<String, String>,
+    /// GPUs of the node that could not be queried
+    #[serde(default)]
+    pub errors: Vec<DeviceError>,
+}
+
+impl NodeSnapshot {
+    /// Snapshot of the local GPUs for the coordinator. A node whose devices did
+    /// not all answer reports itself `Degraded`, since its listing may be partial.
+    pub async fn collect(
+        gpu_manager: &crate::vendor::GpuManager,
+        node_id: String,
+        hostname: String,
+        tags: HashMap<String, String>,
+    ) -> Result<Self> {
+        let gpus = gpu_manager.collect_snapshots_async().await?;
+        let processes = gpu_manager.collect_processes_async().await?;
+        let degraded = gpus.is_degraded() || !processes.unavailable.is_empty();
+
+        Ok(Self {
+            node_id,
+            hostname,
+            timestamp: Utc::now(),
+            gpus: gpus.snapshots,
+            processes: processes.processes,
+            status: if degraded {
+                NodeStatus::Degraded
+            } else {
+                NodeStatus::Online
+            },
+            tags,
+            errors: gpus.errors,
+        })
+    }
+}
+
+/// Largest snapshot upload accepted after decompression
+pub const MAX_SNAPSHOT_UPLOAD_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Changes between two snapshots of the same node, sent by agents running with
+/// `--snapshot-delta` instead of the full snapshot. GPUs are keyed by index;
+/// fields that did not change are left out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotDelta {
+    /// Timestamp of the snapshot the changes apply to
+    pub base_timestamp: DateTime<Utc>,
+    pub timestamp: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changed_gpus: Vec<GpuSnapshot>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_gpus: Vec<u16>,
+    /// The complete process list, when any process changed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub processes: Option<Vec<GpuProc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<NodeStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<HashMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub errors: Option<Vec<DeviceError>>,
+}
+
+/// Whether two values serialize identically
+fn same_json<T: Serialize>(a: &T, b: &T) -> bool {
+    serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+impl SnapshotDelta {
+    /// The changes that turn `previous` into `current`
+    pub fn between(previous: &NodeSnapshot, current: &NodeSnapshot) -> Self {
+        let changed_gpus = current
+            .gpus
+            .iter()
+            .filter(|gpu| {
+                !previous
+                    .gpus
+                    .iter()
+                    .any(|old| old.gpu_index == gpu.gpu_index && same_json(old, *gpu))
+            })
+            .cloned()
+            .collect();
+        let removed_gpus = previous
+            .gpus
+            .iter()
+            .map(|gpu| gpu.gpu_index)
+            .filter(|index| !current.gpus.iter().any(|gpu| gpu.gpu_index == *index))
+            .collect();
+
+        Self {
+            base_timestamp: previous.timestamp,
+            timestamp: current.timestamp,
+            changed_gpus,
+            removed_gpus,
+            processes: (!same_json(&previous.processes, &current.processes))
+                .then(|| current.processes.clone()),
+            status: (!same_json(&previous.status, &current.status)).then(|| current.status.clone()),
+            tags: (previous.tags != current.tags).then(|| current.tags.clone()),
+            errors: (previous.errors != current.errors).then(|| current.errors.clone()),
+        }
+    }
+
+    /// Whether nothing but the timestamp changed
+    pub fn is_empty(&self) -> bool {
+        self.changed_gpus.is_empty()
+            && self.removed_gpus.is_empty()
+            && self.processes.is_none()
+            && self.status.is_none()
+            && self.tags.is_none()
+            && self.errors.is_none()
+    }
+
+    /// Apply the changes to `base`, which must be the snapshot they were computed
+    /// against
+    pub fn apply(self, base: &NodeSnapshot) -> Result<NodeSnapshot> {
+        if base.timestamp != self.base_timestamp {
+            return Err(anyhow::anyhow!(
+                "Snapshot delta is based on {}, but the coordinator holds {}",
+                self.base_timestamp.to_rfc3339(),
+                base.timestamp.to_rfc3339()
+            ));
+        }
+
+        let mut gpus: Vec<GpuSnapshot> = base
+            .gpus
+            .iter()
+            .filter(|gpu| !self.removed_gpus.contains(&gpu.gpu_index))
+            .cloned()
+            .collect();
+        for gpu in self.changed_gpus {
+            match gpus.iter_mut().find(|old| old.gpu_index == gpu.gpu_index) {
+                Some(old) => *old = gpu,
+                None => gpus.push(gpu),
+            }
+        }
+        gpus.sort_by_key(|gpu| gpu.gpu_index);
+
+        Ok(NodeSnapshot {
+            node_id: base.node_id.clone(),
+            hostname: base.hostname.clone(),
+            timestamp: self.timestamp,
+            gpus,
+            processes: self.processes.unwrap_or_else(|| base.processes.clone()),
+            status: self.status.unwrap_or_else(|| base.status.clone()),
+            tags: self.tags.unwrap_or_else(|| base.tags.clone()),
+            errors: self.errors.unwrap_or_else(|| base.errors.clone()),
+        })
+    }
+}
+
+/// Body of `POST /api/nodes/:id/snapshot`: a full snapshot, or the changes since
+/// the node's previous upload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SnapshotUpload {
+    Delta { delta: SnapshotDelta },
+    Full(NodeSnapshot),
+}
+
+impl SnapshotUpload {
+    /// `current` as a delta against `previous` when given, otherwise in full
+    pub fn new(current: &NodeSnapshot, previous: Option<&NodeSnapshot>) -> Self {
+        match previous {
+            Some(previous) => SnapshotUpload::Delta {
+                delta: SnapshotDelta::between(previous, current),
+            },
+            None => SnapshotUpload::Full(current.clone()),
+        }
+    }
+
+    /// JSON body, gzipped when `gzip` is set (sent with `Content-Encoding: gzip`)
+    pub fn encode(&self, gzip: bool) -> Result<Vec<u8>> {
+        use std::io::Write;
+
+        let json = serde_json::to_vec(self)?;
+        if !gzip {
+            return Ok(json);
+        }
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&json)?;
+        Ok(encoder.finish()?)
+    }
+
+    /// Parse an upload body, gunzipping it first when `gzip` is set
+    pub fn decode(body: &[u8], gzip: bool) -> Result<Self> {
+        use std::io::Read;
+
+        if !gzip {
+            return Ok(serde_json::from_slice(body)?);
+        }
+        let mut json = Vec::new();
+        flate2::read::GzDecoder::new(body)
+            .take(MAX_SNAPSHOT_UPLOAD_BYTES + 1)
+            .read_to_end(&mut json)
+            .map_err(|e| anyhow::anyhow!("Invalid gzip snapshot body: {}", e))?;
+        if json.len() as u64 > MAX_SNAPSHOT_UPLOAD_BYTES {
+            return Err(anyhow::anyhow!(
+                "Snapshot body exceeds {} bytes once decompressed",
+                MAX_SNAPSHOT_UPLOAD_BYTES
+            ));
+        }
+        Ok(serde_json::from_slice(&json)?)
+    }
 }
 
 /// Contention analysis for Magic Moment
@@ -66,6 +339,233 @@ pub struct ContentionAnalysis {
     pub blocked_gpus: Vec<BlockedGpu>,
     pub top_users: Vec<UserUsage>,
     pub recommendations: Vec<String>,
+    /// GPUs inside a Guard Mode maintenance window (shown greyed out by the dashboard)
+    #[serde(default)]
+    pub maintenance_gpus: Vec<MaintenanceGpu>,
+    /// Contention per node, most blocked GPUs first
+    #[serde(default)]
+    pub node_summaries: Vec<NodeContention>,
+    /// GPUs with no processes and almost no load, as (node_id, gpu_index)
+    #[serde(default)]
+    pub idle_gpus: Vec<(String, u16)>,
+    /// GPUs reporting uncorrected ECC errors or flagged for RMA
+    #[serde(default)]
+    pub unhealthy_gpus: Vec<UnhealthyGpu>,
+    /// Users holding more than [`FAIR_SHARE_LIMIT`] times an equal share of the
+    /// cluster's GPU memory or utilization, largest overage first
+    #[serde(default)]
+    pub fair_share_violations: Vec<FairShareViolation>,
+}
+
+impl ContentionAnalysis {
+    /// Render the analysis as a Markdown document for attaching to tickets: summary
+    /// counts, tables of blocked GPUs, top users and nodes, and the recommendations
+    pub fn to_markdown(&self, generated_at: DateTime<Utc>) -> String {
+        use crate::util::format_memory_mb_to_gib;
+        use std::fmt::Write;
+
+        let mut md = String::new();
+        let _ = writeln!(md, "# GPU Contention Report\n");
+        let _ = writeln!(
+            md,
+            "Generated: {}\n",
+            generated_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+        );
+
+        md.push_str("## Summary\n\n| Metric | Value |\n| --- | --- |\n");
+        for (metric, value) in [
+            ("Blocked GPUs", self.blocked_gpus.len()),
+            ("Idle GPUs", self.idle_gpus.len()),
+            ("GPUs in maintenance", self.maintenance_gpus.len()),
+            ("Unhealthy GPUs", self.unhealthy_gpus.len()),
+            ("Active users", self.top_users.len()),
+        ] {
+            let _ = writeln!(md, "| {} | {} |", metric, value);
+        }
+
+        md.push_str("\n## Blocked GPUs\n\n");
+        if self.blocked_gpus.is_empty() {
+            md.push_str("No blocked GPUs.\n");
+        } else {
+            md.push_str(
+                "| Node | GPU | Name | Utilization | Memory (GiB) | Blocking processes |\n",
+            );
+            md.push_str("| --- | --- | --- | --- | --- | --- |\n");
+            for gpu in &self.blocked_gpus {
+                let processes: Vec<String> = gpu
+                    .blocking_processes
+                    .iter()
+                    .map(|p| format!("{} (PID {}, {})", p.proc_name, p.pid, p.user))
+                    .collect();
+                let _ = writeln!(
+                    md,
+                    "| {} | {} | {} | {:.1}% | {}/{} | {} |",
+                    markdown_cell(&gpu.node_id),
+                    gpu.gpu_index,
+                    markdown_cell(&gpu.gpu_name),
+                    gpu.utilization_pct,
+                    format_memory_mb_to_gib(gpu.memory_used_mb),
+                    format_memory_mb_to_gib(gpu.memory_total_mb),
+                    markdown_cell(&processes.join(", "))
+                );
+            }
+        }
+
+        md.push_str("\n## Top Users\n\n");
+        if self.top_users.is_empty() {
+            md.push_str("No GPU users.\n");
+        } else {
+            md.push_str("| User | GPUs | Memory (GiB) | Avg utilization | Processes |\n");
+            md.push_str("| --- | --- | --- | --- | --- |\n");
+            for user in &self.top_users {
+                let _ = writeln!(
+                    md,
+                    "| {} | {} | {} | {:.1}% | {} |",
+                    markdown_cell(&user.user),
+                    user.gpu_count,
+                    format_memory_mb_to_gib(user.total_memory_mb),
+                    user.avg_utilization,
+                    user.process_count
+                );
+            }
+        }
+
+        if !self.node_summaries.is_empty() {
+            md.push_str("\n## Nodes\n\n");
+            md.push_str(
+                "| Node | Hostname | GPUs | Blocked | Idle | Maintenance | Unhealthy | Avg utilization |\n",
+            );
+            md.push_str("| --- | --- | --- | --- | --- | --- | --- | --- |\n");
+            for node in &self.node_summaries {
+                let _ = writeln!(
+                    md,
+                    "| {} | {} | {} | {} | {} | {} | {} | {:.1}% |",
+                    markdown_cell(&node.node_id),
+                    markdown_cell(&node.hostname),
+                    node.total_gpus,
+                    node.blocked_gpus,
+                    node.idle_gpus,
+                    node.maintenance_gpus,
+                    node.unhealthy_gpus,
+                    node.avg_utilization
+                );
+            }
+        }
+
+        if !self.fair_share_violations.is_empty() {
+            md.push_str("\n## Fair Share\n\n");
+            md.push_str("| User | Memory share | Compute share | Fair share | Target (GiB) | Release (GiB) |\n");
+            md.push_str("| --- | --- | --- | --- | --- | --- |\n");
+            for violation in &self.fair_share_violations {
+                let _ = writeln!(
+                    md,
+                    "| {} | {:.0}% | {:.0}% | {:.0}% | {} | {} |",
+                    markdown_cell(&violation.usage.user),
+                    violation.memory_share * 100.0,
+                    violation.utilization_share * 100.0,
+                    violation.fair_share * 100.0,
+                    format_memory_mb_to_gib(violation.target_mb),
+                    format_memory_mb_to_gib(violation.relinquish_mb)
+                );
+            }
+        }
+
+        if !self.unhealthy_gpus.is_empty() {
+            md.push_str("\n## Unhealthy GPUs\n\n");
+            md.push_str("| Node | GPU | Name | Uncorrected ECC | Retired pages | RMA |\n");
+            md.push_str("| --- | --- | --- | --- | --- | --- |\n");
+            for gpu in &self.unhealthy_gpus {
+                let _ = writeln!(
+                    md,
+                    "| {} | {} | {} | {} | {} | {} |",
+                    markdown_cell(&gpu.node_id),
+                    gpu.gpu_index,
+                    markdown_cell(&gpu.gpu_name),
+                    gpu.ecc_uncorrected,
+                    gpu.retired_pages
+                        .map_or_else(|| "-".to_string(), |p| p.to_string()),
+                    if gpu.rma_flagged { "yes" } else { "no" }
+                );
+            }
+        }
+
+        if !self.maintenance_gpus.is_empty() {
+            md.push_str("\n## GPUs in Maintenance\n\n");
+            md.push_str("| Node | GPU | Name | Message |\n| --- | --- | --- | --- |\n");
+            for gpu in &self.maintenance_gpus {
+                let _ = writeln!(
+                    md,
+                    "| {} | {} | {} | {} |",
+                    markdown_cell(&gpu.node_id),
+                    gpu.gpu_index,
+                    markdown_cell(&gpu.gpu_name),
+                    markdown_cell(&gpu.message)
+                );
+            }
+        }
+
+        md.push_str("\n## Recommendations\n\n");
+        if self.recommendations.is_empty() {
+            md.push_str("No recommendations.\n");
+        } else {
+            for recommendation in &self.recommendations {
+                let _ = writeln!(md, "- {}", recommendation.replace('\n', " "));
+            }
+        }
+        md
+    }
+}
+
+/// Escape a value for a Markdown table cell
+fn markdown_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+/// Contention on a single node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeContention {
+    pub node_id: String,
+    pub hostname: String,
+    pub total_gpus: u32,
+    pub blocked_gpus: u32,
+    pub idle_gpus: u32,
+    pub maintenance_gpus: u32,
+    #[serde(default)]
+    pub unhealthy_gpus: u32,
+    pub avg_utilization: f32,
+    /// Drained for maintenance, so none of its GPUs count as idle
+    #[serde(default)]
+    pub draining: bool,
+}
+
+/// A GPU counts as idle below this utilization when it runs no processes
+const IDLE_GPU_UTILIZATION_PCT: f32 = 5.0;
+
+/// ... and uses less than this fraction of its memory
+const IDLE_GPU_MEMORY_RATIO: f32 = 0.1;
+
+/// Most rebalancing suggestions added to the recommendations
+const MAX_REBALANCE_RECOMMENDATIONS: usize = 5;
+
+/// A GPU that is currently in a maintenance window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceGpu {
+    pub node_id: String,
+    pub gpu_index: u16,
+    pub gpu_name: String,
+    pub message: String,
+}
+
+/// A GPU whose memory errors need attention
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnhealthyGpu {
+    pub node_id: String,
+    pub gpu_index: u16,
+    pub gpu_name: String,
+    /// Uncorrected ECC errors since the driver was loaded
+    pub ecc_uncorrected: u64,
+    pub retired_pages: Option<u32>,
+    pub rma_flagged: bool,
 }
 
 /// Information about a blocked GPU
@@ -90,1198 +590,6164 @@ pub struct UserUsage {
     pub process_count: u32,
 }
 
-/// Coordinator state
-#[derive(Debug, Clone)]
-pub struct CoordinatorState {
-    pub nodes: Arc<RwLock<HashMap<String, NodeInfo>>>,
-    pub snapshots: Arc<RwLock<HashMap<String, NodeSnapshot>>>,
-    pub last_cluster_snapshot: Arc<RwLock<Option<ClusterSnapshot>>>,
+/// How many times their equal share a user may hold before being flagged
+pub const FAIR_SHARE_LIMIT: f32 = 2.0;
+
+/// A user over [`FAIR_SHARE_LIMIT`] times their fair share, with how much memory
+/// to release to get back to it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FairShareViolation {
+    #[serde(flatten)]
+    pub usage: UserUsage,
+    /// Fraction of the cluster's GPU memory the user holds
+    pub memory_share: f32,
+    /// Fraction of the cluster's GPU compute the user's GPUs are busy with
+    pub utilization_share: f32,
+    /// Equal share of the cluster: 1 / number of active users
+    pub fair_share: f32,
+    /// GPU memory the user would hold at their fair share
+    pub target_mb: u32,
+    /// Memory to release to get down to `target_mb`
+    pub relinquish_mb: u32,
 }
 
-impl Default for CoordinatorState {
-    fn default() -> Self {
-        Self::new()
+/// Compare each user's share of `total_memory_mb` and of the compute of `total_gpus`
+/// GPUs with an equal split between all users, flagging those above
+/// [`FAIR_SHARE_LIMIT`] times it
+fn fair_share_violations(
+    users: &[UserUsage],
+    total_memory_mb: u64,
+    total_gpus: u32,
+) -> Vec<FairShareViolation> {
+    if users.is_empty() || total_memory_mb == 0 || total_gpus == 0 {
+        return Vec::new();
     }
+    let fair_share = 1.0 / users.len() as f32;
+    let target_mb = (total_memory_mb as f64 * fair_share as f64) as u32;
+
+    let mut violations: Vec<FairShareViolation> = users
+        .iter()
+        .filter_map(|usage| {
+            let memory_share = usage.total_memory_mb as f32 / total_memory_mb as f32;
+            let utilization_share =
+                usage.avg_utilization * usage.gpu_count as f32 / (100.0 * total_gpus as f32);
+            let limit = FAIR_SHARE_LIMIT * fair_share;
+            (memory_share > limit || utilization_share > limit).then(|| FairShareViolation {
+                usage: usage.clone(),
+                memory_share,
+                utilization_share,
+                fair_share,
+                target_mb,
+                relinquish_mb: usage.total_memory_mb.saturating_sub(target_mb),
+            })
+        })
+        .collect();
+    violations.sort_by(|a, b| {
+        b.relinquish_mb
+            .cmp(&a.relinquish_mb)
+            .then_with(|| a.usage.user.cmp(&b.usage.user))
+    });
+    violations
 }
 
-impl CoordinatorState {
-    pub fn new() -> Self {
-        Self {
-            nodes: Arc::new(RwLock::new(HashMap::new())),
-            snapshots: Arc::new(RwLock::new(HashMap::new())),
-            last_cluster_snapshot: Arc::new(RwLock::new(None)),
+/// Suggest moving work from nodes with blocked GPUs and no idle GPU of their own to
+/// the nodes with the most idle GPUs. `summaries` must be sorted most blocked first.
+fn rebalance_recommendations(summaries: &[NodeContention]) -> Vec<String> {
+    let mut capacity: Vec<(&NodeContention, u32)> = summaries
+        .iter()
+        .filter(|n| n.idle_gpus > 0)
+        .map(|n| (n, n.idle_gpus))
+        .collect();
+
+    let mut recommendations = Vec::new();
+    for source in summaries
+        .iter()
+        .filter(|n| n.blocked_gpus > 0 && n.idle_gpus == 0)
+    {
+        if recommendations.len() == MAX_REBALANCE_RECOMMENDATIONS {
+            break;
         }
+        let Some((target, remaining)) = capacity
+            .iter_mut()
+            .filter(|(_, remaining)| *remaining > 0)
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.node_id.cmp(&a.0.node_id)))
+        else {
+            break;
+        };
+        recommendations.push(format!(
+            "Move jobs from node {} ({} blocked GPU{}) to node {}, which has {} idle GPU{}",
+            source.hostname,
+            source.blocked_gpus,
+            if source.blocked_gpus == 1 { "" } else { "s" },
+            target.hostname,
+            *remaining,
+            if *remaining == 1 { "" } else { "s" }
+        ));
+        *remaining = remaining.saturating_sub(source.blocked_gpus);
     }
+    recommendations
+}
 
-    /// Start background tasks for cluster management
-    pub fn start_background_tasks(&self) {
-        let state = self.clone();
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(30));
-            loop {
-                interval.tick().await;
+/// Events kept in memory for `GET /api/cluster/events`
+pub const EVENT_BUFFER_CAPACITY: usize = 1000;
+
+/// Default and maximum number of events returned by one events request
+pub const EVENT_PAGE_LIMIT: usize = 500;
+
+/// Something that changed in the cluster, published on the events API and the
+/// `events` WebSocket topic
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClusterEvent {
+    /// A node joined: it registered for the first time, or registered or reported
+    /// again after being marked offline
+    NodeRegistered {
+        timestamp: DateTime<Utc>,
+        node_id: String,
+        hostname: String,
+        gpu_count: u32,
+    },
+    /// A node stopped reporting and was marked offline
+    NodeOffline {
+        timestamp: DateTime<Utc>,
+        node_id: String,
+        hostname: String,
+        last_seen: DateTime<Utc>,
+    },
+    /// A GPU became blocked by high utilization or memory use
+    GpuBlocked {
+        timestamp: DateTime<Utc>,
+        node_id: String,
+        gpu_index: u16,
+        gpu_name: String,
+        utilization_pct: f32,
+        memory_used_mb: u32,
+        memory_total_mb: u32,
+        users: Vec<String>,
+    },
+    /// A previously blocked GPU is no longer blocked
+    GpuRecovered {
+        timestamp: DateTime<Utc>,
+        node_id: String,
+        gpu_index: u16,
+        gpu_name: String,
+    },
+    /// A process on a node started violating a Guard Mode policy
+    PolicyViolation {
+        timestamp: DateTime<Utc>,
+        node_id: String,
+        gpu_index: u16,
+        pid: u32,
+        user: String,
+        policy_name: String,
+        violation_type: ViolationType,
+        severity: ViolationSeverity,
+        message: String,
+    },
+    /// Rogue detection flagged a process
+    RogueDetected {
+        timestamp: DateTime<Utc>,
+        node_id: Option<String>,
+        gpu_index: u16,
+        pid: u32,
+        user: String,
+        process_name: String,
+        /// suspicious_process, crypto_miner, resource_abuser or data_exfiltrator
+        category: String,
+        confidence: f32,
+        reasons: Vec<String>,
+    },
+    /// A node was drained for maintenance; the owners of its processes should move
+    /// their work elsewhere before the deadline
+    NodeDraining {
+        timestamp: DateTime<Utc>,
+        node_id: String,
+        hostname: String,
+        deadline: Option<DateTime<Utc>>,
+        enforce: bool,
+        reason: Option<String>,
+        /// GPU processes running on the node when the drain started
+        processes: u32,
+        owners: Vec<String>,
+    },
+    /// A node's drain ended
+    NodeUndrained {
+        timestamp: DateTime<Utc>,
+        node_id: String,
+        hostname: String,
+    },
+}
 
-                // Clean up stale nodes
-                if let Err(e) = state.cleanup_stale_nodes().await {
-                    tracing::warn!("Failed to cleanup stale nodes: {}", e);
-                }
+impl ClusterEvent {
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        match self {
+            ClusterEvent::NodeRegistered { timestamp, .. }
+            | ClusterEvent::NodeOffline { timestamp, .. }
+            | ClusterEvent::NodeDraining { timestamp, .. }
+            | ClusterEvent::NodeUndrained { timestamp, .. }
+            | ClusterEvent::GpuBlocked { timestamp, .. }
+            | ClusterEvent::GpuRecovered { timestamp, .. }
+            | ClusterEvent::PolicyViolation { timestamp, .. }
+            | ClusterEvent::RogueDetected { timestamp, .. } => *timestamp,
+        }
+    }
 
-                // Update cluster snapshot
-                if let Err(e) = state.update_cluster_snapshot().await {
-                    tracing::warn!("Failed to update cluster snapshot: {}", e);
-                }
+    /// Events for the processes in a rogue detection result
+    pub fn from_rogue_result(result: &crate::rogue_detection::RogueDetectionResult) -> Vec<Self> {
+        let event = |process: &GpuProc, category: &str, confidence: f32, reasons: Vec<String>| {
+            ClusterEvent::RogueDetected {
+                timestamp: result.timestamp,
+                node_id: process.node_id.clone(),
+                gpu_index: process.gpu_index,
+                pid: process.pid,
+                user: process.user.clone(),
+                process_name: process.proc_name.clone(),
+                category: category.to_string(),
+                confidence,
+                reasons,
             }
-        });
-    }
+        };
 
-    /// Register or update a node
-    pub async fn register_node(&self, node_info: NodeInfo) -> Result<()> {
-        let mut nodes = self.nodes.write().await;
-        nodes.insert(node_info.id.clone(), node_info);
-        Ok(())
+        let mut events = Vec::new();
+        for miner in &result.crypto_miners {
+            events.push(event(
+                &miner.process,
+                "crypto_miner",
+                miner.confidence,
+                miner.mining_indicators.clone(),
+            ));
+        }
+        for exfiltrator in &result.data_exfiltrators {
+            events.push(event(
+                &exfiltrator.process,
+                "data_exfiltrator",
+                exfiltrator.confidence,
+                exfiltrator.exfil_indicators.clone(),
+            ));
+        }
+        for abuser in &result.resource_abusers {
+            events.push(event(
+                &abuser.process,
+                "resource_abuser",
+                abuser.severity,
+                vec![format!(
+                    "{:?} for {:.1} hours",
+                    abuser.abuse_type, abuser.duration_hours
+                )],
+            ));
+        }
+        for suspicious in &result.suspicious_processes {
+            events.push(event(
+                &suspicious.process,
+                "suspicious_process",
+                suspicious.confidence,
+                suspicious.reasons.clone(),
+            ));
+        }
+        events
     }
 
-    /// Update node snapshot
-    pub async fn update_snapshot(&self, node_id: String, snapshot: NodeSnapshot) -> Result<()> {
-        // Update node last seen
-        {
-            let mut nodes = self.nodes.write().await;
-            let node = nodes
-                .get_mut(&node_id)
-                .ok_or_else(|| anyhow::anyhow!("Node {} is not registered", node_id))?;
-            node.last_seen = Utc::now();
-            node.status = NodeStatus::Online;
+    /// Key identifying the condition behind a policy or rogue event, so a condition
+    /// that persists across checks is only published when it starts
+    fn condition_key(&self) -> Option<String> {
+        match self {
+            ClusterEvent::PolicyViolation {
+                node_id,
+                pid,
+                policy_name,
+                violation_type,
+                ..
+            } => Some(format!(
+                "{}:{}:{}:{:?}",
+                node_id, pid, policy_name, violation_type
+            )),
+            ClusterEvent::RogueDetected {
+                node_id,
+                pid,
+                category,
+                ..
+            } => Some(format!(
+                "{}:{}:{}",
+                node_id.as_deref().unwrap_or("local"),
+                pid,
+                category
+            )),
+            _ => None,
         }
+    }
+}
 
-        // Store snapshot
-        {
-            let mut snapshots = self.snapshots.write().await;
-            snapshots.insert(node_id.clone(), snapshot);
-        }
+/// Recent cluster events: a bounded in-memory ring buffer, a broadcast channel
+/// for WebSocket subscribers and, optionally, a store that keeps events across
+/// restarts and beyond the buffer
+#[derive(Clone)]
+pub struct ClusterEvents {
+    buffer: Arc<std::sync::Mutex<VecDeque<ClusterEvent>>>,
+    capacity: usize,
+    sender: tokio::sync::broadcast::Sender<ClusterEvent>,
+    store: Option<Arc<dyn AuditStore>>,
+    /// Policy and rogue conditions active at the last check, by scope
+    active: Arc<std::sync::Mutex<HashMap<String, HashSet<String>>>>,
+    /// Blocked GPUs at the last contention check and their names
+    blocked: Arc<std::sync::Mutex<HashMap<(String, u16), String>>>,
+}
 
-        // Update cluster snapshot
-        self.update_cluster_snapshot().await?;
-        Ok(())
+impl std::fmt::Debug for ClusterEvents {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClusterEvents")
+            .field("capacity", &self.capacity)
+            .field("store", &self.store.as_ref().map(|s| s.describe()))
+            .finish_non_exhaustive()
     }
+}
 
-    /// Get all nodes
-    pub async fn get_nodes(&self) -> Vec<NodeInfo> {
-        let nodes = self.nodes.read().await;
-        nodes.values().cloned().collect()
+impl ClusterEvents {
+    /// Keep up to `capacity` events in memory
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = tokio::sync::broadcast::channel(capacity.max(1));
+        Self {
+            buffer: Arc::new(std::sync::Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+            sender,
+            store: None,
+            active: Arc::default(),
+            blocked: Arc::default(),
+        }
     }
 
-    /// Get cluster snapshot
-    pub async fn get_cluster_snapshot(&self) -> Option<ClusterSnapshot> {
-        let snapshot = self.last_cluster_snapshot.read().await;
-        snapshot.clone()
+    /// Also append events to `store` and read older events back from it
+    pub fn with_store(mut self, store: Arc<dyn AuditStore>) -> Self {
+        self.store = Some(store);
+        self
     }
 
-    /// Build cluster snapshot from current node data
-    pub async fn build_cluster_snapshot(&self) -> Result<ClusterSnapshot> {
-        let nodes = self.nodes.read().await;
-        let snapshots = self.snapshots.read().await;
-
-        let mut node_snapshots = Vec::new();
-        let mut total_gpus = 0;
-        let mut total_memory_gb = 0.0;
-        let mut active_processes = 0;
-        let mut total_utilization = 0.0;
-        let mut gpu_count = 0;
-
-        for (node_id, node_info) in nodes.iter() {
-            if let Some(snapshot) = snapshots.get(node_id) {
-                let node_snapshot = NodeSnapshot {
-                    node_id: node_id.clone(),
-                    hostname: node_info.hostname.clone(),
-                    timestamp: snapshot.timestamp,
-                    gpus: snapshot.gpus.clone(),
-                    processes: snapshot.processes.clone(),
-                    status: node_info.status.clone(),
-                };
-
-                node_snapshots.push(node_snapshot);
+    /// Receive events as they are published
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<ClusterEvent> {
+        self.sender.subscribe()
+    }
 
-                total_gpus += snapshot.gpus.len() as u32;
-                for gpu in &snapshot.gpus {
-                    total_memory_gb += gpu.mem_total_mb as f32 / 1024.0;
-                    total_utilization += gpu.util_pct;
-                    gpu_count += 1;
+    /// Record events, push them to subscribers and persist them. Store failures
+    /// are logged; the events stay available in memory.
+    pub async fn publish(&self, events: Vec<ClusterEvent>) {
+        if events.is_empty() {
+            return;
+        }
+        {
+            let mut buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+            for event in &events {
+                if buffer.len() == self.capacity {
+                    buffer.pop_front();
                 }
-                active_processes += snapshot.processes.len() as u32;
+                buffer.push_back(event.clone());
             }
         }
+        for event in &events {
+            tracing::debug!(event = ?event, "Cluster event");
+            // No receivers just means no WebSocket client is subscribed
+            let _ = self.sender.send(event.clone());
+        }
 
-        let utilization_avg = if gpu_count > 0 {
-            total_utilization / gpu_count as f32
-        } else {
-            0.0
+        if let Some(store) = &self.store {
+            let records: Vec<EventRecord> = events
+                .iter()
+                .filter_map(|event| {
+                    Some(EventRecord {
+                        timestamp: event.timestamp(),
+                        event: serde_json::to_value(event).ok()?,
+                    })
+                })
+                .collect();
+            if let Err(e) = store.append_events(&records).await {
+                tracing::warn!("Failed to persist cluster events: {}", e);
+            }
+        }
+    }
+
+    /// Up to `limit` events newer than `since`, oldest first. Events that have left
+    /// the buffer are read from the store when there is one.
+    pub async fn since(
+        &self,
+        since: Option<DateTime<Utc>>,
+        limit: usize,
+    ) -> Result<Vec<ClusterEvent>> {
+        let (buffered, oldest) = {
+            let buffer = self.buffer.lock().unwrap_or_else(|e| e.into_inner());
+            let buffered: Vec<ClusterEvent> = buffer
+                .iter()
+                .filter(|event| since.is_none_or(|since| event.timestamp() > since))
+                .take(limit)
+                .cloned()
+                .collect();
+            (buffered, buffer.front().map(ClusterEvent::timestamp))
         };
 
-        Ok(ClusterSnapshot {
-            timestamp: Utc::now(),
-            nodes: node_snapshots,
-            total_gpus,
-            total_memory_gb,
-            active_processes,
-            utilization_avg,
-        })
+        let (Some(store), Some(since)) = (&self.store, since) else {
+            return Ok(buffered);
+        };
+        if oldest.is_some_and(|oldest| oldest <= since) {
+            return Ok(buffered);
+        }
+        store
+            .events_since(since, limit)
+            .await?
+            .into_iter()
+            .map(|record| Ok(serde_json::from_value(record.event)?))
+            .collect()
     }
 
-    /// Update cluster snapshot and cache it
-    pub async fn update_cluster_snapshot(&self) -> Result<()> {
-        let snapshot = self.build_cluster_snapshot().await?;
-        let mut cached = self.last_cluster_snapshot.write().await;
-        *cached = Some(snapshot);
-        Ok(())
+    /// Keep only the events whose condition was not active in `scope` at the
+    /// previous check, and remember the conditions active now
+    fn started(&self, scope: &str, events: Vec<ClusterEvent>) -> Vec<ClusterEvent> {
+        let current: HashSet<String> = events.iter().filter_map(|e| e.condition_key()).collect();
+        let mut active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        let previous = active
+            .insert(scope.to_string(), current)
+            .unwrap_or_default();
+        let mut seen = HashSet::new();
+        events
+            .into_iter()
+            .filter(|event| {
+                event
+                    .condition_key()
+                    .is_some_and(|key| !previous.contains(&key) && seen.insert(key))
+            })
+            .collect()
     }
 
-    /// Get contention analysis (Magic Moment)
-    pub async fn get_contention_analysis(&self) -> Result<ContentionAnalysis> {
-        let nodes = self.nodes.read().await;
-        let snapshots = self.snapshots.read().await;
-        let mut blocked_gpus = Vec::new();
-        // Track unique (node_id, gpu_index) pairs per user to correctly count GPUs
-        // Tuple: (unique_gpus, memory, utilization_sum, process_count)
-        #[allow(clippy::type_complexity)]
-        let mut user_stats: HashMap<String, (HashSet<(String, u16)>, u32, f32, u32)> =
-            HashMap::new();
+    /// Blocked and recovered GPU events for the blocked set `blocked_gpus`. GPUs of
+    /// nodes no longer in `nodes` are forgotten without a recovery event.
+    fn contention_changes(
+        &self,
+        blocked_gpus: &[BlockedGpu],
+        nodes: &HashSet<String>,
+        now: DateTime<Utc>,
+    ) -> Vec<ClusterEvent> {
+        let mut blocked = self.blocked.lock().unwrap_or_else(|e| e.into_inner());
+        let mut events = Vec::new();
+        for gpu in blocked_gpus {
+            let key = (gpu.node_id.clone(), gpu.gpu_index);
+            if blocked.insert(key, gpu.gpu_name.clone()).is_none() {
+                let mut users: Vec<String> = gpu
+                    .blocking_processes
+                    .iter()
+                    .map(|p| p.user.clone())
+                    .collect();
+                users.sort();
+                users.dedup();
+                events.push(ClusterEvent::GpuBlocked {
+                    timestamp: now,
+                    node_id: gpu.node_id.clone(),
+                    gpu_index: gpu.gpu_index,
+                    gpu_name: gpu.gpu_name.clone(),
+                    utilization_pct: gpu.utilization_pct,
+                    memory_used_mb: gpu.memory_used_mb,
+                    memory_total_mb: gpu.memory_total_mb,
+                    users,
+                });
+            }
+        }
 
-        for (node_id, _node_info) in nodes.iter() {
-            let Some(snapshot) = snapshots.get(node_id) else {
+        let still_blocked: HashSet<(&str, u16)> = blocked_gpus
+            .iter()
+            .map(|gpu| (gpu.node_id.as_str(), gpu.gpu_index))
+            .collect();
+        let mut recovered: Vec<(String, u16)> = blocked
+            .keys()
+            .filter(|(node_id, gpu_index)| !still_blocked.contains(&(node_id.as_str(), *gpu_index)))
+            .cloned()
+            .collect();
+        recovered.sort();
+        for key in recovered {
+            let Some(gpu_name) = blocked.remove(&key) else {
                 continue;
             };
-            for gpu in &snapshot.gpus {
-                // Find processes using this GPU
-                let gpu_processes: Vec<GpuProc> = snapshot
-                    .processes
-                    .iter()
-                    .filter(|p| p.gpu_index == gpu.gpu_index)
-                    .cloned()
-                    .collect();
+            let (node_id, gpu_index) = key;
+            if nodes.contains(&node_id) {
+                events.push(ClusterEvent::GpuRecovered {
+                    timestamp: now,
+                    node_id,
+                    gpu_index,
+                    gpu_name,
+                });
+            }
+        }
+        events
+    }
+}
 
-                // Check if GPU is blocked (high utilization or memory usage)
-                let is_blocked =
-                    gpu.util_pct > 80.0 || (gpu.mem_used_mb as f32 / gpu.mem_total_mb as f32) > 0.8;
+/// Default age after which a node's rogue detection result is flagged stale
+pub const DEFAULT_ROGUE_STALE_AFTER: Duration = Duration::from_secs(60 * 60);
+/// Hours of audit history each node's rogue detection scan covers
+pub const ROGUE_SCAN_HOURS: u32 = 24;
+
+/// Work the coordinator asks a node to do, fetched by the agent from
+/// `GET /api/nodes/:id/commands` after each snapshot upload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NodeCommand {
+    /// Run rogue detection now and post the result to `/api/nodes/:id/rogue`
+    RogueScan,
+    /// Reset a GPU for a cluster reset and post the outcome to `/api/nodes/:id/reset`
+    Reset {
+        batch_id: String,
+        gpu_index: u16,
+        force: bool,
+    },
+    /// Record the drain locally so `--list` and Guard Mode on the node know about it
+    Drain { drain: NodeDrain },
+    /// Remove the local drain record
+    Undrain,
+    /// Kill processes left on a node whose enforced drain deadline has passed
+    Kill { pids: Vec<u32>, reason: String },
+}
 
-                if is_blocked && !gpu_processes.is_empty() {
-                    blocked_gpus.push(BlockedGpu {
-                        node_id: node_id.clone(),
-                        gpu_index: gpu.gpu_index,
-                        gpu_name: gpu.name.clone(),
-                        blocking_processes: gpu_processes.clone(),
-                        utilization_pct: gpu.util_pct,
-                        memory_used_mb: gpu.mem_used_mb,
-                        memory_total_mb: gpu.mem_total_mb,
-                    });
-                }
+/// How long the coordinator keeps cluster reset batches for `GET /api/cluster/reset/:id`
+pub const RESET_BATCH_RETENTION: Duration = Duration::from_secs(60 * 60);
+/// Longest a `POST /api/cluster/reset` waits for the nodes to report
+pub const MAX_RESET_WAIT: Duration = Duration::from_secs(120);
 
-                // Aggregate user statistics
-                for process in &gpu_processes {
-                    let entry = user_stats.entry(process.user.clone()).or_insert((
-                        HashSet::new(),
-                        0,
-                        0.0,
-                        0,
-                    ));
-                    // Track unique (node_id, gpu_index) pairs to correctly count GPUs
-                    if entry.0.insert((node_id.clone(), gpu.gpu_index)) {
-                        entry.2 += gpu.util_pct; // utilization (sum per unique GPU)
-                    }
-                    entry.1 += process.used_mem_mb; // memory
-                    entry.3 += 1; // process_count
-                }
-            }
-        }
+/// A GPU on a registered node
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ResetTarget {
+    pub node_id: String,
+    pub gpu_index: u16,
+}
 
-        // Convert user stats to UserUsage
-        let mut top_users: Vec<UserUsage> = user_stats
-            .into_iter()
-            .map(
-                |(user, (gpu_set, total_memory_mb, total_util, process_count))| UserUsage {
-                    user,
-                    gpu_count: gpu_set.len() as u32, // Count unique GPUs
-                    total_memory_mb,
-                    avg_utilization: if gpu_set.is_empty() {
-                        0.0
-                    } else {
-                        total_util / gpu_set.len() as f32
-                    },
-                    process_count,
-                },
-            )
-            .collect();
+/// Body of `POST /api/cluster/reset`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterResetRequest {
+    pub targets: Vec<ResetTarget>,
+    /// Reset GPUs that still run processes, terminating them
+    #[serde(default)]
+    pub force: bool,
+    /// Wait up to this many seconds for the nodes to report before answering
+    #[serde(default)]
+    pub wait_secs: u64,
+}
 
-        // Sort by memory usage
-        top_users.sort_by(|a, b| b.total_memory_mb.cmp(&a.total_memory_mb));
-        top_users.truncate(10);
+/// Body of `POST /api/nodes/:id/drain`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DrainRequest {
+    /// When running processes must be gone
+    #[serde(default)]
+    pub deadline: Option<DateTime<Utc>>,
+    /// The deadline as seconds from now, instead of `deadline`
+    #[serde(default)]
+    pub deadline_secs: Option<u64>,
+    /// Kill the processes still running at the deadline; needs a deadline
+    #[serde(default)]
+    pub enforce: bool,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
 
-        // Generate recommendations
-        let mut recommendations = Vec::new();
-        if !blocked_gpus.is_empty() {
-            recommendations.push(format!(
-                "{} GPUs are currently blocked by high utilization",
-                blocked_gpus.len()
-            ));
-        }
-        if let Some(top_user) = top_users.first() {
-            recommendations.push(format!(
-                "User '{}' is using the most GPU memory ({} MB)",
-                top_user.user, top_user.total_memory_mb
-            ));
+impl DrainRequest {
+    /// The drain this request places on a node as of `now`
+    pub fn into_drain(self, now: DateTime<Utc>) -> Result<NodeDrain> {
+        let deadline = match (self.deadline, self.deadline_secs) {
+            (Some(_), Some(_)) => anyhow::bail!("Give either deadline or deadline_secs, not both"),
+            (Some(deadline), None) => Some(deadline),
+            (None, Some(secs)) => {
+                Some(now + chrono::Duration::seconds(secs.min(i64::MAX as u64) as i64))
+            }
+            (None, None) => None,
+        };
+        if self.enforce && deadline.is_none() {
+            anyhow::bail!("Enforcing a drain needs a deadline");
         }
-
-        Ok(ContentionAnalysis {
-            blocked_gpus,
-            top_users,
-            recommendations,
+        Ok(NodeDrain {
+            since: now,
+            deadline,
+            enforce: self.enforce,
+            reason: self.reason.filter(|reason| !reason.trim().is_empty()),
         })
     }
+}
 
-    /// Clean up stale nodes (offline for more than 5 minutes)
-    pub async fn cleanup_stale_nodes(&self) -> Result<()> {
-        let cutoff = Utc::now() - chrono::Duration::minutes(5);
-        let mut nodes = self.nodes.write().await;
-        let mut snapshots = self.snapshots.write().await;
+/// File under the gpukill config directory where the coordinator keeps its nodes
+pub const NODE_REGISTRY_FILE: &str = "coordinator_nodes.json";
 
-        let stale_nodes: Vec<String> = nodes
-            .iter()
-            .filter(|(_, node)| node.last_seen < cutoff)
-            .map(|(id, _)| id.clone())
-            .collect();
+/// Registered nodes saved by the coordinator, so registrations, tags and drains
+/// survive a restart
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NodeRegistry {
+    pub nodes: Vec<NodeInfo>,
+}
 
-        for node_id in stale_nodes {
-            nodes.remove(&node_id);
-            snapshots.remove(&node_id);
-        }
+impl NodeRegistry {
+    /// `coordinator_nodes.json` in the gpukill config directory
+    pub fn default_path() -> Result<PathBuf> {
+        let mut path = if let Some(config_dir) = dirs::config_dir() {
+            config_dir
+        } else if let Some(home_dir) = dirs::home_dir() {
+            home_dir.join(".config")
+        } else {
+            std::env::current_dir()?
+        };
 
-        snapshots.retain(|node_id, _| nodes.contains_key(node_id));
+        path.push("gpukill");
+        std::fs::create_dir_all(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to create config directory: {}", e))?;
 
-        Ok(())
+        path.push(NODE_REGISTRY_FILE);
+        Ok(path)
+    }
+
+    /// Load the registry at `path`; a missing file is an empty registry
+    pub fn load(path: &std::path::Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read node registry {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse node registry {}", path.display()))
+    }
+
+    /// Write the registry to `path` through a temporary file
+    pub fn save(&self, path: &std::path::Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        let tmp_path = path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, content)
+            .with_context(|| format!("Failed to write node registry {}", path.display()))?;
+        std::fs::rename(&tmp_path, path)
+            .with_context(|| format!("Failed to write node registry {}", path.display()))
     }
 }
 
-/// Create the coordinator API router
-pub fn create_router(state: CoordinatorState) -> Router {
-    Router::new()
-        .route("/api/nodes", get(get_nodes))
-        .route("/api/nodes/:node_id/register", post(register_node))
-        .route("/api/nodes/:node_id/snapshot", post(update_snapshot))
-        .route("/api/cluster/snapshot", get(get_cluster_snapshot))
-        .route("/api/cluster/contention", get(get_contention_analysis))
-        .route("/api/cluster/rogue", get(get_rogue_analysis))
-        .route("/api/cluster/rogue/test", get(get_rogue_analysis_test))
-        .route("/api/guard/config", get(get_guard_config))
-        .route("/api/guard/config", post(update_guard_config))
-        .route("/api/guard/policies", get(get_guard_policies))
-        .route("/api/guard/policies", post(update_guard_policies))
-        .route("/api/guard/status", get(get_guard_status))
-        .route("/api/guard/toggle-dry-run", post(toggle_guard_dry_run))
-        .route("/api/guard/test-policies", post(test_guard_policies))
-        .route("/ws", get(websocket_handler))
-        .layer(CorsLayer::permissive())
-        .with_state(state)
+/// Where one target of a cluster reset stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TargetResetStatus {
+    /// Queued; the node has not reported yet
+    Pending,
+    Reset,
+    Failed,
 }
 
-/// Get all nodes
-async fn get_nodes(State(state): State<CoordinatorState>) -> Json<Vec<NodeInfo>> {
-    let nodes = state.get_nodes().await;
-    Json(nodes)
+/// Outcome for one target of a cluster reset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TargetResetOutcome {
+    pub node_id: String,
+    pub gpu_index: u16,
+    pub status: TargetResetStatus,
+    /// How the node reset the GPU, once it reported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mechanism: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
-/// Register a new node
-async fn register_node(
-    State(state): State<CoordinatorState>,
-    Path(_node_id): Path<String>,
-    Json(node_info): Json<NodeInfo>,
-) -> Result<Json<()>, StatusCode> {
-    state
-        .register_node(node_info)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    Ok(Json(()))
+/// A cluster reset and what each node reported so far
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClusterResetReport {
+    pub batch_id: String,
+    pub created_at: DateTime<Utc>,
+    pub force: bool,
+    pub targets: Vec<TargetResetOutcome>,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub pending: usize,
 }
 
-/// Update node snapshot
-async fn update_snapshot(
-    State(state): State<CoordinatorState>,
-    Path(node_id): Path<String>,
-    Json(snapshot): Json<NodeSnapshot>,
-) -> Result<Json<()>, StatusCode> {
-    match state.update_snapshot(node_id, snapshot).await {
-        Ok(()) => Ok(Json(())),
-        Err(e) => {
-            if e.to_string().contains("not registered") {
-                Err(StatusCode::NOT_FOUND)
-            } else {
-                Err(StatusCode::INTERNAL_SERVER_ERROR)
-            }
+impl ClusterResetReport {
+    fn new(batch_id: String, created_at: DateTime<Utc>, force: bool) -> Self {
+        Self {
+            batch_id,
+            created_at,
+            force,
+            targets: Vec::new(),
+            succeeded: 0,
+            failed: 0,
+            pending: 0,
         }
     }
-}
 
-/// Get cluster snapshot
-async fn get_cluster_snapshot(
-    State(state): State<CoordinatorState>,
-) -> Json<Option<ClusterSnapshot>> {
-    let snapshot = state.get_cluster_snapshot().await;
-    Json(snapshot)
-}
+    /// Recount the targets by status
+    fn tally(&mut self) {
+        let count = |status| self.targets.iter().filter(|t| t.status == status).count();
+        self.succeeded = count(TargetResetStatus::Reset);
+        self.failed = count(TargetResetStatus::Failed);
+        self.pending = count(TargetResetStatus::Pending);
+    }
 
-/// Get contention analysis (Magic Moment)
-async fn get_contention_analysis(
-    State(state): State<CoordinatorState>,
-) -> Result<Json<ContentionAnalysis>, StatusCode> {
-    let analysis = state
-        .get_contention_analysis()
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    Ok(Json(analysis))
+    /// Whether every node has reported
+    pub fn is_complete(&self) -> bool {
+        self.pending == 0
+    }
 }
 
-/// Convert cluster node snapshots into audit records for rogue detection.
-/// Each process becomes one record; utilization is attributed from the GPU (proportional share).
-pub(crate) fn snapshots_to_audit_records(
-    snapshots: &[NodeSnapshot],
-) -> Vec<crate::audit::AuditRecord> {
-    use crate::audit::AuditRecord;
+/// Outcome of a `NodeCommand::Reset`, posted by the node to `/api/nodes/:id/reset`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeResetResult {
+    pub batch_id: String,
+    #[serde(flatten)]
+    pub outcome: crate::report::ResetOutcome,
+}
 
-    let mut records = Vec::new();
-    for snapshot in snapshots {
-        let node_id = Some(snapshot.node_id.clone());
-        let timestamp = snapshot.timestamp;
-        for process in &snapshot.processes {
-            let gpu_index = process.gpu_index;
-            let gpu_name = snapshot
-                .gpus
-                .iter()
-                .find(|g| g.gpu_index == gpu_index)
-                .map(|g| g.name.as_str())
-                .unwrap_or("GPU");
-            let process_count = snapshot
-                .processes
+/// Reset GPU `gpu_index` of this host for a cluster reset. Without `force` a GPU
+/// with processes on it is refused, as `--reset` does.
+pub fn run_reset_command(
+    gpu_manager: &crate::vendor::GpuManager,
+    gpu_index: u16,
+    force: bool,
+) -> crate::report::ResetOutcome {
+    use crate::report::{ResetOutcome, ResetStatus};
+
+    let started = std::time::Instant::now();
+    let reset = || -> Result<()> {
+        gpu_manager.check_resettable(gpu_index as u32)?;
+        if !force {
+            let active = gpu_manager
+                .get_all_processes()?
                 .iter()
                 .filter(|p| p.gpu_index == gpu_index)
-                .count()
-                .max(1);
-            let util_pct = snapshot
-                .gpus
-                .iter()
-                .find(|g| g.gpu_index == gpu_index)
-                .map(|g| g.util_pct / process_count as f32)
-                .unwrap_or(0.0);
-
-            let id = timestamp
-                .timestamp_millis()
-                .wrapping_add(process.pid as i64);
-            records.push(AuditRecord {
-                id,
-                timestamp,
-                gpu_index: process.gpu_index,
-                gpu_name: gpu_name.to_string(),
-                pid: Some(process.pid),
-                user: Some(process.user.clone()),
-                process_name: Some(process.proc_name.clone()),
-                memory_used_mb: process.used_mem_mb,
-                utilization_pct: util_pct,
-                temperature_c: 0,
-                power_w: 0.0,
-                container: process.container.clone(),
-                node_id: node_id.clone(),
-            });
+                .count();
+            if active > 0 {
+                anyhow::bail!(
+                    "Cannot reset GPU {} with {} active process(es). Use force to override.",
+                    gpu_index,
+                    active
+                );
+            }
         }
+        gpu_manager.reset_gpu(gpu_index as u32)
+    };
+    let (status, error) = match reset() {
+        Ok(()) => (ResetStatus::Reset, None),
+        Err(e) => (ResetStatus::Failed, Some(e.to_string())),
+    };
+    ResetOutcome {
+        gpu_index,
+        status,
+        mechanism: gpu_manager.reset_mechanism_of(gpu_index as u32).to_string(),
+        duration_ms: Some(started.elapsed().as_millis() as u64),
+        error,
+        waited_ms: None,
+        processes: Vec::new(),
     }
-    records
 }
 
-/// Get rogue activity analysis from cluster snapshots (all registered nodes).
-/// Uses current in-memory snapshots so worker-node rogue activity is included.
-async fn get_rogue_analysis(
-    State(state): State<CoordinatorState>,
-) -> Result<Json<crate::rogue_detection::RogueDetectionResult>, StatusCode> {
-    use crate::audit::AuditManager;
-    use crate::rogue_detection::RogueDetector;
-
-    let snapshots = state.snapshots.read().await;
-    let snapshot_list: Vec<NodeSnapshot> = snapshots.values().cloned().collect();
-    drop(snapshots);
-
-    let records = snapshots_to_audit_records(&snapshot_list);
-
-    let audit_manager = AuditManager::new()
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let detector = RogueDetector::new(audit_manager);
-    let result = detector
-        .detect_rogue_activity_from_records(records)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+/// Latest rogue detection result a node posted
+#[derive(Debug, Clone)]
+pub struct NodeRogueResult {
+    /// When the coordinator received the result; ages are measured from here so
+    /// clock skew between hosts does not make results look fresh or stale
+    pub received_at: DateTime<Utc>,
+    pub result: RogueDetectionResult,
+}
 
-    Ok(Json(result))
+/// One node's line in the cluster rogue report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeRogueSummary {
+    pub node_id: String,
+    pub hostname: String,
+    /// When the node ran its scan; None if it has not reported one yet
+    pub scanned_at: Option<DateTime<Utc>>,
+    /// Seconds since the coordinator received the node's result
+    pub age_secs: Option<u64>,
+    /// The node has no result, or its result is older than the stale threshold
+    pub stale: bool,
+    pub risk_score: f32,
+    pub suspicious_processes: usize,
+    pub crypto_miners: usize,
+    pub resource_abusers: usize,
+    pub data_exfiltrators: usize,
 }
 
-/// Get test rogue activity analysis with sample data
-async fn get_rogue_analysis_test(
-) -> Result<Json<crate::rogue_detection::RogueDetectionResult>, StatusCode> {
-    use crate::nvml_api::GpuProc;
-    use crate::rogue_detection::{
-        AbuseType, CryptoMiner, ResourceAbuser, RiskLevel, RogueDetectionResult, SuspiciousProcess,
-    };
-    use chrono::Utc;
+/// Cluster-wide rogue detection report (`GET /api/cluster/rogue`): the findings of
+/// every node's latest scan, each process tagged with its `node_id`, under the same
+/// field names as a single-host [`RogueDetectionResult`], plus a per-node breakdown
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterRogueReport {
+    pub timestamp: DateTime<Utc>,
+    pub suspicious_processes: Vec<SuspiciousProcess>,
+    pub crypto_miners: Vec<CryptoMiner>,
+    pub resource_abusers: Vec<ResourceAbuser>,
+    pub data_exfiltrators: Vec<DataExfiltrator>,
+    /// Highest risk score of any node; one compromised node puts the cluster at its risk
+    pub risk_score: f32,
+    /// Recommendations of all nodes, each listed once, riskiest node first
+    pub recommendations: Vec<String>,
+    pub nodes: Vec<NodeRogueSummary>,
+    /// Results older than this many seconds are flagged stale
+    pub stale_after_secs: u64,
+}
 
-    let test_result = RogueDetectionResult {
-        timestamp: Utc::now(),
-        suspicious_processes: vec![SuspiciousProcess {
-            process: GpuProc {
-                gpu_index: 0,
-                pid: 12345,
-                user: "hacker".to_string(),
-                proc_name: "suspicious_miner".to_string(),
-                used_mem_mb: 2048,
-                start_time: "2025-09-20T01:00:00Z".to_string(),
-                container: None,
-                node_id: None,
-            },
-            reasons: vec![
-                "High GPU utilization with low CPU usage".to_string(),
-                "Process name contains mining keywords".to_string(),
-                "Unusual memory allocation patterns".to_string(),
-            ],
-            confidence: 0.85,
-            risk_level: RiskLevel::High,
-        }],
-        crypto_miners: vec![CryptoMiner {
-            process: GpuProc {
-                gpu_index: 1,
-                pid: 67890,
-                user: "miner".to_string(),
-                proc_name: "xmrig".to_string(),
-                used_mem_mb: 1024,
-                start_time: "2025-09-20T00:30:00Z".to_string(),
-                container: None,
-                node_id: None,
-            },
-            mining_indicators: vec![
-                "Known cryptocurrency mining software".to_string(),
-                "Extremely high GPU utilization".to_string(),
-                "Long-running process with consistent resource usage".to_string(),
-            ],
-            confidence: 0.92,
-            estimated_hashrate: Some(150.5),
-        }],
-        resource_abusers: vec![ResourceAbuser {
-            process: GpuProc {
-                gpu_index: 2,
-                pid: 11111,
-                user: "abuser".to_string(),
-                proc_name: "gpu_hog".to_string(),
-                used_mem_mb: 8192,
-                start_time: "2025-09-19T20:00:00Z".to_string(),
-                container: None,
-                node_id: None,
-            },
-            abuse_type: AbuseType::MemoryHog,
-            severity: 0.9,
-            duration_hours: 8.5,
-        }],
-        data_exfiltrators: vec![],
-        risk_score: 0.78,
-        recommendations: vec![
-            "🚨 Immediate action required: Terminate crypto mining processes".to_string(),
-            "⚠️ Review user 'miner' and 'hacker' accounts for unauthorized access".to_string(),
-            "🔍 Investigate process 'gpu_hog' for potential resource abuse".to_string(),
-            "📊 Consider implementing GPU usage quotas per user".to_string(),
-        ],
-    };
+impl ClusterRogueReport {
+    /// Combine the latest results of the registered `nodes` as of `now`. Findings
+    /// keep the `node_id` they were stored with; results of nodes that are no longer
+    /// registered are ignored.
+    pub fn aggregate(
+        nodes: &HashMap<String, NodeInfo>,
+        results: &HashMap<String, NodeRogueResult>,
+        stale_after: Duration,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let mut report = Self {
+            timestamp: now,
+            suspicious_processes: Vec::new(),
+            crypto_miners: Vec::new(),
+            resource_abusers: Vec::new(),
+            data_exfiltrators: Vec::new(),
+            risk_score: 0.0,
+            recommendations: Vec::new(),
+            nodes: Vec::new(),
+            stale_after_secs: stale_after.as_secs(),
+        };
 
-    Ok(Json(test_result))
-}
+        let mut reported: Vec<(&NodeInfo, &NodeRogueResult)> = Vec::new();
+        for node in nodes.values() {
+            let Some(latest) = results.get(&node.id) else {
+                report.nodes.push(NodeRogueSummary {
+                    node_id: node.id.clone(),
+                    hostname: node.hostname.clone(),
+                    scanned_at: None,
+                    age_secs: None,
+                    stale: true,
+                    risk_score: 0.0,
+                    suspicious_processes: 0,
+                    crypto_miners: 0,
+                    resource_abusers: 0,
+                    data_exfiltrators: 0,
+                });
+                continue;
+            };
+            let age = (now - latest.received_at).to_std().unwrap_or_default();
+            let result = &latest.result;
+            report.nodes.push(NodeRogueSummary {
+                node_id: node.id.clone(),
+                hostname: node.hostname.clone(),
+                scanned_at: Some(result.timestamp),
+                age_secs: Some(age.as_secs()),
+                stale: age > stale_after,
+                risk_score: result.risk_score,
+                suspicious_processes: result.suspicious_processes.len(),
+                crypto_miners: result.crypto_miners.len(),
+                resource_abusers: result.resource_abusers.len(),
+                data_exfiltrators: result.data_exfiltrators.len(),
+            });
+            reported.push((node, latest));
+        }
+        report.nodes.sort_by(|a, b| {
+            b.risk_score
+                .total_cmp(&a.risk_score)
+                .then_with(|| a.hostname.cmp(&b.hostname))
+                .then_with(|| a.node_id.cmp(&b.node_id))
+        });
+        reported.sort_by(|(a, x), (b, y)| {
+            y.result
+                .risk_score
+                .total_cmp(&x.result.risk_score)
+                .then_with(|| a.hostname.cmp(&b.hostname))
+                .then_with(|| a.id.cmp(&b.id))
+        });
 
-/// WebSocket handler for real-time updates
-async fn websocket_handler(
-    ws: WebSocketUpgrade,
-    State(state): State<CoordinatorState>,
-) -> axum::response::Response {
-    ws.on_upgrade(|socket| websocket_connection(socket, state))
+        let mut seen = HashSet::new();
+        for (_, latest) in reported {
+            let mut result = latest.result.clone();
+            report.risk_score = report.risk_score.max(result.risk_score);
+            report
+                .suspicious_processes
+                .append(&mut result.suspicious_processes);
+            report.crypto_miners.append(&mut result.crypto_miners);
+            report.resource_abusers.append(&mut result.resource_abusers);
+            report
+                .data_exfiltrators
+                .append(&mut result.data_exfiltrators);
+            report.recommendations.extend(
+                result
+                    .recommendations
+                    .into_iter()
+                    .filter(|recommendation| seen.insert(recommendation.clone())),
+            );
+        }
+        report
+    }
 }
 
-/// Handle WebSocket connection
-async fn websocket_connection(socket: axum::extract::ws::WebSocket, state: CoordinatorState) {
-    use axum::extract::ws::Message;
-    use futures_util::{sink::SinkExt, stream::StreamExt};
+/// Coordinator state
+#[derive(Debug, Clone)]
+pub struct CoordinatorState {
+    pub nodes: Arc<RwLock<HashMap<String, NodeInfo>>>,
+    pub snapshots: Arc<RwLock<HashMap<String, NodeSnapshot>>>,
+    pub last_cluster_snapshot: Arc<RwLock<Option<ClusterSnapshot>>>,
+    /// Guard Mode configuration used to report maintenance windows
+    pub guard_config: Arc<RwLock<Option<GuardModeConfig>>>,
+    /// When silent nodes are marked offline and later removed
+    pub node_lifecycle: NodeLifecycle,
+    /// Node, contention, policy and rogue events
+    pub events: ClusterEvents,
+    /// Recent GPU readings served to Grafana
+    pub metrics: MetricHistory,
+    /// Long-lived audit writer that batches node snapshots; without it every upload
+    /// opens the audit store and writes on its own
+    pub audit: Option<Arc<crate::audit::AuditManager>>,
+    /// Latest rogue detection result posted by each node
+    pub rogue_results: Arc<RwLock<HashMap<String, NodeRogueResult>>>,
+    /// Age after which a node's rogue detection result is flagged stale
+    pub rogue_stale_after: Duration,
+    /// Commands waiting for each node to fetch them
+    pub commands: Arc<RwLock<HashMap<String, Vec<NodeCommand>>>>,
+    /// Cluster resets by batch ID, kept for `RESET_BATCH_RETENTION`
+    pub resets: Arc<RwLock<HashMap<String, ClusterResetReport>>>,
+    /// Bearer token required by cluster resets and drains; without one they are refused
+    pub cluster_token: Option<Arc<str>>,
+    /// File the registered nodes are saved to, see `NodeRegistry`
+    pub node_registry: Option<Arc<PathBuf>>,
+}
 
-    let (mut sender, mut receiver) = socket.split();
+/// Default time without a report before a node is marked offline (nodes report every 30s)
+pub const DEFAULT_NODE_OFFLINE_AFTER: Duration = Duration::from_secs(90);
+/// Default time without a report before an offline node is removed
+pub const DEFAULT_NODE_REMOVE_AFTER: Duration = Duration::from_secs(30 * 60);
+
+/// Thresholds, measured from a node's `last_seen`, for marking it `Offline` and
+/// for removing it. Offline nodes stay listed until `remove_after` so operators
+/// can see which node went away.
+#[derive(Debug, Clone, Copy)]
+pub struct NodeLifecycle {
+    pub offline_after: Duration,
+    pub remove_after: Duration,
+}
 
-    // Send initial cluster snapshot
-    if let Some(snapshot) = state.get_cluster_snapshot().await {
-        if let Ok(json) = serde_json::to_string(&snapshot) {
-            let _ = sender.send(Message::Text(json)).await;
+impl Default for NodeLifecycle {
+    fn default() -> Self {
+        Self {
+            offline_after: DEFAULT_NODE_OFFLINE_AFTER,
+            remove_after: DEFAULT_NODE_REMOVE_AFTER,
         }
     }
+}
 
-    // Handle incoming messages and send periodic updates
-    let mut interval = tokio::time::interval(Duration::from_secs(5));
+impl Default for CoordinatorState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-    loop {
-        tokio::select! {
-            _ = interval.tick() => {
-                // Send updated cluster snapshot
-                if let Some(snapshot) = state.get_cluster_snapshot().await {
-                    if let Ok(json) = serde_json::to_string(&snapshot) {
-                        let _ = sender.send(Message::Text(json)).await;
-                    }
-                }
-            }
-            msg = receiver.next() => {
-                match msg {
-                    Some(Ok(Message::Close(_))) | None => break,
-                    Some(Ok(Message::Ping(data))) => {
-                        let _ = sender.send(Message::Pong(data)).await;
-                    }
-                    Some(Err(_)) => break,
-                    _ => {}
-                }
-            }
+impl CoordinatorState {
+    pub fn new() -> Self {
+        Self {
+            nodes: Arc::new(RwLock::new(HashMap::new())),
+            snapshots: Arc::new(RwLock::new(HashMap::new())),
+            last_cluster_snapshot: Arc::new(RwLock::new(None)),
+            guard_config: Arc::new(RwLock::new(None)),
+            node_lifecycle: NodeLifecycle::default(),
+            events: ClusterEvents::new(EVENT_BUFFER_CAPACITY),
+            metrics: MetricHistory::default(),
+            audit: None,
+            rogue_results: Arc::new(RwLock::new(HashMap::new())),
+            rogue_stale_after: DEFAULT_ROGUE_STALE_AFTER,
+            commands: Arc::new(RwLock::new(HashMap::new())),
+            resets: Arc::new(RwLock::new(HashMap::new())),
+            cluster_token: None,
+            node_registry: None,
         }
     }
-}
 
-/// Get Guard Mode configuration
-async fn get_guard_config(
-    State(_state): State<CoordinatorState>,
-) -> Result<Json<crate::guard_mode::GuardModeConfig>, StatusCode> {
-    use crate::guard_mode::GuardModeManager;
+    /// Accept cluster resets and drains from clients and nodes that send `token` as a
+    /// bearer token
+    pub fn with_cluster_token(mut self, token: Option<String>) -> Self {
+        self.cluster_token = token.map(Arc::from);
+        self
+    }
 
-    let guard_manager = GuardModeManager::new().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    /// Save registered nodes to `path` whenever they join, leave or are drained, and
+    /// start from the nodes in `saved`. Those are listed `Offline` until they report
+    /// again, counting as last seen now so they get the usual time to come back.
+    pub fn with_node_registry(mut self, path: PathBuf, saved: NodeRegistry) -> Self {
+        let now = Utc::now();
+        let nodes = saved
+            .nodes
+            .into_iter()
+            .map(|mut node| {
+                node.status = NodeStatus::Offline;
+                node.last_seen = now;
+                (node.id.clone(), node)
+            })
+            .collect();
+        self.nodes = Arc::new(RwLock::new(nodes));
+        self.node_registry = Some(Arc::new(path));
+        self
+    }
 
-    let config = guard_manager.get_config();
-    Ok(Json(config.clone()))
-}
+    /// Write the registered nodes to the node registry, if there is one
+    async fn save_node_registry(&self) {
+        let Some(path) = &self.node_registry else {
+            return;
+        };
+        let mut nodes: Vec<NodeInfo> = self.nodes.read().await.values().cloned().collect();
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+        if let Err(e) = (NodeRegistry { nodes }).save(path) {
+            tracing::warn!("Failed to save registered nodes: {:#}", e);
+        }
+    }
 
-/// Update Guard Mode configuration
-async fn update_guard_config(
-    State(_state): State<CoordinatorState>,
-    Json(config): Json<crate::guard_mode::GuardModeConfig>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    use crate::guard_mode::GuardModeManager;
+    /// Use custom thresholds for marking silent nodes offline and removing them
+    pub fn with_node_lifecycle(mut self, lifecycle: NodeLifecycle) -> Self {
+        self.node_lifecycle = lifecycle;
+        self
+    }
 
-    let mut guard_manager =
-        GuardModeManager::new().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    /// Flag node rogue detection results older than `stale_after` as stale
+    pub fn with_rogue_stale_after(mut self, stale_after: Duration) -> Self {
+        self.rogue_stale_after = stale_after;
+        self
+    }
 
-    guard_manager
-        .update_config(config)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    /// Persist cluster events in `store` (see `ClusterEvents::with_store`)
+    pub fn with_event_store(mut self, store: Arc<dyn AuditStore>) -> Self {
+        self.events = self.events.with_store(store);
+        self
+    }
 
-    Ok(Json(
-        serde_json::json!({"success": true, "message": "Guard Mode configuration updated"}),
-    ))
-}
+    /// Record node snapshots through `audit`, whose buffer the background tasks flush
+    pub fn with_audit_manager(mut self, audit: Arc<crate::audit::AuditManager>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
 
-/// Get Guard Mode policies
-async fn get_guard_policies(
-    State(_state): State<CoordinatorState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    use crate::guard_mode::GuardModeManager;
+    /// Set the Guard Mode configuration whose maintenance windows apply to the cluster
+    pub async fn set_guard_config(&self, config: Option<GuardModeConfig>) {
+        *self.guard_config.write().await = config;
+    }
 
-    let guard_manager = GuardModeManager::new().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    /// Start background tasks for cluster management
+    pub fn start_background_tasks(&self) {
+        let state = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(30));
+            loop {
+                interval.tick().await;
 
-    let config = guard_manager.get_config();
+                // Clean up stale nodes
+                if let Err(e) = state.cleanup_stale_nodes().await {
+                    tracing::warn!("Failed to cleanup stale nodes: {}", e);
+                }
 
-    let policies = serde_json::json!({
-        "user_policies": config.user_policies,
-        "group_policies": config.group_policies,
-        "gpu_policies": config.gpu_policies,
-        "time_policies": config.time_policies,
-        "enforcement": config.enforcement
-    });
+                // Update cluster snapshot
+                if let Err(e) = state.update_cluster_snapshot().await {
+                    tracing::warn!("Failed to update cluster snapshot: {}", e);
+                }
 
-    Ok(Json(policies))
-}
+                // Kill what is left on nodes past an enforced drain deadline
+                state.enforce_drain_deadlines(Utc::now()).await;
 
-/// Update Guard Mode policies
-async fn update_guard_policies(
-    State(_state): State<CoordinatorState>,
-    Json(policies): Json<serde_json::Value>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    use crate::guard_mode::GuardModeManager;
+                // Write audit records held back since the last upload
+                if let Some(audit) = &state.audit {
+                    if let Err(e) = audit.flush().await {
+                        tracing::warn!("Failed to flush audit records: {}", e);
+                    }
+                }
+            }
+        });
+    }
 
-    let mut guard_manager =
-        GuardModeManager::new().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    /// Re-read the Guard Mode config from disk every check interval so edits from
+    /// the CLI or the config API take effect without restarting the server
+    pub fn start_guard_config_watcher(&self, manager: Option<GuardModeManager>) {
+        let state = self.clone();
+        tokio::spawn(async move {
+            let mut manager = manager;
+            loop {
+                let interval_secs = manager
+                    .as_ref()
+                    .map(|m| m.get_config().global.check_interval_seconds.max(1))
+                    .unwrap_or(60);
+                tokio::time::sleep(Duration::from_secs(interval_secs as u64)).await;
+
+                match manager.as_mut() {
+                    Some(manager) => {
+                        if manager.reload_if_changed().is_some() {
+                            state
+                                .set_guard_config(Some(manager.get_config().clone()))
+                                .await;
+                        }
+                    }
+                    None => match GuardModeManager::load_existing() {
+                        Ok(Some(loaded)) => {
+                            state
+                                .set_guard_config(Some(loaded.get_config().clone()))
+                                .await;
+                            manager = Some(loaded);
+                        }
+                        Ok(None) => {}
+                        Err(e) => tracing::warn!("Failed to load Guard Mode config: {}", e),
+                    },
+                }
+            }
+        });
+    }
 
-    // Parse and update policies
-    if let Some(user_policies) = policies.get("user_policies") {
-        if let Ok(user_policies_map) = serde_json::from_value::<
-            std::collections::HashMap<String, crate::guard_mode::UserPolicy>,
-        >(user_policies.clone())
-        {
-            for (_, policy) in user_policies_map {
-                guard_manager
-                    .add_user_policy(policy)
-                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    /// Register or update a node. A drain placed on the node stays in place, and a
+    /// node registering with a drain of its own (kept across an agent restart) is
+    /// draining from the start.
+    pub async fn register_node(&self, mut node_info: NodeInfo) -> Result<()> {
+        let event = ClusterEvent::NodeRegistered {
+            timestamp: Utc::now(),
+            node_id: node_info.id.clone(),
+            hostname: node_info.hostname.clone(),
+            gpu_count: node_info.gpu_count,
+        };
+        let joined = {
+            let mut nodes = self.nodes.write().await;
+            let existing = nodes.get(&node_info.id);
+            let joined = existing.is_none_or(|node| matches!(node.status, NodeStatus::Offline));
+            // A drain placed here outranks the one the node remembers
+            if let Some(drain) = existing.and_then(|node| node.drain.clone()) {
+                node_info.drain = Some(drain);
+            }
+            if let Some(drain) = &node_info.drain {
+                node_info.status = NodeStatus::Draining {
+                    deadline: drain.deadline,
+                };
             }
+            nodes.insert(node_info.id.clone(), node_info);
+            joined
+        };
+        if joined {
+            self.events.publish(vec![event]).await;
+            self.save_node_registry().await;
         }
+        Ok(())
     }
 
-    Ok(Json(
-        serde_json::json!({"success": true, "message": "Policies updated"}),
-    ))
-}
-
-/// Get Guard Mode status
-async fn get_guard_status(
-    State(_state): State<CoordinatorState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    use crate::guard_mode::GuardModeManager;
+    /// Drain a node: its GPUs stop counting as available, owners of its processes
+    /// are told to move their work, and with `enforce` the processes left at the
+    /// deadline are killed. Draining a drained node replaces its drain.
+    pub async fn drain_node(&self, node_id: &str, drain: NodeDrain) -> Result<NodeDrain> {
+        let hostname = {
+            let mut nodes = self.nodes.write().await;
+            let node = nodes
+                .get_mut(node_id)
+                .ok_or_else(|| anyhow::anyhow!("Node {} is not registered", node_id))?;
+            if !matches!(node.status, NodeStatus::Offline) {
+                node.status = NodeStatus::Draining {
+                    deadline: drain.deadline,
+                };
+            }
+            node.drain = Some(drain.clone());
+            node.hostname.clone()
+        };
+        tracing::info!(
+            node_id = %node_id,
+            hostname = %hostname,
+            deadline = ?drain.deadline,
+            enforce = drain.enforce,
+            "Draining node"
+        );
 
-    let guard_manager = GuardModeManager::new().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let processes = self
+            .snapshots
+            .read()
+            .await
+            .get(node_id)
+            .map(|snapshot| snapshot.processes.clone())
+            .unwrap_or_default();
+        let owners: BTreeSet<String> = processes.iter().map(|p| p.user.clone()).collect();
+        self.commands
+            .write()
+            .await
+            .entry(node_id.to_string())
+            .or_default()
+            .push(NodeCommand::Drain {
+                drain: drain.clone(),
+            });
+        self.events
+            .publish(vec![ClusterEvent::NodeDraining {
+                timestamp: drain.since,
+                node_id: node_id.to_string(),
+                hostname,
+                deadline: drain.deadline,
+                enforce: drain.enforce,
+                reason: drain.reason.clone(),
+                processes: processes.len() as u32,
+                owners: owners.into_iter().collect(),
+            }])
+            .await;
+        self.save_node_registry().await;
+        Ok(drain)
+    }
 
-    let config = guard_manager.get_config();
-    let violation_history = guard_manager.get_violation_history();
+    /// End the drain of a node. Returns false if the node was not draining.
+    pub async fn undrain_node(&self, node_id: &str, now: DateTime<Utc>) -> Result<bool> {
+        let hostname = {
+            let mut nodes = self.nodes.write().await;
+            let node = nodes
+                .get_mut(node_id)
+                .ok_or_else(|| anyhow::anyhow!("Node {} is not registered", node_id))?;
+            if node.drain.take().is_none() {
+                return Ok(false);
+            }
+            // The next snapshot tells whether the node is degraded
+            if matches!(node.status, NodeStatus::Draining { .. }) {
+                node.status = NodeStatus::Online;
+            }
+            node.hostname.clone()
+        };
+        tracing::info!(node_id = %node_id, hostname = %hostname, "Node no longer draining");
+
+        let mut commands = self.commands.write().await;
+        let queued = commands.entry(node_id.to_string()).or_default();
+        queued.retain(|command| {
+            !matches!(
+                command,
+                NodeCommand::Drain { .. } | NodeCommand::Kill { .. }
+            )
+        });
+        queued.push(NodeCommand::Undrain);
+        drop(commands);
+
+        self.events
+            .publish(vec![ClusterEvent::NodeUndrained {
+                timestamp: now,
+                node_id: node_id.to_string(),
+                hostname,
+            }])
+            .await;
+        self.save_node_registry().await;
+        Ok(true)
+    }
+
+    /// For each node past the deadline of an enforced drain, queue a kill of the
+    /// processes in its latest snapshot unless one is already waiting. Offline
+    /// nodes are left alone until they report again.
+    pub async fn enforce_drain_deadlines(&self, now: DateTime<Utc>) {
+        let nodes = self.nodes.read().await;
+        let snapshots = self.snapshots.read().await;
+        let mut commands = self.commands.write().await;
+        for (node_id, node) in nodes.iter() {
+            let Some(drain) = &node.drain else {
+                continue;
+            };
+            if !drain.enforce
+                || !drain.deadline_passed(now)
+                || matches!(node.status, NodeStatus::Offline)
+            {
+                continue;
+            }
+            let pids: Vec<u32> = snapshots
+                .get(node_id)
+                .map(|snapshot| snapshot.processes.iter().map(|p| p.pid).collect())
+                .unwrap_or_default();
+            if pids.is_empty() {
+                continue;
+            }
+            let queued = commands.entry(node_id.clone()).or_default();
+            if queued
+                .iter()
+                .any(|command| matches!(command, NodeCommand::Kill { .. }))
+            {
+                continue;
+            }
+            tracing::warn!(
+                node_id = %node_id,
+                hostname = %node.hostname,
+                processes = pids.len(),
+                "Drain deadline passed; killing remaining processes"
+            );
+            queued.push(NodeCommand::Kill {
+                pids,
+                reason: format!(
+                    "Drain deadline {} passed",
+                    drain
+                        .deadline
+                        .map(|deadline| deadline.to_rfc3339())
+                        .unwrap_or_default()
+                ),
+            });
+        }
+    }
+
+    /// Update node snapshot
+    pub async fn update_snapshot(&self, node_id: String, mut snapshot: NodeSnapshot) -> Result<()> {
+        let now = Utc::now();
+        let mut events = Vec::new();
+
+        // Update node last seen
+        let hostname = {
+            let mut nodes = self.nodes.write().await;
+            let node = nodes
+                .get_mut(&node_id)
+                .ok_or_else(|| anyhow::anyhow!("Node {} is not registered", node_id))?;
+            node.last_seen = now;
+            if matches!(node.status, NodeStatus::Offline) {
+                events.push(ClusterEvent::NodeRegistered {
+                    timestamp: now,
+                    node_id: node_id.clone(),
+                    hostname: node.hostname.clone(),
+                    gpu_count: node.gpu_count,
+                });
+            }
+            node.status = match snapshot.status {
+                NodeStatus::Degraded => {
+                    if !matches!(node.status, NodeStatus::Degraded) {
+                        tracing::warn!(
+                            node_id = %node_id,
+                            hostname = %node.hostname,
+                            "Node reports GPUs that did not answer; marking it degraded"
+                        );
+                    }
+                    NodeStatus::Degraded
+                }
+                _ => NodeStatus::Online,
+            };
+            // A drain outranks what the node reports about itself
+            if let Some(drain) = &node.drain {
+                node.status = NodeStatus::Draining {
+                    deadline: drain.deadline,
+                };
+                snapshot.status = node.status.clone();
+            }
+            node.hostname.clone()
+        };
+
+        let processes = snapshot.processes.clone();
+        let gpus = snapshot.gpus.clone();
+        self.metrics.record(&node_id, &hostname, &snapshot);
+
+        // Store snapshot
+        {
+            let mut snapshots = self.snapshots.write().await;
+            snapshots.insert(node_id.clone(), snapshot);
+        }
+
+        // Update cluster snapshot
+        self.update_cluster_snapshot().await?;
+
+        events.extend(self.contention_events(now).await);
+        events.extend(self.policy_events(&node_id, &processes, &gpus, now).await);
+        self.events.publish(events).await;
+        Ok(())
+    }
+
+    /// Rebuild a node's full snapshot from a delta against the one stored for it
+    pub async fn resolve_snapshot_delta(
+        &self,
+        node_id: &str,
+        delta: SnapshotDelta,
+    ) -> Result<NodeSnapshot> {
+        let snapshots = self.snapshots.read().await;
+        let base = snapshots.get(node_id).ok_or_else(|| {
+            anyhow::anyhow!("No snapshot from node {} to apply a delta to", node_id)
+        })?;
+        delta.apply(base)
+    }
+
+    /// GPUs that became blocked or recovered since the previous check
+    async fn contention_events(&self, now: DateTime<Utc>) -> Vec<ClusterEvent> {
+        match self.get_contention_analysis().await {
+            Ok(analysis) => {
+                let nodes: HashSet<String> = self.nodes.read().await.keys().cloned().collect();
+                self.events
+                    .contention_changes(&analysis.blocked_gpus, &nodes, now)
+            }
+            Err(e) => {
+                tracing::warn!("Failed to check contention for events: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Guard Mode violations that started with this snapshot of `node_id`. The
+    /// coordinator only reports them; enforcement stays with each node's guard.
+    async fn policy_events(
+        &self,
+        node_id: &str,
+        processes: &[GpuProc],
+        gpus: &[GpuSnapshot],
+        now: DateTime<Utc>,
+    ) -> Vec<ClusterEvent> {
+        let Some(mut config) = self.guard_config.read().await.clone() else {
+            return Vec::new();
+        };
+        config.global.dry_run = true;
+        let drain = self
+            .nodes
+            .read()
+            .await
+            .get(node_id)
+            .and_then(|node| node.drain.clone());
+        let result = match GuardModeManager::with_config(Default::default(), config)
+            .with_drain(drain)
+            .check_policies_at(processes, gpus, now)
+        {
+            Ok(result) => result,
+            Err(e) => {
+                tracing::warn!(node_id = %node_id, "Failed to check Guard Mode policies: {}", e);
+                return Vec::new();
+            }
+        };
+        let events = result
+            .violations
+            .into_iter()
+            .map(|violation| ClusterEvent::PolicyViolation {
+                timestamp: now,
+                node_id: node_id.to_string(),
+                gpu_index: violation.process.gpu_index,
+                pid: violation.process.pid,
+                user: violation.user,
+                policy_name: violation.policy_name,
+                violation_type: violation.violation_type,
+                severity: violation.severity,
+                message: violation.message,
+            })
+            .collect();
+        self.events.started(&format!("policy:{}", node_id), events)
+    }
+
+    /// Get all nodes
+    pub async fn get_nodes(&self) -> Vec<NodeInfo> {
+        let nodes = self.nodes.read().await;
+        nodes.values().cloned().collect()
+    }
+
+    /// Get cluster snapshot
+    pub async fn get_cluster_snapshot(&self) -> Option<ClusterSnapshot> {
+        let snapshot = self.last_cluster_snapshot.read().await;
+        snapshot.clone()
+    }
+
+    /// Build cluster snapshot from current node data
+    pub async fn build_cluster_snapshot(&self) -> Result<ClusterSnapshot> {
+        let nodes = self.nodes.read().await;
+        let snapshots = self.snapshots.read().await;
+
+        let mut node_snapshots = Vec::new();
+        for (node_id, node_info) in nodes.iter() {
+            if let Some(snapshot) = snapshots.get(node_id) {
+                node_snapshots.push(NodeSnapshot {
+                    node_id: node_id.clone(),
+                    hostname: node_info.hostname.clone(),
+                    timestamp: snapshot.timestamp,
+                    gpus: snapshot.gpus.clone(),
+                    processes: snapshot.processes.clone(),
+                    status: node_info.status.clone(),
+                    tags: node_info.tags.clone(),
+                    errors: snapshot.errors.clone(),
+                });
+            }
+        }
+
+        Ok(ClusterSnapshot::from_nodes(Utc::now(), node_snapshots))
+    }
+
+    /// Update cluster snapshot and cache it
+    pub async fn update_cluster_snapshot(&self) -> Result<()> {
+        let snapshot = self.build_cluster_snapshot().await?;
+        let mut cached = self.last_cluster_snapshot.write().await;
+        *cached = Some(snapshot);
+        Ok(())
+    }
+
+    /// Get contention analysis (Magic Moment)
+    pub async fn get_contention_analysis(&self) -> Result<ContentionAnalysis> {
+        self.get_contention_analysis_for(None).await
+    }
+
+    /// Contention analysis limited to the nodes matching `selector`
+    pub async fn get_contention_analysis_for(
+        &self,
+        selector: Option<&LabelSelector>,
+    ) -> Result<ContentionAnalysis> {
+        let nodes = self.nodes.read().await;
+        let snapshots = self.snapshots.read().await;
+        let mut blocked_gpus = Vec::new();
+        // Track unique (node_id, gpu_index) pairs per user to correctly count GPUs
+        // Tuple: (unique_gpus, memory, utilization_sum, process_count)
+        #[allow(clippy::type_complexity)]
+        let mut user_stats: HashMap<String, (HashSet<(String, u16)>, u32, f32, u32)> =
+            HashMap::new();
+
+        // Maintenance windows are keyed by GPU index and apply on every node
+        let active_windows: HashMap<u16, String> = match self.guard_config.read().await.clone() {
+            Some(config) => GuardModeManager::with_config(Default::default(), config)
+                .active_maintenance_windows(Utc::now())
+                .into_iter()
+                .map(|w| (w.gpu_index, w.message))
+                .collect(),
+            None => HashMap::new(),
+        };
+        let mut maintenance_gpus = Vec::new();
+        let mut idle_gpus = Vec::new();
+        let mut unhealthy_gpus = Vec::new();
+        let mut node_summaries = Vec::new();
+        let mut draining_nodes = Vec::new();
+        let mut total_memory_mb: u64 = 0;
+        let mut total_gpus: u32 = 0;
+
+        for (node_id, node_info) in nodes.iter() {
+            if selector.is_some_and(|s| !s.matches(&node_info.tags)) {
+                continue;
+            }
+            let Some(snapshot) = snapshots.get(node_id) else {
+                continue;
+            };
+            let mut summary = NodeContention {
+                node_id: node_id.clone(),
+                hostname: snapshot.hostname.clone(),
+                total_gpus: snapshot.gpus.len() as u32,
+                blocked_gpus: 0,
+                idle_gpus: 0,
+                maintenance_gpus: 0,
+                unhealthy_gpus: 0,
+                avg_utilization: 0.0,
+                draining: node_info.drain.is_some(),
+            };
+            if let Some(drain) = &node_info.drain {
+                draining_nodes.push((snapshot.hostname.clone(), snapshot.processes.len(), drain));
+            }
+            for gpu in &snapshot.gpus {
+                summary.avg_utilization += gpu.util_pct;
+                total_memory_mb += gpu.mem_total_mb as u64;
+                total_gpus += 1;
+                let in_maintenance = active_windows.contains_key(&gpu.gpu_index);
+                if let Some(message) = active_windows.get(&gpu.gpu_index) {
+                    summary.maintenance_gpus += 1;
+                    maintenance_gpus.push(MaintenanceGpu {
+                        node_id: node_id.clone(),
+                        gpu_index: gpu.gpu_index,
+                        gpu_name: gpu.name.clone(),
+                        message: message.clone(),
+                    });
+                }
+
+                let ecc_uncorrected = gpu.ecc_volatile.map_or(0, |ecc| ecc.uncorrected);
+                let rma_flagged = gpu.rma_flagged.unwrap_or(false);
+                if ecc_uncorrected > 0 || rma_flagged {
+                    summary.unhealthy_gpus += 1;
+                    unhealthy_gpus.push(UnhealthyGpu {
+                        node_id: node_id.clone(),
+                        gpu_index: gpu.gpu_index,
+                        gpu_name: gpu.name.clone(),
+                        ecc_uncorrected,
+                        retired_pages: gpu.retired_pages.map(|pages| pages.total()),
+                        rma_flagged,
+                    });
+                }
+
+                // Find processes using this GPU
+                let gpu_processes: Vec<GpuProc> = snapshot
+                    .processes
+                    .iter()
+                    .filter(|p| p.gpu_index == gpu.gpu_index)
+                    .cloned()
+                    .collect();
+
+                // Check if GPU is blocked (high utilization or memory usage)
+                let is_blocked =
+                    gpu.util_pct > 80.0 || (gpu.mem_used_mb as f32 / gpu.mem_total_mb as f32) > 0.8;
+
+                let memory_ratio = if gpu.mem_total_mb == 0 {
+                    0.0
+                } else {
+                    gpu.mem_used_mb as f32 / gpu.mem_total_mb as f32
+                };
+                if !in_maintenance
+                    && !summary.draining
+                    && gpu_processes.is_empty()
+                    && gpu.util_pct < IDLE_GPU_UTILIZATION_PCT
+                    && memory_ratio < IDLE_GPU_MEMORY_RATIO
+                {
+                    summary.idle_gpus += 1;
+                    idle_gpus.push((node_id.clone(), gpu.gpu_index));
+                }
+
+                if is_blocked && !gpu_processes.is_empty() {
+                    summary.blocked_gpus += 1;
+                    blocked_gpus.push(BlockedGpu {
+                        node_id: node_id.clone(),
+                        gpu_index: gpu.gpu_index,
+                        gpu_name: gpu.name.clone(),
+                        blocking_processes: gpu_processes.clone(),
+                        utilization_pct: gpu.util_pct,
+                        memory_used_mb: gpu.mem_used_mb,
+                        memory_total_mb: gpu.mem_total_mb,
+                    });
+                }
+
+                // Aggregate user statistics
+                for process in &gpu_processes {
+                    let entry = user_stats.entry(process.user.clone()).or_insert((
+                        HashSet::new(),
+                        0,
+                        0.0,
+                        0,
+                    ));
+                    // Track unique (node_id, gpu_index) pairs to correctly count GPUs
+                    if entry.0.insert((node_id.clone(), gpu.gpu_index)) {
+                        entry.2 += gpu.util_pct; // utilization (sum per unique GPU)
+                    }
+                    entry.1 += process.used_mem_mb; // memory
+                    entry.3 += 1; // process_count
+                }
+            }
+            if !snapshot.gpus.is_empty() {
+                summary.avg_utilization /= snapshot.gpus.len() as f32;
+            }
+            node_summaries.push(summary);
+        }
+
+        node_summaries.sort_by(|a, b| {
+            b.blocked_gpus
+                .cmp(&a.blocked_gpus)
+                .then_with(|| a.node_id.cmp(&b.node_id))
+        });
+        idle_gpus.sort();
+        unhealthy_gpus.sort_by(|a, b| {
+            a.node_id
+                .cmp(&b.node_id)
+                .then_with(|| a.gpu_index.cmp(&b.gpu_index))
+        });
+
+        // Convert user stats to UserUsage
+        let mut top_users: Vec<UserUsage> = user_stats
+            .into_iter()
+            .map(
+                |(user, (gpu_set, total_memory_mb, total_util, process_count))| UserUsage {
+                    user,
+                    gpu_count: gpu_set.len() as u32, // Count unique GPUs
+                    total_memory_mb,
+                    avg_utilization: if gpu_set.is_empty() {
+                        0.0
+                    } else {
+                        total_util / gpu_set.len() as f32
+                    },
+                    process_count,
+                },
+            )
+            .collect();
+
+        // Sort by memory usage
+        top_users.sort_by_key(|u| std::cmp::Reverse(u.total_memory_mb));
+        let fair_share_violations = fair_share_violations(&top_users, total_memory_mb, total_gpus);
+        top_users.truncate(10);
+
+        // Generate recommendations
+        let mut recommendations = Vec::new();
+        if !blocked_gpus.is_empty() {
+            recommendations.push(format!(
+                "{} GPUs are currently blocked by high utilization",
+                blocked_gpus.len()
+            ));
+        }
+        if let Some(top_user) = top_users.first() {
+            recommendations.push(format!(
+                "User '{}' is using the most GPU memory ({} MB)",
+                top_user.user, top_user.total_memory_mb
+            ));
+        }
+
+        if !maintenance_gpus.is_empty() {
+            recommendations.push(format!(
+                "{} GPUs are in a maintenance window",
+                maintenance_gpus.len()
+            ));
+        }
+        if !unhealthy_gpus.is_empty() {
+            let rma = unhealthy_gpus.iter().filter(|g| g.rma_flagged).count();
+            recommendations.push(format!(
+                "{} GPUs report uncorrected ECC errors or retired memory ({} flagged for RMA); drain them before scheduling new work",
+                unhealthy_gpus.len(),
+                rma
+            ));
+        }
+        for violation in &fair_share_violations {
+            recommendations.push(format!(
+                "User '{}' holds {:.0}% of GPU memory and {:.0}% of GPU compute, over {}x their fair share of {:.0}%; releasing {} MB would bring them to {} MB",
+                violation.usage.user,
+                violation.memory_share * 100.0,
+                violation.utilization_share * 100.0,
+                FAIR_SHARE_LIMIT,
+                violation.fair_share * 100.0,
+                violation.relinquish_mb,
+                violation.target_mb
+            ));
+        }
+        draining_nodes.sort_by(|a, b| a.0.cmp(&b.0));
+        for (hostname, processes, drain) in draining_nodes {
+            let deadline = drain
+                .deadline
+                .map(|deadline| format!(" before {}", deadline.format("%Y-%m-%d %H:%M UTC")))
+                .unwrap_or_default();
+            recommendations.push(if processes == 0 {
+                format!(
+                    "Node {} is draining and has no GPU processes left",
+                    hostname
+                )
+            } else {
+                format!(
+                    "Node {} is draining: move its {} GPU process{} elsewhere{}",
+                    hostname,
+                    processes,
+                    if processes == 1 { "" } else { "es" },
+                    deadline
+                )
+            });
+        }
+        recommendations.extend(rebalance_recommendations(&node_summaries));
+
+        Ok(ContentionAnalysis {
+            blocked_gpus,
+            top_users,
+            recommendations,
+            maintenance_gpus,
+            node_summaries,
+            idle_gpus,
+            unhealthy_gpus,
+            fair_share_violations,
+        })
+    }
+
+    /// Store the rogue detection result a node ran on its own audit history and
+    /// publish events for processes it flags for the first time. Findings are
+    /// tagged with the node's ID.
+    pub async fn update_rogue_result(
+        &self,
+        node_id: &str,
+        mut result: RogueDetectionResult,
+    ) -> Result<()> {
+        if !self.nodes.read().await.contains_key(node_id) {
+            anyhow::bail!("Node {} is not registered", node_id);
+        }
+        let processes = result
+            .suspicious_processes
+            .iter_mut()
+            .map(|finding| &mut finding.process)
+            .chain(result.crypto_miners.iter_mut().map(|f| &mut f.process))
+            .chain(result.resource_abusers.iter_mut().map(|f| &mut f.process))
+            .chain(result.data_exfiltrators.iter_mut().map(|f| &mut f.process));
+        for process in processes {
+            process.node_id = Some(node_id.to_string());
+        }
+
+        let events = self.events.started(
+            &format!("rogue:{}", node_id),
+            ClusterEvent::from_rogue_result(&result),
+        );
+        self.rogue_results.write().await.insert(
+            node_id.to_string(),
+            NodeRogueResult {
+                received_at: Utc::now(),
+                result,
+            },
+        );
+        self.events.publish(events).await;
+        Ok(())
+    }
+
+    /// Ask every registered node to run rogue detection; returns their IDs
+    pub async fn request_rogue_scans(&self) -> Vec<String> {
+        let mut node_ids: Vec<String> = self.nodes.read().await.keys().cloned().collect();
+        node_ids.sort();
+        let mut commands = self.commands.write().await;
+        for node_id in &node_ids {
+            let pending = commands.entry(node_id.clone()).or_default();
+            if !pending.contains(&NodeCommand::RogueScan) {
+                pending.push(NodeCommand::RogueScan);
+            }
+        }
+        node_ids
+    }
+
+    /// Queue a reset of each target on its node. Targets on unknown or offline
+    /// nodes, or beyond a node's GPU count, fail at once; the rest stay pending
+    /// until their node reports.
+    pub async fn request_cluster_reset(
+        &self,
+        targets: &[ResetTarget],
+        force: bool,
+        now: DateTime<Utc>,
+    ) -> ClusterResetReport {
+        let batch_id = uuid::Uuid::new_v4().to_string();
+        let mut report = ClusterResetReport::new(batch_id.clone(), now, force);
+        let nodes = self.nodes.read().await;
+        let mut commands = self.commands.write().await;
+        let mut seen = HashSet::new();
+        for target in targets {
+            if !seen.insert(target) {
+                continue;
+            }
+            let error = match nodes.get(&target.node_id) {
+                None => Some(format!("Node {} is not registered", target.node_id)),
+                Some(node) if matches!(node.status, NodeStatus::Offline) => {
+                    Some(format!("Node {} is offline", target.node_id))
+                }
+                Some(node) if target.gpu_index as u32 >= node.gpu_count => Some(format!(
+                    "GPU {} not found on node {} ({} GPUs)",
+                    target.gpu_index, target.node_id, node.gpu_count
+                )),
+                Some(_) => None,
+            };
+            if error.is_none() {
+                commands
+                    .entry(target.node_id.clone())
+                    .or_default()
+                    .push(NodeCommand::Reset {
+                        batch_id: batch_id.clone(),
+                        gpu_index: target.gpu_index,
+                        force,
+                    });
+            }
+            report.targets.push(TargetResetOutcome {
+                node_id: target.node_id.clone(),
+                gpu_index: target.gpu_index,
+                status: match error {
+                    Some(_) => TargetResetStatus::Failed,
+                    None => TargetResetStatus::Pending,
+                },
+                mechanism: None,
+                duration_ms: None,
+                error,
+            });
+        }
+        report.tally();
+
+        let mut resets = self.resets.write().await;
+        let retention = chrono::Duration::from_std(RESET_BATCH_RETENTION)
+            .unwrap_or_else(|_| chrono::Duration::hours(1));
+        resets.retain(|_, batch| now - batch.created_at < retention);
+        resets.insert(batch_id, report.clone());
+        report
+    }
+
+    /// Record the outcome a node reported for one target of a cluster reset
+    pub async fn record_reset_result(&self, node_id: &str, result: NodeResetResult) -> Result<()> {
+        let mut resets = self.resets.write().await;
+        let batch = resets
+            .get_mut(&result.batch_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown reset batch {}", result.batch_id))?;
+        let target = batch
+            .targets
+            .iter_mut()
+            .find(|t| {
+                t.node_id == node_id
+                    && t.gpu_index == result.outcome.gpu_index
+                    && t.status == TargetResetStatus::Pending
+            })
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Reset batch {} has no pending reset of GPU {} on node {}",
+                    result.batch_id,
+                    result.outcome.gpu_index,
+                    node_id
+                )
+            })?;
+        target.status = match result.outcome.status {
+            crate::report::ResetStatus::Reset => TargetResetStatus::Reset,
+            _ => TargetResetStatus::Failed,
+        };
+        target.mechanism = Some(result.outcome.mechanism);
+        target.duration_ms = result.outcome.duration_ms;
+        target.error = result.outcome.error;
+        batch.tally();
+        Ok(())
+    }
+
+    /// A cluster reset as reported so far
+    pub async fn reset_report(&self, batch_id: &str) -> Option<ClusterResetReport> {
+        self.resets.read().await.get(batch_id).cloned()
+    }
+
+    /// Commands waiting for `node_id`, which are handed out once
+    pub async fn take_commands(&self, node_id: &str) -> Vec<NodeCommand> {
+        self.commands
+            .write()
+            .await
+            .remove(node_id)
+            .unwrap_or_default()
+    }
+
+    /// Cluster rogue report from the latest result of each registered node
+    pub async fn rogue_report_at(&self, now: DateTime<Utc>) -> ClusterRogueReport {
+        let nodes = self.nodes.read().await;
+        let results = self.rogue_results.read().await;
+        ClusterRogueReport::aggregate(&nodes, &results, self.rogue_stale_after, now)
+    }
+
+    /// Mark silent nodes offline and remove those silent past the removal threshold
+    pub async fn cleanup_stale_nodes(&self) -> Result<()> {
+        self.cleanup_stale_nodes_at(Utc::now()).await
+    }
+
+    /// Apply the node lifecycle as of `now`: nodes not seen for `offline_after`
+    /// become `Offline`, nodes not seen for `remove_after` are removed
+    pub async fn cleanup_stale_nodes_at(&self, now: DateTime<Utc>) -> Result<()> {
+        let lifecycle = self.node_lifecycle;
+        let mut nodes = self.nodes.write().await;
+        let mut snapshots = self.snapshots.write().await;
+
+        let mut stale_nodes = Vec::new();
+        let mut went_offline = Vec::new();
+        for (node_id, node) in nodes.iter_mut() {
+            let silent_for = (now - node.last_seen).to_std().unwrap_or_default();
+            if silent_for >= lifecycle.remove_after {
+                stale_nodes.push(node_id.clone());
+            } else if silent_for >= lifecycle.offline_after
+                && !matches!(node.status, NodeStatus::Offline)
+            {
+                tracing::warn!(
+                    node_id = %node_id,
+                    hostname = %node.hostname,
+                    last_seen = %node.last_seen,
+                    "Node stopped reporting; marking it offline"
+                );
+                node.status = NodeStatus::Offline;
+                went_offline.push((node_id.clone(), node.hostname.clone(), node.last_seen));
+            }
+        }
+
+        let removed_any = !stale_nodes.is_empty();
+        for node_id in stale_nodes {
+            tracing::info!(node_id = %node_id, "Removing node that has been offline too long");
+            nodes.remove(&node_id);
+            snapshots.remove(&node_id);
+        }
+
+        snapshots.retain(|node_id, _| nodes.contains_key(node_id));
+        self.rogue_results
+            .write()
+            .await
+            .retain(|node_id, _| nodes.contains_key(node_id));
+        self.commands
+            .write()
+            .await
+            .retain(|node_id, _| nodes.contains_key(node_id));
+        // A removed node will never report the resets it was sent
+        for batch in self.resets.write().await.values_mut() {
+            let mut orphaned = batch
+                .targets
+                .iter_mut()
+                .filter(|t| {
+                    t.status == TargetResetStatus::Pending && !nodes.contains_key(&t.node_id)
+                })
+                .peekable();
+            if orphaned.peek().is_none() {
+                continue;
+            }
+            for target in orphaned {
+                target.status = TargetResetStatus::Failed;
+                target.error = Some(format!(
+                    "Node {} was removed before reporting",
+                    target.node_id
+                ));
+            }
+            batch.tally();
+        }
+        self.metrics
+            .retain_nodes(|node_id| nodes.contains_key(node_id));
+        drop(snapshots);
+        drop(nodes);
+        if removed_any {
+            self.save_node_registry().await;
+        }
+
+        went_offline.sort();
+        let events = went_offline
+            .into_iter()
+            .map(|(node_id, hostname, last_seen)| ClusterEvent::NodeOffline {
+                timestamp: now,
+                node_id,
+                hostname,
+                last_seen,
+            })
+            .collect();
+        self.events.publish(events).await;
+        Ok(())
+    }
+}
+
+/// Label selectors over node tags, e.g. `rack=a1,gpu in (a100,h100)`
+pub mod selector {
+    use anyhow::{anyhow, bail, Result};
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::str::FromStr;
+
+    /// One comma-separated term of a selector
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum Requirement {
+        /// `key=value` or `key==value`
+        Equals(String, String),
+        /// `key!=value`; also matches nodes without the key
+        NotEquals(String, String),
+        /// `key in (a,b)`
+        In(String, Vec<String>),
+        /// `key notin (a,b)`; also matches nodes without the key
+        NotIn(String, Vec<String>),
+        /// `key`
+        Exists(String),
+        /// `!key`
+        DoesNotExist(String),
+    }
+
+    impl Requirement {
+        pub fn matches(&self, tags: &HashMap<String, String>) -> bool {
+            match self {
+                Requirement::Equals(key, value) => tags.get(key) == Some(value),
+                Requirement::NotEquals(key, value) => tags.get(key) != Some(value),
+                Requirement::In(key, values) => tags.get(key).is_some_and(|v| values.contains(v)),
+                Requirement::NotIn(key, values) => {
+                    !tags.get(key).is_some_and(|v| values.contains(v))
+                }
+                Requirement::Exists(key) => tags.contains_key(key),
+                Requirement::DoesNotExist(key) => !tags.contains_key(key),
+            }
+        }
+    }
+
+    /// All requirements must hold; an empty selector matches every node
+    #[derive(Debug, Clone, Default, PartialEq, Eq)]
+    pub struct LabelSelector {
+        pub requirements: Vec<Requirement>,
+    }
+
+    impl LabelSelector {
+        pub fn matches(&self, tags: &HashMap<String, String>) -> bool {
+            self.requirements.iter().all(|r| r.matches(tags))
+        }
+    }
+
+    impl FromStr for LabelSelector {
+        type Err = anyhow::Error;
+
+        fn from_str(s: &str) -> Result<Self> {
+            let requirements = split_terms(s)?
+                .into_iter()
+                .map(parse_requirement)
+                .collect::<Result<_>>()?;
+            Ok(Self { requirements })
+        }
+    }
+
+    impl fmt::Display for LabelSelector {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let terms: Vec<String> = self
+                .requirements
+                .iter()
+                .map(|r| match r {
+                    Requirement::Equals(k, v) => format!("{}={}", k, v),
+                    Requirement::NotEquals(k, v) => format!("{}!={}", k, v),
+                    Requirement::In(k, vs) => format!("{} in ({})", k, vs.join(",")),
+                    Requirement::NotIn(k, vs) => format!("{} notin ({})", k, vs.join(",")),
+                    Requirement::Exists(k) => k.clone(),
+                    Requirement::DoesNotExist(k) => format!("!{}", k),
+                })
+                .collect();
+            write!(f, "{}", terms.join(","))
+        }
+    }
+
+    /// Parse a `key=value` tag as given to `--node-tag`
+    pub fn parse_tag(s: &str) -> Result<(String, String)> {
+        let (key, value) = s
+            .split_once('=')
+            .ok_or_else(|| anyhow!("expected key=value, got '{}'", s))?;
+        let (key, value) = (key.trim(), value.trim());
+        validate_key(key)?;
+        validate_value(value)?;
+        Ok((key.to_string(), value.to_string()))
+    }
+
+    /// Split on commas outside parentheses, skipping empty terms
+    fn split_terms(s: &str) -> Result<Vec<&str>> {
+        let mut terms = Vec::new();
+        let (mut depth, mut start) = (0usize, 0);
+        for (i, c) in s.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => {
+                    depth = depth
+                        .checked_sub(1)
+                        .ok_or_else(|| anyhow!("unbalanced ')' in selector '{}'", s))?
+                }
+                ',' if depth == 0 => {
+                    terms.push(&s[start..i]);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        if depth != 0 {
+            bail!("unbalanced '(' in selector '{}'", s);
+        }
+        terms.push(&s[start..]);
+        Ok(terms
+            .into_iter()
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .collect())
+    }
+
+    fn parse_requirement(term: &str) -> Result<Requirement> {
+        if let Some(key) = term.strip_prefix('!') {
+            let key = key.trim();
+            validate_key(key)?;
+            return Ok(Requirement::DoesNotExist(key.to_string()));
+        }
+        if let Some((key, value)) = term.split_once("!=") {
+            let (key, value) = (key.trim(), value.trim());
+            validate_key(key)?;
+            validate_value(value)?;
+            return Ok(Requirement::NotEquals(key.to_string(), value.to_string()));
+        }
+        if let Some((key, value)) = term.split_once('=') {
+            let value = value.strip_prefix('=').unwrap_or(value);
+            let (key, value) = (key.trim(), value.trim());
+            validate_key(key)?;
+            validate_value(value)?;
+            return Ok(Requirement::Equals(key.to_string(), value.to_string()));
+        }
+        if let Some(open) = term.find('(') {
+            let mut words = term[..open].split_whitespace();
+            let (Some(key), Some(op), None) = (words.next(), words.next(), words.next()) else {
+                bail!(
+                    "expected 'key in (a,b)' or 'key notin (a,b)', got '{}'",
+                    term
+                );
+            };
+            let list = term[open + 1..]
+                .strip_suffix(')')
+                .ok_or_else(|| anyhow!("expected ')' at the end of '{}'", term))?;
+            let values: Vec<String> = list
+                .split(',')
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+                .collect();
+            if values.is_empty() {
+                bail!("empty value list in '{}'", term);
+            }
+            for value in &values {
+                validate_value(value)?;
+            }
+            validate_key(key)?;
+            return match op {
+                "in" => Ok(Requirement::In(key.to_string(), values)),
+                "notin" => Ok(Requirement::NotIn(key.to_string(), values)),
+                _ => bail!(
+                    "unknown operator '{}' in '{}' (expected in or notin)",
+                    op,
+                    term
+                ),
+            };
+        }
+        validate_key(term)?;
+        Ok(Requirement::Exists(term.to_string()))
+    }
+
+    fn validate_key(key: &str) -> Result<()> {
+        let valid = !key.is_empty()
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'));
+        if !valid {
+            bail!(
+                "invalid tag key '{}': use letters, digits, '-', '_', '.' or '/'",
+                key
+            );
+        }
+        Ok(())
+    }
+
+    fn validate_value(value: &str) -> Result<()> {
+        if value
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, ',' | '(' | ')' | '=' | '!'))
+        {
+            bail!("invalid tag value '{}'", value);
+        }
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn tags(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        }
+
+        #[test]
+        fn test_parse_equality_and_set_requirements() {
+            let selector: LabelSelector = "rack=a1, gpu in (a100, h100),team!=infra,!draining,zone"
+                .parse()
+                .unwrap();
+            assert_eq!(
+                selector.requirements,
+                vec![
+                    Requirement::Equals("rack".into(), "a1".into()),
+                    Requirement::In("gpu".into(), vec!["a100".into(), "h100".into()]),
+                    Requirement::NotEquals("team".into(), "infra".into()),
+                    Requirement::DoesNotExist("draining".into()),
+                    Requirement::Exists("zone".into()),
+                ]
+            );
+            assert_eq!(
+                selector.to_string(),
+                "rack=a1,gpu in (a100,h100),team!=infra,!draining,zone"
+            );
+
+            let selector: LabelSelector = "team==nlp,gpu notin (t4)".parse().unwrap();
+            assert_eq!(
+                selector.requirements,
+                vec![
+                    Requirement::Equals("team".into(), "nlp".into()),
+                    Requirement::NotIn("gpu".into(), vec!["t4".into()]),
+                ]
+            );
+            assert!("".parse::<LabelSelector>().unwrap().requirements.is_empty());
+        }
+
+        #[test]
+        fn test_selector_matching() {
+            let node = tags(&[("rack", "a1"), ("gpu", "a100"), ("team", "nlp")]);
+            let matches = |s: &str| s.parse::<LabelSelector>().unwrap().matches(&node);
+
+            assert!(matches(""));
+            assert!(matches("rack=a1,gpu=a100"));
+            assert!(!matches("rack=a1,gpu=h100"));
+            assert!(matches("gpu in (a100,h100)"));
+            assert!(!matches("gpu notin (a100)"));
+            assert!(matches("team!=infra"));
+            assert!(matches("owner!=bob"));
+            assert!(matches("zone notin (us)"));
+            assert!(matches("team,!draining"));
+            assert!(!matches("zone"));
+        }
+
+        #[test]
+        fn test_malformed_selectors_are_rejected() {
+            for bad in [
+                "gpu in (a100",
+                "gpu in a100)",
+                "gpu within (a100)",
+                "gpu in ()",
+                "=a1",
+                "rack=a 1",
+                "bad key=x",
+            ] {
+                assert!(bad.parse::<LabelSelector>().is_err(), "{}", bad);
+            }
+        }
+
+        #[test]
+        fn test_parse_tag() {
+            assert_eq!(
+                parse_tag("team = nlp").unwrap(),
+                ("team".to_string(), "nlp".to_string())
+            );
+            assert!(parse_tag("team").is_err());
+            assert!(parse_tag("=nlp").is_err());
+        }
+    }
+}
+
+pub use selector::LabelSelector;
+
+/// Create the coordinator API router
+pub fn create_router(state: CoordinatorState) -> Router {
+    Router::new()
+        .route("/api/nodes", get(get_nodes))
+        .route("/api/nodes/:node_id/register", post(register_node))
+        .route("/api/nodes/:node_id/snapshot", post(update_snapshot))
+        .route("/api/nodes/:node_id/rogue", post(update_rogue_result))
+        .route("/api/nodes/:node_id/commands", get(get_node_commands))
+        .route("/api/cluster/snapshot", get(get_cluster_snapshot))
+        .route("/api/cluster/contention", get(get_contention_analysis))
+        .route("/api/cluster/contention/report", get(get_contention_report))
+        .route("/api/cluster/accounting", get(get_cluster_accounting))
+        .route("/api/cluster/events", get(get_cluster_events))
+        .route("/api/cluster/rogue", get(get_rogue_analysis))
+        .route("/api/cluster/reset", post(request_cluster_reset))
+        .route("/api/cluster/reset/:batch_id", get(get_cluster_reset))
+        .route("/api/nodes/:node_id/reset", post(update_reset_result))
+        .route("/api/nodes/:node_id/drain", post(drain_node))
+        .route("/api/nodes/:node_id/undrain", post(undrain_node))
+        .route("/api/cluster/rogue/scan", post(request_rogue_scans))
+        .route("/api/cluster/rogue/test", get(get_rogue_analysis_test))
+        .route("/api/guard/config", get(get_guard_config))
+        .route("/api/guard/config", post(update_guard_config))
+        .route("/api/guard/policies", get(get_guard_policies))
+        .route("/api/guard/policies", post(update_guard_policies))
+        .route("/api/guard/status", get(get_guard_status))
+        .route("/api/guard/toggle-dry-run", post(toggle_guard_dry_run))
+        .route("/api/guard/test-policies", post(test_guard_policies))
+        .route("/grafana", get(grafana_health))
+        .route("/grafana/", get(grafana_health))
+        .route("/grafana/search", post(grafana_search))
+        .route("/grafana/query", post(grafana_query))
+        .route("/ws", get(websocket_handler))
+        .layer(CorsLayer::permissive())
+        .with_state(state)
+}
+
+/// Query parameter restricting a response to nodes whose tags match a label selector
+#[derive(Debug, Default, Deserialize)]
+pub struct SelectorQuery {
+    /// e.g. `rack=a1,gpu in (a100,h100)`
+    pub selector: Option<String>,
+}
+
+impl SelectorQuery {
+    /// Parse the selector, rejecting malformed ones with 400 Bad Request
+    pub fn parse(&self) -> Result<Option<LabelSelector>, StatusCode> {
+        self.selector
+            .as_deref()
+            .map(|s| s.parse::<LabelSelector>())
+            .transpose()
+            .map_err(|e| {
+                tracing::debug!("Rejecting label selector: {}", e);
+                StatusCode::BAD_REQUEST
+            })
+    }
+}
+
+/// Get all nodes
+async fn get_nodes(
+    State(state): State<CoordinatorState>,
+    Query(query): Query<SelectorQuery>,
+) -> Result<Json<Vec<NodeListing>>, StatusCode> {
+    let selector = query.parse()?;
+    let now = Utc::now();
+    let nodes = state
+        .get_nodes()
+        .await
+        .into_iter()
+        .filter(|node| selector.as_ref().is_none_or(|s| s.matches(&node.tags)))
+        .map(|node| NodeListing::new(node, now))
+        .collect();
+    Ok(Json(nodes))
+}
+
+/// Register a new node
+async fn register_node(
+    State(state): State<CoordinatorState>,
+    Path(_node_id): Path<String>,
+    Json(node_info): Json<NodeInfo>,
+) -> Result<Json<()>, StatusCode> {
+    state
+        .register_node(node_info)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(()))
+}
+
+/// Update node snapshot. The body may be gzipped (`Content-Encoding: gzip`) and
+/// may be a delta against the node's previous snapshot; a delta that does not
+/// fit the stored snapshot gets 409 Conflict so the node sends it in full.
+async fn update_snapshot(
+    State(state): State<CoordinatorState>,
+    Path(node_id): Path<String>,
+    headers: axum::http::HeaderMap,
+    body: axum::body::Bytes,
+) -> Result<Json<()>, StatusCode> {
+    let gzip = match headers
+        .get(axum::http::header::CONTENT_ENCODING)
+        .map(|value| value.to_str().unwrap_or_default().trim())
+    {
+        None | Some("identity") => false,
+        Some(encoding) if encoding.eq_ignore_ascii_case("gzip") => true,
+        Some(_) => return Err(StatusCode::UNSUPPORTED_MEDIA_TYPE),
+    };
+    let upload = SnapshotUpload::decode(&body, gzip).map_err(|e| {
+        tracing::debug!(node_id = %node_id, "Rejecting snapshot upload: {}", e);
+        StatusCode::BAD_REQUEST
+    })?;
+    let snapshot = match upload {
+        SnapshotUpload::Full(snapshot) => snapshot,
+        SnapshotUpload::Delta { delta } => state
+            .resolve_snapshot_delta(&node_id, delta)
+            .await
+            .map_err(|e| {
+                tracing::debug!(node_id = %node_id, "Asking for a full snapshot: {}", e);
+                StatusCode::CONFLICT
+            })?,
+    };
+
+    // Persist process samples with their node_id so cluster accounting has history
+    let records = snapshots_to_audit_records(std::slice::from_ref(&snapshot));
+
+    match state.update_snapshot(node_id, snapshot).await {
+        Ok(()) => {
+            if !records.is_empty() {
+                let result = match &state.audit {
+                    Some(audit_manager) => audit_manager.append_records(&records).await,
+                    None => match crate::audit::AuditManager::new().await {
+                        Ok(audit_manager) => audit_manager.append_records(&records).await,
+                        Err(e) => Err(e.context("Failed to open audit log")),
+                    },
+                };
+                if let Err(e) = result {
+                    tracing::warn!("Failed to record node snapshot in audit log: {}", e);
+                }
+            }
+            Ok(Json(()))
+        }
+        Err(e) => {
+            if e.to_string().contains("not registered") {
+                Err(StatusCode::NOT_FOUND)
+            } else {
+                Err(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        }
+    }
+}
+
+/// Get cluster snapshot
+async fn get_cluster_snapshot(
+    State(state): State<CoordinatorState>,
+    Query(query): Query<SelectorQuery>,
+) -> Result<Json<Option<ClusterSnapshot>>, StatusCode> {
+    let selector = query.parse()?;
+    let snapshot = state.get_cluster_snapshot().await;
+    Ok(Json(match selector {
+        Some(selector) => snapshot.map(|s| s.select(&selector)),
+        None => snapshot,
+    }))
+}
+
+/// Get contention analysis (Magic Moment)
+async fn get_contention_analysis(
+    State(state): State<CoordinatorState>,
+    Query(query): Query<SelectorQuery>,
+) -> Result<Json<ContentionAnalysis>, StatusCode> {
+    let selector = query.parse()?;
+    let analysis = state
+        .get_contention_analysis_for(selector.as_ref())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(analysis))
+}
+
+/// Query parameters for the contention report
+#[derive(Debug, Deserialize)]
+pub struct ContentionReportQuery {
+    /// "markdown" (default) or "json"
+    pub format: Option<String>,
+    /// Only report on nodes whose tags match this label selector
+    pub selector: Option<String>,
+}
+
+/// Contention analysis with the time it was taken, as served by the JSON report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentionReport {
+    pub generated_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub analysis: ContentionAnalysis,
+}
+
+/// Download the contention analysis as a shareable Markdown or JSON file
+async fn get_contention_report(
+    State(state): State<CoordinatorState>,
+    Query(query): Query<ContentionReportQuery>,
+) -> Result<axum::response::Response, StatusCode> {
+    use axum::http::header;
+    use axum::response::IntoResponse;
+
+    let selector = SelectorQuery {
+        selector: query.selector.clone(),
+    }
+    .parse()?;
+    let analysis = state
+        .get_contention_analysis_for(selector.as_ref())
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let generated_at = Utc::now();
+    let stem = format!(
+        "contention-report-{}",
+        generated_at.format("%Y%m%dT%H%M%SZ")
+    );
+
+    match query.format.as_deref().unwrap_or("markdown") {
+        "markdown" | "md" => Ok((
+            [
+                (
+                    header::CONTENT_TYPE,
+                    "text/markdown; charset=utf-8".to_string(),
+                ),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}.md\"", stem),
+                ),
+            ],
+            analysis.to_markdown(generated_at),
+        )
+            .into_response()),
+        "json" => Ok((
+            [(
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.json\"", stem),
+            )],
+            Json(ContentionReport {
+                generated_at,
+                analysis,
+            }),
+        )
+            .into_response()),
+        _ => Err(StatusCode::BAD_REQUEST),
+    }
+}
+
+/// Query parameters for cluster accounting
+#[derive(Debug, Deserialize)]
+pub struct AccountingQuery {
+    /// Range start (YYYY-MM-DD or RFC3339); defaults to 24 hours before `to`
+    pub from: Option<String>,
+    /// Range end (YYYY-MM-DD or RFC3339); defaults to now
+    pub to: Option<String>,
+    pub max_gap_secs: Option<u64>,
+}
+
+/// Get per-user and per-process GPU-hours across all nodes that reported snapshots
+async fn get_cluster_accounting(
+    Query(query): Query<AccountingQuery>,
+) -> Result<Json<crate::audit::AccountingReport>, StatusCode> {
+    use crate::audit::{AuditManager, DEFAULT_ACCOUNTING_MAX_GAP_SECS};
+    use crate::util::parse_date_or_timestamp;
+
+    let end = match query.to.as_deref() {
+        Some(to) => parse_date_or_timestamp(to).map_err(|_| StatusCode::BAD_REQUEST)?,
+        None => Utc::now(),
+    };
+    let start = match query.from.as_deref() {
+        Some(from) => parse_date_or_timestamp(from).map_err(|_| StatusCode::BAD_REQUEST)?,
+        None => end - chrono::Duration::hours(24),
+    };
+    if start > end {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let audit_manager = AuditManager::new()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let report = audit_manager
+        .get_accounting(
+            start,
+            end,
+            query
+                .max_gap_secs
+                .unwrap_or(DEFAULT_ACCOUNTING_MAX_GAP_SECS),
+        )
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(report))
+}
+
+/// Query parameters for `GET /api/cluster/events`
+#[derive(Debug, Default, Deserialize)]
+pub struct EventsQuery {
+    /// Only events strictly after this time (YYYY-MM-DD or RFC3339); omitted
+    /// returns the events still in memory
+    pub since: Option<String>,
+    /// Maximum number of events, at most `EVENT_PAGE_LIMIT`
+    pub limit: Option<usize>,
+}
+
+/// Get cluster events, oldest first. Clients page forward by passing the last
+/// event's timestamp as `since`.
+async fn get_cluster_events(
+    State(state): State<CoordinatorState>,
+    Query(query): Query<EventsQuery>,
+) -> Result<Json<Vec<ClusterEvent>>, StatusCode> {
+    let since = query
+        .since
+        .as_deref()
+        .map(crate::util::parse_date_or_timestamp)
+        .transpose()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let limit = query
+        .limit
+        .unwrap_or(EVENT_PAGE_LIMIT)
+        .min(EVENT_PAGE_LIMIT);
+
+    let events = state.events.since(since, limit).await.map_err(|e| {
+        tracing::warn!("Failed to read cluster events: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(Json(events))
+}
+
+/// Grafana's datasource connection test
+async fn grafana_health() -> StatusCode {
+    StatusCode::OK
+}
+
+/// Metric names for Grafana's query editor
+async fn grafana_search(
+    State(state): State<CoordinatorState>,
+    Json(request): Json<crate::grafana::SearchRequest>,
+) -> Json<Vec<String>> {
+    Json(crate::grafana::search(&state.metrics, &request))
+}
+
+/// Timeseries and table data for Grafana panels
+async fn grafana_query(
+    State(state): State<CoordinatorState>,
+    Json(request): Json<crate::grafana::QueryRequest>,
+) -> Result<Json<Vec<crate::grafana::QueryResponse>>, StatusCode> {
+    let cluster = state.get_cluster_snapshot().await;
+    crate::grafana::query(&state.metrics, cluster.as_ref(), &request)
+        .map(Json)
+        .map_err(|e| {
+            tracing::debug!("Rejected Grafana query: {}", e);
+            StatusCode::BAD_REQUEST
+        })
+}
+
+/// Convert node snapshots into audit records, so node history can be queried for
+/// accounting and rogue detection. Each process becomes one record; utilization is
+/// attributed from the GPU (proportional share).
+pub(crate) fn snapshots_to_audit_records(
+    snapshots: &[NodeSnapshot],
+) -> Vec<crate::audit::AuditRecord> {
+    use crate::audit::AuditRecord;
+
+    let mut records = Vec::new();
+    for snapshot in snapshots {
+        let node_id = Some(snapshot.node_id.clone());
+        let timestamp = snapshot.timestamp;
+        for process in &snapshot.processes {
+            let gpu_index = process.gpu_index;
+            let gpu = snapshot.gpus.iter().find(|g| g.gpu_index == gpu_index);
+            let gpu_name = gpu.map(|g| g.name.as_str()).unwrap_or("GPU");
+            let process_count = snapshot
+                .processes
+                .iter()
+                .filter(|p| p.gpu_index == gpu_index)
+                .count()
+                .max(1);
+            let util_pct = gpu
+                .map(|g| g.util_pct / process_count as f32)
+                .unwrap_or(0.0);
+
+            let id = timestamp
+                .timestamp_millis()
+                .wrapping_add(process.pid as i64);
+            records.push(AuditRecord {
+                id,
+                timestamp,
+                gpu_index: process.gpu_index,
+                gpu_name: gpu_name.to_string(),
+                pid: Some(process.pid),
+                user: Some(process.user.clone()),
+                process_name: Some(process.proc_name.clone()),
+                memory_used_mb: process.used_mem_mb,
+                utilization_pct: util_pct,
+                temperature_c: 0,
+                power_w: 0.0,
+                container: process.container.clone(),
+                node_id: node_id.clone(),
+                hostname: Some(snapshot.hostname.clone()),
+                cmdline: process.cmdline.clone(),
+                ecc_uncorrected: None,
+                ecc_uncorrected_delta: None,
+                pcie_mb_per_sec: gpu.and_then(crate::audit::pcie_mb_per_sec),
+                nvlink_bytes: gpu.and_then(|g| g.nvlink_bytes),
+                slurm_job_id: process.slurm_job_id.clone(),
+                start_unix_secs: process.start_unix_secs,
+            });
+        }
+    }
+    records
+}
+
+/// Cluster rogue report combining the latest scan each node posted
+async fn get_rogue_analysis(
+    State(state): State<CoordinatorState>,
+) -> Result<Json<ClusterRogueReport>, StatusCode> {
+    Ok(Json(state.rogue_report_at(Utc::now()).await))
+}
+
+/// Ask every node to run rogue detection now; returns the IDs of the nodes asked
+async fn request_rogue_scans(
+    State(state): State<CoordinatorState>,
+) -> Result<Json<Vec<String>>, StatusCode> {
+    Ok(Json(state.request_rogue_scans().await))
+}
+
+/// Store the rogue detection result a node ran on its own audit history
+async fn update_rogue_result(
+    State(state): State<CoordinatorState>,
+    Path(node_id): Path<String>,
+    Json(result): Json<RogueDetectionResult>,
+) -> Result<Json<()>, StatusCode> {
+    state
+        .update_rogue_result(&node_id, result)
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    Ok(Json(()))
+}
+
+/// Refuse cluster resets and drains unless the request carries the cluster token;
+/// with no token configured they are refused outright
+fn authorize_cluster_token(
+    state: &CoordinatorState,
+    headers: &axum::http::HeaderMap,
+) -> Result<(), StatusCode> {
+    let Some(token) = &state.cluster_token else {
+        tracing::debug!("Refusing cluster request: the coordinator has no --cluster-token");
+        return Err(StatusCode::FORBIDDEN);
+    };
+    if !crate::util::bearer_token_matches(headers, token) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    Ok(())
+}
+
+/// Reset GPUs on many nodes: queue a reset command for each target's node and,
+/// with `wait_secs`, wait for the nodes to report. Answers 202 Accepted while
+/// targets are still pending; poll `GET /api/cluster/reset/:batch_id` for the rest.
+async fn request_cluster_reset(
+    State(state): State<CoordinatorState>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<ClusterResetRequest>,
+) -> Result<(StatusCode, Json<ClusterResetReport>), StatusCode> {
+    authorize_cluster_token(&state, &headers)?;
+    if request.targets.is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let mut report = state
+        .request_cluster_reset(&request.targets, request.force, Utc::now())
+        .await;
+    let deadline =
+        tokio::time::Instant::now() + Duration::from_secs(request.wait_secs).min(MAX_RESET_WAIT);
+    while !report.is_complete() && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        match state.reset_report(&report.batch_id).await {
+            Some(latest) => report = latest,
+            None => break,
+        }
+    }
+    let status = if report.is_complete() {
+        StatusCode::OK
+    } else {
+        StatusCode::ACCEPTED
+    };
+    Ok((status, Json(report)))
+}
+
+/// A cluster reset with the outcome each node reported so far
+async fn get_cluster_reset(
+    State(state): State<CoordinatorState>,
+    headers: axum::http::HeaderMap,
+    Path(batch_id): Path<String>,
+) -> Result<Json<ClusterResetReport>, StatusCode> {
+    authorize_cluster_token(&state, &headers)?;
+    state
+        .reset_report(&batch_id)
+        .await
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Store the outcome of a reset command a node ran
+async fn update_reset_result(
+    State(state): State<CoordinatorState>,
+    headers: axum::http::HeaderMap,
+    Path(node_id): Path<String>,
+    Json(result): Json<NodeResetResult>,
+) -> Result<Json<()>, StatusCode> {
+    authorize_cluster_token(&state, &headers)?;
+    state
+        .record_reset_result(&node_id, result)
+        .await
+        .map_err(|e| {
+            tracing::debug!("Rejecting reset result from node {}: {}", node_id, e);
+            StatusCode::NOT_FOUND
+        })?;
+    Ok(Json(()))
+}
+
+/// Drain a node for maintenance
+async fn drain_node(
+    State(state): State<CoordinatorState>,
+    headers: axum::http::HeaderMap,
+    Path(node_id): Path<String>,
+    Json(request): Json<DrainRequest>,
+) -> Result<Json<NodeDrain>, StatusCode> {
+    authorize_cluster_token(&state, &headers)?;
+    let drain = request.into_drain(Utc::now()).map_err(|e| {
+        tracing::debug!("Rejecting drain of node {}: {}", node_id, e);
+        StatusCode::BAD_REQUEST
+    })?;
+    state
+        .drain_node(&node_id, drain)
+        .await
+        .map(Json)
+        .map_err(|_| StatusCode::NOT_FOUND)
+}
+
+/// End the drain of a node; ending a drain that is not in place is not an error
+async fn undrain_node(
+    State(state): State<CoordinatorState>,
+    headers: axum::http::HeaderMap,
+    Path(node_id): Path<String>,
+) -> Result<Json<()>, StatusCode> {
+    authorize_cluster_token(&state, &headers)?;
+    state
+        .undrain_node(&node_id, Utc::now())
+        .await
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    Ok(Json(()))
+}
+
+/// Hand a node the commands waiting for it. Handing them out removes them, so with
+/// a cluster token configured only callers presenting it get them; without one,
+/// resets, drains and drain-deadline kills cannot be queued and only rogue scans are.
+async fn get_node_commands(
+    State(state): State<CoordinatorState>,
+    headers: axum::http::HeaderMap,
+    Path(node_id): Path<String>,
+) -> Result<Json<Vec<NodeCommand>>, StatusCode> {
+    if state.cluster_token.is_some() {
+        authorize_cluster_token(&state, &headers)?;
+    }
+    Ok(Json(state.take_commands(&node_id).await))
+}
+
+/// Get test rogue activity analysis with sample data
+async fn get_rogue_analysis_test(
+) -> Result<Json<crate::rogue_detection::RogueDetectionResult>, StatusCode> {
+    use crate::nvml_api::GpuProc;
+    use crate::rogue_detection::{
+        AbuseType, CryptoMiner, ResourceAbuser, RiskLevel, RogueDetectionResult, SuspiciousProcess,
+    };
+    use chrono::Utc;
+
+    let test_result = RogueDetectionResult {
+        timestamp: Utc::now(),
+        suspicious_processes: vec![SuspiciousProcess {
+            process: GpuProc {
+                gpu_index: 0,
+                pid: 12345,
+                user: "hacker".to_string(),
+                proc_name: "suspicious_miner".to_string(),
+                used_mem_mb: 2048,
+                start_time: "2025-09-20T01:00:00Z".to_string(),
+                ..Default::default()
+            },
+            reasons: vec![
+                "High GPU utilization with low CPU usage".to_string(),
+                "Process name contains mining keywords".to_string(),
+                "Unusual memory allocation patterns".to_string(),
+            ],
+            confidence: 0.85,
+            risk_level: RiskLevel::High,
+        }],
+        crypto_miners: vec![CryptoMiner {
+            process: GpuProc {
+                gpu_index: 1,
+                pid: 67890,
+                user: "miner".to_string(),
+                proc_name: "xmrig".to_string(),
+                used_mem_mb: 1024,
+                start_time: "2025-09-20T00:30:00Z".to_string(),
+                ..Default::default()
+            },
+            mining_indicators: vec![
+                "Known cryptocurrency mining software".to_string(),
+                "Extremely high GPU utilization".to_string(),
+                "Long-running process with consistent resource usage".to_string(),
+            ],
+            confidence: 0.92,
+            estimated_hashrate: Some(150.5),
+        }],
+        resource_abusers: vec![ResourceAbuser {
+            process: GpuProc {
+                gpu_index: 2,
+                pid: 11111,
+                user: "abuser".to_string(),
+                proc_name: "gpu_hog".to_string(),
+                used_mem_mb: 8192,
+                start_time: "2025-09-19T20:00:00Z".to_string(),
+                ..Default::default()
+            },
+            abuse_type: AbuseType::MemoryHog,
+            severity: 0.9,
+            duration_hours: 8.5,
+        }],
+        data_exfiltrators: vec![],
+        risk_score: 0.78,
+        recommendations: vec![
+            "🚨 Immediate action required: Terminate crypto mining processes".to_string(),
+            "⚠️ Review user 'miner' and 'hacker' accounts for unauthorized access".to_string(),
+            "🔍 Investigate process 'gpu_hog' for potential resource abuse".to_string(),
+            "📊 Consider implementing GPU usage quotas per user".to_string(),
+        ],
+    };
+
+    Ok(Json(test_result))
+}
+
+/// WebSocket handler for real-time updates
+async fn websocket_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<CoordinatorState>,
+) -> axum::response::Response {
+    ws.on_upgrade(|socket| websocket_connection(socket, state))
+}
+
+/// Interval between WebSocket pushes
+const WS_PUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Interval between full resyncs, on the default stream and for subscribed topics
+const WS_RESYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Frames queued per client before it is considered too slow and dropped
+const WS_SEND_BUFFER: usize = 16;
+
+/// A WebSocket subscription topic
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum WsTopic {
+    /// All nodes, one entry per node
+    Nodes,
+    /// Cluster contention analysis
+    Contention,
+    /// A single node, one entry per GPU plus its processes and status
+    Node(String),
+    /// Cluster events, pushed one `event` frame each as they happen
+    Events,
+}
+
+impl std::str::FromStr for WsTopic {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "nodes" => Ok(WsTopic::Nodes),
+            "contention" => Ok(WsTopic::Contention),
+            "events" => Ok(WsTopic::Events),
+            _ => match s.strip_prefix("node:") {
+                Some(id) if !id.is_empty() => Ok(WsTopic::Node(id.to_string())),
+                _ => Err(format!(
+                    "unknown topic '{}' (expected nodes, contention, events or node:<id>)",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for WsTopic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WsTopic::Nodes => write!(f, "nodes"),
+            WsTopic::Contention => write!(f, "contention"),
+            WsTopic::Node(id) => write!(f, "node:{}", id),
+            WsTopic::Events => write!(f, "events"),
+        }
+    }
+}
+
+/// Message sent by a WebSocket client
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WsClientMessage {
+    #[serde(default)]
+    subscribe: Vec<String>,
+    #[serde(default)]
+    unsubscribe: Vec<String>,
+}
+
+/// Message pushed to a subscribed WebSocket client
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WsServerMessage {
+    /// Complete state of a topic, replacing anything the client holds
+    Full {
+        topic: String,
+        data: BTreeMap<String, serde_json::Value>,
+    },
+    /// Entries that changed or disappeared since the previous push
+    Delta {
+        topic: String,
+        changed: BTreeMap<String, serde_json::Value>,
+        removed: Vec<String>,
+    },
+    /// A cluster event, on the `events` topic
+    Event { event: ClusterEvent },
+    /// The client sent something the server could not understand
+    Error { message: String },
+}
+
+type TopicState = BTreeMap<String, serde_json::Value>;
+
+/// Serialize a value for topic state, dropping its `timestamp` so entries only
+/// count as changed when their contents change
+fn topic_value<T: Serialize>(value: &T) -> serde_json::Value {
+    let mut value = serde_json::to_value(value).unwrap_or(serde_json::Value::Null);
+    if let Some(object) = value.as_object_mut() {
+        object.remove("timestamp");
+    }
+    value
+}
+
+/// Build the keyed state of a topic from the latest cluster data
+fn topic_state(
+    topic: &WsTopic,
+    snapshot: Option<&ClusterSnapshot>,
+    contention: Option<&ContentionAnalysis>,
+) -> TopicState {
+    let mut state = TopicState::new();
+    match topic {
+        WsTopic::Nodes => {
+            for node in snapshot.iter().flat_map(|s| &s.nodes) {
+                state.insert(node.node_id.clone(), topic_value(node));
+            }
+        }
+        WsTopic::Contention => {
+            if let Some(serde_json::Value::Object(fields)) = contention.map(topic_value) {
+                state.extend(fields);
+            }
+        }
+        WsTopic::Node(id) => {
+            let node = snapshot.and_then(|s| s.nodes.iter().find(|n| &n.node_id == id));
+            if let Some(node) = node {
+                state.insert("hostname".to_string(), topic_value(&node.hostname));
+                state.insert("status".to_string(), topic_value(&node.status));
+                state.insert("processes".to_string(), topic_value(&node.processes));
+                for gpu in &node.gpus {
+                    state.insert(format!("gpu:{}", gpu.gpu_index), topic_value(gpu));
+                }
+            }
+        }
+        // Events are pushed as they are published, not diffed
+        WsTopic::Events => {}
+    }
+    state
+}
+
+/// Subscriptions of one WebSocket client and the state last pushed for each topic
+#[derive(Debug, Default)]
+pub struct WsSubscriptions {
+    last: BTreeMap<WsTopic, Option<TopicState>>,
+}
+
+impl WsSubscriptions {
+    /// Apply a client message. Newly subscribed topics receive full state on the
+    /// next call to `updates`.
+    pub fn handle_client_message(&mut self, text: &str) -> std::result::Result<(), String> {
+        let message: WsClientMessage =
+            serde_json::from_str(text).map_err(|e| format!("invalid message: {}", e))?;
+        let parse = |topics: &[String]| {
+            topics
+                .iter()
+                .map(|t| t.parse::<WsTopic>())
+                .collect::<std::result::Result<Vec<_>, _>>()
+        };
+        let subscribe = parse(&message.subscribe)?;
+        let unsubscribe = parse(&message.unsubscribe)?;
+
+        for topic in subscribe {
+            self.last.insert(topic, None);
+        }
+        for topic in unsubscribe {
+            self.last.remove(&topic);
+        }
+        Ok(())
+    }
+
+    /// Whether any topic needs the contention analysis
+    pub fn needs_contention(&self) -> bool {
+        self.last.contains_key(&WsTopic::Contention)
+    }
+
+    /// Whether the client is subscribed to cluster events
+    pub fn wants_events(&self) -> bool {
+        self.last.contains_key(&WsTopic::Events)
+    }
+
+    /// Frames to push for the latest cluster data: full state for new topics (or
+    /// every topic on `full_resync`), otherwise deltas for topics that changed
+    pub fn updates(
+        &mut self,
+        snapshot: Option<&ClusterSnapshot>,
+        contention: Option<&ContentionAnalysis>,
+        full_resync: bool,
+    ) -> Vec<WsServerMessage> {
+        let mut messages = Vec::new();
+        for (topic, last) in self.last.iter_mut() {
+            // Keep the previous contention state if the analysis is unavailable
+            if *topic == WsTopic::Contention && contention.is_none() {
+                continue;
+            }
+            if *topic == WsTopic::Events {
+                continue;
+            }
+            let state = topic_state(topic, snapshot, contention);
+            match last.as_ref().filter(|_| !full_resync) {
+                None => messages.push(WsServerMessage::Full {
+                    topic: topic.to_string(),
+                    data: state.clone(),
+                }),
+                Some(previous) => {
+                    let changed: TopicState = state
+                        .iter()
+                        .filter(|(key, value)| previous.get(*key) != Some(*value))
+                        .map(|(key, value)| (key.clone(), value.clone()))
+                        .collect();
+                    let removed: Vec<String> = previous
+                        .keys()
+                        .filter(|key| !state.contains_key(*key))
+                        .cloned()
+                        .collect();
+                    if !changed.is_empty() || !removed.is_empty() {
+                        messages.push(WsServerMessage::Delta {
+                            topic: topic.to_string(),
+                            changed,
+                            removed,
+                        });
+                    }
+                }
+            }
+            *last = Some(state);
+        }
+        messages
+    }
+}
+
+/// Version of the envelope around messages on the default `/ws` stream
+pub const WS_PROTOCOL_VERSION: u32 = 1;
+
+/// Message on the default `/ws` stream, for clients that never subscribe to topics:
+/// `{"type": "full" | "delta", "version": 1, "payload": ...}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WsSnapshotMessage {
+    /// The whole cluster snapshot, replacing anything the client holds
+    Full {
+        version: u32,
+        payload: ClusterSnapshot,
+    },
+    /// What changed since the previous message on the connection
+    Delta { version: u32, payload: ClusterDelta },
+}
+
+/// Changes to the cluster snapshot between two pushes on one connection. Nodes that
+/// did not change are left out, and changed nodes carry only the GPUs that changed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterDelta {
+    pub timestamp: DateTime<Utc>,
+    pub total_gpus: u32,
+    pub total_memory_gb: f32,
+    pub active_processes: u32,
+    pub utilization_avg: f32,
+    /// Nodes the client has not seen yet, in full
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub added_nodes: Vec<NodeSnapshot>,
+    /// Changes to nodes the client already holds, keyed by node ID
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub changed_nodes: BTreeMap<String, SnapshotDelta>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_nodes: Vec<String>,
+}
+
+impl ClusterDelta {
+    /// The changes that turn `previous` into `current`, or `None` if only
+    /// timestamps changed
+    pub fn between(previous: &ClusterSnapshot, current: &ClusterSnapshot) -> Option<Self> {
+        let mut added_nodes = Vec::new();
+        let mut changed_nodes = BTreeMap::new();
+        for node in &current.nodes {
+            match previous
+                .nodes
+                .iter()
+                .find(|old| old.node_id == node.node_id)
+            {
+                // A renamed host is sent in full, since deltas do not carry the hostname
+                Some(old) if old.hostname == node.hostname => {
+                    let delta = SnapshotDelta::between(old, node);
+                    if !delta.is_empty() {
+                        changed_nodes.insert(node.node_id.clone(), delta);
+                    }
+                }
+                _ => added_nodes.push(node.clone()),
+            }
+        }
+        let removed_nodes: Vec<String> = previous
+            .nodes
+            .iter()
+            .filter(|old| !current.nodes.iter().any(|node| node.node_id == old.node_id))
+            .map(|old| old.node_id.clone())
+            .collect();
+
+        if added_nodes.is_empty() && changed_nodes.is_empty() && removed_nodes.is_empty() {
+            return None;
+        }
+        Some(Self {
+            timestamp: current.timestamp,
+            total_gpus: current.total_gpus,
+            total_memory_gb: current.total_memory_gb,
+            active_processes: current.active_processes,
+            utilization_avg: current.utilization_avg,
+            added_nodes,
+            changed_nodes,
+            removed_nodes,
+        })
+    }
+}
+
+/// The cluster snapshot last sent on a connection's default stream
+#[derive(Debug, Default)]
+pub struct WsSnapshotStream {
+    last: Option<ClusterSnapshot>,
+}
+
+impl WsSnapshotStream {
+    /// Message to push for `snapshot`: the full snapshot on the first push (or on
+    /// `full_resync`), then a delta against the last one sent, or nothing if
+    /// nothing changed
+    pub fn next(
+        &mut self,
+        snapshot: &ClusterSnapshot,
+        full_resync: bool,
+    ) -> Option<WsSnapshotMessage> {
+        let message = match self.last.as_ref().filter(|_| !full_resync) {
+            None => WsSnapshotMessage::Full {
+                version: WS_PROTOCOL_VERSION,
+                payload: snapshot.clone(),
+            },
+            Some(previous) => WsSnapshotMessage::Delta {
+                version: WS_PROTOCOL_VERSION,
+                payload: ClusterDelta::between(previous, snapshot)?,
+            },
+        };
+        self.last = Some(snapshot.clone());
+        Some(message)
+    }
+}
+
+/// Queue a frame for the client's writer task. Returns false if the client's
+/// buffer is full or its connection is gone, in which case it should be dropped.
+fn queue_frame(
+    tx: &tokio::sync::mpsc::Sender<axum::extract::ws::Message>,
+    frame: axum::extract::ws::Message,
+) -> bool {
+    tx.try_send(frame).is_ok()
+}
+
+fn json_frame<T: Serialize>(value: &T) -> Option<axum::extract::ws::Message> {
+    serde_json::to_string(value)
+        .ok()
+        .map(axum::extract::ws::Message::Text)
+}
+
+/// Handle WebSocket connection.
+///
+/// Clients that never subscribe receive the full `ClusterSnapshot` once, then only
+/// what changed (see `WsSnapshotMessage`). Sending `{"subscribe": [...]}` switches the connection to topic frames (see
+/// `WsServerMessage`); the `events` topic gets each cluster event as it is
+/// published. Frames are written by a separate task through a bounded queue so a
+/// slow client is disconnected rather than stalling its push loop.
+async fn websocket_connection(socket: axum::extract::ws::WebSocket, state: CoordinatorState) {
+    use axum::extract::ws::Message;
+    use futures_util::{sink::SinkExt, stream::StreamExt};
+
+    let (mut sender, mut receiver) = socket.split();
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<Message>(WS_SEND_BUFFER);
+    let writer = tokio::spawn(async move {
+        while let Some(frame) = rx.recv().await {
+            if sender.send(frame).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut subscriptions = WsSubscriptions::default();
+    let mut subscribed = false;
+    let mut stream = WsSnapshotStream::default();
+    let mut events = state.events.subscribe();
+    let mut interval = tokio::time::interval(WS_PUSH_INTERVAL);
+    let mut last_resync = tokio::time::Instant::now();
+
+    loop {
+        let mut frames = Vec::new();
+
+        tokio::select! {
+            _ = interval.tick() => {
+                let full_resync = last_resync.elapsed() >= WS_RESYNC_INTERVAL;
+                if full_resync {
+                    last_resync = tokio::time::Instant::now();
+                }
+                if subscribed {
+                    frames.extend(subscription_frames(&state, &mut subscriptions, full_resync).await);
+                } else if let Some(snapshot) = state.get_cluster_snapshot().await {
+                    frames.extend(stream.next(&snapshot, full_resync).as_ref().and_then(json_frame));
+                }
+            }
+            event = events.recv() => {
+                use tokio::sync::broadcast::error::RecvError;
+                match event {
+                    Ok(event) if subscriptions.wants_events() => {
+                        frames.extend(json_frame(&WsServerMessage::Event { event }));
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(missed)) if subscriptions.wants_events() => {
+                        frames.extend(json_frame(&WsServerMessage::Error {
+                            message: format!(
+                                "missed {} events; fetch /api/cluster/events to catch up",
+                                missed
+                            ),
+                        }));
+                    }
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                }
+            }
+            msg = receiver.next() => {
+                match msg {
+                    Some(Ok(Message::Text(text))) => {
+                        match subscriptions.handle_client_message(&text) {
+                            Ok(()) => {
+                                subscribed = true;
+                                frames.extend(subscription_frames(&state, &mut subscriptions, false).await);
+                            }
+                            Err(message) => {
+                                frames.extend(json_frame(&WsServerMessage::Error { message }));
+                            }
+                        }
+                    }
+                    Some(Ok(Message::Binary(_))) => {
+                        frames.extend(json_frame(&WsServerMessage::Error {
+                            message: "binary frames are not supported".to_string(),
+                        }));
+                    }
+                    Some(Ok(Message::Ping(data))) => frames.push(Message::Pong(data)),
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(Message::Pong(_))) => {}
+                }
+            }
+        }
+
+        if !frames.into_iter().all(|frame| queue_frame(&tx, frame)) {
+            tracing::warn!("Dropping slow WebSocket client: send buffer is full");
+            break;
+        }
+    }
+
+    drop(tx);
+    writer.abort();
+}
+
+/// Compute topic frames for a subscribed client from the coordinator's latest data
+async fn subscription_frames(
+    state: &CoordinatorState,
+    subscriptions: &mut WsSubscriptions,
+    full_resync: bool,
+) -> Vec<axum::extract::ws::Message> {
+    let snapshot = state.get_cluster_snapshot().await;
+    let contention = if subscriptions.needs_contention() {
+        state.get_contention_analysis().await.ok()
+    } else {
+        None
+    };
+    subscriptions
+        .updates(snapshot.as_ref(), contention.as_ref(), full_resync)
+        .iter()
+        .filter_map(json_frame)
+        .collect()
+}
+
+/// Get Guard Mode configuration
+async fn get_guard_config(
+    State(_state): State<CoordinatorState>,
+) -> Result<Json<crate::guard_mode::GuardModeConfig>, StatusCode> {
+    use crate::guard_mode::GuardModeManager;
+
+    let guard_manager = GuardModeManager::new().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let config = guard_manager.get_config();
+    Ok(Json(config.clone()))
+}
+
+/// Update Guard Mode configuration
+async fn update_guard_config(
+    State(state): State<CoordinatorState>,
+    Json(config): Json<crate::guard_mode::GuardModeConfig>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    use crate::guard_mode::GuardModeManager;
+
+    if let Err(e) = crate::guard_mode::validate_config(&config) {
+        tracing::warn!("Rejected Guard Mode configuration update: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut guard_manager =
+        GuardModeManager::new().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    guard_manager
+        .update_config(config)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    state
+        .set_guard_config(Some(guard_manager.get_config().clone()))
+        .await;
+
+    Ok(Json(
+        serde_json::json!({"success": true, "message": "Guard Mode configuration updated"}),
+    ))
+}
+
+/// Get Guard Mode policies
+async fn get_guard_policies(
+    State(_state): State<CoordinatorState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    use crate::guard_mode::GuardModeManager;
+
+    let guard_manager = GuardModeManager::new().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let config = guard_manager.get_config();
+
+    let policies = serde_json::json!({
+        "user_policies": config.user_policies,
+        "group_policies": config.group_policies,
+        "gpu_policies": config.gpu_policies,
+        "time_policies": config.time_policies,
+        "enforcement": config.enforcement
+    });
+
+    Ok(Json(policies))
+}
+
+/// Update Guard Mode policies
+async fn update_guard_policies(
+    State(_state): State<CoordinatorState>,
+    Json(policies): Json<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    use crate::guard_mode::GuardModeManager;
+
+    let mut guard_manager =
+        GuardModeManager::new().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // Parse and update policies
+    if let Some(user_policies) = policies.get("user_policies") {
+        if let Ok(user_policies_map) = serde_json::from_value::<
+            std::collections::HashMap<String, crate::guard_mode::UserPolicy>,
+        >(user_policies.clone())
+        {
+            for (_, policy) in user_policies_map {
+                guard_manager
+                    .add_user_policy(policy)
+                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            }
+        }
+    }
+
+    Ok(Json(
+        serde_json::json!({"success": true, "message": "Policies updated"}),
+    ))
+}
+
+/// Get Guard Mode status
+async fn get_guard_status(
+    State(_state): State<CoordinatorState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    use crate::guard_mode::GuardModeManager;
+
+    let guard_manager = GuardModeManager::new().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let config = guard_manager.get_config();
+    let violation_history = guard_manager.get_violation_history();
     let warning_history = guard_manager.get_warning_history();
 
-    let status = serde_json::json!({
-        "enabled": config.global.enabled,
-        "dry_run": config.global.dry_run,
-        "soft_enforcement": config.enforcement.soft_enforcement,
-        "hard_enforcement": config.enforcement.hard_enforcement,
-        "total_violations": violation_history.len(),
-        "total_warnings": warning_history.len(),
-        "recent_violations": violation_history.iter().rev().take(10).collect::<Vec<_>>(),
-        "recent_warnings": warning_history.iter().rev().take(10).collect::<Vec<_>>(),
-        "user_policy_count": config.user_policies.len(),
-        "group_policy_count": config.group_policies.len(),
-        "gpu_policy_count": config.gpu_policies.len()
-    });
+    let status = serde_json::json!({
+        "enabled": config.global.enabled,
+        "dry_run": config.global.dry_run,
+        "soft_enforcement": config.enforcement.soft_enforcement,
+        "hard_enforcement": config.enforcement.hard_enforcement,
+        "total_violations": violation_history.len(),
+        "total_warnings": warning_history.len(),
+        "recent_violations": violation_history.iter().rev().take(10).collect::<Vec<_>>(),
+        "recent_warnings": warning_history.iter().rev().take(10).collect::<Vec<_>>(),
+        "user_policy_count": config.user_policies.len(),
+        "group_policy_count": config.group_policies.len(),
+        "gpu_policy_count": config.gpu_policies.len()
+    });
+
+    Ok(Json(status))
+}
+
+/// Toggle Guard Mode dry-run
+async fn toggle_guard_dry_run(
+    State(_state): State<CoordinatorState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    use crate::guard_mode::GuardModeManager;
+
+    let mut guard_manager =
+        GuardModeManager::new().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let new_dry_run = guard_manager
+        .toggle_dry_run()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "dry_run": new_dry_run,
+        "message": format!("Dry-run mode {}", if new_dry_run { "enabled" } else { "disabled" })
+    })))
+}
+
+/// Test Guard Mode policies
+async fn test_guard_policies(
+    State(_state): State<CoordinatorState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    use crate::guard_mode::GuardModeManager;
+    use crate::vendor::GpuManager;
+
+    let mut guard_manager =
+        GuardModeManager::new().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // Get current GPU processes for testing
+    let gpu_manager = GpuManager::initialize()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .with_stable_indices();
+    let test_processes = gpu_manager
+        .get_all_processes()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let test_gpus = gpu_manager
+        .get_all_snapshots()
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let result = guard_manager
+        .simulate_policy_check(&test_processes, &test_gpus)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(serde_json::json!({
+        "success": true,
+        "simulation_result": {
+            "violations": result.violations,
+            "warnings": result.warnings,
+            "actions_taken": result.actions_taken,
+            "dry_run": result.dry_run,
+            "timestamp": result.timestamp
+        },
+        "summary": {
+            "violation_count": result.violations.len(),
+            "warning_count": result.warnings.len(),
+            "action_count": result.actions_taken.len()
+        }
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vendor::GpuVendor;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_contention_analysis_flags_memory_errors() {
+        let state = CoordinatorState::new();
+        let gpu = |index: u16, uncorrected: u64, rma_flagged: Option<bool>| GpuSnapshot {
+            gpu_index: index,
+            name: format!("Test GPU {}", index),
+            vendor: GpuVendor::Nvidia,
+            mem_used_mb: 0,
+            mem_total_mb: 10000,
+            util_pct: 0.0,
+            temp_c: 40,
+            power_w: 50.0,
+            ecc_volatile: Some(crate::nvml_api::EccCounts {
+                corrected: 10,
+                uncorrected,
+            }),
+            rma_flagged,
+            pids: 0,
+            ..Default::default()
+        };
+        state
+            .register_node(NodeInfo {
+                id: "node-a".to_string(),
+                hostname: "host-a".to_string(),
+                ip_address: "127.0.0.1".to_string(),
+                last_seen: Utc::now(),
+                status: NodeStatus::Online,
+                gpu_count: 3,
+                total_memory_gb: 29.3,
+                tags: HashMap::new(),
+                drain: None,
+            })
+            .await
+            .unwrap();
+        state
+            .update_snapshot(
+                "node-a".to_string(),
+                NodeSnapshot {
+                    node_id: "node-a".to_string(),
+                    hostname: "host-a".to_string(),
+                    timestamp: Utc::now(),
+                    gpus: vec![gpu(0, 0, None), gpu(1, 2, None), gpu(2, 0, Some(true))],
+                    processes: Vec::new(),
+                    status: NodeStatus::Online,
+                    tags: HashMap::new(),
+                    errors: Vec::new(),
+                },
+            )
+            .await
+            .unwrap();
+
+        let analysis = state.get_contention_analysis().await.unwrap();
+        let flagged: Vec<(u16, u64, bool)> = analysis
+            .unhealthy_gpus
+            .iter()
+            .map(|g| (g.gpu_index, g.ecc_uncorrected, g.rma_flagged))
+            .collect();
+        assert_eq!(flagged, vec![(1, 2, false), (2, 0, true)]);
+        assert_eq!(analysis.node_summaries[0].unhealthy_gpus, 2);
+        assert!(analysis
+            .recommendations
+            .iter()
+            .any(|r| r.contains("2 GPUs report uncorrected ECC errors")));
+    }
+
+    #[tokio::test]
+    async fn test_contention_analysis_reports_maintenance_gpus() {
+        use crate::guard_mode::{GpuPolicy, MaintenanceWindow, ViolationSeverity};
+
+        let state = CoordinatorState::new();
+        let gpu = |index: u16| GpuSnapshot {
+            gpu_index: index,
+            name: format!("Test GPU {}", index),
+            vendor: GpuVendor::Nvidia,
+            mem_used_mb: 0,
+            mem_total_mb: 10000,
+            util_pct: 0.0,
+            temp_c: 40,
+            power_w: 50.0,
+            pids: 0,
+            ..Default::default()
+        };
+        state
+            .register_node(NodeInfo {
+                id: "node-a".to_string(),
+                hostname: "host-a".to_string(),
+                ip_address: "127.0.0.1".to_string(),
+                last_seen: Utc::now(),
+                status: NodeStatus::Online,
+                gpu_count: 2,
+                total_memory_gb: 19.5,
+                tags: HashMap::new(),
+                drain: None,
+            })
+            .await
+            .unwrap();
+        state
+            .update_snapshot(
+                "node-a".to_string(),
+                NodeSnapshot {
+                    node_id: "node-a".to_string(),
+                    hostname: "host-a".to_string(),
+                    timestamp: Utc::now(),
+                    gpus: vec![gpu(0), gpu(1)],
+                    processes: Vec::new(),
+                    status: NodeStatus::Online,
+                    tags: HashMap::new(),
+                    errors: Vec::new(),
+                },
+            )
+            .await
+            .unwrap();
+
+        let analysis = state.get_contention_analysis().await.unwrap();
+        assert!(analysis.maintenance_gpus.is_empty());
+
+        let mut config = GuardModeConfig::default();
+        config.global.maintenance_timezone = Some("UTC".to_string());
+        config.gpu_policies.insert(
+            "1".to_string(),
+            GpuPolicy {
+                gpu_index: 1,
+                max_memory_gb: 80.0,
+                max_utilization_pct: 100.0,
+                reserved_memory_gb: 0.0,
+                allowed_users: Vec::new(),
+                blocked_users: Vec::new(),
+                maintenance_window: Some(MaintenanceWindow {
+                    start_time: "00:00".to_string(),
+                    end_time: "23:59".to_string(),
+                    days_of_week: Vec::new(),
+                    message: "firmware update".to_string(),
+                    severity: ViolationSeverity::High,
+                }),
+            },
+        );
+        state.set_guard_config(Some(config)).await;
+
+        let analysis = state.get_contention_analysis().await.unwrap();
+        assert_eq!(analysis.maintenance_gpus.len(), 1);
+        assert_eq!(analysis.maintenance_gpus[0].node_id, "node-a");
+        assert_eq!(analysis.maintenance_gpus[0].gpu_index, 1);
+        assert_eq!(analysis.maintenance_gpus[0].message, "firmware update");
+    }
+
+    #[tokio::test]
+    async fn test_contention_analysis_suggests_rebalancing_to_idle_node() {
+        let state = CoordinatorState::new();
+        let gpu = |index: u16, util_pct: f32, mem_used_mb: u32, pids: usize| GpuSnapshot {
+            gpu_index: index,
+            name: format!("Test GPU {}", index),
+            vendor: GpuVendor::Nvidia,
+            mem_used_mb,
+            mem_total_mb: 10000,
+            util_pct,
+            temp_c: 40,
+            power_w: 50.0,
+            pids,
+            ..Default::default()
+        };
+        let proc = |index: u16, pid: u32| GpuProc {
+            gpu_index: index,
+            pid,
+            user: "alice".to_string(),
+            proc_name: "train".to_string(),
+            used_mem_mb: 9000,
+            start_time: "1h".to_string(),
+            ..Default::default()
+        };
+
+        for (id, hostname, gpus, processes) in [
+            (
+                "node-a",
+                "host-a",
+                vec![gpu(0, 95.0, 9000, 1), gpu(1, 95.0, 9000, 1)],
+                vec![proc(0, 100), proc(1, 101)],
+            ),
+            (
+                "node-b",
+                "host-b",
+                (0..4).map(|i| gpu(i, 0.0, 0, 0)).collect(),
+                Vec::new(),
+            ),
+        ] {
+            state
+                .register_node(NodeInfo {
+                    id: id.to_string(),
+                    hostname: hostname.to_string(),
+                    ip_address: "127.0.0.1".to_string(),
+                    last_seen: Utc::now(),
+                    status: NodeStatus::Online,
+                    gpu_count: gpus.len() as u32,
+                    total_memory_gb: 39.0,
+                    tags: HashMap::new(),
+                    drain: None,
+                })
+                .await
+                .unwrap();
+            state
+                .update_snapshot(
+                    id.to_string(),
+                    NodeSnapshot {
+                        node_id: id.to_string(),
+                        hostname: hostname.to_string(),
+                        timestamp: Utc::now(),
+                        gpus,
+                        processes,
+                        status: NodeStatus::Online,
+                        tags: HashMap::new(),
+                        errors: Vec::new(),
+                    },
+                )
+                .await
+                .unwrap();
+        }
+
+        let analysis = state.get_contention_analysis().await.unwrap();
+
+        assert_eq!(analysis.blocked_gpus.len(), 2);
+        assert_eq!(
+            analysis.idle_gpus,
+            (0..4)
+                .map(|i| ("node-b".to_string(), i))
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(analysis.node_summaries.len(), 2);
+        let saturated = &analysis.node_summaries[0];
+        assert_eq!(saturated.node_id, "node-a");
+        assert_eq!(saturated.blocked_gpus, 2);
+        assert_eq!(saturated.idle_gpus, 0);
+        assert_eq!(saturated.avg_utilization, 95.0);
+        let idle = &analysis.node_summaries[1];
+        assert_eq!(idle.node_id, "node-b");
+        assert_eq!(idle.blocked_gpus, 0);
+        assert_eq!(idle.idle_gpus, 4);
+
+        assert!(analysis.recommendations.contains(
+            &"Move jobs from node host-a (2 blocked GPUs) to node host-b, which has 4 idle GPUs"
+                .to_string()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_contention_analysis_flags_fair_share_violations() {
+        let state = CoordinatorState::new();
+        let gpu = |index: u16, util_pct: f32, mem_used_mb: u32, pids: usize| GpuSnapshot {
+            gpu_index: index,
+            name: format!("Test GPU {}", index),
+            vendor: GpuVendor::Nvidia,
+            mem_used_mb,
+            mem_total_mb: 10000,
+            util_pct,
+            temp_c: 40,
+            power_w: 50.0,
+            pids,
+            ..Default::default()
+        };
+        let proc = |index: u16, pid: u32, user: &str, used_mem_mb: u32| GpuProc {
+            gpu_index: index,
+            pid,
+            user: user.to_string(),
+            proc_name: "train".to_string(),
+            used_mem_mb,
+            start_time: "1h".to_string(),
+            ..Default::default()
+        };
+
+        // Four users on four GPUs: alice holds three of them, the others share the fourth
+        state
+            .register_node(NodeInfo {
+                id: "node-a".to_string(),
+                hostname: "host-a".to_string(),
+                ip_address: "127.0.0.1".to_string(),
+                last_seen: Utc::now(),
+                status: NodeStatus::Online,
+                gpu_count: 4,
+                total_memory_gb: 39.0,
+                tags: HashMap::new(),
+                drain: None,
+            })
+            .await
+            .unwrap();
+        state
+            .update_snapshot(
+                "node-a".to_string(),
+                NodeSnapshot {
+                    node_id: "node-a".to_string(),
+                    hostname: "host-a".to_string(),
+                    timestamp: Utc::now(),
+                    gpus: vec![
+                        gpu(0, 90.0, 9000, 1),
+                        gpu(1, 90.0, 9000, 1),
+                        gpu(2, 90.0, 9000, 1),
+                        gpu(3, 30.0, 3000, 3),
+                    ],
+                    processes: vec![
+                        proc(0, 100, "alice", 9000),
+                        proc(1, 101, "alice", 9000),
+                        proc(2, 102, "alice", 9000),
+                        proc(3, 200, "bob", 1000),
+                        proc(3, 300, "carol", 1000),
+                        proc(3, 400, "dave", 1000),
+                    ],
+                    status: NodeStatus::Online,
+                    tags: HashMap::new(),
+                    errors: Vec::new(),
+                },
+            )
+            .await
+            .unwrap();
+
+        let analysis = state.get_contention_analysis().await.unwrap();
+
+        assert_eq!(analysis.fair_share_violations.len(), 1);
+        let violation = &analysis.fair_share_violations[0];
+        assert_eq!(violation.usage.user, "alice");
+        assert_eq!(violation.usage.total_memory_mb, 27000);
+        assert_eq!(violation.fair_share, 0.25);
+        assert!((violation.memory_share - 0.675).abs() < 1e-6);
+        assert!((violation.utilization_share - 0.675).abs() < 1e-6);
+        assert_eq!(violation.target_mb, 10000);
+        assert_eq!(violation.relinquish_mb, 17000);
+        assert!(analysis
+            .recommendations
+            .iter()
+            .any(|r| r.starts_with("User 'alice' holds 68% of GPU memory")
+                && r.ends_with("releasing 17000 MB would bring them to 10000 MB")));
+
+        // Serialized flat, with the usage fields next to the target
+        let json = serde_json::to_value(violation).unwrap();
+        assert_eq!(json["user"], "alice");
+        assert_eq!(json["target_mb"], 10000);
+        assert!(analysis.to_markdown(Utc::now()).contains("## Fair Share"));
+
+        // An even split flags nobody
+        let even: Vec<UserUsage> = ["alice", "bob"]
+            .iter()
+            .map(|user| UserUsage {
+                user: user.to_string(),
+                gpu_count: 1,
+                total_memory_mb: 9000,
+                avg_utilization: 90.0,
+                process_count: 1,
+            })
+            .collect();
+        assert!(fair_share_violations(&even, 20000, 2).is_empty());
+        assert!(fair_share_violations(&even, 0, 0).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_node_snapshot_collect_from_mock_gpus() {
+        use crate::vendor::{MockFailure, MockFixture, MockGpu, MockProcess, MockVendor};
+
+        let mock_gpu = |failures| MockGpu {
+            name: "Mock GPU".to_string(),
+            mem_total_mb: 16384,
+            util_pct: 30.0,
+            processes: vec![MockProcess {
+                pid: 4242,
+                user: "alice".to_string(),
+                name: "python".to_string(),
+                used_mem_mb: 2048,
+                cmdline: None,
+            }],
+            failures,
+            ..Default::default()
+        };
+        let vendor = std::sync::Arc::new(MockVendor::new(MockFixture {
+            gpus: vec![
+                mock_gpu(Vec::new()),
+                mock_gpu(vec![MockFailure {
+                    call: crate::vendor::MockCall::Snapshot,
+                    nth: Some(2),
+                    message: "GPU is lost".to_string(),
+                }]),
+            ],
+            ..Default::default()
+        }));
+        let manager = vendor.manager();
+        let tags = HashMap::from([("rack".to_string(), "a1".to_string())]);
+
+        let snapshot = NodeSnapshot::collect(&manager, "node-1".into(), "host-1".into(), tags)
+            .await
+            .unwrap();
+        assert!(matches!(snapshot.status, NodeStatus::Online));
+        assert_eq!(snapshot.gpus.len(), 2);
+        assert_eq!(snapshot.gpus[1].mem_used_mb, 2048);
+        assert_eq!(snapshot.processes.len(), 2);
+        assert_eq!(snapshot.tags["rack"], "a1");
+
+        // A device that stops answering marks the node degraded and is reported
+        let snapshot =
+            NodeSnapshot::collect(&manager, "node-1".into(), "host-1".into(), HashMap::new())
+                .await
+                .unwrap();
+        assert!(matches!(snapshot.status, NodeStatus::Degraded));
+        assert_eq!(snapshot.gpus.len(), 1);
+        assert_eq!(snapshot.errors[0].gpu_index, 1);
+    }
+
+    #[tokio::test]
+    async fn test_contention_analysis_gpu_count_unique() {
+        let state = CoordinatorState::new();
+
+        // Scenario: User "alice" has 2 processes on GPU 0
+        // Expected: gpu_count should be 1 (alice is using 1 unique GPU)
+        let snapshot = NodeSnapshot {
+            node_id: "test-node".to_string(),
+            hostname: "test-host".to_string(),
+            timestamp: Utc::now(),
+            gpus: vec![GpuSnapshot {
+                gpu_index: 0,
+                name: "Test GPU".to_string(),
+                vendor: GpuVendor::Nvidia,
+                mem_used_mb: 8000,
+                mem_total_mb: 10000,
+                util_pct: 90.0,
+                temp_c: 75,
+                power_w: 200.0,
+                pids: 2,
+                ..Default::default()
+            }],
+            processes: vec![
+                GpuProc {
+                    gpu_index: 0,
+                    pid: 1234,
+                    user: "alice".to_string(),
+                    proc_name: "process1".to_string(),
+                    used_mem_mb: 4000,
+                    start_time: "2025-09-20T01:00:00Z".to_string(),
+                    ..Default::default()
+                },
+                GpuProc {
+                    gpu_index: 0,
+                    pid: 5678,
+                    user: "alice".to_string(),
+                    proc_name: "process2".to_string(),
+                    used_mem_mb: 4000,
+                    start_time: "2025-09-20T01:00:00Z".to_string(),
+                    ..Default::default()
+                },
+            ],
+            status: NodeStatus::Online,
+            tags: HashMap::new(),
+            errors: Vec::new(),
+        };
+
+        state
+            .register_node(NodeInfo {
+                id: "test-node".to_string(),
+                hostname: "test-host".to_string(),
+                ip_address: "127.0.0.1".to_string(),
+                last_seen: Utc::now(),
+                status: NodeStatus::Online,
+                gpu_count: 1,
+                total_memory_gb: 9.8,
+                tags: HashMap::new(),
+                drain: None,
+            })
+            .await
+            .unwrap();
+
+        // Update the snapshot
+        state
+            .update_snapshot("test-node".to_string(), snapshot)
+            .await
+            .unwrap();
+
+        // Get contention analysis
+        let analysis = state.get_contention_analysis().await.unwrap();
+
+        // Find alice's stats
+        let alice_stats = analysis
+            .top_users
+            .iter()
+            .find(|u| u.user == "alice")
+            .expect("Alice should be in top users");
+
+        // Verify that gpu_count correctly reflects unique GPUs, not process count
+        assert_eq!(alice_stats.process_count, 2, "Alice has 2 processes");
+        assert_eq!(
+            alice_stats.gpu_count, 1,
+            "Alice uses 1 unique GPU, not 2 (one per process)"
+        );
+        assert_eq!(alice_stats.total_memory_mb, 8000, "Total memory is correct");
+    }
+
+    #[tokio::test]
+    async fn test_contention_analysis_multi_gpu_multi_node() {
+        let state = CoordinatorState::new();
+
+        // Scenario: User "bob" has processes on GPU 0 and GPU 1 across two nodes
+        // Expected: gpu_count should be 4 (2 GPUs per node × 2 nodes)
+
+        // Node 1: bob has 2 processes on GPU 0, 1 process on GPU 1
+        let snapshot1 = NodeSnapshot {
+            node_id: "node-1".to_string(),
+            hostname: "host-1".to_string(),
+            timestamp: Utc::now(),
+            gpus: vec![
+                GpuSnapshot {
+                    gpu_index: 0,
+                    name: "GPU 0".to_string(),
+                    vendor: GpuVendor::Nvidia,
+                    mem_used_mb: 4000,
+                    mem_total_mb: 10000,
+                    util_pct: 50.0,
+                    temp_c: 70,
+                    power_w: 150.0,
+                    pids: 2,
+                    ..Default::default()
+                },
+                GpuSnapshot {
+                    gpu_index: 1,
+                    name: "GPU 1".to_string(),
+                    vendor: GpuVendor::Nvidia,
+                    mem_used_mb: 2000,
+                    mem_total_mb: 10000,
+                    util_pct: 25.0,
+                    temp_c: 65,
+                    power_w: 100.0,
+                    pids: 1,
+                    ..Default::default()
+                },
+            ],
+            processes: vec![
+                GpuProc {
+                    gpu_index: 0,
+                    pid: 1001,
+                    user: "bob".to_string(),
+                    proc_name: "train1".to_string(),
+                    used_mem_mb: 2000,
+                    start_time: "2025-09-20T01:00:00Z".to_string(),
+                    ..Default::default()
+                },
+                GpuProc {
+                    gpu_index: 0,
+                    pid: 1002,
+                    user: "bob".to_string(),
+                    proc_name: "train2".to_string(),
+                    used_mem_mb: 2000,
+                    start_time: "2025-09-20T01:00:00Z".to_string(),
+                    ..Default::default()
+                },
+                GpuProc {
+                    gpu_index: 1,
+                    pid: 1003,
+                    user: "bob".to_string(),
+                    proc_name: "train3".to_string(),
+                    used_mem_mb: 2000,
+                    start_time: "2025-09-20T01:00:00Z".to_string(),
+                    ..Default::default()
+                },
+            ],
+            status: NodeStatus::Online,
+            tags: HashMap::new(),
+            errors: Vec::new(),
+        };
+
+        // Node 2: bob has 1 process each on GPU 0 and GPU 1 (same indices as node 1)
+        let snapshot2 = NodeSnapshot {
+            node_id: "node-2".to_string(),
+            hostname: "host-2".to_string(),
+            timestamp: Utc::now(),
+            gpus: vec![
+                GpuSnapshot {
+                    gpu_index: 0,
+                    name: "GPU 0".to_string(),
+                    vendor: GpuVendor::Nvidia,
+                    mem_used_mb: 2000,
+                    mem_total_mb: 10000,
+                    util_pct: 30.0,
+                    temp_c: 60,
+                    power_w: 120.0,
+                    pids: 1,
+                    ..Default::default()
+                },
+                GpuSnapshot {
+                    gpu_index: 1,
+                    name: "GPU 1".to_string(),
+                    vendor: GpuVendor::Nvidia,
+                    mem_used_mb: 2000,
+                    mem_total_mb: 10000,
+                    util_pct: 30.0,
+                    temp_c: 60,
+                    power_w: 120.0,
+                    pids: 1,
+                    ..Default::default()
+                },
+            ],
+            processes: vec![
+                GpuProc {
+                    gpu_index: 0,
+                    pid: 2001,
+                    user: "bob".to_string(),
+                    proc_name: "train4".to_string(),
+                    used_mem_mb: 2000,
+                    start_time: "2025-09-20T01:00:00Z".to_string(),
+                    ..Default::default()
+                },
+                GpuProc {
+                    gpu_index: 1,
+                    pid: 2002,
+                    user: "bob".to_string(),
+                    proc_name: "train5".to_string(),
+                    used_mem_mb: 2000,
+                    start_time: "2025-09-20T01:00:00Z".to_string(),
+                    ..Default::default()
+                },
+            ],
+            status: NodeStatus::Online,
+            tags: HashMap::new(),
+            errors: Vec::new(),
+        };
+
+        state
+            .register_node(NodeInfo {
+                id: "node-1".to_string(),
+                hostname: "host-1".to_string(),
+                ip_address: "10.0.0.1".to_string(),
+                last_seen: Utc::now(),
+                status: NodeStatus::Online,
+                gpu_count: 2,
+                total_memory_gb: 19.5,
+                tags: HashMap::new(),
+                drain: None,
+            })
+            .await
+            .unwrap();
+        state
+            .register_node(NodeInfo {
+                id: "node-2".to_string(),
+                hostname: "host-2".to_string(),
+                ip_address: "10.0.0.2".to_string(),
+                last_seen: Utc::now(),
+                status: NodeStatus::Online,
+                gpu_count: 2,
+                total_memory_gb: 19.5,
+                tags: HashMap::new(),
+                drain: None,
+            })
+            .await
+            .unwrap();
+
+        state
+            .update_snapshot("node-1".to_string(), snapshot1)
+            .await
+            .unwrap();
+        state
+            .update_snapshot("node-2".to_string(), snapshot2)
+            .await
+            .unwrap();
+
+        let analysis = state.get_contention_analysis().await.unwrap();
+
+        let bob_stats = analysis
+            .top_users
+            .iter()
+            .find(|u| u.user == "bob")
+            .expect("Bob should be in top users");
+
+        // Bob has 5 processes total
+        assert_eq!(bob_stats.process_count, 5, "Bob has 5 processes total");
+
+        // Bob uses 4 unique GPUs: (node-1, 0), (node-1, 1), (node-2, 0), (node-2, 1)
+        assert_eq!(
+            bob_stats.gpu_count, 4,
+            "Bob uses 4 unique GPUs across 2 nodes"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_contention_analysis_avg_utilization_unique_gpus() {
+        let state = CoordinatorState::new();
+
+        let snapshot = NodeSnapshot {
+            node_id: "test-node".to_string(),
+            hostname: "test-host".to_string(),
+            timestamp: Utc::now(),
+            gpus: vec![
+                GpuSnapshot {
+                    gpu_index: 0,
+                    name: "GPU 0".to_string(),
+                    vendor: GpuVendor::Nvidia,
+                    mem_used_mb: 8000,
+                    mem_total_mb: 10000,
+                    util_pct: 90.0,
+                    temp_c: 75,
+                    power_w: 200.0,
+                    pids: 2,
+                    ..Default::default()
+                },
+                GpuSnapshot {
+                    gpu_index: 1,
+                    name: "GPU 1".to_string(),
+                    vendor: GpuVendor::Nvidia,
+                    mem_used_mb: 3000,
+                    mem_total_mb: 10000,
+                    util_pct: 30.0,
+                    temp_c: 65,
+                    power_w: 100.0,
+                    pids: 1,
+                    ..Default::default()
+                },
+            ],
+            processes: vec![
+                GpuProc {
+                    gpu_index: 0,
+                    pid: 1001,
+                    user: "charlie".to_string(),
+                    proc_name: "train1".to_string(),
+                    used_mem_mb: 4000,
+                    start_time: "2025-09-20T01:00:00Z".to_string(),
+                    ..Default::default()
+                },
+                GpuProc {
+                    gpu_index: 0,
+                    pid: 1002,
+                    user: "charlie".to_string(),
+                    proc_name: "train2".to_string(),
+                    used_mem_mb: 4000,
+                    start_time: "2025-09-20T01:00:00Z".to_string(),
+                    ..Default::default()
+                },
+                GpuProc {
+                    gpu_index: 1,
+                    pid: 1003,
+                    user: "charlie".to_string(),
+                    proc_name: "train3".to_string(),
+                    used_mem_mb: 3000,
+                    start_time: "2025-09-20T01:00:00Z".to_string(),
+                    ..Default::default()
+                },
+            ],
+            status: NodeStatus::Online,
+            tags: HashMap::new(),
+            errors: Vec::new(),
+        };
+
+        state
+            .register_node(NodeInfo {
+                id: "test-node".to_string(),
+                hostname: "test-host".to_string(),
+                ip_address: "127.0.0.1".to_string(),
+                last_seen: Utc::now(),
+                status: NodeStatus::Online,
+                gpu_count: 2,
+                total_memory_gb: 19.5,
+                tags: HashMap::new(),
+                drain: None,
+            })
+            .await
+            .unwrap();
+
+        state
+            .update_snapshot("test-node".to_string(), snapshot)
+            .await
+            .unwrap();
+
+        let analysis = state.get_contention_analysis().await.unwrap();
+
+        let charlie_stats = analysis
+            .top_users
+            .iter()
+            .find(|u| u.user == "charlie")
+            .expect("Charlie should be in top users");
+
+        assert_eq!(charlie_stats.gpu_count, 2, "Charlie uses 2 unique GPUs");
+        assert_eq!(charlie_stats.process_count, 3, "Charlie has 3 processes");
+
+        let expected_avg = 60.0;
+        let diff = (charlie_stats.avg_utilization - expected_avg).abs();
+        assert!(
+            diff < 0.01,
+            "Average utilization should be calculated per unique GPU"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_rejected_for_unregistered_node() {
+        let state = CoordinatorState::new();
+
+        let snapshot = NodeSnapshot {
+            node_id: "rogue-node".to_string(),
+            hostname: "rogue-host".to_string(),
+            timestamp: Utc::now(),
+            gpus: vec![],
+            processes: vec![],
+            status: NodeStatus::Online,
+            tags: HashMap::new(),
+            errors: Vec::new(),
+        };
+
+        let result = state
+            .update_snapshot("rogue-node".to_string(), snapshot)
+            .await;
+        assert!(result.is_err());
+
+        let snapshots = state.snapshots.read().await;
+        assert!(!snapshots.contains_key("rogue-node"));
+    }
+
+    #[tokio::test]
+    async fn test_rogue_analysis_uses_cluster_snapshots() {
+        use crate::audit::AuditManager;
+        use crate::rogue_detection::{DetectionRules, RogueDetector};
+        use crate::vendor::GpuVendor;
+
+        // Node snapshot with a clear crypto miner (xmrig at 99% GPU util)
+        let snapshot = NodeSnapshot {
+            node_id: "worker-1".to_string(),
+            hostname: "worker-host".to_string(),
+            timestamp: Utc::now(),
+            gpus: vec![GpuSnapshot {
+                gpu_index: 0,
+                name: "NVIDIA A100".to_string(),
+                vendor: GpuVendor::Nvidia,
+                mem_used_mb: 8000,
+                mem_total_mb: 40000,
+                util_pct: 99.0,
+                temp_c: 80,
+                power_w: 250.0,
+                pids: 1,
+                ..Default::default()
+            }],
+            processes: vec![GpuProc {
+                gpu_index: 0,
+                pid: 12345,
+                user: "attacker".to_string(),
+                proc_name: "xmrig".to_string(),
+                used_mem_mb: 1024,
+                start_time: "unknown".to_string(),
+                ..Default::default()
+            }],
+            status: NodeStatus::Online,
+            tags: HashMap::new(),
+            errors: Vec::new(),
+        };
+
+        let records = snapshots_to_audit_records(&[snapshot]);
+        assert!(!records.is_empty());
+        assert_eq!(records[0].process_name.as_deref(), Some("xmrig"));
+        assert_eq!(records[0].node_id.as_deref(), Some("worker-1"));
 
-    Ok(Json(status))
-}
+        let rules = DetectionRules {
+            min_confidence_threshold: 0.5,
+            ..DetectionRules::default()
+        };
+        let audit_manager = AuditManager::new().await.unwrap();
+        let detector = RogueDetector::with_rules(audit_manager, rules);
+        let result = detector
+            .detect_rogue_activity_from_records(records)
+            .await
+            .unwrap();
 
-/// Toggle Guard Mode dry-run
-async fn toggle_guard_dry_run(
-    State(_state): State<CoordinatorState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    use crate::guard_mode::GuardModeManager;
+        assert!(
+            !result.crypto_miners.is_empty(),
+            "Cluster snapshot with xmrig should produce at least one crypto miner"
+        );
+        let miner = &result.crypto_miners[0];
+        assert_eq!(miner.process.proc_name, "xmrig");
+        assert_eq!(miner.process.node_id.as_deref(), Some("worker-1"));
+    }
 
-    let mut guard_manager =
-        GuardModeManager::new().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    fn ws_cluster(util: &[(&str, f32)]) -> ClusterSnapshot {
+        let nodes = util
+            .iter()
+            .map(|(id, util_pct)| NodeSnapshot {
+                node_id: id.to_string(),
+                hostname: format!("{}.example", id),
+                timestamp: Utc::now(),
+                gpus: vec![
+                    GpuSnapshot {
+                        gpu_index: 0,
+                        name: "Test GPU".to_string(),
+                        vendor: GpuVendor::Nvidia,
+                        mem_used_mb: 0,
+                        mem_total_mb: 10000,
+                        util_pct: *util_pct,
+                        temp_c: 40,
+                        power_w: 50.0,
+                        pids: 0,
+                        ..Default::default()
+                    },
+                    GpuSnapshot {
+                        gpu_index: 1,
+                        name: "Test GPU".to_string(),
+                        vendor: GpuVendor::Nvidia,
+                        mem_used_mb: 0,
+                        mem_total_mb: 10000,
+                        util_pct: 0.0,
+                        temp_c: 40,
+                        power_w: 50.0,
+                        pids: 0,
+                        ..Default::default()
+                    },
+                ],
+                processes: Vec::new(),
+                status: NodeStatus::Online,
+                tags: HashMap::new(),
+                errors: Vec::new(),
+            })
+            .collect();
+        ClusterSnapshot {
+            timestamp: Utc::now(),
+            nodes,
+            total_gpus: 2 * util.len() as u32,
+            total_memory_gb: 0.0,
+            active_processes: 0,
+            utilization_avg: 0.0,
+        }
+    }
 
-    let new_dry_run = guard_manager
-        .toggle_dry_run()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    #[test]
+    fn test_ws_snapshot_stream_full_then_delta_of_changed_node() {
+        let mut stream = WsSnapshotStream::default();
+
+        // The first message on a connection is the full snapshot
+        let first = ws_cluster(&[("worker-01", 10.0), ("worker-07", 20.0)]);
+        let message = stream.next(&first, false).unwrap();
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(json["type"], "full");
+        assert_eq!(json["version"], WS_PROTOCOL_VERSION);
+        assert_eq!(json["payload"]["nodes"].as_array().unwrap().len(), 2);
+
+        // Fresh timestamps alone send nothing
+        let same = ws_cluster(&[("worker-01", 10.0), ("worker-07", 20.0)]);
+        assert!(stream.next(&same, false).is_none());
+
+        // One GPU changes on worker-07: the delta holds only that node and GPU
+        let changed = ws_cluster(&[("worker-01", 10.0), ("worker-07", 95.0)]);
+        let message = stream.next(&changed, false).unwrap();
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(json["type"], "delta");
+        assert_eq!(json["version"], WS_PROTOCOL_VERSION);
+        let WsSnapshotMessage::Delta { payload, .. } = message else {
+            panic!("expected a delta, got {:?}", message);
+        };
+        assert!(payload.added_nodes.is_empty() && payload.removed_nodes.is_empty());
+        assert_eq!(
+            payload.changed_nodes.keys().collect::<Vec<_>>(),
+            vec!["worker-07"]
+        );
+        let node = &payload.changed_nodes["worker-07"];
+        assert_eq!(node.changed_gpus.len(), 1);
+        assert_eq!(node.changed_gpus[0].gpu_index, 0);
+        assert_eq!(node.changed_gpus[0].util_pct, 95.0);
+        assert!(node.processes.is_none() && node.status.is_none());
+
+        // Deltas are against the last message sent, and nodes come and go
+        let message = stream
+            .next(
+                &ws_cluster(&[("worker-07", 95.0), ("worker-09", 0.0)]),
+                false,
+            )
+            .unwrap();
+        let WsSnapshotMessage::Delta { payload, .. } = message else {
+            panic!("expected a delta, got {:?}", message);
+        };
+        assert!(payload.changed_nodes.is_empty());
+        assert_eq!(payload.added_nodes[0].node_id, "worker-09");
+        assert_eq!(payload.removed_nodes, vec!["worker-01"]);
+
+        // A resync sends the full snapshot again
+        assert!(matches!(
+            stream.next(&changed, true),
+            Some(WsSnapshotMessage::Full { .. })
+        ));
+    }
 
-    Ok(Json(serde_json::json!({
-        "success": true,
-        "dry_run": new_dry_run,
-        "message": format!("Dry-run mode {}", if new_dry_run { "enabled" } else { "disabled" })
-    })))
-}
+    #[test]
+    fn test_ws_subscription_full_then_deltas() {
+        let mut subs = WsSubscriptions::default();
+        subs.handle_client_message(r#"{"subscribe": ["nodes", "node:worker-07"]}"#)
+            .unwrap();
+        assert!(!subs.needs_contention());
+
+        // Full state on subscribe
+        let first = ws_cluster(&[("worker-01", 10.0), ("worker-07", 20.0)]);
+        let messages = subs.updates(Some(&first), None, false);
+        assert_eq!(messages.len(), 2);
+        match &messages[0] {
+            WsServerMessage::Full { topic, data } => {
+                assert_eq!(topic, "nodes");
+                assert_eq!(
+                    data.keys().collect::<Vec<_>>(),
+                    vec!["worker-01", "worker-07"]
+                );
+            }
+            other => panic!("expected full nodes frame, got {:?}", other),
+        }
+        match &messages[1] {
+            WsServerMessage::Full { topic, data } => {
+                assert_eq!(topic, "node:worker-07");
+                assert!(data.contains_key("gpu:0") && data.contains_key("gpu:1"));
+            }
+            other => panic!("expected full node frame, got {:?}", other),
+        }
 
-/// Test Guard Mode policies
-async fn test_guard_policies(
-    State(_state): State<CoordinatorState>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    use crate::guard_mode::GuardModeManager;
-    use crate::vendor::GpuManager;
+        // Fresh timestamps alone produce no frames
+        let same = ws_cluster(&[("worker-01", 10.0), ("worker-07", 20.0)]);
+        assert!(subs.updates(Some(&same), None, false).is_empty());
 
-    let mut guard_manager =
-        GuardModeManager::new().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        // One GPU changes on worker-07 and worker-01 disappears
+        let changed = ws_cluster(&[("worker-07", 95.0)]);
+        let messages = subs.updates(Some(&changed), None, false);
+        assert_eq!(
+            messages[0],
+            WsServerMessage::Delta {
+                topic: "nodes".to_string(),
+                changed: [("worker-07".to_string(), topic_value(&changed.nodes[0]))]
+                    .into_iter()
+                    .collect(),
+                removed: vec!["worker-01".to_string()],
+            }
+        );
+        match &messages[1] {
+            WsServerMessage::Delta {
+                topic,
+                changed,
+                removed,
+            } => {
+                assert_eq!(topic, "node:worker-07");
+                assert_eq!(changed.keys().collect::<Vec<_>>(), vec!["gpu:0"]);
+                assert!(removed.is_empty());
+            }
+            other => panic!("expected node delta, got {:?}", other),
+        }
 
-    // Get current GPU processes for testing
-    let gpu_manager = GpuManager::initialize().map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-    let test_processes = gpu_manager
-        .get_all_processes()
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        // Periodic resync sends full state again
+        let messages = subs.updates(Some(&changed), None, true);
+        assert!(messages
+            .iter()
+            .all(|m| matches!(m, WsServerMessage::Full { .. })));
 
-    let result = guard_manager
-        .simulate_policy_check(&test_processes)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        // Unsubscribing stops pushes for the topic
+        subs.handle_client_message(r#"{"unsubscribe": ["nodes", "node:worker-07"]}"#)
+            .unwrap();
+        assert!(subs.updates(Some(&first), None, false).is_empty());
+    }
 
-    Ok(Json(serde_json::json!({
-        "success": true,
-        "simulation_result": {
-            "violations": result.violations,
-            "warnings": result.warnings,
-            "actions_taken": result.actions_taken,
-            "dry_run": result.dry_run,
-            "timestamp": result.timestamp
-        },
-        "summary": {
-            "violation_count": result.violations.len(),
-            "warning_count": result.warnings.len(),
-            "action_count": result.actions_taken.len()
+    #[test]
+    fn test_ws_contention_topic_and_malformed_messages() {
+        let mut subs = WsSubscriptions::default();
+        subs.handle_client_message(r#"{"subscribe": ["contention"]}"#)
+            .unwrap();
+        assert!(subs.needs_contention());
+
+        // Nothing is sent (or forgotten) while the analysis is unavailable
+        assert!(subs.updates(None, None, false).is_empty());
+        let analysis = ContentionAnalysis {
+            blocked_gpus: Vec::new(),
+            top_users: Vec::new(),
+            recommendations: vec!["all clear".to_string()],
+            maintenance_gpus: Vec::new(),
+            node_summaries: Vec::new(),
+            idle_gpus: Vec::new(),
+            unhealthy_gpus: Vec::new(),
+            fair_share_violations: Vec::new(),
+        };
+        let messages = subs.updates(None, Some(&analysis), false);
+        match &messages[0] {
+            WsServerMessage::Full { data, .. } => {
+                assert_eq!(data["recommendations"], serde_json::json!(["all clear"]));
+            }
+            other => panic!("expected full contention frame, got {:?}", other),
         }
-    })))
-}
+        assert!(subs.updates(None, None, false).is_empty());
+        assert!(subs.updates(None, Some(&analysis), false).is_empty());
+
+        // Malformed messages are rejected without changing subscriptions
+        for bad in [
+            "not json",
+            r#"{"subscribe": "nodes"}"#,
+            r#"{"subscribe": ["gpus"]}"#,
+            r#"{"subscribe": ["node:"]}"#,
+            r#"{"subscrib": ["nodes"]}"#,
+        ] {
+            assert!(subs.handle_client_message(bad).is_err(), "{}", bad);
+        }
+        assert!(!subs.last.contains_key(&WsTopic::Nodes));
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::vendor::GpuVendor;
-    use std::collections::HashMap;
+        let frame = serde_json::to_value(WsServerMessage::Error {
+            message: "invalid message".to_string(),
+        })
+        .unwrap();
+        assert_eq!(frame["type"], "error");
+    }
 
     #[tokio::test]
-    async fn test_contention_analysis_gpu_count_unique() {
+    async fn test_ws_slow_client_is_dropped() {
+        use axum::extract::ws::Message;
+
+        let (tx, mut rx) = tokio::sync::mpsc::channel::<Message>(2);
+        assert!(queue_frame(&tx, Message::Text("1".to_string())));
+        assert!(queue_frame(&tx, Message::Text("2".to_string())));
+        // The writer has not drained anything: the client is too slow
+        assert!(!queue_frame(&tx, Message::Text("3".to_string())));
+
+        rx.recv().await.unwrap();
+        assert!(queue_frame(&tx, Message::Text("3".to_string())));
+
+        // A closed connection is also reported
+        drop(rx);
+        assert!(!queue_frame(&tx, Message::Text("4".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_stale_nodes_go_offline_then_are_removed() {
+        let state = CoordinatorState::new().with_node_lifecycle(NodeLifecycle {
+            offline_after: Duration::from_secs(60),
+            remove_after: Duration::from_secs(600),
+        });
+        let last_seen = Utc::now();
+        state
+            .register_node(NodeInfo {
+                id: "node-a".to_string(),
+                hostname: "host-a".to_string(),
+                ip_address: "127.0.0.1".to_string(),
+                last_seen,
+                status: NodeStatus::Online,
+                gpu_count: 0,
+                total_memory_gb: 0.0,
+                tags: HashMap::new(),
+                drain: None,
+            })
+            .await
+            .unwrap();
+        state
+            .update_snapshot(
+                "node-a".to_string(),
+                NodeSnapshot {
+                    node_id: "node-a".to_string(),
+                    hostname: "host-a".to_string(),
+                    timestamp: last_seen,
+                    gpus: Vec::new(),
+                    processes: Vec::new(),
+                    status: NodeStatus::Online,
+                    tags: HashMap::new(),
+                    errors: Vec::new(),
+                },
+            )
+            .await
+            .unwrap();
+        let last_seen = state.get_nodes().await[0].last_seen;
+        let status = |nodes: &[NodeInfo]| nodes.first().map(|n| n.status.clone());
+
+        // Below the offline threshold the node stays online
+        state
+            .cleanup_stale_nodes_at(last_seen + chrono::Duration::seconds(30))
+            .await
+            .unwrap();
+        assert!(matches!(
+            status(&state.get_nodes().await),
+            Some(NodeStatus::Online)
+        ));
+
+        // Past the offline threshold it is marked offline but still listed
+        let offline_at = last_seen + chrono::Duration::seconds(120);
+        state.cleanup_stale_nodes_at(offline_at).await.unwrap();
+        let nodes = state.get_nodes().await;
+        assert!(matches!(status(&nodes), Some(NodeStatus::Offline)));
+        assert!(state.snapshots.read().await.contains_key("node-a"));
+        let listing = NodeListing::new(nodes[0].clone(), offline_at);
+        assert_eq!(listing.last_seen_secs, 120);
+        let json = serde_json::to_value(&listing).unwrap();
+        assert_eq!(json["status"], "Offline");
+        assert_eq!(json["last_seen_secs"], 120);
+
+        // Past the removal threshold it is gone, along with its snapshot
+        state
+            .cleanup_stale_nodes_at(last_seen + chrono::Duration::seconds(600))
+            .await
+            .unwrap();
+        assert!(state.get_nodes().await.is_empty());
+        assert!(state.snapshots.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_offline_node_comes_back_online_when_it_reports() {
         let state = CoordinatorState::new();
+        let last_seen = Utc::now() - chrono::Duration::minutes(5);
+        state
+            .register_node(NodeInfo {
+                id: "node-b".to_string(),
+                hostname: "host-b".to_string(),
+                ip_address: "127.0.0.1".to_string(),
+                last_seen,
+                status: NodeStatus::Online,
+                gpu_count: 0,
+                total_memory_gb: 0.0,
+                tags: HashMap::new(),
+                drain: None,
+            })
+            .await
+            .unwrap();
+        state.cleanup_stale_nodes().await.unwrap();
+        assert!(matches!(
+            state.get_nodes().await[0].status,
+            NodeStatus::Offline
+        ));
 
-        // Scenario: User "alice" has 2 processes on GPU 0
-        // Expected: gpu_count should be 1 (alice is using 1 unique GPU)
-        let snapshot = NodeSnapshot {
-            node_id: "test-node".to_string(),
-            hostname: "test-host".to_string(),
-            timestamp: Utc::now(),
-            gpus: vec![GpuSnapshot {
-                gpu_index: 0,
-                name: "Test GPU".to_string(),
-                vendor: GpuVendor::Nvidia,
-                mem_used_mb: 8000,
-                mem_total_mb: 10000,
-                util_pct: 90.0,
-                temp_c: 75,
-                power_w: 200.0,
-                ecc_volatile: None,
-                pids: 2,
-                top_proc: None,
-            }],
-            processes: vec![
-                GpuProc {
-                    gpu_index: 0,
-                    pid: 1234,
-                    user: "alice".to_string(),
-                    proc_name: "process1".to_string(),
-                    used_mem_mb: 4000,
-                    start_time: "2025-09-20T01:00:00Z".to_string(),
-                    container: None,
-                    node_id: None,
+        state
+            .update_snapshot(
+                "node-b".to_string(),
+                NodeSnapshot {
+                    node_id: "node-b".to_string(),
+                    hostname: "host-b".to_string(),
+                    timestamp: Utc::now(),
+                    gpus: Vec::new(),
+                    processes: Vec::new(),
+                    status: NodeStatus::Online,
+                    tags: HashMap::new(),
+                    errors: Vec::new(),
                 },
-                GpuProc {
-                    gpu_index: 0,
-                    pid: 5678,
+            )
+            .await
+            .unwrap();
+        assert!(matches!(
+            state.get_nodes().await[0].status,
+            NodeStatus::Online
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_degraded_snapshot_marks_node_degraded() {
+        let state = CoordinatorState::new();
+        state
+            .register_node(NodeInfo {
+                id: "node-c".to_string(),
+                hostname: "host-c".to_string(),
+                ip_address: "127.0.0.1".to_string(),
+                last_seen: Utc::now(),
+                status: NodeStatus::Online,
+                gpu_count: 2,
+                total_memory_gb: 0.0,
+                tags: HashMap::new(),
+                drain: None,
+            })
+            .await
+            .unwrap();
+        let snapshot = |status| NodeSnapshot {
+            node_id: "node-c".to_string(),
+            hostname: "host-c".to_string(),
+            timestamp: Utc::now(),
+            gpus: Vec::new(),
+            processes: Vec::new(),
+            status,
+            tags: HashMap::new(),
+            errors: Vec::new(),
+        };
+
+        state
+            .update_snapshot("node-c".to_string(), snapshot(NodeStatus::Degraded))
+            .await
+            .unwrap();
+        assert!(matches!(
+            state.get_nodes().await[0].status,
+            NodeStatus::Degraded
+        ));
+        let cluster = state.get_cluster_snapshot().await.unwrap();
+        assert!(matches!(cluster.nodes[0].status, NodeStatus::Degraded));
+
+        // Once every GPU answers again the node is back online
+        state
+            .update_snapshot("node-c".to_string(), snapshot(NodeStatus::Online))
+            .await
+            .unwrap();
+        assert!(matches!(
+            state.get_nodes().await[0].status,
+            NodeStatus::Online
+        ));
+    }
+
+    #[test]
+    fn test_contention_report_markdown_sections() {
+        let analysis = ContentionAnalysis {
+            blocked_gpus: vec![BlockedGpu {
+                node_id: "node-a".to_string(),
+                gpu_index: 1,
+                gpu_name: "NVIDIA A100".to_string(),
+                blocking_processes: vec![GpuProc {
+                    gpu_index: 1,
+                    pid: 4242,
                     user: "alice".to_string(),
-                    proc_name: "process2".to_string(),
-                    used_mem_mb: 4000,
-                    start_time: "2025-09-20T01:00:00Z".to_string(),
-                    container: None,
-                    node_id: None,
-                },
-            ],
+                    proc_name: "train|v2".to_string(),
+                    used_mem_mb: 30720,
+                    start_time: "2h".to_string(),
+                    node_id: Some("node-a".to_string()),
+                    ..Default::default()
+                }],
+                utilization_pct: 97.5,
+                memory_used_mb: 30720,
+                memory_total_mb: 40960,
+            }],
+            top_users: vec![UserUsage {
+                user: "alice".to_string(),
+                gpu_count: 2,
+                total_memory_mb: 61440,
+                avg_utilization: 91.0,
+                process_count: 3,
+            }],
+            recommendations: vec!["Move jobs from node-a to node-b".to_string()],
+            maintenance_gpus: Vec::new(),
+            node_summaries: vec![NodeContention {
+                node_id: "node-a".to_string(),
+                hostname: "host-a".to_string(),
+                total_gpus: 2,
+                blocked_gpus: 1,
+                idle_gpus: 0,
+                maintenance_gpus: 0,
+                unhealthy_gpus: 0,
+                avg_utilization: 80.0,
+                draining: false,
+            }],
+            idle_gpus: vec![("node-b".to_string(), 0)],
+            unhealthy_gpus: Vec::new(),
+            fair_share_violations: Vec::new(),
+        };
+        let generated_at = DateTime::parse_from_rfc3339("2024-06-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let md = analysis.to_markdown(generated_at);
+        assert!(md.starts_with("# GPU Contention Report\n"));
+        assert!(md.contains("Generated: 2024-06-01T12:00:00Z"));
+        assert!(md.contains("| Blocked GPUs | 1 |"));
+        assert!(md.contains("| Idle GPUs | 1 |"));
+        assert!(md.contains(
+            "## Blocked GPUs\n\n| Node | GPU | Name | Utilization | Memory (GiB) | Blocking processes |"
+        ));
+        // Pipes in process names must not break the table
+        assert!(md.contains(
+            "| node-a | 1 | NVIDIA A100 | 97.5% | 30.0/40.0 | train\\|v2 (PID 4242, alice) |"
+        ));
+        assert!(md.contains("## Top Users\n\n| User | GPUs |"));
+        assert!(md.contains("| alice | 2 | 60.0 | 91.0% | 3 |"));
+        assert!(md.contains("| node-a | host-a | 2 | 1 | 0 | 0 | 0 | 80.0% |"));
+        assert!(md.contains("## Recommendations\n\n- Move jobs from node-a to node-b\n"));
+        // Empty sections are left out
+        assert!(!md.contains("## Unhealthy GPUs"));
+        assert!(!md.contains("## GPUs in Maintenance"));
+    }
+
+    #[tokio::test]
+    async fn test_node_tags_drive_snapshot_and_contention_selectors() {
+        let state = CoordinatorState::new();
+        let gpu = |util_pct: f32| GpuSnapshot {
+            gpu_index: 0,
+            name: "Test GPU".to_string(),
+            vendor: GpuVendor::Nvidia,
+            mem_used_mb: 0,
+            mem_total_mb: 8192,
+            util_pct,
+            temp_c: 40,
+            power_w: 50.0,
+            pids: 0,
+            ..Default::default()
+        };
+
+        for (id, util_pct, tags) in [
+            ("node-a", 80.0, [("rack", "a1"), ("team", "nlp")]),
+            ("node-b", 20.0, [("rack", "b2"), ("team", "vision")]),
+        ] {
+            let tags: HashMap<String, String> = tags
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            state
+                .register_node(NodeInfo {
+                    id: id.to_string(),
+                    hostname: id.to_string(),
+                    ip_address: "127.0.0.1".to_string(),
+                    last_seen: Utc::now(),
+                    status: NodeStatus::Online,
+                    gpu_count: 1,
+                    total_memory_gb: 8.0,
+                    tags,
+                    drain: None,
+                })
+                .await
+                .unwrap();
+            // Agents need not repeat their tags; the registration is authoritative
+            state
+                .update_snapshot(
+                    id.to_string(),
+                    NodeSnapshot {
+                        node_id: id.to_string(),
+                        hostname: id.to_string(),
+                        timestamp: Utc::now(),
+                        gpus: vec![gpu(util_pct)],
+                        processes: Vec::new(),
+                        status: NodeStatus::Online,
+                        tags: HashMap::new(),
+                        errors: Vec::new(),
+                    },
+                )
+                .await
+                .unwrap();
+        }
+
+        let snapshot = state.get_cluster_snapshot().await.unwrap();
+        assert_eq!(snapshot.total_gpus, 2);
+        assert!(snapshot.nodes.iter().all(|n| n.tags.contains_key("rack")));
+
+        let nlp = snapshot.select(&"team=nlp".parse().unwrap());
+        assert_eq!(nlp.nodes.len(), 1);
+        assert_eq!(nlp.nodes[0].node_id, "node-a");
+        assert_eq!(nlp.total_gpus, 1);
+        assert_eq!(nlp.total_memory_gb, 8.0);
+        assert_eq!(nlp.utilization_avg, 80.0);
+
+        let both = snapshot.select(&"rack in (a1,b2)".parse().unwrap());
+        assert_eq!(both.nodes.len(), 2);
+        assert_eq!(both.utilization_avg, 50.0);
+
+        let selector: LabelSelector = "team notin (nlp)".parse().unwrap();
+        let analysis = state
+            .get_contention_analysis_for(Some(&selector))
+            .await
+            .unwrap();
+        assert_eq!(analysis.node_summaries.len(), 1);
+        assert_eq!(analysis.node_summaries[0].node_id, "node-b");
+        assert_eq!(
+            state
+                .get_contention_analysis()
+                .await
+                .unwrap()
+                .node_summaries
+                .len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_node_churn_publishes_cluster_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let store: Arc<dyn AuditStore> =
+            Arc::new(crate::audit_store::JsonlStore::new(dir.path().to_path_buf()).unwrap());
+        let state = CoordinatorState::new()
+            .with_node_lifecycle(NodeLifecycle {
+                offline_after: Duration::from_secs(60),
+                remove_after: Duration::from_secs(600),
+            })
+            .with_event_store(store.clone());
+        let mut subscriber = state.events.subscribe();
+
+        let node = |id: &str| NodeInfo {
+            id: id.to_string(),
+            hostname: format!("host-{}", id),
+            ip_address: "127.0.0.1".to_string(),
+            last_seen: Utc::now(),
+            status: NodeStatus::Online,
+            gpu_count: 1,
+            total_memory_gb: 10.0,
+            tags: HashMap::new(),
+            drain: None,
+        };
+        let snapshot = |id: &str, util_pct: f32| NodeSnapshot {
+            node_id: id.to_string(),
+            hostname: format!("host-{}", id),
+            timestamp: Utc::now(),
+            gpus: vec![GpuSnapshot {
+                gpu_index: 0,
+                name: "Test GPU".to_string(),
+                vendor: GpuVendor::Nvidia,
+                mem_used_mb: 1000,
+                mem_total_mb: 10000,
+                util_pct,
+                temp_c: 40,
+                power_w: 50.0,
+                pids: 1,
+                ..Default::default()
+            }],
+            processes: vec![GpuProc {
+                gpu_index: 0,
+                pid: 4242,
+                user: "alice".to_string(),
+                proc_name: "train".to_string(),
+                used_mem_mb: 1000,
+                start_time: "unknown".to_string(),
+                ..Default::default()
+            }],
             status: NodeStatus::Online,
+            tags: HashMap::new(),
+            errors: Vec::new(),
+        };
+        let kinds = |events: &[ClusterEvent]| -> Vec<String> {
+            events
+                .iter()
+                .map(|event| {
+                    let value = serde_json::to_value(event).unwrap();
+                    format!(
+                        "{}:{}",
+                        value["type"].as_str().unwrap(),
+                        value["node_id"].as_str().unwrap()
+                    )
+                })
+                .collect()
         };
 
+        // Two nodes join; re-registering a node that is online is not a new join
+        state.register_node(node("a")).await.unwrap();
+        state.register_node(node("b")).await.unwrap();
+        state.register_node(node("a")).await.unwrap();
+        let start = Utc::now();
+
+        // GPU 0 on node a becomes blocked once, however many snapshots report it
         state
-            .register_node(NodeInfo {
-                id: "test-node".to_string(),
-                hostname: "test-host".to_string(),
-                ip_address: "127.0.0.1".to_string(),
-                last_seen: Utc::now(),
-                status: NodeStatus::Online,
-                gpu_count: 1,
-                total_memory_gb: 9.8,
-                tags: HashMap::new(),
-            })
+            .update_snapshot("a".to_string(), snapshot("a", 95.0))
+            .await
+            .unwrap();
+        state
+            .update_snapshot("a".to_string(), snapshot("a", 97.0))
+            .await
+            .unwrap();
+        state
+            .update_snapshot("a".to_string(), snapshot("a", 5.0))
             .await
             .unwrap();
 
-        // Update the snapshot
+        // Both nodes go silent, then b comes back and a is removed
+        let later = Utc::now() + chrono::Duration::seconds(61);
+        state.cleanup_stale_nodes_at(later).await.unwrap();
+        state.register_node(node("b")).await.unwrap();
         state
-            .update_snapshot("test-node".to_string(), snapshot)
+            .cleanup_stale_nodes_at(later + chrono::Duration::seconds(600))
             .await
             .unwrap();
 
-        // Get contention analysis
-        let analysis = state.get_contention_analysis().await.unwrap();
+        let expected = vec![
+            "node_registered:a",
+            "node_registered:b",
+            "gpu_blocked:a",
+            "gpu_recovered:a",
+            "node_offline:a",
+            "node_offline:b",
+            "node_registered:b",
+        ];
+        let all = state.events.since(None, EVENT_PAGE_LIMIT).await.unwrap();
+        assert_eq!(kinds(&all), expected);
+        match &all[2] {
+            ClusterEvent::GpuBlocked {
+                utilization_pct,
+                users,
+                ..
+            } => {
+                assert_eq!(*utilization_pct, 95.0);
+                assert_eq!(users, &vec!["alice".to_string()]);
+            }
+            other => panic!("expected gpu_blocked, got {:?}", other),
+        }
 
-        // Find alice's stats
-        let alice_stats = analysis
-            .top_users
-            .iter()
-            .find(|u| u.user == "alice")
-            .expect("Alice should be in top users");
+        // WebSocket subscribers receive the same events as they happen
+        let mut pushed = Vec::new();
+        while let Ok(event) = subscriber.try_recv() {
+            pushed.push(event);
+        }
+        assert_eq!(pushed, all);
 
-        // Verify that gpu_count correctly reflects unique GPUs, not process count
-        assert_eq!(alice_stats.process_count, 2, "Alice has 2 processes");
-        assert_eq!(
-            alice_stats.gpu_count, 1,
-            "Alice uses 1 unique GPU, not 2 (one per process)"
-        );
-        assert_eq!(alice_stats.total_memory_mb, 8000, "Total memory is correct");
+        // `since` skips older events
+        let recent = state.events.since(Some(start), 2).await.unwrap();
+        assert_eq!(kinds(&recent), vec!["gpu_blocked:a", "gpu_recovered:a"]);
+
+        // A restarted coordinator reads the persisted events back from the store
+        let restarted = ClusterEvents::new(EVENT_BUFFER_CAPACITY).with_store(store);
+        let persisted = restarted
+            .since(Some(start - chrono::Duration::hours(1)), EVENT_PAGE_LIMIT)
+            .await
+            .unwrap();
+        assert_eq!(persisted, all);
     }
 
     #[tokio::test]
-    async fn test_contention_analysis_multi_gpu_multi_node() {
-        let state = CoordinatorState::new();
-
-        // Scenario: User "bob" has processes on GPU 0 and GPU 1 across two nodes
-        // Expected: gpu_count should be 4 (2 GPUs per node × 2 nodes)
-
-        // Node 1: bob has 2 processes on GPU 0, 1 process on GPU 1
-        let snapshot1 = NodeSnapshot {
-            node_id: "node-1".to_string(),
-            hostname: "host-1".to_string(),
+    async fn test_event_buffer_is_bounded_and_conditions_fire_once() {
+        let events = ClusterEvents::new(3);
+        let offline = |n: usize| ClusterEvent::NodeOffline {
             timestamp: Utc::now(),
-            gpus: vec![
-                GpuSnapshot {
-                    gpu_index: 0,
-                    name: "GPU 0".to_string(),
-                    vendor: GpuVendor::Nvidia,
-                    mem_used_mb: 4000,
-                    mem_total_mb: 10000,
-                    util_pct: 50.0,
-                    temp_c: 70,
-                    power_w: 150.0,
-                    ecc_volatile: None,
-                    pids: 2,
-                    top_proc: None,
-                },
-                GpuSnapshot {
-                    gpu_index: 1,
-                    name: "GPU 1".to_string(),
-                    vendor: GpuVendor::Nvidia,
-                    mem_used_mb: 2000,
-                    mem_total_mb: 10000,
-                    util_pct: 25.0,
-                    temp_c: 65,
-                    power_w: 100.0,
-                    ecc_volatile: None,
-                    pids: 1,
-                    top_proc: None,
-                },
-            ],
-            processes: vec![
-                GpuProc {
-                    gpu_index: 0,
-                    pid: 1001,
-                    user: "bob".to_string(),
-                    proc_name: "train1".to_string(),
-                    used_mem_mb: 2000,
-                    start_time: "2025-09-20T01:00:00Z".to_string(),
-                    container: None,
-                    node_id: None,
-                },
-                GpuProc {
-                    gpu_index: 0,
-                    pid: 1002,
-                    user: "bob".to_string(),
-                    proc_name: "train2".to_string(),
-                    used_mem_mb: 2000,
-                    start_time: "2025-09-20T01:00:00Z".to_string(),
-                    container: None,
-                    node_id: None,
-                },
-                GpuProc {
-                    gpu_index: 1,
-                    pid: 1003,
-                    user: "bob".to_string(),
-                    proc_name: "train3".to_string(),
-                    used_mem_mb: 2000,
-                    start_time: "2025-09-20T01:00:00Z".to_string(),
-                    container: None,
-                    node_id: None,
-                },
-            ],
-            status: NodeStatus::Online,
+            node_id: format!("node-{}", n),
+            hostname: format!("host-{}", n),
+            last_seen: Utc::now(),
         };
+        events.publish((0..5).map(offline).collect()).await;
+        let buffered = events.since(None, EVENT_PAGE_LIMIT).await.unwrap();
+        assert_eq!(buffered.len(), 3);
+        assert!(
+            matches!(&buffered[0], ClusterEvent::NodeOffline { node_id, .. } if node_id == "node-2")
+        );
 
-        // Node 2: bob has 1 process each on GPU 0 and GPU 1 (same indices as node 1)
-        let snapshot2 = NodeSnapshot {
-            node_id: "node-2".to_string(),
-            hostname: "host-2".to_string(),
+        let rogue = |pid: u32| ClusterEvent::RogueDetected {
             timestamp: Utc::now(),
-            gpus: vec![
-                GpuSnapshot {
-                    gpu_index: 0,
-                    name: "GPU 0".to_string(),
-                    vendor: GpuVendor::Nvidia,
-                    mem_used_mb: 2000,
-                    mem_total_mb: 10000,
-                    util_pct: 30.0,
-                    temp_c: 60,
-                    power_w: 120.0,
-                    ecc_volatile: None,
-                    pids: 1,
-                    top_proc: None,
-                },
-                GpuSnapshot {
-                    gpu_index: 1,
-                    name: "GPU 1".to_string(),
-                    vendor: GpuVendor::Nvidia,
-                    mem_used_mb: 2000,
-                    mem_total_mb: 10000,
-                    util_pct: 30.0,
-                    temp_c: 60,
-                    power_w: 120.0,
-                    ecc_volatile: None,
-                    pids: 1,
-                    top_proc: None,
-                },
-            ],
-            processes: vec![
-                GpuProc {
-                    gpu_index: 0,
-                    pid: 2001,
-                    user: "bob".to_string(),
-                    proc_name: "train4".to_string(),
-                    used_mem_mb: 2000,
-                    start_time: "2025-09-20T01:00:00Z".to_string(),
-                    container: None,
-                    node_id: None,
-                },
-                GpuProc {
-                    gpu_index: 1,
-                    pid: 2002,
-                    user: "bob".to_string(),
-                    proc_name: "train5".to_string(),
-                    used_mem_mb: 2000,
-                    start_time: "2025-09-20T01:00:00Z".to_string(),
-                    container: None,
-                    node_id: None,
-                },
-            ],
+            node_id: Some("node-a".to_string()),
+            gpu_index: 0,
+            pid,
+            user: "mallory".to_string(),
+            process_name: "xmrig".to_string(),
+            category: "crypto_miner".to_string(),
+            confidence: 0.9,
+            reasons: Vec::new(),
+        };
+        assert_eq!(events.started("rogue", vec![rogue(1)]).len(), 1);
+        // Still running: not reported again, while a new process is
+        let started = events.started("rogue", vec![rogue(1), rogue(2)]);
+        assert!(matches!(
+            &started[..],
+            [ClusterEvent::RogueDetected { pid: 2, .. }]
+        ));
+        // Once it stops and starts again it is reported again
+        assert!(events.started("rogue", vec![rogue(2)]).is_empty());
+        assert_eq!(events.started("rogue", vec![rogue(1), rogue(2)]).len(), 1);
+
+        let mut subs = WsSubscriptions::default();
+        subs.handle_client_message(r#"{"subscribe": ["events"]}"#)
+            .unwrap();
+        assert!(subs.wants_events());
+        assert!(subs.updates(None, None, false).is_empty());
+        let frame = serde_json::to_value(WsServerMessage::Event { event: rogue(1) }).unwrap();
+        assert_eq!(frame["type"], "event");
+        assert_eq!(frame["event"]["type"], "rogue_detected");
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_uploads_compress_and_apply_deltas() {
+        let gpu = |index: u16, util_pct: f32| GpuSnapshot {
+            gpu_index: index,
+            name: "NVIDIA H100 80GB HBM3".to_string(),
+            vendor: GpuVendor::Nvidia,
+            mem_used_mb: 40000,
+            mem_total_mb: 81920,
+            util_pct,
+            temp_c: 60,
+            power_w: 350.0,
+            pids: 250,
+            uuid: Some(format!("GPU-0000000{}-aaaa-bbbb-cccc-dddddddddddd", index)),
+            ..Default::default()
+        };
+        let processes: Vec<GpuProc> = (0..2000)
+            .map(|i| GpuProc {
+                gpu_index: (i % 8) as u16,
+                pid: 100_000 + i,
+                user: format!("user{}", i % 40),
+                proc_name: "python".to_string(),
+                used_mem_mb: 160,
+                start_time: "2026-03-02T08:00:00Z".to_string(),
+                container: Some(format!("job-{}", i % 100)),
+                cmdline: Some(format!(
+                    "python train.py --config configs/run-{}.yaml --seed {}",
+                    i % 100,
+                    i
+                )),
+                ..Default::default()
+            })
+            .collect();
+        let base_time = Utc::now();
+        let previous = NodeSnapshot {
+            node_id: "node-a".to_string(),
+            hostname: "host-a".to_string(),
+            timestamp: base_time,
+            gpus: (0..8).map(|i| gpu(i, 90.0)).collect(),
+            processes,
             status: NodeStatus::Online,
+            tags: HashMap::new(),
+            errors: Vec::new(),
+        };
+
+        // The gzipped body is a fraction of the JSON and decodes to the same snapshot
+        let full = SnapshotUpload::new(&previous, None);
+        let plain = full.encode(false).unwrap();
+        let gzipped = full.encode(true).unwrap();
+        assert!(
+            gzipped.len() * 5 < plain.len(),
+            "gzip {} bytes vs JSON {} bytes",
+            gzipped.len(),
+            plain.len()
+        );
+        match SnapshotUpload::decode(&gzipped, true).unwrap() {
+            SnapshotUpload::Full(decoded) => assert!(same_json(&decoded, &previous)),
+            other => panic!("expected a full snapshot, got {:?}", other),
+        }
+        assert!(SnapshotUpload::decode(&plain, true).is_err());
+
+        // One GPU changed: the delta carries only that GPU
+        let mut current = previous.clone();
+        current.timestamp = base_time + chrono::Duration::seconds(30);
+        current.gpus[3].util_pct = 12.0;
+        let delta = SnapshotUpload::new(&current, Some(&previous));
+        let delta_body = delta.encode(true).unwrap();
+        assert!(delta_body.len() * 10 < gzipped.len());
+        let SnapshotUpload::Delta { delta } = SnapshotUpload::decode(&delta_body, true).unwrap()
+        else {
+            panic!("expected a delta");
         };
+        assert_eq!(delta.changed_gpus.len(), 1);
+        assert!(delta.processes.is_none() && delta.status.is_none());
 
+        // The coordinator rebuilds the full snapshot from the one it holds
+        let state = CoordinatorState::new();
         state
             .register_node(NodeInfo {
-                id: "node-1".to_string(),
-                hostname: "host-1".to_string(),
-                ip_address: "10.0.0.1".to_string(),
+                id: "node-a".to_string(),
+                hostname: "host-a".to_string(),
+                ip_address: "127.0.0.1".to_string(),
                 last_seen: Utc::now(),
                 status: NodeStatus::Online,
-                gpu_count: 2,
-                total_memory_gb: 19.5,
+                gpu_count: 8,
+                total_memory_gb: 640.0,
                 tags: HashMap::new(),
+                drain: None,
             })
             .await
             .unwrap();
+        assert!(state
+            .resolve_snapshot_delta("node-a", delta.clone())
+            .await
+            .is_err());
+        state
+            .update_snapshot("node-a".to_string(), previous.clone())
+            .await
+            .unwrap();
+        let rebuilt = state
+            .resolve_snapshot_delta("node-a", delta.clone())
+            .await
+            .unwrap();
+        assert!(same_json(&rebuilt, &current));
+
+        // A delta against a snapshot the coordinator no longer holds is refused
+        state
+            .update_snapshot("node-a".to_string(), current.clone())
+            .await
+            .unwrap();
+        assert!(state.resolve_snapshot_delta("node-a", delta).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_grafana_routes_answer_simple_json_queries() {
+        let state = CoordinatorState::new();
         state
             .register_node(NodeInfo {
-                id: "node-2".to_string(),
-                hostname: "host-2".to_string(),
-                ip_address: "10.0.0.2".to_string(),
+                id: "node-a".to_string(),
+                hostname: "gpu-01".to_string(),
+                ip_address: "127.0.0.1".to_string(),
                 last_seen: Utc::now(),
                 status: NodeStatus::Online,
                 gpu_count: 2,
-                total_memory_gb: 19.5,
+                total_memory_gb: 20.0,
                 tags: HashMap::new(),
+                drain: None,
+            })
+            .await
+            .unwrap();
+        let mut snapshot = ws_cluster(&[("node-a", 42.0)]).nodes.remove(0);
+        snapshot.hostname = "gpu-01".to_string();
+        state
+            .update_snapshot("node-a".to_string(), snapshot)
+            .await
+            .unwrap();
+
+        assert_eq!(grafana_health().await, StatusCode::OK);
+
+        let search: crate::grafana::SearchRequest =
+            serde_json::from_value(serde_json::json!({ "target": "gpu0/u" })).unwrap();
+        let Json(names) = grafana_search(State(state.clone()), Json(search)).await;
+        assert_eq!(names, vec!["gpu-01/gpu0/util"]);
+
+        let now = Utc::now();
+        let query = |targets: serde_json::Value| {
+            serde_json::from_value::<crate::grafana::QueryRequest>(serde_json::json!({
+                "range": {
+                    "from": (now - chrono::Duration::hours(1)).to_rfc3339(),
+                    "to": (now + chrono::Duration::minutes(1)).to_rfc3339(),
+                },
+                "maxDataPoints": 100,
+                "targets": targets,
+            }))
+            .unwrap()
+        };
+
+        let Json(responses) = grafana_query(
+            State(state.clone()),
+            Json(query(serde_json::json!([
+                { "target": "gpu-01/gpu0/util", "refId": "A", "type": "timeserie" },
+                { "target": "gpus", "refId": "B", "type": "table" },
+            ]))),
+        )
+        .await
+        .unwrap();
+        let body = serde_json::to_value(responses).unwrap();
+        assert_eq!(body[0]["target"], "gpu-01/gpu0/util");
+        let points = body[0]["datapoints"].as_array().unwrap();
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0][0], 42.0);
+        assert!(points[0][1].as_f64().unwrap() > 0.0);
+        assert_eq!(body[1]["type"], "table");
+        assert_eq!(body[1]["columns"][0]["text"], "Node");
+        assert_eq!(body[1]["rows"].as_array().unwrap().len(), 2);
+        assert_eq!(body[1]["rows"][0][0], "gpu-01");
+
+        let rejected = grafana_query(
+            State(state.clone()),
+            Json(query(serde_json::json!([
+                { "target": "gpu-01/gpu0/fan", "type": "timeserie" },
+            ]))),
+        )
+        .await;
+        assert_eq!(rejected.unwrap_err(), StatusCode::BAD_REQUEST);
+    }
+
+    fn rogue_node(id: &str) -> NodeInfo {
+        NodeInfo {
+            id: id.to_string(),
+            hostname: format!("{}.example", id),
+            ip_address: "10.0.0.1".to_string(),
+            last_seen: Utc::now(),
+            status: NodeStatus::Online,
+            gpu_count: 1,
+            total_memory_gb: 40.0,
+            tags: HashMap::new(),
+            drain: None,
+        }
+    }
+
+    fn rogue_result(
+        risk_score: f32,
+        miner_pid: Option<u32>,
+        recommendations: &[&str],
+    ) -> RogueDetectionResult {
+        let crypto_miners = miner_pid
+            .map(|pid| CryptoMiner {
+                process: GpuProc {
+                    gpu_index: 0,
+                    pid,
+                    user: "mallory".to_string(),
+                    proc_name: "xmrig".to_string(),
+                    used_mem_mb: 1024,
+                    start_time: "unknown".to_string(),
+                    ..Default::default()
+                },
+                mining_indicators: vec!["Known mining software: xmrig".to_string()],
+                confidence: 0.9,
+                estimated_hashrate: None,
             })
+            .into_iter()
+            .collect();
+        RogueDetectionResult {
+            timestamp: Utc::now(),
+            suspicious_processes: Vec::new(),
+            crypto_miners,
+            resource_abusers: Vec::new(),
+            data_exfiltrators: Vec::new(),
+            risk_score,
+            recommendations: recommendations.iter().map(|r| r.to_string()).collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cluster_rogue_report_aggregates_node_results() {
+        let state = CoordinatorState::new().with_rogue_stale_after(Duration::from_secs(30 * 60));
+        for id in ["node-a", "node-b", "node-c"] {
+            state.register_node(rogue_node(id)).await.unwrap();
+        }
+        let mut subscriber = state.events.subscribe();
+
+        state
+            .update_rogue_result(
+                "node-a",
+                rogue_result(0.4, None, &["Review GPU quotas", "Enable auditing"]),
+            )
+            .await
+            .unwrap();
+        state
+            .update_rogue_result(
+                "node-b",
+                rogue_result(0.9, Some(4242), &["Terminate miners", "Review GPU quotas"]),
+            )
+            .await
+            .unwrap();
+        assert!(state
+            .update_rogue_result("unknown", rogue_result(1.0, None, &[]))
+            .await
+            .is_err());
+
+        // The miner is published once, tagged with the node that found it
+        match subscriber.try_recv().unwrap() {
+            ClusterEvent::RogueDetected { node_id, pid, .. } => {
+                assert_eq!((node_id.as_deref(), pid), (Some("node-b"), 4242));
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+        state
+            .update_rogue_result("node-b", rogue_result(0.9, Some(4242), &[]))
             .await
             .unwrap();
+        assert!(subscriber.try_recv().is_err());
+
+        let report = state.rogue_report_at(Utc::now()).await;
+        assert!((report.risk_score - 0.9).abs() < 1e-6);
+        assert_eq!(report.crypto_miners.len(), 1);
+        assert_eq!(
+            report.crypto_miners[0].process.node_id.as_deref(),
+            Some("node-b")
+        );
+        // node-b's latest result replaced its earlier recommendations
+        assert_eq!(
+            report.recommendations,
+            ["Review GPU quotas", "Enable auditing"]
+        );
+
+        let ids: Vec<&str> = report.nodes.iter().map(|n| n.node_id.as_str()).collect();
+        assert_eq!(ids, ["node-b", "node-a", "node-c"]);
+        assert_eq!(report.nodes[0].crypto_miners, 1);
+        assert!(!report.nodes[0].stale && !report.nodes[1].stale);
+        // A node that never reported is flagged but does not add risk
+        assert!(report.nodes[2].stale);
+        assert!(report.nodes[2].scanned_at.is_none());
+
+        let later = state
+            .rogue_report_at(Utc::now() + chrono::Duration::hours(1))
+            .await;
+        assert!(later.nodes.iter().all(|node| node.stale));
+        assert_eq!(later.stale_after_secs, 30 * 60);
+    }
+
+    #[test]
+    fn test_cluster_rogue_report_deduplicates_recommendations_riskiest_first() {
+        let now = Utc::now();
+        let nodes: HashMap<String, NodeInfo> = ["node-a", "node-b"]
+            .into_iter()
+            .map(|id| (id.to_string(), rogue_node(id)))
+            .collect();
+        let result = |risk_score, recommendations: &[&str]| NodeRogueResult {
+            received_at: now,
+            result: rogue_result(risk_score, None, recommendations),
+        };
+        let results = HashMap::from([
+            (
+                "node-a".to_string(),
+                result(0.2, &["Enable auditing", "Review quotas"]),
+            ),
+            (
+                "node-b".to_string(),
+                result(0.7, &["Review quotas", "Terminate miners"]),
+            ),
+            ("removed".to_string(), result(1.0, &["Stale advice"])),
+        ]);
+
+        let report =
+            ClusterRogueReport::aggregate(&nodes, &results, DEFAULT_ROGUE_STALE_AFTER, now);
+        assert_eq!(
+            report.recommendations,
+            ["Review quotas", "Terminate miners", "Enable auditing"]
+        );
+        assert!((report.risk_score - 0.7).abs() < 1e-6);
+        assert_eq!(report.nodes.len(), 2);
+    }
+
+    #[test]
+    fn test_cluster_rogue_report_serde_matches_dashboard_fields() {
+        let now = Utc::now();
+        let nodes = HashMap::from([("node-a".to_string(), rogue_node("node-a"))]);
+        let mut result = rogue_result(0.8, Some(7), &["Terminate miners"]);
+        result.crypto_miners[0].process.node_id = Some("node-a".to_string());
+        let results = HashMap::from([(
+            "node-a".to_string(),
+            NodeRogueResult {
+                received_at: now - chrono::Duration::seconds(90),
+                result,
+            },
+        )]);
+        let report =
+            ClusterRogueReport::aggregate(&nodes, &results, DEFAULT_ROGUE_STALE_AFTER, now);
+
+        // The dashboard reads the single-host field names at the top level
+        let json = serde_json::to_value(&report).unwrap();
+        for field in [
+            "timestamp",
+            "suspicious_processes",
+            "crypto_miners",
+            "resource_abusers",
+            "data_exfiltrators",
+            "risk_score",
+            "recommendations",
+        ] {
+            assert!(json.get(field).is_some(), "missing {}", field);
+        }
+        assert_eq!(json["crypto_miners"][0]["process"]["pid"], 7);
+        assert_eq!(json["crypto_miners"][0]["process"]["node_id"], "node-a");
+        assert_eq!(json["nodes"][0]["hostname"], "node-a.example");
+        assert_eq!(json["nodes"][0]["age_secs"], 90);
+        assert_eq!(json["nodes"][0]["stale"], false);
+        assert_eq!(json["stale_after_secs"], 3600);
+
+        let parsed: ClusterRogueReport = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.nodes.len(), 1);
+        assert_eq!(parsed.crypto_miners[0].process.pid, 7);
+
+        // Node commands are tagged objects
+        assert_eq!(
+            serde_json::to_value(NodeCommand::RogueScan).unwrap(),
+            serde_json::json!({ "type": "rogue_scan" })
+        );
+    }
 
+    #[tokio::test]
+    async fn test_rogue_scan_requests_are_fetched_once() {
+        let state = CoordinatorState::new();
+        state.register_node(rogue_node("node-a")).await.unwrap();
+        state.register_node(rogue_node("node-b")).await.unwrap();
+
+        let Json(asked) = request_rogue_scans(State(state.clone())).await.unwrap();
+        assert_eq!(asked, ["node-a", "node-b"]);
+        // Asking again before the nodes poll does not queue a second scan
+        state.request_rogue_scans().await;
+
+        let Json(commands) = get_node_commands(
+            State(state.clone()),
+            axum::http::HeaderMap::new(),
+            Path("node-a".to_string()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(commands, [NodeCommand::RogueScan]);
+        let Json(commands) = get_node_commands(
+            State(state.clone()),
+            axum::http::HeaderMap::new(),
+            Path("node-a".to_string()),
+        )
+        .await
+        .unwrap();
+        assert!(commands.is_empty());
+
+        let rejected = update_rogue_result(
+            State(state.clone()),
+            Path("node-z".to_string()),
+            Json(rogue_result(0.1, None, &[])),
+        )
+        .await;
+        assert_eq!(rejected.unwrap_err(), StatusCode::NOT_FOUND);
+
+        // Removed nodes take their pending commands and results with them
         state
-            .update_snapshot("node-1".to_string(), snapshot1)
+            .update_rogue_result("node-b", rogue_result(0.5, None, &[]))
             .await
             .unwrap();
         state
-            .update_snapshot("node-2".to_string(), snapshot2)
+            .cleanup_stale_nodes_at(Utc::now() + chrono::Duration::hours(2))
             .await
             .unwrap();
+        assert!(state.commands.read().await.is_empty());
+        assert!(state.rogue_results.read().await.is_empty());
+    }
 
-        let analysis = state.get_contention_analysis().await.unwrap();
-
-        let bob_stats = analysis
-            .top_users
-            .iter()
-            .find(|u| u.user == "bob")
-            .expect("Bob should be in top users");
+    fn reset_result(batch_id: &str, gpu_index: u16, error: Option<&str>) -> NodeResetResult {
+        use crate::report::{ResetOutcome, ResetStatus};
 
-        // Bob has 5 processes total
-        assert_eq!(bob_stats.process_count, 5, "Bob has 5 processes total");
+        NodeResetResult {
+            batch_id: batch_id.to_string(),
+            outcome: ResetOutcome {
+                gpu_index,
+                status: match error {
+                    Some(_) => ResetStatus::Failed,
+                    None => ResetStatus::Reset,
+                },
+                mechanism: "nvml".to_string(),
+                duration_ms: Some(1200),
+                error: error.map(str::to_string),
+                waited_ms: None,
+                processes: Vec::new(),
+            },
+        }
+    }
 
-        // Bob uses 4 unique GPUs: (node-1, 0), (node-1, 1), (node-2, 0), (node-2, 1)
-        assert_eq!(
-            bob_stats.gpu_count, 4,
-            "Bob uses 4 unique GPUs across 2 nodes"
+    fn bearer(token: &str) -> axum::http::HeaderMap {
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert(
+            axum::http::header::AUTHORIZATION,
+            format!("Bearer {}", token).parse().unwrap(),
         );
+        headers
     }
 
     #[tokio::test]
-    async fn test_contention_analysis_avg_utilization_unique_gpus() {
-        let state = CoordinatorState::new();
-
-        let snapshot = NodeSnapshot {
-            node_id: "test-node".to_string(),
-            hostname: "test-host".to_string(),
-            timestamp: Utc::now(),
-            gpus: vec![
-                GpuSnapshot {
-                    gpu_index: 0,
-                    name: "GPU 0".to_string(),
-                    vendor: GpuVendor::Nvidia,
-                    mem_used_mb: 8000,
-                    mem_total_mb: 10000,
-                    util_pct: 90.0,
-                    temp_c: 75,
-                    power_w: 200.0,
-                    ecc_volatile: None,
-                    pids: 2,
-                    top_proc: None,
-                },
-                GpuSnapshot {
-                    gpu_index: 1,
-                    name: "GPU 1".to_string(),
-                    vendor: GpuVendor::Nvidia,
-                    mem_used_mb: 3000,
-                    mem_total_mb: 10000,
-                    util_pct: 30.0,
-                    temp_c: 65,
-                    power_w: 100.0,
-                    ecc_volatile: None,
-                    pids: 1,
-                    top_proc: None,
-                },
-            ],
-            processes: vec![
-                GpuProc {
-                    gpu_index: 0,
-                    pid: 1001,
-                    user: "charlie".to_string(),
-                    proc_name: "train1".to_string(),
-                    used_mem_mb: 4000,
-                    start_time: "2025-09-20T01:00:00Z".to_string(),
-                    container: None,
-                    node_id: None,
-                },
-                GpuProc {
-                    gpu_index: 0,
-                    pid: 1002,
-                    user: "charlie".to_string(),
-                    proc_name: "train2".to_string(),
-                    used_mem_mb: 4000,
-                    start_time: "2025-09-20T01:00:00Z".to_string(),
-                    container: None,
-                    node_id: None,
-                },
-                GpuProc {
-                    gpu_index: 1,
-                    pid: 1003,
-                    user: "charlie".to_string(),
-                    proc_name: "train3".to_string(),
-                    used_mem_mb: 3000,
-                    start_time: "2025-09-20T01:00:00Z".to_string(),
-                    container: None,
-                    node_id: None,
-                },
-            ],
-            status: NodeStatus::Online,
-        };
-
+    async fn test_cluster_reset_fans_out_and_aggregates_node_results() {
+        let state = CoordinatorState::new().with_cluster_token(Some("s3cret".to_string()));
         state
             .register_node(NodeInfo {
-                id: "test-node".to_string(),
-                hostname: "test-host".to_string(),
-                ip_address: "127.0.0.1".to_string(),
-                last_seen: Utc::now(),
-                status: NodeStatus::Online,
                 gpu_count: 2,
-                total_memory_gb: 19.5,
-                tags: HashMap::new(),
+                ..rogue_node("node-a")
             })
             .await
             .unwrap();
-
+        state.register_node(rogue_node("node-b")).await.unwrap();
         state
-            .update_snapshot("test-node".to_string(), snapshot)
+            .register_node(NodeInfo {
+                status: NodeStatus::Offline,
+                ..rogue_node("node-c")
+            })
             .await
             .unwrap();
 
-        let analysis = state.get_contention_analysis().await.unwrap();
-
-        let charlie_stats = analysis
-            .top_users
+        let target = |node: &str, gpu_index| ResetTarget {
+            node_id: node.to_string(),
+            gpu_index,
+        };
+        let request = |targets| ClusterResetRequest {
+            targets,
+            force: true,
+            wait_secs: 0,
+        };
+        let targets = vec![
+            target("node-a", 0),
+            target("node-a", 1),
+            target("node-a", 0),
+            target("node-b", 0),
+            target("node-b", 3),
+            target("node-c", 0),
+            target("node-z", 0),
+        ];
+
+        // Without the cluster token nothing is queued
+        let rejected = request_cluster_reset(
+            State(state.clone()),
+            bearer("guess"),
+            Json(request(targets.clone())),
+        )
+        .await;
+        assert_eq!(rejected.unwrap_err(), StatusCode::UNAUTHORIZED);
+        let rejected = request_cluster_reset(
+            State(state.clone()),
+            axum::http::HeaderMap::new(),
+            Json(request(targets.clone())),
+        )
+        .await;
+        assert_eq!(rejected.unwrap_err(), StatusCode::UNAUTHORIZED);
+        assert!(state.commands.read().await.is_empty());
+
+        let (status, Json(report)) = request_cluster_reset(
+            State(state.clone()),
+            bearer("s3cret"),
+            Json(request(targets)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!((report.pending, report.failed, report.succeeded), (3, 3, 0));
+        let errors: Vec<_> = report
+            .targets
             .iter()
-            .find(|u| u.user == "charlie")
-            .expect("Charlie should be in top users");
+            .filter_map(|t| t.error.as_deref())
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "GPU 3 not found on node node-b (1 GPUs)",
+                "Node node-c is offline",
+                "Node node-z is not registered",
+            ]
+        );
 
-        assert_eq!(charlie_stats.gpu_count, 2, "Charlie uses 2 unique GPUs");
-        assert_eq!(charlie_stats.process_count, 3, "Charlie has 3 processes");
+        // Each node is handed only its own resets, once
+        let reset = |gpu_index| NodeCommand::Reset {
+            batch_id: report.batch_id.clone(),
+            gpu_index,
+            force: true,
+        };
+        // Fetching them needs the token too, and a refused fetch leaves them queued
+        let fetch = |node: &str, headers| {
+            get_node_commands(State(state.clone()), headers, Path(node.to_string()))
+        };
+        let rejected = fetch("node-a", axum::http::HeaderMap::new()).await;
+        assert_eq!(rejected.unwrap_err(), StatusCode::UNAUTHORIZED);
+        let rejected = fetch("node-a", bearer("guess")).await;
+        assert_eq!(rejected.unwrap_err(), StatusCode::UNAUTHORIZED);
+        let Json(commands) = fetch("node-a", bearer("s3cret")).await.unwrap();
+        assert_eq!(commands, [reset(0), reset(1)]);
+        assert_eq!(state.take_commands("node-b").await, [reset(0)]);
+        assert!(state.take_commands("node-a").await.is_empty());
+        assert!(state.take_commands("node-c").await.is_empty());
+
+        let batch = &report.batch_id;
+        let post = |node: &str, result, token: &str| {
+            update_reset_result(
+                State(state.clone()),
+                bearer(token),
+                Path(node.to_string()),
+                Json(result),
+            )
+        };
+        assert!(post("node-a", reset_result(batch, 0, None), "s3cret")
+            .await
+            .is_ok());
+        let failure = reset_result(batch, 1, Some("GPU reset is not supported"));
+        assert!(post("node-a", failure, "s3cret").await.is_ok());
+        // Results need the token, must match a pending target, and count once
+        let forged = post("node-b", reset_result(batch, 0, None), "guess").await;
+        assert_eq!(forged.unwrap_err(), StatusCode::UNAUTHORIZED);
+        let repeated = post("node-a", reset_result(batch, 0, None), "s3cret").await;
+        assert_eq!(repeated.unwrap_err(), StatusCode::NOT_FOUND);
+        let unknown = post("node-a", reset_result("no-such-batch", 0, None), "s3cret").await;
+        assert_eq!(unknown.unwrap_err(), StatusCode::NOT_FOUND);
+
+        let Json(progress) =
+            get_cluster_reset(State(state.clone()), bearer("s3cret"), Path(batch.clone()))
+                .await
+                .unwrap();
+        assert_eq!(
+            (progress.pending, progress.failed, progress.succeeded),
+            (1, 4, 1)
+        );
+        assert!(!progress.is_complete());
 
-        let expected_avg = 60.0;
-        let diff = (charlie_stats.avg_utilization - expected_avg).abs();
-        assert!(
-            diff < 0.01,
-            "Average utilization should be calculated per unique GPU"
+        assert!(post("node-b", reset_result(batch, 0, None), "s3cret")
+            .await
+            .is_ok());
+        let report = state.reset_report(batch).await.unwrap();
+        assert!(report.is_complete());
+        assert_eq!((report.failed, report.succeeded), (4, 2));
+        let outcomes: Vec<_> = report
+            .targets
+            .iter()
+            .map(|t| (t.node_id.as_str(), t.gpu_index, t.status))
+            .collect();
+        assert_eq!(
+            outcomes,
+            [
+                ("node-a", 0, TargetResetStatus::Reset),
+                ("node-a", 1, TargetResetStatus::Failed),
+                ("node-b", 0, TargetResetStatus::Reset),
+                ("node-b", 3, TargetResetStatus::Failed),
+                ("node-c", 0, TargetResetStatus::Failed),
+                ("node-z", 0, TargetResetStatus::Failed),
+            ]
+        );
+        assert_eq!(report.targets[0].mechanism.as_deref(), Some("nvml"));
+        assert_eq!(report.targets[0].duration_ms, Some(1200));
+        assert_eq!(
+            report.targets[1].error.as_deref(),
+            Some("GPU reset is not supported")
+        );
+
+        // Targets of a node removed before it reports fail instead of pending forever
+        let Json(report) = request_cluster_reset(
+            State(state.clone()),
+            bearer("s3cret"),
+            Json(request(vec![target("node-b", 0)])),
+        )
+        .await
+        .unwrap()
+        .1;
+        state
+            .cleanup_stale_nodes_at(Utc::now() + chrono::Duration::hours(2))
+            .await
+            .unwrap();
+        let report = state.reset_report(&report.batch_id).await.unwrap();
+        assert_eq!((report.pending, report.failed), (0, 1));
+        assert_eq!(
+            report.targets[0].error.as_deref(),
+            Some("Node node-b was removed before reporting")
         );
     }
 
     #[tokio::test]
-    async fn test_snapshot_rejected_for_unregistered_node() {
-        let state = CoordinatorState::new();
-
-        let snapshot = NodeSnapshot {
-            node_id: "rogue-node".to_string(),
-            hostname: "rogue-host".to_string(),
-            timestamp: Utc::now(),
-            gpus: vec![],
-            processes: vec![],
-            status: NodeStatus::Online,
-        };
+    async fn test_cluster_reset_waits_for_nodes_and_needs_a_token() {
+        let state = CoordinatorState::new().with_cluster_token(Some("s3cret".to_string()));
+        state.register_node(rogue_node("node-a")).await.unwrap();
 
-        let result = state
-            .update_snapshot("rogue-node".to_string(), snapshot)
-            .await;
-        assert!(result.is_err());
+        // A node that polls for commands and reports like an agent would
+        let node_state = state.clone();
+        let node = tokio::spawn(async move {
+            loop {
+                for command in node_state.take_commands("node-a").await {
+                    if let NodeCommand::Reset {
+                        batch_id,
+                        gpu_index,
+                        ..
+                    } = command
+                    {
+                        let result = reset_result(&batch_id, gpu_index, None);
+                        node_state
+                            .record_reset_result("node-a", result)
+                            .await
+                            .unwrap();
+                        return;
+                    }
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        });
 
-        let snapshots = state.snapshots.read().await;
-        assert!(!snapshots.contains_key("rogue-node"));
+        let (status, Json(report)) = request_cluster_reset(
+            State(state.clone()),
+            bearer("s3cret"),
+            Json(ClusterResetRequest {
+                targets: vec![ResetTarget {
+                    node_id: "node-a".to_string(),
+                    gpu_index: 0,
+                }],
+                force: false,
+                wait_secs: 10,
+            }),
+        )
+        .await
+        .unwrap();
+        node.await.unwrap();
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(report.succeeded, 1);
+
+        let empty = request_cluster_reset(
+            State(state.clone()),
+            bearer("s3cret"),
+            Json(ClusterResetRequest {
+                targets: Vec::new(),
+                force: false,
+                wait_secs: 0,
+            }),
+        )
+        .await;
+        assert_eq!(empty.unwrap_err(), StatusCode::BAD_REQUEST);
+
+        // A coordinator started without --cluster-token refuses cluster resets
+        let unguarded = CoordinatorState::new();
+        let refused =
+            get_cluster_reset(State(unguarded), bearer("s3cret"), Path(report.batch_id)).await;
+        assert_eq!(refused.unwrap_err(), StatusCode::FORBIDDEN);
     }
 
-    #[tokio::test]
-    async fn test_rogue_analysis_uses_cluster_snapshots() {
-        use crate::audit::AuditManager;
-        use crate::rogue_detection::{DetectionRules, RogueDetector};
-        use crate::vendor::GpuVendor;
-
-        // Node snapshot with a clear crypto miner (xmrig at 99% GPU util)
-        let snapshot = NodeSnapshot {
-            node_id: "worker-1".to_string(),
-            hostname: "worker-host".to_string(),
+    /// A node with one GPU running `pids` for alice and bob in turn, idle without any
+    fn drain_snapshot(node_id: &str, pids: &[u32]) -> NodeSnapshot {
+        let processes: Vec<GpuProc> = pids
+            .iter()
+            .enumerate()
+            .map(|(i, &pid)| GpuProc {
+                gpu_index: 0,
+                pid,
+                user: ["alice", "bob"][i % 2].to_string(),
+                proc_name: "train".to_string(),
+                used_mem_mb: 4000,
+                start_time: "1h".to_string(),
+                ..Default::default()
+            })
+            .collect();
+        NodeSnapshot {
+            node_id: node_id.to_string(),
+            hostname: format!("{}.example", node_id),
             timestamp: Utc::now(),
             gpus: vec![GpuSnapshot {
                 gpu_index: 0,
-                name: "NVIDIA A100".to_string(),
+                name: "Test GPU".to_string(),
                 vendor: GpuVendor::Nvidia,
-                mem_used_mb: 8000,
+                mem_used_mb: 4000 * pids.len() as u32,
                 mem_total_mb: 40000,
-                util_pct: 99.0,
-                temp_c: 80,
-                power_w: 250.0,
-                ecc_volatile: None,
-                pids: 1,
-                top_proc: None,
-            }],
-            processes: vec![GpuProc {
-                gpu_index: 0,
-                pid: 12345,
-                user: "attacker".to_string(),
-                proc_name: "xmrig".to_string(),
-                used_mem_mb: 1024,
-                start_time: "unknown".to_string(),
-                container: None,
-                node_id: None,
+                util_pct: if pids.is_empty() { 0.0 } else { 50.0 },
+                temp_c: 40,
+                power_w: 50.0,
+                pids: pids.len(),
+                ..Default::default()
             }],
+            processes,
             status: NodeStatus::Online,
-        };
+            tags: HashMap::new(),
+            errors: Vec::new(),
+        }
+    }
 
-        let records = snapshots_to_audit_records(&[snapshot]);
-        assert!(!records.is_empty());
-        assert_eq!(records[0].process_name.as_deref(), Some("xmrig"));
-        assert_eq!(records[0].node_id.as_deref(), Some("worker-1"));
+    #[tokio::test]
+    async fn test_drain_takes_node_out_of_rotation_and_needs_a_token() {
+        let state = CoordinatorState::new().with_cluster_token(Some("s3cret".to_string()));
+        for (node_id, pids) in [("node-a", &[100, 101][..]), ("node-b", &[])] {
+            state.register_node(rogue_node(node_id)).await.unwrap();
+            state
+                .update_snapshot(node_id.to_string(), drain_snapshot(node_id, pids))
+                .await
+                .unwrap();
+        }
+        let drain = |token: &str, node_id: &str, request: DrainRequest| {
+            drain_node(
+                State(state.clone()),
+                bearer(token),
+                Path(node_id.to_string()),
+                Json(request),
+            )
+        };
+        let enforced = DrainRequest {
+            deadline_secs: Some(3600),
+            enforce: true,
+            reason: Some("PSU swap".to_string()),
+            ..Default::default()
+        };
 
-        let rules = DetectionRules {
-            min_confidence_threshold: 0.5,
-            ..DetectionRules::default()
+        let refused = drain("guess", "node-b", enforced.clone()).await;
+        assert_eq!(refused.unwrap_err(), StatusCode::UNAUTHORIZED);
+        let unguarded = drain_node(
+            State(CoordinatorState::new()),
+            bearer("s3cret"),
+            Path("node-b".to_string()),
+            Json(enforced.clone()),
+        )
+        .await;
+        assert_eq!(unguarded.unwrap_err(), StatusCode::FORBIDDEN);
+        let no_deadline = DrainRequest {
+            enforce: true,
+            ..Default::default()
         };
-        let audit_manager = AuditManager::new().await.unwrap();
-        let detector = RogueDetector::with_rules(audit_manager, rules);
-        let result = detector
-            .detect_rogue_activity_from_records(records)
+        let rejected = drain("s3cret", "node-b", no_deadline).await;
+        assert_eq!(rejected.unwrap_err(), StatusCode::BAD_REQUEST);
+        let unknown = drain("s3cret", "node-z", enforced.clone()).await;
+        assert_eq!(unknown.unwrap_err(), StatusCode::NOT_FOUND);
+        assert!(state.commands.read().await.is_empty());
+
+        // The idle node is drained: it no longer offers its GPU
+        let analysis = state.get_contention_analysis().await.unwrap();
+        assert_eq!(analysis.idle_gpus, [("node-b".to_string(), 0)]);
+        let Json(placed) = drain("s3cret", "node-b", DrainRequest::default())
+            .await
+            .unwrap();
+        assert_eq!(placed.deadline, None);
+        assert!(matches!(
+            state.take_commands("node-b").await[..],
+            [NodeCommand::Drain { .. }]
+        ));
+        let analysis = state.get_contention_analysis().await.unwrap();
+        assert!(analysis.idle_gpus.is_empty());
+        assert!(analysis.recommendations.contains(
+            &"Node node-b.example is draining and has no GPU processes left".to_string()
+        ));
+
+        // The busy node is told to drain, its owners are named, and the drain
+        // survives the node registering again
+        let Json(placed) = drain("s3cret", "node-a", enforced).await.unwrap();
+        let deadline = placed.deadline.unwrap();
+        assert_eq!(
+            state.take_commands("node-a").await,
+            [NodeCommand::Drain {
+                drain: placed.clone()
+            }]
+        );
+        let events = state.events.since(None, EVENT_PAGE_LIMIT).await.unwrap();
+        let draining = events.iter().rev().find_map(|event| match event {
+            ClusterEvent::NodeDraining {
+                node_id,
+                processes,
+                owners,
+                enforce,
+                ..
+            } if node_id == "node-a" => Some((*processes, owners.clone(), *enforce)),
+            _ => None,
+        });
+        assert_eq!(
+            draining,
+            Some((2, vec!["alice".to_string(), "bob".to_string()], true))
+        );
+        state.register_node(rogue_node("node-a")).await.unwrap();
+        state
+            .update_snapshot("node-a".to_string(), drain_snapshot("node-a", &[100, 101]))
             .await
             .unwrap();
+        let nodes = state.nodes.read().await.clone();
+        assert_eq!(nodes["node-a"].drain.as_ref(), Some(&placed));
+        assert!(matches!(
+            nodes["node-a"].status,
+            NodeStatus::Draining { deadline: Some(d) } if d == deadline
+        ));
+        let cluster = state.get_cluster_snapshot().await.unwrap();
+        assert!(cluster
+            .nodes
+            .iter()
+            .all(|node| matches!(node.status, NodeStatus::Draining { .. })));
+        let analysis = state.get_contention_analysis().await.unwrap();
+        assert!(analysis.node_summaries.iter().all(|n| n.draining));
 
-        assert!(
-            !result.crypto_miners.is_empty(),
-            "Cluster snapshot with xmrig should produce at least one crypto miner"
+        // Past the deadline the remaining processes are killed, once
+        state.enforce_drain_deadlines(deadline).await;
+        state
+            .enforce_drain_deadlines(deadline + chrono::Duration::seconds(30))
+            .await;
+        // Only a node with the token can take the kill; a refused fetch leaves it queued
+        let fetch =
+            |headers| get_node_commands(State(state.clone()), headers, Path("node-a".to_string()));
+        let rejected = fetch(axum::http::HeaderMap::new()).await;
+        assert_eq!(rejected.unwrap_err(), StatusCode::UNAUTHORIZED);
+        let Json(commands) = fetch(bearer("s3cret")).await.unwrap();
+        assert_eq!(
+            commands,
+            [NodeCommand::Kill {
+                pids: vec![100, 101],
+                reason: format!("Drain deadline {} passed", deadline.to_rfc3339()),
+            }]
         );
-        let miner = &result.crypto_miners[0];
-        assert_eq!(miner.process.proc_name, "xmrig");
-        assert_eq!(miner.process.node_id.as_deref(), Some("worker-1"));
+        // An unenforced drain never kills
+        state
+            .enforce_drain_deadlines(deadline + chrono::Duration::days(1))
+            .await;
+        assert!(state.take_commands("node-b").await.is_empty());
+
+        // Ending the drain drops a kill that was not fetched yet
+        state.enforce_drain_deadlines(deadline).await;
+        let Json(()) = undrain_node(
+            State(state.clone()),
+            bearer("s3cret"),
+            Path("node-a".to_string()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(state.take_commands("node-a").await, [NodeCommand::Undrain]);
+        let nodes = state.get_nodes().await;
+        let node_a = nodes.iter().find(|n| n.id == "node-a").unwrap();
+        assert!(node_a.drain.is_none());
+        assert!(matches!(node_a.status, NodeStatus::Online));
+        let events = state.events.since(None, EVENT_PAGE_LIMIT).await.unwrap();
+        assert!(matches!(
+            events.last(),
+            Some(ClusterEvent::NodeUndrained { node_id, .. }) if node_id == "node-a"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_node_registry_keeps_drains_across_restarts() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(NODE_REGISTRY_FILE);
+        let state =
+            CoordinatorState::new().with_node_registry(path.clone(), NodeRegistry::default());
+        state.register_node(rogue_node("node-a")).await.unwrap();
+        state.register_node(rogue_node("node-b")).await.unwrap();
+        let drain = DrainRequest {
+            deadline_secs: Some(600),
+            enforce: true,
+            ..Default::default()
+        }
+        .into_drain(Utc::now())
+        .unwrap();
+        state.drain_node("node-a", drain.clone()).await.unwrap();
+
+        // A restarted coordinator lists the saved nodes offline until they report
+        let restarted = CoordinatorState::new()
+            .with_node_registry(path.clone(), NodeRegistry::load(&path).unwrap());
+        let mut nodes = restarted.get_nodes().await;
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(nodes.len(), 2);
+        assert!(nodes
+            .iter()
+            .all(|node| matches!(node.status, NodeStatus::Offline)));
+        assert_eq!(nodes[0].drain.as_ref(), Some(&drain));
+        assert_eq!(nodes[1].drain, None);
+        restarted
+            .update_snapshot("node-a".to_string(), drain_snapshot("node-a", &[]))
+            .await
+            .unwrap();
+        assert!(matches!(
+            restarted.nodes.read().await["node-a"].status,
+            NodeStatus::Draining { .. }
+        ));
+
+        // Nodes removed for silence leave the registry too
+        restarted
+            .cleanup_stale_nodes_at(Utc::now() + chrono::Duration::hours(2))
+            .await
+            .unwrap();
+        assert!(NodeRegistry::load(&path).unwrap().nodes.is_empty());
+    }
+
+    #[test]
+    fn test_run_reset_command_refuses_busy_gpu_unless_forced() {
+        use crate::report::ResetStatus;
+        use crate::vendor::{MockCall, MockFixture, MockGpu, MockProcess, MockVendor};
+
+        let vendor = std::sync::Arc::new(MockVendor::new(MockFixture {
+            gpus: vec![
+                MockGpu::default(),
+                MockGpu {
+                    processes: vec![MockProcess {
+                        pid: 4242,
+                        user: "alice".to_string(),
+                        name: "python".to_string(),
+                        used_mem_mb: 2048,
+                        cmdline: None,
+                    }],
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }));
+        let manager = vendor.manager();
+
+        let outcome = run_reset_command(&manager, 0, false);
+        assert_eq!(outcome.status, ResetStatus::Reset);
+        assert!(outcome.duration_ms.is_some());
+
+        let outcome = run_reset_command(&manager, 1, false);
+        assert_eq!(outcome.status, ResetStatus::Failed);
+        assert!(outcome.error.unwrap().contains("1 active process"));
+        assert_eq!(vendor.calls(1, MockCall::Reset), 0);
+
+        let outcome = run_reset_command(&manager, 1, true);
+        assert_eq!(outcome.status, ResetStatus::Reset);
+        assert_eq!(vendor.calls(1, MockCall::Reset), 1);
+
+        let outcome = run_reset_command(&manager, 7, true);
+        assert_eq!(outcome.status, ResetStatus::Failed);
+        assert!(outcome.error.unwrap().contains("GPU 7 not found"));
+
+        let serialized = serde_json::to_value(NodeResetResult {
+            batch_id: "b1".to_string(),
+            outcome: run_reset_command(&manager, 0, false),
+        })
+        .unwrap();
+        assert_eq!(serialized["batch_id"], "b1");
+        assert_eq!(serialized["status"], "reset");
     }
 }
@@ -0,0 +1,409 @@
+//! Profiling metrics for NVIDIA GPUs from DCGM
+//!
+//! NVML only reports coarse utilization. When the `dcgm` feature is enabled and a
+//! DCGM host engine is reachable, NVIDIA snapshots also carry SM, memory and
+//! pipe activity. Without the feature, or without DCGM, snapshots are unchanged.
+
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// DCGM profiling metrics for one GPU, each a ratio from 0.0 to 1.0 over the last
+/// sample period. A metric the GPU does not support is None.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProfilingMetrics {
+    /// Fraction of time at least one warp was resident on an SM
+    pub sm_active: Option<f32>,
+    /// Resident warps relative to the SMs' maximum
+    pub sm_occupancy: Option<f32>,
+    /// Fraction of cycles the memory interface was busy (memory bandwidth utilization)
+    pub dram_active: Option<f32>,
+    /// Fraction of cycles the tensor cores were busy
+    pub tensor_active: Option<f32>,
+    pub fp64_active: Option<f32>,
+    pub fp32_active: Option<f32>,
+    pub fp16_active: Option<f32>,
+}
+
+/// DCGM field IDs of the profiling metrics (DCGM_FI_PROF_*) and their decoding.
+/// Only the DCGM provider reads them, so without the feature they exist for tests.
+#[cfg(any(feature = "dcgm", test))]
+mod fields {
+    use super::ProfilingMetrics;
+
+    pub const FIELD_SM_ACTIVE: u16 = 1002;
+    pub const FIELD_SM_OCCUPANCY: u16 = 1003;
+    pub const FIELD_TENSOR_ACTIVE: u16 = 1004;
+    pub const FIELD_DRAM_ACTIVE: u16 = 1005;
+    pub const FIELD_FP64_ACTIVE: u16 = 1006;
+    pub const FIELD_FP32_ACTIVE: u16 = 1007;
+    pub const FIELD_FP16_ACTIVE: u16 = 1008;
+
+    impl ProfilingMetrics {
+        /// Build metrics from `(field_id, value)` pairs; blank values and unknown
+        /// fields are ignored. Returns None if no field had a value.
+        pub fn from_fields(fields: &[(u16, Option<f64>)]) -> Option<Self> {
+            let mut metrics = Self::default();
+            let mut any = false;
+            for &(field, value) in fields {
+                let Some(value) = value else {
+                    continue;
+                };
+                let slot = match field {
+                    FIELD_SM_ACTIVE => &mut metrics.sm_active,
+                    FIELD_SM_OCCUPANCY => &mut metrics.sm_occupancy,
+                    FIELD_TENSOR_ACTIVE => &mut metrics.tensor_active,
+                    FIELD_DRAM_ACTIVE => &mut metrics.dram_active,
+                    FIELD_FP64_ACTIVE => &mut metrics.fp64_active,
+                    FIELD_FP32_ACTIVE => &mut metrics.fp32_active,
+                    FIELD_FP16_ACTIVE => &mut metrics.fp16_active,
+                    _ => continue,
+                };
+                *slot = Some(value.clamp(0.0, 1.0) as f32);
+                any = true;
+            }
+            any.then_some(metrics)
+        }
+    }
+}
+
+/// Source of profiling metrics, keyed by NVML device index
+pub trait ProfilingProvider: Send + Sync {
+    /// Latest metrics for the GPU, or None if they are unavailable
+    fn metrics(&self, nvml_index: u32) -> Option<ProfilingMetrics>;
+}
+
+/// Provider answering from a fixed table, for tests
+#[cfg(test)]
+#[derive(Debug, Clone, Default)]
+pub struct StaticProfilingProvider {
+    metrics: HashMap<u32, ProfilingMetrics>,
+}
+
+#[cfg(test)]
+impl StaticProfilingProvider {
+    pub fn new(metrics: HashMap<u32, ProfilingMetrics>) -> Self {
+        Self { metrics }
+    }
+}
+
+#[cfg(test)]
+impl ProfilingProvider for StaticProfilingProvider {
+    fn metrics(&self, nvml_index: u32) -> Option<ProfilingMetrics> {
+        self.metrics.get(&nvml_index).copied()
+    }
+}
+
+/// Connect to DCGM if gpukill was built with the `dcgm` feature and a host engine is
+/// reachable; None otherwise, which leaves snapshots without profiling metrics
+pub fn detect() -> Option<Arc<dyn ProfilingProvider>> {
+    #[cfg(feature = "dcgm")]
+    {
+        match DcgmProvider::connect() {
+            Ok(provider) => {
+                tracing::info!("DCGM profiling metrics enabled");
+                return Some(Arc::new(provider));
+            }
+            Err(e) => tracing::debug!("DCGM not available: {:#}", e),
+        }
+    }
+    None
+}
+
+#[cfg(feature = "dcgm")]
+pub use self::provider::DcgmProvider;
+
+#[cfg(feature = "dcgm")]
+mod provider {
+    use super::fields::*;
+    use super::{ffi, ProfilingMetrics, ProfilingProvider};
+    use anyhow::Result;
+    use std::ffi::CString;
+    use std::sync::Mutex;
+
+    /// Profiling fields gpukill watches, in the order they are requested
+    const PROFILING_FIELDS: [u16; 7] = [
+        FIELD_SM_ACTIVE,
+        FIELD_SM_OCCUPANCY,
+        FIELD_TENSOR_ACTIVE,
+        FIELD_DRAM_ACTIVE,
+        FIELD_FP64_ACTIVE,
+        FIELD_FP32_ACTIVE,
+        FIELD_FP16_ACTIVE,
+    ];
+
+    /// How often DCGM samples the watched fields, in microseconds
+    const UPDATE_INTERVAL_US: i64 = 1_000_000;
+    /// How long DCGM keeps samples, in seconds
+    const MAX_KEEP_AGE_SECS: f64 = 60.0;
+
+    /// Profiling metrics from libdcgm: a standalone nv-hostengine on localhost if
+    /// one is running, otherwise an engine embedded in this process
+    pub struct DcgmProvider {
+        handle: ffi::DcgmHandle,
+        field_group: ffi::DcgmFieldGroup,
+        embedded: bool,
+        /// libdcgm handles are not documented as thread-safe
+        lock: Mutex<()>,
+    }
+
+    impl DcgmProvider {
+        /// Connect to DCGM and start watching the profiling fields
+        pub fn connect() -> Result<Self> {
+            // SAFETY: plain libdcgm calls; every out-pointer refers to a live local
+            unsafe {
+                ffi::check(ffi::dcgmInit(), "dcgmInit")?;
+
+                let mut handle: ffi::DcgmHandle = 0;
+                let address = CString::new("127.0.0.1").expect("no NUL in address");
+                let embedded = if ffi::dcgmConnect(address.as_ptr(), &mut handle) == ffi::DCGM_ST_OK
+                {
+                    false
+                } else {
+                    let status = ffi::dcgmStartEmbedded(ffi::DCGM_OPERATION_MODE_AUTO, &mut handle);
+                    if let Err(e) = ffi::check(status, "dcgmStartEmbedded") {
+                        ffi::dcgmShutdown();
+                        return Err(e);
+                    }
+                    true
+                };
+
+                let mut provider = Self {
+                    handle,
+                    field_group: 0,
+                    embedded,
+                    lock: Mutex::new(()),
+                };
+                let name = CString::new(format!("gpukill-profiling-{}", std::process::id()))
+                    .expect("no NUL in group name");
+                let mut fields = PROFILING_FIELDS;
+                ffi::check(
+                    ffi::dcgmFieldGroupCreate(
+                        handle,
+                        fields.len() as i32,
+                        fields.as_mut_ptr(),
+                        name.as_ptr(),
+                        &mut provider.field_group,
+                    ),
+                    "dcgmFieldGroupCreate",
+                )?;
+                ffi::check(
+                    ffi::dcgmWatchFields(
+                        handle,
+                        ffi::DCGM_GROUP_ALL_GPUS,
+                        provider.field_group,
+                        UPDATE_INTERVAL_US,
+                        MAX_KEEP_AGE_SECS,
+                        0,
+                    ),
+                    "dcgmWatchFields",
+                )?;
+                // Wait for a first sample so one-shot listings have values
+                ffi::check(ffi::dcgmUpdateAllFields(handle, 1), "dcgmUpdateAllFields")?;
+                Ok(provider)
+            }
+        }
+    }
+
+    impl ProfilingProvider for DcgmProvider {
+        fn metrics(&self, nvml_index: u32) -> Option<ProfilingMetrics> {
+            let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+            let mut fields = PROFILING_FIELDS;
+            let mut values: Vec<ffi::DcgmFieldValue> =
+                fields.iter().map(|_| ffi::DcgmFieldValue::new()).collect();
+            // SAFETY: `fields` and `values` have the same length, passed as `count`
+            let status = unsafe {
+                ffi::dcgmGetLatestValuesForFields(
+                    self.handle,
+                    nvml_index as i32,
+                    fields.as_mut_ptr(),
+                    fields.len() as u32,
+                    values.as_mut_ptr(),
+                )
+            };
+            if status != ffi::DCGM_ST_OK {
+                tracing::debug!(gpu = nvml_index, status, "DCGM field query failed");
+                return None;
+            }
+            let readings: Vec<(u16, Option<f64>)> =
+                values.iter().map(|v| (v.field_id, v.as_f64())).collect();
+            ProfilingMetrics::from_fields(&readings)
+        }
+    }
+
+    impl Drop for DcgmProvider {
+        fn drop(&mut self) {
+            // SAFETY: the handle and field group were created by `connect`
+            unsafe {
+                if self.field_group != 0 {
+                    ffi::dcgmFieldGroupDestroy(self.handle, self.field_group);
+                }
+                if self.embedded {
+                    ffi::dcgmStopEmbedded(self.handle);
+                } else {
+                    ffi::dcgmDisconnect(self.handle);
+                }
+                ffi::dcgmShutdown();
+            }
+        }
+    }
+}
+
+/// Minimal bindings to libdcgm (dcgm_agent.h, dcgm_structs.h)
+#[cfg(feature = "dcgm")]
+#[allow(non_snake_case)]
+mod ffi {
+    use std::os::raw::{c_char, c_double, c_int, c_longlong, c_uint, c_ushort};
+
+    pub type DcgmReturn = c_int;
+    pub type DcgmHandle = usize;
+    pub type DcgmFieldGroup = usize;
+    pub type DcgmGpuGroup = usize;
+
+    pub const DCGM_ST_OK: DcgmReturn = 0;
+    pub const DCGM_OPERATION_MODE_AUTO: c_int = 1;
+    pub const DCGM_GROUP_ALL_GPUS: DcgmGpuGroup = 0x7fff_ffff;
+    const DCGM_FT_DOUBLE: c_ushort = b'd' as c_ushort;
+    /// Doubles at or above this are DCGM's "blank" markers
+    const DCGM_FP64_BLANK: f64 = 140_737_488_355_328.0;
+    const DCGM_MAX_BLOB_LENGTH: usize = 4096;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub union FieldValueUnion {
+        pub i64: i64,
+        pub dbl: f64,
+        pub blob: [u8; DCGM_MAX_BLOB_LENGTH],
+    }
+
+    /// dcgmFieldValue_v1
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct DcgmFieldValue {
+        pub version: c_uint,
+        pub field_id: c_ushort,
+        pub field_type: c_ushort,
+        pub status: c_int,
+        pub ts: i64,
+        pub value: FieldValueUnion,
+    }
+
+    impl DcgmFieldValue {
+        pub fn new() -> Self {
+            Self {
+                version: (std::mem::size_of::<Self>() as c_uint) | (1 << 24),
+                field_id: 0,
+                field_type: 0,
+                status: 0,
+                ts: 0,
+                value: FieldValueUnion {
+                    blob: [0; DCGM_MAX_BLOB_LENGTH],
+                },
+            }
+        }
+
+        /// The value as a double, or None if DCGM reported an error or a blank
+        pub fn as_f64(&self) -> Option<f64> {
+            if self.status != DCGM_ST_OK || self.field_type != DCGM_FT_DOUBLE {
+                return None;
+            }
+            // SAFETY: DCGM_FT_DOUBLE values are stored in `dbl`
+            let value = unsafe { self.value.dbl };
+            (value < DCGM_FP64_BLANK).then_some(value)
+        }
+    }
+
+    pub fn check(status: DcgmReturn, call: &str) -> anyhow::Result<()> {
+        if status == DCGM_ST_OK {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!(
+                "{} failed with DCGM status {}",
+                call,
+                status
+            ))
+        }
+    }
+
+    #[link(name = "dcgm")]
+    extern "C" {
+        pub fn dcgmInit() -> DcgmReturn;
+        pub fn dcgmShutdown() -> DcgmReturn;
+        pub fn dcgmConnect(ip_address: *const c_char, handle: *mut DcgmHandle) -> DcgmReturn;
+        pub fn dcgmDisconnect(handle: DcgmHandle) -> DcgmReturn;
+        pub fn dcgmStartEmbedded(op_mode: c_int, handle: *mut DcgmHandle) -> DcgmReturn;
+        pub fn dcgmStopEmbedded(handle: DcgmHandle) -> DcgmReturn;
+        pub fn dcgmFieldGroupCreate(
+            handle: DcgmHandle,
+            num_field_ids: c_int,
+            field_ids: *mut c_ushort,
+            field_group_name: *const c_char,
+            field_group: *mut DcgmFieldGroup,
+        ) -> DcgmReturn;
+        pub fn dcgmFieldGroupDestroy(handle: DcgmHandle, field_group: DcgmFieldGroup)
+            -> DcgmReturn;
+        pub fn dcgmWatchFields(
+            handle: DcgmHandle,
+            group: DcgmGpuGroup,
+            field_group: DcgmFieldGroup,
+            update_freq_us: c_longlong,
+            max_keep_age_secs: c_double,
+            max_keep_samples: c_int,
+        ) -> DcgmReturn;
+        pub fn dcgmUpdateAllFields(handle: DcgmHandle, wait_for_update: c_int) -> DcgmReturn;
+        pub fn dcgmGetLatestValuesForFields(
+            handle: DcgmHandle,
+            gpu_id: c_int,
+            fields: *mut c_ushort,
+            count: c_uint,
+            values: *mut DcgmFieldValue,
+        ) -> DcgmReturn;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fields::*;
+    use super::*;
+
+    #[test]
+    fn test_metrics_from_fields() {
+        let metrics = ProfilingMetrics::from_fields(&[
+            (FIELD_SM_ACTIVE, Some(0.92)),
+            (FIELD_SM_OCCUPANCY, Some(0.41)),
+            (FIELD_DRAM_ACTIVE, Some(0.63)),
+            (FIELD_TENSOR_ACTIVE, Some(0.55)),
+            // Not supported by this GPU
+            (FIELD_FP64_ACTIVE, None),
+            (FIELD_FP32_ACTIVE, Some(1.2)),
+            (FIELD_FP16_ACTIVE, Some(0.0)),
+            // Not a profiling field
+            (150, Some(0.5)),
+        ])
+        .unwrap();
+        assert_eq!(metrics.sm_active, Some(0.92));
+        assert_eq!(metrics.sm_occupancy, Some(0.41));
+        assert_eq!(metrics.dram_active, Some(0.63));
+        assert_eq!(metrics.tensor_active, Some(0.55));
+        assert_eq!(metrics.fp64_active, None);
+        assert_eq!(metrics.fp32_active, Some(1.0));
+        assert_eq!(metrics.fp16_active, Some(0.0));
+
+        assert_eq!(
+            ProfilingMetrics::from_fields(&[(FIELD_SM_ACTIVE, None)]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_static_provider() {
+        let metrics = ProfilingMetrics {
+            sm_active: Some(0.5),
+            ..Default::default()
+        };
+        let provider = StaticProfilingProvider::new(HashMap::from([(1, metrics)]));
+        assert_eq!(provider.metrics(1), Some(metrics));
+        assert_eq!(provider.metrics(0), None);
+    }
+}
@@ -0,0 +1,217 @@
+//! Node draining ahead of maintenance.
+//!
+//! The coordinator marks a node as draining with an optional deadline: it stops
+//! counting the node's GPUs as available, owners of running processes are asked
+//! to move their work elsewhere, and with `enforce` the processes still running at
+//! the deadline are killed. Agents keep a copy of the drain in the gpukill config
+//! directory so local commands (`--list`, Guard Mode) know about it.
+
+use crate::util::format_duration;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File under the gpukill config directory marking this node as draining
+pub const DRAIN_FILE: &str = "drain.json";
+
+/// How long before the deadline Guard Mode turns drain warnings into violations
+pub const DRAIN_ESCALATE_BEFORE: chrono::Duration = chrono::Duration::hours(1);
+
+/// A drain placed on a node
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeDrain {
+    pub since: DateTime<Utc>,
+    /// When running processes must be gone; None drains without a deadline
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<DateTime<Utc>>,
+    /// Kill the processes still running at the deadline
+    #[serde(default)]
+    pub enforce: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl NodeDrain {
+    /// Time left until the deadline, zero once it has passed
+    pub fn remaining(&self, now: DateTime<Utc>) -> Option<chrono::Duration> {
+        self.deadline
+            .map(|deadline| (deadline - now).max(chrono::Duration::zero()))
+    }
+
+    pub fn deadline_passed(&self, now: DateTime<Utc>) -> bool {
+        self.deadline.is_some_and(|deadline| now >= deadline)
+    }
+
+    /// Whether the deadline is close enough that remaining processes are violations
+    pub fn is_urgent(&self, now: DateTime<Utc>) -> bool {
+        self.deadline
+            .is_some_and(|deadline| deadline - now <= DRAIN_ESCALATE_BEFORE)
+    }
+
+    /// One-line notice for users of a draining node
+    pub fn banner(&self, now: DateTime<Utc>) -> String {
+        let mut banner = String::from("This node is draining");
+        if let Some(reason) = &self.reason {
+            banner.push_str(&format!(" ({})", reason));
+        }
+        match (self.deadline, self.remaining(now)) {
+            (Some(deadline), _) if self.deadline_passed(now) => {
+                banner.push_str(&format!(
+                    ": the deadline {} has passed",
+                    deadline.format("%Y-%m-%d %H:%M UTC")
+                ));
+                if self.enforce {
+                    banner.push_str(" and remaining GPU processes are being killed");
+                }
+            }
+            (Some(deadline), Some(remaining)) => {
+                banner.push_str(&format!(
+                    ": move GPU work elsewhere before {} (in {})",
+                    deadline.format("%Y-%m-%d %H:%M UTC"),
+                    format_duration(remaining.to_std().unwrap_or_default())
+                ));
+                if self.enforce {
+                    banner.push_str("; processes still running then will be killed");
+                }
+            }
+            _ => banner.push_str(": it takes no new work, move GPU jobs elsewhere"),
+        }
+        banner
+    }
+
+    /// `drain.json` in the gpukill config directory
+    pub fn default_path() -> Result<PathBuf> {
+        let mut path = if let Some(config_dir) = dirs::config_dir() {
+            config_dir
+        } else if let Some(home_dir) = dirs::home_dir() {
+            home_dir.join(".config")
+        } else {
+            std::env::current_dir()?
+        };
+
+        path.push("gpukill");
+        fs::create_dir_all(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to create config directory: {}", e))?;
+
+        path.push(DRAIN_FILE);
+        Ok(path)
+    }
+
+    /// Load the drain at `path`; a missing file means the node is not draining
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read drain marker {}", path.display()))?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .with_context(|| format!("Failed to parse drain marker {}", path.display()))
+    }
+
+    /// The drain recorded for this node, if any. Unreadable markers are logged and
+    /// ignored, since they must not stop local commands.
+    pub fn load_local() -> Option<Self> {
+        match Self::default_path().and_then(|path| Self::load(&path)) {
+            Ok(drain) => drain,
+            Err(e) => {
+                tracing::debug!("Ignoring drain marker: {:#}", e);
+                None
+            }
+        }
+    }
+
+    /// Write the drain to `path` through a temporary file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, content)
+            .with_context(|| format!("Failed to write drain marker {}", path.display()))?;
+        fs::rename(&tmp_path, path)
+            .with_context(|| format!("Failed to write drain marker {}", path.display()))
+    }
+
+    /// Remove the marker at `path`; a missing marker is not an error
+    pub fn clear(path: &Path) -> Result<()> {
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("Failed to remove drain marker {}", path.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn test_drain(deadline_mins: Option<i64>, enforce: bool) -> (NodeDrain, DateTime<Utc>) {
+        let since = Utc.with_ymd_and_hms(2026, 10, 16, 9, 0, 0).unwrap();
+        let drain = NodeDrain {
+            since,
+            deadline: deadline_mins.map(|mins| since + chrono::Duration::minutes(mins)),
+            enforce,
+            reason: Some("PSU swap".to_string()),
+        };
+        (drain, since)
+    }
+
+    #[test]
+    fn test_drain_deadline() {
+        let (drain, since) = test_drain(Some(120), true);
+        assert!(!drain.deadline_passed(since));
+        assert!(!drain.is_urgent(since));
+        assert!(drain.is_urgent(since + chrono::Duration::minutes(61)));
+        assert!(drain.deadline_passed(since + chrono::Duration::minutes(120)));
+        assert_eq!(
+            drain.remaining(since + chrono::Duration::hours(3)),
+            Some(chrono::Duration::zero())
+        );
+
+        let (open_ended, since) = test_drain(None, false);
+        assert!(!open_ended.is_urgent(since + chrono::Duration::days(30)));
+        assert!(!open_ended.deadline_passed(since + chrono::Duration::days(30)));
+        assert_eq!(open_ended.remaining(since), None);
+    }
+
+    #[test]
+    fn test_drain_banner() {
+        let (drain, since) = test_drain(Some(90), true);
+        assert_eq!(
+            drain.banner(since),
+            "This node is draining (PSU swap): move GPU work elsewhere before \
+             2026-10-16 10:30 UTC (in 1h 30m 0s); processes still running then will be killed"
+        );
+        assert_eq!(
+            drain.banner(since + chrono::Duration::hours(2)),
+            "This node is draining (PSU swap): the deadline 2026-10-16 10:30 UTC has passed \
+             and remaining GPU processes are being killed"
+        );
+
+        let (mut open_ended, since) = test_drain(None, false);
+        open_ended.reason = None;
+        assert_eq!(
+            open_ended.banner(since),
+            "This node is draining: it takes no new work, move GPU jobs elsewhere"
+        );
+    }
+
+    #[test]
+    fn test_drain_marker_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DRAIN_FILE);
+        assert_eq!(NodeDrain::load(&path).unwrap(), None);
+        NodeDrain::clear(&path).unwrap();
+
+        let (drain, _) = test_drain(Some(30), false);
+        drain.save(&path).unwrap();
+        assert_eq!(NodeDrain::load(&path).unwrap(), Some(drain));
+
+        NodeDrain::clear(&path).unwrap();
+        assert_eq!(NodeDrain::load(&path).unwrap(), None);
+    }
+}
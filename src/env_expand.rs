@@ -0,0 +1,295 @@
+//! Environment variable expansion for config and policy files, so secrets such as
+//! webhook URLs can stay out of committed files.
+//!
+//! String values may reference `${VAR}`, or `${VAR:-default}` to fall back when
+//! `VAR` is unset or empty; `$${` is a literal `${`. Only values are expanded,
+//! never keys, and expansion happens on the parsed document, so a variable's value
+//! cannot change the file's structure. A reference to an unset variable without a
+//! default is an error.
+//!
+//! Saving a config that was loaded from a file with references writes the
+//! references back rather than the values they expanded to, for every value that
+//! was not changed in between.
+
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use std::path::Path;
+
+/// Expand every `${VAR}` reference in `text` from the process environment
+pub fn expand(text: &str) -> Result<String> {
+    expand_with(text, |name| std::env::var(name).ok())
+}
+
+/// Expand every `${VAR}` reference in `text`, looking variables up with `lookup`
+pub fn expand_with(text: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String> {
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("$${") {
+            expanded.push_str("${");
+            rest = after;
+            continue;
+        }
+        let Some(after) = rest.strip_prefix("${") else {
+            expanded.push('$');
+            rest = &rest[1..];
+            continue;
+        };
+        let end = after
+            .find('}')
+            .with_context(|| format!("Unterminated '${{' in {:?}", text))?;
+        let reference = &after[..end];
+        let (name, default) = match reference.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (reference, None),
+        };
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            anyhow::bail!(
+                "Invalid environment variable reference '${{{}}}'",
+                reference
+            );
+        }
+        match (lookup(name), default) {
+            (Some(value), Some(default)) if value.is_empty() => expanded.push_str(default),
+            (Some(value), _) => expanded.push_str(&value),
+            (None, Some(default)) => expanded.push_str(default),
+            (None, None) => anyhow::bail!(
+                "Environment variable {} is not set (referenced as '${{{}}}'; use '${{{}:-default}}' for a fallback)",
+                name,
+                name,
+                name
+            ),
+        }
+        rest = &after[end + 1..];
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Parse a TOML config, expanding references in its string values first
+pub fn from_toml_str<T: DeserializeOwned>(content: &str) -> Result<T> {
+    // Files without references go straight to the typed config, keeping toml's
+    // line and column in parse errors
+    if !content.contains("${") {
+        return Ok(toml::from_str(content)?);
+    }
+    let mut value: toml::Value = toml::from_str(content)?;
+    expand_toml(&mut value, "")?;
+    Ok(value.try_into()?)
+}
+
+/// Parse a JSON config, expanding references in its string values first
+pub fn from_json_str<T: DeserializeOwned>(content: &str) -> Result<T> {
+    if !content.contains("${") {
+        return Ok(serde_json::from_str(content)?);
+    }
+    let mut value: serde_json::Value = serde_json::from_str(content)?;
+    expand_json(&mut value, "")?;
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Serialize `config` as TOML for writing to `path`. When the file there has
+/// references, values that still equal what a reference expands to are written
+/// as the reference again.
+pub fn to_toml_string_for<T: Serialize>(config: &T, path: &Path) -> Result<String> {
+    let template = std::fs::read_to_string(path)
+        .ok()
+        .filter(|content| content.contains("${"))
+        .and_then(|content| toml::from_str::<toml::Value>(&content).ok());
+    let Some(template) = template else {
+        return Ok(toml::to_string_pretty(config)?);
+    };
+    let mut value = toml::Value::try_from(config)?;
+    restore_toml(&mut value, &template);
+    Ok(toml::to_string_pretty(&value)?)
+}
+
+fn field_path(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", parent, key)
+    }
+}
+
+fn expand_toml(value: &mut toml::Value, path: &str) -> Result<()> {
+    match value {
+        toml::Value::String(text) => {
+            *text = expand(text).with_context(|| format!("In config field {}", path))?
+        }
+        toml::Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                expand_toml(item, &field_path(path, &i.to_string()))?;
+            }
+        }
+        toml::Value::Table(table) => {
+            for (key, item) in table.iter_mut() {
+                expand_toml(item, &field_path(path, key))?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn expand_json(value: &mut serde_json::Value, path: &str) -> Result<()> {
+    match value {
+        serde_json::Value::String(text) => {
+            *text = expand(text).with_context(|| format!("In config field {}", path))?
+        }
+        serde_json::Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                expand_json(item, &field_path(path, &i.to_string()))?;
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                expand_json(item, &field_path(path, key))?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Put the references from `template` back into `value` wherever the value is
+/// still what the reference expands to now
+fn restore_toml(value: &mut toml::Value, template: &toml::Value) {
+    match (value, template) {
+        (toml::Value::String(text), toml::Value::String(reference))
+            if reference.contains("${") && expand(reference).is_ok_and(|v| v == *text) =>
+        {
+            *text = reference.clone();
+        }
+        (toml::Value::Array(items), toml::Value::Array(references)) => {
+            for (item, reference) in items.iter_mut().zip(references) {
+                restore_toml(item, reference);
+            }
+        }
+        (toml::Value::Table(table), toml::Value::Table(references)) => {
+            for (key, item) in table.iter_mut() {
+                if let Some(reference) = references.get(key) {
+                    restore_toml(item, reference);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Webhook {
+        url: String,
+        retries: u32,
+    }
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "HOST" => Some("gpu01".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_expand_references_and_defaults() {
+        assert_eq!(
+            expand_with("https://${HOST}:8080/${PATH:-hook}", lookup).unwrap(),
+            "https://gpu01:8080/hook"
+        );
+        assert_eq!(
+            expand_with("${EMPTY:-fallback}", lookup).unwrap(),
+            "fallback"
+        );
+        assert_eq!(expand_with("${PATH:-}", lookup).unwrap(), "");
+        assert_eq!(expand_with("[${EMPTY}]", lookup).unwrap(), "[]");
+        assert_eq!(
+            expand_with("cost $5, $${HOST}", lookup).unwrap(),
+            "cost $5, ${HOST}"
+        );
+    }
+
+    #[test]
+    fn test_expand_rejects_unset_and_malformed_references() {
+        let err = expand_with("${MISSING}", lookup).unwrap_err().to_string();
+        assert!(err.contains("MISSING is not set"), "{}", err);
+        assert!(expand_with("${HOST", lookup).is_err());
+        assert!(expand_with("${}", lookup).is_err());
+        assert!(expand_with("${BAD NAME}", lookup).is_err());
+    }
+
+    #[test]
+    fn test_toml_config_resolves_set_variable() {
+        std::env::set_var(
+            "GPUKILL_TEST_EXPAND_URL",
+            "https://hooks.example/\"quoted\"",
+        );
+        let webhook: Webhook =
+            from_toml_str("url = \"${GPUKILL_TEST_EXPAND_URL}\"\nretries = 3\n").unwrap();
+        // The value is substituted as data, so quotes in it cannot break the file
+        assert_eq!(webhook.url, "https://hooks.example/\"quoted\"");
+        assert_eq!(webhook.retries, 3);
+
+        let webhook: Webhook =
+            from_json_str(r#"{"url": "${GPUKILL_TEST_EXPAND_URL}", "retries": 1}"#).unwrap();
+        assert_eq!(webhook.url, "https://hooks.example/\"quoted\"");
+    }
+
+    #[test]
+    fn test_config_with_unset_variable_fails_naming_the_field() {
+        std::env::remove_var("GPUKILL_TEST_EXPAND_UNSET");
+        let err = from_toml_str::<Webhook>("url = \"${GPUKILL_TEST_EXPAND_UNSET}\"\nretries = 3\n")
+            .unwrap_err();
+        let message = format!("{:#}", err);
+        assert!(message.contains("In config field url"), "{}", message);
+        assert!(
+            message.contains("GPUKILL_TEST_EXPAND_UNSET is not set"),
+            "{}",
+            message
+        );
+
+        assert!(from_json_str::<Webhook>(
+            r#"{"url": "${GPUKILL_TEST_EXPAND_UNSET}", "retries": 1}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_saving_keeps_references() {
+        std::env::set_var("GPUKILL_TEST_EXPAND_SAVE", "https://hooks.example/secret");
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(
+            &path,
+            "url = \"${GPUKILL_TEST_EXPAND_SAVE}\"\nretries = 3\n",
+        )
+        .unwrap();
+
+        let mut webhook: Webhook = from_toml_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        webhook.retries = 5;
+        let saved = to_toml_string_for(&webhook, &path).unwrap();
+        assert!(
+            saved.contains("url = \"${GPUKILL_TEST_EXPAND_SAVE}\""),
+            "{}",
+            saved
+        );
+        assert!(!saved.contains("secret"));
+        assert!(saved.contains("retries = 5"));
+
+        // A value changed since loading is saved as it is now
+        webhook.url = "https://hooks.example/other".to_string();
+        let saved = to_toml_string_for(&webhook, &path).unwrap();
+        assert!(
+            saved.contains("url = \"https://hooks.example/other\""),
+            "{}",
+            saved
+        );
+    }
+}
@@ -0,0 +1,446 @@
+//! Stable GPU indices that survive reboots and driver reloads.
+//!
+//! Drivers may enumerate the same cards in a different order after a reboot, which
+//! would silently move Guard Mode GPU policies and audit history to another card.
+//! Each GPU is recorded on first sight by its UUID (NVIDIA) or PCI bus id (other
+//! vendors) together with the index it was given, and keeps that index from then on.
+
+use crate::nvml_api::GpuInfo;
+use crate::vendor::GpuVendor;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File under the gpukill config directory holding the index map
+pub const INDEX_MAP_FILE: &str = "gpu_index_map.json";
+
+/// A GPU recorded in the index map
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MappedGpu {
+    /// Stable index shown to users and used by policies and audit records
+    pub index: u16,
+    /// UUID, PCI bus id or serial identifying the card
+    pub id: String,
+    pub name: String,
+    pub first_seen: DateTime<Utc>,
+}
+
+/// A GPU found on this host, at the position the driver enumerated it
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectedGpu {
+    /// Index in vendor enumeration order, as used to address the device
+    pub enumeration_index: u16,
+    /// Identifier from [`device_id`]; None if the device could not be queried
+    pub id: Option<String>,
+    pub name: String,
+}
+
+/// Identifier for a device: the UUID for NVIDIA and the PCI bus id for other
+/// vendors, falling back to the other one and then the serial number. A device
+/// reporting none of them is identified by vendor and position.
+pub fn device_id(vendor: GpuVendor, local_index: u32, info: &GpuInfo) -> String {
+    let (first, second) = match vendor {
+        GpuVendor::Nvidia => (&info.uuid, &info.pci_bus_id),
+        _ => (&info.pci_bus_id, &info.uuid),
+    };
+    first
+        .as_ref()
+        .or(second.as_ref())
+        .or(info.serial.as_ref())
+        .cloned()
+        .unwrap_or_else(|| format!("{}:{}", vendor.to_string().to_lowercase(), local_index))
+}
+
+/// Compare identifiers ignoring case and NVIDIA's `GPU-` prefix
+fn normalize_id(id: &str) -> String {
+    let id = id.trim().to_ascii_lowercase();
+    match id.strip_prefix("gpu-") {
+        Some(rest) => rest.to_string(),
+        None => id,
+    }
+}
+
+/// Persistent map from device identifier to stable index
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GpuIndexMap {
+    /// Mapped GPUs ordered by stable index
+    pub devices: Vec<MappedGpu>,
+}
+
+impl GpuIndexMap {
+    /// `gpu_index_map.json` in the gpukill config directory
+    pub fn default_path() -> Result<PathBuf> {
+        let mut path = if let Some(config_dir) = dirs::config_dir() {
+            config_dir
+        } else if let Some(home_dir) = dirs::home_dir() {
+            home_dir.join(".config")
+        } else {
+            std::env::current_dir()?
+        };
+
+        path.push("gpukill");
+        fs::create_dir_all(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to create config directory: {}", e))?;
+
+        path.push(INDEX_MAP_FILE);
+        Ok(path)
+    }
+
+    /// Load the map at `path`; a missing file is an empty map
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read GPU index map {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse GPU index map {}", path.display()))
+    }
+
+    /// Write the map to `path` through a temporary file, so a concurrent reader
+    /// never sees a partial map
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, content)
+            .with_context(|| format!("Failed to write GPU index map {}", path.display()))?;
+        fs::rename(&tmp_path, path)
+            .with_context(|| format!("Failed to write GPU index map {}", path.display()))
+    }
+
+    /// Entry for the device with this identifier
+    pub fn find(&self, id: &str) -> Option<&MappedGpu> {
+        let wanted = normalize_id(id);
+        self.devices
+            .iter()
+            .find(|gpu| normalize_id(&gpu.id) == wanted)
+    }
+
+    /// Entry holding a stable index
+    pub fn at(&self, index: u16) -> Option<&MappedGpu> {
+        self.devices.iter().find(|gpu| gpu.index == index)
+    }
+
+    /// Record devices seen for the first time. A new device keeps its enumeration
+    /// index when no other device holds it, and otherwise gets the lowest free
+    /// index. Returns whether the map changed.
+    pub fn assign(&mut self, detected: &[DetectedGpu]) -> bool {
+        let mut changed = false;
+        for gpu in detected {
+            let Some(id) = &gpu.id else {
+                continue;
+            };
+            if self.find(id).is_some() {
+                continue;
+            }
+            let index = if self.at(gpu.enumeration_index).is_none() {
+                gpu.enumeration_index
+            } else {
+                self.lowest_free_index(&HashSet::new())
+            };
+            self.devices.push(MappedGpu {
+                index,
+                id: id.clone(),
+                name: gpu.name.clone(),
+                first_seen: Utc::now(),
+            });
+            changed = true;
+        }
+        self.devices.sort_by_key(|gpu| gpu.index);
+        changed
+    }
+
+    /// Give the device with identifier `id` the stable index `index`, e.g. after a
+    /// card was replaced. A different device holding `index` is dropped from the
+    /// map and returned; it gets a new index the next time it is seen.
+    pub fn remap(&mut self, id: &str, index: u16) -> Result<Option<MappedGpu>> {
+        let wanted = normalize_id(id);
+        if !self
+            .devices
+            .iter()
+            .any(|gpu| normalize_id(&gpu.id) == wanted)
+        {
+            anyhow::bail!(
+                "Invalid argument: no GPU with UUID or PCI bus id '{}' has been seen on this host; run gpukill --list --show-ids to see known GPUs",
+                id
+            );
+        }
+
+        let displaced = self
+            .devices
+            .iter()
+            .position(|gpu| gpu.index == index && normalize_id(&gpu.id) != wanted)
+            .map(|other| self.devices.remove(other));
+        for gpu in &mut self.devices {
+            if normalize_id(&gpu.id) == wanted {
+                gpu.index = index;
+            }
+        }
+        self.devices.sort_by_key(|gpu| gpu.index);
+        Ok(displaced)
+    }
+
+    fn lowest_free_index(&self, also_taken: &HashSet<u16>) -> u16 {
+        (0..=u16::MAX)
+            .find(|index| self.at(*index).is_none() && !also_taken.contains(index))
+            .unwrap_or(u16::MAX)
+    }
+}
+
+/// One row of `gpukill --list --show-ids`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GpuIdEntry {
+    pub index: u16,
+    /// Current enumeration index; None if the GPU is not present
+    pub enumeration_index: Option<u16>,
+    pub id: Option<String>,
+    pub name: String,
+    pub present: bool,
+}
+
+/// Translation between enumeration indices and stable indices for the devices
+/// present when it was built
+#[derive(Debug, Clone, Default)]
+pub struct StableIndices {
+    map: GpuIndexMap,
+    to_stable: HashMap<u16, u16>,
+    to_enumeration: HashMap<u16, u16>,
+    /// Present devices without an identifier, as (stable index, name)
+    unidentified: Vec<(u16, String)>,
+}
+
+impl StableIndices {
+    /// Translation for `detected` devices through `map`, which should already
+    /// include them (see [`GpuIndexMap::assign`]). Devices without an identifier
+    /// are not recorded and get the lowest index no mapped device holds.
+    pub fn new(map: GpuIndexMap, detected: &[DetectedGpu]) -> Self {
+        let mut to_stable = HashMap::new();
+        let mut unidentified = Vec::new();
+        let mut taken = HashSet::new();
+        for gpu in detected {
+            if let Some(mapped) = gpu.id.as_deref().and_then(|id| map.find(id)) {
+                to_stable.insert(gpu.enumeration_index, mapped.index);
+                taken.insert(mapped.index);
+            }
+        }
+        for gpu in detected {
+            if to_stable.contains_key(&gpu.enumeration_index) {
+                continue;
+            }
+            let index = map.lowest_free_index(&taken);
+            taken.insert(index);
+            to_stable.insert(gpu.enumeration_index, index);
+            unidentified.push((index, gpu.name.clone()));
+        }
+        let to_enumeration = to_stable.iter().map(|(&e, &s)| (s, e)).collect();
+
+        Self {
+            map,
+            to_stable,
+            to_enumeration,
+            unidentified,
+        }
+    }
+
+    /// Stable index of the device at an enumeration index
+    pub fn stable(&self, enumeration_index: u16) -> u16 {
+        self.to_stable
+            .get(&enumeration_index)
+            .copied()
+            .unwrap_or(enumeration_index)
+    }
+
+    /// Enumeration index of the present device holding a stable index
+    pub fn enumeration(&self, stable_index: u16) -> Option<u16> {
+        self.to_enumeration.get(&stable_index).copied()
+    }
+
+    /// Every mapped GPU plus present GPUs without an identifier, by stable index
+    pub fn entries(&self) -> Vec<GpuIdEntry> {
+        let mut entries: Vec<GpuIdEntry> = self
+            .map
+            .devices
+            .iter()
+            .map(|gpu| GpuIdEntry {
+                index: gpu.index,
+                enumeration_index: self.enumeration(gpu.index),
+                id: Some(gpu.id.clone()),
+                name: gpu.name.clone(),
+                present: self.to_enumeration.contains_key(&gpu.index),
+            })
+            .chain(self.unidentified.iter().map(|(index, name)| GpuIdEntry {
+                index: *index,
+                enumeration_index: self.enumeration(*index),
+                id: None,
+                name: name.clone(),
+                present: true,
+            }))
+            .collect();
+        entries.sort_by_key(|entry| entry.index);
+        entries
+    }
+
+    /// Mapped GPUs behind `indices` that are no longer present, so policies keyed
+    /// by those indices apply to no card
+    pub fn missing(&self, indices: impl IntoIterator<Item = u16>) -> Vec<&MappedGpu> {
+        let mut missing: Vec<&MappedGpu> = indices
+            .into_iter()
+            .filter(|index| !self.to_enumeration.contains_key(index))
+            .filter_map(|index| self.map.at(index))
+            .collect();
+        missing.sort_by_key(|gpu| gpu.index);
+        missing.dedup_by_key(|gpu| gpu.index);
+        missing
+    }
+}
+
+/// Parse `--remap-gpu <uuid>=<index>`
+pub fn parse_remap(value: &str) -> Result<(String, u16), String> {
+    let (id, index) = value
+        .rsplit_once('=')
+        .ok_or_else(|| format!("expected <uuid>=<index>, got '{}'", value))?;
+    let id = id.trim();
+    if id.is_empty() {
+        return Err("GPU UUID or PCI bus id must not be empty".to_string());
+    }
+    let index = index
+        .trim()
+        .parse::<u16>()
+        .map_err(|e| format!("invalid GPU index '{}': {}", index.trim(), e))?;
+    Ok((id.to_string(), index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detected(enumeration_index: u16, id: &str) -> DetectedGpu {
+        DetectedGpu {
+            enumeration_index,
+            id: Some(id.to_string()),
+            name: format!("GPU {}", id),
+        }
+    }
+
+    fn info(uuid: Option<&str>, pci_bus_id: Option<&str>) -> GpuInfo {
+        GpuInfo {
+            index: 0,
+            name: "Test GPU".to_string(),
+            mem_total_mb: 16384,
+            uuid: uuid.map(str::to_string),
+            serial: None,
+            pci_bus_id: pci_bus_id.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_device_id_prefers_uuid_for_nvidia_and_bus_id_elsewhere() {
+        let both = info(Some("GPU-aaaa"), Some("0000:3b:00.0"));
+        assert_eq!(device_id(GpuVendor::Nvidia, 0, &both), "GPU-aaaa");
+        assert_eq!(device_id(GpuVendor::Amd, 0, &both), "0000:3b:00.0");
+        assert_eq!(
+            device_id(GpuVendor::Amd, 0, &info(Some("0x18f6"), None)),
+            "0x18f6"
+        );
+        assert_eq!(device_id(GpuVendor::Intel, 1, &info(None, None)), "intel:1");
+    }
+
+    #[test]
+    fn test_indices_survive_reordering() {
+        let mut map = GpuIndexMap::default();
+        assert!(map.assign(&[detected(0, "GPU-a"), detected(1, "GPU-b")]));
+        assert!(!map.assign(&[detected(0, "GPU-a"), detected(1, "GPU-b")]));
+
+        // After a reboot the driver lists the cards the other way round
+        let reordered = [detected(0, "GPU-b"), detected(1, "GPU-a")];
+        assert!(!map.assign(&reordered));
+        let stable = StableIndices::new(map, &reordered);
+        assert_eq!(stable.stable(0), 1);
+        assert_eq!(stable.stable(1), 0);
+        assert_eq!(stable.enumeration(0), Some(1));
+    }
+
+    #[test]
+    fn test_new_card_does_not_take_index_of_missing_card() {
+        let mut map = GpuIndexMap::default();
+        map.assign(&[detected(0, "GPU-a"), detected(1, "GPU-b")]);
+
+        // GPU-b was pulled and a new card enumerates in its slot
+        let now = [detected(0, "GPU-a"), detected(1, "GPU-c")];
+        assert!(map.assign(&now));
+        let stable = StableIndices::new(map, &now);
+        assert_eq!(stable.stable(1), 2);
+
+        let missing = stable.missing([0, 1, 2, 7]);
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].id, "GPU-b");
+
+        let entries = stable.entries();
+        assert_eq!(entries.len(), 3);
+        assert!(!entries[1].present);
+        assert_eq!(entries[2].enumeration_index, Some(1));
+    }
+
+    #[test]
+    fn test_remap_takes_over_index_of_replaced_card() {
+        let mut map = GpuIndexMap::default();
+        map.assign(&[detected(0, "GPU-a"), detected(1, "GPU-b")]);
+        map.assign(&[detected(0, "GPU-a"), detected(1, "GPU-c")]);
+
+        let displaced = map.remap("gpu-C", 1).unwrap().unwrap();
+        assert_eq!(displaced.id, "GPU-b");
+        assert_eq!(map.find("GPU-c").unwrap().index, 1);
+        assert_eq!(map.devices.len(), 2);
+
+        assert!(map.remap("GPU-zzz", 0).is_err());
+        assert_eq!(map.remap("GPU-a", 0).unwrap(), None);
+    }
+
+    #[test]
+    fn test_unidentified_devices_get_free_indices() {
+        let mut map = GpuIndexMap::default();
+        map.assign(&[detected(1, "GPU-a")]);
+        let now = [
+            DetectedGpu {
+                enumeration_index: 0,
+                id: None,
+                name: "broken".to_string(),
+            },
+            detected(1, "GPU-a"),
+        ];
+        let stable = StableIndices::new(map, &now);
+        assert_eq!(stable.stable(1), 1);
+        assert_eq!(stable.stable(0), 0);
+        assert_eq!(stable.entries()[0].id, None);
+    }
+
+    #[test]
+    fn test_index_map_round_trips_through_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(INDEX_MAP_FILE);
+        assert_eq!(GpuIndexMap::load(&path).unwrap(), GpuIndexMap::default());
+
+        let mut map = GpuIndexMap::default();
+        map.assign(&[detected(0, "GPU-a")]);
+        map.save(&path).unwrap();
+        assert_eq!(GpuIndexMap::load(&path).unwrap(), map);
+    }
+
+    #[test]
+    fn test_parse_remap() {
+        assert_eq!(
+            parse_remap("GPU-5fd4=2").unwrap(),
+            ("GPU-5fd4".to_string(), 2)
+        );
+        assert_eq!(
+            parse_remap("0000:3b:00.0=0").unwrap(),
+            ("0000:3b:00.0".to_string(), 0)
+        );
+        assert!(parse_remap("GPU-5fd4").is_err());
+        assert!(parse_remap("=1").is_err());
+        assert!(parse_remap("GPU-5fd4=x").is_err());
+    }
+}
@@ -0,0 +1,495 @@
+//! Grafana datasource for the coordinator, speaking the simple-json protocol
+//! (`/grafana/search` and `/grafana/query`).
+//!
+//! The coordinator only stores the latest snapshot of each node, so it keeps a
+//! short, bounded history of GPU readings for timeseries queries. Series are named
+//! `<hostname>/gpu<index>/<metric>`; a bare metric name (`util`, `mem`, `temp`)
+//! selects that metric on every GPU, and the `gpus` table lists the GPUs as of
+//! the latest cluster snapshot.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
+
+use crate::coordinator::{ClusterSnapshot, NodeSnapshot};
+use crate::nvml_api::GpuSnapshot;
+
+/// Readings kept per GPU; six hours at the agents' 30 second reporting interval
+pub const METRIC_HISTORY_CAPACITY: usize = 720;
+
+/// Name of the table target listing every GPU
+pub const GPU_TABLE_TARGET: &str = "gpus";
+
+/// GPU metric available as a timeseries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    /// Utilization in percent
+    Util,
+    /// Memory in use, in MB
+    Mem,
+    /// Temperature in °C
+    Temp,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 3] = [Metric::Util, Metric::Mem, Metric::Temp];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Metric::Util => "util",
+            Metric::Mem => "mem",
+            Metric::Temp => "temp",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|metric| metric.name() == name)
+    }
+
+    fn value(self, sample: &Sample) -> f64 {
+        match self {
+            Metric::Util => sample.util_pct as f64,
+            Metric::Mem => sample.mem_used_mb as f64,
+            Metric::Temp => sample.temp_c as f64,
+        }
+    }
+}
+
+/// One reading of a GPU
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    timestamp: DateTime<Utc>,
+    util_pct: f32,
+    mem_used_mb: u32,
+    temp_c: i32,
+}
+
+impl Sample {
+    fn from_gpu(timestamp: DateTime<Utc>, gpu: &GpuSnapshot) -> Self {
+        Self {
+            timestamp,
+            util_pct: gpu.util_pct,
+            mem_used_mb: gpu.mem_used_mb,
+            temp_c: gpu.temp_c,
+        }
+    }
+}
+
+/// Readings of one GPU, oldest first
+#[derive(Debug, Default)]
+struct Series {
+    hostname: String,
+    samples: VecDeque<Sample>,
+}
+
+/// Recent readings of every GPU in the cluster, by node ID and GPU index
+#[derive(Debug, Clone)]
+pub struct MetricHistory {
+    series: Arc<std::sync::Mutex<BTreeMap<(String, u16), Series>>>,
+    capacity: usize,
+}
+
+impl Default for MetricHistory {
+    fn default() -> Self {
+        Self::new(METRIC_HISTORY_CAPACITY)
+    }
+}
+
+impl MetricHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            series: Arc::new(std::sync::Mutex::new(BTreeMap::new())),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Record the GPUs of a snapshot from `node_id`, registered as `hostname`,
+    /// dropping the oldest readings beyond the capacity
+    pub fn record(&self, node_id: &str, hostname: &str, snapshot: &NodeSnapshot) {
+        let mut series = self.series.lock().unwrap_or_else(|e| e.into_inner());
+        for gpu in &snapshot.gpus {
+            let entry = series
+                .entry((node_id.to_string(), gpu.gpu_index))
+                .or_default();
+            entry.hostname = hostname.to_string();
+            if entry.samples.len() >= self.capacity {
+                entry.samples.pop_front();
+            }
+            entry
+                .samples
+                .push_back(Sample::from_gpu(snapshot.timestamp, gpu));
+        }
+    }
+
+    /// Forget the readings of nodes for which `keep` returns false
+    pub fn retain_nodes(&self, keep: impl Fn(&str) -> bool) {
+        let mut series = self.series.lock().unwrap_or_else(|e| e.into_inner());
+        series.retain(|(node_id, _), _| keep(node_id));
+    }
+
+    /// Names of every recorded series, in node and GPU order
+    fn series_names(&self) -> Vec<String> {
+        let series = self.series.lock().unwrap_or_else(|e| e.into_inner());
+        series
+            .iter()
+            .flat_map(|((_, gpu_index), s)| {
+                Metric::ALL
+                    .into_iter()
+                    .map(move |metric| series_name(&s.hostname, *gpu_index, metric))
+            })
+            .collect()
+    }
+
+    /// Series of `metric` within `range`, on the GPUs `matches` accepts
+    fn timeseries(
+        &self,
+        metric: Metric,
+        range: &QueryRange,
+        max_points: Option<usize>,
+        matches: impl Fn(&str, u16) -> bool,
+    ) -> Vec<QueryResponse> {
+        let series = self.series.lock().unwrap_or_else(|e| e.into_inner());
+        series
+            .iter()
+            .filter(|((_, gpu_index), s)| matches(&s.hostname, *gpu_index))
+            .map(|((_, gpu_index), s)| {
+                let points: Vec<[f64; 2]> = s
+                    .samples
+                    .iter()
+                    .filter(|sample| sample.timestamp >= range.from && sample.timestamp <= range.to)
+                    .map(|sample| {
+                        [
+                            metric.value(sample),
+                            sample.timestamp.timestamp_millis() as f64,
+                        ]
+                    })
+                    .collect();
+                QueryResponse::Timeserie {
+                    target: series_name(&s.hostname, *gpu_index, metric),
+                    datapoints: downsample(points, max_points),
+                }
+            })
+            .collect()
+    }
+}
+
+fn series_name(hostname: &str, gpu_index: u16, metric: Metric) -> String {
+    format!("{}/gpu{}/{}", hostname, gpu_index, metric.name())
+}
+
+/// Keep at most `max_points` evenly spaced points, always including the latest
+fn downsample(points: Vec<[f64; 2]>, max_points: Option<usize>) -> Vec<[f64; 2]> {
+    let max_points = match max_points {
+        Some(max) if max > 0 && points.len() > max => max,
+        _ => return points,
+    };
+    let step = points.len().div_ceil(max_points);
+    let mut kept: Vec<[f64; 2]> = points.iter().rev().step_by(step).copied().collect();
+    kept.reverse();
+    kept
+}
+
+/// Body of `POST /grafana/search`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SearchRequest {
+    #[serde(default)]
+    pub target: String,
+}
+
+/// Body of `POST /grafana/query`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryRequest {
+    pub range: QueryRange,
+    #[serde(default)]
+    pub targets: Vec<QueryTarget>,
+    #[serde(default)]
+    pub max_data_points: Option<usize>,
+}
+
+/// Time range of a query
+#[derive(Debug, Clone, Deserialize)]
+pub struct QueryRange {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+/// One panel query
+#[derive(Debug, Clone, Deserialize)]
+pub struct QueryTarget {
+    pub target: String,
+    #[serde(default, rename = "type")]
+    pub kind: TargetKind,
+}
+
+/// Whether a target asks for timeseries or a table
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetKind {
+    #[default]
+    Timeserie,
+    Table,
+}
+
+/// One result of `POST /grafana/query`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum QueryResponse {
+    /// `[value, unix milliseconds]` pairs, oldest first
+    Timeserie {
+        target: String,
+        datapoints: Vec<[f64; 2]>,
+    },
+    Table {
+        #[serde(rename = "type")]
+        kind: &'static str,
+        columns: Vec<TableColumn>,
+        rows: Vec<Vec<serde_json::Value>>,
+    },
+}
+
+/// Column of a table result
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TableColumn {
+    pub text: &'static str,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+}
+
+/// Metric names matching `request`: the bare metrics, the GPU table and every
+/// recorded series
+pub fn search(history: &MetricHistory, request: &SearchRequest) -> Vec<String> {
+    let query = request.target.trim().to_lowercase();
+    Metric::ALL
+        .into_iter()
+        .map(|metric| metric.name().to_string())
+        .chain(std::iter::once(GPU_TABLE_TARGET.to_string()))
+        .chain(history.series_names())
+        .filter(|name| query.is_empty() || name.to_lowercase().contains(&query))
+        .collect()
+}
+
+/// Answer every target of `request`, in order. Unknown targets are an error so
+/// that a mistyped panel shows it rather than an empty graph.
+pub fn query(
+    history: &MetricHistory,
+    cluster: Option<&ClusterSnapshot>,
+    request: &QueryRequest,
+) -> Result<Vec<QueryResponse>> {
+    let mut responses = Vec::new();
+    for target in &request.targets {
+        match target.kind {
+            TargetKind::Table => {
+                if target.target != GPU_TABLE_TARGET {
+                    anyhow::bail!(
+                        "Unknown table '{}'; the only table is '{}'",
+                        target.target,
+                        GPU_TABLE_TARGET
+                    );
+                }
+                responses.push(gpu_table(cluster));
+            }
+            TargetKind::Timeserie => {
+                let (metric, selector) = parse_series_target(&target.target)?;
+                responses.extend(history.timeseries(
+                    metric,
+                    &request.range,
+                    request.max_data_points,
+                    |hostname, gpu_index| match &selector {
+                        Some((host, index)) => host == hostname && *index == gpu_index,
+                        None => true,
+                    },
+                ));
+            }
+        }
+    }
+    Ok(responses)
+}
+
+/// Split `<hostname>/gpu<index>/<metric>` or a bare metric name
+fn parse_series_target(target: &str) -> Result<(Metric, Option<(String, u16)>)> {
+    let unknown = || {
+        anyhow::anyhow!(
+            "Unknown target '{}'; expected one of util, mem, temp or <host>/gpu<index>/<metric>",
+            target
+        )
+    };
+    if let Some(metric) = Metric::parse(target) {
+        return Ok((metric, None));
+    }
+    let mut parts = target.rsplitn(3, '/');
+    let metric = parts.next().and_then(Metric::parse).ok_or_else(unknown)?;
+    let gpu_index = parts
+        .next()
+        .and_then(|gpu| gpu.strip_prefix("gpu"))
+        .and_then(|index| index.parse::<u16>().ok())
+        .ok_or_else(unknown)?;
+    let hostname = parts.next().filter(|h| !h.is_empty()).ok_or_else(unknown)?;
+    Ok((metric, Some((hostname.to_string(), gpu_index))))
+}
+
+/// Current GPUs of the cluster as a table
+fn gpu_table(cluster: Option<&ClusterSnapshot>) -> QueryResponse {
+    let column = |text, kind| TableColumn { text, kind };
+    let columns = vec![
+        column("Node", "string"),
+        column("GPU", "number"),
+        column("Name", "string"),
+        column("Util %", "number"),
+        column("Mem Used MB", "number"),
+        column("Mem Total MB", "number"),
+        column("Temp C", "number"),
+        column("Processes", "number"),
+    ];
+    let rows = cluster
+        .into_iter()
+        .flat_map(|cluster| cluster.nodes.iter())
+        .flat_map(|node| {
+            node.gpus.iter().map(move |gpu| {
+                vec![
+                    node.hostname.clone().into(),
+                    gpu.gpu_index.into(),
+                    gpu.name.clone().into(),
+                    gpu.util_pct.into(),
+                    gpu.mem_used_mb.into(),
+                    gpu.mem_total_mb.into(),
+                    gpu.temp_c.into(),
+                    gpu.pids.into(),
+                ]
+            })
+        })
+        .collect();
+    QueryResponse::Table {
+        kind: "table",
+        columns,
+        rows,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::coordinator::NodeStatus;
+    use crate::nvml_api::fixtures::gpu;
+    use std::collections::HashMap;
+
+    fn snapshot(hostname: &str, timestamp: DateTime<Utc>, util_pct: f32) -> NodeSnapshot {
+        NodeSnapshot {
+            node_id: format!("id-{}", hostname),
+            hostname: hostname.to_string(),
+            timestamp,
+            gpus: (0..2)
+                .map(|gpu_index| GpuSnapshot {
+                    mem_total_mb: 10000,
+                    power_w: 50.0,
+                    pids: 1,
+                    ..gpu(gpu_index, util_pct, 1000 * (gpu_index as u32 + 1))
+                })
+                .collect(),
+            processes: Vec::new(),
+            status: NodeStatus::Online,
+            tags: HashMap::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_history_answers_series_and_bare_metric_targets() {
+        let history = MetricHistory::new(3);
+        let start = Utc::now();
+        for i in 0..5 {
+            let at = start + chrono::Duration::seconds(i);
+            history.record("id-a", "a", &snapshot("a", at, i as f32 * 10.0));
+        }
+        history.record("id-b", "b", &snapshot("b", start, 99.0));
+
+        let names = search(&history, &SearchRequest::default());
+        assert!(names.contains(&"util".to_string()));
+        assert!(names.contains(&GPU_TABLE_TARGET.to_string()));
+        assert!(names.contains(&"a/gpu1/temp".to_string()));
+        let matching = search(
+            &history,
+            &SearchRequest {
+                target: "b/gpu0".to_string(),
+            },
+        );
+        assert_eq!(matching, vec!["b/gpu0/util", "b/gpu0/mem", "b/gpu0/temp"]);
+
+        let request = |targets: &[&str], max_data_points| QueryRequest {
+            range: QueryRange {
+                from: start - chrono::Duration::minutes(1),
+                to: start + chrono::Duration::minutes(1),
+            },
+            targets: targets
+                .iter()
+                .map(|target| QueryTarget {
+                    target: target.to_string(),
+                    kind: TargetKind::Timeserie,
+                })
+                .collect(),
+            max_data_points,
+        };
+
+        // Capacity 3 keeps the last three readings of node a
+        let responses = query(&history, None, &request(&["a/gpu0/util"], None)).unwrap();
+        let start_ms = start.timestamp_millis() as f64;
+        assert_eq!(
+            responses,
+            vec![QueryResponse::Timeserie {
+                target: "a/gpu0/util".to_string(),
+                datapoints: vec![
+                    [20.0, start_ms + 2000.0],
+                    [30.0, start_ms + 3000.0],
+                    [40.0, start_ms + 4000.0],
+                ],
+            }]
+        );
+
+        let responses = query(&history, None, &request(&["mem"], Some(2))).unwrap();
+        assert_eq!(responses.len(), 4);
+        match &responses[1] {
+            QueryResponse::Timeserie { target, datapoints } => {
+                assert_eq!(target, "a/gpu1/mem");
+                assert_eq!(
+                    datapoints,
+                    &vec![[2000.0, start_ms + 2000.0], [2000.0, start_ms + 4000.0]]
+                );
+            }
+            other => panic!("expected a timeserie, got {:?}", other),
+        }
+
+        history.retain_nodes(|node_id| node_id != "id-a");
+        let responses = query(&history, None, &request(&["util"], None)).unwrap();
+        assert_eq!(responses.len(), 2);
+
+        assert!(query(&history, None, &request(&["power"], None)).is_err());
+        assert!(query(&history, None, &request(&["b/gpux/util"], None)).is_err());
+    }
+
+    #[test]
+    fn test_gpu_table_lists_current_gpus() {
+        let now = Utc::now();
+        let cluster = ClusterSnapshot::from_nodes(now, vec![snapshot("a", now, 55.0)]);
+        let request = QueryRequest {
+            range: QueryRange { from: now, to: now },
+            targets: vec![QueryTarget {
+                target: GPU_TABLE_TARGET.to_string(),
+                kind: TargetKind::Table,
+            }],
+            max_data_points: None,
+        };
+        let json = serde_json::to_value(
+            query(&MetricHistory::default(), Some(&cluster), &request).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(json[0]["type"], "table");
+        assert_eq!(json[0]["columns"][0]["text"], "Node");
+        assert_eq!(json[0]["columns"][3]["type"], "number");
+        assert_eq!(json[0]["rows"].as_array().unwrap().len(), 2);
+        assert_eq!(json[0]["rows"][1][0], "a");
+        assert_eq!(json[0]["rows"][1][1], 1);
+        assert_eq!(json[0]["rows"][1][3], 55.0);
+    }
+}
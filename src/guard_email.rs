@@ -0,0 +1,921 @@
+//! Email notifications for Guard Mode
+//!
+//! Violations and warnings are not mailed one at a time. [`EmailNotifier`]
+//! collects them for `smtp.digest_interval_seconds` and then sends one digest per
+//! recipient: a plain-text table with the same entries attached as JSON. Mail
+//! that cannot be delivered stays queued and is retried with exponential backoff;
+//! delivery problems are logged and never interrupt enforcement.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::transport::smtp::client::{Tls, TlsParameters};
+use lettre::{FileTransport, Message, SmtpTransport, Transport};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+use tabled::settings::Style;
+use tabled::{Table, Tabled};
+use tracing::{info, warn};
+
+use crate::guard_mode::{EnforcementResult, NotificationSettings};
+
+/// Environment variable that overrides `smtp.username`
+pub const SMTP_USERNAME_ENV: &str = "GPUKILL_SMTP_USERNAME";
+/// Environment variable that overrides `smtp.password`
+pub const SMTP_PASSWORD_ENV: &str = "GPUKILL_SMTP_PASSWORD";
+
+/// Delay before the first retry of an undelivered digest; doubles on each attempt
+const RETRY_BASE_SECONDS: i64 = 30;
+/// Longest delay between two delivery attempts
+const RETRY_MAX_SECONDS: i64 = 3600;
+/// Delivery attempts per digest and recipient before the digest is dropped
+const MAX_DELIVERY_ATTEMPTS: u32 = 6;
+
+/// How the connection to the SMTP server is secured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpTlsMode {
+    /// Plain SMTP without encryption
+    None,
+    /// Plain connection upgraded with STARTTLS, which the server must offer
+    #[default]
+    StartTls,
+    /// TLS from the first byte (SMTPS)
+    Tls,
+}
+
+impl SmtpTlsMode {
+    /// Port used when `smtp.port` is not set
+    pub fn default_port(&self) -> u16 {
+        match self {
+            SmtpTlsMode::None => 25,
+            SmtpTlsMode::StartTls => 587,
+            SmtpTlsMode::Tls => 465,
+        }
+    }
+}
+
+impl fmt::Display for SmtpTlsMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SmtpTlsMode::None => "none",
+            SmtpTlsMode::StartTls => "starttls",
+            SmtpTlsMode::Tls => "tls",
+        })
+    }
+}
+
+/// SMTP server and digest settings for Guard Mode email notifications
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SmtpSettings {
+    /// SMTP server host name
+    #[serde(default = "default_smtp_host")]
+    pub host: String,
+    /// SMTP server port; defaults to 25, 587 or 465 depending on `tls`
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// Connection security: `none`, `starttls` or `tls`
+    #[serde(default)]
+    pub tls: SmtpTlsMode,
+    /// Login name; GPUKILL_SMTP_USERNAME takes precedence
+    #[serde(default)]
+    pub username: Option<String>,
+    /// Password; GPUKILL_SMTP_PASSWORD takes precedence and keeps it out of the file
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Sender address; defaults to gpukill@<hostname>
+    #[serde(default)]
+    pub from: Option<String>,
+    /// How long violations and warnings are collected before a digest is sent
+    #[serde(default = "default_digest_interval_seconds")]
+    pub digest_interval_seconds: u64,
+    /// Timeout for each SMTP connection
+    #[serde(default = "default_smtp_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+fn default_smtp_host() -> String {
+    "localhost".to_string()
+}
+
+fn default_digest_interval_seconds() -> u64 {
+    900 // 15 minutes
+}
+
+fn default_smtp_timeout_seconds() -> u64 {
+    10
+}
+
+impl Default for SmtpSettings {
+    fn default() -> Self {
+        Self {
+            host: default_smtp_host(),
+            port: None,
+            tls: SmtpTlsMode::default(),
+            username: None,
+            password: None,
+            from: None,
+            digest_interval_seconds: default_digest_interval_seconds(),
+            timeout_seconds: default_smtp_timeout_seconds(),
+        }
+    }
+}
+
+impl SmtpSettings {
+    /// Port to connect to
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or_else(|| self.tls.default_port())
+    }
+
+    /// `host:port (tls)`, for messages
+    pub fn endpoint(&self) -> String {
+        format!("{}:{} ({})", self.host, self.port(), self.tls)
+    }
+
+    /// Sender mailbox
+    pub fn sender_mailbox(&self) -> Result<Mailbox> {
+        let from = match &self.from {
+            Some(from) => from.clone(),
+            None => format!("gpukill@{}", crate::util::get_hostname()),
+        };
+        from.parse()
+            .map_err(|e| anyhow::anyhow!("invalid sender address '{}': {}", from, e))
+    }
+
+    /// Login credentials, from the environment first and the config second
+    pub fn credentials(&self) -> Option<Credentials> {
+        self.credentials_from(|key| std::env::var(key).ok())
+    }
+
+    /// Same as [`SmtpSettings::credentials`] with variables read through `lookup`
+    pub fn credentials_from(&self, lookup: impl Fn(&str) -> Option<String>) -> Option<Credentials> {
+        let username = lookup(SMTP_USERNAME_ENV).or_else(|| self.username.clone())?;
+        let password = lookup(SMTP_PASSWORD_ENV)
+            .or_else(|| self.password.clone())
+            .unwrap_or_default();
+        Some(Credentials::new(username, password))
+    }
+
+    /// SMTP transport for these settings
+    pub fn transport(&self) -> Result<SmtpTransport> {
+        let tls = match self.tls {
+            SmtpTlsMode::None => Tls::None,
+            SmtpTlsMode::StartTls => Tls::Required(
+                TlsParameters::new(self.host.clone())
+                    .with_context(|| format!("Failed to set up TLS for {}", self.host))?,
+            ),
+            SmtpTlsMode::Tls => Tls::Wrapper(
+                TlsParameters::new(self.host.clone())
+                    .with_context(|| format!("Failed to set up TLS for {}", self.host))?,
+            ),
+        };
+        let mut builder = SmtpTransport::builder_dangerous(self.host.clone())
+            .port(self.port())
+            .tls(tls)
+            .timeout(Some(Duration::from_secs(self.timeout_seconds.max(1))));
+        if let Some(credentials) = self.credentials() {
+            builder = builder.credentials(credentials);
+        }
+        Ok(builder.build())
+    }
+}
+
+/// Where messages are delivered: an SMTP server, or a directory of .eml files
+pub trait MailSender: Send + Sync {
+    fn send_mail(&self, message: &Message) -> Result<()>;
+}
+
+impl MailSender for SmtpTransport {
+    fn send_mail(&self, message: &Message) -> Result<()> {
+        self.send(message)
+            .map(|_| ())
+            .map_err(|e| anyhow::anyhow!(describe_smtp_error(&e)))
+    }
+}
+
+impl MailSender for FileTransport {
+    fn send_mail(&self, message: &Message) -> Result<()> {
+        self.send(message)
+            .map(|_| ())
+            .map_err(|e| anyhow::anyhow!("Failed to write message: {}", e))
+    }
+}
+
+/// SMTP error with a short explanation of which side failed
+fn describe_smtp_error(error: &lettre::transport::smtp::Error) -> String {
+    let cause = if error.is_permanent() {
+        "server rejected the message"
+    } else if error.is_transient() {
+        "server temporarily refused the message"
+    } else if error.is_timeout() {
+        "connection timed out"
+    } else if error.is_tls() {
+        "TLS negotiation failed"
+    } else {
+        "could not talk to the server"
+    };
+    format!("{}: {}", cause, error)
+}
+
+/// One violation or warning in a digest
+#[derive(Debug, Clone, Serialize)]
+pub struct DigestEntry {
+    /// `violation` or `warning`
+    pub kind: String,
+    pub timestamp: DateTime<Utc>,
+    pub user: String,
+    pub pid: u32,
+    pub gpu_index: u16,
+    pub process: String,
+    pub policy: String,
+    /// Violation severity; None for warnings
+    pub severity: Option<String>,
+    pub current_value: f32,
+    pub limit_value: f32,
+    pub message: String,
+}
+
+impl DigestEntry {
+    /// Entries for every violation and warning of a policy check
+    pub fn from_result(result: &EnforcementResult, timestamp: DateTime<Utc>) -> Vec<Self> {
+        let violations = result.violations.iter().map(|violation| DigestEntry {
+            kind: "violation".to_string(),
+            timestamp,
+            user: violation.user.clone(),
+            pid: violation.process.pid,
+            gpu_index: violation.process.gpu_index,
+            process: violation.process.proc_name.clone(),
+            policy: violation.policy_name.clone(),
+            severity: Some(violation.severity.as_str().to_string()),
+            current_value: violation.current_value,
+            limit_value: violation.limit_value,
+            message: violation.message.clone(),
+        });
+        let warnings = result.warnings.iter().map(|warning| DigestEntry {
+            kind: "warning".to_string(),
+            timestamp,
+            user: warning.user.clone(),
+            pid: warning.process.pid,
+            gpu_index: warning.process.gpu_index,
+            process: warning.process.proc_name.clone(),
+            policy: warning.policy_name.clone(),
+            severity: None,
+            current_value: warning.current_value,
+            limit_value: warning.limit_value,
+            message: warning.message.clone(),
+        });
+        violations.chain(warnings).collect()
+    }
+}
+
+#[derive(Tabled)]
+struct DigestRow {
+    #[tabled(rename = "Time (UTC)")]
+    time: String,
+    #[tabled(rename = "Kind")]
+    kind: String,
+    #[tabled(rename = "Severity")]
+    severity: String,
+    #[tabled(rename = "User")]
+    user: String,
+    #[tabled(rename = "PID")]
+    pid: u32,
+    #[tabled(rename = "GPU")]
+    gpu_index: u16,
+    #[tabled(rename = "Process")]
+    process: String,
+    #[tabled(rename = "Policy")]
+    policy: String,
+    #[tabled(rename = "Message")]
+    message: String,
+}
+
+/// Violations and warnings collected over one digest interval
+#[derive(Debug, Clone, Serialize)]
+pub struct Digest {
+    pub hostname: String,
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    pub violations: usize,
+    pub warnings: usize,
+    pub entries: Vec<DigestEntry>,
+}
+
+impl Digest {
+    pub fn new(
+        hostname: String,
+        since: DateTime<Utc>,
+        until: DateTime<Utc>,
+        entries: Vec<DigestEntry>,
+    ) -> Self {
+        let violations = entries.iter().filter(|e| e.kind == "violation").count();
+        Self {
+            hostname,
+            since,
+            until,
+            violations,
+            warnings: entries.len() - violations,
+            entries,
+        }
+    }
+
+    pub fn subject(&self) -> String {
+        format!(
+            "[gpukill] Guard Mode on {}: {} violation{}, {} warning{}",
+            self.hostname,
+            self.violations,
+            if self.violations == 1 { "" } else { "s" },
+            self.warnings,
+            if self.warnings == 1 { "" } else { "s" }
+        )
+    }
+
+    /// Plain-text body: a summary line and one table row per entry
+    pub fn to_text(&self) -> String {
+        let rows: Vec<DigestRow> = self
+            .entries
+            .iter()
+            .map(|entry| DigestRow {
+                time: entry.timestamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+                kind: entry.kind.clone(),
+                severity: entry.severity.clone().unwrap_or_else(|| "-".to_string()),
+                user: entry.user.clone(),
+                pid: entry.pid,
+                gpu_index: entry.gpu_index,
+                process: entry.process.clone(),
+                policy: entry.policy.clone(),
+                message: entry.message.clone(),
+            })
+            .collect();
+        let mut table = Table::new(rows);
+        table.with(Style::psql());
+
+        format!(
+            "Guard Mode on {} recorded {} violation(s) and {} warning(s) between {} and {} (UTC).\n\n{}\n\nThe same entries are attached as JSON.\n",
+            self.hostname,
+            self.violations,
+            self.warnings,
+            self.since.format("%Y-%m-%d %H:%M:%S"),
+            self.until.format("%Y-%m-%d %H:%M:%S"),
+            table
+        )
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).context("Failed to serialize Guard Mode digest")
+    }
+
+    /// Digest mail for one recipient
+    pub fn to_message(&self, from: &Mailbox, to: &Mailbox) -> Result<Message> {
+        let attachment = Attachment::new(format!(
+            "gpukill-guard-{}.json",
+            self.until.format("%Y%m%dT%H%M%SZ")
+        ))
+        .body(self.to_json()?, ContentType::parse("application/json")?);
+        Message::builder()
+            .from(from.clone())
+            .to(to.clone())
+            .subject(self.subject())
+            .multipart(
+                MultiPart::mixed()
+                    .singlepart(SinglePart::plain(self.to_text()))
+                    .singlepart(attachment),
+            )
+            .context("Failed to build Guard Mode digest email")
+    }
+}
+
+/// A digest waiting to be delivered to one recipient
+struct QueuedMail {
+    recipient: String,
+    message: Message,
+    attempts: u32,
+    next_attempt: DateTime<Utc>,
+}
+
+/// Collects Guard Mode violations and warnings and mails them as periodic digests
+pub struct EmailNotifier {
+    settings: NotificationSettings,
+    sender: Box<dyn MailSender>,
+    pending: Vec<DigestEntry>,
+    window_start: DateTime<Utc>,
+    outbox: Vec<QueuedMail>,
+}
+
+impl EmailNotifier {
+    pub fn new(
+        settings: NotificationSettings,
+        sender: Box<dyn MailSender>,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            settings,
+            sender,
+            pending: Vec::new(),
+            window_start: now,
+            outbox: Vec::new(),
+        }
+    }
+
+    /// Notifier delivering through the SMTP server in `settings`
+    pub fn from_settings(settings: &NotificationSettings, now: DateTime<Utc>) -> Result<Self> {
+        let transport = settings.smtp.transport()?;
+        Ok(Self::new(settings.clone(), Box::new(transport), now))
+    }
+
+    /// Settings this notifier was built from
+    pub fn settings(&self) -> &NotificationSettings {
+        &self.settings
+    }
+
+    /// Take over the collected entries and undelivered mail of a notifier being
+    /// replaced after a config change
+    pub fn adopt(&mut self, previous: EmailNotifier) {
+        self.window_start = self.window_start.min(previous.window_start);
+        self.pending.extend(previous.pending);
+        self.outbox.extend(previous.outbox);
+    }
+
+    /// Add the violations and warnings of a policy check to the next digest
+    pub fn record(&mut self, result: &EnforcementResult, now: DateTime<Utc>) {
+        self.pending.extend(DigestEntry::from_result(result, now));
+    }
+
+    /// Entries waiting for the next digest
+    #[allow(dead_code)]
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Digests built but not delivered yet, counted per recipient
+    #[allow(dead_code)]
+    pub fn queued(&self) -> usize {
+        self.outbox.len()
+    }
+
+    /// Build a digest if the interval has passed and deliver whatever mail is due.
+    /// Returns the number of messages delivered.
+    pub fn tick(&mut self, now: DateTime<Utc>) -> usize {
+        let interval = chrono::Duration::seconds(self.settings.smtp.digest_interval_seconds as i64);
+        if now.signed_duration_since(self.window_start) >= interval {
+            if !self.pending.is_empty() {
+                let digest = Digest::new(
+                    crate::util::get_hostname(),
+                    self.window_start,
+                    now,
+                    std::mem::take(&mut self.pending),
+                );
+                self.enqueue(&digest, now);
+            }
+            self.window_start = now;
+        }
+        self.deliver_due(now)
+    }
+
+    fn enqueue(&mut self, digest: &Digest, now: DateTime<Utc>) {
+        let from = match self.settings.smtp.sender_mailbox() {
+            Ok(from) => from,
+            Err(e) => {
+                warn!(error = %e, "Dropping Guard Mode email digest");
+                return;
+            }
+        };
+        for recipient in &self.settings.email_recipients {
+            let message = recipient
+                .parse::<Mailbox>()
+                .map_err(|e| anyhow::anyhow!("invalid recipient address: {}", e))
+                .and_then(|to| digest.to_message(&from, &to));
+            match message {
+                Ok(message) => self.outbox.push(QueuedMail {
+                    recipient: recipient.clone(),
+                    message,
+                    attempts: 0,
+                    next_attempt: now,
+                }),
+                Err(e) => warn!(
+                    recipient = %recipient,
+                    error = %e,
+                    "Skipping Guard Mode email digest"
+                ),
+            }
+        }
+    }
+
+    fn deliver_due(&mut self, now: DateTime<Utc>) -> usize {
+        let mut delivered = 0;
+        let mut remaining = Vec::new();
+        for mut mail in std::mem::take(&mut self.outbox) {
+            if mail.next_attempt > now {
+                remaining.push(mail);
+                continue;
+            }
+            match self.sender.send_mail(&mail.message) {
+                Ok(()) => {
+                    info!(recipient = %mail.recipient, "Sent Guard Mode email digest");
+                    delivered += 1;
+                }
+                Err(e) => {
+                    mail.attempts += 1;
+                    if mail.attempts >= MAX_DELIVERY_ATTEMPTS {
+                        warn!(
+                            recipient = %mail.recipient,
+                            attempts = mail.attempts,
+                            error = %e,
+                            "Giving up on Guard Mode email digest"
+                        );
+                        continue;
+                    }
+                    let delay = retry_delay(mail.attempts);
+                    warn!(
+                        recipient = %mail.recipient,
+                        server = %self.settings.smtp.endpoint(),
+                        attempt = mail.attempts,
+                        retry_in_seconds = delay.num_seconds(),
+                        error = %e,
+                        "Failed to send Guard Mode email digest"
+                    );
+                    mail.next_attempt = now + delay;
+                    remaining.push(mail);
+                }
+            }
+        }
+        self.outbox = remaining;
+        delivered
+    }
+
+    /// Send a test message to every recipient right away (`--test-notification`).
+    /// Returns the recipients the message was delivered to.
+    pub fn send_test(&self) -> Result<Vec<String>> {
+        if self.settings.email_recipients.is_empty() {
+            anyhow::bail!(
+                "No email recipients configured (enforcement.notifications.email_recipients)"
+            );
+        }
+        let from = self.settings.smtp.sender_mailbox()?;
+        let hostname = crate::util::get_hostname();
+        let mut delivered = Vec::new();
+        for recipient in &self.settings.email_recipients {
+            let to: Mailbox = recipient
+                .parse()
+                .map_err(|e| anyhow::anyhow!("invalid recipient address '{}': {}", recipient, e))?;
+            let message = Message::builder()
+                .from(from.clone())
+                .to(to)
+                .subject(format!("[gpukill] Guard Mode test notification from {}", hostname))
+                .body(format!(
+                    "This is a test message from gpukill Guard Mode on {}.\n\nViolations and warnings will be sent to this address as a digest every {} seconds.\n",
+                    hostname, self.settings.smtp.digest_interval_seconds
+                ))
+                .context("Failed to build test email")?;
+            self.sender.send_mail(&message).with_context(|| {
+                format!(
+                    "Failed to send test notification to {} via {}",
+                    recipient,
+                    self.settings.smtp.endpoint()
+                )
+            })?;
+            delivered.push(recipient.clone());
+        }
+        Ok(delivered)
+    }
+}
+
+/// Backoff before the next attempt after `attempts` failed deliveries
+fn retry_delay(attempts: u32) -> chrono::Duration {
+    let seconds = RETRY_BASE_SECONDS.saturating_mul(1 << attempts.saturating_sub(1).min(16));
+    chrono::Duration::seconds(seconds.min(RETRY_MAX_SECONDS))
+}
+
+/// Check the email settings of a Guard Mode config
+pub fn validate_settings(settings: &NotificationSettings) -> Result<()> {
+    if !settings.email {
+        return Ok(());
+    }
+    if settings.email_recipients.is_empty() {
+        anyhow::bail!("enforcement.notifications: email is enabled but email_recipients is empty");
+    }
+    for recipient in &settings.email_recipients {
+        recipient.parse::<Mailbox>().map_err(|e| {
+            anyhow::anyhow!(
+                "enforcement.notifications: invalid email recipient '{}': {}",
+                recipient,
+                e
+            )
+        })?;
+    }
+    settings
+        .smtp
+        .sender_mailbox()
+        .map_err(|e| anyhow::anyhow!("enforcement.notifications.smtp: {}", e))?;
+    if settings.smtp.host.trim().is_empty() {
+        anyhow::bail!("enforcement.notifications.smtp: host must not be empty");
+    }
+    if settings.smtp.digest_interval_seconds == 0 {
+        anyhow::bail!(
+            "enforcement.notifications.smtp: digest_interval_seconds must be greater than 0"
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::guard_mode::{PolicyViolation, PolicyWarning, ViolationSeverity};
+    use crate::guard_mode::{ViolationType, WarningType};
+    use crate::nvml_api::fixtures::proc;
+    use chrono::TimeZone;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+
+    fn result(pid: u32) -> EnforcementResult {
+        EnforcementResult {
+            timestamp: Utc::now(),
+            violations: vec![PolicyViolation {
+                violation_type: ViolationType::MemoryLimitExceeded,
+                severity: ViolationSeverity::High,
+                user: "alice".to_string(),
+                process: proc(1, pid, "alice", "train", 20480),
+                policy_name: "user:alice".to_string(),
+                current_value: 20.0,
+                limit_value: 16.0,
+                message: "Memory usage 20.0GB exceeds limit 16.0GB".to_string(),
+                recommended_action: "Reduce memory usage".to_string(),
+            }],
+            warnings: vec![PolicyWarning {
+                warning_type: WarningType::ApproachingUtilizationLimit,
+                user: "alice".to_string(),
+                process: proc(1, pid, "alice", "train", 20480),
+                policy_name: "user:alice".to_string(),
+                current_value: 75.0,
+                limit_value: 80.0,
+                message: "Utilization 75.0% is approaching limit 80.0%".to_string(),
+                time_to_limit: None,
+            }],
+            actions_taken: Vec::new(),
+            dry_run: false,
+        }
+    }
+
+    fn settings(recipients: &[&str]) -> NotificationSettings {
+        let mut settings = NotificationSettings {
+            email: true,
+            email_recipients: recipients.iter().map(|r| r.to_string()).collect(),
+            ..NotificationSettings::default()
+        };
+        settings.smtp.from = Some("gpukill@gpu-node-1".to_string());
+        settings.smtp.digest_interval_seconds = 600;
+        settings
+    }
+
+    /// Fails the first `failures` deliveries, then records recipients of the rest
+    #[derive(Clone, Default)]
+    struct FlakySender {
+        failures: Arc<Mutex<u32>>,
+        delivered: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl MailSender for FlakySender {
+        fn send_mail(&self, message: &Message) -> Result<()> {
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                anyhow::bail!("server temporarily refused the message: transient error (451)");
+            }
+            let to = message.envelope().to()[0].to_string();
+            self.delivered.lock().unwrap().push(to);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_digest_text_and_json() {
+        let since = Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap();
+        let until = Utc.with_ymd_and_hms(2026, 3, 2, 9, 15, 0).unwrap();
+        let digest = Digest::new(
+            "gpu-node-1".to_string(),
+            since,
+            until,
+            DigestEntry::from_result(&result(4242), since),
+        );
+
+        assert_eq!(
+            digest.subject(),
+            "[gpukill] Guard Mode on gpu-node-1: 1 violation, 1 warning"
+        );
+        let text = digest.to_text();
+        assert!(text.contains("between 2026-03-02 09:00:00 and 2026-03-02 09:15:00"));
+        let row = text
+            .lines()
+            .find(|line| line.contains("| violation"))
+            .unwrap();
+        for cell in ["high", "alice", "4242", "train", "user:alice"] {
+            assert!(row.contains(cell), "{} missing from {}", cell, row);
+        }
+
+        let json: serde_json::Value = serde_json::from_str(&digest.to_json().unwrap()).unwrap();
+        assert_eq!(json["violations"], 1);
+        assert_eq!(json["warnings"], 1);
+        assert_eq!(json["entries"][0]["severity"], "high");
+        assert_eq!(json["entries"][1]["kind"], "warning");
+        assert!(json["entries"][1]["severity"].is_null());
+    }
+
+    #[test]
+    fn test_credentials_prefer_environment() {
+        let mut smtp = SmtpSettings {
+            username: Some("config-user".to_string()),
+            password: Some("config-pass".to_string()),
+            ..SmtpSettings::default()
+        };
+        let env = |key: &str| match key {
+            SMTP_PASSWORD_ENV => Some("env-pass".to_string()),
+            _ => None,
+        };
+        let expected = Credentials::new("config-user".to_string(), "env-pass".to_string());
+        assert_eq!(smtp.credentials_from(env), Some(expected));
+
+        smtp.username = None;
+        assert_eq!(smtp.credentials_from(env), None);
+        assert_eq!(smtp.port(), 587);
+        smtp.tls = SmtpTlsMode::Tls;
+        assert_eq!(smtp.endpoint(), "localhost:465 (tls)");
+    }
+
+    #[test]
+    fn test_violations_are_batched_into_one_digest_per_recipient() {
+        let dir = tempfile::tempdir().unwrap();
+        let start = Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap();
+        let mut notifier = EmailNotifier::new(
+            settings(&["ops@example.com", "Alice <alice@example.com>"]),
+            Box::new(FileTransport::new(dir.path())),
+            start,
+        );
+
+        for minute in 0..5 {
+            let now = start + chrono::Duration::minutes(minute);
+            notifier.record(&result(1000 + minute as u32), now);
+            assert_eq!(notifier.tick(now), 0);
+        }
+        assert_eq!(notifier.pending(), 10);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+
+        assert_eq!(notifier.tick(start + chrono::Duration::minutes(10)), 2);
+        assert_eq!(notifier.pending(), 0);
+        let mut mails: Vec<String> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+            .collect();
+        mails.sort();
+        assert_eq!(mails.len(), 2);
+        for mail in &mails {
+            assert!(mail.contains("5 violations, 5 warnings"), "{}", mail);
+            assert!(mail.contains("Content-Type: text/plain"));
+            assert!(mail.contains("Content-Type: application/json"));
+            assert!(mail.contains("Content-Disposition: attachment;"));
+            assert!(mail.contains("\"gpukill-guard-20260302T091000Z.json\""));
+        }
+        assert!(mails.iter().any(|m| m.contains("To: ops@example.com")));
+        assert!(mails
+            .iter()
+            .any(|m| m.contains("To: Alice <alice@example.com>")));
+
+        // Nothing recorded in the next interval, nothing sent
+        assert_eq!(notifier.tick(start + chrono::Duration::minutes(30)), 0);
+    }
+
+    #[test]
+    fn test_failed_digests_are_retried_with_backoff() {
+        let sender = FlakySender::default();
+        *sender.failures.lock().unwrap() = 2;
+        let start = Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap();
+        let mut notifier = EmailNotifier::new(
+            settings(&["ops@example.com"]),
+            Box::new(sender.clone()),
+            start,
+        );
+
+        notifier.record(&result(1), start);
+        let due = start + chrono::Duration::minutes(10);
+        assert_eq!(notifier.tick(due), 0);
+        assert_eq!(notifier.queued(), 1);
+
+        // First retry after 30s, second after another 60s
+        assert_eq!(notifier.tick(due + chrono::Duration::seconds(29)), 0);
+        assert_eq!(notifier.tick(due + chrono::Duration::seconds(30)), 0);
+        assert_eq!(notifier.tick(due + chrono::Duration::seconds(89)), 0);
+        assert_eq!(notifier.tick(due + chrono::Duration::seconds(90)), 1);
+        assert_eq!(notifier.queued(), 0);
+        assert_eq!(*sender.delivered.lock().unwrap(), vec!["ops@example.com"]);
+
+        assert_eq!(retry_delay(1).num_seconds(), 30);
+        assert_eq!(retry_delay(3).num_seconds(), 120);
+        assert_eq!(retry_delay(20).num_seconds(), RETRY_MAX_SECONDS);
+    }
+
+    /// Minimal SMTP server for one session; answers RCPT TO with `rcpt_reply` and
+    /// returns everything the client sent
+    fn mock_smtp_server(rcpt_reply: &'static str) -> (u16, std::thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut transcript = String::new();
+            writer.write_all(b"220 mock ESMTP\r\n").unwrap();
+            let mut in_data = false;
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap_or(0) > 0 {
+                transcript.push_str(&line);
+                let command = line.trim_end().to_ascii_uppercase();
+                let reply = if in_data {
+                    if command == "." {
+                        in_data = false;
+                        "250 2.0.0 queued\r\n"
+                    } else {
+                        ""
+                    }
+                } else if command.starts_with("EHLO") {
+                    "250-mock\r\n250 8BITMIME\r\n"
+                } else if command.starts_with("RCPT TO") {
+                    rcpt_reply
+                } else if command == "DATA" {
+                    in_data = true;
+                    "354 go ahead\r\n"
+                } else if command == "QUIT" {
+                    writer.write_all(b"221 bye\r\n").unwrap();
+                    break;
+                } else {
+                    "250 OK\r\n"
+                };
+                writer.write_all(reply.as_bytes()).unwrap();
+                line.clear();
+            }
+            transcript
+        });
+        (port, handle)
+    }
+
+    fn local_smtp_settings(port: u16) -> NotificationSettings {
+        let mut settings = settings(&["ops@example.com"]);
+        settings.smtp.host = "127.0.0.1".to_string();
+        settings.smtp.port = Some(port);
+        settings.smtp.tls = SmtpTlsMode::None;
+        settings
+    }
+
+    #[test]
+    fn test_send_test_over_smtp() {
+        let (port, server) = mock_smtp_server("250 OK\r\n");
+        let notifier =
+            EmailNotifier::from_settings(&local_smtp_settings(port), Utc::now()).unwrap();
+        assert_eq!(notifier.send_test().unwrap(), vec!["ops@example.com"]);
+        drop(notifier);
+
+        let transcript = server.join().unwrap();
+        assert!(
+            transcript.contains("RCPT TO:<ops@example.com>"),
+            "{}",
+            transcript
+        );
+        assert!(transcript.contains("Subject: [gpukill] Guard Mode test notification"));
+    }
+
+    #[test]
+    fn test_send_test_reports_smtp_rejection() {
+        let (port, server) = mock_smtp_server("550 5.1.1 <ops@example.com>: user unknown\r\n");
+        let notifier =
+            EmailNotifier::from_settings(&local_smtp_settings(port), Utc::now()).unwrap();
+        let err = format!("{:#}", notifier.send_test().unwrap_err());
+        drop(notifier);
+        server.join().unwrap();
+
+        assert!(
+            err.contains(&format!(
+                "Failed to send test notification to ops@example.com via 127.0.0.1:{} (none)",
+                port
+            )),
+            "{}",
+            err
+        );
+        assert!(err.contains("server rejected the message"), "{}", err);
+        assert!(err.contains("550"), "{}", err);
+        assert!(err.contains("user unknown"), "{}", err);
+    }
+
+    #[test]
+    fn test_validate_settings() {
+        assert!(validate_settings(&settings(&["ops@example.com"])).is_ok());
+        assert!(validate_settings(&settings(&[])).is_err());
+        assert!(validate_settings(&settings(&["not an address"])).is_err());
+
+        let mut disabled = settings(&[]);
+        disabled.email = false;
+        assert!(validate_settings(&disabled).is_ok());
+    }
+}
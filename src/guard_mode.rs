@@ -1,12 +1,16 @@
 use anyhow::Result;
-use chrono::{DateTime, Datelike, Utc};
+use chrono::{DateTime, Datelike, FixedOffset, Local, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
-use std::path::PathBuf;
-use tracing::{error, info};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::{error, info, warn};
 
-use crate::nvml_api::GpuProc;
+use crate::cgroup::{CgroupManager, ThrottleLimits, DEFAULT_CGROUP_ROOT, DEFAULT_SLICE};
+use crate::drain::NodeDrain;
+use crate::guard_email::{EmailNotifier, SmtpSettings};
+use crate::nvml_api::{GpuProc, GpuSnapshot};
 
 /// Guard Mode policy configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -23,10 +27,23 @@ pub struct GuardModeConfig {
     pub time_policies: Vec<TimePolicy>,
     /// Enforcement settings
     pub enforcement: EnforcementSettings,
+    /// Processes and users the idle reaper never terminates
+    #[serde(default)]
+    pub idle_whitelist: IdleWhitelist,
     /// Configuration metadata
     pub metadata: ConfigMetadata,
 }
 
+/// Exemptions for `--kill --idle-for`. Process entries match the exact name or a
+/// name prefix, like the rogue detection whitelist.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IdleWhitelist {
+    #[serde(default)]
+    pub users: Vec<String>,
+    #[serde(default)]
+    pub processes: Vec<String>,
+}
+
 /// Global guard mode settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GlobalSettings {
@@ -42,6 +59,9 @@ pub struct GlobalSettings {
     pub check_interval_seconds: u32,
     /// Enable dry-run mode (no actual enforcement)
     pub dry_run: bool,
+    /// Timezone for maintenance windows: "local" (default), "UTC" or a fixed offset such as "+02:00"
+    #[serde(default)]
+    pub maintenance_timezone: Option<String>,
 }
 
 /// User-specific policy
@@ -110,16 +130,17 @@ pub struct GpuPolicy {
     pub maintenance_window: Option<MaintenanceWindow>,
 }
 
-/// Time-based policy
+/// Time-based policy. While active, its multipliers scale every user, group and
+/// GPU limit; a multiplier of 0 disallows GPU use altogether.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TimePolicy {
     /// Policy name
     pub name: String,
     /// Start time (HH:MM format)
     pub start_time: String,
-    /// End time (HH:MM format)
+    /// End time (HH:MM format); an end before the start wraps past midnight
     pub end_time: String,
-    /// Days of week (0=Sunday, 1=Monday, etc.)
+    /// Days of week (0=Sunday, 1=Monday, etc.; empty = every day)
     pub days_of_week: Vec<u8>,
     /// Memory limit multiplier
     pub memory_multiplier: f32,
@@ -127,6 +148,33 @@ pub struct TimePolicy {
     pub utilization_multiplier: f32,
     /// Duration limit multiplier
     pub duration_multiplier: f32,
+    /// Severity of violations for processes running while the policy blocks GPU use
+    #[serde(default = "default_time_policy_severity")]
+    pub severity: ViolationSeverity,
+}
+
+fn default_time_policy_severity() -> ViolationSeverity {
+    ViolationSeverity::High
+}
+
+impl TimePolicy {
+    /// Whether the policy covers `now`, evaluated in the given timezone (see
+    /// `GlobalSettings::maintenance_timezone`)
+    pub fn is_active(&self, now: DateTime<Utc>, timezone: Option<&str>) -> bool {
+        is_window_active_at(
+            window_local_time(now, timezone),
+            &self.start_time,
+            &self.end_time,
+            &self.days_of_week,
+        )
+    }
+
+    /// A multiplier of 0 means GPU use is not allowed while the policy is active
+    pub fn blocks_usage(&self) -> bool {
+        self.memory_multiplier == 0.0
+            || self.utilization_multiplier == 0.0
+            || self.duration_multiplier == 0.0
+    }
 }
 
 /// Time-based override for user policies
@@ -154,14 +202,43 @@ pub struct PolicyOverrides {
 /// Maintenance window
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MaintenanceWindow {
-    /// Start time
+    /// Start time (HH:MM format)
     pub start_time: String,
-    /// End time
+    /// End time (HH:MM format); an end before the start wraps past midnight
     pub end_time: String,
-    /// Days of week
+    /// Days of week the window starts on (0=Sunday, 1=Monday, etc.; empty = every day)
     pub days_of_week: Vec<u8>,
     /// Maintenance message
     pub message: String,
+    /// Severity of violations for processes found on the GPU during the window
+    #[serde(default = "default_maintenance_severity")]
+    pub severity: ViolationSeverity,
+}
+
+fn default_maintenance_severity() -> ViolationSeverity {
+    ViolationSeverity::Critical
+}
+
+impl MaintenanceWindow {
+    /// Whether the window covers `now`, evaluated in the given timezone (see
+    /// `GlobalSettings::maintenance_timezone`)
+    pub fn is_active(&self, now: DateTime<Utc>, timezone: Option<&str>) -> bool {
+        is_window_active_at(
+            window_local_time(now, timezone),
+            &self.start_time,
+            &self.end_time,
+            &self.days_of_week,
+        )
+    }
+}
+
+/// A GPU whose maintenance window is currently open
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveMaintenanceWindow {
+    pub gpu_index: u16,
+    pub start_time: String,
+    pub end_time: String,
+    pub message: String,
 }
 
 /// Enforcement settings
@@ -175,12 +252,109 @@ pub struct EnforcementSettings {
     pub grace_period_seconds: u32,
     /// Maximum warnings before enforcement
     pub max_warnings: u32,
+    /// Maximum process terminations allowed within `termination_window_seconds`
+    /// before hard enforcement falls back to warn-only (0 = no cap)
+    #[serde(default = "default_max_terminations")]
+    pub max_terminations: u32,
+    /// Rolling window for `max_terminations` (seconds)
+    #[serde(default = "default_termination_window_seconds")]
+    pub termination_window_seconds: u64,
+    /// Throttle offenders through cgroups instead of warning or terminating them
+    #[serde(default)]
+    pub throttling: ThrottleSettings,
     /// Notification channels
     pub notifications: NotificationSettings,
 }
 
-/// Notification settings
+/// Host-side throttling of offending processes (Linux, cgroup v2). GPU work itself
+/// cannot be throttled; limiting the CPU and memory of the process slows how fast
+/// it feeds the GPU.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThrottleSettings {
+    /// Throttle violations whose severity has a level below
+    #[serde(default)]
+    pub enabled: bool,
+    /// Mount point of the cgroup v2 hierarchy
+    #[serde(default = "default_cgroup_root")]
+    pub cgroup_root: PathBuf,
+    /// Slice under `cgroup_root` that holds the throttle cgroups
+    #[serde(default = "default_throttle_slice")]
+    pub slice: String,
+    /// Limits per violation severity (`low`, `medium`, `high`, `critical`);
+    /// severities without an entry are enforced as usual
+    #[serde(default = "default_throttle_levels")]
+    pub levels: BTreeMap<String, ThrottleLimits>,
+}
+
+fn default_cgroup_root() -> PathBuf {
+    PathBuf::from(DEFAULT_CGROUP_ROOT)
+}
+
+fn default_throttle_slice() -> String {
+    DEFAULT_SLICE.to_string()
+}
+
+fn default_throttle_levels() -> BTreeMap<String, ThrottleLimits> {
+    BTreeMap::from([
+        (
+            "medium".to_string(),
+            ThrottleLimits {
+                cpu_max_pct: 100,
+                memory_high_mb: None,
+            },
+        ),
+        (
+            "high".to_string(),
+            ThrottleLimits {
+                cpu_max_pct: 50,
+                memory_high_mb: None,
+            },
+        ),
+        (
+            "critical".to_string(),
+            ThrottleLimits {
+                cpu_max_pct: 10,
+                memory_high_mb: None,
+            },
+        ),
+    ])
+}
+
+impl Default for ThrottleSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cgroup_root: default_cgroup_root(),
+            slice: default_throttle_slice(),
+            levels: default_throttle_levels(),
+        }
+    }
+}
+
+impl ThrottleSettings {
+    /// Limits for `severity`, if throttling is enabled and configured for it
+    pub fn limits_for(&self, severity: &ViolationSeverity) -> Option<&ThrottleLimits> {
+        if !self.enabled {
+            return None;
+        }
+        self.levels.get(severity.as_str())
+    }
+
+    pub fn cgroup_manager(&self) -> CgroupManager {
+        CgroupManager::new(&self.cgroup_root, &self.slice)
+    }
+}
+
+fn default_max_terminations() -> u32 {
+    5
+}
+
+fn default_termination_window_seconds() -> u64 {
+    600 // 10 minutes
+}
+
+/// Notification settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct NotificationSettings {
     /// Enable console notifications
     pub console: bool,
@@ -190,6 +364,9 @@ pub struct NotificationSettings {
     pub email: bool,
     /// Email recipients
     pub email_recipients: Vec<String>,
+    /// SMTP server and digest interval for email notifications
+    #[serde(default)]
+    pub smtp: SmtpSettings,
     /// Enable webhook notifications
     pub webhook: bool,
     /// Webhook URL
@@ -254,7 +431,7 @@ pub struct EnforcementAction {
 }
 
 /// Violation types
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ViolationType {
     MemoryLimitExceeded,
     UtilizationLimitExceeded,
@@ -263,10 +440,13 @@ pub enum ViolationType {
     UnauthorizedGpuAccess,
     UnauthorizedUserAccess,
     MaintenanceWindowViolation,
+    TimePolicyViolation,
+    /// Still running on a draining node close to or past its deadline
+    NodeDraining,
 }
 
 /// Violation severity
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum ViolationSeverity {
     Low,
     Medium,
@@ -274,6 +454,18 @@ pub enum ViolationSeverity {
     Critical,
 }
 
+impl ViolationSeverity {
+    /// Lowercase name, as used for throttle level keys
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ViolationSeverity::Low => "low",
+            ViolationSeverity::Medium => "medium",
+            ViolationSeverity::High => "high",
+            ViolationSeverity::Critical => "critical",
+        }
+    }
+}
+
 /// Warning types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum WarningType {
@@ -282,6 +474,8 @@ pub enum WarningType {
     ApproachingDurationLimit,
     HighResourceUsage,
     UnusualActivity,
+    /// Running on a node drained for maintenance
+    NodeDraining,
 }
 
 /// Action types
@@ -305,6 +499,7 @@ impl Default for GlobalSettings {
             default_duration_limit_hours: 12.0,
             check_interval_seconds: 60,
             dry_run: true,
+            maintenance_timezone: None,
         }
     }
 }
@@ -316,6 +511,9 @@ impl Default for EnforcementSettings {
             hard_enforcement: false,
             grace_period_seconds: 300, // 5 minutes
             max_warnings: 3,
+            max_terminations: default_max_terminations(),
+            termination_window_seconds: default_termination_window_seconds(),
+            throttling: ThrottleSettings::default(),
             notifications: NotificationSettings::default(),
         }
     }
@@ -328,6 +526,7 @@ impl Default for NotificationSettings {
             log_file: true,
             email: false,
             email_recipients: Vec::new(),
+            smtp: SmtpSettings::default(),
             webhook: false,
             webhook_url: None,
         }
@@ -351,6 +550,27 @@ pub struct GuardModeManager {
     config: GuardModeConfig,
     violation_history: Vec<PolicyViolation>,
     warning_history: Vec<PolicyWarning>,
+    /// When recent hard-enforcement terminations happened, for the termination cap
+    termination_history: Vec<DateTime<Utc>>,
+    /// Modification time and size of the config file when it was last read
+    config_stamp: Option<(SystemTime, u64)>,
+    /// Processes currently moved into a throttle cgroup, by PID
+    throttled: BTreeMap<u32, ThrottledProcess>,
+    /// Email digest of violations and warnings, set up on the first enforced check
+    email: Option<EmailNotifier>,
+    /// Drain placed on the node whose processes are checked
+    drain: Option<NodeDrain>,
+}
+
+/// A process Guard Mode has throttled and must move back once its violation clears
+#[derive(Debug, Clone)]
+struct ThrottledProcess {
+    process: GpuProc,
+    user: String,
+    policy_name: String,
+    level: String,
+    /// Cgroup the process was in before it was throttled
+    original: PathBuf,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -373,13 +593,118 @@ impl GuardModeManager {
         };
 
         Ok(Self {
+            config_stamp: config_file_stamp(&config_path),
             config_path,
             config,
             violation_history: Vec::new(),
             warning_history: Vec::new(),
+            termination_history: Vec::new(),
+            throttled: BTreeMap::new(),
+            email: None,
+            drain: None,
         })
     }
 
+    /// Create a manager around an already-loaded configuration without touching disk
+    pub fn with_config(config_path: PathBuf, config: GuardModeConfig) -> Self {
+        Self {
+            config_stamp: config_file_stamp(&config_path),
+            config_path,
+            config,
+            violation_history: Vec::new(),
+            warning_history: Vec::new(),
+            termination_history: Vec::new(),
+            throttled: BTreeMap::new(),
+            email: None,
+            drain: None,
+        }
+    }
+
+    /// Check processes as running on a node under `drain`
+    pub fn with_drain(mut self, drain: Option<NodeDrain>) -> Self {
+        self.drain = drain;
+        self
+    }
+
+    /// Set or clear the drain of the node whose processes are checked
+    pub fn set_drain(&mut self, drain: Option<NodeDrain>) {
+        self.drain = drain;
+    }
+
+    /// Reload the configuration if the file changed on disk since it was last read.
+    /// The new configuration replaces the current one only if it parses and
+    /// validates; otherwise the current one stays in effect. Returns the changes
+    /// that were applied, if any.
+    pub fn reload_if_changed(&mut self) -> Option<Vec<String>> {
+        let stamp = config_file_stamp(&self.config_path)?;
+        if self.config_stamp == Some(stamp) {
+            return None;
+        }
+        self.config_stamp = Some(stamp);
+
+        let new_config = match Self::load_config(&self.config_path)
+            .and_then(|config| validate_config(&config).map(|_| config))
+        {
+            Ok(config) => config,
+            Err(e) => {
+                warn!(
+                    path = %self.config_path.display(),
+                    error = %e,
+                    "Ignoring Guard Mode config change; keeping the current configuration"
+                );
+                return None;
+            }
+        };
+
+        let changes = config_changes(&self.config, &new_config);
+        self.config = new_config;
+        if changes.is_empty() {
+            return None;
+        }
+        info!(
+            path = %self.config_path.display(),
+            changes = changes.len(),
+            "Reloaded Guard Mode configuration"
+        );
+        for change in &changes {
+            info!(change = %change, "Guard Mode config changed");
+        }
+        Some(changes)
+    }
+
+    /// Load the saved configuration, if any, without writing a default file
+    pub fn load_existing() -> Result<Option<Self>> {
+        let config_path = Self::get_config_path()?;
+        if !config_path.exists() {
+            return Ok(None);
+        }
+        let config = Self::load_config(&config_path)?;
+        Ok(Some(Self::with_config(config_path, config)))
+    }
+
+    /// GPUs whose maintenance window is open at `now`, ordered by GPU index
+    pub fn active_maintenance_windows(&self, now: DateTime<Utc>) -> Vec<ActiveMaintenanceWindow> {
+        let timezone = self.config.global.maintenance_timezone.as_deref();
+        let mut active: Vec<ActiveMaintenanceWindow> = self
+            .config
+            .gpu_policies
+            .values()
+            .filter_map(|policy| {
+                let window = policy.maintenance_window.as_ref()?;
+                window
+                    .is_active(now, timezone)
+                    .then(|| ActiveMaintenanceWindow {
+                        gpu_index: policy.gpu_index,
+                        start_time: window.start_time.clone(),
+                        end_time: window.end_time.clone(),
+                        message: window.message.clone(),
+                    })
+            })
+            .collect();
+        active.sort_by_key(|w| w.gpu_index);
+        active
+    }
+
     /// Get the configuration file path
     fn get_config_path() -> Result<PathBuf> {
         let mut path = if let Some(config_dir) = dirs::config_dir() {
@@ -403,8 +728,8 @@ impl GuardModeManager {
         let content = fs::read_to_string(path)
             .map_err(|e| anyhow::anyhow!("Failed to read config file: {}", e))?;
 
-        let config: GuardModeConfig = toml::from_str(&content)
-            .map_err(|e| anyhow::anyhow!("Failed to parse config file: {}", e))?;
+        let config: GuardModeConfig = crate::env_expand::from_toml_str(&content)
+            .map_err(|e| anyhow::anyhow!("Failed to parse config file: {:#}", e))?;
 
         info!("Loaded Guard Mode configuration from: {}", path.display());
         Ok(config)
@@ -413,7 +738,7 @@ impl GuardModeManager {
     /// Save configuration to file
     fn save_config(path: &PathBuf, config: &GuardModeConfig) -> Result<()> {
         info!("Serializing config to TOML...");
-        let content = match toml::to_string_pretty(config) {
+        let content = match crate::env_expand::to_toml_string_for(config, path) {
             Ok(content) => {
                 info!("Config serialized successfully, writing to file...");
                 content
@@ -424,7 +749,12 @@ impl GuardModeManager {
             }
         };
 
-        fs::write(path, content)
+        // Write to a sibling file and rename so a running daemon never reads a
+        // half-written config
+        let tmp_path = path.with_extension("toml.tmp");
+        fs::write(&tmp_path, content)
+            .map_err(|e| anyhow::anyhow!("Failed to write config file: {}", e))?;
+        fs::rename(&tmp_path, path)
             .map_err(|e| anyhow::anyhow!("Failed to write config file: {}", e))?;
 
         info!("Saved Guard Mode configuration to: {}", path.display());
@@ -526,8 +856,27 @@ impl GuardModeManager {
         }
     }
 
-    /// Check processes against policies
-    pub fn check_policies(&mut self, processes: &[GpuProc]) -> Result<EnforcementResult> {
+    /// Check processes against policies, picking up any edits to the config file first.
+    /// `gpus` supplies the memory in use on each GPU for GPU policies; GPUs without a
+    /// snapshot are judged by their processes alone.
+    #[allow(dead_code)]
+    pub fn check_policies(
+        &mut self,
+        processes: &[GpuProc],
+        gpus: &[GpuSnapshot],
+    ) -> Result<EnforcementResult> {
+        self.reload_if_changed();
+        self.check_policies_at(processes, gpus, Utc::now())
+    }
+
+    /// Check processes against policies as of `now` (time overrides, time-based
+    /// multipliers and maintenance windows are evaluated at this instant)
+    pub fn check_policies_at(
+        &mut self,
+        processes: &[GpuProc],
+        gpus: &[GpuSnapshot],
+        now: DateTime<Utc>,
+    ) -> Result<EnforcementResult> {
         if !self.config.global.enabled {
             return Ok(EnforcementResult {
                 timestamp: Utc::now(),
@@ -541,7 +890,6 @@ impl GuardModeManager {
         let mut violations = Vec::new();
         let mut warnings = Vec::new();
         let mut actions_taken = Vec::new();
-        let now = Utc::now();
         let time_multipliers = self.get_time_multipliers(now);
 
         // Group processes by user
@@ -563,8 +911,11 @@ impl GuardModeManager {
         }
 
         self.check_group_policies(processes, time_multipliers, &mut violations, &mut warnings);
+        self.check_time_policies(processes, now, &mut violations);
+        self.check_drain(processes, now, &mut violations, &mut warnings);
         self.check_gpu_policies(
             processes,
+            gpus,
             now,
             time_multipliers,
             &mut violations,
@@ -576,20 +927,55 @@ impl GuardModeManager {
             actions_taken = self.simulate_actions(&violations, &warnings);
         } else {
             // In enforcement mode, actually take actions
-            actions_taken = self.execute_actions(&violations, &warnings)?;
+            actions_taken = self.execute_actions(&violations, &warnings, now)?;
         }
 
         // Store violations and warnings in history
         self.violation_history.extend(violations.clone());
         self.warning_history.extend(warnings.clone());
 
-        Ok(EnforcementResult {
+        let result = EnforcementResult {
             timestamp: Utc::now(),
             violations,
             warnings,
             actions_taken,
             dry_run: self.config.global.dry_run,
-        })
+        };
+        if !result.dry_run {
+            self.notify_by_email(&result, now);
+        }
+        Ok(result)
+    }
+
+    /// Queue the check's violations and warnings for the email digest and send
+    /// whatever is due. Mail problems are logged, never returned.
+    fn notify_by_email(&mut self, result: &EnforcementResult, now: DateTime<Utc>) {
+        let settings = &self.config.enforcement.notifications;
+        if !settings.email {
+            self.email = None;
+            return;
+        }
+        if self.email.as_ref().map(|n| n.settings()) != Some(settings) {
+            match EmailNotifier::from_settings(settings, now) {
+                Ok(mut notifier) => {
+                    if let Some(previous) = self.email.take() {
+                        notifier.adopt(previous);
+                    }
+                    self.email = Some(notifier);
+                }
+                Err(e) => warn!(error = %e, "Cannot set up Guard Mode email notifications"),
+            }
+        }
+        if let Some(notifier) = self.email.as_mut() {
+            notifier.record(result, now);
+            notifier.tick(now);
+        }
+    }
+
+    /// Send a test email to the configured recipients (`--test-notification`)
+    pub fn send_test_notification(&self) -> Result<Vec<String>> {
+        EmailNotifier::from_settings(&self.config.enforcement.notifications, Utc::now())?
+            .send_test()
     }
 
     /// Check policies for a specific user
@@ -744,7 +1130,7 @@ impl GuardModeManager {
 
         // Check duration limits per process
         for process in processes {
-            if let Some(duration_hours) = parse_duration_hours(&process.start_time, now) {
+            if let Some(duration_hours) = process_duration_hours(process, now) {
                 if duration_hours > user_policy.duration_limit_hours {
                     violations.push(PolicyViolation {
                         violation_type: ViolationType::DurationLimitExceeded,
@@ -808,27 +1194,148 @@ impl GuardModeManager {
         }
     }
 
+    /// Multipliers of the active time policies; where several overlap, the most
+    /// restrictive multiplier wins. Blocking policies are left out, they are
+    /// enforced by [`Self::check_time_policies`] instead.
     fn get_time_multipliers(&self, now: DateTime<Utc>) -> TimeMultipliers {
-        let mut multipliers = TimeMultipliers {
-            memory: 1.0,
-            utilization: 1.0,
-            duration: 1.0,
+        let timezone = self.config.global.maintenance_timezone.as_deref();
+        self.config
+            .time_policies
+            .iter()
+            .filter(|policy| !policy.blocks_usage() && policy.is_active(now, timezone))
+            .map(|policy| TimeMultipliers {
+                memory: policy.memory_multiplier,
+                utilization: policy.utilization_multiplier,
+                duration: policy.duration_multiplier,
+            })
+            .reduce(|a, b| TimeMultipliers {
+                memory: a.memory.min(b.memory),
+                utilization: a.utilization.min(b.utilization),
+                duration: a.duration.min(b.duration),
+            })
+            .unwrap_or(TimeMultipliers {
+                memory: 1.0,
+                utilization: 1.0,
+                duration: 1.0,
+            })
+    }
+
+    /// Report every process while a time policy with a zero multiplier is active.
+    /// When several blocking policies overlap, the most severe one is reported.
+    fn check_time_policies(
+        &self,
+        processes: &[GpuProc],
+        now: DateTime<Utc>,
+        violations: &mut Vec<PolicyViolation>,
+    ) {
+        let timezone = self.config.global.maintenance_timezone.as_deref();
+        let blocking = self
+            .config
+            .time_policies
+            .iter()
+            .filter(|policy| policy.blocks_usage() && policy.is_active(now, timezone))
+            .max_by(|a, b| a.severity.cmp(&b.severity));
+        let policy = match blocking {
+            Some(policy) => policy,
+            None => return,
         };
 
-        for policy in &self.config.time_policies {
-            if is_time_window_active(
-                now,
-                &policy.start_time,
-                &policy.end_time,
-                &policy.days_of_week,
-            ) {
-                multipliers.memory *= policy.memory_multiplier;
-                multipliers.utilization *= policy.utilization_multiplier;
-                multipliers.duration *= policy.duration_multiplier;
-            }
+        for process in processes {
+            violations.push(PolicyViolation {
+                violation_type: ViolationType::TimePolicyViolation,
+                severity: policy.severity.clone(),
+                user: process.user.clone(),
+                process: process.clone(),
+                policy_name: "time_policy".to_string(),
+                current_value: 1.0,
+                limit_value: 0.0,
+                message: format!(
+                    "GPU use is not allowed during time policy '{}' ({}-{})",
+                    policy.name, policy.start_time, policy.end_time
+                ),
+                recommended_action: "Stop the process and run it outside the blocked hours"
+                    .to_string(),
+            });
         }
+    }
+
+    /// Ask the owners of processes on a draining node to move them. Within
+    /// `DRAIN_ESCALATE_BEFORE` of the deadline they become Medium violations, and
+    /// High once it has passed. Never Critical: killing stragglers is up to the
+    /// coordinator, which does it only for drains placed with `enforce`.
+    fn check_drain(
+        &self,
+        processes: &[GpuProc],
+        now: DateTime<Utc>,
+        violations: &mut Vec<PolicyViolation>,
+        warnings: &mut Vec<PolicyWarning>,
+    ) {
+        let Some(drain) = &self.drain else {
+            return;
+        };
+        let deadline = drain
+            .deadline
+            .map(|deadline| format!(" before {}", deadline.format("%Y-%m-%d %H:%M UTC")))
+            .unwrap_or_default();
+        let reason = drain
+            .reason
+            .as_ref()
+            .map(|reason| format!(" ({})", reason))
+            .unwrap_or_default();
+
+        for process in processes {
+            if !drain.is_urgent(now) {
+                warnings.push(PolicyWarning {
+                    warning_type: WarningType::NodeDraining,
+                    user: process.user.clone(),
+                    process: process.clone(),
+                    policy_name: "node_drain".to_string(),
+                    current_value: 1.0,
+                    limit_value: 0.0,
+                    message: format!(
+                        "This node is draining{}: move PID {} elsewhere{}",
+                        reason, process.pid, deadline
+                    ),
+                    time_to_limit: drain
+                        .remaining(now)
+                        .map(|remaining| remaining.num_seconds().clamp(0, u32::MAX as i64) as u32),
+                });
+                continue;
+            }
 
-        multipliers
+            let (severity, message) = if drain.deadline_passed(now) {
+                (
+                    ViolationSeverity::High,
+                    format!(
+                        "PID {} is still running on this draining node{} past its deadline",
+                        process.pid, reason
+                    ),
+                )
+            } else {
+                (
+                    ViolationSeverity::Medium,
+                    format!(
+                        "PID {} must leave this draining node{}{}",
+                        process.pid, reason, deadline
+                    ),
+                )
+            };
+            violations.push(PolicyViolation {
+                violation_type: ViolationType::NodeDraining,
+                severity,
+                user: process.user.clone(),
+                process: process.clone(),
+                policy_name: "node_drain".to_string(),
+                current_value: 1.0,
+                limit_value: 0.0,
+                message,
+                recommended_action: if drain.enforce {
+                    "Move the job to another node; it will be killed at the deadline".to_string()
+                } else {
+                    "Move the job to another node".to_string()
+                },
+            });
+        }
     }
 
     fn apply_time_overrides(&self, policy: &mut UserPolicy, now: DateTime<Utc>) {
@@ -1013,6 +1520,7 @@ impl GuardModeManager {
     fn check_gpu_policies(
         &self,
         processes: &[GpuProc],
+        gpus: &[GpuSnapshot],
         now: DateTime<Utc>,
         time_multipliers: TimeMultipliers,
         violations: &mut Vec<PolicyViolation>,
@@ -1033,16 +1541,11 @@ impl GuardModeManager {
             };
 
             if let Some(window) = &policy.maintenance_window {
-                if is_time_window_active(
-                    now,
-                    &window.start_time,
-                    &window.end_time,
-                    &window.days_of_week,
-                ) {
+                if window.is_active(now, self.config.global.maintenance_timezone.as_deref()) {
                     for process in gpu_processes {
                         violations.push(PolicyViolation {
                             violation_type: ViolationType::MaintenanceWindowViolation,
-                            severity: ViolationSeverity::Critical,
+                            severity: window.severity.clone(),
                             user: process.user.clone(),
                             process: (*process).clone(),
                             policy_name: "gpu_maintenance_window".to_string(),
@@ -1059,10 +1562,17 @@ impl GuardModeManager {
                 }
             }
 
-            let total_memory = gpu_processes
+            // The device counter also sees memory held by processes we cannot list
+            // (other containers, the driver), so it wins when it reports more
+            let process_memory_mb = gpu_processes
                 .iter()
-                .map(|p| p.used_mem_mb as f32 / 1024.0)
-                .sum::<f32>();
+                .map(|p| p.used_mem_mb as u64)
+                .sum::<u64>();
+            let device_memory_mb = gpus
+                .iter()
+                .find(|gpu| gpu.gpu_index == policy.gpu_index)
+                .map_or(0, |gpu| gpu.mem_used_mb as u64);
+            let total_memory = process_memory_mb.max(device_memory_mb) as f32 / 1024.0;
             let effective_max_memory = (policy.max_memory_gb - policy.reserved_memory_gb).max(0.0)
                 * time_multipliers.memory;
 
@@ -1190,8 +1700,8 @@ impl GuardModeManager {
 
     /// Import configuration from JSON
     pub fn import_from_json(&mut self, json: &str) -> Result<()> {
-        let config: GuardModeConfig = serde_json::from_str(json)
-            .map_err(|e| anyhow::anyhow!("Failed to import config from JSON: {}", e))?;
+        let config: GuardModeConfig = crate::env_expand::from_json_str(json)
+            .map_err(|e| anyhow::anyhow!("Failed to import config from JSON: {:#}", e))?;
 
         self.update_config(config)?;
         Ok(())
@@ -1213,9 +1723,13 @@ impl GuardModeManager {
         violations: &[PolicyViolation],
         warnings: &[PolicyWarning],
     ) -> Vec<EnforcementAction> {
-        let mut actions = Vec::new();
+        let mut actions = self.simulate_throttles(violations);
+        let throttle_targets = self.throttle_targets(violations);
 
         for violation in violations {
+            if throttle_targets.contains_key(&violation.process.pid) {
+                continue;
+            }
             let action = match violation.severity {
                 ViolationSeverity::Critical => EnforcementAction {
                     action_type: ActionType::ProcessTermination,
@@ -1285,16 +1799,34 @@ impl GuardModeManager {
 
     /// Execute actions in enforcement mode
     fn execute_actions(
-        &self,
+        &mut self,
         violations: &[PolicyViolation],
         warnings: &[PolicyWarning],
+        now: DateTime<Utc>,
     ) -> Result<Vec<EnforcementAction>> {
         let mut actions = Vec::new();
+        let max_terminations = self.config.enforcement.max_terminations as usize;
+        let window =
+            chrono::Duration::seconds(self.config.enforcement.termination_window_seconds as i64);
+        self.termination_history
+            .retain(|terminated_at| now.signed_duration_since(*terminated_at) < window);
+        let mut cap_reported = false;
+
+        // Throttled processes are handled by their cgroup instead of the actions below
+        let (throttle_actions, throttled) = self.apply_throttles(violations);
+        actions.extend(throttle_actions);
 
         // Send notifications for warnings
         for warning in warnings {
             if self.config.enforcement.notifications.console {
-                info!("⚠️ Policy Warning: {} - {}", warning.user, warning.message);
+                info!(
+                    user = %warning.user,
+                    pid = warning.process.pid,
+                    gpu_index = warning.process.gpu_index,
+                    policy = %warning.policy_name,
+                    message = %warning.message,
+                    "Policy warning"
+                );
             }
 
             actions.push(EnforcementAction {
@@ -1309,14 +1841,47 @@ impl GuardModeManager {
 
         // Handle violations based on enforcement settings
         for violation in violations {
+            if throttled.contains(&violation.process.pid) {
+                continue;
+            }
             let action = match violation.severity {
                 ViolationSeverity::Critical => {
-                    if self.config.enforcement.hard_enforcement {
+                    let cap_reached =
+                        max_terminations > 0 && self.termination_history.len() >= max_terminations;
+                    if self.config.enforcement.hard_enforcement && cap_reached {
+                        if !cap_reported {
+                            error!(
+                                terminations = self.termination_history.len(),
+                                window_seconds = self.config.enforcement.termination_window_seconds,
+                                "Guard Mode termination cap reached; hard enforcement is falling back to warn-only"
+                            );
+                            cap_reported = true;
+                        }
+
+                        EnforcementAction {
+                            action_type: ActionType::Warning,
+                            user: violation.user.clone(),
+                            process: violation.process.clone(),
+                            policy_name: violation.policy_name.clone(),
+                            message: format!(
+                                "Critical violation detected (termination cap of {} per {}s reached, warn-only): {}",
+                                max_terminations,
+                                self.config.enforcement.termination_window_seconds,
+                                violation.message
+                            ),
+                            success: true,
+                        }
+                    } else if self.config.enforcement.hard_enforcement {
+                        self.termination_history.push(now);
                         // In a real implementation, this would actually terminate the process
                         // For now, we'll just log it
                         info!(
-                            "🚨 CRITICAL VIOLATION: Would terminate process {} for user {}: {}",
-                            violation.process.pid, violation.user, violation.message
+                            user = %violation.user,
+                            pid = violation.process.pid,
+                            gpu_index = violation.process.gpu_index,
+                            policy = %violation.policy_name,
+                            message = %violation.message,
+                            "Critical violation: would terminate process"
                         );
 
                         EnforcementAction {
@@ -1347,8 +1912,12 @@ impl GuardModeManager {
                 ViolationSeverity::High | ViolationSeverity::Medium => {
                     if self.config.enforcement.soft_enforcement {
                         info!(
-                            "⚠️ Policy Violation: {} - {}",
-                            violation.user, violation.message
+                            user = %violation.user,
+                            pid = violation.process.pid,
+                            gpu_index = violation.process.gpu_index,
+                            policy = %violation.policy_name,
+                            message = %violation.message,
+                            "Policy violation"
                         );
 
                         EnforcementAction {
@@ -1388,44 +1957,556 @@ impl GuardModeManager {
         Ok(actions)
     }
 
-    /// Run policy check simulation (dry-run mode)
-    pub fn simulate_policy_check(&mut self, processes: &[GpuProc]) -> Result<EnforcementResult> {
-        let original_dry_run = self.config.global.dry_run;
-        self.config.global.dry_run = true;
+    /// Highest-severity violation of each process whose severity has a throttle level
+    fn throttle_targets<'a>(
+        &self,
+        violations: &'a [PolicyViolation],
+    ) -> BTreeMap<u32, (&'a PolicyViolation, ThrottleLimits)> {
+        let throttling = &self.config.enforcement.throttling;
+        let mut targets: BTreeMap<u32, (&PolicyViolation, ThrottleLimits)> = BTreeMap::new();
+        for violation in violations {
+            let Some(limits) = throttling.limits_for(&violation.severity) else {
+                continue;
+            };
+            let replace = targets
+                .get(&violation.process.pid)
+                .map(|(current, _)| violation.severity > current.severity)
+                .unwrap_or(true);
+            if replace {
+                targets.insert(violation.process.pid, (violation, *limits));
+            }
+        }
+        targets
+    }
 
-        let result = self.check_policies(processes)?;
+    /// Dry-run actions listing the cgroup operations throttling would perform
+    fn simulate_throttles(&self, violations: &[PolicyViolation]) -> Vec<EnforcementAction> {
+        let cgroups = self.config.enforcement.throttling.cgroup_manager();
+        let targets = self.throttle_targets(violations);
+        let access_error = if targets.is_empty() {
+            None
+        } else {
+            cgroups.check_access().err()
+        };
+        let mut actions = Vec::new();
 
-        // Restore original setting
-        self.config.global.dry_run = original_dry_run;
+        for (pid, throttled) in &self.throttled {
+            if targets.contains_key(pid) {
+                continue;
+            }
+            actions.push(EnforcementAction {
+                action_type: ActionType::ResourceThrottling,
+                user: throttled.user.clone(),
+                process: throttled.process.clone(),
+                policy_name: throttled.policy_name.clone(),
+                message: format!(
+                    "[DRY-RUN] Would release process {} from throttling: {}",
+                    pid,
+                    describe_ops(&cgroups.plan_release(*pid, &throttled.original))
+                ),
+                success: true,
+            });
+        }
 
-        Ok(result)
+        for (pid, (violation, limits)) in &targets {
+            let level = violation.severity.as_str();
+            let mut message = format!(
+                "[DRY-RUN] Would throttle process {} at {} level ({}) for violation: {}",
+                pid,
+                level,
+                describe_ops(&cgroups.plan_throttle(*pid, level, limits)),
+                violation.message
+            );
+            if let Some(e) = &access_error {
+                message.push_str(&format!(" [would fail: {}]", e));
+            }
+            actions.push(EnforcementAction {
+                action_type: ActionType::ResourceThrottling,
+                user: violation.user.clone(),
+                process: violation.process.clone(),
+                policy_name: violation.policy_name.clone(),
+                message,
+                success: access_error.is_none(),
+            });
+        }
+        actions
     }
 
-    /// Toggle dry-run mode
-    pub fn toggle_dry_run(&mut self) -> Result<bool> {
-        self.config.global.dry_run = !self.config.global.dry_run;
-        self.config.metadata.last_modified = chrono::Utc::now().to_rfc3339();
-        Self::save_config(&self.config_path, &self.config)?;
-        Ok(self.config.global.dry_run)
-    }
-}
+    /// Move processes with a throttled violation into their level's cgroup and move
+    /// back those whose violation cleared. Returns the actions and the PIDs now
+    /// throttled; violations of other processes (including ones that could not be
+    /// throttled) are enforced as usual.
+    fn apply_throttles(
+        &mut self,
+        violations: &[PolicyViolation],
+    ) -> (Vec<EnforcementAction>, BTreeSet<u32>) {
+        let cgroups = self.config.enforcement.throttling.cgroup_manager();
+        let targets = self.throttle_targets(violations);
+        let mut actions = Vec::new();
+        let mut handled = BTreeSet::new();
+        let mut moved = false;
+
+        let cleared: Vec<u32> = self
+            .throttled
+            .keys()
+            .filter(|pid| !targets.contains_key(pid))
+            .copied()
+            .collect();
+        for pid in cleared {
+            if let Some(throttled) = self.throttled.remove(&pid) {
+                actions.push(release_throttle(
+                    &cgroups,
+                    pid,
+                    throttled,
+                    "violation cleared",
+                ));
+                moved = true;
+            }
+        }
 
-fn is_time_window_active(
-    now: DateTime<Utc>,
-    start_time: &str,
-    end_time: &str,
-    days_of_week: &[u8],
-) -> bool {
-    let (start, end) = match (
-        chrono::NaiveTime::parse_from_str(start_time, "%H:%M"),
-        chrono::NaiveTime::parse_from_str(end_time, "%H:%M"),
-    ) {
-        (Ok(s), Ok(e)) => (s, e),
-        _ => return false,
-    };
+        if !targets.is_empty() {
+            if let Err(e) = cgroups.check_access() {
+                error!(error = %e, "Guard Mode throttling unavailable; enforcing violations as usual");
+                for (pid, (violation, _)) in &targets {
+                    actions.push(EnforcementAction {
+                        action_type: ActionType::ResourceThrottling,
+                        user: violation.user.clone(),
+                        process: violation.process.clone(),
+                        policy_name: violation.policy_name.clone(),
+                        message: format!("Failed to throttle process {}: {}", pid, e),
+                        success: false,
+                    });
+                }
+                return (actions, handled);
+            }
+        }
 
-    let weekday = now.weekday().num_days_from_sunday() as u8;
-    let now_time = now.time();
+        for (pid, (violation, limits)) in targets {
+            let level = violation.severity.as_str();
+            let existing = self.throttled.get(&pid);
+            if existing.is_some_and(|t| t.level == level) {
+                handled.insert(pid);
+                actions.push(EnforcementAction {
+                    action_type: ActionType::ResourceThrottling,
+                    user: violation.user.clone(),
+                    process: violation.process.clone(),
+                    policy_name: violation.policy_name.clone(),
+                    message: format!(
+                        "Process {} remains throttled at {} level: {}",
+                        pid, level, violation.message
+                    ),
+                    success: true,
+                });
+                continue;
+            }
+
+            let original = match existing {
+                Some(throttled) => Ok(throttled.original.clone()),
+                None => cgroups.current_cgroup(pid),
+            };
+            let result = original.and_then(|original| {
+                cgroups.apply(&cgroups.plan_throttle(pid, level, &limits))?;
+                Ok(original)
+            });
+            match result {
+                Ok(original) => {
+                    info!(
+                        user = %violation.user,
+                        pid,
+                        level,
+                        cpu_max = %limits.cpu_max(),
+                        memory_high = %limits.memory_high(),
+                        "Process throttled"
+                    );
+                    moved |= existing.is_some();
+                    self.throttled.insert(
+                        pid,
+                        ThrottledProcess {
+                            process: violation.process.clone(),
+                            user: violation.user.clone(),
+                            policy_name: violation.policy_name.clone(),
+                            level: level.to_string(),
+                            original,
+                        },
+                    );
+                    handled.insert(pid);
+                    actions.push(EnforcementAction {
+                        action_type: ActionType::ResourceThrottling,
+                        user: violation.user.clone(),
+                        process: violation.process.clone(),
+                        policy_name: violation.policy_name.clone(),
+                        message: format!(
+                            "Process {} throttled at {} level (cpu.max {}, memory.high {}): {}",
+                            pid,
+                            level,
+                            limits.cpu_max(),
+                            limits.memory_high(),
+                            violation.message
+                        ),
+                        success: true,
+                    });
+                }
+                Err(e) => {
+                    warn!(pid, error = %e, "Failed to throttle process; enforcing its violation as usual");
+                    actions.push(EnforcementAction {
+                        action_type: ActionType::ResourceThrottling,
+                        user: violation.user.clone(),
+                        process: violation.process.clone(),
+                        policy_name: violation.policy_name.clone(),
+                        message: format!("Failed to throttle process {}: {:#}", pid, e),
+                        success: false,
+                    });
+                }
+            }
+        }
+
+        if moved {
+            remove_empty_throttle_cgroups(&cgroups);
+        }
+        (actions, handled)
+    }
+
+    /// Move every throttled process back to its original cgroup and remove the
+    /// throttle cgroups; call when Guard Mode stops enforcing
+    pub fn release_throttles(&mut self) -> Vec<EnforcementAction> {
+        if self.throttled.is_empty() {
+            return Vec::new();
+        }
+        let cgroups = self.config.enforcement.throttling.cgroup_manager();
+        let actions = std::mem::take(&mut self.throttled)
+            .into_iter()
+            .map(|(pid, throttled)| {
+                release_throttle(&cgroups, pid, throttled, "Guard Mode stopped")
+            })
+            .collect();
+        remove_empty_throttle_cgroups(&cgroups);
+        actions
+    }
+
+    /// PIDs currently held in a throttle cgroup
+    #[allow(dead_code)]
+    pub fn throttled_pids(&self) -> Vec<u32> {
+        self.throttled.keys().copied().collect()
+    }
+
+    /// Run policy check simulation (dry-run mode)
+    pub fn simulate_policy_check(
+        &mut self,
+        processes: &[GpuProc],
+        gpus: &[GpuSnapshot],
+    ) -> Result<EnforcementResult> {
+        // Reload before forcing dry-run so a reload cannot switch it back off
+        self.reload_if_changed();
+        let original_dry_run = self.config.global.dry_run;
+        self.config.global.dry_run = true;
+
+        let result = self.check_policies_at(processes, gpus, Utc::now());
+        self.config.global.dry_run = original_dry_run;
+        let result = result?;
+
+        Ok(result)
+    }
+
+    /// Toggle dry-run mode
+    pub fn toggle_dry_run(&mut self) -> Result<bool> {
+        self.config.global.dry_run = !self.config.global.dry_run;
+        self.config.metadata.last_modified = chrono::Utc::now().to_rfc3339();
+        Self::save_config(&self.config_path, &self.config)?;
+        Ok(self.config.global.dry_run)
+    }
+}
+
+impl Drop for GuardModeManager {
+    fn drop(&mut self) {
+        // Never leave processes throttled after Guard Mode goes away
+        self.release_throttles();
+    }
+}
+
+/// Move a throttled process back to the cgroup it came from
+fn release_throttle(
+    cgroups: &CgroupManager,
+    pid: u32,
+    throttled: ThrottledProcess,
+    reason: &str,
+) -> EnforcementAction {
+    let result = if cgroups.process_exists(pid) {
+        cgroups.apply(&cgroups.plan_release(pid, &throttled.original))
+    } else {
+        Ok(())
+    };
+    let (message, success) = match result {
+        Ok(()) => {
+            info!(pid, reason, "Process released from throttling");
+            (
+                format!(
+                    "Process {} released from throttling ({}), back in {}",
+                    pid,
+                    reason,
+                    throttled.original.display()
+                ),
+                true,
+            )
+        }
+        Err(e) => {
+            warn!(pid, error = %e, "Failed to release throttled process");
+            (
+                format!("Failed to release process {} from throttling: {:#}", pid, e),
+                false,
+            )
+        }
+    };
+    EnforcementAction {
+        action_type: ActionType::ResourceThrottling,
+        user: throttled.user,
+        process: throttled.process,
+        policy_name: throttled.policy_name,
+        message,
+        success,
+    }
+}
+
+/// Remove throttle cgroups that no longer hold processes
+fn remove_empty_throttle_cgroups(cgroups: &CgroupManager) {
+    if let Err(e) = cgroups.apply(&cgroups.plan_cleanup()) {
+        warn!(error = %e, "Failed to remove empty throttle cgroups");
+    }
+}
+
+/// Cgroup operations joined for an action message
+fn describe_ops(ops: &[crate::cgroup::CgroupOp]) -> String {
+    ops.iter()
+        .map(|op| op.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Reject configurations that would make enforcement misbehave
+pub fn validate_config(config: &GuardModeConfig) -> Result<()> {
+    fn check_limits(scope: &str, memory_gb: f32, utilization_pct: f32) -> Result<()> {
+        if !memory_gb.is_finite() || memory_gb < 0.0 {
+            anyhow::bail!("{}: memory limit must be a non-negative number", scope);
+        }
+        if !(0.0..=100.0).contains(&utilization_pct) {
+            anyhow::bail!("{}: utilization limit must be between 0 and 100", scope);
+        }
+        Ok(())
+    }
+
+    let global = &config.global;
+    check_limits(
+        "global",
+        global.default_memory_limit_gb,
+        global.default_utilization_limit_pct,
+    )?;
+    if global.check_interval_seconds == 0 {
+        anyhow::bail!("global: check_interval_seconds must be greater than 0");
+    }
+    if let Some(tz) = global.maintenance_timezone.as_deref().map(str::trim) {
+        let known = tz.is_empty()
+            || tz.eq_ignore_ascii_case("local")
+            || tz.eq_ignore_ascii_case("utc")
+            || tz == "Z"
+            || tz.parse::<FixedOffset>().is_ok();
+        if !known {
+            anyhow::bail!(
+                "global: unsupported maintenance_timezone '{}' (expected local, UTC or +HH:MM)",
+                tz
+            );
+        }
+    }
+
+    for (name, policy) in &config.user_policies {
+        check_limits(
+            &format!("user policy '{}'", name),
+            policy.memory_limit_gb,
+            policy.utilization_limit_pct,
+        )?;
+    }
+    for (name, policy) in &config.group_policies {
+        check_limits(
+            &format!("group policy '{}'", name),
+            policy.total_memory_limit_gb,
+            policy.total_utilization_limit_pct,
+        )?;
+    }
+    for policy in config.gpu_policies.values() {
+        let scope = format!("GPU policy {}", policy.gpu_index);
+        check_limits(&scope, policy.max_memory_gb, policy.max_utilization_pct)?;
+        if let Some(window) = &policy.maintenance_window {
+            for time in [&window.start_time, &window.end_time] {
+                if chrono::NaiveTime::parse_from_str(time, "%H:%M").is_err() {
+                    anyhow::bail!(
+                        "{}: invalid maintenance time '{}' (expected HH:MM)",
+                        scope,
+                        time
+                    );
+                }
+            }
+            if window.days_of_week.iter().any(|&day| day > 6) {
+                anyhow::bail!(
+                    "{}: maintenance days must be between 0 (Sunday) and 6",
+                    scope
+                );
+            }
+        }
+    }
+
+    for policy in &config.time_policies {
+        let scope = format!("time policy '{}'", policy.name);
+        for time in [&policy.start_time, &policy.end_time] {
+            if chrono::NaiveTime::parse_from_str(time, "%H:%M").is_err() {
+                anyhow::bail!("{}: invalid time '{}' (expected HH:MM)", scope, time);
+            }
+        }
+        if policy.days_of_week.iter().any(|&day| day > 6) {
+            anyhow::bail!("{}: days must be between 0 (Sunday) and 6", scope);
+        }
+        for multiplier in [
+            policy.memory_multiplier,
+            policy.utilization_multiplier,
+            policy.duration_multiplier,
+        ] {
+            if !multiplier.is_finite() || multiplier < 0.0 {
+                anyhow::bail!("{}: multipliers must be non-negative numbers", scope);
+            }
+        }
+    }
+
+    let throttling = &config.enforcement.throttling;
+    for (level, limits) in &throttling.levels {
+        if !["low", "medium", "high", "critical"].contains(&level.as_str()) {
+            anyhow::bail!(
+                "enforcement.throttling: unknown level '{}' (expected low, medium, high or critical)",
+                level
+            );
+        }
+        if limits.cpu_max_pct == 0 {
+            anyhow::bail!(
+                "enforcement.throttling.levels.{}: cpu_max_pct must be greater than 0",
+                level
+            );
+        }
+    }
+    if throttling.slice.is_empty() || throttling.slice.contains('/') {
+        anyhow::bail!(
+            "enforcement.throttling: slice must be a single directory name, got '{}'",
+            throttling.slice
+        );
+    }
+    crate::guard_email::validate_settings(&config.enforcement.notifications)?;
+    Ok(())
+}
+
+/// Human-readable differences between two configurations, one entry per changed
+/// setting (e.g. `user_policies.alice.memory_limit_gb: 16 -> 8`). Metadata is ignored.
+pub fn config_changes(old: &GuardModeConfig, new: &GuardModeConfig) -> Vec<String> {
+    let (Ok(mut old), Ok(mut new)) = (serde_json::to_value(old), serde_json::to_value(new)) else {
+        return Vec::new();
+    };
+    for value in [&mut old, &mut new] {
+        if let Some(object) = value.as_object_mut() {
+            object.remove("metadata");
+        }
+    }
+    let mut changes = Vec::new();
+    diff_config_values("", &old, &new, &mut changes);
+    changes
+}
+
+fn diff_config_values(
+    path: &str,
+    old: &serde_json::Value,
+    new: &serde_json::Value,
+    changes: &mut Vec<String>,
+) {
+    use serde_json::Value;
+
+    if let (Value::Object(old), Value::Object(new)) = (old, new) {
+        let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+        for key in keys {
+            let child = if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", path, key)
+            };
+            match (old.get(key), new.get(key)) {
+                (Some(a), Some(b)) => diff_config_values(&child, a, b, changes),
+                (None, Some(_)) => changes.push(format!("{} added", child)),
+                (Some(_), None) => changes.push(format!("{} removed", child)),
+                (None, None) => {}
+            }
+        }
+    } else if old != new {
+        changes.push(format!(
+            "{}: {} -> {}",
+            path,
+            display_config_value(old),
+            display_config_value(new)
+        ));
+    }
+}
+
+fn display_config_value(value: &serde_json::Value) -> String {
+    match value {
+        // Limits are f32 in the config; print them without f64 widening noise
+        serde_json::Value::Number(n) if n.is_f64() => {
+            (n.as_f64().unwrap_or_default() as f32).to_string()
+        }
+        other => other.to_string(),
+    }
+}
+
+/// Modification time and size of the config file, used to notice edits
+fn config_file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+fn is_time_window_active(
+    now: DateTime<Utc>,
+    start_time: &str,
+    end_time: &str,
+    days_of_week: &[u8],
+) -> bool {
+    is_window_active_at(now.naive_utc(), start_time, end_time, days_of_week)
+}
+
+/// Wall-clock time used for maintenance windows: host local time unless overridden
+fn window_local_time(now: DateTime<Utc>, timezone: Option<&str>) -> NaiveDateTime {
+    match timezone.map(str::trim) {
+        None | Some("") => now.with_timezone(&Local).naive_local(),
+        Some(tz) if tz.eq_ignore_ascii_case("local") => now.with_timezone(&Local).naive_local(),
+        Some(tz) if tz.eq_ignore_ascii_case("utc") || tz == "Z" => now.naive_utc(),
+        Some(tz) => match tz.parse::<FixedOffset>() {
+            Ok(offset) => now.with_timezone(&offset).naive_local(),
+            Err(_) => {
+                warn!(
+                    "Unsupported maintenance_timezone '{}' (expected local, UTC or +HH:MM); using local time",
+                    tz
+                );
+                now.with_timezone(&Local).naive_local()
+            }
+        },
+    }
+}
+
+fn is_window_active_at(
+    now: NaiveDateTime,
+    start_time: &str,
+    end_time: &str,
+    days_of_week: &[u8],
+) -> bool {
+    let (start, end) = match (
+        chrono::NaiveTime::parse_from_str(start_time, "%H:%M"),
+        chrono::NaiveTime::parse_from_str(end_time, "%H:%M"),
+    ) {
+        (Ok(s), Ok(e)) => (s, e),
+        _ => return false,
+    };
+
+    let weekday = now.weekday().num_days_from_sunday() as u8;
+    let now_time = now.time();
 
     if start <= end {
         // Same-day window: current day must be in list and time in [start, end]
@@ -1444,6 +2525,18 @@ fn is_time_window_active(
     }
 }
 
+/// How long a process has been running, in hours: from its start timestamp when
+/// the backend reported one, otherwise parsed from its `start_time` text
+fn process_duration_hours(process: &GpuProc, now: DateTime<Utc>) -> Option<f32> {
+    if let Some(started) = process
+        .start_unix_secs
+        .and_then(|secs| DateTime::from_timestamp(secs as i64, 0))
+    {
+        return Some(now.signed_duration_since(started).num_seconds().max(0) as f32 / 3600.0);
+    }
+    parse_duration_hours(&process.start_time, now)
+}
+
 fn parse_duration_hours(start_time: &str, now: DateTime<Utc>) -> Option<f32> {
     if start_time == "unknown" {
         return None;
@@ -1485,6 +2578,7 @@ fn parse_duration_hours(start_time: &str, now: DateTime<Utc>) -> Option<f32> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::nvml_api::fixtures::{gpu, proc};
 
     #[test]
     fn test_default_config() {
@@ -1502,6 +2596,11 @@ mod tests {
             config,
             violation_history: Vec::new(),
             warning_history: Vec::new(),
+            termination_history: Vec::new(),
+            config_stamp: None,
+            throttled: BTreeMap::new(),
+            email: None,
+            drain: None,
         };
 
         let user_policy = manager.get_user_policy("testuser");
@@ -1532,26 +2631,148 @@ mod tests {
             config,
             violation_history: Vec::new(),
             warning_history: Vec::new(),
+            termination_history: Vec::new(),
+            config_stamp: None,
+            throttled: BTreeMap::new(),
+            email: None,
+            drain: None,
         };
 
-        let processes = vec![GpuProc {
-            gpu_index: 0,
-            pid: 1234,
-            user: "testuser".to_string(),
-            proc_name: "test_proc".to_string(),
-            used_mem_mb: 512,
-            start_time: "unknown".to_string(),
-            container: None,
-            node_id: None,
-        }];
-
-        let result = manager.check_policies(&processes).unwrap();
+        let processes = vec![proc(0, 1234, "testuser", "test_proc", 512)];
+
+        let result = manager.check_policies(&processes, &[]).unwrap();
         assert!(result
             .violations
             .iter()
             .any(|v| matches!(v.violation_type, ViolationType::UnauthorizedUserAccess)));
     }
 
+    fn gpu_policy(max_memory_gb: f32, reserved_memory_gb: f32) -> GuardModeManager {
+        let mut config = GuardModeConfig::default();
+        config.global.enabled = true;
+        config.gpu_policies.insert(
+            "0".to_string(),
+            GpuPolicy {
+                gpu_index: 0,
+                max_memory_gb,
+                max_utilization_pct: 100.0,
+                reserved_memory_gb,
+                allowed_users: vec!["alice".to_string(), "bob".to_string()],
+                blocked_users: vec!["bob".to_string()],
+                maintenance_window: None,
+            },
+        );
+        GuardModeManager::with_config(PathBuf::new(), config)
+    }
+
+    fn gpu_memory_violations(result: &EnforcementResult) -> Vec<f32> {
+        result
+            .violations
+            .iter()
+            .filter(|v| v.policy_name == "gpu_memory_limit")
+            .map(|v| v.current_value)
+            .collect()
+    }
+
+    #[test]
+    fn test_gpu_policy_memory_limit_subtracts_reserved_memory() {
+        // 10 GB with 2 GB reserved leaves 8 GB (8192 MB) for processes
+        let mut manager = gpu_policy(10.0, 2.0);
+
+        let at_limit = manager
+            .check_policies(&[proc(0, 1, "alice", "train", 8192)], &[gpu(0, 50.0, 8192)])
+            .unwrap();
+        assert!(gpu_memory_violations(&at_limit).is_empty());
+        assert!(at_limit
+            .warnings
+            .iter()
+            .any(|w| w.policy_name == "gpu_memory_limit"));
+
+        let over_limit = manager
+            .check_policies(&[proc(0, 1, "alice", "train", 8193)], &[gpu(0, 50.0, 8193)])
+            .unwrap();
+        assert_eq!(gpu_memory_violations(&over_limit).len(), 1);
+        assert_eq!(over_limit.violations[0].limit_value, 8.0);
+
+        // Without the reservation the same usage fits
+        let mut manager = gpu_policy(10.0, 0.0);
+        let result = manager
+            .check_policies(&[proc(0, 1, "alice", "train", 8193)], &[gpu(0, 50.0, 8193)])
+            .unwrap();
+        assert!(gpu_memory_violations(&result).is_empty());
+    }
+
+    #[test]
+    fn test_gpu_policy_reserving_everything_leaves_no_memory() {
+        let mut manager = gpu_policy(8.0, 12.0);
+        let result = manager
+            .check_policies(&[proc(0, 1, "alice", "train", 1)], &[gpu(0, 50.0, 1)])
+            .unwrap();
+        let violation = result
+            .violations
+            .iter()
+            .find(|v| v.policy_name == "gpu_memory_limit")
+            .unwrap();
+        assert_eq!(violation.limit_value, 0.0);
+    }
+
+    #[test]
+    fn test_gpu_policy_counts_memory_only_the_device_reports() {
+        let mut manager = gpu_policy(10.0, 2.0);
+
+        // The processes we can see fit, but the GPU reports 9 GB in use
+        let processes = [
+            proc(0, 1, "alice", "train", 2048),
+            proc(0, 2, "alice", "train", 2048),
+        ];
+        let result = manager
+            .check_policies(&processes, &[gpu(0, 50.0, 9216)])
+            .unwrap();
+        assert_eq!(gpu_memory_violations(&result), vec![9.0]);
+
+        // A snapshot for another GPU, or none, falls back to the processes
+        let mut other = gpu(0, 50.0, 9216);
+        other.gpu_index = 1;
+        let result = manager.check_policies(&processes, &[other]).unwrap();
+        assert!(gpu_memory_violations(&result).is_empty());
+        let result = manager.check_policies(&processes, &[]).unwrap();
+        assert!(gpu_memory_violations(&result).is_empty());
+
+        // A stale snapshot below the processes' total does not hide them
+        let result = manager
+            .check_policies(&[proc(0, 1, "alice", "train", 9216)], &[gpu(0, 50.0, 1024)])
+            .unwrap();
+        assert_eq!(gpu_memory_violations(&result), vec![9.0]);
+    }
+
+    #[test]
+    fn test_gpu_policy_allowed_and_blocked_users() {
+        let mut manager = gpu_policy(80.0, 0.0);
+        let processes = [
+            proc(0, 1, "alice", "train", 1024),
+            proc(0, 2, "bob", "train", 1024),
+            proc(0, 3, "mallory", "train", 1024),
+        ];
+        let result = manager
+            .check_policies(&processes, &[gpu(0, 50.0, 3072)])
+            .unwrap();
+        let mut unauthorized: Vec<(&str, &str)> = result
+            .violations
+            .iter()
+            .filter(|v| matches!(v.violation_type, ViolationType::UnauthorizedUserAccess))
+            .map(|v| (v.user.as_str(), v.policy_name.as_str()))
+            .collect();
+        unauthorized.sort();
+        // Being allowed does not override a block
+        assert_eq!(
+            unauthorized,
+            vec![
+                ("bob", "gpu_blocked_users"),
+                ("mallory", "gpu_allowed_users")
+            ]
+        );
+    }
+
     #[test]
     fn test_midnight_crossing_day_match_bug() {
         use chrono::TimeZone;
@@ -1574,4 +2795,716 @@ mod tests {
             "Should be active on Saturday morning (Friday window)"
         );
     }
+
+    fn maintenance_window(start: &str, end: &str, days: Vec<u8>) -> MaintenanceWindow {
+        MaintenanceWindow {
+            start_time: start.to_string(),
+            end_time: end.to_string(),
+            days_of_week: days,
+            message: "scheduled maintenance".to_string(),
+            severity: ViolationSeverity::Critical,
+        }
+    }
+
+    #[test]
+    fn test_maintenance_window_wraps_past_midnight() {
+        use chrono::TimeZone;
+        // Friday night window in UTC+02:00
+        let window = maintenance_window("22:00", "02:00", vec![5]);
+        let tz = Some("+02:00");
+
+        // Friday 23:00 local
+        let fri_late = Utc.with_ymd_and_hms(2026, 2, 20, 21, 0, 0).unwrap();
+        assert!(window.is_active(fri_late, tz));
+        // Saturday 01:30 local, continuation of the Friday window
+        let sat_early = Utc.with_ymd_and_hms(2026, 2, 20, 23, 30, 0).unwrap();
+        assert!(window.is_active(sat_early, tz));
+        // Friday 21:00 local, before the window opens
+        let fri_evening = Utc.with_ymd_and_hms(2026, 2, 20, 19, 0, 0).unwrap();
+        assert!(!window.is_active(fri_evening, tz));
+        // Saturday 23:00 local opens no window: Saturday is not listed
+        let sat_late = Utc.with_ymd_and_hms(2026, 2, 21, 21, 0, 0).unwrap();
+        assert!(!window.is_active(sat_late, tz));
+        // The same instant evaluated in UTC is Friday 21:00, outside the window
+        assert!(!window.is_active(fri_late, Some("UTC")));
+    }
+
+    #[test]
+    fn test_maintenance_window_multi_day() {
+        use chrono::TimeZone;
+        // Weekday business hours
+        let weekdays = maintenance_window("09:00", "17:00", vec![1, 2, 3, 4, 5]);
+        let mon = Utc.with_ymd_and_hms(2026, 2, 16, 10, 0, 0).unwrap();
+        let fri = Utc.with_ymd_and_hms(2026, 2, 20, 16, 59, 0).unwrap();
+        let sat = Utc.with_ymd_and_hms(2026, 2, 21, 10, 0, 0).unwrap();
+        let wed_night = Utc.with_ymd_and_hms(2026, 2, 18, 20, 0, 0).unwrap();
+        assert!(weekdays.is_active(mon, Some("UTC")));
+        assert!(weekdays.is_active(fri, Some("UTC")));
+        assert!(!weekdays.is_active(sat, Some("UTC")));
+        assert!(!weekdays.is_active(wed_night, Some("UTC")));
+
+        // Weekend nights wrapping past midnight: Fri and Sat starts
+        let weekend_nights = maintenance_window("23:00", "01:00", vec![5, 6]);
+        let sun_early = Utc.with_ymd_and_hms(2026, 2, 22, 0, 30, 0).unwrap();
+        let mon_early = Utc.with_ymd_and_hms(2026, 2, 23, 0, 30, 0).unwrap();
+        let sun_late = Utc.with_ymd_and_hms(2026, 2, 22, 23, 30, 0).unwrap();
+        assert!(weekend_nights.is_active(sun_early, Some("UTC")));
+        assert!(!weekend_nights.is_active(mon_early, Some("UTC")));
+        assert!(!weekend_nights.is_active(sun_late, Some("UTC")));
+    }
+
+    #[test]
+    fn test_maintenance_window_violations_and_active_query() {
+        let mut config = GuardModeConfig::default();
+        config.global.enabled = true;
+        config.global.maintenance_timezone = Some("UTC".to_string());
+        let mut window = maintenance_window("00:00", "23:59", Vec::new());
+        window.message = "driver upgrade".to_string();
+        window.severity = ViolationSeverity::Medium;
+        config.gpu_policies.insert(
+            "1".to_string(),
+            GpuPolicy {
+                gpu_index: 1,
+                max_memory_gb: 80.0,
+                max_utilization_pct: 100.0,
+                reserved_memory_gb: 0.0,
+                allowed_users: Vec::new(),
+                blocked_users: Vec::new(),
+                maintenance_window: Some(window),
+            },
+        );
+        let mut manager = GuardModeManager::with_config(PathBuf::new(), config);
+
+        let active = manager.active_maintenance_windows(Utc::now());
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].gpu_index, 1);
+        assert_eq!(active[0].message, "driver upgrade");
+
+        let process = |gpu_index: u16, pid: u32| proc(gpu_index, pid, "testuser", "train", 512);
+        let result = manager
+            .check_policies(&[process(0, 100), process(1, 200)], &[])
+            .unwrap();
+        let maintenance: Vec<&PolicyViolation> = result
+            .violations
+            .iter()
+            .filter(|v| matches!(v.violation_type, ViolationType::MaintenanceWindowViolation))
+            .collect();
+        assert_eq!(maintenance.len(), 1);
+        assert_eq!(maintenance[0].process.pid, 200);
+        assert!(maintenance[0].message.contains("driver upgrade"));
+        assert!(matches!(maintenance[0].severity, ViolationSeverity::Medium));
+    }
+
+    #[test]
+    fn test_drain_warns_owners_then_escalates_near_deadline() {
+        use chrono::TimeZone;
+        let mut config = GuardModeConfig::default();
+        config.global.enabled = true;
+        config.global.dry_run = true;
+        let since = Utc.with_ymd_and_hms(2026, 10, 16, 9, 0, 0).unwrap();
+        let drain = NodeDrain {
+            since,
+            deadline: Some(since + chrono::Duration::hours(3)),
+            enforce: true,
+            reason: Some("PSU swap".to_string()),
+        };
+        let mut manager =
+            GuardModeManager::with_config(PathBuf::new(), config).with_drain(Some(drain));
+        let processes = [
+            proc(0, 100, "alice", "train", 512),
+            proc(0, 200, "bob", "train", 512),
+        ];
+        let drain_findings = |manager: &mut GuardModeManager, now| {
+            let result = manager.check_policies_at(&processes, &[], now).unwrap();
+            let warnings: Vec<PolicyWarning> = result
+                .warnings
+                .into_iter()
+                .filter(|w| matches!(w.warning_type, WarningType::NodeDraining))
+                .collect();
+            let violations: Vec<PolicyViolation> = result
+                .violations
+                .into_iter()
+                .filter(|v| v.violation_type == ViolationType::NodeDraining)
+                .collect();
+            (warnings, violations)
+        };
+
+        // Hours ahead of the deadline owners are only warned
+        let (warnings, violations) = drain_findings(&mut manager, since);
+        assert!(violations.is_empty());
+        let owners: Vec<&str> = warnings.iter().map(|w| w.user.as_str()).collect();
+        assert_eq!(owners, ["alice", "bob"]);
+        assert_eq!(warnings[0].time_to_limit, Some(3 * 3600));
+        assert_eq!(
+            warnings[0].message,
+            "This node is draining (PSU swap): move PID 100 elsewhere before 2026-10-16 12:00 UTC"
+        );
+
+        // Within the last hour they become violations, High once the deadline passed
+        let (warnings, violations) =
+            drain_findings(&mut manager, since + chrono::Duration::minutes(150));
+        assert!(warnings.is_empty());
+        assert_eq!(violations.len(), 2);
+        assert!(violations
+            .iter()
+            .all(|v| v.severity == ViolationSeverity::Medium));
+        let (_, violations) = drain_findings(&mut manager, since + chrono::Duration::hours(4));
+        assert!(violations
+            .iter()
+            .all(|v| v.severity == ViolationSeverity::High));
+        assert!(violations[0].message.contains("past its deadline"));
+
+        manager.set_drain(None);
+        let (warnings, violations) = drain_findings(&mut manager, since);
+        assert!(warnings.is_empty() && violations.is_empty());
+    }
+
+    #[test]
+    fn test_maintenance_window_defaults_from_toml() {
+        let window: MaintenanceWindow = toml::from_str(
+            r#"
+start_time = "22:00"
+end_time = "02:00"
+days_of_week = [5]
+message = "maintenance"
+"#,
+        )
+        .unwrap();
+        assert!(matches!(window.severity, ViolationSeverity::Critical));
+        assert!(GlobalSettings::default().maintenance_timezone.is_none());
+    }
+
+    #[test]
+    fn test_maintenance_window_enforced_across_midnight() {
+        use chrono::TimeZone;
+        let mut config = GuardModeConfig::default();
+        config.global.enabled = true;
+        config.global.dry_run = false;
+        config.global.maintenance_timezone = Some("UTC".to_string());
+        // Friday 22:00 through Saturday 02:00
+        let mut window = maintenance_window("22:00", "02:00", vec![5]);
+        window.message = "firmware flash".to_string();
+        config.gpu_policies.insert(
+            "0".to_string(),
+            GpuPolicy {
+                gpu_index: 0,
+                max_memory_gb: 80.0,
+                max_utilization_pct: 100.0,
+                reserved_memory_gb: 0.0,
+                allowed_users: Vec::new(),
+                blocked_users: Vec::new(),
+                maintenance_window: Some(window),
+            },
+        );
+        let mut manager = GuardModeManager::with_config(PathBuf::new(), config);
+
+        let process = proc(0, 4242, "testuser", "train", 512);
+        let maintenance_violations = |result: &EnforcementResult| {
+            result
+                .violations
+                .iter()
+                .filter(|v| matches!(v.violation_type, ViolationType::MaintenanceWindowViolation))
+                .count()
+        };
+
+        // Saturday 01:00, inside the window that opened on Friday night
+        let inside = Utc.with_ymd_and_hms(2026, 2, 21, 1, 0, 0).unwrap();
+        let result = manager
+            .check_policies_at(std::slice::from_ref(&process), &[], inside)
+            .unwrap();
+        assert_eq!(maintenance_violations(&result), 1);
+        assert!(result.violations[0].message.contains("firmware flash"));
+        // Hard enforcement is off by default, so the critical violation only warns
+        assert!(result
+            .actions_taken
+            .iter()
+            .any(|a| { a.process.pid == 4242 && matches!(a.action_type, ActionType::Warning) }));
+
+        // Saturday 03:00, after the window closed
+        let outside = Utc.with_ymd_and_hms(2026, 2, 21, 3, 0, 0).unwrap();
+        let result = manager
+            .check_policies_at(std::slice::from_ref(&process), &[], outside)
+            .unwrap();
+        assert_eq!(maintenance_violations(&result), 0);
+    }
+
+    #[test]
+    fn test_hard_enforcement_falls_back_to_warn_only_after_termination_cap() {
+        use chrono::TimeZone;
+        let mut config = GuardModeConfig::default();
+        config.global.enabled = true;
+        config.global.dry_run = false;
+        config.global.maintenance_timezone = Some("UTC".to_string());
+        config.enforcement.hard_enforcement = true;
+        config.enforcement.max_terminations = 3;
+        config.enforcement.termination_window_seconds = 600;
+        // An all-day maintenance window makes every process a critical violation
+        config.gpu_policies.insert(
+            "0".to_string(),
+            GpuPolicy {
+                gpu_index: 0,
+                max_memory_gb: 80.0,
+                max_utilization_pct: 100.0,
+                reserved_memory_gb: 0.0,
+                allowed_users: Vec::new(),
+                blocked_users: Vec::new(),
+                maintenance_window: Some(maintenance_window("00:00", "23:59", Vec::new())),
+            },
+        );
+        let mut manager = GuardModeManager::with_config(PathBuf::new(), config);
+
+        let processes: Vec<GpuProc> = (0..8)
+            .map(|i| proc(0, 5000 + i, "testuser", "train", 512))
+            .collect();
+        let count = |result: &EnforcementResult, termination: bool| {
+            result
+                .actions_taken
+                .iter()
+                .filter(|a| {
+                    if termination {
+                        matches!(a.action_type, ActionType::ProcessTermination)
+                    } else {
+                        matches!(a.action_type, ActionType::Warning)
+                            && a.message.contains("termination cap")
+                    }
+                })
+                .count()
+        };
+
+        let start = Utc.with_ymd_and_hms(2026, 3, 2, 12, 0, 0).unwrap();
+        let result = manager.check_policies_at(&processes, &[], start).unwrap();
+        let critical = result
+            .violations
+            .iter()
+            .filter(|v| matches!(v.severity, ViolationSeverity::Critical))
+            .count();
+        assert_eq!(critical, 8);
+        assert_eq!(count(&result, true), 3);
+        assert_eq!(count(&result, false), 5);
+
+        // Still inside the window: enforcement stays degraded
+        let later = start + chrono::Duration::minutes(5);
+        let result = manager.check_policies_at(&processes, &[], later).unwrap();
+        assert_eq!(count(&result, true), 0);
+        assert_eq!(count(&result, false), 8);
+
+        // Once the window has rolled past the earlier terminations, kills resume
+        let after_window = start + chrono::Duration::minutes(11);
+        let result = manager
+            .check_policies_at(&processes, &[], after_window)
+            .unwrap();
+        assert_eq!(count(&result, true), 3);
+    }
+
+    /// Push the file's mtime forward so a rewrite is noticed even on coarse clocks
+    fn bump_mtime(path: &Path) {
+        let file = fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() + std::time::Duration::from_secs(5))
+            .unwrap();
+    }
+
+    fn memory_violations(result: &EnforcementResult) -> usize {
+        result
+            .violations
+            .iter()
+            .filter(|v| matches!(v.violation_type, ViolationType::MemoryLimitExceeded))
+            .count()
+    }
+
+    #[test]
+    fn test_config_rewritten_mid_run_is_enforced_on_next_check() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("guard_mode_config.toml");
+        let mut config = GuardModeConfig::default();
+        config.global.enabled = true;
+        config.global.default_memory_limit_gb = 16.0;
+        GuardModeManager::save_config(&path, &config).unwrap();
+        let mut manager = GuardModeManager::with_config(path.clone(), config.clone());
+
+        let processes = vec![proc(0, 777, "alice", "train", 4096)];
+        let result = manager.check_policies(&processes, &[]).unwrap();
+        assert_eq!(memory_violations(&result), 0);
+
+        // Another process (CLI, coordinator API, an editor) tightens the limit
+        config.global.default_memory_limit_gb = 2.0;
+        GuardModeManager::save_config(&path, &config).unwrap();
+        bump_mtime(&path);
+
+        let result = manager.check_policies(&processes, &[]).unwrap();
+        assert_eq!(memory_violations(&result), 1);
+        assert_eq!(manager.get_config().global.default_memory_limit_gb, 2.0);
+    }
+
+    #[test]
+    fn test_reload_keeps_current_config_when_file_is_invalid() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("guard_mode_config.toml");
+        let config = GuardModeConfig::default();
+        GuardModeManager::save_config(&path, &config).unwrap();
+        let mut manager = GuardModeManager::with_config(path.clone(), config.clone());
+        assert!(manager.reload_if_changed().is_none());
+
+        fs::write(&path, "this is [not valid toml").unwrap();
+        bump_mtime(&path);
+        assert!(manager.reload_if_changed().is_none());
+        assert_eq!(
+            manager.get_config().global.default_memory_limit_gb,
+            config.global.default_memory_limit_gb
+        );
+
+        // Parses, but fails validation
+        let mut invalid = config.clone();
+        invalid.global.default_utilization_limit_pct = 150.0;
+        fs::write(&path, toml::to_string_pretty(&invalid).unwrap()).unwrap();
+        bump_mtime(&path);
+        assert!(manager.reload_if_changed().is_none());
+        assert_eq!(
+            manager.get_config().global.default_utilization_limit_pct,
+            config.global.default_utilization_limit_pct
+        );
+    }
+
+    #[test]
+    fn test_config_changes_lists_added_removed_and_changed_settings() {
+        let policy = |username: &str, memory_limit_gb: f32| UserPolicy {
+            username: username.to_string(),
+            memory_limit_gb,
+            utilization_limit_pct: 80.0,
+            duration_limit_hours: 12.0,
+            max_concurrent_processes: 5,
+            priority: 5,
+            allowed_gpus: Vec::new(),
+            blocked_gpus: Vec::new(),
+            time_overrides: Vec::new(),
+        };
+        let mut old = GuardModeConfig::default();
+        old.user_policies
+            .insert("alice".to_string(), policy("alice", 16.0));
+        old.user_policies
+            .insert("bob".to_string(), policy("bob", 8.0));
+
+        let mut new = old.clone();
+        new.user_policies.remove("bob");
+        new.user_policies
+            .insert("carol".to_string(), policy("carol", 4.0));
+        new.user_policies.get_mut("alice").unwrap().memory_limit_gb = 12.5;
+        new.enforcement.hard_enforcement = true;
+        new.metadata.last_modified = "later".to_string();
+
+        let changes = config_changes(&old, &new);
+        assert_eq!(
+            changes,
+            vec![
+                "enforcement.hard_enforcement: false -> true".to_string(),
+                "user_policies.alice.memory_limit_gb: 16 -> 12.5".to_string(),
+                "user_policies.bob removed".to_string(),
+                "user_policies.carol added".to_string(),
+            ]
+        );
+        assert!(config_changes(&old, &old).is_empty());
+    }
+
+    /// Guard config where `testuser` is blocked on GPU 0 (a critical violation)
+    /// and throttling uses the cgroup hierarchy at `cgroup_root`
+    fn throttling_config(cgroup_root: &Path) -> GuardModeConfig {
+        let mut config = GuardModeConfig::default();
+        config.global.enabled = true;
+        config.enforcement.throttling.enabled = true;
+        config.enforcement.throttling.cgroup_root = cgroup_root.to_path_buf();
+        config.gpu_policies.insert(
+            "0".to_string(),
+            GpuPolicy {
+                gpu_index: 0,
+                max_memory_gb: 80.0,
+                max_utilization_pct: 100.0,
+                reserved_memory_gb: 0.0,
+                allowed_users: Vec::new(),
+                blocked_users: vec!["testuser".to_string()],
+                maintenance_window: None,
+            },
+        );
+        config
+    }
+
+    fn blocked_process() -> GpuProc {
+        proc(0, 1234, "testuser", "train", 512)
+    }
+
+    fn time_policy(name: &str, memory_multiplier: f32) -> TimePolicy {
+        TimePolicy {
+            name: name.to_string(),
+            start_time: "09:00".to_string(),
+            end_time: "17:00".to_string(),
+            days_of_week: vec![1, 2, 3, 4, 5],
+            memory_multiplier,
+            utilization_multiplier: 1.0,
+            duration_multiplier: 1.0,
+            severity: ViolationSeverity::High,
+        }
+    }
+
+    fn time_policy_manager(policies: Vec<TimePolicy>) -> GuardModeManager {
+        let mut config = GuardModeConfig::default();
+        config.global.enabled = true;
+        config.global.default_memory_limit_gb = 16.0;
+        config.global.default_utilization_limit_pct = 100.0;
+        config.global.maintenance_timezone = Some("UTC".to_string());
+        config.time_policies = policies;
+        validate_config(&config).unwrap();
+        GuardModeManager::with_config(PathBuf::new(), config)
+    }
+
+    fn process_using_gb(pid: u32, memory_gb: u32) -> GpuProc {
+        GpuProc {
+            pid,
+            used_mem_mb: memory_gb * 1024,
+            ..blocked_process()
+        }
+    }
+
+    #[test]
+    fn test_weekday_time_policy_tightens_limits() {
+        use chrono::TimeZone;
+        // Monday 10:00 and Saturday 10:00 (UTC)
+        let weekday = Utc.with_ymd_and_hms(2026, 3, 2, 10, 0, 0).unwrap();
+        let weekend = Utc.with_ymd_and_hms(2026, 3, 7, 10, 0, 0).unwrap();
+        let process = process_using_gb(1234, 10);
+
+        // Overlapping policies: the most restrictive multiplier applies, a looser
+        // one (or a product of both) does not
+        let mut manager = time_policy_manager(vec![
+            time_policy("business_hours", 0.5),
+            time_policy("office_overflow", 0.75),
+            time_policy("burst", 2.0),
+        ]);
+
+        let result = manager
+            .check_policies_at(std::slice::from_ref(&process), &[], weekday)
+            .unwrap();
+        let memory: Vec<_> = result
+            .violations
+            .iter()
+            .filter(|v| matches!(v.violation_type, ViolationType::MemoryLimitExceeded))
+            .collect();
+        assert_eq!(memory.len(), 1);
+        assert_eq!(memory[0].limit_value, 8.0);
+
+        let result = manager
+            .check_policies_at(std::slice::from_ref(&process), &[], weekend)
+            .unwrap();
+        assert!(result.violations.is_empty(), "{:?}", result.violations);
+    }
+
+    #[test]
+    fn test_duration_limit_prefers_process_start_time() {
+        use chrono::TimeZone;
+        let now = Utc.with_ymd_and_hms(2026, 3, 2, 10, 0, 0).unwrap();
+        let mut config = GuardModeConfig::default();
+        config.global.enabled = true;
+        let mut manager = GuardModeManager::with_config(PathBuf::new(), config);
+        let duration_violations = |manager: &mut GuardModeManager, process: &GpuProc| {
+            manager
+                .check_policies_at(std::slice::from_ref(process), &[], now)
+                .unwrap()
+                .violations
+                .into_iter()
+                .filter(|v| matches!(v.violation_type, ViolationType::DurationLimitExceeded))
+                .map(|v| v.current_value)
+                .collect::<Vec<_>>()
+        };
+
+        // The start time text is stale (taken when the process was listed), the
+        // start timestamp is not
+        let process = GpuProc {
+            start_time: "2h 0m 0s".to_string(),
+            start_unix_secs: Some((now - chrono::Duration::hours(20)).timestamp() as u64),
+            ..proc(0, 1234, "alice", "train", 512)
+        };
+        let hours = duration_violations(&mut manager, &process);
+        assert_eq!(hours.len(), 1);
+        assert!((hours[0] - 20.0).abs() < 0.01);
+
+        // Without a start timestamp the text is all there is
+        let process = GpuProc {
+            start_unix_secs: None,
+            ..process
+        };
+        assert!(duration_violations(&mut manager, &process).is_empty());
+        let process = GpuProc {
+            start_time: "13h 0m 0s".to_string(),
+            ..process
+        };
+        assert_eq!(duration_violations(&mut manager, &process), [13.0]);
+    }
+
+    #[test]
+    fn test_zero_multiplier_time_policy_blocks_usage() {
+        use chrono::TimeZone;
+        let weekday = Utc.with_ymd_and_hms(2026, 3, 2, 10, 0, 0).unwrap();
+        let evening = Utc.with_ymd_and_hms(2026, 3, 2, 18, 0, 0).unwrap();
+        let mut critical = time_policy("freeze", 0.5);
+        critical.utilization_multiplier = 0.0;
+        critical.severity = ViolationSeverity::Critical;
+        let mut manager = time_policy_manager(vec![time_policy("no_jobs_9_to_5", 0.0), critical]);
+        assert!(manager
+            .config
+            .time_policies
+            .iter()
+            .all(|p| p.blocks_usage()));
+
+        let processes = [process_using_gb(1, 1), process_using_gb(2, 1)];
+        let result = manager.check_policies_at(&processes, &[], weekday).unwrap();
+        assert_eq!(result.violations.len(), 2, "{:?}", result.violations);
+        for violation in &result.violations {
+            assert!(matches!(
+                violation.violation_type,
+                ViolationType::TimePolicyViolation
+            ));
+            assert_eq!(violation.severity, ViolationSeverity::Critical);
+            assert!(violation.message.contains("'freeze'"));
+        }
+
+        let result = manager.check_policies_at(&processes, &[], evening).unwrap();
+        assert!(result.violations.is_empty(), "{:?}", result.violations);
+
+        let mut config = manager.config.clone();
+        config.time_policies[0].memory_multiplier = -1.0;
+        assert!(validate_config(&config).is_err());
+    }
+
+    struct UnreachableSmtp;
+
+    impl crate::guard_email::MailSender for UnreachableSmtp {
+        fn send_mail(&self, _message: &lettre::Message) -> Result<()> {
+            anyhow::bail!("could not talk to the server: Connection error: connection refused")
+        }
+    }
+
+    #[test]
+    fn test_email_failures_do_not_break_enforcement() {
+        use chrono::TimeZone;
+        let mut config = throttling_config(Path::new("/nonexistent"));
+        config.enforcement.throttling.enabled = false;
+        config.global.dry_run = false;
+        config.enforcement.notifications.email = true;
+        config.enforcement.notifications.email_recipients = vec!["ops@example.com".to_string()];
+        config
+            .enforcement
+            .notifications
+            .smtp
+            .digest_interval_seconds = 60;
+        let now = Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap();
+
+        let mut manager = GuardModeManager::with_config(PathBuf::new(), config);
+        manager.email = Some(EmailNotifier::new(
+            manager.config.enforcement.notifications.clone(),
+            Box::new(UnreachableSmtp),
+            now,
+        ));
+
+        for seconds in [0, 30, 60] {
+            let result = manager
+                .check_policies_at(
+                    &[blocked_process()],
+                    &[],
+                    now + chrono::Duration::seconds(seconds),
+                )
+                .unwrap();
+            assert_eq!(result.violations.len(), 1);
+            assert_eq!(result.actions_taken.len(), 1);
+        }
+        let notifier = manager.email.as_ref().unwrap();
+        assert_eq!(notifier.pending(), 0);
+        assert_eq!(notifier.queued(), 1);
+
+        // Dry runs never mail
+        manager.config.global.dry_run = true;
+        manager
+            .check_policies_at(
+                &[blocked_process()],
+                &[],
+                now + chrono::Duration::seconds(70),
+            )
+            .unwrap();
+        assert_eq!(manager.email.as_ref().unwrap().pending(), 0);
+    }
+
+    #[test]
+    fn test_throttling_dry_run_lists_cgroup_operations() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("cgroup.controllers"), "cpu memory pids\n").unwrap();
+        let mut manager =
+            GuardModeManager::with_config(PathBuf::new(), throttling_config(root.path()));
+
+        let result = manager
+            .simulate_policy_check(&[blocked_process()], &[])
+            .unwrap();
+        assert!(!result.violations.is_empty());
+        // The throttle replaces the termination a critical violation would get
+        assert_eq!(result.actions_taken.len(), 1);
+        let action = &result.actions_taken[0];
+        assert!(matches!(action.action_type, ActionType::ResourceThrottling));
+        assert!(action.success);
+
+        let leaf = root.path().join("gpukill.slice").join("critical");
+        for expected in [
+            format!("mkdir {}", leaf.display()),
+            format!("write '10000 100000' to {}", leaf.join("cpu.max").display()),
+            format!("write 'max' to {}", leaf.join("memory.high").display()),
+            format!("write '1234' to {}", leaf.join("cgroup.procs").display()),
+        ] {
+            assert!(action.message.contains(&expected), "{}", action.message);
+        }
+        // Dry-run leaves the hierarchy untouched
+        assert!(!root.path().join("gpukill.slice").exists());
+        assert!(manager.throttled_pids().is_empty());
+    }
+
+    #[test]
+    fn test_throttling_without_cgroup_v2_enforces_as_usual() {
+        let root = tempfile::tempdir().unwrap();
+        let mut config = throttling_config(root.path());
+        config.global.dry_run = false;
+        let mut manager = GuardModeManager::with_config(PathBuf::new(), config);
+
+        let result = manager.check_policies(&[blocked_process()], &[]).unwrap();
+        let failed = result
+            .actions_taken
+            .iter()
+            .find(|a| matches!(a.action_type, ActionType::ResourceThrottling))
+            .unwrap();
+        assert!(!failed.success);
+        assert!(failed.message.contains("cgroup v2 is not mounted"));
+        assert!(result
+            .actions_taken
+            .iter()
+            .any(|a| matches!(a.action_type, ActionType::Warning)));
+        assert!(manager.throttled_pids().is_empty());
+    }
+
+    #[test]
+    fn test_throttle_levels_are_validated() {
+        let mut config = GuardModeConfig::default();
+        assert!(validate_config(&config).is_ok());
+        assert!(!config.enforcement.throttling.levels.contains_key("low"));
+
+        config.enforcement.throttling.levels.insert(
+            "severe".to_string(),
+            ThrottleLimits {
+                cpu_max_pct: 10,
+                memory_high_mb: None,
+            },
+        );
+        assert!(validate_config(&config).is_err());
+
+        let mut config = GuardModeConfig::default();
+        config
+            .enforcement
+            .throttling
+            .levels
+            .get_mut("high")
+            .unwrap()
+            .cpu_max_pct = 0;
+        assert!(validate_config(&config).is_err());
+    }
 }
@@ -0,0 +1,550 @@
+//! Last-resort NVIDIA driver reset by unloading and reloading the kernel modules.
+//!
+//! `--reset --hard` is for GPUs that NVML can no longer reset in place. The steps
+//! are: turn off persistence mode, `modprobe -r` each NVIDIA module, load them back,
+//! and check that the devices enumerate again. External commands go through
+//! [`CommandRunner`] so tests can run the steps without touching the host.
+
+use anyhow::Result;
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::command::CommandRunner;
+
+/// NVIDIA kernel modules in unload order; they are reloaded in reverse.
+pub const NVIDIA_MODULES: [&str; 4] = ["nvidia_uvm", "nvidia_drm", "nvidia_modeset", "nvidia"];
+
+/// Outcome of one hard reset step
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepStatus {
+    Ok,
+    Skipped(String),
+    Warning(String),
+    Failed(String),
+}
+
+/// A named step and what happened when it ran
+#[derive(Debug, Clone)]
+pub struct StepOutcome {
+    pub step: String,
+    pub status: StepStatus,
+}
+
+impl fmt::Display for StepOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.status {
+            StepStatus::Ok => write!(f, "{}: ok", self.step),
+            StepStatus::Skipped(reason) => write!(f, "{}: skipped ({})", self.step, reason),
+            StepStatus::Warning(reason) => write!(f, "{}: warning ({})", self.step, reason),
+            StepStatus::Failed(reason) => write!(f, "{}: failed ({})", self.step, reason),
+        }
+    }
+}
+
+/// Why a module could not be unloaded
+#[derive(Debug, Clone, PartialEq)]
+pub struct BusyModule {
+    pub module: String,
+    pub use_count: u32,
+    pub used_by: Vec<String>,
+    pub holder_pids: Vec<u32>,
+}
+
+impl fmt::Display for BusyModule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "module {} is in use (use count {}",
+            self.module, self.use_count
+        )?;
+        if !self.used_by.is_empty() {
+            write!(f, ", used by {}", self.used_by.join(", "))?;
+        }
+        write!(f, ")")?;
+        if self.holder_pids.is_empty() {
+            write!(f, "; no process holds /dev/nvidia* open")
+        } else {
+            let pids: Vec<String> = self.holder_pids.iter().map(|p| p.to_string()).collect();
+            write!(f, "; held open by PID(s) {}", pids.join(", "))
+        }
+    }
+}
+
+/// Result of a hard reset run
+#[derive(Debug, Clone, Default)]
+pub struct HardResetReport {
+    pub steps: Vec<StepOutcome>,
+    pub busy: Option<BusyModule>,
+    pub devices_after: Option<u32>,
+    pub success: bool,
+}
+
+/// One row of `lsmod` output
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadedModule {
+    pub name: String,
+    pub use_count: u32,
+    pub used_by: Vec<String>,
+}
+
+/// Parse `lsmod` output into its module rows.
+pub fn parse_lsmod(output: &str) -> Vec<LoadedModule> {
+    output
+        .lines()
+        .skip_while(|line| line.starts_with("Module"))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?.to_string();
+            let _size = fields.next()?;
+            let use_count = fields.next()?.parse().ok()?;
+            let used_by = fields
+                .next()
+                .map(|deps| {
+                    deps.split(',')
+                        .filter(|d| !d.is_empty() && *d != "-")
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default();
+            Some(LoadedModule {
+                name,
+                use_count,
+                used_by,
+            })
+        })
+        .collect()
+}
+
+/// Collect PIDs from `fuser` output, which prints them with trailing access codes (e.g. `1234m`).
+pub fn parse_fuser_pids(output: &str) -> Vec<u32> {
+    let pids: BTreeSet<u32> = output
+        .split_whitespace()
+        .filter_map(|token| {
+            let digits: String = token.chars().take_while(|c| c.is_ascii_digit()).collect();
+            digits.parse().ok()
+        })
+        .collect();
+    pids.into_iter().collect()
+}
+
+/// Runs the hard reset steps against a command runner
+pub struct HardReset<'a, R: CommandRunner> {
+    runner: &'a R,
+    device_nodes: Vec<String>,
+}
+
+impl<'a, R: CommandRunner> HardReset<'a, R> {
+    /// `device_nodes` are the `/dev/nvidia*` paths checked for holders when a module is busy.
+    pub fn new(runner: &'a R, device_nodes: Vec<String>) -> Self {
+        Self {
+            runner,
+            device_nodes,
+        }
+    }
+
+    /// Device nodes present on this host
+    pub fn system_device_nodes() -> Vec<String> {
+        glob::glob("/dev/nvidia*")
+            .map(|paths| {
+                paths
+                    .filter_map(|p| p.ok())
+                    .map(|p| p.display().to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The steps that would run, for dry-run output.
+    pub fn plan(&self) -> Vec<String> {
+        let mut steps = vec!["Disable persistence mode (nvidia-smi -pm 0)".to_string()];
+        steps.extend(
+            NVIDIA_MODULES
+                .iter()
+                .map(|m| format!("Unload {} (modprobe -r {})", m, m)),
+        );
+        steps.extend(
+            NVIDIA_MODULES
+                .iter()
+                .rev()
+                .map(|m| format!("Load {} (modprobe {})", m, m)),
+        );
+        steps.push("Re-enumerate GPUs".to_string());
+        steps
+    }
+
+    /// Run every step. `verify` re-enumerates the GPUs once the modules are back and
+    /// returns the device count; `expected_devices` is the count seen before the reset.
+    pub fn run<F>(&self, expected_devices: u32, verify: F) -> HardResetReport
+    where
+        F: FnOnce() -> Result<u32>,
+    {
+        let mut report = HardResetReport::default();
+
+        report.steps.push(self.disable_persistence());
+
+        let loaded = match self.lsmod() {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                report.steps.push(StepOutcome {
+                    step: "Read loaded modules (lsmod)".to_string(),
+                    status: StepStatus::Failed(e.to_string()),
+                });
+                return report;
+            }
+        };
+
+        let mut unloaded = Vec::new();
+        for module in NVIDIA_MODULES {
+            let step = format!("Unload {}", module);
+            if !loaded.iter().any(|m| m.name == module) {
+                report.steps.push(StepOutcome {
+                    step,
+                    status: StepStatus::Skipped("not loaded".to_string()),
+                });
+                continue;
+            }
+            match self.runner.run("modprobe", &["-r", module]) {
+                Ok(output) if output.success => {
+                    unloaded.push(module);
+                    report.steps.push(StepOutcome {
+                        step,
+                        status: StepStatus::Ok,
+                    });
+                }
+                result => {
+                    let busy = self.diagnose_busy(module);
+                    let reason = match result {
+                        Ok(output) if !output.stderr.trim().is_empty() => {
+                            format!("{}: {}", output.stderr.trim(), busy)
+                        }
+                        Err(e) => format!("{}: {}", e, busy),
+                        Ok(_) => busy.to_string(),
+                    };
+                    report.steps.push(StepOutcome {
+                        step,
+                        status: StepStatus::Failed(reason),
+                    });
+                    report.busy = Some(busy);
+                    break;
+                }
+            }
+        }
+
+        // Load back whatever was removed, even after a failed unload, so the host is not
+        // left without a driver.
+        let mut reloaded = true;
+        for module in unloaded.iter().rev() {
+            let status = match self.runner.run("modprobe", &[module]) {
+                Ok(output) if output.success => StepStatus::Ok,
+                Ok(output) => {
+                    reloaded = false;
+                    StepStatus::Failed(output.stderr.trim().to_string())
+                }
+                Err(e) => {
+                    reloaded = false;
+                    StepStatus::Failed(e.to_string())
+                }
+            };
+            report.steps.push(StepOutcome {
+                step: format!("Load {}", module),
+                status,
+            });
+        }
+
+        if report.busy.is_some() || !reloaded {
+            return report;
+        }
+
+        let step = "Re-enumerate GPUs".to_string();
+        match verify() {
+            Ok(count) if count >= expected_devices => {
+                report.devices_after = Some(count);
+                report.steps.push(StepOutcome {
+                    step,
+                    status: StepStatus::Ok,
+                });
+                report.success = true;
+            }
+            Ok(count) => {
+                report.devices_after = Some(count);
+                report.steps.push(StepOutcome {
+                    step,
+                    status: StepStatus::Failed(format!(
+                        "found {} GPU(s), expected {}",
+                        count, expected_devices
+                    )),
+                });
+            }
+            Err(e) => report.steps.push(StepOutcome {
+                step,
+                status: StepStatus::Failed(e.to_string()),
+            }),
+        }
+
+        report
+    }
+
+    fn disable_persistence(&self) -> StepOutcome {
+        let step = "Disable persistence mode".to_string();
+        // Not every board supports persistence mode, so a failure here is only a warning;
+        // a still-open handle shows up as a busy module in the next step.
+        let status = match self.runner.run("nvidia-smi", &["-pm", "0"]) {
+            Ok(output) if output.success => StepStatus::Ok,
+            Ok(output) => StepStatus::Warning(output.stderr.trim().to_string()),
+            Err(e) => StepStatus::Warning(e.to_string()),
+        };
+        StepOutcome { step, status }
+    }
+
+    fn lsmod(&self) -> Result<Vec<LoadedModule>> {
+        let output = self.runner.run("lsmod", &[])?;
+        if !output.success {
+            return Err(anyhow::anyhow!("lsmod failed: {}", output.stderr.trim()));
+        }
+        Ok(parse_lsmod(&output.stdout))
+    }
+
+    fn diagnose_busy(&self, module: &str) -> BusyModule {
+        let entry = self
+            .lsmod()
+            .ok()
+            .and_then(|mods| mods.into_iter().find(|m| m.name == module));
+
+        let holder_pids = if self.device_nodes.is_empty() {
+            Vec::new()
+        } else {
+            let args: Vec<&str> = self.device_nodes.iter().map(String::as_str).collect();
+            // fuser writes PIDs to stdout and file names to stderr; it exits non-zero
+            // when nothing holds the files.
+            self.runner
+                .run("fuser", &args)
+                .map(|output| parse_fuser_pids(&output.stdout))
+                .unwrap_or_default()
+        };
+
+        BusyModule {
+            module: module.to_string(),
+            use_count: entry.as_ref().map(|m| m.use_count).unwrap_or(0),
+            used_by: entry.map(|m| m.used_by).unwrap_or_default(),
+            holder_pids,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::CommandOutput;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    const LSMOD: &str = "Module                  Size  Used by\n\
+nvidia_uvm           1437696  2\n\
+nvidia_drm             77824  0\n\
+nvidia_modeset       1314816  1 nvidia_drm\n\
+nvidia              56717312  3 nvidia_uvm,nvidia_modeset\n\
+drm                   622592  4 nvidia_drm\n";
+
+    /// Records every call and answers from a table keyed by the full command line
+    struct MockRunner {
+        calls: RefCell<Vec<String>>,
+        failures: HashMap<String, CommandOutput>,
+        responses: HashMap<String, CommandOutput>,
+    }
+
+    impl MockRunner {
+        fn new() -> Self {
+            let mut responses = HashMap::new();
+            responses.insert(
+                "lsmod".to_string(),
+                CommandOutput {
+                    success: true,
+                    stdout: LSMOD.to_string(),
+                    stderr: String::new(),
+                    code: None,
+                },
+            );
+            Self {
+                calls: RefCell::new(Vec::new()),
+                failures: HashMap::new(),
+                responses,
+            }
+        }
+
+        fn fail(mut self, command: &str, stderr: &str) -> Self {
+            self.failures.insert(
+                command.to_string(),
+                CommandOutput {
+                    success: false,
+                    stdout: String::new(),
+                    stderr: stderr.to_string(),
+                    code: None,
+                },
+            );
+            self
+        }
+
+        fn respond(mut self, command: &str, output: CommandOutput) -> Self {
+            self.responses.insert(command.to_string(), output);
+            self
+        }
+
+        fn calls(&self) -> Vec<String> {
+            self.calls.borrow().clone()
+        }
+    }
+
+    impl CommandRunner for MockRunner {
+        fn run(&self, program: &str, args: &[&str]) -> Result<CommandOutput> {
+            let command = std::iter::once(program)
+                .chain(args.iter().copied())
+                .collect::<Vec<_>>()
+                .join(" ");
+            self.calls.borrow_mut().push(command.clone());
+            if let Some(output) = self.failures.get(&command) {
+                return Ok(output.clone());
+            }
+            Ok(self
+                .responses
+                .get(&command)
+                .cloned()
+                .unwrap_or(CommandOutput {
+                    success: true,
+                    ..Default::default()
+                }))
+        }
+    }
+
+    fn module_calls(runner: &MockRunner) -> Vec<String> {
+        runner
+            .calls()
+            .into_iter()
+            .filter(|c| c.starts_with("modprobe") || c.starts_with("nvidia-smi"))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_lsmod() {
+        let modules = parse_lsmod(LSMOD);
+        assert_eq!(modules.len(), 5);
+        let nvidia = modules.iter().find(|m| m.name == "nvidia").unwrap();
+        assert_eq!(nvidia.use_count, 3);
+        assert_eq!(nvidia.used_by, vec!["nvidia_uvm", "nvidia_modeset"]);
+        assert!(modules[1].used_by.is_empty());
+    }
+
+    #[test]
+    fn test_parse_fuser_pids() {
+        assert_eq!(parse_fuser_pids(" 4242m  1717  4242"), vec![1717, 4242]);
+        assert!(parse_fuser_pids("").is_empty());
+    }
+
+    #[test]
+    fn test_hard_reset_runs_steps_in_order() {
+        let runner = MockRunner::new();
+        let reset = HardReset::new(&runner, vec!["/dev/nvidia0".to_string()]);
+        let report = reset.run(2, || Ok(2));
+
+        assert!(report.success);
+        assert_eq!(report.devices_after, Some(2));
+        assert_eq!(
+            module_calls(&runner),
+            vec![
+                "nvidia-smi -pm 0",
+                "modprobe -r nvidia_uvm",
+                "modprobe -r nvidia_drm",
+                "modprobe -r nvidia_modeset",
+                "modprobe -r nvidia",
+                "modprobe nvidia",
+                "modprobe nvidia_modeset",
+                "modprobe nvidia_drm",
+                "modprobe nvidia_uvm",
+            ]
+        );
+        assert!(report.steps.iter().all(|s| s.status == StepStatus::Ok));
+    }
+
+    #[test]
+    fn test_hard_reset_reports_busy_module_and_reloads() {
+        let runner = MockRunner::new()
+            .fail(
+                "modprobe -r nvidia_drm",
+                "modprobe: FATAL: Module nvidia_drm is in use.",
+            )
+            .respond(
+                "fuser /dev/nvidia0 /dev/nvidiactl",
+                CommandOutput {
+                    success: true,
+                    stdout: "  2211m 3344".to_string(),
+                    stderr: "/dev/nvidia0:  /dev/nvidiactl:".to_string(),
+                    code: None,
+                },
+            );
+        let reset = HardReset::new(
+            &runner,
+            vec!["/dev/nvidia0".to_string(), "/dev/nvidiactl".to_string()],
+        );
+        let mut verified = false;
+        let report = reset.run(1, || {
+            verified = true;
+            Ok(1)
+        });
+
+        assert!(!report.success);
+        assert!(!verified);
+        let busy = report.busy.unwrap();
+        assert_eq!(busy.module, "nvidia_drm");
+        assert_eq!(busy.holder_pids, vec![2211, 3344]);
+
+        // Only nvidia_uvm came out, so only it goes back in; the rest are untouched
+        let calls = module_calls(&runner);
+        assert_eq!(
+            calls,
+            vec![
+                "nvidia-smi -pm 0",
+                "modprobe -r nvidia_uvm",
+                "modprobe -r nvidia_drm",
+                "modprobe nvidia_uvm",
+            ]
+        );
+        let failed = report
+            .steps
+            .iter()
+            .find(|s| matches!(s.status, StepStatus::Failed(_)))
+            .unwrap();
+        assert_eq!(failed.step, "Unload nvidia_drm");
+        assert!(failed
+            .to_string()
+            .contains("held open by PID(s) 2211, 3344"));
+    }
+
+    #[test]
+    fn test_hard_reset_skips_missing_modules_and_checks_enumeration() {
+        let runner = MockRunner::new()
+            .fail("nvidia-smi -pm 0", "Unable to set persistence mode")
+            .respond(
+                "lsmod",
+                CommandOutput {
+                    success: true,
+                    stdout: "Module  Size  Used by\nnvidia  100  0\n".to_string(),
+                    stderr: String::new(),
+                    code: None,
+                },
+            );
+        let reset = HardReset::new(&runner, Vec::new());
+        let report = reset.run(4, || Ok(3));
+
+        assert!(!report.success);
+        assert_eq!(report.devices_after, Some(3));
+        assert!(matches!(report.steps[0].status, StepStatus::Warning(_)));
+        assert_eq!(
+            module_calls(&runner),
+            vec!["nvidia-smi -pm 0", "modprobe -r nvidia", "modprobe nvidia"]
+        );
+        let last = report.steps.last().unwrap();
+        assert_eq!(
+            last.status,
+            StepStatus::Failed("found 3 GPU(s), expected 4".to_string())
+        );
+    }
+}
@@ -0,0 +1,508 @@
+//! Idle-process reaper: finds processes that hold GPU memory while their utilization
+//! has stayed near zero for a continuous stretch of time. An optional memory threshold
+//! narrows this to leaked allocations: processes holding more than the threshold in
+//! every sample of the window.
+//!
+//! Observations come from the audit log (`--list` and `--list --watch` record a
+//! sample per process each refresh). A process only counts as idle when the samples
+//! cover the whole window without gaps, so sparse or stale history never leads to a
+//! kill.
+
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::audit::AuditRecord;
+use crate::nvml_api::{GpuProc, GpuSnapshot};
+
+/// Utilization at or below this percentage counts as idle
+pub const DEFAULT_IDLE_MAX_UTIL_PCT: f32 = 1.0;
+
+/// Largest gap between two samples that still counts as continuous observation
+pub const DEFAULT_MAX_SAMPLE_GAP: Duration = Duration::from_secs(10 * 60);
+
+/// Thresholds a process must meet before it is reaped
+#[derive(Debug, Clone)]
+pub struct IdleCriteria {
+    /// How long utilization must have stayed at or below `max_util_pct`
+    pub idle_for: Duration,
+    pub max_util_pct: f32,
+    pub max_sample_gap: Duration,
+    /// When set, a sample only counts as idle if the process held more than this
+    /// much GPU memory
+    pub min_mem_mb: Option<u32>,
+}
+
+impl IdleCriteria {
+    pub fn new(idle_for: Duration) -> Self {
+        Self {
+            idle_for,
+            max_util_pct: DEFAULT_IDLE_MAX_UTIL_PCT,
+            max_sample_gap: DEFAULT_MAX_SAMPLE_GAP,
+            min_mem_mb: None,
+        }
+    }
+
+    pub fn with_max_util_pct(mut self, max_util_pct: f32) -> Self {
+        self.max_util_pct = max_util_pct;
+        self
+    }
+
+    pub fn with_min_mem_mb(mut self, min_mem_mb: Option<u32>) -> Self {
+        self.min_mem_mb = min_mem_mb;
+        self
+    }
+
+    /// Whether a process holding `memory_mb` at `util_pct` utilization counts as idle
+    pub fn is_idle(&self, util_pct: f32, memory_mb: u32) -> bool {
+        util_pct <= self.max_util_pct && self.min_mem_mb.is_none_or(|min| memory_mb > min)
+    }
+}
+
+/// Users and process names the reaper leaves alone
+#[derive(Debug, Clone, Default)]
+pub struct IdleExemptions {
+    pub users: Vec<String>,
+    pub processes: Vec<String>,
+}
+
+impl IdleExemptions {
+    pub fn is_exempt(&self, process: &GpuProc) -> bool {
+        let name = process.proc_name.to_lowercase();
+        self.users
+            .iter()
+            .any(|u| u.eq_ignore_ascii_case(&process.user))
+            || self.processes.iter().any(|entry| {
+                let entry = entry.to_lowercase();
+                name == entry || name.starts_with(&entry)
+            })
+    }
+}
+
+/// A running process that has been idle for at least the requested window
+#[derive(Debug, Clone)]
+pub struct IdleCandidate {
+    pub process: GpuProc,
+    /// Span covered by consecutive idle samples, oldest to newest
+    pub idle_for: Duration,
+    pub samples: usize,
+    pub avg_util_pct: f32,
+}
+
+/// Length of the trailing run of idle samples, or `None` when the latest sample is
+/// busy or older than the allowed gap. `samples` must be sorted oldest first.
+pub fn idle_span(
+    samples: &[&AuditRecord],
+    now: DateTime<Utc>,
+    criteria: &IdleCriteria,
+) -> Option<(Duration, usize, f32)> {
+    let max_gap = chrono::Duration::from_std(criteria.max_sample_gap).ok()?;
+    let last = samples.last()?;
+    if now.signed_duration_since(last.timestamp) > max_gap
+        || !criteria.is_idle(last.utilization_pct, last.memory_used_mb)
+    {
+        return None;
+    }
+
+    let mut start = last.timestamp;
+    let mut count = 1;
+    let mut util_total = last.utilization_pct;
+    for pair in samples.windows(2).rev() {
+        let (earlier, later) = (pair[0], pair[1]);
+        if !criteria.is_idle(earlier.utilization_pct, earlier.memory_used_mb)
+            || later.timestamp.signed_duration_since(earlier.timestamp) > max_gap
+        {
+            break;
+        }
+        start = earlier.timestamp;
+        count += 1;
+        util_total += earlier.utilization_pct;
+    }
+
+    let span = last.timestamp.signed_duration_since(start).to_std().ok()?;
+    Some((span, count, util_total / count as f32))
+}
+
+/// Running processes whose audit samples show them idle for the whole window.
+///
+/// `ages` maps PID to how long the process has been running; processes without a
+/// known age, or younger than the window, are never returned. Samples taken before
+/// the process started are ignored so a reused PID cannot inherit another
+/// process's history.
+pub fn find_idle_processes(
+    records: &[AuditRecord],
+    processes: &[GpuProc],
+    ages: &HashMap<u32, Duration>,
+    now: DateTime<Utc>,
+    criteria: &IdleCriteria,
+    exemptions: &IdleExemptions,
+) -> Vec<IdleCandidate> {
+    let mut candidates = Vec::new();
+
+    for process in processes {
+        if exemptions.is_exempt(process)
+            || criteria
+                .min_mem_mb
+                .is_some_and(|min| process.used_mem_mb <= min)
+        {
+            continue;
+        }
+        let Some(age) = ages.get(&process.pid) else {
+            continue;
+        };
+        if *age < criteria.idle_for {
+            continue;
+        }
+        let Ok(age) = chrono::Duration::from_std(*age) else {
+            continue;
+        };
+        let started = now - age;
+
+        let mut samples: Vec<&AuditRecord> = records
+            .iter()
+            .filter(|r| {
+                r.node_id.is_none()
+                    && r.pid == Some(process.pid)
+                    && r.gpu_index == process.gpu_index
+                    && r.timestamp >= started
+                    && r.process_name
+                        .as_deref()
+                        .is_none_or(|name| name == process.proc_name)
+            })
+            .collect();
+        samples.sort_by_key(|r| r.timestamp);
+
+        if let Some((span, count, avg_util_pct)) = idle_span(&samples, now, criteria) {
+            if span >= criteria.idle_for {
+                candidates.push(IdleCandidate {
+                    process: process.clone(),
+                    idle_for: span,
+                    samples: count,
+                    avg_util_pct,
+                });
+            }
+        }
+    }
+
+    candidates.sort_by_key(|c| std::cmp::Reverse(c.process.used_mem_mb));
+    candidates
+}
+
+/// Re-check a candidate against a fresh snapshot, e.g. after the warning period.
+/// Utilization is split evenly across the processes on a GPU, the same way audit
+/// samples attribute it.
+pub fn still_idle(
+    candidate: &IdleCandidate,
+    snapshots: &[GpuSnapshot],
+    processes: &[GpuProc],
+    criteria: &IdleCriteria,
+) -> bool {
+    let target = &candidate.process;
+    let Some(current) = processes
+        .iter()
+        .find(|p| p.pid == target.pid && p.proc_name == target.proc_name)
+    else {
+        return false;
+    };
+    let Some(gpu) = snapshots.iter().find(|s| s.gpu_index == target.gpu_index) else {
+        return false;
+    };
+    let sharing = processes
+        .iter()
+        .filter(|p| p.gpu_index == target.gpu_index)
+        .count()
+        .max(1);
+    criteria.is_idle(gpu.util_pct / sharing as f32, current.used_mem_mb)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nvml_api::fixtures::{gpu, proc};
+
+    fn sample(
+        pid: u32,
+        name: &str,
+        minutes_ago: i64,
+        util: f32,
+        now: DateTime<Utc>,
+    ) -> AuditRecord {
+        AuditRecord {
+            id: 0,
+            timestamp: now - chrono::Duration::minutes(minutes_ago),
+            gpu_index: 0,
+            gpu_name: "Test GPU".to_string(),
+            pid: Some(pid),
+            user: Some("alice".to_string()),
+            process_name: Some(name.to_string()),
+            memory_used_mb: 30000,
+            utilization_pct: util,
+            temperature_c: 40,
+            power_w: 60.0,
+            container: None,
+            node_id: None,
+            hostname: None,
+            cmdline: None,
+            ecc_uncorrected: None,
+            ecc_uncorrected_delta: None,
+            pcie_mb_per_sec: None,
+            nvlink_bytes: None,
+            slurm_job_id: None,
+            start_unix_secs: None,
+        }
+    }
+
+    fn samples_every_5m(
+        pid: u32,
+        name: &str,
+        from: i64,
+        util: f32,
+        now: DateTime<Utc>,
+    ) -> Vec<AuditRecord> {
+        (0..=from / 5)
+            .map(|i| sample(pid, name, from - i * 5, util, now))
+            .collect()
+    }
+
+    fn ages(entries: &[(u32, u64)]) -> HashMap<u32, Duration> {
+        entries
+            .iter()
+            .map(|(pid, minutes)| (*pid, Duration::from_secs(minutes * 60)))
+            .collect()
+    }
+
+    #[test]
+    fn test_idle_process_is_found() {
+        let now = Utc::now();
+        let records = samples_every_5m(100, "python", 40, 0.0, now);
+        let processes = vec![proc(0, 100, "alice", "python", 30000)];
+        let criteria = IdleCriteria::new(Duration::from_secs(30 * 60));
+
+        let found = find_idle_processes(
+            &records,
+            &processes,
+            &ages(&[(100, 120)]),
+            now,
+            &criteria,
+            &IdleExemptions::default(),
+        );
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].idle_for, Duration::from_secs(40 * 60));
+        assert_eq!(found[0].samples, 9);
+    }
+
+    #[test]
+    fn test_busy_sample_resets_idle_run() {
+        let now = Utc::now();
+        let mut records = samples_every_5m(100, "python", 40, 0.0, now);
+        // Busy 20 minutes ago: only the last 15 minutes are idle
+        records[4].utilization_pct = 50.0;
+        let processes = vec![proc(0, 100, "alice", "python", 30000)];
+        let criteria = IdleCriteria::new(Duration::from_secs(30 * 60));
+
+        let found = find_idle_processes(
+            &records,
+            &processes,
+            &ages(&[(100, 120)]),
+            now,
+            &criteria,
+            &IdleExemptions::default(),
+        );
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn test_sample_gap_breaks_continuity() {
+        let now = Utc::now();
+        let records = vec![
+            sample(100, "python", 60, 0.0, now),
+            sample(100, "python", 5, 0.0, now),
+            sample(100, "python", 0, 0.0, now),
+        ];
+        let processes = vec![proc(0, 100, "alice", "python", 30000)];
+        let criteria = IdleCriteria::new(Duration::from_secs(30 * 60));
+
+        let found = find_idle_processes(
+            &records,
+            &processes,
+            &ages(&[(100, 120)]),
+            now,
+            &criteria,
+            &IdleExemptions::default(),
+        );
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn test_young_and_exempt_processes_are_skipped() {
+        let now = Utc::now();
+        let mut records = samples_every_5m(100, "python", 40, 0.0, now);
+        records.extend(samples_every_5m(200, "jupyter-lab", 40, 0.0, now));
+        records.extend(samples_every_5m(300, "python", 40, 0.0, now));
+        let processes = vec![
+            // Reused PID: started 20 minutes ago, older samples belong to someone else
+            proc(0, 100, "alice", "python", 30000),
+            proc(0, 200, "bob", "jupyter-lab", 20000),
+            proc(0, 300, "root", "python", 10000),
+        ];
+        let criteria = IdleCriteria::new(Duration::from_secs(30 * 60));
+        let exemptions = IdleExemptions {
+            users: vec!["root".to_string()],
+            processes: vec!["jupyter".to_string()],
+        };
+
+        let found = find_idle_processes(
+            &records,
+            &processes,
+            &ages(&[(100, 20), (200, 120), (300, 120)]),
+            now,
+            &criteria,
+            &exemptions,
+        );
+        assert!(found.is_empty());
+
+        // Without an age the process is never considered
+        let found = find_idle_processes(
+            &records,
+            &processes[..1],
+            &HashMap::new(),
+            now,
+            &criteria,
+            &IdleExemptions::default(),
+        );
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn test_stale_history_is_ignored() {
+        let now = Utc::now();
+        let records: Vec<AuditRecord> = samples_every_5m(100, "python", 40, 0.0, now)
+            .into_iter()
+            .map(|mut r| {
+                r.timestamp -= chrono::Duration::hours(2);
+                r
+            })
+            .collect();
+        let processes = vec![proc(0, 100, "alice", "python", 30000)];
+        let criteria = IdleCriteria::new(Duration::from_secs(30 * 60));
+
+        let found = find_idle_processes(
+            &records,
+            &processes,
+            &ages(&[(100, 240)]),
+            now,
+            &criteria,
+            &IdleExemptions::default(),
+        );
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn test_still_idle_rechecks_live_utilization() {
+        let snapshot = |util: f32| GpuSnapshot {
+            mem_total_mb: 40000,
+            power_w: 60.0,
+            pids: 1,
+            ..gpu(0, util, 30000)
+        };
+        let candidate = IdleCandidate {
+            process: proc(0, 100, "alice", "python", 30000),
+            idle_for: Duration::from_secs(3600),
+            samples: 12,
+            avg_util_pct: 0.0,
+        };
+        let processes = vec![candidate.process.clone()];
+        let criteria = IdleCriteria::new(Duration::from_secs(3600));
+
+        assert!(still_idle(
+            &candidate,
+            &[snapshot(0.5)],
+            &processes,
+            &criteria
+        ));
+        assert!(!still_idle(
+            &candidate,
+            &[snapshot(40.0)],
+            &processes,
+            &criteria
+        ));
+        // Process exited or PID now belongs to another program
+        let replaced = vec![proc(0, 100, "alice", "bash", 10)];
+        assert!(!still_idle(&candidate, &[snapshot(0.0)], &[], &criteria));
+        assert!(!still_idle(
+            &candidate,
+            &[snapshot(0.0)],
+            &replaced,
+            &criteria
+        ));
+
+        // A process that released its memory is spared by a memory threshold
+        let criteria = criteria.with_min_mem_mb(Some(1024));
+        assert!(still_idle(
+            &candidate,
+            &[snapshot(0.0)],
+            &processes,
+            &criteria
+        ));
+        let released = vec![proc(0, 100, "alice", "python", 512)];
+        assert!(!still_idle(
+            &candidate,
+            &[snapshot(0.0)],
+            &released,
+            &criteria
+        ));
+    }
+
+    #[test]
+    fn test_memory_threshold_finds_leaked_allocations() {
+        let now = Utc::now();
+        let mut records = samples_every_5m(100, "python", 40, 0.0, now);
+        // Idle but holding only 512 MB: not a leak
+        records.extend(
+            samples_every_5m(200, "python", 40, 0.0, now)
+                .into_iter()
+                .map(|mut r| {
+                    r.memory_used_mb = 512;
+                    r
+                }),
+        );
+        // Dropped below the threshold 20 minutes ago, so only 15 minutes count
+        let mut dipped = samples_every_5m(300, "python", 40, 0.0, now);
+        dipped[4].memory_used_mb = 256;
+        records.extend(dipped);
+        // Holding memory but busy
+        records.extend(samples_every_5m(400, "python", 40, 35.0, now));
+        let processes = vec![
+            proc(0, 100, "alice", "python", 30000),
+            proc(0, 200, "bob", "python", 512),
+            proc(0, 300, "carol", "python", 30000),
+            proc(0, 400, "dave", "python", 30000),
+        ];
+        let criteria = IdleCriteria::new(Duration::from_secs(30 * 60)).with_min_mem_mb(Some(1024));
+
+        let found = find_idle_processes(
+            &records,
+            &processes,
+            &ages(&[(100, 120), (200, 120), (300, 120), (400, 120)]),
+            now,
+            &criteria,
+            &IdleExemptions::default(),
+        );
+        let pids: Vec<u32> = found.iter().map(|c| c.process.pid).collect();
+        assert_eq!(pids, vec![100]);
+
+        // Without the threshold every idle process qualifies
+        let found = find_idle_processes(
+            &records,
+            &processes,
+            &ages(&[(100, 120), (200, 120), (300, 120), (400, 120)]),
+            now,
+            &IdleCriteria::new(Duration::from_secs(30 * 60)),
+            &IdleExemptions::default(),
+        );
+        let mut pids: Vec<u32> = found.iter().map(|c| c.process.pid).collect();
+        pids.sort();
+        assert_eq!(pids, vec![100, 200, 300]);
+    }
+}
@@ -1,15 +1,36 @@
 pub mod args;
 pub mod audit;
+pub mod audit_store;
+pub mod cgroup;
+pub mod command;
 pub mod config;
 pub mod coordinator;
+pub mod dcgm;
+pub mod drain;
+pub mod env_expand;
+pub mod gpu_index;
+pub mod grafana;
+pub mod guard_email;
 pub mod guard_mode;
+#[cfg(target_os = "linux")]
+pub mod hard_reset;
+pub mod idle_reaper;
+pub mod local_api;
 pub mod nvml_api;
+pub mod privileges;
 pub mod proc;
+pub mod proc_history;
 pub mod process_mgmt;
 pub mod remote;
 pub mod render;
+pub mod report;
 pub mod rogue_config;
 pub mod rogue_detection;
+pub mod selftest;
+pub mod slurm;
+pub mod snapshot;
+pub mod tls;
+pub mod topology;
 pub mod util;
 pub mod vendor;
 pub mod version;
@@ -0,0 +1,512 @@
+//! Local REST API (`--serve-local`): listing, kill, reset and audit queries over
+//! HTTP on this host, for IDE plugins and scripts.
+//!
+//! Every endpoint runs the same GPU manager and process management calls as the
+//! CLI and answers with the document `--output json` prints; failures use the
+//! `--output json` error report. Unlike the coordinator (`--server`) there are no
+//! nodes, background tasks or cached cluster state: each request queries the GPUs.
+
+use crate::args::ProcessSort;
+use crate::audit::{ActionKind, AuditManager};
+use crate::audit_store::AuditQuery;
+use crate::nvml_api::Snapshot;
+use crate::proc::{KillSignal, ProcessManager};
+use crate::process_mgmt::{EnhancedProcessManager, KillProtection};
+use crate::report::{
+    ErrorKind, ErrorReport, KillOutcome, KillReport, KillStatus, PartialFailure, ResetReport,
+    ResetStatus,
+};
+use crate::snapshot::SnapshotReport;
+use crate::vendor::GpuManager;
+use anyhow::{Context, Result};
+use axum::{
+    extract::{Query, Request, State},
+    http::StatusCode,
+    middleware::{self, Next},
+    response::{IntoResponse, Json, Response},
+    routing::{get, post},
+    Router,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::{info, warn};
+
+/// Port `--serve-local` listens on when `--port` is not given
+pub const DEFAULT_LOCAL_API_PORT: u16 = 7780;
+
+/// Seconds a kill waits for a process to exit when the request does not say
+const DEFAULT_KILL_TIMEOUT_SECS: u16 = 5;
+
+/// Hours of audit records returned when the request does not say
+const DEFAULT_AUDIT_HOURS: u32 = 24;
+
+/// What the local API handlers share: only the GPU manager and settings, so each
+/// request sees the GPUs as they are now
+#[derive(Clone)]
+pub struct LocalApiState {
+    gpu_manager: GpuManager,
+    /// Required as `Authorization: Bearer <token>` when set
+    token: Option<Arc<str>>,
+    /// Names protected from kills on top of the defaults, as in the config file
+    protected_names: Arc<[String]>,
+    /// Answers `/v1/audit` and records kills and resets; None when the log could not be opened
+    audit: Option<Arc<AuditManager>>,
+}
+
+impl LocalApiState {
+    pub fn new(gpu_manager: GpuManager) -> Self {
+        Self {
+            gpu_manager,
+            token: None,
+            protected_names: Arc::from(Vec::new()),
+            audit: None,
+        }
+    }
+
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token.map(Arc::from);
+        self
+    }
+
+    pub fn with_protected_names(mut self, names: &[String]) -> Self {
+        self.protected_names = Arc::from(names.to_vec());
+        self
+    }
+
+    pub fn with_audit(mut self, audit: Arc<AuditManager>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Record a kill or reset in the action audit log, like the CLI does. Recording
+    /// failures are only logged.
+    async fn record_action(
+        &self,
+        kind: ActionKind,
+        target: &str,
+        dry_run: bool,
+        result: &Result<()>,
+    ) {
+        let Some(audit) = &self.audit else {
+            return;
+        };
+        let invoking_user = crate::proc::current_username();
+        if let Err(e) = audit
+            .log_action(kind, target, &invoking_user, dry_run, result)
+            .await
+        {
+            warn!("Failed to record {} in the action audit log: {}", kind, e);
+        }
+    }
+}
+
+/// Error answered by the local API: the `--output json` error report with an HTTP status
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    report: ErrorReport,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            report: ErrorReport::new(message),
+        }
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    /// Status from the error category the CLI maps to its exit code
+    fn from(error: anyhow::Error) -> Self {
+        let report = ErrorReport::from_error(&error);
+        let status = match report.error.kind {
+            ErrorKind::InvalidArgument => StatusCode::BAD_REQUEST,
+            ErrorKind::PermissionDenied => StatusCode::FORBIDDEN,
+            ErrorKind::Unsupported => StatusCode::NOT_IMPLEMENTED,
+            ErrorKind::Nvml => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorKind::NotFound => StatusCode::NOT_FOUND,
+            ErrorKind::General => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self { status, report }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(self.report)).into_response()
+    }
+}
+
+/// Create the local API router
+pub fn router(state: LocalApiState) -> Router {
+    Router::new()
+        .route("/v1/snapshot", get(get_snapshot))
+        .route("/v1/processes", get(get_processes))
+        .route("/v1/kill", post(kill))
+        .route("/v1/reset", post(reset))
+        .route("/v1/audit", get(get_audit))
+        .layer(middleware::from_fn_with_state(state.clone(), require_token))
+        .with_state(state)
+}
+
+/// Serve the local API on `listener` until `shutdown` completes
+pub async fn serve(
+    listener: tokio::net::TcpListener,
+    state: LocalApiState,
+    shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+) -> Result<()> {
+    let audit = state.audit.clone();
+    axum::serve(listener, router(state))
+        .with_graceful_shutdown(shutdown)
+        .await
+        .context("Local API server failed")?;
+    if let Some(audit) = audit {
+        audit
+            .flush()
+            .await
+            .context("Failed to write buffered audit records")?;
+    }
+    Ok(())
+}
+
+/// Refuse requests without the bearer token, when one is configured
+async fn require_token(
+    State(state): State<LocalApiState>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if let Some(token) = &state.token {
+        if !crate::util::bearer_token_matches(request.headers(), token) {
+            return ApiError::new(
+                StatusCode::UNAUTHORIZED,
+                "Missing or wrong bearer token (Authorization: Bearer <token>)",
+            )
+            .into_response();
+        }
+    }
+    next.run(request).await
+}
+
+/// Query of `GET /v1/snapshot` and `GET /v1/processes`
+#[derive(Debug, Default, Deserialize)]
+pub struct GpuFilter {
+    /// Only this GPU, like `--gpu`
+    pub gpu: Option<u16>,
+}
+
+/// `GET /v1/snapshot`: what `--list --output json` prints
+async fn get_snapshot(
+    State(state): State<LocalApiState>,
+    Query(filter): Query<GpuFilter>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+    let collection = state.gpu_manager.collect_snapshots_async().await?;
+    let mut gpu_status = collection.gpu_status();
+    let mut gpus = collection.snapshots;
+    let mut errors = collection.errors;
+    let mut procs = state.gpu_manager.get_all_processes_async().await?;
+    if let Some(index) = filter.gpu {
+        gpus.retain(|gpu| gpu.gpu_index == index);
+        procs.retain(|p| p.gpu_index == index);
+        gpu_status.retain(|status| status.gpu_index == index);
+        errors.retain(|error| error.gpu_index == index);
+    }
+
+    let snapshot = Snapshot {
+        host: crate::util::get_hostname(),
+        ts: crate::util::get_current_timestamp_iso(),
+        gpus,
+        procs,
+        degraded: false,
+        gpu_status: Vec::new(),
+        errors,
+    }
+    .with_gpu_status(gpu_status);
+    let report = serde_json::to_value(SnapshotReport::new(&snapshot))
+        .context("Failed to serialize snapshot")?;
+    Ok(Json(report))
+}
+
+/// `GET /v1/processes`: what `--list --processes-only --output json` prints
+async fn get_processes(
+    State(state): State<LocalApiState>,
+    Query(filter): Query<GpuFilter>,
+) -> Result<Json<Vec<crate::nvml_api::GpuProc>>, ApiError> {
+    let mut procs = state.gpu_manager.get_all_processes_async().await?;
+    if let Some(index) = filter.gpu {
+        procs.retain(|p| p.gpu_index == index);
+    }
+    ProcessSort::Memory.sort(&mut procs);
+    Ok(Json(procs))
+}
+
+/// Body of `POST /v1/kill`: one PID, or the processes matching a name pattern
+/// and/or user like `--kill --filter --filter-user --batch`
+#[derive(Debug, Default, Deserialize)]
+pub struct KillRequest {
+    pub pid: Option<u32>,
+    /// Process name pattern (regex)
+    pub filter: Option<String>,
+    /// Only processes of this user
+    pub user: Option<String>,
+    /// Match `filter` against the full command line instead of the name
+    #[serde(default)]
+    pub match_cmdline: bool,
+    /// Escalate to SIGKILL after the timeout; for a PID, also kill it when it is not on a GPU
+    #[serde(default)]
+    pub force: bool,
+    #[serde(default)]
+    pub dry_run: bool,
+    pub timeout_secs: Option<u16>,
+    /// Also kill processes protected by name
+    #[serde(default)]
+    pub allow_protected: bool,
+}
+
+impl KillRequest {
+    /// Target as recorded in the action audit log
+    fn describe(&self) -> String {
+        match (&self.pid, &self.filter, &self.user) {
+            (Some(pid), _, _) => format!("pid {}", pid),
+            (None, Some(filter), Some(user)) => format!("filter {} of user {}", filter, user),
+            (None, Some(filter), None) => format!("filter {}", filter),
+            (None, None, Some(user)) => format!("user {}", user),
+            (None, None, None) => "none".to_string(),
+        }
+    }
+}
+
+/// `POST /v1/kill`: a `--kill --output json` report
+async fn kill(
+    State(state): State<LocalApiState>,
+    Json(request): Json<KillRequest>,
+) -> Result<Json<KillReport>, ApiError> {
+    let by_pattern = request.filter.is_some() || request.user.is_some();
+    if request.pid.is_some() == by_pattern {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "Invalid argument: give either pid, or filter and/or user",
+        ));
+    }
+
+    let target = request.describe();
+    let dry_run = request.dry_run;
+    let (gpu_manager, protected_names) = (state.gpu_manager.clone(), state.protected_names.clone());
+    // Kills wait for processes to exit, so they run off the async workers
+    let result =
+        tokio::task::spawn_blocking(move || kill_blocking(gpu_manager, &protected_names, &request))
+            .await
+            .context("Kill task panicked")?;
+
+    let outcome = result
+        .as_ref()
+        .map(|_| ())
+        .map_err(|e| anyhow::anyhow!("{}", e));
+    state
+        .record_action(ActionKind::Kill, &target, dry_run, &outcome)
+        .await;
+    Ok(Json(result?))
+}
+
+fn kill_blocking(
+    gpu_manager: GpuManager,
+    protected_names: &[String],
+    request: &KillRequest,
+) -> Result<KillReport> {
+    let started = Instant::now();
+    let timeout_secs = request.timeout_secs.unwrap_or(DEFAULT_KILL_TIMEOUT_SECS);
+    let (dry_run, force) = (request.dry_run, request.force);
+    let mut report = KillReport::new(dry_run, KillSignal::default(), timeout_secs, force);
+    let mut manager =
+        EnhancedProcessManager::new(ProcessManager::with_gpu_manager(gpu_manager.clone()))
+            .with_protection(protected_names, request.allow_protected);
+
+    let Some(pid) = request.pid else {
+        let processes = gpu_manager.get_all_processes()?;
+        let matched = crate::process_mgmt::filter_kill_targets(
+            &processes,
+            request.filter.as_deref(),
+            request.match_cmdline,
+            request.user.as_deref(),
+        )?;
+        let (targets, skipped) = manager.screen_protected(matched);
+        if dry_run {
+            report.push_processes(&targets, KillStatus::WouldKill);
+            report.push_skipped(&skipped, KillStatus::WouldSkip);
+            return Ok(report.finish(started));
+        }
+        let mut outcome = manager.batch_kill(&targets, timeout_secs, force);
+        outcome.skipped.extend(skipped);
+        report.push_batch(&outcome);
+        let report = report.finish(started);
+        if !outcome.failed.is_empty() {
+            return Err(PartialFailure::new(outcome.failure_message(), &report).into());
+        }
+        return Ok(report);
+    };
+
+    manager.process_manager.validate_process(pid, !force)?;
+    let process_info = manager.process_manager.get_process_info(pid)?;
+    let gpu_proc = gpu_manager
+        .get_all_processes()
+        .ok()
+        .and_then(|procs| procs.into_iter().find(|p| p.pid == pid));
+    let mut outcome = match &gpu_proc {
+        Some(p) => KillOutcome::for_process(p, KillStatus::WouldKill),
+        None => KillOutcome::for_pid(pid, KillStatus::WouldKill),
+    };
+    outcome.process = Some(process_info.name.clone());
+    outcome.user = Some(process_info.user.clone());
+
+    if dry_run {
+        let protection = KillProtection::snapshot(protected_names)
+            .allowing_self(&manager.process_manager.self_guard());
+        if let Some(reason) =
+            protection.skip_reason(pid, &process_info.name, request.allow_protected)
+        {
+            outcome.status = KillStatus::WouldSkip;
+            outcome.reason = Some(reason);
+        }
+    } else {
+        let kill_started = Instant::now();
+        let ended_by = manager.kill_process(pid, timeout_secs, force)?;
+        info!(
+            pid,
+            signal = ended_by,
+            "Terminated process for local API request"
+        );
+        outcome.status = KillStatus::Killed;
+        outcome.signal = Some(ended_by.to_string());
+        outcome.duration_ms = Some(kill_started.elapsed().as_millis() as u64);
+    }
+    report.push(outcome);
+    Ok(report.finish(started))
+}
+
+/// Body of `POST /v1/reset`
+#[derive(Debug, Deserialize)]
+pub struct ResetRequest {
+    pub gpu: u16,
+    /// Reset even with processes on the GPU, which the reset terminates
+    #[serde(default)]
+    pub force: bool,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// `POST /v1/reset`: a `--reset --output json` report
+async fn reset(
+    State(state): State<LocalApiState>,
+    Json(request): Json<ResetRequest>,
+) -> Result<Json<ResetReport>, ApiError> {
+    let gpu_manager = state.gpu_manager.clone();
+    let (gpu, dry_run) = (request.gpu, request.dry_run);
+    let result = tokio::task::spawn_blocking(move || reset_blocking(&gpu_manager, &request))
+        .await
+        .context("Reset task panicked")?;
+
+    let outcome = result
+        .as_ref()
+        .map(|_| ())
+        .map_err(|e| anyhow::anyhow!("{}", e.report.error.message));
+    state
+        .record_action(
+            ActionKind::Reset,
+            &format!("gpu {}", gpu),
+            dry_run,
+            &outcome,
+        )
+        .await;
+    Ok(Json(result?))
+}
+
+fn reset_blocking(
+    gpu_manager: &GpuManager,
+    request: &ResetRequest,
+) -> Result<ResetReport, ApiError> {
+    let started = Instant::now();
+    let gpu = request.gpu;
+    let mut report = ResetReport::new(request.dry_run, false);
+    gpu_manager.check_resettable(gpu as u32)?;
+    let mechanism = gpu_manager.reset_mechanism_of(gpu as u32);
+    let processes = gpu_manager.get_all_processes()?;
+
+    if request.dry_run {
+        report.push_preview(&[(gpu, mechanism)], &processes, request.force);
+        return Ok(report.finish(started));
+    }
+    let active = processes.iter().filter(|p| p.gpu_index == gpu).count();
+    if !request.force && active > 0 {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            format!(
+                "Cannot reset GPU {} with {} active process(es). Use force to override.",
+                gpu, active
+            ),
+        ));
+    }
+
+    let reset_started = Instant::now();
+    gpu_manager.reset_gpu(gpu as u32)?;
+    info!(gpu_index = gpu, "GPU reset for local API request");
+    report.push(
+        gpu,
+        ResetStatus::Reset,
+        mechanism,
+        Some(reset_started),
+        None,
+    );
+    Ok(report.finish(started))
+}
+
+/// Query of `GET /v1/audit`, like `--audit --audit-hours --audit-user ...`
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditParams {
+    pub hours: Option<u32>,
+    pub user: Option<String>,
+    pub process: Option<String>,
+    pub gpu: Option<u16>,
+    #[serde(default)]
+    pub offset: usize,
+    /// At most this many records, newest first; 0 for all
+    pub limit: Option<usize>,
+}
+
+/// `GET /v1/audit`: what `--audit --output json` prints
+async fn get_audit(
+    State(state): State<LocalApiState>,
+    Query(params): Query<AuditParams>,
+) -> Result<Json<Vec<crate::audit::AuditRecord>>, ApiError> {
+    let Some(audit) = &state.audit else {
+        return Err(ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "The audit log is not available",
+        ));
+    };
+    let end = chrono::Utc::now();
+    let hours = params.hours.unwrap_or(DEFAULT_AUDIT_HOURS);
+    let Some(start) = end.checked_sub_signed(chrono::Duration::hours(hours as i64)) else {
+        return Err(ApiError::new(
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Invalid argument: hours={} reaches past the oldest supported date",
+                hours
+            ),
+        ));
+    };
+    let limit = params.limit.unwrap_or(crate::args::DEFAULT_AUDIT_LIMIT);
+    let query = AuditQuery {
+        user: params.user,
+        process: params.process,
+        gpu: params.gpu,
+        offset: params.offset,
+        limit: (limit > 0).then_some(limit),
+        ..AuditQuery::range(start, end)
+    };
+    let page = audit
+        .query(&query)
+        .await
+        .context("Failed to query audit records")?;
+    Ok(Json(page.records))
+}
@@ -1,139 +1,400 @@
-use crate::args::{Cli, OutputFormat, VendorFilter};
+use crate::args::{Cli, ColorChoice, LogFormat, OutputFormat, ProcessSort, VendorFilter};
+use crate::audit::ActionKind;
 use crate::config::get_config;
 use crate::coordinator::{create_router, CoordinatorState};
 use crate::nvml_api::{NvmlApi, Snapshot};
-use crate::proc::ProcessManager;
-use crate::process_mgmt::EnhancedProcessManager;
-use crate::render::{render_error, render_info, render_success, render_warning, Renderer};
+use crate::proc::{KillSignal, PidTarget, ProcessManager};
+use crate::process_mgmt::{EnhancedProcessManager, KillProtection, SkippedProcess};
+use crate::render::{
+    ecc_warnings, icon, init_output_style, render_error, render_info, render_success,
+    render_warning, Icon, OutputStyle, Renderer,
+};
+use crate::report::{
+    CapabilitiesReport, ErrorKind, ErrorReport, GuardConfigReport, InfoReport, KillOutcome,
+    KillReport, KillStatus, PartialFailure, ResetReport, ResetStatus,
+};
+use crate::topology::{NvmlTopology, TopologyReport};
 use crate::vendor::GpuManager;
 use crate::version::get_version_string;
 use anyhow::{Context, Result};
 use std::process;
 use std::time::Duration;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, warn, Instrument};
 
 mod args;
 mod audit;
+mod audit_store;
+mod cgroup;
+mod command;
 mod config;
 mod coordinator;
+mod dcgm;
+mod drain;
+mod env_expand;
+mod gpu_index;
+mod grafana;
+mod guard_email;
 mod guard_mode;
+#[cfg(target_os = "linux")]
+mod hard_reset;
+mod idle_reaper;
+mod local_api;
 mod nvml_api;
+mod privileges;
 mod proc;
+mod proc_history;
 mod process_mgmt;
 mod remote;
 mod render;
+mod report;
 mod rogue_config;
 mod rogue_detection;
+mod selftest;
+mod slurm;
+mod snapshot;
+mod tls;
+mod topology;
 mod util;
 mod vendor;
 mod version;
 
 fn main() -> Result<()> {
-    // Initialize error handling
-    color_eyre::install().map_err(|e| anyhow::anyhow!("Failed to install error handler: {}", e))?;
-
     // Parse command line arguments
-    let cli = Cli::parse();
+    let mut cli = Cli::parse_args();
 
-    // Initialize logging
-    init_logging(&cli.log_level.to_string())?;
-
-    // Load configuration
+    // Load configuration; it supplies defaults for options not given on the command line
     let config_manager = get_config(cli.config.clone()).context("Failed to load configuration")?;
+    cli.apply_config_defaults(config_manager.config())
+        .context("Failed to apply configuration")?;
+    cli.validate();
+    crate::command::init_tool_limits(crate::command::ToolLimits {
+        timeout: Duration::from_secs(config_manager.config().tool_timeout_secs.max(1)),
+        cooldown: Duration::from_secs(config_manager.config().tool_cooldown_secs),
+    });
+
+    // Pick rich or plain output before anything is printed
+    let color = match cli.color_choice() {
+        ColorChoice::Auto if !config_manager.config().use_colors => ColorChoice::Never,
+        choice => choice,
+    };
+    let style = OutputStyle::detect(color);
+    init_output_style(style);
+
+    // Initialize error handling
+    let mut eyre_hook = color_eyre::config::HookBuilder::default();
+    if !style.color {
+        eyre_hook = eyre_hook.theme(color_eyre::config::Theme::new());
+    }
+    eyre_hook
+        .install()
+        .map_err(|e| anyhow::anyhow!("Failed to install error handler: {}", e))?;
+
+    // Initialize logging; the flag wins over the config file
+    let log_format = match cli.log_format {
+        Some(format) => format,
+        None => config_manager
+            .config()
+            .log_format
+            .parse()
+            .context("Invalid log_format in configuration")?,
+    };
+    init_logging(
+        &cli.log_level.to_string(),
+        log_format,
+        style.color,
+        cli.output == OutputFormat::Json,
+    )?;
 
     info!("Starting gpukill {}", get_version_string());
+    let privileges = crate::privileges::Privileges::current();
+    tracing::debug!(
+        user = %privileges.user,
+        euid = ?privileges.euid,
+        can_signal_any_process = privileges.can_signal_any_process(),
+        "Detected privileges"
+    );
+
+    // Every event from the operation carries its name
+    let operation = cli.operation_name();
+    let json_output = cli.output == OutputFormat::Json;
+    let remote = cli.remote.is_some() || cli.remote_file.is_some();
+    let span = tracing::info_span!("operation", operation);
+    let started = std::time::Instant::now();
 
     // Execute the requested operation
     let rt = tokio::runtime::Builder::new_current_thread()
         .enable_all()
         .build()
         .map_err(|e| anyhow::anyhow!("Failed to create tokio runtime: {}", e))?;
-    match rt.block_on(execute_operation(cli, config_manager)) {
+    let result = rt.block_on(execute_operation(cli, config_manager).instrument(span.clone()));
+    let duration_ms = started.elapsed().as_millis() as u64;
+    let _entered = span.enter();
+    match result {
         Ok(()) => {
-            info!("Operation completed successfully");
+            info!(duration_ms, "Operation completed successfully");
             Ok(())
         }
         Err(e) => {
-            error!("Operation failed: {}", e);
-            render_error(&e.to_string());
-
-            // Set appropriate exit codes
-            let exit_code = if e.to_string().contains("NVML") {
-                2 // NVML initialization failure
-            } else if e.to_string().contains("Invalid argument") {
-                3 // Invalid arguments
-            } else if e.to_string().contains("permission") || e.to_string().contains("Permission") {
-                4 // Permission errors
-            } else if e.to_string().contains("not supported")
-                || e.to_string().contains("unsupported")
-            {
-                5 // Operation not supported
+            error!(duration_ms, error = %e, "Operation failed");
+
+            // Exit codes: 2 NVML, 3 invalid arguments, 4 permission, 5 unsupported, 1 other
+            let report = ErrorReport::from_error(&e);
+            if json_output {
+                match serde_json::to_string_pretty(&report) {
+                    Ok(json) => println!("{}", json),
+                    Err(_) => render_error(&e.to_string()),
+                }
             } else {
-                1 // General error
-            };
+                render_error(&e.to_string());
+                // Remote hosts have their own privileges, so sudo here would not help
+                if report.error.kind == ErrorKind::PermissionDenied
+                    && !remote
+                    && !crate::privileges::Privileges::current().is_root()
+                {
+                    render_warning(&format!(
+                        "Re-run with elevated privileges: {}",
+                        crate::privileges::sudo_command()
+                    ));
+                }
+            }
 
-            process::exit(exit_code);
+            process::exit(report.error.exit_code);
         }
     }
 }
 
-/// Initialize logging system
-fn init_logging(log_level: &str) -> Result<()> {
+/// Initialize logging system. Logs go to stderr when stdout is reserved for a
+/// JSON result (`--output json`).
+fn init_logging(log_level: &str, format: LogFormat, ansi: bool, to_stderr: bool) -> Result<()> {
+    use tracing_subscriber::fmt::writer::BoxMakeWriter;
+
     let filter = tracing_subscriber::EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(log_level));
+    let writer = if to_stderr {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
+
+    match format {
+        LogFormat::Human => tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_writer(writer)
+            .with_ansi(ansi)
+            .with_target(false)
+            .with_thread_ids(false)
+            .with_thread_names(false)
+            .init(),
+        LogFormat::Json => tracing::subscriber::set_global_default(json_subscriber(filter, writer))
+            .context("Failed to install JSON logger")?,
+    }
+
+    Ok(())
+}
 
+/// JSON logger: one object per event, with event fields at the top level and the
+/// enclosing operation span under "span"
+fn json_subscriber<W>(
+    filter: tracing_subscriber::EnvFilter,
+    writer: W,
+) -> impl tracing::Subscriber + Send + Sync
+where
+    W: for<'a> tracing_subscriber::fmt::MakeWriter<'a> + Send + Sync + 'static,
+{
     tracing_subscriber::fmt()
+        .json()
         .with_env_filter(filter)
+        .with_writer(writer)
         .with_target(false)
-        .with_thread_ids(false)
-        .with_thread_names(false)
-        .init();
-
-    Ok(())
+        .with_current_span(true)
+        .with_span_list(false)
+        .flatten_event(true)
+        .finish()
 }
 
 /// Execute the requested operation
-async fn execute_operation(cli: Cli, config_manager: crate::config::ConfigManager) -> Result<()> {
+async fn execute_operation(
+    mut cli: Cli,
+    config_manager: crate::config::ConfigManager,
+) -> Result<()> {
     // Check if this is a remote operation
-    if let Some(remote_host) = cli.remote.clone() {
-        return execute_remote_operation(cli, &remote_host).await;
+    if cli.is_remote() {
+        return execute_remote_operation(cli, config_manager).await;
+    }
+
+    // The self-test falls back to a mock GPU, so it runs before GPU initialization
+    if cli.selftest {
+        return execute_selftest_operation(
+            cli.selftest_helper,
+            cli.selftest_mem_mb,
+            cli.timeout_secs,
+            &Renderer::new(cli.output.clone()),
+        );
+    }
+
+    // The matrix is static, so it prints even when no GPU is found
+    if cli.capabilities {
+        return execute_capabilities_operation(
+            cli.check_privileges,
+            &Renderer::new(cli.output.clone()),
+        );
+    }
+
+    // The probe reports a failed GPU detection instead of failing itself
+    if cli.info {
+        return Renderer::new(cli.output.clone())
+            .render_info_report(&InfoReport::detect())
+            .map_err(|e| anyhow::anyhow!("Render error: {}", e));
     }
 
     // Initialize GPU manager for local operations
-    let gpu_manager = GpuManager::initialize().context("Failed to initialize GPU manager")?;
+    let gpu_manager = GpuManager::initialize()
+        .context("Failed to initialize GPU manager")?
+        .with_snapshot_timeout(Duration::from_secs(
+            config_manager.config().snapshot_timeout_secs,
+        ))
+        .with_snapshot_cache_ttl(Duration::from_millis(
+            config_manager.config().snapshot_cache_ms,
+        ))
+        .with_fast_mode(cli.fast)
+        .with_stable_indices();
+    // Kill, reset and guard results and messages go through this renderer
+    let renderer = Renderer::new(cli.output.clone());
 
-    if cli.list {
+    if let Some((id, index)) = &cli.remap_gpu {
+        return execute_remap_gpu_operation(id, *index, &renderer);
+    }
+
+    if cli.topology {
+        return execute_topology_operation(&gpu_manager, &renderer);
+    }
+
+    // From here on a GPU picked by UUID or serial is handled like --gpu <index>
+    if let Some(id) = cli.gpu_uuid.as_deref() {
+        let index = gpu_manager.resolve_gpu_id(id)?;
+        debug!("Resolved GPU {} to index {}", id, index);
+        cli.gpu = Some(index);
+    }
+
+    if cli.list && cli.show_ids {
+        execute_show_ids_operation(&gpu_manager, &renderer)
+    } else if cli.list {
         execute_list_operation(
             cli.details,
+            cli.tree,
             cli.watch,
+            cli.show_rates,
+            cli.watch_count,
+            cli.follow_pid,
+            cli.alert_thresholds(),
             cli.output,
             cli.vendor,
+            cli.gpu,
             cli.containers,
+            cli.full_cmd,
+            cli.raw_units,
+            cli.processes_only.then_some(cli.sort),
+            cli.save_snapshot.as_deref(),
+            cli.diff.as_deref(),
+            ListAudit::from_flags(cli.no_audit, cli.sync_audit),
+            cli.timing,
             gpu_manager,
             config_manager,
         )
         .await
+    } else if cli.kill
+        && (cli.protect_process.is_some() || cli.unprotect_process.is_some() || cli.list_protected)
+    {
+        execute_protected_list_operation(&cli, &config_manager)
+    } else if let (true, Some(idle_for)) = (cli.kill, cli.idle_window()) {
+        let target = cli.action_target();
+        let result = execute_idle_kill_operation(
+            idle_for,
+            cli.idle_max_util,
+            cli.idle_min_mem_mb(),
+            cli.idle_warn,
+            cli.gpu,
+            cli.batch,
+            cli.timeout_secs,
+            cli.force,
+            cli.signal,
+            cli.dry_run,
+            cli.allow_protected,
+            &config_manager.config().protected_processes,
+            gpu_manager,
+            &renderer,
+        )
+        .await;
+        record_action(ActionKind::Kill, &target, cli.dry_run, &result).await;
+        result
     } else if cli.kill {
-        execute_kill_operation(
-            cli.pid,
+        // A container-qualified PID is translated to the host PID before anything is signalled
+        let pid = match &cli.pid {
+            Some(target @ PidTarget::Container { .. }) => {
+                let host_pid = target.resolve()?;
+                info!("Resolved {} to host PID {}", target, host_pid);
+                Some(host_pid)
+            }
+            Some(target) => Some(target.pid()),
+            None => None,
+        };
+        let target = cli.action_target();
+        let result = execute_kill_operation(
+            pid,
             cli.timeout_secs,
             cli.force,
+            cli.signal,
             cli.filter,
+            cli.filter_user,
+            cli.match_cmdline,
+            cli.job,
+            cli.use_scancel,
             cli.batch,
+            cli.with_children,
+            cli.with_parent,
+            cli.allow_protected,
+            cli.allow_self,
             cli.gpu,
             cli.dry_run,
+            cli.yes,
             gpu_manager,
             config_manager,
-        )
+            &renderer,
+        );
+        record_action(ActionKind::Kill, &target, cli.dry_run, &result).await;
+        result
     } else if cli.reset {
-        execute_reset_operation(
+        let target = cli.action_target();
+        let result = execute_reset_operation(
             cli.gpu,
             cli.all,
+            cli.hard,
+            cli.wait_secs.map(Duration::from_secs),
             cli.force,
+            cli.timeout_secs,
+            cli.signal,
             cli.dry_run,
+            cli.yes,
             gpu_manager,
             config_manager,
+            &renderer,
         )
+        .await;
+        record_action(ActionKind::Reset, &target, cli.dry_run, &result).await;
+        result
+    } else if cli.set {
+        let target = cli.action_target();
+        let result = execute_set_operation(
+            cli.gpu,
+            cli.power_limit,
+            cli.lock_clocks,
+            cli.reset_clocks,
+            cli.dry_run,
+            &gpu_manager,
+            &renderer,
+        );
+        record_action(ActionKind::Set, &target, cli.dry_run, &result).await;
+        result
     } else if cli.audit {
         execute_audit_operation(
             cli.audit_user.clone(),
@@ -146,96 +407,271 @@ async fn execute_operation(cli: Cli, config_manager: crate::config::ConfigManage
         )
         .await
     } else if cli.server {
-        let host = cli.server_host.clone();
+        let tags = config_manager.config().node_tags_with(&cli.node_tags);
+        let host = cli.server_host;
         let port = cli.server_port;
+        let lifecycle = crate::coordinator::NodeLifecycle {
+            offline_after: cli.node_offline_after,
+            remove_after: cli.node_remove_after,
+        };
+        let rogue = RogueScanSettings {
+            interval: cli.rogue_scan_interval,
+            stale_after: cli.rogue_stale_after,
+        };
+        let cluster_token = cli.cluster_token.clone();
+        // Load the certificate before anything starts, so a bad pair fails fast
+        let tls = match (&cli.server_tls_cert, &cli.server_tls_key) {
+            (Some(cert), Some(key)) => Some(crate::tls::load_server_config(cert, key)?),
+            _ => None,
+        };
+        let scheme = if tls.is_some() { "https" } else { "http" };
         if cli.open {
             // Spawn server so we can open the browser once it is listening (instead of blocking forever)
-            let server_handle =
-                tokio::spawn(
-                    async move { execute_server_operation(host, port, gpu_manager).await },
-                );
+            let server_handle = tokio::spawn(async move {
+                execute_server_operation(
+                    host,
+                    port,
+                    lifecycle,
+                    rogue,
+                    tags,
+                    cluster_token,
+                    tls,
+                    gpu_manager,
+                )
+                .await
+            });
             tokio::time::sleep(Duration::from_millis(500)).await;
-            open_browser_at_port(port);
+            open_browser_at(&format!("{}://localhost:{}", scheme, port));
             server_handle
                 .await
                 .context("Server task panicked")?
                 .context("Server exited with error")?;
         } else {
-            execute_server_operation(host, port, gpu_manager).await?;
+            execute_server_operation(
+                host,
+                port,
+                lifecycle,
+                rogue,
+                tags,
+                cluster_token,
+                tls,
+                gpu_manager,
+            )
+            .await?;
         }
         Ok(())
+    } else if cli.serve_local {
+        execute_serve_local_operation(
+            cli.bind,
+            cli.port,
+            cli.api_token.clone(),
+            gpu_manager,
+            &config_manager,
+        )
+        .await
     } else if cli.guard {
-        execute_guard_operation(&cli, gpu_manager).await
+        execute_guard_operation(&cli, gpu_manager, &renderer).await
     } else if let Some(coordinator_url) = cli.register_node {
-        execute_register_node_operation(coordinator_url, gpu_manager).await
+        let tags = config_manager.config().node_tags_with(&cli.node_tags);
+        execute_register_node_operation(
+            coordinator_url,
+            tags,
+            cli.snapshot_delta,
+            cli.rogue_scan_interval,
+            cli.cluster_token.clone(),
+            gpu_manager,
+        )
+        .await
     } else {
         Err(anyhow::anyhow!("No operation specified"))
     }
 }
 
+/// Record a kill or reset in the action audit log. Recording failures are only
+/// logged so auditing never changes the outcome of the operation.
+async fn record_action(kind: ActionKind, target: &str, dry_run: bool, result: &Result<()>) {
+    let manager = match crate::audit::AuditManager::new().await {
+        Ok(manager) => manager,
+        Err(e) => {
+            warn!("Failed to open audit log to record {}: {}", kind, e);
+            return;
+        }
+    };
+    let invoking_user = crate::proc::current_username();
+    if let Err(e) = manager
+        .log_action(kind, target, &invoking_user, dry_run, result)
+        .await
+    {
+        warn!("Failed to record {} in the action audit log: {}", kind, e);
+    }
+}
+
 /// Execute list operation
+#[allow(clippy::too_many_arguments)]
 async fn execute_list_operation(
     details: bool,
+    tree: bool,
     watch: bool,
+    show_rates: bool,
+    watch_count: Option<u64>,
+    follow_pid: Option<u32>,
+    alerts: crate::snapshot::AlertThresholds,
     output: OutputFormat,
     vendor_filter: Option<VendorFilter>,
+    gpu_filter: Option<u16>,
     containers: bool,
+    full_cmd: bool,
+    raw_units: bool,
+    processes_only: Option<ProcessSort>,
+    save_snapshot: Option<&std::path::Path>,
+    diff: Option<&std::path::Path>,
+    audit: ListAudit,
+    timing: bool,
     gpu_manager: GpuManager,
     config_manager: crate::config::ConfigManager,
 ) -> Result<()> {
-    let renderer = Renderer::new(output);
-
-    if watch {
+    // --vendor all groups GPUs into per-vendor sections even on single-vendor hosts
+    let renderer = Renderer::new(output)
+        .with_vendor_sections(matches!(vendor_filter, Some(VendorFilter::All)))
+        .with_cmdline_width((!full_cmd).then_some(config_manager.config().cmdline_width))
+        .with_raw_units(raw_units);
+
+    if let Some(sort) = processes_only {
+        execute_process_list(containers, sort, gpu_filter, &renderer, &gpu_manager).await
+    } else if let (true, Some(pid)) = (watch, follow_pid) {
+        execute_follow_pid(pid, watch_count, gpu_filter, &gpu_manager, &config_manager).await
+    } else if watch {
         execute_watch_mode(
             details,
+            tree,
             containers,
+            show_rates,
+            watch_count,
+            alerts,
             vendor_filter,
+            gpu_filter,
+            audit,
+            timing,
             renderer,
             gpu_manager,
             config_manager,
         )
         .await
     } else {
-        execute_single_list(details, containers, &vendor_filter, &renderer, &gpu_manager).await
+        execute_single_list(
+            details,
+            tree,
+            containers,
+            &vendor_filter,
+            gpu_filter,
+            save_snapshot,
+            diff,
+            None,
+            None,
+            audit,
+            timing,
+            &renderer,
+            &gpu_manager,
+        )
+        .await
+        .map(|_| ())
+    }
+}
+
+/// List only the processes across all GPUs, without the GPU summary
+async fn execute_process_list(
+    containers: bool,
+    sort: ProcessSort,
+    gpu_filter: Option<u16>,
+    renderer: &Renderer,
+    gpu_manager: &GpuManager,
+) -> Result<()> {
+    let mut procs = gpu_manager.get_all_processes_async().await?;
+    if let Some(index) = gpu_filter {
+        procs.retain(|p| p.gpu_index == index);
+    }
+    if containers {
+        procs = enrich_with_containers(procs)?;
+    }
+    sort.sort(&mut procs);
+    renderer
+        .render_process_list(&procs)
+        .map_err(|e| anyhow::anyhow!("Render error: {}", e))
+}
+
+/// Add container names to processes; skipped with a warning when NVML is unavailable
+fn enrich_with_containers(
+    procs: Vec<crate::nvml_api::GpuProc>,
+) -> Result<Vec<crate::nvml_api::GpuProc>> {
+    match NvmlApi::new() {
+        Ok(nvml_api) => {
+            let proc_manager = ProcessManager::new(nvml_api);
+            let mut enhanced_manager = EnhancedProcessManager::new(proc_manager);
+            enhanced_manager.enrich_with_containers(procs)
+        }
+        Err(e) => {
+            tracing::warn!(
+                "Skipping container enrichment: NVML unavailable ({}). Container names will not be shown.",
+                e
+            );
+            Ok(procs)
+        }
     }
 }
 
 /// Execute single list operation
+#[allow(clippy::too_many_arguments)]
 async fn execute_single_list(
     details: bool,
+    tree: bool,
     containers: bool,
     vendor_filter: &Option<VendorFilter>,
+    gpu_filter: Option<u16>,
+    save_snapshot: Option<&std::path::Path>,
+    diff: Option<&std::path::Path>,
+    rates: Option<&mut crate::snapshot::RateTracker>,
+    history: Option<&mut crate::proc_history::ProcessHistory>,
+    audit: ListAudit,
+    timing: bool,
     renderer: &Renderer,
     gpu_manager: &GpuManager,
-) -> Result<()> {
-    // Get all GPU snapshots
-    let mut gpus = gpu_manager.get_all_snapshots()?;
+) -> Result<Snapshot> {
+    // Load the baseline first so a bad file fails before GPUs are queried
+    let baseline = diff.map(crate::snapshot::load_snapshot).transpose()?;
+    let mut timer = PhaseTimer::new(timing);
+
+    // Get all GPU snapshots; devices that fail or hang are listed with their error
+    // instead of blocking the list or vanishing from it
+    let collection = gpu_manager.collect_snapshots_async().await?;
+    timer.mark("snapshots");
+    let mut gpu_status = collection.gpu_status();
+    let mut gpus = collection.snapshots;
+    let mut errors = collection.errors;
 
     // Filter by vendor if specified
     if let Some(filter) = vendor_filter {
         if let Some(target_vendor) = filter.to_gpu_vendor() {
             gpus.retain(|gpu| gpu.vendor == target_vendor);
+            errors.retain(|error| error.vendor == target_vendor);
         }
     }
 
     // Get all processes
-    let mut procs = gpu_manager.get_all_processes()?;
+    let mut procs = gpu_manager.get_all_processes_async().await?;
+    timer.mark("processes");
+
+    // Filter to one GPU if --gpu or --gpu-uuid was given
+    if let Some(index) = gpu_filter {
+        gpus.retain(|gpu| gpu.gpu_index == index);
+        procs.retain(|p| p.gpu_index == index);
+        gpu_status.retain(|status| status.gpu_index == index);
+        errors.retain(|error| error.gpu_index == index);
+    }
 
     // Enrich with container information if requested (uses sysinfo; NVML not required)
     if containers {
-        match NvmlApi::new() {
-            Ok(nvml_api) => {
-                let proc_manager = ProcessManager::new(nvml_api);
-                let mut enhanced_manager = EnhancedProcessManager::new(proc_manager);
-                procs = enhanced_manager.enrich_with_containers(procs)?;
-            }
-            Err(e) => {
-                tracing::warn!(
-                    "Skipping container enrichment: NVML unavailable ({}). Container names will not be shown.",
-                    e
-                );
-            }
-        }
+        procs = enrich_with_containers(procs)?;
+        timer.mark("containers");
     }
 
     // Create snapshot for rendering
@@ -244,12 +680,200 @@ async fn execute_single_list(
         ts: crate::util::get_current_timestamp_iso(),
         gpus: gpus.clone(),
         procs: procs.clone(),
+        degraded: false,
+        gpu_status: Vec::new(),
+        errors,
+    }
+    .with_gpu_status(gpu_status);
+
+    // The audit write runs while the listing renders and is awaited before returning,
+    // so the process does not exit mid-write
+    let mut audit_write = Some({
+        let (gpus, procs) = (gpus.clone(), procs.clone());
+        tokio::spawn(async move {
+            log_list_audit(
+                audit != ListAudit::Off,
+                &gpus,
+                &procs,
+                crate::audit::AuditManager::new,
+            )
+            .await
+        })
+    });
+    if audit == ListAudit::Sync {
+        finish_list_audit(audit_write.take()).await;
+        timer.mark("audit");
+    }
+
+    if let Some(path) = save_snapshot {
+        crate::snapshot::save_snapshot(path, &snapshot)?;
+        // Keep JSON output machine-readable
+        if matches!(renderer.get_output_format(), OutputFormat::Table) {
+            render_info(&format!("Saved snapshot to {}", path.display()));
+        } else {
+            info!("Saved snapshot to {}", path.display());
+        }
+    }
+
+    if let Some(baseline) = baseline {
+        let diff = crate::snapshot::diff_snapshots(&baseline, &snapshot);
+        renderer
+            .render_snapshot_diff(&diff)
+            .map_err(|e| anyhow::anyhow!("Render error: {}", e))?;
+        timer.mark("render");
+        finish_list_audit(audit_write).await;
+        timer.mark("audit");
+        timer.report();
+        return Ok(snapshot);
+    }
+
+    // Annotate GPUs inside a Guard Mode maintenance window
+    let maintenance = match crate::guard_mode::GuardModeManager::load_existing() {
+        Ok(Some(guard_manager)) => guard_manager.active_maintenance_windows(chrono::Utc::now()),
+        Ok(None) => Vec::new(),
+        Err(e) => {
+            tracing::debug!("Skipping maintenance windows: {}", e);
+            Vec::new()
+        }
     };
 
-    // Log to audit database (async)
-    // Now that execute_single_list is async, we can directly log to audit
-    match crate::audit::AuditManager::new().await {
-        Ok(audit_manager) => match audit_manager.log_snapshot(&gpus, &procs).await {
+    let mut renderer_for_list = renderer.clone().with_maintenance_windows(&maintenance);
+    if let Some(tracker) = rates {
+        renderer_for_list = renderer_for_list.with_rates(tracker.update(&snapshot));
+    }
+    if let Some(history) = history {
+        history.record(&snapshot);
+        renderer_for_list = renderer_for_list.with_process_trends(history.trends());
+    }
+    renderer_for_list
+        .render_snapshot(&snapshot, details)
+        .map_err(|e| anyhow::anyhow!("Render error: {}", e))?;
+
+    // Group GPU processes under their launchers (torchrun, accelerate, ...)
+    if tree && matches!(renderer.get_output_format(), OutputFormat::Table) {
+        let table = crate::process_mgmt::ProcessTable::snapshot();
+        let process_tree = renderer.format_gpu_process_tree(&table, &procs);
+        if !process_tree.is_empty() {
+            println!("\nProcess Tree:");
+            print!("{}", process_tree);
+        }
+    }
+
+    // Without privileges other users' processes may show as "unknown"
+    if matches!(renderer.get_output_format(), OutputFormat::Table) {
+        if let Some(note) =
+            crate::privileges::hidden_process_note(crate::privileges::Privileges::current(), &procs)
+        {
+            render_warning(&note);
+        }
+        if let Some(drain) = crate::drain::NodeDrain::load_local() {
+            render_warning(&drain.banner(chrono::Utc::now()));
+        }
+    }
+    timer.mark("render");
+    if audit_write.is_some() {
+        finish_list_audit(audit_write).await;
+        timer.mark("audit");
+    }
+    timer.report();
+    Ok(snapshot)
+}
+
+/// How a listing is written to the audit log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ListAudit {
+    /// --no-audit: the audit store is not touched
+    Off,
+    /// Written while the listing renders
+    Background,
+    /// --sync-audit: written before the listing renders
+    Sync,
+}
+
+impl ListAudit {
+    fn from_flags(no_audit: bool, sync_audit: bool) -> Self {
+        match (no_audit, sync_audit) {
+            (true, _) => ListAudit::Off,
+            (false, true) => ListAudit::Sync,
+            (false, false) => ListAudit::Background,
+        }
+    }
+}
+
+/// Wait for a background audit write, if one is still running
+async fn finish_list_audit(audit_write: Option<tokio::task::JoinHandle<()>>) {
+    if let Some(handle) = audit_write {
+        if let Err(e) = handle.await {
+            tracing::warn!("Audit logging task failed: {}", e);
+        }
+    }
+}
+
+/// Per-phase durations of a listing, printed to stderr with --timing
+struct PhaseTimer {
+    enabled: bool,
+    started: std::time::Instant,
+    last: std::time::Instant,
+    phases: Vec<(&'static str, Duration)>,
+}
+
+impl PhaseTimer {
+    fn new(enabled: bool) -> Self {
+        let now = std::time::Instant::now();
+        Self {
+            enabled,
+            started: now,
+            last: now,
+            phases: Vec::new(),
+        }
+    }
+
+    /// End `phase`, which ran since the previous mark
+    fn mark(&mut self, phase: &'static str) {
+        let now = std::time::Instant::now();
+        self.phases.push((phase, now - self.last));
+        self.last = now;
+    }
+
+    fn format(&self) -> String {
+        self.phases
+            .iter()
+            .copied()
+            .chain(std::iter::once(("total", self.last - self.started)))
+            .map(|(phase, duration)| {
+                format!(
+                    "timing: {:<10} {:>9.1} ms\n",
+                    phase,
+                    duration.as_secs_f64() * 1000.0
+                )
+            })
+            .collect()
+    }
+
+    fn report(&self) {
+        if self.enabled {
+            eprint!("{}", self.format());
+        }
+    }
+}
+
+/// Log a listed snapshot to the audit database. `open_audit` is only called when
+/// `enabled`, so with --no-audit the audit store is never touched.
+async fn log_list_audit<F, Fut>(
+    enabled: bool,
+    gpus: &[crate::nvml_api::GpuSnapshot],
+    procs: &[crate::nvml_api::GpuProc],
+    open_audit: F,
+) where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = Result<crate::audit::AuditManager>>,
+{
+    if !enabled {
+        tracing::debug!("Skipping audit logging (--no-audit)");
+        return;
+    }
+    match open_audit().await {
+        Ok(audit_manager) => match audit_manager.log_snapshot(gpus, procs).await {
             Ok(()) => {
                 tracing::debug!(
                     "Successfully logged audit snapshot with {} GPUs and {} processes",
@@ -265,51 +889,149 @@ async fn execute_single_list(
             tracing::warn!("Failed to initialize audit manager: {}", e);
         }
     }
-
-    renderer
-        .render_snapshot(&snapshot, details)
-        .map_err(|e| anyhow::anyhow!("Render error: {}", e))?;
-    Ok(())
 }
 
-/// Execute watch mode
+/// Execute watch mode. With `watch_count` it stops after that many refreshes, and
+/// fails if any refresh breached one of `alerts`.
+#[allow(clippy::too_many_arguments)]
 async fn execute_watch_mode(
     details: bool,
+    tree: bool,
     containers: bool,
+    show_rates: bool,
+    watch_count: Option<u64>,
+    alerts: crate::snapshot::AlertThresholds,
     vendor_filter: Option<VendorFilter>,
+    gpu_filter: Option<u16>,
+    audit: ListAudit,
+    timing: bool,
     renderer: Renderer,
     gpu_manager: GpuManager,
     config_manager: crate::config::ConfigManager,
 ) -> Result<()> {
-    let _interval = Duration::from_secs(config_manager.config().watch_interval_secs);
+    let interval = Duration::from_secs(config_manager.config().watch_interval_secs);
+    // Keep the totals on the last line while the tables above them refresh
+    let renderer = renderer.with_pinned_totals();
 
     info!(
         "Starting watch mode (refresh every {}s). Press Ctrl-C to stop.",
         config_manager.config().watch_interval_secs
     );
 
+    // Previous iteration, kept to compute --show-rates deltas
+    let mut rate_tracker = show_rates.then(crate::snapshot::RateTracker::default);
+    // Recent samples of each process, drawn as trends in the --details process table
+    let mut history = crate::proc_history::ProcessHistory::default();
+    // Every --alert-* breach seen so far
+    let mut breaches = Vec::new();
+
+    let mut iteration = 0;
     loop {
+        iteration += 1;
         // Clear screen BEFORE rendering new data so users see the data
         // during the entire sleep interval (matches standard `watch` behavior)
         if matches!(renderer.get_output_format(), OutputFormat::Table) {
             renderer.clear_screen();
         }
 
-        match execute_single_list(details, containers, &vendor_filter, &renderer, &gpu_manager)
-            .await
+        match execute_single_list(
+            details,
+            tree,
+            containers,
+            &vendor_filter,
+            gpu_filter,
+            None,
+            None,
+            rate_tracker.as_mut(),
+            Some(&mut history),
+            audit,
+            timing,
+            &renderer,
+            &gpu_manager,
+        )
+        .await
         {
-            Ok(()) => {
+            Ok(snapshot) => {
                 // Data is now visible during the entire sleep interval
+                for breach in alerts.breaches(&snapshot) {
+                    renderer.warning(&format!("Alert: {}", breach));
+                    breaches.push(breach);
+                }
             }
             Err(e) => {
                 warn!("Failed to refresh data: {}", e);
             }
         }
 
-        tokio::time::sleep(tokio::time::Duration::from_secs(
-            config_manager.config().watch_interval_secs,
-        ))
-        .await;
+        if watch_count.is_some_and(|count| iteration >= count) {
+            break;
+        }
+        tokio::time::sleep(interval).await;
+    }
+
+    if breaches.is_empty() {
+        return Ok(());
+    }
+    Err(anyhow::anyhow!(
+        "Alert thresholds exceeded {} time(s):\n{}",
+        breaches.len(),
+        breaches
+            .iter()
+            .map(|breach| format!("  {}", breach))
+            .collect::<Vec<_>>()
+            .join("\n")
+    ))
+}
+
+/// Print one line per refresh with the memory and GPU utilization of `pid`
+/// (`--watch --follow-pid`), until Ctrl-C or `watch_count` refreshes
+async fn execute_follow_pid(
+    pid: u32,
+    watch_count: Option<u64>,
+    gpu_filter: Option<u16>,
+    gpu_manager: &GpuManager,
+    config_manager: &crate::config::ConfigManager,
+) -> Result<()> {
+    let interval = Duration::from_secs(config_manager.config().watch_interval_secs);
+    info!(
+        "Following PID {} (refresh every {}s). Press Ctrl-C to stop.",
+        pid,
+        config_manager.config().watch_interval_secs
+    );
+
+    let mut iteration = 0;
+    loop {
+        iteration += 1;
+        let refresh = async {
+            let mut gpus = gpu_manager.collect_snapshots_async().await?.snapshots;
+            let mut procs = gpu_manager.get_all_processes_async().await?;
+            if let Some(index) = gpu_filter {
+                gpus.retain(|gpu| gpu.gpu_index == index);
+                procs.retain(|p| p.gpu_index == index);
+            }
+            Ok::<_, anyhow::Error>(Snapshot {
+                host: crate::util::get_hostname(),
+                ts: crate::util::get_current_timestamp_iso(),
+                gpus,
+                procs,
+                degraded: false,
+                gpu_status: Vec::new(),
+                errors: Vec::new(),
+            })
+        };
+        match refresh.await {
+            Ok(snapshot) => {
+                for line in crate::proc_history::follow_lines(&snapshot, pid) {
+                    println!("{}", line);
+                }
+            }
+            Err(e) => warn!("Failed to refresh data: {}", e),
+        }
+
+        if watch_count.is_some_and(|count| iteration >= count) {
+            return Ok(());
+        }
+        tokio::time::sleep(interval).await;
     }
 }
 
@@ -319,84 +1041,221 @@ fn execute_kill_operation(
     pid: Option<u32>,
     timeout_secs: u16,
     force: bool,
+    signal: KillSignal,
     filter: Option<String>,
+    filter_user: Option<String>,
+    match_cmdline: bool,
+    job: Option<String>,
+    use_scancel: bool,
     batch: bool,
+    with_children: bool,
+    with_parent: bool,
+    allow_protected: bool,
+    allow_self: bool,
     gpu_id: Option<u16>,
     dry_run: bool,
+    assume_yes: bool,
     gpu_manager: GpuManager,
-    _config_manager: crate::config::ConfigManager,
+    config_manager: crate::config::ConfigManager,
+    renderer: &Renderer,
 ) -> Result<()> {
-    // Initialize process manager for enhanced operations
-    let nvml_api = match NvmlApi::new() {
-        Ok(api) => api,
-        Err(e) => {
-            // Friendlier error when non-NVIDIA vendors are present
-            let available_vendors = gpu_manager.get_vendors();
-            if !available_vendors.is_empty()
-                && !available_vendors.contains(&crate::vendor::GpuVendor::Nvidia)
-            {
-                return Err(anyhow::anyhow!(
-                    "Kill operations currently require NVIDIA/NVML. Detected vendors: {:?}. Use --list/watch/audit, or run on a NVIDIA node.",
-                    available_vendors
-                ));
-            }
-            return Err(anyhow::anyhow!(
-                "Failed to initialize NVML. Ensure NVIDIA drivers are installed and GPU is accessible. ({})",
-                e
-            ));
-        }
-    };
-    let proc_manager = ProcessManager::new(nvml_api);
-    let mut enhanced_manager = EnhancedProcessManager::new(proc_manager);
-
-    if let Some(filter_pattern) = filter {
-        // Batch kill based on filter
+    let started = std::time::Instant::now();
+    let mut report = KillReport::new(dry_run, signal, timeout_secs, force);
+    let protected_names = &config_manager.config().protected_processes;
+    let mut enhanced_manager = init_process_manager(&gpu_manager, signal)?
+        .with_protection(protected_names, allow_protected);
+    enhanced_manager
+        .process_manager
+        .set_allow_self(allow_self && force);
+    let tree_kill = with_children || with_parent;
+    let protection = KillProtection::snapshot(protected_names)
+        .allowing_self(&enhanced_manager.process_manager.self_guard());
+
+    if filter.is_some() || filter_user.is_some() {
+        // Batch kill based on filter and/or user; both must match when given
         let all_processes = gpu_manager.get_all_processes()?;
-        let filtered_processes =
-            enhanced_manager.filter_processes_by_name(&all_processes, &filter_pattern)?;
+        let filtered_processes = crate::process_mgmt::filter_kill_targets(
+            &all_processes,
+            filter.as_deref(),
+            match_cmdline,
+            filter_user.as_deref(),
+        )?;
+        let criteria = kill_filter_description(filter.as_deref(), filter_user.as_deref());
 
         if filtered_processes.is_empty() {
-            render_warning(&format!(
-                "No processes found matching pattern: {}",
-                filter_pattern
+            renderer.warning(&format!("No processes found matching {}", criteria));
+        } else {
+            renderer.info(&format!(
+                "Found {} processes matching {}",
+                filtered_processes.len(),
+                criteria
             ));
-            return Ok(());
-        }
-
-        render_info(&format!(
-            "Found {} processes matching pattern '{}'",
-            filtered_processes.len(),
-            filter_pattern
-        ));
-
-        if batch {
-            let killed_pids = if dry_run {
-                // Preview only
-                render_info("Dry-run: would kill the following processes:");
-                for p in &filtered_processes {
-                    render_info(&format!(
+            let confirmed =
+                confirm_without_batch(&filtered_processes, batch, dry_run, assume_yes, renderer)?;
+            let (batch, assume_yes) = (batch || confirmed, assume_yes || confirmed);
+            if tree_kill && (batch || dry_run) {
+                let targets: Vec<u32> = filtered_processes.iter().map(|p| p.pid).collect();
+                execute_tree_kill(
+                    &mut enhanced_manager,
+                    &targets,
+                    with_parent,
+                    with_children,
+                    &protection,
+                    allow_protected,
+                    &mut report,
+                    renderer,
+                )?;
+            } else if dry_run {
+                let (targets, skipped) = screen_protected(
+                    filtered_processes,
+                    &protection,
+                    allow_protected,
+                    dry_run,
+                    renderer,
+                )?;
+                render_kill_preview(&targets, &skipped, &mut report, renderer);
+            } else if !batch {
+                // Nobody to ask: only show the processes
+                for proc in &filtered_processes {
+                    renderer.info(&format!(
                         "  PID {}: {} ({}) - {} MB",
-                        p.pid, p.proc_name, p.user, p.used_mem_mb
+                        proc.pid,
+                        proc.command(),
+                        proc.user,
+                        proc.used_mem_mb
                     ));
+                    report.push(
+                        KillOutcome::for_process(proc, KillStatus::NotKilled)
+                            .with_reason("--batch not given"),
+                    );
                 }
-                Vec::new()
+                renderer.warning("Use --batch flag to actually kill these processes");
             } else {
-                enhanced_manager.batch_kill_processes(&filtered_processes, timeout_secs, force)?
-            };
-            render_success(&format!(
-                "Successfully killed {} processes: {:?}",
-                killed_pids.len(),
-                killed_pids
+                let (targets, skipped) = screen_protected(
+                    filtered_processes,
+                    &protection,
+                    allow_protected,
+                    dry_run,
+                    renderer,
+                )?;
+                confirm_batch_kill(&targets, assume_yes, renderer)?;
+                kill_batch(
+                    &mut enhanced_manager,
+                    &targets,
+                    skipped,
+                    &mut report,
+                    renderer,
+                )?;
+                renderer.success(&format!(
+                    "Successfully killed {} processes: {:?}",
+                    report.count(KillStatus::Killed),
+                    pids_with_status(&report, KillStatus::Killed)
+                ));
+            }
+        }
+    } else if let Some(job_id) = job {
+        // Kill the GPU processes of a Slurm job, or cancel the whole job with scancel
+        if !crate::slurm::is_available() {
+            return Err(anyhow::anyhow!(
+                "--job is not supported on this host: scontrol and squeue were not found"
             ));
-        } else {
-            // Show processes and ask for confirmation (for now, just show them)
-            for proc in &filtered_processes {
-                render_info(&format!(
+        }
+        let job_processes =
+            crate::slurm::processes_of_job(&gpu_manager.get_all_processes()?, &job_id);
+        renderer.info(&format!(
+            "Found {} GPU processes in Slurm job {}",
+            job_processes.len(),
+            job_id
+        ));
+        let confirmed = !use_scancel
+            && confirm_without_batch(&job_processes, batch, dry_run, assume_yes, renderer)?;
+        let (batch, assume_yes) = (batch || confirmed, assume_yes || confirmed);
+
+        if use_scancel {
+            if dry_run {
+                renderer.info(&format!("Dry-run: would run scancel {}", job_id));
+                for p in &job_processes {
+                    report.push(
+                        KillOutcome::for_process(p, KillStatus::WouldKill)
+                            .with_reason(format!("scancel {}", job_id)),
+                    );
+                }
+            } else if !batch && !should_confirm(assume_yes, renderer) {
+                renderer.warning(&format!(
+                    "Use --batch to confirm cancelling Slurm job {}",
+                    job_id
+                ));
+            } else {
+                confirm_destructive(
+                    &format!("Cancel Slurm job {}?", job_id),
+                    assume_yes,
+                    renderer,
+                )?;
+                crate::slurm::scancel(&job_id, &crate::command::SystemCommandRunner)?;
+                for p in &job_processes {
+                    let mut outcome = KillOutcome::for_process(p, KillStatus::Killed);
+                    outcome.signal = Some("scancel".to_string());
+                    report.push(outcome);
+                }
+                renderer.success(&format!("Cancelled Slurm job {}", job_id));
+            }
+        } else if job_processes.is_empty() {
+            renderer.warning(&format!("No GPU processes found for Slurm job {}", job_id));
+        } else if tree_kill && (batch || dry_run) {
+            let targets: Vec<u32> = job_processes.iter().map(|p| p.pid).collect();
+            execute_tree_kill(
+                &mut enhanced_manager,
+                &targets,
+                with_parent,
+                with_children,
+                &protection,
+                allow_protected,
+                &mut report,
+                renderer,
+            )?;
+        } else if dry_run {
+            let (targets, skipped) = screen_protected(
+                job_processes,
+                &protection,
+                allow_protected,
+                dry_run,
+                renderer,
+            )?;
+            render_kill_preview(&targets, &skipped, &mut report, renderer);
+        } else if !batch {
+            renderer.warning("Use --batch to confirm killing all GPU processes of this job");
+            for p in &job_processes {
+                renderer.info(&format!(
                     "  PID {}: {} ({}) - {} MB",
-                    proc.pid, proc.proc_name, proc.user, proc.used_mem_mb
+                    p.pid, p.proc_name, p.user, p.used_mem_mb
                 ));
+                report.push(
+                    KillOutcome::for_process(p, KillStatus::NotKilled)
+                        .with_reason("--batch not given"),
+                );
             }
-            render_warning("Use --batch flag to actually kill these processes");
+        } else {
+            let (targets, skipped) = screen_protected(
+                job_processes,
+                &protection,
+                allow_protected,
+                dry_run,
+                renderer,
+            )?;
+            confirm_batch_kill(&targets, assume_yes, renderer)?;
+            kill_batch(
+                &mut enhanced_manager,
+                &targets,
+                skipped,
+                &mut report,
+                renderer,
+            )?;
+            renderer.success(&format!(
+                "Successfully killed {} processes of Slurm job {}: {:?}",
+                report.count(KillStatus::Killed),
+                job_id,
+                pids_with_status(&report, KillStatus::Killed)
+            ));
         }
     } else if let Some(target_pid) = pid {
         // Single process kill
@@ -409,122 +1268,1044 @@ fn execute_kill_operation(
         let process_info = enhanced_manager
             .process_manager
             .get_process_info(target_pid)?;
-        render_info(&format!(
+        renderer.info(&format!(
             "Terminating process {} ({}: {})",
             target_pid, process_info.user, process_info.name
         ));
+        let gpu_proc = gpu_manager
+            .get_all_processes()
+            .ok()
+            .and_then(|procs| procs.into_iter().find(|p| p.pid == target_pid));
+        let outcome = |status| {
+            let mut outcome = match &gpu_proc {
+                Some(p) => KillOutcome::for_process(p, status),
+                None => KillOutcome::for_pid(target_pid, status),
+            };
+            outcome.process = Some(process_info.name.clone());
+            outcome.user = Some(process_info.user.clone());
+            outcome
+        };
+        if !dry_run && !force && !batch {
+            confirm_destructive(
+                &format!(
+                    "Kill process {} ({}: {})?",
+                    target_pid, process_info.user, process_info.name
+                ),
+                assume_yes,
+                renderer,
+            )?;
+        }
 
-        if dry_run {
-            render_info(&format!(
-                "Dry-run: would terminate process {} (timeout {}s, force: {})",
-                target_pid, timeout_secs, force
-            ));
-        } else {
-            // Perform graceful kill
-            enhanced_manager
-                .process_manager
-                .graceful_kill(target_pid, timeout_secs, force)?;
-            render_success(&format!("Process {} terminated successfully", target_pid));
+        if tree_kill {
+            execute_tree_kill(
+                &mut enhanced_manager,
+                &[target_pid],
+                with_parent,
+                with_children,
+                &protection,
+                allow_protected,
+                &mut report,
+                renderer,
+            )?;
+        } else if dry_run {
+            if let Some(reason) =
+                protection.skip_reason(target_pid, &process_info.name, allow_protected)
+            {
+                renderer.warning(&format!(
+                    "Dry-run: would refuse to kill PID {}: {}",
+                    target_pid, reason
+                ));
+                report.push(outcome(KillStatus::WouldSkip).with_reason(reason));
+            } else {
+                info!(
+                    pid = target_pid,
+                    user = %process_info.user,
+                    dry_run = true,
+                    signal = signal.name(),
+                    timeout_secs,
+                    force,
+                    "Would terminate process"
+                );
+                renderer.info(&format!(
+                    "Dry-run: would send {} to process {} (timeout {}s, force: {})",
+                    signal, target_pid, timeout_secs, force
+                ));
+                report.push(outcome(KillStatus::WouldKill));
+            }
+        } else {
+            if allow_protected {
+                if let Some(reason) = protection.reason(target_pid, &process_info.name) {
+                    confirm_protected_kill(
+                        &[format!("PID {} ({})", target_pid, reason)],
+                        renderer,
+                    )?;
+                }
+            }
+            // Perform graceful kill; protected processes are refused
+            let kill_started = std::time::Instant::now();
+            let ended_by = enhanced_manager.kill_process(target_pid, timeout_secs, force)?;
+            let mut killed = outcome(KillStatus::Killed);
+            killed.signal = Some(ended_by.to_string());
+            killed.duration_ms = Some(kill_started.elapsed().as_millis() as u64);
+            report.push(killed);
+            renderer.success(&format!("Process {} terminated successfully", target_pid));
         }
     } else if let Some(target_gpu) = gpu_id {
-        // Kill all processes on a specific GPU
-        let all_processes = gpu_manager.get_all_processes()?;
-        let gpu_processes: Vec<_> = all_processes
+        // Kill all processes on a specific GPU; one that stopped answering is an
+        // error rather than "no processes"
+        let collection = gpu_manager.collect_processes()?;
+        if let Some((_, error)) = collection
+            .unavailable
+            .iter()
+            .find(|(index, _)| *index == target_gpu)
+        {
+            return Err(anyhow::anyhow!(
+                "GPU {} is unavailable: {}",
+                target_gpu,
+                error
+            ));
+        }
+        let gpu_processes: Vec<_> = collection
+            .processes
             .into_iter()
             .filter(|p| p.gpu_index == target_gpu)
             .collect();
 
         if gpu_processes.is_empty() {
-            render_warning(&format!("No processes found on GPU {}", target_gpu));
-            return Ok(());
+            renderer.warning(&format!("No processes found on GPU {}", target_gpu));
+        } else {
+            renderer.info(&format!(
+                "Found {} processes on GPU {}",
+                gpu_processes.len(),
+                target_gpu
+            ));
+            let confirmed =
+                confirm_without_batch(&gpu_processes, batch, dry_run, assume_yes, renderer)?;
+            let (batch, assume_yes) = (batch || confirmed, assume_yes || confirmed);
+
+            if tree_kill && (batch || dry_run) {
+                let targets: Vec<u32> = gpu_processes.iter().map(|p| p.pid).collect();
+                execute_tree_kill(
+                    &mut enhanced_manager,
+                    &targets,
+                    with_parent,
+                    with_children,
+                    &protection,
+                    allow_protected,
+                    &mut report,
+                    renderer,
+                )?;
+            } else if dry_run {
+                let (targets, skipped) = screen_protected(
+                    gpu_processes,
+                    &protection,
+                    allow_protected,
+                    dry_run,
+                    renderer,
+                )?;
+                render_kill_preview(&targets, &skipped, &mut report, renderer);
+            } else if !batch {
+                renderer.warning("Use --batch to confirm killing all processes on this GPU");
+                for p in &gpu_processes {
+                    renderer.info(&format!(
+                        "  PID {}: {} ({}) - {} MB",
+                        p.pid, p.proc_name, p.user, p.used_mem_mb
+                    ));
+                    report.push(
+                        KillOutcome::for_process(p, KillStatus::NotKilled)
+                            .with_reason("--batch not given"),
+                    );
+                }
+            } else {
+                let (targets, skipped) = screen_protected(
+                    gpu_processes,
+                    &protection,
+                    allow_protected,
+                    dry_run,
+                    renderer,
+                )?;
+                confirm_batch_kill(&targets, assume_yes, renderer)?;
+                kill_batch(
+                    &mut enhanced_manager,
+                    &targets,
+                    skipped,
+                    &mut report,
+                    renderer,
+                )?;
+                renderer.success(&format!(
+                    "Successfully killed {} processes on GPU {}: {:?}",
+                    report.count(KillStatus::Killed),
+                    target_gpu,
+                    pids_with_status(&report, KillStatus::Killed)
+                ));
+            }
         }
-
-        render_info(&format!(
-            "Found {} processes on GPU {}",
-            gpu_processes.len(),
-            target_gpu
+    } else {
+        return Err(anyhow::anyhow!(
+            "Either --pid, --filter, or --gpu must be specified"
         ));
+    }
 
-        if dry_run {
-            render_info("Dry-run: would kill the following processes:");
-            for p in &gpu_processes {
-                render_info(&format!(
-                    "  PID {}: {} ({}) - {} MB",
-                    p.pid, p.proc_name, p.user, p.used_mem_mb
+    renderer
+        .render_result(&report.finish(started))
+        .map_err(|e| anyhow::anyhow!("Render error: {}", e))
+}
+
+/// Kill `targets` and record each outcome, naming the processes that needed
+/// SIGKILL or survived. Fails with the partial report when any process survives.
+fn kill_batch(
+    enhanced_manager: &mut EnhancedProcessManager,
+    targets: &[crate::nvml_api::GpuProc],
+    screened_out: Vec<SkippedProcess>,
+    report: &mut KillReport,
+    renderer: &Renderer,
+) -> Result<()> {
+    let timeout_secs = report.timeout_secs;
+    let force = report.force;
+    let mut outcome = enhanced_manager.batch_kill(targets, timeout_secs, force);
+    outcome.skipped.extend(screened_out);
+    for process in outcome.outcomes() {
+        let name = process.process.as_deref().unwrap_or("unknown");
+        if process.status == KillStatus::Failed {
+            renderer.warning(&format!(
+                "PID {} ({}) was not killed: {}",
+                process.pid,
+                name,
+                process.reason.as_deref().unwrap_or("unknown error")
+            ));
+        } else if process.needed_sigkill() && report.signal != "SIGKILL" {
+            renderer.info(&format!(
+                "PID {} ({}) ignored {} for {}s and was killed with SIGKILL",
+                process.pid, name, report.signal, timeout_secs
+            ));
+        }
+    }
+    report.push_batch(&outcome);
+
+    if !outcome.failed.is_empty() {
+        return Err(PartialFailure::new(outcome.failure_message(), report).into());
+    }
+    Ok(())
+}
+
+/// PIDs in `report` with the given status
+fn pids_with_status(report: &KillReport, status: KillStatus) -> Vec<u32> {
+    report
+        .processes
+        .iter()
+        .filter(|p| p.status == status)
+        .map(|p| p.pid)
+        .collect()
+}
+
+/// Kill processes that have held GPU memory at near-zero utilization for `idle_for`,
+/// optionally only those holding more than `min_mem_mb` throughout
+#[allow(clippy::too_many_arguments)]
+async fn execute_idle_kill_operation(
+    idle_for: Duration,
+    max_util_pct: f32,
+    min_mem_mb: Option<u32>,
+    warn_for: Option<Duration>,
+    gpu_id: Option<u16>,
+    batch: bool,
+    timeout_secs: u16,
+    force: bool,
+    signal: KillSignal,
+    dry_run: bool,
+    allow_protected: bool,
+    protected_names: &[String],
+    gpu_manager: GpuManager,
+    renderer: &Renderer,
+) -> Result<()> {
+    use crate::idle_reaper::{find_idle_processes, still_idle, IdleCriteria, IdleExemptions};
+
+    let started = std::time::Instant::now();
+    let mut report = KillReport::new(dry_run, signal, timeout_secs, force);
+    let render_report = |report: KillReport| {
+        renderer
+            .render_result(&report.finish(started))
+            .map_err(|e| anyhow::anyhow!("Render error: {}", e))
+    };
+
+    let mut enhanced_manager = init_process_manager(&gpu_manager, signal)?
+        .with_protection(protected_names, allow_protected);
+    let processes: Vec<_> = gpu_manager
+        .get_all_processes()?
+        .into_iter()
+        .filter(|p| gpu_id.is_none_or(|gpu| p.gpu_index == gpu))
+        .collect();
+
+    let now = std::time::SystemTime::now();
+    let ages: std::collections::HashMap<u32, Duration> = processes
+        .iter()
+        .filter_map(|p| {
+            let info = enhanced_manager
+                .process_manager
+                .get_process_info(p.pid)
+                .ok()?;
+            Some((p.pid, now.duration_since(info.start_time).ok()?))
+        })
+        .collect();
+
+    let criteria = IdleCriteria::new(idle_for)
+        .with_max_util_pct(max_util_pct)
+        .with_min_mem_mb(min_mem_mb);
+    let idle_description = match min_mem_mb {
+        Some(min) => format!(
+            "idle (<= {}% utilization, holding more than {} GiB)",
+            max_util_pct,
+            crate::util::format_memory_mb_to_gib(min)
+        ),
+        None => format!("idle (<= {}% utilization)", max_util_pct),
+    };
+    let until = chrono::Utc::now();
+    let since = until
+        - chrono::Duration::from_std(idle_for + criteria.max_sample_gap)
+            .context("Idle window is too large")?;
+    let audit_manager = crate::audit::AuditManager::new()
+        .await
+        .context("Failed to initialize audit manager")?;
+    let records = audit_manager
+        .query_records_range(since, until, None, None)
+        .await?;
+
+    // Whitelisted rogue-detection users and the guard idle whitelist are never reaped
+    let mut exemptions = IdleExemptions::default();
+    let rogue_config = crate::rogue_config::RogueConfigManager::new()
+        .context("Failed to initialize rogue config manager")?;
+    exemptions.users.extend(
+        rogue_config
+            .get_config()
+            .patterns
+            .user_whitelist
+            .iter()
+            .cloned(),
+    );
+    if let Some(guard) = crate::guard_mode::GuardModeManager::load_existing()? {
+        let whitelist = &guard.get_config().idle_whitelist;
+        exemptions.users.extend(whitelist.users.iter().cloned());
+        exemptions
+            .processes
+            .extend(whitelist.processes.iter().cloned());
+    }
+
+    let mut candidates =
+        find_idle_processes(&records, &processes, &ages, until, &criteria, &exemptions);
+    let protection = KillProtection::snapshot(protected_names);
+    candidates.retain(|c| {
+        let p = &c.process;
+        match protection.skip_reason(p.pid, &p.proc_name, allow_protected) {
+            Some(reason) => {
+                renderer.warning(&format!(
+                    "Skipping idle PID {} ({}): {}",
+                    p.pid, p.proc_name, reason
                 ));
+                let status = if dry_run {
+                    KillStatus::WouldSkip
+                } else {
+                    KillStatus::Skipped
+                };
+                report.push(KillOutcome::for_process(p, status).with_reason(reason));
+                false
             }
-            return Ok(());
+            None => true,
         }
+    });
+    if candidates.is_empty() {
+        renderer.info(&format!(
+            "No processes {} for {}",
+            idle_description,
+            crate::util::format_duration(idle_for)
+        ));
+        if records.is_empty() {
+            renderer.warning(
+                "No audit samples in the idle window; run 'gpukill --list --watch' (or a periodic 'gpukill --list') to record them",
+            );
+        }
+        return render_report(report);
+    }
 
-        if !batch {
-            render_warning("Use --batch to confirm killing all processes on this GPU");
-            for p in &gpu_processes {
-                render_info(&format!(
-                    "  PID {}: {} ({}) - {} MB",
-                    p.pid, p.proc_name, p.user, p.used_mem_mb
+    renderer.info(&format!(
+        "Found {} processes {} for at least {}",
+        candidates.len(),
+        idle_description,
+        crate::util::format_duration(idle_for)
+    ));
+    for c in &candidates {
+        let p = &c.process;
+        if dry_run {
+            info!(
+                pid = p.pid,
+                user = %p.user,
+                gpu_index = p.gpu_index,
+                process = %p.proc_name,
+                memory_mb = p.used_mem_mb,
+                idle_secs = c.idle_for.as_secs(),
+                dry_run = true,
+                "Would terminate idle process"
+            );
+        }
+        renderer.info(&format!(
+            "  PID {}: {} ({}) on GPU {} - {} held, idle for {} (avg {:.1}% over {} samples)",
+            p.pid,
+            p.proc_name,
+            p.user,
+            p.gpu_index,
+            crate::util::format_memory_mb_to_gib(p.used_mem_mb),
+            crate::util::format_duration(c.idle_for),
+            c.avg_util_pct,
+            c.samples
+        ));
+    }
+
+    if dry_run || !batch {
+        let (status, reason) = if dry_run {
+            renderer.info("Dry-run: no processes were killed");
+            (KillStatus::WouldKill, None)
+        } else {
+            renderer.warning("Use --batch to kill these idle processes");
+            (KillStatus::NotKilled, Some("--batch not given"))
+        };
+        for c in &candidates {
+            let outcome = KillOutcome::for_process(&c.process, status);
+            report.push(match reason {
+                Some(reason) => outcome.with_reason(reason),
+                None => outcome,
+            });
+        }
+        return render_report(report);
+    }
+
+    let mut targets = candidates;
+    if let Some(warn_for) = warn_for {
+        for c in &targets {
+            let p = &c.process;
+            warn!(
+                pid = p.pid,
+                user = %p.user,
+                gpu_index = p.gpu_index,
+                process = %p.proc_name,
+                memory_mb = p.used_mem_mb,
+                idle_secs = c.idle_for.as_secs(),
+                grace_secs = warn_for.as_secs(),
+                "Idle process will be terminated"
+            );
+        }
+        renderer.warning(&format!(
+            "Waiting {} before killing; processes that become active again are spared",
+            crate::util::format_duration(warn_for)
+        ));
+        tokio::time::sleep(warn_for).await;
+
+        gpu_manager.invalidate_cache();
+        let snapshots = gpu_manager.get_all_snapshots_async().await?;
+        let current = gpu_manager.get_all_processes_async().await?;
+        targets.retain(|c| {
+            let idle = still_idle(c, &snapshots, &current, &criteria);
+            if !idle {
+                renderer.info(&format!(
+                    "Skipping PID {}: no longer idle or no longer running",
+                    c.process.pid
                 ));
+                report.push(
+                    KillOutcome::for_process(&c.process, KillStatus::Skipped)
+                        .with_reason("no longer idle or no longer running"),
+                );
             }
-            return Ok(());
+            idle
+        });
+        if targets.is_empty() {
+            renderer.info("No idle processes left to kill");
+            return render_report(report);
+        }
+    }
+
+    let procs: Vec<_> = targets.into_iter().map(|c| c.process).collect();
+    let (procs, skipped) =
+        screen_protected(procs, &protection, allow_protected, dry_run, renderer)?;
+    kill_batch(
+        &mut enhanced_manager,
+        &procs,
+        skipped,
+        &mut report,
+        renderer,
+    )?;
+    renderer.success(&format!(
+        "Successfully killed {} idle processes: {:?}",
+        report.count(KillStatus::Killed),
+        pids_with_status(&report, KillStatus::Killed)
+    ));
+    render_report(report)
+}
+
+/// Show or edit the protected process list in the config file
+fn execute_protected_list_operation(
+    cli: &Cli,
+    config_manager: &crate::config::ConfigManager,
+) -> Result<()> {
+    use crate::process_mgmt::DEFAULT_PROTECTED_PROCESSES;
+
+    if let Some(name) = &cli.protect_process {
+        let (path, added) =
+            crate::config::edit_config_file(cli.config.as_deref(), |c| c.protect_process(name))?;
+        if added {
+            render_success(&format!(
+                "Added '{}' to protected processes in {}",
+                name,
+                path.display()
+            ));
+        } else {
+            render_info(&format!("'{}' is already protected", name));
         }
+    }
 
-        let killed_pids =
-            enhanced_manager.batch_kill_processes(&gpu_processes, timeout_secs, force)?;
+    if let Some(name) = &cli.unprotect_process {
+        if DEFAULT_PROTECTED_PROCESSES.contains(&name.as_str()) {
+            return Err(anyhow::anyhow!(
+                "'{}' is protected by default and cannot be removed; use --allow-protected for a single kill",
+                name
+            ));
+        }
+        let (path, removed) =
+            crate::config::edit_config_file(cli.config.as_deref(), |c| c.unprotect_process(name))?;
+        if !removed {
+            return Err(anyhow::anyhow!(
+                "'{}' is not in the protected process list",
+                name
+            ));
+        }
         render_success(&format!(
-            "Successfully killed {} processes on GPU {}: {:?}",
-            killed_pids.len(),
-            target_gpu,
-            killed_pids
+            "Removed '{}' from protected processes in {}",
+            name,
+            path.display()
         ));
-    } else {
+    }
+
+    if cli.list_protected {
+        // Show the list as saved, including any edit above
+        let configured = if cli.protect_process.is_some() || cli.unprotect_process.is_some() {
+            crate::config::get_config(cli.config.clone())?
+                .config()
+                .protected_processes
+                .clone()
+        } else {
+            config_manager.config().protected_processes.clone()
+        };
+        if cli.output == OutputFormat::Json {
+            let json = serde_json::json!({
+                "default": DEFAULT_PROTECTED_PROCESSES,
+                "configured": configured,
+            });
+            println!("{}", serde_json::to_string_pretty(&json)?);
+        } else {
+            render_info(&format!("{}Protected by default:", icon(Icon::List)));
+            for name in DEFAULT_PROTECTED_PROCESSES {
+                render_info(&format!("  - {}", name));
+            }
+            render_info(&format!("{}Protected by configuration:", icon(Icon::List)));
+            if configured.is_empty() {
+                render_info("  (none)");
+            }
+            for name in &configured {
+                render_info(&format!("  - {}", name));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Initialize the process manager used by kill operations
+fn init_process_manager(
+    gpu_manager: &GpuManager,
+    signal: KillSignal,
+) -> Result<EnhancedProcessManager> {
+    // Kills need NVML, but go through the GPU manager so they refresh its cached process list
+    if let Err(e) = NvmlApi::new() {
+        // Friendlier error when non-NVIDIA vendors are present
+        let available_vendors = gpu_manager.get_vendors();
+        if !available_vendors.is_empty()
+            && !available_vendors.contains(&crate::vendor::GpuVendor::Nvidia)
+        {
+            return Err(anyhow::anyhow!(
+                "Kill operations currently require NVIDIA/NVML. Detected vendors: {:?}. Use --list/watch/audit, run on a NVIDIA node, or see `gpukill --capabilities` for what each vendor supports.",
+                available_vendors
+            ));
+        }
         return Err(anyhow::anyhow!(
-            "Either --pid, --filter, or --gpu must be specified"
+            "Failed to initialize NVML. Ensure NVIDIA drivers are installed and GPU is accessible. ({})",
+            e
         ));
     }
+    let mut proc_manager = ProcessManager::with_gpu_manager(gpu_manager.clone());
+    proc_manager.set_kill_signal(signal);
+    Ok(EnhancedProcessManager::new(proc_manager))
+}
 
+/// "pattern 'python' owned by bob" or "user bob", as shown when a filtered kill reports its matches
+fn kill_filter_description(pattern: Option<&str>, user: Option<&str>) -> String {
+    match (pattern, user) {
+        (Some(pattern), Some(user)) => format!("pattern '{}' owned by {}", pattern, user),
+        (Some(pattern), None) => format!("pattern '{}'", pattern),
+        (None, Some(user)) => format!("user {}", user),
+        (None, None) => "all processes".to_string(),
+    }
+}
+
+/// Show the processes a kill would target and those it would skip, logging one
+/// structured event for each
+fn render_kill_preview(
+    processes: &[crate::nvml_api::GpuProc],
+    skipped: &[SkippedProcess],
+    report: &mut KillReport,
+    renderer: &Renderer,
+) {
+    let (timeout_secs, force) = (report.timeout_secs, report.force);
+    renderer.info("Dry-run: would kill the following processes:");
+    for p in processes {
+        info!(
+            pid = p.pid,
+            user = %p.user,
+            gpu_index = p.gpu_index,
+            process = %p.proc_name,
+            memory_mb = p.used_mem_mb,
+            dry_run = true,
+            timeout_secs,
+            force,
+            "Would terminate process"
+        );
+        renderer.info(&format!(
+            "  PID {}: {} ({}) - {} MB",
+            p.pid, p.proc_name, p.user, p.used_mem_mb
+        ));
+    }
+    for (p, reason) in skipped {
+        info!(
+            pid = p.pid,
+            user = %p.user,
+            gpu_index = p.gpu_index,
+            process = %p.proc_name,
+            reason = %reason,
+            dry_run = true,
+            "Would skip protected process"
+        );
+        renderer.info(&format!(
+            "  PID {}: {} ({}) - {} MB (skipped: {})",
+            p.pid, p.proc_name, p.user, p.used_mem_mb, reason
+        ));
+    }
+    report.push_processes(processes, KillStatus::WouldKill);
+    report.push_skipped(skipped, KillStatus::WouldSkip);
+}
+
+/// Drop protected processes from a batch kill, warning about each one. Processes
+/// let through by `--allow-protected` need confirmation when attached to a terminal.
+fn screen_protected(
+    processes: Vec<crate::nvml_api::GpuProc>,
+    protection: &KillProtection,
+    allow_protected: bool,
+    dry_run: bool,
+    renderer: &Renderer,
+) -> Result<(Vec<crate::nvml_api::GpuProc>, Vec<SkippedProcess>)> {
+    let (targets, skipped) = protection.partition(processes, allow_protected);
+    if !dry_run {
+        for (p, reason) in &skipped {
+            warn!(pid = p.pid, process = %p.proc_name, reason = %reason, "Skipping protected process");
+            renderer.warning(&format!(
+                "Skipping PID {} ({}): {}",
+                p.pid, p.proc_name, reason
+            ));
+        }
+        let overridden: Vec<String> = targets
+            .iter()
+            .filter_map(|p| {
+                protection
+                    .reason(p.pid, &p.proc_name)
+                    .map(|reason| format!("PID {} ({})", p.pid, reason))
+            })
+            .collect();
+        confirm_protected_kill(&overridden, renderer)?;
+    }
+    if !skipped.is_empty() && !allow_protected {
+        renderer.info("Use --allow-protected to include protected processes");
+    }
+    Ok((targets, skipped))
+}
+
+/// "1 GPU", "3 GPUs", "2 processes"
+fn plural(count: usize, noun: &str) -> String {
+    match (count, noun.ends_with('s')) {
+        (1, _) => format!("{} {}", count, noun),
+        (_, true) => format!("{} {}es", count, noun),
+        (_, false) => format!("{} {}s", count, noun),
+    }
+}
+
+/// Confirmation question for a batch kill of `targets`
+fn batch_kill_question(targets: &[crate::nvml_api::GpuProc]) -> String {
+    let pids: std::collections::HashSet<u32> = targets.iter().map(|p| p.pid).collect();
+    let users: std::collections::HashSet<&str> = targets.iter().map(|p| p.user.as_str()).collect();
+    format!(
+        "Kill {} belonging to {}?",
+        plural(pids.len(), "process"),
+        plural(users.len(), "user")
+    )
+}
+
+/// Whether destructive operations should ask first: a person is at the terminal,
+/// `--yes` was not given and the output is not JSON
+fn should_confirm(assume_yes: bool, renderer: &Renderer) -> bool {
+    use std::io::IsTerminal;
+
+    crate::util::needs_confirmation(
+        assume_yes,
+        renderer.is_json(),
+        std::io::stdout().is_terminal() && std::io::stdin().is_terminal(),
+    )
+}
+
+/// Ask `question` on the terminal when [`should_confirm`] says so, failing unless
+/// the answer is yes
+fn confirm_destructive(question: &str, assume_yes: bool, renderer: &Renderer) -> Result<()> {
+    if !should_confirm(assume_yes, renderer) || ask(question, renderer)? {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!("Aborted: nothing was changed"))
+    }
+}
+
+/// Read a yes/no answer to `question` from stdin, prompting where
+/// [`Renderer::text`] writes
+fn ask(question: &str, renderer: &Renderer) -> Result<bool> {
+    let stdin = std::io::BufReader::new(std::io::stdin());
+    let timeout = crate::util::CONFIRM_TIMEOUT;
+    let confirmed = if renderer.is_json() {
+        crate::util::confirm(question, stdin, &mut std::io::stderr(), timeout)?
+    } else {
+        crate::util::confirm(question, stdin, &mut std::io::stdout(), timeout)?
+    };
+    Ok(confirmed)
+}
+
+/// Show the processes a batch kill targets and ask before killing them
+fn confirm_batch_kill(
+    targets: &[crate::nvml_api::GpuProc],
+    assume_yes: bool,
+    renderer: &Renderer,
+) -> Result<()> {
+    if targets.is_empty() || !should_confirm(assume_yes, renderer) {
+        return Ok(());
+    }
+    renderer.text(&format!("{}\n", renderer.format_process_list(targets)));
+    confirm_destructive(&batch_kill_question(targets), assume_yes, renderer)
+}
+
+/// Without --batch a kill of several processes only lists them, unless someone at
+/// the terminal can confirm it instead. Returns whether the targets were confirmed
+/// that way, in which case the kill goes ahead as if --batch and --yes were given.
+fn confirm_without_batch(
+    targets: &[crate::nvml_api::GpuProc],
+    batch: bool,
+    dry_run: bool,
+    assume_yes: bool,
+    renderer: &Renderer,
+) -> Result<bool> {
+    if batch || dry_run || targets.is_empty() || !should_confirm(assume_yes, renderer) {
+        return Ok(false);
+    }
+    confirm_batch_kill(targets, assume_yes, renderer)?;
+    Ok(true)
+}
+
+/// Ask before killing protected processes when stdin is a terminal
+fn confirm_protected_kill(protected: &[String], renderer: &Renderer) -> Result<()> {
+    use std::io::IsTerminal;
+
+    if protected.is_empty() {
+        return Ok(());
+    }
+    renderer.warning(&format!(
+        "--allow-protected: about to kill protected processes: {}",
+        protected.join(", ")
+    ));
+    if !std::io::stdin().is_terminal() {
+        return Ok(());
+    }
+    if ask(
+        &format!("Kill {} protected processes?", protected.len()),
+        renderer,
+    )? {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "Aborted: protected processes were not killed"
+        ))
+    }
+}
+
+/// Kill the process trees of `targets` bottom-up, or print them in dry-run mode
+#[allow(clippy::too_many_arguments)]
+fn execute_tree_kill(
+    enhanced_manager: &mut EnhancedProcessManager,
+    targets: &[u32],
+    with_parent: bool,
+    with_children: bool,
+    protection: &KillProtection,
+    allow_protected: bool,
+    report: &mut KillReport,
+    renderer: &Renderer,
+) -> Result<()> {
+    let (timeout_secs, force, dry_run) = (report.timeout_secs, report.force, report.dry_run);
+    let table = crate::process_mgmt::ProcessTable::snapshot();
+    let mut plan = table.kill_plan(targets, with_parent, with_children);
+    protection.restrict_plan(&mut plan, allow_protected);
+
+    let tree_outcome = |pid: u32, status| {
+        let mut outcome = KillOutcome::for_pid(pid, status);
+        outcome.process = table.get(pid).map(|n| n.name.clone());
+        outcome
+    };
+    let skipped_status = if dry_run {
+        KillStatus::WouldSkip
+    } else {
+        KillStatus::Skipped
+    };
+    for (pid, reason) in &plan.protected {
+        renderer.warning(&format!("Skipping PID {}: {}", pid, reason));
+        report.push(tree_outcome(*pid, skipped_status).with_reason(reason.clone()));
+    }
+    if plan.order.is_empty() {
+        return Err(anyhow::anyhow!("No killable processes in the process tree"));
+    }
+
+    if dry_run {
+        let protected: std::collections::HashMap<u32, &str> = plan
+            .protected
+            .iter()
+            .map(|(pid, reason)| (*pid, reason.as_str()))
+            .collect();
+        let include = plan.order.iter().chain(protected.keys()).copied().collect();
+        let tree = Renderer::new(OutputFormat::Table).format_process_tree(
+            &table,
+            &plan.roots,
+            &include,
+            |node| match protected.get(&node.pid) {
+                Some(reason) => format!("{} {} (skipped: {})", node.pid, node.name, reason),
+                None => format!(
+                    "{} {}",
+                    node.pid,
+                    crate::util::truncate_string(node.command(), 60)
+                ),
+            },
+        );
+        renderer.info("Dry-run: would kill the following process tree:");
+        renderer.text(&tree);
+        renderer.info(&format!(
+            "Kill order (children first): {:?} (timeout {}s, force: {})",
+            plan.order, timeout_secs, force
+        ));
+        for &pid in &plan.order {
+            report.push(tree_outcome(pid, KillStatus::WouldKill));
+        }
+        return Ok(());
+    }
+
+    let overridden: Vec<String> = plan
+        .order
+        .iter()
+        .filter_map(|&pid| {
+            let name = table.get(pid).map(|n| n.name.as_str()).unwrap_or_default();
+            protection
+                .reason(pid, name)
+                .map(|reason| format!("PID {} ({})", pid, reason))
+        })
+        .collect();
+    confirm_protected_kill(&overridden, renderer)?;
+
+    renderer.info(&format!(
+        "Terminating {} processes (children first): {:?}",
+        plan.order.len(),
+        plan.order
+    ));
+    let killed_pids = enhanced_manager.kill_pids(&plan.order, timeout_secs, force)?;
+    for &pid in &killed_pids {
+        report.push(tree_outcome(pid, KillStatus::Killed));
+    }
+    renderer.success(&format!(
+        "Successfully killed process tree: {} processes",
+        killed_pids.len()
+    ));
     Ok(())
 }
 
 /// Execute reset operation
-fn execute_reset_operation(
+#[allow(clippy::too_many_arguments)]
+async fn execute_reset_operation(
     gpu: Option<u16>,
     all: bool,
+    hard: bool,
+    wait: Option<Duration>,
     force: bool,
+    timeout_secs: u16,
+    signal: KillSignal,
     dry_run: bool,
+    assume_yes: bool,
     gpu_manager: GpuManager,
-    _config_manager: crate::config::ConfigManager,
+    config_manager: crate::config::ConfigManager,
+    renderer: &Renderer,
 ) -> Result<()> {
-    if all {
-        execute_reset_all_gpus(&gpu_manager, force, dry_run)
+    let started = std::time::Instant::now();
+    let mut report = ResetReport::new(dry_run, hard);
+    if hard {
+        execute_hard_reset(gpu_manager, force, &mut report, renderer)?;
+    } else if all {
+        execute_reset_all_gpus(&gpu_manager, force, assume_yes, &mut report, renderer)?;
     } else if let Some(gpu_id) = gpu {
-        execute_reset_single_gpu(&gpu_manager, gpu_id, force, dry_run)
+        let wait = wait.map(|timeout| DrainWait {
+            timeout,
+            poll: RESET_DRAIN_POLL,
+            kill_timeout_secs: timeout_secs,
+            kill_signal: signal,
+            protected_names: config_manager.config().protected_processes.clone(),
+        });
+        execute_reset_single_gpu(
+            &gpu_manager,
+            gpu_id,
+            wait,
+            force,
+            assume_yes,
+            &mut report,
+            renderer,
+        )
+        .await?;
     } else {
-        Err(anyhow::anyhow!("No GPU specified for reset operation"))
+        return Err(anyhow::anyhow!("No GPU specified for reset operation"));
+    }
+    renderer
+        .render_result(&report.finish(started))
+        .map_err(|e| anyhow::anyhow!("Render error: {}", e))
+}
+
+/// Apply `--set` power and clock changes to one GPU, or with `dry_run` describe them
+fn execute_set_operation(
+    gpu: Option<u16>,
+    power_limit: Option<u32>,
+    lock_clocks: Option<u32>,
+    reset_clocks: bool,
+    dry_run: bool,
+    gpu_manager: &GpuManager,
+    renderer: &Renderer,
+) -> Result<()> {
+    let index = gpu.ok_or_else(|| anyhow::anyhow!("No GPU specified for --set"))?;
+    let vendor = gpu_manager
+        .vendor_of(u32::from(index))
+        .ok_or_else(|| anyhow::anyhow!("GPU index {} not found", index))?;
+    if !vendor.capabilities().power_limit_supported {
+        return Err(crate::vendor::unsupported_operation(
+            vendor,
+            "Changing power limits and clocks",
+        ));
+    }
+    let limits = gpu_manager
+        .get_all_snapshots()?
+        .into_iter()
+        .find(|snapshot| snapshot.gpu_index == index)
+        .and_then(|snapshot| snapshot.power_limit);
+
+    if dry_run {
+        for change in describe_set_changes(index, power_limit, lock_clocks, reset_clocks, limits) {
+            renderer.info(&format!("Dry-run: would {}", change));
+        }
+        return Ok(());
+    }
+
+    if let Some(watts) = power_limit {
+        gpu_manager.set_power_limit(u32::from(index), watts)?;
+        info!(gpu_index = index, watts, "Set power limit");
+        renderer.success(&format!("Set GPU {} power limit to {} W", index, watts));
+    }
+    if let Some(mhz) = lock_clocks {
+        gpu_manager.lock_clocks(u32::from(index), mhz)?;
+        info!(gpu_index = index, mhz, "Locked clocks");
+        renderer.success(&format!("Locked GPU {} clocks at {} MHz", index, mhz));
+    }
+    if reset_clocks {
+        gpu_manager.reset_clocks(u32::from(index))?;
+        info!(gpu_index = index, "Reset clocks");
+        renderer.success(&format!("Reset GPU {} clocks", index));
+    }
+    Ok(())
+}
+
+/// One line per change `--set` would make, with the current limits for context
+fn describe_set_changes(
+    index: u16,
+    power_limit: Option<u32>,
+    lock_clocks: Option<u32>,
+    reset_clocks: bool,
+    limits: Option<crate::nvml_api::PowerLimits>,
+) -> Vec<String> {
+    let mut changes = Vec::new();
+    if let Some(watts) = power_limit {
+        changes.push(match limits {
+            Some(limits) => format!(
+                "set GPU {} power limit to {} W (currently {:.0} W, default {:.0} W)",
+                index, watts, limits.current_w, limits.default_w
+            ),
+            None => format!("set GPU {} power limit to {} W", index, watts),
+        });
+    }
+    if let Some(mhz) = lock_clocks {
+        changes.push(format!("lock GPU {} clocks at {} MHz", index, mhz));
     }
+    if reset_clocks {
+        changes.push(format!("reset GPU {} clocks to driver control", index));
+    }
+    changes
 }
 
 /// Execute reset for all GPUs
-fn execute_reset_all_gpus(gpu_manager: &GpuManager, force: bool, dry_run: bool) -> Result<()> {
+fn execute_reset_all_gpus(
+    gpu_manager: &GpuManager,
+    force: bool,
+    assume_yes: bool,
+    report: &mut ResetReport,
+    renderer: &Renderer,
+) -> Result<()> {
     let device_count = gpu_manager.total_device_count()?;
 
     if device_count == 0 {
         return Err(anyhow::anyhow!("No GPUs found"));
     }
 
-    if dry_run {
-        render_info(&format!("Dry-run: would reset all {} GPUs", device_count));
+    if report.dry_run {
+        let targets: Vec<(u16, &str)> = (0..device_count)
+            .map(|i| (i as u16, gpu_manager.reset_mechanism_of(i)))
+            .collect();
+        let processes = gpu_manager.get_all_processes()?;
+        for line in preview_reset(&targets, &processes, force, report) {
+            renderer.info(&line);
+        }
         return Ok(());
     } else {
-        render_info(&format!("Resetting all {} GPUs", device_count));
+        renderer.info(&format!("Resetting all {} GPUs", device_count));
     }
 
     // Check for active processes if not forcing
     if !force {
-        let active_processes = gpu_manager.get_all_processes()?;
+        let collection = gpu_manager.collect_processes()?;
+        for (index, error) in &collection.unavailable {
+            renderer.warning(&format!(
+                "Could not check GPU {} for active processes: {}",
+                index, error
+            ));
+        }
+        let active_processes = collection.processes;
 
         if !active_processes.is_empty() {
-            render_warning("Active GPU processes found:");
+            renderer.warning("Active GPU processes found:");
             for proc in &active_processes {
-                render_warning(&format!(
+                renderer.warning(&format!(
                     "  GPU {}: PID {} ({})",
                     proc.gpu_index, proc.pid, proc.proc_name
                 ));
@@ -534,15 +2315,36 @@ fn execute_reset_all_gpus(gpu_manager: &GpuManager, force: bool, dry_run: bool)
             ));
         }
     }
+    confirm_destructive(
+        &format!("Reset {}?", plural(device_count as usize, "GPU")),
+        assume_yes,
+        renderer,
+    )?;
 
     // Reset each GPU
     for i in 0..device_count {
+        let started = std::time::Instant::now();
+        let mechanism = gpu_manager.reset_mechanism_of(i);
         match gpu_manager.reset_gpu(i) {
             Ok(()) => {
-                render_success(&format!("GPU {} reset successfully", i));
+                info!(
+                    gpu_index = i,
+                    duration_ms = started.elapsed().as_millis() as u64,
+                    "GPU reset"
+                );
+                renderer.success(&format!("GPU {} reset successfully", i));
+                report.push(i as u16, ResetStatus::Reset, mechanism, Some(started), None);
             }
             Err(e) => {
-                render_error(&format!("Failed to reset GPU {}: {}", i, e));
+                error!(gpu_index = i, error = %e, "GPU reset failed");
+                renderer.error(&format!("Failed to reset GPU {}: {}", i, e));
+                report.push(
+                    i as u16,
+                    ResetStatus::Failed,
+                    mechanism,
+                    Some(started),
+                    Some(e.to_string()),
+                );
             }
         }
     }
@@ -550,32 +2352,328 @@ fn execute_reset_all_gpus(gpu_manager: &GpuManager, force: bool, dry_run: bool)
     Ok(())
 }
 
-/// Execute reset for a single GPU
-fn execute_reset_single_gpu(
-    gpu_manager: &GpuManager,
-    gpu_id: u16,
+/// Unload and reload the NVIDIA kernel modules
+#[cfg(target_os = "linux")]
+fn execute_hard_reset(
+    gpu_manager: GpuManager,
     force: bool,
-    dry_run: bool,
+    report: &mut ResetReport,
+    renderer: &Renderer,
 ) -> Result<()> {
-    let device_count = gpu_manager.total_device_count()?;
+    use crate::command::SystemCommandRunner;
+    use crate::hard_reset::{HardReset, StepStatus};
 
-    if gpu_id as u32 >= device_count {
-        return Err(anyhow::anyhow!(
-            "GPU {} not found. Available GPUs: 0-{}",
-            gpu_id,
-            device_count - 1
+    const MECHANISM: &str = "driver-reload";
+
+    let runner = SystemCommandRunner;
+    let reset = HardReset::new(
+        &runner,
+        HardReset::<SystemCommandRunner>::system_device_nodes(),
+    );
+
+    if report.dry_run {
+        renderer.info("Dry-run: would hard reset the NVIDIA driver:");
+        for step in reset.plan() {
+            renderer.info(&format!("  {}", step));
+            report.steps.push(step);
+        }
+        for i in 0..gpu_manager.total_device_count()? {
+            report.push(i as u16, ResetStatus::WouldReset, MECHANISM, None, None);
+        }
+        return Ok(());
+    }
+
+    // SAFETY: geteuid has no preconditions and cannot fail
+    if unsafe { libc::geteuid() } != 0 {
+        return Err(anyhow::anyhow!("--reset --hard must be run as root"));
+    }
+
+    let active_processes = gpu_manager.get_all_processes()?;
+    if !active_processes.is_empty() {
+        renderer.warning("Active GPU processes found:");
+        for proc in &active_processes {
+            renderer.warning(&format!(
+                "  GPU {}: PID {} ({})",
+                proc.gpu_index, proc.pid, proc.proc_name
+            ));
+        }
+        if !force {
+            return Err(anyhow::anyhow!(
+                "Cannot hard reset with active GPU processes. Use --force to override."
+            ));
+        }
+    }
+
+    let expected_devices = gpu_manager.total_device_count()?;
+    renderer.warning(&format!(
+        "Hard resetting the NVIDIA driver ({} GPUs)",
+        expected_devices
+    ));
+
+    // Our own NVML handle keeps /dev/nvidia* open, so release it before unloading
+    drop(gpu_manager);
+
+    let started = std::time::Instant::now();
+    let hard_report = reset.run(expected_devices, || {
+        GpuManager::initialize()?.total_device_count()
+    });
+
+    for outcome in &hard_report.steps {
+        info!(step = %outcome.step, status = ?outcome.status, "Hard reset step");
+        match outcome.status {
+            StepStatus::Ok => renderer.success(&outcome.to_string()),
+            StepStatus::Skipped(_) => renderer.info(&outcome.to_string()),
+            StepStatus::Warning(_) => renderer.warning(&outcome.to_string()),
+            StepStatus::Failed(_) => renderer.error(&outcome.to_string()),
+        }
+        report.steps.push(outcome.to_string());
+    }
+
+    let failure = if let Some(busy) = &hard_report.busy {
+        Some(format!("Hard reset aborted: {}", busy))
+    } else if !hard_report.success {
+        Some("Hard reset did not complete".to_string())
+    } else {
+        None
+    };
+    let status = if failure.is_some() {
+        ResetStatus::Failed
+    } else {
+        ResetStatus::Reset
+    };
+    for i in 0..expected_devices {
+        report.push(i as u16, status, MECHANISM, Some(started), failure.clone());
+    }
+    if let Some(failure) = failure {
+        return Err(PartialFailure::new(failure, report).into());
+    }
+
+    renderer.success(&format!(
+        "NVIDIA driver reloaded; {} GPUs enumerated",
+        hard_report.devices_after.unwrap_or(0)
+    ));
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn execute_hard_reset(
+    _gpu_manager: GpuManager,
+    _force: bool,
+    _report: &mut ResetReport,
+    _renderer: &Renderer,
+) -> Result<()> {
+    Err(anyhow::anyhow!("--reset --hard is only supported on Linux"))
+}
+
+/// Dry-run preview of resetting `targets` (GPU index and mechanism) while
+/// `processes` are running. Lists every process on a target GPU, which a forced
+/// reset would terminate; without `force` any such process makes the reset a
+/// refusal instead. Records each GPU in `report` and returns the lines to show.
+fn preview_reset(
+    targets: &[(u16, &str)],
+    processes: &[crate::nvml_api::GpuProc],
+    force: bool,
+    report: &mut ResetReport,
+) -> Vec<String> {
+    let affected: Vec<&crate::nvml_api::GpuProc> = processes
+        .iter()
+        .filter(|p| targets.iter().any(|(index, _)| *index == p.gpu_index))
+        .collect();
+    let what = match targets {
+        [(index, _)] => format!("GPU {}", index),
+        _ => format!("all {} GPUs", targets.len()),
+    };
+    let refused = !force && !affected.is_empty();
+
+    let mut lines = vec![if affected.is_empty() {
+        format!("Dry-run: would reset {} (no active processes)", what)
+    } else if refused {
+        format!(
+            "Dry-run: would refuse to reset {}: {} active process(es); with --force the reset would terminate:",
+            what,
+            affected.len()
+        )
+    } else {
+        format!(
+            "Dry-run: would reset {} and terminate {} process(es):",
+            what,
+            affected.len()
+        )
+    }];
+    lines.extend(affected.iter().map(|p| {
+        format!(
+            "  GPU {}: PID {} ({}, user {}, {} MB)",
+            p.gpu_index, p.pid, p.proc_name, p.user, p.used_mem_mb
+        )
+    }));
+
+    report.push_preview(targets, processes, force);
+    lines
+}
+
+/// How often `--reset --wait-secs` checks whether the GPU has drained
+const RESET_DRAIN_POLL: Duration = Duration::from_millis(500);
+
+/// `--reset --wait-secs`: how long to wait for a GPU's processes to exit before resetting
+#[derive(Debug, Clone)]
+struct DrainWait {
+    timeout: Duration,
+    poll: Duration,
+    /// With --force: --timeout-secs for killing the processes left after `timeout`
+    kill_timeout_secs: u16,
+    /// With --force: the --signal sent first to those processes
+    kill_signal: KillSignal,
+    /// Protected process names from the config file, which are never killed
+    protected_names: Vec<String>,
+}
+
+/// Poll the processes on GPU `gpu_id` until there are none or `wait.timeout` elapses.
+/// Returns how long it waited and the processes still running.
+fn wait_for_gpu_drain(
+    gpu_manager: &GpuManager,
+    gpu_id: u16,
+    wait: &DrainWait,
+) -> Result<(Duration, Vec<crate::nvml_api::GpuProc>)> {
+    let started = std::time::Instant::now();
+    loop {
+        let remaining: Vec<_> = gpu_manager
+            .get_all_processes()?
+            .into_iter()
+            .filter(|p| p.gpu_index == gpu_id)
+            .collect();
+        let waited = started.elapsed();
+        if remaining.is_empty() || waited >= wait.timeout {
+            return Ok((waited, remaining));
+        }
+        debug!(
+            gpu_index = gpu_id,
+            processes = remaining.len(),
+            "Waiting for GPU to drain"
+        );
+        std::thread::sleep(wait.poll.min(wait.timeout - waited));
+    }
+}
+
+/// `--reset --wait-secs --force`: kill the processes still on GPU `gpu_id` once the
+/// wait is over, like `--kill` would, and record the kill in the action audit log.
+/// Protected processes are left running; fails naming the processes that survived.
+async fn kill_before_reset(
+    gpu_manager: &GpuManager,
+    gpu_id: u16,
+    remaining: &[crate::nvml_api::GpuProc],
+    wait: &DrainWait,
+    renderer: &Renderer,
+) -> Result<()> {
+    let mut killer = init_process_manager(gpu_manager, wait.kill_signal)?
+        .with_protection(&wait.protected_names, false);
+    let outcome = killer.batch_kill(remaining, wait.kill_timeout_secs, true);
+    for (process, reason) in &outcome.skipped {
+        renderer.warning(&format!(
+            "Not killing PID {} ({}) before the reset: {}",
+            process.pid, process.proc_name, reason
         ));
     }
+    for killed in &outcome.killed {
+        info!(
+            pid = killed.process.pid,
+            gpu_index = gpu_id,
+            signal = %killed.signal,
+            "Killed process before reset"
+        );
+    }
 
-    if dry_run {
-        render_info(&format!("Dry-run: would reset GPU {}", gpu_id));
-        return Ok(());
+    let result = if outcome.failed.is_empty() {
+        Ok(())
     } else {
-        render_info(&format!("Resetting GPU {}", gpu_id));
+        Err(anyhow::anyhow!(
+            "{} before resetting GPU {}",
+            outcome.failure_message(),
+            gpu_id
+        ))
+    };
+    let killed = outcome.killed_pids();
+    if !killed.is_empty() || result.is_err() {
+        record_action(
+            ActionKind::Kill,
+            &killed_action_target(&killed, &format!("before reset of gpu {}", gpu_id)),
+            false,
+            &result,
+        )
+        .await;
     }
+    result
+}
 
-    // Check for active processes on this GPU if not forcing
+/// Execute reset for a single GPU
+async fn execute_reset_single_gpu(
+    gpu_manager: &GpuManager,
+    gpu_id: u16,
+    wait: Option<DrainWait>,
+    force: bool,
+    assume_yes: bool,
+    report: &mut ResetReport,
+    renderer: &Renderer,
+) -> Result<()> {
+    gpu_manager.check_resettable(gpu_id as u32)?;
+
+    let mechanism = gpu_manager.reset_mechanism_of(gpu_id as u32);
+    if report.dry_run {
+        if let Some(wait) = wait {
+            renderer.info(&format!(
+                "Dry-run: would wait up to {}s for the processes on GPU {} to exit",
+                wait.timeout.as_secs(),
+                gpu_id
+            ));
+        }
+        let processes = gpu_manager.get_all_processes()?;
+        for line in preview_reset(&[(gpu_id, mechanism)], &processes, force, report) {
+            renderer.info(&line);
+        }
+        return Ok(());
+    }
     if !force {
+        confirm_destructive(&format!("Reset GPU {}?", gpu_id), assume_yes, renderer)?;
+    }
+
+    let mut waited_ms = None;
+    if let Some(wait) = &wait {
+        renderer.info(&format!(
+            "Waiting up to {}s for the processes on GPU {} to exit",
+            wait.timeout.as_secs(),
+            gpu_id
+        ));
+        let (waited, remaining) = wait_for_gpu_drain(gpu_manager, gpu_id, wait)?;
+        waited_ms = Some(waited.as_millis() as u64);
+        if remaining.is_empty() {
+            renderer.info(&format!(
+                "GPU {} drained after {:.1}s",
+                gpu_id,
+                waited.as_secs_f64()
+            ));
+        } else if force {
+            renderer.warning(&format!(
+                "GPU {} still has {} process(es) after {:.1}s; killing them",
+                gpu_id,
+                remaining.len(),
+                waited.as_secs_f64()
+            ));
+            kill_before_reset(gpu_manager, gpu_id, &remaining, wait, renderer).await?;
+        } else {
+            for proc in &remaining {
+                renderer.warning(&format!("  PID {} ({})", proc.pid, proc.proc_name));
+            }
+            return Err(anyhow::anyhow!(
+                "GPU {} still has {} active process(es) after waiting {}s. Use --force to kill them.",
+                gpu_id,
+                remaining.len(),
+                wait.timeout.as_secs()
+            ));
+        }
+    }
+    renderer.info(&format!("Resetting GPU {}", gpu_id));
+
+    // Check for active processes on this GPU if not forcing
+    if !force && wait.is_none() {
         let all_processes = gpu_manager.get_all_processes()?;
         let gpu_processes: Vec<_> = all_processes
             .iter()
@@ -583,9 +2681,9 @@ fn execute_reset_single_gpu(
             .collect();
 
         if !gpu_processes.is_empty() {
-            render_warning(&format!("Active processes found on GPU {}:", gpu_id));
+            renderer.warning(&format!("Active processes found on GPU {}:", gpu_id));
             for proc in &gpu_processes {
-                render_warning(&format!("  PID {} ({})", proc.pid, proc.proc_name));
+                renderer.warning(&format!("  PID {} ({})", proc.pid, proc.proc_name));
             }
             return Err(anyhow::anyhow!(
                 "Cannot reset GPU {} with active processes. Use --force to override.",
@@ -595,12 +2693,156 @@ fn execute_reset_single_gpu(
     }
 
     // Reset the GPU
+    let started = std::time::Instant::now();
     gpu_manager.reset_gpu(gpu_id as u32)?;
-    render_success(&format!("GPU {} reset successfully", gpu_id));
+    info!(
+        gpu_index = gpu_id,
+        duration_ms = started.elapsed().as_millis() as u64,
+        "GPU reset"
+    );
+    renderer.success(&format!("GPU {} reset successfully", gpu_id));
+    report
+        .push(gpu_id, ResetStatus::Reset, mechanism, Some(started), None)
+        .waited_ms = waited_ms;
 
     Ok(())
 }
 
+/// Run the self-test on the local GPUs, or on a mock GPU when there is no GPU or
+/// no helper to allocate memory on one
+fn execute_selftest_operation(
+    helper: Option<std::path::PathBuf>,
+    mem_mb: u32,
+    timeout_secs: u16,
+    renderer: &Renderer,
+) -> Result<()> {
+    use crate::selftest::Selftest;
+
+    let selftest = match helper {
+        Some(helper) => match GpuManager::initialize() {
+            Ok(manager) => Selftest::gpu(manager, helper, mem_mb, timeout_secs),
+            Err(e) => {
+                renderer.warning(&format!(
+                    "No GPU available ({}); running the mock self-test",
+                    e
+                ));
+                Selftest::mock(mem_mb, timeout_secs)
+            }
+        },
+        None => {
+            renderer.info("No --selftest-helper given; running the mock self-test");
+            Selftest::mock(mem_mb, timeout_secs)
+        }
+    };
+
+    renderer.info(&format!(
+        "Self-test in {} mode with a {} MB workload",
+        selftest.mode(),
+        mem_mb
+    ));
+    let report = selftest.run(|check| {
+        let line = format!("{}: {}", check.name, check.detail);
+        if check.passed {
+            renderer.success(&line);
+        } else {
+            renderer.error(&line);
+        }
+    });
+
+    let failed = report.failed();
+    if !failed.is_empty() {
+        return Err(PartialFailure::new(
+            format!(
+                "Self-test failed: {} of {} checks failed ({})",
+                failed.len(),
+                report.checks.len(),
+                failed.join(", ")
+            ),
+            &report,
+        )
+        .into());
+    }
+    renderer.success(&format!("Self-test passed: {} checks", report.checks.len()));
+    renderer
+        .render_result(&report)
+        .map_err(|e| anyhow::anyhow!("Render error: {}", e))
+}
+
+/// Print the stable index of every known GPU with its identifier
+fn execute_show_ids_operation(gpu_manager: &GpuManager, renderer: &Renderer) -> Result<()> {
+    let stable = gpu_manager.stable_indices().ok_or_else(|| {
+        anyhow::anyhow!("Stable GPU indices are unavailable; GPUs are listed in enumeration order")
+    })?;
+    renderer
+        .render_gpu_ids(&stable.entries())
+        .map_err(|e| anyhow::anyhow!("Render error: {}", e))
+}
+
+/// Move a GPU to another stable index in the index map, e.g. when a replacement
+/// card should take over the index (and policies) of the card it replaced
+fn execute_remap_gpu_operation(id: &str, index: u16, renderer: &Renderer) -> Result<()> {
+    use crate::gpu_index::GpuIndexMap;
+
+    let path = GpuIndexMap::default_path()?;
+    let mut map = GpuIndexMap::load(&path)?;
+    let displaced = map.remap(id, index)?;
+    map.save(&path)?;
+    info!(gpu = id, index, "Remapped GPU stable index");
+
+    renderer.success(&format!("GPU {} now has stable index {}", id, index));
+    if let Some(previous) = &displaced {
+        renderer.warning(&format!(
+            "GPU {} ({}) no longer has index {}; it gets a new index if it is seen again",
+            previous.id, previous.name, index
+        ));
+    }
+    renderer
+        .render_result(&serde_json::json!({
+            "operation": "remap_gpu",
+            "id": id,
+            "index": index,
+            "displaced": displaced,
+        }))
+        .map_err(|e| anyhow::anyhow!("Render error: {}", e))
+}
+
+/// Print the vendor capability matrix, marking the vendors found on this host
+fn execute_capabilities_operation(check_privileges: bool, renderer: &Renderer) -> Result<()> {
+    let detected = GpuManager::initialize()
+        .map(|manager| manager.get_vendors())
+        .unwrap_or_default();
+    let mut report = CapabilitiesReport::new(&detected);
+    if check_privileges {
+        report = report.with_privileges(crate::privileges::Privileges::current());
+    }
+    renderer
+        .render_capabilities(&report)
+        .map_err(|e| anyhow::anyhow!("Render error: {}", e))
+}
+
+/// Print the link matrix between NVIDIA GPUs, numbered by stable index
+fn execute_topology_operation(gpu_manager: &GpuManager, renderer: &Renderer) -> Result<()> {
+    if !gpu_manager
+        .get_vendors()
+        .contains(&crate::vendor::GpuVendor::Nvidia)
+    {
+        return Err(anyhow::anyhow!(
+            "GPU topology is not supported without NVIDIA GPUs. Detected vendors: {:?}",
+            gpu_manager.get_vendors()
+        ));
+    }
+    let nvml_api = NvmlApi::new()?;
+    let report = TopologyReport::collect(&NvmlTopology::new(nvml_api.nvml()))?;
+    // NVIDIA devices enumerate first, so NVML indices are enumeration indices
+    let report = match gpu_manager.stable_indices() {
+        Some(stable) => report.relabel(|gpu| Some(stable.stable(gpu.index))),
+        None => report,
+    };
+    renderer
+        .render_topology(&report)
+        .map_err(|e| anyhow::anyhow!("Render error: {}", e))
+}
+
 /// Execute audit operation
 async fn execute_audit_operation(
     user_filter: Option<String>,
@@ -614,6 +2856,13 @@ async fn execute_audit_operation(
     use crate::audit::AuditManager;
     use crate::render::{render_info, render_warning};
 
+    // Resolve an explicit --audit-since/--audit-until range, if any
+    let time_range = cli.audit_time_range()?;
+    let range_label = match time_range {
+        Some((start, end)) => format!("{} to {}", start.to_rfc3339(), end.to_rfc3339()),
+        None => format!("Last {} hours", hours),
+    };
+
     // Initialize audit manager
     let audit_manager = AuditManager::new()
         .await
@@ -625,10 +2874,16 @@ async fn execute_audit_operation(
         || cli.rogue_utilization_threshold.is_some()
         || cli.rogue_duration_threshold.is_some()
         || cli.rogue_confidence_threshold.is_some()
+        || cli.rogue_exfil_rate_threshold.is_some()
+        || cli.rogue_exfil_util_threshold.is_some()
+        || cli.rogue_exfil_window.is_some()
         || cli.rogue_whitelist_process.is_some()
         || cli.rogue_unwhitelist_process.is_some()
         || cli.rogue_whitelist_user.is_some()
         || cli.rogue_unwhitelist_user.is_some()
+        || cli.rogue_add_rule.is_some()
+        || cli.rogue_list_rules
+        || cli.rogue_remove_rule.is_some()
         || cli.rogue_export_config
         || cli.rogue_import_config.is_some()
     {
@@ -646,7 +2901,10 @@ async fn execute_audit_operation(
                     .context("Failed to export config to JSON")?;
                 println!("{}", json);
             } else {
-                render_info("🕵️ Rogue Detection Configuration:");
+                render_info(&format!(
+                    "{}Rogue Detection Configuration:",
+                    icon(Icon::Detective)
+                ));
                 render_info(&format!(
                     "  Memory Threshold: {:.1} GB",
                     config.detection.max_memory_usage_gb
@@ -695,19 +2953,32 @@ async fn execute_audit_operation(
                         "disabled"
                     }
                 ));
+                let exfiltration = &config.detection.exfiltration;
+                render_info(&format!(
+                    "  Exfiltration Thresholds: >= {:.0} MB/s at <= {:.1}% utilization for {:.1} minutes",
+                    exfiltration.min_transfer_mb_per_sec,
+                    exfiltration.max_compute_util_pct,
+                    exfiltration.window_minutes
+                ));
 
-                render_info("\n📋 Whitelisted Users:");
+                render_info(&format!("\n{}Whitelisted Users:", icon(Icon::List)));
                 for user in &config.patterns.user_whitelist {
                     render_info(&format!("  - {}", user));
                 }
 
-                render_info("\n📋 Whitelisted Processes:");
+                render_info(&format!("\n{}Whitelisted Processes:", icon(Icon::List)));
                 for process in &config.patterns.process_whitelist {
                     render_info(&format!("  - {}", process));
                 }
 
+                render_info(&format!("\n{}Custom Rules:", icon(Icon::List)));
+                for rule in &config.patterns.custom_rules {
+                    render_info(&format!("  - {}", rule.describe()));
+                }
+
                 render_info(&format!(
-                    "\n📁 Config file: {}",
+                    "\n{}Config file: {}",
+                    icon(Icon::File),
                     config_manager.get_config_file_path().display()
                 ));
             }
@@ -728,7 +2999,28 @@ async fn execute_audit_operation(
                 )
                 .context("Failed to update thresholds")?;
 
-            render_info("✅ Rogue detection thresholds updated successfully");
+            render_info(&format!(
+                "{}Rogue detection thresholds updated successfully",
+                icon(Icon::Ok)
+            ));
+        }
+
+        if cli.rogue_exfil_rate_threshold.is_some()
+            || cli.rogue_exfil_util_threshold.is_some()
+            || cli.rogue_exfil_window.is_some()
+        {
+            config_manager
+                .update_exfiltration_thresholds(
+                    cli.rogue_exfil_rate_threshold,
+                    cli.rogue_exfil_util_threshold,
+                    cli.rogue_exfil_window,
+                )
+                .context("Failed to update exfiltration thresholds")?;
+
+            render_info(&format!(
+                "{}Exfiltration thresholds updated successfully",
+                icon(Icon::Ok)
+            ));
         }
 
         // Manage whitelists
@@ -736,28 +3028,90 @@ async fn execute_audit_operation(
             config_manager
                 .add_process_to_whitelist(process.clone())
                 .context("Failed to add process to whitelist")?;
-            render_info(&format!("✅ Added '{}' to process whitelist", process));
+            render_info(&format!(
+                "{}Added '{}' to process whitelist",
+                icon(Icon::Ok),
+                process
+            ));
         }
 
         if let Some(process) = &cli.rogue_unwhitelist_process {
             config_manager
                 .remove_process_from_whitelist(process)
                 .context("Failed to remove process from whitelist")?;
-            render_info(&format!("✅ Removed '{}' from process whitelist", process));
+            render_info(&format!(
+                "{}Removed '{}' from process whitelist",
+                icon(Icon::Ok),
+                process
+            ));
         }
 
         if let Some(user) = &cli.rogue_whitelist_user {
             config_manager
                 .add_user_to_whitelist(user.clone())
                 .context("Failed to add user to whitelist")?;
-            render_info(&format!("✅ Added '{}' to user whitelist", user));
+            render_info(&format!(
+                "{}Added '{}' to user whitelist",
+                icon(Icon::Ok),
+                user
+            ));
         }
 
         if let Some(user) = &cli.rogue_unwhitelist_user {
             config_manager
                 .remove_user_from_whitelist(user)
                 .context("Failed to remove user from whitelist")?;
-            render_info(&format!("✅ Removed '{}' from user whitelist", user));
+            render_info(&format!(
+                "{}Removed '{}' from user whitelist",
+                icon(Icon::Ok),
+                user
+            ));
+        }
+
+        // Manage custom rules
+        if let Some(name) = &cli.rogue_add_rule {
+            let rule = crate::rogue_config::CustomRule {
+                name: name.clone(),
+                proc_name_regex: cli.rogue_rule_regex.clone().unwrap_or_default(),
+                min_memory_gb: cli.rogue_rule_min_memory,
+                min_duration_hours: cli.rogue_rule_min_duration,
+                min_util_pct: cli.rogue_rule_min_util,
+                confidence: cli.rogue_rule_confidence,
+            };
+            let description = rule.describe();
+            config_manager
+                .add_custom_rule(rule)
+                .context("Failed to add custom rule")?;
+            render_info(&format!(
+                "{}Added custom rule {}",
+                icon(Icon::Ok),
+                description
+            ));
+        }
+
+        if let Some(name) = &cli.rogue_remove_rule {
+            config_manager
+                .remove_custom_rule(name)
+                .context("Failed to remove custom rule")?;
+            render_info(&format!("{}Removed custom rule '{}'", icon(Icon::Ok), name));
+        }
+
+        if cli.rogue_list_rules {
+            let rules = config_manager.custom_rules();
+            if output_format == crate::args::OutputFormat::Json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(rules)
+                        .context("Failed to serialize custom rules")?
+                );
+            } else if rules.is_empty() {
+                render_info("No custom rogue detection rules configured");
+            } else {
+                render_info(&format!("{}Custom Rules:", icon(Icon::List)));
+                for rule in rules {
+                    render_info(&format!("  - {}", rule.describe()));
+                }
+            }
         }
 
         // Export configuration
@@ -775,7 +3129,11 @@ async fn execute_audit_operation(
             config_manager
                 .import_from_json(&content)
                 .context("Failed to import config from JSON")?;
-            render_info(&format!("✅ Imported configuration from: {}", file_path));
+            render_info(&format!(
+                "{}Imported configuration from: {}",
+                icon(Icon::Ok),
+                file_path
+            ));
         }
 
         return Ok(());
@@ -803,14 +3161,16 @@ async fn execute_audit_operation(
         } else {
             // Table output
             render_info(&format!(
-                "🕵️ Rogue Activity Detection Results (Last {} hours)",
+                "{}Rogue Activity Detection Results (Last {} hours)",
+                icon(Icon::Detective),
                 hours
             ));
             render_info(&format!("Overall Risk Score: {:.2}/1.0", result.risk_score));
 
             if !result.crypto_miners.is_empty() {
                 render_warning(&format!(
-                    "🚨 CRITICAL: {} crypto miners detected!",
+                    "{}CRITICAL: {} crypto miners detected!",
+                    icon(Icon::Alert),
                     result.crypto_miners.len()
                 ));
                 for (i, miner) in result.crypto_miners.iter().enumerate() {
@@ -829,20 +3189,21 @@ async fn execute_audit_operation(
 
             if !result.suspicious_processes.is_empty() {
                 render_warning(&format!(
-                    "⚠️ {} suspicious processes detected!",
+                    "{}{} suspicious processes detected!",
+                    icon(Icon::Warning),
                     result.suspicious_processes.len()
                 ));
                 for (i, process) in result.suspicious_processes.iter().enumerate() {
-                    let risk_emoji = match process.risk_level {
-                        crate::rogue_detection::RiskLevel::Critical => "🚨",
-                        crate::rogue_detection::RiskLevel::High => "⚠️",
-                        crate::rogue_detection::RiskLevel::Medium => "⚡",
-                        crate::rogue_detection::RiskLevel::Low => "ℹ️",
-                    };
+                    let risk_icon = icon(match process.risk_level {
+                        crate::rogue_detection::RiskLevel::Critical => Icon::Alert,
+                        crate::rogue_detection::RiskLevel::High => Icon::Warning,
+                        crate::rogue_detection::RiskLevel::Medium => Icon::Action,
+                        crate::rogue_detection::RiskLevel::Low => Icon::Info,
+                    });
                     render_warning(&format!(
-                        "  {}. {} PID {}: {} (confidence: {:.2})",
+                        "  {}. {}PID {}: {} (confidence: {:.2})",
                         i + 1,
-                        risk_emoji,
+                        risk_icon,
                         process.process.pid,
                         process.process.proc_name,
                         process.confidence
@@ -855,7 +3216,8 @@ async fn execute_audit_operation(
 
             if !result.resource_abusers.is_empty() {
                 render_warning(&format!(
-                    "📊 {} resource abusers detected!",
+                    "{}{} resource abusers detected!",
+                    icon(Icon::Stats),
                     result.resource_abusers.len()
                 ));
                 for (i, abuser) in result.resource_abusers.iter().enumerate() {
@@ -880,15 +3242,41 @@ async fn execute_audit_operation(
                 }
             }
 
+            if !result.data_exfiltrators.is_empty() {
+                render_warning(&format!(
+                    "{}{} possible data exfiltrators detected!",
+                    icon(Icon::Alert),
+                    result.data_exfiltrators.len()
+                ));
+                for (i, exfiltrator) in result.data_exfiltrators.iter().enumerate() {
+                    render_warning(&format!(
+                        "  {}. PID {}: {} - {:.0} MB/s for {:.1} min (confidence: {:.2})",
+                        i + 1,
+                        exfiltrator.process.pid,
+                        exfiltrator.process.proc_name,
+                        exfiltrator.observed_mb_per_sec,
+                        exfiltrator.duration_minutes,
+                        exfiltrator.confidence
+                    ));
+                    for indicator in &exfiltrator.exfil_indicators {
+                        render_info(&format!("     - {}", indicator));
+                    }
+                }
+            }
+
             if result.crypto_miners.is_empty()
                 && result.suspicious_processes.is_empty()
                 && result.resource_abusers.is_empty()
+                && result.data_exfiltrators.is_empty()
             {
-                render_info("✅ No suspicious activity detected!");
+                render_info(&format!(
+                    "{}No suspicious activity detected!",
+                    icon(Icon::Ok)
+                ));
             }
 
             if !result.recommendations.is_empty() {
-                render_info("\n📋 Recommendations:");
+                render_info(&format!("\n{}Recommendations:", icon(Icon::List)));
                 for recommendation in &result.recommendations {
                     render_info(&format!("  {}", recommendation));
                 }
@@ -897,14 +3285,50 @@ async fn execute_audit_operation(
         return Ok(());
     }
 
+    if cli.audit_actions {
+        let (start, end) = time_range.unwrap_or_else(|| {
+            let end = chrono::Utc::now();
+            (end - chrono::Duration::hours(hours as i64), end)
+        });
+        let actions = audit_manager
+            .query_actions(start, end, user_filter.as_deref())
+            .await
+            .context("Failed to query the action audit log")?;
+        return render_action_records(&actions, &range_label, &output_format);
+    }
+
+    if cli.accounting {
+        let (start, end) = cli.accounting_time_range()?;
+        let report = audit_manager
+            .get_accounting(start, end, cli.accounting_max_gap)
+            .await
+            .context("Failed to compute GPU-hours accounting")?;
+        return render_accounting_report(&report, &output_format);
+    }
+
     if summary {
         // Show audit summary
-        let summary = audit_manager
-            .get_summary(hours)
-            .await
-            .context("Failed to get audit summary")?;
+        let summary = match time_range {
+            Some((start, end)) => audit_manager.get_summary_range(start, end).await,
+            None => audit_manager.get_summary(hours).await,
+        }
+        .context("Failed to get audit summary")?;
+
+        if output_format == crate::args::OutputFormat::Html {
+            let html = summary.to_html(&range_label, chrono::Utc::now());
+            match &cli.audit_report_file {
+                Some(path) => {
+                    std::fs::write(path, html).with_context(|| {
+                        format!("Failed to write audit report to {}", path.display())
+                    })?;
+                    render_info(&format!("Wrote audit report to {}", path.display()));
+                }
+                None => print!("{}", html),
+            }
+            return Ok(());
+        }
 
-        render_info(&format!("GPU Usage Audit Summary (Last {} hours)", hours));
+        render_info(&format!("GPU Usage Audit Summary ({})", range_label));
         render_info(&format!("Total records: {}", summary.total_records));
 
         if !summary.top_users.is_empty() {
@@ -937,36 +3361,69 @@ async fn execute_audit_operation(
         for (hour, avg_memory) in &summary.gpu_usage_by_hour {
             render_info(&format!("  Hour {}: {} MB average", hour, avg_memory));
         }
+    } else if output_format == crate::args::OutputFormat::Ndjson {
+        // Stream records straight to stdout so memory stays flat for large windows
+        let query = cli.audit_query()?;
+        let stdout = std::io::stdout();
+        let mut out = std::io::BufWriter::new(stdout.lock());
+        let written = audit_manager
+            .export_records_ndjson(&query, &mut out)
+            .await
+            .context("Failed to export audit records")?;
+        tracing::debug!(
+            "Exported {} audit records as NDJSON ({})",
+            written,
+            range_label
+        );
+    } else if cli.stream {
+        // JSON array written record by record; only the page size bounds memory
+        let query = cli.audit_query()?;
+        let stdout = std::io::stdout();
+        let mut out = std::io::BufWriter::new(stdout.lock());
+        let (written, has_more) = audit_manager
+            .write_records_json(&query, &mut out)
+            .await
+            .context("Failed to stream audit records")?;
+        tracing::debug!("Streamed {} audit records ({})", written, range_label);
+        if has_more {
+            render_warning(&audit_more_hint(&query, written));
+        }
     } else {
         // Show detailed audit records
-        let records = audit_manager
-            .query_records(hours, user_filter.as_deref(), process_filter.as_deref())
+        let query = cli.audit_query()?;
+        let page = audit_manager
+            .query(&query)
             .await
             .context("Failed to query audit records")?;
+        let records = page.records;
 
         if records.is_empty() {
-            render_warning(&format!(
-                "No audit records found for the last {} hours",
-                hours
-            ));
-            if user_filter.is_some() || process_filter.is_some() {
+            render_warning(&format!("No audit records found ({})", range_label));
+            if user_filter.is_some()
+                || process_filter.is_some()
+                || query.gpu.is_some()
+                || query.container.is_some()
+            {
                 render_info("Try removing filters to see all records");
             }
             return Ok(());
         }
 
-        render_info(&format!(
-            "Found {} audit records (Last {} hours)",
-            records.len(),
-            hours
-        ));
-
         if output_format == crate::args::OutputFormat::Json {
-            // JSON output
+            // JSON output; the hint goes to stderr so stdout stays parseable
             let json = serde_json::to_string_pretty(&records)
                 .context("Failed to serialize audit records to JSON")?;
             println!("{}", json);
+            if page.has_more {
+                render_warning(&audit_more_hint(&query, records.len()));
+            }
         } else {
+            render_info(&format!(
+                "Found {} audit records ({})",
+                records.len(),
+                range_label
+            ));
+
             // Table output
             use tabled::{Table, Tabled};
 
@@ -982,6 +3439,8 @@ async fn execute_audit_operation(
                 user: String,
                 #[tabled(rename = "Process")]
                 process: String,
+                #[tabled(rename = "Running")]
+                running: String,
                 #[tabled(rename = "Memory (MB)")]
                 memory: u32,
                 #[tabled(rename = "Container")]
@@ -1002,6 +3461,11 @@ async fn execute_audit_operation(
                         .process_name
                         .clone()
                         .unwrap_or_else(|| "-".to_string()),
+                    running: record
+                        .runtime()
+                        .and_then(|runtime| runtime.to_std().ok())
+                        .map(crate::util::format_duration)
+                        .unwrap_or_else(|| "-".to_string()),
                     memory: record.memory_used_mb,
                     container: record.container.clone().unwrap_or_else(|| "-".to_string()),
                 })
@@ -1009,40 +3473,268 @@ async fn execute_audit_operation(
 
             let table = Table::new(table_rows);
             println!("{}", table);
+            if page.has_more {
+                render_info(&audit_more_hint(&query, records.len()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Tell the user a page of audit records was cut short and how to see the rest
+fn audit_more_hint(query: &crate::audit_store::AuditQuery, shown: usize) -> String {
+    format!(
+        "More audit records match; showing the newest {} from offset {}. \
+         Use --audit-offset {} for the next page, or --audit-limit 0 to show all",
+        shown,
+        query.offset,
+        query.offset + shown
+    )
+}
+
+/// Render a GPU-hours accounting report as a table, JSON, or CSV
+fn render_accounting_report(
+    report: &crate::audit::AccountingReport,
+    output_format: &OutputFormat,
+) -> Result<()> {
+    use crate::render::{render_info, render_warning};
+    use tabled::{Table, Tabled};
+
+    match output_format {
+        OutputFormat::Json | OutputFormat::Ndjson => {
+            let json = serde_json::to_string_pretty(report)
+                .context("Failed to serialize accounting report to JSON")?;
+            println!("{}", json);
+        }
+        OutputFormat::Csv => print!("{}", report.to_csv()),
+        OutputFormat::Table | OutputFormat::Html => {
+            #[derive(Tabled)]
+            struct AccountingRow {
+                #[tabled(rename = "Name")]
+                name: String,
+                #[tabled(rename = "GPU-hours")]
+                gpu_hours: String,
+                #[tabled(rename = "GB-hours")]
+                gb_hours: String,
+                #[tabled(rename = "Samples")]
+                samples: u64,
+            }
+
+            let rows = |entries: &[crate::audit::AccountingEntry]| -> Vec<AccountingRow> {
+                entries
+                    .iter()
+                    .map(|entry| AccountingRow {
+                        name: entry.name.clone(),
+                        gpu_hours: format!("{:.2}", entry.gpu_hours),
+                        gb_hours: format!("{:.2}", entry.gb_hours),
+                        samples: entry.samples,
+                    })
+                    .collect()
+            };
+
+            render_info(&format!(
+                "GPU-hours accounting ({} to {})",
+                report.start.to_rfc3339(),
+                report.end.to_rfc3339()
+            ));
+            if report.by_user.is_empty() {
+                render_warning("No process samples found in this range");
+                return Ok(());
+            }
+            render_info(&format!(
+                "Total: {:.2} GPU-hours, {:.2} GB-hours (gaps capped at {}s)",
+                report.total_gpu_hours, report.total_gb_hours, report.max_gap_secs
+            ));
+
+            render_info("\nBy user:");
+            println!("{}", Table::new(rows(&report.by_user)));
+            render_info("\nBy process:");
+            println!("{}", Table::new(rows(&report.by_process)));
+            if !report.by_job.is_empty() {
+                render_info("\nBy Slurm job:");
+                println!("{}", Table::new(rows(&report.by_job)));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Print the action audit log (`--audit --audit-actions`)
+fn render_action_records(
+    actions: &[crate::audit::ActionRecord],
+    range_label: &str,
+    output_format: &OutputFormat,
+) -> Result<()> {
+    use crate::render::{render_info, render_warning};
+    use tabled::{settings::Style, Table, Tabled};
+
+    match output_format {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(actions)
+                .context("Failed to serialize action records to JSON")?;
+            println!("{}", json);
+        }
+        OutputFormat::Ndjson => {
+            for action in actions {
+                println!(
+                    "{}",
+                    serde_json::to_string(action).context("Failed to serialize action record")?
+                );
+            }
+        }
+        OutputFormat::Table | OutputFormat::Csv | OutputFormat::Html => {
+            #[derive(Tabled)]
+            struct ActionRow {
+                #[tabled(rename = "Time")]
+                time: String,
+                #[tabled(rename = "Action")]
+                kind: String,
+                #[tabled(rename = "Target")]
+                target: String,
+                #[tabled(rename = "User")]
+                user: String,
+                #[tabled(rename = "Host")]
+                host: String,
+                #[tabled(rename = "Result")]
+                result: String,
+            }
+
+            if actions.is_empty() {
+                render_warning(&format!("No kill or reset actions found ({})", range_label));
+                return Ok(());
+            }
+            let rows: Vec<ActionRow> = actions
+                .iter()
+                .map(|action| ActionRow {
+                    time: action.timestamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    kind: if action.dry_run {
+                        format!("{} (dry run)", action.kind)
+                    } else {
+                        action.kind.to_string()
+                    },
+                    target: action.target.clone(),
+                    user: match &action.sudo_user {
+                        Some(sudo_user) => {
+                            format!("{} (sudo by {})", action.invoking_user, sudo_user)
+                        }
+                        None => action.invoking_user.clone(),
+                    },
+                    host: action.hostname.clone(),
+                    result: match &action.error {
+                        Some(error) => format!("failed: {}", error),
+                        None if action.success => "ok".to_string(),
+                        None => "failed".to_string(),
+                    },
+                })
+                .collect();
+
+            render_info(&format!(
+                "Found {} kill and reset actions ({})",
+                actions.len(),
+                range_label
+            ));
+            let mut table = Table::new(rows);
+            table.with(Style::psql());
+            println!("{}", table);
         }
     }
 
     Ok(())
 }
 
-/// Open the default browser to http://localhost:{port} (used for --server --open).
-fn open_browser_at_port(port: u16) {
-    let url = format!("http://localhost:{}", port);
+/// Open the default browser at `url` (used for --server --open).
+fn open_browser_at(url: &str) {
     #[cfg(target_os = "macos")]
     {
-        let _ = std::process::Command::new("open").arg(&url).status();
+        let _ = std::process::Command::new("open").arg(url).status();
     }
     #[cfg(target_os = "linux")]
     {
-        let _ = std::process::Command::new("xdg-open").arg(&url).status();
+        let _ = std::process::Command::new("xdg-open").arg(url).status();
     }
     #[cfg(target_os = "windows")]
     {
         let _ = std::process::Command::new("cmd")
-            .args(["/C", "start", "", &url])
+            .args(["/C", "start", "", url])
             .status();
     }
 }
 
 /// Execute server operation
-async fn execute_server_operation(host: String, port: u16, gpu_manager: GpuManager) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+async fn execute_server_operation(
+    host: std::net::IpAddr,
+    port: u16,
+    lifecycle: crate::coordinator::NodeLifecycle,
+    rogue: RogueScanSettings,
+    tags: std::collections::HashMap<String, String>,
+    cluster_token: Option<String>,
+    tls: Option<axum_server::tls_rustls::RustlsConfig>,
+    gpu_manager: GpuManager,
+) -> Result<()> {
     use axum::serve;
     use std::net::SocketAddr;
 
-    info!("Starting GPU Kill Coordinator Server on {}:{}", host, port);
+    let addr = SocketAddr::new(host, port);
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    info!(
+        "Starting GPU Kill Coordinator Server on {}://{}",
+        scheme, addr
+    );
 
     // Initialize coordinator state
-    let state = CoordinatorState::new();
+    let mut state = CoordinatorState::new()
+        .with_node_lifecycle(lifecycle)
+        .with_rogue_stale_after(rogue.stale_after)
+        .with_cluster_token(cluster_token);
+    // Registered nodes and their drains survive a restart of the coordinator
+    match crate::coordinator::NodeRegistry::default_path()
+        .and_then(|path| crate::coordinator::NodeRegistry::load(&path).map(|saved| (path, saved)))
+    {
+        Ok((path, saved)) => {
+            if !saved.nodes.is_empty() {
+                info!(
+                    "Restored {} nodes from {}",
+                    saved.nodes.len(),
+                    path.display()
+                );
+            }
+            state = state.with_node_registry(path, saved);
+        }
+        Err(e) => warn!("Registered nodes will not survive a restart: {:#}", e),
+    }
+    let audit_config = crate::config::get_config(None)?.config().audit.clone();
+    match crate::audit_store::open_store(audit_config.database_url.as_deref()).await {
+        Ok(store) => state = state.with_event_store(std::sync::Arc::from(store)),
+        Err(e) => warn!("Cluster events will only be kept in memory: {}", e),
+    }
+    // One audit writer for all node uploads, so snapshots arriving together are
+    // written in one batch. Only this writer buffers: the background task and the
+    // shutdown below flush it.
+    let audit = match crate::audit::AuditManager::new().await {
+        Ok(audit) => {
+            let audit = std::sync::Arc::new(
+                audit.with_flush_interval(Duration::from_secs(audit_config.flush_interval_secs)),
+            );
+            state = state.with_audit_manager(audit.clone());
+            Some(audit)
+        }
+        Err(e) => {
+            warn!("Failed to open audit log: {}", e);
+            None
+        }
+    };
+    match crate::guard_mode::GuardModeManager::load_existing() {
+        Ok(guard_manager) => {
+            state
+                .set_guard_config(guard_manager.as_ref().map(|m| m.get_config().clone()))
+                .await;
+            state.start_guard_config_watcher(guard_manager);
+        }
+        Err(e) => warn!("Maintenance windows unavailable: {}", e),
+    }
 
     // Start background tasks for cluster management
     state.start_background_tasks();
@@ -1052,9 +3744,15 @@ async fn execute_server_operation(host: String, port: u16, gpu_manager: GpuManag
     let hostname = crate::util::get_hostname();
 
     // Get initial GPU information
-    let gpu_snapshots = gpu_manager.get_all_snapshots()?;
-    let gpu_processes = gpu_manager.get_all_processes()?;
-    let total_memory_gb = gpu_snapshots
+    let initial_snapshot = crate::coordinator::NodeSnapshot::collect(
+        &gpu_manager,
+        node_id.clone(),
+        hostname.clone(),
+        tags.clone(),
+    )
+    .await?;
+    let total_memory_gb = initial_snapshot
+        .gpus
         .iter()
         .map(|gpu| gpu.mem_total_mb as f32 / 1024.0)
         .sum();
@@ -1064,74 +3762,276 @@ async fn execute_server_operation(host: String, port: u16, gpu_manager: GpuManag
         hostname: hostname.clone(),
         ip_address: "127.0.0.1".to_string(), // TODO: Get actual IP
         last_seen: chrono::Utc::now(),
-        status: crate::coordinator::NodeStatus::Online,
-        gpu_count: gpu_snapshots.len() as u32,
+        status: initial_snapshot.status.clone(),
+        gpu_count: initial_snapshot.gpus.len() as u32,
         total_memory_gb,
-        tags: std::collections::HashMap::new(),
+        tags,
+        // A drain placed on this host before a restart stays in place
+        drain: crate::drain::NodeDrain::load_local(),
     };
 
     state.register_node(node_info).await?;
-
-    // Create initial snapshot
-    let initial_snapshot = crate::coordinator::NodeSnapshot {
-        node_id: node_id.clone(),
-        hostname,
-        timestamp: chrono::Utc::now(),
-        gpus: gpu_snapshots,
-        processes: gpu_processes,
-        status: crate::coordinator::NodeStatus::Online,
-    };
-
-    state.update_snapshot(node_id, initial_snapshot).await?;
+    state
+        .update_snapshot(node_id.clone(), initial_snapshot)
+        .await?;
+
+    // The coordinator host runs its own commands and reports rogue scans like any other node
+    let scan_state = state.clone();
+    let own_gpus = gpu_manager.clone();
+    tokio::spawn(async move {
+        let mut next_scan = tokio::time::Instant::now();
+        let mut interval = tokio::time::interval(Duration::from_secs(30));
+        loop {
+            interval.tick().await;
+            let commands = scan_state.take_commands(&node_id).await;
+            for command in &commands {
+                apply_drain_command(command);
+                if let crate::coordinator::NodeCommand::Kill { pids, reason } = command {
+                    if let Err(e) = execute_drain_kill(&own_gpus, pids, reason).await {
+                        warn!("Failed to kill processes at the drain deadline: {:#}", e);
+                    }
+                    continue;
+                }
+                let crate::coordinator::NodeCommand::Reset {
+                    batch_id,
+                    gpu_index,
+                    force,
+                } = command
+                else {
+                    continue;
+                };
+                let result =
+                    match execute_cluster_reset(&own_gpus, batch_id, *gpu_index, *force).await {
+                        Ok(result) => result,
+                        Err(e) => {
+                            warn!("Cluster reset of GPU {} failed to run: {:#}", gpu_index, e);
+                            continue;
+                        }
+                    };
+                if let Err(e) = scan_state.record_reset_result(&node_id, result).await {
+                    warn!("Failed to store cluster reset result: {}", e);
+                }
+            }
+            let requested = commands.contains(&crate::coordinator::NodeCommand::RogueScan);
+            if !requested && tokio::time::Instant::now() < next_scan {
+                continue;
+            }
+            next_scan = tokio::time::Instant::now() + rogue.interval;
+            match scan_own_rogue_activity(&hostname).await {
+                Ok(result) => {
+                    if let Err(e) = scan_state.update_rogue_result(&node_id, result).await {
+                        warn!("Failed to store rogue detection result: {}", e);
+                    }
+                }
+                Err(e) => warn!("Rogue detection failed: {:#}", e),
+            }
+        }
+    });
 
     // Create router
     let app = create_router(state);
 
     // Start server
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
-    info!("GPU Kill Coordinator Server listening on http://{}", addr);
-    info!("Dashboard will be available at http://{}:{}", host, port);
+    let listener = bind_server_listener(addr).await?;
+    let addr = listener.local_addr().unwrap_or(addr);
+    info!(
+        "GPU Kill Coordinator Server listening on {}://{}",
+        scheme, addr
+    );
     info!("API endpoints:");
     info!("  GET  /api/nodes - List all nodes");
     info!("  GET  /api/cluster/snapshot - Get cluster snapshot");
     info!("  GET  /api/cluster/contention - Get contention analysis");
+    info!("  GET  /api/cluster/rogue - Get cluster rogue detection report");
+    info!("  POST /api/cluster/reset - Reset GPUs across nodes (requires --cluster-token)");
+    info!("  POST /api/nodes/:id/drain - Drain a node for maintenance (requires --cluster-token)");
     info!("  WS   /ws - WebSocket for real-time updates");
 
-    let listener = tokio::net::TcpListener::bind(&addr)
-        .await
-        .context("Failed to bind to address")?;
+    let shutdown = async {
+        shutdown_signal().await;
+        info!("Shutting down coordinator");
+    };
+    match tls {
+        Some(tls) => {
+            let handle = axum_server::Handle::new();
+            let shutdown_handle = handle.clone();
+            tokio::spawn(async move {
+                shutdown.await;
+                shutdown_handle.graceful_shutdown(None);
+            });
+            let listener = listener
+                .into_std()
+                .context("Failed to hand the listener to the TLS server")?;
+            axum_server::from_tcp_rustls(listener, tls)
+                .handle(handle)
+                .serve(app.into_make_service())
+                .await
+                .context("Failed to start server")?;
+        }
+        None => serve(listener, app)
+            .with_graceful_shutdown(shutdown)
+            .await
+            .context("Failed to start server")?,
+    }
+
+    if let Some(audit) = audit {
+        audit
+            .flush()
+            .await
+            .context("Failed to write buffered audit records")?;
+    }
+    Ok(())
+}
 
-    serve(listener, app)
+/// Bind the coordinator listener to exactly the requested address
+async fn bind_server_listener(addr: std::net::SocketAddr) -> Result<tokio::net::TcpListener> {
+    tokio::net::TcpListener::bind(addr)
         .await
-        .context("Failed to start server")?;
+        .with_context(|| format!("Failed to bind to {}", addr))
+}
 
-    Ok(())
+/// Serve the local REST API (`--serve-local`) until Ctrl-C
+async fn execute_serve_local_operation(
+    bind: std::net::IpAddr,
+    port: u16,
+    token: Option<String>,
+    gpu_manager: GpuManager,
+    config_manager: &crate::config::ConfigManager,
+) -> Result<()> {
+    let token_required = token.is_some();
+    let mut state = crate::local_api::LocalApiState::new(gpu_manager)
+        .with_token(token)
+        .with_protected_names(&config_manager.config().protected_processes);
+    match crate::audit::AuditManager::new().await {
+        Ok(audit) => state = state.with_audit(std::sync::Arc::new(audit)),
+        Err(e) => warn!(
+            "Audit log unavailable ({}); /v1/audit is disabled and kills and resets are not recorded",
+            e
+        ),
+    }
+
+    let addr = std::net::SocketAddr::new(bind, port);
+    let listener = bind_server_listener(addr).await?;
+    let addr = listener.local_addr().unwrap_or(addr);
+    info!(
+        "GPU Kill local API listening on http://{}{}",
+        addr,
+        if token_required {
+            " (bearer token required)"
+        } else {
+            ""
+        }
+    );
+    info!("API endpoints:");
+    info!("  GET  /v1/snapshot - GPUs and processes, as --list --output json");
+    info!("  GET  /v1/processes - Processes, as --list --processes-only --output json");
+    info!("  POST /v1/kill - Kill a PID or matching processes");
+    info!("  POST /v1/reset - Reset a GPU");
+    info!("  GET  /v1/audit - Audit records of the last hours");
+
+    crate::local_api::serve(listener, state, async {
+        shutdown_signal().await;
+        info!("Shutting down local API");
+    })
+    .await
 }
 
-/// Execute operation on remote host via SSH
-async fn execute_remote_operation(cli: Cli, remote_host: &str) -> Result<()> {
+/// Wait for Ctrl-C or, on Unix, SIGTERM (e.g. from `systemctl stop`), so servers
+/// shut down gracefully and write their buffered audit records
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    if let Ok(mut terminate) =
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+    {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+        return;
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Execute operation on one or more remote hosts via SSH
+async fn execute_remote_operation(
+    cli: Cli,
+    config_manager: crate::config::ConfigManager,
+) -> Result<()> {
     use crate::remote::{execute_remote_operation as remote_exec, SshConfig};
-    use std::time::Duration;
 
-    info!("Executing remote operation on {}", remote_host);
+    let hosts = cli.remote_hosts()?;
+    let ssh_configs: Vec<SshConfig> = hosts
+        .iter()
+        .map(|host| remote_ssh_config(&cli, config_manager.config(), host))
+        .collect::<Result<_>>()?;
+
+    if let [ssh_config] = ssh_configs.as_slice() {
+        info!("Executing remote operation on {}", ssh_config.host);
+        let remote_args = build_remote_args(&cli, &cli.output)?;
+        return remote_exec(ssh_config.clone(), &remote_args);
+    }
+
+    if cli.watch {
+        return Err(anyhow::anyhow!(
+            "Invalid argument: --watch supports a single remote host"
+        ));
+    }
+
+    info!(
+        "Executing remote operation on {} hosts ({} at a time)",
+        ssh_configs.len(),
+        cli.remote_parallel
+    );
+    if cli.list {
+        execute_remote_list_fanout(&cli, ssh_configs, config_manager.config().cmdline_width)
+    } else {
+        execute_remote_command_fanout(&cli, ssh_configs)
+    }
+}
 
-    // Build SSH configuration
-    let username = cli
-        .ssh_user
+/// Build the SSH configuration for one host: per-host overrides from the config
+/// file take precedence over the --ssh-* flags.
+fn remote_ssh_config(
+    cli: &Cli,
+    config: &crate::config::Config,
+    host: &str,
+) -> Result<crate::remote::SshConfig> {
+    use crate::remote::{parse_jump_hosts, SshConfig};
+
+    let overrides = config.remote_hosts.get(host).cloned().unwrap_or_default();
+    let username = overrides
+        .user
+        .or_else(|| cli.ssh_user.clone())
         .unwrap_or_else(|| std::env::var("USER").unwrap_or_else(|_| "root".to_string()));
+    let port = overrides.port.unwrap_or(cli.ssh_port);
+    let timeout_secs = overrides.timeout_secs.unwrap_or(cli.ssh_timeout);
+
+    let jump_hosts = match overrides.jump.as_ref().or(cli.ssh_jump.as_ref()) {
+        Some(spec) => parse_jump_hosts(spec)
+            .with_context(|| format!("Invalid argument: jump host for {}", host))?,
+        None => Vec::new(),
+    };
 
-    let mut ssh_config = SshConfig::new(remote_host.to_string(), cli.ssh_port, username)
-        .with_timeout(Duration::from_secs(cli.ssh_timeout as u64));
+    let mut ssh_config = SshConfig::new(host.to_string(), port, username)
+        .with_timeout(Duration::from_secs(timeout_secs as u64))
+        .with_jump_hosts(jump_hosts)
+        .with_insecure(cli.ssh_insecure)
+        .with_ssh_config_proxy_jump();
 
     // Add authentication options
-    if let Some(key_path) = &cli.ssh_key {
-        ssh_config = ssh_config.with_key_path(key_path.clone());
+    if let Some(key_path) = overrides.key.or_else(|| cli.ssh_key.clone()) {
+        ssh_config = ssh_config.with_key_path(key_path);
     }
 
     if let Some(password) = &cli.ssh_password {
         ssh_config = ssh_config.with_password(password.clone());
     }
 
+    Ok(ssh_config)
+}
+
+/// Build the gpukill arguments forwarded to remote hosts
+fn build_remote_args(cli: &Cli, output: &OutputFormat) -> Result<Vec<String>> {
     // Build command arguments for remote execution
     let mut remote_args = Vec::new();
 
@@ -1144,43 +4044,126 @@ async fn execute_remote_operation(cli: Cli, remote_host: &str) -> Result<()> {
         if cli.watch {
             remote_args.push("--watch".to_string());
         }
+        if cli.show_rates {
+            remote_args.push("--show-rates".to_string());
+        }
+        if let Some(count) = cli.watch_count {
+            remote_args.extend(["--watch-count".to_string(), count.to_string()]);
+        }
+        if let Some(pid) = cli.follow_pid {
+            remote_args.extend(["--follow-pid".to_string(), pid.to_string()]);
+        }
+        if let Some(pct) = cli.alert_util {
+            remote_args.extend(["--alert-util".to_string(), pct.to_string()]);
+        }
+        if let Some(pct) = cli.alert_mem_pct {
+            remote_args.extend(["--alert-mem-pct".to_string(), pct.to_string()]);
+        }
+        if let Some(celsius) = cli.alert_temp {
+            remote_args.extend(["--alert-temp".to_string(), celsius.to_string()]);
+        }
         if cli.containers {
             remote_args.push("--containers".to_string());
         }
+        if cli.full_cmd {
+            remote_args.push("--full-cmd".to_string());
+        }
+        if cli.raw_units {
+            remote_args.push("--raw-units".to_string());
+        }
+        if cli.tree {
+            remote_args.push("--tree".to_string());
+        }
+        push_remote_gpu_args(cli, &mut remote_args);
     } else if cli.kill {
         remote_args.push("--kill".to_string());
-        if let Some(pid) = cli.pid {
+        if let Some(pid) = &cli.pid {
             remote_args.push("--pid".to_string());
             remote_args.push(pid.to_string());
         }
         if let Some(filter) = &cli.filter {
             remote_args.push("--filter".to_string());
             remote_args.push(filter.clone());
+            if cli.match_cmdline {
+                remote_args.push("--match-cmdline".to_string());
+            }
+        }
+        if let Some(user) = &cli.filter_user {
+            remote_args.push("--filter-user".to_string());
+            remote_args.push(user.clone());
         }
-        if let Some(gpu_id) = cli.gpu {
-            remote_args.push("--gpu".to_string());
-            remote_args.push(gpu_id.to_string());
+        if let Some(job) = &cli.job {
+            remote_args.push("--job".to_string());
+            remote_args.push(job.clone());
+            if cli.use_scancel {
+                remote_args.push("--use-scancel".to_string());
+            }
         }
+        push_remote_gpu_args(cli, &mut remote_args);
         if cli.batch {
             remote_args.push("--batch".to_string());
         }
+        if cli.with_children {
+            remote_args.push("--with-children".to_string());
+        }
+        if cli.with_parent {
+            remote_args.push("--with-parent".to_string());
+        }
+        if let Some(idle_for) = cli.idle_window() {
+            remote_args.push("--idle-for".to_string());
+            remote_args.push(format!("{}s", idle_for.as_secs()));
+            remote_args.push("--idle-max-util".to_string());
+            remote_args.push(cli.idle_max_util.to_string());
+            if let Some(gb) = cli.idle_mem_gb {
+                remote_args.push("--idle-mem-gb".to_string());
+                remote_args.push(gb.to_string());
+            }
+        }
+        if let Some(idle_warn) = cli.idle_warn {
+            remote_args.push("--idle-warn".to_string());
+            remote_args.push(format!("{}s", idle_warn.as_secs()));
+        }
         if cli.force {
             remote_args.push("--force".to_string());
         }
+        remote_args.push("--signal".to_string());
+        remote_args.push(cli.signal.name().to_string());
         remote_args.push("--timeout-secs".to_string());
         remote_args.push(cli.timeout_secs.to_string());
     } else if cli.reset {
         remote_args.push("--reset".to_string());
-        if let Some(gpu_id) = cli.gpu {
-            remote_args.push("--gpu".to_string());
-            remote_args.push(gpu_id.to_string());
+        if cli.hard {
+            remote_args.push("--hard".to_string());
+        }
+        if let Some(wait_secs) = cli.wait_secs {
+            remote_args.push("--wait-secs".to_string());
+            remote_args.push(wait_secs.to_string());
+            remote_args.push("--signal".to_string());
+            remote_args.push(cli.signal.name().to_string());
+            remote_args.push("--timeout-secs".to_string());
+            remote_args.push(cli.timeout_secs.to_string());
         }
+        push_remote_gpu_args(cli, &mut remote_args);
         if cli.all {
             remote_args.push("--all".to_string());
         }
         if cli.force {
             remote_args.push("--force".to_string());
         }
+    } else if cli.set {
+        remote_args.push("--set".to_string());
+        push_remote_gpu_args(cli, &mut remote_args);
+        if let Some(watts) = cli.power_limit {
+            remote_args.push("--power-limit".to_string());
+            remote_args.push(watts.to_string());
+        }
+        if let Some(mhz) = cli.lock_clocks {
+            remote_args.push("--lock-clocks".to_string());
+            remote_args.push(mhz.to_string());
+        }
+        if cli.reset_clocks {
+            remote_args.push("--reset-clocks".to_string());
+        }
     } else if cli.audit {
         remote_args.push("--audit".to_string());
         if let Some(user) = &cli.audit_user {
@@ -1191,11 +4174,33 @@ async fn execute_remote_operation(cli: Cli, remote_host: &str) -> Result<()> {
             remote_args.push("--audit-process".to_string());
             remote_args.push(process.clone());
         }
-        remote_args.push("--audit-hours".to_string());
-        remote_args.push(cli.audit_hours.to_string());
+        if let Some(since) = &cli.audit_since {
+            remote_args.push("--audit-since".to_string());
+            remote_args.push(since.to_rfc3339());
+        } else {
+            remote_args.push("--audit-hours".to_string());
+            remote_args.push(cli.audit_hours.to_string());
+        }
+        if let Some(until) = &cli.audit_until {
+            remote_args.push("--audit-until".to_string());
+            remote_args.push(until.to_rfc3339());
+        }
         if cli.audit_summary {
             remote_args.push("--audit-summary".to_string());
         }
+        if cli.accounting {
+            remote_args.push("--accounting".to_string());
+            if let Some(from) = &cli.accounting_from {
+                remote_args.push("--from".to_string());
+                remote_args.push(from.to_rfc3339());
+            }
+            if let Some(to) = &cli.accounting_to {
+                remote_args.push("--to".to_string());
+                remote_args.push(to.to_rfc3339());
+            }
+            remote_args.push("--accounting-max-gap".to_string());
+            remote_args.push(cli.accounting_max_gap.to_string());
+        }
     } else if cli.server {
         return Err(anyhow::anyhow!(
             "Server mode cannot be used with remote operations"
@@ -1203,10 +4208,13 @@ async fn execute_remote_operation(cli: Cli, remote_host: &str) -> Result<()> {
     }
 
     // Add output format
-    match cli.output {
-        crate::args::OutputFormat::Json => {
+    match output {
+        crate::args::OutputFormat::Json
+        | crate::args::OutputFormat::Csv
+        | crate::args::OutputFormat::Ndjson
+        | crate::args::OutputFormat::Html => {
             remote_args.push("--output".to_string());
-            remote_args.push("json".to_string());
+            remote_args.push(output.to_string());
         }
         crate::args::OutputFormat::Table => {
             // Table is default, no need to specify
@@ -1224,24 +4232,214 @@ async fn execute_remote_operation(cli: Cli, remote_host: &str) -> Result<()> {
         remote_args.push(format!("{:?}", vendor).to_lowercase());
     }
 
-    // Execute the remote operation
-    remote_exec(ssh_config, &remote_args)?;
+    Ok(remote_args)
+}
+
+/// Forward the GPU selection; a UUID is resolved by the remote host, where indices may differ
+fn push_remote_gpu_args(cli: &Cli, remote_args: &mut Vec<String>) {
+    if let Some(uuid) = &cli.gpu_uuid {
+        remote_args.push("--gpu-uuid".to_string());
+        remote_args.push(uuid.clone());
+    } else if let Some(gpu_id) = cli.gpu {
+        remote_args.push("--gpu".to_string());
+        remote_args.push(gpu_id.to_string());
+    }
+}
+
+/// Run --list on every host and merge the snapshots into one view grouped by host
+fn execute_remote_list_fanout(
+    cli: &Cli,
+    ssh_configs: Vec<crate::remote::SshConfig>,
+    cmdline_width: usize,
+) -> Result<()> {
+    use crate::remote::execute_remote_fanout;
+
+    let total = ssh_configs.len();
+    let mut remote_args = build_remote_args(cli, &OutputFormat::Json)?;
+    remote_args.extend(["--log-level".to_string(), "error".to_string()]);
+
+    let outcomes = execute_remote_fanout(ssh_configs, &remote_args, cli.remote_parallel as usize);
+    let mut snapshots = Vec::new();
+    let mut failures = Vec::new();
+    for outcome in outcomes {
+        match outcome
+            .result
+            .and_then(|output| parse_remote_snapshot(&output))
+        {
+            Ok(snapshot) => snapshots.push((outcome.host, snapshot)),
+            Err(e) => failures.push((outcome.host, e.to_string())),
+        }
+    }
+
+    if cli.output == OutputFormat::Json {
+        let mut hosts: Vec<serde_json::Value> = snapshots
+            .iter()
+            .map(|(host, snapshot)| serde_json::json!({ "host": host, "snapshot": snapshot }))
+            .collect();
+        hosts.extend(
+            failures
+                .iter()
+                .map(|(host, error)| serde_json::json!({ "host": host, "error": error })),
+        );
+        println!("{}", serde_json::to_string_pretty(&hosts)?);
+    } else {
+        let renderer = Renderer::new(cli.output.clone())
+            .with_cmdline_width((!cli.full_cmd).then_some(cmdline_width))
+            .with_raw_units(cli.raw_units);
+        if !snapshots.is_empty() {
+            println!(
+                "{}",
+                renderer.format_host_snapshots(&snapshots, cli.details)
+            );
+        }
+        for (host, snapshot) in &snapshots {
+            for warning in ecc_warnings(snapshot) {
+                render_warning(&format!("{}: {}", host, warning));
+            }
+        }
+        for (host, error) in &failures {
+            render_warning(&format!("{}: {}", host, error));
+        }
+    }
+
+    if snapshots.is_empty() {
+        return Err(anyhow::anyhow!("All {} remote hosts failed", total));
+    }
+    Ok(())
+}
+
+/// Parse the JSON snapshot printed by a remote `gpukill --list --output json`,
+/// skipping any log lines emitted before it.
+fn parse_remote_snapshot(output: &str) -> Result<Snapshot> {
+    let start = output
+        .find('{')
+        .ok_or_else(|| anyhow::anyhow!("Remote gpukill did not return a JSON snapshot"))?;
+    serde_json::Deserializer::from_str(&output[start..])
+        .into_iter::<Snapshot>()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("Remote gpukill did not return a JSON snapshot"))?
+        .context("Failed to parse remote snapshot")
+}
+
+/// Run a kill/reset/audit operation on every host and summarize the per-host outcome.
+/// Fails if any host failed, after all hosts have been tried.
+fn execute_remote_command_fanout(
+    cli: &Cli,
+    ssh_configs: Vec<crate::remote::SshConfig>,
+) -> Result<()> {
+    use crate::remote::execute_remote_fanout;
+
+    let total = ssh_configs.len();
+    let mut remote_args = build_remote_args(cli, &cli.output)?;
+    remote_args.extend(["--log-level".to_string(), "error".to_string()]);
+
+    let outcomes = execute_remote_fanout(ssh_configs, &remote_args, cli.remote_parallel as usize);
+    let failed = outcomes.iter().filter(|o| o.result.is_err()).count();
+
+    if cli.output == OutputFormat::Json {
+        let hosts: Vec<serde_json::Value> = outcomes
+            .iter()
+            .map(|outcome| match &outcome.result {
+                Ok(output) => serde_json::json!({
+                    "host": outcome.host,
+                    "success": true,
+                    "output": serde_json::from_str::<serde_json::Value>(output)
+                        .unwrap_or_else(|_| serde_json::Value::String(output.clone())),
+                }),
+                Err(e) => serde_json::json!({
+                    "host": outcome.host,
+                    "success": false,
+                    "error": e.to_string(),
+                }),
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&hosts)?);
+    } else {
+        for outcome in &outcomes {
+            if let Ok(output) = &outcome.result {
+                println!("=== {} ===", outcome.host);
+                print!("{}", output);
+            }
+        }
+        let results: Vec<(String, std::result::Result<(), String>)> = outcomes
+            .iter()
+            .map(|outcome| {
+                let result = match &outcome.result {
+                    Ok(_) => Ok(()),
+                    Err(e) => Err(e.to_string()),
+                };
+                (outcome.host.clone(), result)
+            })
+            .collect();
+        println!();
+        println!(
+            "{}",
+            Renderer::new(cli.output.clone()).format_host_results(&results)
+        );
+    }
 
+    if failed > 0 {
+        return Err(anyhow::anyhow!(
+            "{} of {} remote hosts failed",
+            failed,
+            total
+        ));
+    }
     Ok(())
 }
 
 /// Execute Guard Mode operation
 async fn execute_guard_operation(
     cli: &crate::args::Cli,
-    _gpu_manager: crate::vendor::GpuManager,
+    gpu_manager: crate::vendor::GpuManager,
+    renderer: &Renderer,
 ) -> Result<()> {
     use crate::guard_mode::GuardModeManager;
-    use crate::render::render_info;
 
     // Initialize guard mode manager
     let mut guard_manager =
         GuardModeManager::new().context("Failed to initialize Guard Mode manager")?;
 
+    // A policy whose card was removed must not silently apply to another one
+    if let Some(stable) = gpu_manager.stable_indices() {
+        let policy_indices = guard_manager
+            .get_config()
+            .gpu_policies
+            .values()
+            .map(|policy| policy.gpu_index);
+        for gpu in stable.missing(policy_indices) {
+            renderer.warning(&format!(
+                "GPU policy for index {} refers to {} ({}), which is no longer present; it applies to no GPU until the card returns or another card is given this index with --remap-gpu",
+                gpu.index, gpu.id, gpu.name
+            ));
+        }
+    }
+
+    if cli.test_notification {
+        let notifications = &guard_manager.get_config().enforcement.notifications;
+        if !notifications.email {
+            renderer.warning(
+                "Email notifications are disabled (enforcement.notifications.email = false); sending the test message anyway",
+            );
+        }
+        renderer.info(&format!(
+            "{}Sending test notification via {}",
+            icon(Icon::Test),
+            notifications.smtp.endpoint()
+        ));
+        let recipients = guard_manager
+            .send_test_notification()
+            .context("Test notification failed")?;
+        for recipient in recipients {
+            renderer.info(&format!(
+                "{}Test notification sent to {}",
+                icon(Icon::Ok),
+                recipient
+            ));
+        }
+        return Ok(());
+    }
+
     // Handle configuration management
     if cli.guard_config
         || cli.guard_enable
@@ -1268,45 +4466,67 @@ async fn execute_guard_operation(
         || cli.guard_test_policies
         || cli.guard_toggle_dry_run
     {
-        // Show current configuration
-        if cli.guard_config {
+        let mut report =
+            GuardConfigReport::new(guard_manager.get_config_file_path().display().to_string());
+
+        // Show current configuration; with changes in JSON output the result
+        // document carries the full config instead
+        let changes_requested = cli.guard_enable
+            || cli.guard_disable
+            || cli.guard_dry_run
+            || cli.guard_enforce
+            || cli.guard_add_user.is_some()
+            || cli.guard_remove_user.is_some()
+            || cli.guard_add_group.is_some()
+            || cli.guard_remove_group.is_some()
+            || cli.guard_add_gpu.is_some()
+            || cli.guard_remove_gpu.is_some()
+            || cli.guard_import_config.is_some()
+            || cli.guard_toggle_dry_run;
+        if cli.guard_config && renderer.is_json() && changes_requested {
+            for section in GUARD_CONFIG_SECTIONS {
+                report.include(section);
+            }
+        } else if cli.guard_config && renderer.is_json() {
+            write_guard_config_json(&mut std::io::stdout(), &guard_manager)?;
+        } else if cli.guard_config {
             let config = guard_manager.get_config();
-            render_info("🛡️ Guard Mode Configuration:");
-            render_info(&format!("  Enabled: {}", config.global.enabled));
-            render_info(&format!("  Dry Run: {}", config.global.dry_run));
-            render_info(&format!(
+            renderer.info(&format!("{}Guard Mode Configuration:", icon(Icon::Shield)));
+            renderer.info(&format!("  Enabled: {}", config.global.enabled));
+            renderer.info(&format!("  Dry Run: {}", config.global.dry_run));
+            renderer.info(&format!(
                 "  Default Memory Limit: {:.1} GB",
                 config.global.default_memory_limit_gb
             ));
-            render_info(&format!(
+            renderer.info(&format!(
                 "  Default Utilization Limit: {:.1}%",
                 config.global.default_utilization_limit_pct
             ));
-            render_info(&format!(
+            renderer.info(&format!(
                 "  Default Duration Limit: {:.1} hours",
                 config.global.default_duration_limit_hours
             ));
-            render_info(&format!(
+            renderer.info(&format!(
                 "  Check Interval: {} seconds",
                 config.global.check_interval_seconds
             ));
 
-            render_info(&format!(
+            renderer.info(&format!(
                 "  Soft Enforcement: {}",
                 config.enforcement.soft_enforcement
             ));
-            render_info(&format!(
+            renderer.info(&format!(
                 "  Hard Enforcement: {}",
                 config.enforcement.hard_enforcement
             ));
-            render_info(&format!(
+            renderer.info(&format!(
                 "  Grace Period: {} seconds",
                 config.enforcement.grace_period_seconds
             ));
 
-            render_info("\n👥 User Policies:");
+            renderer.info(&format!("\n{}User Policies:", icon(Icon::Users)));
             for (username, policy) in &config.user_policies {
-                render_info(&format!(
+                renderer.info(&format!(
                     "  - {}: {:.1}GB memory, {:.1}% util, {} processes",
                     username,
                     policy.memory_limit_gb,
@@ -1315,7 +4535,7 @@ async fn execute_guard_operation(
                 ));
             }
 
-            render_info("\n👥 Group Policies:");
+            renderer.info(&format!("\n{}Group Policies:", icon(Icon::Users)));
             for (group_name, policy) in &config.group_policies {
                 let members_info = if !policy.members.is_empty() {
                     format!(
@@ -1326,7 +4546,7 @@ async fn execute_guard_operation(
                 } else {
                     "".to_string()
                 };
-                render_info(&format!(
+                renderer.info(&format!(
                     "  - {}: {:.1}GB memory, {:.1}% util, {} processes{}",
                     group_name,
                     policy.total_memory_limit_gb,
@@ -1336,7 +4556,7 @@ async fn execute_guard_operation(
                 ));
             }
 
-            render_info("\n🖥️ GPU Policies:");
+            renderer.info(&format!("\n{}GPU Policies:", icon(Icon::Gpu)));
             for (gpu_index, policy) in &config.gpu_policies {
                 let users_info = if !policy.allowed_users.is_empty() {
                     format!(
@@ -1347,7 +4567,7 @@ async fn execute_guard_operation(
                 } else {
                     "".to_string()
                 };
-                render_info(&format!(
+                renderer.info(&format!(
                     "  - GPU {}: {:.1}GB memory, {:.1}% util, {:.1}GB reserved{}",
                     gpu_index,
                     policy.max_memory_gb,
@@ -1357,8 +4577,9 @@ async fn execute_guard_operation(
                 ));
             }
 
-            render_info(&format!(
-                "\n📁 Config file: {}",
+            renderer.info(&format!(
+                "\n{}Config file: {}",
+                icon(Icon::File),
                 guard_manager.get_config_file_path().display()
             ));
         }
@@ -1368,14 +4589,16 @@ async fn execute_guard_operation(
             guard_manager
                 .set_enabled(true)
                 .context("Failed to enable Guard Mode")?;
-            render_info("✅ Guard Mode enabled");
+            renderer.info(&format!("{}Guard Mode enabled", icon(Icon::Ok)));
+            report.changed("global", "Guard Mode enabled");
         }
 
         if cli.guard_disable {
             guard_manager
                 .set_enabled(false)
                 .context("Failed to disable Guard Mode")?;
-            render_info("✅ Guard Mode disabled");
+            renderer.info(&format!("{}Guard Mode disabled", icon(Icon::Ok)));
+            report.changed("global", "Guard Mode disabled");
         }
 
         // Set dry-run mode
@@ -1383,14 +4606,22 @@ async fn execute_guard_operation(
             guard_manager
                 .set_dry_run(true)
                 .context("Failed to set dry-run mode")?;
-            render_info("✅ Guard Mode set to dry-run (no enforcement)");
+            renderer.info(&format!(
+                "{}Guard Mode set to dry-run (no enforcement)",
+                icon(Icon::Ok)
+            ));
+            report.changed("global", "Guard Mode set to dry-run");
         }
 
         if cli.guard_enforce {
             guard_manager
                 .set_dry_run(false)
                 .context("Failed to set enforcement mode")?;
-            render_info("✅ Guard Mode set to enforce policies");
+            renderer.info(&format!(
+                "{}Guard Mode set to enforce policies",
+                icon(Icon::Ok)
+            ));
+            report.changed("global", "Guard Mode set to enforce policies");
         }
 
         // Add user policy
@@ -1414,10 +4645,18 @@ async fn execute_guard_operation(
             guard_manager
                 .add_user_policy(user_policy)
                 .context("Failed to add user policy")?;
-            render_info(&format!(
-                "✅ Added policy for user '{}': {:.1}GB memory, {:.1}% util, {} processes",
-                username, memory_limit, utilization_limit, process_limit
+            renderer.info(&format!(
+                "{}Added policy for user '{}': {:.1}GB memory, {:.1}% util, {} processes",
+                icon(Icon::Ok),
+                username,
+                memory_limit,
+                utilization_limit,
+                process_limit
             ));
+            report.changed(
+                "user_policies",
+                format!("Added policy for user '{}'", username),
+            );
         }
 
         // Remove user policy
@@ -1425,7 +4664,15 @@ async fn execute_guard_operation(
             guard_manager
                 .remove_user_policy(username)
                 .context("Failed to remove user policy")?;
-            render_info(&format!("✅ Removed policy for user '{}'", username));
+            renderer.info(&format!(
+                "{}Removed policy for user '{}'",
+                icon(Icon::Ok),
+                username
+            ));
+            report.changed(
+                "user_policies",
+                format!("Removed policy for user '{}'", username),
+            );
         }
 
         // Add group policy
@@ -1466,10 +4713,19 @@ async fn execute_guard_operation(
                 .add_group_policy(group_policy)
                 .context("Failed to add group policy")?;
 
-            render_info(&format!(
-                "✅ Added policy for group '{}': {:.1}GB memory, {:.1}% util, {} processes{}",
-                group_name, memory_limit, utilization_limit, process_limit, members_info
+            renderer.info(&format!(
+                "{}Added policy for group '{}': {:.1}GB memory, {:.1}% util, {} processes{}",
+                icon(Icon::Ok),
+                group_name,
+                memory_limit,
+                utilization_limit,
+                process_limit,
+                members_info
             ));
+            report.changed(
+                "group_policies",
+                format!("Added policy for group '{}'", group_name),
+            );
         }
 
         // Remove group policy
@@ -1477,7 +4733,15 @@ async fn execute_guard_operation(
             guard_manager
                 .remove_group_policy(group_name)
                 .context("Failed to remove group policy")?;
-            render_info(&format!("✅ Removed policy for group '{}'", group_name));
+            renderer.info(&format!(
+                "{}Removed policy for group '{}'",
+                icon(Icon::Ok),
+                group_name
+            ));
+            report.changed(
+                "group_policies",
+                format!("Removed policy for group '{}'", group_name),
+            );
         }
 
         // Add GPU policy
@@ -1521,10 +4785,19 @@ async fn execute_guard_operation(
                 .add_gpu_policy(gpu_policy)
                 .context("Failed to add GPU policy")?;
 
-            render_info(&format!(
-                "✅ Added policy for GPU {}: {:.1}GB memory, {:.1}% util, {:.1}GB reserved{}",
-                gpu_index, memory_limit, utilization_limit, reserved_memory, users_info
+            renderer.info(&format!(
+                "{}Added policy for GPU {}: {:.1}GB memory, {:.1}% util, {:.1}GB reserved{}",
+                icon(Icon::Ok),
+                gpu_index,
+                memory_limit,
+                utilization_limit,
+                reserved_memory,
+                users_info
             ));
+            report.changed(
+                "gpu_policies",
+                format!("Added policy for GPU {}", gpu_index),
+            );
         }
 
         // Remove GPU policy
@@ -1532,7 +4805,15 @@ async fn execute_guard_operation(
             guard_manager
                 .remove_gpu_policy(gpu_index)
                 .context("Failed to remove GPU policy")?;
-            render_info(&format!("✅ Removed policy for GPU {}", gpu_index));
+            renderer.info(&format!(
+                "{}Removed policy for GPU {}",
+                icon(Icon::Ok),
+                gpu_index
+            ));
+            report.changed(
+                "gpu_policies",
+                format!("Removed policy for GPU {}", gpu_index),
+            );
         }
 
         // Export configuration
@@ -1550,39 +4831,60 @@ async fn execute_guard_operation(
             guard_manager
                 .import_from_json(&content)
                 .context("Failed to import Guard Mode config from JSON")?;
-            render_info(&format!(
-                "✅ Imported Guard Mode configuration from: {}",
+            renderer.info(&format!(
+                "{}Imported Guard Mode configuration from: {}",
+                icon(Icon::Ok),
                 file_path
             ));
+            report.changed(
+                "global",
+                format!("Imported Guard Mode configuration from {}", file_path),
+            );
+            for section in GUARD_CONFIG_SECTIONS {
+                report.include(section);
+            }
         }
 
         // Test policies in dry-run mode
         if cli.guard_test_policies {
-            render_info("🧪 Testing policies in dry-run mode...");
+            let json_output = cli.output == OutputFormat::Json;
+            if !json_output {
+                renderer.info(&format!(
+                    "{}Testing policies in dry-run mode...",
+                    icon(Icon::Test)
+                ));
+            }
 
             // Get current GPU processes for testing
-            let gpu_manager = crate::vendor::GpuManager::initialize()
-                .context("Failed to initialize GPU manager")?;
             let test_processes = gpu_manager
                 .get_all_processes()
                 .context("Failed to get GPU processes")?;
+            let test_gpus = gpu_manager
+                .get_all_snapshots()
+                .context("Failed to get GPU snapshots")?;
 
+            guard_manager.set_drain(crate::drain::NodeDrain::load_local());
             let result = guard_manager
-                .simulate_policy_check(&test_processes)
+                .simulate_policy_check(&test_processes, &test_gpus)
                 .context("Failed to simulate policy check")?;
 
-            render_info("📊 Simulation Results:");
-            render_info(&format!("  Violations found: {}", result.violations.len()));
-            render_info(&format!("  Warnings found: {}", result.warnings.len()));
-            render_info(&format!(
+            if json_output {
+                write_enforcement_result_json(&mut std::io::stdout(), &result)?;
+                return Ok(());
+            }
+
+            renderer.info(&format!("{}Simulation Results:", icon(Icon::Stats)));
+            renderer.info(&format!("  Violations found: {}", result.violations.len()));
+            renderer.info(&format!("  Warnings found: {}", result.warnings.len()));
+            renderer.info(&format!(
                 "  Actions simulated: {}",
                 result.actions_taken.len()
             ));
 
             if !result.violations.is_empty() {
-                render_info("\n🚨 Simulated Violations:");
+                renderer.info(&format!("\n{}Simulated Violations:", icon(Icon::Alert)));
                 for (i, violation) in result.violations.iter().enumerate() {
-                    render_info(&format!(
+                    renderer.info(&format!(
                         "  {}. {} - {:?} ({:?}): {}",
                         i + 1,
                         violation.user,
@@ -1594,9 +4896,9 @@ async fn execute_guard_operation(
             }
 
             if !result.actions_taken.is_empty() {
-                render_info("\n⚡ Simulated Actions:");
+                renderer.info(&format!("\n{}Simulated Actions:", icon(Icon::Action)));
                 for (i, action) in result.actions_taken.iter().enumerate() {
-                    render_info(&format!(
+                    renderer.info(&format!(
                         "  {}. {:?}: {}",
                         i + 1,
                         action.action_type,
@@ -1606,7 +4908,10 @@ async fn execute_guard_operation(
             }
 
             if result.violations.is_empty() && result.warnings.is_empty() {
-                render_info("✅ No policy violations detected in simulation!");
+                renderer.info(&format!(
+                    "{}No policy violations detected in simulation!",
+                    icon(Icon::Ok)
+                ));
             }
         }
 
@@ -1615,34 +4920,88 @@ async fn execute_guard_operation(
             let new_dry_run = guard_manager
                 .toggle_dry_run()
                 .context("Failed to toggle dry-run mode")?;
-            render_info(&format!(
-                "✅ Dry-run mode {} (simulation only)",
+            renderer.info(&format!(
+                "{}Dry-run mode {} (simulation only)",
+                icon(Icon::Ok),
                 if new_dry_run { "enabled" } else { "disabled" }
             ));
+            report.changed(
+                "global",
+                format!(
+                    "Dry-run mode {}",
+                    if new_dry_run { "enabled" } else { "disabled" }
+                ),
+            );
         }
 
+        if changes_requested {
+            let report = report.with_sections(guard_manager.get_config())?;
+            renderer
+                .render_result(&report)
+                .map_err(|e| anyhow::anyhow!("Render error: {}", e))?;
+        }
         return Ok(());
     }
 
     // If no specific guard operations, show help
-    render_info("🛡️ Guard Mode - Soft Policy Enforcement");
-    render_info("Use --guard-config to view current configuration");
-    render_info("Use --guard-enable to enable Guard Mode");
-    render_info("Use --guard-dry-run to test policies without enforcement");
-    render_info("Use --guard-add-user <username> to add user policies");
+    renderer.info(&format!(
+        "{}Guard Mode - Soft Policy Enforcement",
+        icon(Icon::Shield)
+    ));
+    renderer.info("Use --guard-config to view current configuration");
+    renderer.info("Use --guard-enable to enable Guard Mode");
+    renderer.info("Use --guard-dry-run to test policies without enforcement");
+    renderer.info("Use --guard-add-user <username> to add user policies");
+
+    Ok(())
+}
 
+/// Guard Mode config sections reported when a change replaces the whole config
+const GUARD_CONFIG_SECTIONS: [&str; 7] = [
+    "global",
+    "user_policies",
+    "group_policies",
+    "gpu_policies",
+    "time_policies",
+    "enforcement",
+    "idle_whitelist",
+];
+
+/// Write the Guard Mode configuration as JSON (`--guard-config --output json`)
+fn write_guard_config_json(
+    out: &mut impl std::io::Write,
+    guard_manager: &crate::guard_mode::GuardModeManager,
+) -> Result<()> {
+    let json = guard_manager
+        .export_to_json()
+        .context("Failed to export Guard Mode config to JSON")?;
+    writeln!(out, "{}", json).context("Failed to write Guard Mode config")?;
+    Ok(())
+}
+
+/// Write a policy simulation result as JSON (`--guard-test-policies --output json`)
+fn write_enforcement_result_json(
+    out: &mut impl std::io::Write,
+    result: &crate::guard_mode::EnforcementResult,
+) -> Result<()> {
+    let json = serde_json::to_string_pretty(result)
+        .context("Failed to serialize policy simulation result to JSON")?;
+    writeln!(out, "{}", json).context("Failed to write policy simulation result")?;
     Ok(())
 }
 
 /// Execute node registration operation
 async fn execute_register_node_operation(
     coordinator_url: String,
+    tags: std::collections::HashMap<String, String>,
+    snapshot_delta: bool,
+    rogue_scan_interval: Duration,
+    cluster_token: Option<String>,
     gpu_manager: GpuManager,
 ) -> Result<()> {
-    use crate::coordinator::{NodeInfo, NodeSnapshot, NodeStatus};
+    use crate::coordinator::{NodeCommand, NodeInfo, NodeSnapshot};
     use crate::render::render_info;
     use reqwest::Client;
-    use std::collections::HashMap;
     use uuid::Uuid;
 
     info!("Registering node with coordinator: {}", coordinator_url);
@@ -1653,14 +5012,12 @@ async fn execute_register_node_operation(
     let ip_address = "127.0.0.1".to_string(); // Simplified for now
 
     // Get GPU information
-    let gpus = gpu_manager
-        .get_all_snapshots()
+    let snapshot = NodeSnapshot::collect(&gpu_manager, node_id.clone(), hostname.clone(), tags)
+        .await
         .context("Failed to get GPU snapshots")?;
-    let procs = gpu_manager
-        .get_all_processes()
-        .context("Failed to get GPU processes")?;
 
-    let total_memory_gb: f32 = gpus
+    let total_memory_gb: f32 = snapshot
+        .gpus
         .iter()
         .map(|gpu| gpu.mem_total_mb as f32 / 1024.0)
         .sum();
@@ -1668,23 +5025,14 @@ async fn execute_register_node_operation(
     // Create node info
     let node_info = NodeInfo {
         id: node_id.clone(),
-        hostname: hostname.clone(),
+        hostname,
         ip_address,
         last_seen: chrono::Utc::now(),
-        status: NodeStatus::Online,
-        gpu_count: gpus.len() as u32,
+        status: snapshot.status.clone(),
+        gpu_count: snapshot.gpus.len() as u32,
         total_memory_gb,
-        tags: HashMap::new(),
-    };
-
-    // Create node snapshot
-    let snapshot = NodeSnapshot {
-        node_id: node_id.clone(),
-        hostname,
-        timestamp: chrono::Utc::now(),
-        gpus,
-        processes: procs,
-        status: NodeStatus::Online,
+        tags: snapshot.tags.clone(),
+        drain: crate::drain::NodeDrain::load_local(),
     };
 
     let client = Client::new();
@@ -1695,7 +5043,8 @@ async fn execute_register_node_operation(
         Ok(response) => {
             if response.status().is_success() {
                 render_info(&format!(
-                    "✅ Successfully registered node {} with coordinator",
+                    "{}Successfully registered node {} with coordinator",
+                    icon(Icon::Ok),
                     node_id
                 ));
             } else {
@@ -1712,79 +5061,907 @@ async fn execute_register_node_operation(
 
     // Send initial snapshot
     let snapshot_url = format!("{}/api/nodes/{}/snapshot", coordinator_url, node_id);
-    match client.post(&snapshot_url).json(&snapshot).send().await {
-        Ok(response) => {
-            if response.status().is_success() {
-                render_info("✅ Successfully sent initial snapshot to coordinator");
-            } else {
-                return Err(anyhow::anyhow!(
-                    "Failed to send snapshot: HTTP {}",
-                    response.status()
-                ));
+    let mut gzip = true;
+    upload_snapshot(&client, &snapshot_url, &snapshot, None, &mut gzip)
+        .await
+        .context("Failed to send snapshot")?;
+    render_info(&format!(
+        "{}Successfully sent initial snapshot to coordinator",
+        icon(Icon::Ok)
+    ));
+    let mut last_sent = Some(snapshot);
+
+    // Rogue detection runs on this node's audit history. With a shared audit
+    // database the coordinator already records the node's snapshots there;
+    // otherwise the node keeps its own history in the local log.
+    let commands_url = format!("{}/api/nodes/{}/commands", coordinator_url, node_id);
+    let rogue_url = format!("{}/api/nodes/{}/rogue", coordinator_url, node_id);
+    let reset_url = format!("{}/api/nodes/{}/reset", coordinator_url, node_id);
+    let local_audit = if crate::config::get_config(None)?
+        .config()
+        .audit
+        .database_url
+        .is_none()
+    {
+        match crate::audit::AuditManager::with_database_url(None).await {
+            Ok(audit) => Some(audit),
+            Err(e) => {
+                warn!("Failed to open local audit log: {}", e);
+                None
             }
         }
-        Err(e) => {
-            return Err(anyhow::anyhow!("Failed to send snapshot: {}", e));
-        }
-    }
+    } else {
+        None
+    };
+    let mut next_rogue_scan = tokio::time::Instant::now();
 
     // Start periodic snapshot updates
-    render_info("🔄 Starting periodic snapshot updates...");
+    render_info(&format!(
+        "{}Starting periodic snapshot updates...",
+        icon(Icon::Refresh)
+    ));
     let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
 
     loop {
         interval.tick().await;
 
-        // Get fresh snapshot
-        let gpus = match gpu_manager.get_all_snapshots() {
-            Ok(gpus) => gpus,
+        // Get fresh snapshot; GPUs that stop answering mark the node degraded
+        let snapshot = match NodeSnapshot::collect(
+            &gpu_manager,
+            node_id.clone(),
+            node_info.hostname.clone(),
+            node_info.tags.clone(),
+        )
+        .await
+        {
+            Ok(snapshot) => snapshot,
             Err(e) => {
                 warn!("Failed to get GPU snapshots: {}", e);
                 continue;
             }
         };
 
-        let procs = match gpu_manager.get_all_processes() {
-            Ok(procs) => procs,
-            Err(e) => {
-                warn!("Failed to get GPU processes: {}", e);
-                continue;
+        // Send snapshot; a failed upload is sent in full next time
+        let previous = last_sent.take().filter(|_| snapshot_delta);
+        match upload_snapshot(
+            &client,
+            &snapshot_url,
+            &snapshot,
+            previous.as_ref(),
+            &mut gzip,
+        )
+        .await
+        {
+            Ok(()) => {
+                debug!("Successfully sent snapshot update");
+                last_sent = Some(snapshot.clone());
             }
-        };
+            Err(e) => warn!("Failed to send snapshot update: {:#}", e),
+        }
 
-        let snapshot = NodeSnapshot {
-            node_id: node_id.clone(),
-            hostname: node_info.hostname.clone(),
-            timestamp: chrono::Utc::now(),
-            gpus,
-            processes: procs,
-            status: NodeStatus::Online,
-        };
+        if let Some(audit) = &local_audit {
+            let records =
+                crate::coordinator::snapshots_to_audit_records(std::slice::from_ref(&snapshot));
+            if let Err(e) = audit.append_records(&records).await {
+                warn!("Failed to record snapshot in local audit log: {}", e);
+            }
+        }
 
-        // Send snapshot
-        match client.post(&snapshot_url).json(&snapshot).send().await {
-            Ok(response) => {
-                if response.status().is_success() {
-                    debug!("Successfully sent snapshot update");
-                } else {
-                    warn!("Failed to send snapshot update: HTTP {}", response.status());
+        let commands =
+            match fetch_node_commands(&client, &commands_url, cluster_token.as_deref()).await {
+                Ok(commands) => commands,
+                Err(e) => {
+                    debug!("Failed to fetch commands from coordinator: {:#}", e);
+                    Vec::new()
                 }
+            };
+        for command in &commands {
+            apply_drain_command(command);
+            if let NodeCommand::Kill { pids, reason } = command {
+                // The coordinator kills only through nodes trusted with the cluster token
+                if cluster_token.is_none() {
+                    warn!(
+                        "Ignoring kill at the drain deadline: register with --cluster-token to let the coordinator enforce drains"
+                    );
+                } else if let Err(e) = execute_drain_kill(&gpu_manager, pids, reason).await {
+                    warn!("Failed to kill processes at the drain deadline: {:#}", e);
+                }
+                continue;
             }
-            Err(e) => {
-                warn!("Failed to send snapshot update: {}", e);
+            let NodeCommand::Reset {
+                batch_id,
+                gpu_index,
+                force,
+            } = command
+            else {
+                continue;
+            };
+            // Results are posted with the cluster token, so a node without one
+            // cannot take part in cluster resets
+            let Some(token) = &cluster_token else {
+                warn!(
+                    "Ignoring cluster reset of GPU {}: register with --cluster-token to accept cluster resets",
+                    gpu_index
+                );
+                continue;
+            };
+            let sent = match execute_cluster_reset(&gpu_manager, batch_id, *gpu_index, *force).await
+            {
+                Ok(result) => client
+                    .post(&reset_url)
+                    .bearer_auth(token)
+                    .json(&result)
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status())
+                    .map(|_| ())
+                    .map_err(anyhow::Error::from),
+                Err(e) => Err(e),
+            };
+            match sent {
+                Ok(()) => debug!("Sent cluster reset result for GPU {}", gpu_index),
+                Err(e) => warn!(
+                    "Failed to report cluster reset of GPU {}: {:#}",
+                    gpu_index, e
+                ),
+            }
+        }
+
+        let requested = commands.contains(&NodeCommand::RogueScan);
+        if requested || tokio::time::Instant::now() >= next_rogue_scan {
+            next_rogue_scan = tokio::time::Instant::now() + rogue_scan_interval;
+            let sent = match scan_own_rogue_activity(&node_info.hostname).await {
+                Ok(result) => client
+                    .post(&rogue_url)
+                    .json(&result)
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status())
+                    .map(|_| ())
+                    .map_err(anyhow::Error::from),
+                Err(e) => Err(e),
+            };
+            match sent {
+                Ok(()) => debug!("Sent rogue detection result"),
+                Err(e) => warn!("Failed to report rogue detection result: {:#}", e),
             }
         }
     }
 }
 
+/// Run a reset the coordinator queued for this host and record it in the action audit log
+async fn execute_cluster_reset(
+    gpu_manager: &GpuManager,
+    batch_id: &str,
+    gpu_index: u16,
+    force: bool,
+) -> Result<crate::coordinator::NodeResetResult> {
+    info!("Cluster reset {}: resetting GPU {}", batch_id, gpu_index);
+    let manager = gpu_manager.clone();
+    let outcome = tokio::task::spawn_blocking(move || {
+        crate::coordinator::run_reset_command(&manager, gpu_index, force)
+    })
+    .await
+    .context("Reset task panicked")?;
+    let result = match &outcome.error {
+        Some(error) => Err(anyhow::anyhow!("{}", error)),
+        None => Ok(()),
+    };
+    record_action(
+        ActionKind::Reset,
+        &format!("gpu {}", gpu_index),
+        false,
+        &result,
+    )
+    .await;
+    Ok(crate::coordinator::NodeResetResult {
+        batch_id: batch_id.to_string(),
+        outcome,
+    })
+}
+
+/// Seconds processes left at an enforced drain deadline get to exit before SIGKILL
+const DRAIN_KILL_TIMEOUT_SECS: u16 = 10;
+
+/// Record a drain of this host, or its end, in the local drain marker read by
+/// `--list` and Guard Mode
+fn apply_drain_command(command: &crate::coordinator::NodeCommand) {
+    use crate::coordinator::NodeCommand;
+    use crate::drain::NodeDrain;
+
+    let result = match command {
+        NodeCommand::Drain { drain } => {
+            info!("{}", drain.banner(chrono::Utc::now()));
+            NodeDrain::default_path().and_then(|path| drain.save(&path))
+        }
+        NodeCommand::Undrain => {
+            info!("This node is no longer draining");
+            NodeDrain::default_path().and_then(|path| NodeDrain::clear(&path))
+        }
+        _ => return,
+    };
+    if let Err(e) = result {
+        warn!("Failed to update the local drain marker: {:#}", e);
+    }
+}
+
+/// Kill the processes the coordinator found still running at an enforced drain
+/// deadline and record the kill in the action audit log. Only PIDs that are still
+/// GPU processes on this host are signalled, and protected processes are skipped.
+async fn execute_drain_kill(gpu_manager: &GpuManager, pids: &[u32], reason: &str) -> Result<()> {
+    info!("{}: killing {} remaining processes", reason, pids.len());
+    let manager = gpu_manager.clone();
+    let wanted = pids.to_vec();
+    let outcome = tokio::task::spawn_blocking(move || -> Result<_> {
+        let processes: Vec<_> = manager
+            .get_all_processes()?
+            .into_iter()
+            .filter(|p| wanted.contains(&p.pid))
+            .collect();
+        let protected = get_config(None)?.config().protected_processes.clone();
+        let mut killer = init_process_manager(&manager, KillSignal::default())?
+            .with_protection(&protected, false);
+        Ok(killer.batch_kill(&processes, DRAIN_KILL_TIMEOUT_SECS, true))
+    })
+    .await
+    .context("Kill task panicked")??;
+    for (process, reason) in &outcome.skipped {
+        warn!(
+            "Not killing PID {} at the drain deadline: {}",
+            process.pid, reason
+        );
+    }
+
+    let result = if outcome.failed.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(outcome.failure_message()))
+    };
+    let killed = outcome.killed_pids();
+    if !killed.is_empty() || result.is_err() {
+        record_action(
+            ActionKind::Kill,
+            &killed_action_target(&killed, reason),
+            false,
+            &result,
+        )
+        .await;
+    }
+    result
+}
+
+/// Audit target of a kill the coordinator or a reset asked for: only the PIDs that
+/// died, since the error of the recorded result names those that survived
+fn killed_action_target(killed: &[u32], reason: &str) -> String {
+    if killed.is_empty() {
+        return format!("no pids ({})", reason);
+    }
+    let pids: Vec<String> = killed.iter().map(|pid| pid.to_string()).collect();
+    format!("pids {} ({})", pids.join(", "), reason)
+}
+
+/// Commands the coordinator has queued for this node. A coordinator with a cluster
+/// token hands them out only to nodes presenting it.
+async fn fetch_node_commands(
+    client: &reqwest::Client,
+    url: &str,
+    cluster_token: Option<&str>,
+) -> Result<Vec<crate::coordinator::NodeCommand>> {
+    let mut request = client.get(url);
+    if let Some(token) = cluster_token {
+        request = request.bearer_auth(token);
+    }
+    Ok(request.send().await?.error_for_status()?.json().await?)
+}
+
+/// How often nodes scan for rogue activity and when the coordinator calls a
+/// node's result stale
+#[derive(Debug, Clone, Copy)]
+struct RogueScanSettings {
+    interval: Duration,
+    stale_after: Duration,
+}
+
+/// Rogue detection over this host's audit history, as reported to the coordinator
+async fn scan_own_rogue_activity(
+    hostname: &str,
+) -> Result<crate::rogue_detection::RogueDetectionResult> {
+    use crate::rogue_config::RogueConfigManager;
+    use crate::rogue_detection::RogueDetector;
+
+    let config_manager =
+        RogueConfigManager::new().context("Failed to initialize rogue config manager")?;
+    let audit_manager = crate::audit::AuditManager::new()
+        .await
+        .context("Failed to open audit log")?;
+    RogueDetector::with_config(audit_manager, &config_manager)
+        .detect_rogue_activity_on_host(crate::coordinator::ROGUE_SCAN_HOURS, hostname)
+        .await
+        .context("Failed to perform rogue detection")
+}
+
+/// POST `snapshot` to the coordinator, as a delta against `previous` when given
+/// and gzipped while `gzip` is set. Falls back to the full snapshot when the
+/// coordinator cannot apply the delta (409), and turns `gzip` off for good when
+/// the coordinator does not understand compressed bodies.
+async fn upload_snapshot(
+    client: &reqwest::Client,
+    url: &str,
+    snapshot: &crate::coordinator::NodeSnapshot,
+    previous: Option<&crate::coordinator::NodeSnapshot>,
+    gzip: &mut bool,
+) -> Result<()> {
+    use crate::coordinator::SnapshotUpload;
+    use reqwest::{header, StatusCode};
+
+    let mut upload = SnapshotUpload::new(snapshot, previous);
+    loop {
+        let mut request = client
+            .post(url)
+            .header(header::CONTENT_TYPE, "application/json");
+        if *gzip {
+            request = request.header(header::CONTENT_ENCODING, "gzip");
+        }
+        let status = request.body(upload.encode(*gzip)?).send().await?.status();
+        match status {
+            status if status.is_success() => return Ok(()),
+            StatusCode::CONFLICT if matches!(upload, SnapshotUpload::Delta { .. }) => {
+                debug!("Coordinator cannot apply the snapshot delta; sending it in full");
+                upload = SnapshotUpload::new(snapshot, None);
+            }
+            StatusCode::BAD_REQUEST | StatusCode::UNSUPPORTED_MEDIA_TYPE if *gzip => {
+                warn!(
+                    "Coordinator rejected a gzipped snapshot (HTTP {}); sending uncompressed",
+                    status
+                );
+                *gzip = false;
+            }
+            status => return Err(anyhow::anyhow!("HTTP {}", status)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// One GPU running PID 4242 for the first `busy_polls` process queries and
+    /// nothing afterwards
+    struct DrainingVendor {
+        busy_polls: usize,
+        polls: std::sync::atomic::AtomicUsize,
+        resets_after: std::sync::Mutex<Vec<usize>>,
+    }
+
+    impl crate::vendor::GpuVendorInterface for DrainingVendor {
+        fn initialize() -> Result<Self> {
+            Ok(Self {
+                busy_polls: 0,
+                polls: Default::default(),
+                resets_after: Default::default(),
+            })
+        }
+
+        fn vendor_type(&self) -> crate::vendor::GpuVendor {
+            crate::vendor::GpuVendor::Unknown
+        }
+
+        fn device_count(&self) -> Result<u32> {
+            Ok(1)
+        }
+
+        fn get_gpu_info(&self, index: u32) -> Result<crate::nvml_api::GpuInfo> {
+            Ok(crate::nvml_api::GpuInfo {
+                index: index as u16,
+                name: "Draining GPU".to_string(),
+                mem_total_mb: 1024,
+                uuid: None,
+                serial: None,
+                pci_bus_id: None,
+            })
+        }
+
+        fn get_gpu_snapshot(&self, _index: u32) -> Result<crate::nvml_api::GpuSnapshot> {
+            Err(anyhow::anyhow!("not used"))
+        }
+
+        fn get_gpu_processes(&self, index: u32) -> Result<Vec<crate::nvml_api::GpuProc>> {
+            let n = self.polls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(if n < self.busy_polls {
+                vec![reset_test_proc(index as u16, 4242)]
+            } else {
+                Vec::new()
+            })
+        }
+
+        fn reset_gpu(&self, _index: u32) -> Result<()> {
+            let polls = self.polls.load(std::sync::atomic::Ordering::SeqCst);
+            self.resets_after.lock().unwrap().push(polls);
+            Ok(())
+        }
+
+        fn capabilities(&self) -> crate::vendor::VendorCapabilities {
+            crate::vendor::VendorCapabilities {
+                reset_supported: true,
+                process_enum_supported: true,
+                power_limit_supported: false,
+                util_supported: true,
+            }
+        }
+
+        fn is_available() -> bool {
+            true
+        }
+
+        fn get_availability_error() -> String {
+            String::new()
+        }
+    }
+
+    /// Reset GPU 0 of a [`DrainingVendor`] busy for `busy_polls` polls, waiting up to
+    /// `timeout`. Returns the result, the report and after how many polls it reset.
+    async fn reset_draining_gpu(
+        busy_polls: usize,
+        timeout: Duration,
+    ) -> (Result<()>, ResetReport, Vec<usize>) {
+        let vendor = std::sync::Arc::new(DrainingVendor {
+            busy_polls,
+            polls: Default::default(),
+            resets_after: Default::default(),
+        });
+        let gpu_manager =
+            GpuManager::with_vendor(vendor.clone()).with_snapshot_cache_ttl(Duration::ZERO);
+        let wait = DrainWait {
+            timeout,
+            poll: Duration::from_millis(1),
+            kill_timeout_secs: 1,
+            kill_signal: KillSignal::Term,
+            protected_names: Vec::new(),
+        };
+        let mut report = ResetReport::new(false, false);
+        let result = execute_reset_single_gpu(
+            &gpu_manager,
+            0,
+            Some(wait),
+            false,
+            false,
+            &mut report,
+            &Renderer::new(OutputFormat::Json),
+        )
+        .await;
+        let resets_after = vendor.resets_after.lock().unwrap().clone();
+        (result, report, resets_after)
+    }
+
+    #[tokio::test]
+    async fn test_reset_wait_resets_after_gpu_drains() {
+        let (result, report, resets_after) = reset_draining_gpu(2, Duration::from_secs(10)).await;
+        result.unwrap();
+        assert_eq!(resets_after, vec![3]);
+        assert_eq!(report.gpus[0].status, ResetStatus::Reset);
+        assert!(report.gpus[0].waited_ms.is_some());
+
+        // Still busy at the deadline: no reset without --force
+        let (result, report, resets_after) =
+            reset_draining_gpu(usize::MAX, Duration::from_millis(20)).await;
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("still has 1 active process(es)"), "{}", err);
+        assert!(resets_after.is_empty());
+        assert!(report.gpus.is_empty());
+    }
+
+    #[test]
+    fn test_killed_action_target_names_only_killed_pids() {
+        assert_eq!(
+            killed_action_target(&[100, 101], "Drain deadline passed"),
+            "pids 100, 101 (Drain deadline passed)"
+        );
+        assert_eq!(
+            killed_action_target(&[], "before reset of gpu 1"),
+            "no pids (before reset of gpu 1)"
+        );
+    }
+
+    #[test]
+    fn test_batch_kill_question_counts_processes_and_users() {
+        let proc = |pid, gpu_index, user: &str| crate::nvml_api::GpuProc {
+            gpu_index,
+            pid,
+            user: user.to_string(),
+            proc_name: "python".to_string(),
+            used_mem_mb: 1024,
+            start_time: "0s".to_string(),
+            ..Default::default()
+        };
+        // A process on two GPUs is counted once
+        let targets = vec![
+            proc(10, 0, "alice"),
+            proc(10, 1, "alice"),
+            proc(11, 0, "alice"),
+            proc(12, 1, "bob"),
+        ];
+        assert_eq!(
+            batch_kill_question(&targets),
+            "Kill 3 processes belonging to 2 users?"
+        );
+        assert_eq!(
+            batch_kill_question(&targets[..1]),
+            "Kill 1 process belonging to 1 user?"
+        );
+        assert_eq!(plural(1, "GPU"), "1 GPU");
+        assert_eq!(plural(4, "GPU"), "4 GPUs");
+
+        // --yes and JSON output never prompt
+        assert!(!should_confirm(true, &Renderer::new(OutputFormat::Table)));
+        assert!(!should_confirm(false, &Renderer::new(OutputFormat::Json)));
+    }
+
+    #[test]
+    fn test_yes_accepts_without_asking_but_does_not_replace_batch() {
+        let target = crate::nvml_api::GpuProc {
+            gpu_index: 0,
+            pid: 10,
+            user: "alice".to_string(),
+            proc_name: "python".to_string(),
+            used_mem_mb: 1024,
+            start_time: "0s".to_string(),
+            ..Default::default()
+        };
+        let table = Renderer::new(OutputFormat::Table);
+        assert!(confirm_destructive("Reset GPU 0?", true, &table).is_ok());
+        assert!(
+            confirm_destructive("Reset GPU 0?", false, &Renderer::new(OutputFormat::Json)).is_ok()
+        );
+
+        // Nobody is asked, so without --batch the targets are only listed
+        let targets = std::slice::from_ref(&target);
+        assert!(!confirm_without_batch(targets, false, false, true, &table).unwrap());
+        // --batch and dry-run need no confirmation in its place
+        assert!(!confirm_without_batch(targets, true, false, false, &table).unwrap());
+        assert!(!confirm_without_batch(targets, false, true, false, &table).unwrap());
+    }
+
+    /// One GPU whose utilization reads `spike_util` on the `spike_at`th snapshot
+    /// (counting from 1) and 10% otherwise
+    struct SpikingVendor {
+        snapshots: std::sync::atomic::AtomicUsize,
+        spike_at: usize,
+        spike_util: f32,
+    }
+
+    impl crate::vendor::GpuVendorInterface for SpikingVendor {
+        fn initialize() -> Result<Self> {
+            Ok(Self {
+                snapshots: Default::default(),
+                spike_at: 0,
+                spike_util: 0.0,
+            })
+        }
+
+        fn vendor_type(&self) -> crate::vendor::GpuVendor {
+            crate::vendor::GpuVendor::Unknown
+        }
+
+        fn device_count(&self) -> Result<u32> {
+            Ok(1)
+        }
+
+        fn get_gpu_info(&self, index: u32) -> Result<crate::nvml_api::GpuInfo> {
+            Ok(crate::nvml_api::GpuInfo {
+                index: index as u16,
+                name: "Spiking GPU".to_string(),
+                mem_total_mb: 1024,
+                uuid: None,
+                serial: None,
+                pci_bus_id: None,
+            })
+        }
+
+        fn get_gpu_snapshot(&self, index: u32) -> Result<crate::nvml_api::GpuSnapshot> {
+            let n = 1 + self
+                .snapshots
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(crate::nvml_api::GpuSnapshot {
+                gpu_index: index as u16,
+                name: "Spiking GPU".to_string(),
+                vendor: crate::vendor::GpuVendor::Unknown,
+                mem_used_mb: 128,
+                mem_total_mb: 1024,
+                util_pct: if n == self.spike_at {
+                    self.spike_util
+                } else {
+                    10.0
+                },
+                temp_c: 40,
+                power_w: 50.0,
+                pids: 0,
+                ..Default::default()
+            })
+        }
+
+        fn get_gpu_processes(&self, _index: u32) -> Result<Vec<crate::nvml_api::GpuProc>> {
+            Ok(Vec::new())
+        }
+
+        fn reset_gpu(&self, _index: u32) -> Result<()> {
+            Ok(())
+        }
+
+        fn capabilities(&self) -> crate::vendor::VendorCapabilities {
+            crate::vendor::VendorCapabilities {
+                reset_supported: false,
+                process_enum_supported: true,
+                power_limit_supported: false,
+                util_supported: true,
+            }
+        }
+
+        fn is_available() -> bool {
+            true
+        }
+
+        fn get_availability_error() -> String {
+            String::new()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watch_alert_breach_fails_the_watch() {
+        let watch = |spike_util: f32| {
+            let vendor = SpikingVendor {
+                snapshots: Default::default(),
+                spike_at: 2,
+                spike_util,
+            };
+            let gpu_manager = GpuManager::with_vendor(std::sync::Arc::new(vendor))
+                .with_snapshot_cache_ttl(Duration::ZERO);
+            let mut config_manager = crate::config::ConfigManager::new();
+            config_manager.config_mut().watch_interval_secs = 0;
+            execute_watch_mode(
+                false,
+                false,
+                false,
+                false,
+                Some(3),
+                crate::snapshot::AlertThresholds {
+                    util_pct: Some(95.0),
+                    ..Default::default()
+                },
+                None,
+                None,
+                ListAudit::Off,
+                false,
+                Renderer::new(OutputFormat::Json),
+                gpu_manager,
+                config_manager,
+            )
+        };
+
+        // Only the second of three refreshes breaches, and that still fails the watch
+        let err = watch(99.0).await.unwrap_err().to_string();
+        assert!(err.contains("exceeded 1 time(s)"), "{}", err);
+        assert!(
+            err.contains("GPU 0 (Spiking GPU) utilization 99.0% exceeded 95%"),
+            "{}",
+            err
+        );
+
+        watch(50.0).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_no_audit_never_opens_audit_manager() {
+        let opened = std::cell::Cell::new(false);
+        let open = || async {
+            opened.set(true);
+            Err(anyhow::anyhow!("audit store unavailable"))
+        };
+
+        log_list_audit(false, &[], &[], open).await;
+        assert!(!opened.get());
+
+        log_list_audit(true, &[], &[], open).await;
+        assert!(opened.get());
+    }
+
+    #[test]
+    fn test_list_timing_and_audit_mode() {
+        assert_eq!(ListAudit::from_flags(false, false), ListAudit::Background);
+        assert_eq!(ListAudit::from_flags(false, true), ListAudit::Sync);
+        assert_eq!(ListAudit::from_flags(true, false), ListAudit::Off);
+
+        let mut timer = PhaseTimer::new(true);
+        timer.mark("snapshots");
+        std::thread::sleep(Duration::from_millis(5));
+        timer.mark("render");
+        let report = timer.format();
+        let phases: Vec<&str> = report
+            .lines()
+            .map(|line| line.split_whitespace().nth(1).unwrap())
+            .collect();
+        assert_eq!(phases, vec!["snapshots", "render", "total"]);
+        assert!(timer.phases[1].1 >= Duration::from_millis(5));
+        assert!(report.lines().all(|line| line.ends_with(" ms")));
+    }
+
+    #[tokio::test]
+    async fn test_bind_server_listener_uses_requested_address() {
+        let addr = std::net::SocketAddr::new(std::net::Ipv4Addr::LOCALHOST.into(), 0);
+        let listener = bind_server_listener(addr).await.unwrap();
+        let bound = listener.local_addr().unwrap();
+        assert_eq!(bound.ip(), addr.ip());
+        assert_ne!(bound.port(), 0);
+
+        // The port is taken now, so binding it again must fail and name the address
+        let err = bind_server_listener(bound).await.unwrap_err();
+        assert!(err.to_string().contains(&bound.to_string()));
+    }
+
+    #[test]
+    fn test_remote_ssh_config_host_overrides() {
+        use clap::Parser;
+
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--list",
+            "--remote",
+            "gpu-01,gpu-02",
+            "--ssh-user",
+            "alice",
+            "--ssh-key",
+            "/keys/default",
+        ])
+        .unwrap();
+        let mut config = crate::config::Config::default();
+        config.remote_hosts.insert(
+            "gpu-02".to_string(),
+            crate::config::RemoteHostConfig {
+                user: Some("ops".to_string()),
+                port: Some(2222),
+                ..Default::default()
+            },
+        );
+
+        let default_host = remote_ssh_config(&cli, &config, "gpu-01").unwrap();
+        assert_eq!(default_host.username, "alice");
+        assert_eq!(default_host.port, 22);
+        assert_eq!(default_host.key_path.as_deref(), Some("/keys/default"));
+
+        let overridden = remote_ssh_config(&cli, &config, "gpu-02").unwrap();
+        assert_eq!(overridden.username, "ops");
+        assert_eq!(overridden.port, 2222);
+        assert_eq!(overridden.key_path.as_deref(), Some("/keys/default"));
+        assert_eq!(overridden.timeout, Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_remote_ssh_config_jump_hosts() {
+        use clap::Parser;
+
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--list",
+            "--remote",
+            "gpu-01,gpu-02",
+            "--ssh-jump",
+            "ops@bastion:2222",
+            "--ssh-insecure",
+        ])
+        .unwrap();
+        let mut config = crate::config::Config::default();
+        config.remote_hosts.insert(
+            "gpu-02".to_string(),
+            crate::config::RemoteHostConfig {
+                jump: Some("inner-bastion".to_string()),
+                ..Default::default()
+            },
+        );
+
+        let default_host = remote_ssh_config(&cli, &config, "gpu-01").unwrap();
+        assert_eq!(default_host.jump_hosts[0].to_string(), "ops@bastion:2222");
+        assert!(default_host.insecure);
+
+        let overridden = remote_ssh_config(&cli, &config, "gpu-02").unwrap();
+        assert_eq!(overridden.jump_hosts[0].to_string(), "inner-bastion");
+
+        // Malformed jump specs are rejected while parsing arguments
+        assert!(Cli::try_parse_from([
+            "gpukill",
+            "--list",
+            "--remote",
+            "gpu-01",
+            "--ssh-jump",
+            "bastion:notaport",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_remote_snapshot_skips_log_lines() {
+        let output = "2024-06-01T00:00:00Z  WARN something noisy\n\
+            {\"host\":\"gpu-01\",\"ts\":\"2024-06-01T00:00:00Z\",\"gpus\":[],\"procs\":[]}\n";
+        let snapshot = parse_remote_snapshot(output).unwrap();
+        assert_eq!(snapshot.host, "gpu-01");
+
+        assert!(parse_remote_snapshot("connection closed").is_err());
+    }
+
+    #[test]
+    fn test_build_remote_args_forces_output() {
+        use clap::Parser;
+
+        let cli =
+            Cli::try_parse_from(["gpukill", "--list", "--details", "--remote", "a,b"]).unwrap();
+        let args = build_remote_args(&cli, &OutputFormat::Json).unwrap();
+        assert_eq!(args, vec!["--list", "--details", "--output", "json"]);
+
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--kill",
+            "--filter",
+            "python",
+            "--filter-user",
+            "bob",
+            "--batch",
+            "--remote",
+            "a",
+        ])
+        .unwrap();
+        let args = build_remote_args(&cli, &OutputFormat::Json).unwrap();
+        assert_eq!(
+            &args[..6],
+            &[
+                "--kill",
+                "--filter",
+                "python",
+                "--filter-user",
+                "bob",
+                "--batch"
+            ]
+        );
+
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--kill",
+            "--job",
+            "4242",
+            "--use-scancel",
+            "--batch",
+            "--remote",
+            "a",
+        ])
+        .unwrap();
+        let args = build_remote_args(&cli, &OutputFormat::Json).unwrap();
+        assert_eq!(
+            &args[..5],
+            &["--kill", "--job", "4242", "--use-scancel", "--batch"]
+        );
+    }
+
+    #[test]
+    fn test_build_remote_args_forwards_gpu_uuid() {
+        use clap::Parser;
+
+        let cli = Cli::try_parse_from([
+            "gpukill",
+            "--reset",
+            "--gpu-uuid",
+            "GPU-5fd4",
+            "--remote",
+            "a",
+        ])
+        .unwrap();
+        let args = build_remote_args(&cli, &OutputFormat::Json).unwrap();
+        assert_eq!(
+            args,
+            vec!["--reset", "--gpu-uuid", "GPU-5fd4", "--output", "json"]
+        );
+    }
+
     #[test]
     fn test_logging_initialization() {
         // This test just ensures the function doesn't panic
-        let result = init_logging("info");
+        let result = init_logging("info", LogFormat::Human, false, false);
         assert!(result.is_ok());
     }
 
@@ -1793,4 +5970,249 @@ mod tests {
         let version = get_version_string();
         assert!(version.contains("gpukill"));
     }
+
+    #[test]
+    fn test_guard_config_json_output_round_trips() {
+        use crate::guard_mode::{GpuPolicy, GuardModeConfig, GuardModeManager};
+
+        let mut config = GuardModeConfig::default();
+        config.global.enabled = true;
+        config.gpu_policies.insert(
+            "0".to_string(),
+            GpuPolicy {
+                gpu_index: 0,
+                max_memory_gb: 1.0,
+                max_utilization_pct: 10.0,
+                reserved_memory_gb: 0.0,
+                allowed_users: Vec::new(),
+                blocked_users: vec!["testuser".to_string()],
+                maintenance_window: None,
+            },
+        );
+        let mut guard_manager = GuardModeManager::with_config(std::path::PathBuf::new(), config);
+
+        let mut out = Vec::new();
+        write_guard_config_json(&mut out, &guard_manager).unwrap();
+        let parsed: GuardModeConfig = serde_json::from_slice(&out).unwrap();
+        assert!(parsed.global.enabled);
+        assert_eq!(parsed.gpu_policies["0"].blocked_users, vec!["testuser"]);
+
+        let processes = vec![crate::nvml_api::GpuProc {
+            gpu_index: 0,
+            pid: 1234,
+            user: "testuser".to_string(),
+            proc_name: "test_proc".to_string(),
+            used_mem_mb: 512,
+            start_time: "unknown".to_string(),
+            ..Default::default()
+        }];
+        let result = guard_manager
+            .simulate_policy_check(&processes, &[])
+            .unwrap();
+
+        let mut out = Vec::new();
+        write_enforcement_result_json(&mut out, &result).unwrap();
+        let parsed: crate::guard_mode::EnforcementResult = serde_json::from_slice(&out).unwrap();
+        assert!(parsed.dry_run);
+        assert_eq!(parsed.violations.len(), result.violations.len());
+        assert!(!parsed.violations.is_empty());
+    }
+
+    /// Collects everything the JSON logger writes
+    #[derive(Clone, Default)]
+    struct CapturedLogs(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for CapturedLogs {
+        type Writer = CapturedLogs;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    fn reset_test_proc(gpu_index: u16, pid: u32) -> crate::nvml_api::GpuProc {
+        crate::nvml_api::GpuProc {
+            gpu_index,
+            pid,
+            user: "alice".to_string(),
+            proc_name: "python".to_string(),
+            used_mem_mb: 1024,
+            start_time: "0s".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_reset_dry_run_lists_processes_force_would_kill() {
+        let processes = vec![
+            reset_test_proc(0, 1111),
+            reset_test_proc(1, 2222),
+            reset_test_proc(1, 3333),
+        ];
+
+        let mut report = ResetReport::new(true, false);
+        let lines = preview_reset(&[(1, "nvml")], &processes, true, &mut report);
+        assert_eq!(
+            lines[0],
+            "Dry-run: would reset GPU 1 and terminate 2 process(es):"
+        );
+        assert!(lines.iter().any(|l| l.contains("PID 2222")));
+        assert!(lines.iter().any(|l| l.contains("PID 3333")));
+        assert!(!lines.iter().any(|l| l.contains("PID 1111")));
+        assert_eq!(report.gpus[0].status, ResetStatus::WouldReset);
+        let pids: Vec<u32> = report.gpus[0].processes.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![2222, 3333]);
+        assert!(report.gpus[0]
+            .processes
+            .iter()
+            .all(|p| p.status == KillStatus::WouldKill));
+
+        let mut report = ResetReport::new(true, false);
+        let targets = [(0, "nvml"), (1, "nvml"), (2, "nvml")];
+        let lines = preview_reset(&targets, &processes, false, &mut report);
+        assert!(lines[0].starts_with("Dry-run: would refuse to reset all 3 GPUs"));
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].contains("GPU 0: PID 1111"));
+        assert!(report
+            .gpus
+            .iter()
+            .all(|g| g.status == ResetStatus::WouldRefuse));
+        assert_eq!(report.gpus[0].processes.len(), 1);
+        assert_eq!(report.gpus[1].processes.len(), 2);
+        assert!(report.gpus[2].processes.is_empty());
+
+        let mut report = ResetReport::new(true, false);
+        let lines = preview_reset(&[(2, "nvml")], &processes, false, &mut report);
+        assert_eq!(
+            lines,
+            vec!["Dry-run: would reset GPU 2 (no active processes)"]
+        );
+        assert_eq!(report.gpus[0].status, ResetStatus::WouldReset);
+    }
+
+    #[test]
+    fn test_set_dry_run_describes_changes() {
+        let limits = crate::nvml_api::PowerLimits {
+            current_w: 300.0,
+            default_w: 350.0,
+        };
+        assert_eq!(
+            describe_set_changes(2, Some(250), Some(1410), false, Some(limits)),
+            vec![
+                "set GPU 2 power limit to 250 W (currently 300 W, default 350 W)",
+                "lock GPU 2 clocks at 1410 MHz",
+            ]
+        );
+        assert_eq!(
+            describe_set_changes(0, Some(250), None, true, None),
+            vec![
+                "set GPU 0 power limit to 250 W",
+                "reset GPU 0 clocks to driver control",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filtered_kill_dry_run_previews_only_the_intersection() {
+        let proc = |pid: u32, name: &str, user: &str| crate::nvml_api::GpuProc {
+            gpu_index: 0,
+            pid,
+            user: user.to_string(),
+            proc_name: name.to_string(),
+            used_mem_mb: 1024,
+            start_time: "0s".to_string(),
+            ..Default::default()
+        };
+        let processes = vec![
+            proc(101, "python", "bob"),
+            proc(102, "python", "alice"),
+            proc(103, "bash", "bob"),
+            proc(104, "python", "bob"),
+        ];
+        let targets = crate::process_mgmt::filter_kill_targets(
+            &processes,
+            Some("python"),
+            false,
+            Some("bob"),
+        )
+        .unwrap();
+
+        let protection = KillProtection::new(
+            crate::process_mgmt::ProcessTable::from_nodes(Vec::new(), None),
+            &[],
+        );
+        let renderer = Renderer::new(OutputFormat::Json);
+        let (targets, skipped) =
+            screen_protected(targets, &protection, false, true, &renderer).unwrap();
+        let mut report = KillReport::new(true, KillSignal::Term, 5, false);
+        render_kill_preview(&targets, &skipped, &mut report, &renderer);
+
+        let previewed: Vec<(u32, KillStatus)> =
+            report.processes.iter().map(|p| (p.pid, p.status)).collect();
+        assert_eq!(
+            previewed,
+            vec![(101, KillStatus::WouldKill), (104, KillStatus::WouldKill)]
+        );
+        assert_eq!(report.count(KillStatus::Killed), 0);
+        assert_eq!(
+            kill_filter_description(Some("python"), Some("bob")),
+            "pattern 'python' owned by bob"
+        );
+        assert_eq!(kill_filter_description(None, Some("bob")), "user bob");
+    }
+
+    #[test]
+    fn test_json_logging_kill_dry_run_fields() {
+        let logs = CapturedLogs::default();
+        let subscriber = json_subscriber(tracing_subscriber::EnvFilter::new("info"), logs.clone());
+        let proc = crate::nvml_api::GpuProc {
+            gpu_index: 1,
+            pid: 4242,
+            user: "alice".to_string(),
+            proc_name: "python".to_string(),
+            used_mem_mb: 2048,
+            start_time: "0s".to_string(),
+            ..Default::default()
+        };
+
+        let mut report = KillReport::new(true, KillSignal::Term, 5, false);
+        let renderer = Renderer::new(OutputFormat::Json);
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("operation", operation = "kill");
+            let _entered = span.enter();
+            render_kill_preview(std::slice::from_ref(&proc), &[], &mut report, &renderer);
+        });
+        assert_eq!(report.processes.len(), 1);
+        assert_eq!(report.processes[0].status, KillStatus::WouldKill);
+        assert_eq!(report.processes[0].memory_mb, Some(2048));
+
+        let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        let events: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let event = events
+            .iter()
+            .find(|e| e["message"] == "Would terminate process")
+            .expect("kill dry-run event");
+
+        assert_eq!(event["level"], "INFO");
+        assert_eq!(event["span"]["operation"], "kill");
+        assert_eq!(event["pid"], 4242);
+        assert_eq!(event["user"], "alice");
+        assert_eq!(event["gpu_index"], 1);
+        assert_eq!(event["dry_run"], true);
+        assert!(event["timestamp"].is_string());
+    }
 }
@@ -1,5 +1,8 @@
 use crate::util::{get_current_timestamp_iso, get_hostname};
 use anyhow::{Context, Result};
+use nvml_wrapper::enum_wrappers::device::{
+    EccCounter, MemoryError, PcieUtilCounter, RetirementCause,
+};
 use nvml_wrapper::enums::device::UsedGpuMemory;
 use nvml_wrapper::error::NvmlError;
 use nvml_wrapper::struct_wrappers::device::ProcessInfo;
@@ -15,39 +18,328 @@ pub struct GpuInfo {
     pub index: u16,
     pub name: String,
     pub mem_total_mb: u32,
+    /// Stable device identifier (NVML UUID, ROCm unique ID); None if unavailable
+    #[serde(default)]
+    pub uuid: Option<String>,
+    /// Board serial number; None if unavailable
+    #[serde(default)]
+    pub serial: Option<String>,
+    /// PCI bus id such as `0000:3b:00.0`; None if unavailable
+    #[serde(default)]
+    pub pci_bus_id: Option<String>,
 }
 
 /// GPU process information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct GpuProc {
     pub gpu_index: u16,
+    /// Host PID, as reported by the driver
     pub pid: u32,
     pub user: String,
     pub proc_name: String,
     pub used_mem_mb: u32,
+    /// How long the process has been running, e.g. "2h 13m 4s"
     pub start_time: String,
+    /// When the process started, in seconds since the Unix epoch; None if unknown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_unix_secs: Option<u64>,
     pub container: Option<String>,
+    /// PID inside the process's own PID namespace (e.g. a container), when it differs from `pid`
+    #[serde(default)]
+    pub container_pid: Option<u32>,
     /// When set, process is on this cluster node (from cluster rogue analysis).
     #[serde(default)]
     pub node_id: Option<String>,
+    /// Full command line with arguments; None when it could not be read
+    #[serde(default)]
+    pub cmdline: Option<String>,
+    /// Slurm job that owns the process, on hosts that run Slurm
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slurm_job_id: Option<String>,
+}
+
+impl GpuProc {
+    /// Full command line, or the process name when it could not be read
+    pub fn command(&self) -> &str {
+        self.cmdline.as_deref().unwrap_or(&self.proc_name)
+    }
 }
 
 /// GPU snapshot with current status
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct GpuSnapshot {
     pub gpu_index: u16,
     pub name: String,
+    /// Stable device identifier (NVML UUID, ROCm unique ID), for `--gpu-uuid`
+    #[serde(default)]
+    pub uuid: Option<String>,
+    /// Board serial number, also accepted by `--gpu-uuid`
+    #[serde(default)]
+    pub serial: Option<String>,
     pub vendor: crate::vendor::GpuVendor,
     pub mem_used_mb: u32,
     pub mem_total_mb: u32,
     pub util_pct: f32,
     pub temp_c: i32,
     pub power_w: f32,
-    pub ecc_volatile: Option<u64>,
+    /// ECC error counts since the driver was last loaded (None if ECC is unsupported)
+    pub ecc_volatile: Option<EccCounts>,
+    /// ECC error counts over the lifetime of the device (None if ECC is unsupported)
+    #[serde(default)]
+    pub ecc_aggregate: Option<EccCounts>,
+    /// Memory pages retired after ECC errors (None if page retirement is unsupported)
+    #[serde(default)]
+    pub retired_pages: Option<RetiredPages>,
+    /// Whether the memory errors warrant replacing the card (None if unknown)
+    #[serde(default)]
+    pub rma_flagged: Option<bool>,
+    /// PCIe transmit throughput in KB/s (None if the counter is unsupported)
+    #[serde(default)]
+    pub pcie_tx_kbps: Option<u32>,
+    /// PCIe receive throughput in KB/s (None if the counter is unsupported)
+    #[serde(default)]
+    pub pcie_rx_kbps: Option<u32>,
+    /// Cumulative NVLink traffic (sent plus received) over all active links, from
+    /// utilization counter 0 (None if the GPU has no active NVLink)
+    #[serde(default)]
+    pub nvlink_bytes: Option<u64>,
+    /// DCGM profiling metrics (None without DCGM or for other vendors)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profiling: Option<crate::dcgm::ProfilingMetrics>,
+    /// Enforced and default board power limit (None if the vendor does not report it)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub power_limit: Option<PowerLimits>,
     pub pids: usize,
     pub top_proc: Option<GpuProc>,
 }
 
+/// Corrected and uncorrected ECC memory error counts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EccCounts {
+    pub corrected: u64,
+    pub uncorrected: u64,
+}
+
+impl EccCounts {
+    /// Total number of ECC errors, corrected and uncorrected
+    pub fn total(&self) -> u64 {
+        self.corrected.saturating_add(self.uncorrected)
+    }
+}
+
+/// Board power limit in watts, as set by `gpukill --set --power-limit` or the driver
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PowerLimits {
+    pub current_w: f32,
+    pub default_w: f32,
+}
+
+impl PowerLimits {
+    /// Read both limits, given in milliwatts; `None` when either query fails
+    pub fn read<C, D>(current_mw: C, default_mw: D) -> Option<Self>
+    where
+        C: FnOnce() -> std::result::Result<u32, NvmlError>,
+        D: FnOnce() -> std::result::Result<u32, NvmlError>,
+    {
+        Some(Self {
+            current_w: current_mw().ok()? as f32 / 1000.0,
+            default_w: default_mw().ok()? as f32 / 1000.0,
+        })
+    }
+
+    /// Whether the enforced limit differs from the default by at least a watt
+    pub fn drifted(&self) -> bool {
+        (self.current_w - self.default_w).abs() >= 1.0
+    }
+}
+
+/// Retired pages at which NVIDIA considers a card due for RMA
+pub const RMA_RETIRED_PAGES_THRESHOLD: u32 = 60;
+
+/// Memory pages retired by the driver after ECC errors
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetiredPages {
+    /// Pages retired after repeated single-bit (corrected) errors
+    pub single_bit: u32,
+    /// Pages retired after a double-bit (uncorrected) error
+    pub double_bit: u32,
+    /// Whether retirements are waiting for the next driver reload
+    pub pending: bool,
+}
+
+impl RetiredPages {
+    /// Total number of retired pages
+    pub fn total(&self) -> u32 {
+        self.single_bit.saturating_add(self.double_bit)
+    }
+
+    /// Whether the card has retired enough pages to be flagged for RMA
+    pub fn rma_flagged(&self) -> bool {
+        self.total() >= RMA_RETIRED_PAGES_THRESHOLD
+    }
+}
+
+/// Read retired page counts through NVML retired-page and pending-status queries.
+///
+/// Devices without page retirement report an error for the count queries and get
+/// `None`; a failing pending-status query alone is treated as nothing pending.
+pub fn read_retired_pages<F, P>(count: F, pending: P) -> Option<RetiredPages>
+where
+    F: Fn(RetirementCause) -> std::result::Result<usize, NvmlError>,
+    P: FnOnce() -> std::result::Result<bool, NvmlError>,
+{
+    let single_bit = count(RetirementCause::MultipleSingleBitEccErrors).ok()?;
+    let double_bit = count(RetirementCause::DoubleBitEccError).ok()?;
+    Some(RetiredPages {
+        single_bit: single_bit as u32,
+        double_bit: double_bit as u32,
+        pending: pending().unwrap_or(false),
+    })
+}
+
+/// Read volatile and aggregate ECC counts through an NVML error-count query.
+///
+/// Devices without ECC support (or with ECC disabled) report an error for
+/// these queries; in that case the corresponding counts are left as `None`
+/// rather than failing the whole snapshot.
+pub fn read_ecc_counts<F>(query: F) -> (Option<EccCounts>, Option<EccCounts>)
+where
+    F: Fn(MemoryError, EccCounter) -> std::result::Result<u64, NvmlError>,
+{
+    let read = |counter: EccCounter| -> Option<EccCounts> {
+        let corrected = query(MemoryError::Corrected, counter).ok()?;
+        let uncorrected = query(MemoryError::Uncorrected, counter).ok()?;
+        Some(EccCounts {
+            corrected,
+            uncorrected,
+        })
+    };
+
+    (read(EccCounter::Volatile), read(EccCounter::Aggregate))
+}
+
+/// Read PCIe transmit and receive throughput (KB/s) through an NVML counter query.
+///
+/// Older devices, vGPU guests and some drivers do not support the counter; each
+/// direction is `None` when its query fails.
+pub fn read_pcie_throughput<F>(query: F) -> (Option<u32>, Option<u32>)
+where
+    F: Fn(PcieUtilCounter) -> std::result::Result<u32, NvmlError>,
+{
+    (
+        query(PcieUtilCounter::Send).ok(),
+        query(PcieUtilCounter::Receive).ok(),
+    )
+}
+
+/// Links NVML can report per device (NVML_NVLINK_MAX_LINKS)
+const NVLINK_MAX_LINKS: u32 = 18;
+
+/// Sum cumulative NVLink traffic over every link a per-link query answers for.
+///
+/// Inactive links and devices without NVLink report an error and are skipped;
+/// the result is `None` when no link could be read.
+pub fn read_nvlink_traffic<F>(query: F) -> Option<u64>
+where
+    F: Fn(u32) -> std::result::Result<u64, NvmlError>,
+{
+    (0..NVLINK_MAX_LINKS)
+        .filter_map(|link| query(link).ok())
+        .reduce(|total, bytes| total.saturating_add(bytes))
+}
+
+/// Read a device's cumulative NVLink traffic from utilization counter 0. The counter
+/// counts bytes when configured to (`nvidia-smi nvlink --setcontrol`).
+pub fn read_device_nvlink_traffic(device: &nvml_wrapper::Device) -> Option<u64> {
+    read_nvlink_traffic(|link| {
+        let nvlink = device.link_wrapper_for(link);
+        if !nvlink.is_active()? {
+            return Err(NvmlError::NotSupported);
+        }
+        let counter = nvlink.utilization_counter(nvml_wrapper::enums::nv_link::Counter::Zero)?;
+        Ok(counter.send.saturating_add(counter.receive))
+    })
+}
+
+/// Read a device's UUID and board serial; either is None when the device does not report it
+pub fn read_device_ids(device: &nvml_wrapper::Device) -> (Option<String>, Option<String>) {
+    (device.uuid().ok(), device.serial().ok())
+}
+
+/// Whether a GPU answered when the snapshot was taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GpuStatus {
+    Ok,
+    /// The device did not answer within the snapshot timeout
+    TimedOut,
+    /// The device could not be queried, e.g. it fell off the bus or the driver restarted
+    Unavailable,
+    /// The vendor's tools were skipped after failing repeatedly
+    Degraded,
+}
+
+/// Query outcome for one GPU of a snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GpuStatusEntry {
+    pub gpu_index: u16,
+    pub status: GpuStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl GpuStatusEntry {
+    pub fn ok(gpu_index: u16) -> Self {
+        Self {
+            gpu_index,
+            status: GpuStatus::Ok,
+            error: None,
+        }
+    }
+}
+
+/// A GPU that could not be queried, listed with its vendor instead of being left out
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceError {
+    pub gpu_index: u16,
+    pub vendor: crate::vendor::GpuVendor,
+    pub status: GpuStatus,
+    /// Short reason naming the vendor's query source, e.g. "NVML: GPU is lost"
+    pub error: String,
+}
+
+impl DeviceError {
+    pub fn failed(gpu_index: u16, vendor: crate::vendor::GpuVendor, error: &anyhow::Error) -> Self {
+        Self {
+            gpu_index,
+            vendor,
+            status: match crate::command::tool_error(error) {
+                Some(crate::command::ToolError::TimedOut { .. }) => GpuStatus::TimedOut,
+                Some(crate::command::ToolError::Skipped { .. }) => GpuStatus::Degraded,
+                None => GpuStatus::Unavailable,
+            },
+            error: format!("{}: {}", vendor.query_source(), error.root_cause()),
+        }
+    }
+
+    pub fn timed_out(gpu_index: u16, vendor: crate::vendor::GpuVendor, timeout: Duration) -> Self {
+        Self {
+            gpu_index,
+            vendor,
+            status: GpuStatus::TimedOut,
+            error: format!("{} timed out after {:?}", vendor.query_source(), timeout),
+        }
+    }
+
+    pub fn status_entry(&self) -> GpuStatusEntry {
+        GpuStatusEntry {
+            gpu_index: self.gpu_index,
+            status: self.status,
+            error: Some(self.error.clone()),
+        }
+    }
+}
+
 /// Complete system snapshot
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Snapshot {
@@ -55,6 +347,25 @@ pub struct Snapshot {
     pub ts: String,
     pub gpus: Vec<GpuSnapshot>,
     pub procs: Vec<GpuProc>,
+    /// Some GPUs could not be queried, so `gpus` and `procs` may be partial
+    #[serde(default)]
+    pub degraded: bool,
+    /// Query outcome of every GPU the host reported
+    #[serde(default)]
+    pub gpu_status: Vec<GpuStatusEntry>,
+    /// GPUs that could not be queried
+    #[serde(default)]
+    pub errors: Vec<DeviceError>,
+}
+
+impl Snapshot {
+    /// Record the per-GPU query outcomes; the snapshot is degraded when any GPU
+    /// did not answer
+    pub fn with_gpu_status(mut self, gpu_status: Vec<GpuStatusEntry>) -> Self {
+        self.degraded = gpu_status.iter().any(|g| g.status != GpuStatus::Ok);
+        self.gpu_status = gpu_status;
+        self
+    }
 }
 
 /// NVML API wrapper for GPU operations
@@ -73,6 +384,11 @@ impl NvmlApi {
         Ok(Self { nvml })
     }
 
+    /// The underlying NVML handle, for queries this wrapper does not cover
+    pub fn nvml(&self) -> &Nvml {
+        &self.nvml
+    }
+
     /// Get the number of available GPUs
     pub fn device_count(&self) -> Result<u32> {
         self.nvml
@@ -98,11 +414,15 @@ impl NvmlApi {
             .memory_info()
             .map_err(map_nvml_error)
             .context("Failed to get memory info")?;
+        let (uuid, serial) = read_device_ids(&device);
 
         Ok(GpuInfo {
             index: index as u16,
             name,
             mem_total_mb: (mem_info.total / 1024 / 1024) as u32,
+            uuid,
+            serial,
+            pci_bus_id: device.pci_info().ok().map(|pci| pci.bus_id),
         })
     }
 
@@ -139,7 +459,17 @@ impl NvmlApi {
             .map_err(map_nvml_error)
             .context("Failed to get power usage")?;
 
-        let ecc_volatile = None; // ECC errors not available in this version
+        let (ecc_volatile, ecc_aggregate) = read_ecc_counts(|error_type, counter_type| {
+            device.total_ecc_errors(error_type, counter_type)
+        });
+        let retired_pages = read_retired_pages(
+            |cause| device.retired_pages(cause).map(|pages| pages.len()),
+            || device.are_pages_pending_retired(),
+        );
+        let (pcie_tx_kbps, pcie_rx_kbps) =
+            read_pcie_throughput(|counter| device.pcie_throughput(counter));
+        let nvlink_bytes = read_device_nvlink_traffic(&device);
+        let (uuid, serial) = read_device_ids(&device);
 
         let compute_processes = device
             .running_compute_processes()
@@ -169,6 +499,10 @@ impl NvmlApi {
                     start_time: "unknown".to_string(), // Will be filled by process info
                     container: None,
                     node_id: None,
+                    cmdline: None,
+                    container_pid: None,
+                    slurm_job_id: None,
+                    start_unix_secs: None,
                 };
                 enrich_gpu_proc(&mut proc);
                 proc
@@ -177,6 +511,8 @@ impl NvmlApi {
         Ok(GpuSnapshot {
             gpu_index: index as u16,
             name,
+            uuid,
+            serial,
             vendor: crate::vendor::GpuVendor::Nvidia,
             mem_used_mb: (mem_info.used / 1024 / 1024) as u32,
             mem_total_mb: (mem_info.total / 1024 / 1024) as u32,
@@ -184,22 +520,45 @@ impl NvmlApi {
             temp_c: temperature as i32,
             power_w: power_usage as f32 / 1000.0, // Convert mW to W
             ecc_volatile,
+            ecc_aggregate,
+            retired_pages,
+            rma_flagged: retired_pages.map(|pages| pages.rma_flagged()),
+            pcie_tx_kbps,
+            pcie_rx_kbps,
+            nvlink_bytes,
+            power_limit: None,
             pids: pids.len(),
             top_proc,
+            profiling: None,
         })
     }
 
     /// Get all GPU snapshots
     pub fn get_all_snapshots(&self) -> Result<Vec<GpuSnapshot>> {
+        Ok(self.collect_snapshots()?.0)
+    }
+
+    /// Snapshots of every device that answers, plus the devices that did not
+    fn collect_snapshots(&self) -> Result<(Vec<GpuSnapshot>, Vec<DeviceError>)> {
         let count = self.device_count()?;
         let mut snapshots = Vec::new();
+        let mut errors = Vec::new();
 
         for i in 0..count {
-            let snapshot = self.get_gpu_snapshot(i)?;
-            snapshots.push(snapshot);
+            match self.get_gpu_snapshot(i) {
+                Ok(snapshot) => snapshots.push(snapshot),
+                Err(e) => {
+                    tracing::warn!("Skipping unavailable GPU {}: {:#}", i, e);
+                    errors.push(DeviceError::failed(
+                        i as u16,
+                        crate::vendor::GpuVendor::Nvidia,
+                        &e,
+                    ));
+                }
+            }
         }
 
-        Ok(snapshots)
+        Ok((snapshots, errors))
     }
 
     /// Get processes using GPUs
@@ -208,25 +567,14 @@ impl NvmlApi {
         let mut all_processes = Vec::new();
 
         for i in 0..count {
-            let device = self
-                .nvml
-                .device_by_index(i)
-                .map_err(map_nvml_error)
-                .with_context(|| format!("Failed to get device at index {}", i))?;
-
-            let compute_processes = device
-                .running_compute_processes()
-                .map_err(map_nvml_error)
-                .with_context(|| format!("Failed to get compute processes for GPU {}", i))?;
-            let graphics_processes = match device.running_graphics_processes() {
+            // A GPU that fell off the bus is skipped so the others are still listed
+            let processes = match self.device_processes(i) {
                 Ok(processes) => processes,
-                Err(NvmlError::NotSupported) => Vec::new(),
-                Err(error) => {
-                    return Err(map_nvml_error(error))
-                        .with_context(|| format!("Failed to get graphics processes for GPU {}", i))
+                Err(e) => {
+                    tracing::warn!("Skipping unavailable GPU {}: {:#}", i, e);
+                    continue;
                 }
             };
-            let processes = merge_nvml_processes(compute_processes, graphics_processes);
 
             for process in processes {
                 all_processes.push(GpuProc {
@@ -241,6 +589,10 @@ impl NvmlApi {
                     start_time: "unknown".to_string(), // Will be filled by process info
                     container: None,
                     node_id: None,
+                    cmdline: None, // Will be filled by process info
+                    container_pid: None,
+                    slurm_job_id: None,
+                    start_unix_secs: None,
                 });
             }
         }
@@ -248,38 +600,55 @@ impl NvmlApi {
         Ok(all_processes)
     }
 
-    /// Check if a process is using any GPU
+    /// Compute and graphics processes on one device
+    fn device_processes(&self, index: u32) -> Result<Vec<ProcessInfo>> {
+        let device = self
+            .nvml
+            .device_by_index(index)
+            .map_err(map_nvml_error)
+            .with_context(|| format!("Failed to get device at index {}", index))?;
+
+        let compute_processes = device
+            .running_compute_processes()
+            .map_err(map_nvml_error)
+            .with_context(|| format!("Failed to get compute processes for GPU {}", index))?;
+        let graphics_processes = match device.running_graphics_processes() {
+            Ok(processes) => processes,
+            Err(NvmlError::NotSupported) => Vec::new(),
+            Err(error) => {
+                return Err(map_nvml_error(error))
+                    .with_context(|| format!("Failed to get graphics processes for GPU {}", index))
+            }
+        };
+        Ok(merge_nvml_processes(compute_processes, graphics_processes))
+    }
+
+    /// Check if a process is using any GPU. Devices that cannot be queried are
+    /// skipped; if none can be, the last error is returned.
     pub fn is_process_using_gpu(&self, pid: u32) -> Result<bool> {
         let count = self.device_count()?;
+        let mut last_error = None;
+        let mut answered = 0;
 
         for i in 0..count {
-            let device = self
-                .nvml
-                .device_by_index(i)
-                .map_err(map_nvml_error)
-                .with_context(|| format!("Failed to get device at index {}", i))?;
-
-            let compute_processes = device
-                .running_compute_processes()
-                .map_err(map_nvml_error)
-                .with_context(|| format!("Failed to get compute processes for GPU {}", i))?;
-            let graphics_processes = match device.running_graphics_processes() {
-                Ok(processes) => processes,
-                Err(NvmlError::NotSupported) => Vec::new(),
-                Err(error) => {
-                    return Err(map_nvml_error(error))
-                        .with_context(|| format!("Failed to get graphics processes for GPU {}", i))
+            match self.device_processes(i) {
+                Ok(processes) => {
+                    if processes.iter().any(|p| p.pid == pid) {
+                        return Ok(true);
+                    }
+                    answered += 1;
+                }
+                Err(e) => {
+                    tracing::warn!("Skipping unavailable GPU {}: {:#}", i, e);
+                    last_error = Some(e);
                 }
-            };
-
-            if compute_processes.iter().any(|p| p.pid == pid)
-                || graphics_processes.iter().any(|p| p.pid == pid)
-            {
-                return Ok(true);
             }
         }
 
-        Ok(false)
+        match last_error {
+            Some(e) if answered == 0 => Err(e),
+            _ => Ok(false),
+        }
     }
 
     /// Reset a specific GPU
@@ -300,15 +669,25 @@ impl NvmlApi {
 
     /// Create a complete system snapshot
     pub fn create_snapshot(&self) -> Result<Snapshot> {
-        let gpus = self.get_all_snapshots()?;
+        let (gpus, errors) = self.collect_snapshots()?;
         let procs = self.get_gpu_processes()?;
 
+        let mut gpu_status: Vec<GpuStatusEntry> = gpus
+            .iter()
+            .map(|gpu| GpuStatusEntry::ok(gpu.gpu_index))
+            .chain(errors.iter().map(DeviceError::status_entry))
+            .collect();
+        gpu_status.sort_by_key(|entry| entry.gpu_index);
         Ok(Snapshot {
             host: get_hostname(),
             ts: get_current_timestamp_iso(),
             gpus,
             procs,
-        })
+            degraded: false,
+            gpu_status: Vec::new(),
+            errors,
+        }
+        .with_gpu_status(gpu_status))
     }
 }
 
@@ -344,11 +723,14 @@ fn enrich_gpu_proc(proc: &mut GpuProc) {
     system.refresh_processes();
     let users = Users::new_with_refreshed_list();
 
+    proc.cmdline = crate::proc::get_process_cmdline(proc.pid);
+    proc.container_pid = crate::proc::get_container_pid(proc.pid);
     let sys_pid = SysPid::from_u32(proc.pid);
     if let Some(process) = system.process(sys_pid) {
         proc.proc_name = process.name().to_string();
         let start_time = SystemTime::UNIX_EPOCH + Duration::from_secs(process.start_time());
         proc.start_time = crate::util::parse_process_start_time(start_time);
+        proc.start_unix_secs = Some(process.start_time());
         if let Some(user_id) = process.user_id() {
             if let Some(user) = users.get_user_by_id(user_id) {
                 proc.user = user.name().to_string();
@@ -427,6 +809,41 @@ fn map_nvml_error(error: NvmlError) -> anyhow::Error {
     }
 }
 
+/// Process and device fixtures shared by the unit tests of other modules
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::{GpuProc, GpuSnapshot};
+    use crate::vendor::GpuVendor;
+
+    /// Process started an hour ago, with no container, cmdline or Slurm job
+    pub fn proc(gpu_index: u16, pid: u32, user: &str, name: &str, used_mem_mb: u32) -> GpuProc {
+        GpuProc {
+            gpu_index,
+            pid,
+            user: user.to_string(),
+            proc_name: name.to_string(),
+            used_mem_mb,
+            start_time: "1h".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// 24 GiB NVIDIA GPU at 40°C and 100 W, without ECC, PCIe or NVLink counters
+    pub fn gpu(gpu_index: u16, util_pct: f32, mem_used_mb: u32) -> GpuSnapshot {
+        GpuSnapshot {
+            gpu_index,
+            name: "Test GPU".to_string(),
+            vendor: GpuVendor::Nvidia,
+            mem_used_mb,
+            mem_total_mb: 24576,
+            util_pct,
+            temp_c: 40,
+            power_w: 100.0,
+            ..Default::default()
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -437,6 +854,9 @@ mod tests {
             index: 0,
             name: "Test GPU".to_string(),
             mem_total_mb: 8192,
+            uuid: None,
+            serial: None,
+            pci_bus_id: None,
         };
 
         let json = serde_json::to_string(&gpu_info).unwrap();
@@ -457,14 +877,173 @@ mod tests {
             util_pct: 50.0,
             temp_c: 75,
             power_w: 150.0,
-            ecc_volatile: Some(0),
+            ecc_volatile: Some(EccCounts::default()),
+            ecc_aggregate: Some(EccCounts {
+                corrected: 3,
+                uncorrected: 1,
+            }),
+            pcie_tx_kbps: Some(1200),
             pids: 2,
-            top_proc: None,
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&snapshot).unwrap();
         let deserialized: GpuSnapshot = serde_json::from_str(&json).unwrap();
         assert_eq!(snapshot.gpu_index, deserialized.gpu_index);
         assert_eq!(snapshot.util_pct, deserialized.util_pct);
+        assert_eq!(snapshot.ecc_aggregate, deserialized.ecc_aggregate);
+        assert_eq!(deserialized.pcie_tx_kbps, Some(1200));
+        assert_eq!(deserialized.pcie_rx_kbps, None);
+    }
+
+    #[test]
+    fn test_read_ecc_counts() {
+        let (volatile, aggregate) = read_ecc_counts(|error_type, counter_type| {
+            Ok(match (error_type, counter_type) {
+                (MemoryError::Corrected, EccCounter::Volatile) => 4,
+                (MemoryError::Uncorrected, EccCounter::Volatile) => 1,
+                (MemoryError::Corrected, EccCounter::Aggregate) => 40,
+                (MemoryError::Uncorrected, EccCounter::Aggregate) => 2,
+            })
+        });
+
+        assert_eq!(
+            volatile,
+            Some(EccCounts {
+                corrected: 4,
+                uncorrected: 1
+            })
+        );
+        assert_eq!(
+            aggregate,
+            Some(EccCounts {
+                corrected: 40,
+                uncorrected: 2
+            })
+        );
+        assert_eq!(volatile.unwrap().total(), 5);
+    }
+
+    #[test]
+    fn test_read_ecc_counts_not_supported() {
+        let (volatile, aggregate) = read_ecc_counts(|_, _| Err(NvmlError::NotSupported));
+        assert_eq!(volatile, None);
+        assert_eq!(aggregate, None);
+    }
+
+    #[test]
+    fn test_read_retired_pages() {
+        let pages = read_retired_pages(
+            |cause| {
+                Ok(match cause {
+                    RetirementCause::MultipleSingleBitEccErrors => 58,
+                    RetirementCause::DoubleBitEccError => 2,
+                })
+            },
+            || Err(NvmlError::NotSupported),
+        )
+        .unwrap();
+        assert_eq!(pages.total(), 60);
+        assert!(!pages.pending);
+        assert!(pages.rma_flagged());
+
+        let pages = read_retired_pages(|_| Ok(1), || Ok(true)).unwrap();
+        assert!(pages.pending);
+        assert!(!pages.rma_flagged());
+
+        let unsupported = read_retired_pages(|_| Err(NvmlError::NotSupported), || Ok(true));
+        assert_eq!(unsupported, None);
+    }
+
+    #[test]
+    fn test_read_pcie_throughput() {
+        let (tx, rx) = read_pcie_throughput(|counter| {
+            Ok(match counter {
+                PcieUtilCounter::Send => 2048,
+                PcieUtilCounter::Receive => 512_000,
+            })
+        });
+        assert_eq!(tx, Some(2048));
+        assert_eq!(rx, Some(512_000));
+
+        let (tx, rx) = read_pcie_throughput(|_| Err(NvmlError::NotSupported));
+        assert_eq!(tx, None);
+        assert_eq!(rx, None);
+    }
+
+    #[test]
+    fn test_read_nvlink_traffic() {
+        // Links 0 and 1 active, the rest inactive
+        let total = read_nvlink_traffic(|link| match link {
+            0 => Ok(1_000),
+            1 => Ok(u64::MAX),
+            _ => Err(NvmlError::NotSupported),
+        });
+        assert_eq!(total, Some(u64::MAX));
+
+        let total = read_nvlink_traffic(|link| {
+            if link == 3 {
+                Ok(42)
+            } else {
+                Err(NvmlError::InvalidArg)
+            }
+        });
+        assert_eq!(total, Some(42));
+
+        assert_eq!(read_nvlink_traffic(|_| Err(NvmlError::NotSupported)), None);
+    }
+
+    #[test]
+    fn test_gpu_snapshot_without_pcie_fields_deserializes() {
+        // Snapshots written before PCIe throughput was recorded
+        let json = r#"{"gpu_index":0,"name":"Test GPU","vendor":"Nvidia","mem_used_mb":0,
+            "mem_total_mb":8192,"util_pct":0.0,"temp_c":40,"power_w":50.0,
+            "ecc_volatile":null,"pids":0,"top_proc":null}"#;
+        let snapshot: GpuSnapshot = serde_json::from_str(json).unwrap();
+        assert_eq!(snapshot.pcie_tx_kbps, None);
+        assert_eq!(snapshot.pcie_rx_kbps, None);
+        assert_eq!(snapshot.nvlink_bytes, None);
+    }
+
+    #[test]
+    fn test_snapshot_gpu_status_marks_degraded() {
+        // Snapshots saved before per-GPU status was recorded
+        let json = r#"{"host":"node-1","ts":"2024-06-01T00:00:00Z","gpus":[],"procs":[]}"#;
+        let snapshot: Snapshot = serde_json::from_str(json).unwrap();
+        assert!(!snapshot.degraded);
+        assert!(snapshot.gpu_status.is_empty());
+
+        let healthy = snapshot
+            .clone()
+            .with_gpu_status(vec![GpuStatusEntry::ok(0), GpuStatusEntry::ok(1)]);
+        assert!(!healthy.degraded);
+
+        let partial = snapshot.with_gpu_status(vec![
+            GpuStatusEntry::ok(0),
+            GpuStatusEntry {
+                gpu_index: 1,
+                status: GpuStatus::Unavailable,
+                error: Some("GPU has been lost and needs to be reset.".to_string()),
+            },
+        ]);
+        assert!(partial.degraded);
+        let value = serde_json::to_value(&partial).unwrap();
+        assert_eq!(value["degraded"], true);
+        assert_eq!(value["gpu_status"][0]["status"], "ok");
+        assert!(value["gpu_status"][0].get("error").is_none());
+        assert_eq!(value["gpu_status"][1]["status"], "unavailable");
+    }
+
+    #[test]
+    fn test_gpu_proc_command_falls_back_to_name() {
+        // Processes recorded before command lines were captured
+        let json = r#"{"gpu_index":0,"pid":42,"user":"alice","proc_name":"python",
+            "used_mem_mb":100,"start_time":"1h","container":null}"#;
+        let mut proc: GpuProc = serde_json::from_str(json).unwrap();
+        assert_eq!(proc.cmdline, None);
+        assert_eq!(proc.command(), "python");
+
+        proc.cmdline = Some("python train.py --epochs 10".to_string());
+        assert_eq!(proc.command(), "python train.py --epochs 10");
     }
 }
@@ -0,0 +1,408 @@
+//! What the user running gpukill may do. Many operations half-work without root:
+//! other users' processes can be listed but not signalled, their names and command
+//! lines may be unreadable, and resets and power changes are refused by the driver.
+//!
+//! Privileges are detected once per run from the effective user ID and, on Linux,
+//! the effective capability set in `/proc/self/status`, so commands can explain a
+//! limitation up front instead of failing with a generic error.
+
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::sync::OnceLock;
+
+use crate::nvml_api::GpuProc;
+
+/// Linux capabilities gpukill's operations depend on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /// Signal processes of other users
+    Kill,
+    /// Read other users' process details
+    SysPtrace,
+    /// Reset GPUs and change power limits and clocks
+    SysAdmin,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 3] = [
+        Capability::Kill,
+        Capability::SysPtrace,
+        Capability::SysAdmin,
+    ];
+
+    /// Bit of the capability in the `CapEff` mask
+    fn bit(self) -> u32 {
+        match self {
+            Capability::Kill => 5,
+            Capability::SysPtrace => 19,
+            Capability::SysAdmin => 21,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Capability::Kill => "CAP_KILL",
+            Capability::SysPtrace => "CAP_SYS_PTRACE",
+            Capability::SysAdmin => "CAP_SYS_ADMIN",
+        }
+    }
+}
+
+/// The user gpukill runs as and what that user may do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Privileges {
+    pub user: String,
+    /// Effective user ID; None on platforms without one
+    pub euid: Option<u32>,
+    /// Effective capability mask; None when it could not be read, in which case
+    /// root is assumed to hold every capability and other users none
+    effective: Option<u64>,
+}
+
+impl Privileges {
+    pub fn new(user: impl Into<String>, euid: Option<u32>, effective: Option<u64>) -> Self {
+        Self {
+            user: user.into(),
+            euid,
+            effective,
+        }
+    }
+
+    /// Privileges of the running process
+    pub fn detect() -> Self {
+        #[cfg(unix)]
+        // SAFETY: geteuid has no preconditions and cannot fail
+        let euid = Some(unsafe { libc::geteuid() });
+        #[cfg(not(unix))]
+        let euid = None;
+
+        #[cfg(target_os = "linux")]
+        let effective = std::fs::read_to_string("/proc/self/status")
+            .ok()
+            .and_then(|status| parse_effective_capabilities(&status));
+        #[cfg(not(target_os = "linux"))]
+        let effective = None;
+
+        Self::new(crate::proc::current_username(), euid, effective)
+    }
+
+    /// Privileges of the running process, detected on first use
+    pub fn current() -> &'static Privileges {
+        static CURRENT: OnceLock<Privileges> = OnceLock::new();
+        CURRENT.get_or_init(Self::detect)
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.euid == Some(0)
+    }
+
+    pub fn has(&self, capability: Capability) -> bool {
+        match self.effective {
+            Some(mask) => mask & (1 << capability.bit()) != 0,
+            None => self.is_root(),
+        }
+    }
+
+    /// Whether processes of other users can be signalled
+    pub fn can_signal_any_process(&self) -> bool {
+        self.has(Capability::Kill)
+    }
+
+    /// Whether other users' process names and command lines are always readable
+    pub fn can_inspect_any_process(&self) -> bool {
+        self.has(Capability::SysPtrace)
+    }
+
+    /// Whether GPUs can be reset and their power limits and clocks changed
+    pub fn can_manage_gpus(&self) -> bool {
+        self.has(Capability::SysAdmin)
+    }
+
+    /// Which operations are available at this privilege level
+    pub fn operations(&self) -> Vec<OperationAccess> {
+        let access = |operation: &str, full: bool, otherwise: Access, note: &str| {
+            if full {
+                OperationAccess::new(operation, Access::Full, None)
+            } else {
+                OperationAccess::new(operation, otherwise, Some(note.to_string()))
+            }
+        };
+        let limited_unless = |operation: &str, full: bool, note: &str| {
+            access(operation, full, Access::Limited, note)
+        };
+        let unavailable_unless = |operation: &str, full: bool, note: &str| {
+            access(operation, full, Access::Unavailable, note)
+        };
+        let own_processes = format!("Only processes owned by {}", self.user);
+
+        vec![
+            limited_unless(
+                "list",
+                self.can_inspect_any_process(),
+                "Other users' process names and command lines may show as unknown",
+            ),
+            limited_unless("kill", self.can_signal_any_process(), &own_processes),
+            limited_unless("guard", self.can_signal_any_process(), &own_processes),
+            unavailable_unless(
+                "reset",
+                self.can_manage_gpus(),
+                "Needs root or CAP_SYS_ADMIN",
+            ),
+            unavailable_unless("set", self.can_manage_gpus(), "Needs root or CAP_SYS_ADMIN"),
+            unavailable_unless(
+                "hard_reset",
+                self.is_root() && self.can_manage_gpus(),
+                "Needs root",
+            ),
+        ]
+    }
+
+    /// Report for `--capabilities --check-privileges`
+    pub fn report(&self) -> PrivilegeReport {
+        PrivilegeReport {
+            user: self.user.clone(),
+            euid: self.euid,
+            root: self.is_root(),
+            capabilities: Capability::ALL
+                .into_iter()
+                .filter(|capability| self.has(*capability))
+                .map(|capability| capability.name().to_string())
+                .collect(),
+            operations: self.operations(),
+        }
+    }
+}
+
+/// How much of an operation works at the current privilege level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Access {
+    Full,
+    /// Works on the user's own processes only
+    Limited,
+    Unavailable,
+}
+
+impl std::fmt::Display for Access {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Access::Full => "full",
+            Access::Limited => "limited",
+            Access::Unavailable => "unavailable",
+        })
+    }
+}
+
+/// One row of the `--check-privileges` table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperationAccess {
+    pub operation: String,
+    pub access: Access,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl OperationAccess {
+    fn new(operation: &str, access: Access, note: Option<String>) -> Self {
+        Self {
+            operation: operation.to_string(),
+            access,
+            note,
+        }
+    }
+}
+
+/// The user gpukill runs as and the operations available to it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PrivilegeReport {
+    pub user: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub euid: Option<u32>,
+    pub root: bool,
+    /// Capabilities gpukill depends on that this process holds
+    pub capabilities: Vec<String>,
+    pub operations: Vec<OperationAccess>,
+}
+
+/// The effective capability mask from the contents of `/proc/<pid>/status`
+pub fn parse_effective_capabilities(status: &str) -> Option<u64> {
+    let mask = status
+        .lines()
+        .find_map(|line| line.strip_prefix("CapEff:"))?;
+    u64::from_str_radix(mask.trim(), 16).ok()
+}
+
+/// Note for a listing in which some processes' owner or name could not be read
+/// because gpukill runs without the privileges to read them
+pub fn hidden_process_note(privileges: &Privileges, procs: &[GpuProc]) -> Option<String> {
+    if privileges.can_inspect_any_process() {
+        return None;
+    }
+    let mut hidden: Vec<u32> = procs
+        .iter()
+        .filter(|p| p.user == "unknown" || p.proc_name == "unknown")
+        .map(|p| p.pid)
+        .collect();
+    hidden.sort_unstable();
+    hidden.dedup();
+    match hidden.len() {
+        0 => None,
+        count => Some(format!(
+            "{} process(es) are hidden from {}: their owner or name shows as unknown. \
+             Run with sudo to see other users' process details",
+            count, privileges.user
+        )),
+    }
+}
+
+/// The command line of this run prefixed with `sudo`, quoted for a POSIX shell
+pub fn sudo_command() -> String {
+    sudo_command_for(std::env::args())
+}
+
+fn sudo_command_for(args: impl IntoIterator<Item = String>) -> String {
+    std::iter::once(Cow::Borrowed("sudo"))
+        .chain(
+            args.into_iter()
+                .map(|arg| Cow::Owned(shell_quote(&arg).into_owned())),
+        )
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// `arg` as one POSIX shell word
+fn shell_quote(arg: &str) -> Cow<'_, str> {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:,@%+".contains(c));
+    if plain {
+        Cow::Borrowed(arg)
+    } else {
+        Cow::Owned(format!("'{}'", arg.replace('\'', r"'\''")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nvml_api::fixtures::proc;
+
+    const STATUS: &str = "Name:\tgpukill\nUid:\t1000\t1000\t1000\t1000\n\
+        CapInh:\t0000000000000000\nCapPrm:\t0000000000000020\n\
+        CapEff:\t0000000000000020\nCapBnd:\t000001ffffffffff\n";
+
+    #[test]
+    fn test_capabilities_come_from_the_effective_mask() {
+        let mask = parse_effective_capabilities(STATUS);
+        assert_eq!(mask, Some(1 << 5));
+        assert_eq!(parse_effective_capabilities("Name:\tgpukill\n"), None);
+
+        // CAP_KILL alone: any process can be signalled, but GPUs cannot be reset
+        let alice = Privileges::new("alice", Some(1000), mask);
+        assert!(alice.can_signal_any_process());
+        assert!(!alice.can_inspect_any_process());
+        assert!(!alice.can_manage_gpus());
+        assert_eq!(alice.report().capabilities, ["CAP_KILL"]);
+
+        // Root in a container without CAP_SYS_ADMIN still cannot reset
+        let root = Privileges::new("root", Some(0), Some(1 << 5 | 1 << 19));
+        assert!(root.is_root());
+        assert!(!root.can_manage_gpus());
+
+        // Without a readable mask, root is assumed to hold everything
+        assert!(Privileges::new("root", Some(0), None).can_manage_gpus());
+        assert!(!Privileges::new("bob", Some(1001), None).can_signal_any_process());
+    }
+
+    #[test]
+    fn test_operations_at_each_privilege_level() {
+        let access = |privileges: &Privileges| {
+            privileges
+                .operations()
+                .into_iter()
+                .map(|op| (op.operation, op.access))
+                .collect::<Vec<_>>()
+        };
+        let ops = |levels: [Access; 6]| {
+            ["list", "kill", "guard", "reset", "set", "hard_reset"]
+                .into_iter()
+                .map(str::to_string)
+                .zip(levels)
+                .collect::<Vec<_>>()
+        };
+
+        let root = Privileges::new("root", Some(0), Some(u64::MAX));
+        assert_eq!(access(&root), ops([Access::Full; 6]));
+        assert!(root.operations().iter().all(|op| op.note.is_none()));
+
+        let bob = Privileges::new("bob", Some(1001), Some(0));
+        assert_eq!(
+            access(&bob),
+            ops([
+                Access::Limited,
+                Access::Limited,
+                Access::Limited,
+                Access::Unavailable,
+                Access::Unavailable,
+                Access::Unavailable,
+            ])
+        );
+        assert_eq!(
+            bob.operations()[1].note.as_deref(),
+            Some("Only processes owned by bob")
+        );
+
+        // CAP_SYS_ADMIN without root resets GPUs but cannot reload the driver
+        let admin = Privileges::new("ops", Some(1002), Some(1 << 21));
+        assert_eq!(access(&admin)[3].1, Access::Full);
+        assert_eq!(access(&admin)[5].1, Access::Unavailable);
+
+        let json = serde_json::to_value(bob.report()).unwrap();
+        assert_eq!(json["root"], false);
+        assert_eq!(json["operations"][3]["access"], "unavailable");
+    }
+
+    #[test]
+    fn test_hidden_process_note_counts_unreadable_processes() {
+        let procs = [
+            proc(0, 10, "bob", "python", 1024),
+            proc(0, 11, "unknown", "unknown", 1024),
+            proc(0, 12, "carol", "unknown", 1024),
+            // Same process on a second GPU
+            proc(0, 11, "unknown", "unknown", 1024),
+        ];
+        let bob = Privileges::new("bob", Some(1001), Some(0));
+        let note = hidden_process_note(&bob, &procs).unwrap();
+        assert!(
+            note.starts_with("2 process(es) are hidden from bob"),
+            "{}",
+            note
+        );
+        assert!(note.contains("sudo"), "{}", note);
+
+        let root = Privileges::new("root", Some(0), None);
+        assert_eq!(hidden_process_note(&root, &procs), None);
+        assert_eq!(hidden_process_note(&bob, &procs[..1]), None);
+    }
+
+    #[test]
+    fn test_sudo_command_quotes_arguments() {
+        let args = [
+            "gpukill",
+            "--kill",
+            "--filter",
+            "^python .*",
+            "--user",
+            "o'brien",
+        ];
+        assert_eq!(
+            sudo_command_for(args.map(str::to_string)),
+            r"sudo gpukill --kill --filter '^python .*' --user 'o'\''brien'"
+        );
+        assert_eq!(
+            sudo_command_for(["/opt/bin/gpukill", "--pid=42", ""].map(str::to_string)),
+            "sudo /opt/bin/gpukill --pid=42 ''"
+        );
+    }
+}
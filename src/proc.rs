@@ -1,4 +1,5 @@
 use crate::nvml_api::NvmlApi;
+use crate::report::NotFound;
 use crate::util::parse_process_start_time;
 use anyhow::{Context, Result};
 #[cfg(unix)]
@@ -6,6 +7,8 @@ use nix::sys::signal::{kill, Signal};
 #[cfg(unix)]
 use nix::unistd::Pid;
 // use std::process::Command; // Used conditionally below
+use std::fmt;
+use std::str::FromStr;
 use std::time::{Duration, SystemTime};
 use sysinfo::{Pid as SysPid, System};
 
@@ -22,20 +25,511 @@ pub struct ProcessInfo {
     pub cmdline: String,
 }
 
+/// Signal `graceful_kill` sends first, before escalating to SIGKILL. Signal numbers are
+/// parsed as the POSIX values shared by Linux and macOS; on Windows the choice has no
+/// effect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KillSignal {
+    #[default]
+    Term,
+    Int,
+    Quit,
+    Hup,
+}
+
+impl KillSignal {
+    /// Signal name, e.g. "SIGTERM"
+    pub fn name(&self) -> &'static str {
+        match self {
+            KillSignal::Term => "SIGTERM",
+            KillSignal::Int => "SIGINT",
+            KillSignal::Quit => "SIGQUIT",
+            KillSignal::Hup => "SIGHUP",
+        }
+    }
+
+    #[cfg(unix)]
+    fn to_nix(self) -> Signal {
+        match self {
+            KillSignal::Term => Signal::SIGTERM,
+            KillSignal::Int => Signal::SIGINT,
+            KillSignal::Quit => Signal::SIGQUIT,
+            KillSignal::Hup => Signal::SIGHUP,
+        }
+    }
+}
+
+impl fmt::Display for KillSignal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for KillSignal {
+    type Err = anyhow::Error;
+
+    /// Accepts "SIGINT", "INT", "int" or the signal number
+    fn from_str(value: &str) -> Result<Self> {
+        let upper = value.trim().to_ascii_uppercase();
+        let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+        match name {
+            "TERM" | "15" => Ok(KillSignal::Term),
+            "INT" | "2" => Ok(KillSignal::Int),
+            "QUIT" | "3" => Ok(KillSignal::Quit),
+            "HUP" | "1" => Ok(KillSignal::Hup),
+            "KILL" | "9" => Err(anyhow::anyhow!(
+                "SIGKILL cannot be the initial signal because it skips the graceful timeout; use --force to escalate to SIGKILL"
+            )),
+            _ => Err(anyhow::anyhow!(
+                "Unsupported signal '{}' (expected SIGTERM, SIGINT, SIGQUIT or SIGHUP)",
+                value
+            )),
+        }
+    }
+}
+
+/// Target of `--pid`: a host PID, or a PID as seen inside a container's PID namespace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PidTarget {
+    Host(u32),
+    /// `container:<name-or-id>:<pid>`
+    Container {
+        container: String,
+        pid: u32,
+    },
+}
+
+impl PidTarget {
+    /// The PID as given, which for a container target is not a host PID
+    pub fn pid(&self) -> u32 {
+        match self {
+            PidTarget::Host(pid) | PidTarget::Container { pid, .. } => *pid,
+        }
+    }
+
+    /// Host PID to signal; container PIDs are translated through the PID namespace
+    pub fn resolve(&self) -> Result<u32> {
+        match self {
+            PidTarget::Host(pid) => Ok(*pid),
+            PidTarget::Container { container, pid } => {
+                resolve_container_pid(container, *pid, &crate::command::SystemCommandRunner)
+            }
+        }
+    }
+}
+
+impl fmt::Display for PidTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PidTarget::Host(pid) => write!(f, "{}", pid),
+            PidTarget::Container { container, pid } => {
+                write!(f, "container:{}:{}", container, pid)
+            }
+        }
+    }
+}
+
+impl FromStr for PidTarget {
+    type Err = anyhow::Error;
+
+    /// Accepts "12345" or "container:<name-or-id>:<pid>"
+    fn from_str(value: &str) -> Result<Self> {
+        let value = value.trim();
+        let parse_pid = |pid: &str| {
+            pid.parse::<u32>()
+                .map_err(|_| anyhow::anyhow!("'{}' is not a valid PID", pid))
+        };
+        match value.strip_prefix("container:") {
+            Some(rest) => {
+                let (container, pid) = rest.rsplit_once(':').ok_or_else(|| {
+                    anyhow::anyhow!("expected container:<name-or-id>:<pid>, got '{}'", value)
+                })?;
+                if container.is_empty() {
+                    anyhow::bail!("missing container name or ID in '{}'", value);
+                }
+                Ok(PidTarget::Container {
+                    container: container.to_string(),
+                    pid: parse_pid(pid)?,
+                })
+            }
+            None => Ok(PidTarget::Host(parse_pid(value)?)),
+        }
+    }
+}
+
+/// PIDs of a process in each PID namespace it belongs to, outermost (the host's) first,
+/// from the `NSpid:` line of /proc/<pid>/status. Empty when the line is missing
+/// (kernels before 4.1).
+pub fn parse_nspid(status: &str) -> Vec<u32> {
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("NSpid:"))
+        .map(|pids| {
+            pids.split_whitespace()
+                .map_while(|pid| pid.parse().ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// PID of a process inside its innermost PID namespace, when that differs from the
+/// host PID. None outside containers and wherever NSpid cannot be read.
+pub fn get_container_pid(pid: u32) -> Option<u32> {
+    #[cfg(target_os = "linux")]
+    {
+        let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+        innermost_pid(&parse_nspid(&status)).filter(|inner| *inner != pid)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = pid;
+        None
+    }
+}
+
+fn innermost_pid(nspid: &[u32]) -> Option<u32> {
+    nspid.last().copied().filter(|_| nspid.len() > 1)
+}
+
+/// Container ID in a /proc/<pid>/cgroup file: the 64 hex digit ID that Docker, Podman,
+/// containerd and CRI-O embed in the cgroup path (`/docker/<id>`, `docker-<id>.scope`,
+/// `libpod-<id>.scope`, `cri-containerd-<id>.scope`, `crio-<id>.scope`)
+pub fn parse_cgroup_container_id(cgroup: &str) -> Option<String> {
+    cgroup.lines().find_map(|line| {
+        let path = line.splitn(3, ':').nth(2)?;
+        path.rsplit('/').find_map(|segment| {
+            let segment = segment.strip_suffix(".scope").unwrap_or(segment);
+            let id = segment.rsplit(['-', ':']).next().unwrap_or(segment);
+            (id.len() == 64 && id.bytes().all(|b| b.is_ascii_hexdigit()))
+                .then(|| id.to_ascii_lowercase())
+        })
+    })
+}
+
+/// Host PID of the process that has PID `container_pid` inside the container whose ID
+/// starts with `container_id`, from (host PID, cgroup file, status file) of candidates
+pub fn find_host_pid<'a>(
+    processes: impl IntoIterator<Item = (u32, &'a str, &'a str)>,
+    container_id: &str,
+    container_pid: u32,
+) -> Result<u32> {
+    let wanted = container_id.to_ascii_lowercase();
+    let mut containers = std::collections::BTreeSet::new();
+    let mut found = Vec::new();
+    for (host_pid, cgroup, status) in processes {
+        let Some(id) = parse_cgroup_container_id(cgroup).filter(|id| id.starts_with(&wanted))
+        else {
+            continue;
+        };
+        if parse_nspid(status).last() == Some(&container_pid) {
+            found.push(host_pid);
+        }
+        containers.insert(id);
+    }
+
+    if containers.len() > 1 {
+        anyhow::bail!(
+            "Invalid argument: container ID prefix '{}' matches {} containers",
+            container_id,
+            containers.len()
+        );
+    }
+    match found.as_slice() {
+        [host_pid] => Ok(*host_pid),
+        [] if containers.is_empty() => anyhow::bail!(
+            "Invalid argument: no running container matches '{}'",
+            container_id
+        ),
+        [] => anyhow::bail!(
+            "Invalid argument: no process with PID {} in container {}",
+            container_pid,
+            container_id
+        ),
+        _ => anyhow::bail!(
+            "Invalid argument: PID {} in container {} matches host PIDs {:?}",
+            container_pid,
+            container_id,
+            found
+        ),
+    }
+}
+
+/// Full container ID for a container name, asking Docker then Podman. Anything they do
+/// not recognize is taken to be an ID (or ID prefix) already.
+fn resolve_container_id(name_or_id: &str, runner: &dyn crate::command::CommandRunner) -> String {
+    for engine in ["docker", "podman"] {
+        if let Ok(output) = runner.run(engine, &["inspect", "--format", "{{.Id}}", name_or_id]) {
+            let id = output.stdout.trim();
+            if output.success && !id.is_empty() && id.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return id.to_ascii_lowercase();
+            }
+        }
+    }
+    name_or_id.to_string()
+}
+
+/// Translate `pid` as seen inside container `name_or_id` to the host PID
+pub fn resolve_container_pid(
+    name_or_id: &str,
+    pid: u32,
+    runner: &dyn crate::command::CommandRunner,
+) -> Result<u32> {
+    let container_id = resolve_container_id(name_or_id, runner);
+
+    #[cfg(target_os = "linux")]
+    {
+        let mut candidates = Vec::new();
+        for entry in std::fs::read_dir("/proc").context("Failed to read /proc")? {
+            let Some(host_pid) = entry
+                .ok()
+                .and_then(|e| e.file_name().to_str()?.parse::<u32>().ok())
+            else {
+                continue;
+            };
+            // Processes can exit while we scan; skip the ones that are gone
+            let (Ok(cgroup), Ok(status)) = (
+                std::fs::read_to_string(format!("/proc/{}/cgroup", host_pid)),
+                std::fs::read_to_string(format!("/proc/{}/status", host_pid)),
+            ) else {
+                continue;
+            };
+            candidates.push((host_pid, cgroup, status));
+        }
+        find_host_pid(
+            candidates
+                .iter()
+                .map(|(pid, cgroup, status)| (*pid, cgroup.as_str(), status.as_str())),
+            &container_id,
+            pid,
+        )
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (container_id, pid);
+        anyhow::bail!("Container-qualified PIDs are not supported on this platform")
+    }
+}
+
+/// A signal the kernel refused to deliver (EPERM) because gpukill lacks the
+/// privileges to signal the process, usually because another user owns it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KillPermissionDenied {
+    pub pid: u32,
+    pub signal: &'static str,
+    /// Owner of the process, when it could be read
+    pub owner: Option<String>,
+    /// User gpukill runs as
+    pub user: String,
+    /// gpukill already runs as root, so sudo would not help
+    pub root: bool,
+}
+
+impl KillPermissionDenied {
+    pub fn new(
+        pid: u32,
+        signal: &'static str,
+        owner: Option<String>,
+        privileges: &crate::privileges::Privileges,
+    ) -> Self {
+        Self {
+            pid,
+            signal,
+            owner,
+            user: privileges.user.clone(),
+            root: privileges.is_root(),
+        }
+    }
+}
+
+impl fmt::Display for KillPermissionDenied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Permission denied: cannot send {} to PID {}",
+            self.signal, self.pid
+        )?;
+        if let Some(owner) = &self.owner {
+            write!(f, " (owned by {})", owner)?;
+        }
+        if self.root {
+            write!(
+                f,
+                "; it was refused even as root, so the process may be in another user \
+                 namespace or protected by a security module"
+            )
+        } else {
+            write!(
+                f,
+                " as {}; only its owner or root can signal it, re-run with sudo",
+                self.user
+            )
+        }
+    }
+}
+
+impl std::error::Error for KillPermissionDenied {}
+
+/// The [`KillPermissionDenied`] behind `error`, if a kill failed for lack of privileges
+pub fn kill_permission_denied(error: &anyhow::Error) -> Option<&KillPermissionDenied> {
+    error
+        .chain()
+        .find_map(|e| e.downcast_ref::<KillPermissionDenied>())
+}
+
+/// Send `signal` to `pid`, turning EPERM into a [`KillPermissionDenied`]
+#[cfg(unix)]
+fn send_signal(pid: u32, signal: Signal) -> Result<()> {
+    kill(Pid::from_raw(pid as i32), signal).map_err(|errno| signal_error(pid, signal, errno))
+}
+
+#[cfg(unix)]
+fn signal_error(pid: u32, signal: Signal, errno: nix::errno::Errno) -> anyhow::Error {
+    match errno {
+        nix::errno::Errno::EPERM => KillPermissionDenied::new(
+            pid,
+            signal.as_str(),
+            get_process_owner(pid),
+            crate::privileges::Privileges::current(),
+        )
+        .into(),
+        errno => anyhow::anyhow!("Failed to send {}: {}", signal, errno),
+    }
+}
+
+/// Refuses kills aimed at gpukill itself or its direct parent (usually the shell
+/// that started it) unless explicitly allowed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelfGuard {
+    own_pid: u32,
+    parent_pid: Option<u32>,
+    allow: bool,
+}
+
+impl SelfGuard {
+    pub fn new(own_pid: u32, parent_pid: Option<u32>) -> Self {
+        Self {
+            own_pid,
+            parent_pid,
+            allow: false,
+        }
+    }
+
+    /// Guard for the running gpukill process
+    pub fn current() -> Self {
+        Self::new(std::process::id(), current_parent_pid())
+    }
+
+    /// Let kills through anyway; the CLI only does this for `--force --allow-self`
+    pub fn allowing(mut self, allow: bool) -> Self {
+        self.allow = allow;
+        self
+    }
+
+    pub fn is_allowed(&self) -> bool {
+        self.allow
+    }
+
+    /// The PIDs this guard covers: gpukill and, if known, its parent
+    pub fn pids(&self) -> Vec<u32> {
+        std::iter::once(self.own_pid)
+            .chain(self.parent_pid)
+            .collect()
+    }
+
+    /// Why `pid` is guarded, whether or not kills are allowed
+    pub fn reason(&self, pid: u32) -> Option<&'static str> {
+        if pid == self.own_pid {
+            Some("gpukill's own process")
+        } else if Some(pid) == self.parent_pid {
+            Some("gpukill's parent process")
+        } else {
+            None
+        }
+    }
+
+    /// Error if `pid` is guarded and kills are not allowed
+    pub fn check(&self, pid: u32) -> Result<()> {
+        match self.reason(pid) {
+            Some(reason) if !self.allow => Err(anyhow::anyhow!(
+                "Refusing to kill PID {}: it is {}; pass --force --allow-self to kill it anyway",
+                pid,
+                reason
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn current_parent_pid() -> Option<u32> {
+    Some(std::os::unix::process::parent_id())
+}
+
+#[cfg(not(unix))]
+fn current_parent_pid() -> Option<u32> {
+    let mut system = System::new();
+    system.refresh_processes();
+    system
+        .process(SysPid::from_u32(std::process::id()))
+        .and_then(|p| p.parent())
+        .map(|p| p.as_u32())
+}
+
+/// Where a [`ProcessManager`] finds GPUs and the processes using them
+enum GpuBackend {
+    Nvml(Box<NvmlApi>),
+    /// Any vendor, such as the `mock_nvml` fixture vendor
+    Vendors(crate::vendor::GpuManager),
+}
+
 /// Process management utilities
 pub struct ProcessManager {
-    nvml_api: NvmlApi,
+    gpus: GpuBackend,
     system: System,
+    signal: KillSignal,
+    self_guard: SelfGuard,
 }
 
 #[allow(dead_code)]
 impl ProcessManager {
     /// Create a new process manager
     pub fn new(nvml_api: NvmlApi) -> Self {
+        Self::with_backend(GpuBackend::Nvml(Box::new(nvml_api)))
+    }
+
+    /// Process manager that looks up GPUs and their processes through
+    /// `gpu_manager` instead of NVML
+    pub fn with_gpu_manager(gpu_manager: crate::vendor::GpuManager) -> Self {
+        Self::with_backend(GpuBackend::Vendors(gpu_manager))
+    }
+
+    fn with_backend(gpus: GpuBackend) -> Self {
         let mut system = System::new_all();
         system.refresh_all();
 
-        Self { nvml_api, system }
+        Self {
+            gpus,
+            system,
+            signal: KillSignal::default(),
+            self_guard: SelfGuard::current(),
+        }
+    }
+
+    /// Set the signal `graceful_kill` sends first
+    pub fn set_kill_signal(&mut self, signal: KillSignal) {
+        self.signal = signal;
+    }
+
+    /// Let kills target gpukill itself and its parent (`--force --allow-self`)
+    pub fn set_allow_self(&mut self, allow: bool) {
+        self.self_guard = self.self_guard.allowing(allow);
+    }
+
+    /// The guard refusing kills of gpukill itself and its parent
+    pub fn self_guard(&self) -> SelfGuard {
+        self.self_guard
     }
 
     /// Get process information by PID
@@ -46,7 +540,7 @@ impl ProcessManager {
         let process = self
             .system
             .process(sys_pid)
-            .ok_or_else(|| anyhow::anyhow!("Process with PID {} not found", pid))?;
+            .ok_or_else(|| NotFound(format!("Process with PID {} not found", pid)))?;
 
         let user = get_process_user(pid).unwrap_or_else(|_| "unknown".to_string());
 
@@ -64,62 +558,46 @@ impl ProcessManager {
 
     /// Check if a process is using any GPU
     pub fn is_process_using_gpu(&self, pid: u32) -> Result<bool> {
-        self.nvml_api.is_process_using_gpu(pid)
+        match &self.gpus {
+            GpuBackend::Nvml(nvml_api) => nvml_api.is_process_using_gpu(pid),
+            GpuBackend::Vendors(gpu_manager) => Ok(gpu_manager
+                .get_all_processes()?
+                .iter()
+                .any(|p| p.pid == pid)),
+        }
     }
 
-    /// Gracefully terminate a process with timeout and escalation
+    /// Gracefully terminate a process with timeout and escalation. Returns the name
+    /// of the signal that ended the process.
     #[cfg(unix)]
-    pub fn graceful_kill(&mut self, pid: u32, timeout_secs: u16, force: bool) -> Result<()> {
-        let pid = Pid::from_raw(pid as i32);
-
-        // First, try SIGTERM
-        tracing::info!("Sending SIGTERM to process {}", pid);
-        kill(pid, Signal::SIGTERM).map_err(|e| anyhow::anyhow!("Failed to send SIGTERM: {}", e))?;
-
-        // Wait for the process to terminate
-        let timeout = Duration::from_secs(timeout_secs as u64);
-        let start = SystemTime::now();
-
-        while SystemTime::now().duration_since(start).unwrap_or_default() < timeout {
-            // Check if process still exists (with fresh data)
-            if !self.is_process_running(pid.as_raw() as u32)? {
-                tracing::info!("Process {} terminated gracefully", pid);
-                return Ok(());
-            }
-
-            std::thread::sleep(Duration::from_millis(100));
-        }
-
-        // Process didn't terminate, escalate if force is enabled
-        if force {
-            tracing::warn!("Process {} did not terminate, escalating to SIGKILL", pid);
-            kill(pid, Signal::SIGKILL)
-                .map_err(|e| anyhow::anyhow!("Failed to send SIGKILL: {}", e))?;
-
-            // Wait a bit more for SIGKILL to take effect
-            std::thread::sleep(Duration::from_millis(500));
-
-            if !self.is_process_running(pid.as_raw() as u32)? {
-                tracing::info!("Process {} terminated with SIGKILL", pid);
-                Ok(())
-            } else {
-                Err(anyhow::anyhow!(
-                    "Process {} still running after SIGKILL",
-                    pid
-                ))
-            }
-        } else {
-            Err(anyhow::anyhow!(
-                "Process {} did not terminate within {} seconds. Use --force to escalate to SIGKILL",
-                pid,
-                timeout_secs
-            ))
-        }
+    pub fn graceful_kill(
+        &mut self,
+        pid: u32,
+        timeout_secs: u16,
+        force: bool,
+    ) -> Result<&'static str> {
+        let signal = self.signal;
+        let result = terminate_process(
+            pid,
+            signal,
+            timeout_secs,
+            force,
+            |sig| send_signal(pid, sig),
+            || self.is_process_running(pid),
+        );
+        // Even a failed kill may have ended the process or some of its GPU work
+        self.invalidate_gpu_cache();
+        result
     }
 
     /// Gracefully terminate a process with timeout and escalation (Windows stub)
     #[cfg(windows)]
-    pub fn graceful_kill(&mut self, _pid: u32, _timeout_secs: u16, _force: bool) -> Result<()> {
+    pub fn graceful_kill(
+        &mut self,
+        _pid: u32,
+        _timeout_secs: u16,
+        _force: bool,
+    ) -> Result<&'static str> {
         // On Windows, we can't use Unix signals, so we'll use a different approach
         // For now, just return an error indicating this feature isn't available on Windows
         Err(anyhow::anyhow!(
@@ -127,6 +605,14 @@ impl ProcessManager {
         ))
     }
 
+    /// Forget the processes and snapshots the GPU manager has cached, so the next
+    /// query after a kill no longer shows the PIDs it ended
+    pub fn invalidate_gpu_cache(&self) {
+        if let GpuBackend::Vendors(gpu_manager) = &self.gpus {
+            gpu_manager.invalidate_cache();
+        }
+    }
+
     /// Check if a process is still running
     ///
     /// Uses the return value of refresh_process: sysinfo does not remove dead
@@ -150,7 +636,14 @@ impl ProcessManager {
                 process.user = process_info.user;
                 process.proc_name = process_info.name;
                 process.start_time = parse_process_start_time(process_info.start_time);
+                process.start_unix_secs = process_info
+                    .start_time
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .ok()
+                    .map(|elapsed| elapsed.as_secs());
             }
+            process.cmdline = get_process_cmdline(process.pid);
+            process.container_pid = get_container_pid(process.pid);
         }
 
         Ok(processes)
@@ -158,16 +651,21 @@ impl ProcessManager {
 
     /// Get all processes using GPUs with enriched information
     pub fn get_enriched_gpu_processes(&mut self) -> Result<Vec<crate::nvml_api::GpuProc>> {
-        let processes = self.nvml_api.get_gpu_processes()?;
+        let processes = match &self.gpus {
+            GpuBackend::Nvml(nvml_api) => nvml_api.get_gpu_processes()?,
+            GpuBackend::Vendors(gpu_manager) => gpu_manager.get_all_processes()?,
+        };
         self.enrich_gpu_processes(processes)
     }
 
     /// Validate that a process exists and optionally check GPU usage
     pub fn validate_process(&self, pid: u32, check_gpu_usage: bool) -> Result<()> {
+        self.self_guard.check(pid)?;
+
         // Check if process exists
         let sys_pid = SysPid::from_u32(pid);
         if self.system.process(sys_pid).is_none() {
-            return Err(anyhow::anyhow!("Process with PID {} not found", pid));
+            return Err(NotFound(format!("Process with PID {} not found", pid)).into());
         }
 
         // Check GPU usage if requested
@@ -186,17 +684,38 @@ impl ProcessManager {
 
     /// Get device count
     pub fn device_count(&self) -> Result<u32> {
-        self.nvml_api.device_count()
+        match &self.gpus {
+            GpuBackend::Nvml(nvml_api) => nvml_api.device_count(),
+            GpuBackend::Vendors(gpu_manager) => gpu_manager.total_device_count(),
+        }
     }
 
     /// Create snapshot
     pub fn create_snapshot(&self) -> Result<crate::nvml_api::Snapshot> {
-        self.nvml_api.create_snapshot()
+        let gpu_manager = match &self.gpus {
+            GpuBackend::Nvml(nvml_api) => return nvml_api.create_snapshot(),
+            GpuBackend::Vendors(gpu_manager) => gpu_manager,
+        };
+        let collection = gpu_manager.collect_snapshots()?;
+        let gpu_status = collection.gpu_status();
+        Ok(crate::nvml_api::Snapshot {
+            host: crate::util::get_hostname(),
+            ts: crate::util::get_current_timestamp_iso(),
+            gpus: collection.snapshots,
+            procs: gpu_manager.get_all_processes()?,
+            degraded: false,
+            gpu_status: Vec::new(),
+            errors: collection.errors,
+        }
+        .with_gpu_status(gpu_status))
     }
 
     /// Reset GPU
     pub fn reset_gpu(&self, index: u32) -> Result<()> {
-        self.nvml_api.reset_gpu(index)
+        match &self.gpus {
+            GpuBackend::Nvml(nvml_api) => nvml_api.reset_gpu(index),
+            GpuBackend::Vendors(gpu_manager) => gpu_manager.reset_gpu(index),
+        }
     }
 }
 
@@ -274,6 +793,113 @@ fn get_process_user(pid: u32) -> Result<String> {
     Ok("unknown".to_string())
 }
 
+/// Read the full command line of a process: /proc/<pid>/cmdline on Linux, sysinfo
+/// elsewhere. None when it cannot be read or is empty (kernel threads, zombies).
+pub fn get_process_cmdline(pid: u32) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        let raw = std::fs::read(format!("/proc/{}/cmdline", pid)).ok()?;
+        parse_cmdline(&raw)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let sys_pid = SysPid::from_u32(pid);
+        let mut system = System::new();
+        system.refresh_process(sys_pid);
+        let cmd = system.process(sys_pid)?.cmd().join(" ");
+        (!cmd.trim().is_empty()).then_some(cmd)
+    }
+}
+
+/// Join the NUL-separated arguments of a /proc/<pid>/cmdline buffer with spaces
+#[allow(dead_code)]
+pub fn parse_cmdline(raw: &[u8]) -> Option<String> {
+    let args: Vec<String> = raw
+        .split(|b| *b == 0)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .collect();
+    if args.is_empty() {
+        None
+    } else {
+        Some(args.join(" "))
+    }
+}
+
+/// Name of a process straight from /proc: `comm`, widened to the basename of
+/// argv[0] when `comm` was truncated by the kernel. None when /proc has neither.
+pub fn get_process_name(pid: u32) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        let comm = std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok();
+        let cmdline = std::fs::read(format!("/proc/{}/cmdline", pid)).ok();
+        process_name_from_proc(comm.as_deref(), cmdline.as_deref())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = pid;
+        None
+    }
+}
+
+/// Username owning /proc/<pid>, resolved through passwd. None when /proc cannot
+/// be read.
+pub fn get_process_owner(pid: u32) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let uid = std::fs::metadata(format!("/proc/{}", pid)).ok()?.uid();
+        get_username_from_uid(uid).ok()
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = pid;
+        None
+    }
+}
+
+/// Pick a process name from the contents of /proc/<pid>/comm and cmdline.
+/// `comm` is capped at 15 bytes, so argv[0]'s basename wins when it extends it;
+/// interpreters and renamed threads (`comm` not a prefix of argv[0]) keep `comm`.
+#[allow(dead_code)]
+pub fn process_name_from_proc(comm: Option<&str>, cmdline: Option<&[u8]>) -> Option<String> {
+    let comm = comm.map(str::trim_end).filter(|c| !c.is_empty());
+    let argv0 = cmdline
+        .and_then(|raw| raw.split(|b| *b == 0).next())
+        .map(|arg| String::from_utf8_lossy(arg).into_owned())
+        .and_then(|arg| {
+            let base = arg
+                .rsplit('/')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string();
+            (!base.is_empty()).then_some(base)
+        });
+
+    match (comm, argv0) {
+        (Some(comm), Some(base)) if base.starts_with(comm) => Some(base),
+        (Some(comm), _) => Some(comm.to_string()),
+        (None, base) => base,
+    }
+}
+
+/// Name of the user this process runs as (the effective user, so `root` under sudo)
+pub fn current_username() -> String {
+    #[cfg(target_os = "linux")]
+    {
+        // SAFETY: geteuid has no preconditions and cannot fail
+        let uid = unsafe { libc::geteuid() };
+        if let Ok(name) = get_username_from_uid(uid) {
+            return name;
+        }
+    }
+    std::env::var("USER").unwrap_or_else(|_| "unknown".to_string())
+}
+
 #[cfg(target_os = "linux")]
 fn get_username_from_uid(uid: u32) -> Result<String> {
     use std::ffi::CStr;
@@ -295,23 +921,142 @@ fn get_username_from_uid(_uid: u32) -> Result<String> {
     Ok("unknown".to_string())
 }
 
+/// Terminate a process the way [`ProcessManager::graceful_kill`] does, without
+/// needing NVML. Returns the name of the signal that ended the process.
+#[cfg(unix)]
+pub fn graceful_kill_pid(
+    pid: u32,
+    signal: KillSignal,
+    timeout_secs: u16,
+    force: bool,
+) -> Result<&'static str> {
+    let mut system = System::new();
+    terminate_process(
+        pid,
+        signal,
+        timeout_secs,
+        force,
+        |sig| send_signal(pid, sig),
+        || Ok(system.refresh_process(SysPid::from_u32(pid))),
+    )
+}
+
+/// Terminate a process without NVML (Windows stub)
+#[cfg(windows)]
+pub fn graceful_kill_pid(
+    _pid: u32,
+    _signal: KillSignal,
+    _timeout_secs: u16,
+    _force: bool,
+) -> Result<&'static str> {
+    Err(anyhow::anyhow!(
+        "Process termination not yet implemented for Windows"
+    ))
+}
+
+/// Send `signal`, wait up to `timeout_secs` for the process to exit and, with `force`,
+/// escalate to SIGKILL. Returns the name of the signal the process exited after.
+#[cfg(unix)]
+fn terminate_process(
+    pid: u32,
+    signal: KillSignal,
+    timeout_secs: u16,
+    force: bool,
+    mut send: impl FnMut(Signal) -> Result<()>,
+    mut is_running: impl FnMut() -> Result<bool>,
+) -> Result<&'static str> {
+    tracing::info!(pid, signal = signal.name(), "Sending signal");
+    send(signal.to_nix())?;
+
+    // Wait for the process to terminate
+    let timeout = Duration::from_secs(timeout_secs as u64);
+    let start = SystemTime::now();
+
+    while SystemTime::now().duration_since(start).unwrap_or_default() < timeout {
+        // Check if process still exists (with fresh data)
+        if !is_running()? {
+            tracing::info!(
+                pid,
+                signal = signal.name(),
+                duration_ms = start.elapsed().unwrap_or_default().as_millis() as u64,
+                "Process terminated"
+            );
+            return Ok(signal.name());
+        }
+
+        std::thread::sleep(Duration::from_millis(100));
+    }
+
+    // Process didn't terminate, escalate if force is enabled
+    if force {
+        tracing::warn!(
+            pid,
+            signal = signal.name(),
+            timeout_secs,
+            "Process did not terminate, escalating to SIGKILL"
+        );
+        send(Signal::SIGKILL)?;
+
+        // Wait a bit more for SIGKILL to take effect
+        std::thread::sleep(Duration::from_millis(500));
+
+        if !is_running()? {
+            tracing::info!(
+                pid,
+                signal = "SIGKILL",
+                duration_ms = start.elapsed().unwrap_or_default().as_millis() as u64,
+                "Process terminated"
+            );
+            Ok("SIGKILL")
+        } else {
+            Err(anyhow::anyhow!(
+                "Process {} still running after SIGKILL",
+                pid
+            ))
+        }
+    } else {
+        Err(anyhow::anyhow!(
+            "Process {} did not terminate within {} seconds after {}. Use --force to escalate to SIGKILL",
+            pid,
+            timeout_secs,
+            signal
+        ))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::nvml_api::NvmlApi;
+
+    /// Process manager over one mock GPU running `pids`
+    fn mock_process_manager(pids: &[u32]) -> ProcessManager {
+        use crate::vendor::{MockFixture, MockGpu, MockProcess, MockVendor};
+
+        let processes = pids
+            .iter()
+            .map(|&pid| MockProcess {
+                pid,
+                user: "alice".to_string(),
+                name: "python".to_string(),
+                used_mem_mb: 1024,
+                cmdline: None,
+            })
+            .collect();
+        let vendor = std::sync::Arc::new(MockVendor::new(MockFixture {
+            gpus: vec![MockGpu {
+                name: "Mock GPU".to_string(),
+                mem_total_mb: 8192,
+                processes,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }));
+        ProcessManager::with_gpu_manager(vendor.manager())
+    }
 
     #[test]
     fn test_process_info_creation() {
-        // Skip this test if NVML is not available
-        let nvml_api = match NvmlApi::new() {
-            Ok(api) => api,
-            Err(_) => {
-                // Skip test if NVML is not available
-                return;
-            }
-        };
-
-        let mut proc_mgr = ProcessManager::new(nvml_api);
+        let mut proc_mgr = mock_process_manager(&[]);
 
         // Test with a known process (init/systemd)
         if let Ok(info) = proc_mgr.get_process_info(1) {
@@ -322,19 +1067,432 @@ mod tests {
 
     #[test]
     fn test_process_validation() {
-        // Skip this test if NVML is not available
-        let nvml_api = match NvmlApi::new() {
-            Ok(api) => api,
-            Err(_) => {
-                // Skip test if NVML is not available
-                return;
-            }
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let pid = child.id();
+
+        let on_gpu = mock_process_manager(&[pid]);
+        assert!(on_gpu.is_process_using_gpu(pid).unwrap());
+        on_gpu.validate_process(pid, true).unwrap();
+        // Test validation of non-existent process
+        assert!(on_gpu.validate_process(999999, false).is_err());
+
+        let off_gpu = mock_process_manager(&[]);
+        off_gpu.validate_process(pid, false).unwrap();
+        let err = off_gpu.validate_process(pid, true).unwrap_err().to_string();
+        assert!(err.contains("not using any GPU"), "{}", err);
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_kill_invalidates_gpu_manager_cache() {
+        use crate::vendor::{MockCall, MockFixture, MockGpu, MockVendor};
+
+        let vendor = std::sync::Arc::new(MockVendor::new(MockFixture {
+            gpus: vec![MockGpu::default()],
+            ..Default::default()
+        }));
+        let gpu_manager = crate::vendor::GpuManager::with_vendor(vendor.clone())
+            .with_snapshot_cache_ttl(Duration::from_secs(60));
+        let mut proc_mgr = ProcessManager::with_gpu_manager(gpu_manager.clone());
+        gpu_manager.get_all_processes().unwrap();
+        gpu_manager.get_all_processes().unwrap();
+        assert_eq!(vendor.calls(0, MockCall::Processes), 1);
+
+        // Failed kills invalidate too
+        assert!(proc_mgr.graceful_kill(999999, 1, false).is_err());
+        gpu_manager.get_all_processes().unwrap();
+        assert_eq!(vendor.calls(0, MockCall::Processes), 2);
+
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let pid = child.id();
+        let reaper = std::thread::spawn(move || child.wait());
+        proc_mgr.graceful_kill(pid, 5, true).unwrap();
+        reaper.join().unwrap().unwrap();
+        gpu_manager.get_all_processes().unwrap();
+        assert_eq!(vendor.calls(0, MockCall::Processes), 3);
+    }
+
+    #[test]
+    fn test_parse_cmdline() {
+        assert_eq!(
+            parse_cmdline(b"python\0train.py\0--epochs\x0010\0").as_deref(),
+            Some("python train.py --epochs 10")
+        );
+        // Processes that rewrite their title may leave a single argument with spaces
+        assert_eq!(
+            parse_cmdline(b"ray::Worker train\0").as_deref(),
+            Some("ray::Worker train")
+        );
+        assert_eq!(parse_cmdline(b""), None);
+        assert_eq!(parse_cmdline(b"\0\0"), None);
+    }
+
+    #[test]
+    fn test_process_name_from_proc() {
+        // comm truncated at 15 bytes, argv[0] carries the full name
+        assert_eq!(
+            process_name_from_proc(
+                Some("tritonserver_wo\n"),
+                Some(b"/opt/bin/tritonserver_worker\0--model-repo\0/models\0")
+            )
+            .as_deref(),
+            Some("tritonserver_worker")
+        );
+        // Interpreters keep comm rather than the script's argv[0]
+        assert_eq!(
+            process_name_from_proc(Some("python3\n"), Some(b"python\0train.py\0")).as_deref(),
+            Some("python3")
+        );
+        // Kernel threads and zombies have an empty cmdline
+        assert_eq!(
+            process_name_from_proc(Some("kworker/0:1\n"), Some(b"")).as_deref(),
+            Some("kworker/0:1")
+        );
+        assert_eq!(
+            process_name_from_proc(None, Some(b"/usr/bin/ollama\0serve\0")).as_deref(),
+            Some("ollama")
+        );
+        assert_eq!(process_name_from_proc(Some("\n"), None), None);
+        assert_eq!(process_name_from_proc(None, None), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_process_name_and_owner_of_own_process() {
+        let pid = std::process::id();
+        let exe = std::env::args().next().unwrap();
+        let base = exe.rsplit('/').next().unwrap();
+        let name = get_process_name(pid).unwrap();
+        assert!(base.starts_with(&name), "{} vs {}", name, base);
+        assert!(get_process_owner(pid).is_some());
+        assert_eq!(get_process_name(u32::MAX), None);
+        assert_eq!(get_process_owner(u32::MAX), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_get_process_cmdline_reads_own_process() {
+        let cmdline = get_process_cmdline(std::process::id()).unwrap();
+        let exe = std::env::args().next().unwrap();
+        assert!(cmdline.starts_with(&exe));
+        assert_eq!(get_process_cmdline(u32::MAX), None);
+    }
+
+    #[test]
+    fn test_kill_signal_names_and_numbers() {
+        for (name, signal) in [
+            ("SIGTERM", KillSignal::Term),
+            ("int", KillSignal::Int),
+            ("SIGQUIT", KillSignal::Quit),
+            ("HUP", KillSignal::Hup),
+            ("2", KillSignal::Int),
+        ] {
+            assert_eq!(name.parse::<KillSignal>().unwrap(), signal);
+        }
+        assert_eq!(KillSignal::default(), KillSignal::Term);
+        assert_eq!(KillSignal::Quit.to_string(), "SIGQUIT");
+
+        let err = "SIGKILL".parse::<KillSignal>().unwrap_err().to_string();
+        assert!(err.contains("--force"));
+        assert!("9".parse::<KillSignal>().is_err());
+        assert!("SIGUSR1".parse::<KillSignal>().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_kill_signal_nix_numbers_match() {
+        for signal in [
+            KillSignal::Term,
+            KillSignal::Int,
+            KillSignal::Quit,
+            KillSignal::Hup,
+        ] {
+            let number = (signal.to_nix() as i32).to_string();
+            assert_eq!(number.parse::<KillSignal>().unwrap(), signal);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_terminate_process_sends_chosen_signal_first() {
+        let mut sent = Vec::new();
+        let ended_by = terminate_process(
+            42,
+            KillSignal::Int,
+            5,
+            false,
+            |sig| {
+                sent.push(sig);
+                Ok(())
+            },
+            || Ok(false),
+        )
+        .unwrap();
+        assert_eq!(sent, vec![Signal::SIGINT]);
+        assert_eq!(ended_by, "SIGINT");
+
+        // Still running after the timeout: escalate to SIGKILL only with force
+        let mut sent = Vec::new();
+        let mut checks = 0;
+        let ended_by = terminate_process(
+            42,
+            KillSignal::Hup,
+            0,
+            true,
+            |sig| {
+                sent.push(sig);
+                Ok(())
+            },
+            || {
+                checks += 1;
+                Ok(false)
+            },
+        )
+        .unwrap();
+        assert_eq!(sent, vec![Signal::SIGHUP, Signal::SIGKILL]);
+        assert_eq!(ended_by, "SIGKILL");
+        assert_eq!(checks, 1);
+
+        let mut sent = Vec::new();
+        let err = terminate_process(
+            42,
+            KillSignal::Quit,
+            0,
+            false,
+            |sig| {
+                sent.push(sig);
+                Ok(())
+            },
+            || Ok(true),
+        )
+        .unwrap_err();
+        assert_eq!(sent, vec![Signal::SIGQUIT]);
+        assert!(err.to_string().contains("after SIGQUIT"));
+    }
+
+    const DOCKER_ID: &str = "4f66ad9a0b2e8c1d3e5f7a9b1c3d5e7f9a1b3c5d7e9f1a3b5c7d9e1f3a5b7c9d";
+    const OTHER_ID: &str = "9a1b3c5d7e9f1a3b5c7d9e1f3a5b7c9d4f66ad9a0b2e8c1d3e5f7a9b1c3d5e7f";
+
+    fn status_with_nspid(nspid: &str) -> String {
+        format!(
+            "Name:\tpython\nState:\tS (sleeping)\nTgid:\t48213\nNgid:\t0\nPid:\t48213\nPPid:\t48190\nNSpid:\t{}\nNSpgid:\t48213\t1\n",
+            nspid
+        )
+    }
+
+    #[test]
+    fn test_parse_nspid() {
+        assert_eq!(parse_nspid(&status_with_nspid("48213\t7")), vec![48213, 7]);
+        assert_eq!(
+            parse_nspid(&status_with_nspid("48213\t912\t7")),
+            vec![48213, 912, 7]
+        );
+        assert_eq!(innermost_pid(&[48213, 912, 7]), Some(7));
+        // Host namespace only, or a kernel without NSpid
+        assert_eq!(parse_nspid(&status_with_nspid("48213")), vec![48213]);
+        assert_eq!(innermost_pid(&[48213]), None);
+        assert!(parse_nspid("Name:\tpython\nPid:\t48213\n").is_empty());
+        assert_eq!(innermost_pid(&[]), None);
+    }
+
+    #[test]
+    fn test_parse_cgroup_container_id() {
+        for cgroup in [
+            format!("0::/system.slice/docker-{}.scope\n", DOCKER_ID),
+            format!("12:memory:/docker/{}\n11:cpu:/docker/{}\n", DOCKER_ID, DOCKER_ID),
+            format!(
+                "0::/kubepods.slice/kubepods-besteffort.slice/kubepods-besteffort-pod1a2b.slice/cri-containerd-{}.scope\n",
+                DOCKER_ID
+            ),
+            format!("0::/machine.slice/libpod-{}.scope/container\n", DOCKER_ID),
+            format!("0::/kubepods/burstable/pod7c1e/crio-{}.scope\n", DOCKER_ID.to_uppercase()),
+        ] {
+            assert_eq!(
+                parse_cgroup_container_id(&cgroup).as_deref(),
+                Some(DOCKER_ID),
+                "{}",
+                cgroup
+            );
+        }
+        assert_eq!(
+            parse_cgroup_container_id("0::/user.slice/session-3.scope\n"),
+            None
+        );
+        assert_eq!(parse_cgroup_container_id(""), None);
+    }
+
+    #[test]
+    fn test_find_host_pid_in_container() {
+        let in_docker = format!("0::/system.slice/docker-{}.scope\n", DOCKER_ID);
+        let in_other = format!("0::/system.slice/docker-{}.scope\n", OTHER_ID);
+        let on_host = "0::/user.slice/session-3.scope\n".to_string();
+        let processes = [
+            (48190, in_docker.clone(), status_with_nspid("48190\t1")),
+            (48213, in_docker.clone(), status_with_nspid("48213\t7")),
+            (51002, in_other.clone(), status_with_nspid("51002\t7")),
+            (7, on_host.clone(), status_with_nspid("7")),
+        ];
+        let candidates = || {
+            processes
+                .iter()
+                .map(|(pid, cgroup, status)| (*pid, cgroup.as_str(), status.as_str()))
         };
 
-        let proc_mgr = ProcessManager::new(nvml_api);
+        assert_eq!(find_host_pid(candidates(), DOCKER_ID, 7).unwrap(), 48213);
+        assert_eq!(
+            find_host_pid(candidates(), &DOCKER_ID[..12], 1).unwrap(),
+            48190
+        );
+        assert_eq!(find_host_pid(candidates(), "9A1B3C5D", 7).unwrap(), 51002);
 
-        // Test validation of non-existent process
-        let result = proc_mgr.validate_process(999999, false);
-        assert!(result.is_err());
+        let err = find_host_pid(candidates(), DOCKER_ID, 99)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("no process with PID 99"), "{}", err);
+        let err = find_host_pid(candidates(), "ffff", 7)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("no running container"), "{}", err);
+        // An empty prefix matches every container
+        let err = find_host_pid(candidates(), "", 7).unwrap_err().to_string();
+        assert!(err.contains("matches 2 containers"), "{}", err);
+    }
+
+    #[test]
+    fn test_pid_target_parsing() {
+        assert_eq!(
+            "12345".parse::<PidTarget>().unwrap(),
+            PidTarget::Host(12345)
+        );
+        let target: PidTarget = "container:trainer:42".parse().unwrap();
+        assert_eq!(
+            target,
+            PidTarget::Container {
+                container: "trainer".to_string(),
+                pid: 42
+            }
+        );
+        assert_eq!(target.pid(), 42);
+        assert_eq!(target.to_string(), "container:trainer:42");
+
+        for bad in [
+            "",
+            "abc",
+            "container:42",
+            "container::42",
+            "container:trainer:x",
+        ] {
+            assert!(bad.parse::<PidTarget>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_resolve_container_id_by_name() {
+        struct Engines;
+        impl crate::command::CommandRunner for Engines {
+            fn run(&self, program: &str, args: &[&str]) -> Result<crate::command::CommandOutput> {
+                let known = program == "podman" && args.last() == Some(&"trainer");
+                Ok(crate::command::CommandOutput {
+                    success: known,
+                    stdout: if known {
+                        format!("{}\n", DOCKER_ID)
+                    } else {
+                        String::new()
+                    },
+                    stderr: String::new(),
+                    code: None,
+                })
+            }
+        }
+
+        assert_eq!(resolve_container_id("trainer", &Engines), DOCKER_ID);
+        assert_eq!(resolve_container_id("4f66ad9a", &Engines), "4f66ad9a");
+    }
+
+    #[test]
+    fn test_self_guard_blocks_self_and_parent_by_default() {
+        let guard = SelfGuard::new(200, Some(60));
+        assert_eq!(guard.pids(), vec![200, 60]);
+        for pid in [200, 60] {
+            let err = guard.check(pid).unwrap_err().to_string();
+            assert!(err.contains("--allow-self"), "{}", err);
+        }
+        assert!(guard.check(500).is_ok());
+
+        let current = SelfGuard::current();
+        assert!(current.check(std::process::id()).is_err());
+
+        // The override lets both through
+        let allowed = guard.allowing(true);
+        assert!(allowed.check(200).is_ok());
+        assert!(allowed.check(60).is_ok());
+        assert_eq!(allowed.reason(200), Some("gpukill's own process"));
+    }
+
+    #[test]
+    fn test_kill_permission_denied_message() {
+        use crate::privileges::Privileges;
+
+        let alice = Privileges::new("alice", Some(1000), Some(0));
+        let error = KillPermissionDenied::new(1, "SIGTERM", Some("root".to_string()), &alice);
+        assert_eq!(
+            error.to_string(),
+            "Permission denied: cannot send SIGTERM to PID 1 (owned by root) as alice; \
+             only its owner or root can signal it, re-run with sudo"
+        );
+
+        let root = Privileges::new("root", Some(0), None);
+        let error = KillPermissionDenied::new(1, "SIGKILL", None, &root);
+        let message = error.to_string();
+        assert!(message.starts_with("Permission denied"), "{}", message);
+        assert!(message.contains("even as root"), "{}", message);
+        assert!(!message.contains("sudo"), "{}", message);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_signal_error_maps_eperm() {
+        use nix::errno::Errno;
+
+        let error = signal_error(1, Signal::SIGTERM, Errno::EPERM);
+        let denied = kill_permission_denied(&error).expect("EPERM is typed");
+        assert_eq!((denied.pid, denied.signal), (1, "SIGTERM"));
+        // Classified as a permission error for the exit code
+        assert!(error.to_string().contains("Permission denied"), "{}", error);
+
+        let error = signal_error(1, Signal::SIGTERM, Errno::ESRCH);
+        assert!(kill_permission_denied(&error).is_none());
+        assert!(
+            error.to_string().starts_with("Failed to send SIGTERM"),
+            "{}",
+            error
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_signal_unkillable_pid_without_privileges() {
+        // Only meaningful, and only safe, when the kernel is certain to refuse:
+        // PID 1 belongs to another user and we cannot signal other users
+        let privileges = crate::privileges::Privileges::current();
+        let owner = get_process_owner(1);
+        if privileges.can_signal_any_process() || owner.as_deref() == Some(&privileges.user) {
+            return;
+        }
+
+        let error = send_signal(1, Signal::SIGTERM).unwrap_err();
+        let denied = kill_permission_denied(&error).expect("EPERM is typed");
+        assert_eq!(denied.pid, 1);
+        assert_eq!(denied.owner, owner);
+        assert!(error.to_string().contains("re-run with sudo"), "{}", error);
     }
 }
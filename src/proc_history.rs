@@ -0,0 +1,328 @@
+//! Short per-process usage history for `--watch`: the last few samples of each
+//! (GPU, PID), drawn as sparklines next to the process table, and the one-line
+//! samples printed by `--watch --follow-pid`.
+//!
+//! Processes report memory but not utilization, so a process's utilization sample
+//! is that of the GPU it runs on, as in the audit log.
+
+use crate::nvml_api::{GpuProc, Snapshot};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// Samples kept per process, which is also the width of its sparklines
+pub const DEFAULT_HISTORY_LEN: usize = 20;
+
+/// Bar heights from lowest to highest
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One process's usage at one refresh
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProcessSample {
+    pub used_mem_mb: u32,
+    /// Utilization of the GPU the process runs on
+    pub gpu_util_pct: f32,
+}
+
+/// Sparklines for one process, oldest sample first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessTrend {
+    /// Memory, scaled to the highest sample in the history
+    pub memory: String,
+    /// GPU utilization, scaled to 100%
+    pub utilization: String,
+}
+
+/// Samples of one process. A refresh the process was missing from is kept as a
+/// gap (None) so the sparkline does not hide it.
+#[derive(Debug)]
+struct ProcessSeries {
+    proc_name: String,
+    start_unix_secs: Option<u64>,
+    samples: VecDeque<Option<ProcessSample>>,
+}
+
+impl ProcessSeries {
+    /// Whether `proc` is the process this series was recorded for, rather than a
+    /// new process that was given the same PID
+    fn is_same_process(&self, proc: &GpuProc) -> bool {
+        self.proc_name == proc.proc_name
+            && match (self.start_unix_secs, proc.start_unix_secs) {
+                (Some(before), Some(now)) => before == now,
+                _ => true,
+            }
+    }
+}
+
+/// Ring buffer of the last `capacity` samples of every process seen in `--watch`
+#[derive(Debug)]
+pub struct ProcessHistory {
+    capacity: usize,
+    series: HashMap<(u16, u32), ProcessSeries>,
+}
+
+impl Default for ProcessHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_LEN)
+    }
+}
+
+impl ProcessHistory {
+    /// Keep up to `capacity` samples per process (at least one)
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            series: HashMap::new(),
+        }
+    }
+
+    /// Add a sample for every process in `snapshot`. Processes missing from it get a
+    /// gap, and are forgotten once their history holds nothing but gaps. A PID that
+    /// comes back as a different process starts a new history.
+    pub fn record(&mut self, snapshot: &Snapshot) {
+        let utilization: HashMap<u16, f32> = snapshot
+            .gpus
+            .iter()
+            .map(|gpu| (gpu.gpu_index, gpu.util_pct))
+            .collect();
+        let mut seen = Vec::with_capacity(snapshot.procs.len());
+
+        for proc in &snapshot.procs {
+            let key = (proc.gpu_index, proc.pid);
+            let sample = ProcessSample {
+                used_mem_mb: proc.used_mem_mb,
+                gpu_util_pct: utilization.get(&proc.gpu_index).copied().unwrap_or(0.0),
+            };
+            let series = self.series.entry(key).or_insert_with(|| ProcessSeries {
+                proc_name: proc.proc_name.clone(),
+                start_unix_secs: proc.start_unix_secs,
+                samples: VecDeque::with_capacity(self.capacity),
+            });
+            if !series.is_same_process(proc) {
+                *series = ProcessSeries {
+                    proc_name: proc.proc_name.clone(),
+                    start_unix_secs: proc.start_unix_secs,
+                    samples: VecDeque::with_capacity(self.capacity),
+                };
+            }
+            series.start_unix_secs = series.start_unix_secs.or(proc.start_unix_secs);
+            push_bounded(&mut series.samples, Some(sample), self.capacity);
+            seen.push(key);
+        }
+
+        let capacity = self.capacity;
+        self.series.retain(|key, series| {
+            if !seen.contains(key) {
+                push_bounded(&mut series.samples, None, capacity);
+            }
+            series.samples.iter().any(Option::is_some)
+        });
+    }
+
+    /// Samples of one process, oldest first, with None for refreshes it was missing from
+    #[allow(dead_code)]
+    pub fn samples(&self, gpu_index: u16, pid: u32) -> Vec<Option<ProcessSample>> {
+        self.series
+            .get(&(gpu_index, pid))
+            .map(|series| series.samples.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Sparklines for every process with a history
+    pub fn trends(&self) -> BTreeMap<(u16, u32), ProcessTrend> {
+        self.series
+            .iter()
+            .map(|(key, series)| {
+                let memory: Vec<Option<f64>> = series
+                    .samples
+                    .iter()
+                    .map(|s| s.map(|s| s.used_mem_mb as f64))
+                    .collect();
+                let peak = memory.iter().flatten().copied().fold(0.0, f64::max);
+                let utilization: Vec<Option<f64>> = series
+                    .samples
+                    .iter()
+                    .map(|s| s.map(|s| s.gpu_util_pct as f64))
+                    .collect();
+                (
+                    *key,
+                    ProcessTrend {
+                        memory: sparkline(&memory, peak),
+                        utilization: sparkline(&utilization, 100.0),
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+fn push_bounded<T>(buffer: &mut VecDeque<T>, value: T, capacity: usize) {
+    if buffer.len() == capacity {
+        buffer.pop_front();
+    }
+    buffer.push_back(value);
+}
+
+/// Draw `values` as bars from 0 to `max`; gaps are drawn as spaces and values
+/// above `max` as full bars
+pub fn sparkline(values: &[Option<f64>], max: f64) -> String {
+    values
+        .iter()
+        .map(|value| match value {
+            None => ' ',
+            Some(_) if max <= 0.0 => SPARK_LEVELS[0],
+            Some(value) => {
+                let level = (value / max * (SPARK_LEVELS.len() - 1) as f64).round();
+                SPARK_LEVELS[level.clamp(0.0, (SPARK_LEVELS.len() - 1) as f64) as usize]
+            }
+        })
+        .collect()
+}
+
+/// `--follow-pid` lines for one refresh: one per GPU the process uses, or a single
+/// "not running" line, each starting with the snapshot's timestamp
+pub fn follow_lines(snapshot: &Snapshot, pid: u32) -> Vec<String> {
+    let lines: Vec<String> = snapshot
+        .procs
+        .iter()
+        .filter(|proc| proc.pid == pid)
+        .map(|proc| {
+            let util = snapshot
+                .gpus
+                .iter()
+                .find(|gpu| gpu.gpu_index == proc.gpu_index)
+                .map(|gpu| format!("{:.0}%", gpu.util_pct))
+                .unwrap_or_else(|| "-".to_string());
+            format!(
+                "{} pid={} gpu={} mem={}MB util={}",
+                snapshot.ts, pid, proc.gpu_index, proc.used_mem_mb, util
+            )
+        })
+        .collect();
+    if lines.is_empty() {
+        return vec![format!("{} pid={} not running", snapshot.ts, pid)];
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nvml_api::fixtures::{gpu, proc};
+
+    fn snapshot(util_pct: f32, procs: Vec<GpuProc>) -> Snapshot {
+        Snapshot {
+            host: "node1".to_string(),
+            ts: "2024-06-01T12:00:00Z".to_string(),
+            gpus: vec![gpu(0, util_pct, 0)],
+            procs,
+            degraded: false,
+            gpu_status: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn memory(samples: &[Option<ProcessSample>]) -> Vec<Option<u32>> {
+        samples.iter().map(|s| s.map(|s| s.used_mem_mb)).collect()
+    }
+
+    #[test]
+    fn test_history_wraps_around_at_capacity() {
+        let mut history = ProcessHistory::new(3);
+        for mem in [100, 200, 300, 400, 500] {
+            history.record(&snapshot(50.0, vec![proc(0, 42, "alice", "train", mem)]));
+        }
+        assert_eq!(
+            memory(&history.samples(0, 42)),
+            vec![Some(300), Some(400), Some(500)]
+        );
+        assert_eq!(history.samples(0, 42)[0].unwrap().gpu_util_pct, 50.0);
+    }
+
+    #[test]
+    fn test_process_that_reappears_keeps_its_history_with_a_gap() {
+        let mut history = ProcessHistory::new(4);
+        history.record(&snapshot(10.0, vec![proc(0, 42, "alice", "train", 100)]));
+        history.record(&snapshot(10.0, Vec::new()));
+        history.record(&snapshot(10.0, vec![proc(0, 42, "alice", "train", 300)]));
+
+        assert_eq!(
+            memory(&history.samples(0, 42)),
+            vec![Some(100), None, Some(300)]
+        );
+        assert_eq!(history.trends()[&(0, 42)].memory, "▃ █");
+    }
+
+    #[test]
+    fn test_reused_pid_starts_a_new_history() {
+        let mut history = ProcessHistory::new(4);
+        let mut first = proc(0, 42, "alice", "train", 100);
+        first.start_unix_secs = Some(1_000);
+        history.record(&snapshot(10.0, vec![first.clone()]));
+        history.record(&snapshot(10.0, Vec::new()));
+
+        // Same PID and name, but started later: a different process
+        let mut second = first.clone();
+        second.start_unix_secs = Some(2_000);
+        second.used_mem_mb = 500;
+        history.record(&snapshot(10.0, vec![second]));
+        assert_eq!(memory(&history.samples(0, 42)), vec![Some(500)]);
+
+        // Same PID with another name is also a new process
+        history.record(&snapshot(10.0, vec![proc(0, 42, "alice", "eval", 700)]));
+        assert_eq!(memory(&history.samples(0, 42)), vec![Some(700)]);
+    }
+
+    #[test]
+    fn test_gone_process_is_forgotten_after_a_full_buffer_of_gaps() {
+        let mut history = ProcessHistory::new(2);
+        history.record(&snapshot(10.0, vec![proc(0, 42, "alice", "train", 100)]));
+        history.record(&snapshot(10.0, Vec::new()));
+        assert_eq!(history.samples(0, 42).len(), 2);
+        history.record(&snapshot(10.0, Vec::new()));
+        assert!(history.samples(0, 42).is_empty());
+        assert!(history.trends().is_empty());
+    }
+
+    #[test]
+    fn test_same_pid_on_two_gpus_is_tracked_separately() {
+        let mut snap = snapshot(
+            10.0,
+            vec![
+                proc(0, 42, "alice", "train", 100),
+                proc(1, 42, "alice", "train", 900),
+            ],
+        );
+        snap.gpus.push(gpu(1, 90.0, 0));
+        let mut history = ProcessHistory::default();
+        history.record(&snap);
+
+        assert_eq!(memory(&history.samples(0, 42)), vec![Some(100)]);
+        assert_eq!(history.samples(1, 42)[0].unwrap().gpu_util_pct, 90.0);
+    }
+
+    #[test]
+    fn test_sparkline_levels() {
+        let values = [Some(0.0), Some(50.0), None, Some(100.0), Some(250.0)];
+        assert_eq!(sparkline(&values, 100.0), "▁▅ ██");
+        assert_eq!(sparkline(&[Some(0.0), Some(0.0)], 0.0), "▁▁");
+        assert_eq!(sparkline(&[], 100.0), "");
+    }
+
+    #[test]
+    fn test_follow_lines() {
+        let snap = snapshot(
+            87.4,
+            vec![
+                proc(0, 42, "alice", "train", 2048),
+                proc(0, 7, "alice", "other", 1),
+            ],
+        );
+        assert_eq!(
+            follow_lines(&snap, 42),
+            vec!["2024-06-01T12:00:00Z pid=42 gpu=0 mem=2048MB util=87%"]
+        );
+        assert_eq!(
+            follow_lines(&snap, 99),
+            vec!["2024-06-01T12:00:00Z pid=99 not running"]
+        );
+    }
+}
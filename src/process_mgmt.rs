@@ -1,7 +1,9 @@
 use crate::nvml_api::GpuProc;
-use crate::proc::ProcessManager;
+use crate::proc::{ProcessManager, SelfGuard};
+use crate::report::{KillOutcome, KillStatus, NotFound};
 use anyhow::Result;
 use regex::Regex;
+use serde::Serialize;
 use std::collections::{HashMap, HashSet};
 use sysinfo::{Pid as SysPid, System};
 
@@ -9,6 +11,10 @@ use sysinfo::{Pid as SysPid, System};
 pub struct EnhancedProcessManager {
     pub process_manager: ProcessManager,
     system: System,
+    /// Names protected on top of the defaults; None leaves kills unchecked
+    protected_names: Option<Vec<String>>,
+    /// Let kills through that only the protected name list would refuse
+    override_protection: bool,
 }
 
 #[allow(dead_code)]
@@ -17,9 +23,67 @@ impl EnhancedProcessManager {
         Self {
             process_manager,
             system: System::new_all(),
+            protected_names: None,
+            override_protection: false,
         }
     }
 
+    /// Refuse to kill protected processes: the defaults plus `extra_names` (e.g. from
+    /// the config file). With `override_protection` only init, kernel threads and
+    /// gpukill itself are refused.
+    pub fn with_protection(mut self, extra_names: &[String], override_protection: bool) -> Self {
+        self.protected_names = Some(extra_names.to_vec());
+        self.override_protection = override_protection;
+        self
+    }
+
+    /// Protection against the current process table, if kills are checked
+    fn protection(&mut self) -> Option<KillProtection> {
+        let names = self.protected_names.as_ref()?;
+        self.system.refresh_processes();
+        Some(
+            KillProtection::new(ProcessTable::from_system(&self.system), names)
+                .allowing_self(&self.process_manager.self_guard()),
+        )
+    }
+
+    /// Split `processes` into those that may be killed and those refused as protected
+    pub fn screen_protected(
+        &mut self,
+        processes: Vec<GpuProc>,
+    ) -> (Vec<GpuProc>, Vec<SkippedProcess>) {
+        match self.protection() {
+            Some(protection) => protection.partition(processes, self.override_protection),
+            None => (processes, Vec::new()),
+        }
+    }
+
+    /// Kill a single process unless it is protected. Returns the name of the
+    /// signal that ended the process.
+    pub fn kill_process(
+        &mut self,
+        pid: u32,
+        timeout_secs: u16,
+        force: bool,
+    ) -> Result<&'static str> {
+        if let Some(protection) = self.protection() {
+            if let Some(reason) = protection.skip_reason(pid, "", self.override_protection) {
+                let hint = if protection.skip_reason(pid, "", true).is_none() {
+                    "; pass --allow-protected to kill it anyway"
+                } else {
+                    ""
+                };
+                return Err(anyhow::anyhow!(
+                    "Refusing to kill PID {}: {}{}",
+                    pid,
+                    reason,
+                    hint
+                ));
+            }
+        }
+        self.process_manager.graceful_kill(pid, timeout_secs, force)
+    }
+
     /// Filter processes by name pattern (supports regex)
     pub fn filter_processes_by_name(
         &mut self,
@@ -39,6 +103,23 @@ impl EnhancedProcessManager {
         Ok(filtered)
     }
 
+    /// Filter processes by command line pattern (supports regex). Processes whose
+    /// command line could not be read are matched on their name.
+    pub fn filter_processes_by_cmdline(
+        &mut self,
+        processes: &[GpuProc],
+        pattern: &str,
+    ) -> Result<Vec<GpuProc>> {
+        let regex = Regex::new(pattern)
+            .map_err(|e| anyhow::anyhow!("Invalid regex pattern '{}': {}", pattern, e))?;
+
+        Ok(processes
+            .iter()
+            .filter(|proc| regex.is_match(proc.command()))
+            .cloned()
+            .collect())
+    }
+
     /// Filter processes by user
     pub fn filter_processes_by_user(
         &mut self,
@@ -71,27 +152,10 @@ impl EnhancedProcessManager {
             .collect()
     }
 
-    /// Get process tree for a given PID
+    /// Get process tree for a given PID (the root and all of its descendants)
     pub fn get_process_tree(&mut self, root_pid: u32) -> Result<Vec<u32>> {
         self.system.refresh_processes();
-
-        let mut pids = Vec::new();
-        let mut to_process = vec![root_pid];
-
-        while let Some(pid) = to_process.pop() {
-            pids.push(pid);
-
-            // Find child processes
-            for process in self.system.processes().values() {
-                if let Some(parent) = process.parent() {
-                    if parent.as_u32() == pid {
-                        to_process.push(process.pid().as_u32());
-                    }
-                }
-            }
-        }
-
-        Ok(pids)
+        Ok(ProcessTable::from_system(&self.system).descendants(root_pid))
     }
 
     /// Kill a process and its children
@@ -101,73 +165,91 @@ impl EnhancedProcessManager {
         timeout_secs: u16,
         force: bool,
     ) -> Result<()> {
-        let pids = self.get_process_tree(root_pid)?;
-
-        tracing::info!("Killing process tree: {:?}", pids);
-
-        // Kill children first, then parent
-        for pid in pids.iter().rev() {
-            if let Err(e) = self
-                .process_manager
-                .graceful_kill(*pid, timeout_secs, force)
-            {
-                tracing::warn!("Failed to kill process {}: {}", pid, e);
-            }
-        }
+        self.system.refresh_processes();
+        let plan = ProcessTable::from_system(&self.system).kill_plan(&[root_pid], false, true);
 
+        tracing::info!(pid = root_pid, order = ?plan.order, "Killing process tree");
+        self.kill_pids(&plan.order, timeout_secs, force)?;
         Ok(())
     }
 
-    /// Batch kill processes. Deduplicates by PID so a process using multiple GPUs
-    /// is only killed once (otherwise the first kill succeeds and later attempts fail with ESRCH).
-    pub fn batch_kill_processes(
-        &mut self,
-        processes: &[GpuProc],
-        timeout_secs: u16,
-        force: bool,
-    ) -> Result<Vec<u32>> {
+    /// Kill PIDs in the given order (callers pass children first). Processes that
+    /// have already exited, e.g. because their parent reaped them, count as killed.
+    pub fn kill_pids(&mut self, pids: &[u32], timeout_secs: u16, force: bool) -> Result<Vec<u32>> {
         let mut killed_pids = Vec::new();
         let mut failed_pids = Vec::new();
-        let mut seen_pids = HashSet::new();
-        for proc in processes {
-            if !seen_pids.insert(proc.pid) {
+        let mut permission_denied = Vec::new();
+
+        for &pid in pids {
+            if !self.system.refresh_process(SysPid::from_u32(pid)) {
+                tracing::debug!(pid, "Process already exited");
+                killed_pids.push(pid);
                 continue;
             }
-            match self
-                .process_manager
-                .graceful_kill(proc.pid, timeout_secs, force)
-            {
-                Ok(()) => {
-                    killed_pids.push(proc.pid);
-                    tracing::info!(
-                        "Successfully killed process {} ({})",
-                        proc.pid,
-                        proc.proc_name
-                    );
+
+            match self.process_manager.graceful_kill(pid, timeout_secs, force) {
+                Ok(_) => killed_pids.push(pid),
+                Err(e) if !self.system.refresh_process(SysPid::from_u32(pid)) => {
+                    tracing::debug!(pid, error = %e, "Process exited while being killed");
+                    killed_pids.push(pid);
                 }
                 Err(e) => {
-                    failed_pids.push(proc.pid);
-                    tracing::warn!(
-                        "Failed to kill process {} ({}): {}",
-                        proc.pid,
-                        proc.proc_name,
-                        e
-                    );
+                    tracing::warn!(pid, error = %e, "Failed to kill process");
+                    if crate::proc::kill_permission_denied(&e).is_some() {
+                        permission_denied.push(pid);
+                    }
+                    failed_pids.push(pid);
                 }
             }
         }
 
         if !failed_pids.is_empty() {
-            return Err(anyhow::anyhow!(
-                "Failed to kill {} processes: {:?}",
-                failed_pids.len(),
-                failed_pids
-            ));
+            return Err(anyhow::anyhow!(kill_failure_message(
+                &failed_pids,
+                &permission_denied
+            )));
         }
 
         Ok(killed_pids)
     }
 
+    /// Batch kill processes. Deduplicates by PID so a process using multiple GPUs
+    /// is only killed once (otherwise the first kill succeeds and later attempts fail with ESRCH).
+    /// Protected processes are skipped with a warning. Nothing is killed if the
+    /// batch includes gpukill itself or its parent, unless the self guard allows it.
+    /// Kills that fail do not fail the batch: each candidate gets an outcome saying
+    /// whether it was killed, the signal that ended it or why it was left running.
+    pub fn batch_kill_processes(
+        &mut self,
+        processes: &[GpuProc],
+        timeout_secs: u16,
+        force: bool,
+    ) -> Result<Vec<KillOutcome>> {
+        check_not_self(processes, &self.process_manager.self_guard())?;
+        Ok(self.batch_kill(processes, timeout_secs, force).outcomes())
+    }
+
+    /// Batch kill processes like `batch_kill_processes`, keeping the processes
+    /// themselves and the PIDs refused for lack of privileges
+    pub fn batch_kill(
+        &mut self,
+        processes: &[GpuProc],
+        timeout_secs: u16,
+        force: bool,
+    ) -> BatchKillOutcome {
+        let protection = self.protection();
+        let override_protection = self.override_protection;
+        let self_guard = self.process_manager.self_guard();
+        let process_manager = &mut self.process_manager;
+        kill_unprotected(
+            processes,
+            &self_guard,
+            protection.as_ref(),
+            override_protection,
+            |pid| process_manager.graceful_kill(pid, timeout_secs, force),
+        )
+    }
+
     /// Detect if a process is running in a container
     pub fn detect_container(&mut self, pid: u32) -> Result<Option<String>> {
         self.system.refresh_processes();
@@ -176,7 +258,7 @@ impl EnhancedProcessManager {
         let process = self
             .system
             .process(sys_pid)
-            .ok_or_else(|| anyhow::anyhow!("Process {} not found", pid))?;
+            .ok_or_else(|| NotFound(format!("Process {} not found", pid)))?;
 
         // Check for common container indicators
         let cmdline = process.cmd().join(" ");
@@ -261,6 +343,34 @@ impl EnhancedProcessManager {
     }
 }
 
+/// Processes a `--filter`/`--filter-user` kill targets: those whose name (or command
+/// line, with `match_cmdline`) matches `pattern` and that belong to `user`. Leaving
+/// either out drops that constraint.
+pub fn filter_kill_targets(
+    processes: &[GpuProc],
+    pattern: Option<&str>,
+    match_cmdline: bool,
+    user: Option<&str>,
+) -> Result<Vec<GpuProc>> {
+    let regex = pattern
+        .map(|pattern| {
+            Regex::new(pattern)
+                .map_err(|e| anyhow::anyhow!("Invalid regex pattern '{}': {}", pattern, e))
+        })
+        .transpose()?;
+
+    Ok(processes
+        .iter()
+        .filter(|proc| match &regex {
+            Some(regex) if match_cmdline => regex.is_match(proc.command()),
+            Some(regex) => regex.is_match(&proc.proc_name),
+            None => true,
+        })
+        .filter(|proc| user.is_none_or(|user| proc.user == user))
+        .cloned()
+        .collect())
+}
+
 /// Process statistics
 #[derive(Debug, Default)]
 pub struct ProcessStats {
@@ -308,89 +418,1055 @@ impl std::fmt::Display for ProcessStats {
     }
 }
 
+/// Process names that end an upward walk: init systems, login/session managers,
+/// terminal multiplexers, container runtimes and schedulers are never part of a job
+const PROTECTED_ANCESTORS: &[&str] = &[
+    "systemd",
+    "init",
+    "launchd",
+    "sshd",
+    "login",
+    "su",
+    "sudo",
+    "tmux",
+    "screen",
+    "containerd",
+    "containerd-shim",
+    "dockerd",
+    "docker-init",
+    "conmon",
+    "tini",
+    "kubelet",
+    "slurmd",
+    "slurmstepd",
+    "cron",
+    "supervisord",
+];
+
+/// A process in a point-in-time process table
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProcessNode {
+    pub pid: u32,
+    pub ppid: Option<u32>,
+    pub name: String,
+    /// Full command line, or empty if unavailable
+    pub cmd: String,
+    /// Whether the process leads its session (e.g. a login shell)
+    pub session_leader: bool,
+}
+
+impl ProcessNode {
+    /// Command line if known, otherwise the process name
+    pub fn command(&self) -> &str {
+        if self.cmd.is_empty() {
+            &self.name
+        } else {
+            &self.cmd
+        }
+    }
+}
+
+/// Parent and top-level launcher of a process
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProcessAncestry {
+    pub pid: u32,
+    pub ppid: Option<u32>,
+    /// Highest ancestor that is still part of the job (may be the process itself)
+    pub ancestor_pid: u32,
+    pub ancestor_cmd: String,
+}
+
+/// Processes to terminate for a tree kill
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KillPlan {
+    /// Roots of the trees being killed
+    pub roots: Vec<u32>,
+    /// PIDs to terminate, children before their parents
+    pub order: Vec<u32>,
+    /// PIDs inside the trees that were left alone, with the reason
+    pub protected: Vec<(u32, String)>,
+}
+
+/// Snapshot of the process table used to build process trees
+#[derive(Debug, Clone, Default)]
+pub struct ProcessTable {
+    nodes: HashMap<u32, ProcessNode>,
+    children: HashMap<u32, Vec<u32>>,
+    /// gpukill itself and its ancestors, which must never be killed
+    guarded: HashSet<u32>,
+}
+
+impl ProcessTable {
+    /// Build a table from a list of nodes; `self_pid` and its ancestors are never killed
+    pub fn from_nodes(nodes: Vec<ProcessNode>, self_pid: Option<u32>) -> Self {
+        let nodes: HashMap<u32, ProcessNode> = nodes.into_iter().map(|n| (n.pid, n)).collect();
+
+        let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+        for node in nodes.values() {
+            if let Some(ppid) = node.ppid {
+                if ppid != node.pid {
+                    children.entry(ppid).or_default().push(node.pid);
+                }
+            }
+        }
+        for pids in children.values_mut() {
+            pids.sort_unstable();
+        }
+
+        let mut table = Self {
+            nodes,
+            children,
+            guarded: HashSet::new(),
+        };
+        if let Some(pid) = self_pid {
+            table.guarded = table.ancestors(pid).into_iter().collect();
+            table.guarded.insert(pid);
+        }
+        table
+    }
+
+    /// Build a table from sysinfo's current process list
+    pub fn from_system(system: &System) -> Self {
+        let nodes = system
+            .processes()
+            .values()
+            .map(|process| {
+                let pid = process.pid().as_u32();
+                ProcessNode {
+                    pid,
+                    ppid: process.parent().map(|p| p.as_u32()),
+                    name: process.name().to_string(),
+                    cmd: process.cmd().join(" "),
+                    session_leader: process.session_id().map(|s| s.as_u32()) == Some(pid),
+                }
+            })
+            .collect();
+        Self::from_nodes(nodes, Some(std::process::id()))
+    }
+
+    /// Take a fresh snapshot of the system process table
+    pub fn snapshot() -> Self {
+        let mut system = System::new();
+        system.refresh_processes();
+        Self::from_system(&system)
+    }
+
+    /// Look up a process
+    pub fn get(&self, pid: u32) -> Option<&ProcessNode> {
+        self.nodes.get(&pid)
+    }
+
+    /// Direct children of a process, sorted by PID
+    pub fn children(&self, pid: u32) -> &[u32] {
+        self.children.get(&pid).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// Ancestors of a process, nearest first (stops on cycles and unknown parents)
+    fn ancestors(&self, pid: u32) -> Vec<u32> {
+        let mut ancestors = Vec::new();
+        let mut seen = HashSet::from([pid]);
+        let mut current = pid;
+        while let Some(ppid) = self.nodes.get(&current).and_then(|n| n.ppid) {
+            if !seen.insert(ppid) || !self.nodes.contains_key(&ppid) {
+                break;
+            }
+            ancestors.push(ppid);
+            current = ppid;
+        }
+        ancestors
+    }
+
+    /// Why a process must never be killed as part of a tree, if it must not
+    fn kill_guard(&self, pid: u32) -> Option<String> {
+        if pid <= 1 {
+            Some("init process".to_string())
+        } else if self.guarded.contains(&pid) {
+            Some("gpukill or one of its parents".to_string())
+        } else {
+            None
+        }
+    }
+
+    /// Why an upward walk must stop before this process, if it must
+    fn ancestor_guard(&self, pid: u32) -> Option<String> {
+        if let Some(reason) = self.kill_guard(pid) {
+            return Some(reason);
+        }
+        let node = self.nodes.get(&pid)?;
+        if node.session_leader {
+            return Some(format!("session leader {}", node.name));
+        }
+        let name = node.name.as_str();
+        PROTECTED_ANCESTORS
+            .iter()
+            .any(|p| name == *p || name.starts_with(&format!("{}-", p)))
+            .then(|| format!("system process {}", name))
+    }
+
+    /// Highest ancestor of `pid` that is still part of the same job. The walk stops
+    /// below PID 1, session leaders, protected system processes and gpukill itself.
+    pub fn top_level_ancestor(&self, pid: u32) -> u32 {
+        let mut top = pid;
+        for ancestor in self.ancestors(pid) {
+            if self.ancestor_guard(ancestor).is_some() {
+                break;
+            }
+            top = ancestor;
+        }
+        top
+    }
+
+    /// Parent and top-level launcher of a process
+    pub fn ancestry(&self, pid: u32) -> Option<ProcessAncestry> {
+        let node = self.nodes.get(&pid)?;
+        let ancestor_pid = self.top_level_ancestor(pid);
+        let ancestor_cmd = self
+            .nodes
+            .get(&ancestor_pid)
+            .map(|n| n.command().to_string())
+            .unwrap_or_default();
+        Some(ProcessAncestry {
+            pid,
+            ppid: node.ppid,
+            ancestor_pid,
+            ancestor_cmd,
+        })
+    }
+
+    /// A process and all of its descendants, parents before children
+    pub fn descendants(&self, root: u32) -> Vec<u32> {
+        let mut pids = Vec::new();
+        let mut seen = HashSet::new();
+        let mut queue = std::collections::VecDeque::from([root]);
+        while let Some(pid) = queue.pop_front() {
+            if !seen.insert(pid) {
+                continue;
+            }
+            pids.push(pid);
+            queue.extend(self.children(pid));
+        }
+        pids
+    }
+
+    /// Plan a tree kill for `targets`. With `with_parent` each target is replaced by
+    /// its top-level ancestor, and with either flag the whole subtree is included.
+    /// The result is ordered bottom-up so launchers are killed after their workers
+    /// and cannot respawn them.
+    pub fn kill_plan(&self, targets: &[u32], with_parent: bool, with_children: bool) -> KillPlan {
+        let mut plan = KillPlan::default();
+        let mut members = HashSet::new();
+
+        for &target in targets {
+            let root = if with_parent {
+                self.top_level_ancestor(target)
+            } else {
+                target
+            };
+            if !plan.roots.contains(&root) {
+                plan.roots.push(root);
+            }
+
+            let pids = if with_parent || with_children {
+                self.descendants(root)
+            } else {
+                vec![root]
+            };
+            for pid in pids {
+                if !members.insert(pid) {
+                    continue;
+                }
+                match self.kill_guard(pid) {
+                    Some(reason) => plan.protected.push((pid, reason)),
+                    None => plan.order.push(pid),
+                }
+            }
+        }
+
+        let depth: HashMap<u32, usize> = plan
+            .order
+            .iter()
+            .map(|&pid| (pid, self.ancestors(pid).len()))
+            .collect();
+        plan.order
+            .sort_by_key(|pid| (std::cmp::Reverse(depth[pid]), *pid));
+        plan.protected.sort();
+        plan
+    }
+}
+
+/// Process names batch kills skip by default: display servers, Wayland compositors,
+/// display managers, remote access and container runtimes. Killing any of these takes
+/// down the desktop, the SSH session or every container on the host.
+pub const DEFAULT_PROTECTED_PROCESSES: &[&str] = &[
+    "Xorg",
+    "Xwayland",
+    "gnome-shell",
+    "kwin_wayland",
+    "kwin_x11",
+    "mutter",
+    "sway",
+    "weston",
+    "Hyprland",
+    "gdm",
+    "gdm3",
+    "sddm",
+    "lightdm",
+    "sshd",
+    "containerd",
+    "dockerd",
+];
+
+/// A kill candidate left alone, with the reason
+pub type SkippedProcess = (GpuProc, String);
+
+/// A process a batch kill terminated
+#[derive(Debug, Clone)]
+pub struct KilledProcess {
+    pub process: GpuProc,
+    /// Signal the process exited after, e.g. "SIGKILL" after escalation
+    pub signal: &'static str,
+    pub duration_ms: u64,
+}
+
+/// What a batch kill did with each candidate
+#[derive(Debug, Default)]
+pub struct BatchKillOutcome {
+    pub killed: Vec<KilledProcess>,
+    /// Processes that could not be killed, with the error
+    pub failed: Vec<(GpuProc, String)>,
+    /// Failed PIDs gpukill lacked the privileges to signal
+    pub permission_denied: Vec<u32>,
+    pub skipped: Vec<SkippedProcess>,
+}
+
+impl BatchKillOutcome {
+    pub fn killed_pids(&self) -> Vec<u32> {
+        self.killed.iter().map(|k| k.process.pid).collect()
+    }
+
+    pub fn failed_pids(&self) -> Vec<u32> {
+        self.failed.iter().map(|(p, _)| p.pid).collect()
+    }
+
+    /// One outcome per candidate: killed ones with the signal that ended them, then
+    /// failed ones with the error, then skipped ones with the reason
+    pub fn outcomes(&self) -> Vec<KillOutcome> {
+        let killed = self.killed.iter().map(|k| {
+            let mut outcome = KillOutcome::for_process(&k.process, KillStatus::Killed);
+            outcome.signal = Some(k.signal.to_string());
+            outcome.duration_ms = Some(k.duration_ms);
+            outcome
+        });
+        let failed = self.failed.iter().map(|(p, error)| {
+            KillOutcome::for_process(p, KillStatus::Failed).with_reason(error.clone())
+        });
+        let skipped = self.skipped.iter().map(|(p, reason)| {
+            KillOutcome::for_process(p, KillStatus::Skipped).with_reason(reason.clone())
+        });
+        killed.chain(failed).chain(skipped).collect()
+    }
+
+    /// Error message for a batch where some kills failed, naming those refused
+    /// for lack of privileges
+    pub fn failure_message(&self) -> String {
+        kill_failure_message(&self.failed_pids(), &self.permission_denied)
+    }
+}
+
+/// Error message for kills that failed, calling out those refused for lack of privileges
+fn kill_failure_message(failed: &[u32], permission_denied: &[u32]) -> String {
+    let mut message = format!("Failed to kill {} processes: {:?}", failed.len(), failed);
+    if !permission_denied.is_empty() {
+        message.push_str(&format!(
+            " (Permission denied for {:?}: only their owners or root can signal them)",
+            permission_denied
+        ));
+    }
+    message
+}
+
+/// Error naming the first of `processes` that `guard` refuses to kill
+fn check_not_self(processes: &[GpuProc], guard: &SelfGuard) -> Result<()> {
+    processes.iter().try_for_each(|proc| guard.check(proc.pid))
+}
+
+/// Kill each distinct PID in `processes` with `kill`, skipping those `self_guard`
+/// or `protection` refuses
+fn kill_unprotected<F>(
+    processes: &[GpuProc],
+    self_guard: &SelfGuard,
+    protection: Option<&KillProtection>,
+    override_protection: bool,
+    mut kill: F,
+) -> BatchKillOutcome
+where
+    F: FnMut(u32) -> Result<&'static str>,
+{
+    let mut outcome = BatchKillOutcome::default();
+    let mut seen_pids = HashSet::new();
+    for proc in processes {
+        if !seen_pids.insert(proc.pid) {
+            continue;
+        }
+        let reason = match self_guard.check(proc.pid) {
+            Err(_) => self_guard.reason(proc.pid).map(str::to_string),
+            Ok(()) => protection
+                .and_then(|p| p.skip_reason(proc.pid, &proc.proc_name, override_protection)),
+        };
+        if let Some(reason) = reason {
+            tracing::warn!(
+                pid = proc.pid,
+                process = %proc.proc_name,
+                reason = %reason,
+                "Skipping protected process"
+            );
+            outcome.skipped.push((proc.clone(), reason));
+            continue;
+        }
+        let started = std::time::Instant::now();
+        match kill(proc.pid) {
+            Ok(signal) => {
+                outcome.killed.push(KilledProcess {
+                    process: proc.clone(),
+                    signal,
+                    duration_ms: started.elapsed().as_millis() as u64,
+                });
+                tracing::info!(
+                    pid = proc.pid,
+                    user = %proc.user,
+                    gpu_index = proc.gpu_index,
+                    process = %proc.proc_name,
+                    "Killed process"
+                );
+            }
+            Err(e) => {
+                if crate::proc::kill_permission_denied(&e).is_some() {
+                    outcome.permission_denied.push(proc.pid);
+                }
+                outcome.failed.push((proc.clone(), e.to_string()));
+                tracing::warn!(
+                    pid = proc.pid,
+                    user = %proc.user,
+                    gpu_index = proc.gpu_index,
+                    process = %proc.proc_name,
+                    error = %e,
+                    "Failed to kill process"
+                );
+            }
+        }
+    }
+    outcome
+}
+
+/// Decides which kill candidates are protected and why
+#[derive(Debug, Clone)]
+pub struct KillProtection {
+    table: ProcessTable,
+    names: Vec<String>,
+}
+
+impl KillProtection {
+    /// Protect the default process names plus `extra_names` (e.g. from the config file)
+    pub fn new(table: ProcessTable, extra_names: &[String]) -> Self {
+        let mut names: Vec<String> = DEFAULT_PROTECTED_PROCESSES
+            .iter()
+            .map(|n| n.to_string())
+            .collect();
+        for name in extra_names {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        Self { table, names }
+    }
+
+    /// Protection for the current system process table
+    pub fn snapshot(extra_names: &[String]) -> Self {
+        Self::new(ProcessTable::snapshot(), extra_names)
+    }
+
+    /// Stop guarding gpukill and its parent if `guard` allows killing them. Further
+    /// ancestors stay guarded.
+    pub fn allowing_self(mut self, guard: &SelfGuard) -> Self {
+        if guard.is_allowed() {
+            for pid in guard.pids() {
+                self.table.guarded.remove(&pid);
+            }
+        }
+        self
+    }
+
+    /// Why `pid` must be skipped by batch kills, if it must. `name` is the name the
+    /// GPU backend reported, used when the process is missing from the table.
+    pub fn reason(&self, pid: u32, name: &str) -> Option<String> {
+        if let Some(reason) = self.hard_reason(pid) {
+            return Some(reason);
+        }
+        let name = self.table.get(pid).map(|n| n.name.as_str()).unwrap_or(name);
+        let name = name.rsplit('/').next().unwrap_or(name);
+        self.names
+            .iter()
+            .any(|p| name == p || name.starts_with(&format!("{}-", p)))
+            .then(|| format!("protected process {}", name))
+    }
+
+    /// Protection that `--allow-protected` cannot lift: init, kernel threads and
+    /// gpukill itself with its parents
+    fn hard_reason(&self, pid: u32) -> Option<String> {
+        if let Some(reason) = self.table.kill_guard(pid) {
+            return Some(reason);
+        }
+        // Kernel threads are children of kthreadd (PID 2)
+        let kernel_thread = pid == 2 || self.table.get(pid).is_some_and(|n| n.ppid == Some(2));
+        kernel_thread.then(|| "kernel thread".to_string())
+    }
+
+    /// Why a batch kill must skip `pid`; with `allow_protected` only the protection
+    /// that cannot be lifted applies
+    pub fn skip_reason(&self, pid: u32, name: &str, allow_protected: bool) -> Option<String> {
+        if allow_protected {
+            self.hard_reason(pid)
+        } else {
+            self.reason(pid, name)
+        }
+    }
+
+    /// Split `processes` into those that may be killed and those skipped, with the
+    /// reason. With `allow_protected` only init, kernel threads and gpukill are skipped.
+    pub fn partition(
+        &self,
+        processes: Vec<GpuProc>,
+        allow_protected: bool,
+    ) -> (Vec<GpuProc>, Vec<SkippedProcess>) {
+        let mut allowed = Vec::new();
+        let mut skipped = Vec::new();
+        for proc in processes {
+            match self.skip_reason(proc.pid, &proc.proc_name, allow_protected) {
+                Some(reason) => skipped.push((proc, reason)),
+                None => allowed.push(proc),
+            }
+        }
+        (allowed, skipped)
+    }
+
+    /// Move protected processes out of a tree kill plan
+    pub fn restrict_plan(&self, plan: &mut KillPlan, allow_protected: bool) {
+        let mut order = Vec::with_capacity(plan.order.len());
+        for pid in plan.order.drain(..) {
+            let name = self
+                .table
+                .get(pid)
+                .map(|n| n.name.clone())
+                .unwrap_or_default();
+            match self.skip_reason(pid, &name, allow_protected) {
+                Some(reason) => plan.protected.push((pid, reason)),
+                None => order.push(pid),
+            }
+        }
+        plan.order = order;
+        plan.protected.sort();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::nvml_api::GpuProc;
+    use crate::nvml_api::fixtures::proc;
 
-    fn create_test_process(pid: u32, name: &str, user: &str, memory: u32) -> GpuProc {
-        GpuProc {
-            gpu_index: 0,
-            pid,
-            user: user.to_string(),
-            proc_name: name.to_string(),
-            used_mem_mb: memory,
-            start_time: "1h".to_string(),
-            container: None,
-            node_id: None,
-        }
+    /// Manager over a mock GPU, so these tests run without NVML
+    fn test_manager() -> EnhancedProcessManager {
+        let vendor = std::sync::Arc::new(crate::vendor::MockVendor::default());
+        EnhancedProcessManager::new(ProcessManager::with_gpu_manager(vendor.manager()))
     }
 
     #[test]
     fn test_filter_processes_by_name() {
         let processes = vec![
-            create_test_process(1, "python", "user1", 100),
-            create_test_process(2, "python3", "user1", 200),
-            create_test_process(3, "java", "user2", 300),
+            proc(0, 1, "user1", "python", 100),
+            proc(0, 2, "user1", "python3", 200),
+            proc(0, 3, "user2", "java", 300),
         ];
 
-        // Skip test if NVML is not available
-        if let Ok(nvml_api) = crate::nvml_api::NvmlApi::new() {
-            let mut manager = EnhancedProcessManager {
-                process_manager: ProcessManager::new(nvml_api),
-                system: System::new_all(),
-            };
+        let mut manager = test_manager();
 
-            let filtered = manager
-                .filter_processes_by_name(&processes, "python")
-                .unwrap();
-            assert_eq!(filtered.len(), 2);
-            assert_eq!(filtered[0].proc_name, "python");
-            assert_eq!(filtered[1].proc_name, "python3");
-        }
+        let filtered = manager
+            .filter_processes_by_name(&processes, "python")
+            .unwrap();
+        assert_eq!(filtered.len(), 2);
+        assert_eq!(filtered[0].proc_name, "python");
+        assert_eq!(filtered[1].proc_name, "python3");
+    }
+
+    #[test]
+    fn test_filter_kill_targets_intersects_name_and_user() {
+        let mut with_cmdline = proc(0, 5, "bob", "python", 100);
+        with_cmdline.cmdline = Some("python train.py".to_string());
+        let processes = vec![
+            proc(0, 1, "bob", "python", 100),
+            proc(0, 2, "alice", "python", 200),
+            proc(0, 3, "bob", "java", 300),
+            proc(0, 4, "bobby", "python3", 400),
+            with_cmdline,
+        ];
+        let pids = |pattern, match_cmdline, user| -> Vec<u32> {
+            filter_kill_targets(&processes, pattern, match_cmdline, user)
+                .unwrap()
+                .iter()
+                .map(|p| p.pid)
+                .collect()
+        };
+
+        assert_eq!(pids(Some("python"), false, Some("bob")), vec![1, 5]);
+        assert_eq!(pids(Some("train"), true, Some("bob")), vec![5]);
+        // Omitting one constraint keeps the broader match
+        assert_eq!(pids(Some("python"), false, None), vec![1, 2, 4, 5]);
+        assert_eq!(pids(None, false, Some("bob")), vec![1, 3, 5]);
+        assert!(pids(Some("java"), false, Some("alice")).is_empty());
+        assert!(filter_kill_targets(&processes, Some("("), false, None).is_err());
     }
 
     #[test]
     fn test_filter_processes_by_memory() {
         let processes = vec![
-            create_test_process(1, "python", "user1", 100),
-            create_test_process(2, "python3", "user1", 200),
-            create_test_process(3, "java", "user2", 300),
+            proc(0, 1, "user1", "python", 100),
+            proc(0, 2, "user1", "python3", 200),
+            proc(0, 3, "user2", "java", 300),
         ];
 
-        // Skip test if NVML is not available
-        if let Ok(nvml_api) = crate::nvml_api::NvmlApi::new() {
-            let mut manager = EnhancedProcessManager {
-                process_manager: ProcessManager::new(nvml_api),
-                system: System::new_all(),
-            };
+        let mut manager = test_manager();
 
-            let filtered = manager.filter_processes_by_memory(&processes, 200);
-            assert_eq!(filtered.len(), 2);
-            assert!(filtered.iter().all(|p| p.used_mem_mb >= 200));
-        }
+        let filtered = manager.filter_processes_by_memory(&processes, 200);
+        assert_eq!(filtered.len(), 2);
+        assert!(filtered.iter().all(|p| p.used_mem_mb >= 200));
     }
 
     #[test]
     fn test_process_stats() {
         let processes = vec![
-            create_test_process(1, "python", "user1", 100),
-            create_test_process(2, "python", "user1", 200),
-            create_test_process(3, "java", "user2", 300),
+            proc(0, 1, "user1", "python", 100),
+            proc(0, 2, "user1", "python", 200),
+            proc(0, 3, "user2", "java", 300),
         ];
 
-        // Skip test if NVML is not available
-        if let Ok(nvml_api) = crate::nvml_api::NvmlApi::new() {
-            let mut manager = EnhancedProcessManager {
-                process_manager: ProcessManager::new(nvml_api),
-                system: System::new_all(),
-            };
+        let mut manager = test_manager();
 
-            let stats = manager.get_process_stats(&processes);
-            assert_eq!(stats.total_processes, 3);
-            assert_eq!(stats.total_memory_mb, 600);
-            assert_eq!(stats.users.len(), 2);
-            assert_eq!(stats.process_names.len(), 2);
+        let stats = manager.get_process_stats(&processes);
+        assert_eq!(stats.total_processes, 3);
+        assert_eq!(stats.total_memory_mb, 600);
+        assert_eq!(stats.users.len(), 2);
+        assert_eq!(stats.process_names.len(), 2);
+    }
+
+    fn node(pid: u32, ppid: u32, name: &str, session_leader: bool) -> ProcessNode {
+        ProcessNode {
+            pid,
+            ppid: if ppid == 0 { None } else { Some(ppid) },
+            name: name.to_string(),
+            cmd: String::new(),
+            session_leader,
         }
     }
+
+    /// systemd -> sshd -> bash (session) -> torchrun -> python workers, plus gpukill
+    /// running from the same shell
+    fn job_table() -> ProcessTable {
+        ProcessTable::from_nodes(
+            vec![
+                node(1, 0, "systemd", true),
+                node(50, 1, "sshd", true),
+                node(55, 50, "sshd-session", false),
+                node(60, 55, "bash", true),
+                ProcessNode {
+                    cmd: "torchrun --nproc_per_node=2 train.py".to_string(),
+                    ..node(100, 60, "torchrun", false)
+                },
+                node(101, 100, "python", false),
+                node(102, 100, "python", false),
+                node(103, 101, "python", false),
+                node(200, 60, "gpukill", false),
+                node(300, 1, "orphan", false),
+            ],
+            Some(200),
+        )
+    }
+
+    #[test]
+    fn test_process_table_ancestry() {
+        let table = job_table();
+        assert_eq!(table.children(100), &[101, 102]);
+        assert_eq!(table.descendants(100), vec![100, 101, 102, 103]);
+
+        // The walk stops below the session leader shell
+        let ancestry = table.ancestry(103).unwrap();
+        assert_eq!(ancestry.ppid, Some(101));
+        assert_eq!(ancestry.ancestor_pid, 100);
+        assert_eq!(
+            ancestry.ancestor_cmd,
+            "torchrun --nproc_per_node=2 train.py"
+        );
+
+        // Never walks up into PID 1
+        assert_eq!(table.top_level_ancestor(300), 300);
+        // Protected names stop the walk even without a session id
+        assert_eq!(table.top_level_ancestor(60), 60);
+        assert!(table.ancestry(999).is_none());
+    }
+
+    #[test]
+    fn test_kill_plan_children_first() {
+        let table = job_table();
+
+        let plan = table.kill_plan(&[100], false, true);
+        assert_eq!(plan.roots, vec![100]);
+        assert_eq!(plan.order, vec![103, 101, 102, 100]);
+        assert!(plan.protected.is_empty());
+
+        // --with-parent from a worker kills the launcher's whole tree
+        let plan = table.kill_plan(&[103, 102], true, false);
+        assert_eq!(plan.roots, vec![100]);
+        assert_eq!(plan.order, vec![103, 101, 102, 100]);
+
+        // Without tree flags only the target is planned
+        let plan = table.kill_plan(&[101], false, false);
+        assert_eq!(plan.order, vec![101]);
+    }
+
+    #[test]
+    fn test_kill_plan_never_includes_init_or_self() {
+        let table = job_table();
+
+        // Killing the shell's tree must spare the shell (gpukill's parent) and gpukill
+        let plan = table.kill_plan(&[60], false, true);
+        assert_eq!(plan.order, vec![103, 101, 102, 100]);
+        let protected: Vec<u32> = plan.protected.iter().map(|(pid, _)| *pid).collect();
+        assert_eq!(protected, vec![60, 200]);
+
+        // PID 1 is never killed, even when targeted directly
+        let plan = table.kill_plan(&[1], false, true);
+        assert!(!plan.order.contains(&1));
+        assert!(!plan.order.contains(&50));
+        assert!(!plan.order.contains(&200));
+        assert!(plan.order.contains(&300));
+    }
+
+    #[test]
+    fn test_process_table_handles_cycles() {
+        // Corrupt parent links must not hang the tree walks
+        let table = ProcessTable::from_nodes(
+            vec![node(10, 11, "a", false), node(11, 10, "b", false)],
+            None,
+        );
+        assert_eq!(table.descendants(10), vec![10, 11]);
+        assert_eq!(table.top_level_ancestor(10), 11);
+        let plan = table.kill_plan(&[10], true, true);
+        assert_eq!(plan.order.len(), 2);
+    }
+
+    #[test]
+    fn test_kill_protection_reasons() {
+        let mut nodes = vec![
+            node(1, 0, "systemd", true),
+            node(2, 0, "kthreadd", false),
+            node(3, 2, "kworker/0:0", false),
+            node(40, 1, "Xorg", false),
+            node(41, 40, "gnome-shell", false),
+            node(42, 1, "containerd-shim-runc-v2", false),
+            node(43, 1, "slurm-agent", false),
+            node(60, 1, "bash", true),
+            node(200, 60, "gpukill", false),
+            node(300, 60, "python", false),
+        ];
+        nodes.push(ProcessNode {
+            cmd: "/usr/lib/xorg/Xorg :0".to_string(),
+            ..node(44, 1, "/usr/lib/xorg/Xorg", false)
+        });
+        let protection = KillProtection::new(
+            ProcessTable::from_nodes(nodes, Some(200)),
+            &["slurm-agent".to_string()],
+        );
+
+        assert_eq!(protection.reason(1, "systemd").unwrap(), "init process");
+        assert_eq!(
+            protection.reason(3, "kworker/0:0").unwrap(),
+            "kernel thread"
+        );
+        assert_eq!(
+            protection.reason(40, "Xorg").unwrap(),
+            "protected process Xorg"
+        );
+        assert!(protection.reason(41, "gnome-shell").is_some());
+        assert!(protection.reason(42, "containerd-shim").is_some());
+        assert!(protection.reason(44, "Xorg").is_some());
+        // Configured additions
+        assert!(protection.reason(43, "slurm-agent").is_some());
+        // gpukill and its parent shell
+        assert!(protection.reason(200, "gpukill").is_some());
+        assert!(protection.reason(60, "bash").is_some());
+        assert!(protection.reason(300, "python").is_none());
+        // Processes missing from the table fall back to the reported name
+        assert!(protection.reason(900, "Xwayland").is_some());
+        assert!(protection.reason(901, "train.py").is_none());
+    }
+
+    #[test]
+    fn test_kill_protection_partitions_candidates() {
+        let table = ProcessTable::from_nodes(
+            vec![node(1, 0, "systemd", true), node(40, 1, "Xorg", false)],
+            None,
+        );
+        let protection = KillProtection::new(table, &[]);
+        let candidates = vec![
+            proc(0, 1, "root", "systemd", 10),
+            proc(0, 40, "root", "Xorg", 200),
+            proc(0, 500, "alice", "python", 8000),
+        ];
+        let (allowed, skipped) = protection.partition(candidates.clone(), false);
+        assert_eq!(allowed.len(), 1);
+        assert_eq!(allowed[0].pid, 500);
+        let skipped: Vec<(u32, String)> = skipped.into_iter().map(|(p, r)| (p.pid, r)).collect();
+        assert_eq!(
+            skipped,
+            vec![
+                (1, "init process".to_string()),
+                (40, "protected process Xorg".to_string())
+            ]
+        );
+
+        // --allow-protected lifts the name list but never the init guard
+        let (allowed, skipped) = protection.partition(candidates, true);
+        let allowed: Vec<u32> = allowed.iter().map(|p| p.pid).collect();
+        assert_eq!(allowed, vec![40, 500]);
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].0.pid, 1);
+    }
+
+    #[test]
+    fn test_kill_protection_restricts_tree_plan() {
+        let table = ProcessTable::from_nodes(
+            vec![
+                node(1, 0, "systemd", true),
+                node(10, 1, "launcher", false),
+                node(11, 10, "Xwayland", false),
+                node(12, 10, "python", false),
+            ],
+            None,
+        );
+        let protection = KillProtection::new(table.clone(), &[]);
+        let mut plan = table.kill_plan(&[10], false, true);
+        protection.restrict_plan(&mut plan, false);
+        assert_eq!(plan.order, vec![12, 10]);
+        assert_eq!(
+            plan.protected,
+            vec![(11, "protected process Xwayland".to_string())]
+        );
+
+        let mut plan = table.kill_plan(&[10], false, true);
+        protection.restrict_plan(&mut plan, true);
+        assert_eq!(plan.order, vec![11, 12, 10]);
+        assert!(plan.protected.is_empty());
+    }
+
+    #[test]
+    fn test_batch_kill_skips_protected_unless_overridden() {
+        let table = ProcessTable::from_nodes(
+            vec![
+                node(1, 0, "systemd", true),
+                node(40, 1, "Xorg", false),
+                node(43, 1, "slurm-agent", false),
+                node(500, 1, "python", false),
+            ],
+            None,
+        );
+        let protection = KillProtection::new(table, &["slurm-agent".to_string()]);
+        let guard = SelfGuard::new(900, Some(901));
+        let candidates = vec![
+            proc(0, 40, "root", "Xorg", 200),
+            proc(0, 43, "root", "slurm-agent", 10),
+            proc(0, 500, "alice", "python", 8000),
+            // Same process on a second GPU
+            proc(0, 500, "alice", "python", 8000),
+        ];
+
+        let mut attempted = Vec::new();
+        let outcome = kill_unprotected(&candidates, &guard, Some(&protection), false, |pid| {
+            attempted.push(pid);
+            Ok("SIGTERM")
+        });
+        assert_eq!(attempted, vec![500]);
+        assert_eq!(outcome.killed_pids(), vec![500]);
+        assert_eq!(outcome.killed[0].signal, "SIGTERM");
+        let skipped: Vec<u32> = outcome.skipped.iter().map(|(p, _)| p.pid).collect();
+        assert_eq!(skipped, vec![40, 43]);
+
+        let mut attempted = Vec::new();
+        let outcome = kill_unprotected(&candidates, &guard, Some(&protection), true, |pid| {
+            attempted.push(pid);
+            if pid == 43 {
+                Err(anyhow::anyhow!("permission denied"))
+            } else {
+                Ok("SIGKILL")
+            }
+        });
+        assert_eq!(attempted, vec![40, 43, 500]);
+        assert_eq!(outcome.killed_pids(), vec![40, 500]);
+        assert_eq!(outcome.failed_pids(), vec![43]);
+        assert_eq!(outcome.failed[0].1, "permission denied");
+        assert!(outcome.skipped.is_empty());
+
+        // Without a protection check everything is attempted
+        let outcome = kill_unprotected(&candidates, &guard, None, false, |_| Ok("SIGTERM"));
+        assert_eq!(outcome.killed_pids(), vec![40, 43, 500]);
+    }
+
+    #[test]
+    fn test_batch_kill_refuses_self_unless_allowed() {
+        let table = ProcessTable::from_nodes(
+            vec![
+                node(1, 0, "systemd", true),
+                node(60, 1, "bash", true),
+                node(200, 60, "gpukill", false),
+                node(500, 1, "python", false),
+            ],
+            Some(200),
+        );
+        let guard = SelfGuard::new(200, Some(60));
+        let candidates = vec![
+            proc(0, 60, "alice", "bash", 0),
+            proc(0, 200, "alice", "gpukill", 0),
+            proc(0, 500, "alice", "python", 8000),
+        ];
+
+        // Blocked by default: the whole batch errors and a kill skips both
+        let err = check_not_self(&candidates, &guard).unwrap_err().to_string();
+        assert!(err.contains("PID 60"), "{}", err);
+        assert!(err.contains("--force --allow-self"), "{}", err);
+        let mut attempted = Vec::new();
+        let outcome = kill_unprotected(&candidates, &guard, None, false, |pid| {
+            attempted.push(pid);
+            Ok("SIGTERM")
+        });
+        assert_eq!(attempted, vec![500]);
+        let skipped: Vec<(u32, &str)> = outcome
+            .skipped
+            .iter()
+            .map(|(p, reason)| (p.pid, reason.as_str()))
+            .collect();
+        assert_eq!(
+            skipped,
+            vec![
+                (60, "gpukill's parent process"),
+                (200, "gpukill's own process")
+            ]
+        );
+
+        // The override permits them, also past the protection's own guard
+        let allowed = guard.allowing(true);
+        assert!(check_not_self(&candidates, &allowed).is_ok());
+        let protection = KillProtection::new(table, &[]).allowing_self(&allowed);
+        let outcome = kill_unprotected(&candidates, &allowed, Some(&protection), true, |_| {
+            Ok("SIGKILL")
+        });
+        assert_eq!(outcome.killed_pids(), vec![60, 200, 500]);
+        assert!(outcome.skipped.is_empty());
+    }
+
+    #[test]
+    fn test_kill_init_refused_by_safety_rails() {
+        // PID 1 is never signalled, even with the protection overridden
+        let mut manager = test_manager().with_protection(&[], true);
+        let err = manager.kill_process(1, 1, true).unwrap_err().to_string();
+        assert!(err.contains("Refusing to kill PID 1"), "{}", err);
+        assert!(err.contains("init process"), "{}", err);
+        assert!(!err.contains("--allow-protected"), "{}", err);
+    }
+
+    #[test]
+    fn test_batch_kill_reports_permission_denied() {
+        let alice = crate::privileges::Privileges::new("alice", Some(1000), Some(0));
+        let guard = SelfGuard::new(900, Some(901));
+        let candidates = vec![
+            proc(0, 1, "root", "systemd", 0),
+            proc(0, 500, "alice", "python", 8000),
+            proc(0, 600, "bob", "python", 8000),
+        ];
+
+        let outcome = kill_unprotected(&candidates, &guard, None, false, |pid| match pid {
+            500 => Ok("SIGTERM"),
+            600 => Err(anyhow::anyhow!("No such process")),
+            _ => Err(crate::proc::KillPermissionDenied::new(
+                pid,
+                "SIGTERM",
+                Some("root".to_string()),
+                &alice,
+            )
+            .into()),
+        });
+        assert_eq!(outcome.killed_pids(), vec![500]);
+        assert_eq!(outcome.failed_pids(), vec![1, 600]);
+        assert_eq!(outcome.permission_denied, vec![1]);
+        assert!(outcome.failed[0].1.contains("re-run with sudo"));
+
+        let message = outcome.failure_message();
+        assert!(
+            message.starts_with("Failed to kill 2 processes: [1, 600]"),
+            "{}",
+            message
+        );
+        assert!(message.contains("Permission denied for [1]"), "{}", message);
+        assert_eq!(
+            crate::report::ErrorKind::classify(&message),
+            crate::report::ErrorKind::PermissionDenied
+        );
+
+        // No privilege hint when nothing was refused for lack of privileges
+        let outcome = kill_unprotected(&candidates[1..], &guard, None, false, |_| {
+            Err(anyhow::anyhow!("No such process"))
+        });
+        assert!(outcome.permission_denied.is_empty());
+        assert!(!outcome.failure_message().contains("Permission"));
+    }
+
+    #[test]
+    fn test_batch_kill_outcomes_cover_killable_and_denied_targets() {
+        let alice = crate::privileges::Privileges::new("alice", Some(1000), Some(0));
+        let guard = SelfGuard::new(900, Some(901));
+        let candidates = vec![
+            proc(0, 500, "alice", "python", 8000),
+            proc(0, 501, "alice", "python", 8000),
+            proc(0, 600, "bob", "python", 8000),
+            proc(0, 900, "alice", "gpukill", 0),
+        ];
+
+        let outcomes = kill_unprotected(&candidates, &guard, None, false, |pid| match pid {
+            500 => Ok("SIGTERM"),
+            501 => Ok("SIGKILL"),
+            _ => Err(crate::proc::KillPermissionDenied::new(
+                pid,
+                "SIGTERM",
+                Some("bob".to_string()),
+                &alice,
+            )
+            .into()),
+        })
+        .outcomes();
+
+        let summary: Vec<_> = outcomes
+            .iter()
+            .map(|o| (o.pid, o.status, o.succeeded(), o.needed_sigkill()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (500, KillStatus::Killed, true, false),
+                (501, KillStatus::Killed, true, true),
+                (600, KillStatus::Failed, false, false),
+                (900, KillStatus::Skipped, false, false),
+            ]
+        );
+        assert_eq!(outcomes[0].reason, None);
+        let error = outcomes[2].reason.as_deref().unwrap();
+        assert!(error.contains("re-run with sudo"), "{}", error);
+        assert_eq!(outcomes[2].user.as_deref(), Some("bob"));
+        assert!(outcomes[3].reason.is_some());
+    }
 }
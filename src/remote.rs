@@ -2,11 +2,26 @@ use anyhow::{Context, Result};
 use shell_escape::unix::escape as unix_escape;
 use std::{
     borrow::Cow,
+    fmt,
+    path::PathBuf,
     process::{Command, Stdio},
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+    sync::{Arc, Mutex},
     time::Duration,
 };
 use tracing::{debug, info, warn};
 
+use crate::command::{CommandOutput, CommandRunner, SystemCommandRunner};
+
+/// How often the master connection of a [`RemoteSession`] probes the server, and
+/// how many unanswered probes close it
+const KEEPALIVE_INTERVAL_SECS: u64 = 15;
+const KEEPALIVE_COUNT_MAX: u32 = 3;
+
+/// Times a dropped `--watch` stream is reopened before giving up
+const WATCH_RECONNECT_ATTEMPTS: u32 = 3;
+
 /// SSH connection configuration
 #[derive(Debug, Clone)]
 pub struct SshConfig {
@@ -16,6 +31,12 @@ pub struct SshConfig {
     pub key_path: Option<String>,
     pub password: Option<String>,
     pub timeout: Duration,
+    /// Bastion hosts to hop through, in order (ssh -J)
+    pub jump_hosts: Vec<JumpHost>,
+    /// Skip known_hosts verification
+    pub insecure: bool,
+    /// Socket of a shared master connection (ssh ControlPath) to run commands through
+    pub control_path: Option<PathBuf>,
 }
 
 impl SshConfig {
@@ -28,6 +49,9 @@ impl SshConfig {
             key_path: None,
             password: None,
             timeout: Duration::from_secs(30),
+            jump_hosts: Vec::new(),
+            insecure: false,
+            control_path: None,
         }
     }
 
@@ -48,240 +72,463 @@ impl SshConfig {
         self.timeout = timeout;
         self
     }
-}
 
-/// SSH remote connection manager using system SSH
-pub struct SshRemote {
-    config: SshConfig,
-}
+    /// Connect through these jump hosts
+    pub fn with_jump_hosts(mut self, jump_hosts: Vec<JumpHost>) -> Self {
+        self.jump_hosts = jump_hosts;
+        self
+    }
 
-impl SshRemote {
-    /// Create a new SSH remote connection
-    pub fn new(config: SshConfig) -> Self {
-        Self { config }
+    /// Skip host key verification
+    pub fn with_insecure(mut self, insecure: bool) -> Self {
+        self.insecure = insecure;
+        self
     }
 
-    /// Execute a command on the remote host and return raw output (for callers that need exit code).
-    fn execute_command_output(&self, command: &str) -> Result<std::process::Output> {
-        debug!("Executing remote command: {}", command);
-        let timeout_secs = self.config.timeout.as_secs();
-        let host_spec = format!("{}@{}", self.config.username, self.config.host);
+    /// Run commands through the master connection listening on `control_path`
+    pub fn with_control_path(mut self, control_path: PathBuf) -> Self {
+        self.control_path = Some(control_path);
+        self
+    }
 
-        let output = if let Some(password) = &self.config.password {
-            self.execute_with_sshpass(command, password, &host_spec, timeout_secs)?
-        } else {
-            self.execute_with_ssh(command, &host_spec, timeout_secs)?
+    /// Fill in jump hosts from the ProxyJump entry for this host in ~/.ssh/config
+    /// when none were given explicitly.
+    pub fn with_ssh_config_proxy_jump(mut self) -> Self {
+        if !self.jump_hosts.is_empty() {
+            return self;
+        }
+        let Some(path) = dirs::home_dir().map(|h| h.join(".ssh").join("config")) else {
+            return self;
+        };
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return self;
         };
-        Ok(output)
+        if let Some(spec) = proxy_jump_for_host(&content, &self.host) {
+            match parse_jump_hosts(&spec) {
+                Ok(jumps) => {
+                    debug!("Using ProxyJump {} for {} from ssh config", spec, self.host);
+                    self.jump_hosts = jumps;
+                }
+                Err(e) => warn!("Ignoring ProxyJump for {} in ssh config: {}", self.host, e),
+            }
+        }
+        self
     }
 
-    /// Execute a command on the remote host
-    ///
-    /// # Authentication
-    /// - SSH key authentication is preferred and works automatically
-    /// - Password authentication requires `sshpass` to be installed on the system,
-    ///   as SSH requires a TTY for interactive password prompts
-    pub fn execute_command(&self, command: &str) -> Result<String> {
-        let output = self.execute_command_output(command)?;
+    /// Arguments for `ssh` that run `command` on this host
+    pub fn ssh_args(&self, command: &str) -> Vec<String> {
+        let mut args = self.connection_args();
+        args.push(command.to_string());
+        args
+    }
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(anyhow::anyhow!(
-                "SSH command failed with exit code {}: {}",
-                output.status.code().unwrap_or(-1),
-                stderr
-            ));
+    /// Arguments for `ssh` that start a background master connection on
+    /// `control_path`, kept alive with server probes
+    pub fn master_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "-M".to_string(),
+            "-N".to_string(),
+            "-f".to_string(),
+            "-o".to_string(),
+            "ControlPersist=yes".to_string(),
+            "-o".to_string(),
+            format!("ServerAliveInterval={}", KEEPALIVE_INTERVAL_SECS),
+            "-o".to_string(),
+            format!("ServerAliveCountMax={}", KEEPALIVE_COUNT_MAX),
+        ];
+        args.extend(self.connection_args());
+        args
+    }
+
+    /// Arguments for `ssh -O <operation>` (`check`, `exit`) against the master connection
+    pub fn control_args(&self, operation: &str) -> Vec<String> {
+        let mut args = vec!["-O".to_string(), operation.to_string()];
+        args.extend(self.connection_args());
+        args
+    }
+
+    /// Options, jump hosts, port, key and destination shared by every invocation
+    fn connection_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        let mut option = |value: String| {
+            args.push("-o".to_string());
+            args.push(value);
+        };
+
+        option(format!("ConnectTimeout={}", self.timeout.as_secs()));
+        if self.insecure {
+            option("StrictHostKeyChecking=no".to_string());
+            option("UserKnownHostsFile=/dev/null".to_string());
+        } else {
+            // Trust a host the first time it is seen, but refuse a changed key
+            option("StrictHostKeyChecking=accept-new".to_string());
+        }
+        option("LogLevel=ERROR".to_string());
+        if let Some(control_path) = &self.control_path {
+            option(format!("ControlPath={}", control_path.display()));
+        }
+        if self.password.is_some() {
+            option("PasswordAuthentication=yes".to_string());
+            option("PubkeyAuthentication=no".to_string());
+        } else {
+            // Key or ssh-agent authentication; never stop at an interactive prompt
+            option("BatchMode=yes".to_string());
         }
 
-        let stdout = String::from_utf8(output.stdout)
-            .context("Failed to decode SSH command output as UTF-8")?;
-        debug!(
-            "Command executed successfully, output length: {} bytes",
-            stdout.len()
-        );
-        Ok(stdout)
+        if !self.jump_hosts.is_empty() {
+            let chain: Vec<String> = self.jump_hosts.iter().map(|j| j.to_string()).collect();
+            args.push("-J".to_string());
+            args.push(chain.join(","));
+        }
+        if self.port != 22 {
+            args.push("-p".to_string());
+            args.push(self.port.to_string());
+        }
+        if let Some(key_path) = &self.key_path {
+            args.push("-i".to_string());
+            args.push(key_path.clone());
+        }
+
+        args.push(format!("{}@{}", self.username, self.host));
+        args
     }
+}
 
-    /// Execute command using sshpass for password authentication
-    ///
-    /// SSH does not read passwords from stdin - it requires a controlling TTY.
-    /// sshpass is a utility that provides password to SSH in a non-interactive way.
-    fn execute_with_sshpass(
-        &self,
-        command: &str,
-        password: &str,
-        host_spec: &str,
-        timeout_secs: u64,
-    ) -> Result<std::process::Output> {
-        // Check if sshpass is available
-        let sshpass_check = Command::new("which").arg("sshpass").output();
-
-        if sshpass_check.is_err() || !sshpass_check.unwrap().status.success() {
-            return Err(anyhow::anyhow!(
-                "Password authentication requires 'sshpass' to be installed. \
-                SSH requires a TTY for password prompts, which is not available in this context. \
-                Please install sshpass (e.g., 'apt install sshpass' or 'brew install sshpass') \
-                or use SSH key authentication instead (--ssh-key)."
-            ));
+/// One hop of a jump chain: `[user@]host[:port]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct JumpHost {
+    pub user: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl FromStr for JumpHost {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (user, rest) = match s.rsplit_once('@') {
+            Some((user, rest)) if !user.is_empty() => (Some(user.to_string()), rest),
+            Some(_) => return Err(anyhow::anyhow!("Invalid jump host '{}': empty user", s)),
+            None => (None, s),
+        };
+        let (host, port) = match rest.rsplit_once(':') {
+            Some((host, port)) => {
+                let port = port
+                    .parse::<u16>()
+                    .map_err(|_| anyhow::anyhow!("Invalid jump host '{}': bad port", s))?;
+                (host, Some(port))
+            }
+            None => (rest, None),
+        };
+        if host.is_empty() {
+            return Err(anyhow::anyhow!("Invalid jump host '{}': empty host", s));
         }
+        Ok(Self {
+            user,
+            host: host.to_string(),
+            port,
+        })
+    }
+}
 
-        let mut cmd = Command::new("sshpass");
-        cmd.arg("-p").arg(password);
-        cmd.arg("ssh");
+impl fmt::Display for JumpHost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(user) = &self.user {
+            write!(f, "{}@", user)?;
+        }
+        write!(f, "{}", self.host)?;
+        if let Some(port) = self.port {
+            write!(f, ":{}", port)?;
+        }
+        Ok(())
+    }
+}
 
-        // Add SSH options
-        cmd.arg("-o")
-            .arg(format!("ConnectTimeout={}", timeout_secs))
-            .arg("-o")
-            .arg("StrictHostKeyChecking=no")
-            .arg("-o")
-            .arg("UserKnownHostsFile=/dev/null")
-            .arg("-o")
-            .arg("LogLevel=ERROR")
-            .arg("-o")
-            .arg("PasswordAuthentication=yes")
-            .arg("-o")
-            .arg("PubkeyAuthentication=no");
+/// Parse a comma-separated jump chain such as `ops@bastion:2222,inner`
+pub fn parse_jump_hosts(spec: &str) -> Result<Vec<JumpHost>> {
+    spec.split(',').map(str::parse).collect()
+}
 
-        // Add port if not default
-        if self.config.port != 22 {
-            cmd.arg("-p").arg(self.config.port.to_string());
+/// Find the ProxyJump value that applies to `host` in an ssh_config file. Like ssh,
+/// the first value found wins. `Match` blocks are not evaluated and never apply.
+pub fn proxy_jump_for_host(config: &str, host: &str) -> Option<String> {
+    let mut applies = true;
+    for line in config.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
         }
+        let (keyword, value) = match line.split_once(|c: char| c.is_whitespace() || c == '=') {
+            Some((keyword, value)) => (keyword, value.trim_start_matches(['=', ' ', '\t'])),
+            None => continue,
+        };
 
-        // Add host and command
-        cmd.arg(host_spec).arg(command);
+        if keyword.eq_ignore_ascii_case("Host") {
+            applies = host_patterns_match(value, host);
+        } else if keyword.eq_ignore_ascii_case("Match") {
+            applies = false;
+        } else if applies && keyword.eq_ignore_ascii_case("ProxyJump") {
+            let value = value.trim();
+            return if value.eq_ignore_ascii_case("none") {
+                None
+            } else {
+                Some(value.to_string())
+            };
+        }
+    }
+    None
+}
 
-        debug!("Running SSH command with sshpass: {:?}", cmd);
+/// ssh_config `Host` line matching: any positive pattern matches and no `!` pattern does
+fn host_patterns_match(patterns: &str, host: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns.split_whitespace() {
+        if let Some(negated) = pattern.strip_prefix('!') {
+            if glob_match(negated, host) {
+                return false;
+            }
+        } else if glob_match(pattern, host) {
+            matched = true;
+        }
+    }
+    matched
+}
 
-        cmd.stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output()
-            .context("Failed to execute sshpass command")
+/// Match `*` and `?` wildcards, case-insensitively as ssh does for host names
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            p = star + 1;
+            t = matched + 1;
+            backtrack = Some((star, matched + 1));
+        } else {
+            return false;
+        }
     }
+    pattern[p..].iter().all(|&c| c == '*')
+}
 
-    /// Execute command using direct SSH (key-based or agent authentication)
-    fn execute_with_ssh(
-        &self,
-        command: &str,
-        host_spec: &str,
-        timeout_secs: u64,
-    ) -> Result<std::process::Output> {
-        let mut ssh_cmd = Command::new("ssh");
+/// Why ssh itself failed, as opposed to the remote command
+#[derive(Debug, Clone, PartialEq)]
+pub enum SshFailureKind {
+    /// Every offered credential was rejected
+    AuthFailed,
+    /// The host's key differs from the one in known_hosts
+    HostKeyMismatch,
+    /// The connection attempt timed out
+    Timeout,
+    /// DNS failure, refused connection or other network error
+    Unreachable,
+}
 
-        // Add SSH options
-        ssh_cmd
-            .arg("-o")
-            .arg(format!("ConnectTimeout={}", timeout_secs))
-            .arg("-o")
-            .arg("StrictHostKeyChecking=no")
-            .arg("-o")
-            .arg("UserKnownHostsFile=/dev/null")
-            .arg("-o")
-            .arg("LogLevel=ERROR");
+/// SSH connection error with a message that says what to do about it
+#[derive(Debug, Clone)]
+pub struct SshError {
+    pub host: String,
+    pub kind: SshFailureKind,
+    pub detail: String,
+}
 
-        // Add port if not default
-        if self.config.port != 22 {
-            ssh_cmd.arg("-p").arg(self.config.port.to_string());
+impl fmt::Display for SshError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            SshFailureKind::AuthFailed => write!(
+                f,
+                "SSH authentication failed for {}: {}. Load a key into ssh-agent (ssh-add), pass --ssh-key, or use --ssh-password",
+                self.host, self.detail
+            ),
+            SshFailureKind::HostKeyMismatch => write!(
+                f,
+                "SSH host key verification failed for {}: {}. Check the new key and update ~/.ssh/known_hosts, or pass --ssh-insecure to skip verification",
+                self.host, self.detail
+            ),
+            SshFailureKind::Timeout => write!(
+                f,
+                "SSH connection to {} timed out: {}. Check the host, --ssh-jump and --ssh-timeout",
+                self.host, self.detail
+            ),
+            SshFailureKind::Unreachable => {
+                write!(f, "Could not reach {} over SSH: {}", self.host, self.detail)
+            }
         }
+    }
+}
+
+impl std::error::Error for SshError {}
+
+/// Classify ssh's own error output (ssh exits with 255 when it, not the remote
+/// command, fails). Returns None when the output is not recognized.
+pub fn classify_ssh_failure(stderr: &str) -> Option<SshFailureKind> {
+    let lower = stderr.to_lowercase();
+    if lower.contains("remote host identification has changed")
+        || lower.contains("host key verification failed")
+    {
+        Some(SshFailureKind::HostKeyMismatch)
+    } else if lower.contains("permission denied")
+        || lower.contains("too many authentication failures")
+    {
+        Some(SshFailureKind::AuthFailed)
+    } else if lower.contains("timed out") {
+        Some(SshFailureKind::Timeout)
+    } else if lower.contains("could not resolve hostname")
+        || lower.contains("connection refused")
+        || lower.contains("no route to host")
+        || lower.contains("network is unreachable")
+        || lower.contains("connection closed by")
+    {
+        Some(SshFailureKind::Unreachable)
+    } else {
+        None
+    }
+}
 
-        // Add key file if specified
-        if let Some(key_path) = &self.config.key_path {
-            ssh_cmd.arg("-i").arg(key_path);
+/// SSH remote connection manager using system SSH
+pub struct SshRemote {
+    config: SshConfig,
+    runner: Arc<dyn CommandRunner + Send + Sync>,
+}
+
+impl SshRemote {
+    /// Create a new SSH remote connection
+    #[allow(dead_code)]
+    pub fn new(config: SshConfig) -> Self {
+        Self::with_runner(config, Arc::new(SystemCommandRunner))
+    }
+
+    /// Run ssh through `runner`; streamed commands always spawn ssh directly
+    pub fn with_runner(config: SshConfig, runner: Arc<dyn CommandRunner + Send + Sync>) -> Self {
+        Self { config, runner }
+    }
+
+    /// Program and arguments that run ssh with `args`, through sshpass when a
+    /// password is configured
+    fn invocation(&self, args: Vec<String>) -> (String, Vec<String>) {
+        match &self.config.password {
+            Some(password) => {
+                let mut sshpass_args = vec!["-p".to_string(), password.clone(), "ssh".to_string()];
+                sshpass_args.extend(args);
+                ("sshpass".to_string(), sshpass_args)
+            }
+            None => ("ssh".to_string(), args),
         }
+    }
 
-        // Add host and command
-        ssh_cmd.arg(host_spec).arg(command);
+    /// Run ssh with `args` through the runner
+    fn run_ssh(&self, args: Vec<String>) -> Result<CommandOutput> {
+        let (program, args) = self.invocation(args);
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        self.runner.run(&program, &args)
+    }
 
-        debug!("Running SSH command: {:?}", ssh_cmd);
+    /// Build the ssh (or sshpass) invocation for `command`
+    ///
+    /// # Authentication
+    /// - Without a key or password, ssh uses the running ssh-agent (SSH_AUTH_SOCK)
+    ///   and the default identity files
+    /// - Password authentication requires `sshpass` to be installed on the system,
+    ///   as SSH requires a TTY for interactive password prompts
+    fn build_command(&self, command: &str) -> Result<Command> {
+        self.check_sshpass()?;
+        let (program, args) = self.invocation(self.config.ssh_args(command));
+        debug!("Running SSH command: {} {:?}", program, args);
+        let mut cmd = Command::new(program);
+        cmd.args(&args);
+        Ok(cmd)
+    }
 
-        ssh_cmd
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .output()
+    /// Password authentication needs sshpass; without a key or password, note that
+    /// ssh falls back to its default identities
+    fn check_sshpass(&self) -> Result<()> {
+        if self.config.password.is_some() {
+            let sshpass_check = Command::new("which").arg("sshpass").output();
+            if !sshpass_check.map(|o| o.status.success()).unwrap_or(false) {
+                return Err(anyhow::anyhow!(
+                    "Password authentication requires 'sshpass' to be installed. \
+                    SSH requires a TTY for password prompts, which is not available in this context. \
+                    Please install sshpass (e.g., 'apt install sshpass' or 'brew install sshpass') \
+                    or use SSH key authentication instead (--ssh-key)."
+                ));
+            }
+        } else if self.config.key_path.is_none() && std::env::var_os("SSH_AUTH_SOCK").is_none() {
+            debug!("No --ssh-key and no ssh-agent; ssh will try its default identity files");
+        }
+        Ok(())
+    }
+
+    /// Turn ssh's own failures (exit code 255) into an [`SshError`]
+    fn connection_error(&self, output: &CommandOutput) -> Option<anyhow::Error> {
+        if output.code != Some(255) {
+            return None;
+        }
+        let kind = classify_ssh_failure(&output.stderr)?;
+        Some(
+            SshError {
+                host: self.config.host.clone(),
+                kind,
+                detail: output
+                    .stderr
+                    .trim()
+                    .lines()
+                    .last()
+                    .unwrap_or_default()
+                    .to_string(),
+            }
+            .into(),
+        )
+    }
+
+    /// Execute a command on the remote host and return raw output (for callers that need exit code).
+    fn execute_command_output(&self, command: &str) -> Result<CommandOutput> {
+        debug!("Executing remote command: {}", command);
+        self.check_sshpass()?;
+        self.run_ssh(self.config.ssh_args(command))
             .context("Failed to execute SSH command")
     }
 
-    /// Execute command via SSH and stream stdout/stderr to the local process (for long-running commands like --watch).
-    fn execute_with_ssh_streaming(
-        &self,
-        command: &str,
-        host_spec: &str,
-        timeout_secs: u64,
-    ) -> Result<std::process::ExitStatus> {
-        let mut ssh_cmd = Command::new("ssh");
-        ssh_cmd
-            .arg("-o")
-            .arg(format!("ConnectTimeout={}", timeout_secs))
-            .arg("-o")
-            .arg("StrictHostKeyChecking=no")
-            .arg("-o")
-            .arg("UserKnownHostsFile=/dev/null")
-            .arg("-o")
-            .arg("LogLevel=ERROR");
-
-        if self.config.port != 22 {
-            ssh_cmd.arg("-p").arg(self.config.port.to_string());
-        }
-        if let Some(key_path) = &self.config.key_path {
-            ssh_cmd.arg("-i").arg(key_path);
-        }
-        ssh_cmd.arg(host_spec).arg(command);
-
-        let mut child = ssh_cmd
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .stdin(Stdio::null())
-            .spawn()
-            .context("Failed to spawn SSH command")?;
+    /// Execute a command on the remote host
+    pub fn execute_command(&self, command: &str) -> Result<String> {
+        let output = self.execute_command_output(command)?;
 
-        let mut stdout = child.stdout.take().unwrap();
-        let mut stderr = child.stderr.take().unwrap();
-        let mut local_stdout = std::io::stdout();
-        let mut local_stderr = std::io::stderr();
-        std::thread::scope(|s| {
-            s.spawn(|| {
-                let _ = std::io::copy(&mut stdout, &mut local_stdout);
-            });
-            s.spawn(|| {
-                let _ = std::io::copy(&mut stderr, &mut local_stderr);
-            });
-        });
-        child.wait().context("Failed to wait for SSH command")
+        if !output.success {
+            if let Some(e) = self.connection_error(&output) {
+                return Err(e);
+            }
+            return Err(anyhow::anyhow!(
+                "SSH command failed with exit code {}: {}",
+                output.code.unwrap_or(-1),
+                output.stderr
+            ));
+        }
+
+        let stdout = output.stdout;
+        debug!(
+            "Command executed successfully, output length: {} bytes",
+            stdout.len()
+        );
+        Ok(stdout)
     }
 
-    /// Execute command via sshpass and stream stdout/stderr to the local process.
-    fn execute_with_sshpass_streaming(
-        &self,
-        command: &str,
-        password: &str,
-        host_spec: &str,
-        timeout_secs: u64,
-    ) -> Result<std::process::ExitStatus> {
-        let mut cmd = Command::new("sshpass");
-        cmd.arg("-p").arg(password).arg("ssh");
-        cmd.arg("-o")
-            .arg(format!("ConnectTimeout={}", timeout_secs))
-            .arg("-o")
-            .arg("StrictHostKeyChecking=no")
-            .arg("-o")
-            .arg("UserKnownHostsFile=/dev/null")
-            .arg("-o")
-            .arg("LogLevel=ERROR")
-            .arg("-o")
-            .arg("PasswordAuthentication=yes")
-            .arg("-o")
-            .arg("PubkeyAuthentication=no");
-        if self.config.port != 22 {
-            cmd.arg("-p").arg(self.config.port.to_string());
-        }
-        cmd.arg(host_spec).arg(command);
-
-        let mut child = cmd
+    /// Execute a command on the remote host and stream output to local stdout/stderr.
+    /// Use this for long-running commands (e.g. --watch) so the process does not hang.
+    fn execute_command_streaming(&self, command: &str) -> Result<std::process::ExitStatus> {
+        let mut child = self
+            .build_command(command)?
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .stdin(Stdio::null())
             .spawn()
-            .context("Failed to spawn sshpass command")?;
+            .context("Failed to spawn SSH command")?;
 
         let mut stdout = child.stdout.take().unwrap();
         let mut stderr = child.stderr.take().unwrap();
@@ -295,20 +542,7 @@ impl SshRemote {
                 let _ = std::io::copy(&mut stderr, &mut local_stderr);
             });
         });
-        child.wait().context("Failed to wait for sshpass command")
-    }
-
-    /// Execute a command on the remote host and stream output to local stdout/stderr.
-    /// Use this for long-running commands (e.g. --watch) so the process does not hang.
-    fn execute_command_streaming(&self, command: &str) -> Result<std::process::ExitStatus> {
-        let timeout_secs = self.config.timeout.as_secs();
-        let host_spec = format!("{}@{}", self.config.username, self.config.host);
-
-        if let Some(password) = &self.config.password {
-            self.execute_with_sshpass_streaming(command, password, &host_spec, timeout_secs)
-        } else {
-            self.execute_with_ssh_streaming(command, &host_spec, timeout_secs)
-        }
+        child.wait().context("Failed to wait for SSH command")
     }
 
     /// Execute gpukill command on remote host
@@ -342,10 +576,10 @@ impl SshRemote {
     /// reporting "gpukill not available".
     pub fn check_gpukill_availability(&self) -> Result<bool> {
         let output = self.execute_command_output("which gpukill")?;
-        let code = output.status.code();
-        let stdout_trim = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let code = output.code;
+        let stdout_trim = output.stdout.trim().to_string();
 
-        if output.status.success() {
+        if output.success {
             let available = !stdout_trim.is_empty();
             if available {
                 info!("gpukill is available on remote host");
@@ -360,11 +594,13 @@ impl SshRemote {
             return Ok(false);
         }
         // Any other exit code or failure (e.g. SSH connection/auth error) is propagated
-        let stderr = String::from_utf8_lossy(&output.stderr);
+        if let Some(e) = self.connection_error(&output) {
+            return Err(e);
+        }
         Err(anyhow::anyhow!(
             "SSH command failed with exit code {}: {}",
             code.unwrap_or(-1),
-            stderr
+            output.stderr
         ))
     }
 
@@ -382,6 +618,183 @@ impl SshRemote {
     }
 }
 
+/// An SSH connection kept open across several commands through OpenSSH connection
+/// multiplexing: a background master connection listens on a control socket and
+/// each command runs over it instead of connecting and authenticating again. If the
+/// master cannot be started, or stops answering its keepalive check, the session
+/// falls back to a fresh connection per command.
+pub struct RemoteSession {
+    config: SshConfig,
+    runner: Arc<dyn CommandRunner + Send + Sync>,
+    /// Commands go through this remote; it uses the master while one is up
+    remote: SshRemote,
+    /// Control socket of the master connection, while it is up
+    control_path: Option<PathBuf>,
+}
+
+impl RemoteSession {
+    /// Open a session to the host in `config`
+    pub fn open(config: SshConfig) -> Self {
+        Self::open_with_runner(config, Arc::new(SystemCommandRunner))
+    }
+
+    /// Open a session whose ssh invocations go through `runner`
+    pub fn open_with_runner(
+        config: SshConfig,
+        runner: Arc<dyn CommandRunner + Send + Sync>,
+    ) -> Self {
+        let mut session = Self {
+            remote: SshRemote::with_runner(config.clone(), runner.clone()),
+            config,
+            runner,
+            control_path: None,
+        };
+        if cfg!(unix) {
+            session.start_master();
+        }
+        session
+    }
+
+    fn start_master(&mut self) {
+        let control_path = control_socket_path();
+        let config = self.config.clone().with_control_path(control_path.clone());
+        let master = SshRemote::with_runner(config.clone(), self.runner.clone());
+        let started = master
+            .check_sshpass()
+            .and_then(|_| master.run_ssh(config.master_args()));
+        match started {
+            Ok(output) if output.success => {
+                debug!(
+                    "Opened SSH master connection to {} on {}",
+                    self.config.host,
+                    control_path.display()
+                );
+                self.remote = master;
+                self.control_path = Some(control_path);
+            }
+            Ok(output) => debug!(
+                "Could not open a shared SSH connection to {} ({}); connecting per command",
+                self.config.host,
+                output.stderr.trim()
+            ),
+            Err(e) => debug!(
+                "Could not open a shared SSH connection to {} ({}); connecting per command",
+                self.config.host, e
+            ),
+        }
+    }
+
+    /// Whether commands currently share the master connection
+    pub fn is_multiplexed(&self) -> bool {
+        self.control_path.is_some()
+    }
+
+    /// Fall back to per-command connections if the master stopped answering
+    fn ensure_alive(&mut self) {
+        if !self.is_multiplexed() {
+            return;
+        }
+        let alive = self
+            .remote
+            .run_ssh(self.remote.config.control_args("check"))
+            .map(|output| output.success)
+            .unwrap_or(false);
+        if !alive {
+            warn!(
+                "Shared SSH connection to {} failed its keepalive check; connecting per command",
+                self.config.host
+            );
+            self.drop_master();
+        }
+    }
+
+    /// Stop using the master connection and remove its socket
+    fn drop_master(&mut self) {
+        if let Some(control_path) = self.control_path.take() {
+            let _ = std::fs::remove_file(&control_path);
+        }
+        self.remote = SshRemote::with_runner(self.config.clone(), self.runner.clone());
+    }
+
+    /// Run a command on the remote host
+    #[allow(dead_code)]
+    pub fn execute_command(&mut self, command: &str) -> Result<String> {
+        self.ensure_alive();
+        self.remote.execute_command(command)
+    }
+
+    /// Run gpukill on the remote host
+    pub fn execute_gpukill(&mut self, args: &[String]) -> Result<String> {
+        self.ensure_alive();
+        self.remote.execute_gpukill(args)
+    }
+
+    /// Run gpukill on the remote host and stream its output, e.g. for `--watch`.
+    /// A stream that ssh drops (exit code 255) is reopened over the session, up to
+    /// a few times in a row.
+    pub fn execute_gpukill_streaming(
+        &mut self,
+        args: &[String],
+    ) -> Result<std::process::ExitStatus> {
+        let mut attempts = 0;
+        loop {
+            self.ensure_alive();
+            let status = self.remote.execute_gpukill_streaming(args)?;
+            if status.code() != Some(255) || attempts >= WATCH_RECONNECT_ATTEMPTS {
+                return Ok(status);
+            }
+            attempts += 1;
+            warn!(
+                "Connection to {} dropped; reconnecting ({}/{})",
+                self.config.host, attempts, WATCH_RECONNECT_ATTEMPTS
+            );
+        }
+    }
+
+    /// Check if gpukill is available on the remote host
+    pub fn check_gpukill_availability(&mut self) -> Result<bool> {
+        self.ensure_alive();
+        self.remote.check_gpukill_availability()
+    }
+
+    /// Get remote host information
+    pub fn get_host_info(&mut self) -> Result<RemoteHostInfo> {
+        self.ensure_alive();
+        self.remote.get_host_info()
+    }
+
+    /// Close the master connection
+    #[allow(dead_code)]
+    pub fn close(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        if self.is_multiplexed() {
+            if let Err(e) = self.remote.run_ssh(self.remote.config.control_args("exit")) {
+                debug!("Failed to close SSH master connection: {}", e);
+            }
+            self.drop_master();
+        }
+    }
+}
+
+impl Drop for RemoteSession {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// A control socket path unique to this process and session
+fn control_socket_path() -> PathBuf {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    std::env::temp_dir().join(format!(
+        "gpukill-ssh-{}-{}.sock",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::SeqCst)
+    ))
+}
+
 /// Information about the remote host
 #[derive(Debug, Clone)]
 pub struct RemoteHostInfo {
@@ -394,7 +807,8 @@ pub struct RemoteHostInfo {
 /// Execute a local gpukill command with remote forwarding.
 /// When `local_args` contains `--watch`, streams output instead of buffering so the process does not hang.
 pub fn execute_remote_operation(config: SshConfig, local_args: &[String]) -> Result<()> {
-    let remote = SshRemote::new(config);
+    // The availability check, host info and the command itself share one connection
+    let mut remote = RemoteSession::open(config);
 
     // Check if gpukill is available on remote host (propagates connection/auth errors)
     if !remote.check_gpukill_availability()? {
@@ -428,6 +842,72 @@ pub fn execute_remote_operation(config: SshConfig, local_args: &[String]) -> Res
     Ok(())
 }
 
+/// Result of running gpukill on one host of a multi-host operation
+#[derive(Debug)]
+pub struct HostOutcome {
+    pub host: String,
+    pub result: Result<String>,
+}
+
+/// Execute gpukill on several hosts, keeping at most `parallelism` SSH sessions in flight.
+/// Each host's failure is captured in its outcome so one unreachable host does not abort
+/// the others. Outcomes are returned in the same order as `configs`.
+pub fn execute_remote_fanout(
+    configs: Vec<SshConfig>,
+    args: &[String],
+    parallelism: usize,
+) -> Vec<HostOutcome> {
+    fan_out(&configs, parallelism, |config| {
+        let mut remote = RemoteSession::open(config.clone());
+        let result = match remote.check_gpukill_availability() {
+            Ok(true) => remote.execute_gpukill(args),
+            Ok(false) => Err(anyhow::anyhow!(
+                "gpukill is not available on the remote host"
+            )),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = &result {
+            warn!("Remote operation on {} failed: {}", config.host, e);
+        }
+        HostOutcome {
+            host: config.host.clone(),
+            result,
+        }
+    })
+}
+
+/// Apply `f` to every item using a bounded pool of worker threads, preserving input order.
+fn fan_out<T, R, F>(items: &[T], parallelism: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new(items.iter().map(|_| None).collect());
+    let workers = parallelism.clamp(1, items.len().max(1));
+
+    std::thread::scope(|s| {
+        for _ in 0..workers {
+            s.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::SeqCst);
+                let Some(item) = items.get(index) else {
+                    break;
+                };
+                let result = f(item);
+                results.lock().unwrap_or_else(|e| e.into_inner())[index] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap_or_else(|e| e.into_inner())
+        .into_iter()
+        .map(|r| r.expect("every fan-out item is processed"))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -455,6 +935,275 @@ mod tests {
         assert_eq!(config.timeout, Duration::from_secs(60));
     }
 
+    #[test]
+    fn test_parse_jump_hosts() {
+        let jumps = parse_jump_hosts("ops@bastion.example.com:2222,inner").unwrap();
+        assert_eq!(
+            jumps,
+            vec![
+                JumpHost {
+                    user: Some("ops".to_string()),
+                    host: "bastion.example.com".to_string(),
+                    port: Some(2222),
+                },
+                JumpHost {
+                    user: None,
+                    host: "inner".to_string(),
+                    port: None,
+                },
+            ]
+        );
+        assert_eq!(jumps[0].to_string(), "ops@bastion.example.com:2222");
+
+        assert!(parse_jump_hosts("bastion:ssh").is_err());
+        assert!(parse_jump_hosts("@bastion").is_err());
+        assert!(parse_jump_hosts("ops@").is_err());
+    }
+
+    #[test]
+    fn test_proxy_jump_from_ssh_config() {
+        let config = "\
+# global defaults
+ServerAliveInterval 30
+
+Host gpu-*.internal !gpu-dev.internal
+    User ops
+    ProxyJump ops@bastion:2222
+
+Host gpu-dev.internal
+    ProxyJump=none
+
+Match host legacy
+    ProxyJump legacy-bastion
+
+Host *
+    ProxyJump fallback
+";
+        assert_eq!(
+            proxy_jump_for_host(config, "GPU-01.internal").as_deref(),
+            Some("ops@bastion:2222")
+        );
+        // Negated pattern skips the first block, and `none` disables the jump
+        assert_eq!(proxy_jump_for_host(config, "gpu-dev.internal"), None);
+        // Match blocks are not evaluated, so the catch-all applies
+        assert_eq!(
+            proxy_jump_for_host(config, "legacy").as_deref(),
+            Some("fallback")
+        );
+        assert_eq!(proxy_jump_for_host("Host a\n  User x\n", "a"), None);
+    }
+
+    #[test]
+    fn test_ssh_args_default_uses_agent_and_known_hosts() {
+        let config = SshConfig::new("gpu-01".to_string(), 22, "alice".to_string());
+        let args = config.ssh_args("which gpukill");
+
+        assert!(args.contains(&"StrictHostKeyChecking=accept-new".to_string()));
+        assert!(!args.iter().any(|a| a.contains("UserKnownHostsFile")));
+        assert!(args.contains(&"BatchMode=yes".to_string()));
+        assert!(!args.contains(&"-i".to_string()));
+        assert!(!args.contains(&"-J".to_string()));
+        assert_eq!(
+            &args[args.len() - 2..],
+            &["alice@gpu-01".to_string(), "which gpukill".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_ssh_args_with_jump_key_and_insecure() {
+        let config = SshConfig::new("gpu-01".to_string(), 2200, "alice".to_string())
+            .with_key_path("/keys/id".to_string())
+            .with_jump_hosts(parse_jump_hosts("ops@bastion:2222").unwrap())
+            .with_insecure(true)
+            .with_timeout(Duration::from_secs(5));
+        let args = config.ssh_args("hostname");
+        let pos = |flag: &str| args.iter().position(|a| a == flag).unwrap();
+
+        assert_eq!(args[pos("-J") + 1], "ops@bastion:2222");
+        assert_eq!(args[pos("-p") + 1], "2200");
+        assert_eq!(args[pos("-i") + 1], "/keys/id");
+        assert!(args.contains(&"ConnectTimeout=5".to_string()));
+        assert!(args.contains(&"StrictHostKeyChecking=no".to_string()));
+        assert!(args.contains(&"UserKnownHostsFile=/dev/null".to_string()));
+
+        // Password auth turns off batch mode so sshpass can answer the prompt
+        let args = config
+            .with_password("secret".to_string())
+            .ssh_args("hostname");
+        assert!(args.contains(&"PasswordAuthentication=yes".to_string()));
+        assert!(!args.contains(&"BatchMode=yes".to_string()));
+        assert!(!args.contains(&"secret".to_string()));
+    }
+
+    /// Stands in for ssh: answers master, control and remote commands and records
+    /// every invocation
+    struct MockTransport {
+        master_ok: bool,
+        check_ok: std::sync::atomic::AtomicBool,
+        calls: Mutex<Vec<Vec<String>>>,
+    }
+
+    impl MockTransport {
+        fn new(master_ok: bool) -> Arc<Self> {
+            Arc::new(Self {
+                master_ok,
+                check_ok: std::sync::atomic::AtomicBool::new(true),
+                calls: Mutex::new(Vec::new()),
+            })
+        }
+
+        fn calls(&self) -> Vec<Vec<String>> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
+
+    impl CommandRunner for MockTransport {
+        fn run(&self, program: &str, args: &[&str]) -> Result<CommandOutput> {
+            assert_eq!(program, "ssh");
+            self.calls
+                .lock()
+                .unwrap()
+                .push(args.iter().map(|a| a.to_string()).collect());
+            let success = if args.contains(&"-M") {
+                self.master_ok
+            } else if let Some(i) = args.iter().position(|a| *a == "-O") {
+                args[i + 1] != "check" || self.check_ok.load(Ordering::SeqCst)
+            } else {
+                true
+            };
+            let stdout = match *args.last().unwrap() {
+                "which gpukill" => "/usr/local/bin/gpukill\n",
+                "hostname" => "gpu-01\n",
+                command if command.starts_with("gpukill ") => "GPU 0: idle\n",
+                _ => "",
+            };
+            Ok(CommandOutput {
+                success,
+                code: Some(if success { 0 } else { 255 }),
+                stdout: stdout.to_string(),
+                stderr: String::new(),
+            })
+        }
+    }
+
+    fn uses_master(args: &[String]) -> bool {
+        args.iter().any(|a| a.starts_with("ControlPath="))
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remote_session_reuses_master_connection() {
+        let transport = MockTransport::new(true);
+        let config = SshConfig::new("gpu-01".to_string(), 22, "alice".to_string());
+        let mut session = RemoteSession::open_with_runner(config.clone(), transport.clone());
+        assert!(session.is_multiplexed());
+
+        assert!(session.check_gpukill_availability().unwrap());
+        assert_eq!(session.get_host_info().unwrap().hostname, "gpu-01");
+        for _ in 0..3 {
+            assert_eq!(
+                session.execute_gpukill(&["--list".to_string()]).unwrap(),
+                "GPU 0: idle\n"
+            );
+        }
+
+        let calls = transport.calls();
+        assert_eq!(
+            calls
+                .iter()
+                .filter(|c| c.contains(&"-M".to_string()))
+                .count(),
+            1
+        );
+        assert!(calls.iter().all(|c| uses_master(c)));
+        // Each session call checks the master once; host info runs three commands
+        let commands = calls
+            .iter()
+            .filter(|c| !c.contains(&"-O".to_string()))
+            .count()
+            - 1;
+        let checks = calls
+            .iter()
+            .filter(|c| c.contains(&"check".to_string()))
+            .count();
+        assert_eq!(commands, 7);
+        assert_eq!(checks, 5);
+
+        session.close();
+        let last = transport.calls().pop().unwrap();
+        assert_eq!(&last[..2], ["-O", "exit"]);
+        assert!(uses_master(&last));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remote_session_falls_back_when_keepalive_fails() {
+        let transport = MockTransport::new(true);
+        let config = SshConfig::new("gpu-01".to_string(), 22, "alice".to_string());
+        let mut session = RemoteSession::open_with_runner(config, transport.clone());
+        assert!(session.is_multiplexed());
+
+        transport.check_ok.store(false, Ordering::SeqCst);
+        assert_eq!(session.execute_command("hostname").unwrap(), "gpu-01\n");
+        assert!(!session.is_multiplexed());
+        let last = transport.calls().pop().unwrap();
+        assert_eq!(last.last().unwrap(), "hostname");
+        assert!(!uses_master(&last));
+
+        // No master is left to close
+        let before = transport.calls().len();
+        drop(session);
+        assert_eq!(transport.calls().len(), before);
+    }
+
+    #[test]
+    fn test_remote_session_connects_per_command_without_master() {
+        let transport = MockTransport::new(false);
+        let config = SshConfig::new("gpu-01".to_string(), 22, "alice".to_string());
+        let mut session = RemoteSession::open_with_runner(config, transport.clone());
+        assert!(!session.is_multiplexed());
+
+        assert_eq!(session.execute_command("hostname").unwrap(), "gpu-01\n");
+        let last = transport.calls().pop().unwrap();
+        assert!(!uses_master(&last));
+        assert!(!last.contains(&"-O".to_string()));
+    }
+
+    #[test]
+    fn test_classify_ssh_failure() {
+        assert_eq!(
+            classify_ssh_failure("alice@gpu-01: Permission denied (publickey)."),
+            Some(SshFailureKind::AuthFailed)
+        );
+        assert_eq!(
+            classify_ssh_failure(
+                "@@@ WARNING: REMOTE HOST IDENTIFICATION HAS CHANGED! @@@\nHost key verification failed."
+            ),
+            Some(SshFailureKind::HostKeyMismatch)
+        );
+        assert_eq!(
+            classify_ssh_failure("ssh: connect to host gpu-01 port 22: Connection timed out"),
+            Some(SshFailureKind::Timeout)
+        );
+        assert_eq!(
+            classify_ssh_failure(
+                "ssh: Could not resolve hostname gpu-99: Name or service not known"
+            ),
+            Some(SshFailureKind::Unreachable)
+        );
+        assert_eq!(
+            classify_ssh_failure("bash: gpukill: command not found"),
+            None
+        );
+
+        let err = SshError {
+            host: "gpu-01".to_string(),
+            kind: SshFailureKind::HostKeyMismatch,
+            detail: "Host key verification failed.".to_string(),
+        };
+        assert!(err.to_string().contains("--ssh-insecure"));
+    }
+
     /// Test that execute_gpukill properly escapes arguments
     ///
     /// Note: We always use Unix shell escaping for SSH targets.
@@ -522,4 +1271,41 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_fan_out_preserves_order_and_bounds_parallelism() {
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let hosts: Vec<String> = (0..10).map(|i| format!("gpu-{:02}", i)).collect();
+
+        let results = fan_out(&hosts, 3, |host| {
+            let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(20));
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            host.to_uppercase()
+        });
+
+        assert_eq!(results.len(), hosts.len());
+        assert_eq!(results[0], "GPU-00");
+        assert_eq!(results[9], "GPU-09");
+        assert!(peak.load(Ordering::SeqCst) <= 3);
+        assert!(peak.load(Ordering::SeqCst) > 1);
+    }
+
+    #[test]
+    fn test_fan_out_isolates_failures() {
+        let hosts = vec!["ok-1", "down", "ok-2"];
+        let results: Vec<Result<&str>> = fan_out(&hosts, 2, |host| {
+            if *host == "down" {
+                Err(anyhow::anyhow!("connection refused"))
+            } else {
+                Ok(*host)
+            }
+        });
+
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        assert_eq!(results[2].as_ref().unwrap(), &"ok-2");
+    }
 }
@@ -1,24 +1,381 @@
-use crate::args::OutputFormat;
-use crate::nvml_api::Snapshot;
-use crate::util::{format_memory_mb_to_gib, truncate_string};
+use crate::args::{ColorChoice, OutputFormat};
+use crate::gpu_index::GpuIdEntry;
+use crate::guard_mode::ActiveMaintenanceWindow;
+use crate::nvml_api::{DeviceError, EccCounts, GpuProc, GpuSnapshot, GpuStatus, Snapshot};
+use crate::proc_history::ProcessTrend;
+use crate::process_mgmt::{ProcessNode, ProcessTable};
+use crate::report::{CapabilitiesReport, InfoReport};
+use crate::snapshot::{GpuDelta, GpuRate, SnapshotDiff, SnapshotReport, UsageTotals};
+use crate::topology::{TopologyReport, LINK_LEGEND};
+use crate::util::{
+    format_memory_mb_to_gib, format_mib, format_mib_pair, format_start_time, truncate_string,
+};
+use crate::vendor::GpuVendor;
 // serde_json is used via serde_json::to_string_pretty
-use std::io::{self, Write};
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, IsTerminal, Write};
+use std::sync::OnceLock;
 use tabled::{
-    settings::{object::Rows, style::Style, Alignment, Modify, Padding, Width},
+    builder::Builder,
+    settings::{
+        location::ByColumnName, object::Rows, style::Style, Alignment, Modify, Padding, Width,
+    },
     Table, Tabled,
 };
 
+/// Default number of command line characters shown in process tables
+pub const DEFAULT_CMDLINE_WIDTH: usize = 40;
+
+/// Process-wide output style, set once from CLI flags and environment
+static OUTPUT_STYLE: OnceLock<OutputStyle> = OnceLock::new();
+
+/// Styling context shared by all render helpers.
+///
+/// Rich output uses ANSI colors, emoji icons and Unicode table borders; plain
+/// output is ASCII-only so it stays readable in log files and dumb terminals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputStyle {
+    pub color: bool,
+    pub unicode: bool,
+}
+
+/// Severity of a status message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageLevel {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+/// Icons used to decorate status messages in rich output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Icon {
+    Ok,
+    Info,
+    Alert,
+    Warning,
+    Action,
+    Stats,
+    List,
+    File,
+    Shield,
+    Detective,
+    Users,
+    Gpu,
+    Test,
+    Refresh,
+}
+
+impl OutputStyle {
+    pub const RICH: Self = Self {
+        color: true,
+        unicode: true,
+    };
+    pub const PLAIN: Self = Self {
+        color: false,
+        unicode: false,
+    };
+
+    /// Pick a style from --color, the NO_COLOR environment variable (any non-empty
+    /// value, see https://no-color.org) and whether stdout is a terminal. An explicit
+    /// `always` or `never` overrides the environment.
+    pub fn resolve(choice: ColorChoice, no_color_env: Option<&str>, is_tty: bool) -> Self {
+        let no_color_env = no_color_env.is_some_and(|v| !v.is_empty());
+        match choice {
+            ColorChoice::Always => Self::RICH,
+            ColorChoice::Never => Self::PLAIN,
+            ColorChoice::Auto if no_color_env || !is_tty => Self::PLAIN,
+            ColorChoice::Auto => Self::RICH,
+        }
+    }
+
+    /// Detect the style for the current process
+    pub fn detect(choice: ColorChoice) -> Self {
+        let no_color_env = std::env::var("NO_COLOR").ok();
+        Self::resolve(choice, no_color_env.as_deref(), io::stdout().is_terminal())
+    }
+
+    /// Icon followed by a space in rich output, empty in plain output
+    pub fn icon(&self, icon: Icon) -> &'static str {
+        if !self.unicode {
+            return "";
+        }
+        match icon {
+            Icon::Ok => "✅ ",
+            Icon::Info => "ℹ️ ",
+            Icon::Alert => "🚨 ",
+            Icon::Warning => "⚠️ ",
+            Icon::Action => "⚡ ",
+            Icon::Stats => "📊 ",
+            Icon::List => "📋 ",
+            Icon::File => "📁 ",
+            Icon::Shield => "🛡️ ",
+            Icon::Detective => "🕵️ ",
+            Icon::Users => "👥 ",
+            Icon::Gpu => "🖥️ ",
+            Icon::Test => "🧪 ",
+            Icon::Refresh => "🔄 ",
+        }
+    }
+
+    /// Format a status message with its level prefix
+    pub fn format_message(&self, level: MessageLevel, message: &str) -> String {
+        if !self.unicode {
+            let prefix = match level {
+                MessageLevel::Info => "INFO",
+                MessageLevel::Success => "OK",
+                MessageLevel::Warning => "WARN",
+                MessageLevel::Error => "ERROR",
+            };
+            return format!("{}: {}", prefix, strip_emoji(message));
+        }
+
+        let (prefix, color) = match level {
+            MessageLevel::Info => ("Info:", "36"),
+            MessageLevel::Success => ("Success:", "32"),
+            MessageLevel::Warning => ("Warning:", "33"),
+            MessageLevel::Error => ("Error:", "31"),
+        };
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m {}", color, prefix, message)
+        } else {
+            format!("{} {}", prefix, message)
+        }
+    }
+
+    /// Apply the border style for this output style to a table
+    pub fn style_table(&self, table: &mut Table) {
+        if self.unicode {
+            table.with(Style::modern());
+        } else {
+            table.with(Style::ascii());
+        }
+    }
+}
+
+/// Set the process-wide output style. Only the first call has an effect.
+pub fn init_output_style(style: OutputStyle) {
+    let _ = OUTPUT_STYLE.set(style);
+}
+
+/// Get the process-wide output style, detecting it if it was never set
+pub fn output_style() -> OutputStyle {
+    *OUTPUT_STYLE.get_or_init(|| OutputStyle::detect(ColorChoice::Auto))
+}
+
+/// Icon for the process-wide output style (empty in plain output)
+pub fn icon(icon: Icon) -> &'static str {
+    output_style().icon(icon)
+}
+
+/// Remove emoji and their variation selectors, e.g. from recommendation text
+/// that is stored with icons but printed in plain output
+fn strip_emoji(message: &str) -> String {
+    let mut out = String::with_capacity(message.len());
+    let mut skip_space = false;
+    for c in message.chars() {
+        let is_emoji = matches!(c as u32,
+            0x2139 | 0x2190..=0x2BFF | 0xFE0F | 0x1F000..=0x1FAFF);
+        if is_emoji {
+            skip_space = true;
+            continue;
+        }
+        if skip_space && c == ' ' {
+            skip_space = false;
+            continue;
+        }
+        skip_space = false;
+        out.push(c);
+    }
+    out
+}
+
+/// Table columns holding numbers, which are right-aligned
+const NUMERIC_COLUMNS: [&str; 9] = [
+    "MEM_USED/TOTAL",
+    "UTIL(%)",
+    "TEMP(°C)",
+    "POWER(W)",
+    "ECC(volatile)",
+    "PIDS",
+    "PID",
+    "CTR_PID",
+    "VRAM",
+];
+
+/// How memory sizes are shown in tables. JSON always carries plain MB numbers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MemoryUnits {
+    /// The largest binary unit that keeps the size at least 1, e.g. "512 MiB", "80.0 GiB"
+    #[default]
+    Human,
+    /// Plain MB numbers (`--raw-units`)
+    RawMb,
+}
+
+impl MemoryUnits {
+    fn format(self, mb: u32) -> String {
+        match self {
+            MemoryUnits::Human => format_mib(u64::from(mb)),
+            MemoryUnits::RawMb => format!("{} MB", mb),
+        }
+    }
+
+    fn format_pair(self, used_mb: u32, total_mb: u32) -> String {
+        match self {
+            MemoryUnits::Human => format_mib_pair(u64::from(used_mb), u64::from(total_mb)),
+            MemoryUnits::RawMb => format!("{}/{} MB", used_mb, total_mb),
+        }
+    }
+}
+
 /// Render GPU information to various output formats
 #[derive(Clone)]
 pub struct Renderer {
     output_format: OutputFormat,
+    style: OutputStyle,
+    /// GPUs currently in a Guard Mode maintenance window, with the window's message
+    maintenance: BTreeMap<u16, String>,
+    /// Group GPUs into per-vendor sections even when only one vendor is present
+    vendor_sections: bool,
+    /// Characters of each command line shown in process tables; None shows them in full
+    cmdline_width: Option<usize>,
+    /// Per-GPU rates for `--watch --show-rates`; None hides the rate columns
+    rates: Option<BTreeMap<u16, GpuRate>>,
+    /// Per-process sparklines by (GPU index, PID) for `--watch`; None hides the trend columns
+    trends: Option<BTreeMap<(u16, u32), ProcessTrend>>,
+    /// Print the totals footer after everything else rather than under the GPU table
+    pin_totals: bool,
+    memory_units: MemoryUnits,
 }
 
 #[allow(dead_code)]
 impl Renderer {
     /// Create a new renderer
     pub fn new(output_format: OutputFormat) -> Self {
-        Self { output_format }
+        Self::with_style(output_format, output_style())
+    }
+
+    /// Create a renderer with an explicit output style
+    pub fn with_style(output_format: OutputFormat, style: OutputStyle) -> Self {
+        Self {
+            output_format,
+            style,
+            maintenance: BTreeMap::new(),
+            vendor_sections: false,
+            cmdline_width: Some(DEFAULT_CMDLINE_WIDTH),
+            rates: None,
+            trends: None,
+            pin_totals: false,
+            memory_units: MemoryUnits::Human,
+        }
+    }
+
+    /// Always group GPUs by vendor (`--vendor all`). Without this, sections are
+    /// only used when GPUs from more than one vendor are present.
+    pub fn with_vendor_sections(mut self, force: bool) -> Self {
+        self.vendor_sections = force;
+        self
+    }
+
+    /// Truncate process command lines to `width` characters, or show them in full with None
+    pub fn with_cmdline_width(mut self, width: Option<usize>) -> Self {
+        self.cmdline_width = width;
+        self
+    }
+
+    /// Annotate GPUs that are inside an active maintenance window
+    pub fn with_maintenance_windows(mut self, windows: &[ActiveMaintenanceWindow]) -> Self {
+        self.maintenance = windows
+            .iter()
+            .map(|w| (w.gpu_index, w.message.clone()))
+            .collect();
+        self
+    }
+
+    /// Keep the totals footer on the last line of the output, as `--watch` does
+    pub fn with_pinned_totals(mut self) -> Self {
+        self.pin_totals = true;
+        self
+    }
+
+    /// Show memory in tables as plain MB numbers instead of MiB/GiB (`--raw-units`)
+    pub fn with_raw_units(mut self, raw: bool) -> Self {
+        self.memory_units = if raw {
+            MemoryUnits::RawMb
+        } else {
+            MemoryUnits::Human
+        };
+        self
+    }
+
+    /// Show rate columns in the GPU table; GPUs without a rate get blank cells
+    pub fn with_rates(mut self, rates: BTreeMap<u16, GpuRate>) -> Self {
+        self.rates = Some(rates);
+        self
+    }
+
+    /// Show memory and utilization trend columns in the process table; processes
+    /// without a history get blank cells
+    pub fn with_process_trends(mut self, trends: BTreeMap<(u16, u32), ProcessTrend>) -> Self {
+        self.trends = Some(trends);
+        self
+    }
+
+    /// Whether operation results are printed as one JSON document
+    pub fn is_json(&self) -> bool {
+        self.output_format == OutputFormat::Json
+    }
+
+    /// Print a status message for an operation. With JSON output every message
+    /// goes to stderr so stdout carries only the result document.
+    pub fn message(&self, level: MessageLevel, message: &str) {
+        let line = self.style.format_message(level, message);
+        if self.is_json() || matches!(level, MessageLevel::Warning | MessageLevel::Error) {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    }
+
+    pub fn info(&self, message: &str) {
+        self.message(MessageLevel::Info, message);
+    }
+
+    pub fn success(&self, message: &str) {
+        self.message(MessageLevel::Success, message);
+    }
+
+    pub fn warning(&self, message: &str) {
+        self.message(MessageLevel::Warning, message);
+    }
+
+    pub fn error(&self, message: &str) {
+        self.message(MessageLevel::Error, message);
+    }
+
+    /// Print preformatted text such as a process tree or a prompt; to stderr with
+    /// JSON output
+    pub fn text(&self, text: &str) {
+        if self.is_json() {
+            eprint!("{}", text);
+        } else {
+            print!("{}", text);
+            let _ = io::stdout().flush();
+        }
+    }
+
+    /// Print the result of a kill, reset or config change. Table output has already
+    /// been printed as messages while the operation ran, so only JSON prints here.
+    pub fn render_result<T: serde::Serialize>(
+        &self,
+        result: &T,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.is_json() {
+            println!("{}", serde_json::to_string_pretty(result)?);
+        }
+        Ok(())
     }
 
     /// Render a complete snapshot
@@ -28,166 +385,1285 @@ impl Renderer {
         details: bool,
     ) -> Result<(), Box<dyn std::error::Error>> {
         match self.output_format {
-            OutputFormat::Table => self.render_table(snapshot, details),
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Ndjson | OutputFormat::Html => {
+                self.render_table(snapshot, details)
+            }
             OutputFormat::Json => self.render_json(snapshot),
         }
     }
 
-    /// Render as a table
-    fn render_table(
-        &self,
-        snapshot: &Snapshot,
-        details: bool,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        if details {
-            self.render_detailed_table(snapshot)
+    /// Render as a table
+    fn render_table(
+        &self,
+        snapshot: &Snapshot,
+        details: bool,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if details {
+            self.render_detailed_table(snapshot)?;
+        } else {
+            self.render_summary_table(snapshot)?;
+        }
+
+        for warning in device_error_warnings(snapshot)
+            .into_iter()
+            .chain(ecc_warnings(snapshot))
+        {
+            render_warning(&warning);
+        }
+        for gpu in &snapshot.gpus {
+            if let Some(message) = self.maintenance.get(&gpu.gpu_index) {
+                render_warning(&format!(
+                    "GPU {} (marked *) is in a maintenance window: {}",
+                    gpu.gpu_index, message
+                ));
+            }
+        }
+        if self.pin_totals {
+            if let Some(footer) = format_totals_footer(&snapshot.gpus) {
+                println!("{}", footer);
+            }
+        }
+        Ok(())
+    }
+
+    /// Render summary table (one row per GPU)
+    fn render_summary_table(&self, snapshot: &Snapshot) -> Result<(), Box<dyn std::error::Error>> {
+        println!("{}", self.format_summary_table(snapshot));
+        Ok(())
+    }
+
+    /// Format summary table (one row per GPU, including GPUs that could not be
+    /// queried), split into vendor sections on mixed-vendor hosts, with a totals
+    /// footer when there is more than one GPU
+    fn format_summary_table(&self, snapshot: &Snapshot) -> String {
+        let groups = vendor_groups(&snapshot.gpus, &snapshot.errors);
+        let mut table = if groups.len() > 1 || (self.vendor_sections && !groups.is_empty()) {
+            self.format_vendor_sections(&groups)
+        } else {
+            self.format_gpu_table(snapshot.gpus.iter(), snapshot.errors.iter())
+        };
+        if !self.pin_totals {
+            if let Some(footer) = format_totals_footer(&snapshot.gpus) {
+                table = format!("{}\n{}", table.trim_end_matches('\n'), footer);
+            }
+        }
+        table
+    }
+
+    /// Format one labeled table per vendor, each followed by a subtotal line
+    fn format_vendor_sections(&self, groups: &[VendorGroup]) -> String {
+        groups
+            .iter()
+            .map(|group| {
+                let count = group.gpus.len() + group.errors.len();
+                format!(
+                    "{} ({} GPU{})\n{}\nSubtotal: {}\n",
+                    group.vendor,
+                    count,
+                    if count == 1 { "" } else { "s" },
+                    self.format_gpu_table(group.gpus.iter().copied(), group.errors.iter().copied()),
+                    format_usage_totals(&UsageTotals::from_gpus(group.gpus.iter().copied()))
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Format a GPU table for the given GPUs, with a row in index order for each
+    /// GPU that could not be queried
+    fn format_gpu_table<'a>(
+        &self,
+        gpus: impl Iterator<Item = &'a GpuSnapshot>,
+        errors: impl Iterator<Item = &'a DeviceError>,
+    ) -> String {
+        let mut rows: Vec<(u16, SummaryRow)> = gpus
+            .map(|gpu| {
+                let mut row = summary_row(gpu, self.memory_units);
+                if self.maintenance.contains_key(&gpu.gpu_index) {
+                    row.gpu.push('*');
+                }
+                (gpu.gpu_index, row)
+            })
+            .chain(errors.map(|error| (error.gpu_index, error_row(error))))
+            .collect();
+        rows.sort_by_key(|(index, _)| *index);
+        match &self.rates {
+            // The rate columns would push the table past the wrap width and squeeze every cell
+            Some(rates) => self.format_wide_table(Table::new(
+                rows.into_iter()
+                    .map(|(index, row)| rate_row(row, rates.get(&index)))
+                    .collect::<Vec<_>>(),
+            )),
+            None => self.format_table(Table::new(
+                rows.into_iter().map(|(_, row)| row).collect::<Vec<_>>(),
+            )),
+        }
+    }
+
+    /// Apply the shared table layout and border style
+    fn format_table(&self, table: Table) -> String {
+        self.layout_table(table).with(Width::wrap(120)).to_string()
+    }
+
+    /// Apply the shared table layout without wrapping cells to the default width
+    fn format_wide_table(&self, table: Table) -> String {
+        self.layout_table(table).to_string()
+    }
+
+    fn layout_table(&self, mut table: Table) -> Table {
+        self.style.style_table(&mut table);
+        table
+            .with(Modify::new(Rows::new(1..)).with(Alignment::left()))
+            .with(Modify::new(Rows::new(1..)).with(Padding::new(1, 1, 0, 0)));
+        for column in NUMERIC_COLUMNS {
+            table.with(Modify::new(ByColumnName::new(column)).with(Alignment::right()));
+        }
+        table
+    }
+
+    /// Render detailed table (one row per process)
+    fn render_detailed_table(&self, snapshot: &Snapshot) -> Result<(), Box<dyn std::error::Error>> {
+        // First render summary
+        self.render_summary_table(snapshot)?;
+        println!();
+
+        if let Some(table) = self.format_pcie_table(&snapshot.gpus) {
+            println!("PCIe Throughput:");
+            println!("{}", table);
+            println!();
+        }
+
+        if let Some(table) = self.format_profiling_table(&snapshot.gpus) {
+            println!("Profiling (DCGM):");
+            println!("{}", table);
+            println!();
+        }
+
+        if let Some(table) = self.format_device_id_table(&snapshot.gpus) {
+            println!("Device IDs:");
+            println!("{}", table);
+            println!();
+        }
+
+        if let Some(table) = self.format_memory_health_table(&snapshot.gpus) {
+            println!("Memory Health:");
+            println!("{}", table);
+            println!();
+        }
+
+        if let Some(table) = self.format_power_limit_table(&snapshot.gpus) {
+            println!("Power Limits:");
+            println!("{}", table);
+            println!();
+        }
+
+        // Then render process details
+        if !snapshot.procs.is_empty() {
+            println!("Process Details:");
+            println!("{}", self.format_process_details(&snapshot.procs));
+        }
+
+        Ok(())
+    }
+
+    /// Format the detailed process table. A SLURM_JOB column is added when any
+    /// process belongs to a Slurm job, and trend columns when trends were given.
+    pub fn format_process_details(&self, procs: &[GpuProc]) -> String {
+        let table = if procs.iter().any(|proc| proc.slurm_job_id.is_some()) {
+            let rows: Vec<SlurmProcessRow> = procs
+                .iter()
+                .map(|proc| SlurmProcessRow {
+                    job: proc.slurm_job_id.clone().unwrap_or_else(|| "-".to_string()),
+                    row: process_row(proc, self.cmdline_width, self.memory_units),
+                })
+                .collect();
+            self.process_table(procs, rows)
+        } else {
+            let rows: Vec<ProcessRow> = procs
+                .iter()
+                .map(|proc| process_row(proc, self.cmdline_width, self.memory_units))
+                .collect();
+            self.process_table(procs, rows)
+        };
+        // Full command lines are not wrapped, so they can be copied as-is, and
+        // wrapping would break the sparklines across lines
+        if self.cmdline_width.is_none() || self.trends.is_some() {
+            self.format_wide_table(table)
+        } else {
+            self.format_table(table)
+        }
+    }
+
+    /// Build a table of `rows`, one per process in `procs`, with MEM_TREND and
+    /// UTIL_TREND columns right after VRAM when trends are shown
+    fn process_table<R: Tabled>(&self, procs: &[GpuProc], rows: Vec<R>) -> Table {
+        let Some(trends) = &self.trends else {
+            return Table::new(rows);
+        };
+        let with_trend = |mut cells: Vec<String>, trend: [String; 2]| {
+            let at = R::headers()
+                .iter()
+                .position(|header| header == "VRAM")
+                .map_or(cells.len(), |vram| vram + 1);
+            cells.splice(at..at, trend);
+            cells
+        };
+        let mut builder = Builder::default();
+        builder.push_record(with_trend(
+            R::headers().into_iter().map(|h| h.into_owned()).collect(),
+            ["MEM_TREND".to_string(), "UTIL_TREND".to_string()],
+        ));
+        for (proc, row) in procs.iter().zip(rows) {
+            let trend = trends.get(&(proc.gpu_index, proc.pid));
+            builder.push_record(with_trend(
+                row.fields().into_iter().map(|f| f.into_owned()).collect(),
+                [
+                    trend.map(|t| t.memory.clone()).unwrap_or_default(),
+                    trend.map(|t| t.utilization.clone()).unwrap_or_default(),
+                ],
+            ));
+        }
+        builder.build()
+    }
+
+    /// Format snapshots collected from several hosts as one table grouped by host.
+    /// With `details`, a combined process table follows the GPU table. The HOST
+    /// column makes these wider than the single-host tables, so they are not wrapped.
+    pub fn format_host_snapshots(&self, snapshots: &[(String, Snapshot)], details: bool) -> String {
+        let gpu_rows: Vec<HostSummaryRow> = snapshots
+            .iter()
+            .flat_map(|(host, snapshot)| {
+                snapshot.gpus.iter().map(move |gpu| HostSummaryRow {
+                    host: truncate_string(host, 20),
+                    row: summary_row(gpu, self.memory_units),
+                })
+            })
+            .collect();
+        let mut output = self.format_wide_table(Table::new(&gpu_rows));
+
+        if details {
+            let proc_rows: Vec<HostProcessRow> = snapshots
+                .iter()
+                .flat_map(|(host, snapshot)| {
+                    snapshot.procs.iter().map(move |proc| HostProcessRow {
+                        host: truncate_string(host, 20),
+                        row: process_row(proc, self.cmdline_width, self.memory_units),
+                    })
+                })
+                .collect();
+            if !proc_rows.is_empty() {
+                output.push_str("\n\nProcess Details:\n");
+                output.push_str(&self.format_wide_table(Table::new(&proc_rows)));
+            }
+        }
+
+        output
+    }
+
+    /// Render only the flat process list across all GPUs (`--list --processes-only`)
+    pub fn render_process_list(&self, procs: &[GpuProc]) -> Result<(), Box<dyn std::error::Error>> {
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(procs)?),
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Ndjson | OutputFormat::Html => {
+                println!("{}", self.format_process_list(procs))
+            }
+        }
+        Ok(())
+    }
+
+    /// Format the flat process list, in the order given
+    pub fn format_process_list(&self, procs: &[GpuProc]) -> String {
+        if procs.is_empty() {
+            return "No GPU processes running".to_string();
+        }
+        let rows: Vec<ProcessListRow> = procs
+            .iter()
+            .map(|proc| ProcessListRow {
+                gpu: proc.gpu_index.to_string(),
+                pid: proc.pid.to_string(),
+                user: truncate_string(&proc.user, 12),
+                process: truncate_string(&proc.proc_name, 20),
+                memory: self.memory_units.format(proc.used_mem_mb),
+                container: proc
+                    .container
+                    .as_ref()
+                    .map(|c| truncate_string(c, 15))
+                    .unwrap_or_else(|| "-".to_string()),
+            })
+            .collect();
+        self.format_table(Table::new(&rows))
+    }
+
+    /// Render the vendor capability matrix (`--capabilities`)
+    pub fn render_capabilities(
+        &self,
+        report: &CapabilitiesReport,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(report)?),
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Ndjson | OutputFormat::Html => {
+                println!("{}", self.format_capabilities(report))
+            }
+        }
+        Ok(())
+    }
+
+    /// Format the vendor x capability matrix
+    pub fn format_capabilities(&self, report: &CapabilitiesReport) -> String {
+        let mark = |supported: bool| if supported { "yes" } else { "no" }.to_string();
+        let rows: Vec<CapabilityRow> = report
+            .vendors
+            .iter()
+            .map(|entry| CapabilityRow {
+                vendor: entry.vendor.to_string(),
+                detected: mark(entry.detected),
+                reset: mark(entry.capabilities.reset_supported),
+                processes: mark(entry.capabilities.process_enum_supported),
+                power_limit: mark(entry.capabilities.power_limit_supported),
+                utilization: mark(entry.capabilities.util_supported),
+            })
+            .collect();
+        let mut output = self.format_table(Table::new(&rows));
+
+        if let Some(privileges) = &report.privileges {
+            let euid = privileges
+                .euid
+                .map(|euid| format!(" (euid {})", euid))
+                .unwrap_or_default();
+            let capabilities = if privileges.capabilities.is_empty() {
+                "none".to_string()
+            } else {
+                privileges.capabilities.join(", ")
+            };
+            output.push_str(&format!(
+                "\n\nRunning as {}{}; capabilities: {}\n",
+                privileges.user, euid, capabilities
+            ));
+            let rows: Vec<PrivilegeRow> = privileges
+                .operations
+                .iter()
+                .map(|op| PrivilegeRow {
+                    operation: op.operation.clone(),
+                    access: op.access.to_string(),
+                    note: op.note.clone().unwrap_or_else(|| "-".to_string()),
+                })
+                .collect();
+            output.push_str(&self.format_table(Table::new(&rows)));
+        }
+        output
+    }
+
+    /// Render the GPU link matrix (`--topology`)
+    pub fn render_topology(
+        &self,
+        report: &TopologyReport,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(report)?),
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Ndjson | OutputFormat::Html => {
+                println!("{}", self.format_topology(report))
+            }
+        }
+        Ok(())
+    }
+
+    /// Format the GPU x GPU link matrix, followed by the state of each NVLink link
+    /// and a legend
+    pub fn format_topology(&self, report: &TopologyReport) -> String {
+        if report.gpus.is_empty() {
+            return "No NVIDIA GPUs found".to_string();
+        }
+        let mut matrix = Builder::default();
+        matrix.push_record(
+            std::iter::once(String::new())
+                .chain(report.gpus.iter().map(|gpu| format!("GPU{}", gpu.index))),
+        );
+        for (gpu, links) in report.gpus.iter().zip(&report.matrix) {
+            matrix.push_record(
+                std::iter::once(format!("GPU{}", gpu.index))
+                    .chain(links.iter().map(|link| link.to_string())),
+            );
+        }
+        let mut output = self.format_table(matrix.build());
+
+        output.push_str("\n\n");
+        if report.has_nvlink() {
+            let rows: Vec<NvLinkRow> = report
+                .gpus
+                .iter()
+                .flat_map(|gpu| {
+                    gpu.nvlinks.iter().map(move |link| NvLinkRow {
+                        gpu: gpu.index,
+                        link: link.link,
+                        state: if link.active { "active" } else { "inactive" }.to_string(),
+                        remote: match link.remote_pci_bus_id.as_deref() {
+                            Some(bus_id) => match report.gpu_at(bus_id) {
+                                Some(index) => format!("GPU {}", index),
+                                None => bus_id.to_string(),
+                            },
+                            None => "-".to_string(),
+                        },
+                    })
+                })
+                .collect();
+            output.push_str(&self.format_table(Table::new(&rows)));
+        } else {
+            output.push_str("No NVLink links; GPUs are connected over PCIe only");
+        }
+
+        output.push_str("\n\nLegend:\n");
+        for line in LINK_LEGEND {
+            output.push_str("  ");
+            output.push_str(line);
+            output.push('\n');
+        }
+        output.trim_end().to_string()
+    }
+
+    /// Render the stable index map (`--list --show-ids`)
+    pub fn render_gpu_ids(&self, entries: &[GpuIdEntry]) -> Result<(), Box<dyn std::error::Error>> {
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(entries)?),
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Ndjson | OutputFormat::Html => {
+                println!("{}", self.format_gpu_ids(entries))
+            }
+        }
+        Ok(())
+    }
+
+    /// Format the stable index map; GPUs that are not present show `-` as their
+    /// enumeration index
+    pub fn format_gpu_ids(&self, entries: &[GpuIdEntry]) -> String {
+        let rows: Vec<GpuIdRow> = entries
+            .iter()
+            .map(|entry| GpuIdRow {
+                index: entry.index,
+                enumeration_index: entry
+                    .enumeration_index
+                    .map_or("-".to_string(), |index| index.to_string()),
+                id: entry
+                    .id
+                    .clone()
+                    .unwrap_or_else(|| "(unidentified)".to_string()),
+                name: entry.name.clone(),
+                present: if entry.present { "yes" } else { "no" }.to_string(),
+            })
+            .collect();
+        self.format_table(Table::new(&rows))
+    }
+
+    /// Render version, build and GPU detection details (`--info`)
+    pub fn render_info_report(
+        &self,
+        report: &InfoReport,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(report)?),
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Ndjson | OutputFormat::Html => {
+                print!("{}", self.format_info_report(report))
+            }
+        }
+        Ok(())
+    }
+
+    /// Format `--info` as one `key: value` line per field
+    pub fn format_info_report(&self, report: &InfoReport) -> String {
+        let vendors = if report.vendors.is_empty() {
+            "none".to_string()
+        } else {
+            report
+                .vendors
+                .iter()
+                .map(|vendor| vendor.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut out = format!(
+            "Version: {}\nBuild target: {}\nBuild date: {}\nVendors: {}\nGPUs: {}\n",
+            report.version, report.build_target, report.build_date, vendors, report.gpu_count
+        );
+        if let Some(error) = &report.gpu_error {
+            out.push_str(&format!("GPU detection: {}\n", error));
+        }
+        out
+    }
+
+    /// Render a comparison against a saved baseline snapshot (`--list --diff`)
+    pub fn render_snapshot_diff(
+        &self,
+        diff: &SnapshotDiff,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match self.output_format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(diff)?),
+            OutputFormat::Table | OutputFormat::Csv | OutputFormat::Ndjson | OutputFormat::Html => {
+                print!("{}", self.format_snapshot_diff(diff))
+            }
+        }
+        Ok(())
+    }
+
+    /// Format a snapshot comparison: per-GPU deltas, GPUs that came or went,
+    /// then processes that appeared (+) or disappeared (-)
+    pub fn format_snapshot_diff(&self, diff: &SnapshotDiff) -> String {
+        let mut output = format!(
+            "Comparing {} ({}) with baseline {} ({})\n",
+            diff.current_host, diff.current_ts, diff.baseline_host, diff.baseline_ts
+        );
+        if diff.is_empty() {
+            output.push_str("No changes since the baseline\n");
+            return output;
+        }
+
+        if !diff.gpus.is_empty() {
+            let rows: Vec<GpuDeltaRow> = diff.gpus.iter().map(gpu_delta_row).collect();
+            output.push_str(&self.format_table(Table::new(&rows)));
+            output.push('\n');
+        }
+        for index in &diff.added_gpus {
+            output.push_str(&format!("GPU {} is new since the baseline\n", index));
+        }
+        for index in &diff.removed_gpus {
+            output.push_str(&format!("GPU {} is missing since the baseline\n", index));
+        }
+
+        let proc_rows: Vec<ProcessChangeRow> = diff
+            .appeared
+            .iter()
+            .map(|proc| ("+", proc))
+            .chain(diff.disappeared.iter().map(|proc| ("-", proc)))
+            .map(|(change, proc)| ProcessChangeRow {
+                change: change.to_string(),
+                row: process_row(proc, self.cmdline_width, self.memory_units),
+            })
+            .collect();
+        if !proc_rows.is_empty() {
+            output.push_str("\nProcess Changes:\n");
+            output.push_str(&self.format_table(Table::new(&proc_rows)));
+            output.push('\n');
+        }
+        output
+    }
+
+    /// Format a per-host success/failure summary for multi-host operations
+    pub fn format_host_results(&self, results: &[(String, Result<(), String>)]) -> String {
+        let rows: Vec<HostResultRow> = results
+            .iter()
+            .map(|(host, result)| HostResultRow {
+                host: host.clone(),
+                status: if result.is_ok() { "ok" } else { "failed" }.to_string(),
+                detail: match result {
+                    Ok(()) => "-".to_string(),
+                    Err(e) => truncate_string(e.lines().next().unwrap_or(e).trim(), 80),
+                },
+            })
+            .collect();
+        self.format_table(Table::new(&rows))
+    }
+
+    /// Format the process trees below `roots`, showing only PIDs in `include`
+    pub fn format_process_tree(
+        &self,
+        table: &ProcessTable,
+        roots: &[u32],
+        include: &HashSet<u32>,
+        label: impl Fn(&ProcessNode) -> String,
+    ) -> String {
+        let mut output = String::new();
+        for &root in roots {
+            if let Some(node) = table.get(root) {
+                output.push_str(&label(node));
+                output.push('\n');
+                self.format_tree_children(table, root, include, &label, "", &mut output);
+            }
+        }
+        output
+    }
+
+    fn format_tree_children(
+        &self,
+        table: &ProcessTable,
+        pid: u32,
+        include: &HashSet<u32>,
+        label: &impl Fn(&ProcessNode) -> String,
+        prefix: &str,
+        output: &mut String,
+    ) {
+        let (branch, last, pipe) = if self.style.unicode {
+            ("├─ ", "└─ ", "│  ")
+        } else {
+            ("|- ", "`- ", "|  ")
+        };
+        let children: Vec<&ProcessNode> = table
+            .children(pid)
+            .iter()
+            .filter(|child| include.contains(child))
+            .filter_map(|&child| table.get(child))
+            .collect();
+        for (i, child) in children.iter().enumerate() {
+            let is_last = i + 1 == children.len();
+            output.push_str(prefix);
+            output.push_str(if is_last { last } else { branch });
+            output.push_str(&label(child));
+            output.push('\n');
+            let child_prefix = format!("{}{}", prefix, if is_last { "   " } else { pipe });
+            self.format_tree_children(table, child.pid, include, label, &child_prefix, output);
+        }
+    }
+
+    /// Format GPU processes as trees under their top-level launchers
+    pub fn format_gpu_process_tree(&self, table: &ProcessTable, procs: &[GpuProc]) -> String {
+        let mut gpu_usage: BTreeMap<u32, (Vec<u16>, u32, &str)> = BTreeMap::new();
+        for proc in procs {
+            let entry = gpu_usage
+                .entry(proc.pid)
+                .or_insert_with(|| (Vec::new(), 0, proc.user.as_str()));
+            entry.0.push(proc.gpu_index);
+            entry.1 += proc.used_mem_mb;
+        }
+
+        let mut roots = Vec::new();
+        let mut include = HashSet::new();
+        for &pid in gpu_usage.keys() {
+            let Some(ancestry) = table.ancestry(pid) else {
+                continue;
+            };
+            // Include the path from the GPU process up to its launcher
+            let mut current = pid;
+            while include.insert(current) && current != ancestry.ancestor_pid {
+                match table.get(current).and_then(|n| n.ppid) {
+                    Some(ppid) => current = ppid,
+                    None => break,
+                }
+            }
+            if !roots.contains(&ancestry.ancestor_pid) {
+                roots.push(ancestry.ancestor_pid);
+            }
+        }
+        roots.sort_unstable();
+
+        self.format_process_tree(table, &roots, &include, |node| {
+            let command = truncate_string(node.command(), 60);
+            match gpu_usage.get(&node.pid) {
+                Some((gpus, mem_mb, user)) => {
+                    let gpus: Vec<String> = gpus.iter().map(|g| g.to_string()).collect();
+                    format!(
+                        "{} {} [GPU {}, {} GiB, {}]",
+                        node.pid,
+                        command,
+                        gpus.join(","),
+                        format_memory_mb_to_gib(*mem_mb),
+                        user
+                    )
+                }
+                None => format!("{} {}", node.pid, command),
+            }
+        })
+    }
+
+    /// Render as JSON
+    fn render_json(&self, snapshot: &Snapshot) -> Result<(), Box<dyn std::error::Error>> {
+        let json = serde_json::to_string_pretty(&SnapshotReport::new(snapshot))?;
+        println!("{}", json);
+        Ok(())
+    }
+
+    /// Render JSON snapshot for watch mode (newline-delimited)
+    pub fn render_json_snapshot(
+        &self,
+        snapshot: &Snapshot,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let json = serde_json::to_string(&SnapshotReport::new(snapshot))?;
+        println!("{}", json);
+        io::stdout().flush()?;
+        Ok(())
+    }
+
+    /// Clear screen for watch mode
+    /// Format PCIe throughput per GPU, or `None` when no GPU reports the counter
+    fn format_pcie_table(&self, gpus: &[GpuSnapshot]) -> Option<String> {
+        if gpus
+            .iter()
+            .all(|g| g.pcie_tx_kbps.is_none() && g.pcie_rx_kbps.is_none())
+        {
+            return None;
+        }
+        let rows: Vec<PcieRow> = gpus
+            .iter()
+            .map(|gpu| PcieRow {
+                gpu: gpu.gpu_index.to_string(),
+                tx: format_pcie_kbps(gpu.pcie_tx_kbps),
+                rx: format_pcie_kbps(gpu.pcie_rx_kbps),
+            })
+            .collect();
+        Some(self.format_table(Table::new(&rows)))
+    }
+
+    /// Format DCGM profiling metrics per GPU, or `None` when no GPU has them
+    fn format_profiling_table(&self, gpus: &[GpuSnapshot]) -> Option<String> {
+        if gpus.iter().all(|g| g.profiling.is_none()) {
+            return None;
+        }
+        let rows: Vec<ProfilingRow> = gpus
+            .iter()
+            .map(|gpu| {
+                let metrics = gpu.profiling.unwrap_or_default();
+                ProfilingRow {
+                    gpu: gpu.gpu_index.to_string(),
+                    sm_active: format_ratio(metrics.sm_active),
+                    sm_occupancy: format_ratio(metrics.sm_occupancy),
+                    dram_active: format_ratio(metrics.dram_active),
+                    tensor_active: format_ratio(metrics.tensor_active),
+                    fp64_active: format_ratio(metrics.fp64_active),
+                    fp32_active: format_ratio(metrics.fp32_active),
+                    fp16_active: format_ratio(metrics.fp16_active),
+                }
+            })
+            .collect();
+        Some(self.format_table(Table::new(&rows)))
+    }
+
+    /// Format each GPU's UUID and serial, or `None` when no GPU reports either
+    fn format_device_id_table(&self, gpus: &[GpuSnapshot]) -> Option<String> {
+        if gpus.iter().all(|g| g.uuid.is_none() && g.serial.is_none()) {
+            return None;
+        }
+        let rows: Vec<DeviceIdRow> = gpus
+            .iter()
+            .map(|gpu| DeviceIdRow {
+                gpu: gpu.gpu_index.to_string(),
+                uuid: gpu.uuid.clone().unwrap_or_else(|| "-".to_string()),
+                serial: gpu.serial.clone().unwrap_or_else(|| "-".to_string()),
+            })
+            .collect();
+        // UUIDs are not wrapped, so they can be copied into --gpu-uuid as-is
+        Some(self.format_wide_table(Table::new(&rows)))
+    }
+
+    /// Format ECC counters and retired pages per GPU, or `None` when no GPU reports them
+    fn format_memory_health_table(&self, gpus: &[GpuSnapshot]) -> Option<String> {
+        if gpus.iter().all(|g| {
+            g.ecc_volatile.is_none() && g.ecc_aggregate.is_none() && g.retired_pages.is_none()
+        }) {
+            return None;
+        }
+        let rows: Vec<MemoryHealthRow> = gpus
+            .iter()
+            .map(|gpu| MemoryHealthRow {
+                gpu: gpu.gpu_index.to_string(),
+                ecc_volatile: format_ecc_counts(gpu.ecc_volatile),
+                ecc_aggregate: format_ecc_counts(gpu.ecc_aggregate),
+                retired_pages: gpu
+                    .retired_pages
+                    .map(|p| format!("{} sbe/{} dbe", p.single_bit, p.double_bit))
+                    .unwrap_or_else(|| "-".to_string()),
+                pending: match gpu.retired_pages {
+                    Some(p) if p.pending => "yes".to_string(),
+                    Some(_) => "no".to_string(),
+                    None => "-".to_string(),
+                },
+                rma: match gpu.rma_flagged {
+                    Some(true) => "FLAGGED".to_string(),
+                    Some(false) => "ok".to_string(),
+                    None => "-".to_string(),
+                },
+            })
+            .collect();
+        Some(self.format_table(Table::new(&rows)))
+    }
+
+    /// Format current against default power limits per GPU, or `None` when no GPU
+    /// reports them
+    fn format_power_limit_table(&self, gpus: &[GpuSnapshot]) -> Option<String> {
+        if gpus.iter().all(|g| g.power_limit.is_none()) {
+            return None;
+        }
+        let rows: Vec<PowerLimitRow> = gpus
+            .iter()
+            .map(|gpu| match gpu.power_limit {
+                Some(limits) => PowerLimitRow {
+                    gpu: gpu.gpu_index.to_string(),
+                    power: format!("{:.1}", gpu.power_w),
+                    limit: format!("{:.0}", limits.current_w),
+                    default: format!("{:.0}", limits.default_w),
+                    drift: if limits.drifted() {
+                        format!("{:+.0} W", limits.current_w - limits.default_w)
+                    } else {
+                        "-".to_string()
+                    },
+                },
+                None => PowerLimitRow {
+                    gpu: gpu.gpu_index.to_string(),
+                    power: format!("{:.1}", gpu.power_w),
+                    limit: "-".to_string(),
+                    default: "-".to_string(),
+                    drift: "-".to_string(),
+                },
+            })
+            .collect();
+        Some(self.format_table(Table::new(&rows)))
+    }
+
+    pub fn clear_screen(&self) {
+        print!("\x1B[2J\x1B[1;1H");
+        io::stdout().flush().unwrap_or_default();
+    }
+
+    /// Get output format
+    pub fn get_output_format(&self) -> OutputFormat {
+        self.output_format.clone()
+    }
+}
+
+/// Summary table row structure
+#[derive(Tabled)]
+struct SummaryRow {
+    #[tabled(rename = "GPU")]
+    gpu: String,
+    #[tabled(rename = "NAME")]
+    name: String,
+    #[tabled(rename = "MEM_USED/TOTAL")]
+    memory: String,
+    #[tabled(rename = "UTIL(%)")]
+    utilization: String,
+    #[tabled(rename = "TEMP(°C)")]
+    temperature: String,
+    #[tabled(rename = "POWER(W)")]
+    power: String,
+    #[tabled(rename = "ECC(volatile)")]
+    ecc_volatile: String,
+    #[tabled(rename = "PIDS")]
+    pids: String,
+    #[tabled(rename = "TOP_PROC")]
+    top_process: String,
+}
+
+/// Summary row followed by `--watch --show-rates` columns
+#[derive(Tabled)]
+struct RateSummaryRow {
+    #[tabled(inline)]
+    row: SummaryRow,
+    #[tabled(rename = "Δmem/s")]
+    memory_rate: String,
+    #[tabled(rename = "Δutil")]
+    utilization_delta: String,
+}
+
+fn rate_row(row: SummaryRow, rate: Option<&GpuRate>) -> RateSummaryRow {
+    RateSummaryRow {
+        row,
+        memory_rate: rate
+            .map(|r| format!("{:+.0}MB/s", r.mem_mb_per_sec))
+            .unwrap_or_default(),
+        utilization_delta: rate
+            .map(|r| format!("{:+.1}", r.util_delta_pct))
+            .unwrap_or_default(),
+    }
+}
+
+/// Summary row prefixed with the host it was collected from
+#[derive(Tabled)]
+struct HostSummaryRow {
+    #[tabled(rename = "HOST")]
+    host: String,
+    #[tabled(inline)]
+    row: SummaryRow,
+}
+
+/// Process row prefixed with the host it was collected from
+#[derive(Tabled)]
+struct HostProcessRow {
+    #[tabled(rename = "HOST")]
+    host: String,
+    #[tabled(inline)]
+    row: ProcessRow,
+}
+
+/// Process row prefixed with the Slurm job that owns the process
+#[derive(Tabled)]
+struct SlurmProcessRow {
+    #[tabled(rename = "SLURM_JOB")]
+    job: String,
+    #[tabled(inline)]
+    row: ProcessRow,
+}
+
+/// Per-GPU change row for snapshot comparisons
+#[derive(Tabled)]
+struct GpuDeltaRow {
+    #[tabled(rename = "GPU")]
+    gpu: String,
+    #[tabled(rename = "NAME")]
+    name: String,
+    #[tabled(rename = "MEM_USED")]
+    memory: String,
+    #[tabled(rename = "MEM_DELTA")]
+    memory_delta: String,
+    #[tabled(rename = "UTIL(%)")]
+    utilization: String,
+    #[tabled(rename = "UTIL_DELTA")]
+    utilization_delta: String,
+    #[tabled(rename = "TEMP_DELTA")]
+    temperature_delta: String,
+    #[tabled(rename = "HEALTH")]
+    health: String,
+}
+
+/// Row of the flat `--processes-only` list
+#[derive(Tabled)]
+struct ProcessListRow {
+    #[tabled(rename = "GPU")]
+    gpu: String,
+    #[tabled(rename = "PID")]
+    pid: String,
+    #[tabled(rename = "USER")]
+    user: String,
+    #[tabled(rename = "PROC")]
+    process: String,
+    #[tabled(rename = "VRAM")]
+    memory: String,
+    #[tabled(rename = "CONTAINER?")]
+    container: String,
+}
+
+/// Process row marked as appeared (+) or disappeared (-) since a baseline
+#[derive(Tabled)]
+struct ProcessChangeRow {
+    #[tabled(rename = "CHANGE")]
+    change: String,
+    #[tabled(inline)]
+    row: ProcessRow,
+}
+
+fn gpu_delta_row(delta: &GpuDelta) -> GpuDeltaRow {
+    GpuDeltaRow {
+        gpu: delta.gpu_index.to_string(),
+        name: truncate_string(&delta.name, 20),
+        memory: format!(
+            "{} -> {} GiB",
+            format_memory_mb_to_gib(delta.mem_used_mb_before),
+            format_memory_mb_to_gib(delta.mem_used_mb_after)
+        ),
+        memory_delta: format!("{:+}MB", delta.mem_delta_mb),
+        utilization: format!(
+            "{:.1}% -> {:.1}%",
+            delta.util_pct_before, delta.util_pct_after
+        ),
+        utilization_delta: format!("{:+.1}", delta.util_delta_pct),
+        temperature_delta: format!("{:+}°C", delta.temp_delta_c),
+        health: if delta.health_changes.is_empty() {
+            "-".to_string()
         } else {
-            self.render_summary_table(snapshot)
-        }
+            delta.health_changes.join("; ")
+        },
     }
+}
 
-    /// Render summary table (one row per GPU)
-    fn render_summary_table(&self, snapshot: &Snapshot) -> Result<(), Box<dyn std::error::Error>> {
-        let mut table_data = Vec::new();
+/// Per-host outcome row for multi-host operations
+#[derive(Tabled)]
+struct HostResultRow {
+    #[tabled(rename = "HOST")]
+    host: String,
+    #[tabled(rename = "STATUS")]
+    status: String,
+    #[tabled(rename = "DETAIL")]
+    detail: String,
+}
 
-        for gpu in &snapshot.gpus {
-            let mem_used_gib = format_memory_mb_to_gib(gpu.mem_used_mb);
-            let mem_total_gib = format_memory_mb_to_gib(gpu.mem_total_mb);
-            let mem_usage = format!("{}/{} GiB", mem_used_gib, mem_total_gib);
+/// GPUs of one vendor, and those of its GPUs that could not be queried
+struct VendorGroup<'a> {
+    vendor: GpuVendor,
+    gpus: Vec<&'a GpuSnapshot>,
+    errors: Vec<&'a DeviceError>,
+}
 
-            let top_proc_info = if let Some(ref top_proc) = gpu.top_proc {
-                format!(
-                    "{}:{}:{}MB",
-                    truncate_string(&top_proc.proc_name, 15),
-                    top_proc.pid,
-                    top_proc.used_mem_mb
-                )
-            } else {
-                "-".to_string()
-            };
+/// Group GPUs by vendor in a fixed NVIDIA, AMD, Intel, Apple, Unknown order
+fn vendor_groups<'a>(gpus: &'a [GpuSnapshot], errors: &'a [DeviceError]) -> Vec<VendorGroup<'a>> {
+    const ORDER: [GpuVendor; 5] = [
+        GpuVendor::Nvidia,
+        GpuVendor::Amd,
+        GpuVendor::Intel,
+        GpuVendor::Apple,
+        GpuVendor::Unknown,
+    ];
+    ORDER
+        .iter()
+        .map(|vendor| VendorGroup {
+            vendor: *vendor,
+            gpus: gpus.iter().filter(|g| g.vendor == *vendor).collect(),
+            errors: errors.iter().filter(|e| e.vendor == *vendor).collect(),
+        })
+        .filter(|group| !group.gpus.is_empty() || !group.errors.is_empty())
+        .collect()
+}
 
-            let ecc_info = gpu
-                .ecc_volatile
-                .map(|e| e.to_string())
-                .unwrap_or_else(|| "-".to_string());
+/// "Total: ..." line summarizing every GPU, or None for a single GPU, whose row
+/// already says it all
+fn format_totals_footer(gpus: &[GpuSnapshot]) -> Option<String> {
+    if gpus.len() < 2 {
+        return None;
+    }
+    Some(format!(
+        "Total: {} GPUs, {}",
+        gpus.len(),
+        format_usage_totals(&UsageTotals::from_gpus(gpus))
+    ))
+}
 
-            table_data.push(SummaryRow {
-                gpu: gpu.gpu_index.to_string(),
-                name: truncate_string(&gpu.name, 20),
-                memory: mem_usage,
-                utilization: format!("{:.1}%", gpu.util_pct),
-                temperature: format!("{}°C", gpu.temp_c),
-                power: format!("{:.1}W", gpu.power_w),
-                ecc_volatile: ecc_info,
-                pids: gpu.pids.to_string(),
-                top_process: top_proc_info,
-            });
-        }
-
-        let table = Table::new(&table_data)
-            .with(Style::modern())
-            .with(Modify::new(Rows::new(1..)).with(Alignment::left()))
-            .with(Modify::new(Rows::new(1..)).with(Padding::new(1, 1, 0, 0)))
-            .with(Width::wrap(120))
-            .to_string();
+/// Memory, utilization, power and process counts of a set of GPUs on one line.
+/// Power covers only the GPUs that report it.
+fn format_usage_totals(totals: &UsageTotals) -> String {
+    let gib = |mb: u64| format!("{:.1}", mb as f64 / 1024.0);
+    let power = match totals.power_w {
+        None => "power n/a".to_string(),
+        Some(watts) if totals.power_reporting_gpus < totals.gpus => format!(
+            "{:.1}W ({} of {} GPUs report power)",
+            watts, totals.power_reporting_gpus, totals.gpus
+        ),
+        Some(watts) => format!("{:.1}W", watts),
+    };
+    format!(
+        "{}/{} GiB, {:.1}% avg utilization, {}, {} processes",
+        gib(totals.mem_used_mb),
+        gib(totals.mem_total_mb),
+        totals.avg_util_pct,
+        power,
+        totals.processes
+    )
+}
 
-        println!("{}", table);
-        Ok(())
+fn summary_row(gpu: &GpuSnapshot, units: MemoryUnits) -> SummaryRow {
+    let top_proc_info = if let Some(ref top_proc) = gpu.top_proc {
+        format!(
+            "{}:{}:{}",
+            truncate_string(&top_proc.proc_name, 15),
+            top_proc.pid,
+            units.format(top_proc.used_mem_mb)
+        )
+    } else {
+        "-".to_string()
+    };
+
+    let ecc_info = gpu
+        .ecc_volatile
+        .map(|e| e.total().to_string())
+        .unwrap_or_else(|| "-".to_string());
+
+    SummaryRow {
+        gpu: gpu.gpu_index.to_string(),
+        name: truncate_string(&gpu.name, 20),
+        memory: units.format_pair(gpu.mem_used_mb, gpu.mem_total_mb),
+        utilization: format!("{:.1}%", gpu.util_pct),
+        temperature: format!("{}°C", gpu.temp_c),
+        power: format!("{:.1}W", gpu.power_w),
+        ecc_volatile: ecc_info,
+        pids: gpu.pids.to_string(),
+        top_process: top_proc_info,
     }
+}
 
-    /// Render detailed table (one row per process)
-    fn render_detailed_table(&self, snapshot: &Snapshot) -> Result<(), Box<dyn std::error::Error>> {
-        // First render summary
-        self.render_summary_table(snapshot)?;
-        println!();
+/// Row for a GPU that could not be queried: its vendor and status in place of a
+/// name, and no readings. The error itself is printed below the table.
+fn error_row(error: &DeviceError) -> SummaryRow {
+    let none = || "-".to_string();
+    let status = match error.status {
+        GpuStatus::TimedOut => "timed out",
+        GpuStatus::Degraded => "degraded",
+        _ => "unavailable",
+    };
+    SummaryRow {
+        gpu: error.gpu_index.to_string(),
+        name: truncate_string(&format!("{} {}", error.vendor, status), 20),
+        memory: none(),
+        utilization: none(),
+        temperature: none(),
+        power: none(),
+        ecc_volatile: none(),
+        pids: none(),
+        top_process: none(),
+    }
+}
 
-        // Then render process details
-        if !snapshot.procs.is_empty() {
-            let mut table_data = Vec::new();
+fn process_row(proc: &GpuProc, cmdline_width: Option<usize>, units: MemoryUnits) -> ProcessRow {
+    let container_info = proc
+        .container
+        .as_ref()
+        .map(|c| truncate_string(c, 15))
+        .unwrap_or_else(|| "-".to_string());
 
-            for proc in &snapshot.procs {
-                let container_info = proc
-                    .container
-                    .as_ref()
-                    .map(|c| truncate_string(c, 15))
-                    .unwrap_or_else(|| "-".to_string());
-
-                table_data.push(ProcessRow {
-                    gpu: proc.gpu_index.to_string(),
-                    pid: proc.pid.to_string(),
-                    user: truncate_string(&proc.user, 12),
-                    process: truncate_string(&proc.proc_name, 20),
-                    vram_mb: format!("{}MB", proc.used_mem_mb),
-                    start_time: truncate_string(&proc.start_time, 10),
-                    container: container_info,
-                });
+    ProcessRow {
+        gpu: proc.gpu_index.to_string(),
+        pid: proc.pid.to_string(),
+        container_pid: proc
+            .container_pid
+            .map(|pid| pid.to_string())
+            .unwrap_or_else(|| "-".to_string()),
+        user: truncate_string(&proc.user, 12),
+        process: truncate_string(&proc.proc_name, 20),
+        vram_mb: units.format(proc.used_mem_mb),
+        start_time: match proc
+            .start_unix_secs
+            .and_then(|secs| chrono::DateTime::from_timestamp(secs as i64, 0))
+        {
+            Some(started) => {
+                format_start_time(started.with_timezone(&chrono::Local), chrono::Local::now())
             }
+            None => truncate_string(&proc.start_time, 10),
+        },
+        container: container_info,
+        cmd: match cmdline_width {
+            Some(width) => truncate_string(proc.command(), width),
+            None => proc.command().to_string(),
+        },
+    }
+}
 
-            let table = Table::new(&table_data)
-                .with(Style::modern())
-                .with(Modify::new(Rows::new(1..)).with(Alignment::left()))
-                .with(Modify::new(Rows::new(1..)).with(Padding::new(1, 1, 0, 0)))
-                .with(Width::wrap(120))
-                .to_string();
+/// Vendor capability row for `--capabilities`
+#[derive(Tabled)]
+struct CapabilityRow {
+    #[tabled(rename = "VENDOR")]
+    vendor: String,
+    #[tabled(rename = "DETECTED")]
+    detected: String,
+    #[tabled(rename = "RESET")]
+    reset: String,
+    #[tabled(rename = "PROCESSES")]
+    processes: String,
+    #[tabled(rename = "POWER_LIMIT")]
+    power_limit: String,
+    #[tabled(rename = "UTILIZATION")]
+    utilization: String,
+}
 
-            println!("Process Details:");
-            println!("{}", table);
-        }
+/// Operation row for `--capabilities --check-privileges`
+#[derive(Tabled)]
+struct PrivilegeRow {
+    #[tabled(rename = "OPERATION")]
+    operation: String,
+    #[tabled(rename = "ACCESS")]
+    access: String,
+    #[tabled(rename = "NOTE")]
+    note: String,
+}
 
-        Ok(())
-    }
+/// NVLink link row for `--topology`
+#[derive(Tabled)]
+struct NvLinkRow {
+    #[tabled(rename = "GPU")]
+    gpu: u16,
+    #[tabled(rename = "LINK")]
+    link: u32,
+    #[tabled(rename = "STATE")]
+    state: String,
+    #[tabled(rename = "REMOTE")]
+    remote: String,
+}
 
-    /// Render as JSON
-    fn render_json(&self, snapshot: &Snapshot) -> Result<(), Box<dyn std::error::Error>> {
-        let json = serde_json::to_string_pretty(snapshot)?;
-        println!("{}", json);
-        Ok(())
-    }
+/// Stable index row for `--list --show-ids`
+#[derive(Tabled)]
+struct GpuIdRow {
+    #[tabled(rename = "GPU")]
+    index: u16,
+    #[tabled(rename = "ENUM")]
+    enumeration_index: String,
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "NAME")]
+    name: String,
+    #[tabled(rename = "PRESENT")]
+    present: String,
+}
 
-    /// Render JSON snapshot for watch mode (newline-delimited)
-    pub fn render_json_snapshot(
-        &self,
-        snapshot: &Snapshot,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let json = serde_json::to_string(snapshot)?;
-        println!("{}", json);
-        io::stdout().flush()?;
-        Ok(())
-    }
+/// PCIe throughput row structure
+#[derive(Tabled)]
+struct PcieRow {
+    #[tabled(rename = "GPU")]
+    gpu: String,
+    #[tabled(rename = "PCIE_TX")]
+    tx: String,
+    #[tabled(rename = "PCIE_RX")]
+    rx: String,
+}
 
-    /// Clear screen for watch mode
-    pub fn clear_screen(&self) {
-        print!("\x1B[2J\x1B[1;1H");
-        io::stdout().flush().unwrap_or_default();
-    }
+/// DCGM profiling row for `--details`
+#[derive(Tabled)]
+struct ProfilingRow {
+    #[tabled(rename = "GPU")]
+    gpu: String,
+    #[tabled(rename = "SM_ACTIVE")]
+    sm_active: String,
+    #[tabled(rename = "SM_OCCUPANCY")]
+    sm_occupancy: String,
+    #[tabled(rename = "DRAM_ACTIVE")]
+    dram_active: String,
+    #[tabled(rename = "TENSOR")]
+    tensor_active: String,
+    #[tabled(rename = "FP64")]
+    fp64_active: String,
+    #[tabled(rename = "FP32")]
+    fp32_active: String,
+    #[tabled(rename = "FP16")]
+    fp16_active: String,
+}
 
-    /// Get output format
-    pub fn get_output_format(&self) -> OutputFormat {
-        self.output_format.clone()
-    }
+/// UUID and serial row for `--details`
+#[derive(Tabled)]
+struct DeviceIdRow {
+    #[tabled(rename = "GPU")]
+    gpu: String,
+    #[tabled(rename = "UUID")]
+    uuid: String,
+    #[tabled(rename = "SERIAL")]
+    serial: String,
 }
 
-/// Summary table row structure
+/// Power limit row for `--details`
 #[derive(Tabled)]
-struct SummaryRow {
+struct PowerLimitRow {
     #[tabled(rename = "GPU")]
     gpu: String,
-    #[tabled(rename = "NAME")]
-    name: String,
-    #[tabled(rename = "MEM_USED/TOTAL")]
-    memory: String,
-    #[tabled(rename = "UTIL(%)")]
-    utilization: String,
-    #[tabled(rename = "TEMP(°C)")]
-    temperature: String,
     #[tabled(rename = "POWER(W)")]
     power: String,
-    #[tabled(rename = "ECC(volatile)")]
+    #[tabled(rename = "LIMIT(W)")]
+    limit: String,
+    #[tabled(rename = "DEFAULT(W)")]
+    default: String,
+    #[tabled(rename = "DRIFT")]
+    drift: String,
+}
+
+/// ECC and page retirement row for `--details`
+#[derive(Tabled)]
+struct MemoryHealthRow {
+    #[tabled(rename = "GPU")]
+    gpu: String,
+    #[tabled(rename = "ECC_VOLATILE(C/U)")]
     ecc_volatile: String,
-    #[tabled(rename = "PIDS")]
-    pids: String,
-    #[tabled(rename = "TOP_PROC")]
-    top_process: String,
+    #[tabled(rename = "ECC_AGGREGATE(C/U)")]
+    ecc_aggregate: String,
+    #[tabled(rename = "RETIRED_PAGES")]
+    retired_pages: String,
+    #[tabled(rename = "PENDING")]
+    pending: String,
+    #[tabled(rename = "RMA")]
+    rma: String,
+}
+
+fn format_ecc_counts(counts: Option<EccCounts>) -> String {
+    counts
+        .map(|c| format!("{}/{}", c.corrected, c.uncorrected))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// A 0.0-1.0 ratio as a percentage, "-" when missing
+fn format_ratio(ratio: Option<f32>) -> String {
+    ratio
+        .map(|r| format!("{:.0}%", r * 100.0))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn format_pcie_kbps(kbps: Option<u32>) -> String {
+    kbps.map(|kbps| format!("{:.1} MB/s", kbps as f64 / 1024.0))
+        .unwrap_or_else(|| "-".to_string())
 }
 
 /// Process table row structure
@@ -197,42 +1673,96 @@ struct ProcessRow {
     gpu: String,
     #[tabled(rename = "PID")]
     pid: String,
+    /// PID inside the container's PID namespace, for `kill` run from inside it
+    #[tabled(rename = "CTR_PID")]
+    container_pid: String,
     #[tabled(rename = "USER")]
     user: String,
     #[tabled(rename = "PROC")]
     process: String,
-    #[tabled(rename = "VRAM_MB")]
+    #[tabled(rename = "VRAM")]
     vram_mb: String,
     #[tabled(rename = "START_TIME")]
     start_time: String,
     #[tabled(rename = "CONTAINER?")]
     container: String,
+    #[tabled(rename = "CMD")]
+    cmd: String,
+}
+
+/// Build warnings for GPUs that could not be queried, naming the failing vendor source
+pub fn device_error_warnings(snapshot: &Snapshot) -> Vec<String> {
+    snapshot
+        .errors
+        .iter()
+        .map(|error| {
+            format!(
+                "GPU {} ({}) could not be queried: {}",
+                error.gpu_index, error.vendor, error.error
+            )
+        })
+        .collect()
+}
+
+/// Build warnings for GPUs reporting uncorrected volatile ECC errors or flagged for RMA
+pub fn ecc_warnings(snapshot: &Snapshot) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for gpu in &snapshot.gpus {
+        if let Some(ecc) = gpu.ecc_volatile.filter(|ecc| ecc.uncorrected > 0) {
+            warnings.push(format!(
+                "GPU {} ({}) reported {} uncorrected ECC error(s) since the driver was loaded; \
+                 the device may need to be drained and reset",
+                gpu.gpu_index, gpu.name, ecc.uncorrected
+            ));
+        }
+        if let (Some(true), Some(pages)) = (gpu.rma_flagged, gpu.retired_pages) {
+            warnings.push(format!(
+                "GPU {} ({}) has retired {} memory pages and should be considered for RMA",
+                gpu.gpu_index,
+                gpu.name,
+                pages.total()
+            ));
+        }
+    }
+    warnings
 }
 
 /// Render error messages
 pub fn render_error(message: &str) {
-    eprintln!("Error: {}", message);
+    eprintln!(
+        "{}",
+        output_style().format_message(MessageLevel::Error, message)
+    );
 }
 
 /// Render warning messages
 pub fn render_warning(message: &str) {
-    eprintln!("Warning: {}", message);
+    eprintln!(
+        "{}",
+        output_style().format_message(MessageLevel::Warning, message)
+    );
 }
 
 /// Render info messages
 pub fn render_info(message: &str) {
-    println!("Info: {}", message);
+    println!(
+        "{}",
+        output_style().format_message(MessageLevel::Info, message)
+    );
 }
 
 /// Render success messages
 pub fn render_success(message: &str) {
-    println!("Success: {}", message);
+    println!(
+        "{}",
+        output_style().format_message(MessageLevel::Success, message)
+    );
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::nvml_api::{GpuProc, GpuSnapshot, Snapshot};
+    use crate::nvml_api::EccCounts;
 
     fn create_test_snapshot() -> Snapshot {
         Snapshot {
@@ -247,7 +1777,7 @@ mod tests {
                 util_pct: 50.0,
                 temp_c: 75,
                 power_w: 150.0,
-                ecc_volatile: Some(0),
+                ecc_volatile: Some(EccCounts::default()),
                 pids: 2,
                 top_proc: Some(GpuProc {
                     gpu_index: 0,
@@ -256,9 +1786,9 @@ mod tests {
                     proc_name: "test_process".to_string(),
                     used_mem_mb: 1024,
                     start_time: "1h 30m".to_string(),
-                    container: None,
-                    node_id: None,
+                    ..Default::default()
                 }),
+                ..Default::default()
             }],
             procs: vec![GpuProc {
                 gpu_index: 0,
@@ -267,18 +1797,737 @@ mod tests {
                 proc_name: "test_process".to_string(),
                 used_mem_mb: 1024,
                 start_time: "1h 30m".to_string(),
-                container: None,
-                node_id: None,
+                ..Default::default()
             }],
+            degraded: false,
+            gpu_status: Vec::new(),
+            errors: Vec::new(),
         }
     }
 
+    #[test]
+    fn test_output_style_resolution() {
+        let resolve = OutputStyle::resolve;
+        assert_eq!(resolve(ColorChoice::Auto, None, true), OutputStyle::RICH);
+        assert_eq!(resolve(ColorChoice::Never, None, true), OutputStyle::PLAIN);
+        assert_eq!(
+            resolve(ColorChoice::Auto, Some("1"), true),
+            OutputStyle::PLAIN
+        );
+        // An empty NO_COLOR does not disable color
+        assert_eq!(
+            resolve(ColorChoice::Auto, Some(""), true),
+            OutputStyle::RICH
+        );
+        // Redirected output (log files, pipes) is plain
+        assert_eq!(resolve(ColorChoice::Auto, None, false), OutputStyle::PLAIN);
+        // An explicit choice overrides both the environment and terminal detection
+        assert_eq!(
+            resolve(ColorChoice::Always, Some("1"), false),
+            OutputStyle::RICH
+        );
+        assert_eq!(resolve(ColorChoice::Never, None, true), OutputStyle::PLAIN);
+    }
+
+    #[test]
+    fn test_disabled_color_has_no_escape_sequences() {
+        let style = OutputStyle::resolve(ColorChoice::Never, None, true);
+        for level in [
+            MessageLevel::Info,
+            MessageLevel::Success,
+            MessageLevel::Warning,
+            MessageLevel::Error,
+        ] {
+            let message = style.format_message(level, &format!("{}done", style.icon(Icon::Ok)));
+            assert!(!message.contains('\x1b'), "{:?}", message);
+            assert!(message.is_ascii(), "{:?}", message);
+        }
+
+        let mut snapshot = create_test_snapshot();
+        snapshot.procs[0].slurm_job_id = Some("4242".to_string());
+        let renderer = Renderer::with_style(OutputFormat::Table, style);
+        for output in [
+            renderer.format_summary_table(&snapshot),
+            renderer.format_process_details(&snapshot.procs),
+            renderer.format_process_list(&snapshot.procs),
+        ] {
+            assert!(!output.contains('\x1b'), "{}", output);
+        }
+
+        // NO_COLOR does the same in auto mode
+        let style = OutputStyle::resolve(ColorChoice::Auto, Some("1"), true);
+        assert!(!style
+            .format_message(MessageLevel::Warning, "disk low")
+            .contains('\x1b'));
+    }
+
+    #[test]
+    fn test_message_format_rich() {
+        let style = OutputStyle::RICH;
+        assert_eq!(
+            style.format_message(
+                MessageLevel::Info,
+                &format!("{}Guard Mode enabled", style.icon(Icon::Ok))
+            ),
+            "\x1b[36mInfo:\x1b[0m ✅ Guard Mode enabled"
+        );
+        assert_eq!(
+            style.format_message(MessageLevel::Error, "boom"),
+            "\x1b[31mError:\x1b[0m boom"
+        );
+    }
+
+    #[test]
+    fn test_message_format_plain() {
+        let style = OutputStyle::PLAIN;
+        assert_eq!(
+            style.format_message(
+                MessageLevel::Info,
+                &format!("{}Guard Mode enabled", style.icon(Icon::Ok))
+            ),
+            "INFO: Guard Mode enabled"
+        );
+        assert_eq!(
+            style.format_message(MessageLevel::Warning, "disk low"),
+            "WARN: disk low"
+        );
+        assert_eq!(
+            style.format_message(MessageLevel::Error, "boom"),
+            "ERROR: boom"
+        );
+        // Emoji embedded in data (e.g. rogue recommendations) are stripped too
+        assert_eq!(
+            style.format_message(MessageLevel::Info, "  🚨 CRITICAL: ⚠️ check"),
+            "INFO:   CRITICAL: check"
+        );
+    }
+
+    #[test]
+    fn test_summary_table_snapshot_rich() {
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::RICH);
+        let table = renderer.format_summary_table(&create_test_snapshot());
+        let expected = "\
+┌─────┬──────────┬────────────────┬─────────┬──────────┬──────────┬───────────────┬──────┬────────────────────────────┐
+│ GPU │ NAME     │ MEM_USED/TOTAL │ UTIL(%) │ TEMP(°C) │ POWER(W) │ ECC(volatile) │ PIDS │ TOP_PROC                   │
+├─────┼──────────┼────────────────┼─────────┼──────────┼──────────┼───────────────┼──────┼────────────────────────────┤
+│ 0   │ Test GPU │    2.0/8.0 GiB │   50.0% │     75°C │   150.0W │             0 │    2 │ test_process:12345:1.0 GiB │
+└─────┴──────────┴────────────────┴─────────┴──────────┴──────────┴───────────────┴──────┴────────────────────────────┘";
+        assert_eq!(table, expected);
+    }
+
+    #[test]
+    fn test_summary_table_snapshot_plain() {
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN);
+        let table = renderer.format_summary_table(&create_test_snapshot());
+        assert!(!table.contains(['┌', '│', '─']));
+        let expected = "\
++-----+----------+----------------+---------+----------+----------+---------------+------+----------------------------+
+| GPU | NAME     | MEM_USED/TOTAL | UTIL(%) | TEMP(°C) | POWER(W) | ECC(volatile) | PIDS | TOP_PROC                   |
++-----+----------+----------------+---------+----------+----------+---------------+------+----------------------------+
+| 0   | Test GPU |    2.0/8.0 GiB |   50.0% |     75°C |   150.0W |             0 |    2 | test_process:12345:1.0 GiB |
++-----+----------+----------------+---------+----------+----------+---------------+------+----------------------------+";
+        assert_eq!(table, expected);
+    }
+
+    #[test]
+    fn test_raw_units_show_plain_mb() {
+        let mut snapshot = create_test_snapshot();
+        snapshot.procs[0].start_unix_secs = Some(0);
+        let human = Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN);
+        let raw = human.clone().with_raw_units(true);
+
+        let table = raw.format_summary_table(&snapshot);
+        assert!(table.contains("2048/8192 MB"), "{}", table);
+        assert!(table.contains("test_process:12345:1024 MB"), "{}", table);
+        let details = raw.format_process_details(&snapshot.procs);
+        assert!(details.contains("1024 MB"), "{}", details);
+        assert!(!human
+            .format_process_details(&snapshot.procs)
+            .contains(" MB "));
+
+        // Start times read as an age plus the time itself, whatever the units
+        let row = process_row(&snapshot.procs[0], Some(20), MemoryUnits::RawMb);
+        assert!(row.start_time.contains(" ago ("), "{}", row.start_time);
+
+        // JSON keeps plain numbers, with the start time in Unix seconds
+        let json = serde_json::to_value(&snapshot.procs[0]).unwrap();
+        assert_eq!(json["used_mem_mb"], 1024);
+        assert_eq!(json["start_unix_secs"], 0);
+    }
+
+    #[test]
+    fn test_pcie_table() {
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN);
+        let mut snapshot = create_test_snapshot();
+        assert_eq!(renderer.format_pcie_table(&snapshot.gpus), None);
+
+        snapshot.gpus[0].pcie_tx_kbps = Some(2048);
+        let expected = "\
++-----+----------+---------+
+| GPU | PCIE_TX  | PCIE_RX |
++-----+----------+---------+
+| 0   | 2.0 MB/s | -       |
++-----+----------+---------+";
+        assert_eq!(
+            renderer.format_pcie_table(&snapshot.gpus).as_deref(),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn test_profiling_table() {
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN);
+        let mut snapshot = create_test_snapshot();
+        assert_eq!(renderer.format_profiling_table(&snapshot.gpus), None);
+
+        snapshot.gpus[0].profiling = Some(crate::dcgm::ProfilingMetrics {
+            sm_active: Some(0.92),
+            sm_occupancy: Some(0.41),
+            dram_active: Some(0.634),
+            tensor_active: Some(0.55),
+            fp64_active: None,
+            fp32_active: Some(0.2),
+            fp16_active: Some(0.0),
+        });
+        let expected = "\
++-----+-----------+--------------+-------------+--------+------+------+------+
+| GPU | SM_ACTIVE | SM_OCCUPANCY | DRAM_ACTIVE | TENSOR | FP64 | FP32 | FP16 |
++-----+-----------+--------------+-------------+--------+------+------+------+
+| 0   | 92%       | 41%          | 63%         | 55%    | -    | 20%  | 0%   |
++-----+-----------+--------------+-------------+--------+------+------+------+";
+        assert_eq!(
+            renderer.format_profiling_table(&snapshot.gpus).as_deref(),
+            Some(expected)
+        );
+
+        // JSON carries the metrics only when present
+        let json = serde_json::to_value(&snapshot.gpus[0]).unwrap();
+        assert_eq!(json["profiling"]["tensor_active"], 0.55f32 as f64);
+        let json = serde_json::to_value(create_test_snapshot().gpus[0].clone()).unwrap();
+        assert!(json.get("profiling").is_none());
+    }
+
+    #[test]
+    fn test_device_id_table() {
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN);
+        let mut snapshot = create_test_snapshot();
+        assert_eq!(renderer.format_device_id_table(&snapshot.gpus), None);
+
+        snapshot.gpus[0].uuid = Some("GPU-5fd4a1c2-0b3e-4c1d-9f7a-2e6b8d0c4a11".to_string());
+        let expected = "\
++-----+------------------------------------------+--------+
+| GPU | UUID                                     | SERIAL |
++-----+------------------------------------------+--------+
+| 0   | GPU-5fd4a1c2-0b3e-4c1d-9f7a-2e6b8d0c4a11 | -      |
++-----+------------------------------------------+--------+";
+        assert_eq!(
+            renderer.format_device_id_table(&snapshot.gpus).as_deref(),
+            Some(expected)
+        );
+    }
+
+    #[test]
+    fn test_memory_health_table() {
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN);
+        let mut snapshot = create_test_snapshot();
+        snapshot.gpus[0].ecc_volatile = None;
+        assert_eq!(renderer.format_memory_health_table(&snapshot.gpus), None);
+
+        snapshot.gpus[0].ecc_aggregate = Some(EccCounts {
+            corrected: 12,
+            uncorrected: 1,
+        });
+        snapshot.gpus[0].retired_pages = Some(crate::nvml_api::RetiredPages {
+            single_bit: 59,
+            double_bit: 1,
+            pending: true,
+        });
+        snapshot.gpus[0].rma_flagged = Some(true);
+        let table = renderer.format_memory_health_table(&snapshot.gpus).unwrap();
+        assert!(table.contains("12/1"), "{}", table);
+        assert!(table.contains("59 sbe/1 dbe"), "{}", table);
+        assert!(table.contains("FLAGGED"), "{}", table);
+
+        let warnings = ecc_warnings(&snapshot);
+        assert_eq!(warnings.len(), 1);
+        assert!(
+            warnings[0].contains("retired 60 memory pages"),
+            "{}",
+            warnings[0]
+        );
+    }
+
+    #[test]
+    fn test_power_limit_table_shows_drift() {
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN);
+        let mut snapshot = create_test_snapshot();
+        assert_eq!(renderer.format_power_limit_table(&snapshot.gpus), None);
+
+        snapshot.gpus[0].power_limit = Some(crate::nvml_api::PowerLimits {
+            current_w: 250.0,
+            default_w: 300.0,
+        });
+        let table = renderer.format_power_limit_table(&snapshot.gpus).unwrap();
+        assert!(table.contains("DEFAULT(W)"), "{}", table);
+        assert!(table.contains("250"), "{}", table);
+        assert!(table.contains("-50 W"), "{}", table);
+
+        snapshot.gpus[0].power_limit = Some(crate::nvml_api::PowerLimits {
+            current_w: 300.0,
+            default_w: 300.0,
+        });
+        let table = renderer.format_power_limit_table(&snapshot.gpus).unwrap();
+        assert!(!table.contains(" W"), "{}", table);
+    }
+
+    #[test]
+    fn test_host_snapshots_grouped_by_host() {
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN);
+        let snapshots = vec![
+            ("gpu-01".to_string(), create_test_snapshot()),
+            ("gpu-02".to_string(), create_test_snapshot()),
+        ];
+
+        let table = renderer.format_host_snapshots(&snapshots, true);
+        let header = table.lines().nth(1).unwrap();
+        assert!(header.starts_with("| HOST "));
+        let gpu_01 = table.find("gpu-01").unwrap();
+        let gpu_02 = table.find("gpu-02").unwrap();
+        assert!(gpu_01 < gpu_02);
+        assert!(table.contains("Process Details:"));
+        assert_eq!(table.matches("gpu-01").count(), 2);
+    }
+
+    #[test]
+    fn test_process_cmd_column() {
+        let mut snapshot = create_test_snapshot();
+        snapshot.procs[0].cmdline =
+            Some("python train.py --config configs/large.yaml --epochs 100".to_string());
+
+        // Truncated to the configured width by default
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN)
+            .with_cmdline_width(Some(20));
+        let table =
+            renderer.format_host_snapshots(&[("gpu-01".to_string(), snapshot.clone())], true);
+        assert!(table.contains("| CMD "));
+        assert!(table.contains("python train.py -..."));
+        assert!(!table.contains("--epochs 100"));
+
+        // --full-cmd shows the whole command line
+        let renderer =
+            Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN).with_cmdline_width(None);
+        let table =
+            renderer.format_host_snapshots(&[("gpu-01".to_string(), snapshot.clone())], true);
+        assert!(table.contains("python train.py --config configs/large.yaml --epochs 100"));
+
+        // Without a command line the process name is shown
+        snapshot.procs[0].cmdline = None;
+        let row = process_row(&snapshot.procs[0], Some(20), MemoryUnits::Human);
+        assert_eq!(row.cmd, snapshot.procs[0].proc_name);
+    }
+
+    #[test]
+    fn test_process_row_shows_container_pid() {
+        let mut snapshot = create_test_snapshot();
+        let row = process_row(&snapshot.procs[0], Some(20), MemoryUnits::Human);
+        assert_eq!(row.container_pid, "-");
+
+        snapshot.procs[0].container_pid = Some(7);
+        let row = process_row(&snapshot.procs[0], Some(20), MemoryUnits::Human);
+        assert_eq!(row.pid, snapshot.procs[0].pid.to_string());
+        assert_eq!(row.container_pid, "7");
+    }
+
+    #[test]
+    fn test_process_details_slurm_job_column() {
+        let mut snapshot = create_test_snapshot();
+        let renderer =
+            Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN).with_cmdline_width(None);
+
+        // Hosts without Slurm keep the usual columns
+        let table = renderer.format_process_details(&snapshot.procs);
+        assert!(!table.contains("SLURM_JOB"));
+
+        snapshot.procs[0].slurm_job_id = Some("4242".to_string());
+        let table = renderer.format_process_details(&snapshot.procs);
+        assert!(table.contains("| SLURM_JOB "));
+        assert!(table.contains("| 4242 "));
+    }
+
+    #[test]
+    fn test_process_details_trend_columns_follow_vram() {
+        let snapshot = create_test_snapshot();
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN);
+        assert!(!renderer
+            .format_process_details(&snapshot.procs)
+            .contains("MEM_TREND"));
+
+        let trends = BTreeMap::from([(
+            (0, 12345),
+            ProcessTrend {
+                memory: "▁▄█".to_string(),
+                utilization: "▂▂▇".to_string(),
+            },
+        )]);
+        let table = renderer
+            .with_process_trends(trends)
+            .format_process_details(&snapshot.procs);
+        let header = table.lines().find(|line| line.contains("PID")).unwrap();
+        let vram = header.find("VRAM").unwrap();
+        assert!(vram < header.find("MEM_TREND").unwrap());
+        assert!(header.find("UTIL_TREND").unwrap() < header.find("START_TIME").unwrap());
+        assert!(table.contains("| ▁▄█ "));
+        assert!(table.contains("| ▂▂▇ "));
+    }
+
+    #[test]
+    fn test_host_results_summary() {
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN);
+        let results = vec![
+            ("gpu-01".to_string(), Ok(())),
+            (
+                "gpu-02".to_string(),
+                Err("SSH command failed with exit code 255: connection refused\nmore".to_string()),
+            ),
+        ];
+
+        let table = renderer.format_host_results(&results);
+        assert!(table.contains("| gpu-01 | ok     | -"));
+        assert!(table.contains(
+            "| gpu-02 | failed | SSH command failed with exit code 255: connection refused"
+        ));
+        assert!(!table.contains("more"));
+    }
+
+    #[test]
+    fn test_format_gpu_process_tree() {
+        use crate::process_mgmt::ProcessNode;
+
+        let node = |pid: u32, ppid: u32, name: &str, cmd: &str| ProcessNode {
+            pid,
+            ppid: Some(ppid),
+            name: name.to_string(),
+            cmd: cmd.to_string(),
+            session_leader: false,
+        };
+        let table = ProcessTable::from_nodes(
+            vec![
+                ProcessNode {
+                    session_leader: true,
+                    ..node(60, 1, "bash", "-bash")
+                },
+                node(100, 60, "torchrun", "torchrun train.py"),
+                node(101, 100, "python", "python train.py --rank 0"),
+                node(102, 100, "python", "python train.py --rank 1"),
+                node(110, 100, "python", "python dataloader.py"),
+            ],
+            None,
+        );
+        let gpu_proc = |pid: u32, gpu_index: u16| GpuProc {
+            gpu_index,
+            pid,
+            user: "alice".to_string(),
+            proc_name: "python".to_string(),
+            used_mem_mb: 2048,
+            start_time: "1h".to_string(),
+            ..Default::default()
+        };
+        let procs = vec![gpu_proc(101, 0), gpu_proc(102, 1), gpu_proc(102, 2)];
+
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN);
+        let tree = renderer.format_gpu_process_tree(&table, &procs);
+        assert_eq!(
+            tree,
+            "100 torchrun train.py\n\
+             |- 101 python train.py --rank 0 [GPU 0, 2.0 GiB, alice]\n\
+             `- 102 python train.py --rank 1 [GPU 1,2, 4.0 GiB, alice]\n"
+        );
+
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::RICH);
+        let tree = renderer.format_gpu_process_tree(&table, &procs);
+        assert!(tree.contains("├─ 101"));
+        assert!(tree.contains("└─ 102"));
+    }
+
+    #[test]
+    fn test_summary_table_groups_mixed_vendors() {
+        let gpu = |gpu_index: u16, name: &str, vendor: GpuVendor| GpuSnapshot {
+            gpu_index,
+            name: name.to_string(),
+            vendor,
+            pids: 1,
+            top_proc: None,
+            ..create_test_snapshot().gpus[0].clone()
+        };
+        let snapshot = Snapshot {
+            gpus: vec![
+                gpu(0, "Apple M2", GpuVendor::Apple),
+                gpu(1, "Radeon RX 7900", GpuVendor::Amd),
+                gpu(2, "A100", GpuVendor::Nvidia),
+                gpu(3, "A100", GpuVendor::Nvidia),
+                gpu(4, "Arc A770", GpuVendor::Intel),
+            ],
+            ..create_test_snapshot()
+        };
+
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN);
+        let table = renderer.format_summary_table(&snapshot);
+        let position = |header: &str| {
+            table
+                .find(header)
+                .unwrap_or_else(|| panic!("missing section {header}"))
+        };
+        let nvidia = position("NVIDIA (2 GPUs)\n");
+        let amd = position("AMD (1 GPU)\n");
+        let intel = position("Intel (1 GPU)\n");
+        let apple = position("Apple (1 GPU)\n");
+        assert!(nvidia < amd && amd < intel && intel < apple);
+        assert!(
+            table.contains("Subtotal: 4.0/16.0 GiB, 50.0% avg utilization, 300.0W, 2 processes")
+        );
+        assert_eq!(table.matches("Subtotal:").count(), 4);
+        // Intel and Apple report no power, so they are left out of the power sum
+        assert!(
+            table.contains("Subtotal: 2.0/8.0 GiB, 50.0% avg utilization, power n/a, 1 processes")
+        );
+        assert!(table.trim_end().ends_with(
+            "Total: 5 GPUs, 10.0/40.0 GiB, 50.0% avg utilization, 450.0W (3 of 5 GPUs report power), 5 processes"
+        ));
+
+        // Watch mode prints the footer last instead
+        let pinned = renderer.clone().with_pinned_totals();
+        assert!(!pinned.format_summary_table(&snapshot).contains("Total:"));
+
+        // A single-vendor host keeps the plain table unless grouping is forced
+        let single = create_test_snapshot();
+        assert!(!renderer.format_summary_table(&single).contains("Subtotal:"));
+        let forced = renderer.clone().with_vendor_sections(true);
+        let table = forced.format_summary_table(&single);
+        assert!(table.starts_with("Unknown (1 GPU)\n"));
+        assert!(table.contains("Subtotal: 2.0/8.0 GiB"));
+    }
+
+    #[test]
+    fn test_summary_table_lists_failed_gpus() {
+        let gpu = |gpu_index: u16, vendor: GpuVendor| GpuSnapshot {
+            gpu_index,
+            vendor,
+            ..create_test_snapshot().gpus[0].clone()
+        };
+        let lost = DeviceError::failed(
+            1,
+            GpuVendor::Nvidia,
+            &anyhow::anyhow!("GPU is lost").context("Failed to get snapshot"),
+        );
+        let snapshot = Snapshot {
+            gpus: vec![gpu(0, GpuVendor::Nvidia), gpu(2, GpuVendor::Nvidia)],
+            errors: vec![lost],
+            ..create_test_snapshot()
+        };
+
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN);
+        let table = renderer.format_summary_table(&snapshot);
+        let gpu_column: Vec<&str> = table
+            .lines()
+            .filter_map(|line| line.strip_prefix("| "))
+            .filter_map(|line| line.split(' ').next())
+            .filter(|index| index.parse::<u16>().is_ok())
+            .collect();
+        assert_eq!(gpu_column, vec!["0", "1", "2"]);
+        let lost_row = table.lines().find(|line| line.starts_with("| 1 ")).unwrap();
+        assert!(lost_row.contains("| NVIDIA"));
+        assert_eq!(error_row(&snapshot.errors[0]).name, "NVIDIA unavailable");
+        assert!(!lost_row.contains("GiB"));
+        assert_eq!(
+            device_error_warnings(&snapshot),
+            vec!["GPU 1 (NVIDIA) could not be queried: NVML: GPU is lost"]
+        );
+
+        // A vendor whose only GPU failed still gets a section
+        let timed_out =
+            DeviceError::timed_out(3, GpuVendor::Amd, std::time::Duration::from_secs(5));
+        let mixed = Snapshot {
+            errors: vec![timed_out],
+            ..snapshot.clone()
+        };
+        let table = renderer.format_summary_table(&mixed);
+        assert!(table.contains("NVIDIA (2 GPUs)\n"));
+        assert!(table.contains("AMD (1 GPU)\n"));
+        assert_eq!(error_row(&mixed.errors[0]).name, "AMD timed out");
+        assert_eq!(
+            device_error_warnings(&mixed),
+            vec!["GPU 3 (AMD) could not be queried: rocm-smi timed out after 5s"]
+        );
+
+        let json = serde_json::to_value(SnapshotReport::new(&snapshot)).unwrap();
+        assert_eq!(json["errors"][0]["gpu_index"], 1);
+        assert_eq!(json["errors"][0]["vendor"], "Nvidia");
+        assert_eq!(json["errors"][0]["error"], "NVML: GPU is lost");
+    }
+
+    #[test]
+    fn test_summary_table_marks_maintenance_gpus() {
+        let windows = vec![ActiveMaintenanceWindow {
+            gpu_index: 0,
+            start_time: "22:00".to_string(),
+            end_time: "02:00".to_string(),
+            message: "driver upgrade".to_string(),
+        }];
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN)
+            .with_maintenance_windows(&windows);
+        let table = renderer.format_summary_table(&create_test_snapshot());
+        assert!(table.contains("| 0*  |"));
+
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN);
+        let table = renderer.format_summary_table(&create_test_snapshot());
+        assert!(!table.contains("0*"));
+    }
+
+    #[test]
+    fn test_snapshot_diff_table() {
+        let baseline = create_test_snapshot();
+        let mut current = baseline.clone();
+        current.gpus[0].mem_used_mb = 4096;
+        current.gpus[0].util_pct = 75.0;
+        current.procs.clear();
+        current.procs.push(GpuProc {
+            gpu_index: 0,
+            pid: 777,
+            user: "bob".to_string(),
+            proc_name: "new_job".to_string(),
+            used_mem_mb: 2048,
+            start_time: "1m".to_string(),
+            ..Default::default()
+        });
+
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN);
+        let diff = crate::snapshot::diff_snapshots(&baseline, &current);
+        let output = renderer.format_snapshot_diff(&diff);
+        assert!(output.contains("+2048MB"), "{}", output);
+        assert!(output.contains("50.0% -> 75.0%"), "{}", output);
+        assert!(output.contains("+25.0"), "{}", output);
+        assert!(output.contains("| + "), "{}", output);
+        assert!(output.contains("new_job"), "{}", output);
+        assert!(output.contains("| - "), "{}", output);
+
+        let unchanged = crate::snapshot::diff_snapshots(&baseline, &baseline);
+        assert!(renderer
+            .format_snapshot_diff(&unchanged)
+            .contains("No changes since the baseline"));
+    }
+
+    #[test]
+    fn test_summary_table_rate_columns() {
+        let snapshot = create_test_snapshot();
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN);
+        assert!(!renderer.format_summary_table(&snapshot).contains("Δmem/s"));
+
+        // First watch iteration: columns present but blank
+        let first = renderer.clone().with_rates(BTreeMap::new());
+        let output = first.format_summary_table(&snapshot);
+        assert!(output.contains("Δmem/s"), "{}", output);
+        assert!(output.contains("Δutil"), "{}", output);
+        assert!(!output.contains("MB/s |"), "{}", output);
+
+        let rates = BTreeMap::from([(
+            0,
+            GpuRate {
+                mem_mb_per_sec: 200.0,
+                util_delta_pct: -5.0,
+            },
+        )]);
+        let output = renderer.with_rates(rates).format_summary_table(&snapshot);
+        assert!(output.contains("+200MB/s"), "{}", output);
+        assert!(output.contains("-5.0"), "{}", output);
+    }
+
+    #[test]
+    fn test_process_list_table() {
+        let mut procs = create_test_snapshot().procs;
+        procs.push(GpuProc {
+            gpu_index: 1,
+            pid: 777,
+            user: "bob".to_string(),
+            proc_name: "infer".to_string(),
+            used_mem_mb: 2048,
+            start_time: "1m".to_string(),
+            container: Some("vllm-server".to_string()),
+            cmdline: Some("python serve.py --port 8000".to_string()),
+            ..Default::default()
+        });
+
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN);
+        let output = renderer.format_process_list(&procs);
+        for column in ["GPU", "PID", "USER", "PROC", "VRAM", "CONTAINER?"] {
+            assert!(output.contains(column), "{}", output);
+        }
+        // No GPU summary columns and no command line
+        assert!(!output.contains("UTIL"), "{}", output);
+        assert!(!output.contains("serve.py"), "{}", output);
+        assert!(output.contains("vllm-server"), "{}", output);
+        // Rows keep the order they were given in
+        let infer = output.find("infer").unwrap();
+        let first = output.find(&procs[0].proc_name).unwrap();
+        assert!(first < infer, "{}", output);
+
+        assert_eq!(
+            renderer.format_process_list(&[]),
+            "No GPU processes running"
+        );
+    }
+
     #[test]
     fn test_renderer_creation() {
         let renderer = Renderer::new(OutputFormat::Table);
         assert!(matches!(renderer.output_format, OutputFormat::Table));
     }
 
+    #[test]
+    fn test_capabilities_matrix() {
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN);
+        let report = CapabilitiesReport::new(&[GpuVendor::Amd]);
+        let table = renderer.format_capabilities(&report);
+
+        let amd = table.lines().find(|l| l.contains("AMD")).unwrap();
+        let cells: Vec<&str> = amd
+            .split('|')
+            .map(str::trim)
+            .filter(|c| !c.is_empty())
+            .collect();
+        assert_eq!(cells, ["AMD", "yes", "yes", "no", "yes", "yes"]);
+        assert!(table.contains("Apple"));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["vendors"][2]["vendor"], "Intel");
+        assert_eq!(json["vendors"][2]["reset_supported"], false);
+        assert_eq!(json["vendors"][1]["detected"], true);
+        assert!(json.get("privileges").is_none());
+        assert!(!table.contains("OPERATION"));
+    }
+
+    #[test]
+    fn test_capabilities_with_privileges() {
+        use crate::privileges::Privileges;
+
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN);
+        let alice = Privileges::new("alice", Some(1000), Some(0));
+        let report = CapabilitiesReport::new(&[]).with_privileges(&alice);
+        let table = renderer.format_capabilities(&report);
+        assert!(table.contains("Running as alice (euid 1000); capabilities: none"));
+        let kill = table.lines().find(|l| l.contains("| kill ")).unwrap();
+        assert!(kill.contains("limited"), "{}", kill);
+
+        let root = Privileges::new("root", Some(0), None);
+        let json =
+            serde_json::to_value(CapabilitiesReport::new(&[]).with_privileges(&root)).unwrap();
+        assert_eq!(json["privileges"]["root"], true);
+        assert!(json["privileges"]["operations"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|op| op["access"] == "full"));
+    }
+
     #[test]
     fn test_json_rendering() {
         let renderer = Renderer::new(OutputFormat::Json);
@@ -0,0 +1,724 @@
+//! Structured results of kill, reset and Guard Mode config operations, printed as
+//! a single JSON document with `--output json`
+
+use crate::nvml_api::GpuProc;
+use crate::privileges::{PrivilegeReport, Privileges};
+use crate::proc::KillSignal;
+use crate::process_mgmt::{BatchKillOutcome, SkippedProcess};
+use crate::vendor::{GpuVendor, VendorCapabilities};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+/// What happened to one kill candidate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KillStatus {
+    Killed,
+    Failed,
+    Skipped,
+    WouldKill,
+    WouldSkip,
+    /// Listed but left running because --batch was not given
+    NotKilled,
+}
+
+/// Outcome for one PID of a kill operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KillOutcome {
+    pub pid: u32,
+    pub status: KillStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu_index: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_mb: Option<u32>,
+    /// Signal the process exited after
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signal: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// Why the process was skipped or the kill failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl KillOutcome {
+    pub fn for_pid(pid: u32, status: KillStatus) -> Self {
+        Self {
+            pid,
+            status,
+            process: None,
+            user: None,
+            gpu_index: None,
+            memory_mb: None,
+            signal: None,
+            duration_ms: None,
+            reason: None,
+        }
+    }
+
+    pub fn for_process(process: &GpuProc, status: KillStatus) -> Self {
+        Self {
+            process: Some(process.proc_name.clone()),
+            user: Some(process.user.clone()),
+            gpu_index: Some(process.gpu_index),
+            memory_mb: Some(process.used_mem_mb),
+            ..Self::for_pid(process.pid, status)
+        }
+    }
+
+    pub fn with_reason(mut self, reason: impl Into<String>) -> Self {
+        self.reason = Some(reason.into());
+        self
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.status == KillStatus::Killed
+    }
+
+    /// Whether the process only exited after SIGKILL
+    pub fn needed_sigkill(&self) -> bool {
+        self.signal.as_deref() == Some("SIGKILL")
+    }
+}
+
+/// Result of `--kill`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KillReport {
+    pub operation: String,
+    pub dry_run: bool,
+    /// Signal sent first, before any escalation to SIGKILL
+    pub signal: String,
+    pub timeout_secs: u16,
+    pub force: bool,
+    pub duration_ms: u64,
+    /// GPU memory held by the processes that were killed
+    pub memory_reclaimed_mb: u64,
+    pub processes: Vec<KillOutcome>,
+}
+
+impl KillReport {
+    pub fn new(dry_run: bool, signal: KillSignal, timeout_secs: u16, force: bool) -> Self {
+        Self {
+            operation: "kill".to_string(),
+            dry_run,
+            signal: signal.name().to_string(),
+            timeout_secs,
+            force,
+            duration_ms: 0,
+            memory_reclaimed_mb: 0,
+            processes: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, outcome: KillOutcome) {
+        self.processes.push(outcome);
+    }
+
+    /// Record each process with the same status, e.g. dry-run targets
+    pub fn push_processes(&mut self, processes: &[GpuProc], status: KillStatus) {
+        for p in processes {
+            self.push(KillOutcome::for_process(p, status));
+        }
+    }
+
+    /// Record skipped processes with their reasons
+    pub fn push_skipped(&mut self, skipped: &[SkippedProcess], status: KillStatus) {
+        for (p, reason) in skipped {
+            self.push(KillOutcome::for_process(p, status).with_reason(reason.clone()));
+        }
+    }
+
+    /// Record what a batch kill did with each candidate
+    pub fn push_batch(&mut self, outcome: &BatchKillOutcome) {
+        self.processes.extend(outcome.outcomes());
+    }
+
+    pub fn count(&self, status: KillStatus) -> usize {
+        self.processes.iter().filter(|p| p.status == status).count()
+    }
+
+    /// Set the total duration and the memory reclaimed by killed processes
+    pub fn finish(mut self, started: Instant) -> Self {
+        self.duration_ms = started.elapsed().as_millis() as u64;
+        self.memory_reclaimed_mb = self
+            .processes
+            .iter()
+            .filter(|p| p.succeeded())
+            .filter_map(|p| p.memory_mb)
+            .map(u64::from)
+            .sum();
+        self
+    }
+}
+
+/// What happened to one GPU during a reset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResetStatus {
+    Reset,
+    Failed,
+    WouldReset,
+    /// Dry run: the reset would be refused because processes are active and --force was not given
+    WouldRefuse,
+}
+
+/// Outcome for one GPU of a reset operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResetOutcome {
+    pub gpu_index: u16,
+    pub status: ResetStatus,
+    /// How the GPU is reset, e.g. "nvml", "rocm-smi" or "driver-reload"
+    pub mechanism: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// With --wait-secs: how long the reset waited for the GPU's processes to exit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub waited_ms: Option<u64>,
+    /// Dry run: the processes on the GPU, which a forced reset would terminate
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub processes: Vec<KillOutcome>,
+}
+
+/// Result of `--reset`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResetReport {
+    pub operation: String,
+    pub dry_run: bool,
+    pub hard: bool,
+    pub duration_ms: u64,
+    pub gpus: Vec<ResetOutcome>,
+    /// Steps of a hard reset, e.g. "unload nvidia_uvm: ok"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<String>,
+}
+
+impl ResetReport {
+    pub fn new(dry_run: bool, hard: bool) -> Self {
+        Self {
+            operation: "reset".to_string(),
+            dry_run,
+            hard,
+            duration_ms: 0,
+            gpus: Vec::new(),
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn push(
+        &mut self,
+        gpu_index: u16,
+        status: ResetStatus,
+        mechanism: &str,
+        started: Option<Instant>,
+        error: Option<String>,
+    ) -> &mut ResetOutcome {
+        self.gpus.push(ResetOutcome {
+            gpu_index,
+            status,
+            mechanism: mechanism.to_string(),
+            duration_ms: started.map(|s| s.elapsed().as_millis() as u64),
+            error,
+            waited_ms: None,
+            processes: Vec::new(),
+        });
+        self.gpus.last_mut().expect("outcome was just pushed")
+    }
+
+    /// Record the dry-run outcome of resetting `targets` (GPU index and mechanism)
+    /// while `processes` are running. Without `force` a target with processes is a
+    /// refusal; each target lists its processes, which a forced reset would terminate.
+    pub fn push_preview(&mut self, targets: &[(u16, &str)], processes: &[GpuProc], force: bool) {
+        let affected: Vec<&GpuProc> = processes
+            .iter()
+            .filter(|p| targets.iter().any(|(index, _)| *index == p.gpu_index))
+            .collect();
+        let (status, kill_status, error) = if !force && !affected.is_empty() {
+            (
+                ResetStatus::WouldRefuse,
+                KillStatus::NotKilled,
+                Some("Active processes found. Use --force to override.".to_string()),
+            )
+        } else {
+            (ResetStatus::WouldReset, KillStatus::WouldKill, None)
+        };
+        for (index, mechanism) in targets {
+            let outcome = self.push(*index, status, mechanism, None, error.clone());
+            outcome.processes = affected
+                .iter()
+                .filter(|p| p.gpu_index == *index)
+                .map(|p| KillOutcome::for_process(p, kill_status))
+                .collect();
+        }
+    }
+
+    pub fn finish(mut self, started: Instant) -> Self {
+        self.duration_ms = started.elapsed().as_millis() as u64;
+        self
+    }
+}
+
+/// Result of a Guard Mode config change: what changed and the resulting sections
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GuardConfigReport {
+    pub operation: String,
+    pub changes: Vec<String>,
+    pub config_file: String,
+    /// Effective config sections touched by the changes, keyed by section name
+    pub config: BTreeMap<String, serde_json::Value>,
+}
+
+impl GuardConfigReport {
+    pub fn new(config_file: impl Into<String>) -> Self {
+        Self {
+            operation: "guard".to_string(),
+            changes: Vec::new(),
+            config_file: config_file.into(),
+            config: BTreeMap::new(),
+        }
+    }
+
+    /// Record a change and the config section it touched
+    pub fn changed(&mut self, section: &str, change: impl Into<String>) {
+        self.changes.push(change.into());
+        self.include(section);
+    }
+
+    /// Report a config section without recording a change to it
+    pub fn include(&mut self, section: &str) {
+        self.config.entry(section.to_string()).or_default();
+    }
+
+    /// Fill in the touched sections from the effective config
+    pub fn with_sections<T: Serialize>(mut self, config: &T) -> Result<Self> {
+        let value = serde_json::to_value(config).context("Failed to serialize config")?;
+        for (section, slot) in self.config.iter_mut() {
+            *slot = value
+                .get(section)
+                .cloned()
+                .unwrap_or(serde_json::Value::Null);
+        }
+        Ok(self)
+    }
+}
+
+/// Outcome of one `--selftest` check
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelftestCheck {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+/// Result of `--selftest`: every check in the order it ran
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelftestReport {
+    pub operation: String,
+    /// `gpu` with a real workload, `mock` with a sleep process on a mock GPU
+    pub mode: String,
+    /// GPU memory the workload allocates
+    pub memory_mb: u32,
+    pub passed: bool,
+    pub duration_ms: u64,
+    pub checks: Vec<SelftestCheck>,
+}
+
+impl SelftestReport {
+    pub fn new(mode: &str, memory_mb: u32) -> Self {
+        Self {
+            operation: "selftest".to_string(),
+            mode: mode.to_string(),
+            memory_mb,
+            passed: true,
+            duration_ms: 0,
+            checks: Vec::new(),
+        }
+    }
+
+    /// Record a check; any failed check fails the whole self-test
+    pub fn check(&mut self, name: &str, passed: bool, detail: impl Into<String>) -> &SelftestCheck {
+        self.passed &= passed;
+        self.checks.push(SelftestCheck {
+            name: name.to_string(),
+            passed,
+            detail: detail.into(),
+        });
+        self.checks.last().expect("check was just pushed")
+    }
+
+    /// Names of the checks that failed
+    pub fn failed(&self) -> Vec<&str> {
+        self.checks
+            .iter()
+            .filter(|c| !c.passed)
+            .map(|c| c.name.as_str())
+            .collect()
+    }
+
+    pub fn finish(mut self, started: Instant) -> Self {
+        self.duration_ms = started.elapsed().as_millis() as u64;
+        self
+    }
+}
+
+/// Result of `--capabilities`: what gpukill supports for each vendor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapabilitiesReport {
+    pub operation: String,
+    pub vendors: Vec<VendorCapabilityEntry>,
+    /// Operations available at the current privilege level (`--check-privileges`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub privileges: Option<PrivilegeReport>,
+}
+
+/// One row of the `--capabilities` matrix
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VendorCapabilityEntry {
+    pub vendor: GpuVendor,
+    /// A GPU of this vendor was found on this host
+    pub detected: bool,
+    #[serde(flatten)]
+    pub capabilities: VendorCapabilities,
+}
+
+impl CapabilitiesReport {
+    /// The matrix for every known vendor, marking those in `detected`
+    pub fn new(detected: &[GpuVendor]) -> Self {
+        Self {
+            operation: "capabilities".to_string(),
+            vendors: GpuVendor::known()
+                .into_iter()
+                .map(|vendor| VendorCapabilityEntry {
+                    vendor,
+                    detected: detected.contains(&vendor),
+                    capabilities: vendor.capabilities(),
+                })
+                .collect(),
+            privileges: None,
+        }
+    }
+
+    /// Add what the current user may do, for `--check-privileges`
+    pub fn with_privileges(mut self, privileges: &Privileges) -> Self {
+        self.privileges = Some(privileges.report());
+        self
+    }
+}
+
+/// Result of `--info`: version, build and detected GPUs in one document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InfoReport {
+    pub operation: String,
+    pub version: String,
+    /// Human-readable form, as logged at startup
+    pub version_string: String,
+    pub build_target: String,
+    pub build_date: String,
+    /// Vendors with at least one GPU on this host
+    pub vendors: Vec<GpuVendor>,
+    pub gpu_count: u32,
+    /// Why GPU detection failed, when it did (vendors and count are then empty)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu_error: Option<String>,
+}
+
+impl InfoReport {
+    /// Report for the given detection result
+    pub fn new(vendors: Vec<GpuVendor>, gpu_count: u32, gpu_error: Option<String>) -> Self {
+        Self {
+            operation: "info".to_string(),
+            version: crate::version::VERSION.to_string(),
+            version_string: crate::version::get_version_string(),
+            build_target: crate::version::BUILD_TARGET.to_string(),
+            build_date: crate::version::BUILD_DATE.to_string(),
+            vendors,
+            gpu_count,
+            gpu_error,
+        }
+    }
+
+    /// Detect GPUs on this host. A host without GPUs still gets a report, with
+    /// the detection error recorded instead of failing the probe.
+    pub fn detect() -> Self {
+        let detected = crate::vendor::GpuManager::initialize().and_then(|manager| {
+            let count = manager.total_device_count()?;
+            Ok((manager.get_vendors(), count))
+        });
+        match detected {
+            Ok((vendors, count)) => Self::new(vendors, count, None),
+            Err(e) => Self::new(Vec::new(), 0, Some(e.to_string())),
+        }
+    }
+}
+
+/// Category of a failed operation; each maps to a process exit code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    Nvml,
+    InvalidArgument,
+    PermissionDenied,
+    Unsupported,
+    /// A GPU or process that does not exist; only set from a [`NotFound`] error
+    NotFound,
+    General,
+}
+
+impl ErrorKind {
+    /// Classify an error from its message
+    pub fn classify(message: &str) -> Self {
+        if message.contains("NVML") {
+            ErrorKind::Nvml
+        } else if message.contains("Invalid argument") {
+            ErrorKind::InvalidArgument
+        } else if message.contains("permission") || message.contains("Permission") {
+            ErrorKind::PermissionDenied
+        } else if message.contains("not supported") || message.contains("unsupported") {
+            ErrorKind::Unsupported
+        } else {
+            ErrorKind::General
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ErrorKind::Nvml => 2,
+            ErrorKind::InvalidArgument => 3,
+            ErrorKind::PermissionDenied => 4,
+            ErrorKind::Unsupported => 5,
+            ErrorKind::NotFound | ErrorKind::General => 1,
+        }
+    }
+}
+
+/// Error for a GPU or process that does not exist, reported with kind `not_found`
+#[derive(Debug)]
+pub struct NotFound(pub String);
+
+impl std::fmt::Display for NotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for NotFound {}
+
+/// Error carrying the result of an operation that partly failed, e.g. a batch
+/// kill where some PIDs survived, so `--output json` can report both
+#[derive(Debug)]
+pub struct PartialFailure {
+    pub message: String,
+    pub result: serde_json::Value,
+}
+
+impl PartialFailure {
+    pub fn new<T: Serialize>(message: impl Into<String>, result: &T) -> Self {
+        Self {
+            message: message.into(),
+            result: serde_json::to_value(result).unwrap_or(serde_json::Value::Null),
+        }
+    }
+}
+
+impl std::fmt::Display for PartialFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for PartialFailure {}
+
+/// Machine-readable error printed in place of a result with `--output json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorReport {
+    pub error: ErrorDetail,
+    /// What the operation did before failing, when it got that far
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorDetail {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub exit_code: i32,
+}
+
+impl ErrorReport {
+    pub fn new(message: impl Into<String>) -> Self {
+        let message = message.into();
+        let kind = ErrorKind::classify(&message);
+        Self {
+            error: ErrorDetail {
+                kind,
+                exit_code: kind.exit_code(),
+                message,
+            },
+            result: None,
+        }
+    }
+
+    /// Report an operation error, including the partial result of a `PartialFailure`
+    pub fn from_error(error: &anyhow::Error) -> Self {
+        let mut report = Self::new(error.to_string());
+        if error.chain().any(|cause| cause.is::<NotFound>()) {
+            report.error.kind = ErrorKind::NotFound;
+        }
+        report.result = error
+            .downcast_ref::<PartialFailure>()
+            .map(|failure| failure.result.clone());
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nvml_api::fixtures::proc;
+    use crate::process_mgmt::KilledProcess;
+
+    #[test]
+    fn test_kill_report_counts_reclaimed_memory_of_killed_processes() {
+        let outcome = BatchKillOutcome {
+            killed: vec![KilledProcess {
+                process: proc(0, 10, "alice", "python", 4096),
+                signal: "SIGKILL",
+                duration_ms: 5100,
+            }],
+            failed: vec![(
+                proc(0, 11, "alice", "python", 2048),
+                "permission denied".to_string(),
+            )],
+            permission_denied: vec![11],
+            skipped: vec![(
+                proc(0, 12, "alice", "Xorg", 100),
+                "protected process Xorg".to_string(),
+            )],
+        };
+        let mut report = KillReport::new(false, KillSignal::Term, 5, true);
+        report.push_batch(&outcome);
+        let report = report.finish(Instant::now());
+
+        assert_eq!(report.memory_reclaimed_mb, 4096);
+        assert_eq!(report.count(KillStatus::Killed), 1);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["signal"], "SIGTERM");
+        assert_eq!(json["processes"][0]["signal"], "SIGKILL");
+        assert_eq!(json["processes"][1]["status"], "failed");
+        assert_eq!(json["processes"][1]["reason"], "permission denied");
+        assert_eq!(json["processes"][2]["status"], "skipped");
+    }
+
+    #[test]
+    fn test_not_found_kind_comes_from_the_error_type() {
+        let error = anyhow::Error::new(NotFound("GPU index 7 not found".to_string()))
+            .context("Failed to reset GPU 7");
+        let report = ErrorReport::from_error(&error);
+        assert_eq!(report.error.kind, ErrorKind::NotFound);
+        assert_eq!(report.error.exit_code, 1);
+
+        // The wording alone does not make an error a missing GPU or process
+        let report = ErrorReport::from_error(&anyhow::anyhow!("Custom rule 'x' not found"));
+        assert_eq!(report.error.kind, ErrorKind::General);
+    }
+
+    #[test]
+    fn test_dry_run_kill_reclaims_nothing() {
+        let mut report = KillReport::new(true, KillSignal::Int, 5, false);
+        report.push_processes(
+            &[proc(0, 10, "alice", "python", 4096)],
+            KillStatus::WouldKill,
+        );
+        let report = report.finish(Instant::now());
+
+        assert_eq!(report.memory_reclaimed_mb, 0);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["processes"][0]["status"], "would_kill");
+        assert!(json["processes"][0].get("signal").is_none());
+    }
+
+    #[test]
+    fn test_guard_config_report_includes_touched_sections() {
+        let config = serde_json::json!({
+            "global": {"enabled": true},
+            "user_policies": {"alice": {}},
+            "gpu_policies": {},
+        });
+        let mut report = GuardConfigReport::new("/tmp/guard.toml");
+        report.changed("global", "Guard Mode enabled");
+        report.changed("user_policies", "Added policy for user 'alice'");
+        let report = report.with_sections(&config).unwrap();
+
+        assert_eq!(report.config.len(), 2);
+        assert_eq!(report.config["global"]["enabled"], true);
+        assert!(report.config["user_policies"].get("alice").is_some());
+    }
+
+    #[test]
+    fn test_info_report_json_has_required_keys() {
+        let report = InfoReport::new(vec![GpuVendor::Nvidia], 2, None);
+        let json: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string(&report).unwrap()).unwrap();
+        for key in [
+            "operation",
+            "version",
+            "version_string",
+            "build_target",
+            "build_date",
+            "vendors",
+            "gpu_count",
+        ] {
+            assert!(json.get(key).is_some(), "missing {}", key);
+        }
+        assert_eq!(json["version"], crate::version::VERSION);
+        assert_eq!(json["gpu_count"], 2);
+        assert!(json.get("gpu_error").is_none());
+
+        let no_gpus = InfoReport::new(Vec::new(), 0, Some("No GPUs found".to_string()));
+        let json = serde_json::to_value(&no_gpus).unwrap();
+        assert_eq!(json["gpu_error"], "No GPUs found");
+        assert_eq!(json["vendors"], serde_json::json!([]));
+    }
+
+    #[test]
+    fn test_error_report_kind_and_exit_code() {
+        let cases = [
+            ("Failed to initialize NVML", ErrorKind::Nvml, 2),
+            ("Invalid argument: --gpu", ErrorKind::InvalidArgument, 3),
+            ("Permission denied", ErrorKind::PermissionDenied, 4),
+            ("GPU reset not supported via NVML", ErrorKind::Nvml, 2),
+            ("Intel GPU reset not supported", ErrorKind::Unsupported, 5),
+            ("No GPUs found", ErrorKind::General, 1),
+        ];
+        for (message, kind, exit_code) in cases {
+            let report = ErrorReport::new(message);
+            assert_eq!(report.error.kind, kind, "{}", message);
+            assert_eq!(report.error.exit_code, exit_code);
+        }
+
+        let json = serde_json::to_value(ErrorReport::new("Permission denied")).unwrap();
+        assert_eq!(json["error"]["kind"], "permission_denied");
+        assert_eq!(json["error"]["exit_code"], 4);
+        assert!(json.get("result").is_none());
+    }
+
+    #[test]
+    fn test_error_report_carries_partial_result() {
+        let mut kill = KillReport::new(false, KillSignal::Term, 5, false);
+        kill.push(KillOutcome::for_pid(11, KillStatus::Failed).with_reason("Permission denied"));
+        let error = anyhow::Error::new(PartialFailure::new("Failed to kill 1 processes", &kill));
+        let report = ErrorReport::from_error(&error);
+
+        assert_eq!(report.error.kind, ErrorKind::General);
+        let result = report.result.unwrap();
+        assert_eq!(result["processes"][0]["pid"], 11);
+        assert_eq!(result["processes"][0]["status"], "failed");
+    }
+}
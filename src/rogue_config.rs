@@ -35,6 +35,19 @@ pub struct DetectionConfig {
     pub min_confidence_threshold: f32,
     /// Enable/disable specific detection types
     pub enabled_detections: DetectionTypes,
+    /// Thresholds for the data exfiltration heuristic
+    #[serde(default)]
+    pub exfiltration: ExfiltrationConfig,
+}
+
+/// Data exfiltration thresholds: a process is flagged when its GPU moves at least
+/// `min_transfer_mb_per_sec` over PCIe and NVLink while the process uses at most
+/// `max_compute_util_pct`, for at least `window_minutes` without a break
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExfiltrationConfig {
+    pub min_transfer_mb_per_sec: f32,
+    pub max_compute_util_pct: f32,
+    pub window_minutes: f32,
 }
 
 /// Detection types configuration
@@ -82,6 +95,9 @@ pub struct PatternConfig {
     pub suspicious_process_names: Vec<String>,
     /// Custom regex patterns
     pub custom_patterns: Vec<CustomPattern>,
+    /// Custom detection rules evaluated alongside the built-in heuristics
+    #[serde(default)]
+    pub custom_rules: Vec<CustomRule>,
     /// User whitelist (processes from these users are ignored)
     pub user_whitelist: Vec<String>,
     /// Process whitelist (these processes are ignored)
@@ -99,6 +115,68 @@ pub struct CustomPattern {
     pub confidence_boost: f32,
 }
 
+/// User-defined rule: flags processes whose name matches `proc_name_regex` while
+/// every threshold holds. A threshold of 0 is not checked.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomRule {
+    pub name: String,
+    pub proc_name_regex: String,
+    #[serde(default)]
+    pub min_memory_gb: f32,
+    #[serde(default)]
+    pub min_duration_hours: f32,
+    #[serde(default)]
+    pub min_util_pct: f32,
+    /// Confidence added to a suspicious-process finding when the rule fires
+    pub confidence: f32,
+}
+
+impl CustomRule {
+    /// Check that the rule can be evaluated
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            return Err(anyhow::anyhow!("Custom rule name cannot be empty"));
+        }
+        regex::Regex::new(&self.proc_name_regex).map_err(|e| {
+            anyhow::anyhow!(
+                "Invalid process name regex '{}': {}",
+                self.proc_name_regex,
+                e
+            )
+        })?;
+        if !(0.0..=1.0).contains(&self.confidence) {
+            return Err(anyhow::anyhow!(
+                "Custom rule confidence must be between 0 and 1, got {}",
+                self.confidence
+            ));
+        }
+        if self.min_memory_gb < 0.0 || self.min_duration_hours < 0.0 || self.min_util_pct < 0.0 {
+            return Err(anyhow::anyhow!("Custom rule thresholds cannot be negative"));
+        }
+        Ok(())
+    }
+
+    /// One-line description of the rule's conditions
+    pub fn describe(&self) -> String {
+        let mut conditions = vec![format!("name =~ /{}/", self.proc_name_regex)];
+        if self.min_memory_gb > 0.0 {
+            conditions.push(format!("memory >= {:.1} GB", self.min_memory_gb));
+        }
+        if self.min_util_pct > 0.0 {
+            conditions.push(format!("utilization >= {:.1}%", self.min_util_pct));
+        }
+        if self.min_duration_hours > 0.0 {
+            conditions.push(format!("for >= {:.1}h", self.min_duration_hours));
+        }
+        format!(
+            "{}: {} (confidence +{:.2})",
+            self.name,
+            conditions.join(", "),
+            self.confidence
+        )
+    }
+}
+
 /// Pattern types
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum PatternType {
@@ -186,17 +264,51 @@ impl Default for DetectionConfig {
             max_duration_hours: 24.0,
             min_confidence_threshold: 0.7,
             enabled_detections: DetectionTypes::default(),
+            exfiltration: ExfiltrationConfig::default(),
         }
     }
 }
 
+impl Default for ExfiltrationConfig {
+    fn default() -> Self {
+        Self {
+            min_transfer_mb_per_sec: 1000.0,
+            max_compute_util_pct: 10.0,
+            window_minutes: 10.0,
+        }
+    }
+}
+
+impl ExfiltrationConfig {
+    /// Check that the thresholds can be evaluated
+    pub fn validate(&self) -> Result<()> {
+        if self.min_transfer_mb_per_sec <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "Exfiltration transfer threshold must be greater than 0 MB/s"
+            ));
+        }
+        if !(0.0..=100.0).contains(&self.max_compute_util_pct) {
+            return Err(anyhow::anyhow!(
+                "Exfiltration utilization threshold must be between 0 and 100%, got {}",
+                self.max_compute_util_pct
+            ));
+        }
+        if self.window_minutes <= 0.0 {
+            return Err(anyhow::anyhow!(
+                "Exfiltration window must be greater than 0 minutes"
+            ));
+        }
+        Ok(())
+    }
+}
+
 impl Default for DetectionTypes {
     fn default() -> Self {
         Self {
             crypto_miners: true,
             suspicious_processes: true,
             resource_abusers: true,
-            data_exfiltrators: false, // Disabled by default as it requires network monitoring
+            data_exfiltrators: false,
         }
     }
 }
@@ -250,6 +362,7 @@ impl Default for PatternConfig {
                 "gminer".to_string(),
             ],
             custom_patterns: Vec::new(),
+            custom_rules: Vec::new(),
             user_whitelist: vec![
                 "root".to_string(),
                 "admin".to_string(),
@@ -356,8 +469,8 @@ impl RogueConfigManager {
         let content = fs::read_to_string(path)
             .map_err(|e| anyhow::anyhow!("Failed to read config file: {}", e))?;
 
-        let config: RogueConfig = toml::from_str(&content)
-            .map_err(|e| anyhow::anyhow!("Failed to parse config file: {}", e))?;
+        let config: RogueConfig = crate::env_expand::from_toml_str(&content)
+            .map_err(|e| anyhow::anyhow!("Failed to parse config file: {:#}", e))?;
 
         info!(
             "Loaded rogue detection configuration from: {}",
@@ -368,7 +481,7 @@ impl RogueConfigManager {
 
     /// Save configuration to file
     fn save_config(path: &PathBuf, config: &RogueConfig) -> Result<()> {
-        let content = toml::to_string_pretty(config)
+        let content = crate::env_expand::to_toml_string_for(config, path)
             .map_err(|e| anyhow::anyhow!("Failed to serialize config: {}", e))?;
 
         fs::write(path, content)
@@ -408,7 +521,47 @@ impl RogueConfigManager {
             min_confidence_threshold: self.config.detection.min_confidence_threshold,
             user_whitelist: self.config.patterns.user_whitelist.clone(),
             process_whitelist: self.config.patterns.process_whitelist.clone(),
+            custom_rules: self.config.patterns.custom_rules.clone(),
+            exfiltration: self.config.detection.exfiltration.clone(),
+        }
+    }
+
+    /// Add a custom detection rule; names must be unique
+    pub fn add_custom_rule(&mut self, rule: CustomRule) -> Result<()> {
+        rule.validate()?;
+        if self
+            .config
+            .patterns
+            .custom_rules
+            .iter()
+            .any(|r| r.name == rule.name)
+        {
+            return Err(anyhow::anyhow!(
+                "Custom rule '{}' already exists",
+                rule.name
+            ));
+        }
+        self.config.patterns.custom_rules.push(rule);
+        self.config.metadata.last_modified = chrono::Utc::now().to_rfc3339();
+        Self::save_config(&self.config_path, &self.config)?;
+        Ok(())
+    }
+
+    /// Remove a custom detection rule by name
+    pub fn remove_custom_rule(&mut self, name: &str) -> Result<()> {
+        let before = self.config.patterns.custom_rules.len();
+        self.config.patterns.custom_rules.retain(|r| r.name != name);
+        if self.config.patterns.custom_rules.len() == before {
+            return Err(anyhow::anyhow!("Custom rule '{}' not found", name));
         }
+        self.config.metadata.last_modified = chrono::Utc::now().to_rfc3339();
+        Self::save_config(&self.config_path, &self.config)?;
+        Ok(())
+    }
+
+    /// Custom detection rules in evaluation order
+    pub fn custom_rules(&self) -> &[CustomRule] {
+        &self.config.patterns.custom_rules
     }
 
     /// Add a custom pattern
@@ -514,6 +667,31 @@ impl RogueConfigManager {
         Ok(())
     }
 
+    /// Update data exfiltration thresholds; unset values keep their current setting
+    pub fn update_exfiltration_thresholds(
+        &mut self,
+        min_transfer_mb_per_sec: Option<f32>,
+        max_compute_util_pct: Option<f32>,
+        window_minutes: Option<f32>,
+    ) -> Result<()> {
+        let mut exfiltration = self.config.detection.exfiltration.clone();
+        if let Some(rate) = min_transfer_mb_per_sec {
+            exfiltration.min_transfer_mb_per_sec = rate;
+        }
+        if let Some(utilization) = max_compute_util_pct {
+            exfiltration.max_compute_util_pct = utilization;
+        }
+        if let Some(window) = window_minutes {
+            exfiltration.window_minutes = window;
+        }
+        exfiltration.validate()?;
+
+        self.config.detection.exfiltration = exfiltration;
+        self.config.metadata.last_modified = chrono::Utc::now().to_rfc3339();
+        Self::save_config(&self.config_path, &self.config)?;
+        Ok(())
+    }
+
     /// Enable/disable detection types
     #[allow(dead_code)]
     pub fn toggle_detection_type(&mut self, detection_type: &str, enabled: bool) -> Result<()> {
@@ -557,8 +735,8 @@ impl RogueConfigManager {
 
     /// Import configuration from JSON
     pub fn import_from_json(&mut self, json: &str) -> Result<()> {
-        let config: RogueConfig = serde_json::from_str(json)
-            .map_err(|e| anyhow::anyhow!("Failed to import config from JSON: {}", e))?;
+        let config: RogueConfig = crate::env_expand::from_json_str(json)
+            .map_err(|e| anyhow::anyhow!("Failed to import config from JSON: {:#}", e))?;
 
         self.update_config(config)?;
         Ok(())
@@ -615,4 +793,89 @@ mod tests {
         let processes = &manager.get_config().patterns.process_whitelist;
         assert!(!processes.iter().any(|p| p.eq_ignore_ascii_case("python")));
     }
+
+    #[test]
+    fn test_custom_rule_management() {
+        let temp_dir = tempdir().unwrap();
+        let mut manager = RogueConfigManager {
+            config_path: temp_dir.path().join("rogue_config.toml"),
+            config: RogueConfig::default(),
+        };
+
+        let rule = CustomRule {
+            name: "workers".to_string(),
+            proc_name_regex: r"^worker_\d+$".to_string(),
+            min_memory_gb: 15.0,
+            min_duration_hours: 3.0,
+            min_util_pct: 0.0,
+            confidence: 0.5,
+        };
+        manager.add_custom_rule(rule.clone()).unwrap();
+        assert!(manager.add_custom_rule(rule.clone()).is_err());
+        assert!(manager
+            .add_custom_rule(CustomRule {
+                name: "broken".to_string(),
+                proc_name_regex: "worker_(".to_string(),
+                ..rule.clone()
+            })
+            .is_err());
+        assert!(manager
+            .add_custom_rule(CustomRule {
+                name: "too-sure".to_string(),
+                confidence: 1.5,
+                ..rule.clone()
+            })
+            .is_err());
+
+        // Rules survive a round trip through the config file
+        let loaded = RogueConfigManager::load_config(&manager.config_path).unwrap();
+        assert_eq!(loaded.patterns.custom_rules, vec![rule.clone()]);
+        assert_eq!(manager.to_detection_rules().custom_rules, vec![rule]);
+
+        manager.remove_custom_rule("workers").unwrap();
+        assert!(manager.custom_rules().is_empty());
+        assert!(manager.remove_custom_rule("workers").is_err());
+    }
+
+    #[test]
+    fn test_exfiltration_threshold_updates() {
+        let temp_dir = tempdir().unwrap();
+        let mut manager = RogueConfigManager {
+            config_path: temp_dir.path().join("rogue_config.toml"),
+            config: RogueConfig::default(),
+        };
+
+        manager
+            .update_exfiltration_thresholds(Some(500.0), None, Some(5.0))
+            .unwrap();
+        let expected = ExfiltrationConfig {
+            min_transfer_mb_per_sec: 500.0,
+            max_compute_util_pct: 10.0,
+            window_minutes: 5.0,
+        };
+        assert_eq!(manager.to_detection_rules().exfiltration, expected);
+        let loaded = RogueConfigManager::load_config(&manager.config_path).unwrap();
+        assert_eq!(loaded.detection.exfiltration, expected);
+
+        // Invalid values are rejected and leave the thresholds unchanged
+        assert!(manager
+            .update_exfiltration_thresholds(None, Some(150.0), None)
+            .is_err());
+        assert!(manager
+            .update_exfiltration_thresholds(Some(0.0), None, None)
+            .is_err());
+        assert_eq!(manager.get_config().detection.exfiltration, expected);
+    }
+
+    #[test]
+    fn test_config_without_exfiltration_section_loads() {
+        // Config files written before the exfiltration thresholds existed
+        let mut config = toml::Value::try_from(RogueConfig::default()).unwrap();
+        config["detection"]
+            .as_table_mut()
+            .unwrap()
+            .remove("exfiltration");
+        let config: RogueConfig = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(config.detection.exfiltration, ExfiltrationConfig::default());
+    }
 }
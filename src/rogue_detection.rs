@@ -6,6 +6,7 @@ use tracing::{debug, info};
 
 use crate::audit::{AuditManager, AuditRecord};
 use crate::nvml_api::GpuProc;
+use crate::rogue_config::{CustomRule, ExfiltrationConfig};
 
 /// Rogue detection result
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -53,6 +54,12 @@ pub struct DataExfiltrator {
     pub exfil_indicators: Vec<String>,
     pub confidence: f32,
     pub data_volume_mb: Option<f32>,
+    /// Average PCIe plus NVLink transfer rate over the flagged window
+    #[serde(default)]
+    pub observed_mb_per_sec: f32,
+    /// Length of the flagged window
+    #[serde(default)]
+    pub duration_minutes: f32,
 }
 
 /// Risk levels for suspicious activity
@@ -77,6 +84,8 @@ pub enum AbuseType {
 pub struct RogueDetector {
     audit_manager: AuditManager,
     detection_rules: DetectionRules,
+    /// Custom rules with their process name regex compiled once
+    custom_rules: Vec<(CustomRule, regex::Regex)>,
 }
 
 /// Configurable detection rules
@@ -92,6 +101,10 @@ pub struct DetectionRules {
     pub user_whitelist: Vec<String>,
     /// Processes in this list are exempt from rogue detection
     pub process_whitelist: Vec<String>,
+    /// User-defined rules evaluated during suspicious process detection
+    pub custom_rules: Vec<CustomRule>,
+    /// Thresholds for flagging sustained transfers with little compute
+    pub exfiltration: ExfiltrationConfig,
 }
 
 impl Default for DetectionRules {
@@ -134,17 +147,17 @@ impl Default for DetectionRules {
                 "pytorch".to_string(),
                 "nvidia-smi".to_string(),
             ],
+            custom_rules: Vec::new(),
+            exfiltration: ExfiltrationConfig::default(),
         }
     }
 }
 
 impl RogueDetector {
     /// Create a new rogue detector
+    #[allow(dead_code)]
     pub fn new(audit_manager: AuditManager) -> Self {
-        Self {
-            audit_manager,
-            detection_rules: DetectionRules::default(),
-        }
+        Self::with_rules(audit_manager, DetectionRules::default())
     }
 
     /// Create a new rogue detector with configuration
@@ -152,18 +165,27 @@ impl RogueDetector {
         audit_manager: AuditManager,
         config_manager: &crate::rogue_config::RogueConfigManager,
     ) -> Self {
-        Self {
-            audit_manager,
-            detection_rules: config_manager.to_detection_rules(),
-        }
+        Self::with_rules(audit_manager, config_manager.to_detection_rules())
     }
 
     /// Create a new rogue detector with custom rules
-    #[allow(dead_code)]
     pub fn with_rules(audit_manager: AuditManager, rules: DetectionRules) -> Self {
+        // Rules are validated when added, so a bad regex here means a hand-edited config
+        let custom_rules = rules
+            .custom_rules
+            .iter()
+            .filter_map(|rule| match regex::Regex::new(&rule.proc_name_regex) {
+                Ok(regex) => Some((rule.clone(), regex)),
+                Err(e) => {
+                    tracing::warn!(rule = %rule.name, error = %e, "Skipping custom rule with invalid regex");
+                    None
+                }
+            })
+            .collect();
         Self {
             audit_manager,
             detection_rules: rules,
+            custom_rules,
         }
     }
 
@@ -234,8 +256,35 @@ impl RogueDetector {
         Ok(result)
     }
 
-    /// Analyze a provided list of audit records (e.g. from cluster snapshots) for rogue activity.
-    /// Does not query the audit manager; use this for cluster-wide analysis.
+    /// Analyze the audit records `hostname` logged in the last `hours`. A cluster node
+    /// scans its own history this way, even when the audit database is shared with
+    /// other nodes; records without a hostname predate host tracking and are kept.
+    pub async fn detect_rogue_activity_on_host(
+        &self,
+        hours: u32,
+        hostname: &str,
+    ) -> Result<RogueDetectionResult> {
+        info!(
+            "Starting rogue activity detection for {} over the last {} hours",
+            hostname, hours
+        );
+        let records = self
+            .audit_manager
+            .query_records(hours, None, None)
+            .await?
+            .into_iter()
+            .filter(|record| {
+                record
+                    .hostname
+                    .as_deref()
+                    .is_none_or(|host| host == hostname)
+            })
+            .collect();
+        self.detect_rogue_activity_from_records(records).await
+    }
+
+    /// Analyze a provided list of audit records for rogue activity.
+    /// Does not query the audit manager.
     pub async fn detect_rogue_activity_from_records(
         &self,
         records: Vec<AuditRecord>,
@@ -376,6 +425,20 @@ impl RogueDetector {
                     }
                 }
             }
+            // Miners are often launched through an interpreter or a renamed binary, so
+            // also look for known miner names in the arguments. The generic patterns
+            // ("cuda", "hash", ...) are too common in ordinary command lines to use here.
+            if let Some(cmdline) = &record.cmdline {
+                let cmdline_lower = cmdline.to_lowercase();
+                for miner_name in &self.detection_rules.suspicious_process_names {
+                    if cmdline_lower.contains(miner_name)
+                        && miner_matched.insert(miner_name.clone())
+                    {
+                        indicators.push(format!("Command line references miner: {}", miner_name));
+                        score += 0.5;
+                    }
+                }
+            }
             if score > best_score {
                 best_score = score;
                 best_idx = Some(idx);
@@ -442,6 +505,10 @@ impl RogueDetector {
                 start_time: "unknown".to_string(),
                 container: record.container.clone(),
                 node_id: record.node_id.clone(),
+                cmdline: record.cmdline.clone(),
+                container_pid: None,
+                slurm_job_id: record.slurm_job_id.clone(),
+                start_unix_secs: record.start_unix_secs,
             };
 
             Some(CryptoMiner {
@@ -495,6 +562,17 @@ impl RogueDetector {
             }
         }
 
+        // User-defined rules add their own confidence
+        for (rule, regex) in &self.custom_rules {
+            if let Some((idx, reason)) = Self::evaluate_custom_rule(rule, regex, records) {
+                reasons.push(reason);
+                confidence += rule.confidence;
+                if representative_idx == 0 {
+                    representative_idx = idx;
+                }
+            }
+        }
+
         let representative = &records[representative_idx];
 
         // Check for high resource usage (aggregate)
@@ -528,6 +606,10 @@ impl RogueDetector {
                 start_time: "unknown".to_string(),
                 container: representative.container.clone(),
                 node_id: representative.node_id.clone(),
+                cmdline: representative.cmdline.clone(),
+                container_pid: None,
+                slurm_job_id: None,
+                start_unix_secs: None,
             };
 
             Some(SuspiciousProcess {
@@ -541,6 +623,52 @@ impl RogueDetector {
         }
     }
 
+    /// Check one custom rule against a process's records. The rule fires when the
+    /// samples whose name matches also meet the memory and utilization minimums on
+    /// average and span at least the minimum duration. Returns the index of a
+    /// matching record and the reason to report.
+    fn evaluate_custom_rule(
+        rule: &CustomRule,
+        regex: &regex::Regex,
+        records: &[AuditRecord],
+    ) -> Option<(usize, String)> {
+        let matching: Vec<(usize, &AuditRecord)> = records
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| {
+                r.process_name.as_deref().is_some_and(|n| regex.is_match(n))
+                    || r.cmdline.as_deref().is_some_and(|c| regex.is_match(c))
+            })
+            .collect();
+        let (first_idx, _) = *matching.first()?;
+
+        let count = matching.len() as f32;
+        let avg_memory_gb = matching
+            .iter()
+            .map(|(_, r)| r.memory_used_mb as f32 / 1024.0)
+            .sum::<f32>()
+            / count;
+        let avg_util = matching.iter().map(|(_, r)| r.utilization_pct).sum::<f32>() / count;
+        let first = matching.iter().map(|(_, r)| r.timestamp).min()?;
+        let last = matching.iter().map(|(_, r)| r.timestamp).max()?;
+        let duration_hours = (last - first).num_seconds() as f32 / 3600.0;
+
+        if avg_memory_gb < rule.min_memory_gb
+            || avg_util < rule.min_util_pct
+            || duration_hours < rule.min_duration_hours
+        {
+            return None;
+        }
+
+        Some((
+            first_idx,
+            format!(
+                "Custom rule '{}': {:.1} GB, {:.1}% utilization for {:.1}h",
+                rule.name, avg_memory_gb, avg_util, duration_hours
+            ),
+        ))
+    }
+
     /// Detect resource abuse. Only skips if all records are whitelisted.
     fn detect_resource_abuser(&self, records: &[AuditRecord]) -> Option<ResourceAbuser> {
         if records.is_empty() {
@@ -606,6 +734,10 @@ impl RogueDetector {
                 start_time: "unknown".to_string(),
                 container: record.container.clone(),
                 node_id: record.node_id.clone(),
+                cmdline: record.cmdline.clone(),
+                container_pid: None,
+                slurm_job_id: record.slurm_job_id.clone(),
+                start_unix_secs: record.start_unix_secs,
             };
 
             Some(ResourceAbuser {
@@ -619,11 +751,98 @@ impl RogueDetector {
         }
     }
 
-    /// Detect data exfiltration (placeholder - would need network monitoring)
-    fn detect_data_exfiltrator(&self, _records: &[AuditRecord]) -> Option<DataExfiltrator> {
-        // This would require network monitoring data
-        // For now, we'll implement basic heuristics
-        None
+    /// Detect data exfiltration: the GPU moves data over PCIe and NVLink at a high
+    /// rate while the process barely computes, for at least the configured window.
+    fn detect_data_exfiltrator(&self, records: &[AuditRecord]) -> Option<DataExfiltrator> {
+        if records.is_empty() || self.all_records_whitelisted(records) {
+            return None;
+        }
+
+        let thresholds = &self.detection_rules.exfiltration;
+        let mut samples: Vec<&AuditRecord> = records.iter().collect();
+        samples.sort_by_key(|r| r.timestamp);
+
+        // Longest run of consecutive samples with heavy transfers and little compute
+        let mut best: Option<TransferWindow> = None;
+        let mut current: Option<TransferWindow> = None;
+        for (i, record) in samples.iter().enumerate() {
+            let previous = i.checked_sub(1).map(|p| samples[p]);
+            let rate = transfer_rate_mb_per_sec(previous, record);
+            let qualifies = rate.is_some_and(|rate| rate >= thresholds.min_transfer_mb_per_sec)
+                && record.utilization_pct <= thresholds.max_compute_util_pct;
+            if !qualifies {
+                current = None;
+                continue;
+            }
+
+            let window = current.get_or_insert(TransferWindow {
+                start: record.timestamp,
+                end: record.timestamp,
+                rate_total: 0.0,
+                samples: 0,
+                util_total: 0.0,
+                last: i,
+            });
+            window.end = record.timestamp;
+            window.rate_total += rate.unwrap_or_default();
+            window.util_total += record.utilization_pct;
+            window.samples += 1;
+            window.last = i;
+            if best
+                .as_ref()
+                .is_none_or(|b| window.duration_minutes() > b.duration_minutes())
+            {
+                best = Some(window.clone());
+            }
+        }
+
+        let window = best?;
+        let duration_minutes = window.duration_minutes();
+        if duration_minutes < thresholds.window_minutes {
+            return None;
+        }
+
+        let observed_mb_per_sec = window.rate_total / window.samples as f32;
+        let avg_util = window.util_total / window.samples as f32;
+        let confidence = exfiltration_confidence(observed_mb_per_sec, duration_minutes, thresholds);
+        if confidence < self.detection_rules.min_confidence_threshold {
+            return None;
+        }
+
+        let record = samples[window.last];
+        Some(DataExfiltrator {
+            process: GpuProc {
+                gpu_index: record.gpu_index,
+                pid: record.pid.unwrap_or(0),
+                user: record.user.clone().unwrap_or_else(|| "unknown".to_string()),
+                proc_name: record
+                    .process_name
+                    .clone()
+                    .unwrap_or_else(|| "unknown".to_string()),
+                used_mem_mb: record.memory_used_mb,
+                start_time: "unknown".to_string(),
+                container: record.container.clone(),
+                node_id: record.node_id.clone(),
+                cmdline: record.cmdline.clone(),
+                container_pid: None,
+                slurm_job_id: record.slurm_job_id.clone(),
+                start_unix_secs: record.start_unix_secs,
+            },
+            exfil_indicators: vec![
+                format!(
+                    "GPU {} transferred {:.0} MB/s over PCIe/NVLink for {:.1} minutes",
+                    record.gpu_index, observed_mb_per_sec, duration_minutes
+                ),
+                format!(
+                    "Average compute utilization only {:.1}% during the transfers",
+                    avg_util
+                ),
+            ],
+            confidence,
+            data_volume_mb: Some(observed_mb_per_sec * duration_minutes * 60.0),
+            observed_mb_per_sec,
+            duration_minutes,
+        })
     }
 
     /// Calculate average GPU utilization for a process
@@ -651,23 +870,25 @@ impl RogueDetector {
         Some(total_memory / records.len() as f32)
     }
 
-    /// Calculate process duration in hours
+    /// Process duration in hours: the span covered by the records, or, when a record
+    /// carries the process start time, the time from that start to the newest record,
+    /// whichever is longer. The two overlap, so they are never added together. None
+    /// when a single record has no start time.
     fn calculate_process_duration(&self, records: &[AuditRecord]) -> Option<f32> {
-        if records.len() < 2 {
-            return None;
-        }
-
-        let timestamps: Vec<DateTime<Utc>> = records.iter().map(|r| r.timestamp).collect();
+        let min_time = records.iter().map(|r| r.timestamp).min()?;
+        let max_time = records.iter().map(|r| r.timestamp).max()?;
+        let observed = (records.len() >= 2).then_some(max_time - min_time);
 
-        if timestamps.len() < 2 {
-            return None;
-        }
-
-        let min_time = timestamps.iter().min()?;
-        let max_time = timestamps.iter().max()?;
-        let duration = (*max_time - *min_time).num_seconds() as f32 / 3600.0;
+        let since_start = records
+            .iter()
+            .filter_map(|r| {
+                let started = r.started_at()?;
+                (started <= max_time).then_some(max_time - started)
+            })
+            .max();
 
-        Some(duration)
+        let duration = observed.into_iter().chain(since_start).max()?;
+        Some(duration.num_seconds() as f32 / 3600.0)
     }
 
     /// Estimate hashrate for crypto mining (placeholder)
@@ -718,7 +939,7 @@ impl RogueDetector {
         suspicious: &[SuspiciousProcess],
         miners: &[CryptoMiner],
         abusers: &[ResourceAbuser],
-        _exfiltrators: &[DataExfiltrator],
+        exfiltrators: &[DataExfiltrator],
     ) -> f32 {
         let mut score = 0.0;
 
@@ -740,6 +961,10 @@ impl RogueDetector {
             score += abuser.severity * 0.3; // Resource abuse is medium priority
         }
 
+        for exfiltrator in exfiltrators {
+            score += exfiltrator.confidence * 0.9; // Data leaving the GPU is highest priority
+        }
+
         // Normalize to 0-1 scale
         (score / 10.0).min(1.0)
     }
@@ -750,7 +975,7 @@ impl RogueDetector {
         suspicious: &[SuspiciousProcess],
         miners: &[CryptoMiner],
         abusers: &[ResourceAbuser],
-        _exfiltrators: &[DataExfiltrator],
+        exfiltrators: &[DataExfiltrator],
     ) -> Vec<String> {
         let mut recommendations = Vec::new();
 
@@ -773,6 +998,13 @@ impl RogueDetector {
             );
         }
 
+        if !exfiltrators.is_empty() {
+            recommendations.push(
+                "🚨 Sustained GPU transfers with little compute detected. Check where the data is going."
+                    .to_string(),
+            );
+        }
+
         if recommendations.is_empty() {
             recommendations.push("✅ No suspicious activity detected.".to_string());
         }
@@ -781,6 +1013,50 @@ impl RogueDetector {
     }
 }
 
+/// A run of consecutive samples that met the exfiltration thresholds
+#[derive(Clone)]
+struct TransferWindow {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    rate_total: f32,
+    util_total: f32,
+    samples: usize,
+    /// Index of the newest sample in the run
+    last: usize,
+}
+
+impl TransferWindow {
+    fn duration_minutes(&self) -> f32 {
+        (self.end - self.start).num_seconds() as f32 / 60.0
+    }
+}
+
+/// Transfer rate of a sample in MB/s: its PCIe throughput plus the NVLink rate since
+/// the previous sample. A reset NVLink counter contributes nothing for that sample.
+fn transfer_rate_mb_per_sec(previous: Option<&AuditRecord>, record: &AuditRecord) -> Option<f32> {
+    let nvlink = previous.and_then(|previous| {
+        let elapsed = (record.timestamp - previous.timestamp).num_milliseconds() as f32 / 1000.0;
+        let bytes = record.nvlink_bytes?.checked_sub(previous.nvlink_bytes?)?;
+        (elapsed > 0.0).then(|| bytes as f32 / (1024.0 * 1024.0) / elapsed)
+    });
+    match (record.pcie_mb_per_sec, nvlink) {
+        (None, None) => None,
+        (pcie, nvlink) => Some(pcie.unwrap_or(0.0) + nvlink.unwrap_or(0.0)),
+    }
+}
+
+/// Confidence for a flagged window: 0.7 at the thresholds, rising as the rate and the
+/// duration exceed them, up to 1.0 at twice both
+fn exfiltration_confidence(
+    mb_per_sec: f32,
+    duration_minutes: f32,
+    thresholds: &ExfiltrationConfig,
+) -> f32 {
+    let excess = |value: f32, threshold: f32| (value / threshold - 1.0).clamp(0.0, 1.0);
+    0.7 + 0.15 * excess(mb_per_sec, thresholds.min_transfer_mb_per_sec)
+        + 0.15 * excess(duration_minutes, thresholds.window_minutes)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -860,6 +1136,14 @@ mod tests {
                 power_w: 150.0,
                 container: None,
                 node_id: None,
+                hostname: None,
+                cmdline: None,
+                ecc_uncorrected: None,
+                ecc_uncorrected_delta: None,
+                pcie_mb_per_sec: None,
+                nvlink_bytes: None,
+                slurm_job_id: None,
+                start_unix_secs: None,
             },
             AuditRecord {
                 id: 2,
@@ -875,6 +1159,14 @@ mod tests {
                 power_w: 150.0,
                 container: None,
                 node_id: None,
+                hostname: None,
+                cmdline: None,
+                ecc_uncorrected: None,
+                ecc_uncorrected_delta: None,
+                pcie_mb_per_sec: None,
+                nvlink_bytes: None,
+                slurm_job_id: None,
+                start_unix_secs: None,
             },
         ];
 
@@ -916,6 +1208,14 @@ mod tests {
                 power_w: 150.0,
                 container: None,
                 node_id: None,
+                hostname: None,
+                cmdline: None,
+                ecc_uncorrected: None,
+                ecc_uncorrected_delta: None,
+                pcie_mb_per_sec: None,
+                nvlink_bytes: None,
+                slurm_job_id: None,
+                start_unix_secs: None,
             },
             AuditRecord {
                 id: 2,
@@ -931,6 +1231,14 @@ mod tests {
                 power_w: 150.0,
                 container: None,
                 node_id: None,
+                hostname: None,
+                cmdline: None,
+                ecc_uncorrected: None,
+                ecc_uncorrected_delta: None,
+                pcie_mb_per_sec: None,
+                nvlink_bytes: None,
+                slurm_job_id: None,
+                start_unix_secs: None,
             },
         ];
 
@@ -975,6 +1283,14 @@ mod tests {
                 power_w: 150.0,
                 container: None,
                 node_id: None,
+                hostname: None,
+                cmdline: None,
+                ecc_uncorrected: None,
+                ecc_uncorrected_delta: None,
+                pcie_mb_per_sec: None,
+                nvlink_bytes: None,
+                slurm_job_id: None,
+                start_unix_secs: None,
             },
             AuditRecord {
                 id: 2,
@@ -990,6 +1306,14 @@ mod tests {
                 power_w: 150.0,
                 container: None,
                 node_id: None,
+                hostname: None,
+                cmdline: None,
+                ecc_uncorrected: None,
+                ecc_uncorrected_delta: None,
+                pcie_mb_per_sec: None,
+                nvlink_bytes: None,
+                slurm_job_id: None,
+                start_unix_secs: None,
             },
         ];
 
@@ -1000,4 +1324,436 @@ mod tests {
         assert_eq!(abuser.abuse_type, AbuseType::MemoryHog);
         assert!(abuser.severity >= 2.0);
     }
+
+    #[tokio::test]
+    async fn test_custom_rule_fires_and_contributes_confidence() {
+        use crate::audit::AuditRecord;
+        use chrono::Utc;
+
+        let rules = DetectionRules {
+            min_confidence_threshold: 0.6,
+            custom_rules: vec![
+                CustomRule {
+                    name: "long-workers".to_string(),
+                    proc_name_regex: r"^worker_\d+$".to_string(),
+                    min_memory_gb: 15.0,
+                    min_duration_hours: 3.0,
+                    min_util_pct: 0.0,
+                    confidence: 0.8,
+                },
+                // Matches the name but the utilization floor is never reached
+                CustomRule {
+                    name: "hot-workers".to_string(),
+                    proc_name_regex: r"^worker_".to_string(),
+                    min_memory_gb: 0.0,
+                    min_duration_hours: 0.0,
+                    min_util_pct: 90.0,
+                    confidence: 0.8,
+                },
+            ],
+            ..DetectionRules::default()
+        };
+        let detector = RogueDetector::with_rules(AuditManager::new().await.unwrap(), rules);
+
+        let start = Utc::now() - chrono::Duration::hours(4);
+        let record = |hour: i64, name: &str, memory_gb: u32| AuditRecord {
+            id: hour,
+            timestamp: start + chrono::Duration::hours(hour),
+            gpu_index: 0,
+            gpu_name: "Test GPU".to_string(),
+            pid: Some(4242),
+            user: Some("carol".to_string()),
+            process_name: Some(name.to_string()),
+            memory_used_mb: memory_gb * 1024,
+            utilization_pct: 50.0,
+            temperature_c: 0,
+            power_w: 0.0,
+            container: None,
+            node_id: None,
+            hostname: None,
+            cmdline: None,
+            ecc_uncorrected: None,
+            ecc_uncorrected_delta: None,
+            pcie_mb_per_sec: None,
+            nvlink_bytes: None,
+            slurm_job_id: None,
+            start_unix_secs: None,
+        };
+
+        // 16 GB for 4 hours: fires
+        let records: Vec<AuditRecord> = (0..=4).map(|h| record(h, "worker_17", 16)).collect();
+        let suspicious = detector
+            .detect_suspicious_process(&records)
+            .expect("custom rule should flag the process");
+        assert_eq!(suspicious.process.proc_name, "worker_17");
+        assert!((suspicious.confidence - 0.8).abs() < 1e-6);
+        assert!(suspicious
+            .reasons
+            .iter()
+            .any(|r| r.starts_with("Custom rule 'long-workers'")));
+        assert!(!suspicious.reasons.iter().any(|r| r.contains("hot-workers")));
+
+        // Same name and memory, but only 2 hours: does not fire
+        let records: Vec<AuditRecord> = (0..=2).map(|h| record(h, "worker_17", 16)).collect();
+        assert!(detector.detect_suspicious_process(&records).is_none());
+
+        // Long-running but the name does not match
+        let records: Vec<AuditRecord> = (0..=4).map(|h| record(h, "trainer", 16)).collect();
+        assert!(detector.detect_suspicious_process(&records).is_none());
+    }
+
+    /// One sample per minute for a process on GPU 0
+    fn transfer_series(
+        minutes: i64,
+        sample: impl Fn(i64) -> (Option<f32>, Option<u64>, f32),
+    ) -> Vec<AuditRecord> {
+        let start = Utc::now() - chrono::Duration::minutes(minutes);
+        (0..=minutes)
+            .map(|minute| {
+                let (pcie_mb_per_sec, nvlink_bytes, utilization_pct) = sample(minute);
+                AuditRecord {
+                    id: minute,
+                    timestamp: start + chrono::Duration::minutes(minute),
+                    gpu_index: 0,
+                    gpu_name: "Test GPU".to_string(),
+                    pid: Some(5150),
+                    user: Some("mallory".to_string()),
+                    process_name: Some("copyd".to_string()),
+                    memory_used_mb: 2048,
+                    utilization_pct,
+                    temperature_c: 0,
+                    power_w: 0.0,
+                    container: None,
+                    node_id: None,
+                    hostname: None,
+                    cmdline: None,
+                    ecc_uncorrected: None,
+                    ecc_uncorrected_delta: None,
+                    pcie_mb_per_sec,
+                    nvlink_bytes,
+                    slurm_job_id: None,
+                    start_unix_secs: None,
+                }
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_data_exfiltrator_flags_sustained_pcie_transfers() {
+        let detector = RogueDetector::new(AuditManager::new().await.unwrap());
+
+        // 15 minutes at 1500 MB/s with almost no compute
+        let records = transfer_series(15, |_| (Some(1500.0), None, 2.0));
+        let exfiltrator = detector
+            .detect_data_exfiltrator(&records)
+            .expect("sustained transfers should be flagged");
+        assert_eq!(exfiltrator.process.pid, 5150);
+        assert!((exfiltrator.observed_mb_per_sec - 1500.0).abs() < 1e-3);
+        assert!((exfiltrator.duration_minutes - 15.0).abs() < 1e-3);
+        assert!((exfiltrator.data_volume_mb.unwrap() - 1500.0 * 15.0 * 60.0).abs() < 1.0);
+        // Rate 50% and duration 50% over their thresholds
+        assert!((exfiltrator.confidence - 0.85).abs() < 1e-3);
+
+        let result = detector
+            .detect_rogue_activity_from_records(records)
+            .await
+            .unwrap();
+        assert_eq!(result.data_exfiltrators.len(), 1);
+        assert!(result.risk_score > 0.0);
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(
+            json["data_exfiltrators"][0]["observed_mb_per_sec"],
+            serde_json::json!(1500.0)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_data_exfiltrator_uses_nvlink_counter_deltas() {
+        let detector = RogueDetector::new(AuditManager::new().await.unwrap());
+
+        // 90 GiB per minute over NVLink is 1536 MB/s; PCIe is unsupported
+        let per_minute = 90 * 1024 * 1024 * 1024_u64;
+        let records = transfer_series(12, |minute| (None, Some(minute as u64 * per_minute), 5.0));
+        let exfiltrator = detector
+            .detect_data_exfiltrator(&records)
+            .expect("NVLink transfers should be flagged");
+        assert!((exfiltrator.observed_mb_per_sec - 1536.0).abs() < 1.0);
+        // The first sample has no previous counter, so the window starts at minute 1
+        assert!((exfiltrator.duration_minutes - 11.0).abs() < 1e-3);
+
+        // A counter reset breaks the run into two windows shorter than the threshold
+        let records = transfer_series(12, |minute| {
+            let minute = minute as u64;
+            let bytes = if minute < 6 { minute } else { minute - 6 } * per_minute;
+            (None, Some(bytes), 5.0)
+        });
+        assert!(detector.detect_data_exfiltrator(&records).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_data_exfiltrator_ignores_compute_and_short_bursts() {
+        let detector = RogueDetector::new(AuditManager::new().await.unwrap());
+
+        // Heavy transfers that feed real compute are normal training traffic
+        let records = transfer_series(30, |_| (Some(5000.0), None, 80.0));
+        assert!(detector.detect_data_exfiltrator(&records).is_none());
+
+        // Shorter than the 10 minute window
+        let records = transfer_series(5, |_| (Some(5000.0), None, 1.0));
+        assert!(detector.detect_data_exfiltrator(&records).is_none());
+
+        // Two 8 minute bursts separated by a quiet minute
+        let records = transfer_series(17, |minute| {
+            let rate = if minute == 8 { 10.0 } else { 5000.0 };
+            (Some(rate), None, 1.0)
+        });
+        assert!(detector.detect_data_exfiltrator(&records).is_none());
+
+        // No counters at all
+        let records = transfer_series(30, |_| (None, None, 0.0));
+        assert!(detector.detect_data_exfiltrator(&records).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_data_exfiltrator_uses_configured_thresholds() {
+        let rules = DetectionRules {
+            exfiltration: ExfiltrationConfig {
+                min_transfer_mb_per_sec: 200.0,
+                max_compute_util_pct: 30.0,
+                window_minutes: 3.0,
+            },
+            ..DetectionRules::default()
+        };
+        let detector = RogueDetector::with_rules(AuditManager::new().await.unwrap(), rules);
+
+        let records = transfer_series(5, |_| (Some(400.0), None, 25.0));
+        let exfiltrator = detector.detect_data_exfiltrator(&records).unwrap();
+        // Twice the rate threshold, 5 of 3 minutes
+        assert!((exfiltrator.confidence - 0.95).abs() < 1e-3);
+        assert!(RogueDetector::new(AuditManager::new().await.unwrap())
+            .detect_data_exfiltrator(&records)
+            .is_none());
+    }
+
+    #[test]
+    fn test_exfiltration_confidence_scales_with_excess() {
+        let thresholds = ExfiltrationConfig::default();
+        assert!((exfiltration_confidence(1000.0, 10.0, &thresholds) - 0.7).abs() < 1e-6);
+        assert!((exfiltration_confidence(2000.0, 10.0, &thresholds) - 0.85).abs() < 1e-6);
+        assert!((exfiltration_confidence(9000.0, 90.0, &thresholds) - 1.0).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_crypto_miner_detected_from_cmdline() {
+        use crate::audit::AuditRecord;
+        use chrono::Utc;
+
+        let rules = DetectionRules {
+            min_confidence_threshold: 0.5,
+            ..DetectionRules::default()
+        };
+        let detector = RogueDetector::with_rules(AuditManager::new().await.unwrap(), rules);
+
+        let record = |cmdline: &str| AuditRecord {
+            id: 1,
+            timestamp: Utc::now(),
+            gpu_index: 0,
+            gpu_name: "Test GPU".to_string(),
+            pid: Some(5150),
+            user: Some("mallory".to_string()),
+            process_name: Some("python3".to_string()),
+            memory_used_mb: 1024,
+            utilization_pct: 0.0,
+            temperature_c: 0,
+            power_w: 0.0,
+            container: None,
+            node_id: None,
+            hostname: None,
+            cmdline: Some(cmdline.to_string()),
+            ecc_uncorrected: None,
+            ecc_uncorrected_delta: None,
+            pcie_mb_per_sec: None,
+            nvlink_bytes: None,
+            slurm_job_id: None,
+            start_unix_secs: None,
+        };
+
+        let miner = detector
+            .detect_crypto_miner(&[record("python3 /tmp/.cache/xmrig.py --url pool:3333")])
+            .expect("miner name in the command line should be detected");
+        assert!(miner
+            .mining_indicators
+            .iter()
+            .any(|i| i == "Command line references miner: xmrig"));
+        assert_eq!(
+            miner.process.cmdline.as_deref(),
+            Some("python3 /tmp/.cache/xmrig.py --url pool:3333")
+        );
+
+        // Generic patterns are not applied to arguments
+        assert!(detector
+            .detect_crypto_miner(&[record("python3 train.py --device cuda --hash-seed 1")])
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_custom_rule_matches_cmdline() {
+        use crate::audit::AuditRecord;
+        use chrono::Utc;
+
+        let rules = DetectionRules {
+            custom_rules: vec![CustomRule {
+                name: "notebooks".to_string(),
+                proc_name_regex: r"jupyter-lab".to_string(),
+                min_memory_gb: 0.0,
+                min_util_pct: 0.0,
+                min_duration_hours: 0.0,
+                confidence: 0.8,
+            }],
+            ..DetectionRules::default()
+        };
+        let detector = RogueDetector::with_rules(AuditManager::new().await.unwrap(), rules);
+
+        let records = vec![AuditRecord {
+            id: 1,
+            timestamp: Utc::now(),
+            gpu_index: 0,
+            gpu_name: "Test GPU".to_string(),
+            pid: Some(6060),
+            user: Some("dave".to_string()),
+            process_name: Some("python3".to_string()),
+            memory_used_mb: 1024,
+            utilization_pct: 10.0,
+            temperature_c: 0,
+            power_w: 0.0,
+            container: None,
+            node_id: None,
+            hostname: None,
+            cmdline: Some("/usr/bin/python3 /usr/local/bin/jupyter-lab --port 8888".to_string()),
+            ecc_uncorrected: None,
+            ecc_uncorrected_delta: None,
+            pcie_mb_per_sec: None,
+            nvlink_bytes: None,
+            slurm_job_id: None,
+            start_unix_secs: None,
+        }];
+
+        let suspicious = detector
+            .detect_suspicious_process(&records)
+            .expect("custom rule should match the command line");
+        assert!(suspicious
+            .reasons
+            .iter()
+            .any(|r| r.starts_with("Custom rule 'notebooks'")));
+    }
+
+    fn xmrig_record(id: i64, timestamp: DateTime<Utc>, started: DateTime<Utc>) -> AuditRecord {
+        AuditRecord {
+            id,
+            timestamp,
+            gpu_index: 0,
+            gpu_name: "Test GPU".to_string(),
+            pid: Some(4242),
+            user: Some("mallory".to_string()),
+            process_name: Some("xmrig".to_string()),
+            memory_used_mb: 1024,
+            utilization_pct: 50.0,
+            temperature_c: 60,
+            power_w: 100.0,
+            container: None,
+            node_id: None,
+            hostname: None,
+            cmdline: None,
+            ecc_uncorrected: None,
+            ecc_uncorrected_delta: None,
+            pcie_mb_per_sec: None,
+            nvlink_bytes: None,
+            slurm_job_id: None,
+            start_unix_secs: Some(started.timestamp() as u64),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_single_record_duration_uses_process_start_time() {
+        let rules = DetectionRules {
+            min_confidence_threshold: 0.1,
+            ..DetectionRules::default()
+        };
+        let detector = RogueDetector::with_rules(AuditManager::new().await.unwrap(), rules);
+        let now = Utc::now();
+        let record = xmrig_record(1, now, now - chrono::Duration::hours(3));
+
+        let duration = detector.calculate_process_duration(std::slice::from_ref(&record));
+        assert!((duration.unwrap() - 3.0).abs() < 0.01);
+
+        let miner = detector
+            .detect_crypto_miner(std::slice::from_ref(&record))
+            .unwrap();
+        assert!(miner
+            .mining_indicators
+            .iter()
+            .any(|indicator| indicator.starts_with("Long-running process: 3.0 hours")));
+
+        // Without a start time one record says nothing about duration
+        let unknown = AuditRecord {
+            start_unix_secs: None,
+            ..record
+        };
+        assert!(detector.calculate_process_duration(&[unknown]).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_multi_record_duration_does_not_double_count() {
+        let detector = RogueDetector::new(AuditManager::new().await.unwrap());
+        let now = Utc::now();
+        let started = now - chrono::Duration::hours(4);
+        let records = vec![
+            xmrig_record(1, now - chrono::Duration::hours(1), started),
+            xmrig_record(2, now, started),
+        ];
+
+        // Four hours since start, not the one-hour span plus the four hours
+        let duration = detector.calculate_process_duration(&records).unwrap();
+        assert!((duration - 4.0).abs() < 0.01);
+
+        // Records without a start time fall back to their span
+        let records: Vec<_> = records
+            .into_iter()
+            .map(|record| AuditRecord {
+                start_unix_secs: None,
+                ..record
+            })
+            .collect();
+        let duration = detector.calculate_process_duration(&records).unwrap();
+        assert!((duration - 1.0).abs() < 0.01);
+    }
+
+    #[tokio::test]
+    async fn test_host_scan_ignores_other_hosts_in_shared_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = crate::audit_store::JsonlStore::new(dir.path().to_path_buf()).unwrap();
+        let audit_manager = AuditManager::with_store(Box::new(store));
+        let now = Utc::now();
+        let on_host = |id, pid, host: &str| AuditRecord {
+            pid: Some(pid),
+            hostname: Some(host.to_string()),
+            ..xmrig_record(id, now, now)
+        };
+        audit_manager
+            .append_records(&[on_host(1, 100, "gpu-01"), on_host(2, 200, "gpu-02")])
+            .await
+            .unwrap();
+
+        let rules = DetectionRules {
+            min_confidence_threshold: 0.1,
+            ..DetectionRules::default()
+        };
+        let detector = RogueDetector::with_rules(audit_manager, rules);
+        let result = detector
+            .detect_rogue_activity_on_host(1, "gpu-01")
+            .await
+            .unwrap();
+        let pids: Vec<u32> = result.crypto_miners.iter().map(|m| m.process.pid).collect();
+        assert_eq!(pids, [100]);
+    }
 }
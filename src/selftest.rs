@@ -0,0 +1,516 @@
+//! `--selftest`: start a controlled workload, then check that gpukill lists it
+//! with the right memory, kills it with and without `--force` and sees the memory
+//! come back. Without a GPU (or a helper to allocate on one) the workload is a
+//! plain `sleep` on a mock GPU, which still exercises the process lifecycle.
+
+use crate::nvml_api::{GpuInfo, GpuProc, GpuSnapshot};
+use crate::proc::{graceful_kill_pid, KillSignal};
+use crate::report::{SelftestCheck, SelftestReport};
+use crate::vendor::{GpuManager, GpuVendor, GpuVendorInterface, VendorCapabilities};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use sysinfo::{Pid as SysPid, System};
+
+/// GPU memory the workload allocates unless `--selftest-mem-mb` says otherwise
+pub const DEFAULT_SELFTEST_MEM_MB: u32 = 256;
+
+/// How long a workload may take to show up, or its memory to be released
+const SETTLE_TIMEOUT: Duration = Duration::from_secs(15);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Memory a real GPU may still report above its pre-test baseline once the
+/// workload is gone (allocator caches, other tenants)
+const RECLAIM_SLACK_MB: u32 = 64;
+
+/// Name of the single device the mock vendor reports
+const MOCK_GPU_NAME: &str = "gpukill mock GPU";
+
+/// Mock GPU for the self-test: every tracked workload that is still running is
+/// reported as a process holding `mem_mb`
+pub struct MockVendor {
+    mem_mb: u32,
+    pids: Mutex<Vec<u32>>,
+}
+
+impl MockVendor {
+    pub fn new(mem_mb: u32) -> Self {
+        Self {
+            mem_mb,
+            pids: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Report `pid` as a GPU process for as long as it runs
+    pub fn track(&self, pid: u32) {
+        if let Ok(mut pids) = self.pids.lock() {
+            pids.push(pid);
+        }
+    }
+
+    fn processes(&self) -> Vec<GpuProc> {
+        let pids = self.pids.lock().map(|p| p.clone()).unwrap_or_default();
+        pids.into_iter()
+            .filter(|pid| is_running(*pid))
+            .map(|pid| GpuProc {
+                gpu_index: 0,
+                pid,
+                user: "selftest".to_string(),
+                proc_name: "selftest-workload".to_string(),
+                used_mem_mb: self.mem_mb,
+                start_time: "unknown".to_string(),
+                container: None,
+                node_id: None,
+                cmdline: None,
+                container_pid: None,
+                slurm_job_id: None,
+                start_unix_secs: None,
+            })
+            .collect()
+    }
+
+    fn mem_total_mb(&self) -> u32 {
+        self.mem_mb.saturating_mul(4).max(1024)
+    }
+}
+
+impl GpuVendorInterface for MockVendor {
+    fn initialize() -> Result<Self> {
+        Ok(Self::new(DEFAULT_SELFTEST_MEM_MB))
+    }
+
+    fn vendor_type(&self) -> GpuVendor {
+        GpuVendor::Unknown
+    }
+
+    fn device_count(&self) -> Result<u32> {
+        Ok(1)
+    }
+
+    fn get_gpu_info(&self, index: u32) -> Result<GpuInfo> {
+        Ok(GpuInfo {
+            index: index as u16,
+            name: MOCK_GPU_NAME.to_string(),
+            mem_total_mb: self.mem_total_mb(),
+            uuid: None,
+            serial: None,
+            pci_bus_id: None,
+        })
+    }
+
+    fn get_gpu_snapshot(&self, index: u32) -> Result<GpuSnapshot> {
+        let processes = self.processes();
+        Ok(GpuSnapshot {
+            gpu_index: index as u16,
+            name: MOCK_GPU_NAME.to_string(),
+            uuid: None,
+            serial: None,
+            vendor: GpuVendor::Unknown,
+            mem_used_mb: processes.iter().map(|p| p.used_mem_mb).sum(),
+            mem_total_mb: self.mem_total_mb(),
+            util_pct: 0.0,
+            temp_c: 0,
+            power_w: 0.0,
+            ecc_volatile: None,
+            ecc_aggregate: None,
+            retired_pages: None,
+            rma_flagged: None,
+            pcie_tx_kbps: None,
+            pcie_rx_kbps: None,
+            nvlink_bytes: None,
+            power_limit: None,
+            pids: processes.len(),
+            top_proc: processes.into_iter().next(),
+            profiling: None,
+        })
+    }
+
+    fn get_gpu_processes(&self, _index: u32) -> Result<Vec<GpuProc>> {
+        Ok(self.processes())
+    }
+
+    fn reset_gpu(&self, _index: u32) -> Result<()> {
+        Ok(())
+    }
+
+    fn capabilities(&self) -> VendorCapabilities {
+        VendorCapabilities {
+            reset_supported: true,
+            process_enum_supported: true,
+            power_limit_supported: false,
+            util_supported: false,
+        }
+    }
+
+    fn is_available() -> bool {
+        true
+    }
+
+    fn get_availability_error() -> String {
+        String::new()
+    }
+}
+
+/// The self-test and the GPU it runs against
+pub struct Selftest {
+    manager: GpuManager,
+    /// Set in mock mode; spawned workloads are registered with it
+    mock: Option<Arc<MockVendor>>,
+    program: PathBuf,
+    args: Vec<String>,
+    mem_mb: u32,
+    timeout_secs: u16,
+    settle_timeout: Duration,
+}
+
+impl Selftest {
+    /// Self-test with a `sleep` workload on a mock GPU
+    pub fn mock(mem_mb: u32, timeout_secs: u16) -> Self {
+        let vendor = Arc::new(MockVendor::new(mem_mb));
+        Self {
+            manager: GpuManager::with_vendor(vendor.clone()),
+            mock: Some(vendor),
+            program: PathBuf::from("sleep"),
+            args: vec!["600".to_string()],
+            mem_mb,
+            timeout_secs,
+            settle_timeout: SETTLE_TIMEOUT,
+        }
+    }
+
+    /// Self-test on real GPUs. `helper` is run as `<helper> <mem_mb>` and must
+    /// allocate that much GPU memory, then sleep until it is killed.
+    pub fn gpu(manager: GpuManager, helper: PathBuf, mem_mb: u32, timeout_secs: u16) -> Self {
+        Self {
+            manager,
+            mock: None,
+            program: helper,
+            args: vec![mem_mb.to_string()],
+            mem_mb,
+            timeout_secs,
+            settle_timeout: SETTLE_TIMEOUT,
+        }
+    }
+
+    pub fn mode(&self) -> &'static str {
+        if self.mock.is_some() {
+            "mock"
+        } else {
+            "gpu"
+        }
+    }
+
+    /// Run every check, passing each to `on_check` as soon as it completes
+    pub fn run(&self, mut on_check: impl FnMut(&SelftestCheck)) -> SelftestReport {
+        let started = Instant::now();
+        let mut report = SelftestReport::new(self.mode(), self.mem_mb);
+        let mut record = |name: &str, passed: bool, detail: String| {
+            on_check(report.check(name, passed, detail));
+        };
+        self.run_cycle(false, &mut record);
+        self.run_cycle(true, &mut record);
+        report.finish(started)
+    }
+
+    /// Spawn a workload, find it, kill it and wait for its memory. With `force`
+    /// the workload ignores SIGTERM, so only `--force` escalation can end it.
+    fn run_cycle(&self, force: bool, record: &mut impl FnMut(&str, bool, String)) {
+        let name = |check: &str| {
+            if force {
+                format!("{}_force", check)
+            } else {
+                check.to_string()
+            }
+        };
+
+        let baseline = self.memory_used();
+        let pid = match spawn_workload(&self.program, &self.args, force) {
+            Ok(pid) => {
+                if let Some(mock) = &self.mock {
+                    mock.track(pid);
+                }
+                let ignoring = if force { ", ignoring SIGTERM" } else { "" };
+                record(
+                    &name("spawn"),
+                    true,
+                    format!(
+                        "Started {} as PID {}{}",
+                        self.program.display(),
+                        pid,
+                        ignoring
+                    ),
+                );
+                pid
+            }
+            Err(e) => {
+                record(&name("spawn"), false, e.to_string());
+                return;
+            }
+        };
+
+        let Some(listed) = self.poll(|| self.find_process(pid)) else {
+            record(
+                &name("visible"),
+                false,
+                format!(
+                    "PID {} not reported by any GPU within {}s",
+                    pid,
+                    self.settle_timeout.as_secs()
+                ),
+            );
+            stop_workload(pid);
+            return;
+        };
+        record(
+            &name("visible"),
+            true,
+            format!("PID {} listed on GPU {}", pid, listed.gpu_index),
+        );
+
+        // A real GPU adds context overhead on top of the allocation
+        let attributed = if self.mock.is_some() {
+            listed.used_mem_mb == self.mem_mb
+        } else {
+            listed.used_mem_mb >= self.mem_mb
+        };
+        record(
+            &name("memory_attribution"),
+            attributed,
+            format!(
+                "PID {} holds {} MB (allocated {} MB)",
+                pid, listed.used_mem_mb, self.mem_mb
+            ),
+        );
+
+        if force {
+            let (passed, detail) =
+                match graceful_kill_pid(pid, KillSignal::Term, self.timeout_secs, false) {
+                    Err(_) if is_running(pid) => (
+                        true,
+                        format!("PID {} ignored SIGTERM and was left running", pid),
+                    ),
+                    Err(e) => (false, e.to_string()),
+                    Ok(signal) => (
+                        false,
+                        format!("PID {} exited after {} without --force", pid, signal),
+                    ),
+                };
+            record("kill_without_force", passed, detail);
+
+            let (passed, detail) =
+                match graceful_kill_pid(pid, KillSignal::Term, self.timeout_secs, true) {
+                    Ok(signal) => (
+                        signal == "SIGKILL",
+                        format!("PID {} exited after {}", pid, signal),
+                    ),
+                    Err(e) => (false, e.to_string()),
+                };
+            record("kill_with_force", passed, detail);
+        } else {
+            let (passed, detail) =
+                match graceful_kill_pid(pid, KillSignal::Term, self.timeout_secs, false) {
+                    Ok(signal) => (
+                        signal == "SIGTERM",
+                        format!("PID {} exited after {}", pid, signal),
+                    ),
+                    Err(e) => (false, e.to_string()),
+                };
+            record("kill_graceful", passed, detail);
+        }
+        if is_running(pid) {
+            stop_workload(pid);
+        }
+        self.manager.invalidate_cache();
+
+        let gpu = listed.gpu_index;
+        let before = baseline.get(&gpu).copied().unwrap_or(0);
+        let slack = if self.mock.is_some() {
+            0
+        } else {
+            RECLAIM_SLACK_MB
+        };
+        let reclaimed = self.poll(|| {
+            let used = self.memory_used().get(&gpu).copied()?;
+            (self.find_process(pid).is_none() && used <= before + slack).then_some(used)
+        });
+        match reclaimed {
+            Some(used) => record(
+                &name("memory_reclaimed"),
+                true,
+                format!("GPU {} uses {} MB (baseline {} MB)", gpu, used, before),
+            ),
+            None => {
+                let used = self.memory_used().get(&gpu).copied().unwrap_or(0);
+                let listed = if self.find_process(pid).is_some() {
+                    "still listed"
+                } else {
+                    "gone"
+                };
+                record(
+                    &name("memory_reclaimed"),
+                    false,
+                    format!(
+                        "After {}s PID {} is {} and GPU {} uses {} MB (baseline {} MB)",
+                        self.settle_timeout.as_secs(),
+                        pid,
+                        listed,
+                        gpu,
+                        used,
+                        before
+                    ),
+                );
+            }
+        }
+    }
+
+    /// The workload as reported by the GPU manager
+    fn find_process(&self, pid: u32) -> Option<GpuProc> {
+        self.manager
+            .get_all_processes()
+            .ok()?
+            .into_iter()
+            .find(|p| p.pid == pid)
+    }
+
+    /// Memory in use on each GPU, by global index
+    fn memory_used(&self) -> BTreeMap<u16, u32> {
+        self.manager
+            .get_all_snapshots()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|s| (s.gpu_index, s.mem_used_mb))
+            .collect()
+    }
+
+    /// Call `probe` until it returns a value or the settle timeout passes
+    fn poll<T>(&self, mut probe: impl FnMut() -> Option<T>) -> Option<T> {
+        let deadline = Instant::now() + self.settle_timeout;
+        loop {
+            if let Some(value) = probe() {
+                return Some(value);
+            }
+            if Instant::now() >= deadline {
+                return None;
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+fn is_running(pid: u32) -> bool {
+    System::new().refresh_process(SysPid::from_u32(pid))
+}
+
+/// Start the workload and reap it in the background, so once killed it does not
+/// linger as a zombie that still looks alive
+#[cfg(unix)]
+fn spawn_workload(program: &Path, args: &[String], ignore_sigterm: bool) -> Result<u32> {
+    use anyhow::Context;
+    use nix::sys::signal::{signal, SigHandler, Signal};
+    use std::os::unix::process::CommandExt;
+
+    let mut command = std::process::Command::new(program);
+    command
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null());
+    if ignore_sigterm {
+        // SAFETY: signal(2) is async-signal-safe, so it may run between fork and exec.
+        // An ignored disposition survives exec.
+        unsafe {
+            command.pre_exec(|| {
+                signal(Signal::SIGTERM, SigHandler::SigIgn)
+                    .map(|_| ())
+                    .map_err(std::io::Error::from)
+            });
+        }
+    }
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Failed to start {}", program.display()))?;
+    let pid = child.id();
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(pid)
+}
+
+#[cfg(not(unix))]
+fn spawn_workload(_program: &Path, _args: &[String], _ignore_sigterm: bool) -> Result<u32> {
+    Err(anyhow::anyhow!(
+        "The self-test is not supported on this platform"
+    ))
+}
+
+/// Kill a workload a check left behind
+fn stop_workload(pid: u32) {
+    #[cfg(unix)]
+    {
+        let _ = nix::sys::signal::kill(
+            nix::unistd::Pid::from_raw(pid as i32),
+            nix::sys::signal::Signal::SIGKILL,
+        );
+    }
+    #[cfg(not(unix))]
+    let _ = pid;
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_vendor_reports_running_workloads() {
+        let vendor = MockVendor::new(300);
+        vendor.track(std::process::id());
+        vendor.track(u32::MAX);
+
+        let processes = vendor.get_gpu_processes(0).unwrap();
+        assert_eq!(processes.len(), 1);
+        assert_eq!(processes[0].pid, std::process::id());
+        assert_eq!(processes[0].used_mem_mb, 300);
+        assert_eq!(vendor.get_gpu_snapshot(0).unwrap().mem_used_mb, 300);
+    }
+
+    #[test]
+    fn test_mock_selftest_passes() {
+        let mut selftest = Selftest::mock(128, 1);
+        selftest.settle_timeout = Duration::from_secs(5);
+        let mut seen = Vec::new();
+        let report = selftest.run(|check| seen.push(check.name.clone()));
+
+        assert!(report.passed, "{:#?}", report.checks);
+        assert_eq!(report.mode, "mock");
+        assert_eq!(
+            seen,
+            vec![
+                "spawn",
+                "visible",
+                "memory_attribution",
+                "kill_graceful",
+                "memory_reclaimed",
+                "spawn_force",
+                "visible_force",
+                "memory_attribution_force",
+                "kill_without_force",
+                "kill_with_force",
+                "memory_reclaimed_force",
+            ]
+        );
+        assert!(report.checks[3].detail.ends_with("exited after SIGTERM"));
+        assert!(report.checks[9].detail.ends_with("exited after SIGKILL"));
+    }
+
+    #[test]
+    fn test_selftest_fails_when_workload_cannot_start() {
+        let mut selftest = Selftest::mock(128, 1);
+        selftest.program = PathBuf::from("/nonexistent/gpukill-selftest-helper");
+        let report = selftest.run(|_| {});
+
+        assert!(!report.passed);
+        assert_eq!(report.failed(), vec!["spawn", "spawn_force"]);
+    }
+}
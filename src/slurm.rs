@@ -0,0 +1,229 @@
+//! Slurm integration: map GPU processes to the Slurm job that owns them, and cancel jobs.
+//!
+//! Everything here is a no-op on hosts without the Slurm client tools, so listings and
+//! kills on such hosts are unaffected.
+
+use crate::command::{CommandRunner, SystemCommandRunner};
+use crate::nvml_api::GpuProc;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Slurm client tools whose presence marks a host as part of a Slurm cluster
+const SLURM_TOOLS: [&str; 2] = ["scontrol", "squeue"];
+
+static SLURM_AVAILABLE: OnceLock<bool> = OnceLock::new();
+
+/// Whether `scontrol` or `squeue` is on PATH; checked once per run
+pub fn is_available() -> bool {
+    *SLURM_AVAILABLE.get_or_init(|| {
+        let Some(path) = std::env::var_os("PATH") else {
+            return false;
+        };
+        std::env::split_paths(&path)
+            .any(|dir| SLURM_TOOLS.iter().any(|tool| dir.join(tool).is_file()))
+    })
+}
+
+/// Slurm job ID from a /proc/<pid>/cgroup file. slurmd places job steps under
+/// `/slurm/uid_<uid>/job_<id>/step_<step>` with cgroup v1 and under
+/// `/system.slice/slurmstepd.scope/job_<id>/step_<step>` with cgroup v2.
+pub fn job_id_from_cgroup(cgroup: &str) -> Option<String> {
+    cgroup.lines().find_map(|line| {
+        let path = line.splitn(3, ':').nth(2)?;
+        if !path.contains("slurm") {
+            return None;
+        }
+        path.split('/').find_map(|segment| {
+            let id = segment.strip_prefix("job_")?;
+            (!id.is_empty() && id.bytes().all(|b| b.is_ascii_digit())).then(|| id.to_string())
+        })
+    })
+}
+
+/// PID to job ID map from `scontrol listpids` output:
+///
+/// ```text
+/// PID      JOBID    STEPID   LOCALID GLOBALID
+/// 1234     4242     0        0       0
+/// ```
+///
+/// The header and malformed lines are skipped.
+pub fn parse_listpids(output: &str) -> HashMap<u32, String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pid = fields.next()?.parse::<u32>().ok()?;
+            let job = fields.next()?;
+            job.bytes()
+                .all(|b| b.is_ascii_digit())
+                .then(|| (pid, job.to_string()))
+        })
+        .collect()
+}
+
+/// Validate a `--job` value: a numeric Slurm job ID
+pub fn parse_job_id(value: &str) -> Result<String> {
+    let value = value.trim();
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        anyhow::bail!("'{}' is not a valid Slurm job ID", value);
+    }
+    Ok(value.to_string())
+}
+
+/// Fill in `slurm_job_id` for processes that belong to a Slurm job. Does nothing on
+/// hosts without Slurm.
+pub fn annotate_jobs(procs: &mut [GpuProc]) {
+    if procs.is_empty() || !is_available() {
+        return;
+    }
+    annotate_with(procs, Path::new("/proc"), &SystemCommandRunner);
+}
+
+/// Job IDs from each process's cgroup under `proc_root`, falling back to a single
+/// `scontrol listpids` for processes whose cgroup does not name a job (e.g. when
+/// slurmd does not use the cgroup plugin).
+fn annotate_with(procs: &mut [GpuProc], proc_root: &Path, runner: &dyn CommandRunner) {
+    for proc in procs.iter_mut() {
+        proc.slurm_job_id =
+            std::fs::read_to_string(proc_root.join(proc.pid.to_string()).join("cgroup"))
+                .ok()
+                .and_then(|cgroup| job_id_from_cgroup(&cgroup));
+    }
+
+    if procs.iter().all(|proc| proc.slurm_job_id.is_some()) {
+        return;
+    }
+    let jobs = match runner.run("scontrol", &["listpids"]) {
+        Ok(output) if output.success => parse_listpids(&output.stdout),
+        Ok(output) => {
+            tracing::debug!("scontrol listpids failed: {}", output.stderr.trim());
+            return;
+        }
+        Err(e) => {
+            tracing::debug!("scontrol listpids failed: {:#}", e);
+            return;
+        }
+    };
+    for proc in procs.iter_mut().filter(|proc| proc.slurm_job_id.is_none()) {
+        proc.slurm_job_id = jobs.get(&proc.pid).cloned();
+    }
+}
+
+/// GPU processes that belong to Slurm job `job_id`
+pub fn processes_of_job(procs: &[GpuProc], job_id: &str) -> Vec<GpuProc> {
+    procs
+        .iter()
+        .filter(|proc| proc.slurm_job_id.as_deref() == Some(job_id))
+        .cloned()
+        .collect()
+}
+
+/// Cancel a whole job with `scancel`, which also ends its CPU-only steps
+pub fn scancel(job_id: &str, runner: &dyn CommandRunner) -> Result<()> {
+    let output = runner.run("scancel", &[job_id])?;
+    if !output.success {
+        anyhow::bail!("scancel {} failed: {}", job_id, output.stderr.trim());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::CommandOutput;
+    use crate::nvml_api::fixtures::proc;
+    use std::cell::Cell;
+
+    const CGROUP_V1: &str = "\
+12:memory:/slurm/uid_1000/job_4242/step_0
+11:devices:/slurm/uid_1000/job_4242/step_0
+1:name=systemd:/system.slice/slurmd.service
+";
+    const CGROUP_V2: &str = "0::/system.slice/slurmstepd.scope/job_98765/step_batch/user/task_0\n";
+    const CGROUP_DOCKER: &str = "0::/system.slice/docker-0123abcd.scope\n";
+    const LISTPIDS: &str = "\
+PID      JOBID    STEPID   LOCALID GLOBALID
+1234     4242     0        0       0
+1240     4242     batch    -       -
+5678     777      extern   -       -
+not-a-pid 1       0        0       0
+";
+
+    struct ListpidsRunner {
+        calls: Cell<usize>,
+    }
+
+    impl CommandRunner for ListpidsRunner {
+        fn run(&self, program: &str, args: &[&str]) -> Result<CommandOutput> {
+            assert_eq!((program, args), ("scontrol", &["listpids"][..]));
+            self.calls.set(self.calls.get() + 1);
+            Ok(CommandOutput {
+                success: true,
+                code: Some(0),
+                stdout: LISTPIDS.to_string(),
+                stderr: String::new(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_job_id_from_cgroup_v1_and_v2() {
+        assert_eq!(job_id_from_cgroup(CGROUP_V1), Some("4242".to_string()));
+        assert_eq!(job_id_from_cgroup(CGROUP_V2), Some("98765".to_string()));
+        assert_eq!(job_id_from_cgroup(CGROUP_DOCKER), None);
+        assert_eq!(job_id_from_cgroup("0::/user.slice/job_12/x\n"), None);
+        assert_eq!(job_id_from_cgroup(""), None);
+    }
+
+    #[test]
+    fn test_parse_listpids_skips_header_and_malformed_lines() {
+        let jobs = parse_listpids(LISTPIDS);
+        assert_eq!(jobs.len(), 3);
+        assert_eq!(jobs[&1234], "4242");
+        assert_eq!(jobs[&1240], "4242");
+        assert_eq!(jobs[&5678], "777");
+        assert!(parse_listpids("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_job_id() {
+        assert_eq!(parse_job_id(" 4242 ").unwrap(), "4242");
+        assert!(parse_job_id("").is_err());
+        assert!(parse_job_id("4242_1").is_err());
+    }
+
+    #[test]
+    fn test_annotate_prefers_cgroup_and_falls_back_to_listpids() {
+        let dir = tempfile::tempdir().unwrap();
+        for (pid, cgroup) in [(1234, CGROUP_V2), (5678, CGROUP_DOCKER)] {
+            std::fs::create_dir(dir.path().join(pid.to_string())).unwrap();
+            std::fs::write(dir.path().join(pid.to_string()).join("cgroup"), cgroup).unwrap();
+        }
+        let runner = ListpidsRunner {
+            calls: Cell::new(0),
+        };
+
+        let mut procs = vec![
+            proc(0, 1234, "alice", "python", 1024),
+            proc(0, 5678, "alice", "python", 1024),
+            proc(0, 9999, "alice", "python", 1024),
+        ];
+        annotate_with(&mut procs, dir.path(), &runner);
+        let jobs: Vec<_> = procs.iter().map(|p| p.slurm_job_id.as_deref()).collect();
+        // The cgroup wins over listpids, which would have said 4242
+        assert_eq!(jobs, [Some("98765"), Some("777"), None]);
+        assert_eq!(runner.calls.get(), 1);
+
+        let matching = processes_of_job(&procs, "777");
+        assert_eq!(matching.len(), 1);
+        assert_eq!(matching[0].pid, 5678);
+
+        // No fallback is needed when every cgroup names a job
+        let mut procs = vec![proc(0, 1234, "alice", "python", 1024)];
+        annotate_with(&mut procs, dir.path(), &runner);
+        assert_eq!(runner.calls.get(), 1);
+    }
+}
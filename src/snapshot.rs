@@ -0,0 +1,660 @@
+//! Saved snapshots and snapshot comparison (`--list --save-snapshot` / `--list --diff`)
+
+use crate::nvml_api::{EccCounts, GpuProc, GpuSnapshot, Snapshot};
+use crate::vendor::GpuVendor;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Fields every saved snapshot has had; their absence means the file is not a snapshot
+const SNAPSHOT_FIELDS: [&str; 4] = ["host", "ts", "gpus", "procs"];
+
+/// Differences between a saved baseline snapshot and a current one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotDiff {
+    pub baseline_host: String,
+    pub baseline_ts: String,
+    pub current_host: String,
+    pub current_ts: String,
+    /// Processes present now but not in the baseline
+    pub appeared: Vec<GpuProc>,
+    /// Processes in the baseline that are gone now
+    pub disappeared: Vec<GpuProc>,
+    /// GPUs present in both snapshots, ordered by index
+    pub gpus: Vec<GpuDelta>,
+    /// GPU indices present now but not in the baseline
+    pub added_gpus: Vec<u16>,
+    /// GPU indices in the baseline that are gone now
+    pub removed_gpus: Vec<u16>,
+}
+
+/// Change in one GPU's usage and health between two snapshots
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpuDelta {
+    pub gpu_index: u16,
+    pub name: String,
+    pub mem_used_mb_before: u32,
+    pub mem_used_mb_after: u32,
+    pub mem_delta_mb: i64,
+    pub util_pct_before: f32,
+    pub util_pct_after: f32,
+    pub util_delta_pct: f32,
+    pub temp_delta_c: i32,
+    /// Human-readable changes to health fields (name, ECC counters)
+    pub health_changes: Vec<String>,
+}
+
+impl GpuDelta {
+    /// Whether any usage or health field changed
+    pub fn is_changed(&self) -> bool {
+        self.mem_delta_mb != 0
+            || self.util_delta_pct != 0.0
+            || self.temp_delta_c != 0
+            || !self.health_changes.is_empty()
+    }
+}
+
+impl SnapshotDiff {
+    /// Whether the two snapshots are equivalent for comparison purposes
+    pub fn is_empty(&self) -> bool {
+        self.appeared.is_empty()
+            && self.disappeared.is_empty()
+            && self.added_gpus.is_empty()
+            && self.removed_gpus.is_empty()
+            && !self.gpus.iter().any(GpuDelta::is_changed)
+    }
+}
+
+/// Compare `current` against a `baseline` snapshot. Processes are matched by
+/// GPU, PID and name so a reused PID running a different program counts as a
+/// new process.
+pub fn diff_snapshots(baseline: &Snapshot, current: &Snapshot) -> SnapshotDiff {
+    let proc_key = |p: &GpuProc| (p.gpu_index, p.pid, p.proc_name.clone());
+    let before: BTreeMap<_, &GpuProc> = baseline.procs.iter().map(|p| (proc_key(p), p)).collect();
+    let after: BTreeMap<_, &GpuProc> = current.procs.iter().map(|p| (proc_key(p), p)).collect();
+
+    let appeared = after
+        .iter()
+        .filter(|(key, _)| !before.contains_key(key))
+        .map(|(_, p)| (*p).clone())
+        .collect();
+    let disappeared = before
+        .iter()
+        .filter(|(key, _)| !after.contains_key(key))
+        .map(|(_, p)| (*p).clone())
+        .collect();
+
+    let before_gpus: BTreeMap<u16, &GpuSnapshot> =
+        baseline.gpus.iter().map(|g| (g.gpu_index, g)).collect();
+    let after_gpus: BTreeMap<u16, &GpuSnapshot> =
+        current.gpus.iter().map(|g| (g.gpu_index, g)).collect();
+
+    let gpus = after_gpus
+        .iter()
+        .filter_map(|(index, now)| before_gpus.get(index).map(|then| gpu_delta(then, now)))
+        .collect();
+    let added_gpus = after_gpus
+        .keys()
+        .filter(|index| !before_gpus.contains_key(index))
+        .copied()
+        .collect();
+    let removed_gpus = before_gpus
+        .keys()
+        .filter(|index| !after_gpus.contains_key(index))
+        .copied()
+        .collect();
+
+    SnapshotDiff {
+        baseline_host: baseline.host.clone(),
+        baseline_ts: baseline.ts.clone(),
+        current_host: current.host.clone(),
+        current_ts: current.ts.clone(),
+        appeared,
+        disappeared,
+        gpus,
+        added_gpus,
+        removed_gpus,
+    }
+}
+
+fn gpu_delta(before: &GpuSnapshot, after: &GpuSnapshot) -> GpuDelta {
+    let mut health_changes = Vec::new();
+    if before.name != after.name {
+        health_changes.push(format!("name: {} -> {}", before.name, after.name));
+    }
+    for (label, then, now) in [
+        ("volatile ECC", before.ecc_volatile, after.ecc_volatile),
+        ("aggregate ECC", before.ecc_aggregate, after.ecc_aggregate),
+    ] {
+        if then != now {
+            health_changes.push(format!(
+                "{}: {} -> {}",
+                label,
+                format_ecc(then),
+                format_ecc(now)
+            ));
+        }
+    }
+
+    GpuDelta {
+        gpu_index: after.gpu_index,
+        name: after.name.clone(),
+        mem_used_mb_before: before.mem_used_mb,
+        mem_used_mb_after: after.mem_used_mb,
+        mem_delta_mb: after.mem_used_mb as i64 - before.mem_used_mb as i64,
+        util_pct_before: before.util_pct,
+        util_pct_after: after.util_pct,
+        util_delta_pct: after.util_pct - before.util_pct,
+        temp_delta_c: after.temp_c - before.temp_c,
+        health_changes,
+    }
+}
+
+fn format_ecc(counts: Option<EccCounts>) -> String {
+    match counts {
+        Some(c) => format!("{} corrected/{} uncorrected", c.corrected, c.uncorrected),
+        None => "unsupported".to_string(),
+    }
+}
+
+/// Change in one GPU's usage between two `--watch` iterations
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GpuRate {
+    /// Memory growth in MB per second (negative when memory is freed)
+    pub mem_mb_per_sec: f64,
+    /// Utilization change in percentage points since the previous iteration
+    pub util_delta_pct: f32,
+}
+
+/// Compute per-GPU rates from `previous` to `current`, `elapsed` apart. GPUs that
+/// appear in only one snapshot have no rate; a zero `elapsed` yields no rates.
+pub fn gpu_rates(
+    previous: &Snapshot,
+    current: &Snapshot,
+    elapsed: Duration,
+) -> BTreeMap<u16, GpuRate> {
+    let secs = elapsed.as_secs_f64();
+    if secs <= 0.0 {
+        return BTreeMap::new();
+    }
+    let before: BTreeMap<u16, &GpuSnapshot> =
+        previous.gpus.iter().map(|g| (g.gpu_index, g)).collect();
+
+    current
+        .gpus
+        .iter()
+        .filter_map(|now| {
+            let then = before.get(&now.gpu_index)?;
+            let mem_delta = now.mem_used_mb as f64 - then.mem_used_mb as f64;
+            Some((
+                now.gpu_index,
+                GpuRate {
+                    mem_mb_per_sec: mem_delta / secs,
+                    util_delta_pct: now.util_pct - then.util_pct,
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Remembers the previous `--watch --show-rates` iteration
+#[derive(Debug, Default)]
+pub struct RateTracker {
+    previous: Option<(Snapshot, Instant)>,
+}
+
+impl RateTracker {
+    /// Record `snapshot` and return the rates since the previous one (empty the first time)
+    pub fn update(&mut self, snapshot: &Snapshot) -> BTreeMap<u16, GpuRate> {
+        let now = Instant::now();
+        let rates = match &self.previous {
+            Some((previous, at)) => gpu_rates(previous, snapshot, now.duration_since(*at)),
+            None => BTreeMap::new(),
+        };
+        self.previous = Some((snapshot.clone(), now));
+        rates
+    }
+}
+
+/// `--watch --alert-*` limits, checked on every refresh
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AlertThresholds {
+    pub util_pct: Option<f32>,
+    pub mem_pct: Option<f32>,
+    pub temp_c: Option<u32>,
+}
+
+/// A GPU over one of the [`AlertThresholds`] in one snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertBreach {
+    pub gpu_index: u16,
+    pub gpu_name: String,
+    /// Timestamp of the snapshot that breached
+    pub ts: String,
+    pub metric: &'static str,
+    pub value: f32,
+    pub limit: f32,
+    pub unit: &'static str,
+}
+
+impl std::fmt::Display for AlertBreach {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "GPU {} ({}) {} {:.1}{} exceeded {}{} at {}",
+            self.gpu_index,
+            self.gpu_name,
+            self.metric,
+            self.value,
+            self.unit,
+            self.limit,
+            self.unit,
+            self.ts
+        )
+    }
+}
+
+impl AlertThresholds {
+    /// Every limit each GPU in `snapshot` is strictly above
+    pub fn breaches(&self, snapshot: &Snapshot) -> Vec<AlertBreach> {
+        let mut breaches = Vec::new();
+        for gpu in &snapshot.gpus {
+            let mem_pct = if gpu.mem_total_mb > 0 {
+                gpu.mem_used_mb as f32 / gpu.mem_total_mb as f32 * 100.0
+            } else {
+                0.0
+            };
+            let checks = [
+                ("utilization", gpu.util_pct, self.util_pct, "%"),
+                ("memory", mem_pct, self.mem_pct, "%"),
+                (
+                    "temperature",
+                    gpu.temp_c as f32,
+                    self.temp_c.map(|c| c as f32),
+                    "°C",
+                ),
+            ];
+            for (metric, value, limit, unit) in checks {
+                if let Some(limit) = limit.filter(|limit| value > *limit) {
+                    breaches.push(AlertBreach {
+                        gpu_index: gpu.gpu_index,
+                        gpu_name: gpu.name.clone(),
+                        ts: snapshot.ts.clone(),
+                        metric,
+                        value,
+                        limit,
+                        unit,
+                    });
+                }
+            }
+        }
+        breaches
+    }
+}
+
+/// Combined usage of a set of GPUs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageTotals {
+    pub gpus: usize,
+    pub mem_used_mb: u64,
+    pub mem_total_mb: u64,
+    pub avg_util_pct: f32,
+    /// Power drawn by the GPUs that report it; None when none of them do
+    pub power_w: Option<f32>,
+    /// GPUs included in `power_w`
+    pub power_reporting_gpus: usize,
+    pub processes: usize,
+}
+
+impl UsageTotals {
+    pub fn from_gpus<'a>(gpus: impl IntoIterator<Item = &'a GpuSnapshot>) -> Self {
+        let mut totals = Self {
+            gpus: 0,
+            mem_used_mb: 0,
+            mem_total_mb: 0,
+            avg_util_pct: 0.0,
+            power_w: None,
+            power_reporting_gpus: 0,
+            processes: 0,
+        };
+        let mut util_sum = 0.0;
+        for gpu in gpus {
+            totals.gpus += 1;
+            totals.mem_used_mb += u64::from(gpu.mem_used_mb);
+            totals.mem_total_mb += u64::from(gpu.mem_total_mb);
+            util_sum += gpu.util_pct;
+            totals.processes += gpu.pids;
+            if let Some(power) = reported_power_w(gpu) {
+                totals.power_w = Some(totals.power_w.unwrap_or(0.0) + power);
+                totals.power_reporting_gpus += 1;
+            }
+        }
+        if totals.gpus > 0 {
+            totals.avg_util_pct = util_sum / totals.gpus as f32;
+        }
+        totals
+    }
+}
+
+/// Power a GPU draws, or None when its vendor cannot measure it (Intel and Apple
+/// GPUs report 0 W)
+pub fn reported_power_w(gpu: &GpuSnapshot) -> Option<f32> {
+    match gpu.vendor {
+        GpuVendor::Intel | GpuVendor::Apple => None,
+        _ => Some(gpu.power_w),
+    }
+}
+
+/// Totals for a whole snapshot and for each vendor in it. The table footer and
+/// the JSON output both come from this, so they always agree.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotTotals {
+    pub totals: UsageTotals,
+    /// Keyed by vendor as it appears in each GPU's `vendor` field
+    pub by_vendor: BTreeMap<String, UsageTotals>,
+}
+
+impl SnapshotTotals {
+    pub fn from_gpus(gpus: &[GpuSnapshot]) -> Self {
+        let mut vendors: BTreeMap<String, Vec<&GpuSnapshot>> = BTreeMap::new();
+        for gpu in gpus {
+            // Debug matches the serialized name, e.g. "Nvidia"
+            vendors
+                .entry(format!("{:?}", gpu.vendor))
+                .or_default()
+                .push(gpu);
+        }
+        Self {
+            totals: UsageTotals::from_gpus(gpus),
+            by_vendor: vendors
+                .into_iter()
+                .map(|(vendor, gpus)| (vendor, UsageTotals::from_gpus(gpus)))
+                .collect(),
+        }
+    }
+}
+
+/// A snapshot as `--list --output json` prints it: the snapshot with its totals
+#[derive(Debug, Serialize)]
+pub struct SnapshotReport<'a> {
+    #[serde(flatten)]
+    pub snapshot: &'a Snapshot,
+    #[serde(flatten)]
+    pub totals: SnapshotTotals,
+}
+
+impl<'a> SnapshotReport<'a> {
+    pub fn new(snapshot: &'a Snapshot) -> Self {
+        Self {
+            snapshot,
+            totals: SnapshotTotals::from_gpus(&snapshot.gpus),
+        }
+    }
+}
+
+/// Write `snapshot` as pretty JSON, the same format as `--list --output json`
+pub fn save_snapshot(path: &Path, snapshot: &Snapshot) -> Result<()> {
+    let json = serde_json::to_string_pretty(&SnapshotReport::new(snapshot))
+        .context("Failed to serialize snapshot")?;
+    fs::write(path, json).with_context(|| format!("Failed to write snapshot to {}", path.display()))
+}
+
+/// Load a snapshot written by `--save-snapshot` (or `--list --output json`)
+pub fn load_snapshot(path: &Path) -> Result<Snapshot> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read snapshot {}", path.display()))?;
+    parse_snapshot(&content).with_context(|| format!("Cannot load snapshot {}", path.display()))
+}
+
+/// Parse a saved snapshot, explaining schema mismatches instead of surfacing raw serde errors
+pub fn parse_snapshot(content: &str) -> Result<Snapshot> {
+    let value: serde_json::Value =
+        serde_json::from_str(content).context("The file is not valid JSON")?;
+    let missing: Vec<&str> = SNAPSHOT_FIELDS
+        .iter()
+        .copied()
+        .filter(|field| value.get(field).is_none())
+        .collect();
+    if !missing.is_empty() {
+        anyhow::bail!(
+            "The file does not look like a gpukill snapshot (missing {}); \
+             save one with `gpukill --list --save-snapshot <path>`",
+            missing.join(", ")
+        );
+    }
+    serde_json::from_value(value).map_err(|e| {
+        anyhow::anyhow!(
+            "The snapshot was saved in an incompatible format, likely by an older gpukill ({}); \
+             save a new baseline with `gpukill --list --save-snapshot <path>`",
+            e
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nvml_api::fixtures::{gpu, proc};
+
+    fn snapshot(gpus: Vec<GpuSnapshot>, procs: Vec<GpuProc>) -> Snapshot {
+        Snapshot {
+            host: "node-1".to_string(),
+            ts: "2026-01-01T00:00:00Z".to_string(),
+            gpus,
+            procs,
+            degraded: false,
+            gpu_status: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_diff_reports_process_and_gpu_changes() {
+        let mut cool = gpu(0, 10.0, 1000);
+        cool.ecc_volatile = Some(EccCounts::default());
+        let baseline = snapshot(
+            vec![cool, gpu(1, 0.0, 0)],
+            vec![
+                proc(0, 100, "alice", "train", 1024),
+                proc(0, 200, "alice", "eval", 1024),
+            ],
+        );
+        let mut hot = gpu(0, 95.0, 9000);
+        hot.temp_c = 70;
+        hot.ecc_volatile = Some(EccCounts {
+            corrected: 0,
+            uncorrected: 2,
+        });
+        let current = snapshot(
+            vec![hot, gpu(2, 0.0, 0)],
+            // PID 200 was reused by a different program
+            vec![
+                proc(0, 100, "alice", "train", 1024),
+                proc(0, 200, "alice", "miner", 1024),
+                proc(2, 300, "alice", "infer", 1024),
+            ],
+        );
+
+        let diff = diff_snapshots(&baseline, &current);
+        let pids = |procs: &[GpuProc]| procs.iter().map(|p| p.pid).collect::<Vec<_>>();
+        assert_eq!(pids(&diff.appeared), vec![200, 300]);
+        assert_eq!(diff.appeared[0].proc_name, "miner");
+        assert_eq!(pids(&diff.disappeared), vec![200]);
+        assert_eq!(diff.disappeared[0].proc_name, "eval");
+        assert_eq!(diff.added_gpus, vec![2]);
+        assert_eq!(diff.removed_gpus, vec![1]);
+
+        assert_eq!(diff.gpus.len(), 1);
+        let delta = &diff.gpus[0];
+        assert_eq!(delta.mem_delta_mb, 8000);
+        assert_eq!(delta.util_delta_pct, 85.0);
+        assert_eq!(delta.temp_delta_c, 30);
+        assert_eq!(
+            delta.health_changes,
+            vec!["volatile ECC: 0 corrected/0 uncorrected -> 0 corrected/2 uncorrected"]
+        );
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn test_diff_of_identical_snapshots_is_empty() {
+        let baseline = snapshot(
+            vec![gpu(0, 10.0, 1000)],
+            vec![proc(0, 100, "alice", "train", 1024)],
+        );
+        let diff = diff_snapshots(&baseline, &baseline.clone());
+        assert!(diff.is_empty());
+        assert!(!diff.gpus[0].is_changed());
+    }
+
+    #[test]
+    fn test_gpu_rates_per_second() {
+        let previous = snapshot(vec![gpu(0, 10.0, 1000), gpu(1, 50.0, 4000)], Vec::new());
+        let current = snapshot(vec![gpu(0, 30.0, 1400), gpu(2, 0.0, 0)], Vec::new());
+
+        let rates = gpu_rates(&previous, &current, Duration::from_secs(2));
+        // GPU 1 disappeared and GPU 2 is new: neither has a rate
+        assert_eq!(rates.len(), 1);
+        assert_eq!(
+            rates[&0],
+            GpuRate {
+                mem_mb_per_sec: 200.0,
+                util_delta_pct: 20.0,
+            }
+        );
+
+        let freed = gpu_rates(&current, &previous, Duration::from_millis(500));
+        assert_eq!(freed[&0].mem_mb_per_sec, -800.0);
+        assert_eq!(freed[&0].util_delta_pct, -20.0);
+
+        assert!(gpu_rates(&previous, &current, Duration::ZERO).is_empty());
+    }
+
+    #[test]
+    fn test_rate_tracker_first_iteration_is_empty() {
+        let mut tracker = RateTracker::default();
+        let first = snapshot(vec![gpu(0, 10.0, 1000)], Vec::new());
+        assert!(tracker.update(&first).is_empty());
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(tracker.update(&first).contains_key(&0));
+    }
+
+    #[test]
+    fn test_saved_snapshot_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("baseline.json");
+        let saved = snapshot(
+            vec![gpu(0, 10.0, 1000)],
+            vec![proc(0, 100, "alice", "train", 1024)],
+        );
+        save_snapshot(&path, &saved).unwrap();
+
+        let loaded = load_snapshot(&path).unwrap();
+        assert!(diff_snapshots(&saved, &loaded).is_empty());
+    }
+
+    #[test]
+    fn test_usage_totals_across_vendors() {
+        let mut gpus = vec![gpu(0, 10.0, 1000), gpu(1, 50.0, 3000), gpu(2, 30.0, 512)];
+        gpus[0].pids = 2;
+        gpus[1].pids = 1;
+        gpus[1].power_w = 250.0;
+        gpus[2].vendor = GpuVendor::Intel;
+        gpus[2].mem_total_mb = 16384;
+        gpus[2].pids = 4;
+
+        let totals = SnapshotTotals::from_gpus(&gpus);
+        assert_eq!(totals.totals.gpus, 3);
+        assert_eq!(totals.totals.mem_used_mb, 4512);
+        assert_eq!(totals.totals.mem_total_mb, 2 * 24576 + 16384);
+        assert!((totals.totals.avg_util_pct - 30.0).abs() < 1e-4);
+        assert_eq!(totals.totals.processes, 7);
+        // Intel reports no power, so only the NVIDIA GPUs count
+        assert_eq!(totals.totals.power_w, Some(350.0));
+        assert_eq!(totals.totals.power_reporting_gpus, 2);
+
+        assert_eq!(totals.by_vendor.len(), 2);
+        let nvidia = &totals.by_vendor["Nvidia"];
+        assert_eq!(nvidia.gpus, 2);
+        assert_eq!(nvidia.mem_used_mb, 4000);
+        assert!((nvidia.avg_util_pct - 30.0).abs() < 1e-4);
+        let intel = &totals.by_vendor["Intel"];
+        assert_eq!(intel.power_w, None);
+        assert_eq!(intel.power_reporting_gpus, 0);
+        assert_eq!(intel.processes, 4);
+    }
+
+    #[test]
+    fn test_usage_totals_of_no_gpus() {
+        let totals = UsageTotals::from_gpus(&[]);
+        assert_eq!(totals.gpus, 0);
+        assert_eq!(totals.mem_total_mb, 0);
+        assert_eq!(totals.avg_util_pct, 0.0);
+        assert_eq!(totals.power_w, None);
+    }
+
+    #[test]
+    fn test_snapshot_report_json_includes_totals() {
+        let saved = snapshot(vec![gpu(0, 10.0, 1000), gpu(1, 20.0, 2000)], vec![]);
+        let json = serde_json::to_value(SnapshotReport::new(&saved)).unwrap();
+        assert_eq!(json["totals"]["mem_used_mb"], 3000);
+        assert_eq!(json["totals"]["power_w"], 200.0);
+        assert_eq!(json["by_vendor"]["Nvidia"]["gpus"], 2);
+        assert_eq!(json["gpus"].as_array().unwrap().len(), 2);
+
+        // The extra fields do not get in the way of loading it back
+        let loaded = parse_snapshot(&json.to_string()).unwrap();
+        assert!(diff_snapshots(&saved, &loaded).is_empty());
+    }
+
+    #[test]
+    fn test_alert_thresholds_report_each_breach() {
+        let mut hot = gpu(1, 40.0, 24000);
+        hot.temp_c = 91;
+        let current = snapshot(vec![gpu(0, 99.0, 1000), hot], vec![]);
+
+        let thresholds = AlertThresholds {
+            util_pct: Some(95.0),
+            mem_pct: Some(90.0),
+            temp_c: Some(85),
+        };
+        let breaches = thresholds.breaches(&current);
+        let found: Vec<_> = breaches.iter().map(|b| (b.gpu_index, b.metric)).collect();
+        assert_eq!(
+            found,
+            [(0, "utilization"), (1, "memory"), (1, "temperature")]
+        );
+        assert_eq!(
+            breaches[0].to_string(),
+            "GPU 0 (Test GPU) utilization 99.0% exceeded 95% at 2026-01-01T00:00:00Z"
+        );
+
+        // Limits are exclusive, and no limits means no breaches
+        let at_limit = AlertThresholds {
+            util_pct: Some(99.0),
+            ..Default::default()
+        };
+        assert!(at_limit.breaches(&current).is_empty());
+        assert!(AlertThresholds::default().breaches(&current).is_empty());
+    }
+
+    #[test]
+    fn test_incompatible_snapshot_gives_helpful_error() {
+        // An older schema without the vendor field
+        let old = r#"{"host":"node-1","ts":"t","procs":[],"gpus":[{"gpu_index":0,"name":"GPU",
+            "mem_used_mb":0,"mem_total_mb":1,"util_pct":0.0,"temp_c":0,"power_w":0.0,
+            "pids":0,"top_proc":null}]}"#;
+        let err = parse_snapshot(old).unwrap_err().to_string();
+        assert!(err.contains("incompatible format"), "{}", err);
+        assert!(err.contains("vendor"), "{}", err);
+        assert!(err.contains("--save-snapshot"), "{}", err);
+
+        let err = parse_snapshot(r#"{"gpus": []}"#).unwrap_err().to_string();
+        assert!(err.contains("missing host, ts, procs"), "{}", err);
+
+        assert!(parse_snapshot("not json").is_err());
+    }
+}
@@ -0,0 +1,82 @@
+//! HTTPS for the coordinator API (`--server-tls-cert`, `--server-tls-key`)
+
+use anyhow::{Context, Result};
+use axum_server::tls_rustls::RustlsConfig;
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::ServerConfig;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Load a PEM certificate chain and its private key into a server configuration.
+/// Fails if either file cannot be read or parsed, or if the key does not belong
+/// to the certificate, so a bad pair is reported at startup rather than on the
+/// first connection.
+pub fn load_server_config(cert_path: &Path, key_path: &Path) -> Result<RustlsConfig> {
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| anyhow::anyhow!("{}", e))
+        .with_context(|| format!("Failed to read TLS certificate {}", cert_path.display()))?;
+    if certs.is_empty() {
+        anyhow::bail!("No certificates found in {}", cert_path.display());
+    }
+    let key = PrivateKeyDer::from_pem_file(key_path)
+        .map_err(|e| anyhow::anyhow!("{}", e))
+        .with_context(|| format!("Failed to read TLS private key {}", key_path.display()))?;
+
+    let mut config =
+        ServerConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .context("Failed to configure TLS")?
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .map_err(|e| anyhow::anyhow!("{}", e))
+            .with_context(|| {
+                format!(
+                    "TLS certificate {} and private key {} do not match",
+                    cert_path.display(),
+                    key_path.display()
+                )
+            })?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(RustlsConfig::from_config(Arc::new(config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write a fresh self-signed certificate and key for localhost to `dir`
+    fn write_self_signed(dir: &Path, name: &str) -> (std::path::PathBuf, std::path::PathBuf) {
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert_path = dir.join(format!("{}.crt", name));
+        let key_path = dir.join(format!("{}.key", name));
+        std::fs::write(&cert_path, certified.cert.pem()).unwrap();
+        std::fs::write(&key_path, certified.key_pair.serialize_pem()).unwrap();
+        (cert_path, key_path)
+    }
+
+    #[test]
+    fn test_load_server_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let (cert, key) = write_self_signed(dir.path(), "a");
+        let (other_cert, other_key) = write_self_signed(dir.path(), "b");
+
+        assert!(load_server_config(&cert, &key).is_ok());
+        assert!(load_server_config(&other_cert, &other_key).is_ok());
+
+        // A key from another pair
+        let err = format!("{:#}", load_server_config(&cert, &other_key).unwrap_err());
+        assert!(err.contains("do not match"), "{}", err);
+
+        // Files swapped: no certificate in the key file and vice versa
+        let err = format!("{:#}", load_server_config(&key, &cert).unwrap_err());
+        assert!(err.contains("No certificates found"), "{}", err);
+        let err = format!("{:#}", load_server_config(&cert, &cert).unwrap_err());
+        assert!(err.contains("Failed to read TLS private key"), "{}", err);
+
+        let missing = dir.path().join("missing.crt");
+        let err = format!("{:#}", load_server_config(&missing, &key).unwrap_err());
+        assert!(err.contains("missing.crt"), "{}", err);
+    }
+}
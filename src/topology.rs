@@ -0,0 +1,425 @@
+//! NVIDIA GPU interconnect topology for `--topology`.
+//!
+//! For every pair of GPUs this reports how they are connected: the number of active
+//! NVLink links between them, or otherwise the PCIe path NVML reports as their common
+//! ancestor. Device queries go through [`TopologySource`] so tests can build a
+//! topology without NVML.
+
+use anyhow::Result;
+use nvml_wrapper::enum_wrappers::device::TopologyLevel;
+use nvml_wrapper::error::NvmlError;
+use nvml_wrapper::Nvml;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Links NVML can report per device (NVML_NVLINK_MAX_LINKS)
+const NVLINK_MAX_LINKS: u32 = 18;
+
+/// How two GPUs are connected, best first. Labels follow `nvidia-smi topo -m`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GpuLink {
+    /// The GPU itself
+    #[serde(rename = "self")]
+    SelfLink,
+    /// Active NVLink links between the two GPUs
+    Nvlink { links: u32 },
+    /// Both GPUs are on one board
+    Board,
+    /// At most one PCIe switch apart
+    PcieSwitch,
+    /// Several PCIe switches apart, without crossing a host bridge
+    PcieSwitches,
+    /// Through a PCIe host bridge
+    HostBridge,
+    /// Across host bridges within one NUMA node
+    NumaNode,
+    /// Across NUMA nodes
+    System,
+    /// NVML could not tell
+    Unknown,
+}
+
+impl GpuLink {
+    /// The PCIe path for an NVML topology level
+    pub fn from_level(level: TopologyLevel) -> Self {
+        match level {
+            TopologyLevel::Internal => GpuLink::Board,
+            TopologyLevel::Single => GpuLink::PcieSwitch,
+            TopologyLevel::Multiple => GpuLink::PcieSwitches,
+            TopologyLevel::HostBridge => GpuLink::HostBridge,
+            TopologyLevel::Node => GpuLink::NumaNode,
+            TopologyLevel::System => GpuLink::System,
+        }
+    }
+}
+
+impl fmt::Display for GpuLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GpuLink::SelfLink => write!(f, "X"),
+            GpuLink::Nvlink { links } => write!(f, "NV{}", links),
+            GpuLink::Board => write!(f, "BRD"),
+            GpuLink::PcieSwitch => write!(f, "PIX"),
+            GpuLink::PcieSwitches => write!(f, "PXB"),
+            GpuLink::HostBridge => write!(f, "PHB"),
+            GpuLink::NumaNode => write!(f, "NODE"),
+            GpuLink::System => write!(f, "SYS"),
+            GpuLink::Unknown => write!(f, "?"),
+        }
+    }
+}
+
+/// Legend for the labels in the topology matrix
+pub const LINK_LEGEND: [&str; 9] = [
+    "X    = Self",
+    "NV#  = Connected by # active NVLink links",
+    "BRD  = On the same board",
+    "PIX  = At most one PCIe switch apart",
+    "PXB  = Several PCIe switches apart, without crossing a host bridge",
+    "PHB  = Through a PCIe host bridge (usually the CPU)",
+    "NODE = Across PCIe host bridges within a NUMA node",
+    "SYS  = Across NUMA nodes (e.g. QPI/UPI)",
+    "?    = Unknown",
+];
+
+/// One NVLink link of a GPU
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NvLinkState {
+    pub link: u32,
+    pub active: bool,
+    /// PCI bus id of the device at the other end; None if the link is down or NVML
+    /// does not report it
+    pub remote_pci_bus_id: Option<String>,
+}
+
+/// A GPU in the topology
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopologyGpu {
+    pub index: u16,
+    pub name: String,
+    pub uuid: Option<String>,
+    pub pci_bus_id: Option<String>,
+    /// Links NVML reports for the GPU; empty when it has no NVLink
+    pub nvlinks: Vec<NvLinkState>,
+}
+
+impl TopologyGpu {
+    /// Active links whose other end is the device at `pci_bus_id`
+    fn active_links_to(&self, pci_bus_id: &str) -> u32 {
+        self.nvlinks
+            .iter()
+            .filter(|link| link.active)
+            .filter(|link| {
+                link.remote_pci_bus_id
+                    .as_deref()
+                    .is_some_and(|remote| remote.eq_ignore_ascii_case(pci_bus_id))
+            })
+            .count() as u32
+    }
+}
+
+/// Device queries needed to build a topology
+pub trait TopologySource {
+    fn device_count(&self) -> Result<u32>;
+
+    /// The GPU at `index`, with its NVLink links
+    fn gpu(&self, index: u32) -> Result<TopologyGpu>;
+
+    /// The PCIe path between two GPUs
+    fn common_ancestor(&self, a: u32, b: u32) -> Result<GpuLink>;
+}
+
+/// Result of `--topology`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopologyReport {
+    pub operation: String,
+    pub gpus: Vec<TopologyGpu>,
+    /// `matrix[i][j]` is how `gpus[i]` reaches `gpus[j]`
+    pub matrix: Vec<Vec<GpuLink>>,
+}
+
+impl TopologyReport {
+    /// Query every GPU and every pair of GPUs. GPUs connected by an active NVLink
+    /// are reported as such; other pairs get their PCIe path, or `Unknown` when NVML
+    /// cannot tell.
+    pub fn collect(source: &dyn TopologySource) -> Result<Self> {
+        let count = source.device_count()?;
+        let gpus = (0..count)
+            .map(|index| source.gpu(index))
+            .collect::<Result<Vec<_>>>()?;
+
+        let matrix = (0..gpus.len())
+            .map(|i| {
+                (0..gpus.len())
+                    .map(|j| {
+                        if i == j {
+                            return GpuLink::SelfLink;
+                        }
+                        let links = gpus[j]
+                            .pci_bus_id
+                            .as_deref()
+                            .map_or(0, |bus_id| gpus[i].active_links_to(bus_id));
+                        if links > 0 {
+                            return GpuLink::Nvlink { links };
+                        }
+                        source
+                            .common_ancestor(i as u32, j as u32)
+                            .unwrap_or_else(|e| {
+                                tracing::debug!(
+                                    "No topology between GPU {} and GPU {}: {:#}",
+                                    i,
+                                    j,
+                                    e
+                                );
+                                GpuLink::Unknown
+                            })
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Ok(Self {
+            operation: "topology".to_string(),
+            gpus,
+            matrix,
+        })
+    }
+
+    /// Whether any GPU reported an NVLink link
+    pub fn has_nvlink(&self) -> bool {
+        self.gpus.iter().any(|gpu| !gpu.nvlinks.is_empty())
+    }
+
+    /// Index of the GPU at `pci_bus_id`, if it is one of ours
+    pub fn gpu_at(&self, pci_bus_id: &str) -> Option<u16> {
+        self.gpus
+            .iter()
+            .find(|gpu| {
+                gpu.pci_bus_id
+                    .as_deref()
+                    .is_some_and(|id| id.eq_ignore_ascii_case(pci_bus_id))
+            })
+            .map(|gpu| gpu.index)
+    }
+
+    /// Renumber GPUs, e.g. to their stable indices, keeping the matrix in index order.
+    /// GPUs `index_of` has no index for keep theirs.
+    pub fn relabel(mut self, index_of: impl Fn(&TopologyGpu) -> Option<u16>) -> Self {
+        for gpu in &mut self.gpus {
+            gpu.index = index_of(gpu).unwrap_or(gpu.index);
+        }
+        let mut order: Vec<usize> = (0..self.gpus.len()).collect();
+        order.sort_by_key(|&i| self.gpus[i].index);
+
+        self.matrix = order
+            .iter()
+            .map(|&i| order.iter().map(|&j| self.matrix[i][j]).collect())
+            .collect();
+        self.gpus = order.iter().map(|&i| self.gpus[i].clone()).collect();
+        self
+    }
+}
+
+/// Topology queries against NVML
+pub struct NvmlTopology<'a> {
+    nvml: &'a Nvml,
+}
+
+impl<'a> NvmlTopology<'a> {
+    pub fn new(nvml: &'a Nvml) -> Self {
+        Self { nvml }
+    }
+}
+
+impl TopologySource for NvmlTopology<'_> {
+    fn device_count(&self) -> Result<u32> {
+        Ok(self.nvml.device_count()?)
+    }
+
+    fn gpu(&self, index: u32) -> Result<TopologyGpu> {
+        let device = self.nvml.device_by_index(index)?;
+        // Links NVML does not answer for do not exist on this GPU; a GPU without
+        // NVLink answers for none
+        let nvlinks = (0..NVLINK_MAX_LINKS)
+            .filter_map(|link| {
+                let nvlink = device.link_wrapper_for(link);
+                let active = nvlink.is_active().ok()?;
+                Some(NvLinkState {
+                    link,
+                    active,
+                    remote_pci_bus_id: active
+                        .then(|| nvlink.remote_pci_info().ok().map(|pci| pci.bus_id))
+                        .flatten(),
+                })
+            })
+            .collect();
+
+        Ok(TopologyGpu {
+            index: index as u16,
+            name: device.name().unwrap_or_else(|_| "Unknown".to_string()),
+            uuid: device.uuid().ok(),
+            pci_bus_id: device.pci_info().ok().map(|pci| pci.bus_id),
+            nvlinks,
+        })
+    }
+
+    fn common_ancestor(&self, a: u32, b: u32) -> Result<GpuLink> {
+        let first = self.nvml.device_by_index(a)?;
+        let second = self.nvml.device_by_index(b)?;
+        match first.topology_common_ancestor(second) {
+            Ok(level) => Ok(GpuLink::from_level(level)),
+            // Older drivers and some platforms (e.g. WSL) do not report topology
+            Err(NvmlError::NotSupported) => Ok(GpuLink::Unknown),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::OutputFormat;
+    use crate::render::{OutputStyle, Renderer};
+
+    fn bus(index: u32) -> String {
+        format!("00000000:{:02X}:00.0", 0x10 + index)
+    }
+
+    fn link(link: u32, remote: Option<u32>) -> NvLinkState {
+        NvLinkState {
+            link,
+            active: remote.is_some(),
+            remote_pci_bus_id: remote.map(bus),
+        }
+    }
+
+    /// GPUs 0 and 1 share two NVLink links, GPU 2 has one to GPU 1 and a link that
+    /// is down, and GPU 3 has no NVLink. 0/1 and 2/3 sit under one PCIe switch each,
+    /// on two NUMA nodes.
+    struct MockTopology;
+
+    impl TopologySource for MockTopology {
+        fn device_count(&self) -> Result<u32> {
+            Ok(4)
+        }
+
+        fn gpu(&self, index: u32) -> Result<TopologyGpu> {
+            let nvlinks = match index {
+                0 => vec![link(0, Some(1)), link(1, Some(1))],
+                1 => vec![link(0, Some(0)), link(1, Some(0)), link(2, Some(2))],
+                2 => vec![link(0, Some(1)), link(1, None)],
+                _ => Vec::new(),
+            };
+            Ok(TopologyGpu {
+                index: index as u16,
+                name: "Mock GPU".to_string(),
+                uuid: Some(format!("GPU-{}", index)),
+                pci_bus_id: Some(bus(index)),
+                nvlinks,
+            })
+        }
+
+        fn common_ancestor(&self, a: u32, b: u32) -> Result<GpuLink> {
+            match (a / 2 == b / 2, a.max(b)) {
+                (true, _) => Ok(GpuLink::PcieSwitch),
+                (false, 3) => Err(anyhow::anyhow!("NVML error")),
+                (false, _) => Ok(GpuLink::System),
+            }
+        }
+    }
+
+    #[test]
+    fn test_topology_matrix_prefers_nvlink_over_pcie() {
+        let report = TopologyReport::collect(&MockTopology).unwrap();
+        let labels: Vec<Vec<String>> = report
+            .matrix
+            .iter()
+            .map(|row| row.iter().map(|link| link.to_string()).collect())
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                vec!["X", "NV2", "SYS", "?"],
+                vec!["NV2", "X", "NV1", "?"],
+                vec!["SYS", "NV1", "X", "PIX"],
+                vec!["?", "?", "PIX", "X"],
+            ]
+        );
+        assert!(report.has_nvlink());
+        assert_eq!(report.gpu_at(&bus(2).to_lowercase()), Some(2));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["matrix"][0][1]["type"], "nvlink");
+        assert_eq!(json["matrix"][0][1]["links"], 2);
+        assert_eq!(json["matrix"][2][3]["type"], "pcie_switch");
+        assert_eq!(json["gpus"][2]["nvlinks"][1]["active"], false);
+    }
+
+    #[test]
+    fn test_topology_table_rendering() {
+        let report = TopologyReport::collect(&MockTopology).unwrap();
+        let renderer = Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN);
+        let output = renderer.format_topology(&report);
+        let expected_matrix = "\
++------+------+------+------+------+
+|      | GPU0 | GPU1 | GPU2 | GPU3 |
++------+------+------+------+------+
+| GPU0 | X    | NV2  | SYS  | ?    |
++------+------+------+------+------+
+| GPU1 | NV2  | X    | NV1  | ?    |
++------+------+------+------+------+
+| GPU2 | SYS  | NV1  | X    | PIX  |
++------+------+------+------+------+
+| GPU3 | ?    | ?    | PIX  | X    |
++------+------+------+------+------+";
+        assert!(output.starts_with(expected_matrix), "{}", output);
+        assert!(output.contains("NV#  = Connected by # active NVLink links"));
+        // Link table: remote ends are named by GPU, inactive links by their state
+        assert!(
+            output.contains("| 2   | 1    | inactive | -      |"),
+            "{}",
+            output
+        );
+        assert!(
+            output.contains("| 2   | 0    | active   | GPU 1  |"),
+            "{}",
+            output
+        );
+
+        // Renumbering keeps each row with its GPU
+        let relabeled = report.relabel(|gpu| Some(3 - gpu.index));
+        assert_eq!(relabeled.gpus[0].uuid.as_deref(), Some("GPU-3"));
+        assert_eq!(relabeled.matrix[0][1].to_string(), "PIX");
+        assert_eq!(relabeled.matrix[2][3].to_string(), "NV2");
+    }
+
+    #[test]
+    fn test_topology_without_nvlink() {
+        struct PcieOnly;
+        impl TopologySource for PcieOnly {
+            fn device_count(&self) -> Result<u32> {
+                Ok(2)
+            }
+            fn gpu(&self, index: u32) -> Result<TopologyGpu> {
+                Ok(TopologyGpu {
+                    index: index as u16,
+                    name: "PCIe GPU".to_string(),
+                    uuid: None,
+                    pci_bus_id: Some(bus(index)),
+                    nvlinks: Vec::new(),
+                })
+            }
+            fn common_ancestor(&self, _a: u32, _b: u32) -> Result<GpuLink> {
+                Ok(GpuLink::HostBridge)
+            }
+        }
+
+        let report = TopologyReport::collect(&PcieOnly).unwrap();
+        assert!(!report.has_nvlink());
+        assert_eq!(report.matrix[0][1], GpuLink::HostBridge);
+        let output =
+            Renderer::with_style(OutputFormat::Table, OutputStyle::PLAIN).format_topology(&report);
+        assert!(output.contains("No NVLink links"), "{}", output);
+    }
+}
@@ -1,6 +1,22 @@
 use chrono::{DateTime, Local, Utc};
+use std::io::{self, BufRead, Write};
 use std::time::{Duration, SystemTime};
 
+/// How long interactive confirmations wait for an answer before assuming no
+pub const CONFIRM_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Whether `headers` carry `Authorization: Bearer <expected>`. The token is compared
+/// in constant time, so response times do not reveal how much of a guess was right.
+pub fn bearer_token_matches(headers: &axum::http::HeaderMap, expected: &str) -> bool {
+    use subtle::ConstantTimeEq;
+
+    headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|given| bool::from(given.as_bytes().ct_eq(expected.as_bytes())))
+}
+
 /// Get the current hostname
 pub fn get_hostname() -> String {
     hostname::get()
@@ -75,6 +91,66 @@ pub fn format_memory_mb_to_gib(mb: u32) -> String {
     format!("{:.1}", gib)
 }
 
+/// Format a size in MiB with the largest binary unit that keeps it at least 1:
+/// "512 MiB", "80.0 GiB", "1.5 TiB"
+pub fn format_mib(mb: u64) -> String {
+    if mb < 1024 {
+        return format!("{} MiB", mb);
+    }
+    let gib = mb as f64 / 1024.0;
+    // Just under 1 TiB would print as "1024.0 GiB"
+    if (gib * 10.0).round() < 1024.0 * 10.0 {
+        format!("{:.1} GiB", gib)
+    } else {
+        format!("{:.1} TiB", gib / 1024.0)
+    }
+}
+
+/// Format used and total memory in MiB: "2.0/8.0 GiB" when both sizes share a
+/// unit, otherwise "512 MiB/80.0 GiB"
+pub fn format_mib_pair(used_mb: u64, total_mb: u64) -> String {
+    let (used, total) = (format_mib(used_mb), format_mib(total_mb));
+    match (used.split_once(' '), total.split_once(' ')) {
+        (Some((used, used_unit)), Some((total, total_unit))) if used_unit == total_unit => {
+            format!("{}/{} {}", used, total, total_unit)
+        }
+        _ => format!("{}/{}", used, total),
+    }
+}
+
+/// How long ago something happened, in its two largest units: "45s ago",
+/// "13m 5s ago", "2h 13m ago", "3d ago"
+pub fn format_age(elapsed: Duration) -> String {
+    const UNITS: [(u64, &str); 4] = [(86400, "d"), (3600, "h"), (60, "m"), (1, "s")];
+    let secs = elapsed.as_secs();
+    let Some(first) = UNITS.iter().position(|(unit_secs, _)| secs >= *unit_secs) else {
+        return "just now".to_string();
+    };
+    let (unit_secs, unit) = UNITS[first];
+    let mut age = format!("{}{}", secs / unit_secs, unit);
+    if let Some((next_secs, next_unit)) = UNITS.get(first + 1) {
+        let rest = secs % unit_secs / next_secs;
+        if rest > 0 {
+            age.push_str(&format!(" {}{}", rest, next_unit));
+        }
+    }
+    format!("{} ago", age)
+}
+
+/// Process start time for tables: how long ago, then the time itself, e.g.
+/// "2h 13m ago (2026-10-16 09:51)". A start after `now` (clock skew) reads "just now".
+pub fn format_start_time<Tz: chrono::TimeZone>(started: DateTime<Tz>, now: DateTime<Tz>) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let elapsed = (now - started.clone()).to_std().unwrap_or_default();
+    format!(
+        "{} ({})",
+        format_age(elapsed),
+        started.format("%Y-%m-%d %H:%M")
+    )
+}
+
 /// Check if running on Linux
 #[allow(dead_code)]
 pub fn is_linux() -> bool {
@@ -129,11 +205,213 @@ pub fn parse_process_start_time(start_time: SystemTime) -> String {
     format_duration(duration)
 }
 
+/// Parse an RFC3339 timestamp or a plain `YYYY-MM-DD` date (midnight UTC)
+pub fn parse_date_or_timestamp(value: &str) -> anyhow::Result<DateTime<Utc>> {
+    if let Ok(ts) = DateTime::parse_from_rfc3339(value) {
+        return Ok(ts.with_timezone(&Utc));
+    }
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_time(chrono::NaiveTime::MIN).and_utc())
+        .map_err(|_| {
+            anyhow::anyhow!(
+                "'{}' is not a valid date or timestamp; expected YYYY-MM-DD or RFC3339 (e.g. 2024-06-01T08:00:00Z)",
+                value
+            )
+        })
+}
+
+/// Parse a duration such as `90s`, `30m`, `2h`, `1d` or `1h30m`; a bare number is minutes
+pub fn parse_duration_spec(value: &str) -> anyhow::Result<Duration> {
+    let value = value.trim();
+    let invalid = || {
+        anyhow::anyhow!(
+            "'{}' is not a valid duration; expected e.g. 90s, 30m, 2h, 1d or 1h30m",
+            value
+        )
+    };
+    if value.is_empty() {
+        return Err(invalid());
+    }
+    if let Ok(minutes) = value.parse::<u64>() {
+        return Ok(Duration::from_secs(minutes * 60));
+    }
+
+    let mut total = 0u64;
+    let mut digits = String::new();
+    for c in value.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let amount: u64 = digits.parse().map_err(|_| invalid())?;
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 3600,
+            'd' => 86400,
+            _ => return Err(invalid()),
+        };
+        total += amount * unit;
+        digits.clear();
+    }
+    if !digits.is_empty() {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// Escape a single CSV field, quoting it when it contains separators or quotes
+pub fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Escape text for HTML element content and quoted attribute values
+pub fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Whether a destructive operation should stop and ask first. It only asks when
+/// someone can answer (`interactive`: stdin and stdout are terminals), the output
+/// is not JSON and `--yes` was not given; otherwise the operation goes ahead.
+pub fn needs_confirmation(assume_yes: bool, json_output: bool, interactive: bool) -> bool {
+    interactive && !assume_yes && !json_output
+}
+
+/// Ask `question` on `output` and wait up to `timeout` for the answer on `input`.
+/// Only "y" or "yes" confirms; anything else, end of input or no answer in time
+/// counts as no.
+pub fn confirm<R, W>(
+    question: &str,
+    input: R,
+    output: &mut W,
+    timeout: Duration,
+) -> io::Result<bool>
+where
+    R: BufRead + Send + 'static,
+    W: Write,
+{
+    write!(output, "{} [y/N]: ", question)?;
+    output.flush()?;
+
+    // The reader thread is left blocked if nobody answers; the process exits soon after
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut input = input;
+        let mut answer = String::new();
+        let _ = tx.send(input.read_line(&mut answer).map(|_| answer));
+    });
+
+    match rx.recv_timeout(timeout) {
+        Ok(answer) => Ok(matches!(
+            answer?.trim().to_lowercase().as_str(),
+            "y" | "yes"
+        )),
+        Err(_) => {
+            writeln!(output)?;
+            Ok(false)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::time::Duration;
 
+    #[test]
+    fn test_needs_confirmation_only_when_someone_can_answer() {
+        assert!(needs_confirmation(false, false, true));
+        // Scripts and pipes are not asked
+        assert!(!needs_confirmation(false, false, false));
+        // --yes answers for the user
+        assert!(!needs_confirmation(true, false, true));
+        assert!(!needs_confirmation(true, false, false));
+        // JSON output is for programs
+        assert!(!needs_confirmation(false, true, true));
+    }
+
+    #[test]
+    fn test_confirm_reads_answer() {
+        let ask = |answer: &'static str| {
+            let mut output = Vec::new();
+            let confirmed = confirm(
+                "Kill 2 processes belonging to 1 user?",
+                io::Cursor::new(answer.as_bytes()),
+                &mut output,
+                Duration::from_secs(5),
+            )
+            .unwrap();
+            (confirmed, String::from_utf8(output).unwrap())
+        };
+
+        let (confirmed, output) = ask("y\n");
+        assert!(confirmed);
+        assert_eq!(output, "Kill 2 processes belonging to 1 user? [y/N]: ");
+        assert!(ask(" YES \n").0);
+        assert!(!ask("n\n").0);
+        assert!(!ask("\n").0);
+        assert!(!ask("yep\n").0);
+        // End of input defaults to no
+        assert!(!ask("").0);
+    }
+
+    #[test]
+    fn test_confirm_times_out_to_no() {
+        struct Silent;
+        impl io::Read for Silent {
+            fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+                std::thread::sleep(Duration::from_secs(5));
+                Ok(0)
+            }
+        }
+
+        let mut output = Vec::new();
+        let confirmed = confirm(
+            "Reset 4 GPUs?",
+            io::BufReader::new(Silent),
+            &mut output,
+            Duration::from_millis(50),
+        )
+        .unwrap();
+        assert!(!confirmed);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Reset 4 GPUs? [y/N]: \n"
+        );
+    }
+
+    #[test]
+    fn test_bearer_token_matches() {
+        let headers = |value: &str| {
+            let mut headers = axum::http::HeaderMap::new();
+            headers.insert(axum::http::header::AUTHORIZATION, value.parse().unwrap());
+            headers
+        };
+        assert!(bearer_token_matches(&headers("Bearer s3cret"), "s3cret"));
+        assert!(!bearer_token_matches(&headers("Bearer s3cre"), "s3cret"));
+        assert!(!bearer_token_matches(&headers("Bearer s3cret2"), "s3cret"));
+        assert!(!bearer_token_matches(&headers("Basic s3cret"), "s3cret"));
+        assert!(!bearer_token_matches(
+            &axum::http::HeaderMap::new(),
+            "s3cret"
+        ));
+    }
+
     #[test]
     fn test_format_duration() {
         assert_eq!(format_duration(Duration::from_secs(30)), "30s");
@@ -141,6 +419,91 @@ mod tests {
         assert_eq!(format_duration(Duration::from_secs(3661)), "1h 1m 1s");
     }
 
+    #[test]
+    fn test_parse_duration_spec() {
+        assert_eq!(parse_duration_spec("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(
+            parse_duration_spec("30m").unwrap(),
+            Duration::from_secs(1800)
+        );
+        assert_eq!(
+            parse_duration_spec("1h30m").unwrap(),
+            Duration::from_secs(5400)
+        );
+        assert_eq!(
+            parse_duration_spec("1d").unwrap(),
+            Duration::from_secs(86400)
+        );
+        assert_eq!(
+            parse_duration_spec("45").unwrap(),
+            Duration::from_secs(2700)
+        );
+        assert!(parse_duration_spec("").is_err());
+        assert!(parse_duration_spec("30x").is_err());
+        assert!(parse_duration_spec("h").is_err());
+        assert!(parse_duration_spec("1h30").is_err());
+    }
+
+    #[test]
+    fn test_format_mib_boundaries() {
+        assert_eq!(format_mib(0), "0 MiB");
+        assert_eq!(format_mib(1), "1 MiB");
+        assert_eq!(format_mib(512), "512 MiB");
+        assert_eq!(format_mib(1023), "1023 MiB");
+        assert_eq!(format_mib(1024), "1.0 GiB");
+        assert_eq!(format_mib(1075), "1.0 GiB");
+        assert_eq!(format_mib(1536), "1.5 GiB");
+        assert_eq!(format_mib(81920), "80.0 GiB");
+        // The largest size still shown in GiB, and the first that would round to 1024.0
+        assert_eq!(format_mib(1024 * 1024 - 52), "1023.9 GiB");
+        assert_eq!(format_mib(1024 * 1024 - 51), "1.0 TiB");
+        assert_eq!(format_mib(1024 * 1024), "1.0 TiB");
+        assert_eq!(format_mib(3 * 1024 * 1024 / 2), "1.5 TiB");
+        assert_eq!(format_mib(u64::from(u32::MAX)), "4096.0 TiB");
+    }
+
+    #[test]
+    fn test_format_mib_pair() {
+        assert_eq!(format_mib_pair(2048, 8192), "2.0/8.0 GiB");
+        assert_eq!(format_mib_pair(0, 81920), "0 MiB/80.0 GiB");
+        assert_eq!(format_mib_pair(1023, 1024), "1023 MiB/1.0 GiB");
+        assert_eq!(format_mib_pair(100, 512), "100/512 MiB");
+        assert_eq!(format_mib_pair(0, 0), "0/0 MiB");
+    }
+
+    #[test]
+    fn test_format_age_boundaries() {
+        let age = |secs| format_age(Duration::from_secs(secs));
+        assert_eq!(format_age(Duration::from_millis(999)), "just now");
+        assert_eq!(age(1), "1s ago");
+        assert_eq!(age(59), "59s ago");
+        assert_eq!(age(60), "1m ago");
+        assert_eq!(age(61), "1m 1s ago");
+        assert_eq!(age(3599), "59m 59s ago");
+        assert_eq!(age(3600), "1h ago");
+        assert_eq!(age(2 * 3600 + 13 * 60 + 4), "2h 13m ago");
+        assert_eq!(age(86399), "23h 59m ago");
+        assert_eq!(age(86400), "1d ago");
+        assert_eq!(age(3 * 86400 + 4 * 3600 + 59), "3d 4h ago");
+    }
+
+    #[test]
+    fn test_format_start_time() {
+        let now = DateTime::parse_from_rfc3339("2026-10-16T12:04:30Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let started = now - chrono::Duration::seconds(2 * 3600 + 13 * 60);
+        assert_eq!(
+            format_start_time(started, now),
+            "2h 13m ago (2026-10-16 09:51)"
+        );
+        let future = now + chrono::Duration::seconds(5);
+        assert_eq!(
+            format_start_time(future, now),
+            "just now (2026-10-16 12:04)"
+        );
+    }
+
     #[test]
     fn test_format_memory_size() {
         assert_eq!(format_memory_size(0), "0 B");
@@ -156,6 +519,37 @@ mod tests {
         assert_eq!(format_memory_mb_to_gib(2048), "2.0");
     }
 
+    #[test]
+    fn test_parse_date_or_timestamp() {
+        assert_eq!(
+            parse_date_or_timestamp("2024-05-01").unwrap().to_rfc3339(),
+            "2024-05-01T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse_date_or_timestamp("2024-05-01T10:00:00+02:00")
+                .unwrap()
+                .to_rfc3339(),
+            "2024-05-01T08:00:00+00:00"
+        );
+        assert!(parse_date_or_timestamp("05/01/2024").is_err());
+    }
+
+    #[test]
+    fn test_csv_escape() {
+        assert_eq!(csv_escape("alice"), "alice");
+        assert_eq!(csv_escape("a,b"), "\"a,b\"");
+        assert_eq!(csv_escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn test_html_escape() {
+        assert_eq!(html_escape("alice"), "alice");
+        assert_eq!(
+            html_escape("<b class=\"x\">Tom & 'Jerry'</b>"),
+            "&lt;b class=&quot;x&quot;&gt;Tom &amp; &#39;Jerry&#39;&lt;/b&gt;"
+        );
+    }
+
     #[test]
     fn test_truncate_string() {
         assert_eq!(truncate_string("short", 10), "short");
@@ -1,19 +1,29 @@
-use crate::nvml_api::{GpuInfo, GpuProc, GpuSnapshot};
-use anyhow::Result;
+use crate::command::{run_with_timeout, tool_error, tool_limits, CommandRunner, ToolRunner};
+use crate::dcgm::ProfilingProvider;
+use crate::gpu_index::{device_id, DetectedGpu, GpuIndexMap, StableIndices};
+use crate::nvml_api::{
+    read_device_ids, read_device_nvlink_traffic, read_ecc_counts, read_pcie_throughput,
+    read_retired_pages, DeviceError, GpuInfo, GpuProc, GpuSnapshot, GpuStatusEntry, PowerLimits,
+};
+use crate::report::NotFound;
+use anyhow::{Context, Result};
 use nvml_wrapper::enums::device::UsedGpuMemory;
 use nvml_wrapper::struct_wrappers::device::ProcessInfo;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
-use std::time::{Duration, SystemTime};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{mpsc, Arc, Mutex, RwLock, RwLockReadGuard};
+use std::time::{Duration, Instant, SystemTime};
 use sysinfo::{Pid as SysPid, System, Users};
 
 /// GPU vendor types
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GpuVendor {
     Nvidia,
     Amd,
     Intel,
     Apple,
+    #[default]
     Unknown,
 }
 
@@ -29,6 +39,121 @@ impl std::fmt::Display for GpuVendor {
     }
 }
 
+impl GpuVendor {
+    /// How `--reset` resets a GPU of this vendor, as reported in JSON results
+    pub fn reset_mechanism(&self) -> &'static str {
+        match self {
+            GpuVendor::Nvidia => "nvml",
+            GpuVendor::Amd => "rocm-smi",
+            GpuVendor::Intel => "intel-gpu-tools",
+            GpuVendor::Apple => "iokit",
+            GpuVendor::Unknown => "unknown",
+        }
+    }
+
+    /// Library or tool GPUs of this vendor are queried through, named in device errors
+    pub fn query_source(&self) -> &'static str {
+        match self {
+            GpuVendor::Nvidia => "NVML",
+            GpuVendor::Amd => "rocm-smi",
+            GpuVendor::Intel => "intel_gpu_top",
+            GpuVendor::Apple => "system_profiler",
+            GpuVendor::Unknown => "unknown",
+        }
+    }
+
+    /// What gpukill can do with GPUs of this vendor
+    pub fn capabilities(&self) -> VendorCapabilities {
+        match self {
+            GpuVendor::Nvidia => VendorCapabilities::NVIDIA,
+            GpuVendor::Amd => VendorCapabilities::AMD,
+            GpuVendor::Intel => VendorCapabilities::INTEL,
+            GpuVendor::Apple => VendorCapabilities::APPLE,
+            GpuVendor::Unknown => VendorCapabilities::NONE,
+        }
+    }
+
+    /// Vendors shown in the `--capabilities` matrix
+    pub fn known() -> [GpuVendor; 4] {
+        [
+            GpuVendor::Nvidia,
+            GpuVendor::Amd,
+            GpuVendor::Intel,
+            GpuVendor::Apple,
+        ]
+    }
+}
+
+/// Operations a vendor backend supports, as shown by `gpukill --capabilities`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VendorCapabilities {
+    /// `--reset` can reset the device
+    pub reset_supported: bool,
+    /// Processes using the device can be listed (and therefore killed)
+    pub process_enum_supported: bool,
+    /// The backend exposes power-limit control
+    pub power_limit_supported: bool,
+    /// Utilization is reported rather than shown as 0%
+    pub util_supported: bool,
+}
+
+impl VendorCapabilities {
+    pub const NVIDIA: Self = Self {
+        reset_supported: true,
+        process_enum_supported: true,
+        power_limit_supported: true,
+        util_supported: true,
+    };
+    pub const AMD: Self = Self {
+        reset_supported: true,
+        process_enum_supported: false,
+        power_limit_supported: true,
+        util_supported: true,
+    };
+    pub const INTEL: Self = Self {
+        reset_supported: false,
+        process_enum_supported: false,
+        power_limit_supported: false,
+        util_supported: true,
+    };
+    pub const APPLE: Self = Self {
+        reset_supported: false,
+        process_enum_supported: true,
+        power_limit_supported: false,
+        util_supported: false,
+    };
+    pub const NONE: Self = Self {
+        reset_supported: false,
+        process_enum_supported: false,
+        power_limit_supported: false,
+        util_supported: false,
+    };
+}
+
+/// Error for an operation `vendor` cannot perform, pointing at the capability matrix
+pub fn unsupported_operation(vendor: GpuVendor, operation: &str) -> anyhow::Error {
+    anyhow::anyhow!(
+        "{} is not supported on {} GPUs. Run `gpukill --capabilities` to see what each vendor supports.",
+        operation,
+        vendor
+    )
+}
+
+/// Whether `error` means the device handle is gone: the GPU fell off the bus or
+/// the driver was restarted underneath us. Such calls are worth one retry after
+/// [`GpuVendorInterface::reinitialize`].
+pub fn is_device_lost(error: &anyhow::Error) -> bool {
+    let message = format!("{:#}", error);
+    [
+        "GpuLost",
+        "Uninitialized",
+        "GPU has been lost",
+        "NVML not initialized",
+    ]
+    .iter()
+    .any(|marker| message.contains(marker))
+}
+
 /// Trait for GPU vendor implementations
 pub trait GpuVendorInterface {
     /// Initialize the vendor interface
@@ -48,12 +173,49 @@ pub trait GpuVendorInterface {
     /// Get a snapshot of GPU state and processes
     fn get_gpu_snapshot(&self, index: u32) -> Result<GpuSnapshot>;
 
+    /// Get a snapshot without the optional metrics that are slow to read (ECC and
+    /// retired pages, PCIe and NVLink traffic, top process details). Backends without
+    /// such metrics return the full snapshot.
+    fn get_gpu_snapshot_fast(&self, index: u32) -> Result<GpuSnapshot> {
+        self.get_gpu_snapshot(index)
+    }
+
     /// Get all processes using a specific GPU
     fn get_gpu_processes(&self, index: u32) -> Result<Vec<GpuProc>>;
 
     /// Reset a specific GPU
     fn reset_gpu(&self, index: u32) -> Result<()>;
 
+    /// Set the board power limit in watts
+    fn set_power_limit(&self, _index: u32, _watts: u32) -> Result<()> {
+        Err(unsupported_operation(
+            self.vendor_type(),
+            "Setting the power limit",
+        ))
+    }
+
+    /// Lock the graphics clock to `mhz`
+    fn lock_clocks(&self, _index: u32, _mhz: u32) -> Result<()> {
+        Err(unsupported_operation(self.vendor_type(), "Locking clocks"))
+    }
+
+    /// Undo [`Self::lock_clocks`], letting the driver manage clocks again
+    fn reset_clocks(&self, _index: u32) -> Result<()> {
+        Err(unsupported_operation(
+            self.vendor_type(),
+            "Resetting clocks",
+        ))
+    }
+
+    /// Operations this vendor backend supports
+    fn capabilities(&self) -> VendorCapabilities;
+
+    /// Re-open the vendor library after its handles were invalidated. Backends that
+    /// run a tool per query have nothing to re-open.
+    fn reinitialize(&self) -> Result<()> {
+        Ok(())
+    }
+
     /// Check if the vendor is available on this system
     fn is_available() -> bool
     where
@@ -67,7 +229,158 @@ pub trait GpuVendorInterface {
 
 /// NVIDIA GPU vendor implementation
 pub struct NvidiaVendor {
-    nvml: nvml_wrapper::Nvml,
+    /// Replaced by [`GpuVendorInterface::reinitialize`] after a driver restart
+    nvml: RwLock<nvml_wrapper::Nvml>,
+}
+
+impl NvidiaVendor {
+    fn nvml(&self) -> RwLockReadGuard<'_, nvml_wrapper::Nvml> {
+        self.nvml.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Snapshot of one device. `fast` skips the ECC, retired page, PCIe and NVLink
+    /// reads and fills in the top process from /proc instead of a full process scan.
+    fn snapshot(&self, index: u32, fast: bool) -> Result<GpuSnapshot> {
+        let nvml = self.nvml();
+        let device = nvml
+            .device_by_index(index)
+            .map_err(|e| anyhow::anyhow!("Failed to get device at index {}: {:?}", index, e))?;
+
+        let name = device
+            .name()
+            .map_err(|e| anyhow::anyhow!("Failed to get device name: {:?}", e))?;
+
+        let mem_info = device
+            .memory_info()
+            .map_err(|e| anyhow::anyhow!("Failed to get memory info: {:?}", e))?;
+
+        let util = device
+            .utilization_rates()
+            .map_err(|e| anyhow::anyhow!("Failed to get utilization rates: {:?}", e))?;
+
+        let temp = device
+            .temperature(nvml_wrapper::enum_wrappers::device::TemperatureSensor::Gpu)
+            .map_err(|e| anyhow::anyhow!("Failed to get temperature: {:?}", e))?;
+
+        let power_usage = device
+            .power_usage()
+            .map_err(|e| anyhow::anyhow!("Failed to get power usage: {:?}", e))?;
+
+        let (ecc_volatile, ecc_aggregate, retired_pages, pcie_tx_kbps, pcie_rx_kbps, nvlink_bytes) =
+            if fast {
+                (None, None, None, None, None, None)
+            } else {
+                let (ecc_volatile, ecc_aggregate) = read_ecc_counts(|error_type, counter_type| {
+                    device.total_ecc_errors(error_type, counter_type)
+                });
+                let retired_pages = read_retired_pages(
+                    |cause| device.retired_pages(cause).map(|pages| pages.len()),
+                    || device.are_pages_pending_retired(),
+                );
+                let (pcie_tx_kbps, pcie_rx_kbps) =
+                    read_pcie_throughput(|counter| device.pcie_throughput(counter));
+                (
+                    ecc_volatile,
+                    ecc_aggregate,
+                    retired_pages,
+                    pcie_tx_kbps,
+                    pcie_rx_kbps,
+                    read_device_nvlink_traffic(&device),
+                )
+            };
+        let (uuid, serial) = read_device_ids(&device);
+        let power_limit = PowerLimits::read(
+            || device.power_management_limit(),
+            || device.power_management_limit_default(),
+        );
+
+        let compute_processes = device
+            .running_compute_processes()
+            .map_err(|e| anyhow::anyhow!("Failed to get running compute processes: {:?}", e))?;
+        let graphics_processes = match device.running_graphics_processes() {
+            Ok(processes) => processes,
+            Err(nvml_wrapper::error::NvmlError::NotSupported) => Vec::new(),
+            Err(e) => {
+                return Err(anyhow::anyhow!(
+                    "Failed to get running graphics processes: {:?}",
+                    e
+                ))
+            }
+        };
+        let processes = merge_nvml_processes(compute_processes, graphics_processes);
+
+        let pids: Vec<u32> = processes.iter().map(|p| p.pid).collect();
+        let top_proc = processes
+            .iter()
+            .max_by_key(|p| used_gpu_memory_bytes(p))
+            .map(|p| {
+                let mut proc = GpuProc {
+                    gpu_index: index as u16,
+                    pid: p.pid,
+                    user: "unknown".to_string(),
+                    proc_name: "unknown".to_string(),
+                    used_mem_mb: used_gpu_memory_mb(p),
+                    start_time: "unknown".to_string(),
+                    container: None,
+                    node_id: None,
+                    cmdline: None,
+                    container_pid: None,
+                    slurm_job_id: None,
+                    start_unix_secs: None,
+                };
+                if fast {
+                    if let Some(user) = crate::proc::get_process_owner(proc.pid) {
+                        proc.user = user;
+                    }
+                    if let Some(name) = crate::proc::get_process_name(proc.pid) {
+                        proc.proc_name = name;
+                    }
+                } else {
+                    enrich_gpu_proc(&mut proc);
+                }
+                proc
+            });
+
+        Ok(GpuSnapshot {
+            gpu_index: index as u16,
+            name,
+            uuid,
+            serial,
+            vendor: GpuVendor::Nvidia,
+            mem_used_mb: (mem_info.used / 1024 / 1024) as u32,
+            mem_total_mb: (mem_info.total / 1024 / 1024) as u32,
+            util_pct: util.gpu as f32,
+            temp_c: temp as i32,
+            power_w: power_usage as f32 / 1000.0,
+            ecc_volatile,
+            ecc_aggregate,
+            retired_pages,
+            rma_flagged: retired_pages.map(|pages| pages.rma_flagged()),
+            pcie_tx_kbps,
+            pcie_rx_kbps,
+            nvlink_bytes,
+            power_limit,
+            pids: pids.len(),
+            top_proc,
+            profiling: None,
+        })
+    }
+}
+
+/// Error for a failed NVML power or clock change, spelling out the common causes
+fn nvml_control_error(operation: &str, error: nvml_wrapper::error::NvmlError) -> anyhow::Error {
+    use nvml_wrapper::error::NvmlError;
+    match error {
+        NvmlError::NoPermission => anyhow::anyhow!(
+            "Permission denied: {} requires root (or CAP_SYS_ADMIN); re-run with sudo",
+            operation.to_lowercase()
+        ),
+        NvmlError::NotSupported => anyhow::anyhow!(
+            "{} is not supported by this GPU or driver (GeForce boards often lock it down)",
+            operation
+        ),
+        e => anyhow::anyhow!("{} failed: {:?}", operation, e),
+    }
 }
 
 fn merge_nvml_processes(
@@ -102,11 +415,13 @@ fn enrich_gpu_proc(proc: &mut GpuProc) {
     system.refresh_processes();
     let users = Users::new_with_refreshed_list();
 
+    proc.cmdline = crate::proc::get_process_cmdline(proc.pid);
     let sys_pid = SysPid::from_u32(proc.pid);
     if let Some(process) = system.process(sys_pid) {
         proc.proc_name = process.name().to_string();
         let start_time = SystemTime::UNIX_EPOCH + Duration::from_secs(process.start_time());
         proc.start_time = crate::util::parse_process_start_time(start_time);
+        proc.start_unix_secs = Some(process.start_time());
         if let Some(user_id) = process.user_id() {
             if let Some(user) = users.get_user_by_id(user_id) {
                 proc.user = user.name().to_string();
@@ -119,7 +434,9 @@ impl GpuVendorInterface for NvidiaVendor {
     fn initialize() -> Result<Self> {
         let nvml = nvml_wrapper::Nvml::init()
             .map_err(|e| anyhow::anyhow!("Failed to initialize NVML: {:?}", e))?;
-        Ok(Self { nvml })
+        Ok(Self {
+            nvml: RwLock::new(nvml),
+        })
     }
 
     fn vendor_type(&self) -> GpuVendor {
@@ -127,14 +444,14 @@ impl GpuVendorInterface for NvidiaVendor {
     }
 
     fn device_count(&self) -> Result<u32> {
-        self.nvml
+        self.nvml()
             .device_count()
             .map_err(|e| anyhow::anyhow!("Failed to get device count: {:?}", e))
     }
 
     fn get_gpu_info(&self, index: u32) -> Result<GpuInfo> {
-        let device = self
-            .nvml
+        let nvml = self.nvml();
+        let device = nvml
             .device_by_index(index)
             .map_err(|e| anyhow::anyhow!("Failed to get device at index {}: {:?}", index, e))?;
 
@@ -145,92 +462,29 @@ impl GpuVendorInterface for NvidiaVendor {
         let mem_info = device
             .memory_info()
             .map_err(|e| anyhow::anyhow!("Failed to get memory info: {:?}", e))?;
+        let (uuid, serial) = read_device_ids(&device);
 
         Ok(GpuInfo {
             index: index as u16,
             name,
             mem_total_mb: (mem_info.total / 1024 / 1024) as u32,
+            uuid,
+            serial,
+            pci_bus_id: device.pci_info().ok().map(|pci| pci.bus_id),
         })
     }
 
     fn get_gpu_snapshot(&self, index: u32) -> Result<GpuSnapshot> {
-        let device = self
-            .nvml
-            .device_by_index(index)
-            .map_err(|e| anyhow::anyhow!("Failed to get device at index {}: {:?}", index, e))?;
-
-        let name = device
-            .name()
-            .map_err(|e| anyhow::anyhow!("Failed to get device name: {:?}", e))?;
-
-        let mem_info = device
-            .memory_info()
-            .map_err(|e| anyhow::anyhow!("Failed to get memory info: {:?}", e))?;
-
-        let util = device
-            .utilization_rates()
-            .map_err(|e| anyhow::anyhow!("Failed to get utilization rates: {:?}", e))?;
-
-        let temp = device
-            .temperature(nvml_wrapper::enum_wrappers::device::TemperatureSensor::Gpu)
-            .map_err(|e| anyhow::anyhow!("Failed to get temperature: {:?}", e))?;
-
-        let power_usage = device
-            .power_usage()
-            .map_err(|e| anyhow::anyhow!("Failed to get power usage: {:?}", e))?;
-
-        let compute_processes = device
-            .running_compute_processes()
-            .map_err(|e| anyhow::anyhow!("Failed to get running compute processes: {:?}", e))?;
-        let graphics_processes = match device.running_graphics_processes() {
-            Ok(processes) => processes,
-            Err(nvml_wrapper::error::NvmlError::NotSupported) => Vec::new(),
-            Err(e) => {
-                return Err(anyhow::anyhow!(
-                    "Failed to get running graphics processes: {:?}",
-                    e
-                ))
-            }
-        };
-        let processes = merge_nvml_processes(compute_processes, graphics_processes);
-
-        let pids: Vec<u32> = processes.iter().map(|p| p.pid).collect();
-        let top_proc = processes
-            .iter()
-            .max_by_key(|p| used_gpu_memory_bytes(p))
-            .map(|p| {
-                let mut proc = GpuProc {
-                    gpu_index: index as u16,
-                    pid: p.pid,
-                    user: "unknown".to_string(),
-                    proc_name: "unknown".to_string(),
-                    used_mem_mb: used_gpu_memory_mb(p),
-                    start_time: "unknown".to_string(),
-                    container: None,
-                    node_id: None,
-                };
-                enrich_gpu_proc(&mut proc);
-                proc
-            });
+        self.snapshot(index, false)
+    }
 
-        Ok(GpuSnapshot {
-            gpu_index: index as u16,
-            name,
-            vendor: GpuVendor::Nvidia,
-            mem_used_mb: (mem_info.used / 1024 / 1024) as u32,
-            mem_total_mb: (mem_info.total / 1024 / 1024) as u32,
-            util_pct: util.gpu as f32,
-            temp_c: temp as i32,
-            power_w: power_usage as f32 / 1000.0,
-            ecc_volatile: None,
-            pids: pids.len(),
-            top_proc,
-        })
+    fn get_gpu_snapshot_fast(&self, index: u32) -> Result<GpuSnapshot> {
+        self.snapshot(index, true)
     }
 
     fn get_gpu_processes(&self, index: u32) -> Result<Vec<GpuProc>> {
-        let device = self
-            .nvml
+        let nvml = self.nvml();
+        let device = nvml
             .device_by_index(index)
             .map_err(|e| anyhow::anyhow!("Failed to get device at index {}: {:?}", index, e))?;
 
@@ -254,8 +508,10 @@ impl GpuVendorInterface for NvidiaVendor {
             gpu_procs.push(GpuProc {
                 gpu_index: index as u16,
                 pid: p.pid,
-                user: "unknown".to_string(),
-                proc_name: "unknown".to_string(),
+                user: crate::proc::get_process_owner(p.pid)
+                    .unwrap_or_else(|| "unknown".to_string()),
+                proc_name: crate::proc::get_process_name(p.pid)
+                    .unwrap_or_else(|| "unknown".to_string()),
                 used_mem_mb: match p.used_gpu_memory {
                     UsedGpuMemory::Used(bytes) => (bytes / 1024 / 1024) as u32,
                     UsedGpuMemory::Unavailable => 0,
@@ -263,6 +519,10 @@ impl GpuVendorInterface for NvidiaVendor {
                 start_time: "unknown".to_string(),
                 container: None,
                 node_id: None,
+                cmdline: crate::proc::get_process_cmdline(p.pid),
+                container_pid: None,
+                slurm_job_id: None,
+                start_unix_secs: None,
             });
         }
 
@@ -271,7 +531,7 @@ impl GpuVendorInterface for NvidiaVendor {
 
     fn reset_gpu(&self, index: u32) -> Result<()> {
         let _device = self
-            .nvml
+            .nvml()
             .device_by_index(index)
             .map_err(|e| anyhow::anyhow!("Failed to get device at index {}: {:?}", index, e))?;
 
@@ -280,6 +540,67 @@ impl GpuVendorInterface for NvidiaVendor {
         Err(anyhow::anyhow!("GPU reset not supported via NVML"))
     }
 
+    fn set_power_limit(&self, index: u32, watts: u32) -> Result<()> {
+        let nvml = self.nvml();
+        let mut device = nvml
+            .device_by_index(index)
+            .map_err(|e| anyhow::anyhow!("Failed to get device at index {}: {:?}", index, e))?;
+        // Check the range up front: NVML only answers InvalidArg
+        if let Ok(constraints) = device.power_management_limit_constraints() {
+            let (min_w, max_w) = (constraints.min_limit / 1000, constraints.max_limit / 1000);
+            if !(min_w..=max_w).contains(&watts) {
+                return Err(anyhow::anyhow!(
+                    "Power limit {} W is outside the range GPU {} supports ({}-{} W)",
+                    watts,
+                    index,
+                    min_w,
+                    max_w
+                ));
+            }
+        }
+        device
+            .set_power_management_limit(watts.saturating_mul(1000))
+            .map_err(|e| nvml_control_error("Setting the power limit", e))
+    }
+
+    fn lock_clocks(&self, index: u32, mhz: u32) -> Result<()> {
+        let nvml = self.nvml();
+        let mut device = nvml
+            .device_by_index(index)
+            .map_err(|e| anyhow::anyhow!("Failed to get device at index {}: {:?}", index, e))?;
+        device
+            .set_gpu_locked_clocks(
+                nvml_wrapper::enums::device::GpuLockedClocksSetting::Numeric {
+                    min_clock_mhz: mhz,
+                    max_clock_mhz: mhz,
+                },
+            )
+            .map_err(|e| nvml_control_error("Locking clocks", e))
+    }
+
+    fn reset_clocks(&self, index: u32) -> Result<()> {
+        let nvml = self.nvml();
+        let mut device = nvml
+            .device_by_index(index)
+            .map_err(|e| anyhow::anyhow!("Failed to get device at index {}: {:?}", index, e))?;
+        device
+            .reset_gpu_locked_clocks()
+            .map_err(|e| nvml_control_error("Resetting clocks", e))
+    }
+
+    fn capabilities(&self) -> VendorCapabilities {
+        VendorCapabilities::NVIDIA
+    }
+
+    fn reinitialize(&self) -> Result<()> {
+        // Open the new handle before the old one is dropped, so NVML stays loaded
+        let nvml = nvml_wrapper::Nvml::init()
+            .map_err(|e| anyhow::anyhow!("Failed to re-initialize NVML: {:?}", e))?;
+        *self.nvml.write().unwrap_or_else(|e| e.into_inner()) = nvml;
+        tracing::info!("Re-initialized NVML after losing the device handle");
+        Ok(())
+    }
+
     fn is_available() -> bool {
         nvml_wrapper::Nvml::init().is_ok()
     }
@@ -290,348 +611,403 @@ impl GpuVendorInterface for NvidiaVendor {
     }
 }
 
-/// AMD GPU vendor implementation using rocm-smi
-pub struct AmdVendor {
-    // We'll use rocm-smi command-line tool for now
-    // In the future, this could use direct kernel interfaces
+/// How long vendor tool output is reused, so the snapshot and process queries of one
+/// refresh share a single `rocm-smi`/`intel_gpu_top` run
+pub const DEFAULT_VENDOR_CACHE_TTL: Duration = Duration::from_secs(1);
+
+/// Output of the most recent vendor tool run, reused until it is older than `ttl`
+struct TtlCache<T> {
+    ttl: Duration,
+    entry: Mutex<Option<(Instant, Arc<T>)>>,
 }
 
-impl GpuVendorInterface for AmdVendor {
-    fn initialize() -> Result<Self> {
-        // Check if AMD GPU is available (either via rocm-smi or lspci/sysfs)
-        if !Self::is_available() {
-            return Err(anyhow::anyhow!("{}", Self::get_availability_error()));
+impl<T> TtlCache<T> {
+    fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entry: Mutex::new(None),
         }
-        Ok(Self {})
     }
 
-    fn vendor_type(&self) -> GpuVendor {
-        GpuVendor::Amd
+    /// Return the cached value or run `fetch`. The lock is held while fetching so
+    /// concurrent snapshot workers wait for one tool run instead of starting their own.
+    fn get_or_fetch(&self, fetch: impl FnOnce() -> Result<T>) -> Result<Arc<T>> {
+        let mut entry = self.entry.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((fetched, value)) = entry.as_ref() {
+            if fetched.elapsed() < self.ttl {
+                return Ok(Arc::clone(value));
+            }
+        }
+        let value = Arc::new(fetch()?);
+        *entry = Some((Instant::now(), Arc::clone(&value)));
+        Ok(value)
     }
 
-    fn device_count(&self) -> Result<u32> {
-        // Try rocm-smi first (most accurate)
-        let rocm_result = std::process::Command::new("rocm-smi")
-            .args(["--showid"])
-            .output();
-
-        if let Ok(output) = rocm_result {
-            if output.status.success() {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-
-                // Parse the output to count actual devices
-                // For MI300X VF, we need to filter out virtual functions
-                // rocm-smi --showid outputs device IDs, one per line
-                let device_count = stdout
-                    .lines()
-                    .filter(|line| {
-                        let line = line.trim();
-                        !line.is_empty() && !line.starts_with("GPU") && !line.starts_with("#")
-                        // Skip comments
-                    })
-                    .count();
-
-                // For MI300X VF, if we detect multiple devices but they're all virtual functions,
-                // we should only count the physical device
-                if device_count > 1 {
-                    // Try to get more detailed info to distinguish physical vs virtual
-                    let detailed_output = std::process::Command::new("rocm-smi")
-                        .args(["--showproductname"])
-                        .output();
-
-                    if let Ok(detailed) = detailed_output {
-                        if detailed.status.success() {
-                            let detailed_stdout = String::from_utf8_lossy(&detailed.stdout);
-                            // If we see "MI300X VF" in the output, it's likely virtual functions
-                            if detailed_stdout.contains("MI300X VF") {
-                                return Ok(1); // Only count the physical device
-                            }
-                        }
-                    }
-                }
+    /// Drop the cached value so the next call fetches again
+    fn invalidate(&self) {
+        *self.entry.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
 
-                return Ok(device_count as u32);
-            }
-        }
+/// `rocm-smi` arguments that read every device in one run
+pub const ROCM_SMI_QUERY_ARGS: [&str; 10] = [
+    "--showuse",
+    "--showtemp",
+    "--showpower",
+    "--showmeminfo",
+    "vram",
+    "--showproductname",
+    "--showuniqueid",
+    "--showserial",
+    "--showbus",
+    "--json",
+];
+
+/// Readings for one AMD device
+#[derive(Debug, Clone, PartialEq)]
+pub struct AmdDeviceReading {
+    pub name: String,
+    /// `Unique ID` from `--showuniqueid`
+    pub uuid: Option<String>,
+    /// `Serial Number` from `--showserial`
+    pub serial: Option<String>,
+    /// `PCI Bus` from `--showbus`
+    pub pci_bus_id: Option<String>,
+    pub mem_total_mb: u32,
+    pub mem_used_mb: u32,
+    pub util_pct: f32,
+    pub temp_c: i32,
+    pub power_w: f32,
+}
 
-        // Fallback: Count AMD GPUs via lspci
-        #[cfg(target_os = "linux")]
-        {
-            let lspci_result = std::process::Command::new("lspci").output();
-            if let Ok(output) = lspci_result {
-                if output.status.success() {
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-                    let count = stdout
-                        .lines()
-                        .filter(|line| {
-                            let lower = line.to_lowercase();
-                            lower.contains("amd")
-                                && (lower.contains("vga")
-                                    || lower.contains("display")
-                                    || lower.contains("3d"))
-                        })
-                        .count();
-                    if count > 0 {
-                        return Ok(count as u32);
-                    }
-                }
-            }
+impl AmdDeviceReading {
+    fn placeholder(index: usize, name: Option<String>, mem_total_mb: u32) -> Self {
+        Self {
+            name: name.unwrap_or_else(|| format!("AMD GPU {}", index)),
+            uuid: None,
+            serial: None,
+            pci_bus_id: None,
+            mem_total_mb,
+            mem_used_mb: 0,
+            util_pct: 0.0,
+            temp_c: 0,
+            power_w: 0.0,
         }
-
-        // If all else fails, return 1 (we detected AMD is available in is_available())
-        Ok(1)
     }
+}
 
-    fn get_gpu_info(&self, index: u32) -> Result<GpuInfo> {
-        // Try rocm-smi first
-        let rocm_result = std::process::Command::new("rocm-smi")
-            .args(["--showproductname", "-d", &index.to_string()])
-            .output();
-
-        let (name, mem_total_mb) = if let Ok(output) = rocm_result {
-            if output.status.success() {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                let gpu_name = stdout
-                    .lines()
-                    .find(|line| line.contains("Card series"))
-                    .and_then(|line| line.split(':').nth(1))
-                    .map(|s| s.trim().to_string())
-                    .unwrap_or_else(|| format!("AMD GPU {}", index));
-
-                // Get memory info
-                let mem_output = std::process::Command::new("rocm-smi")
-                    .args(["--showmeminfo", "vram", "-d", &index.to_string()])
-                    .output();
-
-                let mem = if let Ok(mem_output) = mem_output {
-                    if mem_output.status.success() {
-                        let mem_stdout = String::from_utf8_lossy(&mem_output.stdout);
-                        mem_stdout
-                            .lines()
-                            .find(|line| line.contains("Total"))
-                            .and_then(|line| {
-                                line.split_whitespace()
-                                    .find(|s| s.ends_with("MB"))
-                                    .and_then(|s| s.replace("MB", "").parse::<u32>().ok())
-                            })
-                            .unwrap_or(8192)
-                    } else {
-                        8192
-                    }
-                } else {
-                    8192
-                };
-
-                (gpu_name, mem)
-            } else {
-                (format!("AMD GPU {}", index), 8192)
-            }
-        } else {
-            // Fallback: Use lspci to get GPU name
-            #[cfg(target_os = "linux")]
-            {
-                let lspci_result = std::process::Command::new("lspci").output();
-                if let Ok(output) = lspci_result {
-                    if output.status.success() {
-                        let stdout = String::from_utf8_lossy(&output.stdout);
-                        let amd_gpus: Vec<&str> = stdout
-                            .lines()
-                            .filter(|line| {
-                                let lower = line.to_lowercase();
-                                lower.contains("amd")
-                                    && (lower.contains("vga")
-                                        || lower.contains("display")
-                                        || lower.contains("3d"))
-                            })
-                            .collect();
-
-                        if let Some(gpu_line) = amd_gpus.get(index as usize) {
-                            // Extract GPU name from lspci output
-                            // Format: "00:00.0 VGA compatible controller: AMD/ATI [...]"
-                            let gpu_name = gpu_line
-                                .split(':')
-                                .skip(2)
-                                .collect::<Vec<_>>()
-                                .join(":")
-                                .trim()
-                                .to_string();
-                            return Ok(GpuInfo {
-                                index: index as u16,
-                                name: if gpu_name.is_empty() {
-                                    format!("AMD GPU {}", index)
-                                } else {
-                                    gpu_name
-                                },
-                                mem_total_mb: 4096, // Default for integrated GPUs
-                            });
+/// Parse `rocm-smi ... --json` output into readings ordered by card number.
+///
+/// Field names differ between ROCm releases, so keys are matched by prefix.
+pub fn parse_rocm_smi_json(stdout: &str) -> Result<Vec<AmdDeviceReading>> {
+    // Some releases print warnings before the JSON document
+    let start = stdout
+        .find('{')
+        .ok_or_else(|| anyhow::anyhow!("rocm-smi returned no JSON"))?;
+    let document: serde_json::Value = serde_json::from_str(&stdout[start..])
+        .map_err(|e| anyhow::anyhow!("Failed to parse rocm-smi JSON: {}", e))?;
+    let cards = document
+        .as_object()
+        .ok_or_else(|| anyhow::anyhow!("Unexpected rocm-smi JSON layout"))?;
+
+    let mut devices: Vec<(u32, AmdDeviceReading)> = cards
+        .iter()
+        .filter_map(|(key, fields)| {
+            let card: u32 = key.strip_prefix("card")?.parse().ok()?;
+            let fields = fields.as_object()?;
+            let value = |prefixes: &[&str]| -> Option<f64> {
+                prefixes.iter().find_map(|prefix| {
+                    fields.iter().find_map(|(name, value)| {
+                        if !name.to_lowercase().starts_with(prefix) {
+                            return None;
                         }
-                    }
-                }
-            }
+                        match value {
+                            serde_json::Value::Number(n) => n.as_f64(),
+                            serde_json::Value::String(s) => s.trim().parse().ok(),
+                            _ => None,
+                        }
+                    })
+                })
+            };
+            let text = |prefixes: &[&str]| -> Option<String> {
+                prefixes.iter().find_map(|prefix| {
+                    fields.iter().find_map(|(name, value)| {
+                        let value = value.as_str()?.trim();
+                        (name.to_lowercase().starts_with(prefix) && !value.is_empty())
+                            .then(|| value.to_string())
+                    })
+                })
+            };
+            let mb = |bytes: f64| (bytes / 1024.0 / 1024.0) as u32;
+
+            Some((
+                card,
+                AmdDeviceReading {
+                    name: text(&["card series", "card model"])
+                        .unwrap_or_else(|| format!("AMD GPU {}", card)),
+                    uuid: text(&["unique id"]).filter(|id| !is_rocm_na(id)),
+                    serial: text(&["serial number"]).filter(|id| !is_rocm_na(id)),
+                    pci_bus_id: text(&["pci bus"]).filter(|id| !is_rocm_na(id)),
+                    mem_total_mb: value(&["vram total memory"]).map(mb).unwrap_or(8192),
+                    mem_used_mb: value(&["vram total used memory"]).map(mb).unwrap_or(0),
+                    util_pct: value(&["gpu use"]).unwrap_or(0.0) as f32,
+                    temp_c: value(&[
+                        "temperature (sensor edge)",
+                        "temperature (sensor junction)",
+                        "temperature",
+                    ])
+                    .unwrap_or(0.0)
+                    .round() as i32,
+                    power_w: value(&[
+                        "average graphics package power",
+                        "current socket graphics package power",
+                    ])
+                    .unwrap_or(0.0) as f32,
+                },
+            ))
+        })
+        .collect();
 
-            (format!("AMD GPU {}", index), 4096)
-        };
+    devices.sort_by_key(|(card, _)| *card);
+    Ok(devices.into_iter().map(|(_, reading)| reading).collect())
+}
 
-        Ok(GpuInfo {
-            index: index as u16,
-            name,
-            mem_total_mb,
+/// rocm-smi prints "N/A" for identifiers a device does not expose
+fn is_rocm_na(value: &str) -> bool {
+    value.eq_ignore_ascii_case("n/a")
+}
+
+/// Names of AMD display controllers in `lspci` output
+fn parse_lspci_amd(stdout: &str) -> Vec<String> {
+    stdout
+        .lines()
+        .filter(|line| {
+            let lower = line.to_lowercase();
+            lower.contains("amd")
+                && (lower.contains("vga") || lower.contains("display") || lower.contains("3d"))
         })
-    }
+        .map(|line| {
+            // Format: "00:00.0 VGA compatible controller: AMD/ATI [...]"
+            line.split(':')
+                .skip(2)
+                .collect::<Vec<_>>()
+                .join(":")
+                .trim()
+                .to_string()
+        })
+        .collect()
+}
 
-    fn get_gpu_snapshot(&self, index: u32) -> Result<GpuSnapshot> {
-        // Get basic info first
-        let gpu_info = self.get_gpu_info(index)?;
+/// Device count of a vendor whose tools can hang. While its tool is hung or skipped
+/// the last count read stands in, so the devices are listed with their error instead
+/// of the whole snapshot failing.
+struct DeviceCount(AtomicU32);
 
-        // Get utilization (don't fail if rocm-smi is unavailable)
-        let util_output = std::process::Command::new("rocm-smi")
-            .args(["--showuse", "-d", &index.to_string()])
-            .output();
-
-        let util_pct = if let Ok(output) = util_output {
-            if output.status.success() {
-                let util_stdout = String::from_utf8_lossy(&output.stdout);
-                util_stdout
-                    .lines()
-                    .find(|line| line.contains("GPU use") || line.contains("GFX-Uti"))
-                    .and_then(|line| {
-                        // Try different patterns for ROCm 7.0.0
-                        line.split_whitespace()
-                            .find(|s| s.ends_with("%"))
-                            .and_then(|s| s.replace("%", "").parse::<f32>().ok())
-                            .or_else(|| {
-                                // Alternative parsing for different output formats
-                                line.split_whitespace()
-                                    .find(|s| s.chars().all(|c| c.is_numeric() || c == '.'))
-                                    .and_then(|s| s.parse::<f32>().ok())
-                            })
-                    })
-                    .unwrap_or(0.0)
-            } else {
-                0.0
-            }
-        } else {
-            0.0
-        };
+impl Default for DeviceCount {
+    /// A vendor is only set up once it was detected, so there is at least one device
+    fn default() -> Self {
+        Self(AtomicU32::new(1))
+    }
+}
 
-        // Get temperature (don't fail if rocm-smi is unavailable)
-        let temp_output = std::process::Command::new("rocm-smi")
-            .args(["--showtemp", "-d", &index.to_string()])
-            .output();
-
-        let temp_c = if let Ok(output) = temp_output {
-            if output.status.success() {
-                let temp_stdout = String::from_utf8_lossy(&output.stdout);
-                temp_stdout
-                    .lines()
-                    .find(|line| line.contains("Temperature") || line.contains("Temp"))
-                    .and_then(|line| {
-                        // Try different patterns for ROCm 7.0.0
-                        line.split_whitespace()
-                            .find(|s| s.ends_with("C") || s.ends_with("°C"))
-                            .and_then(|s| s.replace("C", "").replace("°", "").parse::<i32>().ok())
-                            .or_else(|| {
-                                // Alternative parsing for different output formats
-                                line.split_whitespace()
-                                    .find(|s| s.chars().all(|c| c.is_numeric()))
-                                    .and_then(|s| s.parse::<i32>().ok())
-                            })
-                    })
-                    .unwrap_or(0)
-            } else {
-                0
+impl DeviceCount {
+    fn update(&self, counted: Result<u32>) -> Result<u32> {
+        match counted {
+            Ok(count) => {
+                self.0.store(count, Ordering::Relaxed);
+                Ok(count)
             }
-        } else {
-            0
-        };
+            Err(e) if tool_error(&e).is_some() => {
+                tracing::debug!("Keeping the last device count: {:#}", e);
+                Ok(self.0.load(Ordering::Relaxed))
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
 
-        // Get power usage (don't fail if rocm-smi is unavailable)
-        let power_output = std::process::Command::new("rocm-smi")
-            .args(["--showpower", "-d", &index.to_string()])
-            .output();
-
-        let power_w = if let Ok(output) = power_output {
-            if output.status.success() {
-                let power_stdout = String::from_utf8_lossy(&output.stdout);
-                power_stdout
-                    .lines()
-                    .find(|line| {
-                        line.contains("Average Graphics Package Power")
-                            || line.contains("Power-Usage")
-                    })
-                    .and_then(|line| {
-                        // Try different patterns for ROCm 7.0.0
-                        line.split_whitespace()
-                            .find(|s| s.ends_with("W"))
-                            .and_then(|s| s.replace("W", "").parse::<f32>().ok())
-                            .or_else(|| {
-                                // Alternative parsing for "134/750 W" format
-                                line.split_whitespace()
-                                    .find(|s| s.contains("/") && s.ends_with("W"))
-                                    .and_then(|s| {
-                                        s.split("/")
-                                            .next()
-                                            .and_then(|part| part.parse::<f32>().ok())
-                                    })
-                            })
-                    })
-                    .unwrap_or(0.0)
-            } else {
-                0.0
+/// AMD GPU vendor implementation using rocm-smi
+pub struct AmdVendor {
+    runner: Arc<dyn CommandRunner + Send + Sync>,
+    readings: TtlCache<Vec<AmdDeviceReading>>,
+    devices: DeviceCount,
+}
+
+impl AmdVendor {
+    /// Build a vendor that runs its tools through `runner`
+    pub fn with_runner(runner: Arc<dyn CommandRunner + Send + Sync>) -> Self {
+        Self {
+            runner,
+            readings: TtlCache::new(DEFAULT_VENDOR_CACHE_TTL),
+            devices: DeviceCount::default(),
+        }
+    }
+
+    /// Set how long one `rocm-smi` run is reused
+    #[allow(dead_code)]
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.readings = TtlCache::new(ttl);
+        self
+    }
+
+    /// Run a `rocm-smi` power or clock change. `--autorespond` accepts the overdrive
+    /// warning rocm-smi would otherwise wait on.
+    fn run_control(&self, operation: &str, args: &[&str]) -> Result<()> {
+        let mut args = args.to_vec();
+        args.extend(["--autorespond", "y"]);
+        let output = self.runner.run_control("rocm-smi", &args)?;
+        self.readings.invalidate();
+        if output.success {
+            return Ok(());
+        }
+        let message = format!("{} {}", output.stderr.trim(), output.stdout.trim());
+        let lower = message.to_lowercase();
+        if lower.contains("permission") || lower.contains("root") {
+            return Err(anyhow::anyhow!(
+                "Permission denied: {} requires root; re-run with sudo ({})",
+                operation.to_lowercase(),
+                message.trim()
+            ));
+        }
+        if lower.contains("not supported") || lower.contains("unrecognized") {
+            return Err(anyhow::anyhow!(
+                "{} is not supported by this GPU or rocm-smi version: {}",
+                operation,
+                message.trim()
+            ));
+        }
+        Err(anyhow::anyhow!("rocm-smi failed: {}", message.trim()))
+    }
+
+    /// Readings for every device, from the cache or one `rocm-smi` run
+    fn readings(&self) -> Result<Arc<Vec<AmdDeviceReading>>> {
+        self.readings.get_or_fetch(|| self.query_devices())
+    }
+
+    fn query_devices(&self) -> Result<Vec<AmdDeviceReading>> {
+        let started = Instant::now();
+        let mut devices = match self.runner.run("rocm-smi", &ROCM_SMI_QUERY_ARGS) {
+            Ok(output) if output.success => match parse_rocm_smi_json(&output.stdout) {
+                Ok(devices) => devices,
+                Err(e) => {
+                    tracing::warn!("{}", e);
+                    Vec::new()
+                }
+            },
+            Ok(output) => {
+                tracing::debug!("rocm-smi failed: {}", output.stderr.trim());
+                Vec::new()
+            }
+            // A hung or skipped rocm-smi is reported rather than papered over by lspci
+            Err(e) if tool_error(&e).is_some() => return Err(e),
+            Err(e) => {
+                tracing::debug!("{}", e);
+                Vec::new()
             }
-        } else {
-            0.0
         };
+        tracing::debug!(
+            devices = devices.len(),
+            duration_ms = started.elapsed().as_millis() as u64,
+            "Queried rocm-smi"
+        );
+
+        // MI300X virtual functions show up as several devices; only the physical one counts
+        if devices.len() > 1 && devices.iter().any(|d| d.name.contains("MI300X VF")) {
+            devices.truncate(1);
+        }
 
-        // Get memory usage (don't fail if rocm-smi is unavailable)
-        let mem_output = std::process::Command::new("rocm-smi")
-            .args(["--showmemuse", "-d", &index.to_string()])
-            .output();
-
-        let mem_used_mb = if let Ok(output) = mem_output {
-            if output.status.success() {
-                let mem_stdout = String::from_utf8_lossy(&output.stdout);
-                mem_stdout
-                    .lines()
-                    .find(|line| line.contains("GPU memory use") || line.contains("Mem-Usage"))
-                    .and_then(|line| {
-                        // Try different patterns for ROCm 7.0.0
-                        line.split_whitespace()
-                            .find(|s| s.ends_with("MB"))
-                            .and_then(|s| s.replace("MB", "").parse::<u32>().ok())
-                            .or_else(|| {
-                                // Alternative parsing for "285/196288 MB" format
-                                line.split_whitespace()
-                                    .find(|s| s.contains("/") && s.ends_with("MB"))
-                                    .and_then(|s| {
-                                        s.split("/")
-                                            .next()
-                                            .and_then(|part| part.parse::<u32>().ok())
-                                    })
-                            })
-                    })
-                    .unwrap_or(0)
-            } else {
-                0
+        // Without rocm-smi, fall back to names from lspci (integrated/consumer GPUs)
+        #[cfg(target_os = "linux")]
+        if devices.is_empty() {
+            if let Ok(output) = self.runner.run("lspci", &[]) {
+                if output.success {
+                    devices = parse_lspci_amd(&output.stdout)
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, name)| {
+                            AmdDeviceReading::placeholder(
+                                i,
+                                Some(name).filter(|n| !n.is_empty()),
+                                4096,
+                            )
+                        })
+                        .collect();
+                }
             }
-        } else {
-            0
-        };
+        }
+
+        // We detected AMD in is_available(), so report at least one device
+        if devices.is_empty() {
+            devices.push(AmdDeviceReading::placeholder(0, None, 4096));
+        }
+        Ok(devices)
+    }
+
+    fn reading(&self, index: u32) -> Result<AmdDeviceReading> {
+        self.readings()?
+            .get(index as usize)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("AMD GPU {} not found", index))
+    }
+}
+
+impl GpuVendorInterface for AmdVendor {
+    fn initialize() -> Result<Self> {
+        // Check if AMD GPU is available (either via rocm-smi or lspci/sysfs)
+        if !Self::is_available() {
+            return Err(anyhow::anyhow!("{}", Self::get_availability_error()));
+        }
+        Ok(Self::with_runner(Arc::new(ToolRunner::new(tool_limits()))))
+    }
+
+    fn vendor_type(&self) -> GpuVendor {
+        GpuVendor::Amd
+    }
+
+    fn device_count(&self) -> Result<u32> {
+        self.devices
+            .update(self.readings().map(|readings| readings.len() as u32))
+    }
+
+    fn get_gpu_info(&self, index: u32) -> Result<GpuInfo> {
+        let reading = self.reading(index)?;
+        Ok(GpuInfo {
+            index: index as u16,
+            name: reading.name,
+            mem_total_mb: reading.mem_total_mb,
+            uuid: reading.uuid,
+            serial: reading.serial,
+            pci_bus_id: reading.pci_bus_id,
+        })
+    }
+
+    fn get_gpu_snapshot(&self, index: u32) -> Result<GpuSnapshot> {
+        let reading = self.reading(index)?;
 
         // For now, we'll return empty process info for AMD
         // This could be enhanced with additional rocm-smi queries
         Ok(GpuSnapshot {
             gpu_index: index as u16,
-            name: gpu_info.name,
+            name: reading.name,
+            uuid: reading.uuid,
+            serial: reading.serial,
             vendor: GpuVendor::Amd,
-            mem_used_mb,
-            mem_total_mb: gpu_info.mem_total_mb,
-            util_pct,
-            temp_c,
-            power_w,
+            mem_used_mb: reading.mem_used_mb,
+            mem_total_mb: reading.mem_total_mb,
+            util_pct: reading.util_pct,
+            temp_c: reading.temp_c,
+            power_w: reading.power_w,
             ecc_volatile: None,
+            ecc_aggregate: None,
+            retired_pages: None,
+            rma_flagged: None,
+            pcie_tx_kbps: None,
+            pcie_rx_kbps: None,
             pids: 0, // TODO: Implement process detection for AMD
             top_proc: None,
+            nvlink_bytes: None,
+            power_limit: None,
+            profiling: None,
         })
     }
 
@@ -642,27 +1018,60 @@ impl GpuVendorInterface for AmdVendor {
     }
 
     fn reset_gpu(&self, index: u32) -> Result<()> {
-        let output = std::process::Command::new("rocm-smi")
-            .args(["--reset", "-d", &index.to_string()])
-            .output()
-            .map_err(|e| anyhow::anyhow!("Failed to run rocm-smi: {}", e))?;
+        let output = self
+            .runner
+            .run_control("rocm-smi", &["--reset", "-d", &index.to_string()])?;
 
-        if !output.status.success() {
-            return Err(anyhow::anyhow!(
-                "rocm-smi reset failed: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ));
+        if !output.success {
+            return Err(anyhow::anyhow!("rocm-smi reset failed: {}", output.stderr));
         }
 
         Ok(())
     }
 
+    fn set_power_limit(&self, index: u32, watts: u32) -> Result<()> {
+        self.run_control(
+            "Setting the power limit",
+            &[
+                "-d",
+                &index.to_string(),
+                "--setpoweroverdrive",
+                &watts.to_string(),
+            ],
+        )
+    }
+
+    /// rocm-smi's `--setsclk` picks DPM levels rather than a frequency, so the
+    /// clock is pinned by setting both ends of the sclk range to `mhz`
+    fn lock_clocks(&self, index: u32, mhz: u32) -> Result<()> {
+        self.run_control(
+            "Locking clocks",
+            &[
+                "-d",
+                &index.to_string(),
+                "--setsrange",
+                &mhz.to_string(),
+                &mhz.to_string(),
+            ],
+        )
+    }
+
+    fn reset_clocks(&self, index: u32) -> Result<()> {
+        self.run_control(
+            "Resetting clocks",
+            &["-d", &index.to_string(), "--resetclocks"],
+        )
+    }
+
+    fn capabilities(&self) -> VendorCapabilities {
+        VendorCapabilities::AMD
+    }
+
     fn is_available() -> bool {
         // First check for rocm-smi (ROCm drivers)
-        if std::process::Command::new("rocm-smi")
-            .arg("--version")
-            .output()
-            .map(|output| output.status.success())
+        let timeout = tool_limits().timeout;
+        if run_with_timeout("rocm-smi", &["--version"], timeout)
+            .map(|output| output.success)
             .unwrap_or(false)
         {
             return true;
@@ -671,11 +1080,10 @@ impl GpuVendorInterface for AmdVendor {
         // Fallback: Check for AMD GPUs via lspci (works for integrated/consumer GPUs)
         #[cfg(target_os = "linux")]
         {
-            let lspci_check = std::process::Command::new("lspci")
-                .output()
+            let lspci_check = run_with_timeout("lspci", &[], timeout)
                 .map(|output| {
-                    if output.status.success() {
-                        let stdout = String::from_utf8_lossy(&output.stdout);
+                    if output.success {
+                        let stdout = output.stdout;
                         stdout.to_lowercase().contains("amd")
                             && (stdout.to_lowercase().contains("vga")
                                 || stdout.to_lowercase().contains("display")
@@ -716,6 +1124,37 @@ impl GpuVendorInterface for AmdVendor {
 pub struct IntelVendor {
     // Intel GPU management via command-line tools
     // Future: Could integrate with Intel oneAPI Level Zero
+    runner: Arc<dyn CommandRunner + Send + Sync>,
+    /// Output of the last `intel_gpu_top -l 1` run
+    output: TtlCache<String>,
+    devices: DeviceCount,
+}
+
+impl IntelVendor {
+    /// Build a vendor that runs its tools through `runner`
+    pub fn with_runner(runner: Arc<dyn CommandRunner + Send + Sync>) -> Self {
+        Self {
+            runner,
+            output: TtlCache::new(DEFAULT_VENDOR_CACHE_TTL),
+            devices: DeviceCount::default(),
+        }
+    }
+
+    /// `intel_gpu_top` output, from the cache or one run shared by all devices
+    fn gpu_top_output(&self) -> Result<Arc<String>> {
+        self.output.get_or_fetch(|| {
+            let started = Instant::now();
+            let output = self.runner.run("intel_gpu_top", &["-l", "1"])?;
+            tracing::debug!(
+                duration_ms = started.elapsed().as_millis() as u64,
+                "Queried intel_gpu_top"
+            );
+            if !output.success {
+                return Err(anyhow::anyhow!("intel_gpu_top failed: {}", output.stderr));
+            }
+            Ok(output.stdout)
+        })
+    }
 }
 
 impl GpuVendorInterface for IntelVendor {
@@ -724,7 +1163,7 @@ impl GpuVendorInterface for IntelVendor {
         if !Self::is_available() {
             return Err(anyhow::anyhow!("{}", Self::get_availability_error()));
         }
-        Ok(Self {})
+        Ok(Self::with_runner(Arc::new(ToolRunner::new(tool_limits()))))
     }
 
     fn vendor_type(&self) -> GpuVendor {
@@ -732,44 +1171,19 @@ impl GpuVendorInterface for IntelVendor {
     }
 
     fn device_count(&self) -> Result<u32> {
-        // Try to get GPU count from intel_gpu_top
-        let output = std::process::Command::new("intel_gpu_top")
-            .args(["-l", "1"])
-            .output()
-            .map_err(|e| anyhow::anyhow!("Failed to run intel_gpu_top: {}", e))?;
-
-        if !output.status.success() {
-            return Err(anyhow::anyhow!(
-                "intel_gpu_top failed: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ));
-        }
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        // Count GPU entries in the output
-        let gpu_count = stdout
-            .lines()
-            .filter(|line| line.contains("GPU") || line.contains("Render"))
-            .count() as u32;
-
-        Ok(if gpu_count > 0 { gpu_count } else { 1 }) // At least one Intel GPU
+        self.devices.update(self.gpu_top_output().map(|stdout| {
+            // Count GPU entries in the output
+            let gpu_count = stdout
+                .lines()
+                .filter(|line| line.contains("GPU") || line.contains("Render"))
+                .count() as u32;
+            gpu_count.max(1) // At least one Intel GPU
+        }))
     }
 
     fn get_gpu_info(&self, index: u32) -> Result<GpuInfo> {
         // Get GPU name from intel_gpu_top
-        let output = std::process::Command::new("intel_gpu_top")
-            .args(["-l", "1"])
-            .output()
-            .map_err(|e| anyhow::anyhow!("Failed to run intel_gpu_top: {}", e))?;
-
-        if !output.status.success() {
-            return Err(anyhow::anyhow!(
-                "intel_gpu_top failed: {}",
-                String::from_utf8_lossy(&output.stderr)
-            ));
-        }
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stdout = self.gpu_top_output()?;
         let name = stdout
             .lines()
             .find(|line| line.contains("GPU") || line.contains("Render"))
@@ -794,6 +1208,9 @@ impl GpuVendorInterface for IntelVendor {
             index: index as u16,
             name,
             mem_total_mb,
+            uuid: None,
+            serial: None,
+            pci_bus_id: None,
         })
     }
 
@@ -802,29 +1219,19 @@ impl GpuVendorInterface for IntelVendor {
         let gpu_info = self.get_gpu_info(index)?;
 
         // Get utilization from intel_gpu_top
-        let output = std::process::Command::new("intel_gpu_top")
-            .args(["-l", "1"])
-            .output()
-            .map_err(|e| anyhow::anyhow!("Failed to run intel_gpu_top: {}", e))?;
-
-        let (util_pct, mem_used_mb) = if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let util = stdout
-                .lines()
-                .find(|line| line.contains("Render/3D"))
-                .and_then(|line| {
-                    line.split_whitespace()
-                        .find(|s| s.ends_with('%'))
-                        .and_then(|s| s.replace('%', "").parse::<f32>().ok())
-                })
-                .unwrap_or(0.0);
+        let stdout = self.gpu_top_output()?;
+        let util_pct = stdout
+            .lines()
+            .find(|line| line.contains("Render/3D"))
+            .and_then(|line| {
+                line.split_whitespace()
+                    .find(|s| s.ends_with('%'))
+                    .and_then(|s| s.replace('%', "").parse::<f32>().ok())
+            })
+            .unwrap_or(0.0);
 
-            // Estimate memory usage (Intel tools don't provide exact memory info)
-            let mem_used = (util / 100.0 * gpu_info.mem_total_mb as f32) as u32;
-            (util, mem_used)
-        } else {
-            (0.0, 0)
-        };
+        // Estimate memory usage (Intel tools don't provide exact memory info)
+        let mem_used_mb = (util_pct / 100.0 * gpu_info.mem_total_mb as f32) as u32;
 
         // Intel GPUs don't typically provide temperature/power info via command line
         // We'll use reasonable defaults
@@ -838,8 +1245,18 @@ impl GpuVendorInterface for IntelVendor {
             temp_c: 0,    // Not available via intel_gpu_top
             power_w: 0.0, // Not available via intel_gpu_top
             ecc_volatile: None,
+            ecc_aggregate: None,
+            retired_pages: None,
+            rma_flagged: None,
+            pcie_tx_kbps: None,
+            pcie_rx_kbps: None,
             pids: 0, // Process detection would require additional parsing
             top_proc: None,
+            nvlink_bytes: None,
+            power_limit: None,
+            uuid: None,
+            serial: None,
+            profiling: None,
         })
     }
 
@@ -858,12 +1275,14 @@ impl GpuVendorInterface for IntelVendor {
         ))
     }
 
+    fn capabilities(&self) -> VendorCapabilities {
+        VendorCapabilities::INTEL
+    }
+
     fn is_available() -> bool {
         // Check if intel_gpu_top is available
-        std::process::Command::new("intel_gpu_top")
-            .arg("-h")
-            .output()
-            .map(|output| output.status.success())
+        run_with_timeout("intel_gpu_top", &["-h"], tool_limits().timeout)
+            .map(|output| output.success)
             .unwrap_or(false)
     }
 
@@ -876,6 +1295,7 @@ impl GpuVendorInterface for IntelVendor {
 #[cfg(target_os = "macos")]
 pub struct AppleVendor {
     // Apple Silicon GPU management via system APIs
+    runner: ToolRunner,
     gpu_info: Option<GpuInfo>,
 }
 
@@ -888,9 +1308,11 @@ impl GpuVendorInterface for AppleVendor {
         }
 
         // Get initial GPU info
-        let gpu_info = Self::get_system_gpu_info()?;
+        let runner = ToolRunner::new(tool_limits());
+        let gpu_info = Self::get_system_gpu_info(&runner)?;
 
         Ok(Self {
+            runner,
             gpu_info: Some(gpu_info),
         })
     }
@@ -912,7 +1334,7 @@ impl GpuVendorInterface for AppleVendor {
         if let Some(ref info) = self.gpu_info {
             Ok(info.clone())
         } else {
-            Self::get_system_gpu_info()
+            Self::get_system_gpu_info(&self.runner)
         }
     }
 
@@ -924,7 +1346,7 @@ impl GpuVendorInterface for AppleVendor {
         let gpu_info = self.get_gpu_info(index)?;
 
         // Get memory usage from vm_stat
-        let mem_used_mb = Self::get_gpu_memory_usage()?;
+        let mem_used_mb = self.get_gpu_memory_usage()?;
 
         // Get processes using Metal/GPU
         let processes = self.get_gpu_processes(index)?;
@@ -941,6 +1363,11 @@ impl GpuVendorInterface for AppleVendor {
             temp_c: 0,          // Not available via system APIs
             power_w: 0.0,       // Not available via system APIs
             ecc_volatile: None, // Not applicable to Apple Silicon
+            ecc_aggregate: None,
+            retired_pages: None,
+            rma_flagged: None,
+            pcie_tx_kbps: None,
+            pcie_rx_kbps: None,
             pids,
             top_proc,
         })
@@ -948,16 +1375,13 @@ impl GpuVendorInterface for AppleVendor {
 
     fn get_gpu_processes(&self, _index: u32) -> Result<Vec<GpuProc>> {
         // Find processes that might be using Metal/GPU
-        let output = std::process::Command::new("ps")
-            .args(["-axo", "pid,user,comm,%mem"])
-            .output()
-            .map_err(|e| anyhow::anyhow!("Failed to run ps: {}", e))?;
+        let output = self.runner.run("ps", &["-axo", "pid,user,comm,%mem"])?;
 
-        if !output.status.success() {
+        if !output.success {
             return Ok(Vec::new());
         }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stdout = output.stdout;
         let mut processes = Vec::new();
 
         for line in stdout.lines().skip(1) {
@@ -980,6 +1404,9 @@ impl GpuVendorInterface for AppleVendor {
                             start_time: "unknown".to_string(), // Would need more complex parsing
                             container: None,
                             node_id: None,
+                            cmdline: crate::proc::get_process_cmdline(pid),
+                            slurm_job_id: None,
+                            start_unix_secs: None,
                         });
                     }
                 }
@@ -997,6 +1424,10 @@ impl GpuVendorInterface for AppleVendor {
         ))
     }
 
+    fn capabilities(&self) -> VendorCapabilities {
+        VendorCapabilities::APPLE
+    }
+
     fn is_available() -> bool {
         // Check if we're on macOS and have Apple Silicon
         if !cfg!(target_os = "macos") {
@@ -1004,13 +1435,15 @@ impl GpuVendorInterface for AppleVendor {
         }
 
         // Check for Apple Silicon by looking for Apple chip in system profiler
-        let output = std::process::Command::new("system_profiler")
-            .args(["SPHardwareDataType"])
-            .output();
+        let output = run_with_timeout(
+            "system_profiler",
+            &["SPHardwareDataType"],
+            tool_limits().timeout,
+        );
 
         if let Ok(output) = output {
-            if output.status.success() {
-                let stdout = String::from_utf8_lossy(&output.stdout);
+            if output.success {
+                let stdout = output.stdout;
                 return stdout.contains("Apple")
                     && (stdout.contains("M1")
                         || stdout.contains("M2")
@@ -1030,17 +1463,14 @@ impl GpuVendorInterface for AppleVendor {
 #[cfg(target_os = "macos")]
 impl AppleVendor {
     /// Get GPU information from system_profiler
-    fn get_system_gpu_info() -> Result<GpuInfo> {
-        let output = std::process::Command::new("system_profiler")
-            .args(["SPDisplaysDataType"])
-            .output()
-            .map_err(|e| anyhow::anyhow!("Failed to run system_profiler: {}", e))?;
+    fn get_system_gpu_info(runner: &ToolRunner) -> Result<GpuInfo> {
+        let output = runner.run("system_profiler", &["SPDisplaysDataType"])?;
 
-        if !output.status.success() {
+        if !output.success {
             return Err(anyhow::anyhow!("system_profiler failed"));
         }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stdout = output.stdout;
 
         // Parse GPU name and memory
         let mut name = "Apple Silicon GPU".to_string();
@@ -1055,13 +1485,10 @@ impl AppleVendor {
         }
 
         // Get total memory from system
-        let mem_output = std::process::Command::new("system_profiler")
-            .args(["SPHardwareDataType"])
-            .output()
-            .map_err(|e| anyhow::anyhow!("Failed to get memory info: {}", e))?;
+        let mem_output = runner.run("system_profiler", &["SPHardwareDataType"])?;
 
-        if mem_output.status.success() {
-            let mem_stdout = String::from_utf8_lossy(&mem_output.stdout);
+        if mem_output.success {
+            let mem_stdout = mem_output.stdout;
             for line in mem_stdout.lines() {
                 if line.contains("Memory:") {
                     if let Some(mem_str) = line.split("Memory:").nth(1) {
@@ -1085,16 +1512,14 @@ impl AppleVendor {
     }
 
     /// Get GPU memory usage from vm_stat
-    fn get_gpu_memory_usage() -> Result<u32> {
-        let output = std::process::Command::new("vm_stat")
-            .output()
-            .map_err(|e| anyhow::anyhow!("Failed to run vm_stat: {}", e))?;
+    fn get_gpu_memory_usage(&self) -> Result<u32> {
+        let output = self.runner.run("vm_stat", &[])?;
 
-        if !output.status.success() {
+        if !output.success {
             return Ok(0);
         }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stdout = output.stdout;
         let page_size = 16384; // Apple Silicon uses 16KB pages
 
         // Parse active memory (which includes GPU memory on unified architecture)
@@ -1154,150 +1579,1062 @@ impl AppleVendor {
     }
 }
 
-/// Multi-vendor GPU manager
-pub struct GpuManager {
-    vendors: Vec<Box<dyn GpuVendorInterface + Send + Sync>>,
+/// Environment variable naming a [`MockFixture`] JSON file. In `mock_nvml` builds,
+/// [`GpuManager::initialize`] serves that fixture instead of detecting real GPUs.
+#[cfg(any(test, feature = "mock_nvml"))]
+pub const MOCK_FIXTURE_ENV: &str = "GPUKILL_MOCK";
+
+/// GPUs, processes and injected failures served by a [`MockVendor`]:
+///
+/// ```json
+/// {"gpus": [{"name": "NVIDIA A100", "mem_total_mb": 81920, "util_pct": 45.0,
+///   "processes": [{"pid": 4242, "user": "alice", "name": "python", "used_mem_mb": 20480}],
+///   "failures": [{"call": "snapshot", "nth": 2, "message": "GPU is lost"}]}]}
+/// ```
+#[cfg(any(test, feature = "mock_nvml"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MockFixture {
+    #[serde(default = "default_mock_vendor")]
+    pub vendor: GpuVendor,
+    #[serde(default)]
+    pub gpus: Vec<MockGpu>,
 }
 
-#[allow(dead_code)]
-impl GpuManager {
-    /// Initialize the GPU manager with all available vendors
-    pub fn initialize() -> Result<Self> {
-        let mut vendors: Vec<Box<dyn GpuVendorInterface + Send + Sync>> = Vec::new();
+#[cfg(any(test, feature = "mock_nvml"))]
+fn default_mock_vendor() -> GpuVendor {
+    GpuVendor::Nvidia
+}
 
-        // Try to initialize NVIDIA
-        if NvidiaVendor::is_available() {
-            match NvidiaVendor::initialize() {
-                Ok(nvidia) => {
-                    tracing::info!("NVIDIA GPU support initialized");
-                    vendors.push(Box::new(nvidia));
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to initialize NVIDIA support: {}", e);
-                }
-            }
+#[cfg(any(test, feature = "mock_nvml"))]
+impl Default for MockFixture {
+    fn default() -> Self {
+        Self {
+            vendor: default_mock_vendor(),
+            gpus: Vec::new(),
         }
+    }
+}
 
-        // Try to initialize AMD
-        if AmdVendor::is_available() {
-            match AmdVendor::initialize() {
-                Ok(amd) => {
-                    tracing::info!("AMD GPU support initialized");
-                    vendors.push(Box::new(amd));
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to initialize AMD support: {}", e);
-                }
-            }
-        }
+/// One GPU of a [`MockFixture`]; its index is its position in the fixture
+#[cfg(any(test, feature = "mock_nvml"))]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MockGpu {
+    pub name: String,
+    pub mem_total_mb: u32,
+    /// Memory in use; the sum of the processes' memory when unset
+    #[serde(default)]
+    pub mem_used_mb: Option<u32>,
+    #[serde(default)]
+    pub util_pct: f32,
+    #[serde(default)]
+    pub temp_c: i32,
+    #[serde(default)]
+    pub power_w: f32,
+    #[serde(default)]
+    pub uuid: Option<String>,
+    #[serde(default)]
+    pub processes: Vec<MockProcess>,
+    #[serde(default)]
+    pub failures: Vec<MockFailure>,
+}
 
-        // Try to initialize Intel
-        if IntelVendor::is_available() {
-            match IntelVendor::initialize() {
-                Ok(intel) => {
-                    tracing::info!("Intel GPU support initialized");
-                    vendors.push(Box::new(intel));
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to initialize Intel support: {}", e);
-                }
-            }
-        }
+/// A process on a [`MockGpu`]
+#[cfg(any(test, feature = "mock_nvml"))]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MockProcess {
+    pub pid: u32,
+    pub user: String,
+    pub name: String,
+    pub used_mem_mb: u32,
+    #[serde(default)]
+    pub cmdline: Option<String>,
+}
 
-        // Try to initialize Apple Silicon (macOS only)
-        #[cfg(target_os = "macos")]
-        if AppleVendor::is_available() {
-            match AppleVendor::initialize() {
-                Ok(apple) => {
-                    tracing::info!("Apple Silicon GPU support initialized");
-                    vendors.push(Box::new(apple));
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to initialize Apple Silicon support: {}", e);
-                }
-            }
-        }
+/// A vendor call that a [`MockFailure`] can fail
+#[cfg(any(test, feature = "mock_nvml"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MockCall {
+    Info,
+    Snapshot,
+    Processes,
+    Reset,
+}
 
-        if vendors.is_empty() {
-            return Err(anyhow::anyhow!(
-                "No GPU vendors available. Please install NVIDIA, AMD, Intel, or Apple Silicon GPU drivers."
-            ));
+/// Error returned by one kind of call on one [`MockGpu`]
+#[cfg(any(test, feature = "mock_nvml"))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MockFailure {
+    pub call: MockCall,
+    /// Fail only the nth such call, counting from 1; every call when unset
+    #[serde(default)]
+    pub nth: Option<usize>,
+    pub message: String,
+}
+
+/// Vendor serving a programmable [`MockFixture`], for tests of the GPU manager and
+/// everything built on it without GPU hardware
+#[cfg(any(test, feature = "mock_nvml"))]
+#[derive(Debug, Default)]
+pub struct MockVendor {
+    fixture: RwLock<MockFixture>,
+    /// Calls made so far, by GPU index and kind
+    calls: Mutex<HashMap<(u32, MockCall), usize>>,
+}
+
+#[cfg(any(test, feature = "mock_nvml"))]
+#[allow(dead_code)]
+impl MockVendor {
+    pub fn new(fixture: MockFixture) -> Self {
+        Self {
+            fixture: RwLock::new(fixture),
+            calls: Mutex::new(HashMap::new()),
         }
+    }
 
-        Ok(Self { vendors })
+    /// Vendor serving the fixture in a JSON file
+    pub fn from_file(path: &std::path::Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read mock fixture {}", path.display()))?;
+        let fixture = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse mock fixture {}", path.display()))?;
+        Ok(Self::new(fixture))
     }
 
-    /// Get total device count across all vendors
-    pub fn total_device_count(&self) -> Result<u32> {
-        let mut total = 0;
-        for vendor in &self.vendors {
-            total += vendor.device_count()?;
-        }
-        Ok(total)
+    /// GPU manager over this vendor alone that queries it on every call, so
+    /// fixture updates and injected failures show up at once
+    pub fn manager(self: &Arc<Self>) -> GpuManager {
+        GpuManager::with_vendor(self.clone()).with_snapshot_cache_ttl(Duration::ZERO)
     }
 
-    /// Get all GPU snapshots from all vendors
-    pub fn get_all_snapshots(&self) -> Result<Vec<GpuSnapshot>> {
-        let mut snapshots = Vec::new();
-        let mut global_offset: u16 = 0;
-        for vendor in &self.vendors {
-            let count = vendor.device_count()?;
-            for i in 0..count {
-                match vendor.get_gpu_snapshot(i) {
-                    Ok(mut snapshot) => {
-                        let new_index = snapshot.gpu_index.saturating_add(global_offset);
-                        snapshot.gpu_index = new_index;
-                        if let Some(ref mut top_proc) = snapshot.top_proc {
-                            top_proc.gpu_index = new_index;
-                        }
-                        snapshots.push(snapshot);
-                    }
-                    Err(e) => {
-                        tracing::warn!("Failed to get snapshot for GPU {}: {}", i, e);
-                    }
-                }
-            }
-            global_offset = global_offset.saturating_add(count as u16);
-        }
-        Ok(snapshots)
+    /// Change the fixture, e.g. to start a process between two calls
+    pub fn update(&self, change: impl FnOnce(&mut MockFixture)) {
+        change(&mut self.fixture.write().unwrap_or_else(|e| e.into_inner()));
     }
 
-    /// Get all processes from all vendors
-    pub fn get_all_processes(&self) -> Result<Vec<GpuProc>> {
-        let mut processes = Vec::new();
-        let mut global_offset: u16 = 0;
-        for vendor in &self.vendors {
-            let count = vendor.device_count()?;
-            for i in 0..count {
-                match vendor.get_gpu_processes(i) {
-                    Ok(mut vendor_procs) => {
-                        for proc in &mut vendor_procs {
-                            proc.gpu_index = proc.gpu_index.saturating_add(global_offset);
-                        }
-                        processes.append(&mut vendor_procs)
-                    }
-                    Err(e) => {
-                        tracing::warn!("Failed to get processes for GPU {}: {}", i, e);
-                    }
-                }
-            }
-            global_offset = global_offset.saturating_add(count as u16);
+    /// How many `call`s were made on GPU `index`, failed ones included
+    pub fn calls(&self, index: u32, call: MockCall) -> usize {
+        let calls = self.calls.lock().unwrap_or_else(|e| e.into_inner());
+        calls.get(&(index, call)).copied().unwrap_or(0)
+    }
+
+    /// Count a call and return GPU `index`, or the failure injected into this call
+    fn call(&self, index: u32, call: MockCall) -> Result<MockGpu> {
+        let nth = {
+            let mut calls = self.calls.lock().unwrap_or_else(|e| e.into_inner());
+            let count = calls.entry((index, call)).or_insert(0);
+            *count += 1;
+            *count
+        };
+        let fixture = self.fixture.read().unwrap_or_else(|e| e.into_inner());
+        let gpu = fixture
+            .gpus
+            .get(index as usize)
+            .ok_or_else(|| anyhow::anyhow!("GPU {} not found", index))?;
+        if let Some(failure) = gpu
+            .failures
+            .iter()
+            .find(|f| f.call == call && f.nth.is_none_or(|n| n == nth))
+        {
+            anyhow::bail!("{}", failure.message);
         }
-        Ok(processes)
+        Ok(gpu.clone())
     }
 
-    /// Reset a specific GPU by global index
+    fn processes_of(gpu: &MockGpu, index: u32) -> Vec<GpuProc> {
+        gpu.processes
+            .iter()
+            .map(|p| GpuProc {
+                gpu_index: index as u16,
+                pid: p.pid,
+                user: p.user.clone(),
+                proc_name: p.name.clone(),
+                used_mem_mb: p.used_mem_mb,
+                start_time: "unknown".to_string(),
+                container: None,
+                node_id: None,
+                cmdline: p.cmdline.clone(),
+                container_pid: None,
+                slurm_job_id: None,
+                start_unix_secs: None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(any(test, feature = "mock_nvml"))]
+impl GpuVendorInterface for MockVendor {
+    /// Vendor serving the fixture named by [`MOCK_FIXTURE_ENV`]
+    fn initialize() -> Result<Self> {
+        let path = std::env::var_os(MOCK_FIXTURE_ENV)
+            .ok_or_else(|| anyhow::anyhow!("{} is not set", MOCK_FIXTURE_ENV))?;
+        Self::from_file(std::path::Path::new(&path))
+    }
+
+    fn vendor_type(&self) -> GpuVendor {
+        self.fixture
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .vendor
+    }
+
+    fn device_count(&self) -> Result<u32> {
+        let fixture = self.fixture.read().unwrap_or_else(|e| e.into_inner());
+        Ok(fixture.gpus.len() as u32)
+    }
+
+    fn get_gpu_info(&self, index: u32) -> Result<GpuInfo> {
+        let gpu = self.call(index, MockCall::Info)?;
+        Ok(GpuInfo {
+            index: index as u16,
+            name: gpu.name,
+            mem_total_mb: gpu.mem_total_mb,
+            uuid: gpu.uuid,
+            serial: None,
+            pci_bus_id: None,
+        })
+    }
+
+    fn get_gpu_snapshot(&self, index: u32) -> Result<GpuSnapshot> {
+        let gpu = self.call(index, MockCall::Snapshot)?;
+        let processes = Self::processes_of(&gpu, index);
+        Ok(GpuSnapshot {
+            gpu_index: index as u16,
+            name: gpu.name.clone(),
+            vendor: self.vendor_type(),
+            mem_used_mb: gpu
+                .mem_used_mb
+                .unwrap_or_else(|| processes.iter().map(|p| p.used_mem_mb).sum()),
+            mem_total_mb: gpu.mem_total_mb,
+            util_pct: gpu.util_pct,
+            temp_c: gpu.temp_c,
+            power_w: gpu.power_w,
+            ecc_volatile: None,
+            ecc_aggregate: None,
+            retired_pages: None,
+            rma_flagged: None,
+            pcie_tx_kbps: None,
+            pcie_rx_kbps: None,
+            pids: processes.len(),
+            top_proc: processes.into_iter().max_by_key(|p| p.used_mem_mb),
+            nvlink_bytes: None,
+            power_limit: None,
+            uuid: gpu.uuid,
+            serial: None,
+            profiling: None,
+        })
+    }
+
+    fn get_gpu_processes(&self, index: u32) -> Result<Vec<GpuProc>> {
+        let gpu = self.call(index, MockCall::Processes)?;
+        Ok(Self::processes_of(&gpu, index))
+    }
+
+    fn reset_gpu(&self, index: u32) -> Result<()> {
+        self.call(index, MockCall::Reset).map(|_| ())
+    }
+
+    fn capabilities(&self) -> VendorCapabilities {
+        VendorCapabilities {
+            reset_supported: true,
+            process_enum_supported: true,
+            power_limit_supported: false,
+            util_supported: true,
+        }
+    }
+
+    fn is_available() -> bool {
+        std::env::var_os(MOCK_FIXTURE_ENV).is_some()
+    }
+
+    fn get_availability_error() -> String {
+        format!("Set {} to a mock GPU fixture file", MOCK_FIXTURE_ENV)
+    }
+}
+
+/// Multi-vendor GPU manager
+#[derive(Clone)]
+pub struct GpuManager {
+    vendors: Vec<Arc<dyn GpuVendorInterface + Send + Sync>>,
+    snapshot_timeout: Duration,
+    snapshot_parallelism: usize,
+    /// Query devices with [`GpuVendorInterface::get_gpu_snapshot_fast`]
+    fast: bool,
+    /// DCGM profiling metrics for NVIDIA GPUs, when available
+    profiling: Option<Arc<dyn ProfilingProvider>>,
+    /// Recent results, shared by clones so pollers reuse one vendor query
+    snapshot_cache: Arc<TtlCache<SnapshotCollection>>,
+    process_cache: Arc<TtlCache<ProcessCollection>>,
+    /// Stable indices reported instead of enumeration order, when enabled
+    stable_indices: Option<Arc<StableIndices>>,
+}
+
+/// Default time a single device may take to answer a snapshot query
+pub const DEFAULT_SNAPSHOT_TIMEOUT_SECS: u64 = 10;
+
+/// Default number of devices queried concurrently during snapshot collection
+pub const DEFAULT_SNAPSHOT_PARALLELISM: usize = 4;
+
+/// Default time snapshot and process collections are reused, in milliseconds
+pub const DEFAULT_SNAPSHOT_CACHE_MS: u64 = 500;
+
+/// Snapshots collected from all devices, plus the devices that did not answer
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotCollection {
+    /// Snapshots ordered by global GPU index
+    pub snapshots: Vec<GpuSnapshot>,
+    /// Devices whose query failed or exceeded the timeout, ordered by global index
+    pub errors: Vec<DeviceError>,
+}
+
+impl SnapshotCollection {
+    /// Whether any device is missing from `snapshots`
+    pub fn is_degraded(&self) -> bool {
+        !self.errors.is_empty()
+    }
+
+    /// Query outcome of every device, ordered by global index
+    pub fn gpu_status(&self) -> Vec<GpuStatusEntry> {
+        let mut status: Vec<GpuStatusEntry> = self
+            .snapshots
+            .iter()
+            .map(|gpu| GpuStatusEntry::ok(gpu.gpu_index))
+            .chain(self.errors.iter().map(DeviceError::status_entry))
+            .collect();
+        status.sort_by_key(|entry| entry.gpu_index);
+        status
+    }
+}
+
+/// Processes from all devices, plus the devices that could not be queried
+#[derive(Debug, Clone, Default)]
+pub struct ProcessCollection {
+    pub processes: Vec<GpuProc>,
+    /// Global indices of devices whose process query failed, with the error
+    pub unavailable: Vec<(u16, String)>,
+}
+
+/// Run `op` against `vendor`. When the device handle was lost, re-initialize the
+/// vendor and try once more; otherwise the first error is returned.
+fn retry_on_lost<T>(
+    vendor: &(dyn GpuVendorInterface + Send + Sync),
+    op: impl Fn() -> Result<T>,
+) -> Result<T> {
+    match op() {
+        Err(e) if is_device_lost(&e) => {
+            tracing::warn!(
+                "{} device handle lost ({:#}); re-initializing and retrying",
+                vendor.vendor_type(),
+                e
+            );
+            if let Err(reinit) = vendor.reinitialize() {
+                tracing::warn!("{:#}", reinit);
+                return Err(e);
+            }
+            op()
+        }
+        result => result,
+    }
+}
+
+struct SnapshotJob {
+    vendor: Arc<dyn GpuVendorInterface + Send + Sync>,
+    local_index: u32,
+    global_index: u16,
+    fast: bool,
+}
+
+enum SnapshotEvent {
+    Started(u16, Instant),
+    Finished(u16, Box<Result<GpuSnapshot>>),
+}
+
+/// Pull snapshot jobs off the shared queue until it is empty or the collector has gone away
+fn snapshot_worker(queue: Arc<Mutex<VecDeque<SnapshotJob>>>, events: mpsc::Sender<SnapshotEvent>) {
+    loop {
+        let job = match queue.lock() {
+            Ok(mut queue) => queue.pop_front(),
+            Err(_) => None,
+        };
+        let Some(job) = job else {
+            break;
+        };
+        if events
+            .send(SnapshotEvent::Started(job.global_index, Instant::now()))
+            .is_err()
+        {
+            break;
+        }
+        let result = retry_on_lost(job.vendor.as_ref(), || {
+            if job.fast {
+                job.vendor.get_gpu_snapshot_fast(job.local_index)
+            } else {
+                job.vendor.get_gpu_snapshot(job.local_index)
+            }
+        });
+        if events
+            .send(SnapshotEvent::Finished(job.global_index, Box::new(result)))
+            .is_err()
+        {
+            break;
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl GpuManager {
+    /// Initialize the GPU manager with all available vendors
+    pub fn initialize() -> Result<Self> {
+        #[cfg(feature = "mock_nvml")]
+        if MockVendor::is_available() {
+            tracing::info!("Serving mock GPUs from {}", MOCK_FIXTURE_ENV);
+            return Ok(Self::with_vendor(Arc::new(MockVendor::initialize()?)));
+        }
+
+        let mut vendors: Vec<Arc<dyn GpuVendorInterface + Send + Sync>> = Vec::new();
+        let mut profiling = None;
+
+        // Try to initialize NVIDIA
+        if NvidiaVendor::is_available() {
+            match NvidiaVendor::initialize() {
+                Ok(nvidia) => {
+                    tracing::info!("NVIDIA GPU support initialized");
+                    vendors.push(Arc::new(nvidia));
+                    profiling = crate::dcgm::detect();
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to initialize NVIDIA support: {}", e);
+                }
+            }
+        }
+
+        // Try to initialize AMD
+        if AmdVendor::is_available() {
+            match AmdVendor::initialize() {
+                Ok(amd) => {
+                    tracing::info!("AMD GPU support initialized");
+                    vendors.push(Arc::new(amd));
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to initialize AMD support: {}", e);
+                }
+            }
+        }
+
+        // Try to initialize Intel
+        if IntelVendor::is_available() {
+            match IntelVendor::initialize() {
+                Ok(intel) => {
+                    tracing::info!("Intel GPU support initialized");
+                    vendors.push(Arc::new(intel));
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to initialize Intel support: {}", e);
+                }
+            }
+        }
+
+        // Try to initialize Apple Silicon (macOS only)
+        #[cfg(target_os = "macos")]
+        if AppleVendor::is_available() {
+            match AppleVendor::initialize() {
+                Ok(apple) => {
+                    tracing::info!("Apple Silicon GPU support initialized");
+                    vendors.push(Arc::new(apple));
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to initialize Apple Silicon support: {}", e);
+                }
+            }
+        }
+
+        if vendors.is_empty() {
+            return Err(anyhow::anyhow!(
+                "No GPU vendors available. Please install NVIDIA, AMD, Intel, or Apple Silicon GPU drivers."
+            ));
+        }
+
+        let mut manager = Self::with_vendors(vendors);
+        manager.profiling = profiling;
+        Ok(manager)
+    }
+
+    /// Manager over the given vendors, in order, without detecting any
+    pub fn with_vendors(vendors: Vec<Arc<dyn GpuVendorInterface + Send + Sync>>) -> Self {
+        Self {
+            vendors,
+            snapshot_timeout: Duration::from_secs(DEFAULT_SNAPSHOT_TIMEOUT_SECS),
+            snapshot_parallelism: DEFAULT_SNAPSHOT_PARALLELISM,
+            fast: false,
+            profiling: None,
+            snapshot_cache: Arc::new(TtlCache::new(Duration::from_millis(
+                DEFAULT_SNAPSHOT_CACHE_MS,
+            ))),
+            process_cache: Arc::new(TtlCache::new(Duration::from_millis(
+                DEFAULT_SNAPSHOT_CACHE_MS,
+            ))),
+            stable_indices: None,
+        }
+    }
+
+    /// Report GPUs by the stable indices recorded in the default index map (see
+    /// [`crate::gpu_index`]). If the map cannot be read or written, GPUs keep
+    /// their enumeration order and a warning is logged.
+    pub fn with_stable_indices(self) -> Self {
+        match GpuIndexMap::default_path()
+            .and_then(|path| self.clone().with_stable_indices_at(&path))
+        {
+            Ok(manager) => manager,
+            Err(e) => {
+                tracing::warn!(
+                    "Using GPU enumeration order, stable indices unavailable: {:#}",
+                    e
+                );
+                self
+            }
+        }
+    }
+
+    /// Report GPUs by the stable indices in the index map at `path`, recording
+    /// GPUs seen for the first time
+    pub fn with_stable_indices_at(mut self, path: &std::path::Path) -> Result<Self> {
+        let detected = self.detect_devices()?;
+        let mut map = GpuIndexMap::load(path)?;
+        if map.assign(&detected) {
+            map.save(path)?;
+        }
+        self.stable_indices = Some(Arc::new(StableIndices::new(map, &detected)));
+        self.invalidate_cache();
+        Ok(self)
+    }
+
+    /// Stable index translation, if enabled
+    pub fn stable_indices(&self) -> Option<&StableIndices> {
+        self.stable_indices.as_deref()
+    }
+
+    /// Identify every device in enumeration order
+    fn detect_devices(&self) -> Result<Vec<DetectedGpu>> {
+        let mut detected = Vec::new();
+        let mut global_offset: u16 = 0;
+        for vendor in &self.vendors {
+            let count = Self::device_count_of(vendor)?;
+            for i in 0..count {
+                let enumeration_index = global_offset.saturating_add(i as u16);
+                detected.push(
+                    match retry_on_lost(vendor.as_ref(), || vendor.get_gpu_info(i)) {
+                        Ok(info) => DetectedGpu {
+                            enumeration_index,
+                            id: Some(device_id(vendor.vendor_type(), i, &info)),
+                            name: info.name,
+                        },
+                        Err(e) => {
+                            tracing::warn!(
+                                "Failed to identify GPU {}, it gets a temporary index: {:#}",
+                                enumeration_index,
+                                e
+                            );
+                            DetectedGpu {
+                                enumeration_index,
+                                id: None,
+                                name: format!("{} GPU {}", vendor.vendor_type(), i),
+                            }
+                        }
+                    },
+                );
+            }
+            global_offset = global_offset.saturating_add(count as u16);
+        }
+        Ok(detected)
+    }
+
+    /// Index reported for the device at an enumeration index
+    fn reported_index(&self, enumeration_index: u16) -> u16 {
+        match &self.stable_indices {
+            Some(stable) => stable.stable(enumeration_index),
+            None => enumeration_index,
+        }
+    }
+
+    /// Enumeration index of the device reported as `index`
+    fn enumeration_index(&self, index: u32) -> Option<u32> {
+        match &self.stable_indices {
+            Some(stable) => stable
+                .enumeration(u16::try_from(index).ok()?)
+                .map(u32::from),
+            None => Some(index),
+        }
+    }
+
+    /// Set how long snapshot and process collections are reused; zero disables
+    /// the cache
+    pub fn with_snapshot_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.snapshot_cache = Arc::new(TtlCache::new(ttl));
+        self.process_cache = Arc::new(TtlCache::new(ttl));
+        self
+    }
+
+    /// Forget cached snapshots and processes, e.g. after a kill or reset changed them
+    pub fn invalidate_cache(&self) {
+        self.snapshot_cache.invalidate();
+        self.process_cache.invalidate();
+    }
+
+    /// Add profiling metrics from `provider` to NVIDIA snapshots
+    pub fn with_profiling_provider(mut self, provider: Arc<dyn ProfilingProvider>) -> Self {
+        self.profiling = Some(provider);
+        self
+    }
+
+    /// Manager over a single vendor, such as the self-test's mock GPU
+    pub fn with_vendor(vendor: Arc<dyn GpuVendorInterface + Send + Sync>) -> Self {
+        Self::with_vendors(vec![vendor])
+    }
+
+    /// Set how long a single device may take to answer a snapshot query
+    pub fn with_snapshot_timeout(mut self, timeout: Duration) -> Self {
+        self.snapshot_timeout = timeout;
+        self
+    }
+
+    /// Set how many devices are queried concurrently during snapshot collection
+    pub fn with_snapshot_parallelism(mut self, parallelism: usize) -> Self {
+        self.snapshot_parallelism = parallelism.max(1);
+        self
+    }
+
+    /// Skip the optional metrics that are slow to read, and DCGM profiling, when
+    /// collecting snapshots
+    pub fn with_fast_mode(mut self, fast: bool) -> Self {
+        self.fast = fast;
+        self.invalidate_cache();
+        self
+    }
+
+    /// Device count of one vendor, retried once after a lost handle
+    fn device_count_of(vendor: &Arc<dyn GpuVendorInterface + Send + Sync>) -> Result<u32> {
+        retry_on_lost(vendor.as_ref(), || vendor.device_count())
+    }
+
+    /// Get total device count across all vendors
+    pub fn total_device_count(&self) -> Result<u32> {
+        let mut total = 0;
+        for vendor in &self.vendors {
+            total += Self::device_count_of(vendor)?;
+        }
+        Ok(total)
+    }
+
+    /// Get all GPU snapshots from all vendors.
+    /// Devices that time out are logged and left out; see [`Self::collect_snapshots`].
+    pub fn get_all_snapshots(&self) -> Result<Vec<GpuSnapshot>> {
+        Ok(self.collect_snapshots()?.snapshots)
+    }
+
+    /// Query all devices on a bounded pool of worker threads, or reuse a collection
+    /// younger than the cache TTL.
+    ///
+    /// Each device gets `snapshot_timeout` from the moment its query starts. A device
+    /// that does not answer in time is reported in `timed_out` and its worker is
+    /// abandoned and replaced, so one hung vendor command cannot stall the others.
+    pub fn collect_snapshots(&self) -> Result<SnapshotCollection> {
+        let collection = self
+            .snapshot_cache
+            .get_or_fetch(|| self.query_snapshots())?;
+        Ok(SnapshotCollection::clone(&collection))
+    }
+
+    fn query_snapshots(&self) -> Result<SnapshotCollection> {
+        let started = Instant::now();
+        let mut jobs = VecDeque::new();
+        // Global index -> NVML index, for looking up profiling metrics
+        let mut nvml_indices = HashMap::new();
+        // Global index -> vendor, for naming devices that fail
+        let mut device_vendors = HashMap::new();
+        let mut global_offset: u16 = 0;
+        for vendor in &self.vendors {
+            let count = Self::device_count_of(vendor)?;
+            for i in 0..count {
+                if vendor.vendor_type() == GpuVendor::Nvidia {
+                    nvml_indices.insert(global_offset.saturating_add(i as u16), i);
+                }
+                device_vendors.insert(global_offset.saturating_add(i as u16), vendor.vendor_type());
+                jobs.push_back(SnapshotJob {
+                    vendor: Arc::clone(vendor),
+                    local_index: i,
+                    global_index: global_offset.saturating_add(i as u16),
+                    fast: self.fast,
+                });
+            }
+            global_offset = global_offset.saturating_add(count as u16);
+        }
+
+        let total = jobs.len();
+        let queue = Arc::new(Mutex::new(jobs));
+        let (events_tx, events) = mpsc::channel();
+        let spawn_worker = || {
+            let queue = Arc::clone(&queue);
+            let events_tx = events_tx.clone();
+            std::thread::spawn(move || snapshot_worker(queue, events_tx));
+        };
+        for _ in 0..self.snapshot_parallelism.clamp(1, total.max(1)) {
+            spawn_worker();
+        }
+
+        let mut in_flight: HashMap<u16, Instant> = HashMap::new();
+        let mut snapshots = BTreeMap::new();
+        let mut errors = Vec::new();
+        let vendor_of = |index: u16| {
+            device_vendors
+                .get(&index)
+                .copied()
+                .unwrap_or(GpuVendor::Unknown)
+        };
+        let mut done = 0;
+        while done < total {
+            let wait = in_flight
+                .values()
+                .map(|started| {
+                    (*started + self.snapshot_timeout).saturating_duration_since(Instant::now())
+                })
+                .min()
+                .unwrap_or(self.snapshot_timeout);
+
+            match events.recv_timeout(wait) {
+                Ok(SnapshotEvent::Started(index, started)) => {
+                    in_flight.insert(index, started);
+                }
+                Ok(SnapshotEvent::Finished(index, result)) => {
+                    // Late answers from devices already reported as timed out are dropped
+                    if in_flight.remove(&index).is_none() {
+                        continue;
+                    }
+                    done += 1;
+                    match *result {
+                        Ok(mut snapshot) => {
+                            snapshot.gpu_index = index;
+                            if let Some(ref mut top_proc) = snapshot.top_proc {
+                                top_proc.gpu_index = index;
+                            }
+                            snapshots.insert(index, snapshot);
+                        }
+                        Err(e) => {
+                            tracing::warn!("Failed to get snapshot for GPU {}: {:#}", index, e);
+                            errors.push(DeviceError::failed(index, vendor_of(index), &e));
+                        }
+                    }
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {
+                    let now = Instant::now();
+                    let expired: Vec<u16> = in_flight
+                        .iter()
+                        .filter(|(_, started)| {
+                            now.duration_since(**started) >= self.snapshot_timeout
+                        })
+                        .map(|(index, _)| *index)
+                        .collect();
+                    for index in expired {
+                        in_flight.remove(&index);
+                        tracing::warn!(
+                            "Snapshot for GPU {} timed out after {:?}",
+                            index,
+                            self.snapshot_timeout
+                        );
+                        errors.push(DeviceError::timed_out(
+                            index,
+                            vendor_of(index),
+                            self.snapshot_timeout,
+                        ));
+                        done += 1;
+                        spawn_worker();
+                    }
+                }
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        if let Some(provider) = self.profiling.as_ref().filter(|_| !self.fast) {
+            for snapshot in snapshots.values_mut() {
+                if let Some(&nvml_index) = nvml_indices.get(&snapshot.gpu_index) {
+                    snapshot.profiling = provider.metrics(nvml_index);
+                }
+            }
+        }
+
+        let mut snapshots: Vec<GpuSnapshot> = snapshots.into_values().collect();
+        if self.stable_indices.is_some() {
+            for snapshot in &mut snapshots {
+                snapshot.gpu_index = self.reported_index(snapshot.gpu_index);
+                if let Some(ref mut top_proc) = snapshot.top_proc {
+                    top_proc.gpu_index = snapshot.gpu_index;
+                }
+            }
+            snapshots.sort_by_key(|snapshot| snapshot.gpu_index);
+            for error in errors.iter_mut() {
+                error.gpu_index = self.reported_index(error.gpu_index);
+            }
+        }
+
+        errors.sort_by_key(|error| error.gpu_index);
+        tracing::debug!(
+            devices = snapshots.len(),
+            failed = errors.len(),
+            duration_ms = started.elapsed().as_millis() as u64,
+            "Collected GPU snapshots"
+        );
+        Ok(SnapshotCollection { snapshots, errors })
+    }
+
+    /// [`Self::collect_snapshots`] on the blocking thread pool, so slow vendor tools
+    /// do not stall the async runtime (watch mode, node registration)
+    pub async fn collect_snapshots_async(&self) -> Result<SnapshotCollection> {
+        let manager = self.clone();
+        tokio::task::spawn_blocking(move || manager.collect_snapshots())
+            .await
+            .context("Snapshot collection task panicked")?
+    }
+
+    /// [`Self::get_all_snapshots`] on the blocking thread pool
+    pub async fn get_all_snapshots_async(&self) -> Result<Vec<GpuSnapshot>> {
+        Ok(self.collect_snapshots_async().await?.snapshots)
+    }
+
+    /// [`Self::collect_processes`] on the blocking thread pool
+    pub async fn collect_processes_async(&self) -> Result<ProcessCollection> {
+        let manager = self.clone();
+        tokio::task::spawn_blocking(move || manager.collect_processes())
+            .await
+            .context("Process query task panicked")?
+    }
+
+    /// [`Self::get_all_processes`] on the blocking thread pool
+    pub async fn get_all_processes_async(&self) -> Result<Vec<GpuProc>> {
+        let manager = self.clone();
+        tokio::task::spawn_blocking(move || manager.get_all_processes())
+            .await
+            .context("Process query task panicked")?
+    }
+
+    /// Get all processes from all vendors. Devices that cannot be queried are
+    /// logged and left out; see [`Self::collect_processes`].
+    pub fn get_all_processes(&self) -> Result<Vec<GpuProc>> {
+        Ok(self.collect_processes()?.processes)
+    }
+
+    /// Processes from every device that answers, and the devices that did not.
+    /// A collection younger than the cache TTL is reused.
+    pub fn collect_processes(&self) -> Result<ProcessCollection> {
+        let collection = self.process_cache.get_or_fetch(|| self.query_processes())?;
+        Ok(ProcessCollection::clone(&collection))
+    }
+
+    /// Query every device's processes, `snapshot_parallelism` devices at a time
+    fn query_processes(&self) -> Result<ProcessCollection> {
+        let mut jobs = Vec::new();
+        let mut global_offset: u16 = 0;
+        for vendor in &self.vendors {
+            let count = Self::device_count_of(vendor)?;
+            for i in 0..count {
+                jobs.push((vendor, i, global_offset));
+            }
+            global_offset = global_offset.saturating_add(count as u16);
+        }
+
+        let next = std::sync::atomic::AtomicUsize::new(0);
+        let results: Mutex<Vec<Option<Result<Vec<GpuProc>>>>> =
+            Mutex::new(jobs.iter().map(|_| None).collect());
+        std::thread::scope(|scope| {
+            for _ in 0..self.snapshot_parallelism.clamp(1, jobs.len().max(1)) {
+                scope.spawn(|| loop {
+                    let job = next.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    let Some((vendor, i, _)) = jobs.get(job) else {
+                        break;
+                    };
+                    let result = retry_on_lost(vendor.as_ref(), || vendor.get_gpu_processes(*i));
+                    if let Ok(mut results) = results.lock() {
+                        results[job] = Some(result);
+                    }
+                });
+            }
+        });
+
+        // Assemble in device order so the listing does not depend on thread timing
+        let mut collection = ProcessCollection::default();
+        let results = results.into_inner().unwrap_or_else(|e| e.into_inner());
+        for ((_, i, global_offset), result) in jobs.iter().zip(results) {
+            let global_index = global_offset.saturating_add(*i as u16);
+            match result.unwrap_or_else(|| Err(anyhow::anyhow!("Process query did not run"))) {
+                Ok(mut vendor_procs) => {
+                    for proc in &mut vendor_procs {
+                        proc.gpu_index =
+                            self.reported_index(proc.gpu_index.saturating_add(*global_offset));
+                    }
+                    collection.processes.append(&mut vendor_procs)
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to get processes for GPU {}: {:#}", global_index, e);
+                    collection
+                        .unavailable
+                        .push((self.reported_index(global_index), format!("{:#}", e)));
+                }
+            }
+        }
+        crate::slurm::annotate_jobs(&mut collection.processes);
+        Ok(collection)
+    }
+
+    /// Reset a specific GPU by global index
     pub fn reset_gpu(&self, global_index: u32) -> Result<()> {
+        let global_index = self
+            .enumeration_index(global_index)
+            .ok_or_else(|| NotFound(format!("GPU index {} not found", global_index)))?;
+        let mut current_index = 0;
+        for vendor in &self.vendors {
+            let count = Self::device_count_of(vendor)?;
+            if global_index < current_index + count {
+                let local_index = global_index - current_index;
+                if !vendor.capabilities().reset_supported {
+                    return Err(unsupported_operation(vendor.vendor_type(), "GPU reset"));
+                }
+                let result = retry_on_lost(vendor.as_ref(), || vendor.reset_gpu(local_index));
+                self.invalidate_cache();
+                return result;
+            }
+            current_index += count;
+        }
+        Err(NotFound(format!("GPU index {} not found", global_index)).into())
+    }
+
+    /// Set the power limit of the GPU reported as `index`, in watts
+    pub fn set_power_limit(&self, index: u32, watts: u32) -> Result<()> {
+        self.control_device(index, "Setting the power limit", |vendor, local| {
+            vendor.set_power_limit(local, watts)
+        })
+    }
+
+    /// Lock the graphics clock of the GPU reported as `index` to `mhz`
+    pub fn lock_clocks(&self, index: u32, mhz: u32) -> Result<()> {
+        self.control_device(index, "Locking clocks", |vendor, local| {
+            vendor.lock_clocks(local, mhz)
+        })
+    }
+
+    /// Let the driver manage the clocks of the GPU reported as `index` again
+    pub fn reset_clocks(&self, index: u32) -> Result<()> {
+        self.control_device(index, "Resetting clocks", |vendor, local| {
+            vendor.reset_clocks(local)
+        })
+    }
+
+    /// Run a power or clock change against the vendor owning the GPU reported as
+    /// `index`, if that vendor supports power control
+    fn control_device(
+        &self,
+        index: u32,
+        operation: &str,
+        op: impl Fn(&(dyn GpuVendorInterface + Send + Sync), u32) -> Result<()>,
+    ) -> Result<()> {
+        let global_index = self
+            .enumeration_index(index)
+            .ok_or_else(|| NotFound(format!("GPU index {} not found", index)))?;
+        let mut current_index = 0;
+        for vendor in &self.vendors {
+            let count = Self::device_count_of(vendor)?;
+            if global_index < current_index + count {
+                if !vendor.capabilities().power_limit_supported {
+                    return Err(unsupported_operation(vendor.vendor_type(), operation));
+                }
+                let local_index = global_index - current_index;
+                let result = retry_on_lost(vendor.as_ref(), || op(vendor.as_ref(), local_index));
+                self.invalidate_cache();
+                return result;
+            }
+            current_index += count;
+        }
+        Err(NotFound(format!("GPU index {} not found", index)).into())
+    }
+
+    /// Identity of every device, with global indices
+    pub fn get_all_gpu_info(&self) -> Result<Vec<GpuInfo>> {
+        let mut devices = Vec::new();
+        let mut global_offset: u16 = 0;
+        for vendor in &self.vendors {
+            let count = Self::device_count_of(vendor)?;
+            for i in 0..count {
+                match retry_on_lost(vendor.as_ref(), || vendor.get_gpu_info(i)) {
+                    Ok(mut info) => {
+                        info.index = self.reported_index(info.index.saturating_add(global_offset));
+                        devices.push(info);
+                    }
+                    Err(e) => tracing::warn!("Failed to get info for GPU {}: {}", i, e),
+                }
+            }
+            global_offset = global_offset.saturating_add(count as u16);
+        }
+        devices.sort_by_key(|info| info.index);
+        Ok(devices)
+    }
+
+    /// Global index of the GPU with this UUID or serial number (see [`find_gpu_by_id`])
+    pub fn resolve_gpu_id(&self, id: &str) -> Result<u16> {
+        find_gpu_by_id(&self.get_all_gpu_info()?, id)
+    }
+
+    /// Vendor of the GPU at a global index
+    pub fn vendor_of(&self, global_index: u32) -> Option<GpuVendor> {
+        let global_index = self.enumeration_index(global_index)?;
         let mut current_index = 0;
         for vendor in &self.vendors {
-            let count = vendor.device_count()?;
+            let count = vendor.device_count().ok()?;
             if global_index < current_index + count {
-                let local_index = global_index - current_index;
-                return vendor.reset_gpu(local_index);
+                return Some(vendor.vendor_type());
+            }
+            current_index += count;
+        }
+        None
+    }
+
+    /// Vendor and capabilities of the device at a global index
+    pub fn capabilities_of(&self, global_index: u32) -> Option<(GpuVendor, VendorCapabilities)> {
+        let global_index = self.enumeration_index(global_index)?;
+        let mut current_index = 0;
+        for vendor in &self.vendors {
+            let count = vendor.device_count().ok()?;
+            if global_index < current_index + count {
+                return Some((vendor.vendor_type(), vendor.capabilities()));
             }
             current_index += count;
         }
-        Err(anyhow::anyhow!("GPU index {} not found", global_index))
+        None
+    }
+
+    /// How `--reset` resets the GPU at a global index
+    pub fn reset_mechanism_of(&self, global_index: u32) -> &'static str {
+        self.vendor_of(global_index)
+            .unwrap_or(GpuVendor::Unknown)
+            .reset_mechanism()
+    }
+
+    /// Fail unless the GPU at a global index exists and its vendor can reset it
+    pub fn check_resettable(&self, global_index: u32) -> Result<()> {
+        let device_count = self.total_device_count()?;
+        if device_count == 0 {
+            return Err(anyhow::anyhow!("No GPUs found"));
+        }
+        if global_index >= device_count {
+            return Err(NotFound(format!(
+                "GPU {} not found. Available GPUs: 0-{}",
+                global_index,
+                device_count - 1
+            ))
+            .into());
+        }
+        if let Some((vendor, capabilities)) = self.capabilities_of(global_index) {
+            if !capabilities.reset_supported {
+                return Err(unsupported_operation(vendor, "GPU reset"));
+            }
+        }
+        Ok(())
     }
 
     /// Get available vendors
@@ -1306,13 +2643,78 @@ impl GpuManager {
     }
 }
 
+/// Index of the device whose UUID or serial number is `id`. Matching ignores case
+/// and NVIDIA's `GPU-` prefix, so `GPU-5fd4...` and `5fd4...` name the same card.
+pub fn find_gpu_by_id(devices: &[GpuInfo], id: &str) -> Result<u16> {
+    fn normalize(value: &str) -> String {
+        let value = value.trim().to_ascii_lowercase();
+        match value.strip_prefix("gpu-") {
+            Some(rest) => rest.to_string(),
+            None => value,
+        }
+    }
+
+    let wanted = normalize(id);
+    if wanted.is_empty() {
+        anyhow::bail!("Invalid argument: GPU UUID must not be empty");
+    }
+    let matches: Vec<&GpuInfo> = devices
+        .iter()
+        .filter(|gpu| {
+            [&gpu.uuid, &gpu.serial]
+                .into_iter()
+                .flatten()
+                .any(|known| normalize(known) == wanted)
+        })
+        .collect();
+
+    match matches.as_slice() {
+        [gpu] => Ok(gpu.index),
+        [] => {
+            let known: Vec<String> = devices
+                .iter()
+                .filter_map(|gpu| {
+                    gpu.uuid
+                        .as_ref()
+                        .or(gpu.serial.as_ref())
+                        .map(|id| format!("{}={}", gpu.index, id))
+                })
+                .collect();
+            if known.is_empty() {
+                anyhow::bail!(
+                    "Invalid argument: no GPU with UUID or serial '{}' (no device reports one)",
+                    id
+                )
+            }
+            anyhow::bail!(
+                "Invalid argument: no GPU with UUID or serial '{}' (available: {})",
+                id,
+                known.join(", ")
+            )
+        }
+        several => anyhow::bail!(
+            "Invalid argument: UUID or serial '{}' matches GPUs {}",
+            id,
+            several
+                .iter()
+                .map(|gpu| gpu.index.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::nvml_api::GpuStatus;
+    use std::sync::atomic::AtomicBool;
 
     struct TestVendor {
         vendor: GpuVendor,
         count: u32,
+        /// Local device index that sleeps before answering a snapshot query
+        slow: Option<(u32, Duration)>,
     }
 
     impl GpuVendorInterface for TestVendor {
@@ -1320,6 +2722,7 @@ mod tests {
             Ok(Self {
                 vendor: GpuVendor::Unknown,
                 count: 0,
+                slow: None,
             })
         }
 
@@ -1336,20 +2739,29 @@ mod tests {
                 index: index as u16,
                 name: format!("Test GPU {}", index),
                 mem_total_mb: 1024,
+                uuid: Some(format!("GPU-{}-{}", self.vendor, index)),
+                serial: Some(format!("SN{}", 1000 + index)),
+                pci_bus_id: None,
             })
         }
 
         fn get_gpu_snapshot(&self, index: u32) -> Result<GpuSnapshot> {
+            if let Some((slow_index, delay)) = self.slow {
+                if slow_index == index {
+                    std::thread::sleep(delay);
+                }
+            }
             Ok(GpuSnapshot {
                 gpu_index: index as u16,
                 name: format!("Test GPU {}", index),
+                uuid: Some(format!("GPU-{}-{}", self.vendor, index)),
+                serial: Some(format!("SN{}", 1000 + index)),
                 vendor: self.vendor,
                 mem_used_mb: 128,
                 mem_total_mb: 1024,
                 util_pct: 10.0,
                 temp_c: 40,
                 power_w: 50.0,
-                ecc_volatile: None,
                 pids: 1,
                 top_proc: Some(GpuProc {
                     gpu_index: index as u16,
@@ -1358,9 +2770,9 @@ mod tests {
                     proc_name: "proc".to_string(),
                     used_mem_mb: 64,
                     start_time: "unknown".to_string(),
-                    container: None,
-                    node_id: None,
+                    ..Default::default()
                 }),
+                ..Default::default()
             })
         }
 
@@ -1372,8 +2784,7 @@ mod tests {
                 proc_name: "proc".to_string(),
                 used_mem_mb: 32,
                 start_time: "unknown".to_string(),
-                container: None,
-                node_id: None,
+                ..Default::default()
             }])
         }
 
@@ -1381,6 +2792,10 @@ mod tests {
             Ok(())
         }
 
+        fn capabilities(&self) -> VendorCapabilities {
+            self.vendor.capabilities()
+        }
+
         fn is_available() -> bool {
             true
         }
@@ -1391,20 +2806,359 @@ mod tests {
     }
 
     #[test]
-    fn test_global_gpu_index_normalization() {
-        let manager = GpuManager {
-            vendors: vec![
-                Box::new(TestVendor {
+    fn test_vendor_capabilities() {
+        let runner = CountingRunner::new();
+        let amd = AmdVendor::with_runner(runner.clone());
+        let intel = IntelVendor::with_runner(runner);
+        assert_eq!(amd.capabilities(), GpuVendor::Amd.capabilities());
+        assert_eq!(intel.capabilities(), GpuVendor::Intel.capabilities());
+
+        let nvidia = GpuVendor::Nvidia.capabilities();
+        assert!(nvidia.reset_supported);
+        assert!(nvidia.process_enum_supported);
+        assert!(nvidia.power_limit_supported);
+        assert!(nvidia.util_supported);
+
+        let amd = amd.capabilities();
+        assert!(amd.reset_supported);
+        assert!(!amd.process_enum_supported);
+        assert!(amd.power_limit_supported);
+        assert!(amd.util_supported);
+
+        let intel = intel.capabilities();
+        assert!(!intel.reset_supported);
+        assert!(!intel.process_enum_supported);
+        assert!(!intel.power_limit_supported);
+        assert!(intel.util_supported);
+
+        let apple = GpuVendor::Apple.capabilities();
+        assert!(!apple.reset_supported);
+        assert!(apple.process_enum_supported);
+        assert!(!apple.power_limit_supported);
+        assert!(!apple.util_supported);
+
+        assert_eq!(GpuVendor::Unknown.capabilities(), VendorCapabilities::NONE);
+    }
+
+    #[test]
+    fn test_unsupported_reset_points_at_capabilities() {
+        let manager = GpuManager::with_vendors(vec![
+            Arc::new(TestVendor {
+                vendor: GpuVendor::Nvidia,
+                count: 1,
+                slow: None,
+            }),
+            Arc::new(TestVendor {
+                vendor: GpuVendor::Intel,
+                count: 1,
+                slow: None,
+            }),
+        ]);
+
+        assert!(manager.reset_gpu(0).is_ok());
+        let message = manager.reset_gpu(1).unwrap_err().to_string();
+        assert!(message.contains("not supported on Intel GPUs"));
+        assert!(message.contains("gpukill --capabilities"));
+        assert_eq!(
+            crate::report::ErrorKind::classify(&message),
+            crate::report::ErrorKind::Unsupported
+        );
+        assert_eq!(
+            manager.capabilities_of(1),
+            Some((GpuVendor::Intel, VendorCapabilities::INTEL))
+        );
+        assert_eq!(manager.capabilities_of(2), None);
+    }
+
+    /// Wraps a [`TestVendor`] and fails queries for chosen devices, the way NVML
+    /// does when a GPU falls off the bus or the driver restarts
+    struct FlakyVendor {
+        inner: TestVendor,
+        /// Local indices that fail every query with GPU_IS_LOST
+        lost: HashSet<u32>,
+        /// Queries fail with NVML_ERROR_UNINITIALIZED until re-initialized
+        stale_handle: std::sync::atomic::AtomicBool,
+        reinitialized: std::sync::atomic::AtomicUsize,
+    }
+
+    impl FlakyVendor {
+        fn new(count: u32, lost: &[u32], stale_handle: bool) -> Self {
+            Self {
+                inner: TestVendor {
                     vendor: GpuVendor::Nvidia,
-                    count: 1,
-                }),
-                Box::new(TestVendor {
-                    vendor: GpuVendor::Amd,
-                    count: 1,
-                }),
-            ],
+                    count,
+                    slow: None,
+                },
+                lost: lost.iter().copied().collect(),
+                stale_handle: std::sync::atomic::AtomicBool::new(stale_handle),
+                reinitialized: std::sync::atomic::AtomicUsize::new(0),
+            }
+        }
+
+        fn check(&self, index: u32) -> Result<()> {
+            use std::sync::atomic::Ordering;
+            if self.stale_handle.load(Ordering::SeqCst) {
+                return Err(anyhow::anyhow!(
+                    "Failed to get device at index {}: Uninitialized",
+                    index
+                ));
+            }
+            if self.lost.contains(&index) {
+                return Err(anyhow::anyhow!(
+                    "Failed to get device at index {}: GpuLost",
+                    index
+                ));
+            }
+            Ok(())
+        }
+    }
+
+    impl GpuVendorInterface for FlakyVendor {
+        fn initialize() -> Result<Self> {
+            Ok(Self::new(0, &[], false))
+        }
+
+        fn vendor_type(&self) -> GpuVendor {
+            self.inner.vendor_type()
+        }
+
+        fn device_count(&self) -> Result<u32> {
+            self.inner.device_count()
+        }
+
+        fn get_gpu_info(&self, index: u32) -> Result<GpuInfo> {
+            self.check(index)?;
+            self.inner.get_gpu_info(index)
+        }
+
+        fn get_gpu_snapshot(&self, index: u32) -> Result<GpuSnapshot> {
+            self.check(index)?;
+            self.inner.get_gpu_snapshot(index)
+        }
+
+        fn get_gpu_processes(&self, index: u32) -> Result<Vec<GpuProc>> {
+            self.check(index)?;
+            self.inner.get_gpu_processes(index)
+        }
+
+        fn reset_gpu(&self, index: u32) -> Result<()> {
+            self.check(index)?;
+            self.inner.reset_gpu(index)
+        }
+
+        fn capabilities(&self) -> VendorCapabilities {
+            VendorCapabilities::NVIDIA
+        }
+
+        fn reinitialize(&self) -> Result<()> {
+            use std::sync::atomic::Ordering;
+            self.stale_handle.store(false, Ordering::SeqCst);
+            self.reinitialized.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn is_available() -> bool {
+            true
+        }
+
+        fn get_availability_error() -> String {
+            String::new()
+        }
+    }
+
+    #[test]
+    fn test_is_device_lost() {
+        assert!(is_device_lost(&anyhow::anyhow!(
+            "Failed to get device: GpuLost"
+        )));
+        assert!(is_device_lost(&anyhow::anyhow!("Failed: Uninitialized")));
+        assert!(is_device_lost(
+            &anyhow::anyhow!("GPU has been lost and needs to be reset.").context("GPU 1")
+        ));
+        assert!(!is_device_lost(&anyhow::anyhow!("Failed: NoPermission")));
+    }
+
+    #[test]
+    fn test_profiling_metrics_added_to_nvidia_snapshots() {
+        use crate::dcgm::{ProfilingMetrics, StaticProfilingProvider};
+
+        let vendors: Vec<Arc<dyn GpuVendorInterface + Send + Sync>> = vec![
+            Arc::new(TestVendor {
+                vendor: GpuVendor::Amd,
+                count: 1,
+                slow: None,
+            }),
+            Arc::new(TestVendor {
+                vendor: GpuVendor::Nvidia,
+                count: 2,
+                slow: None,
+            }),
+        ];
+        let metrics = ProfilingMetrics {
+            sm_active: Some(0.8),
+            dram_active: Some(0.3),
+            ..Default::default()
+        };
+
+        // Without DCGM nothing changes
+        let manager = GpuManager::with_vendors(vendors.clone());
+        let snapshots = manager.get_all_snapshots().unwrap();
+        assert!(snapshots.iter().all(|gpu| gpu.profiling.is_none()));
+
+        // Metrics are looked up by NVML index: global GPU 2 is NVIDIA device 1
+        let provider = StaticProfilingProvider::new(HashMap::from([(0, metrics), (1, metrics)]));
+        let manager = GpuManager::with_vendors(vendors).with_profiling_provider(Arc::new(provider));
+        let snapshots = manager.get_all_snapshots().unwrap();
+        let profiled: Vec<(u16, bool)> = snapshots
+            .iter()
+            .map(|gpu| (gpu.gpu_index, gpu.profiling.is_some()))
+            .collect();
+        assert_eq!(profiled, vec![(0, false), (1, true), (2, true)]);
+        assert_eq!(snapshots[2].profiling, Some(metrics));
+    }
+
+    #[test]
+    fn test_lost_gpu_is_skipped_and_reported() {
+        let flaky = Arc::new(FlakyVendor::new(3, &[1], false));
+        let manager = GpuManager::with_vendors(vec![
+            flaky.clone(),
+            Arc::new(TestVendor {
+                vendor: GpuVendor::Amd,
+                count: 1,
+                slow: None,
+            }),
+        ]);
+
+        let collection = manager.collect_snapshots().unwrap();
+        assert_eq!(
+            collection
+                .snapshots
+                .iter()
+                .map(|gpu| gpu.gpu_index)
+                .collect::<Vec<_>>(),
+            vec![0, 2, 3]
+        );
+        assert!(collection.is_degraded());
+        assert_eq!(collection.errors.len(), 1);
+        assert_eq!(collection.errors[0].gpu_index, 1);
+        assert_eq!(collection.errors[0].vendor, GpuVendor::Nvidia);
+        assert!(collection.errors[0].error.starts_with("NVML: "));
+        assert!(collection.errors[0].error.contains("GpuLost"));
+        let status: Vec<(u16, GpuStatus)> = collection
+            .gpu_status()
+            .iter()
+            .map(|entry| (entry.gpu_index, entry.status))
+            .collect();
+        assert_eq!(
+            status,
+            vec![
+                (0, GpuStatus::Ok),
+                (1, GpuStatus::Unavailable),
+                (2, GpuStatus::Ok),
+                (3, GpuStatus::Ok),
+            ]
+        );
+
+        // The handle was re-opened once per failed query, but the GPU stays lost
+        assert!(
+            flaky
+                .reinitialized
+                .load(std::sync::atomic::Ordering::SeqCst)
+                >= 1
+        );
+
+        let processes = manager.collect_processes().unwrap();
+        assert_eq!(processes.processes.len(), 3);
+        assert_eq!(processes.unavailable.len(), 1);
+        assert_eq!(processes.unavailable[0].0, 1);
+
+        // Only the lost GPU fails to reset; global indices stay put
+        assert!(manager.reset_gpu(0).is_ok());
+        assert!(manager
+            .reset_gpu(1)
+            .unwrap_err()
+            .to_string()
+            .contains("GpuLost"));
+        assert!(manager.reset_gpu(3).is_ok());
+        assert_eq!(manager.get_all_gpu_info().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_stale_handle_is_reinitialized_and_retried() {
+        let flaky = Arc::new(FlakyVendor::new(2, &[], true));
+        let manager = GpuManager::with_vendor(flaky.clone());
+
+        let collection = manager.collect_snapshots().unwrap();
+        assert_eq!(collection.snapshots.len(), 2);
+        assert!(!collection.is_degraded());
+        assert!(collection
+            .gpu_status()
+            .iter()
+            .all(|entry| entry.status == GpuStatus::Ok));
+        assert!(
+            flaky
+                .reinitialized
+                .load(std::sync::atomic::Ordering::SeqCst)
+                >= 1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_node_snapshot_degraded_when_gpu_lost() {
+        use crate::coordinator::{NodeSnapshot, NodeStatus};
+
+        let collect = |manager: GpuManager| async move {
+            NodeSnapshot::collect(
+                &manager,
+                "node-a".to_string(),
+                "host-a".to_string(),
+                HashMap::new(),
+            )
+            .await
+            .unwrap()
         };
 
+        let degraded = collect(GpuManager::with_vendor(Arc::new(FlakyVendor::new(
+            2,
+            &[0],
+            false,
+        ))))
+        .await;
+        assert!(matches!(degraded.status, NodeStatus::Degraded));
+        assert_eq!(degraded.gpus.len(), 1);
+        assert_eq!(degraded.gpus[0].gpu_index, 1);
+        assert_eq!(degraded.errors.len(), 1);
+        assert_eq!(degraded.errors[0].gpu_index, 0);
+        let json = serde_json::to_value(&degraded).unwrap();
+        assert_eq!(json["errors"][0]["vendor"], "Nvidia");
+        assert_eq!(json["errors"][0]["status"], "unavailable");
+
+        let healthy = collect(GpuManager::with_vendor(Arc::new(FlakyVendor::new(
+            2,
+            &[],
+            false,
+        ))))
+        .await;
+        assert!(matches!(healthy.status, NodeStatus::Online));
+        assert_eq!(healthy.gpus.len(), 2);
+        assert!(healthy.errors.is_empty());
+    }
+
+    #[test]
+    fn test_global_gpu_index_normalization() {
+        let manager = GpuManager::with_vendors(vec![
+            Arc::new(TestVendor {
+                vendor: GpuVendor::Nvidia,
+                count: 1,
+                slow: None,
+            }),
+            Arc::new(TestVendor {
+                vendor: GpuVendor::Amd,
+                count: 1,
+                slow: None,
+            }),
+        ]);
+
         let snapshots = manager.get_all_snapshots().unwrap();
         assert_eq!(snapshots.len(), 2);
         assert_eq!(snapshots[0].gpu_index, 0);
@@ -1416,5 +3170,613 @@ mod tests {
         assert_eq!(processes.len(), 2);
         assert_eq!(processes[0].gpu_index, 0);
         assert_eq!(processes[1].gpu_index, 1);
+
+        assert_eq!(manager.vendor_of(0), Some(GpuVendor::Nvidia));
+        assert_eq!(manager.vendor_of(1), Some(GpuVendor::Amd));
+        assert_eq!(manager.vendor_of(2), None);
+    }
+
+    #[test]
+    fn test_stable_indices_survive_vendor_reordering() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(crate::gpu_index::INDEX_MAP_FILE);
+        let nvidia = || -> Arc<dyn GpuVendorInterface + Send + Sync> {
+            Arc::new(TestVendor {
+                vendor: GpuVendor::Nvidia,
+                count: 2,
+                slow: None,
+            })
+        };
+        let amd = || -> Arc<dyn GpuVendorInterface + Send + Sync> {
+            Arc::new(TestVendor {
+                vendor: GpuVendor::Amd,
+                count: 1,
+                slow: None,
+            })
+        };
+
+        // First run records NVIDIA 0-1 and AMD 2
+        GpuManager::with_vendors(vec![nvidia(), amd()])
+            .with_stable_indices_at(&path)
+            .unwrap();
+
+        // Enumerated the other way round, every GPU keeps its index
+        let manager = GpuManager::with_vendors(vec![amd(), nvidia()])
+            .with_stable_indices_at(&path)
+            .unwrap();
+        let snapshots = manager.get_all_snapshots().unwrap();
+        let by_index: Vec<(u16, Option<&str>)> = snapshots
+            .iter()
+            .map(|gpu| (gpu.gpu_index, gpu.uuid.as_deref()))
+            .collect();
+        assert_eq!(
+            by_index,
+            vec![
+                (0, Some("GPU-NVIDIA-0")),
+                (1, Some("GPU-NVIDIA-1")),
+                (2, Some("GPU-AMD-0")),
+            ]
+        );
+        assert_eq!(snapshots[2].top_proc.as_ref().unwrap().gpu_index, 2);
+
+        // The AMD process ran on enumeration index 0
+        let mut processes: Vec<(u16, u32)> = manager
+            .get_all_processes()
+            .unwrap()
+            .iter()
+            .map(|p| (p.gpu_index, p.pid))
+            .collect();
+        processes.sort_unstable();
+        assert_eq!(processes, vec![(0, 2000), (1, 2001), (2, 2000)]);
+
+        assert_eq!(manager.vendor_of(2), Some(GpuVendor::Amd));
+        assert_eq!(manager.vendor_of(0), Some(GpuVendor::Nvidia));
+        assert_eq!(manager.resolve_gpu_id("GPU-AMD-0").unwrap(), 2);
+        assert!(manager.reset_gpu(3).is_err());
+        assert_eq!(manager.stable_indices().unwrap().entries().len(), 3);
+    }
+
+    #[test]
+    fn test_resolve_gpu_id_maps_uuid_to_global_index() {
+        let manager = GpuManager::with_vendors(vec![
+            Arc::new(TestVendor {
+                vendor: GpuVendor::Nvidia,
+                count: 2,
+                slow: None,
+            }),
+            Arc::new(TestVendor {
+                vendor: GpuVendor::Amd,
+                count: 2,
+                slow: None,
+            }),
+        ]);
+
+        assert_eq!(manager.resolve_gpu_id("GPU-NVIDIA-1").unwrap(), 1);
+        assert_eq!(manager.resolve_gpu_id("GPU-AMD-1").unwrap(), 3);
+        // Case and the GPU- prefix do not matter
+        assert_eq!(manager.resolve_gpu_id("amd-0").unwrap(), 2);
+
+        let err = manager
+            .resolve_gpu_id("GPU-NVIDIA-7")
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("Invalid argument"), "{}", err);
+        assert!(err.contains("3=GPU-AMD-1"), "{}", err);
+
+        // Serial numbers are accepted too, but must be unique
+        let err = manager.resolve_gpu_id("SN1001").unwrap_err().to_string();
+        assert!(err.contains("matches GPUs 1, 3"), "{}", err);
+
+        let infos = manager.get_all_gpu_info().unwrap();
+        assert_eq!(
+            infos.iter().map(|gpu| gpu.index).collect::<Vec<_>>(),
+            vec![0, 1, 2, 3]
+        );
+        assert_eq!(find_gpu_by_id(&infos[..1], "sn1000").unwrap(), 0);
+        assert!(find_gpu_by_id(&infos, " ").is_err());
+    }
+
+    #[test]
+    fn test_snapshot_collection_times_out_slow_device() {
+        let manager = GpuManager::with_vendors(vec![
+            Arc::new(TestVendor {
+                vendor: GpuVendor::Nvidia,
+                count: 4,
+                slow: Some((1, Duration::from_secs(5))),
+            }),
+            Arc::new(TestVendor {
+                vendor: GpuVendor::Amd,
+                count: 4,
+                slow: None,
+            }),
+        ])
+        .with_snapshot_timeout(Duration::from_millis(200))
+        .with_snapshot_parallelism(2);
+
+        let started = Instant::now();
+        let collection = manager.collect_snapshots().unwrap();
+        let elapsed = started.elapsed();
+
+        assert!(elapsed < Duration::from_secs(2), "took {:?}", elapsed);
+        assert_eq!(collection.errors.len(), 1);
+        assert_eq!(collection.errors[0].gpu_index, 1);
+        assert_eq!(collection.errors[0].status, GpuStatus::TimedOut);
+        assert_eq!(collection.errors[0].error, "NVML timed out after 200ms");
+        let indices: Vec<u16> = collection.snapshots.iter().map(|s| s.gpu_index).collect();
+        assert_eq!(indices, vec![0, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(collection.snapshots[3].vendor, GpuVendor::Amd);
+    }
+
+    const ROCM_SMI_JSON: &str = r#"WARNING: AMD GPU device(s) is/are in a low-power state. Check power control/runtime_status
+{"card0": {"GPU use (%)": "37", "Temperature (Sensor edge) (C)": "45.0", "Temperature (Sensor junction) (C)": "48.0", "Average Graphics Package Power (W)": "134.0", "VRAM Total Memory (B)": "68702699520", "VRAM Total Used Memory (B)": "1073741824", "Card series": "AMD Instinct MI250X", "Unique ID": "0x18f68e602b8a790f", "Serial Number": "PCB052715-0071", "PCI Bus": "0000:C1:00.0"},
+ "card1": {"GPU use (%)": "0", "Temperature (Sensor junction) (C)": "39.5", "Current Socket Graphics Package Power (W)": "88.0", "VRAM Total Memory (B)": "68702699520", "VRAM Total Used Memory (B)": "0", "Card Series": "AMD Instinct MI250X", "Unique ID": "N/A"},
+ "system": {"Driver version": "6.7.0"}}"#;
+
+    /// Answers `rocm-smi` with canned JSON and counts how often each tool ran
+    struct CountingRunner {
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl CountingRunner {
+        fn new() -> Arc<Self> {
+            Arc::new(Self {
+                calls: Mutex::new(Vec::new()),
+            })
+        }
+
+        fn count(&self, program: &str) -> usize {
+            self.calls
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|p| *p == program)
+                .count()
+        }
+    }
+
+    impl CommandRunner for CountingRunner {
+        fn run(&self, program: &str, args: &[&str]) -> Result<crate::command::CommandOutput> {
+            self.calls.lock().unwrap().push(program.to_string());
+            assert_eq!(args, ROCM_SMI_QUERY_ARGS);
+            Ok(crate::command::CommandOutput {
+                success: true,
+                stdout: ROCM_SMI_JSON.to_string(),
+                stderr: String::new(),
+                code: None,
+            })
+        }
+    }
+
+    /// Answers the `rocm-smi` device query with canned JSON and records every other
+    /// invocation, failing those with `fail_with` on stderr when set
+    struct ControlRunner {
+        calls: Mutex<Vec<Vec<String>>>,
+        fail_with: Option<&'static str>,
+    }
+
+    impl CommandRunner for ControlRunner {
+        fn run(&self, _program: &str, args: &[&str]) -> Result<crate::command::CommandOutput> {
+            if args == ROCM_SMI_QUERY_ARGS {
+                return Ok(crate::command::CommandOutput {
+                    success: true,
+                    stdout: ROCM_SMI_JSON.to_string(),
+                    stderr: String::new(),
+                    code: None,
+                });
+            }
+            self.calls
+                .lock()
+                .unwrap()
+                .push(args.iter().map(|a| a.to_string()).collect());
+            Ok(crate::command::CommandOutput {
+                success: self.fail_with.is_none(),
+                stdout: String::new(),
+                stderr: self.fail_with.unwrap_or_default().to_string(),
+                code: None,
+            })
+        }
+    }
+
+    /// Answers `rocm-smi` queries until `wedged` is set, then behaves like a
+    /// tripped breaker
+    struct WedgingRunner {
+        wedged: AtomicBool,
+    }
+
+    impl CommandRunner for WedgingRunner {
+        fn run(&self, program: &str, _args: &[&str]) -> Result<crate::command::CommandOutput> {
+            if self.wedged.load(Ordering::SeqCst) {
+                return Err(crate::command::ToolError::Skipped {
+                    program: program.to_string(),
+                    retry_in: Duration::from_secs(42),
+                }
+                .into());
+            }
+            Ok(crate::command::CommandOutput {
+                success: true,
+                stdout: ROCM_SMI_JSON.to_string(),
+                stderr: String::new(),
+                code: None,
+            })
+        }
+    }
+
+    #[test]
+    fn test_skipped_rocm_smi_keeps_gpus_listed_as_degraded() {
+        let runner = Arc::new(WedgingRunner {
+            wedged: AtomicBool::new(false),
+        });
+        let amd = AmdVendor::with_runner(runner.clone()).with_cache_ttl(Duration::ZERO);
+        let manager =
+            GpuManager::with_vendor(Arc::new(amd)).with_snapshot_cache_ttl(Duration::ZERO);
+
+        let healthy = manager.collect_snapshots().unwrap();
+        let count = healthy.snapshots.len();
+        assert!(count > 0);
+        assert!(healthy.errors.is_empty());
+
+        // No lspci placeholder: the devices seen last are reported as degraded
+        runner.wedged.store(true, Ordering::SeqCst);
+        let wedged = manager.collect_snapshots().unwrap();
+        assert!(wedged.snapshots.is_empty());
+        assert_eq!(wedged.errors.len(), count);
+        assert!(wedged
+            .errors
+            .iter()
+            .all(|error| error.status == GpuStatus::Degraded));
+        assert!(
+            wedged.errors[0]
+                .error
+                .contains("skipped after repeated failures"),
+            "{}",
+            wedged.errors[0].error
+        );
+    }
+
+    #[test]
+    fn test_power_and_clock_controls_route_to_rocm_smi() {
+        let runner = Arc::new(ControlRunner {
+            calls: Mutex::new(Vec::new()),
+            fail_with: None,
+        });
+        let manager = GpuManager::with_vendors(vec![
+            Arc::new(TestVendor {
+                vendor: GpuVendor::Intel,
+                count: 1,
+                slow: None,
+            }),
+            Arc::new(AmdVendor::with_runner(runner.clone())),
+        ]);
+
+        manager.set_power_limit(1, 250).unwrap();
+        manager.lock_clocks(2, 1410).unwrap();
+        manager.reset_clocks(2).unwrap();
+        let calls: Vec<String> = runner
+            .calls
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|args| args.join(" "))
+            .collect();
+        assert_eq!(
+            calls,
+            vec![
+                "-d 0 --setpoweroverdrive 250 --autorespond y",
+                "-d 1 --setsrange 1410 1410 --autorespond y",
+                "-d 1 --resetclocks --autorespond y",
+            ]
+        );
+
+        let message = manager.set_power_limit(0, 250).unwrap_err().to_string();
+        assert!(
+            message.contains("not supported on Intel GPUs"),
+            "{}",
+            message
+        );
+        assert!(manager.lock_clocks(3, 1410).is_err());
+
+        let denied = GpuManager::with_vendors(vec![Arc::new(AmdVendor::with_runner(Arc::new(
+            ControlRunner {
+                calls: Mutex::new(Vec::new()),
+                fail_with: Some("Permission denied"),
+            },
+        )))]);
+        let message = denied.set_power_limit(0, 250).unwrap_err().to_string();
+        assert!(message.contains("requires root"), "{}", message);
+        assert_eq!(
+            crate::report::ErrorKind::classify(&message),
+            crate::report::ErrorKind::PermissionDenied
+        );
+    }
+
+    #[test]
+    fn test_parse_rocm_smi_json() {
+        let devices = parse_rocm_smi_json(ROCM_SMI_JSON).unwrap();
+        assert_eq!(devices.len(), 2);
+        assert_eq!(
+            devices[0],
+            AmdDeviceReading {
+                name: "AMD Instinct MI250X".to_string(),
+                uuid: Some("0x18f68e602b8a790f".to_string()),
+                serial: Some("PCB052715-0071".to_string()),
+                pci_bus_id: Some("0000:C1:00.0".to_string()),
+                mem_total_mb: 65520,
+                mem_used_mb: 1024,
+                util_pct: 37.0,
+                temp_c: 45,
+                power_w: 134.0,
+            }
+        );
+        // Junction temperature and socket power are used when edge/average are missing
+        assert_eq!(devices[1].temp_c, 40);
+        assert_eq!(devices[1].power_w, 88.0);
+        assert_eq!(devices[1].name, "AMD Instinct MI250X");
+        assert_eq!(devices[1].uuid, None);
+
+        assert!(parse_rocm_smi_json("rocm-smi: command not found").is_err());
+    }
+
+    #[test]
+    fn test_parse_lspci_amd() {
+        let stdout = "\
+00:02.0 VGA compatible controller: Intel Corporation UHD Graphics 630
+03:00.0 VGA compatible controller: Advanced Micro Devices, Inc. [AMD/ATI] Navi 21 [Radeon RX 6800]
+04:00.0 Audio device: Advanced Micro Devices, Inc. [AMD/ATI] Navi 21 HDMI Audio";
+        assert_eq!(
+            parse_lspci_amd(stdout),
+            vec!["Advanced Micro Devices, Inc. [AMD/ATI] Navi 21 [Radeon RX 6800]".to_string()]
+        );
+    }
+
+    /// Counts snapshot and process queries reaching the vendor
+    struct CountingVendor {
+        inner: TestVendor,
+        snapshots: std::sync::atomic::AtomicUsize,
+        fast_snapshots: std::sync::atomic::AtomicUsize,
+        processes: std::sync::atomic::AtomicUsize,
+        /// Time each process query takes
+        process_delay: Duration,
+    }
+
+    impl GpuVendorInterface for CountingVendor {
+        fn initialize() -> Result<Self> {
+            Ok(Self {
+                inner: TestVendor::initialize()?,
+                snapshots: Default::default(),
+                fast_snapshots: Default::default(),
+                processes: Default::default(),
+                process_delay: Duration::ZERO,
+            })
+        }
+
+        fn vendor_type(&self) -> GpuVendor {
+            self.inner.vendor_type()
+        }
+
+        fn device_count(&self) -> Result<u32> {
+            self.inner.device_count()
+        }
+
+        fn get_gpu_info(&self, index: u32) -> Result<GpuInfo> {
+            self.inner.get_gpu_info(index)
+        }
+
+        fn get_gpu_snapshot(&self, index: u32) -> Result<GpuSnapshot> {
+            self.snapshots
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            self.inner.get_gpu_snapshot(index)
+        }
+
+        fn get_gpu_snapshot_fast(&self, index: u32) -> Result<GpuSnapshot> {
+            self.fast_snapshots
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            self.inner.get_gpu_snapshot(index)
+        }
+
+        fn get_gpu_processes(&self, index: u32) -> Result<Vec<GpuProc>> {
+            self.processes
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            std::thread::sleep(self.process_delay);
+            self.inner.get_gpu_processes(index)
+        }
+
+        fn reset_gpu(&self, _index: u32) -> Result<()> {
+            Ok(())
+        }
+
+        fn capabilities(&self) -> VendorCapabilities {
+            GpuVendor::Nvidia.capabilities()
+        }
+
+        fn is_available() -> bool {
+            true
+        }
+
+        fn get_availability_error() -> String {
+            String::new()
+        }
+    }
+
+    #[test]
+    fn test_snapshot_cache_reuses_results_within_ttl() {
+        use std::sync::atomic::Ordering;
+
+        let counting = |ttl: Duration| {
+            let vendor = Arc::new(CountingVendor {
+                inner: TestVendor {
+                    vendor: GpuVendor::Nvidia,
+                    count: 2,
+                    slow: None,
+                },
+                snapshots: Default::default(),
+                fast_snapshots: Default::default(),
+                processes: Default::default(),
+                process_delay: Duration::ZERO,
+            });
+            let manager =
+                GpuManager::with_vendors(vec![vendor.clone()]).with_snapshot_cache_ttl(ttl);
+            (vendor, manager)
+        };
+        let calls = |vendor: &CountingVendor| {
+            (
+                vendor.snapshots.load(Ordering::SeqCst),
+                vendor.processes.load(Ordering::SeqCst),
+            )
+        };
+
+        // Repeated queries within the TTL reach each device once, also through clones
+        let (vendor, manager) = counting(Duration::from_secs(60));
+        for _ in 0..3 {
+            assert_eq!(manager.get_all_snapshots().unwrap().len(), 2);
+            manager.get_all_processes().unwrap();
+            manager.clone().get_all_snapshots().unwrap();
+        }
+        assert_eq!(calls(&vendor), (2, 2));
+
+        // A reset or an explicit invalidation (after a kill) forces a fresh query
+        manager.reset_gpu(0).unwrap();
+        manager.get_all_snapshots().unwrap();
+        manager.get_all_processes().unwrap();
+        assert_eq!(calls(&vendor), (4, 4));
+        manager.invalidate_cache();
+        manager.get_all_snapshots().unwrap();
+        assert_eq!(calls(&vendor), (6, 4));
+
+        // Once the TTL passes the devices are queried again
+        let (vendor, manager) = counting(Duration::from_millis(50));
+        manager.get_all_snapshots().unwrap();
+        std::thread::sleep(Duration::from_millis(80));
+        manager.get_all_snapshots().unwrap();
+        assert_eq!(calls(&vendor), (4, 0));
+
+        // A zero TTL disables the cache
+        let (vendor, manager) = counting(Duration::ZERO);
+        manager.get_all_snapshots().unwrap();
+        manager.get_all_snapshots().unwrap();
+        manager.get_all_processes().unwrap();
+        manager.get_all_processes().unwrap();
+        assert_eq!(calls(&vendor), (4, 4));
+    }
+
+    #[test]
+    fn test_processes_are_queried_in_parallel_and_fast_mode_skips_full_snapshots() {
+        use std::sync::atomic::Ordering;
+
+        let vendor = Arc::new(CountingVendor {
+            inner: TestVendor {
+                vendor: GpuVendor::Nvidia,
+                count: 8,
+                slow: None,
+            },
+            snapshots: Default::default(),
+            fast_snapshots: Default::default(),
+            processes: Default::default(),
+            process_delay: Duration::from_millis(100),
+        });
+        let manager = GpuManager::with_vendors(vec![vendor.clone()])
+            .with_snapshot_cache_ttl(Duration::ZERO)
+            .with_snapshot_parallelism(4);
+
+        // Eight 100ms queries on four threads take two rounds instead of eight
+        let started = Instant::now();
+        let processes = manager.get_all_processes().unwrap();
+        let elapsed = started.elapsed();
+        assert!(elapsed < Duration::from_millis(500), "took {:?}", elapsed);
+        let indices: Vec<u16> = processes.iter().map(|p| p.gpu_index).collect();
+        assert_eq!(indices, (0..8).collect::<Vec<u16>>());
+        assert_eq!(vendor.processes.load(Ordering::SeqCst), 8);
+
+        manager.get_all_snapshots().unwrap();
+        let fast = manager.with_fast_mode(true);
+        assert_eq!(fast.get_all_snapshots().unwrap().len(), 8);
+        assert_eq!(vendor.snapshots.load(Ordering::SeqCst), 8);
+        assert_eq!(vendor.fast_snapshots.load(Ordering::SeqCst), 8);
+    }
+
+    #[test]
+    fn test_amd_refresh_runs_rocm_smi_once() {
+        let runner = CountingRunner::new();
+        let amd = AmdVendor::with_runner(runner.clone()).with_cache_ttl(Duration::from_millis(200));
+        // Without the manager's own cache, so every refresh reaches the vendor
+        let manager = GpuManager::with_vendors(vec![Arc::new(amd)])
+            .with_snapshot_parallelism(2)
+            .with_snapshot_cache_ttl(Duration::ZERO);
+
+        // One refresh: device discovery, parallel snapshots and the process listing
+        let snapshots = manager.get_all_snapshots().unwrap();
+        manager.get_all_processes().unwrap();
+        assert_eq!(runner.count("rocm-smi"), 1);
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(snapshots[0].util_pct, 37.0);
+        assert_eq!(snapshots[1].gpu_index, 1);
+        assert_eq!(snapshots[1].vendor, GpuVendor::Amd);
+
+        // The next refresh after the TTL queries the devices again, once
+        std::thread::sleep(Duration::from_millis(250));
+        manager.get_all_snapshots().unwrap();
+        assert_eq!(runner.count("rocm-smi"), 2);
+    }
+
+    #[test]
+    fn test_async_snapshots_run_off_the_runtime_thread() {
+        let manager = GpuManager::with_vendors(vec![Arc::new(TestVendor {
+            vendor: GpuVendor::Nvidia,
+            count: 2,
+            slow: None,
+        })]);
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let (snapshots, processes) = rt.block_on(async {
+            (
+                manager.get_all_snapshots_async().await.unwrap(),
+                manager.get_all_processes_async().await.unwrap(),
+            )
+        });
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(processes.len(), 2);
+    }
+
+    #[test]
+    fn test_mock_vendor_serves_fixture_and_injects_failures() {
+        let fixture: MockFixture = serde_json::from_str(
+            r#"{"gpus": [
+                {"name": "Mock A100", "mem_total_mb": 81920, "util_pct": 45.0, "temp_c": 60,
+                 "processes": [
+                    {"pid": 4242, "user": "alice", "name": "python", "used_mem_mb": 20480},
+                    {"pid": 4243, "user": "bob", "name": "python", "used_mem_mb": 1024}
+                 ],
+                 "failures": [{"call": "snapshot", "nth": 2, "message": "GPU is lost"}]},
+                {"name": "Mock A100", "mem_total_mb": 81920,
+                 "failures": [{"call": "reset", "message": "Reset is not supported"}]}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(fixture.vendor, GpuVendor::Nvidia);
+        let vendor = Arc::new(MockVendor::new(fixture));
+        let manager = vendor.manager();
+
+        let first = manager.collect_snapshots().unwrap();
+        assert!(!first.is_degraded());
+        let gpu = &first.snapshots[0];
+        assert_eq!((gpu.mem_used_mb, gpu.pids, gpu.util_pct), (21504, 2, 45.0));
+        assert_eq!(gpu.top_proc.as_ref().unwrap().pid, 4242);
+
+        // Only the second snapshot of GPU 0 fails
+        let second = manager.collect_snapshots().unwrap();
+        assert_eq!(second.snapshots.len(), 1);
+        assert_eq!(second.errors[0].gpu_index, 0);
+        assert!(second.errors[0].error.contains("GPU is lost"));
+        assert!(!manager.collect_snapshots().unwrap().is_degraded());
+        assert_eq!(vendor.calls(0, MockCall::Snapshot), 3);
+
+        assert!(manager.reset_gpu(0).is_ok());
+        assert!(manager.reset_gpu(1).is_err());
+        assert_eq!(vendor.calls(1, MockCall::Reset), 1);
+
+        // Fixture changes are seen by the next call
+        vendor.update(|fixture| fixture.gpus[0].processes.clear());
+        let processes = manager.get_all_processes().unwrap();
+        assert!(processes.is_empty());
     }
 }
@@ -1,14 +1,12 @@
-use clap::Parser;
-use gpukill::args::{Cli, OutputFormat, VendorFilter};
-use gpukill::nvml_api::{GpuInfo, GpuProc, GpuSnapshot, Snapshot};
-use gpukill::process_mgmt::EnhancedProcessManager;
-use gpukill::render::Renderer;
+use gpukill::args::VendorFilter;
 use gpukill::vendor::{AmdVendor, GpuVendor, GpuVendorInterface, NvidiaVendor};
 use std::process::Command;
 
 #[cfg(feature = "mock_nvml")]
 mod mock_tests {
     use super::*;
+    use clap::Parser;
+    use gpukill::args::{Cli, OutputFormat};
 
     #[test]
     fn test_list_operation_parsing() {
@@ -39,7 +37,7 @@ mod mock_tests {
     fn test_kill_operation() {
         let cli = Cli::parse_from(["gpukill", "--kill", "--pid", "12345"]);
         assert!(cli.kill);
-        assert_eq!(cli.pid, Some(12345));
+        assert_eq!(cli.pid, Some(gpukill::proc::PidTarget::Host(12345)));
         assert_eq!(cli.timeout_secs, 5);
         assert!(!cli.force);
     }
@@ -56,7 +54,7 @@ mod mock_tests {
             "--force",
         ]);
         assert!(cli.kill);
-        assert_eq!(cli.pid, Some(12345));
+        assert_eq!(cli.pid, Some(gpukill::proc::PidTarget::Host(12345)));
         assert_eq!(cli.timeout_secs, 10);
         assert!(cli.force);
     }
@@ -219,6 +217,41 @@ mod mock_tests {
 #[cfg(feature = "mock_nvml")]
 mod mock_nvml_tests {
     use super::*;
+    use gpukill::args::OutputFormat;
+    use gpukill::nvml_api::{
+        read_ecc_counts, read_pcie_throughput, EccCounts, GpuInfo, GpuProc, GpuSnapshot, Snapshot,
+    };
+    use gpukill::proc::ProcessManager;
+    use gpukill::process_mgmt::EnhancedProcessManager;
+    use gpukill::render::{ecc_warnings, Renderer};
+    use gpukill::vendor::{MockFixture, MockGpu, MockProcess, MockVendor};
+    use nvml_wrapper::enum_wrappers::device::{EccCounter, MemoryError, PcieUtilCounter};
+    use nvml_wrapper::error::NvmlError;
+    use std::sync::Arc;
+
+    /// Process manager over two mock GPUs; PID 4242 runs on the first
+    fn mock_process_manager() -> EnhancedProcessManager {
+        let gpu = |processes| MockGpu {
+            name: "Mock GPU".to_string(),
+            mem_total_mb: 8192,
+            processes,
+            ..Default::default()
+        };
+        let vendor = Arc::new(MockVendor::new(MockFixture {
+            gpus: vec![
+                gpu(vec![MockProcess {
+                    pid: 4242,
+                    user: "testuser".to_string(),
+                    name: "python".to_string(),
+                    used_mem_mb: 2048,
+                    cmdline: None,
+                }]),
+                gpu(Vec::new()),
+            ],
+            ..Default::default()
+        }));
+        EnhancedProcessManager::new(ProcessManager::with_gpu_manager(vendor.manager()))
+    }
 
     fn create_mock_snapshot() -> Snapshot {
         Snapshot {
@@ -234,7 +267,8 @@ mod mock_nvml_tests {
                     util_pct: 45.2,
                     temp_c: 72,
                     power_w: 150.3,
-                    ecc_volatile: Some(0),
+                    ecc_volatile: Some(EccCounts::default()),
+                    ecc_aggregate: Some(EccCounts::default()),
                     pids: 2,
                     top_proc: Some(GpuProc {
                         gpu_index: 0,
@@ -243,9 +277,9 @@ mod mock_nvml_tests {
                         proc_name: "python".to_string(),
                         used_mem_mb: 1024,
                         start_time: "1h 30m".to_string(),
-                        container: None,
-                        node_id: None,
+                        ..Default::default()
                     }),
+                    ..Default::default()
                 },
                 GpuSnapshot {
                     gpu_index: 1,
@@ -256,9 +290,8 @@ mod mock_nvml_tests {
                     util_pct: 25.0,
                     temp_c: 65,
                     power_w: 120.0,
-                    ecc_volatile: None,
                     pids: 1,
-                    top_proc: None,
+                    ..Default::default()
                 },
             ],
             procs: vec![
@@ -269,8 +302,7 @@ mod mock_nvml_tests {
                     proc_name: "python".to_string(),
                     used_mem_mb: 1024,
                     start_time: "1h 30m".to_string(),
-                    container: None,
-                    node_id: None,
+                    ..Default::default()
                 },
                 GpuProc {
                     gpu_index: 0,
@@ -280,7 +312,7 @@ mod mock_nvml_tests {
                     used_mem_mb: 1024,
                     start_time: "2h 15m".to_string(),
                     container: Some("docker".to_string()),
-                    node_id: None,
+                    ..Default::default()
                 },
                 GpuProc {
                     gpu_index: 1,
@@ -289,13 +321,54 @@ mod mock_nvml_tests {
                     proc_name: "pytorch".to_string(),
                     used_mem_mb: 1024,
                     start_time: "30m".to_string(),
-                    container: None,
-                    node_id: None,
+                    ..Default::default()
                 },
             ],
+            degraded: false,
+            gpu_status: Vec::new(),
+            errors: Vec::new(),
         }
     }
 
+    #[test]
+    fn test_list_serves_mock_fixture_from_env() {
+        let home = tempfile::tempdir().unwrap();
+        let fixture = home.path().join("gpus.json");
+        std::fs::write(
+            &fixture,
+            r#"{"gpus": [
+                {"name": "Mock A100", "mem_total_mb": 81920, "util_pct": 45.0,
+                 "processes": [{"pid": 4242, "user": "alice", "name": "python", "used_mem_mb": 20480}]},
+                {"name": "Mock A100", "mem_total_mb": 81920,
+                 "failures": [{"call": "snapshot", "message": "GPU is lost"}]}
+            ]}"#,
+        )
+        .unwrap();
+
+        // A scratch HOME keeps the run away from the real config, audit log and index map
+        let output = Command::new(env!("CARGO_BIN_EXE_gpukill"))
+            .args(["--list", "--output", "json", "--no-audit"])
+            .env("GPUKILL_MOCK", &fixture)
+            .env("HOME", home.path())
+            .env("XDG_CONFIG_HOME", home.path().join("config"))
+            .env("XDG_DATA_HOME", home.path().join("data"))
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let snapshot: Snapshot = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(snapshot.gpus.len(), 1);
+        assert_eq!(snapshot.gpus[0].name, "Mock A100");
+        assert_eq!(snapshot.gpus[0].mem_used_mb, 20480);
+        assert_eq!(snapshot.procs[0].pid, 4242);
+        assert_eq!(snapshot.errors.len(), 1);
+        assert!(snapshot.errors[0].error.contains("GPU is lost"));
+    }
+
     #[test]
     fn test_table_rendering() {
         let snapshot = create_mock_snapshot();
@@ -349,12 +422,105 @@ mod mock_nvml_tests {
         assert_eq!(snapshot.procs.len(), deserialized.procs.len());
     }
 
+    #[test]
+    fn test_ecc_counts_read_from_mock_device() {
+        let mut snapshot = create_mock_snapshot();
+
+        // GPU 0 reports ECC errors, GPU 1 has no ECC support
+        let (volatile, aggregate) = read_ecc_counts(|error_type, counter_type| {
+            Ok(match (error_type, counter_type) {
+                (MemoryError::Corrected, EccCounter::Volatile) => 7,
+                (MemoryError::Uncorrected, EccCounter::Volatile) => 2,
+                (MemoryError::Corrected, EccCounter::Aggregate) => 120,
+                (MemoryError::Uncorrected, EccCounter::Aggregate) => 5,
+            })
+        });
+        snapshot.gpus[0].ecc_volatile = volatile;
+        snapshot.gpus[0].ecc_aggregate = aggregate;
+
+        let (volatile, aggregate) = read_ecc_counts(|_, _| Err(NvmlError::NotSupported));
+        snapshot.gpus[1].ecc_volatile = volatile;
+        snapshot.gpus[1].ecc_aggregate = aggregate;
+
+        assert_eq!(
+            snapshot.gpus[0].ecc_volatile,
+            Some(EccCounts {
+                corrected: 7,
+                uncorrected: 2
+            })
+        );
+        assert_eq!(
+            snapshot.gpus[0].ecc_aggregate,
+            Some(EccCounts {
+                corrected: 120,
+                uncorrected: 5
+            })
+        );
+        assert!(snapshot.gpus[1].ecc_volatile.is_none());
+        assert!(snapshot.gpus[1].ecc_aggregate.is_none());
+
+        let warnings = ecc_warnings(&snapshot);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("GPU 0"));
+        assert!(warnings[0].contains("2 uncorrected"));
+    }
+
+    #[test]
+    fn test_pcie_throughput_read_from_mock_device() {
+        let mut snapshot = create_mock_snapshot();
+
+        // GPU 0 reports PCIe counters, GPU 1 (e.g. a vGPU guest) does not
+        let (tx, rx) = read_pcie_throughput(|counter| {
+            Ok(match counter {
+                PcieUtilCounter::Send => 1_500,
+                PcieUtilCounter::Receive => 3_200_000,
+            })
+        });
+        snapshot.gpus[0].pcie_tx_kbps = tx;
+        snapshot.gpus[0].pcie_rx_kbps = rx;
+
+        let (tx, rx) = read_pcie_throughput(|_| Err(NvmlError::NotSupported));
+        snapshot.gpus[1].pcie_tx_kbps = tx;
+        snapshot.gpus[1].pcie_rx_kbps = rx;
+
+        let json = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(json["gpus"][0]["pcie_tx_kbps"], 1_500);
+        assert_eq!(json["gpus"][0]["pcie_rx_kbps"], 3_200_000);
+        assert!(json["gpus"][1]["pcie_tx_kbps"].is_null());
+        assert!(json["gpus"][1]["pcie_rx_kbps"].is_null());
+
+        let deserialized: Snapshot = serde_json::from_value(json).unwrap();
+        assert_eq!(deserialized.gpus[0].pcie_rx_kbps, Some(3_200_000));
+        assert_eq!(deserialized.gpus[1].pcie_tx_kbps, None);
+    }
+
+    #[test]
+    fn test_ecc_warning_requires_uncorrected_errors() {
+        let mut snapshot = create_mock_snapshot();
+        assert!(ecc_warnings(&snapshot).is_empty());
+
+        snapshot.gpus[0].ecc_volatile = Some(EccCounts {
+            corrected: 50,
+            uncorrected: 0,
+        });
+        assert!(ecc_warnings(&snapshot).is_empty());
+
+        snapshot.gpus[0].ecc_volatile = Some(EccCounts {
+            corrected: 50,
+            uncorrected: 1,
+        });
+        assert_eq!(ecc_warnings(&snapshot).len(), 1);
+    }
+
     #[test]
     fn test_gpu_info_serialization() {
         let gpu_info = GpuInfo {
             index: 0,
             name: "Test GPU".to_string(),
             mem_total_mb: 8192,
+            uuid: None,
+            serial: None,
+            pci_bus_id: None,
         };
 
         let json = serde_json::to_string(&gpu_info).unwrap();
@@ -375,7 +541,7 @@ mod mock_nvml_tests {
             used_mem_mb: 1024,
             start_time: "1h 30m".to_string(),
             container: Some("docker".to_string()),
-            node_id: None,
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&gpu_proc).unwrap();
@@ -392,17 +558,17 @@ mod mock_nvml_tests {
 
     // Tests for enhanced process management
     #[test]
-    fn test_enhanced_process_manager_creation() {
-        // This test would require a mock NVML API, but we can test the structure
-        use gpukill::nvml_api::NvmlApi;
-
-        // We'll skip this test if NVML is not available
-        if let Ok(nvml_api) = NvmlApi::new() {
-            use gpukill::proc::ProcessManager;
-            let proc_manager = ProcessManager::new(nvml_api);
-            let _enhanced_manager = EnhancedProcessManager::new(proc_manager);
-            // If we get here without panicking, the test passes
-        }
+    fn test_enhanced_process_manager_over_mock_gpus() {
+        let manager = mock_process_manager();
+        assert_eq!(manager.process_manager.device_count().unwrap(), 2);
+        assert!(manager.process_manager.is_process_using_gpu(4242).unwrap());
+        assert!(!manager.process_manager.is_process_using_gpu(1).unwrap());
+
+        let snapshot = manager.process_manager.create_snapshot().unwrap();
+        assert_eq!(snapshot.gpus.len(), 2);
+        assert_eq!(snapshot.gpus[0].mem_used_mb, 2048);
+        assert_eq!(snapshot.procs.len(), 1);
+        assert!(!snapshot.degraded);
     }
 
     #[test]
@@ -415,8 +581,7 @@ mod mock_nvml_tests {
                 proc_name: "python".to_string(),
                 used_mem_mb: 100,
                 start_time: "1h".to_string(),
-                container: None,
-                node_id: None,
+                ..Default::default()
             },
             GpuProc {
                 gpu_index: 0,
@@ -425,8 +590,7 @@ mod mock_nvml_tests {
                 proc_name: "python3".to_string(),
                 used_mem_mb: 200,
                 start_time: "2h".to_string(),
-                container: None,
-                node_id: None,
+                ..Default::default()
             },
             GpuProc {
                 gpu_index: 0,
@@ -435,26 +599,53 @@ mod mock_nvml_tests {
                 proc_name: "java".to_string(),
                 used_mem_mb: 300,
                 start_time: "3h".to_string(),
-                container: None,
-                node_id: None,
+                ..Default::default()
             },
         ];
 
         // Create a mock enhanced manager for testing
-        use gpukill::nvml_api::NvmlApi;
-        use gpukill::proc::ProcessManager;
+        let mut enhanced_manager = mock_process_manager();
+
+        let filtered = enhanced_manager
+            .filter_processes_by_name(&processes, "python")
+            .unwrap();
+        assert_eq!(filtered.len(), 2);
+        assert_eq!(filtered[0].proc_name, "python");
+        assert_eq!(filtered[1].proc_name, "python3");
+    }
 
-        if let Ok(nvml_api) = NvmlApi::new() {
-            let proc_manager = ProcessManager::new(nvml_api);
-            let mut enhanced_manager = EnhancedProcessManager::new(proc_manager);
+    #[test]
+    fn test_process_filtering_by_cmdline() {
+        let process = |pid: u32, name: &str, cmdline: Option<&str>| GpuProc {
+            gpu_index: 0,
+            pid,
+            user: "user1".to_string(),
+            proc_name: name.to_string(),
+            used_mem_mb: 100,
+            start_time: "1h".to_string(),
+            cmdline: cmdline.map(str::to_string),
+            ..Default::default()
+        };
+        let processes = vec![
+            process(12345, "python", Some("python train.py --epochs 10")),
+            process(12346, "python", Some("python eval.py")),
+            // Unreadable command line: matched on the name
+            process(12347, "train.py", None),
+        ];
 
-            let filtered = enhanced_manager
-                .filter_processes_by_name(&processes, "python")
-                .unwrap();
-            assert_eq!(filtered.len(), 2);
-            assert_eq!(filtered[0].proc_name, "python");
-            assert_eq!(filtered[1].proc_name, "python3");
-        }
+        let mut enhanced_manager = mock_process_manager();
+
+        let filtered = enhanced_manager
+            .filter_processes_by_cmdline(&processes, r"train\.py")
+            .unwrap();
+        let pids: Vec<u32> = filtered.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![12345, 12347]);
+
+        // The name filter still only sees the 15-character comm
+        let by_name = enhanced_manager
+            .filter_processes_by_name(&processes, r"train\.py")
+            .unwrap();
+        assert_eq!(by_name.len(), 1);
     }
 
     #[test]
@@ -467,8 +658,7 @@ mod mock_nvml_tests {
                 proc_name: "python".to_string(),
                 used_mem_mb: 100,
                 start_time: "1h".to_string(),
-                container: None,
-                node_id: None,
+                ..Default::default()
             },
             GpuProc {
                 gpu_index: 0,
@@ -477,8 +667,7 @@ mod mock_nvml_tests {
                 proc_name: "python3".to_string(),
                 used_mem_mb: 200,
                 start_time: "2h".to_string(),
-                container: None,
-                node_id: None,
+                ..Default::default()
             },
             GpuProc {
                 gpu_index: 0,
@@ -487,22 +676,15 @@ mod mock_nvml_tests {
                 proc_name: "java".to_string(),
                 used_mem_mb: 300,
                 start_time: "3h".to_string(),
-                container: None,
-                node_id: None,
+                ..Default::default()
             },
         ];
 
-        use gpukill::nvml_api::NvmlApi;
-        use gpukill::proc::ProcessManager;
-
-        if let Ok(nvml_api) = NvmlApi::new() {
-            let proc_manager = ProcessManager::new(nvml_api);
-            let mut enhanced_manager = EnhancedProcessManager::new(proc_manager);
+        let mut enhanced_manager = mock_process_manager();
 
-            let filtered = enhanced_manager.filter_processes_by_memory(&processes, 200);
-            assert_eq!(filtered.len(), 2);
-            assert!(filtered.iter().all(|p| p.used_mem_mb >= 200));
-        }
+        let filtered = enhanced_manager.filter_processes_by_memory(&processes, 200);
+        assert_eq!(filtered.len(), 2);
+        assert!(filtered.iter().all(|p| p.used_mem_mb >= 200));
     }
 
     #[test]
@@ -516,7 +698,7 @@ mod mock_nvml_tests {
                 used_mem_mb: 100,
                 start_time: "1h".to_string(),
                 container: Some("docker".to_string()),
-                node_id: None,
+                ..Default::default()
             },
             GpuProc {
                 gpu_index: 0,
@@ -526,7 +708,7 @@ mod mock_nvml_tests {
                 used_mem_mb: 200,
                 start_time: "2h".to_string(),
                 container: Some("docker".to_string()),
-                node_id: None,
+                ..Default::default()
             },
             GpuProc {
                 gpu_index: 0,
@@ -535,27 +717,317 @@ mod mock_nvml_tests {
                 proc_name: "java".to_string(),
                 used_mem_mb: 300,
                 start_time: "3h".to_string(),
-                container: None,
-                node_id: None,
+                ..Default::default()
             },
         ];
 
-        use gpukill::nvml_api::NvmlApi;
-        use gpukill::proc::ProcessManager;
+        let mut enhanced_manager = mock_process_manager();
+
+        let stats = enhanced_manager.get_process_stats(&processes);
+        assert_eq!(stats.total_processes, 3);
+        assert_eq!(stats.total_memory_mb, 600);
+        assert_eq!(stats.non_container_processes, 1);
+        assert_eq!(stats.users.len(), 2);
+        assert_eq!(stats.process_names.len(), 2);
+        assert_eq!(stats.containers.len(), 1);
+    }
+}
+
+#[cfg(feature = "mock_nvml")]
+mod local_api_tests {
+    use gpukill::audit::{ActionKind, AuditManager};
+    use gpukill::audit_store::JsonlStore;
+    use gpukill::local_api::{self, LocalApiState};
+    use gpukill::vendor::{MockCall, MockFixture, MockGpu, MockProcess, MockVendor};
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+
+    /// Local API served on an ephemeral port over the vendor, recording to its own audit log
+    struct TestServer {
+        url: String,
+        client: reqwest::Client,
+        vendor: Arc<MockVendor>,
+        audit: Arc<AuditManager>,
+        _audit_dir: tempfile::TempDir,
+    }
+
+    impl TestServer {
+        async fn get(&self, path: &str) -> (u16, Value) {
+            let response = self
+                .client
+                .get(format!("{}{}", self.url, path))
+                .send()
+                .await
+                .unwrap();
+            (response.status().as_u16(), response.json().await.unwrap())
+        }
+
+        async fn post(&self, path: &str, body: Value) -> (u16, Value) {
+            let response = self
+                .client
+                .post(format!("{}{}", self.url, path))
+                .json(&body)
+                .send()
+                .await
+                .unwrap();
+            (response.status().as_u16(), response.json().await.unwrap())
+        }
+    }
 
-        if let Ok(nvml_api) = NvmlApi::new() {
-            let proc_manager = ProcessManager::new(nvml_api);
-            let mut enhanced_manager = EnhancedProcessManager::new(proc_manager);
+    fn mock_process(pid: u32, user: &str, name: &str, used_mem_mb: u32) -> MockProcess {
+        MockProcess {
+            pid,
+            user: user.to_string(),
+            name: name.to_string(),
+            used_mem_mb,
+            cmdline: None,
+        }
+    }
 
-            let stats = enhanced_manager.get_process_stats(&processes);
-            assert_eq!(stats.total_processes, 3);
-            assert_eq!(stats.total_memory_mb, 600);
-            assert_eq!(stats.non_container_processes, 1);
-            assert_eq!(stats.users.len(), 2);
-            assert_eq!(stats.process_names.len(), 2);
-            assert_eq!(stats.containers.len(), 1);
+    /// Two mock GPUs: `processes` run on the first, none on the second
+    async fn start_server(processes: Vec<MockProcess>, token: Option<&str>) -> TestServer {
+        let gpu = |processes| MockGpu {
+            name: "Mock GPU".to_string(),
+            mem_total_mb: 8192,
+            util_pct: 40.0,
+            processes,
+            ..Default::default()
+        };
+        let vendor = Arc::new(MockVendor::new(MockFixture {
+            gpus: vec![gpu(processes), gpu(Vec::new())],
+            ..Default::default()
+        }));
+        let audit_dir = tempfile::tempdir().unwrap();
+        let audit = Arc::new(AuditManager::with_store(Box::new(
+            JsonlStore::new(audit_dir.path().to_path_buf()).unwrap(),
+        )));
+        let state = LocalApiState::new(vendor.manager())
+            .with_token(token.map(str::to_string))
+            .with_audit(audit.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(local_api::serve(listener, state, std::future::pending()));
+        TestServer {
+            url,
+            client: reqwest::Client::new(),
+            vendor,
+            audit,
+            _audit_dir: audit_dir,
         }
     }
+
+    #[tokio::test]
+    async fn test_local_api_snapshot_and_processes() {
+        let server = start_server(
+            vec![
+                mock_process(4242, "alice", "python", 1024),
+                mock_process(4343, "bob", "train", 4096),
+            ],
+            None,
+        )
+        .await;
+
+        // Same document as --list --output json, totals included
+        let (status, snapshot) = server.get("/v1/snapshot").await;
+        assert_eq!(status, 200, "{}", snapshot);
+        assert_eq!(snapshot["gpus"].as_array().unwrap().len(), 2);
+        assert_eq!(snapshot["procs"].as_array().unwrap().len(), 2);
+        assert_eq!(snapshot["degraded"], false);
+        assert!(snapshot.get("host").is_some());
+        assert!(snapshot["totals"].is_object());
+
+        let (_, snapshot) = server.get("/v1/snapshot?gpu=1").await;
+        assert_eq!(snapshot["gpus"].as_array().unwrap().len(), 1);
+        assert!(snapshot["procs"].as_array().unwrap().is_empty());
+
+        // Largest memory first, like --processes-only
+        let (status, processes) = server.get("/v1/processes").await;
+        assert_eq!(status, 200);
+        let pids: Vec<u64> = processes
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["pid"].as_u64().unwrap())
+            .collect();
+        assert_eq!(pids, vec![4343, 4242]);
+        let (_, processes) = server.get("/v1/processes?gpu=1").await;
+        assert!(processes.as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_local_api_kill() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let pid = child.id();
+        let server = start_server(
+            vec![
+                mock_process(pid, "alice", "sleep", 2048),
+                mock_process(4242, "bob", "python", 1024),
+            ],
+            None,
+        )
+        .await;
+
+        let (status, report) = server
+            .post("/v1/kill", json!({"pid": pid, "dry_run": true}))
+            .await;
+        assert_eq!(status, 200, "{}", report);
+        assert_eq!(report["operation"], "kill");
+        assert_eq!(report["dry_run"], true);
+        assert_eq!(report["processes"][0]["pid"], pid);
+        assert_eq!(report["processes"][0]["status"], "would_kill");
+
+        let (status, report) = server
+            .post(
+                "/v1/kill",
+                json!({"filter": "python", "user": "bob", "dry_run": true}),
+            )
+            .await;
+        assert_eq!(status, 200, "{}", report);
+        assert_eq!(report["processes"].as_array().unwrap().len(), 1);
+        assert_eq!(report["processes"][0]["pid"], 4242);
+
+        // Reap the child once it exits so it does not linger as a zombie
+        let reaper = std::thread::spawn(move || child.wait());
+        let (status, report) = server
+            .post(
+                "/v1/kill",
+                json!({"pid": pid, "timeout_secs": 2, "force": true}),
+            )
+            .await;
+        assert_eq!(status, 200, "{}", report);
+        assert_eq!(report["processes"][0]["status"], "killed");
+        assert_eq!(report["memory_reclaimed_mb"], 2048);
+        reaper.join().unwrap().unwrap();
+
+        let (status, error) = server
+            .post("/v1/kill", json!({"pid": pid, "filter": "sleep"}))
+            .await;
+        assert_eq!(status, 400);
+        assert_eq!(error["error"]["kind"], "invalid_argument");
+        let (status, error) = server.post("/v1/kill", json!({"pid": 999_999})).await;
+        assert_eq!(status, 404, "{}", error);
+        assert_eq!(error["error"]["kind"], "not_found");
+        assert!(error["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("not found"));
+
+        // Kills are recorded in the action audit log like CLI kills
+        let now = chrono::Utc::now();
+        let actions = server
+            .audit
+            .query_actions(now - chrono::Duration::hours(1), now, None)
+            .await
+            .unwrap();
+        let killed = actions
+            .iter()
+            .find(|a| a.target == format!("pid {}", pid) && !a.dry_run)
+            .unwrap();
+        assert_eq!(killed.kind, ActionKind::Kill);
+        assert!(killed.success);
+    }
+
+    #[tokio::test]
+    async fn test_local_api_reset() {
+        let server = start_server(vec![mock_process(4242, "alice", "python", 1024)], None).await;
+
+        let (status, report) = server
+            .post("/v1/reset", json!({"gpu": 0, "dry_run": true}))
+            .await;
+        assert_eq!(status, 200, "{}", report);
+        assert_eq!(report["operation"], "reset");
+        assert_eq!(report["gpus"][0]["status"], "would_refuse");
+        assert_eq!(report["gpus"][0]["processes"][0]["pid"], 4242);
+
+        // Active processes refuse a reset unless forced
+        let (status, error) = server.post("/v1/reset", json!({"gpu": 0})).await;
+        assert_eq!(status, 409, "{}", error);
+        assert_eq!(server.vendor.calls(0, MockCall::Reset), 0);
+
+        let (status, report) = server.post("/v1/reset", json!({"gpu": 1})).await;
+        assert_eq!(status, 200, "{}", report);
+        assert_eq!(report["gpus"][0]["gpu_index"], 1);
+        assert_eq!(report["gpus"][0]["status"], "reset");
+        assert_eq!(server.vendor.calls(1, MockCall::Reset), 1);
+
+        let (status, error) = server.post("/v1/reset", json!({"gpu": 7})).await;
+        assert_eq!(status, 404);
+        assert_eq!(error["error"]["kind"], "not_found");
+    }
+
+    #[tokio::test]
+    async fn test_local_api_audit() {
+        let server = start_server(
+            vec![
+                mock_process(4242, "alice", "python", 1024),
+                mock_process(4343, "bob", "train", 4096),
+            ],
+            None,
+        )
+        .await;
+        let manager = server.vendor.manager();
+        let snapshots = manager.collect_snapshots_async().await.unwrap().snapshots;
+        let processes = manager.get_all_processes().unwrap();
+        server
+            .audit
+            .log_snapshot(&snapshots, &processes)
+            .await
+            .unwrap();
+
+        let (status, records) = server.get("/v1/audit?hours=1").await;
+        assert_eq!(status, 200, "{}", records);
+        let pids: Vec<u64> = records
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|r| r["pid"].as_u64())
+            .collect();
+        assert!(pids.contains(&4242) && pids.contains(&4343), "{:?}", pids);
+
+        let (_, records) = server.get("/v1/audit?hours=1&user=bob").await;
+        let records = records.as_array().unwrap();
+        assert!(!records.is_empty());
+        assert!(records.iter().all(|r| r["user"] == "bob"));
+
+        // Hours reaching past the dates chrono can represent are refused, not a panic
+        let (status, error) = server.get("/v1/audit?hours=4294967295").await;
+        assert_eq!(status, 400, "{}", error);
+        assert_eq!(error["error"]["kind"], "invalid_argument");
+    }
+
+    #[tokio::test]
+    async fn test_local_api_bearer_token() {
+        let server = start_server(Vec::new(), Some("s3cret")).await;
+
+        let (status, error) = server.get("/v1/processes").await;
+        assert_eq!(status, 401);
+        assert!(error["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("bearer token"));
+
+        let response = server
+            .client
+            .get(format!("{}/v1/processes", server.url))
+            .bearer_auth("wrong")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 401);
+
+        let response = server
+            .client
+            .get(format!("{}/v1/processes", server.url))
+            .bearer_auth("s3cret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status().as_u16(), 200);
+    }
 }
 
 // Integration tests that don't require NVML
@@ -564,8 +1036,8 @@ mod integration_tests {
 
     #[test]
     fn test_version_flag() {
-        let output = Command::new("cargo")
-            .args(["run", "--", "--version"])
+        let output = Command::new(env!("CARGO_BIN_EXE_gpukill"))
+            .args(["--version"])
             .output()
             .expect("Failed to execute command");
 
@@ -576,8 +1048,8 @@ mod integration_tests {
 
     #[test]
     fn test_help_flag() {
-        let output = Command::new("cargo")
-            .args(["run", "--", "--help"])
+        let output = Command::new(env!("CARGO_BIN_EXE_gpukill"))
+            .args(["--help"])
             .output()
             .expect("Failed to execute command");
 
@@ -589,8 +1061,8 @@ mod integration_tests {
 
     #[test]
     fn test_list_help() {
-        let output = Command::new("cargo")
-            .args(["run", "--", "--list", "--help"])
+        let output = Command::new(env!("CARGO_BIN_EXE_gpukill"))
+            .args(["--list", "--help"])
             .output()
             .expect("Failed to execute command");
 
@@ -601,8 +1073,8 @@ mod integration_tests {
 
     #[test]
     fn test_kill_help() {
-        let output = Command::new("cargo")
-            .args(["run", "--", "--kill", "--help"])
+        let output = Command::new(env!("CARGO_BIN_EXE_gpukill"))
+            .args(["--kill", "--help"])
             .output()
             .expect("Failed to execute command");
 
@@ -613,8 +1085,8 @@ mod integration_tests {
 
     #[test]
     fn test_reset_help() {
-        let output = Command::new("cargo")
-            .args(["run", "--", "--reset", "--help"])
+        let output = Command::new(env!("CARGO_BIN_EXE_gpukill"))
+            .args(["--reset", "--help"])
             .output()
             .expect("Failed to execute command");
 
@@ -625,8 +1097,8 @@ mod integration_tests {
 
     #[test]
     fn test_invalid_operation_combination() {
-        let output = Command::new("cargo")
-            .args(["run", "--", "--list", "--kill"])
+        let output = Command::new(env!("CARGO_BIN_EXE_gpukill"))
+            .args(["--list", "--kill"])
             .output()
             .expect("Failed to execute command");
 
@@ -635,24 +1107,63 @@ mod integration_tests {
 
     #[test]
     fn test_missing_required_args() {
-        let output = Command::new("cargo")
-            .args(["run", "--", "--kill"])
+        let output = Command::new(env!("CARGO_BIN_EXE_gpukill"))
+            .args(["--kill"])
             .output()
             .expect("Failed to execute command");
 
         assert!(!output.status.success());
     }
 
-    // Tests for new validation logic
     #[test]
-    fn test_kill_with_both_pid_and_filter_fails() {
-        let output = Command::new("cargo")
+    fn test_dry_run_kill_json_output() {
+        // The test process itself: --force skips the GPU usage check, --dry-run keeps it alive
+        let pid = std::process::id().to_string();
+        let output = Command::new(env!("CARGO_BIN_EXE_gpukill"))
             .args([
-                "run", "--", "--kill", "--pid", "12345", "--filter", "python",
+                "--kill",
+                "--pid",
+                &pid,
+                "--force",
+                "--dry-run",
+                "--output",
+                "json",
             ])
             .output()
             .expect("Failed to execute command");
 
+        // stdout holds exactly one JSON document: the kill result, or an error
+        // object on hosts without a usable GPU
+        let doc: serde_json::Value =
+            serde_json::from_slice(&output.stdout).expect("stdout is a single JSON document");
+        if output.status.success() {
+            assert_eq!(doc["operation"], "kill");
+            assert_eq!(doc["dry_run"], true);
+            assert_eq!(doc["signal"], "SIGTERM");
+            assert_eq!(doc["memory_reclaimed_mb"], 0);
+            let processes = doc["processes"].as_array().unwrap();
+            assert_eq!(processes.len(), 1);
+            assert_eq!(processes[0]["pid"], std::process::id());
+            assert!(matches!(
+                processes[0]["status"].as_str(),
+                Some("would_kill" | "would_skip")
+            ));
+        } else {
+            let error = &doc["error"];
+            assert!(error["kind"].is_string());
+            assert!(error["message"].is_string());
+            assert_eq!(error["exit_code"], output.status.code().unwrap());
+        }
+    }
+
+    // Tests for new validation logic
+    #[test]
+    fn test_kill_with_both_pid_and_filter_fails() {
+        let output = Command::new(env!("CARGO_BIN_EXE_gpukill"))
+            .args(["--kill", "--pid", "12345", "--filter", "python"])
+            .output()
+            .expect("Failed to execute command");
+
         assert!(!output.status.success());
         let stderr = String::from_utf8_lossy(&output.stderr);
         assert!(stderr.contains("cannot use both --pid and --filter"));
@@ -660,8 +1171,8 @@ mod integration_tests {
 
     #[test]
     fn test_kill_without_pid_or_filter_fails() {
-        let output = Command::new("cargo")
-            .args(["run", "--", "--kill"])
+        let output = Command::new(env!("CARGO_BIN_EXE_gpukill"))
+            .args(["--kill"])
             .output()
             .expect("Failed to execute command");
 
@@ -672,8 +1183,8 @@ mod integration_tests {
 
     #[test]
     fn test_batch_without_filter_fails() {
-        let output = Command::new("cargo")
-            .args(["run", "--", "--kill", "--batch"])
+        let output = Command::new(env!("CARGO_BIN_EXE_gpukill"))
+            .args(["--kill", "--batch"])
             .output()
             .expect("Failed to execute command");
 
@@ -684,8 +1195,8 @@ mod integration_tests {
 
     #[test]
     fn test_containers_without_list_fails() {
-        let output = Command::new("cargo")
-            .args(["run", "--", "--containers"])
+        let output = Command::new(env!("CARGO_BIN_EXE_gpukill"))
+            .args(["--containers"])
             .output()
             .expect("Failed to execute command");
 
@@ -765,3 +1276,26 @@ mod integration_tests {
         assert!(error_msg.contains("drivers"));
     }
 }
+
+/// Real SSH round trip, run only when GPUKILL_SSH_TEST_HOST names a reachable host
+/// (optionally GPUKILL_SSH_TEST_USER and GPUKILL_SSH_TEST_JUMP). Authentication uses
+/// the running ssh-agent.
+#[test]
+fn test_remote_ssh_round_trip() {
+    use gpukill::remote::{parse_jump_hosts, SshConfig, SshRemote};
+
+    let Ok(host) = std::env::var("GPUKILL_SSH_TEST_HOST") else {
+        return;
+    };
+    let user = std::env::var("GPUKILL_SSH_TEST_USER")
+        .unwrap_or_else(|_| std::env::var("USER").unwrap_or_else(|_| "root".to_string()));
+    let mut config = SshConfig::new(host, 22, user);
+    if let Ok(jump) = std::env::var("GPUKILL_SSH_TEST_JUMP") {
+        config = config.with_jump_hosts(parse_jump_hosts(&jump).unwrap());
+    }
+
+    let output = SshRemote::new(config)
+        .execute_command("echo gpukill-ssh-ok")
+        .unwrap();
+    assert_eq!(output.trim(), "gpukill-ssh-ok");
+}